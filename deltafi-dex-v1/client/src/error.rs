@@ -0,0 +1,27 @@
+//! Error types
+
+use solana_program::{
+    program_error::ProgramError,
+    pubkey::{Pubkey, PubkeyError},
+};
+use thiserror::Error;
+
+/// Errors of the client
+#[derive(Debug, Error)]
+pub enum ClientError {
+    /// The RPC request failed
+    #[error("RPC request failed: {0}")]
+    Rpc(#[from] solana_client::client_error::ClientError),
+    /// The account data is not the one of the expected state, or the curve failed
+    #[error("program error: {0}")]
+    Program(#[from] ProgramError),
+    /// The address cannot be derived
+    #[error("cannot derive the address: {0}")]
+    Pubkey(#[from] PubkeyError),
+    /// The mint is not one of the tokens of the pool
+    #[error("{0} is not a mint of the pool")]
+    NotPoolMint(Pubkey),
+}
+
+/// Result of the client
+pub type ClientResult<T> = Result<T, ClientError>;
@@ -0,0 +1,305 @@
+//! Instructions of the user flows built from the fetched account states, the owner of the
+//! token accounts signs as the transfer authority so no approval is needed
+
+use crate::{
+    error::ClientResult,
+    pda::{find_farm_authority, find_market_authority, get_farm_user_seed, get_swap_authority},
+};
+use deltafi_swap::{
+    adapter::ExchangeRateAdapterType,
+    curve::SwapDirection,
+    instruction::{
+        deposit, farm_claim, farm_deposit, farm_user_initialize, farm_withdraw, stable_deposit,
+        stable_swap, stable_withdraw, swap, withdraw, DepositData, FarmDepositData,
+        FarmWithdrawData, SwapData, WithdrawData,
+    },
+    processor::get_farm_user_pubkey,
+    state::{ConfigInfo, FarmInfo, FarmUser, SwapInfo, SwapType},
+};
+use solana_program::{
+    instruction::Instruction, program_pack::Pack, pubkey::Pubkey,
+    system_instruction::create_account_with_seed,
+};
+
+/// Pool of the program as fetched from its account
+#[derive(Clone, Debug, PartialEq)]
+pub struct Pool {
+    /// Pool account
+    pub pubkey: Pubkey,
+    /// Config of the pool
+    pub config: ConfigInfo,
+    /// Pool state
+    pub swap_info: SwapInfo,
+}
+
+impl Pool {
+    fn exchange_rate_account(&self) -> Option<Pubkey> {
+        match self.swap_info.exchange_rate_adapter {
+            ExchangeRateAdapterType::None => None,
+            _ => Some(*self.swap_info.exchange_rate_account()),
+        }
+    }
+
+    /// Swap the source token account of the owner into its destination token account, the
+    /// DELTAFI trade reward goes to its `reward_token` account
+    #[allow(clippy::too_many_arguments)]
+    pub fn swap(
+        &self,
+        program_id: &Pubkey,
+        owner: &Pubkey,
+        source: &Pubkey,
+        destination: &Pubkey,
+        reward_token: &Pubkey,
+        swap_direction: SwapDirection,
+        swap_data: SwapData,
+    ) -> ClientResult<Instruction> {
+        let swap_info = &self.swap_info;
+        let (market_authority, _) = find_market_authority(&swap_info.config_key, program_id);
+        let swap_authority = get_swap_authority(&self.pubkey, swap_info, program_id)?;
+        let (swap_source, source_mint, swap_destination, destination_mint, admin_fee_destination) =
+            match swap_direction {
+                SwapDirection::SellBase => (
+                    swap_info.token_a,
+                    swap_info.token_a_mint,
+                    swap_info.token_b,
+                    swap_info.token_b_mint,
+                    swap_info.admin_fee_key_b,
+                ),
+                SwapDirection::SellQuote => (
+                    swap_info.token_b,
+                    swap_info.token_b_mint,
+                    swap_info.token_a,
+                    swap_info.token_a_mint,
+                    swap_info.admin_fee_key_a,
+                ),
+            };
+
+        let instruction = if swap_info.swap_type == SwapType::Normal {
+            swap(
+                *program_id,
+                swap_info.config_key,
+                self.pubkey,
+                market_authority,
+                swap_authority,
+                *owner,
+                *source,
+                swap_source,
+                source_mint,
+                swap_destination,
+                *destination,
+                destination_mint,
+                *reward_token,
+                self.config.deltafi_token,
+                admin_fee_destination,
+                swap_info.pyth_a,
+                swap_info.pyth_b,
+                None,
+                None,
+                None,
+                None,
+                None,
+                None,
+                None,
+                swap_data,
+            )?
+        } else {
+            // Pegged pools trade with the stable swap instructions
+            stable_swap(
+                *program_id,
+                swap_info.config_key,
+                self.pubkey,
+                market_authority,
+                swap_authority,
+                *owner,
+                *source,
+                swap_source,
+                source_mint,
+                swap_destination,
+                *destination,
+                destination_mint,
+                *reward_token,
+                self.config.deltafi_token,
+                admin_fee_destination,
+                self.exchange_rate_account(),
+                None,
+                None,
+                None,
+                None,
+                swap_data,
+            )?
+        };
+        Ok(instruction)
+    }
+
+    /// Deposit both tokens of the owner, minting the pool tokens to `pool_token`
+    pub fn deposit(
+        &self,
+        program_id: &Pubkey,
+        owner: &Pubkey,
+        token_a: &Pubkey,
+        token_b: &Pubkey,
+        pool_token: &Pubkey,
+        deposit_data: DepositData,
+    ) -> ClientResult<Instruction> {
+        let swap_info = &self.swap_info;
+        let swap_authority = get_swap_authority(&self.pubkey, swap_info, program_id)?;
+        let builder = if swap_info.swap_type == SwapType::Normal {
+            deposit
+        } else {
+            stable_deposit
+        };
+        Ok(builder(
+            *program_id,
+            self.pubkey,
+            swap_authority,
+            *owner,
+            *token_a,
+            *token_b,
+            swap_info.token_a,
+            swap_info.token_b,
+            swap_info.pool_mint,
+            *pool_token,
+            deposit_data,
+        )?)
+    }
+
+    /// Burn the pool tokens of the owner, withdrawing both tokens to its token accounts
+    pub fn withdraw(
+        &self,
+        program_id: &Pubkey,
+        owner: &Pubkey,
+        pool_token: &Pubkey,
+        token_a: &Pubkey,
+        token_b: &Pubkey,
+        withdraw_data: WithdrawData,
+    ) -> ClientResult<Instruction> {
+        let swap_info = &self.swap_info;
+        let swap_authority = get_swap_authority(&self.pubkey, swap_info, program_id)?;
+        let builder = if swap_info.swap_type == SwapType::Normal {
+            withdraw
+        } else {
+            stable_withdraw
+        };
+        Ok(builder(
+            *program_id,
+            self.pubkey,
+            swap_authority,
+            *owner,
+            swap_info.pool_mint,
+            *pool_token,
+            swap_info.token_a,
+            swap_info.token_b,
+            *token_a,
+            *token_b,
+            swap_info.admin_fee_key_a,
+            swap_info.admin_fee_key_b,
+            withdraw_data,
+        )?)
+    }
+}
+
+/// Farm of the program as fetched from its account
+#[derive(Clone, Debug, PartialEq)]
+pub struct Farm {
+    /// Farm pool account
+    pub pubkey: Pubkey,
+    /// Config of the farm
+    pub config: ConfigInfo,
+    /// Farm state
+    pub farm_info: FarmInfo,
+}
+
+impl Farm {
+    /// Create and initialize the farm user account of the owner, `payer` funds its rent
+    /// exempt `lamports`
+    pub fn initialize_farm_user(
+        &self,
+        program_id: &Pubkey,
+        owner: &Pubkey,
+        payer: &Pubkey,
+        lamports: u64,
+    ) -> ClientResult<Vec<Instruction>> {
+        let farm_user = get_farm_user_pubkey(owner, &self.pubkey, program_id)?;
+        Ok(vec![
+            create_account_with_seed(
+                payer,
+                &farm_user,
+                owner,
+                &get_farm_user_seed(&self.pubkey),
+                lamports,
+                FarmUser::LEN as u64,
+                program_id,
+            ),
+            farm_user_initialize(
+                *program_id,
+                self.farm_info.config_key,
+                self.pubkey,
+                farm_user,
+                *owner,
+            )?,
+        ])
+    }
+
+    /// Stake `amount` pool tokens of the `pool_token` account of the owner
+    pub fn deposit(
+        &self,
+        program_id: &Pubkey,
+        owner: &Pubkey,
+        pool_token: &Pubkey,
+        deposit_data: FarmDepositData,
+    ) -> ClientResult<Instruction> {
+        Ok(farm_deposit(
+            *program_id,
+            self.farm_info.config_key,
+            self.pubkey,
+            *owner,
+            *pool_token,
+            self.farm_info.pool_token,
+            get_farm_user_pubkey(owner, &self.pubkey, program_id)?,
+            *owner,
+            deposit_data,
+        )?)
+    }
+
+    /// Unstake pool tokens of the owner to its `pool_token` account
+    pub fn withdraw(
+        &self,
+        program_id: &Pubkey,
+        owner: &Pubkey,
+        pool_token: &Pubkey,
+        withdraw_data: FarmWithdrawData,
+    ) -> ClientResult<Instruction> {
+        let (farm_authority, _) = find_farm_authority(&self.pubkey, program_id);
+        Ok(farm_withdraw(
+            *program_id,
+            self.farm_info.config_key,
+            self.pubkey,
+            get_farm_user_pubkey(owner, &self.pubkey, program_id)?,
+            farm_authority,
+            self.farm_info.pool_token,
+            *pool_token,
+            *owner,
+            withdraw_data,
+        )?)
+    }
+
+    /// Claim the DELTAFI rewards of the owner to its `reward_token` account
+    pub fn claim(
+        &self,
+        program_id: &Pubkey,
+        owner: &Pubkey,
+        reward_token: &Pubkey,
+    ) -> ClientResult<Instruction> {
+        let (market_authority, _) = find_market_authority(&self.farm_info.config_key, program_id);
+        Ok(farm_claim(
+            *program_id,
+            self.farm_info.config_key,
+            self.pubkey,
+            get_farm_user_pubkey(owner, &self.pubkey, program_id)?,
+            *owner,
+            market_authority,
+            *reward_token,
+            self.config.deltafi_token,
+        )?)
+    }
+}
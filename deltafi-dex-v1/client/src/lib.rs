@@ -0,0 +1,15 @@
+#![deny(missing_docs)]
+
+//! Rust client of the DeltaFi swap program: fetch and deserialize the program accounts over
+//! RPC, derive their addresses, build the user instructions and quote swaps off chain with
+//! the curve of the program.
+
+pub mod error;
+pub mod instructions;
+pub mod pda;
+pub mod rpc;
+pub mod simulate;
+
+pub use deltafi_swap;
+pub use error::{ClientError, ClientResult};
+pub use rpc::DeltafiClient;
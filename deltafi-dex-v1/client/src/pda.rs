@@ -0,0 +1,30 @@
+//! Addresses derived from the program id, the authorities are program addresses and the user
+//! accounts are created with a seed from their owner
+
+use deltafi_swap::state::SwapInfo;
+use solana_program::pubkey::{Pubkey, PubkeyError};
+
+pub use deltafi_swap::processor::{
+    find_farm_user_registry_address, find_fee_discount_address, find_price_cache_address,
+    find_referrer_stats_address, find_swap_address, get_farm_user_pubkey, get_farm_user_seed,
+    get_referrer_data_pubkey, get_referrer_data_seed,
+};
+
+/// Market authority of a config, owner of its DELTAFI reward token account
+pub fn find_market_authority(config_key: &Pubkey, program_id: &Pubkey) -> (Pubkey, u8) {
+    Pubkey::find_program_address(&[config_key.as_ref()], program_id)
+}
+
+/// Authority of a farm pool, owner of its staked pool token account
+pub fn find_farm_authority(farm_pool_key: &Pubkey, program_id: &Pubkey) -> (Pubkey, u8) {
+    Pubkey::find_program_address(&[farm_pool_key.as_ref()], program_id)
+}
+
+/// Authority of a pool, owner of its token accounts, from the nonce stored in the pool
+pub fn get_swap_authority(
+    swap_key: &Pubkey,
+    swap_info: &SwapInfo,
+    program_id: &Pubkey,
+) -> Result<Pubkey, PubkeyError> {
+    Pubkey::create_program_address(&[swap_key.as_ref(), &[swap_info.nonce]], program_id)
+}
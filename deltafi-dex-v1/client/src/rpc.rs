@@ -0,0 +1,115 @@
+//! Accounts of the program fetched over RPC and transactions sent to the cluster
+
+use crate::{
+    error::ClientResult,
+    instructions::{Farm, Pool},
+    pda::get_farm_user_pubkey,
+};
+use deltafi_swap::state::{ConfigInfo, FarmInfo, FarmUser, SwapInfo};
+use solana_client::rpc_client::RpcClient;
+use solana_program::{
+    instruction::Instruction,
+    program_pack::{IsInitialized, Pack},
+    pubkey::Pubkey,
+};
+use solana_sdk::{signature::Signature, signers::Signers, transaction::Transaction};
+
+/// RPC client of a deployment of the program
+pub struct DeltafiClient {
+    /// Connection to the cluster
+    pub rpc: RpcClient,
+    /// Program id of the deployment
+    pub program_id: Pubkey,
+}
+
+impl DeltafiClient {
+    /// Client of the program deployed at `program_id`
+    pub fn new(rpc: RpcClient, program_id: Pubkey) -> Self {
+        Self { rpc, program_id }
+    }
+
+    /// Fetch and unpack the account state at `pubkey`
+    pub fn get_state<T: Pack + IsInitialized>(&self, pubkey: &Pubkey) -> ClientResult<T> {
+        let data = self.rpc.get_account_data(pubkey)?;
+        Ok(T::unpack(&data)?)
+    }
+
+    /// Market config
+    pub fn get_config(&self, config_key: &Pubkey) -> ClientResult<ConfigInfo> {
+        self.get_state(config_key)
+    }
+
+    /// Pool state
+    pub fn get_swap_info(&self, swap_key: &Pubkey) -> ClientResult<SwapInfo> {
+        self.get_state(swap_key)
+    }
+
+    /// Farm pool state
+    pub fn get_farm_info(&self, farm_pool_key: &Pubkey) -> ClientResult<FarmInfo> {
+        self.get_state(farm_pool_key)
+    }
+
+    /// Farm user of the owner in the farm pool
+    pub fn get_farm_user(&self, owner: &Pubkey, farm_pool_key: &Pubkey) -> ClientResult<FarmUser> {
+        self.get_state(&get_farm_user_pubkey(
+            owner,
+            farm_pool_key,
+            &self.program_id,
+        )?)
+    }
+
+    /// Pool with its config, to build its instructions
+    pub fn get_pool(&self, swap_key: &Pubkey) -> ClientResult<Pool> {
+        let swap_info = self.get_swap_info(swap_key)?;
+        Ok(Pool {
+            pubkey: *swap_key,
+            config: self.get_config(&swap_info.config_key)?,
+            swap_info,
+        })
+    }
+
+    /// Farm with its config, to build its instructions
+    pub fn get_farm(&self, farm_pool_key: &Pubkey) -> ClientResult<Farm> {
+        let farm_info = self.get_farm_info(farm_pool_key)?;
+        Ok(Farm {
+            pubkey: *farm_pool_key,
+            config: self.get_config(&farm_info.config_key)?,
+            farm_info,
+        })
+    }
+
+    /// Rent exempt balance of the farm user account, funded by `Farm::initialize_farm_user`
+    pub fn farm_user_rent(&self) -> ClientResult<u64> {
+        Ok(self
+            .rpc
+            .get_minimum_balance_for_rent_exemption(FarmUser::LEN)?)
+    }
+
+    /// Transaction of the instructions paid by `payer`, signed with the latest blockhash
+    pub fn transaction<T: Signers>(
+        &self,
+        instructions: &[Instruction],
+        payer: &Pubkey,
+        signers: &T,
+    ) -> ClientResult<Transaction> {
+        let blockhash = self.rpc.get_latest_blockhash()?;
+        Ok(Transaction::new_signed_with_payer(
+            instructions,
+            Some(payer),
+            signers,
+            blockhash,
+        ))
+    }
+
+    /// Send the instructions in one transaction paid by the first signer, there must be at
+    /// least one, and wait for its confirmation
+    pub fn send<T: Signers>(
+        &self,
+        instructions: &[Instruction],
+        signers: &T,
+    ) -> ClientResult<Signature> {
+        let payer = signers.pubkeys()[0];
+        let transaction = self.transaction(instructions, &payer, signers)?;
+        Ok(self.rpc.send_and_confirm_transaction(&transaction)?)
+    }
+}
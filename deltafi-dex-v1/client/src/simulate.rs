@@ -0,0 +1,81 @@
+//! Swaps quoted off chain with the curve and the fees of the program, the same amounts the
+//! swap instructions would transfer at the given market price
+
+use crate::error::{ClientError, ClientResult};
+use deltafi_swap::{
+    curve::SwapDirection,
+    math::Decimal,
+    quote::{get_swap_out_amount, quote_swap},
+    state::SwapInfo,
+};
+use solana_program::pubkey::Pubkey;
+
+pub use deltafi_swap::instruction::SwapQuote;
+
+/// Direction of a swap selling the token of `source_mint`
+pub fn swap_direction(swap_info: &SwapInfo, source_mint: &Pubkey) -> ClientResult<SwapDirection> {
+    if *source_mint == swap_info.token_a_mint {
+        Ok(SwapDirection::SellBase)
+    } else if *source_mint == swap_info.token_b_mint {
+        Ok(SwapDirection::SellQuote)
+    } else {
+        Err(ClientError::NotPoolMint(*source_mint))
+    }
+}
+
+/// Quote a swap of `amount_in`, at `market_price` when given, e.g. the oracle price of a
+/// normal pool, else at the market price the pool last traded at
+pub fn simulate_swap(
+    swap_info: &SwapInfo,
+    amount_in: u64,
+    swap_direction: SwapDirection,
+    market_price: Option<Decimal>,
+) -> ClientResult<SwapQuote> {
+    let quote = match market_price {
+        Some(market_price) => {
+            get_swap_out_amount(swap_info, amount_in, swap_direction, market_price)?
+        }
+        None => quote_swap(swap_info, amount_in, swap_direction)?,
+    };
+    Ok(quote)
+}
+
+/// Minimum amount out of a swap quoted at `amount_out`, tolerating `slippage_bps`
+pub fn minimum_amount_out(amount_out: u64, slippage_bps: u16) -> u64 {
+    let slippage_bps = u128::from(slippage_bps.min(10_000));
+    (u128::from(amount_out) * (10_000 - slippage_bps) / 10_000) as u64
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_swap_direction() {
+        let swap_info = SwapInfo {
+            token_a_mint: Pubkey::new_unique(),
+            token_b_mint: Pubkey::new_unique(),
+            ..SwapInfo::default()
+        };
+        assert_eq!(
+            swap_direction(&swap_info, &swap_info.token_a_mint).unwrap(),
+            SwapDirection::SellBase
+        );
+        assert_eq!(
+            swap_direction(&swap_info, &swap_info.token_b_mint).unwrap(),
+            SwapDirection::SellQuote
+        );
+        assert!(matches!(
+            swap_direction(&swap_info, &Pubkey::new_unique()),
+            Err(ClientError::NotPoolMint(_))
+        ));
+    }
+
+    #[test]
+    fn test_minimum_amount_out() {
+        assert_eq!(minimum_amount_out(1_000_000, 0), 1_000_000);
+        assert_eq!(minimum_amount_out(1_000_000, 50), 995_000);
+        assert_eq!(minimum_amount_out(u64::MAX, 0), u64::MAX);
+        assert_eq!(minimum_amount_out(1_000_000, u16::MAX), 0);
+    }
+}
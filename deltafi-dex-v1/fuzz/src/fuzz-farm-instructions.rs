@@ -1,12 +1,8 @@
 use {
     arbitrary::Arbitrary,
+    deltafi_swap::instruction::{FarmDepositData, FarmWithdrawData},
+    deltafi_swap_fuzz::{native_farm::NativeFarm, native_processor::set_clock},
     honggfuzz::fuzz,
-    deltafi_swap::{
-        instruction::{FarmDepositData, FarmWithdrawData},
-    },
-    deltafi_swap_fuzz::{
-        native_farm:: NativeFarm,
-    },
 };
 
 use solana_program::entrypoint::ProgramResult;
@@ -19,16 +15,14 @@ struct FarmFuzzData {
 
 #[derive(Debug, Arbitrary, Clone)]
 enum FarmFuzzInstruction {
-    Deposit {
-        instruction_data: FarmDepositData,
-    },
-    Withdraw {
-        instruction_data: FarmWithdrawData,
-    },
+    Deposit { instruction_data: FarmDepositData },
+    Withdraw { instruction_data: FarmWithdrawData },
     Refresh,
+    Tick { seconds: u32 },
 }
 
 const MAX_POOL_RESERVED_AMOUNT: u64 = 100_000_000_000;
+const USER_TOKEN_AMOUNT: u64 = 100_000_000_000;
 
 fn main() {
     loop {
@@ -37,90 +31,68 @@ fn main() {
 }
 
 fn run_farm_fuzz(fuzz_data: FarmFuzzData) {
-
     /*  Create and initialize a simulated environment to fuzz Farm Instructions.
         Create accounts that are to be used with FarmInstructions,
         and initialize Farm Pool
     */
-    let mut pool_reserved_amount = fuzz_data.pool_reserved_amount;
-    if pool_reserved_amount > MAX_POOL_RESERVED_AMOUNT {
-        pool_reserved_amount = MAX_POOL_RESERVED_AMOUNT;
-    }
-    let mut native_farm = NativeFarm::new(pool_reserved_amount);
- 
-    let original_amount = native_farm.get_deposited_amount().unwrap();
-    let mut deposited_amount: u64 = 0;
-    let mut withdrawn_amount: u64 = 0;
+    let pool_reserved_amount = fuzz_data.pool_reserved_amount.min(MAX_POOL_RESERVED_AMOUNT);
+    set_clock(0, 0);
+    let mut native_farm = NativeFarm::new(pool_reserved_amount, USER_TOKEN_AMOUNT);
+
+    let token_total = native_farm.get_pool_token_balance() + native_farm.get_user_token_balance();
+    let mut deposited_amount = native_farm.get_deposited_amount().unwrap();
+    let mut unix_timestamp = 0i64;
 
     /* run fuzz instructions with fuzzer inputed data */
     for fuzz_instruction in fuzz_data.instructions {
+        let result = run_fuzz_instruction(fuzz_instruction.clone(), &mut native_farm);
 
-        let result = run_fuzz_instruction(
-            fuzz_instruction.clone(),
-            &mut native_farm,
-        );
-
-        match fuzz_instruction {
-            FarmFuzzInstruction::Deposit {instruction_data} => {
-                match result {
-                    Ok(()) => {
-                        println!("Deposit Ok");
-                        deposited_amount = deposited_amount.checked_add(instruction_data.amount).unwrap();
-                        
-                    }
-                    Err(e) => {
-                        println!("{:?}", e);
-                        println!("Deposit Ok");
-                    }                        
-                };
-                
+        match (fuzz_instruction, result) {
+            (FarmFuzzInstruction::Deposit { instruction_data }, Ok(())) => {
+                deposited_amount = deposited_amount
+                    .checked_add(instruction_data.amount)
+                    .unwrap();
             }
-            FarmFuzzInstruction::Withdraw {instruction_data} => {
-                match result {
-                    Ok(()) => {
-                        println!("Withdraw Ok");
-                        withdrawn_amount = withdrawn_amount.checked_add(instruction_data.amount).unwrap();
-                    }
-                    Err(e) => {
-                        println!("{:?}", e);
-                    }                        
-                };
+            (FarmFuzzInstruction::Withdraw { instruction_data }, Ok(())) => {
+                deposited_amount = deposited_amount
+                    .checked_sub(instruction_data.amount)
+                    .unwrap();
             }
-            FarmFuzzInstruction::Refresh => {
+            (FarmFuzzInstruction::Tick { seconds }, _) => {
+                unix_timestamp += seconds as i64;
+                // About two slots a second
+                set_clock(unix_timestamp as u64 * 2, unix_timestamp);
             }
-    
+            (_, Err(e)) => println!("{:?}", e),
+            (FarmFuzzInstruction::Refresh, Ok(())) => {}
         };
-    }
-
-    /* verify amount */
-    let updated_amount = native_farm.get_deposited_amount().unwrap();
-    let mut latest_amount: u64 = 0;
 
-    latest_amount = latest_amount.checked_add(original_amount).unwrap();
-    latest_amount = latest_amount.checked_add(deposited_amount).unwrap();
-    latest_amount = latest_amount.checked_sub(withdrawn_amount).unwrap();
-    assert_eq!(
-        updated_amount,
-        latest_amount
-    );
-    
+        /* verify no lp tokens were created or lost, and the farm holds the stake */
+        assert_eq!(
+            native_farm.get_deposited_amount().unwrap(),
+            deposited_amount
+        );
+        assert_eq!(native_farm.get_reserved_amount().unwrap(), deposited_amount);
+        assert_eq!(native_farm.get_pool_token_balance(), deposited_amount);
+        assert_eq!(
+            native_farm.get_pool_token_balance() + native_farm.get_user_token_balance(),
+            token_total
+        );
+    }
 }
 
 fn run_fuzz_instruction(
     fuzz_instruction: FarmFuzzInstruction,
     native_farm: &mut NativeFarm,
 ) -> ProgramResult {
-    let result = match fuzz_instruction {
-        FarmFuzzInstruction::Deposit {instruction_data} => {
+    match fuzz_instruction {
+        FarmFuzzInstruction::Deposit { instruction_data } => {
             native_farm.run_farm_deposit(instruction_data)
         }
-        FarmFuzzInstruction::Withdraw {instruction_data} => {
+        FarmFuzzInstruction::Withdraw { instruction_data } => {
             native_farm.run_farm_withdraw(instruction_data)
         }
-        FarmFuzzInstruction::Refresh => {
-            native_farm.run_farm_refresh()
-        }
-
-    };
-    result
+        FarmFuzzInstruction::Refresh => native_farm.run_farm_refresh(),
+        FarmFuzzInstruction::Tick { .. } => Ok(()),
+    }
 }
@@ -0,0 +1,188 @@
+use {
+    arbitrary::Arbitrary,
+    deltafi_swap::{
+        curve::SwapDirection,
+        instruction::{DepositData, SwapData, WithdrawData},
+        math::WAD,
+        state::{Decimal, Fees, Rewards},
+    },
+    deltafi_swap_fuzz::{native_processor::set_clock, native_stable_swap::NativeStableSwap},
+    honggfuzz::fuzz,
+};
+
+use solana_program::entrypoint::ProgramResult;
+
+#[derive(Debug, Arbitrary, Clone)]
+struct StableSwapFuzzData {
+    token_a_amount: u64,
+    token_b_amount: u64,
+    slope: u64,
+    instructions: Vec<StableSwapFuzzInstruction>,
+}
+
+#[derive(Debug, Arbitrary, Clone)]
+enum StableSwapFuzzInstruction {
+    Swap {
+        swap_direction: SwapDirection,
+        instruction_data: SwapData,
+    },
+    Deposit {
+        instruction_data: DepositData,
+    },
+    Withdraw {
+        instruction_data: WithdrawData,
+    },
+    Pause,
+    Unpause,
+    SetNewFees {
+        trade_fee_numerator: u16,
+        admin_trade_fee_numerator: u16,
+        withdraw_fee_numerator: u16,
+        admin_withdraw_fee_numerator: u16,
+    },
+    Tick {
+        slots: u16,
+    },
+}
+
+const MIN_POOL_TOKEN_AMOUNT: u64 = 1_000;
+const MAX_POOL_TOKEN_AMOUNT: u64 = 1_000_000_000_000;
+const USER_TOKEN_AMOUNT: u64 = 1_000_000_000_000;
+const MIN_SLOPE: u64 = 10_000_000_000;
+const FEE_DENOMINATOR: u64 = 10_000;
+
+fn main() {
+    loop {
+        fuzz!(|fuzz_data: StableSwapFuzzData| { run_stable_swap_fuzz(fuzz_data) });
+    }
+}
+
+fn fees(
+    trade_fee_numerator: u64,
+    admin_trade_fee_numerator: u64,
+    withdraw_fee_numerator: u64,
+    admin_withdraw_fee_numerator: u64,
+) -> Fees {
+    Fees {
+        is_initialized: true,
+        admin_trade_fee_numerator,
+        admin_trade_fee_denominator: FEE_DENOMINATOR,
+        admin_withdraw_fee_numerator,
+        admin_withdraw_fee_denominator: FEE_DENOMINATOR,
+        trade_fee_numerator,
+        trade_fee_denominator: FEE_DENOMINATOR,
+        withdraw_fee_numerator,
+        withdraw_fee_denominator: FEE_DENOMINATOR,
+        flash_fee_denominator: FEE_DENOMINATOR,
+        treasury_fee_denominator: FEE_DENOMINATOR,
+        ..Default::default()
+    }
+}
+
+fn run_stable_swap_fuzz(fuzz_data: StableSwapFuzzData) {
+    /*  Create a stable pool balanced by its initial deposit, the user holding all the
+        pool tokens and some of both tokens to trade
+    */
+    let token_a_amount = fuzz_data
+        .token_a_amount
+        .clamp(MIN_POOL_TOKEN_AMOUNT, MAX_POOL_TOKEN_AMOUNT);
+    let token_b_amount = fuzz_data
+        .token_b_amount
+        .clamp(MIN_POOL_TOKEN_AMOUNT, MAX_POOL_TOKEN_AMOUNT);
+    let slope = Decimal::from_scaled_val(fuzz_data.slope.clamp(MIN_SLOPE, WAD).into());
+    let rewards = Rewards {
+        is_initialized: true,
+        trade_reward_numerator: 1,
+        trade_reward_denominator: 1_000,
+        trade_reward_cap: 10_000,
+        ..Default::default()
+    };
+    set_clock(0, 0);
+    let mut native_swap = NativeStableSwap::new(
+        token_a_amount,
+        token_b_amount,
+        USER_TOKEN_AMOUNT,
+        slope,
+        fees(5, 5_000, 10, 5_000),
+        rewards,
+    );
+
+    let token_a_total = native_swap.get_token_a_total();
+    let token_b_total = native_swap.get_token_b_total();
+    let reward_total = native_swap.get_reward_total();
+    let mut slot = 0u64;
+
+    /* run fuzz instructions with fuzzer inputed data */
+    for fuzz_instruction in fuzz_data.instructions {
+        if let StableSwapFuzzInstruction::Tick { slots } = fuzz_instruction {
+            slot += slots as u64;
+            set_clock(slot, (slot / 2) as i64);
+            continue;
+        }
+
+        let is_paused = native_swap.get_swap_info().unwrap().is_paused;
+        let is_trade = matches!(
+            fuzz_instruction,
+            StableSwapFuzzInstruction::Swap { .. }
+                | StableSwapFuzzInstruction::Deposit { .. }
+                | StableSwapFuzzInstruction::Withdraw { .. }
+        );
+        let result = run_fuzz_instruction(fuzz_instruction, &mut native_swap);
+        if let Err(e) = &result {
+            println!("{:?}", e);
+        }
+        if is_paused && is_trade {
+            assert!(result.is_err());
+        }
+
+        /* verify no tokens were created or lost, failed instructions included */
+        assert_eq!(native_swap.get_token_a_total(), token_a_total);
+        assert_eq!(native_swap.get_token_b_total(), token_b_total);
+        assert_eq!(native_swap.get_reward_total(), reward_total);
+
+        /* verify the pool tokens and the reserves match the pool state */
+        let swap_info = native_swap.get_swap_info().unwrap();
+        let pool_mint_supply = native_swap.get_pool_mint_supply();
+        assert_eq!(native_swap.get_user_pool_token_balance(), pool_mint_supply);
+        assert_eq!(swap_info.pool_state.total_supply, pool_mint_supply);
+        swap_info
+            .pool_state
+            .check_reserve_amount(
+                native_swap.get_token_a_balance(),
+                native_swap.get_token_b_balance(),
+            )
+            .unwrap();
+    }
+}
+
+fn run_fuzz_instruction(
+    fuzz_instruction: StableSwapFuzzInstruction,
+    native_swap: &mut NativeStableSwap,
+) -> ProgramResult {
+    match fuzz_instruction {
+        StableSwapFuzzInstruction::Swap {
+            swap_direction,
+            instruction_data,
+        } => native_swap.run_swap(swap_direction, instruction_data),
+        StableSwapFuzzInstruction::Deposit { instruction_data } => {
+            native_swap.run_deposit(instruction_data)
+        }
+        StableSwapFuzzInstruction::Withdraw { instruction_data } => {
+            native_swap.run_withdraw(instruction_data)
+        }
+        StableSwapFuzzInstruction::Pause => native_swap.run_pause(),
+        StableSwapFuzzInstruction::Unpause => native_swap.run_unpause(),
+        StableSwapFuzzInstruction::SetNewFees {
+            trade_fee_numerator,
+            admin_trade_fee_numerator,
+            withdraw_fee_numerator,
+            admin_withdraw_fee_numerator,
+        } => native_swap.run_set_new_fees(fees(
+            trade_fee_numerator as u64,
+            admin_trade_fee_numerator as u64,
+            withdraw_fee_numerator as u64,
+            admin_withdraw_fee_numerator as u64,
+        )),
+        StableSwapFuzzInstruction::Tick { .. } => Ok(()),
+    }
+}
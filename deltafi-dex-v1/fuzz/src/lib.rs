@@ -1,4 +1,5 @@
 pub mod native_account_data;
 pub mod native_processor;
 pub mod native_token;
-pub mod native_farm;
\ No newline at end of file
+pub mod native_farm;
+pub mod native_stable_swap;
\ No newline at end of file
@@ -1,12 +1,44 @@
 use crate::native_account_data::NativeAccountData;
 
 use solana_program::{
-    account_info::AccountInfo, entrypoint::ProgramResult, instruction::Instruction,
-    program_error::ProgramError, program_stubs, pubkey::Pubkey,
+    account_info::AccountInfo,
+    clock::Clock,
+    entrypoint::{ProgramResult, SUCCESS},
+    instruction::Instruction,
+    program_error::ProgramError,
+    program_stubs,
+    pubkey::Pubkey,
 };
+use std::sync::atomic::{AtomicI64, AtomicU64, Ordering};
+
+// Clock of the simulated bank, moved forward by the fuzz targets between instructions
+static CLOCK_SLOT: AtomicU64 = AtomicU64::new(0);
+static CLOCK_UNIX_TIMESTAMP: AtomicI64 = AtomicI64::new(0);
+
+/// Set the clock sysvar returned to the program
+pub fn set_clock(slot: u64, unix_timestamp: i64) {
+    CLOCK_SLOT.store(slot, Ordering::Relaxed);
+    CLOCK_UNIX_TIMESTAMP.store(unix_timestamp, Ordering::Relaxed);
+}
+
+/// Clock sysvar returned to the program
+pub fn get_clock() -> Clock {
+    Clock {
+        slot: CLOCK_SLOT.load(Ordering::Relaxed),
+        unix_timestamp: CLOCK_UNIX_TIMESTAMP.load(Ordering::Relaxed),
+        ..Clock::default()
+    }
+}
 
 struct TestSyscallStubs {}
 impl program_stubs::SyscallStubs for TestSyscallStubs {
+    fn sol_get_clock_sysvar(&self, var_addr: *mut u8) -> u64 {
+        unsafe {
+            *(var_addr as *mut Clock) = get_clock();
+        }
+        SUCCESS
+    }
+
     fn sol_invoke_signed(
         &self,
         instruction: &Instruction,
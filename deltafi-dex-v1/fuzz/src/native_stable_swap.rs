@@ -0,0 +1,494 @@
+//! Helpers for working with stable swaps in a fuzzing environment
+#![allow(clippy::too_many_arguments)]
+use crate::native_account_data::NativeAccountData;
+use crate::native_farm::create_program_account;
+use crate::native_processor::do_process_instruction;
+use crate::native_token;
+
+use solana_program::{
+    entrypoint::ProgramResult, instruction::Instruction, program_error::ProgramError,
+    program_pack::Pack, pubkey::Pubkey, system_program,
+};
+
+use deltafi_swap::{
+    curve::{InitPoolStateParams, PoolState, SwapDirection},
+    instruction::{self, DepositData, SwapData, WithdrawData},
+    state::{
+        ConfigInfo, Decimal, Fees, Rewards, SwapInfo, SwapType, PROGRAM_VERSION, SWAP_INFO_VERSION,
+    },
+};
+use spl_token::{instruction::approve, state::Mint};
+
+/// Deltafi tokens the market authority holds to pay the trade rewards
+const REWARD_SOURCE_AMOUNT: u64 = 1_000_000_000_000_000;
+
+pub struct NativeStableSwap {
+    pub config_account: NativeAccountData,
+    pub swap_account: NativeAccountData,
+    pub market_authority_account: NativeAccountData,
+    pub swap_authority_account: NativeAccountData,
+    pub admin_account: NativeAccountData,
+    pub user_account: NativeAccountData,
+    pub token_a_mint_account: NativeAccountData,
+    pub token_b_mint_account: NativeAccountData,
+    pub pool_mint_account: NativeAccountData,
+    pub deltafi_mint_account: NativeAccountData,
+    pub token_a_account: NativeAccountData,
+    pub token_b_account: NativeAccountData,
+    pub admin_fee_a_account: NativeAccountData,
+    pub admin_fee_b_account: NativeAccountData,
+    pub reward_source_account: NativeAccountData,
+    pub user_reward_account: NativeAccountData,
+    pub user_token_a_account: NativeAccountData,
+    pub user_token_b_account: NativeAccountData,
+    pub user_pool_token_account: NativeAccountData,
+    pub token_program_account: NativeAccountData,
+    pub program_id: Pubkey,
+}
+
+impl NativeStableSwap {
+    /// Stable pool initialized with `token_a_amount` and `token_b_amount` by the user, who
+    /// holds all the pool tokens and `user_token_amount` of both tokens left to trade
+    pub fn new(
+        token_a_amount: u64,
+        token_b_amount: u64,
+        user_token_amount: u64,
+        slope: Decimal,
+        fees: Fees,
+        rewards: Rewards,
+    ) -> Self {
+        let program_id = deltafi_swap::id();
+        let token_program_account = create_program_account(spl_token::id());
+
+        let mut admin_account = NativeAccountData::new(0, system_program::id());
+        admin_account.is_signer = true;
+        let mut user_account = NativeAccountData::new(0, system_program::id());
+        user_account.is_signer = true;
+
+        let mut config_account = NativeAccountData::new(ConfigInfo::LEN, program_id);
+        let (market_authority_key, config_bump_seed) =
+            Pubkey::find_program_address(&[&config_account.key.to_bytes()[..]], &program_id);
+        let market_authority_account = create_program_account(market_authority_key);
+
+        let mut swap_account = NativeAccountData::new(SwapInfo::LEN, program_id);
+        let (swap_authority_key, nonce) =
+            Pubkey::find_program_address(&[&swap_account.key.to_bytes()[..]], &program_id);
+        let swap_authority_account = create_program_account(swap_authority_key);
+
+        let mut deltafi_mint_account = native_token::create_mint(&admin_account.key);
+        let reward_source_account = native_token::create_token_account(
+            &mut deltafi_mint_account,
+            &market_authority_account.key,
+            REWARD_SOURCE_AMOUNT,
+        );
+        let user_reward_account =
+            native_token::create_token_account(&mut deltafi_mint_account, &user_account.key, 0);
+
+        let config_info = ConfigInfo {
+            version: PROGRAM_VERSION,
+            bump_seed: config_bump_seed,
+            admin_key: admin_account.key,
+            deltafi_mint: deltafi_mint_account.key,
+            fees: fees.clone(),
+            rewards: rewards.clone(),
+            ..Default::default()
+        };
+        ConfigInfo::pack(config_info, &mut config_account.data).unwrap();
+
+        // Same pool state as the stable swap initialization
+        let mut pool_state = PoolState::new(InitPoolStateParams {
+            market_price: Decimal::one(),
+            slope,
+            base_reserve: Decimal::zero(),
+            quote_reserve: Decimal::zero(),
+            total_supply: 0,
+            last_market_price: Decimal::one(),
+            last_valid_market_price_slot: 0,
+        });
+        pool_state.set_market_price(0, 0, Decimal::one()).unwrap();
+        let (mint_amount, token_a_amount, token_b_amount) = pool_state
+            .buy_shares(token_a_amount, token_b_amount)
+            .unwrap();
+
+        let mut token_a_mint_account = native_token::create_mint(&admin_account.key);
+        let mut token_b_mint_account = native_token::create_mint(&admin_account.key);
+        let mut pool_mint_account = native_token::create_mint(&swap_authority_account.key);
+        let token_a_account = native_token::create_token_account(
+            &mut token_a_mint_account,
+            &swap_authority_account.key,
+            token_a_amount,
+        );
+        let token_b_account = native_token::create_token_account(
+            &mut token_b_mint_account,
+            &swap_authority_account.key,
+            token_b_amount,
+        );
+        let admin_fee_a_account =
+            native_token::create_token_account(&mut token_a_mint_account, &admin_account.key, 0);
+        let admin_fee_b_account =
+            native_token::create_token_account(&mut token_b_mint_account, &admin_account.key, 0);
+        let user_token_a_account = native_token::create_token_account(
+            &mut token_a_mint_account,
+            &user_account.key,
+            user_token_amount,
+        );
+        let user_token_b_account = native_token::create_token_account(
+            &mut token_b_mint_account,
+            &user_account.key,
+            user_token_amount,
+        );
+        let user_pool_token_account = native_token::create_token_account(
+            &mut pool_mint_account,
+            &user_account.key,
+            mint_amount,
+        );
+
+        let swap_info = SwapInfo {
+            is_initialized: true,
+            nonce,
+            swap_type: SwapType::Stable,
+            config_key: config_account.key,
+            token_a: token_a_account.key,
+            token_b: token_b_account.key,
+            pool_mint: pool_mint_account.key,
+            token_a_mint: token_a_mint_account.key,
+            token_b_mint: token_b_mint_account.key,
+            admin_fee_key_a: admin_fee_a_account.key,
+            admin_fee_key_b: admin_fee_b_account.key,
+            fees,
+            rewards,
+            pool_state,
+            version: SWAP_INFO_VERSION,
+            ..Default::default()
+        };
+        SwapInfo::pack(swap_info, &mut swap_account.data).unwrap();
+
+        Self {
+            config_account,
+            swap_account,
+            market_authority_account,
+            swap_authority_account,
+            admin_account,
+            user_account,
+            token_a_mint_account,
+            token_b_mint_account,
+            pool_mint_account,
+            deltafi_mint_account,
+            token_a_account,
+            token_b_account,
+            admin_fee_a_account,
+            admin_fee_b_account,
+            reward_source_account,
+            user_reward_account,
+            user_token_a_account,
+            user_token_b_account,
+            user_pool_token_account,
+            token_program_account,
+            program_id,
+        }
+    }
+
+    pub fn get_swap_info(&self) -> Result<SwapInfo, ProgramError> {
+        SwapInfo::unpack(&self.swap_account.data)
+    }
+
+    pub fn get_token_a_balance(&self) -> u64 {
+        native_token::get_token_balance(&self.token_a_account)
+    }
+
+    pub fn get_token_b_balance(&self) -> u64 {
+        native_token::get_token_balance(&self.token_b_account)
+    }
+
+    pub fn get_user_pool_token_balance(&self) -> u64 {
+        native_token::get_token_balance(&self.user_pool_token_account)
+    }
+
+    /// Token A held by the user, the pool and the admin
+    pub fn get_token_a_total(&self) -> u64 {
+        native_token::get_token_balance(&self.user_token_a_account)
+            + native_token::get_token_balance(&self.token_a_account)
+            + native_token::get_token_balance(&self.admin_fee_a_account)
+    }
+
+    /// Token B held by the user, the pool and the admin
+    pub fn get_token_b_total(&self) -> u64 {
+        native_token::get_token_balance(&self.user_token_b_account)
+            + native_token::get_token_balance(&self.token_b_account)
+            + native_token::get_token_balance(&self.admin_fee_b_account)
+    }
+
+    /// Deltafi tokens held by the market authority and the user
+    pub fn get_reward_total(&self) -> u64 {
+        native_token::get_token_balance(&self.reward_source_account)
+            + native_token::get_token_balance(&self.user_reward_account)
+    }
+
+    pub fn get_pool_mint_supply(&self) -> u64 {
+        Mint::unpack(&self.pool_mint_account.data).unwrap().supply
+    }
+
+    fn approve(
+        token_program_account: &NativeAccountData,
+        source_account: &mut NativeAccountData,
+        user_transfer_account: &mut NativeAccountData,
+        owner_account: &mut NativeAccountData,
+        amount: u64,
+    ) {
+        do_process_instruction(
+            approve(
+                &token_program_account.key,
+                &source_account.key,
+                &user_transfer_account.key,
+                &owner_account.key,
+                &[],
+                amount,
+            )
+            .unwrap(),
+            &[
+                source_account.as_account_info(),
+                user_transfer_account.as_account_info(),
+                owner_account.as_account_info(),
+            ],
+        )
+        .unwrap();
+    }
+
+    pub fn run_swap(
+        &mut self,
+        swap_direction: SwapDirection,
+        instruction_data: SwapData,
+    ) -> ProgramResult {
+        let mut user_transfer_account = NativeAccountData::new(0, system_program::id());
+        user_transfer_account.is_signer = true;
+
+        let (
+            source_account,
+            swap_source_account,
+            source_mint_account,
+            swap_destination_account,
+            destination_account,
+            destination_mint_account,
+            admin_fee_account,
+        ) = match swap_direction {
+            SwapDirection::SellBase => (
+                &mut self.user_token_a_account,
+                &mut self.token_a_account,
+                &mut self.token_a_mint_account,
+                &mut self.token_b_account,
+                &mut self.user_token_b_account,
+                &mut self.token_b_mint_account,
+                &mut self.admin_fee_b_account,
+            ),
+            SwapDirection::SellQuote => (
+                &mut self.user_token_b_account,
+                &mut self.token_b_account,
+                &mut self.token_b_mint_account,
+                &mut self.token_a_account,
+                &mut self.user_token_a_account,
+                &mut self.token_a_mint_account,
+                &mut self.admin_fee_a_account,
+            ),
+        };
+
+        Self::approve(
+            &self.token_program_account,
+            source_account,
+            &mut user_transfer_account,
+            &mut self.user_account,
+            instruction_data.amount_in,
+        );
+
+        let swap_instruction = instruction::stable_swap(
+            self.program_id,
+            self.config_account.key,
+            self.swap_account.key,
+            self.market_authority_account.key,
+            self.swap_authority_account.key,
+            user_transfer_account.key,
+            source_account.key,
+            swap_source_account.key,
+            source_mint_account.key,
+            swap_destination_account.key,
+            destination_account.key,
+            destination_mint_account.key,
+            self.user_reward_account.key,
+            self.reward_source_account.key,
+            admin_fee_account.key,
+            None,
+            None,
+            None,
+            None,
+            None,
+            instruction_data,
+        )
+        .unwrap();
+
+        do_process_instruction(
+            swap_instruction,
+            &[
+                self.config_account.as_account_info(),
+                self.swap_account.as_account_info(),
+                self.market_authority_account.as_account_info(),
+                self.swap_authority_account.as_account_info(),
+                user_transfer_account.as_account_info(),
+                source_account.as_account_info(),
+                swap_source_account.as_account_info(),
+                source_mint_account.as_account_info(),
+                swap_destination_account.as_account_info(),
+                destination_account.as_account_info(),
+                destination_mint_account.as_account_info(),
+                self.user_reward_account.as_account_info(),
+                self.reward_source_account.as_account_info(),
+                admin_fee_account.as_account_info(),
+                self.token_program_account.as_account_info(),
+            ],
+        )
+    }
+
+    pub fn run_deposit(&mut self, instruction_data: DepositData) -> ProgramResult {
+        let mut user_transfer_account = NativeAccountData::new(0, system_program::id());
+        user_transfer_account.is_signer = true;
+
+        Self::approve(
+            &self.token_program_account,
+            &mut self.user_token_a_account,
+            &mut user_transfer_account,
+            &mut self.user_account,
+            instruction_data.token_a_amount,
+        );
+        Self::approve(
+            &self.token_program_account,
+            &mut self.user_token_b_account,
+            &mut user_transfer_account,
+            &mut self.user_account,
+            instruction_data.token_b_amount,
+        );
+
+        let deposit_instruction = instruction::stable_deposit(
+            self.program_id,
+            self.swap_account.key,
+            self.swap_authority_account.key,
+            user_transfer_account.key,
+            self.user_token_a_account.key,
+            self.user_token_b_account.key,
+            self.token_a_account.key,
+            self.token_b_account.key,
+            self.pool_mint_account.key,
+            self.user_pool_token_account.key,
+            instruction_data,
+        )
+        .unwrap();
+
+        do_process_instruction(
+            deposit_instruction,
+            &[
+                self.swap_account.as_account_info(),
+                self.swap_authority_account.as_account_info(),
+                user_transfer_account.as_account_info(),
+                self.user_token_a_account.as_account_info(),
+                self.user_token_b_account.as_account_info(),
+                self.token_a_account.as_account_info(),
+                self.token_b_account.as_account_info(),
+                self.pool_mint_account.as_account_info(),
+                self.user_pool_token_account.as_account_info(),
+                self.token_program_account.as_account_info(),
+                self.token_program_account.as_account_info(),
+                self.token_program_account.as_account_info(),
+            ],
+        )
+    }
+
+    pub fn run_withdraw(&mut self, instruction_data: WithdrawData) -> ProgramResult {
+        let mut user_transfer_account = NativeAccountData::new(0, system_program::id());
+        user_transfer_account.is_signer = true;
+
+        Self::approve(
+            &self.token_program_account,
+            &mut self.user_pool_token_account,
+            &mut user_transfer_account,
+            &mut self.user_account,
+            instruction_data.pool_token_amount,
+        );
+
+        let withdraw_instruction = instruction::stable_withdraw(
+            self.program_id,
+            self.swap_account.key,
+            self.swap_authority_account.key,
+            user_transfer_account.key,
+            self.pool_mint_account.key,
+            self.user_pool_token_account.key,
+            self.token_a_account.key,
+            self.token_b_account.key,
+            self.user_token_a_account.key,
+            self.user_token_b_account.key,
+            self.admin_fee_a_account.key,
+            self.admin_fee_b_account.key,
+            instruction_data,
+        )
+        .unwrap();
+
+        do_process_instruction(
+            withdraw_instruction,
+            &[
+                self.swap_account.as_account_info(),
+                self.swap_authority_account.as_account_info(),
+                user_transfer_account.as_account_info(),
+                self.pool_mint_account.as_account_info(),
+                self.user_pool_token_account.as_account_info(),
+                self.token_a_account.as_account_info(),
+                self.token_b_account.as_account_info(),
+                self.user_token_a_account.as_account_info(),
+                self.user_token_b_account.as_account_info(),
+                self.admin_fee_a_account.as_account_info(),
+                self.admin_fee_b_account.as_account_info(),
+                self.token_program_account.as_account_info(),
+            ],
+        )
+    }
+
+    pub fn run_pause(&mut self) -> ProgramResult {
+        let pause_instruction = instruction::pause(
+            self.program_id,
+            self.config_account.key,
+            self.swap_account.key,
+            self.admin_account.key,
+        )
+        .unwrap();
+        self.run_admin_instruction(pause_instruction)
+    }
+
+    pub fn run_unpause(&mut self) -> ProgramResult {
+        let unpause_instruction = instruction::unpause(
+            self.program_id,
+            self.config_account.key,
+            self.swap_account.key,
+            self.admin_account.key,
+        )
+        .unwrap();
+        self.run_admin_instruction(unpause_instruction)
+    }
+
+    pub fn run_set_new_fees(&mut self, new_fees: Fees) -> ProgramResult {
+        let set_new_fees_instruction = instruction::set_new_fees(
+            self.program_id,
+            self.config_account.key,
+            self.swap_account.key,
+            self.admin_account.key,
+            new_fees,
+        )
+        .unwrap();
+        self.run_admin_instruction(set_new_fees_instruction)
+    }
+
+    fn run_admin_instruction(&mut self, admin_instruction: Instruction) -> ProgramResult {
+        do_process_instruction(
+            admin_instruction,
+            &[
+                self.config_account.as_account_info(),
+                self.swap_account.as_account_info(),
+                self.admin_account.as_account_info(),
+            ],
+        )
+    }
+}
@@ -0,0 +1,131 @@
+import { PublicKey } from '@solana/web3.js';
+import { struct, u8 } from 'buffer-layout';
+import BigNumber from 'bignumber.js';
+
+import { decimal, publicKey, u64 } from './util';
+
+export const EVENT_VERSION = 1;
+
+export enum EventType {
+  Swap = 0,
+  Deposit,
+  Withdraw,
+  FarmDeposit,
+  FarmWithdraw,
+  FarmClaim,
+  FarmCompound,
+}
+
+export interface SwapEvent {
+  swap: PublicKey;
+  user: PublicKey;
+  direction: number;
+  amountIn: bigint;
+  amountOut: bigint;
+  tradeFee: bigint;
+  adminFee: bigint;
+  marketPrice: BigNumber;
+}
+
+/** @internal */
+export const SwapEventLayout = struct<SwapEvent>(
+  [
+    publicKey('swap'),
+    publicKey('user'),
+    u8('direction'),
+    u64('amountIn'),
+    u64('amountOut'),
+    u64('tradeFee'),
+    u64('adminFee'),
+    decimal('marketPrice'),
+  ],
+  'swapEvent'
+);
+
+export interface LiquidityEvent {
+  swap: PublicKey;
+  user: PublicKey;
+  tokenAAmount: bigint;
+  tokenBAmount: bigint;
+  poolTokenAmount: bigint;
+  feeA: bigint;
+  feeB: bigint;
+  adminFeeA: bigint;
+  adminFeeB: bigint;
+  marketPrice: BigNumber;
+}
+
+/** @internal */
+export const LiquidityEventLayout = struct<LiquidityEvent>(
+  [
+    publicKey('swap'),
+    publicKey('user'),
+    u64('tokenAAmount'),
+    u64('tokenBAmount'),
+    u64('poolTokenAmount'),
+    u64('feeA'),
+    u64('feeB'),
+    u64('adminFeeA'),
+    u64('adminFeeB'),
+    decimal('marketPrice'),
+  ],
+  'liquidityEvent'
+);
+
+export interface FarmEvent {
+  farmPool: PublicKey;
+  farmUser: PublicKey;
+  amount: bigint;
+}
+
+/** @internal */
+export const FarmEventLayout = struct<FarmEvent>(
+  [publicKey('farmPool'), publicKey('farmUser'), u64('amount')],
+  'farmEvent'
+);
+
+export type DeltafiEvent =
+  | { type: EventType.Swap; data: SwapEvent }
+  | { type: EventType.Deposit | EventType.Withdraw; data: LiquidityEvent }
+  | {
+      type: EventType.FarmDeposit | EventType.FarmWithdraw | EventType.FarmClaim | EventType.FarmCompound;
+      data: FarmEvent;
+    };
+
+const PROGRAM_DATA_PREFIX = 'Program data: ';
+
+/**
+ * Decode an event logged by the program, events of another version are skipped
+ */
+export const decodeEvent = (buffer: Buffer): DeltafiEvent | undefined => {
+  if (buffer.length < 2 || buffer[0] !== EVENT_VERSION) return;
+
+  const data = buffer.slice(2);
+  switch (buffer[1]) {
+    case EventType.Swap:
+      return { type: EventType.Swap, data: SwapEventLayout.decode(data) };
+    case EventType.Deposit:
+    case EventType.Withdraw:
+      return { type: buffer[1], data: LiquidityEventLayout.decode(data) };
+    case EventType.FarmDeposit:
+    case EventType.FarmWithdraw:
+    case EventType.FarmClaim:
+    case EventType.FarmCompound:
+      return { type: buffer[1], data: FarmEventLayout.decode(data) };
+    default:
+      return;
+  }
+};
+
+/**
+ * Parse the program events from the log messages of a transaction
+ */
+export const parseEvents = (logMessages: string[]): DeltafiEvent[] => {
+  const events: DeltafiEvent[] = [];
+  for (const log of logMessages) {
+    if (!log.startsWith(PROGRAM_DATA_PREFIX)) continue;
+    const event = decodeEvent(Buffer.from(log.slice(PROGRAM_DATA_PREFIX.length), 'base64'));
+    if (event) events.push(event);
+  }
+  return events;
+};
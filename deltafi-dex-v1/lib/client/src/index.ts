@@ -3,3 +3,4 @@ export * from './state';
 export * from './transactions';
 export * from './instructions';
 export * from './util';
+export * from './events';
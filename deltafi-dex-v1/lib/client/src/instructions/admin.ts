@@ -1,9 +1,28 @@
-import { PublicKey, SYSVAR_RENT_PUBKEY, TransactionInstruction } from '@solana/web3.js';
+import {
+  PublicKey,
+  SystemProgram,
+  SYSVAR_CLOCK_PUBKEY,
+  SYSVAR_RENT_PUBKEY,
+  TransactionInstruction,
+} from '@solana/web3.js';
 import { TOKEN_PROGRAM_ID } from '@solana/spl-token';
-import { struct, u8 } from 'buffer-layout';
+import { seq, struct, u8, u16 } from 'buffer-layout';
+import BigNumber from 'bignumber.js';
 
-import { Fees, FeesLayout, Rewards, RewardsLayout, FarmRewards, FarmRewardsLayout } from '../state';
-import { publicKey } from '../util';
+import {
+  AdminRole,
+  Fees,
+  FeesLayout,
+  Rewards,
+  RewardsLayout,
+  FarmRewards,
+  FarmRewardsLayout,
+  OracleParams,
+  FeeDiscountTier,
+  FeeDiscountTierLayout,
+  MAX_FEE_DISCOUNT_TIERS,
+} from '../state';
+import { decimal, publicKey } from '../util';
 import { u64 } from '../util/layout';
 
 export enum AdminInstruction {
@@ -18,6 +37,50 @@ export enum AdminInstruction {
   SetNewSlope,
   SetDecimals,
   SetSwapLimit,
+  SetPriceJumpPolicy,
+  SetMaxPriceDivergence,
+  SetOrderbookProgram,
+  SetExchangeRateAdapter,
+  SetOracleParams,
+  SetMarketMaker,
+  RotateFeeAccount,
+  CommitInitialPrice,
+  CommitPeggedPrice,
+  ApplyPeggedPrice,
+  SetAdminRole,
+  PauseAll,
+  SetStrictDestinationOwner,
+  EnableFarmUserRegistry,
+  SetPauseFlags,
+  ClaimProtocolFees,
+  SetProtocolFee,
+  SetPoolAuthority,
+  SetMarketAuthority,
+  ClosePool,
+  SetOracleFeed,
+  EnableOracleFeedRegistry,
+  SetPriceCache,
+  CreateScratchAccount,
+  SetAdminOpsLimit,
+  SetFeeDiscountTiers,
+  SetProtocolFeeSwitch,
+  SyncPoolFromConfig,
+  SetConfigFees,
+  BatchSetFees,
+  BatchPause,
+  AddFarmRewardToken,
+  SetFarmRewardRate,
+  SetFarmEmissionRate,
+  FundStakingRewards,
+  SetRebalanceThreshold,
+  SetCircuitBreaker,
+  SetDepositCap,
+  SetWhitelistEnabled,
+  AddWhitelistEntry,
+  RemoveWhitelistEntry,
+  MigrateSwapInfo,
+  MigrateConfigInfo,
+  MigrateFarmInfo,
 }
 
 export interface AdminInitializeData {
@@ -362,3 +425,1317 @@ export const createSetSwapLimitInstruction = (
     programId,
   });
 };
+
+export const createSetPriceJumpPolicyInstruction = (
+  config: PublicKey,
+  tokenSwap: PublicKey,
+  adminKey: PublicKey,
+  priceJumpPolicy: number,
+  programId: PublicKey
+) => {
+  const keys = [
+    { pubkey: config, isSigner: false, isWritable: false },
+    { pubkey: tokenSwap, isSigner: false, isWritable: true },
+    { pubkey: adminKey, isSigner: true, isWritable: false },
+  ];
+  const dataLayout = struct([u8('instruction'), u8('priceJumpPolicy')]);
+  const data = Buffer.alloc(dataLayout.span);
+  dataLayout.encode(
+    {
+      instruction: AdminInstruction.SetPriceJumpPolicy,
+      priceJumpPolicy,
+    },
+    data
+  );
+
+  return new TransactionInstruction({
+    keys,
+    data,
+    programId,
+  });
+};
+
+export const createSetMaxPriceDivergenceInstruction = (
+  config: PublicKey,
+  tokenSwap: PublicKey,
+  adminKey: PublicKey,
+  maxPriceDivergence: number,
+  programId: PublicKey
+) => {
+  const keys = [
+    { pubkey: config, isSigner: false, isWritable: false },
+    { pubkey: tokenSwap, isSigner: false, isWritable: true },
+    { pubkey: adminKey, isSigner: true, isWritable: false },
+  ];
+  const dataLayout = struct([u8('instruction'), u16('maxPriceDivergence')]);
+  const data = Buffer.alloc(dataLayout.span);
+  dataLayout.encode(
+    {
+      instruction: AdminInstruction.SetMaxPriceDivergence,
+      maxPriceDivergence,
+    },
+    data
+  );
+
+  return new TransactionInstruction({
+    keys,
+    data,
+    programId,
+  });
+};
+
+export const createSetOrderbookProgramInstruction = (
+  config: PublicKey,
+  adminKey: PublicKey,
+  orderbookProgramId: PublicKey,
+  programId: PublicKey
+) => {
+  const keys = [
+    { pubkey: config, isSigner: false, isWritable: true },
+    { pubkey: adminKey, isSigner: true, isWritable: false },
+    { pubkey: orderbookProgramId, isSigner: false, isWritable: false },
+  ];
+  const dataLayout = struct([u8('instruction')]);
+  const data = Buffer.alloc(dataLayout.span);
+  dataLayout.encode(
+    {
+      instruction: AdminInstruction.SetOrderbookProgram,
+    },
+    data
+  );
+
+  return new TransactionInstruction({
+    keys,
+    data,
+    programId,
+  });
+};
+
+export const createSetExchangeRateAdapterInstruction = (
+  config: PublicKey,
+  tokenSwap: PublicKey,
+  adminKey: PublicKey,
+  exchangeRateAccount: PublicKey | null,
+  adapterType: number,
+  programId: PublicKey
+) => {
+  const keys = [
+    { pubkey: config, isSigner: false, isWritable: false },
+    { pubkey: tokenSwap, isSigner: false, isWritable: true },
+    { pubkey: adminKey, isSigner: true, isWritable: false },
+  ];
+  if (exchangeRateAccount) {
+    keys.push({ pubkey: exchangeRateAccount, isSigner: false, isWritable: false });
+  }
+  const dataLayout = struct([u8('instruction'), u8('adapterType')]);
+  const data = Buffer.alloc(dataLayout.span);
+  dataLayout.encode(
+    {
+      instruction: AdminInstruction.SetExchangeRateAdapter,
+      adapterType,
+    },
+    data
+  );
+
+  return new TransactionInstruction({
+    keys,
+    data,
+    programId,
+  });
+};
+
+export const createSetOracleParamsInstruction = (
+  config: PublicKey,
+  tokenSwap: PublicKey,
+  adminKey: PublicKey,
+  oracleParams: OracleParams,
+  programId: PublicKey
+) => {
+  const keys = [
+    { pubkey: config, isSigner: false, isWritable: false },
+    { pubkey: tokenSwap, isSigner: false, isWritable: true },
+    { pubkey: adminKey, isSigner: true, isWritable: false },
+  ];
+  const dataLayout = struct([
+    u8('instruction'),
+    u16('staleSlots'),
+    u16('maxConfidenceBps'),
+    u8('minPublishers'),
+  ]);
+  const data = Buffer.alloc(dataLayout.span);
+  dataLayout.encode(
+    {
+      instruction: AdminInstruction.SetOracleParams,
+      ...oracleParams,
+    },
+    data
+  );
+
+  return new TransactionInstruction({
+    keys,
+    data,
+    programId,
+  });
+};
+
+export const createSetMarketMakerInstruction = (
+  config: PublicKey,
+  adminKey: PublicKey,
+  marketMaker: PublicKey,
+  index: number,
+  programId: PublicKey
+) => {
+  const keys = [
+    { pubkey: config, isSigner: false, isWritable: true },
+    { pubkey: adminKey, isSigner: true, isWritable: false },
+    { pubkey: marketMaker, isSigner: false, isWritable: false },
+  ];
+  const dataLayout = struct([u8('instruction'), u8('index')]);
+  const data = Buffer.alloc(dataLayout.span);
+  dataLayout.encode(
+    {
+      instruction: AdminInstruction.SetMarketMaker,
+      index,
+    },
+    data
+  );
+
+  return new TransactionInstruction({
+    keys,
+    data,
+    programId,
+  });
+};
+
+export const createCommitPeggedPriceInstruction = (
+  config: PublicKey,
+  tokenSwap: PublicKey,
+  adminKey: PublicKey,
+  peggedPrice: BigNumber,
+  programId: PublicKey
+) => {
+  const keys = [
+    { pubkey: config, isSigner: false, isWritable: false },
+    { pubkey: tokenSwap, isSigner: false, isWritable: true },
+    { pubkey: adminKey, isSigner: true, isWritable: false },
+    { pubkey: SYSVAR_CLOCK_PUBKEY, isSigner: false, isWritable: false },
+  ];
+  const dataLayout = struct([u8('instruction'), decimal('peggedPrice')]);
+  const data = Buffer.alloc(dataLayout.span);
+  dataLayout.encode(
+    {
+      instruction: AdminInstruction.CommitPeggedPrice,
+      peggedPrice,
+    },
+    data
+  );
+
+  return new TransactionInstruction({
+    keys,
+    data,
+    programId,
+  });
+};
+
+export const createApplyPeggedPriceInstruction = (
+  config: PublicKey,
+  tokenSwap: PublicKey,
+  adminKey: PublicKey,
+  programId: PublicKey
+) => {
+  const keys = [
+    { pubkey: config, isSigner: false, isWritable: false },
+    { pubkey: tokenSwap, isSigner: false, isWritable: true },
+    { pubkey: adminKey, isSigner: true, isWritable: false },
+    { pubkey: SYSVAR_CLOCK_PUBKEY, isSigner: false, isWritable: false },
+  ];
+  const dataLayout = struct([u8('instruction')]);
+  const data = Buffer.alloc(dataLayout.span);
+  dataLayout.encode(
+    {
+      instruction: AdminInstruction.ApplyPeggedPrice,
+    },
+    data
+  );
+
+  return new TransactionInstruction({
+    keys,
+    data,
+    programId,
+  });
+};
+
+export const createSetAdminRoleInstruction = (
+  config: PublicKey,
+  adminKey: PublicKey,
+  roleKey: PublicKey,
+  role: AdminRole,
+  programId: PublicKey
+) => {
+  const keys = [
+    { pubkey: config, isSigner: false, isWritable: true },
+    { pubkey: adminKey, isSigner: true, isWritable: false },
+    { pubkey: roleKey, isSigner: false, isWritable: false },
+  ];
+  const dataLayout = struct([u8('instruction'), u8('role')]);
+  const data = Buffer.alloc(dataLayout.span);
+  dataLayout.encode(
+    {
+      instruction: AdminInstruction.SetAdminRole,
+      role,
+    },
+    data
+  );
+
+  return new TransactionInstruction({
+    keys,
+    data,
+    programId,
+  });
+};
+
+export const createPauseAllInstruction = (
+  config: PublicKey,
+  guardianKey: PublicKey,
+  tokenSwaps: PublicKey[],
+  programId: PublicKey
+) => {
+  const keys = [
+    { pubkey: config, isSigner: false, isWritable: false },
+    { pubkey: guardianKey, isSigner: true, isWritable: false },
+    ...tokenSwaps.map((tokenSwap) => ({ pubkey: tokenSwap, isSigner: false, isWritable: true })),
+  ];
+  const dataLayout = struct([u8('instruction')]);
+  const data = Buffer.alloc(dataLayout.span);
+  dataLayout.encode(
+    {
+      instruction: AdminInstruction.PauseAll,
+    },
+    data
+  );
+
+  return new TransactionInstruction({
+    keys,
+    data,
+    programId,
+  });
+};
+
+export const createSetStrictDestinationOwnerInstruction = (
+  config: PublicKey,
+  tokenSwap: PublicKey,
+  adminKey: PublicKey,
+  strictDestinationOwner: boolean,
+  programId: PublicKey
+) => {
+  const keys = [
+    { pubkey: config, isSigner: false, isWritable: false },
+    { pubkey: tokenSwap, isSigner: false, isWritable: true },
+    { pubkey: adminKey, isSigner: true, isWritable: false },
+  ];
+  const dataLayout = struct([u8('instruction'), u8('strictDestinationOwner')]);
+  const data = Buffer.alloc(dataLayout.span);
+  dataLayout.encode(
+    {
+      instruction: AdminInstruction.SetStrictDestinationOwner,
+      strictDestinationOwner: strictDestinationOwner ? 1 : 0,
+    },
+    data
+  );
+
+  return new TransactionInstruction({
+    keys,
+    data,
+    programId,
+  });
+};
+
+export const createEnableFarmUserRegistryInstruction = (
+  config: PublicKey,
+  farmPool: PublicKey,
+  adminKey: PublicKey,
+  programId: PublicKey
+) => {
+  const keys = [
+    { pubkey: config, isSigner: false, isWritable: false },
+    { pubkey: farmPool, isSigner: false, isWritable: true },
+    { pubkey: adminKey, isSigner: true, isWritable: false },
+  ];
+  const dataLayout = struct([u8('instruction')]);
+  const data = Buffer.alloc(dataLayout.span);
+  dataLayout.encode(
+    {
+      instruction: AdminInstruction.EnableFarmUserRegistry,
+    },
+    data
+  );
+
+  return new TransactionInstruction({
+    keys,
+    data,
+    programId,
+  });
+};
+
+export const createSetPauseFlagsInstruction = (
+  config: PublicKey,
+  tokenSwap: PublicKey,
+  adminKey: PublicKey,
+  pauseFlags: number,
+  programId: PublicKey
+) => {
+  const keys = [
+    { pubkey: config, isSigner: false, isWritable: false },
+    { pubkey: tokenSwap, isSigner: false, isWritable: true },
+    { pubkey: adminKey, isSigner: true, isWritable: false },
+  ];
+  const dataLayout = struct([u8('instruction'), u8('pauseFlags')]);
+  const data = Buffer.alloc(dataLayout.span);
+  dataLayout.encode(
+    {
+      instruction: AdminInstruction.SetPauseFlags,
+      pauseFlags,
+    },
+    data
+  );
+
+  return new TransactionInstruction({
+    keys,
+    data,
+    programId,
+  });
+};
+
+export const createClaimProtocolFeesInstruction = (
+  config: PublicKey,
+  tokenSwap: PublicKey,
+  authority: PublicKey,
+  adminKey: PublicKey,
+  tokenA: PublicKey,
+  tokenB: PublicKey,
+  adminFeeA: PublicKey,
+  adminFeeB: PublicKey,
+  programId: PublicKey
+) => {
+  const keys = [
+    { pubkey: config, isSigner: false, isWritable: false },
+    { pubkey: tokenSwap, isSigner: false, isWritable: true },
+    { pubkey: authority, isSigner: false, isWritable: false },
+    { pubkey: adminKey, isSigner: true, isWritable: false },
+    { pubkey: tokenA, isSigner: false, isWritable: true },
+    { pubkey: tokenB, isSigner: false, isWritable: true },
+    { pubkey: adminFeeA, isSigner: false, isWritable: true },
+    { pubkey: adminFeeB, isSigner: false, isWritable: true },
+    { pubkey: TOKEN_PROGRAM_ID, isSigner: false, isWritable: false },
+  ];
+  const dataLayout = struct([u8('instruction')]);
+  const data = Buffer.alloc(dataLayout.span);
+  dataLayout.encode(
+    {
+      instruction: AdminInstruction.ClaimProtocolFees,
+    },
+    data
+  );
+
+  return new TransactionInstruction({
+    keys,
+    data,
+    programId,
+  });
+};
+
+export const createSetProtocolFeeInstruction = (
+  config: PublicKey,
+  tokenSwap: PublicKey,
+  adminKey: PublicKey,
+  numerator: bigint,
+  denominator: bigint,
+  programId: PublicKey
+) => {
+  const keys = [
+    { pubkey: config, isSigner: false, isWritable: false },
+    { pubkey: tokenSwap, isSigner: false, isWritable: true },
+    { pubkey: adminKey, isSigner: true, isWritable: false },
+  ];
+  const dataLayout = struct([u8('instruction'), u64('numerator'), u64('denominator')]);
+  const data = Buffer.alloc(dataLayout.span);
+  dataLayout.encode(
+    {
+      instruction: AdminInstruction.SetProtocolFee,
+      numerator,
+      denominator,
+    },
+    data
+  );
+
+  return new TransactionInstruction({
+    keys,
+    data,
+    programId,
+  });
+};
+
+/// spl_token authority type index
+export enum TokenAuthorityType {
+  MintTokens = 0,
+  FreezeAccount,
+  AccountOwner,
+  CloseAccount,
+}
+
+export const createSetPoolAuthorityInstruction = (
+  config: PublicKey,
+  tokenSwap: PublicKey,
+  authority: PublicKey,
+  adminKey: PublicKey,
+  account: PublicKey,
+  newAuthority: PublicKey,
+  authorityType: TokenAuthorityType,
+  programId: PublicKey
+) => {
+  const keys = [
+    { pubkey: config, isSigner: false, isWritable: false },
+    { pubkey: tokenSwap, isSigner: false, isWritable: false },
+    { pubkey: authority, isSigner: false, isWritable: false },
+    { pubkey: adminKey, isSigner: true, isWritable: false },
+    { pubkey: account, isSigner: false, isWritable: true },
+    { pubkey: newAuthority, isSigner: false, isWritable: false },
+    { pubkey: TOKEN_PROGRAM_ID, isSigner: false, isWritable: false },
+  ];
+  const dataLayout = struct([u8('instruction'), u8('authorityType')]);
+  const data = Buffer.alloc(dataLayout.span);
+  dataLayout.encode(
+    {
+      instruction: AdminInstruction.SetPoolAuthority,
+      authorityType,
+    },
+    data
+  );
+
+  return new TransactionInstruction({
+    keys,
+    data,
+    programId,
+  });
+};
+
+export const createSetMarketAuthorityInstruction = (
+  config: PublicKey,
+  marketAuthority: PublicKey,
+  adminKey: PublicKey,
+  account: PublicKey,
+  newAuthority: PublicKey,
+  authorityType: TokenAuthorityType,
+  programId: PublicKey
+) => {
+  const keys = [
+    { pubkey: config, isSigner: false, isWritable: false },
+    { pubkey: marketAuthority, isSigner: false, isWritable: false },
+    { pubkey: adminKey, isSigner: true, isWritable: false },
+    { pubkey: account, isSigner: false, isWritable: true },
+    { pubkey: newAuthority, isSigner: false, isWritable: false },
+    { pubkey: TOKEN_PROGRAM_ID, isSigner: false, isWritable: false },
+  ];
+  const dataLayout = struct([u8('instruction'), u8('authorityType')]);
+  const data = Buffer.alloc(dataLayout.span);
+  dataLayout.encode(
+    {
+      instruction: AdminInstruction.SetMarketAuthority,
+      authorityType,
+    },
+    data
+  );
+
+  return new TransactionInstruction({
+    keys,
+    data,
+    programId,
+  });
+};
+
+export const createClosePoolInstruction = (
+  config: PublicKey,
+  tokenSwap: PublicKey,
+  authority: PublicKey,
+  adminKey: PublicKey,
+  poolMint: PublicKey,
+  tokenA: PublicKey,
+  tokenB: PublicKey,
+  destinationA: PublicKey,
+  destinationB: PublicKey,
+  programId: PublicKey
+) => {
+  const keys = [
+    { pubkey: config, isSigner: false, isWritable: false },
+    { pubkey: tokenSwap, isSigner: false, isWritable: true },
+    { pubkey: authority, isSigner: false, isWritable: false },
+    { pubkey: adminKey, isSigner: true, isWritable: true },
+    { pubkey: poolMint, isSigner: false, isWritable: true },
+    { pubkey: tokenA, isSigner: false, isWritable: true },
+    { pubkey: tokenB, isSigner: false, isWritable: true },
+    { pubkey: destinationA, isSigner: false, isWritable: true },
+    { pubkey: destinationB, isSigner: false, isWritable: true },
+    { pubkey: TOKEN_PROGRAM_ID, isSigner: false, isWritable: false },
+  ];
+  const dataLayout = struct([u8('instruction')]);
+  const data = Buffer.alloc(dataLayout.span);
+  dataLayout.encode(
+    {
+      instruction: AdminInstruction.ClosePool,
+    },
+    data
+  );
+
+  return new TransactionInstruction({
+    keys,
+    data,
+    programId,
+  });
+};
+
+export const createSetOracleFeedInstruction = (
+  config: PublicKey,
+  oracleFeed: PublicKey,
+  mint: PublicKey,
+  pythPrice: PublicKey,
+  serumMarket: PublicKey,
+  adminKey: PublicKey,
+  programId: PublicKey
+) => {
+  const keys = [
+    { pubkey: config, isSigner: false, isWritable: false },
+    { pubkey: oracleFeed, isSigner: false, isWritable: true },
+    { pubkey: mint, isSigner: false, isWritable: false },
+    { pubkey: pythPrice, isSigner: false, isWritable: false },
+    { pubkey: serumMarket, isSigner: false, isWritable: false },
+    { pubkey: adminKey, isSigner: true, isWritable: true },
+    { pubkey: SYSVAR_RENT_PUBKEY, isSigner: false, isWritable: false },
+    { pubkey: SystemProgram.programId, isSigner: false, isWritable: false },
+  ];
+  const dataLayout = struct([u8('instruction')]);
+  const data = Buffer.alloc(dataLayout.span);
+  dataLayout.encode(
+    {
+      instruction: AdminInstruction.SetOracleFeed,
+    },
+    data
+  );
+
+  return new TransactionInstruction({
+    keys,
+    data,
+    programId,
+  });
+};
+
+export const createEnableOracleFeedRegistryInstruction = (
+  config: PublicKey,
+  adminKey: PublicKey,
+  programId: PublicKey
+) => {
+  const keys = [
+    { pubkey: config, isSigner: false, isWritable: true },
+    { pubkey: adminKey, isSigner: true, isWritable: false },
+  ];
+  const dataLayout = struct([u8('instruction')]);
+  const data = Buffer.alloc(dataLayout.span);
+  dataLayout.encode(
+    {
+      instruction: AdminInstruction.EnableOracleFeedRegistry,
+    },
+    data
+  );
+
+  return new TransactionInstruction({
+    keys,
+    data,
+    programId,
+  });
+};
+
+export const createSetPriceCacheInstruction = (
+  config: PublicKey,
+  tokenSwap: PublicKey,
+  priceCache: PublicKey,
+  relayer: PublicKey,
+  adminKey: PublicKey,
+  programId: PublicKey
+) => {
+  const keys = [
+    { pubkey: config, isSigner: false, isWritable: false },
+    { pubkey: tokenSwap, isSigner: false, isWritable: false },
+    { pubkey: priceCache, isSigner: false, isWritable: true },
+    { pubkey: relayer, isSigner: false, isWritable: false },
+    { pubkey: adminKey, isSigner: true, isWritable: true },
+    { pubkey: SYSVAR_RENT_PUBKEY, isSigner: false, isWritable: false },
+    { pubkey: SystemProgram.programId, isSigner: false, isWritable: false },
+  ];
+  const dataLayout = struct([u8('instruction')]);
+  const data = Buffer.alloc(dataLayout.span);
+  dataLayout.encode(
+    {
+      instruction: AdminInstruction.SetPriceCache,
+    },
+    data
+  );
+
+  return new TransactionInstruction({
+    keys,
+    data,
+    programId,
+  });
+};
+
+export const createCreateScratchAccountInstruction = (
+  config: PublicKey,
+  scratchAccount: PublicKey,
+  scratchToken: PublicKey,
+  mint: PublicKey,
+  adminKey: PublicKey,
+  index: number,
+  programId: PublicKey
+) => {
+  const keys = [
+    { pubkey: config, isSigner: false, isWritable: false },
+    { pubkey: scratchAccount, isSigner: false, isWritable: true },
+    { pubkey: scratchToken, isSigner: false, isWritable: true },
+    { pubkey: mint, isSigner: false, isWritable: false },
+    { pubkey: adminKey, isSigner: true, isWritable: true },
+    { pubkey: SYSVAR_RENT_PUBKEY, isSigner: false, isWritable: false },
+    { pubkey: SystemProgram.programId, isSigner: false, isWritable: false },
+    { pubkey: TOKEN_PROGRAM_ID, isSigner: false, isWritable: false },
+  ];
+  const dataLayout = struct([u8('instruction'), u8('index')]);
+  const data = Buffer.alloc(dataLayout.span);
+  dataLayout.encode(
+    {
+      instruction: AdminInstruction.CreateScratchAccount,
+      index,
+    },
+    data
+  );
+
+  return new TransactionInstruction({
+    keys,
+    data,
+    programId,
+  });
+};
+
+/**
+ * Create an instruction setting the max admin instructions per epoch, signed by the guardian.
+ * While the max is set, the admin instructions take the config writable.
+ */
+export const createSetAdminOpsLimitInstruction = (
+  config: PublicKey,
+  guardianKey: PublicKey,
+  maxAdminOpsPerEpoch: number,
+  programId: PublicKey
+) => {
+  const keys = [
+    { pubkey: config, isSigner: false, isWritable: true },
+    { pubkey: guardianKey, isSigner: true, isWritable: false },
+  ];
+  const dataLayout = struct([u8('instruction'), u16('maxAdminOpsPerEpoch')]);
+  const data = Buffer.alloc(dataLayout.span);
+  dataLayout.encode(
+    {
+      instruction: AdminInstruction.SetAdminOpsLimit,
+      maxAdminOpsPerEpoch,
+    },
+    data
+  );
+
+  return new TransactionInstruction({
+    keys,
+    data,
+    programId,
+  });
+};
+
+/**
+ * Create an instruction setting the trade fee discount tiers of the DELTAFI stakers, signed
+ * by the fee manager paying for the tiers account on the first call
+ */
+export const createSetFeeDiscountTiersInstruction = (
+  config: PublicKey,
+  feeDiscountTiers: PublicKey,
+  feeManagerKey: PublicKey,
+  tiers: FeeDiscountTier[],
+  programId: PublicKey
+) => {
+  const keys = [
+    { pubkey: config, isSigner: false, isWritable: false },
+    { pubkey: feeDiscountTiers, isSigner: false, isWritable: true },
+    { pubkey: feeManagerKey, isSigner: true, isWritable: true },
+    { pubkey: SYSVAR_RENT_PUBKEY, isSigner: false, isWritable: false },
+    { pubkey: SystemProgram.programId, isSigner: false, isWritable: false },
+  ];
+  const dataLayout = struct([
+    u8('instruction'),
+    seq(FeeDiscountTierLayout(), MAX_FEE_DISCOUNT_TIERS, 'tiers'),
+  ]);
+  const data = Buffer.alloc(dataLayout.span);
+  dataLayout.encode(
+    {
+      instruction: AdminInstruction.SetFeeDiscountTiers,
+      tiers: Array.from(
+        { length: MAX_FEE_DISCOUNT_TIERS },
+        (_, i) => tiers[i] ?? { minStakedAmount: BigInt(0), discountBps: 0 }
+      ),
+    },
+    data
+  );
+
+  return new TransactionInstruction({
+    keys,
+    data,
+    programId,
+  });
+};
+
+export const createSetProtocolFeeSwitchInstruction = (
+  config: PublicKey,
+  adminKey: PublicKey,
+  treasury: PublicKey,
+  isProtocolFeeEnabled: boolean,
+  programId: PublicKey
+) => {
+  const keys = [
+    { pubkey: config, isSigner: false, isWritable: true },
+    { pubkey: adminKey, isSigner: true, isWritable: false },
+    { pubkey: treasury, isSigner: false, isWritable: false },
+  ];
+  const dataLayout = struct([u8('instruction'), u8('isProtocolFeeEnabled')]);
+  const data = Buffer.alloc(dataLayout.span);
+  dataLayout.encode(
+    {
+      instruction: AdminInstruction.SetProtocolFeeSwitch,
+      isProtocolFeeEnabled: isProtocolFeeEnabled ? 1 : 0,
+    },
+    data
+  );
+
+  return new TransactionInstruction({
+    keys,
+    data,
+    programId,
+  });
+};
+
+export const createSyncPoolFromConfigInstruction = (
+  config: PublicKey,
+  adminKey: PublicKey,
+  tokenSwaps: PublicKey[],
+  includePinned: boolean,
+  programId: PublicKey
+) => {
+  const keys = [
+    { pubkey: config, isSigner: false, isWritable: false },
+    { pubkey: adminKey, isSigner: true, isWritable: false },
+    ...tokenSwaps.map((tokenSwap) => ({ pubkey: tokenSwap, isSigner: false, isWritable: true })),
+  ];
+  const dataLayout = struct([u8('instruction'), u8('includePinned')]);
+  const data = Buffer.alloc(dataLayout.span);
+  dataLayout.encode(
+    {
+      instruction: AdminInstruction.SyncPoolFromConfig,
+      includePinned: includePinned ? 1 : 0,
+    },
+    data
+  );
+
+  return new TransactionInstruction({
+    keys,
+    data,
+    programId,
+  });
+};
+
+export const createSetConfigFeesInstruction = (
+  config: PublicKey,
+  adminKey: PublicKey,
+  initData: AdminInitializeData,
+  programId: PublicKey
+) => {
+  const keys = [
+    { pubkey: config, isSigner: false, isWritable: true },
+    { pubkey: adminKey, isSigner: true, isWritable: false },
+  ];
+  const dataLayout = struct([u8('instruction'), AdminInitializeDataLayout]);
+  const data = Buffer.alloc(dataLayout.span);
+  dataLayout.encode(
+    {
+      instruction: AdminInstruction.SetConfigFees,
+      initData,
+    },
+    data
+  );
+
+  return new TransactionInstruction({
+    keys,
+    data,
+    programId,
+  });
+};
+
+export const createBatchSetFeesInstruction = (
+  config: PublicKey,
+  adminKey: PublicKey,
+  tokenSwaps: PublicKey[],
+  newFees: Fees,
+  programId: PublicKey
+) => {
+  const keys = [
+    { pubkey: config, isSigner: false, isWritable: false },
+    { pubkey: adminKey, isSigner: true, isWritable: false },
+    ...tokenSwaps.map((tokenSwap) => ({ pubkey: tokenSwap, isSigner: false, isWritable: true })),
+  ];
+  const dataLayout = struct([u8('instruction'), FeesLayout('newFees')]);
+  const data = Buffer.alloc(dataLayout.span);
+  dataLayout.encode(
+    {
+      instruction: AdminInstruction.BatchSetFees,
+      newFees,
+    },
+    data
+  );
+
+  return new TransactionInstruction({
+    keys,
+    data,
+    programId,
+  });
+};
+
+export const createBatchPauseInstruction = (
+  config: PublicKey,
+  adminKey: PublicKey,
+  tokenSwaps: PublicKey[],
+  programId: PublicKey
+) => {
+  const keys = [
+    { pubkey: config, isSigner: false, isWritable: false },
+    { pubkey: adminKey, isSigner: true, isWritable: false },
+    ...tokenSwaps.map((tokenSwap) => ({ pubkey: tokenSwap, isSigner: false, isWritable: true })),
+  ];
+  const dataLayout = struct([u8('instruction')]);
+  const data = Buffer.alloc(dataLayout.span);
+  dataLayout.encode(
+    {
+      instruction: AdminInstruction.BatchPause,
+    },
+    data
+  );
+
+  return new TransactionInstruction({
+    keys,
+    data,
+    programId,
+  });
+};
+
+export const createAddFarmRewardTokenInstruction = (
+  config: PublicKey,
+  farmPool: PublicKey,
+  farmRewardTokens: PublicKey,
+  vault: PublicKey,
+  adminKey: PublicKey,
+  rewardPerSlot: bigint,
+  programId: PublicKey
+) => {
+  const keys = [
+    { pubkey: config, isSigner: false, isWritable: false },
+    { pubkey: farmPool, isSigner: false, isWritable: true },
+    { pubkey: farmRewardTokens, isSigner: false, isWritable: true },
+    { pubkey: vault, isSigner: false, isWritable: false },
+    { pubkey: adminKey, isSigner: true, isWritable: true },
+    { pubkey: SYSVAR_RENT_PUBKEY, isSigner: false, isWritable: false },
+    { pubkey: SystemProgram.programId, isSigner: false, isWritable: false },
+    { pubkey: TOKEN_PROGRAM_ID, isSigner: false, isWritable: false },
+  ];
+  const dataLayout = struct([u8('instruction'), u64('rewardPerSlot')]);
+  const data = Buffer.alloc(dataLayout.span);
+  dataLayout.encode(
+    {
+      instruction: AdminInstruction.AddFarmRewardToken,
+      rewardPerSlot,
+    },
+    data
+  );
+
+  return new TransactionInstruction({
+    keys,
+    data,
+    programId,
+  });
+};
+
+export const createSetFarmRewardRateInstruction = (
+  config: PublicKey,
+  farmPool: PublicKey,
+  farmRewardTokens: PublicKey,
+  adminKey: PublicKey,
+  index: number,
+  rewardPerSlot: bigint,
+  programId: PublicKey
+) => {
+  const keys = [
+    { pubkey: config, isSigner: false, isWritable: false },
+    { pubkey: farmPool, isSigner: false, isWritable: false },
+    { pubkey: farmRewardTokens, isSigner: false, isWritable: true },
+    { pubkey: adminKey, isSigner: true, isWritable: false },
+  ];
+  const dataLayout = struct([u8('instruction'), u8('index'), u64('rewardPerSlot')]);
+  const data = Buffer.alloc(dataLayout.span);
+  dataLayout.encode(
+    {
+      instruction: AdminInstruction.SetFarmRewardRate,
+      index,
+      rewardPerSlot,
+    },
+    data
+  );
+
+  return new TransactionInstruction({
+    keys,
+    data,
+    programId,
+  });
+};
+
+export const createSetFarmEmissionRateInstruction = (
+  config: PublicKey,
+  farmPool: PublicKey,
+  adminKey: PublicKey,
+  rewardPerSlot: bigint,
+  programId: PublicKey
+) => {
+  const keys = [
+    { pubkey: config, isSigner: false, isWritable: false },
+    { pubkey: farmPool, isSigner: false, isWritable: true },
+    { pubkey: adminKey, isSigner: true, isWritable: false },
+  ];
+  const dataLayout = struct([u8('instruction'), u64('rewardPerSlot')]);
+  const data = Buffer.alloc(dataLayout.span);
+  dataLayout.encode(
+    {
+      instruction: AdminInstruction.SetFarmEmissionRate,
+      rewardPerSlot,
+    },
+    data
+  );
+
+  return new TransactionInstruction({
+    keys,
+    data,
+    programId,
+  });
+};
+
+// Fund the DELTAFI reward reserve of the market authority for a staking pool
+export const createFundStakingRewardsInstruction = (
+  config: PublicKey,
+  farmPool: PublicKey,
+  marketAuthority: PublicKey,
+  rewardReserve: PublicKey,
+  source: PublicKey,
+  adminKey: PublicKey,
+  amount: bigint,
+  programId: PublicKey
+) => {
+  const keys = [
+    { pubkey: config, isSigner: false, isWritable: false },
+    { pubkey: farmPool, isSigner: false, isWritable: false },
+    { pubkey: marketAuthority, isSigner: false, isWritable: false },
+    { pubkey: rewardReserve, isSigner: false, isWritable: true },
+    { pubkey: source, isSigner: false, isWritable: true },
+    { pubkey: adminKey, isSigner: true, isWritable: false },
+    { pubkey: TOKEN_PROGRAM_ID, isSigner: false, isWritable: false },
+  ];
+  const dataLayout = struct([u8('instruction'), u64('amount')]);
+  const data = Buffer.alloc(dataLayout.span);
+  dataLayout.encode(
+    {
+      instruction: AdminInstruction.FundStakingRewards,
+      amount,
+    },
+    data
+  );
+
+  return new TransactionInstruction({
+    keys,
+    data,
+    programId,
+  });
+};
+
+// Set the deviation threshold of the permissionless rebalance of the pool, 0 turns it off
+export const createSetRebalanceThresholdInstruction = (
+  config: PublicKey,
+  tokenSwap: PublicKey,
+  adminKey: PublicKey,
+  rebalanceThresholdBps: number,
+  programId: PublicKey
+) => {
+  const keys = [
+    { pubkey: config, isSigner: false, isWritable: false },
+    { pubkey: tokenSwap, isSigner: false, isWritable: true },
+    { pubkey: adminKey, isSigner: true, isWritable: false },
+  ];
+  const dataLayout = struct([u8('instruction'), u16('rebalanceThresholdBps')]);
+  const data = Buffer.alloc(dataLayout.span);
+  dataLayout.encode(
+    {
+      instruction: AdminInstruction.SetRebalanceThreshold,
+      rebalanceThresholdBps,
+    },
+    data
+  );
+
+  return new TransactionInstruction({
+    keys,
+    data,
+    programId,
+  });
+};
+
+// Set the circuit breaker failing the swaps of the pool on a price deviation within the window
+export const createSetCircuitBreakerInstruction = (
+  config: PublicKey,
+  tokenSwap: PublicKey,
+  adminKey: PublicKey,
+  maxDeviationBps: number,
+  windowSlots: bigint,
+  programId: PublicKey
+) => {
+  const keys = [
+    { pubkey: config, isSigner: false, isWritable: false },
+    { pubkey: tokenSwap, isSigner: false, isWritable: true },
+    { pubkey: adminKey, isSigner: true, isWritable: false },
+  ];
+  const dataLayout = struct([u8('instruction'), u16('maxDeviationBps'), u64('windowSlots')]);
+  const data = Buffer.alloc(dataLayout.span);
+  dataLayout.encode(
+    {
+      instruction: AdminInstruction.SetCircuitBreaker,
+      maxDeviationBps,
+      windowSlots,
+    },
+    data
+  );
+
+  return new TransactionInstruction({
+    keys,
+    data,
+    programId,
+  });
+};
+
+// Cap the total supply of the pool tokens the deposits can mint up to, 0 removes the cap
+export const createSetDepositCapInstruction = (
+  config: PublicKey,
+  tokenSwap: PublicKey,
+  adminKey: PublicKey,
+  maxTotalSupply: bigint,
+  programId: PublicKey
+) => {
+  const keys = [
+    { pubkey: config, isSigner: false, isWritable: false },
+    { pubkey: tokenSwap, isSigner: false, isWritable: true },
+    { pubkey: adminKey, isSigner: true, isWritable: false },
+  ];
+  const dataLayout = struct([u8('instruction'), u64('maxTotalSupply')]);
+  const data = Buffer.alloc(dataLayout.span);
+  dataLayout.encode(
+    {
+      instruction: AdminInstruction.SetDepositCap,
+      maxTotalSupply,
+    },
+    data
+  );
+
+  return new TransactionInstruction({
+    keys,
+    data,
+    programId,
+  });
+};
+
+// Gate the swaps and deposits of the pool on a whitelist entry of the user
+export const createSetWhitelistEnabledInstruction = (
+  config: PublicKey,
+  tokenSwap: PublicKey,
+  adminKey: PublicKey,
+  whitelistEnabled: boolean,
+  programId: PublicKey
+) => {
+  const keys = [
+    { pubkey: config, isSigner: false, isWritable: false },
+    { pubkey: tokenSwap, isSigner: false, isWritable: true },
+    { pubkey: adminKey, isSigner: true, isWritable: false },
+  ];
+  const dataLayout = struct([u8('instruction'), u8('whitelistEnabled')]);
+  const data = Buffer.alloc(dataLayout.span);
+  dataLayout.encode(
+    {
+      instruction: AdminInstruction.SetWhitelistEnabled,
+      whitelistEnabled: whitelistEnabled ? 1 : 0,
+    },
+    data
+  );
+
+  return new TransactionInstruction({
+    keys,
+    data,
+    programId,
+  });
+};
+
+export const createAddWhitelistEntryInstruction = (
+  config: PublicKey,
+  tokenSwap: PublicKey,
+  whitelistEntry: PublicKey,
+  user: PublicKey,
+  adminKey: PublicKey,
+  programId: PublicKey
+) => {
+  const keys = [
+    { pubkey: config, isSigner: false, isWritable: false },
+    { pubkey: tokenSwap, isSigner: false, isWritable: false },
+    { pubkey: whitelistEntry, isSigner: false, isWritable: true },
+    { pubkey: user, isSigner: false, isWritable: false },
+    { pubkey: adminKey, isSigner: true, isWritable: true },
+    { pubkey: SYSVAR_RENT_PUBKEY, isSigner: false, isWritable: false },
+    { pubkey: SystemProgram.programId, isSigner: false, isWritable: false },
+  ];
+  const dataLayout = struct([u8('instruction')]);
+  const data = Buffer.alloc(dataLayout.span);
+  dataLayout.encode(
+    {
+      instruction: AdminInstruction.AddWhitelistEntry,
+    },
+    data
+  );
+
+  return new TransactionInstruction({
+    keys,
+    data,
+    programId,
+  });
+};
+
+export const createRemoveWhitelistEntryInstruction = (
+  config: PublicKey,
+  tokenSwap: PublicKey,
+  whitelistEntry: PublicKey,
+  adminKey: PublicKey,
+  programId: PublicKey
+) => {
+  const keys = [
+    { pubkey: config, isSigner: false, isWritable: false },
+    { pubkey: tokenSwap, isSigner: false, isWritable: false },
+    { pubkey: whitelistEntry, isSigner: false, isWritable: true },
+    { pubkey: adminKey, isSigner: true, isWritable: true },
+  ];
+  const dataLayout = struct([u8('instruction')]);
+  const data = Buffer.alloc(dataLayout.span);
+  dataLayout.encode(
+    {
+      instruction: AdminInstruction.RemoveWhitelistEntry,
+    },
+    data
+  );
+
+  return new TransactionInstruction({
+    keys,
+    data,
+    programId,
+  });
+};
+
+// Upgrade a pool to the current layout version, the admin pays the rent of a legacy pool growth
+export const createMigrateSwapInfoInstruction = (
+  config: PublicKey,
+  tokenSwap: PublicKey,
+  adminKey: PublicKey,
+  programId: PublicKey
+) => {
+  const keys = [
+    { pubkey: config, isSigner: false, isWritable: false },
+    { pubkey: tokenSwap, isSigner: false, isWritable: true },
+    { pubkey: adminKey, isSigner: true, isWritable: true },
+    { pubkey: SYSVAR_RENT_PUBKEY, isSigner: false, isWritable: false },
+    { pubkey: SystemProgram.programId, isSigner: false, isWritable: false },
+  ];
+  const dataLayout = struct([u8('instruction')]);
+  const data = Buffer.alloc(dataLayout.span);
+  dataLayout.encode(
+    {
+      instruction: AdminInstruction.MigrateSwapInfo,
+    },
+    data
+  );
+
+  return new TransactionInstruction({
+    keys,
+    data,
+    programId,
+  });
+};
+
+export const createMigrateConfigInfoInstruction = (
+  config: PublicKey,
+  adminKey: PublicKey,
+  programId: PublicKey
+) => {
+  const keys = [
+    { pubkey: config, isSigner: false, isWritable: true },
+    { pubkey: adminKey, isSigner: true, isWritable: true },
+    { pubkey: SYSVAR_RENT_PUBKEY, isSigner: false, isWritable: false },
+    { pubkey: SystemProgram.programId, isSigner: false, isWritable: false },
+  ];
+  const dataLayout = struct([u8('instruction')]);
+  const data = Buffer.alloc(dataLayout.span);
+  dataLayout.encode(
+    {
+      instruction: AdminInstruction.MigrateConfigInfo,
+    },
+    data
+  );
+
+  return new TransactionInstruction({
+    keys,
+    data,
+    programId,
+  });
+};
+
+export const createMigrateFarmInfoInstruction = (
+  config: PublicKey,
+  farmPool: PublicKey,
+  adminKey: PublicKey,
+  programId: PublicKey
+) => {
+  const keys = [
+    { pubkey: config, isSigner: false, isWritable: false },
+    { pubkey: farmPool, isSigner: false, isWritable: true },
+    { pubkey: adminKey, isSigner: true, isWritable: true },
+    { pubkey: SYSVAR_RENT_PUBKEY, isSigner: false, isWritable: false },
+    { pubkey: SystemProgram.programId, isSigner: false, isWritable: false },
+  ];
+  const dataLayout = struct([u8('instruction')]);
+  const data = Buffer.alloc(dataLayout.span);
+  dataLayout.encode(
+    {
+      instruction: AdminInstruction.MigrateFarmInfo,
+    },
+    data
+  );
+
+  return new TransactionInstruction({
+    keys,
+    data,
+    programId,
+  });
+};
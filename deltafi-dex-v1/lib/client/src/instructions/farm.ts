@@ -1,8 +1,14 @@
 import { TOKEN_PROGRAM_ID } from '@solana/spl-token';
-import { PublicKey, SYSVAR_CLOCK_PUBKEY, SYSVAR_RENT_PUBKEY, TransactionInstruction } from '@solana/web3.js';
-import { struct, u8 } from 'buffer-layout';
+import {
+  PublicKey,
+  SystemProgram,
+  SYSVAR_CLOCK_PUBKEY,
+  SYSVAR_RENT_PUBKEY,
+  TransactionInstruction,
+} from '@solana/web3.js';
+import { seq, struct, u8 } from 'buffer-layout';
 
-import { u64 } from '../util';
+import { publicKey, u64 } from '../util';
 
 export enum FarmInstruction {
   Initialize = 20,
@@ -11,6 +17,16 @@ export enum FarmInstruction {
   Refresh,
   Deposit,
   Withdraw,
+  SetRewardSplit,
+  Checkpoint,
+  FundRewardToken = 40,
+  InitializeFarmUserRewardTokens,
+  ClaimRewardTokens,
+  Compound,
+  TransferPosition,
+  ClaimMulti,
+  InitializeStaking,
+  SetCompoundDelegate = 60,
 }
 
 export interface FarmInitializeData {
@@ -59,13 +75,51 @@ export const createInitFarmInstruction = (
   });
 };
 
+// Instruction for initializing a staking pool, a farm pool staking DELTAFI itself
+export const createInitStakingInstruction = (
+  config: PublicKey,
+  farmPool: PublicKey,
+  authority: PublicKey,
+  poolToken: PublicKey,
+  admin: PublicKey,
+  initData: FarmInitializeData,
+  programId: PublicKey
+): TransactionInstruction => {
+  const keys = [
+    { pubkey: config, isSigner: false, isWritable: false },
+    { pubkey: farmPool, isSigner: true, isWritable: true },
+    { pubkey: authority, isSigner: false, isWritable: false },
+    { pubkey: poolToken, isSigner: false, isWritable: false },
+    { pubkey: admin, isSigner: true, isWritable: false },
+    { pubkey: SYSVAR_RENT_PUBKEY, isSigner: false, isWritable: false },
+  ];
+
+  const dataLayout = struct([u8('instruction'), FarmInitializeDataLayout]);
+  const data = Buffer.alloc(dataLayout.span);
+  dataLayout.encode({ instruction: FarmInstruction.InitializeStaking, initData }, data);
+
+  return new TransactionInstruction({
+    keys,
+    programId,
+    data,
+  });
+};
+
 export interface FarmDepositData {
   amount: bigint;
+  // index of the farm lock tier, 0 or undefined does not lock
+  lockTier?: number;
 }
 
 /** @internal */
 export const FarmDepositDataLayout = struct<FarmDepositData>([u64('amount')], 'depositData');
 
+/** @internal */
+export const FarmLockedDepositDataLayout = struct<FarmDepositData>(
+  [u64('amount'), u8('lockTier')],
+  'depositData'
+);
+
 // Instruction for deposit farm
 export const createFarmDepositInstruction = (
   config: PublicKey,
@@ -76,7 +130,9 @@ export const createFarmDepositInstruction = (
   farmUser: PublicKey,
   farmOwner: PublicKey,
   depositData: FarmDepositData,
-  programId: PublicKey
+  programId: PublicKey,
+  farmRewardTokens?: PublicKey,
+  farmUserRewardTokens?: PublicKey
 ): TransactionInstruction => {
   const keys = [
     { pubkey: config, isSigner: false, isWritable: false },
@@ -88,8 +144,17 @@ export const createFarmDepositInstruction = (
     { pubkey: farmOwner, isSigner: true, isWritable: false },
     { pubkey: TOKEN_PROGRAM_ID, isSigner: false, isWritable: false },
   ];
+  if (farmRewardTokens && farmUserRewardTokens) {
+    keys.push(
+      { pubkey: farmRewardTokens, isSigner: false, isWritable: true },
+      { pubkey: farmUserRewardTokens, isSigner: false, isWritable: true }
+    );
+  }
 
-  const dataLayout = struct([u8('instruction'), FarmDepositDataLayout]);
+  const dataLayout = struct([
+    u8('instruction'),
+    depositData.lockTier ? FarmLockedDepositDataLayout : FarmDepositDataLayout,
+  ]);
   const data = Buffer.alloc(dataLayout.span);
   dataLayout.encode(
     {
@@ -123,7 +188,11 @@ export const createFarmWithdrawInstruction = (
   source: PublicKey,
   destination: PublicKey,
   withdrawData: FarmWithdrawData,
-  programId: PublicKey
+  programId: PublicKey,
+  farmRewardTokens?: PublicKey,
+  farmUserRewardTokens?: PublicKey,
+  // admin token account of the pool mint, required if the farm has a withdraw fee
+  feeDestination?: PublicKey
 ): TransactionInstruction => {
   const keys = [
     { pubkey: config, isSigner: false, isWritable: false },
@@ -135,6 +204,15 @@ export const createFarmWithdrawInstruction = (
     { pubkey: farmOwner, isSigner: false, isWritable: false },
     { pubkey: TOKEN_PROGRAM_ID, isSigner: false, isWritable: false },
   ];
+  if (farmRewardTokens && farmUserRewardTokens) {
+    keys.push(
+      { pubkey: farmRewardTokens, isSigner: false, isWritable: true },
+      { pubkey: farmUserRewardTokens, isSigner: false, isWritable: true }
+    );
+  }
+  if (feeDestination) {
+    keys.push({ pubkey: feeDestination, isSigner: false, isWritable: true });
+  }
 
   const dataLayout = struct([u8('instruction'), FarmWithdrawDataLayout]);
   const data = Buffer.alloc(dataLayout.span);
@@ -185,6 +263,43 @@ export const createInitFarmUserInstruction = (
   });
 };
 
+// Instruction for initialize farm user of a farm pool with the farm user registry enabled
+export const createInitFarmUserWithRegistryInstruction = (
+  config: PublicKey,
+  farmPool: PublicKey,
+  farmUser: PublicKey,
+  farmOwner: PublicKey,
+  farmUserRegistry: PublicKey,
+  payer: PublicKey,
+  programId: PublicKey
+) => {
+  const keys = [
+    { pubkey: config, isSigner: false, isWritable: false },
+    { pubkey: farmPool, isSigner: false, isWritable: true },
+    { pubkey: farmUser, isSigner: false, isWritable: true },
+    { pubkey: farmOwner, isSigner: true, isWritable: false },
+    { pubkey: SYSVAR_RENT_PUBKEY, isSigner: false, isWritable: false },
+    { pubkey: farmUserRegistry, isSigner: false, isWritable: true },
+    { pubkey: payer, isSigner: true, isWritable: true },
+    { pubkey: SystemProgram.programId, isSigner: false, isWritable: false },
+  ];
+
+  const dataLayout = struct([u8('instruction')]);
+  const data = Buffer.alloc(dataLayout.span);
+  dataLayout.encode(
+    {
+      instruction: FarmInstruction.InitializeFarmUser,
+    },
+    data
+  );
+
+  return new TransactionInstruction({
+    keys,
+    programId,
+    data,
+  });
+};
+
 // Instruction for claim farm
 export const createClaimFarmInstruction = (
   config: PublicKey,
@@ -223,16 +338,16 @@ export const createClaimFarmInstruction = (
   });
 };
 
-// Instruction for refresh farm
+// Instruction for refreshing the farm emission and the rewards of farm users, signed by anyone
 export const createRefreshFarmInstruction = (
-  swap: PublicKey,
+  config: PublicKey,
   farmPool: PublicKey,
   farmUsers: Array<PublicKey>,
   programId: PublicKey
 ) => {
   const keys = [
-    { pubkey: swap, isSigner: false, isWritable: false },
-    { pubkey: farmPool, isSigner: false, isWritable: false },
+    { pubkey: config, isSigner: false, isWritable: false },
+    { pubkey: farmPool, isSigner: false, isWritable: true },
     ...farmUsers.map((user) => ({ pubkey: user, isSigner: false, isWritable: true })),
   ];
 
@@ -251,3 +366,353 @@ export const createRefreshFarmInstruction = (
     data,
   });
 };
+
+export interface FarmRewardSplitData {
+  percentages: number[];
+}
+
+/** @internal */
+export const FarmRewardSplitDataLayout = struct<FarmRewardSplitData>([seq(u8(), 4, 'percentages')], 'splitData');
+
+// Instruction for set farm reward split
+export const createFarmSetRewardSplitInstruction = (
+  config: PublicKey,
+  farmPool: PublicKey,
+  farmUser: PublicKey,
+  farmOwner: PublicKey,
+  rewardSplit: PublicKey,
+  recipients: Array<PublicKey>,
+  splitData: FarmRewardSplitData,
+  programId: PublicKey
+): TransactionInstruction => {
+  const keys = [
+    { pubkey: config, isSigner: false, isWritable: false },
+    { pubkey: farmPool, isSigner: false, isWritable: false },
+    { pubkey: farmUser, isSigner: false, isWritable: true },
+    { pubkey: farmOwner, isSigner: true, isWritable: false },
+    { pubkey: rewardSplit, isSigner: false, isWritable: true },
+    { pubkey: SYSVAR_RENT_PUBKEY, isSigner: false, isWritable: false },
+    ...recipients.map((recipient) => ({ pubkey: recipient, isSigner: false, isWritable: false })),
+  ];
+
+  const dataLayout = struct([u8('instruction'), FarmRewardSplitDataLayout]);
+  const data = Buffer.alloc(dataLayout.span);
+  dataLayout.encode({ instruction: FarmInstruction.SetRewardSplit, splitData }, data);
+
+  return new TransactionInstruction({
+    keys,
+    programId,
+    data,
+  });
+};
+
+// Instruction for checkpointing farm user rewards before the farm APR changes
+export const createFarmCheckpointInstruction = (
+  config: PublicKey,
+  farmPool: PublicKey,
+  farmUsers: Array<PublicKey>,
+  programId: PublicKey
+): TransactionInstruction => {
+  const keys = [
+    { pubkey: config, isSigner: false, isWritable: false },
+    { pubkey: farmPool, isSigner: false, isWritable: false },
+    ...farmUsers.map((farmUser) => ({ pubkey: farmUser, isSigner: false, isWritable: true })),
+  ];
+
+  const dataLayout = struct([u8('instruction')]);
+  const data = Buffer.alloc(dataLayout.span);
+  dataLayout.encode({ instruction: FarmInstruction.Checkpoint }, data);
+
+  return new TransactionInstruction({
+    keys,
+    programId,
+    data,
+  });
+};
+
+export interface FarmFundRewardTokenData {
+  index: number;
+  amount: bigint;
+}
+
+/** @internal */
+export const FarmFundRewardTokenDataLayout = struct<FarmFundRewardTokenData>(
+  [u8('index'), u64('amount')],
+  'fundData'
+);
+
+// Instruction for funding a partner reward token of a farm
+export const createFarmFundRewardTokenInstruction = (
+  config: PublicKey,
+  farmPool: PublicKey,
+  farmRewardTokens: PublicKey,
+  vault: PublicKey,
+  source: PublicKey,
+  userTransferAuthority: PublicKey,
+  fundData: FarmFundRewardTokenData,
+  programId: PublicKey
+): TransactionInstruction => {
+  const keys = [
+    { pubkey: config, isSigner: false, isWritable: false },
+    { pubkey: farmPool, isSigner: false, isWritable: false },
+    { pubkey: farmRewardTokens, isSigner: false, isWritable: true },
+    { pubkey: vault, isSigner: false, isWritable: true },
+    { pubkey: source, isSigner: false, isWritable: true },
+    { pubkey: userTransferAuthority, isSigner: true, isWritable: false },
+    { pubkey: TOKEN_PROGRAM_ID, isSigner: false, isWritable: false },
+  ];
+
+  const dataLayout = struct([u8('instruction'), FarmFundRewardTokenDataLayout]);
+  const data = Buffer.alloc(dataLayout.span);
+  dataLayout.encode({ instruction: FarmInstruction.FundRewardToken, fundData }, data);
+
+  return new TransactionInstruction({
+    keys,
+    programId,
+    data,
+  });
+};
+
+// Instruction for initializing the partner reward tokens owed to a farm user
+export const createInitFarmUserRewardTokensInstruction = (
+  config: PublicKey,
+  farmPool: PublicKey,
+  farmUser: PublicKey,
+  farmUserRewardTokens: PublicKey,
+  farmOwner: PublicKey,
+  programId: PublicKey
+): TransactionInstruction => {
+  const keys = [
+    { pubkey: config, isSigner: false, isWritable: false },
+    { pubkey: farmPool, isSigner: false, isWritable: false },
+    { pubkey: farmUser, isSigner: false, isWritable: false },
+    { pubkey: farmUserRewardTokens, isSigner: false, isWritable: true },
+    { pubkey: farmOwner, isSigner: true, isWritable: true },
+    { pubkey: SYSVAR_RENT_PUBKEY, isSigner: false, isWritable: false },
+    { pubkey: SystemProgram.programId, isSigner: false, isWritable: false },
+  ];
+
+  const dataLayout = struct([u8('instruction')]);
+  const data = Buffer.alloc(dataLayout.span);
+  dataLayout.encode({ instruction: FarmInstruction.InitializeFarmUserRewardTokens }, data);
+
+  return new TransactionInstruction({
+    keys,
+    programId,
+    data,
+  });
+};
+
+// Instruction for claiming the partner reward tokens of a farm user, with the vault and the
+// destination of each reward token in use
+export const createFarmClaimRewardTokensInstruction = (
+  config: PublicKey,
+  farmPool: PublicKey,
+  farmUser: PublicKey,
+  farmRewardTokens: PublicKey,
+  farmUserRewardTokens: PublicKey,
+  farmOwner: PublicKey,
+  authority: PublicKey,
+  vaultDestinations: Array<{ vault: PublicKey; destination: PublicKey }>,
+  programId: PublicKey
+): TransactionInstruction => {
+  const keys = [
+    { pubkey: config, isSigner: false, isWritable: false },
+    { pubkey: farmPool, isSigner: false, isWritable: false },
+    { pubkey: farmUser, isSigner: false, isWritable: false },
+    { pubkey: farmRewardTokens, isSigner: false, isWritable: true },
+    { pubkey: farmUserRewardTokens, isSigner: false, isWritable: true },
+    { pubkey: farmOwner, isSigner: true, isWritable: false },
+    { pubkey: authority, isSigner: false, isWritable: false },
+    { pubkey: TOKEN_PROGRAM_ID, isSigner: false, isWritable: false },
+    ...vaultDestinations.flatMap(({ vault, destination }) => [
+      { pubkey: vault, isSigner: false, isWritable: true },
+      { pubkey: destination, isSigner: false, isWritable: true },
+    ]),
+  ];
+
+  const dataLayout = struct([u8('instruction')]);
+  const data = Buffer.alloc(dataLayout.span);
+  dataLayout.encode({ instruction: FarmInstruction.ClaimRewardTokens }, data);
+
+  return new TransactionInstruction({
+    keys,
+    programId,
+    data,
+  });
+};
+
+export interface FarmCompoundData {
+  minMintAmount: bigint;
+}
+
+/** @internal */
+export const FarmCompoundDataLayout = struct<FarmCompoundData>([u64('minMintAmount')], 'compoundData');
+
+// Instruction for compounding the DELTAFI rewards of a farm user into its position, signed by
+// the farm owner or its compound delegate. The price accounts are the market price accounts of
+// the swap as in its deposit one.
+export const createFarmCompoundInstruction = (
+  config: PublicKey,
+  farmPool: PublicKey,
+  farmUser: PublicKey,
+  compoundAuthority: PublicKey,
+  marketAuthority: PublicKey,
+  claimSource: PublicKey,
+  swap: PublicKey,
+  swapAuthority: PublicKey,
+  tokenA: PublicKey,
+  tokenB: PublicKey,
+  adminFeeDestination: PublicKey,
+  poolMint: PublicKey,
+  farmPoolToken: PublicKey,
+  priceAccounts: PublicKey[],
+  compoundData: FarmCompoundData,
+  programId: PublicKey,
+  farmRewardTokens?: PublicKey,
+  farmUserRewardTokens?: PublicKey
+): TransactionInstruction => {
+  const keys = [
+    { pubkey: config, isSigner: false, isWritable: false },
+    { pubkey: farmPool, isSigner: false, isWritable: true },
+    { pubkey: farmUser, isSigner: false, isWritable: true },
+    { pubkey: compoundAuthority, isSigner: true, isWritable: false },
+    { pubkey: marketAuthority, isSigner: false, isWritable: false },
+    { pubkey: claimSource, isSigner: false, isWritable: true },
+    { pubkey: swap, isSigner: false, isWritable: true },
+    { pubkey: swapAuthority, isSigner: false, isWritable: false },
+    { pubkey: tokenA, isSigner: false, isWritable: true },
+    { pubkey: tokenB, isSigner: false, isWritable: true },
+    { pubkey: adminFeeDestination, isSigner: false, isWritable: true },
+    { pubkey: poolMint, isSigner: false, isWritable: true },
+    { pubkey: farmPoolToken, isSigner: false, isWritable: true },
+    ...priceAccounts.map((pubkey) => ({ pubkey, isSigner: false, isWritable: false })),
+    { pubkey: TOKEN_PROGRAM_ID, isSigner: false, isWritable: false },
+  ];
+  if (farmRewardTokens && farmUserRewardTokens) {
+    keys.push(
+      { pubkey: farmRewardTokens, isSigner: false, isWritable: true },
+      { pubkey: farmUserRewardTokens, isSigner: false, isWritable: true }
+    );
+  }
+
+  const dataLayout = struct([u8('instruction'), FarmCompoundDataLayout]);
+  const data = Buffer.alloc(dataLayout.span);
+  dataLayout.encode({ instruction: FarmInstruction.Compound, compoundData }, data);
+
+  return new TransactionInstruction({
+    keys,
+    programId,
+    data,
+  });
+};
+
+// Instruction for moving the stake, lock and rewards of a farm user to the empty farm user of
+// another owner of the same farm
+export const createFarmTransferPositionInstruction = (
+  config: PublicKey,
+  farmPool: PublicKey,
+  sourceFarmUser: PublicKey,
+  sourceOwner: PublicKey,
+  destinationFarmUser: PublicKey,
+  programId: PublicKey,
+  farmRewardTokens?: PublicKey,
+  sourceFarmUserRewardTokens?: PublicKey,
+  destinationFarmUserRewardTokens?: PublicKey
+): TransactionInstruction => {
+  const keys = [
+    { pubkey: config, isSigner: false, isWritable: false },
+    { pubkey: farmPool, isSigner: false, isWritable: false },
+    { pubkey: sourceFarmUser, isSigner: false, isWritable: true },
+    { pubkey: sourceOwner, isSigner: true, isWritable: false },
+    { pubkey: destinationFarmUser, isSigner: false, isWritable: true },
+  ];
+  if (farmRewardTokens && sourceFarmUserRewardTokens && destinationFarmUserRewardTokens) {
+    keys.push(
+      { pubkey: farmRewardTokens, isSigner: false, isWritable: true },
+      { pubkey: sourceFarmUserRewardTokens, isSigner: false, isWritable: true },
+      { pubkey: destinationFarmUserRewardTokens, isSigner: false, isWritable: true }
+    );
+  }
+
+  const dataLayout = struct([u8('instruction')]);
+  const data = Buffer.alloc(dataLayout.span);
+  dataLayout.encode({ instruction: FarmInstruction.TransferPosition }, data);
+
+  return new TransactionInstruction({
+    keys,
+    programId,
+    data,
+  });
+};
+
+// Instruction for claiming the rewards of the farm users of an owner across several farms in one
+// transfer, the referral accounts are passed for the referral reward of a referred owner
+export const createFarmClaimMultiInstruction = (
+  config: PublicKey,
+  farmOwner: PublicKey,
+  marketAuthority: PublicKey,
+  claimDestination: PublicKey,
+  claimSource: PublicKey,
+  farms: Array<{ farmPool: PublicKey; farmUser: PublicKey }>,
+  programId: PublicKey,
+  userReferrerData?: PublicKey,
+  referrerToken?: PublicKey
+): TransactionInstruction => {
+  const keys = [
+    { pubkey: config, isSigner: false, isWritable: false },
+    { pubkey: farmOwner, isSigner: true, isWritable: false },
+    { pubkey: marketAuthority, isSigner: false, isWritable: false },
+    { pubkey: claimDestination, isSigner: false, isWritable: true },
+    { pubkey: claimSource, isSigner: false, isWritable: true },
+    { pubkey: TOKEN_PROGRAM_ID, isSigner: false, isWritable: false },
+    ...farms.flatMap(({ farmPool, farmUser }) => [
+      { pubkey: farmPool, isSigner: false, isWritable: false },
+      { pubkey: farmUser, isSigner: false, isWritable: true },
+    ]),
+  ];
+  if (userReferrerData && referrerToken) {
+    keys.push(
+      { pubkey: userReferrerData, isSigner: false, isWritable: false },
+      { pubkey: referrerToken, isSigner: false, isWritable: true }
+    );
+  }
+
+  const dataLayout = struct([u8('instruction'), u8('farmCount')]);
+  const data = Buffer.alloc(dataLayout.span);
+  dataLayout.encode({ instruction: FarmInstruction.ClaimMulti, farmCount: farms.length }, data);
+
+  return new TransactionInstruction({
+    keys,
+    programId,
+    data,
+  });
+};
+
+// Instruction for setting the authority allowed to compound a farm user besides its owner, the
+// default public key removes it
+export const createFarmSetCompoundDelegateInstruction = (
+  config: PublicKey,
+  farmPool: PublicKey,
+  farmUser: PublicKey,
+  farmOwner: PublicKey,
+  compoundDelegate: PublicKey,
+  programId: PublicKey
+): TransactionInstruction => {
+  const keys = [
+    { pubkey: config, isSigner: false, isWritable: false },
+    { pubkey: farmPool, isSigner: false, isWritable: false },
+    { pubkey: farmUser, isSigner: false, isWritable: true },
+    { pubkey: farmOwner, isSigner: true, isWritable: false },
+  ];
+
+  const dataLayout = struct([u8('instruction'), publicKey('compoundDelegate')]);
+  const data = Buffer.alloc(dataLayout.span);
+  dataLayout.encode({ instruction: FarmInstruction.SetCompoundDelegate, compoundDelegate }, data);
+
+  return new TransactionInstruction({
+    keys,
+    programId,
+    data,
+  });
+};
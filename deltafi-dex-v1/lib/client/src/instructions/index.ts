@@ -2,3 +2,4 @@ export * from './swap';
 export * from './admin';
 export * from './farm';
 export * from './stable_swap';
+export * from './router';
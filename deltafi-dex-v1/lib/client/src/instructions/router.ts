@@ -0,0 +1,110 @@
+import { TOKEN_PROGRAM_ID } from '@solana/spl-token';
+import { PublicKey, TransactionInstruction } from '@solana/web3.js';
+import { struct, u8 } from 'buffer-layout';
+
+import { u64 } from '../util';
+
+export enum RouterInstruction {
+  Route = 30,
+}
+
+/// max number of pools traded by a route
+export const MAX_ROUTE_HOPS = 3;
+
+export interface RouteData {
+  amountIn: bigint;
+  minimumAmountOut: bigint;
+}
+
+/** @internal */
+export const RouteDataLayout = struct<RouteData>([u64('amountIn'), u64('minimumAmountOut')], 'routeData');
+
+export interface RouteHop {
+  tokenSwap: PublicKey;
+  swapAuthority: PublicKey;
+  swapSource: PublicKey;
+  swapDestination: PublicKey;
+  adminFeeDestination: PublicKey;
+  /// treasury token account of the mint swapped FROM, while the protocol fee switch is on
+  treasuryToken?: PublicKey;
+  /// referrer token account of the mint swapped FROM, for a normal pool paying a referral fee
+  referrerFeeToken?: PublicKey;
+  /// pyth a, pyth b, serum market, bids and asks for a normal pool,
+  /// the exchange rate account for a stable pool with an adapter, none otherwise
+  priceAccounts: PublicKey[];
+}
+
+export const createRouteInstruction = (
+  config: PublicKey,
+  marketAuthority: PublicKey,
+  userTransferAuthority: PublicKey,
+  source: PublicKey,
+  destination: PublicKey,
+  rewardToken: PublicKey,
+  sourceRewardToken: PublicKey,
+  hops: RouteHop[],
+  routeData: RouteData,
+  programId: PublicKey,
+  feeDiscount?: PublicKey,
+  feeDiscountTiers?: PublicKey,
+  userReferrerData?: PublicKey,
+  referrerToken?: PublicKey
+): TransactionInstruction => {
+  if (hops.length === 0 || hops.length > MAX_ROUTE_HOPS) {
+    throw new Error(`A route trades 1 to ${MAX_ROUTE_HOPS} pools, got ${hops.length}`);
+  }
+
+  const keys = [
+    { pubkey: config, isSigner: false, isWritable: false },
+    { pubkey: marketAuthority, isSigner: false, isWritable: false },
+    { pubkey: userTransferAuthority, isSigner: true, isWritable: false },
+    { pubkey: source, isSigner: false, isWritable: true },
+    { pubkey: destination, isSigner: false, isWritable: true },
+    { pubkey: rewardToken, isSigner: false, isWritable: true },
+    { pubkey: sourceRewardToken, isSigner: false, isWritable: true },
+    { pubkey: TOKEN_PROGRAM_ID, isSigner: false, isWritable: false },
+  ];
+  if (feeDiscount && feeDiscountTiers) {
+    keys.push(
+      { pubkey: feeDiscount, isSigner: false, isWritable: false },
+      { pubkey: feeDiscountTiers, isSigner: false, isWritable: false }
+    );
+  }
+  if (userReferrerData && referrerToken) {
+    keys.push(
+      { pubkey: userReferrerData, isSigner: false, isWritable: false },
+      { pubkey: referrerToken, isSigner: false, isWritable: true }
+    );
+  }
+  hops.forEach((hop) => {
+    keys.push(
+      { pubkey: hop.tokenSwap, isSigner: false, isWritable: true },
+      { pubkey: hop.swapAuthority, isSigner: false, isWritable: false },
+      { pubkey: hop.swapSource, isSigner: false, isWritable: true },
+      { pubkey: hop.swapDestination, isSigner: false, isWritable: true },
+      { pubkey: hop.adminFeeDestination, isSigner: false, isWritable: true }
+    );
+    [hop.treasuryToken, hop.referrerFeeToken].forEach((pubkey) => {
+      if (pubkey) {
+        keys.push({ pubkey, isSigner: false, isWritable: true });
+      }
+    });
+    keys.push(...hop.priceAccounts.map((pubkey) => ({ pubkey, isSigner: false, isWritable: false })));
+  });
+
+  const dataLayout = struct([u8('instruction'), RouteDataLayout]);
+  const data = Buffer.alloc(dataLayout.span);
+  dataLayout.encode(
+    {
+      instruction: RouterInstruction.Route,
+      routeData,
+    },
+    data
+  );
+
+  return new TransactionInstruction({
+    keys,
+    programId,
+    data,
+  });
+};
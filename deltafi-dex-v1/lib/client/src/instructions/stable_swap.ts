@@ -1,6 +1,13 @@
 import { TOKEN_PROGRAM_ID } from '@solana/spl-token';
-import { PublicKey, SYSVAR_CLOCK_PUBKEY, SYSVAR_RENT_PUBKEY, TransactionInstruction } from '@solana/web3.js';
-import { struct, u8 } from 'buffer-layout';
+import {
+  PublicKey,
+  SystemProgram,
+  SYSVAR_CLOCK_PUBKEY,
+  SYSVAR_RENT_PUBKEY,
+  TransactionInstruction,
+} from '@solana/web3.js';
+import { seq, struct, u8 } from 'buffer-layout';
+import BigNumber from 'bignumber.js';
 
 import { decimal, u64 } from '../util';
 
@@ -9,6 +16,12 @@ export enum StableSwapInstruction {
   Swap,
   Deposit,
   Withdraw,
+  PeggedInitialize = 19,
+  InitializeV2 = 28,
+  TriInitialize = 47,
+  TriSwap,
+  TriDeposit,
+  TriWithdraw,
 }
 
 export interface InitializeStableData {
@@ -73,3 +86,327 @@ export const createInitStableSwapInstruction = (
     data,
   });
 };
+
+/**
+ * Create an instruction initializing a stable pool at the address of `findSwapAddress`, the
+ * pool account is created by the program and paid by the admin
+ */
+export const createInitStableSwapV2Instruction = (
+  config: PublicKey,
+  tokenSwap: PublicKey,
+  authority: PublicKey,
+  adminFeeKeyA: PublicKey,
+  adminFeeKeyB: PublicKey,
+  tokenA: PublicKey,
+  tokenB: PublicKey,
+  poolMint: PublicKey,
+  poolToken: PublicKey,
+  admin: PublicKey,
+  initData: InitializeStableData,
+  programId: PublicKey
+): TransactionInstruction => {
+  const instruction = createInitStableSwapInstruction(
+    config,
+    tokenSwap,
+    authority,
+    adminFeeKeyA,
+    adminFeeKeyB,
+    tokenA,
+    tokenB,
+    poolMint,
+    poolToken,
+    admin,
+    initData,
+    programId
+  );
+  instruction.keys[1].isSigner = false;
+  instruction.keys[9].isWritable = true;
+  instruction.keys.push({ pubkey: SystemProgram.programId, isSigner: false, isWritable: false });
+  instruction.data[0] = StableSwapInstruction.InitializeV2;
+  return instruction;
+};
+
+export interface InitializePeggedData {
+  nonce: number;
+  peggedPrice: BigNumber;
+  tokenADecimals: number;
+  tokenBDecimals: number;
+  tokenAAmount: number | bigint;
+  tokenBAmount: number | bigint;
+}
+
+/** @internal */
+export const InitializePeggedDataLayout = struct<InitializePeggedData>(
+  [
+    u8('nonce'),
+    decimal('peggedPrice'),
+    u8('tokenADecimals'),
+    u8('tokenBDecimals'),
+    u64('tokenAAmount'),
+    u64('tokenBAmount'),
+  ],
+  'initData'
+);
+
+export const createInitPeggedSwapInstruction = (
+  config: PublicKey,
+  tokenSwap: PublicKey,
+  authority: PublicKey,
+  adminFeeKeyA: PublicKey,
+  adminFeeKeyB: PublicKey,
+  tokenA: PublicKey,
+  tokenB: PublicKey,
+  poolMint: PublicKey,
+  poolToken: PublicKey,
+  admin: PublicKey,
+  initData: InitializePeggedData,
+  programId: PublicKey
+): TransactionInstruction => {
+  const keys = [
+    { pubkey: config, isSigner: false, isWritable: false },
+    { pubkey: tokenSwap, isSigner: true, isWritable: true },
+    { pubkey: authority, isSigner: false, isWritable: false },
+    { pubkey: adminFeeKeyA, isSigner: false, isWritable: false },
+    { pubkey: adminFeeKeyB, isSigner: false, isWritable: false },
+    { pubkey: tokenA, isSigner: false, isWritable: false },
+    { pubkey: tokenB, isSigner: false, isWritable: false },
+    { pubkey: poolMint, isSigner: false, isWritable: true },
+    { pubkey: poolToken, isSigner: false, isWritable: true },
+    { pubkey: admin, isSigner: true, isWritable: false },
+    { pubkey: SYSVAR_RENT_PUBKEY, isSigner: false, isWritable: false },
+    { pubkey: TOKEN_PROGRAM_ID, isSigner: false, isWritable: false },
+  ];
+  const dataLayout = struct([u8('instruction'), InitializePeggedDataLayout]);
+  const data = Buffer.alloc(dataLayout.span);
+  dataLayout.encode(
+    {
+      instruction: StableSwapInstruction.PeggedInitialize,
+      initData,
+    },
+    data
+  );
+
+  return new TransactionInstruction({
+    keys,
+    programId,
+    data,
+  });
+};
+
+/** Number of tokens of a tri pool */
+export const TRI_POOL_TOKENS = 3;
+
+export interface InitializeTriData {
+  nonce: number;
+  amp: number | bigint;
+  tokenDecimals: number[];
+}
+
+/** @internal */
+export const InitializeTriDataLayout = struct<InitializeTriData>(
+  [u8('nonce'), u64('amp'), seq(u8(), TRI_POOL_TOKENS, 'tokenDecimals')],
+  'initData'
+);
+
+/**
+ * Create an instruction initializing a pool of three stable tokens, the token accounts hold
+ * the initial liquidity
+ */
+export const createInitTriSwapInstruction = (
+  config: PublicKey,
+  triSwap: PublicKey,
+  authority: PublicKey,
+  adminFeeKeys: PublicKey[],
+  tokens: PublicKey[],
+  poolMint: PublicKey,
+  poolToken: PublicKey,
+  admin: PublicKey,
+  initData: InitializeTriData,
+  programId: PublicKey
+): TransactionInstruction => {
+  const keys = [
+    { pubkey: config, isSigner: false, isWritable: false },
+    { pubkey: triSwap, isSigner: true, isWritable: true },
+    { pubkey: authority, isSigner: false, isWritable: false },
+    ...adminFeeKeys.map((pubkey) => ({ pubkey, isSigner: false, isWritable: false })),
+    ...tokens.map((pubkey) => ({ pubkey, isSigner: false, isWritable: false })),
+    { pubkey: poolMint, isSigner: false, isWritable: true },
+    { pubkey: poolToken, isSigner: false, isWritable: true },
+    { pubkey: admin, isSigner: true, isWritable: false },
+    { pubkey: SYSVAR_RENT_PUBKEY, isSigner: false, isWritable: false },
+    { pubkey: TOKEN_PROGRAM_ID, isSigner: false, isWritable: false },
+  ];
+  const dataLayout = struct([u8('instruction'), InitializeTriDataLayout]);
+  const data = Buffer.alloc(dataLayout.span);
+  dataLayout.encode(
+    {
+      instruction: StableSwapInstruction.TriInitialize,
+      initData,
+    },
+    data
+  );
+
+  return new TransactionInstruction({
+    keys,
+    programId,
+    data,
+  });
+};
+
+export interface TriSwapData {
+  amountIn: number | bigint;
+  minimumAmountOut: number | bigint;
+}
+
+/** @internal */
+export const TriSwapDataLayout = struct<TriSwapData>([u64('amountIn'), u64('minimumAmountOut')], 'swapData');
+
+export const createTriSwapInstruction = (
+  config: PublicKey,
+  triSwap: PublicKey,
+  authority: PublicKey,
+  userTransferAuthority: PublicKey,
+  source: PublicKey,
+  swapSource: PublicKey,
+  swapDestination: PublicKey,
+  destination: PublicKey,
+  adminFeeDestination: PublicKey,
+  swapData: TriSwapData,
+  programId: PublicKey,
+  treasuryToken?: PublicKey
+): TransactionInstruction => {
+  const keys = [
+    { pubkey: config, isSigner: false, isWritable: false },
+    { pubkey: triSwap, isSigner: false, isWritable: true },
+    { pubkey: authority, isSigner: false, isWritable: false },
+    { pubkey: userTransferAuthority, isSigner: true, isWritable: false },
+    { pubkey: source, isSigner: false, isWritable: true },
+    { pubkey: swapSource, isSigner: false, isWritable: true },
+    { pubkey: swapDestination, isSigner: false, isWritable: true },
+    { pubkey: destination, isSigner: false, isWritable: true },
+    { pubkey: adminFeeDestination, isSigner: false, isWritable: true },
+    { pubkey: TOKEN_PROGRAM_ID, isSigner: false, isWritable: false },
+  ];
+  if (treasuryToken) {
+    keys.push({ pubkey: treasuryToken, isSigner: false, isWritable: true });
+  }
+  const dataLayout = struct([u8('instruction'), TriSwapDataLayout]);
+  const data = Buffer.alloc(dataLayout.span);
+  dataLayout.encode(
+    {
+      instruction: StableSwapInstruction.TriSwap,
+      swapData,
+    },
+    data
+  );
+
+  return new TransactionInstruction({
+    keys,
+    programId,
+    data,
+  });
+};
+
+export interface TriDepositData {
+  tokenAmounts: (number | bigint)[];
+  minMintAmount: number | bigint;
+}
+
+/** @internal */
+export const TriDepositDataLayout = struct<TriDepositData>(
+  [seq(u64(), TRI_POOL_TOKENS, 'tokenAmounts'), u64('minMintAmount')],
+  'depositData'
+);
+
+export const createTriDepositInstruction = (
+  config: PublicKey,
+  triSwap: PublicKey,
+  authority: PublicKey,
+  userTransferAuthority: PublicKey,
+  sources: PublicKey[],
+  tokens: PublicKey[],
+  poolMint: PublicKey,
+  destination: PublicKey,
+  depositData: TriDepositData,
+  programId: PublicKey
+): TransactionInstruction => {
+  const keys = [
+    { pubkey: config, isSigner: false, isWritable: false },
+    { pubkey: triSwap, isSigner: false, isWritable: true },
+    { pubkey: authority, isSigner: false, isWritable: false },
+    { pubkey: userTransferAuthority, isSigner: true, isWritable: false },
+    ...sources.map((pubkey) => ({ pubkey, isSigner: false, isWritable: true })),
+    ...tokens.map((pubkey) => ({ pubkey, isSigner: false, isWritable: true })),
+    { pubkey: poolMint, isSigner: false, isWritable: true },
+    { pubkey: destination, isSigner: false, isWritable: true },
+    { pubkey: TOKEN_PROGRAM_ID, isSigner: false, isWritable: false },
+  ];
+  const dataLayout = struct([u8('instruction'), TriDepositDataLayout]);
+  const data = Buffer.alloc(dataLayout.span);
+  dataLayout.encode(
+    {
+      instruction: StableSwapInstruction.TriDeposit,
+      depositData,
+    },
+    data
+  );
+
+  return new TransactionInstruction({
+    keys,
+    programId,
+    data,
+  });
+};
+
+export interface TriWithdrawData {
+  poolTokenAmount: number | bigint;
+  minimumTokenAmounts: (number | bigint)[];
+}
+
+/** @internal */
+export const TriWithdrawDataLayout = struct<TriWithdrawData>(
+  [u64('poolTokenAmount'), seq(u64(), TRI_POOL_TOKENS, 'minimumTokenAmounts')],
+  'withdrawData'
+);
+
+export const createTriWithdrawInstruction = (
+  config: PublicKey,
+  triSwap: PublicKey,
+  authority: PublicKey,
+  userTransferAuthority: PublicKey,
+  poolMint: PublicKey,
+  source: PublicKey,
+  tokens: PublicKey[],
+  destinations: PublicKey[],
+  adminFeeKeys: PublicKey[],
+  withdrawData: TriWithdrawData,
+  programId: PublicKey
+): TransactionInstruction => {
+  const keys = [
+    { pubkey: config, isSigner: false, isWritable: false },
+    { pubkey: triSwap, isSigner: false, isWritable: true },
+    { pubkey: authority, isSigner: false, isWritable: false },
+    { pubkey: userTransferAuthority, isSigner: true, isWritable: false },
+    { pubkey: poolMint, isSigner: false, isWritable: true },
+    { pubkey: source, isSigner: false, isWritable: true },
+    ...tokens.map((pubkey) => ({ pubkey, isSigner: false, isWritable: true })),
+    ...destinations.map((pubkey) => ({ pubkey, isSigner: false, isWritable: true })),
+    ...adminFeeKeys.map((pubkey) => ({ pubkey, isSigner: false, isWritable: true })),
+    { pubkey: TOKEN_PROGRAM_ID, isSigner: false, isWritable: false },
+  ];
+  const dataLayout = struct([u8('instruction'), TriWithdrawDataLayout]);
+  const data = Buffer.alloc(dataLayout.span);
+  dataLayout.encode(
+    {
+      instruction: StableSwapInstruction.TriWithdraw,
+      withdrawData,
+    },
+    data
+  );
+
+  return new TransactionInstruction({
+    keys,
+    programId,
+    data,
+  });
+};
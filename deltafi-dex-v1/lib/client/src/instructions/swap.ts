@@ -1,9 +1,15 @@
 import { TOKEN_PROGRAM_ID } from '@solana/spl-token';
-import { PublicKey, SYSVAR_CLOCK_PUBKEY, SYSVAR_RENT_PUBKEY, TransactionInstruction } from '@solana/web3.js';
+import {
+  PublicKey,
+  SystemProgram,
+  SYSVAR_CLOCK_PUBKEY,
+  SYSVAR_RENT_PUBKEY,
+  TransactionInstruction,
+} from '@solana/web3.js';
 import { struct, u8 } from 'buffer-layout';
 import BigNumber from 'bignumber.js';
 
-import { decimal, u64, publicKey } from '../util';
+import { bool, decimal, u64, u128, publicKey } from '../util';
 import { tokenToString } from 'typescript';
 
 export enum SwapInstruction {
@@ -12,6 +18,20 @@ export enum SwapInstruction {
   Deposit,
   Withdraw,
   SetReferrer,
+  GetQuote = 31,
+  UpdatePriceCache,
+  AcquireScratch,
+  ReleaseScratch,
+  InitializeV2,
+  StakeFeeDiscount,
+  UnstakeFeeDiscount,
+  UpdateReferrer,
+  InitReferrerStats,
+  Rebalance = 51,
+  LockLiquidity,
+  UnlockLiquidity,
+  Sync = 58,
+  RefreshPrice,
 }
 
 export interface InitializeData {
@@ -23,6 +43,8 @@ export interface InitializeData {
   tokenAAmount: number;
   tokenBAmount: number;
   oraclePriorityFlags: number;
+  // Token A is the quote token, the mid price is then the price of token B in token A
+  quoteIsTokenA: boolean;
 }
 
 /** @internal */
@@ -36,6 +58,7 @@ export const InitializeDataLayout = struct<InitializeData>(
     u64('tokenAAmount'),
     u64('tokenBAmount'),
     u8('oraclePriorityFlags'),
+    bool('quoteIsTokenA'),
   ],
   'initData'
 );
@@ -100,6 +123,59 @@ export const createInitSwapInstruction = (
   });
 };
 
+/**
+ * Create an instruction initializing a pool at the address of `findSwapAddress`, the pool
+ * account is created by the program and paid by the admin
+ */
+export const createInitSwapV2Instruction = (
+  config: PublicKey,
+  tokenSwap: PublicKey,
+  authority: PublicKey,
+  adminFeeKeyA: PublicKey,
+  adminFeeKeyB: PublicKey,
+  tokenA: PublicKey,
+  tokenB: PublicKey,
+  poolMint: PublicKey,
+  poolToken: PublicKey,
+  pythProductA: PublicKey,
+  pythPriceA: PublicKey,
+  pythProductB: PublicKey,
+  pythPriceB: PublicKey,
+  serumMarket: PublicKey,
+  serumBids: PublicKey,
+  serumAsks: PublicKey,
+  admin: PublicKey,
+  initData: InitializeData,
+  programId: PublicKey
+): TransactionInstruction => {
+  const instruction = createInitSwapInstruction(
+    config,
+    tokenSwap,
+    authority,
+    adminFeeKeyA,
+    adminFeeKeyB,
+    tokenA,
+    tokenB,
+    poolMint,
+    poolToken,
+    pythProductA,
+    pythPriceA,
+    pythProductB,
+    pythPriceB,
+    serumMarket,
+    serumBids,
+    serumAsks,
+    admin,
+    initData,
+    programId
+  );
+  instruction.keys[1].isSigner = false;
+  instruction.keys[13].isWritable = true;
+  instruction.keys.push({ pubkey: SystemProgram.programId, isSigner: false, isWritable: false });
+  instruction.data[0] = SwapInstruction.InitializeV2;
+  return instruction;
+};
+
 export interface SwapData {
   amountIn: bigint;
   minimumAmountOut: bigint;
@@ -133,7 +209,11 @@ export const createSwapInstruction = (
   pythA: PublicKey,
   pythB: PublicKey,
   swapData: SwapData,
-  programId: PublicKey
+  programId: PublicKey,
+  priceCache?: PublicKey,
+  feeDiscount?: PublicKey,
+  feeDiscountTiers?: PublicKey,
+  treasuryToken?: PublicKey
 ) => {
   const keys = [
     { pubkey: config, isSigner: false, isWritable: false },
@@ -152,6 +232,18 @@ export const createSwapInstruction = (
     { pubkey: pythB, isSigner: false, isWritable: false },
     { pubkey: TOKEN_PROGRAM_ID, isSigner: false, isWritable: false },
   ];
+  if (priceCache) {
+    keys.push({ pubkey: priceCache, isSigner: false, isWritable: false });
+  }
+  if (feeDiscount && feeDiscountTiers) {
+    keys.push(
+      { pubkey: feeDiscount, isSigner: false, isWritable: false },
+      { pubkey: feeDiscountTiers, isSigner: false, isWritable: false }
+    );
+  }
+  if (treasuryToken) {
+    keys.push({ pubkey: treasuryToken, isSigner: false, isWritable: true });
+  }
 
   const dataLayout = struct([u8('instruction'), SwapDataLayout]);
   const data = Buffer.alloc(dataLayout.span);
@@ -322,3 +414,527 @@ export const createSetReferrerInstruction = (
     programId,
   });
 };
+
+/**
+ * Create an instruction changing the referrer of the owner once the referrer cooldown has
+ * passed, the dummy referrer clears it
+ */
+export const createUpdateReferrerInstruction = (
+  config: PublicKey,
+  owner: PublicKey,
+  userReferrerData: PublicKey,
+  referrer: PublicKey,
+  programId: PublicKey
+): TransactionInstruction => {
+  const keys = [
+    { pubkey: config, isSigner: false, isWritable: false },
+    { pubkey: owner, isSigner: true, isWritable: false },
+    { pubkey: userReferrerData, isSigner: false, isWritable: true },
+    { pubkey: referrer, isSigner: false, isWritable: false },
+    { pubkey: TOKEN_PROGRAM_ID, isSigner: false, isWritable: false },
+  ];
+  const dataLayout = struct([u8('instruction')]);
+  const data = Buffer.alloc(dataLayout.span);
+  dataLayout.encode(
+    {
+      instruction: SwapInstruction.UpdateReferrer,
+    },
+    data
+  );
+
+  return new TransactionInstruction({
+    keys,
+    data,
+    programId,
+  });
+};
+
+/**
+ * Create an instruction creating the referral stats of a referrer DELTAFI token account
+ */
+export const createInitReferrerStatsInstruction = (
+  config: PublicKey,
+  referrerToken: PublicKey,
+  referrerStats: PublicKey,
+  payer: PublicKey,
+  programId: PublicKey
+): TransactionInstruction => {
+  const keys = [
+    { pubkey: config, isSigner: false, isWritable: false },
+    { pubkey: referrerToken, isSigner: false, isWritable: false },
+    { pubkey: referrerStats, isSigner: false, isWritable: true },
+    { pubkey: payer, isSigner: true, isWritable: true },
+    { pubkey: SYSVAR_RENT_PUBKEY, isSigner: false, isWritable: false },
+    { pubkey: SystemProgram.programId, isSigner: false, isWritable: false },
+    { pubkey: TOKEN_PROGRAM_ID, isSigner: false, isWritable: false },
+  ];
+  const dataLayout = struct([u8('instruction')]);
+  const data = Buffer.alloc(dataLayout.span);
+  dataLayout.encode(
+    {
+      instruction: SwapInstruction.InitReferrerStats,
+    },
+    data
+  );
+
+  return new TransactionInstruction({
+    keys,
+    data,
+    programId,
+  });
+};
+
+export interface SwapQuote {
+  amountOut: bigint;
+  tradeFee: bigint;
+  adminFee: bigint;
+  priceImpactBps: bigint;
+  marketPrice: bigint;
+}
+
+/** @internal */
+export const SwapQuoteLayout = struct<SwapQuote>(
+  [u64('amountOut'), u64('tradeFee'), u64('adminFee'), u64('priceImpactBps'), u128('marketPrice')],
+  'swapQuote'
+);
+
+/**
+ * Create a read-only quote instruction, the quote is the return data of the instruction
+ * and is decoded with `SwapQuoteLayout`
+ */
+export const createGetQuoteInstruction = (
+  config: PublicKey,
+  tokenSwap: PublicKey,
+  oracleAccounts: PublicKey[],
+  amountIn: bigint,
+  swapDirection: SWAP_DIRECTION,
+  programId: PublicKey
+): TransactionInstruction => {
+  const keys = [
+    { pubkey: config, isSigner: false, isWritable: false },
+    { pubkey: tokenSwap, isSigner: false, isWritable: false },
+    ...oracleAccounts.map((pubkey) => ({ pubkey, isSigner: false, isWritable: false })),
+  ];
+  const dataLayout = struct([u8('instruction'), u64('amountIn'), u8('swapDirection')]);
+  const data = Buffer.alloc(dataLayout.span);
+  dataLayout.encode(
+    {
+      instruction: SwapInstruction.GetQuote,
+      amountIn,
+      swapDirection,
+    },
+    data
+  );
+
+  return new TransactionInstruction({
+    keys,
+    data,
+    programId,
+  });
+};
+
+/**
+ * Create an instruction refreshing the price cache of a pool from its pyth accounts,
+ * signed by the relayer of the cache
+ */
+export const createUpdatePriceCacheInstruction = (
+  tokenSwap: PublicKey,
+  priceCache: PublicKey,
+  relayer: PublicKey,
+  pythA: PublicKey,
+  pythB: PublicKey,
+  programId: PublicKey
+): TransactionInstruction => {
+  const keys = [
+    { pubkey: tokenSwap, isSigner: false, isWritable: false },
+    { pubkey: priceCache, isSigner: false, isWritable: true },
+    { pubkey: relayer, isSigner: true, isWritable: false },
+    { pubkey: pythA, isSigner: false, isWritable: false },
+    { pubkey: pythB, isSigner: false, isWritable: false },
+  ];
+  const dataLayout = struct([u8('instruction')]);
+  const data = Buffer.alloc(dataLayout.span);
+  dataLayout.encode(
+    {
+      instruction: SwapInstruction.UpdatePriceCache,
+    },
+    data
+  );
+
+  return new TransactionInstruction({
+    keys,
+    data,
+    programId,
+  });
+};
+
+/**
+ * Create an instruction holding a free scratch account, its token account is delegated to
+ * the holder until the scratch account is released in the same transaction
+ */
+export const createAcquireScratchInstruction = (
+  scratchAccount: PublicKey,
+  scratchToken: PublicKey,
+  holder: PublicKey,
+  programId: PublicKey
+): TransactionInstruction => {
+  const keys = [
+    { pubkey: scratchAccount, isSigner: false, isWritable: true },
+    { pubkey: scratchToken, isSigner: false, isWritable: true },
+    { pubkey: holder, isSigner: true, isWritable: false },
+    { pubkey: TOKEN_PROGRAM_ID, isSigner: false, isWritable: false },
+  ];
+  const dataLayout = struct([u8('instruction')]);
+  const data = Buffer.alloc(dataLayout.span);
+  dataLayout.encode(
+    {
+      instruction: SwapInstruction.AcquireScratch,
+    },
+    data
+  );
+
+  return new TransactionInstruction({
+    keys,
+    data,
+    programId,
+  });
+};
+
+/**
+ * Create an instruction releasing a scratch account, the tokens left in custody are sent
+ * to the destination
+ */
+export const createReleaseScratchInstruction = (
+  scratchAccount: PublicKey,
+  scratchToken: PublicKey,
+  holder: PublicKey,
+  destination: PublicKey,
+  programId: PublicKey
+): TransactionInstruction => {
+  const keys = [
+    { pubkey: scratchAccount, isSigner: false, isWritable: true },
+    { pubkey: scratchToken, isSigner: false, isWritable: true },
+    { pubkey: holder, isSigner: true, isWritable: false },
+    { pubkey: destination, isSigner: false, isWritable: true },
+    { pubkey: TOKEN_PROGRAM_ID, isSigner: false, isWritable: false },
+  ];
+  const dataLayout = struct([u8('instruction')]);
+  const data = Buffer.alloc(dataLayout.span);
+  dataLayout.encode(
+    {
+      instruction: SwapInstruction.ReleaseScratch,
+    },
+    data
+  );
+
+  return new TransactionInstruction({
+    keys,
+    data,
+    programId,
+  });
+};
+
+/**
+ * Create an instruction staking DELTAFI in the fee discount account of the owner, created on
+ * the first stake. The discount applies to the swaps from the next slot.
+ */
+export const createStakeFeeDiscountInstruction = (
+  config: PublicKey,
+  feeDiscount: PublicKey,
+  feeDiscountToken: PublicKey,
+  source: PublicKey,
+  owner: PublicKey,
+  deltafiMint: PublicKey,
+  amount: bigint,
+  programId: PublicKey
+): TransactionInstruction => {
+  const keys = [
+    { pubkey: config, isSigner: false, isWritable: false },
+    { pubkey: feeDiscount, isSigner: false, isWritable: true },
+    { pubkey: feeDiscountToken, isSigner: false, isWritable: true },
+    { pubkey: source, isSigner: false, isWritable: true },
+    { pubkey: owner, isSigner: true, isWritable: true },
+    { pubkey: deltafiMint, isSigner: false, isWritable: false },
+    { pubkey: SYSVAR_RENT_PUBKEY, isSigner: false, isWritable: false },
+    { pubkey: SystemProgram.programId, isSigner: false, isWritable: false },
+    { pubkey: TOKEN_PROGRAM_ID, isSigner: false, isWritable: false },
+  ];
+  const dataLayout = struct([u8('instruction'), u64('amount')]);
+  const data = Buffer.alloc(dataLayout.span);
+  dataLayout.encode(
+    {
+      instruction: SwapInstruction.StakeFeeDiscount,
+      amount,
+    },
+    data
+  );
+
+  return new TransactionInstruction({
+    keys,
+    data,
+    programId,
+  });
+};
+
+/**
+ * Create an instruction unstaking DELTAFI from the fee discount account of the owner
+ */
+export const createUnstakeFeeDiscountInstruction = (
+  feeDiscount: PublicKey,
+  feeDiscountToken: PublicKey,
+  owner: PublicKey,
+  destination: PublicKey,
+  amount: bigint,
+  programId: PublicKey
+): TransactionInstruction => {
+  const keys = [
+    { pubkey: feeDiscount, isSigner: false, isWritable: true },
+    { pubkey: feeDiscountToken, isSigner: false, isWritable: true },
+    { pubkey: owner, isSigner: true, isWritable: false },
+    { pubkey: destination, isSigner: false, isWritable: true },
+    { pubkey: TOKEN_PROGRAM_ID, isSigner: false, isWritable: false },
+  ];
+  const dataLayout = struct([u8('instruction'), u64('amount')]);
+  const data = Buffer.alloc(dataLayout.span);
+  dataLayout.encode(
+    {
+      instruction: SwapInstruction.UnstakeFeeDiscount,
+      amount,
+    },
+    data
+  );
+
+  return new TransactionInstruction({
+    keys,
+    data,
+    programId,
+  });
+};
+
+export interface RebalanceData {
+  maxAmountIn: bigint;
+  minimumAmountOut: bigint;
+}
+
+/** @internal */
+export const RebalanceDataLayout = struct<RebalanceData>(
+  [u64('maxAmountIn'), u64('minimumAmountOut')],
+  'rebalanceData'
+);
+
+/**
+ * Create an instruction trading the reserves of a pool drifted from the oracle price back to
+ * the targets, paying the short token in and the long token out with the rebalance bounty
+ */
+export const createRebalanceInstruction = (
+  config: PublicKey,
+  tokenSwap: PublicKey,
+  swapAuthority: PublicKey,
+  userTransferAuthority: PublicKey,
+  source: PublicKey,
+  destination: PublicKey,
+  tokenA: PublicKey,
+  tokenB: PublicKey,
+  pythA: PublicKey,
+  pythB: PublicKey,
+  serumMarket: PublicKey,
+  serumBids: PublicKey,
+  serumAsks: PublicKey,
+  rebalanceData: RebalanceData,
+  programId: PublicKey
+): TransactionInstruction => {
+  const keys = [
+    { pubkey: config, isSigner: false, isWritable: false },
+    { pubkey: tokenSwap, isSigner: false, isWritable: true },
+    { pubkey: swapAuthority, isSigner: false, isWritable: false },
+    { pubkey: userTransferAuthority, isSigner: true, isWritable: false },
+    { pubkey: source, isSigner: false, isWritable: true },
+    { pubkey: destination, isSigner: false, isWritable: true },
+    { pubkey: tokenA, isSigner: false, isWritable: true },
+    { pubkey: tokenB, isSigner: false, isWritable: true },
+    { pubkey: pythA, isSigner: false, isWritable: false },
+    { pubkey: pythB, isSigner: false, isWritable: false },
+    { pubkey: serumMarket, isSigner: false, isWritable: false },
+    { pubkey: serumBids, isSigner: false, isWritable: false },
+    { pubkey: serumAsks, isSigner: false, isWritable: false },
+    { pubkey: TOKEN_PROGRAM_ID, isSigner: false, isWritable: false },
+  ];
+  const dataLayout = struct([u8('instruction'), RebalanceDataLayout]);
+  const data = Buffer.alloc(dataLayout.span);
+  dataLayout.encode(
+    {
+      instruction: SwapInstruction.Rebalance,
+      rebalanceData,
+    },
+    data
+  );
+
+  return new TransactionInstruction({
+    keys,
+    data,
+    programId,
+  });
+};
+
+export interface LockLiquidityData {
+  amount: bigint;
+  unlockTs: bigint;
+}
+
+/** @internal */
+export const LockLiquidityDataLayout = struct<LockLiquidityData>(
+  [u64('amount'), u64('unlockTs')],
+  'lockLiquidityData'
+);
+
+/**
+ * Create an instruction locking pool tokens of the owner in its liquidity lock on the pool
+ * until the cliff, created on the first lock. Later locks cannot bring the cliff forward.
+ */
+export const createLockLiquidityInstruction = (
+  tokenSwap: PublicKey,
+  liquidityLock: PublicKey,
+  liquidityLockToken: PublicKey,
+  source: PublicKey,
+  owner: PublicKey,
+  poolMint: PublicKey,
+  lockLiquidityData: LockLiquidityData,
+  programId: PublicKey
+): TransactionInstruction => {
+  const keys = [
+    { pubkey: tokenSwap, isSigner: false, isWritable: false },
+    { pubkey: liquidityLock, isSigner: false, isWritable: true },
+    { pubkey: liquidityLockToken, isSigner: false, isWritable: true },
+    { pubkey: source, isSigner: false, isWritable: true },
+    { pubkey: owner, isSigner: true, isWritable: true },
+    { pubkey: poolMint, isSigner: false, isWritable: false },
+    { pubkey: SYSVAR_RENT_PUBKEY, isSigner: false, isWritable: false },
+    { pubkey: SystemProgram.programId, isSigner: false, isWritable: false },
+    { pubkey: TOKEN_PROGRAM_ID, isSigner: false, isWritable: false },
+  ];
+  const dataLayout = struct([u8('instruction'), LockLiquidityDataLayout]);
+  const data = Buffer.alloc(dataLayout.span);
+  dataLayout.encode(
+    {
+      instruction: SwapInstruction.LockLiquidity,
+      lockLiquidityData,
+    },
+    data
+  );
+
+  return new TransactionInstruction({
+    keys,
+    data,
+    programId,
+  });
+};
+
+/**
+ * Create an instruction withdrawing all the locked pool tokens of the owner past the cliff
+ */
+export const createUnlockLiquidityInstruction = (
+  liquidityLock: PublicKey,
+  liquidityLockToken: PublicKey,
+  owner: PublicKey,
+  destination: PublicKey,
+  programId: PublicKey
+): TransactionInstruction => {
+  const keys = [
+    { pubkey: liquidityLock, isSigner: false, isWritable: true },
+    { pubkey: liquidityLockToken, isSigner: false, isWritable: true },
+    { pubkey: owner, isSigner: true, isWritable: false },
+    { pubkey: destination, isSigner: false, isWritable: true },
+    { pubkey: TOKEN_PROGRAM_ID, isSigner: false, isWritable: false },
+  ];
+  const dataLayout = struct([u8('instruction')]);
+  const data = Buffer.alloc(dataLayout.span);
+  dataLayout.encode(
+    {
+      instruction: SwapInstruction.UnlockLiquidity,
+    },
+    data
+  );
+
+  return new TransactionInstruction({
+    keys,
+    data,
+    programId,
+  });
+};
+
+/**
+ * Create an instruction crediting the tokens sent directly to the pool to its protocol fees
+ */
+export const createSyncInstruction = (
+  tokenSwap: PublicKey,
+  tokenA: PublicKey,
+  tokenB: PublicKey,
+  programId: PublicKey
+): TransactionInstruction => {
+  const keys = [
+    { pubkey: tokenSwap, isSigner: false, isWritable: true },
+    { pubkey: tokenA, isSigner: false, isWritable: false },
+    { pubkey: tokenB, isSigner: false, isWritable: false },
+    { pubkey: TOKEN_PROGRAM_ID, isSigner: false, isWritable: false },
+  ];
+  const dataLayout = struct([u8('instruction')]);
+  const data = Buffer.alloc(dataLayout.span);
+  dataLayout.encode(
+    {
+      instruction: SwapInstruction.Sync,
+    },
+    data
+  );
+
+  return new TransactionInstruction({
+    keys,
+    data,
+    programId,
+  });
+};
+
+/**
+ * Create an instruction refreshing the market price of a quiet normal pool from its oracle,
+ * the caller is paid a DELTAFI reward to the reward token account
+ */
+export const createRefreshPriceInstruction = (
+  config: PublicKey,
+  tokenSwap: PublicKey,
+  marketAuthority: PublicKey,
+  rewardToken: PublicKey,
+  sourceRewardToken: PublicKey,
+  pythA: PublicKey,
+  pythB: PublicKey,
+  serumMarket: PublicKey,
+  serumBids: PublicKey,
+  serumAsks: PublicKey,
+  programId: PublicKey
+): TransactionInstruction => {
+  const keys = [
+    { pubkey: config, isSigner: false, isWritable: false },
+    { pubkey: tokenSwap, isSigner: false, isWritable: true },
+    { pubkey: marketAuthority, isSigner: false, isWritable: false },
+    { pubkey: rewardToken, isSigner: false, isWritable: true },
+    { pubkey: sourceRewardToken, isSigner: false, isWritable: true },
+    { pubkey: pythA, isSigner: false, isWritable: false },
+    { pubkey: pythB, isSigner: false, isWritable: false },
+    { pubkey: serumMarket, isSigner: false, isWritable: false },
+    { pubkey: serumBids, isSigner: false, isWritable: false },
+    { pubkey: serumAsks, isSigner: false, isWritable: false },
+    { pubkey: TOKEN_PROGRAM_ID, isSigner: false, isWritable: false },
+  ];
+  const dataLayout = struct([u8('instruction')]);
+  const data = Buffer.alloc(dataLayout.span);
+  dataLayout.encode(
+    {
+      instruction: SwapInstruction.RefreshPrice,
+    },
+    data
+  );
+
+  return new TransactionInstruction({
+    keys,
+    data,
+    programId,
+  });
+};
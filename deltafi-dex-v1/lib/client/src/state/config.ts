@@ -1,7 +1,7 @@
 import { AccountInfo, PublicKey, Connection } from '@solana/web3.js';
-import { struct, u8, blob } from 'buffer-layout';
+import { struct, u8, u16, blob, seq } from 'buffer-layout';
 
-import { publicKey, AccountParser, loadAccount } from '../util';
+import { bool, publicKey, u64, AccountParser, loadAccount } from '../util';
 import { Fees, FeesLayout } from './fees';
 import { Rewards, RewardsLayout } from './rewards';
 
@@ -12,6 +12,16 @@ export interface MintInfo {
 /** @internal */
 export const MintLayout = struct<MintInfo>([blob(44), u8("decimals"), blob(37)], 'MintLayout');
 
+export const MAX_MARKET_MAKERS = 2;
+
+export enum AdminRole {
+  SuperAdmin = 0,
+  FeeManager,
+  Pauser,
+  OracleManager,
+  Guardian,
+}
+
 export interface ConfigInfo {
   version: number;
   bumpSeed: number;
@@ -20,6 +30,19 @@ export interface ConfigInfo {
   pythProgramId: PublicKey;
   fees: Fees;
   rewards: Rewards;
+  orderbookProgramId: PublicKey;
+  marketMakers: PublicKey[];
+  feeManagerKey: PublicKey;
+  pauserKey: PublicKey;
+  oracleManagerKey: PublicKey;
+  guardianKey: PublicKey;
+  isOracleFeedRegistryEnabled: boolean;
+  adminOpsEpoch: bigint;
+  adminOpsCount: number;
+  maxAdminOpsPerEpoch: number;
+  isAdminOpsPaused: boolean;
+  isProtocolFeeEnabled: boolean;
+  treasuryKey: PublicKey;
 }
 
 /** @internal */
@@ -33,7 +56,20 @@ export const ConfigInfoLayout = struct<ConfigInfo>(
     FeesLayout('fees'),
     RewardsLayout('rewards'),
     publicKey('deltafiToken'),
-    blob(128, 'reserved'),
+    publicKey('orderbookProgramId'),
+    seq(publicKey(), MAX_MARKET_MAKERS, 'marketMakers'),
+    publicKey('feeManagerKey'),
+    publicKey('pauserKey'),
+    publicKey('oracleManagerKey'),
+    publicKey('guardianKey'),
+    bool('isOracleFeedRegistryEnabled'),
+    u64('adminOpsEpoch'),
+    u16('adminOpsCount'),
+    u16('maxAdminOpsPerEpoch'),
+    bool('isAdminOpsPaused'),
+    bool('isProtocolFeeEnabled'),
+    publicKey('treasuryKey'),
+    blob(65, 'reserved'),
   ],
   'configInfo'
 );
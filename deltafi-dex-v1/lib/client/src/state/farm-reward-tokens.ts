@@ -0,0 +1,90 @@
+import { PublicKey } from '@solana/web3.js';
+import { seq, struct, u8 } from 'buffer-layout';
+
+import { bool, publicKey, u64, u128 } from '../util/layout';
+
+export const MAX_FARM_REWARD_TOKENS = 2;
+
+export interface FarmRewardToken {
+  mint: PublicKey;
+  vault: PublicKey;
+  rewardPerSlot: bigint;
+  remainingAmount: bigint;
+  accRewardPerShare: bigint;
+  lastUpdateSlot: bigint;
+}
+
+/** @internal */
+export const FarmRewardTokenLayout = (property = 'rewardToken') =>
+  struct<FarmRewardToken>(
+    [
+      publicKey('mint'),
+      publicKey('vault'),
+      u64('rewardPerSlot'),
+      u64('remainingAmount'),
+      u128('accRewardPerShare'),
+      u64('lastUpdateSlot'),
+    ],
+    property
+  );
+
+export interface FarmRewardTokens {
+  isInitialized: boolean;
+  bumpSeed: number;
+  farmPoolKey: PublicKey;
+  rewardTokensLen: number;
+  rewardTokens: FarmRewardToken[];
+}
+
+/** @internal */
+export const FarmRewardTokensLayout = struct<FarmRewardTokens>(
+  [
+    bool('isInitialized'),
+    u8('bumpSeed'),
+    publicKey('farmPoolKey'),
+    u8('rewardTokensLen'),
+    seq(FarmRewardTokenLayout(), MAX_FARM_REWARD_TOKENS, 'rewardTokens'),
+  ],
+  'farmRewardTokens'
+);
+
+export const FARM_REWARD_TOKENS_SIZE = FarmRewardTokensLayout.span;
+
+export interface FarmUserRewardTokens {
+  isInitialized: boolean;
+  farmUserKey: PublicKey;
+  rewardDebts: bigint[];
+  rewardsOwed: bigint[];
+}
+
+/** @internal */
+export const FarmUserRewardTokensLayout = struct<FarmUserRewardTokens>(
+  [
+    bool('isInitialized'),
+    publicKey('farmUserKey'),
+    seq(u128(), MAX_FARM_REWARD_TOKENS, 'rewardDebts'),
+    seq(u64(), MAX_FARM_REWARD_TOKENS, 'rewardsOwed'),
+  ],
+  'farmUserRewardTokens'
+);
+
+export const FARM_USER_REWARD_TOKENS_SIZE = FarmUserRewardTokensLayout.span;
+
+export const findFarmRewardTokensAddress = async (farmPool: PublicKey, programId: PublicKey): Promise<PublicKey> => {
+  const [address] = await PublicKey.findProgramAddress(
+    [Buffer.from('farmRewardTokens'), farmPool.toBuffer()],
+    programId
+  );
+  return address;
+};
+
+export const findFarmUserRewardTokensAddress = async (
+  farmUser: PublicKey,
+  programId: PublicKey
+): Promise<PublicKey> => {
+  const [address] = await PublicKey.findProgramAddress(
+    [Buffer.from('farmUserRewardTokens'), farmUser.toBuffer()],
+    programId
+  );
+  return address;
+};
@@ -1,7 +1,7 @@
 import { AccountInfo, PublicKey, Connection } from '@solana/web3.js';
-import { blob, seq, struct, u8 } from 'buffer-layout';
+import { blob, seq, struct, u8, u32 } from 'buffer-layout';
 
-import { AccountParser, bool, publicKey, u64 } from '../util/layout';
+import { AccountParser, bool, decimal, publicKey, u64, u128 } from '../util/layout';
 import { loadAccount } from '../util/account';
 
 export interface FarmInfo {
@@ -15,6 +15,14 @@ export interface FarmInfo {
   feeDenominator: bigint;
   rewardsNumerator: bigint;
   rewardsDenominator: bigint;
+  isFarmUserRegistryEnabled: boolean;
+  farmUserCount: bigint;
+  aprEpoch: bigint;
+  rewardTokenCount: number;
+  emissionStartTs: bigint;
+  rewardPerSlot: bigint;
+  accRewardPerShare: bigint;
+  lastRewardSlot: bigint;
 }
 
 /** @internal */
@@ -30,7 +38,15 @@ export const FarmInfoLayout = struct<FarmInfo>(
     u64('feeDenominator'),
     u64('rewardsNumerator'),
     u64('rewardsDenominator'),
-    blob(64, 'reserved')
+    bool('isFarmUserRegistryEnabled'),
+    u64('farmUserCount'),
+    u64('aprEpoch'),
+    u8('rewardTokenCount'),
+    u64('emissionStartTs'),
+    u64('rewardPerSlot'),
+    u128('accRewardPerShare'),
+    u64('lastRewardSlot'),
+    blob(70, 'reserved')
   ],
   'farmInfo'
 );
@@ -128,19 +144,35 @@ export const FarmUserLayout = struct<FarmUserDataFlat>(
     u64('lastUpdateTs'),
     u64('nextClaimTs'),
     u64('latestDepositSlot'),
-    blob(64, 'reserved'),
+    publicKey('rewardSplit'),
+    decimal('rewardsRemainder'),
+    // 0 for a position without apr epoch, the epoch + 1 otherwise
+    u64('aprEpoch'),
+    // scaled by 10^12
+    u64('apr'),
+    // 0 if not locked
+    u8('lockTier'),
+    u64('unlockTs'),
+    // default pubkey if no one but the owner compounds
+    publicKey('compoundDelegate'),
+    blob(32, 'reserved'),
   ],
   'farmUser'
 );
 
 export const FARM_USER_SIZE = FarmUserLayout.span;
 
-export const isFarmUser = (info: AccountInfo<Buffer>) => info.data.length === FARM_USER_SIZE;
+// farm users created before the lock tiers have no lock tier nor unlock timestamp
+export const LEGACY_FARM_USER_SIZE = FARM_USER_SIZE - 9;
+
+export const isFarmUser = (info: AccountInfo<Buffer>) =>
+  info.data.length === FARM_USER_SIZE || info.data.length === LEGACY_FARM_USER_SIZE;
 
 export const parseFarmUser: AccountParser<FarmUser> = (pubkey: PublicKey, info: AccountInfo<Buffer>) => {
   if (!isFarmUser(info)) return;
 
-  const buffer = Buffer.from(info.data);
+  const buffer = Buffer.alloc(FARM_USER_SIZE);
+  Buffer.from(info.data).copy(buffer);
   const farmUser = FarmUserLayout.decode(buffer);
 
   const { isInitialized } = farmUser;
@@ -169,3 +201,62 @@ export const loadFarmUser = async (
     data: parsed.data,
   };
 };
+
+export const FARM_USER_REGISTRY_PAGE_SIZE = 64;
+
+export interface FarmUserRegistry {
+  isInitialized: boolean;
+  farmPoolKey: PublicKey;
+  page: number;
+  farmUserLen: number;
+  farmUsers: PublicKey[];
+}
+
+/** @internal */
+export const FarmUserRegistryLayout = struct<FarmUserRegistry>(
+  [
+    bool('isInitialized'),
+    publicKey('farmPoolKey'),
+    u32('page'),
+    u8('farmUserLen'),
+    seq(publicKey(), FARM_USER_REGISTRY_PAGE_SIZE, 'farmUsers'),
+  ],
+  'farmUserRegistry'
+);
+
+export const FARM_USER_REGISTRY_SIZE = FarmUserRegistryLayout.span;
+
+export const findFarmUserRegistryAddress = async (
+  farmPool: PublicKey,
+  page: number,
+  farmProgramId: PublicKey
+): Promise<PublicKey> => {
+  const pageBuffer = Buffer.alloc(4);
+  pageBuffer.writeUInt32LE(page);
+  const [address] = await PublicKey.findProgramAddress(
+    [Buffer.from('farmUserRegistry'), farmPool.toBuffer(), pageBuffer],
+    farmProgramId
+  );
+  return address;
+};
+
+/**
+ * Enumerate the farm users of a farm pool from its registry pages, only the farm users
+ * initialized after the registry was enabled are listed
+ */
+export const loadFarmUserKeys = async (
+  connection: Connection,
+  farmPool: PublicKey,
+  farmInfo: FarmInfo,
+  farmProgramId: PublicKey
+): Promise<PublicKey[]> => {
+  const pageCount = Math.ceil(Number(farmInfo.farmUserCount) / FARM_USER_REGISTRY_PAGE_SIZE);
+  const farmUsers: PublicKey[] = [];
+  for (let page = 0; page < pageCount; page++) {
+    const address = await findFarmUserRegistryAddress(farmPool, page, farmProgramId);
+    const accountInfo = await loadAccount(connection, address, farmProgramId);
+    const registry = FarmUserRegistryLayout.decode(Buffer.from(accountInfo.data));
+    farmUsers.push(...registry.farmUsers.slice(0, registry.farmUserLen));
+  }
+  return farmUsers;
+};
@@ -0,0 +1,92 @@
+import { PublicKey } from '@solana/web3.js';
+import { seq, struct, u8, u16 } from 'buffer-layout';
+
+import { bool, publicKey, u64 } from '../util/layout';
+
+export const MAX_FEE_DISCOUNT_TIERS = 4;
+
+export interface FeeDiscountTier {
+  minStakedAmount: bigint;
+  discountBps: number;
+}
+
+/** @internal */
+export const FeeDiscountTierLayout = (property = 'tier') =>
+  struct<FeeDiscountTier>([u64('minStakedAmount'), u16('discountBps')], property);
+
+export interface FeeDiscountTiers {
+  isInitialized: boolean;
+  configKey: PublicKey;
+  tiers: FeeDiscountTier[];
+}
+
+/** @internal */
+export const FeeDiscountTiersLayout = struct<FeeDiscountTiers>(
+  [
+    bool('isInitialized'),
+    publicKey('configKey'),
+    seq(FeeDiscountTierLayout(), MAX_FEE_DISCOUNT_TIERS, 'tiers'),
+  ],
+  'feeDiscountTiers'
+);
+
+export interface FeeDiscountAccount {
+  isInitialized: boolean;
+  bumpSeed: number;
+  configKey: PublicKey;
+  owner: PublicKey;
+  tokenAccount: PublicKey;
+  stakedAmount: bigint;
+  lastStakeSlot: bigint;
+}
+
+/** @internal */
+export const FeeDiscountAccountLayout = struct<FeeDiscountAccount>(
+  [
+    bool('isInitialized'),
+    u8('bumpSeed'),
+    publicKey('configKey'),
+    publicKey('owner'),
+    publicKey('tokenAccount'),
+    u64('stakedAmount'),
+    u64('lastStakeSlot'),
+  ],
+  'feeDiscountAccount'
+);
+
+export const FEE_DISCOUNT_TIERS_SIZE = FeeDiscountTiersLayout.span;
+export const FEE_DISCOUNT_ACCOUNT_SIZE = FeeDiscountAccountLayout.span;
+
+export const findFeeDiscountAddress = async (
+  config: PublicKey,
+  owner: PublicKey,
+  programId: PublicKey
+): Promise<PublicKey> => {
+  const [address] = await PublicKey.findProgramAddress(
+    [Buffer.from('feeDiscount'), config.toBuffer(), owner.toBuffer()],
+    programId
+  );
+  return address;
+};
+
+export const findFeeDiscountTokenAddress = async (
+  feeDiscount: PublicKey,
+  programId: PublicKey
+): Promise<PublicKey> => {
+  const [address] = await PublicKey.findProgramAddress(
+    [Buffer.from('feeDiscountToken'), feeDiscount.toBuffer()],
+    programId
+  );
+  return address;
+};
+
+export const findFeeDiscountTiersAddress = async (
+  config: PublicKey,
+  programId: PublicKey
+): Promise<PublicKey> => {
+  const [address] = await PublicKey.findProgramAddress(
+    [Buffer.from('feeDiscountTiers'), config.toBuffer()],
+    programId
+  );
+  return address;
+};
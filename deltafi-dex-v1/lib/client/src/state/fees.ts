@@ -1,4 +1,4 @@
-import { struct } from 'buffer-layout';
+import { struct, u8 } from 'buffer-layout';
 import { bool, u64 } from '../util';
 
 export interface Fees {
@@ -11,6 +11,12 @@ export interface Fees {
   tradeFeeDenominator: bigint;
   withdrawFeeNumerator: bigint;
   withdrawFeeDenominator: bigint;
+  flashFeeNumerator: bigint;
+  flashFeeDenominator: bigint;
+  dynamicFeeFactor: number;
+  maxDynamicFeeMultiplier: number;
+  treasuryFeeNumerator: bigint;
+  treasuryFeeDenominator: bigint;
 }
 
 /** @internal */
@@ -26,6 +32,12 @@ export const FeesLayout = (property = 'fees') =>
       u64('tradeFeeDenominator'),
       u64('withdrawFeeNumerator'),
       u64('withdrawFeeDenominator'),
+      u64('flashFeeNumerator'),
+      u64('flashFeeDenominator'),
+      u8('dynamicFeeFactor'),
+      u8('maxDynamicFeeMultiplier'),
+      u64('treasuryFeeNumerator'),
+      u64('treasuryFeeDenominator'),
     ],
     property
   );
@@ -4,3 +4,8 @@ export * from './pool-state';
 export * from './rewards';
 export * from './swap';
 export * from './farm';
+export * from './farm-reward-tokens';
+export * from './oracle-feed';
+export * from './fee-discount';
+export * from './referrer-stats';
+export * from './liquidity-lock';
@@ -0,0 +1,53 @@
+import { PublicKey } from '@solana/web3.js';
+import { struct, u8 } from 'buffer-layout';
+
+import { bool, publicKey, u64 } from '../util/layout';
+
+export interface LiquidityLock {
+  isInitialized: boolean;
+  bumpSeed: number;
+  swapKey: PublicKey;
+  owner: PublicKey;
+  tokenAccount: PublicKey;
+  lockedAmount: bigint;
+  unlockTs: bigint;
+}
+
+/** @internal */
+export const LiquidityLockLayout = struct<LiquidityLock>(
+  [
+    bool('isInitialized'),
+    u8('bumpSeed'),
+    publicKey('swapKey'),
+    publicKey('owner'),
+    publicKey('tokenAccount'),
+    u64('lockedAmount'),
+    u64('unlockTs'),
+  ],
+  'liquidityLock'
+);
+
+export const LIQUIDITY_LOCK_SIZE = LiquidityLockLayout.span;
+
+export const findLiquidityLockAddress = async (
+  tokenSwap: PublicKey,
+  owner: PublicKey,
+  programId: PublicKey
+): Promise<PublicKey> => {
+  const [address] = await PublicKey.findProgramAddress(
+    [Buffer.from('liquidityLock'), tokenSwap.toBuffer(), owner.toBuffer()],
+    programId
+  );
+  return address;
+};
+
+export const findLiquidityLockTokenAddress = async (
+  liquidityLock: PublicKey,
+  programId: PublicKey
+): Promise<PublicKey> => {
+  const [address] = await PublicKey.findProgramAddress(
+    [Buffer.from('liquidityLockToken'), liquidityLock.toBuffer()],
+    programId
+  );
+  return address;
+};
@@ -0,0 +1,38 @@
+import { PublicKey } from '@solana/web3.js';
+import { struct } from 'buffer-layout';
+
+import { bool, publicKey } from '../util/layout';
+
+export interface OracleFeed {
+  isInitialized: boolean;
+  configKey: PublicKey;
+  mint: PublicKey;
+  pythPrice: PublicKey;
+  serumMarket: PublicKey;
+}
+
+/** @internal */
+export const OracleFeedLayout = struct<OracleFeed>(
+  [
+    bool('isInitialized'),
+    publicKey('configKey'),
+    publicKey('mint'),
+    publicKey('pythPrice'),
+    publicKey('serumMarket'),
+  ],
+  'oracleFeed'
+);
+
+export const ORACLE_FEED_SIZE = OracleFeedLayout.span;
+
+export const findOracleFeedAddress = async (
+  config: PublicKey,
+  mint: PublicKey,
+  programId: PublicKey
+): Promise<PublicKey> => {
+  const [address] = await PublicKey.findProgramAddress(
+    [Buffer.from('oracleFeed'), config.toBuffer(), mint.toBuffer()],
+    programId
+  );
+  return address;
+};
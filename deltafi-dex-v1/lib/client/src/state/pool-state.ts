@@ -24,6 +24,15 @@ export interface PoolState {
   multiplier: Multiplier;
   lastPythPrice: BigNumber;
   lastValidPythPriceSlot: BigNumber;
+  baseFeePending: BigNumber;
+  quoteFeePending: BigNumber;
+  baseFeeTotal: BigNumber;
+  quoteFeeTotal: BigNumber;
+  protocolFeeNumerator: BigNumber;
+  protocolFeeDenominator: BigNumber;
+  baseProtocolFee: BigNumber;
+  quoteProtocolFee: BigNumber;
+  maxSharePrice: BigNumber;
 }
 
 /** @internal */
@@ -40,6 +49,15 @@ export const PoolStateLayout = (property: string) =>
       u8('multiplier'),
       decimal('lastPythPrice'),
       u64('lastValidPythPriceSlot'),
+      u64('baseFeePending'),
+      u64('quoteFeePending'),
+      u64('baseFeeTotal'),
+      u64('quoteFeeTotal'),
+      u64('protocolFeeNumerator'),
+      u64('protocolFeeDenominator'),
+      u64('baseProtocolFee'),
+      u64('quoteProtocolFee'),
+      decimal('maxSharePrice'),
     ],
     property
   );
@@ -0,0 +1,40 @@
+import { PublicKey } from '@solana/web3.js';
+import { struct, u8 } from 'buffer-layout';
+
+import { bool, publicKey, u64 } from '../util/layout';
+
+export interface ReferrerStats {
+  isInitialized: boolean;
+  bumpSeed: number;
+  configKey: PublicKey;
+  referrer: PublicKey;
+  referredVolume: bigint;
+  rewardPaid: bigint;
+}
+
+/** @internal */
+export const ReferrerStatsLayout = struct<ReferrerStats>(
+  [
+    bool('isInitialized'),
+    u8('bumpSeed'),
+    publicKey('configKey'),
+    publicKey('referrer'),
+    u64('referredVolume'),
+    u64('rewardPaid'),
+  ],
+  'referrerStats'
+);
+
+export const REFERRER_STATS_SIZE = ReferrerStatsLayout.span;
+
+export const findReferrerStatsAddress = async (
+  config: PublicKey,
+  referrerToken: PublicKey,
+  programId: PublicKey
+): Promise<PublicKey> => {
+  const [address] = await PublicKey.findProgramAddress(
+    [Buffer.from('referrerStats'), config.toBuffer(), referrerToken.toBuffer()],
+    programId
+  );
+  return address;
+};
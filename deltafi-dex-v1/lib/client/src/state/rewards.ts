@@ -3,8 +3,8 @@ import { bool, u64 } from '../util/layout';
 export interface Rewards {
   isInitialized: boolean;
   decimals: number;
+  referralFeeBps: number,
   reserved8: number,
-  reserved16: number,
   reserved32: number,
   tradeRewardNumerator: bigint;
   tradeRewardDenominator: bigint;
@@ -17,8 +17,8 @@ export const RewardsLayout = (property = 'rewards') =>
     [
       bool('isInitialized'),
       u8('decimals'),
+      u16('referralFeeBps'),
       u8('reserved8'),
-      u16('reserved16'),
       u32('reserved32'),
       u64('tradeRewardNumerator'),
       u64('tradeRewardDenominator'),
@@ -1,8 +1,8 @@
 import { AccountInfo, PublicKey, Connection } from '@solana/web3.js';
-import { struct, u8, blob } from 'buffer-layout';
+import { struct, u8, u16, blob } from 'buffer-layout';
 import BigNumber from 'bignumber.js';
 
-import { publicKey, u64, bool, AccountParser, decimal, loadAccount } from '../util';
+import { publicKey, u64, u128, bool, AccountParser, decimal, loadAccount } from '../util';
 import { Fees, FeesLayout } from './fees';
 import { Rewards, RewardsLayout } from './rewards';
 import { PoolState, PoolStateLayout } from './pool-state';
@@ -10,6 +10,39 @@ import { PoolState, PoolStateLayout } from './pool-state';
 export enum SwapType {
   Normal = 0,
   Stable,
+  Pegged,
+}
+
+export enum OraclePriorityFlag {
+  PythOnly = 0,
+  SerumOnly = 1,
+  PythWithSerumFallback = 2,
+  Manual = 4,
+  Chainlink = 8,
+  PythPull = 16,
+}
+
+export enum PauseFlag {
+  Swap = 1,
+  Deposit = 2,
+  Withdraw = 4,
+}
+
+export enum PriceJumpPolicy {
+  Reject = 0,
+  Accept,
+  Clamp,
+}
+
+export enum ExchangeRateAdapterType {
+  None = 0,
+  SplStakePool,
+}
+
+export interface OracleParams {
+  staleSlots: number;
+  maxConfidenceBps: number;
+  minPublishers: number;
 }
 
 export interface SwapInfo {
@@ -35,6 +68,44 @@ export interface SwapInfo {
   swapOutLimitPercentage: number,
   oraclePriorityFlags: number;
   serumCombinedAddress: PublicKey;
+  priceJumpPolicy: PriceJumpPolicy;
+  maxPriceDivergence: number;
+  exchangeRateAdapter: ExchangeRateAdapterType;
+  oracleParams: OracleParams;
+  isLocked: boolean;
+  futurePeggedPrice: BigNumber;
+  peggedPriceApplySlot: bigint;
+  strictDestinationOwner: boolean;
+  pauseSwap: boolean;
+  pauseDeposit: boolean;
+  pauseWithdraw: boolean;
+  quoteIsTokenA: boolean;
+  feeOverride: boolean;
+  rebalanceThresholdBps: number;
+  circuitBreakerBps: number;
+  circuitBreakerWindowSlots: bigint;
+  circuitBreakerPrice: BigNumber;
+  circuitBreakerSlot: bigint;
+  circuitBreakerTrippedSlot: bigint;
+  maxTotalSupply: bigint;
+  whitelistEnabled: boolean;
+  version: number;
+  priceSetter: PublicKey;
+  manualMaxChangeBps: number;
+  manualStaleSlots: bigint;
+  manualPrice: BigNumber;
+  manualPriceSlot: bigint;
+  emaWindowSlots: bigint;
+  emaMarketPrice: BigNumber;
+  emaSlot: bigint;
+  volumeAIn: bigint;
+  volumeBIn: bigint;
+  feesCollectedA: bigint;
+  feesCollectedB: bigint;
+  swapCount: bigint;
+  lastTradePrice: BigNumber;
+  virtualPrice: BigNumber;
+  skimSurplus: boolean;
 }
 
 /** @internal */
@@ -62,7 +133,48 @@ export const SwapInfoLayout = struct<SwapInfo>(
     u8("swapOutLimitPercentage"),
     u8("oraclePriorityFlags"),
     publicKey("serumCombinedAddress"),
-    blob(28, 'reserved'),
+    u8("priceJumpPolicy"),
+    u16("maxPriceDivergence"),
+    u8("exchangeRateAdapter"),
+    struct<OracleParams>(
+      [u16("staleSlots"), u16("maxConfidenceBps"), u8("minPublishers")],
+      "oracleParams"
+    ),
+    bool("isLocked"),
+    decimal("futurePeggedPrice"),
+    u64("peggedPriceApplySlot"),
+    bool('strictDestinationOwner'),
+    bool('pauseSwap'),
+    bool('pauseDeposit'),
+    bool('pauseWithdraw'),
+    bool('quoteIsTokenA'),
+    bool('feeOverride'),
+    u16('rebalanceThresholdBps'),
+    u16('circuitBreakerBps'),
+    u64('circuitBreakerWindowSlots'),
+    decimal('circuitBreakerPrice'),
+    u64('circuitBreakerSlot'),
+    u64('circuitBreakerTrippedSlot'),
+    u64('maxTotalSupply'),
+    bool('whitelistEnabled'),
+    u8('version'),
+    publicKey('priceSetter'),
+    u16('manualMaxChangeBps'),
+    u64('manualStaleSlots'),
+    decimal('manualPrice'),
+    u64('manualPriceSlot'),
+    u64('emaWindowSlots'),
+    decimal('emaMarketPrice'),
+    u64('emaSlot'),
+    u128('volumeAIn'),
+    u128('volumeBIn'),
+    u128('feesCollectedA'),
+    u128('feesCollectedB'),
+    u64('swapCount'),
+    decimal('lastTradePrice'),
+    decimal('virtualPrice'),
+    bool('skimSurplus'),
+    blob(1, 'reserved'),
   ],
   'swapInfo'
 );
@@ -73,6 +185,29 @@ export const isSwapInfo = (info: AccountInfo<Buffer>): boolean => {
   return info.data.length === SWAP_INFO_SIZE;
 };
 
+// Base and quote mints in the conventional order of the pair, token A is the quote when flagged
+export const getBaseQuoteMints = (swapInfo: SwapInfo): { baseMint: PublicKey; quoteMint: PublicKey } => {
+  return swapInfo.quoteIsTokenA
+    ? { baseMint: swapInfo.tokenMintB, quoteMint: swapInfo.tokenMintA }
+    : { baseMint: swapInfo.tokenMintA, quoteMint: swapInfo.tokenMintB };
+};
+
+// Address of the pool of a pair created with InitializeV2, the mints are seeded in byte order
+export const findSwapAddress = async (
+  config: PublicKey,
+  tokenMintA: PublicKey,
+  tokenMintB: PublicKey,
+  swapType: SwapType,
+  programId: PublicKey
+): Promise<PublicKey> => {
+  const mints = [tokenMintA.toBuffer(), tokenMintB.toBuffer()].sort(Buffer.compare);
+  const [address] = await PublicKey.findProgramAddress(
+    [Buffer.from('swap'), config.toBuffer(), ...mints, Buffer.from([swapType])],
+    programId
+  );
+  return address;
+};
+
 export const parseSwapInfo: AccountParser<SwapInfo> = (pubkey: PublicKey, info: AccountInfo<Buffer>) => {
   if (!isSwapInfo(info)) return;
 
@@ -107,6 +242,7 @@ export interface UserReferrerData {
   configKey: PublicKey;
   owner: PublicKey;
   referrer: PublicKey;
+  lastUpdateSlot: bigint;
 }
 
 /** @internal */
@@ -116,6 +252,7 @@ export const UserReferrerDataLayout = struct<UserReferrerData>(
     publicKey('configKey'),
     publicKey('owner'),
     publicKey('referrer'),
+    u64('lastUpdateSlot'),
   ],
   'userReferrerData'
 );
@@ -1,3 +1,4 @@
 export * from './account';
 export * from './layout';
 export * from './send-and-confirm-transaction';
+export * from './transaction-size';
@@ -6,12 +6,16 @@ import {
   TransactionSignature,
 } from '@solana/web3.js';
 
+import { validateTransactionSize } from './transaction-size';
+
 export const sendAndConfirmTransaction = async (
   title: string,
   connection: Connection,
   transaction: Transaction,
   ...signers: Keypair[]
 ): Promise<TransactionSignature> => {
+  validateTransactionSize(transaction.instructions, transaction.feePayer || signers[0].publicKey);
+
   /* tslint:disable:no-console */
   console.info(`Sending ${title} transaction`);
 
@@ -0,0 +1,126 @@
+import { PublicKey, TransactionInstruction } from '@solana/web3.js';
+
+/// max serialized size of a transaction in bytes
+export const PACKET_DATA_SIZE = 1232;
+
+/// max number of accounts a legacy transaction can index
+export const MAX_LEGACY_ACCOUNT_KEYS = 256;
+
+const SIGNATURE_LENGTH = 64;
+const PUBKEY_LENGTH = 32;
+const BLOCKHASH_LENGTH = 32;
+const MESSAGE_HEADER_LENGTH = 3;
+
+export interface TransactionSizeEstimate {
+  size: number;
+  signerCount: number;
+  accountCount: number;
+}
+
+/// Serialized length of a compact-u16 encoded array length
+const compactU16Length = (value: number): number => {
+  if (value < 0x80) {
+    return 1;
+  }
+  if (value < 0x4000) {
+    return 2;
+  }
+  return 3;
+};
+
+/// Estimate the serialized size and signer count of a legacy transaction
+export const estimateTransactionSize = (
+  instructions: TransactionInstruction[],
+  feePayer: PublicKey
+): TransactionSizeEstimate => {
+  const signers = new Set<string>([feePayer.toBase58()]);
+  const accounts = new Set<string>([feePayer.toBase58()]);
+
+  let instructionsSize = compactU16Length(instructions.length);
+  instructions.forEach((instruction) => {
+    accounts.add(instruction.programId.toBase58());
+    instruction.keys.forEach(({ pubkey, isSigner }) => {
+      accounts.add(pubkey.toBase58());
+      if (isSigner) {
+        signers.add(pubkey.toBase58());
+      }
+    });
+    instructionsSize +=
+      1 +
+      compactU16Length(instruction.keys.length) +
+      instruction.keys.length +
+      compactU16Length(instruction.data.length) +
+      instruction.data.length;
+  });
+
+  const size =
+    compactU16Length(signers.size) +
+    signers.size * SIGNATURE_LENGTH +
+    MESSAGE_HEADER_LENGTH +
+    compactU16Length(accounts.size) +
+    accounts.size * PUBKEY_LENGTH +
+    BLOCKHASH_LENGTH +
+    instructionsSize;
+
+  return { size, signerCount: signers.size, accountCount: accounts.size };
+};
+
+export class TransactionSizeError extends Error {
+  constructor(
+    public readonly estimate: TransactionSizeEstimate,
+    public readonly droppableAccounts: PublicKey[],
+    public readonly needsLookupTable: boolean
+  ) {
+    super(
+      `Transaction is ${estimate.size} bytes with ${estimate.signerCount} signers and ` +
+        `${estimate.accountCount} accounts, the limit is ${PACKET_DATA_SIZE} bytes. ` +
+        (needsLookupTable
+          ? 'Dropping the optional accounts is not enough, move accounts into an address lookup table ' +
+            'or split the instructions into several transactions.'
+          : `Drop the optional accounts: ${droppableAccounts.map((key) => key.toBase58()).join(', ')}.`)
+    );
+    this.name = 'TransactionSizeError';
+  }
+}
+
+/// Check the transaction fits in a packet before it is submitted.
+/// `optionalAccounts` are accounts the instructions work without, e.g. the referral accounts,
+/// and are suggested to be dropped in order when the transaction is too large.
+export const validateTransactionSize = (
+  instructions: TransactionInstruction[],
+  feePayer: PublicKey,
+  optionalAccounts: PublicKey[] = []
+): TransactionSizeEstimate => {
+  const estimate = estimateTransactionSize(instructions, feePayer);
+  if (estimate.size <= PACKET_DATA_SIZE && estimate.accountCount <= MAX_LEGACY_ACCOUNT_KEYS) {
+    return estimate;
+  }
+
+  const excess = estimate.size - PACKET_DATA_SIZE;
+  const droppableAccounts: PublicKey[] = [];
+  let saved = 0;
+  for (const account of optionalAccounts) {
+    if (saved >= excess) {
+      break;
+    }
+    let usages = 0;
+    let isSigner = false;
+    instructions.forEach((instruction) =>
+      instruction.keys.forEach(({ pubkey, isSigner: signer }) => {
+        if (pubkey.equals(account)) {
+          usages += 1;
+          isSigner = isSigner || signer;
+        }
+      })
+    );
+    // Signers and accounts the instructions do not reference cannot be dropped
+    if (usages === 0 || isSigner || account.equals(feePayer)) {
+      continue;
+    }
+    droppableAccounts.push(account);
+    saved += PUBKEY_LENGTH + usages;
+  }
+
+  const needsLookupTable = saved < excess || estimate.accountCount > MAX_LEGACY_ACCOUNT_KEYS;
+  throw new TransactionSizeError(estimate, needsLookupTable ? [] : droppableAccounts, needsLookupTable);
+};
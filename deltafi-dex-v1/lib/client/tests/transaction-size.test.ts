@@ -0,0 +1,71 @@
+import { Keypair, PublicKey, Transaction, TransactionInstruction } from '@solana/web3.js';
+
+import {
+  estimateTransactionSize,
+  validateTransactionSize,
+  TransactionSizeError,
+  PACKET_DATA_SIZE,
+  SWAP_PROGRAM_ID,
+} from '../src';
+
+const createInstruction = (accountCount: number, signer?: PublicKey) => {
+  const keys = [...Array(accountCount)].map(() => ({
+    pubkey: Keypair.generate().publicKey,
+    isSigner: false,
+    isWritable: true,
+  }));
+  if (signer) {
+    keys.push({ pubkey: signer, isSigner: true, isWritable: false });
+  }
+  return new TransactionInstruction({ keys, data: Buffer.alloc(17), programId: SWAP_PROGRAM_ID });
+};
+
+describe('transaction size validation', () => {
+  const payer = Keypair.generate().publicKey;
+  const userTransferAuthority = Keypair.generate().publicKey;
+
+  it('estimates the serialized size', () => {
+    const instructions = [createInstruction(10, userTransferAuthority), createInstruction(5)];
+    const estimate = estimateTransactionSize(instructions, payer);
+
+    const transaction = new Transaction({ feePayer: payer, recentBlockhash: Keypair.generate().publicKey.toBase58() });
+    instructions.forEach((instruction) => transaction.add(instruction));
+    const messageSize = transaction.serializeMessage().length;
+
+    expect(estimate.signerCount).toEqual(2);
+    expect(estimate.accountCount).toEqual(18);
+    expect(estimate.size).toEqual(1 + 2 * 64 + messageSize);
+  });
+
+  it('accepts a transaction in the limit', () => {
+    const estimate = validateTransactionSize([createInstruction(20, userTransferAuthority)], payer);
+    expect(estimate.size).toBeLessThanOrEqual(PACKET_DATA_SIZE);
+  });
+
+  it('suggests the optional accounts to drop', () => {
+    const instruction = createInstruction(30, userTransferAuthority);
+    const optionalAccounts = instruction.keys.slice(28, 30).map(({ pubkey }) => pubkey);
+
+    try {
+      validateTransactionSize([instruction], payer, optionalAccounts);
+      fail('expected TransactionSizeError');
+    } catch (e) {
+      expect(e).toBeInstanceOf(TransactionSizeError);
+      expect(e.needsLookupTable).toBe(false);
+      expect(e.droppableAccounts.length).toBeGreaterThan(0);
+    }
+  });
+
+  it('requires a lookup table when dropping optional accounts is not enough', () => {
+    const instruction = createInstruction(40, userTransferAuthority);
+
+    try {
+      validateTransactionSize([instruction], payer, [instruction.keys[0].pubkey]);
+      fail('expected TransactionSizeError');
+    } catch (e) {
+      expect(e).toBeInstanceOf(TransactionSizeError);
+      expect(e.needsLookupTable).toBe(true);
+      expect(e.droppableAccounts).toEqual([]);
+    }
+  });
+});
@@ -0,0 +1,349 @@
+//! Exchange rate adapters to price stable pools of liquid staking tokens.
+
+use std::{convert::TryFrom, str::FromStr};
+
+use arrayref::{array_ref, array_refs};
+use solana_program::{
+    account_info::AccountInfo, clock::Clock, entrypoint::ProgramResult, msg,
+    program_error::ProgramError, pubkey::Pubkey,
+};
+
+use crate::{
+    error::SwapError,
+    math::{Decimal, TryDiv, TryMul},
+};
+
+#[cfg(feature = "serde")]
+use serde::{Deserialize, Serialize};
+
+/// SPL stake pool mainnet program id
+pub const SPL_STAKE_POOL_PROGRAM_ID: &str = "SPoo1Ku8WFXoNDMHPsrGSTSG1Y47rzgn41SLUNakuHy";
+
+/// Type of the exchange rate adapter used by a stable pool
+#[repr(u8)]
+#[derive(Clone, Copy, Debug, PartialEq)]
+#[cfg_attr(feature = "serde", derive(Serialize, Deserialize))]
+pub enum ExchangeRateAdapterType {
+    /// no adapter, the pool trades at price 1
+    None,
+    /// base token is the pool token of an SPL stake pool
+    SplStakePool,
+}
+
+impl Default for ExchangeRateAdapterType {
+    fn default() -> Self {
+        Self::None
+    }
+}
+
+impl TryFrom<u8> for ExchangeRateAdapterType {
+    type Error = ProgramError;
+
+    fn try_from(adapter_type: u8) -> Result<Self, Self::Error> {
+        match adapter_type {
+            0 => Ok(ExchangeRateAdapterType::None),
+            1 => Ok(ExchangeRateAdapterType::SplStakePool),
+            _ => Err(ProgramError::InvalidAccountData),
+        }
+    }
+}
+
+impl ExchangeRateAdapterType {
+    /// Get the adapter reading the exchange rate account, None if the pool has no adapter
+    pub fn adapter(&self) -> Option<&'static dyn ExchangeRateAdapter> {
+        match self {
+            Self::None => None,
+            Self::SplStakePool => Some(&SplStakePoolAdapter),
+        }
+    }
+}
+
+/// Reads the accrued exchange rate of a base token from its rate account
+pub trait ExchangeRateAdapter {
+    /// Check the rate account is owned by the expected program and quotes the base token mint
+    fn validate_rate_account(
+        &self,
+        rate_account_info: &AccountInfo,
+        base_mint: &Pubkey,
+    ) -> ProgramResult;
+
+    /// Amount of quote token units for one base token unit
+    fn exchange_rate(
+        &self,
+        rate_account_info: &AccountInfo,
+        clock: &Clock,
+    ) -> Result<Decimal, ProgramError>;
+}
+
+/// Adapter for the pool tokens of SPL stake pools, e.g. stake pool SOL / SOL
+pub struct SplStakePoolAdapter;
+
+/// SPL stake pool account type tag
+const SPL_STAKE_POOL_ACCOUNT_TYPE: u8 = 1;
+/// Bytes of the SPL stake pool account needed by the adapter
+const SPL_STAKE_POOL_USED_BYTES: usize = 282;
+
+impl SplStakePoolAdapter {
+    /// Unpack (pool_mint, total_lamports, pool_token_supply, last_update_epoch)
+    fn unpack_stake_pool(
+        rate_account_info: &AccountInfo,
+    ) -> Result<(Pubkey, u64, u64, u64), ProgramError> {
+        if *rate_account_info.owner != Pubkey::from_str(SPL_STAKE_POOL_PROGRAM_ID).unwrap() {
+            return Err(SwapError::InvalidExchangeRateAccount.into());
+        }
+
+        let data = rate_account_info.try_borrow_data()?;
+        if data.len() < SPL_STAKE_POOL_USED_BYTES {
+            return Err(SwapError::InvalidExchangeRateAccount.into());
+        }
+        let input = array_ref![data, 0, SPL_STAKE_POOL_USED_BYTES];
+        #[allow(clippy::ptr_offset_with_cast)]
+        let (account_type, _, pool_mint, _, total_lamports, pool_token_supply, last_update_epoch) =
+            array_refs![input, 1, 161, 32, 64, 8, 8, 8];
+        if account_type[0] != SPL_STAKE_POOL_ACCOUNT_TYPE {
+            return Err(SwapError::InvalidExchangeRateAccount.into());
+        }
+
+        Ok((
+            Pubkey::new_from_array(*pool_mint),
+            u64::from_le_bytes(*total_lamports),
+            u64::from_le_bytes(*pool_token_supply),
+            u64::from_le_bytes(*last_update_epoch),
+        ))
+    }
+}
+
+impl ExchangeRateAdapter for SplStakePoolAdapter {
+    fn validate_rate_account(
+        &self,
+        rate_account_info: &AccountInfo,
+        base_mint: &Pubkey,
+    ) -> ProgramResult {
+        let (pool_mint, _, _, _) = Self::unpack_stake_pool(rate_account_info)?;
+        if pool_mint != *base_mint {
+            return Err(SwapError::InvalidExchangeRateAccount.into());
+        }
+        Ok(())
+    }
+
+    fn exchange_rate(
+        &self,
+        rate_account_info: &AccountInfo,
+        clock: &Clock,
+    ) -> Result<Decimal, ProgramError> {
+        let (_, total_lamports, pool_token_supply, last_update_epoch) =
+            Self::unpack_stake_pool(rate_account_info)?;
+
+        // The stake pool has to be updated in the current epoch to account the latest rewards
+        if last_update_epoch < clock.epoch {
+            msg!("Stake pool is not updated in the current epoch");
+            return Err(SwapError::StaleExchangeRate.into());
+        }
+        if pool_token_supply == 0 {
+            return Err(SwapError::InvalidExchangeRateAccount.into());
+        }
+
+        Decimal::from(total_lamports).try_div(pool_token_supply)
+    }
+}
+
+/// Get the market price of a stable pool from its exchange rate account
+pub fn get_exchange_rate_price(
+    adapter: &dyn ExchangeRateAdapter,
+    rate_account_info: &AccountInfo,
+    clock: &Clock,
+    base_decimals: u8,
+    quote_decimals: u8,
+) -> Result<Decimal, ProgramError> {
+    let exchange_rate = adapter.exchange_rate(rate_account_info, clock)?;
+
+    // Convert the rate between token units to the price between whole tokens
+    let base_multiplier = 10u64
+        .checked_pow(base_decimals as u32)
+        .ok_or(SwapError::CalculationFailure)?;
+    let quote_multiplier = 10u64
+        .checked_pow(quote_decimals as u32)
+        .ok_or(SwapError::CalculationFailure)?;
+    exchange_rate
+        .try_mul(base_multiplier)?
+        .try_div(quote_multiplier)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn stake_pool_data(
+        account_type: u8,
+        pool_mint: &Pubkey,
+        total_lamports: u64,
+        pool_token_supply: u64,
+        last_update_epoch: u64,
+    ) -> Vec<u8> {
+        let mut data = vec![account_type];
+        data.extend_from_slice(&[0u8; 161]);
+        data.extend_from_slice(pool_mint.as_ref());
+        data.extend_from_slice(&[0u8; 64]);
+        data.extend_from_slice(&total_lamports.to_le_bytes());
+        data.extend_from_slice(&pool_token_supply.to_le_bytes());
+        data.extend_from_slice(&last_update_epoch.to_le_bytes());
+        // fee fields and the rest of the stake pool account
+        data.extend_from_slice(&[0u8; 100]);
+        data
+    }
+
+    #[test]
+    fn test_exchange_rate_adapter_type_try_from() {
+        assert_eq!(
+            ExchangeRateAdapterType::try_from(0u8),
+            Ok(ExchangeRateAdapterType::None)
+        );
+        assert_eq!(
+            ExchangeRateAdapterType::try_from(1u8),
+            Ok(ExchangeRateAdapterType::SplStakePool)
+        );
+        assert_eq!(
+            ExchangeRateAdapterType::try_from(2u8),
+            Err(ProgramError::InvalidAccountData)
+        );
+        assert!(ExchangeRateAdapterType::None.adapter().is_none());
+        assert!(ExchangeRateAdapterType::SplStakePool.adapter().is_some());
+    }
+
+    #[test]
+    fn test_spl_stake_pool_adapter() {
+        let stake_pool_key = Pubkey::new_unique();
+        let stake_pool_program_id = Pubkey::from_str(SPL_STAKE_POOL_PROGRAM_ID).unwrap();
+        let pool_mint = Pubkey::new_unique();
+        let clock = Clock {
+            epoch: 300,
+            ..Clock::default()
+        };
+
+        let mut lamports = 0u64;
+        let mut data = stake_pool_data(1, &pool_mint, 1_050_000_000, 1_000_000_000, 300);
+        let rate_account_info = AccountInfo::new(
+            &stake_pool_key,
+            false,
+            false,
+            &mut lamports,
+            &mut data,
+            &stake_pool_program_id,
+            false,
+            0u64,
+        );
+
+        let adapter = SplStakePoolAdapter;
+        assert_eq!(
+            adapter.validate_rate_account(&rate_account_info, &pool_mint),
+            Ok(())
+        );
+        assert_eq!(
+            adapter.validate_rate_account(&rate_account_info, &Pubkey::new_unique()),
+            Err(SwapError::InvalidExchangeRateAccount.into())
+        );
+        assert_eq!(
+            adapter.exchange_rate(&rate_account_info, &clock),
+            Decimal::from(105u64).try_div(100u64)
+        );
+        assert_eq!(
+            get_exchange_rate_price(&adapter, &rate_account_info, &clock, 9, 9),
+            Decimal::from(105u64).try_div(100u64)
+        );
+        assert_eq!(
+            get_exchange_rate_price(&adapter, &rate_account_info, &clock, 9, 6),
+            Ok(Decimal::from(1050u64))
+        );
+
+        let stale_clock = Clock {
+            epoch: 301,
+            ..Clock::default()
+        };
+        assert_eq!(
+            adapter.exchange_rate(&rate_account_info, &stale_clock),
+            Err(SwapError::StaleExchangeRate.into())
+        );
+    }
+
+    #[test]
+    fn test_spl_stake_pool_adapter_invalid_account() {
+        let stake_pool_key = Pubkey::new_unique();
+        let stake_pool_program_id = Pubkey::from_str(SPL_STAKE_POOL_PROGRAM_ID).unwrap();
+        let pool_mint = Pubkey::new_unique();
+        let clock = Clock::default();
+        let adapter = SplStakePoolAdapter;
+
+        // wrong owner
+        let mut lamports = 0u64;
+        let mut data = stake_pool_data(1, &pool_mint, 1, 1, 0);
+        let wrong_owner = Pubkey::new_unique();
+        let rate_account_info = AccountInfo::new(
+            &stake_pool_key,
+            false,
+            false,
+            &mut lamports,
+            &mut data,
+            &wrong_owner,
+            false,
+            0u64,
+        );
+        assert_eq!(
+            adapter.validate_rate_account(&rate_account_info, &pool_mint),
+            Err(SwapError::InvalidExchangeRateAccount.into())
+        );
+
+        // wrong account type
+        let mut lamports = 0u64;
+        let mut data = stake_pool_data(2, &pool_mint, 1, 1, 0);
+        let rate_account_info = AccountInfo::new(
+            &stake_pool_key,
+            false,
+            false,
+            &mut lamports,
+            &mut data,
+            &stake_pool_program_id,
+            false,
+            0u64,
+        );
+        assert_eq!(
+            adapter.validate_rate_account(&rate_account_info, &pool_mint),
+            Err(SwapError::InvalidExchangeRateAccount.into())
+        );
+
+        // data too short
+        let mut lamports = 0u64;
+        let mut data = vec![1u8; 100];
+        let rate_account_info = AccountInfo::new(
+            &stake_pool_key,
+            false,
+            false,
+            &mut lamports,
+            &mut data,
+            &stake_pool_program_id,
+            false,
+            0u64,
+        );
+        assert_eq!(
+            adapter.validate_rate_account(&rate_account_info, &pool_mint),
+            Err(SwapError::InvalidExchangeRateAccount.into())
+        );
+
+        // empty stake pool
+        let mut lamports = 0u64;
+        let mut data = stake_pool_data(1, &pool_mint, 0, 0, 0);
+        let rate_account_info = AccountInfo::new(
+            &stake_pool_key,
+            false,
+            false,
+            &mut lamports,
+            &mut data,
+            &stake_pool_program_id,
+            false,
+            0u64,
+        );
+        assert_eq!(
+            adapter.exchange_rate(&rate_account_info, &clock),
+            Err(SwapError::InvalidExchangeRateAccount.into())
+        );
+    }
+}
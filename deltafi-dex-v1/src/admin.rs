@@ -1,23 +1,48 @@
 //! Module for processing admin-only instructions.
 
+use std::convert::TryFrom;
+
 use solana_program::{
     account_info::{next_account_info, AccountInfo},
     entrypoint::ProgramResult,
     msg,
+    program::invoke,
     program_error::ProgramError,
+    program_option::COption,
     program_pack::Pack,
     pubkey::Pubkey,
-    sysvar::{rent::Rent, Sysvar},
+    system_instruction, system_program,
+    sysvar::{clock::Clock, rent::Rent, Sysvar},
 };
 
 use spl_token::instruction::AuthorityType;
 
 use crate::{
+    adapter::ExchangeRateAdapterType,
+    curve::PriceJumpPolicy,
     error::SwapError,
     instruction::{AdminInitializeData, AdminInstruction, CommitNewAdmin, FarmRewards},
-    processor::{assert_rent_exempt, assert_uninitialized, set_authority, unpack_token_account},
-    state::{ConfigInfo, SwapInfo, PROGRAM_VERSION},
-    state::{Decimal, FarmInfo, Fees, Rewards},
+    processor::{
+        assert_rent_exempt, assert_uninitialized, check_initial_oracle_feeds,
+        check_initial_oracles, create_farm_reward_tokens_account,
+        create_fee_discount_tiers_account, create_oracle_feed_account, create_price_cache_account,
+        create_scratch_accounts, create_whitelist_entry_account, find_farm_reward_tokens_address,
+        find_fee_discount_tiers_address, find_oracle_feed_address, find_price_cache_address,
+        find_whitelist_entry_address, get_price_commitment_pubkey, set_authority,
+        set_authority_signed, token_close_account, token_transfer, unpack_farm_reward_tokens,
+        unpack_mint, unpack_token_account,
+    },
+    state::{
+        AdminRole, ConfigInfo, OracleFeed, OracleParams, OraclePriorityFlag, PauseFlag,
+        PriceCommitment,
+    },
+    state::{
+        Decimal, FarmInfo, FarmRewardTokens, FeeDiscountTier, FeeDiscountTiers, Fees, PriceCache,
+        Rewards,
+    },
+    state::{ScratchAccount, MAX_FEE_DISCOUNT_TIERS},
+    state::{SwapInfo, SwapType, WhitelistEntry, SWAP_INFO_VERSION},
+    state::{MAX_MARKET_MAKERS, PEGGED_PRICE_TIMELOCK_SLOTS, PROGRAM_VERSION},
     utils,
 };
 
@@ -28,6 +53,18 @@ pub fn process_admin_instruction(
     input: &[u8],
 ) -> ProgramResult {
     let instruction = AdminInstruction::unpack(input)?;
+    // Incident response and the guardian instructions are not limited, nor the migration of
+    // the legacy config which cannot record the instruction before its growth
+    if !matches!(
+        instruction,
+        AdminInstruction::Initialize(_)
+            | AdminInstruction::PauseAll
+            | AdminInstruction::SetAdminOpsLimit(_)
+            | AdminInstruction::MigrateConfigInfo
+    ) && !record_admin_op(program_id, accounts)?
+    {
+        return Ok(());
+    }
     match instruction {
         AdminInstruction::Initialize(AdminInitializeData { fees, rewards }) => {
             msg!("AdminInstruction : Initialization");
@@ -73,7 +110,258 @@ pub fn process_admin_instruction(
             msg!("Instruction: SetSwapLimit");
             set_swap_limit(program_id, swap_out_limit_percentage, accounts)
         }
+        AdminInstruction::SetPriceJumpPolicy(price_jump_policy) => {
+            msg!("Instruction: SetPriceJumpPolicy");
+            set_price_jump_policy(program_id, price_jump_policy, accounts)
+        }
+        AdminInstruction::SetMaxPriceDivergence(max_price_divergence) => {
+            msg!("Instruction: SetMaxPriceDivergence");
+            set_max_price_divergence(program_id, max_price_divergence, accounts)
+        }
+        AdminInstruction::SetOrderbookProgram => {
+            msg!("Instruction: SetOrderbookProgram");
+            set_orderbook_program(program_id, accounts)
+        }
+        AdminInstruction::SetExchangeRateAdapter(adapter_type) => {
+            msg!("Instruction: SetExchangeRateAdapter");
+            set_exchange_rate_adapter(program_id, adapter_type, accounts)
+        }
+        AdminInstruction::SetOracleParams(oracle_params) => {
+            msg!("Instruction: SetOracleParams");
+            set_oracle_params(program_id, &oracle_params, accounts)
+        }
+        AdminInstruction::SetMarketMaker(index) => {
+            msg!("Instruction: SetMarketMaker");
+            set_market_maker(program_id, index, accounts)
+        }
+        AdminInstruction::RotateFeeAccount => {
+            msg!("Instruction: RotateFeeAccount");
+            rotate_fee_account(program_id, accounts)
+        }
+        AdminInstruction::CommitInitialPrice(price_hash) => {
+            msg!("Instruction: CommitInitialPrice");
+            commit_initial_price(program_id, price_hash, accounts)
+        }
+        AdminInstruction::CommitPeggedPrice(pegged_price) => {
+            msg!("Instruction: CommitPeggedPrice");
+            commit_pegged_price(program_id, pegged_price, accounts)
+        }
+        AdminInstruction::ApplyPeggedPrice => {
+            msg!("Instruction: ApplyPeggedPrice");
+            apply_pegged_price(program_id, accounts)
+        }
+        AdminInstruction::SetAdminRole(role) => {
+            msg!("Instruction: SetAdminRole");
+            set_admin_role(program_id, role, accounts)
+        }
+        AdminInstruction::PauseAll => {
+            msg!("Instruction: PauseAll");
+            pause_all(program_id, accounts)
+        }
+        AdminInstruction::SetStrictDestinationOwner(strict_destination_owner) => {
+            msg!("Instruction: SetStrictDestinationOwner");
+            set_strict_destination_owner(program_id, strict_destination_owner, accounts)
+        }
+        AdminInstruction::EnableFarmUserRegistry => {
+            msg!("Instruction: EnableFarmUserRegistry");
+            enable_farm_user_registry(program_id, accounts)
+        }
+        AdminInstruction::SetPauseFlags(pause_flags) => {
+            msg!("Instruction: SetPauseFlags");
+            set_pause_flags(program_id, pause_flags, accounts)
+        }
+        AdminInstruction::ClaimProtocolFees => {
+            msg!("Instruction: ClaimProtocolFees");
+            claim_protocol_fees(program_id, accounts)
+        }
+        AdminInstruction::SetProtocolFee(numerator, denominator) => {
+            msg!("Instruction: SetProtocolFee");
+            set_protocol_fee(program_id, numerator, denominator, accounts)
+        }
+        AdminInstruction::SetPoolAuthority(authority_type) => {
+            msg!("Instruction: SetPoolAuthority");
+            set_pool_authority(program_id, authority_type, accounts)
+        }
+        AdminInstruction::SetMarketAuthority(authority_type) => {
+            msg!("Instruction: SetMarketAuthority");
+            set_market_authority(program_id, authority_type, accounts)
+        }
+        AdminInstruction::ClosePool => {
+            msg!("Instruction: ClosePool");
+            close_pool(program_id, accounts)
+        }
+        AdminInstruction::SetOracleFeed => {
+            msg!("Instruction: SetOracleFeed");
+            set_oracle_feed(program_id, accounts)
+        }
+        AdminInstruction::EnableOracleFeedRegistry => {
+            msg!("Instruction: EnableOracleFeedRegistry");
+            enable_oracle_feed_registry(program_id, accounts)
+        }
+        AdminInstruction::SetPriceCache => {
+            msg!("Instruction: SetPriceCache");
+            set_price_cache(program_id, accounts)
+        }
+        AdminInstruction::CreateScratchAccount(index) => {
+            msg!("Instruction: CreateScratchAccount");
+            create_scratch_account(program_id, index, accounts)
+        }
+        AdminInstruction::SetAdminOpsLimit(max_admin_ops_per_epoch) => {
+            msg!("Instruction: SetAdminOpsLimit");
+            set_admin_ops_limit(program_id, max_admin_ops_per_epoch, accounts)
+        }
+        AdminInstruction::SetFeeDiscountTiers(tiers) => {
+            msg!("Instruction: SetFeeDiscountTiers");
+            set_fee_discount_tiers(program_id, tiers, accounts)
+        }
+        AdminInstruction::SetProtocolFeeSwitch(is_protocol_fee_enabled) => {
+            msg!("Instruction: SetProtocolFeeSwitch");
+            set_protocol_fee_switch(program_id, is_protocol_fee_enabled, accounts)
+        }
+        AdminInstruction::SyncPoolFromConfig(include_pinned) => {
+            msg!("Instruction: SyncPoolFromConfig");
+            sync_pool_from_config(program_id, include_pinned, accounts)
+        }
+        AdminInstruction::SetConfigFees(AdminInitializeData { fees, rewards }) => {
+            msg!("Instruction: SetConfigFees");
+            set_config_fees(program_id, &fees, &rewards, accounts)
+        }
+        AdminInstruction::BatchSetFees(new_fees) => {
+            msg!("Instruction: BatchSetFees");
+            batch_set_fees(program_id, &new_fees, accounts)
+        }
+        AdminInstruction::BatchPause => {
+            msg!("Instruction: BatchPause");
+            batch_pause(program_id, accounts)
+        }
+        AdminInstruction::AddFarmRewardToken(reward_per_slot) => {
+            msg!("Instruction: AddFarmRewardToken");
+            add_farm_reward_token(program_id, reward_per_slot, accounts)
+        }
+        AdminInstruction::SetFarmRewardRate(index, reward_per_slot) => {
+            msg!("Instruction: SetFarmRewardRate");
+            set_farm_reward_rate(program_id, index, reward_per_slot, accounts)
+        }
+        AdminInstruction::SetFarmEmissionRate(reward_per_slot) => {
+            msg!("Instruction: SetFarmEmissionRate");
+            set_farm_emission_rate(program_id, reward_per_slot, accounts)
+        }
+        AdminInstruction::FundStakingRewards(amount) => {
+            msg!("Instruction: FundStakingRewards");
+            fund_staking_rewards(program_id, amount, accounts)
+        }
+        AdminInstruction::SetRebalanceThreshold(rebalance_threshold_bps) => {
+            msg!("Instruction: SetRebalanceThreshold");
+            set_rebalance_threshold(program_id, rebalance_threshold_bps, accounts)
+        }
+        AdminInstruction::SetCircuitBreaker(max_deviation_bps, window_slots) => {
+            msg!("Instruction: SetCircuitBreaker");
+            set_circuit_breaker(program_id, max_deviation_bps, window_slots, accounts)
+        }
+        AdminInstruction::SetDepositCap(max_total_supply) => {
+            msg!("Instruction: SetDepositCap");
+            set_deposit_cap(program_id, max_total_supply, accounts)
+        }
+        AdminInstruction::SetWhitelistEnabled(whitelist_enabled) => {
+            msg!("Instruction: SetWhitelistEnabled");
+            set_whitelist_enabled(program_id, whitelist_enabled, accounts)
+        }
+        AdminInstruction::AddWhitelistEntry => {
+            msg!("Instruction: AddWhitelistEntry");
+            add_whitelist_entry(program_id, accounts)
+        }
+        AdminInstruction::RemoveWhitelistEntry => {
+            msg!("Instruction: RemoveWhitelistEntry");
+            remove_whitelist_entry(program_id, accounts)
+        }
+        AdminInstruction::MigrateSwapInfo => {
+            msg!("Instruction: MigrateSwapInfo");
+            migrate_swap_info(program_id, accounts)
+        }
+        AdminInstruction::MigrateConfigInfo => {
+            msg!("Instruction: MigrateConfigInfo");
+            migrate_config_info(program_id, accounts)
+        }
+        AdminInstruction::MigrateFarmInfo => {
+            msg!("Instruction: MigrateFarmInfo");
+            migrate_farm_info(program_id, accounts)
+        }
+        AdminInstruction::SetOracleAccounts => {
+            msg!("Instruction: SetOracleAccounts");
+            set_oracle_accounts(program_id, None, accounts)
+        }
+        AdminInstruction::SetOraclePriority(oracle_priority_flags) => {
+            msg!("Instruction: SetOraclePriority");
+            set_oracle_accounts(program_id, Some(oracle_priority_flags), accounts)
+        }
+        AdminInstruction::SetPriceSetter(max_change_bps, stale_slots) => {
+            msg!("Instruction: SetPriceSetter");
+            set_price_setter(program_id, max_change_bps, stale_slots, accounts)
+        }
+        AdminInstruction::SetPriceEmaWindow(window_slots) => {
+            msg!("Instruction: SetPriceEmaWindow");
+            set_price_ema_window(program_id, window_slots, accounts)
+        }
+        AdminInstruction::SetSkimSurplus(skim_surplus) => {
+            msg!("Instruction: SetSkimSurplus");
+            set_skim_surplus(program_id, skim_surplus, accounts)
+        }
+    }
+}
+
+/// Count the admin instruction in the market config when the guardian set a max per epoch.
+/// Only the instructions signed by the admin or a role are counted, the others are left to
+/// the instruction to reject. The instruction exceeding the max pauses the admin instructions
+/// and is skipped, so the pause is kept, the instructions after it fail until co-signed by
+/// the guardian. Returns if the instruction is executed.
+#[inline(never)]
+fn record_admin_op(program_id: &Pubkey, accounts: &[AccountInfo]) -> Result<bool, ProgramError> {
+    let config_info = match accounts.first() {
+        Some(config_info) if config_info.owner == program_id => config_info,
+        // Left to the instruction to reject
+        _ => return Ok(true),
+    };
+    let mut config = ConfigInfo::unpack(&config_info.data.borrow())?;
+    if config.max_admin_ops_per_epoch == 0 {
+        return Ok(true);
+    }
+    let role_signed = accounts.iter().any(|account| {
+        account.is_signer
+            && [
+                AdminRole::SuperAdmin,
+                AdminRole::FeeManager,
+                AdminRole::Pauser,
+                AdminRole::OracleManager,
+                AdminRole::Guardian,
+            ]
+            .iter()
+            .any(|role| config.has_role(*role, account.key))
+    });
+    if !role_signed {
+        return Ok(true);
+    }
+
+    let guardian_cosigned = config.guardian_key != Pubkey::default()
+        && accounts
+            .iter()
+            .any(|account| account.is_signer && *account.key == config.guardian_key);
+    if config.is_admin_ops_paused {
+        return if guardian_cosigned {
+            Ok(true)
+        } else {
+            Err(SwapError::AdminOpsPaused.into())
+        };
+    }
+
+    let clock = Clock::get()?;
+    let within_max = config.record_admin_op(clock.epoch);
+    ConfigInfo::pack(config, &mut config_info.data.borrow_mut())?;
+    if !within_max {
+        msg!(
+            "Admin instructions exceeded the max of the epoch, paused until the guardian co-signs"
+        );
     }
+    Ok(within_max)
 }
 
 /// Access control for admin only instructions
@@ -89,6 +377,23 @@ pub fn is_admin(expected_admin_key: &Pubkey, admin_account_info: &AccountInfo) -
     Ok(())
 }
 
+/// Access control for instructions delegated to an admin role, the super-admin holds every role
+#[inline(never)]
+pub fn has_role(
+    config: &ConfigInfo,
+    role: AdminRole,
+    admin_account_info: &AccountInfo,
+) -> ProgramResult {
+    if !config.has_role(role, admin_account_info.key) {
+        return Err(SwapError::Unauthorized.into());
+    }
+
+    if !admin_account_info.is_signer {
+        return Err(ProgramError::MissingRequiredSignature);
+    }
+    Ok(())
+}
+
 /// Initialize configuration
 #[inline(never)]
 fn initialize(
@@ -142,6 +447,7 @@ fn initialize(
     )?;
 
     utils::validate(rewards.decimals <= 10, SwapError::InvalidTokenDecimals)?;
+    utils::validate(rewards.referral_fee_bps <= 10_000, SwapError::InvalidInput)?;
 
     config.version = PROGRAM_VERSION;
     config.bump_seed = bump_seed;
@@ -169,7 +475,7 @@ fn pause(program_id: &Pubkey, accounts: &[AccountInfo]) -> ProgramResult {
 
     let config = ConfigInfo::unpack(&config_info.data.borrow())?;
 
-    is_admin(&config.admin_key, admin_info)?;
+    has_role(&config, AdminRole::Pauser, admin_info)?;
 
     let mut token_swap = SwapInfo::unpack(&swap_info.data.borrow())?;
     utils::validate_swap_config_key(&token_swap, config_info.key)?;
@@ -192,7 +498,7 @@ fn unpause(program_id: &Pubkey, accounts: &[AccountInfo]) -> ProgramResult {
     }
 
     let config = ConfigInfo::unpack(&config_info.data.borrow())?;
-    is_admin(&config.admin_key, admin_info)?;
+    has_role(&config, AdminRole::Pauser, admin_info)?;
 
     let mut token_swap = SwapInfo::unpack(&swap_info.data.borrow())?;
     utils::validate_swap_config_key(&token_swap, config_info.key)?;
@@ -219,7 +525,7 @@ fn set_fee_account(program_id: &Pubkey, accounts: &[AccountInfo]) -> ProgramResu
 
     let config = ConfigInfo::unpack(&config_info.data.borrow())?;
 
-    is_admin(&config.admin_key, admin_info)?;
+    has_role(&config, AdminRole::FeeManager, admin_info)?;
 
     let mut token_swap = SwapInfo::unpack(&swap_info.data.borrow())?;
     utils::validate_swap_config_key(&token_swap, config_info.key)?;
@@ -300,12 +606,18 @@ fn set_new_fees(program_id: &Pubkey, new_fees: &Fees, accounts: &[AccountInfo])
     }
 
     let config = ConfigInfo::unpack(&config_info.data.borrow())?;
-    is_admin(&config.admin_key, admin_info)?;
+    has_role(&config, AdminRole::FeeManager, admin_info)?;
+
+    // The protocol share is taken from the trade fee, at most all of it
+    if new_fees.treasury_fee_numerator > new_fees.treasury_fee_denominator {
+        return Err(SwapError::InvalidInput.into());
+    }
 
     let mut token_swap = SwapInfo::unpack(&swap_info.data.borrow())?;
     utils::validate_swap_config_key(&token_swap, config_info.key)?;
 
     token_swap.fees = Fees::new(new_fees);
+    token_swap.fee_override = true;
     SwapInfo::pack(token_swap, &mut swap_info.data.borrow_mut())?;
     Ok(())
 }
@@ -327,14 +639,19 @@ fn set_new_rewards(
     }
 
     let config = ConfigInfo::unpack(&config_info.data.borrow())?;
-    is_admin(&config.admin_key, admin_info)?;
+    has_role(&config, AdminRole::FeeManager, admin_info)?;
 
     let mut token_swap = SwapInfo::unpack(&swap_info.data.borrow())?;
     utils::validate_swap_config_key(&token_swap, config_info.key)?;
 
     utils::validate(new_rewards.decimals <= 10, SwapError::InvalidTokenDecimals)?;
+    utils::validate(
+        new_rewards.referral_fee_bps <= 10_000,
+        SwapError::InvalidInput,
+    )?;
 
     token_swap.rewards = Rewards::new(new_rewards);
+    token_swap.fee_override = true;
     SwapInfo::pack(token_swap, &mut swap_info.data.borrow_mut())?;
     Ok(())
 }
@@ -356,18 +673,223 @@ fn set_farm_rewards(
     }
 
     let config = ConfigInfo::unpack(&config_info.data.borrow())?;
-    is_admin(&config.admin_key, admin_info)?;
+    has_role(&config, AdminRole::FeeManager, admin_info)?;
 
     let mut farm_pool = FarmInfo::unpack(&farm_pool_info.data.borrow())?;
     utils::validate_farm_config_key(&farm_pool, config_info.key)?;
 
     farm_pool.apr_numerator = farm_rewards.apr_numerator;
     farm_pool.apr_denominator = farm_rewards.apr_denominator;
+    // Positions keep accruing at their recorded apr until their next checkpoint
+    farm_pool.apr_epoch = farm_pool
+        .apr_epoch
+        .checked_add(1)
+        .ok_or(SwapError::CalculationFailure)?;
     FarmInfo::pack(farm_pool, &mut farm_pool_info.data.borrow_mut())?;
 
     Ok(())
 }
 
+/// Add a partner reward token to a farm, creating the farm reward tokens on the first one
+#[inline(never)]
+fn add_farm_reward_token(
+    program_id: &Pubkey,
+    reward_per_slot: u64,
+    accounts: &[AccountInfo],
+) -> ProgramResult {
+    let account_info_iter = &mut accounts.iter();
+    let config_info = next_account_info(account_info_iter)?;
+    let farm_pool_info = next_account_info(account_info_iter)?;
+    let farm_reward_tokens_info = next_account_info(account_info_iter)?;
+    let vault_info = next_account_info(account_info_iter)?;
+    let admin_info = next_account_info(account_info_iter)?;
+    let rent = &Rent::from_account_info(next_account_info(account_info_iter)?)?;
+    let system_program_info = next_account_info(account_info_iter)?;
+    let token_program_info = next_account_info(account_info_iter)?;
+
+    if config_info.owner != program_id || farm_pool_info.owner != program_id {
+        return Err(SwapError::InvalidAccountOwner.into());
+    }
+
+    let config = ConfigInfo::unpack(&config_info.data.borrow())?;
+    has_role(&config, AdminRole::FeeManager, admin_info)?;
+
+    let mut farm_pool = FarmInfo::unpack(&farm_pool_info.data.borrow())?;
+    utils::validate_farm_config_key(&farm_pool, config_info.key)?;
+
+    let farm_authority = Pubkey::create_program_address(
+        &[farm_pool_info.key.as_ref(), &[farm_pool.bump_seed]],
+        program_id,
+    )?;
+    let vault = unpack_token_account(vault_info, token_program_info.key)?;
+    if vault.owner != farm_authority {
+        return Err(SwapError::InvalidOwner.into());
+    }
+    if vault.delegate.is_some() {
+        return Err(SwapError::InvalidDelegate.into());
+    }
+    if vault.close_authority.is_some() {
+        return Err(SwapError::InvalidCloseAuthority.into());
+    }
+
+    let (farm_reward_tokens_key, _) =
+        find_farm_reward_tokens_address(farm_pool_info.key, program_id);
+    if *farm_reward_tokens_info.key != farm_reward_tokens_key {
+        return Err(SwapError::InvalidFarmRewardToken.into());
+    }
+    let mut farm_reward_tokens = if farm_reward_tokens_info.owner != program_id {
+        let bump_seed = create_farm_reward_tokens_account(
+            program_id,
+            farm_pool_info.key,
+            farm_reward_tokens_info,
+            admin_info,
+            system_program_info,
+            rent,
+        )?;
+        FarmRewardTokens {
+            is_initialized: true,
+            bump_seed,
+            farm_pool_key: *farm_pool_info.key,
+            ..FarmRewardTokens::default()
+        }
+    } else {
+        unpack_farm_reward_tokens(program_id, farm_pool_info.key, farm_reward_tokens_info)?
+    };
+
+    farm_reward_tokens.add(
+        vault.mint,
+        *vault_info.key,
+        reward_per_slot,
+        Clock::get()?.slot,
+    )?;
+    // Deposits and withdrawals require the reward token accounts from now on
+    farm_pool.reward_token_count = farm_reward_tokens.reward_tokens_len;
+    FarmRewardTokens::pack(
+        farm_reward_tokens,
+        &mut farm_reward_tokens_info.data.borrow_mut(),
+    )?;
+    FarmInfo::pack(farm_pool, &mut farm_pool_info.data.borrow_mut())
+}
+
+/// Set the reward per slot of a partner reward token of a farm
+#[inline(never)]
+fn set_farm_reward_rate(
+    program_id: &Pubkey,
+    index: u8,
+    reward_per_slot: u64,
+    accounts: &[AccountInfo],
+) -> ProgramResult {
+    let account_info_iter = &mut accounts.iter();
+    let config_info = next_account_info(account_info_iter)?;
+    let farm_pool_info = next_account_info(account_info_iter)?;
+    let farm_reward_tokens_info = next_account_info(account_info_iter)?;
+    let admin_info = next_account_info(account_info_iter)?;
+
+    if config_info.owner != program_id || farm_pool_info.owner != program_id {
+        return Err(SwapError::InvalidAccountOwner.into());
+    }
+
+    let config = ConfigInfo::unpack(&config_info.data.borrow())?;
+    has_role(&config, AdminRole::FeeManager, admin_info)?;
+
+    let farm_pool = FarmInfo::unpack(&farm_pool_info.data.borrow())?;
+    utils::validate_farm_config_key(&farm_pool, config_info.key)?;
+
+    let mut farm_reward_tokens =
+        unpack_farm_reward_tokens(program_id, farm_pool_info.key, farm_reward_tokens_info)?;
+    // The slots before the change are emitted at the previous rate
+    let reward_token = farm_reward_tokens.get_mut(index)?;
+    reward_token.accrue(farm_pool.reserved_amount, Clock::get()?.slot)?;
+    reward_token.reward_per_slot = reward_per_slot;
+    FarmRewardTokens::pack(
+        farm_reward_tokens,
+        &mut farm_reward_tokens_info.data.borrow_mut(),
+    )
+}
+
+/// Set the reward emission per slot of a farm, switching it from the apr on the first call
+#[inline(never)]
+fn set_farm_emission_rate(
+    program_id: &Pubkey,
+    reward_per_slot: u64,
+    accounts: &[AccountInfo],
+) -> ProgramResult {
+    let account_info_iter = &mut accounts.iter();
+    let config_info = next_account_info(account_info_iter)?;
+    let farm_pool_info = next_account_info(account_info_iter)?;
+    let admin_info = next_account_info(account_info_iter)?;
+
+    if config_info.owner != program_id || farm_pool_info.owner != program_id {
+        return Err(SwapError::InvalidAccountOwner.into());
+    }
+
+    let config = ConfigInfo::unpack(&config_info.data.borrow())?;
+    has_role(&config, AdminRole::FeeManager, admin_info)?;
+
+    let mut farm_pool = FarmInfo::unpack(&farm_pool_info.data.borrow())?;
+    utils::validate_farm_config_key(&farm_pool, config_info.key)?;
+
+    farm_pool.set_emission_rate(reward_per_slot, &Clock::get()?)?;
+    FarmInfo::pack(farm_pool, &mut farm_pool_info.data.borrow_mut())
+}
+
+/// Fund the DELTAFI reward reserve of the market authority the rewards of a staking pool
+/// are claimed from
+#[inline(never)]
+fn fund_staking_rewards(
+    program_id: &Pubkey,
+    amount: u64,
+    accounts: &[AccountInfo],
+) -> ProgramResult {
+    let account_info_iter = &mut accounts.iter();
+    let config_info = next_account_info(account_info_iter)?;
+    let farm_pool_info = next_account_info(account_info_iter)?;
+    let market_authority_info = next_account_info(account_info_iter)?;
+    let reward_reserve_info = next_account_info(account_info_iter)?;
+    let source_info = next_account_info(account_info_iter)?;
+    let admin_info = next_account_info(account_info_iter)?;
+    let token_program_info = next_account_info(account_info_iter)?;
+    spl_token::check_program_account(token_program_info.key)?;
+
+    if config_info.owner != program_id || farm_pool_info.owner != program_id {
+        return Err(SwapError::InvalidAccountOwner.into());
+    }
+
+    let config = ConfigInfo::unpack(&config_info.data.borrow())?;
+    has_role(&config, AdminRole::FeeManager, admin_info)?;
+
+    let farm_pool = FarmInfo::unpack(&farm_pool_info.data.borrow())?;
+    utils::validate_farm_config_key(&farm_pool, config_info.key)?;
+    if farm_pool.pool_mint != config.deltafi_mint {
+        return Err(SwapError::IncorrectMint.into());
+    }
+
+    if *market_authority_info.key
+        != Pubkey::create_program_address(
+            &[config_info.key.as_ref(), &[config.bump_seed]],
+            program_id,
+        )?
+    {
+        return Err(SwapError::InvalidProgramAddress.into());
+    }
+    let reward_reserve = unpack_token_account(reward_reserve_info, token_program_info.key)?;
+    if reward_reserve.mint != config.deltafi_mint {
+        return Err(SwapError::IncorrectMint.into());
+    }
+    if reward_reserve.owner != *market_authority_info.key {
+        return Err(SwapError::InvalidOwner.into());
+    }
+
+    token_transfer(
+        source_info.clone(),
+        reward_reserve_info.clone(),
+        admin_info.clone(),
+        token_program_info.clone(),
+        amount,
+        &[],
+    )
+}
+
 /// Set new slope
 #[inline(never)]
 fn set_slope(program_id: &Pubkey, slope: u64, accounts: &[AccountInfo]) -> ProgramResult {
@@ -449,336 +971,5230 @@ fn set_swap_limit(
     Ok(())
 }
 
-#[cfg(test)]
-mod tests {
-    use super::*;
-    use crate::pyth::PYTH_PROGRAM_ID;
-    use solana_program::sysvar::Sysvar;
-    use spl_token::{
-        self,
-        state::{Account, AccountState, Mint},
-    };
-    use std::{mem, str::FromStr};
-
-    #[test]
-    fn test_is_admin() {
-        let admin_key = Pubkey::new_unique();
-        let owner_key = Pubkey::new_unique();
-        let wrong_key = Pubkey::new_unique();
-        let mut lamports = 0u64;
-        let mut data = [0u8];
-        let mut test_admin_account = AccountInfo::new(
-            &admin_key,
-            false,
-            false,
-            &mut lamports,
-            &mut data,
-            &owner_key,
-            true,
-            1u64,
-        );
-
-        assert_eq!(
-            is_admin(&wrong_key, &test_admin_account),
-            Err(ProgramError::from(SwapError::Unauthorized))
-        );
-        assert_eq!(
-            is_admin(&admin_key, &test_admin_account),
-            Err(ProgramError::MissingRequiredSignature)
-        );
+/// Set the policy on large oracle price jumps
+#[inline(never)]
+fn set_price_jump_policy(
+    program_id: &Pubkey,
+    price_jump_policy: u8,
+    accounts: &[AccountInfo],
+) -> ProgramResult {
+    let account_info_iter = &mut accounts.iter();
+    let config_info = next_account_info(account_info_iter)?;
+    let swap_info = next_account_info(account_info_iter)?;
+    let admin_info = next_account_info(account_info_iter)?;
 
-        let mut lamports_ = 0u64;
-        let mut data_ = [0u8];
-        test_admin_account = AccountInfo::new(
-            &admin_key,
-            true,
-            false,
-            &mut lamports_,
-            &mut data_,
-            &owner_key,
-            true,
-            1u64,
-        );
-        assert!(is_admin(&admin_key, &test_admin_account).is_ok());
+    if config_info.owner != program_id || swap_info.owner != program_id {
+        return Err(SwapError::InvalidAccountOwner.into());
+    }
+
+    let config = ConfigInfo::unpack(&config_info.data.borrow())?;
+
+    has_role(&config, AdminRole::OracleManager, admin_info)?;
+
+    let mut token_swap = SwapInfo::unpack(&swap_info.data.borrow())?;
+    utils::validate_swap_config_key(&token_swap, config_info.key)?;
+
+    token_swap.price_jump_policy =
+        PriceJumpPolicy::try_from(price_jump_policy).map_err(|_| SwapError::InvalidInput)?;
+    SwapInfo::pack(token_swap, &mut swap_info.data.borrow_mut())?;
+    Ok(())
+}
+
+/// Set the max divergence between pyth and serum prices
+#[inline(never)]
+fn set_max_price_divergence(
+    program_id: &Pubkey,
+    max_price_divergence: u16,
+    accounts: &[AccountInfo],
+) -> ProgramResult {
+    let account_info_iter = &mut accounts.iter();
+    let config_info = next_account_info(account_info_iter)?;
+    let swap_info = next_account_info(account_info_iter)?;
+    let admin_info = next_account_info(account_info_iter)?;
+
+    if config_info.owner != program_id || swap_info.owner != program_id {
+        return Err(SwapError::InvalidAccountOwner.into());
+    }
+
+    let config = ConfigInfo::unpack(&config_info.data.borrow())?;
+
+    has_role(&config, AdminRole::OracleManager, admin_info)?;
+
+    let mut token_swap = SwapInfo::unpack(&swap_info.data.borrow())?;
+    utils::validate_swap_config_key(&token_swap, config_info.key)?;
+
+    token_swap.max_price_divergence = max_price_divergence;
+    SwapInfo::pack(token_swap, &mut swap_info.data.borrow_mut())?;
+    Ok(())
+}
+
+/// Set the orderbook program accepted for serum market prices
+#[inline(never)]
+fn set_orderbook_program(program_id: &Pubkey, accounts: &[AccountInfo]) -> ProgramResult {
+    let account_info_iter = &mut accounts.iter();
+    let config_info = next_account_info(account_info_iter)?;
+    let admin_info = next_account_info(account_info_iter)?;
+    let orderbook_program_info = next_account_info(account_info_iter)?;
+
+    if config_info.owner != program_id {
+        return Err(SwapError::InvalidAccountOwner.into());
+    }
+
+    let mut config = ConfigInfo::unpack(&config_info.data.borrow())?;
+    is_admin(&config.admin_key, admin_info)?;
+
+    utils::check_orderbook_program_id(orderbook_program_info.key)?;
+
+    config.orderbook_program_id = *orderbook_program_info.key;
+    ConfigInfo::pack(config, &mut config_info.data.borrow_mut())?;
+    Ok(())
+}
+
+/// Set the exchange rate adapter pricing a stable pool
+#[inline(never)]
+fn set_exchange_rate_adapter(
+    program_id: &Pubkey,
+    adapter_type: u8,
+    accounts: &[AccountInfo],
+) -> ProgramResult {
+    let account_info_iter = &mut accounts.iter();
+    let config_info = next_account_info(account_info_iter)?;
+    let swap_info = next_account_info(account_info_iter)?;
+    let admin_info = next_account_info(account_info_iter)?;
+
+    if config_info.owner != program_id || swap_info.owner != program_id {
+        return Err(SwapError::InvalidAccountOwner.into());
+    }
+
+    let config = ConfigInfo::unpack(&config_info.data.borrow())?;
+
+    has_role(&config, AdminRole::OracleManager, admin_info)?;
+
+    let mut token_swap = SwapInfo::unpack(&swap_info.data.borrow())?;
+    utils::validate_swap_config_key(&token_swap, config_info.key)?;
+    utils::validate(
+        token_swap.swap_type == SwapType::Stable,
+        SwapError::IncorrectSwapType,
+    )?;
+
+    let adapter_type =
+        ExchangeRateAdapterType::try_from(adapter_type).map_err(|_| SwapError::InvalidInput)?;
+    match adapter_type.adapter() {
+        Some(adapter) => {
+            let exchange_rate_info = next_account_info(account_info_iter)?;
+            adapter.validate_rate_account(exchange_rate_info, &token_swap.token_a_mint)?;
+            token_swap.set_exchange_rate_adapter(adapter_type, *exchange_rate_info.key);
+        }
+        None => token_swap.set_exchange_rate_adapter(adapter_type, Pubkey::default()),
+    }
+
+    SwapInfo::pack(token_swap, &mut swap_info.data.borrow_mut())?;
+    Ok(())
+}
+
+/// Set the pyth price tolerances of the pool
+#[inline(never)]
+fn set_oracle_params(
+    program_id: &Pubkey,
+    oracle_params: &OracleParams,
+    accounts: &[AccountInfo],
+) -> ProgramResult {
+    let account_info_iter = &mut accounts.iter();
+    let config_info = next_account_info(account_info_iter)?;
+    let swap_info = next_account_info(account_info_iter)?;
+    let admin_info = next_account_info(account_info_iter)?;
+
+    if config_info.owner != program_id || swap_info.owner != program_id {
+        return Err(SwapError::InvalidAccountOwner.into());
+    }
+
+    let config = ConfigInfo::unpack(&config_info.data.borrow())?;
+
+    has_role(&config, AdminRole::OracleManager, admin_info)?;
+
+    let mut token_swap = SwapInfo::unpack(&swap_info.data.borrow())?;
+    utils::validate_swap_config_key(&token_swap, config_info.key)?;
+
+    oracle_params.validate()?;
+    token_swap.oracle_params = *oracle_params;
+    SwapInfo::pack(token_swap, &mut swap_info.data.borrow_mut())?;
+    Ok(())
+}
+
+/// Replace the oracle accounts of a normal pool, checked as on initialization for the new
+/// oracle priority flags when set, for the current ones otherwise
+#[inline(never)]
+fn set_oracle_accounts(
+    program_id: &Pubkey,
+    oracle_priority_flags: Option<u8>,
+    accounts: &[AccountInfo],
+) -> ProgramResult {
+    let account_info_iter = &mut accounts.iter();
+    let config_info = next_account_info(account_info_iter)?;
+    let swap_info = next_account_info(account_info_iter)?;
+    let admin_info = next_account_info(account_info_iter)?;
+    let pyth_a_product_info = next_account_info(account_info_iter)?;
+    let pyth_a_price_info = next_account_info(account_info_iter)?;
+    let pyth_b_product_info = next_account_info(account_info_iter)?;
+    let pyth_b_price_info = next_account_info(account_info_iter)?;
+    let serum_market_info = next_account_info(account_info_iter)?;
+    let serum_bids_info = next_account_info(account_info_iter)?;
+    let serum_asks_info = next_account_info(account_info_iter)?;
+
+    if config_info.owner != program_id || swap_info.owner != program_id {
+        return Err(SwapError::InvalidAccountOwner.into());
+    }
+
+    let config = ConfigInfo::unpack(&config_info.data.borrow())?;
+
+    has_role(&config, AdminRole::OracleManager, admin_info)?;
+
+    let mut token_swap = SwapInfo::unpack(&swap_info.data.borrow())?;
+    utils::validate_swap_config_key(&token_swap, config_info.key)?;
+    utils::validate(
+        token_swap.swap_type == SwapType::Normal,
+        SwapError::IncorrectSwapType,
+    )?;
+
+    let oracle_priority_flags = oracle_priority_flags.unwrap_or(token_swap.oracle_priority_flags);
+    let oracle_flags = OraclePriorityFlag::from_bits(oracle_priority_flags)
+        .ok_or(SwapError::UnsupportedOraclePriority)?;
+    let (pyth_a, pyth_b, serum_combined_address) = check_initial_oracles(
+        &config,
+        oracle_flags,
+        token_swap.quote_is_token_a,
+        &token_swap.token_a_mint,
+        &token_swap.token_b_mint,
+        pyth_a_product_info,
+        pyth_a_price_info,
+        pyth_b_product_info,
+        pyth_b_price_info,
+        serum_market_info,
+        serum_bids_info,
+        serum_asks_info,
+    )?;
+    if config.is_oracle_feed_registry_enabled {
+        check_initial_oracle_feeds(
+            program_id,
+            config_info.key,
+            oracle_flags,
+            token_swap.quote_is_token_a,
+            &token_swap.token_a_mint,
+            &token_swap.token_b_mint,
+            pyth_a_price_info.key,
+            pyth_b_price_info.key,
+            serum_market_info.key,
+            account_info_iter,
+        )?;
+    }
+
+    token_swap.oracle_priority_flags = oracle_priority_flags;
+    token_swap.pyth_a = pyth_a;
+    token_swap.pyth_b = pyth_b;
+    token_swap.serum_combined_address = serum_combined_address;
+    SwapInfo::pack(token_swap, &mut swap_info.data.borrow_mut())?;
+    Ok(())
+}
+
+/// Set the price setter of a MANUAL oracle pool with the bounds of its pushed prices
+#[inline(never)]
+fn set_price_setter(
+    program_id: &Pubkey,
+    max_change_bps: u16,
+    stale_slots: u64,
+    accounts: &[AccountInfo],
+) -> ProgramResult {
+    let account_info_iter = &mut accounts.iter();
+    let config_info = next_account_info(account_info_iter)?;
+    let swap_info = next_account_info(account_info_iter)?;
+    let admin_info = next_account_info(account_info_iter)?;
+    let price_setter_info = next_account_info(account_info_iter)?;
+
+    if config_info.owner != program_id || swap_info.owner != program_id {
+        return Err(SwapError::InvalidAccountOwner.into());
+    }
+
+    let config = ConfigInfo::unpack(&config_info.data.borrow())?;
+    has_role(&config, AdminRole::OracleManager, admin_info)?;
+
+    let mut token_swap = SwapInfo::unpack(&swap_info.data.borrow())?;
+    utils::validate_swap_config_key(&token_swap, config_info.key)?;
+    utils::validate(
+        token_swap.swap_type == SwapType::Normal,
+        SwapError::IncorrectSwapType,
+    )?;
+    if *price_setter_info.key != Pubkey::default()
+        && (max_change_bps == 0 || max_change_bps > 10_000 || stale_slots == 0)
+    {
+        return Err(SwapError::InvalidInput.into());
+    }
+
+    // The prices pushed by the previous price setter are not trusted by the new one
+    if token_swap.price_setter != *price_setter_info.key {
+        token_swap.manual_price = Decimal::zero();
+        token_swap.manual_price_slot = 0;
+    }
+    token_swap.price_setter = *price_setter_info.key;
+    token_swap.manual_max_change_bps = max_change_bps;
+    token_swap.manual_stale_slots = stale_slots;
+    SwapInfo::pack(token_swap, &mut swap_info.data.borrow_mut())?;
+    Ok(())
+}
+
+/// Set the window of the moving average of the oracle price the pool reprices at
+#[inline(never)]
+fn set_price_ema_window(
+    program_id: &Pubkey,
+    window_slots: u64,
+    accounts: &[AccountInfo],
+) -> ProgramResult {
+    let account_info_iter = &mut accounts.iter();
+    let config_info = next_account_info(account_info_iter)?;
+    let swap_info = next_account_info(account_info_iter)?;
+    let admin_info = next_account_info(account_info_iter)?;
+
+    if config_info.owner != program_id || swap_info.owner != program_id {
+        return Err(SwapError::InvalidAccountOwner.into());
+    }
+
+    let config = ConfigInfo::unpack(&config_info.data.borrow())?;
+    has_role(&config, AdminRole::OracleManager, admin_info)?;
+
+    let mut token_swap = SwapInfo::unpack(&swap_info.data.borrow())?;
+    utils::validate_swap_config_key(&token_swap, config_info.key)?;
+    utils::validate(
+        token_swap.swap_type == SwapType::Normal,
+        SwapError::IncorrectSwapType,
+    )?;
+
+    token_swap.ema_window_slots = window_slots;
+    // The next oracle price restarts the average on the new window
+    token_swap.pool_state.ema_market_price = Decimal::zero();
+    SwapInfo::pack(token_swap, &mut swap_info.data.borrow_mut())?;
+    Ok(())
+}
+
+/// Turn on or off the skim of the pool surplus to the admin fee accounts by the swaps
+#[inline(never)]
+fn set_skim_surplus(
+    program_id: &Pubkey,
+    skim_surplus: bool,
+    accounts: &[AccountInfo],
+) -> ProgramResult {
+    let account_info_iter = &mut accounts.iter();
+    let config_info = next_account_info(account_info_iter)?;
+    let swap_info = next_account_info(account_info_iter)?;
+    let admin_info = next_account_info(account_info_iter)?;
+
+    if config_info.owner != program_id || swap_info.owner != program_id {
+        return Err(SwapError::InvalidAccountOwner.into());
+    }
+
+    let config = ConfigInfo::unpack(&config_info.data.borrow())?;
+    has_role(&config, AdminRole::FeeManager, admin_info)?;
+
+    let mut token_swap = SwapInfo::unpack(&swap_info.data.borrow())?;
+    utils::validate_swap_config_key(&token_swap, config_info.key)?;
+
+    token_swap.skim_surplus = skim_surplus;
+    SwapInfo::pack(token_swap, &mut swap_info.data.borrow_mut())?;
+    Ok(())
+}
+
+/// Set the market maker allowed to post quotes at the allowlist index,
+/// the default pubkey removes the market maker
+#[inline(never)]
+fn set_market_maker(program_id: &Pubkey, index: u8, accounts: &[AccountInfo]) -> ProgramResult {
+    let account_info_iter = &mut accounts.iter();
+    let config_info = next_account_info(account_info_iter)?;
+    let admin_info = next_account_info(account_info_iter)?;
+    let market_maker_info = next_account_info(account_info_iter)?;
+
+    if config_info.owner != program_id {
+        return Err(SwapError::InvalidAccountOwner.into());
+    }
+
+    let mut config = ConfigInfo::unpack(&config_info.data.borrow())?;
+    is_admin(&config.admin_key, admin_info)?;
+
+    let index = index as usize;
+    utils::validate(index < MAX_MARKET_MAKERS, SwapError::InvalidInput)?;
+    // A market maker takes a single slot of the allowlist
+    if config.market_makers[index] != *market_maker_info.key
+        && config.is_market_maker(market_maker_info.key)
+    {
+        return Err(SwapError::InvalidMarketMaker.into());
+    }
+
+    config.market_makers[index] = *market_maker_info.key;
+    ConfigInfo::pack(config, &mut config_info.data.borrow_mut())?;
+    Ok(())
+}
+
+/// Sweep the old fee account into the new one, close it and set the new one to the pool
+#[inline(never)]
+fn rotate_fee_account(program_id: &Pubkey, accounts: &[AccountInfo]) -> ProgramResult {
+    let account_info_iter = &mut accounts.iter();
+    let config_info = next_account_info(account_info_iter)?;
+    let swap_info = next_account_info(account_info_iter)?;
+    let authority_info = next_account_info(account_info_iter)?;
+    let admin_info = next_account_info(account_info_iter)?;
+    let old_fee_account_info = next_account_info(account_info_iter)?;
+    let new_fee_account_info = next_account_info(account_info_iter)?;
+    let destination_info = next_account_info(account_info_iter)?;
+    let token_program_info = next_account_info(account_info_iter)?;
+
+    if config_info.owner != program_id || swap_info.owner != program_id {
+        return Err(SwapError::InvalidAccountOwner.into());
+    }
+
+    let config = ConfigInfo::unpack(&config_info.data.borrow())?;
+
+    has_role(&config, AdminRole::FeeManager, admin_info)?;
+
+    let mut token_swap = SwapInfo::unpack(&swap_info.data.borrow())?;
+    utils::validate_swap_config_key(&token_swap, config_info.key)?;
+
+    let swap_authority_signer_seeds = &[swap_info.key.as_ref(), &[token_swap.nonce]];
+    if *authority_info.key
+        != Pubkey::create_program_address(swap_authority_signer_seeds, program_id)?
+    {
+        return Err(SwapError::InvalidProgramAddress.into());
+    }
+    if old_fee_account_info.key == new_fee_account_info.key {
+        return Err(SwapError::InvalidInput.into());
+    }
+
+    let old_fee_account = unpack_token_account(old_fee_account_info, token_program_info.key)?;
+    let new_fee_account = unpack_token_account(new_fee_account_info, token_program_info.key)?;
+
+    if *authority_info.key != new_fee_account.owner {
+        return Err(SwapError::InvalidOwner.into());
+    }
+    if new_fee_account.mint != old_fee_account.mint {
+        return Err(SwapError::IncorrectMint.into());
+    }
+    if *old_fee_account_info.key == token_swap.admin_fee_key_a {
+        token_swap.admin_fee_key_a = *new_fee_account_info.key;
+    } else if *old_fee_account_info.key == token_swap.admin_fee_key_b {
+        token_swap.admin_fee_key_b = *new_fee_account_info.key;
+    } else {
+        return Err(SwapError::InvalidAdmin.into());
+    }
+
+    // Check the old fee account can be closed by $authority before anything moves
+    let close_authority = match old_fee_account.close_authority {
+        COption::Some(close_authority) => close_authority,
+        COption::None => old_fee_account.owner,
+    };
+    if *authority_info.key != close_authority {
+        return Err(SwapError::InvalidOwner.into());
+    }
+
+    token_transfer(
+        old_fee_account_info.clone(),
+        new_fee_account_info.clone(),
+        authority_info.clone(),
+        token_program_info.clone(),
+        old_fee_account.amount,
+        swap_authority_signer_seeds,
+    )?;
+    token_close_account(
+        old_fee_account_info.clone(),
+        destination_info.clone(),
+        authority_info.clone(),
+        token_program_info.clone(),
+        swap_authority_signer_seeds,
+    )?;
+
+    SwapInfo::pack(token_swap, &mut swap_info.data.borrow_mut())?;
+    Ok(())
+}
+
+/// Commit the hash of the initial price of a pool before it is initialized
+#[inline(never)]
+fn commit_initial_price(
+    program_id: &Pubkey,
+    price_hash: [u8; 32],
+    accounts: &[AccountInfo],
+) -> ProgramResult {
+    let account_info_iter = &mut accounts.iter();
+    let config_info = next_account_info(account_info_iter)?;
+    let price_commitment_info = next_account_info(account_info_iter)?;
+    let swap_info = next_account_info(account_info_iter)?;
+    let admin_info = next_account_info(account_info_iter)?;
+    let clock = &Clock::from_account_info(next_account_info(account_info_iter)?)?;
+    let rent = &Rent::from_account_info(next_account_info(account_info_iter)?)?;
+
+    if config_info.owner != program_id || price_commitment_info.owner != program_id {
+        return Err(SwapError::InvalidAccountOwner.into());
+    }
+
+    let config = ConfigInfo::unpack(&config_info.data.borrow())?;
+    is_admin(&config.admin_key, admin_info)?;
+
+    // The swap account may not be created yet, a live pool has nothing left to commit
+    if swap_info.owner == program_id {
+        assert_uninitialized::<SwapInfo>(swap_info)?;
+    }
+
+    let expected_commitment_pubkey =
+        get_price_commitment_pubkey(admin_info.key, swap_info.key, program_id)?;
+    utils::validate(
+        expected_commitment_pubkey == *price_commitment_info.key,
+        SwapError::InvalidAccountOwner,
+    )?;
+    assert_rent_exempt(rent, price_commitment_info)?;
+
+    PriceCommitment::pack(
+        PriceCommitment {
+            is_initialized: true,
+            config_key: *config_info.key,
+            swap_key: *swap_info.key,
+            price_hash,
+            commit_slot: clock.slot,
+        },
+        &mut price_commitment_info.data.borrow_mut(),
+    )
+}
+
+/// Commit a new price of a pegged pool, it can only be applied after the timelock
+#[inline(never)]
+fn commit_pegged_price(
+    program_id: &Pubkey,
+    pegged_price: u128,
+    accounts: &[AccountInfo],
+) -> ProgramResult {
+    let account_info_iter = &mut accounts.iter();
+    let config_info = next_account_info(account_info_iter)?;
+    let swap_info = next_account_info(account_info_iter)?;
+    let admin_info = next_account_info(account_info_iter)?;
+    let clock = &Clock::from_account_info(next_account_info(account_info_iter)?)?;
+
+    if config_info.owner != program_id || swap_info.owner != program_id {
+        return Err(SwapError::InvalidAccountOwner.into());
+    }
+
+    let config = ConfigInfo::unpack(&config_info.data.borrow())?;
+    has_role(&config, AdminRole::OracleManager, admin_info)?;
+
+    let mut token_swap = SwapInfo::unpack(&swap_info.data.borrow())?;
+    utils::validate_swap_config_key(&token_swap, config_info.key)?;
+    utils::validate(
+        token_swap.swap_type == SwapType::Pegged,
+        SwapError::IncorrectSwapType,
+    )?;
+    utils::validate(pegged_price > 0, SwapError::InvalidInput)?;
+
+    token_swap.future_pegged_price = Decimal::from_scaled_val(pegged_price);
+    token_swap.pegged_price_apply_slot = clock
+        .slot
+        .checked_add(PEGGED_PRICE_TIMELOCK_SLOTS)
+        .ok_or(SwapError::CalculationFailure)?;
+    SwapInfo::pack(token_swap, &mut swap_info.data.borrow_mut())?;
+    Ok(())
+}
+
+/// Apply the committed price of a pegged pool once the timelock is over
+#[inline(never)]
+fn apply_pegged_price(program_id: &Pubkey, accounts: &[AccountInfo]) -> ProgramResult {
+    let account_info_iter = &mut accounts.iter();
+    let config_info = next_account_info(account_info_iter)?;
+    let swap_info = next_account_info(account_info_iter)?;
+    let admin_info = next_account_info(account_info_iter)?;
+    let clock = &Clock::from_account_info(next_account_info(account_info_iter)?)?;
+
+    if config_info.owner != program_id || swap_info.owner != program_id {
+        return Err(SwapError::InvalidAccountOwner.into());
+    }
+
+    let config = ConfigInfo::unpack(&config_info.data.borrow())?;
+    has_role(&config, AdminRole::OracleManager, admin_info)?;
+
+    let mut token_swap = SwapInfo::unpack(&swap_info.data.borrow())?;
+    utils::validate_swap_config_key(&token_swap, config_info.key)?;
+    utils::validate(
+        token_swap.swap_type == SwapType::Pegged,
+        SwapError::IncorrectSwapType,
+    )?;
+    utils::validate(
+        token_swap.pegged_price_apply_slot != 0,
+        SwapError::InvalidInput,
+    )?;
+    utils::validate(
+        clock.slot >= token_swap.pegged_price_apply_slot,
+        SwapError::TimelockNotExpired,
+    )?;
+
+    let pegged_price = token_swap.future_pegged_price;
+    token_swap.pool_state.last_market_price = pegged_price;
+    token_swap.pool_state.set_market_price(
+        token_swap.token_a_decimals,
+        token_swap.token_b_decimals,
+        pegged_price,
+    )?;
+    token_swap.future_pegged_price = Decimal::zero();
+    token_swap.pegged_price_apply_slot = 0;
+    SwapInfo::pack(token_swap, &mut swap_info.data.borrow_mut())?;
+    Ok(())
+}
+
+/// Delegate an admin role to a key, the default pubkey revokes the role.
+/// The super-admin is moved with `CommitNewAdmin` instead.
+#[inline(never)]
+fn set_admin_role(program_id: &Pubkey, role: u8, accounts: &[AccountInfo]) -> ProgramResult {
+    let account_info_iter = &mut accounts.iter();
+    let config_info = next_account_info(account_info_iter)?;
+    let admin_info = next_account_info(account_info_iter)?;
+    let role_info = next_account_info(account_info_iter)?;
+
+    if config_info.owner != program_id {
+        return Err(SwapError::InvalidAccountOwner.into());
+    }
+
+    let mut config = ConfigInfo::unpack(&config_info.data.borrow())?;
+    is_admin(&config.admin_key, admin_info)?;
+
+    match AdminRole::try_from(role).map_err(|_| SwapError::InvalidInput)? {
+        AdminRole::SuperAdmin => return Err(SwapError::InvalidInput.into()),
+        AdminRole::FeeManager => config.fee_manager_key = *role_info.key,
+        AdminRole::Pauser => config.pauser_key = *role_info.key,
+        AdminRole::OracleManager => config.oracle_manager_key = *role_info.key,
+        AdminRole::Guardian => config.guardian_key = *role_info.key,
+    }
+
+    ConfigInfo::pack(config, &mut config_info.data.borrow_mut())?;
+    Ok(())
+}
+
+/// Pause every pool passed after the guardian, all of them must belong to the config
+#[inline(never)]
+fn pause_all(program_id: &Pubkey, accounts: &[AccountInfo]) -> ProgramResult {
+    let account_info_iter = &mut accounts.iter();
+    let config_info = next_account_info(account_info_iter)?;
+    let guardian_info = next_account_info(account_info_iter)?;
+
+    if config_info.owner != program_id {
+        return Err(SwapError::InvalidAccountOwner.into());
+    }
+
+    let config = ConfigInfo::unpack(&config_info.data.borrow())?;
+    has_role(&config, AdminRole::Guardian, guardian_info)?;
+
+    for swap_info in account_info_iter {
+        if swap_info.owner != program_id {
+            return Err(SwapError::InvalidAccountOwner.into());
+        }
+
+        let mut token_swap = SwapInfo::unpack(&swap_info.data.borrow())?;
+        utils::validate_swap_config_key(&token_swap, config_info.key)?;
+
+        token_swap.is_paused = true;
+        SwapInfo::pack(token_swap, &mut swap_info.data.borrow_mut())?;
+    }
+    Ok(())
+}
+
+/// Turn on or off the check that swaps send the output to a token account owned by a signer
+#[inline(never)]
+fn set_strict_destination_owner(
+    program_id: &Pubkey,
+    strict_destination_owner: bool,
+    accounts: &[AccountInfo],
+) -> ProgramResult {
+    let account_info_iter = &mut accounts.iter();
+    let config_info = next_account_info(account_info_iter)?;
+    let swap_info = next_account_info(account_info_iter)?;
+    let admin_info = next_account_info(account_info_iter)?;
+
+    if config_info.owner != program_id || swap_info.owner != program_id {
+        return Err(SwapError::InvalidAccountOwner.into());
+    }
+
+    let config = ConfigInfo::unpack(&config_info.data.borrow())?;
+
+    is_admin(&config.admin_key, admin_info)?;
+
+    let mut token_swap = SwapInfo::unpack(&swap_info.data.borrow())?;
+    utils::validate_swap_config_key(&token_swap, config_info.key)?;
+
+    token_swap.strict_destination_owner = strict_destination_owner;
+    SwapInfo::pack(token_swap, &mut swap_info.data.borrow_mut())?;
+    Ok(())
+}
+
+/// Enable the farm user registry of the farm pool
+#[inline(never)]
+fn enable_farm_user_registry(program_id: &Pubkey, accounts: &[AccountInfo]) -> ProgramResult {
+    let account_info_iter = &mut accounts.iter();
+    let config_info = next_account_info(account_info_iter)?;
+    let farm_pool_info = next_account_info(account_info_iter)?;
+    let admin_info = next_account_info(account_info_iter)?;
+
+    if config_info.owner != program_id || farm_pool_info.owner != program_id {
+        return Err(SwapError::InvalidAccountOwner.into());
+    }
+
+    let config = ConfigInfo::unpack(&config_info.data.borrow())?;
+    is_admin(&config.admin_key, admin_info)?;
+
+    let mut farm_pool = FarmInfo::unpack(&farm_pool_info.data.borrow())?;
+    utils::validate_farm_config_key(&farm_pool, config_info.key)?;
+
+    farm_pool.is_farm_user_registry_enabled = true;
+    FarmInfo::pack(farm_pool, &mut farm_pool_info.data.borrow_mut())?;
+    Ok(())
+}
+
+/// Set the operations of the swap paused on their own
+#[inline(never)]
+fn set_pause_flags(
+    program_id: &Pubkey,
+    pause_flags: u8,
+    accounts: &[AccountInfo],
+) -> ProgramResult {
+    let account_info_iter = &mut accounts.iter();
+    let config_info = next_account_info(account_info_iter)?;
+    let swap_info = next_account_info(account_info_iter)?;
+    let admin_info = next_account_info(account_info_iter)?;
+
+    if config_info.owner != program_id || swap_info.owner != program_id {
+        return Err(SwapError::InvalidAccountOwner.into());
+    }
+
+    let config = ConfigInfo::unpack(&config_info.data.borrow())?;
+
+    has_role(&config, AdminRole::Pauser, admin_info)?;
+
+    let pause_flags = PauseFlag::from_bits(pause_flags).ok_or(SwapError::InvalidInput)?;
+
+    let mut token_swap = SwapInfo::unpack(&swap_info.data.borrow())?;
+    utils::validate_swap_config_key(&token_swap, config_info.key)?;
+
+    if token_swap.pause_swap && !pause_flags.contains(PauseFlag::SWAP) {
+        token_swap.reset_circuit_breaker();
+    }
+    token_swap.pause_swap = pause_flags.contains(PauseFlag::SWAP);
+    token_swap.pause_deposit = pause_flags.contains(PauseFlag::DEPOSIT);
+    token_swap.pause_withdraw = pause_flags.contains(PauseFlag::WITHDRAW);
+    SwapInfo::pack(token_swap, &mut swap_info.data.borrow_mut())?;
+    Ok(())
+}
+
+/// Claim the protocol fees accrued in the pool state into the fee accounts
+#[inline(never)]
+fn claim_protocol_fees(program_id: &Pubkey, accounts: &[AccountInfo]) -> ProgramResult {
+    let account_info_iter = &mut accounts.iter();
+    let config_info = next_account_info(account_info_iter)?;
+    let swap_info = next_account_info(account_info_iter)?;
+    let authority_info = next_account_info(account_info_iter)?;
+    let admin_info = next_account_info(account_info_iter)?;
+    let token_a_info = next_account_info(account_info_iter)?;
+    let token_b_info = next_account_info(account_info_iter)?;
+    let admin_fee_a_info = next_account_info(account_info_iter)?;
+    let admin_fee_b_info = next_account_info(account_info_iter)?;
+    let token_program_info = next_account_info(account_info_iter)?;
+    spl_token::check_program_account(token_program_info.key)?;
+
+    if config_info.owner != program_id || swap_info.owner != program_id {
+        return Err(SwapError::InvalidAccountOwner.into());
+    }
+
+    let config = ConfigInfo::unpack(&config_info.data.borrow())?;
+
+    has_role(&config, AdminRole::FeeManager, admin_info)?;
+
+    let mut token_swap = SwapInfo::unpack(&swap_info.data.borrow())?;
+    utils::validate_swap_config_key(&token_swap, config_info.key)?;
+
+    let swap_authority_signer_seeds = &[swap_info.key.as_ref(), &[token_swap.nonce]];
+    if *authority_info.key
+        != Pubkey::create_program_address(swap_authority_signer_seeds, program_id)?
+    {
+        return Err(SwapError::InvalidProgramAddress.into());
+    }
+    if *token_a_info.key != token_swap.token_a || *token_b_info.key != token_swap.token_b {
+        return Err(SwapError::IncorrectSwapAccount.into());
+    }
+    if *admin_fee_a_info.key != token_swap.admin_fee_key_a
+        || *admin_fee_b_info.key != token_swap.admin_fee_key_b
+    {
+        return Err(SwapError::InvalidAdmin.into());
+    }
+
+    let (base_protocol_fee, quote_protocol_fee) = token_swap.pool_state.claim_protocol_fee();
+    SwapInfo::pack(token_swap, &mut swap_info.data.borrow_mut())?;
+
+    token_transfer(
+        token_a_info.clone(),
+        admin_fee_a_info.clone(),
+        authority_info.clone(),
+        token_program_info.clone(),
+        base_protocol_fee,
+        swap_authority_signer_seeds,
+    )?;
+    token_transfer(
+        token_b_info.clone(),
+        admin_fee_b_info.clone(),
+        authority_info.clone(),
+        token_program_info.clone(),
+        quote_protocol_fee,
+        swap_authority_signer_seeds,
+    )?;
+
+    Ok(())
+}
+
+/// Set the protocol share of the LP fees
+#[inline(never)]
+fn set_protocol_fee(
+    program_id: &Pubkey,
+    numerator: u64,
+    denominator: u64,
+    accounts: &[AccountInfo],
+) -> ProgramResult {
+    let account_info_iter = &mut accounts.iter();
+    let config_info = next_account_info(account_info_iter)?;
+    let swap_info = next_account_info(account_info_iter)?;
+    let admin_info = next_account_info(account_info_iter)?;
+
+    if config_info.owner != program_id || swap_info.owner != program_id {
+        return Err(SwapError::InvalidAccountOwner.into());
+    }
+
+    let config = ConfigInfo::unpack(&config_info.data.borrow())?;
+    has_role(&config, AdminRole::FeeManager, admin_info)?;
+
+    // A zero numerator turns the protocol fee off, otherwise the share is at most the LP fee
+    if numerator > denominator {
+        return Err(SwapError::InvalidInput.into());
+    }
+
+    let mut token_swap = SwapInfo::unpack(&swap_info.data.borrow())?;
+    utils::validate_swap_config_key(&token_swap, config_info.key)?;
+
+    token_swap.pool_state.protocol_fee_numerator = numerator;
+    token_swap.pool_state.protocol_fee_denominator = denominator;
+    SwapInfo::pack(token_swap, &mut swap_info.data.borrow_mut())?;
+    Ok(())
+}
+
+/// Close a paused pool without liquidity
+#[inline(never)]
+fn close_pool(program_id: &Pubkey, accounts: &[AccountInfo]) -> ProgramResult {
+    let account_info_iter = &mut accounts.iter();
+    let config_info = next_account_info(account_info_iter)?;
+    let swap_info = next_account_info(account_info_iter)?;
+    let authority_info = next_account_info(account_info_iter)?;
+    let admin_info = next_account_info(account_info_iter)?;
+    let pool_mint_info = next_account_info(account_info_iter)?;
+    let token_a_info = next_account_info(account_info_iter)?;
+    let token_b_info = next_account_info(account_info_iter)?;
+    let destination_a_info = next_account_info(account_info_iter)?;
+    let destination_b_info = next_account_info(account_info_iter)?;
+    let token_program_info = next_account_info(account_info_iter)?;
+    spl_token::check_program_account(token_program_info.key)?;
+
+    if config_info.owner != program_id || swap_info.owner != program_id {
+        return Err(SwapError::InvalidAccountOwner.into());
+    }
+
+    let config = ConfigInfo::unpack(&config_info.data.borrow())?;
+    is_admin(&config.admin_key, admin_info)?;
+
+    let token_swap = SwapInfo::unpack(&swap_info.data.borrow())?;
+    utils::validate_swap_config_key(&token_swap, config_info.key)?;
+
+    let swap_authority_signer_seeds = &[swap_info.key.as_ref(), &[token_swap.nonce]];
+    if *authority_info.key
+        != Pubkey::create_program_address(swap_authority_signer_seeds, program_id)?
+    {
+        return Err(SwapError::InvalidProgramAddress.into());
+    }
+    if *pool_mint_info.key != token_swap.pool_mint {
+        return Err(SwapError::IncorrectMint.into());
+    }
+    if *token_a_info.key != token_swap.token_a || *token_b_info.key != token_swap.token_b {
+        return Err(SwapError::IncorrectSwapAccount.into());
+    }
+    let pool_mint = unpack_mint(pool_mint_info, token_program_info.key)?;
+    if !token_swap.is_paused || token_swap.pool_state.total_supply != 0 || pool_mint.supply != 0 {
+        return Err(SwapError::PoolNotClosable.into());
+    }
+
+    // The admin fee accounts belong to the fee owner, not to the pool
+    for (token_info, destination_info) in [
+        (token_a_info, destination_a_info),
+        (token_b_info, destination_b_info),
+    ]
+    .iter()
+    {
+        let amount = unpack_token_account(token_info, token_program_info.key)?.amount;
+        token_transfer(
+            (*token_info).clone(),
+            (*destination_info).clone(),
+            authority_info.clone(),
+            token_program_info.clone(),
+            amount,
+            swap_authority_signer_seeds,
+        )?;
+        token_close_account(
+            (*token_info).clone(),
+            admin_info.clone(),
+            authority_info.clone(),
+            token_program_info.clone(),
+            swap_authority_signer_seeds,
+        )?;
+    }
+
+    set_authority_signed(
+        token_program_info,
+        pool_mint_info,
+        None,
+        AuthorityType::MintTokens,
+        authority_info,
+        swap_authority_signer_seeds,
+    )?;
+
+    SwapInfo::pack(SwapInfo::default(), &mut swap_info.data.borrow_mut())?;
+    let admin_lamports = admin_info.lamports();
+    **admin_info.lamports.borrow_mut() = admin_lamports
+        .checked_add(swap_info.lamports())
+        .ok_or(SwapError::CalculationFailure)?;
+    **swap_info.lamports.borrow_mut() = 0;
+
+    Ok(())
+}
+
+/// Register the oracle accounts of a mint
+#[inline(never)]
+fn set_oracle_feed(program_id: &Pubkey, accounts: &[AccountInfo]) -> ProgramResult {
+    let account_info_iter = &mut accounts.iter();
+    let config_info = next_account_info(account_info_iter)?;
+    let oracle_feed_info = next_account_info(account_info_iter)?;
+    let mint_info = next_account_info(account_info_iter)?;
+    let pyth_price_info = next_account_info(account_info_iter)?;
+    let serum_market_info = next_account_info(account_info_iter)?;
+    let admin_info = next_account_info(account_info_iter)?;
+    let rent = &Rent::from_account_info(next_account_info(account_info_iter)?)?;
+    let system_program_info = next_account_info(account_info_iter)?;
+
+    if config_info.owner != program_id {
+        return Err(SwapError::InvalidAccountOwner.into());
+    }
+
+    let config = ConfigInfo::unpack(&config_info.data.borrow())?;
+    has_role(&config, AdminRole::OracleManager, admin_info)?;
+
+    unpack_mint(mint_info, &spl_token::id())?;
+    let (oracle_feed_key, _) = find_oracle_feed_address(config_info.key, mint_info.key, program_id);
+    if *oracle_feed_info.key != oracle_feed_key {
+        return Err(SwapError::InvalidOracleFeed.into());
+    }
+
+    // Feeds not available for the mint are left to the default pubkey
+    let pyth_price = *pyth_price_info.key;
+    if pyth_price != Pubkey::default() && *pyth_price_info.owner != config.pyth_program_id {
+        return Err(SwapError::InvalidOracleFeed.into());
+    }
+    let serum_market = *serum_market_info.key;
+    if serum_market != Pubkey::default() {
+        utils::check_serum_program_id(
+            serum_market_info.owner,
+            &config.accepted_orderbook_program_id(),
+        )?;
+    }
+
+    if oracle_feed_info.owner != program_id {
+        create_oracle_feed_account(
+            program_id,
+            config_info.key,
+            mint_info.key,
+            oracle_feed_info,
+            admin_info,
+            system_program_info,
+            rent,
+        )?;
+    }
+
+    OracleFeed::pack(
+        OracleFeed {
+            is_initialized: true,
+            config_key: *config_info.key,
+            mint: *mint_info.key,
+            pyth_price,
+            serum_market,
+        },
+        &mut oracle_feed_info.data.borrow_mut(),
+    )
+}
+
+/// Set the relayer of the price cache of a pool
+#[inline(never)]
+fn set_price_cache(program_id: &Pubkey, accounts: &[AccountInfo]) -> ProgramResult {
+    let account_info_iter = &mut accounts.iter();
+    let config_info = next_account_info(account_info_iter)?;
+    let swap_info = next_account_info(account_info_iter)?;
+    let price_cache_info = next_account_info(account_info_iter)?;
+    let relayer_info = next_account_info(account_info_iter)?;
+    let admin_info = next_account_info(account_info_iter)?;
+    let rent = &Rent::from_account_info(next_account_info(account_info_iter)?)?;
+    let system_program_info = next_account_info(account_info_iter)?;
+
+    if config_info.owner != program_id || swap_info.owner != program_id {
+        return Err(SwapError::InvalidAccountOwner.into());
+    }
+
+    let config = ConfigInfo::unpack(&config_info.data.borrow())?;
+    has_role(&config, AdminRole::OracleManager, admin_info)?;
+    if !admin_info.is_signer {
+        return Err(ProgramError::MissingRequiredSignature);
+    }
+
+    let token_swap = SwapInfo::unpack(&swap_info.data.borrow())?;
+    utils::validate_swap_config_key(&token_swap, config_info.key)?;
+    utils::validate(
+        token_swap.swap_type == SwapType::Normal,
+        SwapError::IncorrectSwapType,
+    )?;
+
+    let (price_cache_key, bump_seed) = find_price_cache_address(swap_info.key, program_id);
+    if *price_cache_info.key != price_cache_key {
+        return Err(SwapError::InvalidPriceCache.into());
+    }
+    if *relayer_info.key == Pubkey::default() {
+        return Err(SwapError::InvalidInput.into());
+    }
+
+    if price_cache_info.owner != program_id {
+        create_price_cache_account(
+            program_id,
+            swap_info.key,
+            price_cache_info,
+            admin_info,
+            system_program_info,
+            rent,
+        )?;
+    }
+
+    // The price of the previous relayer is not trusted past the change
+    PriceCache::pack(
+        PriceCache {
+            is_initialized: true,
+            bump_seed,
+            swap_key: *swap_info.key,
+            relayer: *relayer_info.key,
+            pyth_a: token_swap.pyth_a,
+            pyth_b: token_swap.pyth_b,
+            ..PriceCache::default()
+        },
+        &mut price_cache_info.data.borrow_mut(),
+    )
+}
+
+/// Enable the oracle feed registry of the config
+#[inline(never)]
+fn enable_oracle_feed_registry(program_id: &Pubkey, accounts: &[AccountInfo]) -> ProgramResult {
+    let account_info_iter = &mut accounts.iter();
+    let config_info = next_account_info(account_info_iter)?;
+    let admin_info = next_account_info(account_info_iter)?;
+
+    if config_info.owner != program_id {
+        return Err(SwapError::InvalidAccountOwner.into());
+    }
+
+    let mut config = ConfigInfo::unpack(&config_info.data.borrow())?;
+    is_admin(&config.admin_key, admin_info)?;
+
+    config.is_oracle_feed_registry_enabled = true;
+    ConfigInfo::pack(config, &mut config_info.data.borrow_mut())?;
+    Ok(())
+}
+
+/// Convert the spl_token authority type index
+fn unpack_authority_type(authority_type: u8) -> Result<AuthorityType, ProgramError> {
+    match authority_type {
+        0 => Ok(AuthorityType::MintTokens),
+        1 => Ok(AuthorityType::FreezeAccount),
+        2 => Ok(AuthorityType::AccountOwner),
+        3 => Ok(AuthorityType::CloseAccount),
+        _ => Err(SwapError::InvalidInput.into()),
+    }
+}
+
+/// Set an authority of an account held by the pool authority
+#[inline(never)]
+fn set_pool_authority(
+    program_id: &Pubkey,
+    authority_type: u8,
+    accounts: &[AccountInfo],
+) -> ProgramResult {
+    let account_info_iter = &mut accounts.iter();
+    let config_info = next_account_info(account_info_iter)?;
+    let swap_info = next_account_info(account_info_iter)?;
+    let authority_info = next_account_info(account_info_iter)?;
+    let admin_info = next_account_info(account_info_iter)?;
+    let account_info = next_account_info(account_info_iter)?;
+    let new_authority_info = next_account_info(account_info_iter)?;
+    let token_program_info = next_account_info(account_info_iter)?;
+
+    if config_info.owner != program_id || swap_info.owner != program_id {
+        return Err(SwapError::InvalidAccountOwner.into());
+    }
+
+    let config = ConfigInfo::unpack(&config_info.data.borrow())?;
+    is_admin(&config.admin_key, admin_info)?;
+
+    let authority_type = unpack_authority_type(authority_type)?;
+
+    let token_swap = SwapInfo::unpack(&swap_info.data.borrow())?;
+    utils::validate_swap_config_key(&token_swap, config_info.key)?;
+
+    let swap_authority_signer_seeds = &[swap_info.key.as_ref(), &[token_swap.nonce]];
+    if *authority_info.key
+        != Pubkey::create_program_address(swap_authority_signer_seeds, program_id)?
+    {
+        return Err(SwapError::InvalidProgramAddress.into());
+    }
+
+    set_authority_signed(
+        token_program_info,
+        account_info,
+        Some(*new_authority_info.key),
+        authority_type,
+        authority_info,
+        swap_authority_signer_seeds,
+    )
+}
+
+/// Set an authority of an account held by the market authority
+#[inline(never)]
+fn set_market_authority(
+    program_id: &Pubkey,
+    authority_type: u8,
+    accounts: &[AccountInfo],
+) -> ProgramResult {
+    let account_info_iter = &mut accounts.iter();
+    let config_info = next_account_info(account_info_iter)?;
+    let market_authority_info = next_account_info(account_info_iter)?;
+    let admin_info = next_account_info(account_info_iter)?;
+    let account_info = next_account_info(account_info_iter)?;
+    let new_authority_info = next_account_info(account_info_iter)?;
+    let token_program_info = next_account_info(account_info_iter)?;
+
+    if config_info.owner != program_id {
+        return Err(SwapError::InvalidAccountOwner.into());
+    }
+
+    let config = ConfigInfo::unpack(&config_info.data.borrow())?;
+    is_admin(&config.admin_key, admin_info)?;
+
+    let authority_type = unpack_authority_type(authority_type)?;
+
+    let market_authority_signer_seeds = &[config_info.key.as_ref(), &[config.bump_seed]];
+    if *market_authority_info.key
+        != Pubkey::create_program_address(market_authority_signer_seeds, program_id)?
+    {
+        return Err(SwapError::InvalidProgramAddress.into());
+    }
+
+    set_authority_signed(
+        token_program_info,
+        account_info,
+        Some(*new_authority_info.key),
+        authority_type,
+        market_authority_info,
+        market_authority_signer_seeds,
+    )
+}
+
+/// Set the max admin instructions per epoch, only the guardian key can sign it so a
+/// compromised admin key cannot lift the limit
+#[inline(never)]
+fn set_admin_ops_limit(
+    program_id: &Pubkey,
+    max_admin_ops_per_epoch: u16,
+    accounts: &[AccountInfo],
+) -> ProgramResult {
+    let account_info_iter = &mut accounts.iter();
+    let config_info = next_account_info(account_info_iter)?;
+    let guardian_info = next_account_info(account_info_iter)?;
+
+    if config_info.owner != program_id {
+        return Err(SwapError::InvalidAccountOwner.into());
+    }
+
+    let mut config = ConfigInfo::unpack(&config_info.data.borrow())?;
+    if config.guardian_key == Pubkey::default() || config.guardian_key != *guardian_info.key {
+        return Err(SwapError::Unauthorized.into());
+    }
+    if !guardian_info.is_signer {
+        return Err(ProgramError::MissingRequiredSignature);
+    }
+
+    config.max_admin_ops_per_epoch = max_admin_ops_per_epoch;
+    config.admin_ops_epoch = Clock::get()?.epoch;
+    config.admin_ops_count = 0;
+    config.is_admin_ops_paused = false;
+    ConfigInfo::pack(config, &mut config_info.data.borrow_mut())
+}
+
+fn create_scratch_account(
+    program_id: &Pubkey,
+    index: u8,
+    accounts: &[AccountInfo],
+) -> ProgramResult {
+    let account_info_iter = &mut accounts.iter();
+    let config_info = next_account_info(account_info_iter)?;
+    let scratch_account_info = next_account_info(account_info_iter)?;
+    let scratch_token_info = next_account_info(account_info_iter)?;
+    let mint_info = next_account_info(account_info_iter)?;
+    let admin_info = next_account_info(account_info_iter)?;
+    let rent_info = next_account_info(account_info_iter)?;
+    let system_program_info = next_account_info(account_info_iter)?;
+    let token_program_info = next_account_info(account_info_iter)?;
+
+    if config_info.owner != program_id {
+        return Err(SwapError::InvalidAccountOwner.into());
+    }
+    let config = ConfigInfo::unpack(&config_info.data.borrow())?;
+    is_admin(&config.admin_key, admin_info)?;
+    unpack_mint(mint_info, token_program_info.key)?;
+
+    let bump_seed = create_scratch_accounts(
+        program_id,
+        config_info.key,
+        index,
+        scratch_account_info,
+        scratch_token_info,
+        mint_info,
+        admin_info,
+        rent_info,
+        system_program_info,
+        token_program_info,
+    )?;
+
+    ScratchAccount::pack(
+        ScratchAccount {
+            is_initialized: true,
+            bump_seed,
+            index,
+            config_key: *config_info.key,
+            mint: *mint_info.key,
+            token_account: *scratch_token_info.key,
+            ..ScratchAccount::default()
+        },
+        &mut scratch_account_info.data.borrow_mut(),
+    )
+}
+
+fn set_fee_discount_tiers(
+    program_id: &Pubkey,
+    tiers: [FeeDiscountTier; MAX_FEE_DISCOUNT_TIERS],
+    accounts: &[AccountInfo],
+) -> ProgramResult {
+    let account_info_iter = &mut accounts.iter();
+    let config_info = next_account_info(account_info_iter)?;
+    let fee_discount_tiers_info = next_account_info(account_info_iter)?;
+    let admin_info = next_account_info(account_info_iter)?;
+    let rent = &Rent::from_account_info(next_account_info(account_info_iter)?)?;
+    let system_program_info = next_account_info(account_info_iter)?;
+
+    if config_info.owner != program_id {
+        return Err(SwapError::InvalidAccountOwner.into());
+    }
+    let config = ConfigInfo::unpack(&config_info.data.borrow())?;
+    has_role(&config, AdminRole::FeeManager, admin_info)?;
+    utils::validate(
+        FeeDiscountTiers::validate_tiers(&tiers),
+        SwapError::InvalidFeeDiscount,
+    )?;
+
+    let (fee_discount_tiers_key, _) = find_fee_discount_tiers_address(config_info.key, program_id);
+    if *fee_discount_tiers_info.key != fee_discount_tiers_key {
+        return Err(SwapError::InvalidFeeDiscount.into());
+    }
+    if fee_discount_tiers_info.owner != program_id {
+        create_fee_discount_tiers_account(
+            program_id,
+            config_info.key,
+            fee_discount_tiers_info,
+            admin_info,
+            system_program_info,
+            rent,
+        )?;
+    }
+
+    FeeDiscountTiers::pack(
+        FeeDiscountTiers {
+            is_initialized: true,
+            config_key: *config_info.key,
+            tiers,
+        },
+        &mut fee_discount_tiers_info.data.borrow_mut(),
+    )
+}
+
+/// Copy the default fees and rewards of the config to the pools not pinned to custom values,
+/// or to every pool unpinning them
+#[inline(never)]
+fn sync_pool_from_config(
+    program_id: &Pubkey,
+    include_pinned: bool,
+    accounts: &[AccountInfo],
+) -> ProgramResult {
+    let account_info_iter = &mut accounts.iter();
+    let config_info = next_account_info(account_info_iter)?;
+    let admin_info = next_account_info(account_info_iter)?;
+
+    if config_info.owner != program_id {
+        return Err(SwapError::InvalidAccountOwner.into());
+    }
+
+    let config = ConfigInfo::unpack(&config_info.data.borrow())?;
+    has_role(&config, AdminRole::FeeManager, admin_info)?;
+
+    for swap_info in account_info_iter {
+        if swap_info.owner != program_id {
+            return Err(SwapError::InvalidAccountOwner.into());
+        }
+
+        let mut token_swap = SwapInfo::unpack(&swap_info.data.borrow())?;
+        utils::validate_swap_config_key(&token_swap, config_info.key)?;
+
+        if token_swap.fee_override && !include_pinned {
+            continue;
+        }
+        token_swap.fees = Fees::new(&config.fees);
+        token_swap.rewards = Rewards::new(&config.rewards);
+        token_swap.fee_override = false;
+        SwapInfo::pack(token_swap, &mut swap_info.data.borrow_mut())?;
+    }
+    Ok(())
+}
+
+/// Set the same new fees to every pool in the accounts, failing the whole instruction if one
+/// of the pools is invalid
+#[inline(never)]
+fn batch_set_fees(program_id: &Pubkey, new_fees: &Fees, accounts: &[AccountInfo]) -> ProgramResult {
+    let account_info_iter = &mut accounts.iter();
+    let config_info = next_account_info(account_info_iter)?;
+    let admin_info = next_account_info(account_info_iter)?;
+
+    if config_info.owner != program_id {
+        return Err(SwapError::InvalidAccountOwner.into());
+    }
+
+    let config = ConfigInfo::unpack(&config_info.data.borrow())?;
+    has_role(&config, AdminRole::FeeManager, admin_info)?;
+
+    if new_fees.treasury_fee_numerator > new_fees.treasury_fee_denominator {
+        return Err(SwapError::InvalidInput.into());
+    }
+
+    for swap_info in account_info_iter {
+        if swap_info.owner != program_id {
+            return Err(SwapError::InvalidAccountOwner.into());
+        }
+
+        let mut token_swap = SwapInfo::unpack(&swap_info.data.borrow())?;
+        utils::validate_swap_config_key(&token_swap, config_info.key)?;
+
+        token_swap.fees = Fees::new(new_fees);
+        token_swap.fee_override = true;
+        SwapInfo::pack(token_swap, &mut swap_info.data.borrow_mut())?;
+    }
+    Ok(())
+}
+
+/// Pause every pool in the accounts
+#[inline(never)]
+fn batch_pause(program_id: &Pubkey, accounts: &[AccountInfo]) -> ProgramResult {
+    let account_info_iter = &mut accounts.iter();
+    let config_info = next_account_info(account_info_iter)?;
+    let admin_info = next_account_info(account_info_iter)?;
+
+    if config_info.owner != program_id {
+        return Err(SwapError::InvalidAccountOwner.into());
+    }
+
+    let config = ConfigInfo::unpack(&config_info.data.borrow())?;
+    has_role(&config, AdminRole::Pauser, admin_info)?;
+
+    for swap_info in account_info_iter {
+        if swap_info.owner != program_id {
+            return Err(SwapError::InvalidAccountOwner.into());
+        }
+
+        let mut token_swap = SwapInfo::unpack(&swap_info.data.borrow())?;
+        utils::validate_swap_config_key(&token_swap, config_info.key)?;
+
+        token_swap.is_paused = true;
+        SwapInfo::pack(token_swap, &mut swap_info.data.borrow_mut())?;
+    }
+    Ok(())
+}
+
+/// Set the default fees and rewards of the config
+#[inline(never)]
+fn set_config_fees(
+    program_id: &Pubkey,
+    fees: &Fees,
+    rewards: &Rewards,
+    accounts: &[AccountInfo],
+) -> ProgramResult {
+    let account_info_iter = &mut accounts.iter();
+    let config_info = next_account_info(account_info_iter)?;
+    let admin_info = next_account_info(account_info_iter)?;
+
+    if config_info.owner != program_id {
+        return Err(SwapError::InvalidAccountOwner.into());
+    }
+
+    let mut config = ConfigInfo::unpack(&config_info.data.borrow())?;
+    has_role(&config, AdminRole::FeeManager, admin_info)?;
+
+    if fees.treasury_fee_numerator > fees.treasury_fee_denominator {
+        return Err(SwapError::InvalidInput.into());
+    }
+    utils::validate(rewards.decimals <= 10, SwapError::InvalidTokenDecimals)?;
+    utils::validate(rewards.referral_fee_bps <= 10_000, SwapError::InvalidInput)?;
+
+    config.fees = Fees::new(fees);
+    config.rewards = Rewards::new(rewards);
+    ConfigInfo::pack(config, &mut config_info.data.borrow_mut())?;
+    Ok(())
+}
+
+/// Turn on or off the protocol fee switch, setting the treasury owner when turned on
+#[inline(never)]
+fn set_protocol_fee_switch(
+    program_id: &Pubkey,
+    is_protocol_fee_enabled: bool,
+    accounts: &[AccountInfo],
+) -> ProgramResult {
+    let account_info_iter = &mut accounts.iter();
+    let config_info = next_account_info(account_info_iter)?;
+    let admin_info = next_account_info(account_info_iter)?;
+    let treasury_info = next_account_info(account_info_iter)?;
+
+    if config_info.owner != program_id {
+        return Err(SwapError::InvalidAccountOwner.into());
+    }
+
+    let mut config = ConfigInfo::unpack(&config_info.data.borrow())?;
+    is_admin(&config.admin_key, admin_info)?;
+
+    if is_protocol_fee_enabled {
+        utils::validate(
+            *treasury_info.key != Pubkey::default(),
+            SwapError::InvalidTreasury,
+        )?;
+        config.treasury_key = *treasury_info.key;
+    }
+    config.is_protocol_fee_enabled = is_protocol_fee_enabled;
+    ConfigInfo::pack(config, &mut config_info.data.borrow_mut())?;
+    Ok(())
+}
+
+/// Set the deviation threshold of the permissionless rebalance of the pool
+#[inline(never)]
+fn set_rebalance_threshold(
+    program_id: &Pubkey,
+    rebalance_threshold_bps: u16,
+    accounts: &[AccountInfo],
+) -> ProgramResult {
+    let account_info_iter = &mut accounts.iter();
+    let config_info = next_account_info(account_info_iter)?;
+    let swap_info = next_account_info(account_info_iter)?;
+    let admin_info = next_account_info(account_info_iter)?;
+
+    if config_info.owner != program_id || swap_info.owner != program_id {
+        return Err(SwapError::InvalidAccountOwner.into());
+    }
+
+    let config = ConfigInfo::unpack(&config_info.data.borrow())?;
+    has_role(&config, AdminRole::FeeManager, admin_info)?;
+
+    if rebalance_threshold_bps > 10_000 {
+        return Err(SwapError::InvalidInput.into());
+    }
+
+    let mut token_swap = SwapInfo::unpack(&swap_info.data.borrow())?;
+    utils::validate_swap_config_key(&token_swap, config_info.key)?;
+    utils::validate(
+        token_swap.swap_type == SwapType::Normal,
+        SwapError::IncorrectSwapType,
+    )?;
+
+    token_swap.rebalance_threshold_bps = rebalance_threshold_bps;
+    SwapInfo::pack(token_swap, &mut swap_info.data.borrow_mut())?;
+    Ok(())
+}
+
+/// Set the max price deviation and the window of the circuit breaker of the pool
+#[inline(never)]
+fn set_circuit_breaker(
+    program_id: &Pubkey,
+    max_deviation_bps: u16,
+    window_slots: u64,
+    accounts: &[AccountInfo],
+) -> ProgramResult {
+    let account_info_iter = &mut accounts.iter();
+    let config_info = next_account_info(account_info_iter)?;
+    let swap_info = next_account_info(account_info_iter)?;
+    let admin_info = next_account_info(account_info_iter)?;
+
+    if config_info.owner != program_id || swap_info.owner != program_id {
+        return Err(SwapError::InvalidAccountOwner.into());
+    }
+
+    let config = ConfigInfo::unpack(&config_info.data.borrow())?;
+    is_admin(&config.admin_key, admin_info)?;
+
+    if max_deviation_bps > 10_000 || (max_deviation_bps > 0 && window_slots == 0) {
+        return Err(SwapError::InvalidInput.into());
+    }
+
+    let mut token_swap = SwapInfo::unpack(&swap_info.data.borrow())?;
+    utils::validate_swap_config_key(&token_swap, config_info.key)?;
+
+    token_swap.circuit_breaker_bps = max_deviation_bps;
+    token_swap.circuit_breaker_window_slots = window_slots;
+    // The next swap starts a window on the new settings
+    token_swap.circuit_breaker_price = Decimal::zero();
+    SwapInfo::pack(token_swap, &mut swap_info.data.borrow_mut())?;
+    Ok(())
+}
+
+/// Set the deposit cap on the total supply of the pool tokens
+#[inline(never)]
+fn set_deposit_cap(
+    program_id: &Pubkey,
+    max_total_supply: u64,
+    accounts: &[AccountInfo],
+) -> ProgramResult {
+    let account_info_iter = &mut accounts.iter();
+    let config_info = next_account_info(account_info_iter)?;
+    let swap_info = next_account_info(account_info_iter)?;
+    let admin_info = next_account_info(account_info_iter)?;
+
+    if config_info.owner != program_id || swap_info.owner != program_id {
+        return Err(SwapError::InvalidAccountOwner.into());
+    }
+
+    let config = ConfigInfo::unpack(&config_info.data.borrow())?;
+    has_role(&config, AdminRole::FeeManager, admin_info)?;
+
+    let mut token_swap = SwapInfo::unpack(&swap_info.data.borrow())?;
+    utils::validate_swap_config_key(&token_swap, config_info.key)?;
+
+    token_swap.max_total_supply = max_total_supply;
+    SwapInfo::pack(token_swap, &mut swap_info.data.borrow_mut())?;
+    Ok(())
+}
+
+/// Turn on or off the whitelist gating the swaps and deposits of the pool
+#[inline(never)]
+fn set_whitelist_enabled(
+    program_id: &Pubkey,
+    whitelist_enabled: bool,
+    accounts: &[AccountInfo],
+) -> ProgramResult {
+    let account_info_iter = &mut accounts.iter();
+    let config_info = next_account_info(account_info_iter)?;
+    let swap_info = next_account_info(account_info_iter)?;
+    let admin_info = next_account_info(account_info_iter)?;
+
+    if config_info.owner != program_id || swap_info.owner != program_id {
+        return Err(SwapError::InvalidAccountOwner.into());
+    }
+
+    let config = ConfigInfo::unpack(&config_info.data.borrow())?;
+    is_admin(&config.admin_key, admin_info)?;
+
+    let mut token_swap = SwapInfo::unpack(&swap_info.data.borrow())?;
+    utils::validate_swap_config_key(&token_swap, config_info.key)?;
+
+    token_swap.whitelist_enabled = whitelist_enabled;
+    SwapInfo::pack(token_swap, &mut swap_info.data.borrow_mut())?;
+    Ok(())
+}
+
+/// Create the whitelist entry of a user on the pool
+#[inline(never)]
+fn add_whitelist_entry(program_id: &Pubkey, accounts: &[AccountInfo]) -> ProgramResult {
+    let account_info_iter = &mut accounts.iter();
+    let config_info = next_account_info(account_info_iter)?;
+    let swap_info = next_account_info(account_info_iter)?;
+    let whitelist_entry_info = next_account_info(account_info_iter)?;
+    let user_info = next_account_info(account_info_iter)?;
+    let admin_info = next_account_info(account_info_iter)?;
+    let rent = &Rent::from_account_info(next_account_info(account_info_iter)?)?;
+    let system_program_info = next_account_info(account_info_iter)?;
+
+    if config_info.owner != program_id || swap_info.owner != program_id {
+        return Err(SwapError::InvalidAccountOwner.into());
+    }
+
+    let config = ConfigInfo::unpack(&config_info.data.borrow())?;
+    is_admin(&config.admin_key, admin_info)?;
+
+    let token_swap = SwapInfo::unpack(&swap_info.data.borrow())?;
+    utils::validate_swap_config_key(&token_swap, config_info.key)?;
+
+    let (whitelist_entry_key, bump_seed) =
+        find_whitelist_entry_address(swap_info.key, user_info.key, program_id);
+    if *whitelist_entry_info.key != whitelist_entry_key {
+        return Err(SwapError::InvalidWhitelistEntry.into());
+    }
+
+    if whitelist_entry_info.owner != program_id {
+        create_whitelist_entry_account(
+            program_id,
+            swap_info.key,
+            user_info.key,
+            whitelist_entry_info,
+            admin_info,
+            system_program_info,
+            rent,
+        )?;
+    }
+
+    WhitelistEntry::pack(
+        WhitelistEntry {
+            is_initialized: true,
+            bump_seed,
+            swap_key: *swap_info.key,
+            user: *user_info.key,
+        },
+        &mut whitelist_entry_info.data.borrow_mut(),
+    )
+}
+
+/// Close the whitelist entry of a user on the pool, the rent goes back to the admin
+#[inline(never)]
+fn remove_whitelist_entry(program_id: &Pubkey, accounts: &[AccountInfo]) -> ProgramResult {
+    let account_info_iter = &mut accounts.iter();
+    let config_info = next_account_info(account_info_iter)?;
+    let swap_info = next_account_info(account_info_iter)?;
+    let whitelist_entry_info = next_account_info(account_info_iter)?;
+    let admin_info = next_account_info(account_info_iter)?;
+
+    if config_info.owner != program_id
+        || swap_info.owner != program_id
+        || whitelist_entry_info.owner != program_id
+    {
+        return Err(SwapError::InvalidAccountOwner.into());
+    }
+
+    let config = ConfigInfo::unpack(&config_info.data.borrow())?;
+    is_admin(&config.admin_key, admin_info)?;
+
+    let token_swap = SwapInfo::unpack(&swap_info.data.borrow())?;
+    utils::validate_swap_config_key(&token_swap, config_info.key)?;
+
+    let whitelist_entry = WhitelistEntry::unpack(&whitelist_entry_info.data.borrow())?;
+    utils::validate(
+        whitelist_entry.swap_key == *swap_info.key,
+        SwapError::InvalidWhitelistEntry,
+    )?;
+
+    WhitelistEntry::pack(
+        WhitelistEntry::default(),
+        &mut whitelist_entry_info.data.borrow_mut(),
+    )?;
+    let admin_lamports = admin_info.lamports();
+    **admin_info.lamports.borrow_mut() = admin_lamports
+        .checked_add(whitelist_entry_info.lamports())
+        .ok_or(SwapError::CalculationFailure)?;
+    **whitelist_entry_info.lamports.borrow_mut() = 0;
+
+    Ok(())
+}
+
+/// Upgrade a pool to the current layout version, a legacy pool is reallocated to the current
+/// length with the admin paying the rent of the growth
+#[inline(never)]
+fn migrate_swap_info(program_id: &Pubkey, accounts: &[AccountInfo]) -> ProgramResult {
+    let account_info_iter = &mut accounts.iter();
+    let config_info = next_account_info(account_info_iter)?;
+    let swap_info = next_account_info(account_info_iter)?;
+    let admin_info = next_account_info(account_info_iter)?;
+    let rent = &Rent::from_account_info(next_account_info(account_info_iter)?)?;
+    let system_program_info = next_account_info(account_info_iter)?;
+
+    if config_info.owner != program_id || swap_info.owner != program_id {
+        return Err(SwapError::InvalidAccountOwner.into());
+    }
+
+    let config = ConfigInfo::unpack(&config_info.data.borrow())?;
+    is_admin(&config.admin_key, admin_info)?;
+
+    let mut token_swap = SwapInfo::unpack_any(&swap_info.data.borrow())?;
+    utils::validate_swap_config_key(&token_swap, config_info.key)?;
+
+    grow_account(
+        swap_info,
+        SwapInfo::LEN,
+        admin_info,
+        rent,
+        system_program_info,
+    )?;
+
+    // The fields added since the legacy layout are zero, which leaves them off
+    token_swap.version = SWAP_INFO_VERSION;
+    SwapInfo::pack(token_swap, &mut swap_info.data.borrow_mut())?;
+    Ok(())
+}
+
+#[inline(never)]
+fn migrate_config_info(program_id: &Pubkey, accounts: &[AccountInfo]) -> ProgramResult {
+    let account_info_iter = &mut accounts.iter();
+    let config_info = next_account_info(account_info_iter)?;
+    let admin_info = next_account_info(account_info_iter)?;
+    let rent = &Rent::from_account_info(next_account_info(account_info_iter)?)?;
+    let system_program_info = next_account_info(account_info_iter)?;
+
+    if config_info.owner != program_id {
+        return Err(SwapError::InvalidAccountOwner.into());
+    }
+
+    let config = ConfigInfo::unpack_any(&config_info.data.borrow())?;
+    is_admin(&config.admin_key, admin_info)?;
+
+    grow_account(
+        config_info,
+        ConfigInfo::LEN,
+        admin_info,
+        rent,
+        system_program_info,
+    )?;
+    ConfigInfo::pack(config, &mut config_info.data.borrow_mut())?;
+    Ok(())
+}
+
+#[inline(never)]
+fn migrate_farm_info(program_id: &Pubkey, accounts: &[AccountInfo]) -> ProgramResult {
+    let account_info_iter = &mut accounts.iter();
+    let config_info = next_account_info(account_info_iter)?;
+    let farm_pool_info = next_account_info(account_info_iter)?;
+    let admin_info = next_account_info(account_info_iter)?;
+    let rent = &Rent::from_account_info(next_account_info(account_info_iter)?)?;
+    let system_program_info = next_account_info(account_info_iter)?;
+
+    if config_info.owner != program_id || farm_pool_info.owner != program_id {
+        return Err(SwapError::InvalidAccountOwner.into());
+    }
+
+    let config = ConfigInfo::unpack(&config_info.data.borrow())?;
+    is_admin(&config.admin_key, admin_info)?;
+
+    let farm_pool = FarmInfo::unpack_any(&farm_pool_info.data.borrow())?;
+    utils::validate_farm_config_key(&farm_pool, config_info.key)?;
+
+    grow_account(
+        farm_pool_info,
+        FarmInfo::LEN,
+        admin_info,
+        rent,
+        system_program_info,
+    )?;
+    FarmInfo::pack(farm_pool, &mut farm_pool_info.data.borrow_mut())?;
+    Ok(())
+}
+
+/// Reallocate a program account shorter than the current layout, the payer topping up the rent
+fn grow_account<'a>(
+    account_info: &AccountInfo<'a>,
+    len: usize,
+    payer_info: &AccountInfo<'a>,
+    rent: &Rent,
+    system_program_info: &AccountInfo<'a>,
+) -> ProgramResult {
+    if account_info.data_len() >= len {
+        return Ok(());
+    }
+    utils::validate(
+        system_program::check_id(system_program_info.key),
+        SwapError::InvalidInput,
+    )?;
+    let lamports = rent
+        .minimum_balance(len)
+        .saturating_sub(account_info.lamports());
+    if lamports > 0 {
+        invoke(
+            &system_instruction::transfer(payer_info.key, account_info.key, lamports),
+            &[
+                payer_info.clone(),
+                account_info.clone(),
+                system_program_info.clone(),
+            ],
+        )?;
+    }
+    account_info.realloc(len, true)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::{
+        adapter::SPL_STAKE_POOL_PROGRAM_ID,
+        pyth::{self, PYTH_PROGRAM_ID},
+        OPENBOOK_DEX_PROGRAM_ID, SERUM_DEX_V3_PROGRAM_ID,
+    };
+    use solana_program::sysvar::Sysvar;
+    use spl_token::{
+        self,
+        state::{Account, AccountState, Mint},
+    };
+    use std::{mem, str::FromStr};
+
+    #[test]
+    fn test_is_admin() {
+        let admin_key = Pubkey::new_unique();
+        let owner_key = Pubkey::new_unique();
+        let wrong_key = Pubkey::new_unique();
+        let mut lamports = 0u64;
+        let mut data = [0u8];
+        let mut test_admin_account = AccountInfo::new(
+            &admin_key,
+            false,
+            false,
+            &mut lamports,
+            &mut data,
+            &owner_key,
+            true,
+            1u64,
+        );
+
+        assert_eq!(
+            is_admin(&wrong_key, &test_admin_account),
+            Err(ProgramError::from(SwapError::Unauthorized))
+        );
+        assert_eq!(
+            is_admin(&admin_key, &test_admin_account),
+            Err(ProgramError::MissingRequiredSignature)
+        );
+
+        let mut lamports_ = 0u64;
+        let mut data_ = [0u8];
+        test_admin_account = AccountInfo::new(
+            &admin_key,
+            true,
+            false,
+            &mut lamports_,
+            &mut data_,
+            &owner_key,
+            true,
+            1u64,
+        );
+        assert!(is_admin(&admin_key, &test_admin_account).is_ok());
     }
 
     fn get_initialize_result(option: u8) -> ProgramResult {
         let program_id = Pubkey::new_unique();
         let config_key = Pubkey::new_unique();
-        let market_authority_key = if option == 3u8 {
-            Pubkey::new_unique()
-        } else {
-            Pubkey::find_program_address(&[config_key.as_ref()], &program_id).0
+        let market_authority_key = if option == 3u8 {
+            Pubkey::new_unique()
+        } else {
+            Pubkey::find_program_address(&[config_key.as_ref()], &program_id).0
+        };
+
+        let market_authority_owner = Pubkey::new_unique();
+        let rent_key = solana_program::sysvar::rent::id();
+
+        let deltafi_mint_key = Pubkey::new_unique();
+        let deltafi_token_key = Pubkey::new_unique();
+        let admin_key = Pubkey::new_unique();
+        let token_program_key = if option == 7u8 {
+            Pubkey::new_unique()
+        } else {
+            spl_token::id()
+        };
+
+        let pyth_program_key = if option == 6u8 {
+            Pubkey::new_unique()
+        } else {
+            Pubkey::from_str(PYTH_PROGRAM_ID).unwrap()
+        };
+
+        let fees = Fees {
+            ..Default::default()
+        };
+
+        let rewards = Rewards {
+            ..Default::default()
+        };
+
+        let rent_val = Rent {
+            lamports_per_byte_year: 200_000u64,
+            exemption_threshold: 60f64,
+            burn_percent: 10u8,
+        };
+
+        let deltafi_mint = Mint {
+            mint_authority: solana_program::program_option::COption::Some(Pubkey::new_unique()),
+            supply: 0u64,
+            decimals: 0u8,
+            is_initialized: true,
+            freeze_authority: solana_program::program_option::COption::Some(Pubkey::new_unique()),
+        };
+
+        let mut config_lamport = 9_500_000_000u64;
+        let config_initial = ConfigInfo {
+            version: 0u8,
+            bump_seed: 0u8,
+            admin_key,
+            deltafi_mint: deltafi_mint_key,
+            pyth_program_id: pyth_program_key,
+            fees: fees.clone(),
+            rewards: rewards.clone(),
+            deltafi_token: deltafi_token_key,
+            ..ConfigInfo::default()
+        };
+        let mut config_data = [0u8; ConfigInfo::LEN];
+        config_initial.pack_into_slice(&mut config_data);
+        let config_info = AccountInfo::new(
+            &config_key,
+            true,
+            false,
+            &mut config_lamport,
+            &mut config_data,
+            if option == 1u8 {
+                &admin_key
+            } else {
+                &program_id
+            },
+            false,
+            0u64,
+        );
+
+        let mut rent_lamport = 2_000_000_000u64;
+        let mut rent_data = [0u8; mem::size_of::<Rent>()];
+        let mut rent_info = AccountInfo::new(
+            &rent_key,
+            false,
+            false,
+            &mut rent_lamport,
+            &mut rent_data,
+            &program_id,
+            false,
+            0u64,
+        );
+        assert!(rent_val.to_account_info(&mut rent_info).is_some());
+
+        let mut admin_lamports = 0u64;
+        let mut admin_data = [0u8];
+        let admin_info = AccountInfo::new(
+            &admin_key,
+            option != 2u8,
+            false,
+            &mut admin_lamports,
+            &mut admin_data,
+            &market_authority_key,
+            false,
+            0u64,
+        );
+
+        let mut market_authority_lamports = 0u64;
+        let mut market_authority_data = [0u8];
+        let market_authority_info = AccountInfo::new(
+            &market_authority_key,
+            false,
+            false,
+            &mut market_authority_lamports,
+            &mut market_authority_data,
+            &market_authority_owner,
+            false,
+            0u64,
+        );
+
+        let mut packed_mint_data = [0u8; Mint::LEN];
+        assert!(Mint::pack(deltafi_mint, &mut packed_mint_data).is_ok());
+
+        let mut mint_lamports = 0u64;
+        let mint_info = AccountInfo::new(
+            &deltafi_mint_key,
+            false,
+            false,
+            &mut mint_lamports,
+            &mut packed_mint_data,
+            &token_program_key,
+            false,
+            0u64,
+        );
+
+        let mut token_program_lamports = 0u64;
+        let mut token_program_data = [0u8];
+        let token_program_info = AccountInfo::new(
+            &token_program_key,
+            true,
+            false,
+            &mut token_program_lamports,
+            &mut token_program_data,
+            &admin_key,
+            false,
+            0u64,
+        );
+
+        let mut pyth_program_lamports = 0u64;
+        let mut pyth_program_data = [0u8];
+        let pyth_program_info = AccountInfo::new(
+            &pyth_program_key,
+            true,
+            false,
+            &mut pyth_program_lamports,
+            &mut pyth_program_data,
+            &admin_key,
+            false,
+            0u64,
+        );
+
+        let mut token_lamports = 0u64;
+        let mut deltafi_token_account_data = [0u8; 165];
+        let deltafi_token_account = spl_token::state::Account {
+            mint: if option == 4u8 {
+                Pubkey::new_unique()
+            } else {
+                deltafi_mint_key
+            },
+            owner: if option == 5u8 {
+                Pubkey::new_unique()
+            } else {
+                market_authority_key
+            },
+            amount: 0,
+            delegate: solana_program::program_option::COption::None,
+            state: spl_token::state::AccountState::Initialized,
+            is_native: solana_program::program_option::COption::None,
+            delegated_amount: 0,
+            close_authority: solana_program::program_option::COption::None,
+        };
+        deltafi_token_account.pack_into_slice(&mut deltafi_token_account_data);
+        let deltafi_token_info = AccountInfo::new(
+            &deltafi_token_key,
+            false,
+            false,
+            &mut token_lamports,
+            &mut deltafi_token_account_data,
+            &token_program_key,
+            false,
+            0u64,
+        );
+
+        initialize(
+            &program_id,
+            &fees,
+            &rewards,
+            &[
+                config_info,
+                market_authority_info,
+                mint_info,
+                admin_info,
+                rent_info,
+                token_program_info,
+                pyth_program_info,
+                deltafi_token_info,
+            ],
+        )
+    }
+
+    #[test]
+    fn test_initialize() {
+        assert_eq!(get_initialize_result(0u8), Ok(()));
+        assert_eq!(
+            get_initialize_result(1u8),
+            Err(ProgramError::from(SwapError::InvalidAccountOwner))
+        );
+        assert_eq!(
+            get_initialize_result(2u8),
+            Err(ProgramError::MissingRequiredSignature)
+        );
+        assert_eq!(
+            get_initialize_result(3u8),
+            Err(ProgramError::from(SwapError::InvalidProgramAddress))
+        );
+        assert_eq!(
+            get_initialize_result(4u8),
+            Err(ProgramError::from(SwapError::IncorrectMint))
+        );
+        assert_eq!(
+            get_initialize_result(5u8),
+            Err(ProgramError::from(SwapError::InvalidOwner))
+        );
+        assert_eq!(
+            get_initialize_result(6u8),
+            Err(ProgramError::from(SwapError::InvalidPythProgramId))
+        );
+        assert_eq!(
+            get_initialize_result(7u8),
+            Err(ProgramError::IncorrectProgramId)
+        );
+    }
+
+    fn get_pause_result(option: u8) -> ProgramResult {
+        let mut accounts = Vec::new();
+        let program_id = Pubkey::new_unique();
+        let config_key = Pubkey::new_unique();
+        let swap_key = Pubkey::new_unique();
+        let admin_key = Pubkey::new_unique();
+
+        let config = ConfigInfo {
+            version: 1u8,
+            bump_seed: 0u8,
+            admin_key: if option == 3u8 || option >= 7u8 {
+                config_key
+            } else {
+                admin_key
+            },
+            deltafi_mint: Pubkey::new_unique(),
+            pyth_program_id: Pubkey::new_unique(),
+            fees: Fees {
+                ..Default::default()
+            },
+            rewards: Rewards {
+                ..Default::default()
+            },
+            deltafi_token: Pubkey::new_unique(),
+            pauser_key: if option == 7u8 {
+                admin_key
+            } else {
+                Pubkey::default()
+            },
+            fee_manager_key: if option == 8u8 {
+                admin_key
+            } else {
+                Pubkey::default()
+            },
+            ..ConfigInfo::default()
+        };
+
+        let mut swap = SwapInfo {
+            ..Default::default()
+        };
+        if option == 6u8 {
+            swap.config_key = swap_key;
+        } else {
+            swap.config_key = config_key;
+        }
+        if option == 5u8 {
+            swap.is_initialized = false;
+        } else {
+            swap.is_initialized = true;
+        }
+
+        let mut lamports = 0u64;
+        let mut config_data = [0u8; ConfigInfo::LEN];
+        config.pack_into_slice(&mut config_data);
+        accounts.push(AccountInfo::new(
+            &config_key,
+            false,
+            false,
+            &mut lamports,
+            &mut config_data,
+            if option == 1u8 {
+                &config_key
+            } else {
+                &program_id
+            },
+            false,
+            0u64,
+        ));
+
+        let mut lamports = 0u64;
+        let mut swap_data = [0u8; SwapInfo::LEN];
+        swap.pack_into_slice(&mut swap_data);
+        accounts.push(AccountInfo::new(
+            &swap_key,
+            false,
+            false,
+            &mut lamports,
+            &mut swap_data,
+            if option == 2u8 {
+                &swap_key
+            } else {
+                &program_id
+            },
+            false,
+            0u64,
+        ));
+
+        let mut lamports = 0u64;
+        let mut admin_data = [0u8];
+        accounts.push(AccountInfo::new(
+            &admin_key,
+            option != 4u8,
+            false,
+            &mut lamports,
+            &mut admin_data,
+            &program_id,
+            false,
+            0u64,
+        ));
+
+        pause(&program_id, &accounts[..])
+    }
+
+    #[test]
+    fn test_pause() {
+        assert!(get_pause_result(0u8).is_ok());
+        assert_eq!(
+            get_pause_result(1u8),
+            Err(ProgramError::from(SwapError::InvalidAccountOwner))
+        );
+        assert_eq!(
+            get_pause_result(2u8),
+            Err(ProgramError::from(SwapError::InvalidAccountOwner))
+        );
+        assert_eq!(
+            get_pause_result(3u8),
+            Err(ProgramError::from(SwapError::Unauthorized))
+        );
+        assert_eq!(
+            get_pause_result(4u8),
+            Err(ProgramError::MissingRequiredSignature)
+        );
+        assert_eq!(
+            get_pause_result(5u8),
+            Err(ProgramError::UninitializedAccount)
+        );
+        assert_eq!(
+            get_pause_result(6u8),
+            Err(ProgramError::from(SwapError::InvalidMarketConfig))
+        );
+        assert!(get_pause_result(7u8).is_ok());
+        assert_eq!(
+            get_pause_result(8u8),
+            Err(ProgramError::from(SwapError::Unauthorized))
+        );
+    }
+
+    fn get_set_pause_flags_result(pause_flags: u8, option: u8) -> (ProgramResult, SwapInfo) {
+        let program_id = Pubkey::new_unique();
+        let config_key = Pubkey::new_unique();
+        let swap_key = Pubkey::new_unique();
+        let admin_key = Pubkey::new_unique();
+        let pauser_key = Pubkey::new_unique();
+
+        let config = ConfigInfo {
+            version: 1u8,
+            admin_key,
+            pauser_key,
+            ..ConfigInfo::default()
+        };
+
+        let mut config_lamports = 0u64;
+        let mut config_data = [0u8; ConfigInfo::LEN];
+        config.pack_into_slice(&mut config_data);
+        let config_info = AccountInfo::new(
+            &config_key,
+            false,
+            false,
+            &mut config_lamports,
+            &mut config_data,
+            &program_id,
+            false,
+            0u64,
+        );
+
+        let swap = SwapInfo {
+            is_initialized: true,
+            config_key,
+            pause_withdraw: true,
+            ..SwapInfo::default()
+        };
+        let mut swap_lamports = 0u64;
+        let mut swap_data = [0u8; SwapInfo::LEN];
+        swap.pack_into_slice(&mut swap_data);
+        let swap_info = AccountInfo::new(
+            &swap_key,
+            false,
+            true,
+            &mut swap_lamports,
+            &mut swap_data,
+            &program_id,
+            false,
+            0u64,
+        );
+
+        let signer_key = match option {
+            1u8 => pauser_key,
+            2u8 => Pubkey::new_unique(),
+            _ => admin_key,
+        };
+        let mut admin_lamports = 0u64;
+        let mut admin_data = [0u8];
+        let admin_info = AccountInfo::new(
+            &signer_key,
+            true,
+            false,
+            &mut admin_lamports,
+            &mut admin_data,
+            &program_id,
+            false,
+            0u64,
+        );
+
+        let accounts = [config_info, swap_info, admin_info];
+        let result = set_pause_flags(&program_id, pause_flags, &accounts[..]);
+        let swap = SwapInfo::unpack(&accounts[1].data.borrow()).unwrap();
+        (result, swap)
+    }
+
+    #[test]
+    fn test_set_pause_flags() {
+        let (result, swap) =
+            get_set_pause_flags_result((PauseFlag::SWAP | PauseFlag::DEPOSIT).bits(), 0u8);
+        assert!(result.is_ok());
+        assert!(swap.pause_swap && swap.pause_deposit && !swap.pause_withdraw);
+        assert!(!swap.is_paused);
+
+        let (result, swap) = get_set_pause_flags_result(PauseFlag::WITHDRAW.bits(), 1u8);
+        assert!(result.is_ok());
+        assert!(!swap.pause_swap && !swap.pause_deposit && swap.pause_withdraw);
+
+        let (result, swap) = get_set_pause_flags_result(0u8, 1u8);
+        assert!(result.is_ok());
+        assert!(!swap.pause_swap && !swap.pause_deposit && !swap.pause_withdraw);
+
+        let (result, swap) = get_set_pause_flags_result(PauseFlag::SWAP.bits(), 2u8);
+        assert_eq!(result, Err(ProgramError::from(SwapError::Unauthorized)));
+        assert!(!swap.pause_swap && swap.pause_withdraw);
+
+        assert_eq!(
+            get_set_pause_flags_result(0b1000, 0u8).0,
+            Err(ProgramError::from(SwapError::InvalidInput))
+        );
+    }
+
+    fn get_set_protocol_fee_result(
+        numerator: u64,
+        denominator: u64,
+        option: u8,
+    ) -> (ProgramResult, SwapInfo) {
+        let program_id = Pubkey::new_unique();
+        let config_key = Pubkey::new_unique();
+        let swap_key = Pubkey::new_unique();
+        let admin_key = Pubkey::new_unique();
+        let fee_manager_key = Pubkey::new_unique();
+
+        let config = ConfigInfo {
+            version: 1u8,
+            admin_key,
+            fee_manager_key,
+            ..ConfigInfo::default()
+        };
+
+        let mut config_lamports = 0u64;
+        let mut config_data = [0u8; ConfigInfo::LEN];
+        config.pack_into_slice(&mut config_data);
+        let config_info = AccountInfo::new(
+            &config_key,
+            false,
+            false,
+            &mut config_lamports,
+            &mut config_data,
+            &program_id,
+            false,
+            0u64,
+        );
+
+        let swap = SwapInfo {
+            is_initialized: true,
+            config_key,
+            ..SwapInfo::default()
+        };
+        let mut swap_lamports = 0u64;
+        let mut swap_data = [0u8; SwapInfo::LEN];
+        swap.pack_into_slice(&mut swap_data);
+        let swap_info = AccountInfo::new(
+            &swap_key,
+            false,
+            true,
+            &mut swap_lamports,
+            &mut swap_data,
+            &program_id,
+            false,
+            0u64,
+        );
+
+        let signer_key = match option {
+            1u8 => fee_manager_key,
+            2u8 => Pubkey::new_unique(),
+            _ => admin_key,
+        };
+        let mut admin_lamports = 0u64;
+        let mut admin_data = [0u8];
+        let admin_info = AccountInfo::new(
+            &signer_key,
+            true,
+            false,
+            &mut admin_lamports,
+            &mut admin_data,
+            &program_id,
+            false,
+            0u64,
+        );
+
+        let accounts = [config_info, swap_info, admin_info];
+        let result = set_protocol_fee(&program_id, numerator, denominator, &accounts[..]);
+        let swap = SwapInfo::unpack(&accounts[1].data.borrow()).unwrap();
+        (result, swap)
+    }
+
+    #[test]
+    fn test_set_protocol_fee() {
+        let (result, swap) = get_set_protocol_fee_result(1, 5, 0u8);
+        assert!(result.is_ok());
+        assert_eq!(swap.pool_state.protocol_fee_numerator, 1);
+        assert_eq!(swap.pool_state.protocol_fee_denominator, 5);
+
+        let (result, swap) = get_set_protocol_fee_result(0, 0, 1u8);
+        assert!(result.is_ok());
+        assert_eq!(swap.pool_state.protocol_fee(1_000), Ok(0));
+
+        assert_eq!(
+            get_set_protocol_fee_result(1, 5, 2u8).0,
+            Err(ProgramError::from(SwapError::Unauthorized))
+        );
+        assert_eq!(
+            get_set_protocol_fee_result(6, 5, 0u8).0,
+            Err(ProgramError::from(SwapError::InvalidInput))
+        );
+        assert_eq!(
+            get_set_protocol_fee_result(1, 0, 0u8).0,
+            Err(ProgramError::from(SwapError::InvalidInput))
+        );
+    }
+
+    fn get_set_protocol_fee_switch_result(
+        is_protocol_fee_enabled: bool,
+        option: u8,
+    ) -> (ProgramResult, ConfigInfo, Pubkey) {
+        let program_id = Pubkey::new_unique();
+        let config_key = Pubkey::new_unique();
+        let admin_key = Pubkey::new_unique();
+        let fee_manager_key = Pubkey::new_unique();
+        let old_treasury_key = Pubkey::new_unique();
+        let treasury_key = if option == 2u8 {
+            Pubkey::default()
+        } else {
+            Pubkey::new_unique()
+        };
+
+        let config = ConfigInfo {
+            version: 1u8,
+            admin_key,
+            fee_manager_key,
+            is_protocol_fee_enabled: true,
+            treasury_key: old_treasury_key,
+            ..ConfigInfo::default()
+        };
+
+        let mut config_lamports = 0u64;
+        let mut config_data = [0u8; ConfigInfo::LEN];
+        config.pack_into_slice(&mut config_data);
+        let config_info = AccountInfo::new(
+            &config_key,
+            false,
+            true,
+            &mut config_lamports,
+            &mut config_data,
+            &program_id,
+            false,
+            0u64,
+        );
+
+        let signer_key = if option == 1u8 {
+            fee_manager_key
+        } else {
+            admin_key
+        };
+        let mut admin_lamports = 0u64;
+        let mut admin_data = [0u8];
+        let admin_info = AccountInfo::new(
+            &signer_key,
+            true,
+            false,
+            &mut admin_lamports,
+            &mut admin_data,
+            &program_id,
+            false,
+            0u64,
+        );
+
+        let mut treasury_lamports = 0u64;
+        let mut treasury_data = [0u8];
+        let treasury_info = AccountInfo::new(
+            &treasury_key,
+            false,
+            false,
+            &mut treasury_lamports,
+            &mut treasury_data,
+            &program_id,
+            false,
+            0u64,
+        );
+
+        let accounts = [config_info, admin_info, treasury_info];
+        let result = set_protocol_fee_switch(&program_id, is_protocol_fee_enabled, &accounts[..]);
+        let config = ConfigInfo::unpack(&accounts[0].data.borrow()).unwrap();
+        (result, config, old_treasury_key)
+    }
+
+    #[test]
+    fn test_set_protocol_fee_switch() {
+        // the treasury owner is kept while the switch is off
+        let (result, config, old_treasury_key) = get_set_protocol_fee_switch_result(false, 0u8);
+        assert!(result.is_ok());
+        assert!(!config.is_protocol_fee_enabled);
+        assert_eq!(config.treasury_key, old_treasury_key);
+
+        let (result, config, old_treasury_key) = get_set_protocol_fee_switch_result(true, 0u8);
+        assert!(result.is_ok());
+        assert!(config.is_protocol_fee_enabled);
+        assert_ne!(config.treasury_key, old_treasury_key);
+
+        assert_eq!(
+            get_set_protocol_fee_switch_result(true, 1u8).0,
+            Err(ProgramError::from(SwapError::Unauthorized))
+        );
+        assert_eq!(
+            get_set_protocol_fee_switch_result(true, 2u8).0,
+            Err(ProgramError::from(SwapError::InvalidTreasury))
+        );
+    }
+
+    #[test]
+    fn test_unpack_authority_type() {
+        assert_eq!(unpack_authority_type(0), Ok(AuthorityType::MintTokens));
+        assert_eq!(unpack_authority_type(1), Ok(AuthorityType::FreezeAccount));
+        assert_eq!(unpack_authority_type(2), Ok(AuthorityType::AccountOwner));
+        assert_eq!(unpack_authority_type(3), Ok(AuthorityType::CloseAccount));
+        assert_eq!(
+            unpack_authority_type(4),
+            Err(ProgramError::from(SwapError::InvalidInput))
+        );
+    }
+
+    fn get_close_pool_result(option: u8) -> ProgramResult {
+        let program_id = Pubkey::new_unique();
+        let config_key = Pubkey::new_unique();
+        let swap_key = Pubkey::new_unique();
+        let admin_key = Pubkey::new_unique();
+        let pool_mint_key = Pubkey::new_unique();
+        let token_keys = [
+            Pubkey::new_unique(),
+            Pubkey::new_unique(),
+            Pubkey::new_unique(),
+            Pubkey::new_unique(),
+        ];
+        let (authority_key, nonce) =
+            Pubkey::find_program_address(&[&swap_key.to_bytes()[..]], &program_id);
+
+        let config = ConfigInfo {
+            version: 1u8,
+            admin_key,
+            ..ConfigInfo::default()
+        };
+        let mut config_lamports = 0u64;
+        let mut config_data = [0u8; ConfigInfo::LEN];
+        config.pack_into_slice(&mut config_data);
+        let config_info = AccountInfo::new(
+            &config_key,
+            false,
+            false,
+            &mut config_lamports,
+            &mut config_data,
+            &program_id,
+            false,
+            0u64,
+        );
+
+        let mut swap = SwapInfo {
+            is_initialized: true,
+            is_paused: option != 1u8,
+            nonce,
+            config_key,
+            pool_mint: pool_mint_key,
+            token_a: token_keys[0],
+            token_b: token_keys[1],
+            ..SwapInfo::default()
+        };
+        if option == 2u8 {
+            swap.pool_state.total_supply = 1;
+        }
+        let mut swap_lamports = 0u64;
+        let mut swap_data = [0u8; SwapInfo::LEN];
+        swap.pack_into_slice(&mut swap_data);
+        let swap_info = AccountInfo::new(
+            &swap_key,
+            false,
+            true,
+            &mut swap_lamports,
+            &mut swap_data,
+            &program_id,
+            false,
+            0u64,
+        );
+
+        let mut authority_lamports = 0u64;
+        let mut authority_data = [0u8];
+        let authority_info = AccountInfo::new(
+            &authority_key,
+            false,
+            false,
+            &mut authority_lamports,
+            &mut authority_data,
+            &program_id,
+            false,
+            0u64,
+        );
+
+        let signer_key = if option == 4u8 {
+            Pubkey::new_unique()
+        } else {
+            admin_key
+        };
+        let mut admin_lamports = 0u64;
+        let mut admin_data = [0u8];
+        let admin_info = AccountInfo::new(
+            &signer_key,
+            true,
+            true,
+            &mut admin_lamports,
+            &mut admin_data,
+            &program_id,
+            false,
+            0u64,
+        );
+
+        let pool_mint = Mint {
+            mint_authority: COption::Some(authority_key),
+            supply: if option == 3u8 { 1 } else { 0 },
+            decimals: 6,
+            is_initialized: true,
+            freeze_authority: COption::None,
+        };
+        let mut pool_mint_lamports = 0u64;
+        let mut pool_mint_data = [0u8; Mint::LEN];
+        Mint::pack(pool_mint, &mut pool_mint_data).unwrap();
+        let pool_mint_info = AccountInfo::new(
+            &pool_mint_key,
+            false,
+            true,
+            &mut pool_mint_lamports,
+            &mut pool_mint_data,
+            &spl_token::id(),
+            false,
+            0u64,
+        );
+
+        let mut token_lamports = [0u64; 4];
+        let mut token_data = [[0u8; 0]; 4];
+        let mut token_infos = token_keys
+            .iter()
+            .zip(token_lamports.iter_mut())
+            .zip(token_data.iter_mut())
+            .map(|((key, lamports), data)| {
+                AccountInfo::new(
+                    key,
+                    false,
+                    true,
+                    lamports,
+                    data,
+                    &spl_token::id(),
+                    false,
+                    0u64,
+                )
+            })
+            .collect::<Vec<_>>();
+        if option == 5u8 {
+            token_infos.swap(0, 1);
+        }
+
+        let mut token_program_lamports = 0u64;
+        let mut token_program_data = [0u8];
+        let token_program_id = spl_token::id();
+        let token_program_info = AccountInfo::new(
+            &token_program_id,
+            false,
+            false,
+            &mut token_program_lamports,
+            &mut token_program_data,
+            &program_id,
+            true,
+            0u64,
+        );
+
+        let mut accounts = vec![
+            config_info,
+            swap_info,
+            authority_info,
+            admin_info,
+            pool_mint_info,
+        ];
+        accounts.extend(token_infos);
+        accounts.push(token_program_info);
+        close_pool(&program_id, &accounts[..])
+    }
+
+    #[test]
+    fn test_close_pool() {
+        assert_eq!(
+            get_close_pool_result(1u8),
+            Err(ProgramError::from(SwapError::PoolNotClosable))
+        );
+        assert_eq!(
+            get_close_pool_result(2u8),
+            Err(ProgramError::from(SwapError::PoolNotClosable))
+        );
+        assert_eq!(
+            get_close_pool_result(3u8),
+            Err(ProgramError::from(SwapError::PoolNotClosable))
+        );
+        assert_eq!(
+            get_close_pool_result(4u8),
+            Err(ProgramError::from(SwapError::Unauthorized))
+        );
+        assert_eq!(
+            get_close_pool_result(5u8),
+            Err(ProgramError::from(SwapError::IncorrectSwapAccount))
+        );
+    }
+
+    fn get_set_oracle_feed_result(option: u8) -> (ProgramResult, OracleFeed) {
+        let program_id = Pubkey::new_unique();
+        let config_key = Pubkey::new_unique();
+        let admin_key = Pubkey::new_unique();
+        let oracle_manager_key = Pubkey::new_unique();
+        let pyth_program_id = Pubkey::new_unique();
+        let mint_key = Pubkey::new_unique();
+        let pyth_price_key = Pubkey::new_unique();
+        let serum_market_key = Pubkey::default();
+        let oracle_feed_key = if option == 2u8 {
+            Pubkey::new_unique()
+        } else {
+            find_oracle_feed_address(&config_key, &mint_key, &program_id).0
+        };
+
+        let config = ConfigInfo {
+            version: 1u8,
+            admin_key,
+            pyth_program_id,
+            oracle_manager_key,
+            ..ConfigInfo::default()
+        };
+        let mut config_lamports = 0u64;
+        let mut config_data = [0u8; ConfigInfo::LEN];
+        config.pack_into_slice(&mut config_data);
+        let config_info = AccountInfo::new(
+            &config_key,
+            false,
+            false,
+            &mut config_lamports,
+            &mut config_data,
+            &program_id,
+            false,
+            0u64,
+        );
+
+        // The registry entry already exists, so it is updated in place
+        let mut oracle_feed_lamports = 0u64;
+        let mut oracle_feed_data = [0u8; OracleFeed::LEN];
+        let oracle_feed_info = AccountInfo::new(
+            &oracle_feed_key,
+            false,
+            true,
+            &mut oracle_feed_lamports,
+            &mut oracle_feed_data,
+            &program_id,
+            false,
+            0u64,
+        );
+
+        let mint = Mint {
+            is_initialized: true,
+            ..Mint::default()
+        };
+        let mut mint_lamports = 0u64;
+        let mut mint_data = [0u8; Mint::LEN];
+        Mint::pack(mint, &mut mint_data).unwrap();
+        let mint_info = AccountInfo::new(
+            &mint_key,
+            false,
+            false,
+            &mut mint_lamports,
+            &mut mint_data,
+            &spl_token::id(),
+            false,
+            0u64,
+        );
+
+        let mut pyth_price_lamports = 0u64;
+        let mut pyth_price_data = [0u8];
+        let pyth_price_info = AccountInfo::new(
+            &pyth_price_key,
+            false,
+            false,
+            &mut pyth_price_lamports,
+            &mut pyth_price_data,
+            if option == 3u8 {
+                &program_id
+            } else {
+                &pyth_program_id
+            },
+            false,
+            0u64,
+        );
+
+        let mut serum_market_lamports = 0u64;
+        let mut serum_market_data = [0u8];
+        let serum_market_info = AccountInfo::new(
+            &serum_market_key,
+            false,
+            false,
+            &mut serum_market_lamports,
+            &mut serum_market_data,
+            &program_id,
+            false,
+            0u64,
+        );
+
+        let signer_key = match option {
+            1u8 => Pubkey::new_unique(),
+            4u8 => oracle_manager_key,
+            _ => admin_key,
+        };
+        let mut admin_lamports = 0u64;
+        let mut admin_data = [0u8];
+        let admin_info = AccountInfo::new(
+            &signer_key,
+            true,
+            true,
+            &mut admin_lamports,
+            &mut admin_data,
+            &program_id,
+            false,
+            0u64,
+        );
+
+        let rent_key = solana_program::sysvar::rent::id();
+        let mut rent_lamports = 0u64;
+        let mut rent_data = [0u8; mem::size_of::<Rent>()];
+        let mut rent_info = AccountInfo::new(
+            &rent_key,
+            false,
+            false,
+            &mut rent_lamports,
+            &mut rent_data,
+            &program_id,
+            false,
+            0u64,
+        );
+        assert!(Rent::default().to_account_info(&mut rent_info).is_some());
+
+        let system_program_key = solana_program::system_program::id();
+        let mut system_program_lamports = 0u64;
+        let mut system_program_data = [0u8];
+        let system_program_info = AccountInfo::new(
+            &system_program_key,
+            false,
+            false,
+            &mut system_program_lamports,
+            &mut system_program_data,
+            &program_id,
+            true,
+            0u64,
+        );
+
+        let accounts = [
+            config_info,
+            oracle_feed_info,
+            mint_info,
+            pyth_price_info,
+            serum_market_info,
+            admin_info,
+            rent_info,
+            system_program_info,
+        ];
+        let result = set_oracle_feed(&program_id, &accounts[..]);
+        let oracle_feed = OracleFeed::unpack_unchecked(&accounts[1].data.borrow()).unwrap();
+        (result, oracle_feed)
+    }
+
+    #[test]
+    fn test_set_oracle_feed() {
+        let (result, oracle_feed) = get_set_oracle_feed_result(0u8);
+        assert!(result.is_ok());
+        assert!(oracle_feed.is_initialized);
+        assert_ne!(oracle_feed.pyth_price, Pubkey::default());
+        assert_eq!(oracle_feed.serum_market, Pubkey::default());
+        assert!(get_set_oracle_feed_result(4u8).0.is_ok());
+        assert_eq!(
+            get_set_oracle_feed_result(1u8).0,
+            Err(ProgramError::from(SwapError::Unauthorized))
+        );
+        assert_eq!(
+            get_set_oracle_feed_result(2u8).0,
+            Err(ProgramError::from(SwapError::InvalidOracleFeed))
+        );
+        let (result, oracle_feed) = get_set_oracle_feed_result(3u8);
+        assert_eq!(
+            result,
+            Err(ProgramError::from(SwapError::InvalidOracleFeed))
+        );
+        assert!(!oracle_feed.is_initialized);
+    }
+
+    fn get_set_price_cache_result(option: u8) -> (ProgramResult, PriceCache) {
+        let program_id = Pubkey::new_unique();
+        let config_key = Pubkey::new_unique();
+        let admin_key = Pubkey::new_unique();
+        let oracle_manager_key = Pubkey::new_unique();
+        let swap_key = Pubkey::new_unique();
+        let relayer_key = Pubkey::new_unique();
+        let price_cache_key = if option == 2u8 {
+            Pubkey::new_unique()
+        } else {
+            find_price_cache_address(&swap_key, &program_id).0
+        };
+
+        let config = ConfigInfo {
+            version: 1u8,
+            admin_key,
+            oracle_manager_key,
+            ..ConfigInfo::default()
+        };
+        let mut config_lamports = 0u64;
+        let mut config_data = [0u8; ConfigInfo::LEN];
+        config.pack_into_slice(&mut config_data);
+        let config_info = AccountInfo::new(
+            &config_key,
+            false,
+            false,
+            &mut config_lamports,
+            &mut config_data,
+            &program_id,
+            false,
+            0u64,
+        );
+
+        let token_swap = SwapInfo {
+            is_initialized: true,
+            config_key: if option == 3u8 {
+                Pubkey::new_unique()
+            } else {
+                config_key
+            },
+            pyth_a: Pubkey::new_unique(),
+            pyth_b: Pubkey::new_unique(),
+            ..SwapInfo::default()
+        };
+        let mut swap_lamports = 0u64;
+        let mut swap_data = [0u8; SwapInfo::LEN];
+        token_swap.pack_into_slice(&mut swap_data);
+        let swap_info = AccountInfo::new(
+            &swap_key,
+            false,
+            false,
+            &mut swap_lamports,
+            &mut swap_data,
+            &program_id,
+            false,
+            0u64,
+        );
+
+        // The price cache already exists with a price of the previous relayer
+        let mut price_cache_lamports = 0u64;
+        let mut price_cache_data = [0u8; PriceCache::LEN];
+        PriceCache {
+            is_initialized: true,
+            swap_key,
+            relayer: Pubkey::new_unique(),
+            market_price: Decimal::from(100u64),
+            valid_slot: 10,
+            ..PriceCache::default()
+        }
+        .pack_into_slice(&mut price_cache_data);
+        let price_cache_info = AccountInfo::new(
+            &price_cache_key,
+            false,
+            true,
+            &mut price_cache_lamports,
+            &mut price_cache_data,
+            &program_id,
+            false,
+            0u64,
+        );
+
+        let mut relayer_lamports = 0u64;
+        let mut relayer_data = [0u8];
+        let relayer_info = AccountInfo::new(
+            &relayer_key,
+            false,
+            false,
+            &mut relayer_lamports,
+            &mut relayer_data,
+            &program_id,
+            false,
+            0u64,
+        );
+
+        let signer_key = match option {
+            1u8 => Pubkey::new_unique(),
+            4u8 => oracle_manager_key,
+            _ => admin_key,
+        };
+        let mut admin_lamports = 0u64;
+        let mut admin_data = [0u8];
+        let admin_info = AccountInfo::new(
+            &signer_key,
+            option != 5u8,
+            true,
+            &mut admin_lamports,
+            &mut admin_data,
+            &program_id,
+            false,
+            0u64,
+        );
+
+        let rent_key = solana_program::sysvar::rent::id();
+        let mut rent_lamports = 0u64;
+        let mut rent_data = [0u8; mem::size_of::<Rent>()];
+        let mut rent_info = AccountInfo::new(
+            &rent_key,
+            false,
+            false,
+            &mut rent_lamports,
+            &mut rent_data,
+            &program_id,
+            false,
+            0u64,
+        );
+        assert!(Rent::default().to_account_info(&mut rent_info).is_some());
+
+        let system_program_key = solana_program::system_program::id();
+        let mut system_program_lamports = 0u64;
+        let mut system_program_data = [0u8];
+        let system_program_info = AccountInfo::new(
+            &system_program_key,
+            false,
+            false,
+            &mut system_program_lamports,
+            &mut system_program_data,
+            &program_id,
+            true,
+            0u64,
+        );
+
+        let accounts = [
+            config_info,
+            swap_info,
+            price_cache_info,
+            relayer_info,
+            admin_info,
+            rent_info,
+            system_program_info,
+        ];
+        let result = set_price_cache(&program_id, &accounts[..]);
+        let price_cache = PriceCache::unpack_unchecked(&accounts[2].data.borrow()).unwrap();
+        (result, price_cache)
+    }
+
+    #[test]
+    fn test_set_price_cache() {
+        let (result, price_cache) = get_set_price_cache_result(0u8);
+        assert!(result.is_ok());
+        assert!(price_cache.is_initialized);
+        assert_ne!(price_cache.relayer, Pubkey::default());
+        assert_ne!(price_cache.pyth_a, Pubkey::default());
+        assert_eq!(price_cache.market_price, Decimal::zero());
+        assert_eq!(price_cache.valid_slot, 0);
+        assert!(get_set_price_cache_result(4u8).0.is_ok());
+        assert_eq!(
+            get_set_price_cache_result(1u8).0,
+            Err(ProgramError::from(SwapError::Unauthorized))
+        );
+        assert_eq!(
+            get_set_price_cache_result(2u8).0,
+            Err(ProgramError::from(SwapError::InvalidPriceCache))
+        );
+        assert_eq!(
+            get_set_price_cache_result(3u8).0,
+            Err(ProgramError::from(SwapError::InvalidMarketConfig))
+        );
+        let (result, price_cache) = get_set_price_cache_result(5u8);
+        assert_eq!(result, Err(ProgramError::MissingRequiredSignature));
+        assert_eq!(price_cache.market_price, Decimal::from(100u64));
+    }
+
+    fn get_pause_all_result(option: u8) -> (ProgramResult, Vec<bool>) {
+        let program_id = Pubkey::new_unique();
+        let config_key = Pubkey::new_unique();
+        let admin_key = Pubkey::new_unique();
+        let guardian_key = Pubkey::new_unique();
+        let swap_keys = [Pubkey::new_unique(), Pubkey::new_unique()];
+
+        let config = ConfigInfo {
+            version: 1u8,
+            admin_key,
+            guardian_key,
+            ..ConfigInfo::default()
+        };
+
+        let mut config_lamports = 0u64;
+        let mut config_data = [0u8; ConfigInfo::LEN];
+        config.pack_into_slice(&mut config_data);
+        let config_info = AccountInfo::new(
+            &config_key,
+            false,
+            false,
+            &mut config_lamports,
+            &mut config_data,
+            if option == 1u8 {
+                &config_key
+            } else {
+                &program_id
+            },
+            false,
+            0u64,
+        );
+
+        let signer_key = match option {
+            2u8 => admin_key,
+            3u8 => Pubkey::new_unique(),
+            _ => guardian_key,
+        };
+        let mut guardian_lamports = 0u64;
+        let mut guardian_data = [0u8];
+        let guardian_info = AccountInfo::new(
+            &signer_key,
+            option != 4u8,
+            false,
+            &mut guardian_lamports,
+            &mut guardian_data,
+            &program_id,
+            false,
+            0u64,
+        );
+
+        let swap = SwapInfo {
+            is_initialized: true,
+            config_key,
+            ..SwapInfo::default()
+        };
+        let other_swap = SwapInfo {
+            config_key: if option == 5u8 {
+                Pubkey::new_unique()
+            } else {
+                config_key
+            },
+            ..swap.clone()
+        };
+
+        let mut swap_lamports = 0u64;
+        let mut swap_data = [0u8; SwapInfo::LEN];
+        swap.pack_into_slice(&mut swap_data);
+        let swap_info = AccountInfo::new(
+            &swap_keys[0],
+            false,
+            true,
+            &mut swap_lamports,
+            &mut swap_data,
+            &program_id,
+            false,
+            0u64,
+        );
+
+        let mut other_swap_lamports = 0u64;
+        let mut other_swap_data = [0u8; SwapInfo::LEN];
+        other_swap.pack_into_slice(&mut other_swap_data);
+        let other_swap_info = AccountInfo::new(
+            &swap_keys[1],
+            false,
+            true,
+            &mut other_swap_lamports,
+            &mut other_swap_data,
+            if option == 6u8 {
+                &swap_keys[1]
+            } else {
+                &program_id
+            },
+            false,
+            0u64,
+        );
+
+        let accounts = [config_info, guardian_info, swap_info, other_swap_info];
+        let result = pause_all(&program_id, &accounts[..]);
+        let is_paused = accounts[2..]
+            .iter()
+            .map(|swap_info| {
+                SwapInfo::unpack_unchecked(&swap_info.data.borrow())
+                    .unwrap()
+                    .is_paused
+            })
+            .collect();
+
+        (result, is_paused)
+    }
+
+    #[test]
+    fn test_pause_all() {
+        assert_eq!(get_pause_all_result(0u8), (Ok(()), vec![true, true]));
+        assert_eq!(get_pause_all_result(2u8), (Ok(()), vec![true, true]));
+        assert_eq!(
+            get_pause_all_result(1u8).0,
+            Err(ProgramError::from(SwapError::InvalidAccountOwner))
+        );
+        assert_eq!(
+            get_pause_all_result(3u8),
+            (
+                Err(ProgramError::from(SwapError::Unauthorized)),
+                vec![false, false]
+            )
+        );
+        assert_eq!(
+            get_pause_all_result(4u8).0,
+            Err(ProgramError::MissingRequiredSignature)
+        );
+        assert_eq!(
+            get_pause_all_result(5u8).0,
+            Err(ProgramError::from(SwapError::InvalidMarketConfig))
+        );
+        assert_eq!(
+            get_pause_all_result(6u8).0,
+            Err(ProgramError::from(SwapError::InvalidAccountOwner))
+        );
+    }
+
+    fn get_batch_result(set_fees: bool, option: u8) -> (ProgramResult, Vec<(bool, u64)>) {
+        let program_id = Pubkey::new_unique();
+        let config_key = Pubkey::new_unique();
+        let admin_key = Pubkey::new_unique();
+        let fee_manager_key = Pubkey::new_unique();
+        let pauser_key = Pubkey::new_unique();
+        let swap_keys = [Pubkey::new_unique(), Pubkey::new_unique()];
+
+        let config = ConfigInfo {
+            version: 1u8,
+            admin_key,
+            fee_manager_key,
+            pauser_key,
+            ..ConfigInfo::default()
+        };
+
+        let mut config_lamports = 0u64;
+        let mut config_data = [0u8; ConfigInfo::LEN];
+        config.pack_into_slice(&mut config_data);
+        let config_info = AccountInfo::new(
+            &config_key,
+            false,
+            false,
+            &mut config_lamports,
+            &mut config_data,
+            &program_id,
+            false,
+            0u64,
+        );
+
+        let signer_key = match option {
+            1u8 => fee_manager_key,
+            2u8 => pauser_key,
+            _ => admin_key,
+        };
+        let mut admin_lamports = 0u64;
+        let mut admin_data = [0u8];
+        let admin_info = AccountInfo::new(
+            &signer_key,
+            true,
+            false,
+            &mut admin_lamports,
+            &mut admin_data,
+            &program_id,
+            false,
+            0u64,
+        );
+
+        let swap = SwapInfo {
+            is_initialized: true,
+            config_key,
+            ..SwapInfo::default()
+        };
+        let other_swap = SwapInfo {
+            config_key: if option == 3u8 {
+                Pubkey::new_unique()
+            } else {
+                config_key
+            },
+            ..swap.clone()
+        };
+
+        let mut swap_lamports = 0u64;
+        let mut swap_data = [0u8; SwapInfo::LEN];
+        swap.pack_into_slice(&mut swap_data);
+        let swap_info = AccountInfo::new(
+            &swap_keys[0],
+            false,
+            true,
+            &mut swap_lamports,
+            &mut swap_data,
+            &program_id,
+            false,
+            0u64,
+        );
+
+        let mut other_swap_lamports = 0u64;
+        let mut other_swap_data = [0u8; SwapInfo::LEN];
+        other_swap.pack_into_slice(&mut other_swap_data);
+        let other_swap_info = AccountInfo::new(
+            &swap_keys[1],
+            false,
+            true,
+            &mut other_swap_lamports,
+            &mut other_swap_data,
+            &program_id,
+            false,
+            0u64,
+        );
+
+        let accounts = [config_info, admin_info, swap_info, other_swap_info];
+        let result = if set_fees {
+            let new_fees = Fees {
+                trade_fee_numerator: 3,
+                trade_fee_denominator: 1_000,
+                ..Fees::default()
+            };
+            batch_set_fees(&program_id, &new_fees, &accounts[..])
+        } else {
+            batch_pause(&program_id, &accounts[..])
+        };
+        let pools = accounts[2..]
+            .iter()
+            .map(|swap_info| {
+                let token_swap = SwapInfo::unpack_unchecked(&swap_info.data.borrow()).unwrap();
+                (token_swap.is_paused, token_swap.fees.trade_fee_numerator)
+            })
+            .collect();
+
+        (result, pools)
+    }
+
+    #[test]
+    fn test_batch_set_fees() {
+        assert_eq!(
+            get_batch_result(true, 0u8),
+            (Ok(()), vec![(false, 3), (false, 3)])
+        );
+        assert_eq!(
+            get_batch_result(true, 1u8),
+            (Ok(()), vec![(false, 3), (false, 3)])
+        );
+        assert_eq!(
+            get_batch_result(true, 2u8).0,
+            Err(ProgramError::from(SwapError::Unauthorized))
+        );
+        assert_eq!(
+            get_batch_result(true, 3u8).0,
+            Err(ProgramError::from(SwapError::InvalidMarketConfig))
+        );
+    }
+
+    #[test]
+    fn test_batch_pause() {
+        assert_eq!(
+            get_batch_result(false, 0u8),
+            (Ok(()), vec![(true, 0), (true, 0)])
+        );
+        assert_eq!(
+            get_batch_result(false, 2u8),
+            (Ok(()), vec![(true, 0), (true, 0)])
+        );
+        assert_eq!(
+            get_batch_result(false, 1u8).0,
+            Err(ProgramError::from(SwapError::Unauthorized))
+        );
+        assert_eq!(
+            get_batch_result(false, 3u8).0,
+            Err(ProgramError::from(SwapError::InvalidMarketConfig))
+        );
+    }
+
+    fn get_sync_pool_from_config_result(
+        include_pinned: bool,
+        option: u8,
+    ) -> (ProgramResult, Vec<(u64, bool)>) {
+        let program_id = Pubkey::new_unique();
+        let config_key = Pubkey::new_unique();
+        let admin_key = Pubkey::new_unique();
+        let fee_manager_key = Pubkey::new_unique();
+        let swap_keys = [Pubkey::new_unique(), Pubkey::new_unique()];
+
+        let config = ConfigInfo {
+            version: 1u8,
+            admin_key,
+            fee_manager_key,
+            fees: Fees {
+                trade_fee_numerator: 3,
+                trade_fee_denominator: 1_000,
+                ..Fees::default()
+            },
+            ..ConfigInfo::default()
+        };
+
+        let mut config_lamports = 0u64;
+        let mut config_data = [0u8; ConfigInfo::LEN];
+        config.pack_into_slice(&mut config_data);
+        let config_info = AccountInfo::new(
+            &config_key,
+            false,
+            false,
+            &mut config_lamports,
+            &mut config_data,
+            &program_id,
+            false,
+            0u64,
+        );
+
+        let signer_key = match option {
+            1u8 => admin_key,
+            2u8 => Pubkey::new_unique(),
+            _ => fee_manager_key,
+        };
+        let mut admin_lamports = 0u64;
+        let mut admin_data = [0u8];
+        let admin_info = AccountInfo::new(
+            &signer_key,
+            true,
+            false,
+            &mut admin_lamports,
+            &mut admin_data,
+            &program_id,
+            false,
+            0u64,
+        );
+
+        let swap = SwapInfo {
+            is_initialized: true,
+            config_key,
+            fees: Fees {
+                trade_fee_numerator: 1,
+                trade_fee_denominator: 1_000,
+                ..Fees::default()
+            },
+            ..SwapInfo::default()
+        };
+        let pinned_swap = SwapInfo {
+            config_key: if option == 3u8 {
+                Pubkey::new_unique()
+            } else {
+                config_key
+            },
+            fee_override: true,
+            ..swap.clone()
+        };
+
+        let mut swap_lamports = 0u64;
+        let mut swap_data = [0u8; SwapInfo::LEN];
+        swap.pack_into_slice(&mut swap_data);
+        let swap_info = AccountInfo::new(
+            &swap_keys[0],
+            false,
+            true,
+            &mut swap_lamports,
+            &mut swap_data,
+            &program_id,
+            false,
+            0u64,
+        );
+
+        let mut pinned_swap_lamports = 0u64;
+        let mut pinned_swap_data = [0u8; SwapInfo::LEN];
+        pinned_swap.pack_into_slice(&mut pinned_swap_data);
+        let pinned_swap_info = AccountInfo::new(
+            &swap_keys[1],
+            false,
+            true,
+            &mut pinned_swap_lamports,
+            &mut pinned_swap_data,
+            &program_id,
+            false,
+            0u64,
+        );
+
+        let accounts = [config_info, admin_info, swap_info, pinned_swap_info];
+        let result = sync_pool_from_config(&program_id, include_pinned, &accounts[..]);
+        let pools = accounts[2..]
+            .iter()
+            .map(|swap_info| {
+                let token_swap = SwapInfo::unpack_unchecked(&swap_info.data.borrow()).unwrap();
+                (token_swap.fees.trade_fee_numerator, token_swap.fee_override)
+            })
+            .collect();
+
+        (result, pools)
+    }
+
+    #[test]
+    fn test_sync_pool_from_config() {
+        assert_eq!(
+            get_sync_pool_from_config_result(false, 0u8),
+            (Ok(()), vec![(3, false), (1, true)])
+        );
+        assert_eq!(
+            get_sync_pool_from_config_result(true, 1u8),
+            (Ok(()), vec![(3, false), (3, false)])
+        );
+        assert_eq!(
+            get_sync_pool_from_config_result(true, 2u8),
+            (
+                Err(ProgramError::from(SwapError::Unauthorized)),
+                vec![(1, false), (1, true)]
+            )
+        );
+        assert_eq!(
+            get_sync_pool_from_config_result(true, 3u8).0,
+            Err(ProgramError::from(SwapError::InvalidMarketConfig))
+        );
+    }
+
+    fn get_unpause_result(option: u8) -> ProgramResult {
+        let mut accounts = Vec::new();
+        let program_id = Pubkey::new_unique();
+        let config_key = Pubkey::new_unique();
+        let swap_key = Pubkey::new_unique();
+        let admin_key = Pubkey::new_unique();
+
+        let config = ConfigInfo {
+            version: 1u8,
+            bump_seed: 0u8,
+            admin_key: if option == 3u8 { config_key } else { admin_key },
+            deltafi_mint: Pubkey::new_unique(),
+            pyth_program_id: Pubkey::new_unique(),
+            fees: Fees {
+                ..Default::default()
+            },
+            rewards: Rewards {
+                ..Default::default()
+            },
+            deltafi_token: Pubkey::new_unique(),
+            ..ConfigInfo::default()
+        };
+
+        let mut swap = SwapInfo {
+            ..Default::default()
+        };
+        if option == 6u8 {
+            swap.config_key = swap_key;
+        } else {
+            swap.config_key = config_key;
+        }
+        if option == 5u8 {
+            swap.is_initialized = false;
+        } else {
+            swap.is_initialized = true;
+        }
+
+        let mut lamports = 0u64;
+        let mut config_data = [0u8; ConfigInfo::LEN];
+        config.pack_into_slice(&mut config_data);
+        accounts.push(AccountInfo::new(
+            &config_key,
+            false,
+            false,
+            &mut lamports,
+            &mut config_data,
+            if option == 1u8 {
+                &config_key
+            } else {
+                &program_id
+            },
+            false,
+            0u64,
+        ));
+
+        let mut lamports = 0u64;
+        let mut swap_data = [0u8; SwapInfo::LEN];
+        swap.pack_into_slice(&mut swap_data);
+        accounts.push(AccountInfo::new(
+            &swap_key,
+            false,
+            false,
+            &mut lamports,
+            &mut swap_data,
+            if option == 2u8 {
+                &swap_key
+            } else {
+                &program_id
+            },
+            false,
+            0u64,
+        ));
+
+        let mut lamports = 0u64;
+        let mut admin_data = [0u8];
+        accounts.push(AccountInfo::new(
+            &admin_key,
+            option != 4u8,
+            false,
+            &mut lamports,
+            &mut admin_data,
+            &program_id,
+            false,
+            0u64,
+        ));
+
+        unpause(&program_id, &accounts[..])
+    }
+
+    #[test]
+    fn test_unpause() {
+        assert!(get_unpause_result(0u8).is_ok());
+        assert_eq!(
+            get_unpause_result(1u8),
+            Err(ProgramError::from(SwapError::InvalidAccountOwner))
+        );
+        assert_eq!(
+            get_unpause_result(2u8),
+            Err(ProgramError::from(SwapError::InvalidAccountOwner))
+        );
+        assert_eq!(
+            get_unpause_result(3u8),
+            Err(ProgramError::from(SwapError::Unauthorized))
+        );
+        assert_eq!(
+            get_unpause_result(4u8),
+            Err(ProgramError::MissingRequiredSignature)
+        );
+        assert_eq!(
+            get_unpause_result(5u8),
+            Err(ProgramError::UninitializedAccount)
+        );
+        assert_eq!(
+            get_unpause_result(6u8),
+            Err(ProgramError::from(SwapError::InvalidMarketConfig))
+        );
+    }
+
+    fn get_set_fee_account_result(option: u8) -> ProgramResult {
+        let mut accounts = Vec::new();
+        let nonce = 8u8;
+        let program_id = Pubkey::from_str("5NjW2CAV6MBQYxpL4oK2CESrpdj6tkcvxP3iigAgrHyR").unwrap();
+        let config_key = Pubkey::from_str("BuP3jEYfnTCfB4UqQk9L37k2vaXsNuVsbWxrYbGDmL6s").unwrap();
+        let swap_key = Pubkey::from_str("CWWiYh5Rpyf5rHZbzHYM6TT6FfcojTR2rKjr5M4BFa3y").unwrap();
+        let admin_key = Pubkey::from_str("CaR8CBoWip9oFygaVnonzPCKWgBaSQWwSC3jaYNAyNiK").unwrap();
+        let _authority_key =
+            Pubkey::create_program_address(&[swap_key.as_ref(), &[nonce]], &program_id);
+        assert!(_authority_key.is_ok());
+
+        let authority_key = if option == 7u8 {
+            Pubkey::new_unique()
+        } else {
+            _authority_key.unwrap()
+        };
+        let new_admin_fee_key = Pubkey::new_unique();
+        let token_program_key = Pubkey::new_unique();
+
+        let config = ConfigInfo {
+            version: 1u8,
+            bump_seed: 0u8,
+            admin_key: if option == 3u8 { config_key } else { admin_key },
+            deltafi_mint: Pubkey::new_unique(),
+            pyth_program_id: Pubkey::new_unique(),
+            fees: Fees {
+                ..Default::default()
+            },
+            rewards: Rewards {
+                ..Default::default()
+            },
+            deltafi_token: Pubkey::new_unique(),
+            ..ConfigInfo::default()
+        };
+
+        let mut swap = SwapInfo {
+            ..Default::default()
+        };
+        if option == 6u8 {
+            swap.config_key = swap_key;
+        } else {
+            swap.config_key = config_key;
+        }
+        if option == 5u8 {
+            swap.is_initialized = false;
+        } else {
+            swap.is_initialized = true;
+        }
+        swap.token_a_mint = Pubkey::new_unique();
+        swap.token_b_mint = Pubkey::new_unique();
+        swap.nonce = nonce;
+
+        let mut account = Account {
+            ..Default::default()
+        };
+        account.owner = if option == 10u8 {
+            program_id
+        } else {
+            authority_key
+        };
+        account.mint = if option == 11u8 {
+            program_id
+        } else if option == 12u8 {
+            swap.token_b_mint
+        } else {
+            swap.token_a_mint
+        };
+        account.state = if option == 9u8 {
+            AccountState::Uninitialized
+        } else {
+            AccountState::Initialized
+        };
+
+        let mut lamports = 0u64;
+        let mut config_data = [0u8; ConfigInfo::LEN];
+        config.pack_into_slice(&mut config_data);
+        accounts.push(AccountInfo::new(
+            &config_key,
+            false,
+            false,
+            &mut lamports,
+            &mut config_data,
+            if option == 1u8 {
+                &config_key
+            } else {
+                &program_id
+            },
+            false,
+            0u64,
+        ));
+
+        let mut lamports = 0u64;
+        let mut swap_data = [0u8; SwapInfo::LEN];
+        swap.pack_into_slice(&mut swap_data);
+        accounts.push(AccountInfo::new(
+            &swap_key,
+            false,
+            false,
+            &mut lamports,
+            &mut swap_data,
+            if option == 2u8 {
+                &swap_key
+            } else {
+                &program_id
+            },
+            false,
+            0u64,
+        ));
+
+        let mut lamports = 0u64;
+        let mut authority_data = [0u8];
+        accounts.push(AccountInfo::new(
+            &authority_key,
+            false,
+            false,
+            &mut lamports,
+            &mut authority_data,
+            &program_id,
+            false,
+            0u64,
+        ));
+
+        let mut lamports = 0u64;
+        let mut admin_data = [0u8];
+        accounts.push(AccountInfo::new(
+            &admin_key,
+            option != 4u8,
+            false,
+            &mut lamports,
+            &mut admin_data,
+            &program_id,
+            false,
+            0u64,
+        ));
+
+        let mut lamports = 0u64;
+        let mut new_fee_account_data = [0u8; Account::LEN];
+        account.pack_into_slice(&mut new_fee_account_data);
+        accounts.push(AccountInfo::new(
+            &new_admin_fee_key,
+            false,
+            false,
+            &mut lamports,
+            &mut new_fee_account_data,
+            if option == 8u8 {
+                &program_id
+            } else {
+                &token_program_key
+            },
+            false,
+            0u64,
+        ));
+
+        let mut lamports = 0u64;
+        let mut token_swap_data = [0u8];
+        accounts.push(AccountInfo::new(
+            &token_program_key,
+            false,
+            false,
+            &mut lamports,
+            &mut token_swap_data,
+            &program_id,
+            false,
+            0u64,
+        ));
+
+        set_fee_account(&program_id, &accounts[..])
+    }
+
+    #[test]
+    fn test_set_fee_account() {
+        assert!(get_set_fee_account_result(0u8).is_ok());
+        assert_eq!(
+            get_set_fee_account_result(1u8),
+            Err(ProgramError::from(SwapError::InvalidAccountOwner))
+        );
+        assert_eq!(
+            get_set_fee_account_result(2u8),
+            Err(ProgramError::from(SwapError::InvalidAccountOwner))
+        );
+        assert_eq!(
+            get_set_fee_account_result(3u8),
+            Err(ProgramError::from(SwapError::Unauthorized))
+        );
+        assert_eq!(
+            get_set_fee_account_result(4u8),
+            Err(ProgramError::MissingRequiredSignature)
+        );
+        assert_eq!(
+            get_set_fee_account_result(5u8),
+            Err(ProgramError::UninitializedAccount)
+        );
+        assert_eq!(
+            get_set_fee_account_result(6u8),
+            Err(ProgramError::from(SwapError::InvalidMarketConfig))
+        );
+        assert_eq!(
+            get_set_fee_account_result(7u8),
+            Err(ProgramError::from(SwapError::InvalidProgramAddress))
+        );
+        assert_eq!(
+            get_set_fee_account_result(8u8),
+            Err(ProgramError::from(SwapError::IncorrectTokenProgramId))
+        );
+        assert_eq!(
+            get_set_fee_account_result(9u8),
+            Err(ProgramError::from(SwapError::ExpectedAccount))
+        );
+        assert_eq!(
+            get_set_fee_account_result(10u8),
+            Err(ProgramError::from(SwapError::InvalidOwner))
+        );
+        assert_eq!(
+            get_set_fee_account_result(11u8),
+            Err(ProgramError::from(SwapError::IncorrectMint))
+        );
+        assert!(get_set_fee_account_result(12u8).is_ok());
+    }
+
+    fn get_set_new_fees_result(new_fees: &Fees, option: u8) -> (ProgramResult, Fees) {
+        let mut accounts = Vec::new();
+        let program_id = Pubkey::new_unique();
+        let config_key = Pubkey::new_unique();
+        let swap_key = Pubkey::new_unique();
+        let admin_key = Pubkey::new_unique();
+
+        let config = ConfigInfo {
+            version: if option == 3u8 { 0u8 } else { 1u8 },
+            bump_seed: 0u8,
+            admin_key: if option == 4u8 { config_key } else { admin_key },
+            deltafi_mint: Pubkey::new_unique(),
+            pyth_program_id: Pubkey::new_unique(),
+            fees: Fees {
+                ..Default::default()
+            },
+            rewards: Rewards {
+                ..Default::default()
+            },
+            deltafi_token: Pubkey::new_unique(),
+            ..ConfigInfo::default()
+        };
+
+        let mut swap = SwapInfo {
+            ..Default::default()
+        };
+        if option == 7u8 {
+            swap.config_key = swap_key;
+        } else {
+            swap.config_key = config_key;
+        }
+
+        swap.is_initialized = option != 6u8;
+        swap.fees = Fees {
+            ..Default::default()
+        };
+
+        let mut lamports = 0u64;
+        let mut config_data = [0u8; ConfigInfo::LEN];
+        config.pack_into_slice(&mut config_data);
+        accounts.push(AccountInfo::new(
+            &config_key,
+            false,
+            false,
+            &mut lamports,
+            &mut config_data,
+            if option == 1u8 {
+                &config_key
+            } else {
+                &program_id
+            },
+            false,
+            0u64,
+        ));
+
+        let mut lamports = 0u64;
+        let mut swap_data = [0u8; SwapInfo::LEN];
+        swap.pack_into_slice(&mut swap_data);
+        accounts.push(AccountInfo::new(
+            &swap_key,
+            false,
+            false,
+            &mut lamports,
+            &mut swap_data,
+            if option == 2u8 {
+                &swap_key
+            } else {
+                &program_id
+            },
+            false,
+            0u64,
+        ));
+
+        let mut lamports = 0u64;
+        let mut admin_data = [0u8];
+        accounts.push(AccountInfo::new(
+            &admin_key,
+            option != 5u8,
+            false,
+            &mut lamports,
+            &mut admin_data,
+            &program_id,
+            false,
+            0u64,
+        ));
+
+        let result = set_new_fees(&program_id, new_fees, &accounts[..]);
+        let result_swap = SwapInfo::unpack(&accounts[1].data.borrow());
+
+        (
+            result,
+            if let Ok(result_swap_content) = result_swap {
+                result_swap_content.fees
+            } else {
+                Fees {
+                    ..Default::default()
+                }
+            },
+        )
+    }
+
+    #[test]
+    fn test_set_new_fees() {
+        let test_fees = Fees {
+            is_initialized: true,
+            admin_trade_fee_numerator: 123_123_123u64,
+            admin_trade_fee_denominator: 40_000_000u64,
+            admin_withdraw_fee_numerator: 15_000_000u64,
+            admin_withdraw_fee_denominator: 3_000_000u64,
+            trade_fee_numerator: 24_000_000u64,
+            trade_fee_denominator: 31_000_000u64,
+            withdraw_fee_numerator: 5_000_000u64,
+            withdraw_fee_denominator: 91_000_000u64,
+            flash_fee_numerator: 7_000_000u64,
+            flash_fee_denominator: 10_000_000_000u64,
+            dynamic_fee_factor: 50,
+            max_dynamic_fee_multiplier: 3,
+            treasury_fee_numerator: 1,
+            treasury_fee_denominator: 10,
+        };
+
+        let ok_result = get_set_new_fees_result(&test_fees, 0u8);
+        assert!(ok_result.0.is_ok());
+        assert_eq!(ok_result.1, test_fees);
+        assert_eq!(
+            get_set_new_fees_result(&test_fees, 1u8).0,
+            Err(ProgramError::from(SwapError::InvalidAccountOwner))
+        );
+        assert_eq!(
+            get_set_new_fees_result(&test_fees, 2u8).0,
+            Err(ProgramError::from(SwapError::InvalidAccountOwner))
+        );
+        assert_eq!(
+            get_set_new_fees_result(&test_fees, 3u8).0,
+            Err(ProgramError::UninitializedAccount)
+        );
+        assert_eq!(
+            get_set_new_fees_result(&test_fees, 4u8).0,
+            Err(ProgramError::from(SwapError::Unauthorized))
+        );
+        assert_eq!(
+            get_set_new_fees_result(&test_fees, 5u8).0,
+            Err(ProgramError::MissingRequiredSignature)
+        );
+        assert_eq!(
+            get_set_new_fees_result(&test_fees, 6u8).0,
+            Err(ProgramError::UninitializedAccount)
+        );
+        assert_eq!(
+            get_set_new_fees_result(&test_fees, 7u8).0,
+            Err(ProgramError::from(SwapError::InvalidMarketConfig))
+        );
+        let invalid_protocol_fees = Fees {
+            treasury_fee_numerator: 11,
+            ..test_fees
+        };
+        assert_eq!(
+            get_set_new_fees_result(&invalid_protocol_fees, 0u8).0,
+            Err(ProgramError::from(SwapError::InvalidInput))
+        );
+    }
+
+    fn get_set_new_rewards_result(new_rewards: &Rewards, option: u8) -> (ProgramResult, Rewards) {
+        let mut accounts = Vec::new();
+        let program_id = Pubkey::new_unique();
+        let config_key = Pubkey::new_unique();
+        let swap_key = Pubkey::new_unique();
+        let admin_key = Pubkey::new_unique();
+
+        let config = ConfigInfo {
+            version: if option == 3u8 { 0u8 } else { 1u8 },
+            bump_seed: 0u8,
+            admin_key: if option == 4u8 { config_key } else { admin_key },
+            deltafi_mint: Pubkey::new_unique(),
+            pyth_program_id: Pubkey::new_unique(),
+            fees: Fees {
+                ..Default::default()
+            },
+            rewards: Rewards {
+                ..Default::default()
+            },
+            deltafi_token: Pubkey::new_unique(),
+            ..ConfigInfo::default()
+        };
+
+        let mut swap = SwapInfo {
+            ..Default::default()
+        };
+        if option == 7u8 {
+            swap.config_key = swap_key;
+        } else {
+            swap.config_key = config_key;
+        }
+
+        swap.is_initialized = option != 6u8;
+        swap.fees = Fees {
+            ..Default::default()
+        };
+
+        let mut lamports = 0u64;
+        let mut config_data = [0u8; ConfigInfo::LEN];
+        config.pack_into_slice(&mut config_data);
+        accounts.push(AccountInfo::new(
+            &config_key,
+            false,
+            false,
+            &mut lamports,
+            &mut config_data,
+            if option == 1u8 {
+                &config_key
+            } else {
+                &program_id
+            },
+            false,
+            0u64,
+        ));
+
+        let mut lamports = 0u64;
+        let mut swap_data = [0u8; SwapInfo::LEN];
+        swap.pack_into_slice(&mut swap_data);
+        accounts.push(AccountInfo::new(
+            &swap_key,
+            false,
+            false,
+            &mut lamports,
+            &mut swap_data,
+            if option == 2u8 {
+                &swap_key
+            } else {
+                &program_id
+            },
+            false,
+            0u64,
+        ));
+
+        let mut lamports = 0u64;
+        let mut admin_data = [0u8];
+        accounts.push(AccountInfo::new(
+            &admin_key,
+            option != 5u8,
+            false,
+            &mut lamports,
+            &mut admin_data,
+            &program_id,
+            false,
+            0u64,
+        ));
+
+        let result = set_new_rewards(&program_id, new_rewards, &accounts[..]);
+        let result_swap = SwapInfo::unpack(&accounts[1].data.borrow());
+
+        (
+            result,
+            if let Ok(result_swap_content) = result_swap {
+                result_swap_content.rewards
+            } else {
+                Rewards {
+                    ..Default::default()
+                }
+            },
+        )
+    }
+
+    #[test]
+    fn test_set_new_rewards() {
+        let test_rewards = Rewards {
+            is_initialized: true,
+            decimals: 9,
+            referral_fee_bps: 500,
+            reserved: [0u8; 5],
+            trade_reward_numerator: 123_123_123u64,
+            trade_reward_denominator: 40_000_000u64,
+            trade_reward_cap: 15_000_000u64,
+        };
+
+        let ok_result = get_set_new_rewards_result(&test_rewards, 0u8);
+        assert!(ok_result.0.is_ok());
+        assert_eq!(ok_result.1, test_rewards);
+        assert_eq!(
+            get_set_new_rewards_result(&test_rewards, 1u8).0,
+            Err(ProgramError::from(SwapError::InvalidAccountOwner))
+        );
+        assert_eq!(
+            get_set_new_rewards_result(&test_rewards, 2u8).0,
+            Err(ProgramError::from(SwapError::InvalidAccountOwner))
+        );
+        assert_eq!(
+            get_set_new_rewards_result(&test_rewards, 3u8).0,
+            Err(ProgramError::UninitializedAccount)
+        );
+        assert_eq!(
+            get_set_new_rewards_result(&test_rewards, 4u8).0,
+            Err(ProgramError::from(SwapError::Unauthorized))
+        );
+        assert_eq!(
+            get_set_new_rewards_result(&test_rewards, 5u8).0,
+            Err(ProgramError::MissingRequiredSignature)
+        );
+        assert_eq!(
+            get_set_new_rewards_result(&test_rewards, 6u8).0,
+            Err(ProgramError::UninitializedAccount)
+        );
+        assert_eq!(
+            get_set_new_rewards_result(&test_rewards, 7u8).0,
+            Err(ProgramError::from(SwapError::InvalidMarketConfig))
+        );
+        let invalid_referral_rewards = Rewards {
+            referral_fee_bps: 10_001,
+            ..test_rewards
+        };
+        assert_eq!(
+            get_set_new_rewards_result(&invalid_referral_rewards, 0u8).0,
+            Err(ProgramError::from(SwapError::InvalidInput))
+        );
+    }
+
+    fn get_set_farm_rewards_result(
+        farm_rewards: &FarmRewards,
+        option: u8,
+    ) -> (ProgramResult, FarmRewards) {
+        let mut accounts = Vec::new();
+        let program_id = Pubkey::new_unique();
+        let config_key = Pubkey::new_unique();
+        let farm_key = Pubkey::new_unique();
+        let admin_key = Pubkey::new_unique();
+
+        let config = ConfigInfo {
+            version: if option == 3u8 { 0u8 } else { 1u8 },
+            bump_seed: 0u8,
+            admin_key: if option == 4u8 { config_key } else { admin_key },
+            deltafi_mint: Pubkey::new_unique(),
+            pyth_program_id: Pubkey::new_unique(),
+            fees: Fees {
+                ..Default::default()
+            },
+            rewards: Rewards {
+                ..Default::default()
+            },
+            deltafi_token: Pubkey::new_unique(),
+            ..ConfigInfo::default()
+        };
+
+        let mut farm = FarmInfo {
+            ..Default::default()
+        };
+        if option == 7u8 {
+            farm.config_key = farm_key;
+        } else {
+            farm.config_key = config_key;
+        }
+
+        farm.is_initialized = option != 6u8;
+        farm.apr_numerator = farm_rewards.apr_numerator;
+        farm.apr_denominator = farm_rewards.apr_denominator;
+
+        let mut lamports = 0u64;
+        let mut config_data = [0u8; ConfigInfo::LEN];
+        config.pack_into_slice(&mut config_data);
+        accounts.push(AccountInfo::new(
+            &config_key,
+            false,
+            false,
+            &mut lamports,
+            &mut config_data,
+            if option == 1u8 {
+                &config_key
+            } else {
+                &program_id
+            },
+            false,
+            0u64,
+        ));
+
+        let mut lamports = 0u64;
+        let mut farm_data = [0u8; FarmInfo::LEN];
+        farm.pack_into_slice(&mut farm_data);
+        accounts.push(AccountInfo::new(
+            &farm_key,
+            false,
+            false,
+            &mut lamports,
+            &mut farm_data,
+            if option == 2u8 {
+                &farm_key
+            } else {
+                &program_id
+            },
+            false,
+            0u64,
+        ));
+
+        let mut lamports = 0u64;
+        let mut admin_data = [0u8];
+        accounts.push(AccountInfo::new(
+            &admin_key,
+            option != 5u8,
+            false,
+            &mut lamports,
+            &mut admin_data,
+            &program_id,
+            false,
+            0u64,
+        ));
+
+        let result = set_farm_rewards(&program_id, farm_rewards, &accounts[..]);
+        let result_farm = FarmInfo::unpack(&accounts[1].data.borrow());
+
+        (
+            result,
+            if let Ok(result_farm_content) = result_farm {
+                FarmRewards {
+                    apr_numerator: result_farm_content.apr_numerator,
+                    apr_denominator: result_farm_content.apr_denominator,
+                }
+            } else {
+                FarmRewards {
+                    apr_numerator: 0u64,
+                    apr_denominator: 0u64,
+                }
+            },
+        )
+    }
+
+    #[test]
+    fn test_set_farm_rewards() {
+        let test_staking_reward = FarmRewards {
+            apr_numerator: 222_222_222u64,
+            apr_denominator: 333_333_333u64,
+        };
+
+        let ok_result = get_set_farm_rewards_result(&test_staking_reward, 0u8);
+        assert!(ok_result.0.is_ok());
+        assert_eq!(ok_result.1, test_staking_reward);
+        assert_eq!(
+            get_set_farm_rewards_result(&test_staking_reward, 1u8).0,
+            Err(ProgramError::from(SwapError::InvalidAccountOwner))
+        );
+        assert_eq!(
+            get_set_farm_rewards_result(&test_staking_reward, 2u8).0,
+            Err(ProgramError::from(SwapError::InvalidAccountOwner))
+        );
+        assert_eq!(
+            get_set_farm_rewards_result(&test_staking_reward, 3u8).0,
+            Err(ProgramError::UninitializedAccount)
+        );
+        assert_eq!(
+            get_set_farm_rewards_result(&test_staking_reward, 4u8).0,
+            Err(ProgramError::from(SwapError::Unauthorized))
+        );
+        assert_eq!(
+            get_set_farm_rewards_result(&test_staking_reward, 5u8).0,
+            Err(ProgramError::MissingRequiredSignature)
+        );
+        assert_eq!(
+            get_set_farm_rewards_result(&test_staking_reward, 6u8).0,
+            Err(ProgramError::UninitializedAccount)
+        );
+        assert_eq!(
+            get_set_farm_rewards_result(&test_staking_reward, 7u8).0,
+            Err(ProgramError::from(SwapError::InvalidMarketConfig))
+        );
+    }
+
+    fn get_set_slope_result(slope: u64, option: u8) -> (ProgramResult, u64) {
+        let mut accounts = Vec::new();
+        let program_id = Pubkey::new_unique();
+        let config_key = Pubkey::new_unique();
+        let pool_key = Pubkey::new_unique();
+        let admin_key = Pubkey::new_unique();
+
+        let config = ConfigInfo {
+            version: if option == 3u8 { 0u8 } else { 1u8 },
+            bump_seed: 0u8,
+            admin_key: if option == 4u8 { config_key } else { admin_key },
+            deltafi_mint: Pubkey::new_unique(),
+            pyth_program_id: Pubkey::new_unique(),
+            fees: Fees {
+                ..Default::default()
+            },
+            rewards: Rewards {
+                ..Default::default()
+            },
+            deltafi_token: Pubkey::new_unique(),
+            ..ConfigInfo::default()
+        };
+
+        let mut swap = SwapInfo {
+            ..Default::default()
+        };
+        if option == 7u8 {
+            swap.config_key = pool_key;
+        } else {
+            swap.config_key = config_key;
+        }
+
+        swap.is_initialized = option != 6u8;
+        swap.pool_state.slope = Decimal::from(slope);
+
+        let mut lamports = 0u64;
+        let mut config_data = [0u8; ConfigInfo::LEN];
+        config.pack_into_slice(&mut config_data);
+        accounts.push(AccountInfo::new(
+            &config_key,
+            false,
+            false,
+            &mut lamports,
+            &mut config_data,
+            if option == 1u8 {
+                &config_key
+            } else {
+                &program_id
+            },
+            false,
+            0u64,
+        ));
+
+        let mut lamports = 0u64;
+        let mut swap_data = [0u8; SwapInfo::LEN];
+        swap.pack_into_slice(&mut swap_data);
+        accounts.push(AccountInfo::new(
+            &pool_key,
+            false,
+            false,
+            &mut lamports,
+            &mut swap_data,
+            if option == 2u8 {
+                &pool_key
+            } else {
+                &program_id
+            },
+            false,
+            0u64,
+        ));
+
+        let mut lamports = 0u64;
+        let mut admin_data = [0u8];
+        accounts.push(AccountInfo::new(
+            &admin_key,
+            option != 5u8,
+            false,
+            &mut lamports,
+            &mut admin_data,
+            &program_id,
+            false,
+            0u64,
+        ));
+
+        let result = set_slope(&program_id, slope, &accounts[..]);
+        let result_pool = SwapInfo::unpack(&accounts[1].data.borrow());
+
+        (result, if result_pool.is_ok() { slope } else { 0u64 })
+    }
+
+    #[test]
+    fn test_set_slope() {
+        let test_set_slope = 123_123_123u64;
+
+        let ok_result = get_set_slope_result(test_set_slope, 0u8);
+        assert!(ok_result.0.is_ok());
+        assert_eq!(ok_result.1, test_set_slope);
+        assert_eq!(
+            get_set_slope_result(test_set_slope, 1u8).0,
+            Err(ProgramError::from(SwapError::InvalidAccountOwner))
+        );
+        assert_eq!(
+            get_set_slope_result(test_set_slope, 2u8).0,
+            Err(ProgramError::from(SwapError::InvalidAccountOwner))
+        );
+        assert_eq!(
+            get_set_slope_result(test_set_slope, 3u8).0,
+            Err(ProgramError::UninitializedAccount)
+        );
+        assert_eq!(
+            get_set_slope_result(test_set_slope, 4u8).0,
+            Err(ProgramError::from(SwapError::Unauthorized))
+        );
+        assert_eq!(
+            get_set_slope_result(test_set_slope, 5u8).0,
+            Err(ProgramError::MissingRequiredSignature)
+        );
+        assert_eq!(
+            get_set_slope_result(test_set_slope, 6u8).0,
+            Err(ProgramError::UninitializedAccount)
+        );
+        assert_eq!(
+            get_set_slope_result(test_set_slope, 7u8).0,
+            Err(ProgramError::from(SwapError::InvalidMarketConfig))
+        );
+    }
+
+    fn get_set_price_jump_policy_result(
+        price_jump_policy: u8,
+        option: u8,
+    ) -> (ProgramResult, PriceJumpPolicy) {
+        let mut accounts = Vec::new();
+        let program_id = Pubkey::new_unique();
+        let config_key = Pubkey::new_unique();
+        let pool_key = Pubkey::new_unique();
+        let admin_key = Pubkey::new_unique();
+
+        let config = ConfigInfo {
+            version: if option == 3u8 { 0u8 } else { 1u8 },
+            bump_seed: 0u8,
+            admin_key: if option == 4u8 { config_key } else { admin_key },
+            deltafi_mint: Pubkey::new_unique(),
+            pyth_program_id: Pubkey::new_unique(),
+            deltafi_token: Pubkey::new_unique(),
+            ..ConfigInfo::default()
+        };
+
+        let swap = SwapInfo {
+            is_initialized: option != 6u8,
+            config_key: if option == 7u8 { pool_key } else { config_key },
+            ..Default::default()
+        };
+
+        let mut lamports = 0u64;
+        let mut config_data = [0u8; ConfigInfo::LEN];
+        config.pack_into_slice(&mut config_data);
+        accounts.push(AccountInfo::new(
+            &config_key,
+            false,
+            false,
+            &mut lamports,
+            &mut config_data,
+            if option == 1u8 {
+                &config_key
+            } else {
+                &program_id
+            },
+            false,
+            0u64,
+        ));
+
+        let mut lamports = 0u64;
+        let mut swap_data = [0u8; SwapInfo::LEN];
+        swap.pack_into_slice(&mut swap_data);
+        accounts.push(AccountInfo::new(
+            &pool_key,
+            false,
+            false,
+            &mut lamports,
+            &mut swap_data,
+            if option == 2u8 {
+                &pool_key
+            } else {
+                &program_id
+            },
+            false,
+            0u64,
+        ));
+
+        let mut lamports = 0u64;
+        let mut admin_data = [0u8];
+        accounts.push(AccountInfo::new(
+            &admin_key,
+            option != 5u8,
+            false,
+            &mut lamports,
+            &mut admin_data,
+            &program_id,
+            false,
+            0u64,
+        ));
+
+        let result = set_price_jump_policy(&program_id, price_jump_policy, &accounts[..]);
+        let policy = SwapInfo::unpack_unchecked(&accounts[1].data.borrow())
+            .unwrap()
+            .price_jump_policy;
+
+        (result, policy)
+    }
+
+    #[test]
+    fn test_set_price_jump_policy() {
+        assert_eq!(
+            get_set_price_jump_policy_result(2u8, 0u8),
+            (Ok(()), PriceJumpPolicy::Clamp)
+        );
+        assert_eq!(
+            get_set_price_jump_policy_result(1u8, 0u8),
+            (Ok(()), PriceJumpPolicy::Accept)
+        );
+        assert_eq!(
+            get_set_price_jump_policy_result(3u8, 0u8).0,
+            Err(ProgramError::from(SwapError::InvalidInput))
+        );
+        assert_eq!(
+            get_set_price_jump_policy_result(2u8, 1u8).0,
+            Err(ProgramError::from(SwapError::InvalidAccountOwner))
+        );
+        assert_eq!(
+            get_set_price_jump_policy_result(2u8, 2u8).0,
+            Err(ProgramError::from(SwapError::InvalidAccountOwner))
+        );
+        assert_eq!(
+            get_set_price_jump_policy_result(2u8, 3u8).0,
+            Err(ProgramError::UninitializedAccount)
+        );
+        assert_eq!(
+            get_set_price_jump_policy_result(2u8, 4u8),
+            (
+                Err(ProgramError::from(SwapError::Unauthorized)),
+                PriceJumpPolicy::Reject
+            )
+        );
+        assert_eq!(
+            get_set_price_jump_policy_result(2u8, 5u8).0,
+            Err(ProgramError::MissingRequiredSignature)
+        );
+        assert_eq!(
+            get_set_price_jump_policy_result(2u8, 6u8).0,
+            Err(ProgramError::UninitializedAccount)
+        );
+        assert_eq!(
+            get_set_price_jump_policy_result(2u8, 7u8).0,
+            Err(ProgramError::from(SwapError::InvalidMarketConfig))
+        );
+    }
+
+    fn get_set_max_price_divergence_result(
+        max_price_divergence: u16,
+        option: u8,
+    ) -> (ProgramResult, u16) {
+        let mut accounts = Vec::new();
+        let program_id = Pubkey::new_unique();
+        let config_key = Pubkey::new_unique();
+        let pool_key = Pubkey::new_unique();
+        let admin_key = Pubkey::new_unique();
+
+        let config = ConfigInfo {
+            version: if option == 3u8 { 0u8 } else { 1u8 },
+            bump_seed: 0u8,
+            admin_key: if option == 4u8 { config_key } else { admin_key },
+            deltafi_mint: Pubkey::new_unique(),
+            pyth_program_id: Pubkey::new_unique(),
+            deltafi_token: Pubkey::new_unique(),
+            ..ConfigInfo::default()
         };
 
-        let market_authority_owner = Pubkey::new_unique();
-        let rent_key = solana_program::sysvar::rent::id();
+        let swap = SwapInfo {
+            is_initialized: option != 6u8,
+            config_key: if option == 7u8 { pool_key } else { config_key },
+            ..Default::default()
+        };
 
-        let deltafi_mint_key = Pubkey::new_unique();
-        let deltafi_token_key = Pubkey::new_unique();
+        let mut lamports = 0u64;
+        let mut config_data = [0u8; ConfigInfo::LEN];
+        config.pack_into_slice(&mut config_data);
+        accounts.push(AccountInfo::new(
+            &config_key,
+            false,
+            false,
+            &mut lamports,
+            &mut config_data,
+            if option == 1u8 {
+                &config_key
+            } else {
+                &program_id
+            },
+            false,
+            0u64,
+        ));
+
+        let mut lamports = 0u64;
+        let mut swap_data = [0u8; SwapInfo::LEN];
+        swap.pack_into_slice(&mut swap_data);
+        accounts.push(AccountInfo::new(
+            &pool_key,
+            false,
+            false,
+            &mut lamports,
+            &mut swap_data,
+            if option == 2u8 {
+                &pool_key
+            } else {
+                &program_id
+            },
+            false,
+            0u64,
+        ));
+
+        let mut lamports = 0u64;
+        let mut admin_data = [0u8];
+        accounts.push(AccountInfo::new(
+            &admin_key,
+            option != 5u8,
+            false,
+            &mut lamports,
+            &mut admin_data,
+            &program_id,
+            false,
+            0u64,
+        ));
+
+        let result = set_max_price_divergence(&program_id, max_price_divergence, &accounts[..]);
+        let divergence = SwapInfo::unpack_unchecked(&accounts[1].data.borrow())
+            .unwrap()
+            .max_price_divergence;
+
+        (result, divergence)
+    }
+
+    #[test]
+    fn test_set_max_price_divergence() {
+        assert_eq!(
+            get_set_max_price_divergence_result(150u16, 0u8),
+            (Ok(()), 150u16)
+        );
+        assert_eq!(
+            get_set_max_price_divergence_result(0u16, 0u8),
+            (Ok(()), 0u16)
+        );
+        assert_eq!(
+            get_set_max_price_divergence_result(150u16, 1u8).0,
+            Err(ProgramError::from(SwapError::InvalidAccountOwner))
+        );
+        assert_eq!(
+            get_set_max_price_divergence_result(150u16, 2u8).0,
+            Err(ProgramError::from(SwapError::InvalidAccountOwner))
+        );
+        assert_eq!(
+            get_set_max_price_divergence_result(150u16, 3u8).0,
+            Err(ProgramError::UninitializedAccount)
+        );
+        assert_eq!(
+            get_set_max_price_divergence_result(150u16, 4u8),
+            (Err(ProgramError::from(SwapError::Unauthorized)), 0u16)
+        );
+        assert_eq!(
+            get_set_max_price_divergence_result(150u16, 5u8).0,
+            Err(ProgramError::MissingRequiredSignature)
+        );
+        assert_eq!(
+            get_set_max_price_divergence_result(150u16, 6u8).0,
+            Err(ProgramError::UninitializedAccount)
+        );
+        assert_eq!(
+            get_set_max_price_divergence_result(150u16, 7u8).0,
+            Err(ProgramError::from(SwapError::InvalidMarketConfig))
+        );
+    }
+
+    fn get_set_circuit_breaker_result(
+        max_deviation_bps: u16,
+        window_slots: u64,
+        option: u8,
+    ) -> (ProgramResult, SwapInfo) {
+        let program_id = Pubkey::new_unique();
+        let config_key = Pubkey::new_unique();
+        let swap_key = Pubkey::new_unique();
         let admin_key = Pubkey::new_unique();
-        let token_program_key = if option == 7u8 {
+
+        let config = ConfigInfo {
+            version: 1u8,
+            admin_key,
+            ..ConfigInfo::default()
+        };
+
+        let mut config_lamports = 0u64;
+        let mut config_data = [0u8; ConfigInfo::LEN];
+        config.pack_into_slice(&mut config_data);
+        let config_info = AccountInfo::new(
+            &config_key,
+            false,
+            false,
+            &mut config_lamports,
+            &mut config_data,
+            &program_id,
+            false,
+            0u64,
+        );
+
+        let swap = SwapInfo {
+            is_initialized: true,
+            config_key,
+            circuit_breaker_bps: 100,
+            circuit_breaker_window_slots: 50,
+            circuit_breaker_price: Decimal::from(2u64),
+            circuit_breaker_slot: 1_000,
+            ..SwapInfo::default()
+        };
+        let mut swap_lamports = 0u64;
+        let mut swap_data = [0u8; SwapInfo::LEN];
+        swap.pack_into_slice(&mut swap_data);
+        let swap_info = AccountInfo::new(
+            &swap_key,
+            false,
+            true,
+            &mut swap_lamports,
+            &mut swap_data,
+            &program_id,
+            false,
+            0u64,
+        );
+
+        let signer_key = if option == 1u8 {
             Pubkey::new_unique()
         } else {
-            spl_token::id()
+            admin_key
         };
+        let mut admin_lamports = 0u64;
+        let mut admin_data = [0u8];
+        let admin_info = AccountInfo::new(
+            &signer_key,
+            true,
+            false,
+            &mut admin_lamports,
+            &mut admin_data,
+            &program_id,
+            false,
+            0u64,
+        );
 
-        let pyth_program_key = if option == 6u8 {
+        let accounts = [config_info, swap_info, admin_info];
+        let result =
+            set_circuit_breaker(&program_id, max_deviation_bps, window_slots, &accounts[..]);
+        let swap = SwapInfo::unpack(&accounts[1].data.borrow()).unwrap();
+        (result, swap)
+    }
+
+    #[test]
+    fn test_set_circuit_breaker() {
+        let (result, swap) = get_set_circuit_breaker_result(500u16, 150u64, 0u8);
+        assert!(result.is_ok());
+        assert_eq!(swap.circuit_breaker_bps, 500u16);
+        assert_eq!(swap.circuit_breaker_window_slots, 150u64);
+        assert_eq!(swap.circuit_breaker_price, Decimal::zero());
+
+        let (result, swap) = get_set_circuit_breaker_result(0u16, 0u64, 0u8);
+        assert!(result.is_ok());
+        assert_eq!(swap.circuit_breaker_bps, 0u16);
+
+        let (result, swap) = get_set_circuit_breaker_result(500u16, 150u64, 1u8);
+        assert_eq!(result, Err(ProgramError::from(SwapError::Unauthorized)));
+        assert_eq!(swap.circuit_breaker_bps, 100u16);
+
+        assert_eq!(
+            get_set_circuit_breaker_result(10_001u16, 150u64, 0u8).0,
+            Err(ProgramError::from(SwapError::InvalidInput))
+        );
+        assert_eq!(
+            get_set_circuit_breaker_result(500u16, 0u64, 0u8).0,
+            Err(ProgramError::from(SwapError::InvalidInput))
+        );
+    }
+
+    fn get_set_orderbook_program_result(option: u8) -> (ProgramResult, Pubkey) {
+        let program_id = Pubkey::new_unique();
+        let config_key = Pubkey::new_unique();
+        let admin_key = Pubkey::new_unique();
+        let orderbook_program_key = if option == 5u8 {
             Pubkey::new_unique()
         } else {
-            Pubkey::from_str(PYTH_PROGRAM_ID).unwrap()
+            Pubkey::from_str(OPENBOOK_DEX_PROGRAM_ID).unwrap()
         };
 
-        let fees = Fees {
-            ..Default::default()
+        let config = ConfigInfo {
+            version: if option == 2u8 { 0u8 } else { 1u8 },
+            bump_seed: 0u8,
+            admin_key: if option == 3u8 { config_key } else { admin_key },
+            deltafi_mint: Pubkey::new_unique(),
+            pyth_program_id: Pubkey::new_unique(),
+            deltafi_token: Pubkey::new_unique(),
+            ..ConfigInfo::default()
         };
 
-        let rewards = Rewards {
-            ..Default::default()
-        };
+        let mut lamports = 0u64;
+        let mut config_data = [0u8; ConfigInfo::LEN];
+        config.pack_into_slice(&mut config_data);
+        let config_info = AccountInfo::new(
+            &config_key,
+            false,
+            true,
+            &mut lamports,
+            &mut config_data,
+            if option == 1u8 {
+                &config_key
+            } else {
+                &program_id
+            },
+            false,
+            0u64,
+        );
 
-        let rent_val = Rent {
-            lamports_per_byte_year: 200_000u64,
-            exemption_threshold: 60f64,
-            burn_percent: 10u8,
-        };
+        let mut admin_lamports = 0u64;
+        let mut admin_data = [0u8];
+        let admin_info = AccountInfo::new(
+            &admin_key,
+            option != 4u8,
+            false,
+            &mut admin_lamports,
+            &mut admin_data,
+            &program_id,
+            false,
+            0u64,
+        );
+
+        let mut orderbook_lamports = 0u64;
+        let mut orderbook_data = [0u8];
+        let orderbook_program_info = AccountInfo::new(
+            &orderbook_program_key,
+            false,
+            false,
+            &mut orderbook_lamports,
+            &mut orderbook_data,
+            &program_id,
+            true,
+            0u64,
+        );
+
+        let accounts = [config_info, admin_info, orderbook_program_info];
+        let result = set_orderbook_program(&program_id, &accounts[..]);
+        let orderbook_program_id = ConfigInfo::unpack_unchecked(&accounts[0].data.borrow())
+            .unwrap()
+            .accepted_orderbook_program_id();
+
+        (result, orderbook_program_id)
+    }
+
+    #[test]
+    fn test_set_orderbook_program() {
+        let openbook_program_id = Pubkey::from_str(OPENBOOK_DEX_PROGRAM_ID).unwrap();
+        let serum_program_id = Pubkey::from_str(SERUM_DEX_V3_PROGRAM_ID).unwrap();
+        assert_eq!(
+            get_set_orderbook_program_result(0u8),
+            (Ok(()), openbook_program_id)
+        );
+        assert_eq!(
+            get_set_orderbook_program_result(1u8).0,
+            Err(ProgramError::from(SwapError::InvalidAccountOwner))
+        );
+        assert_eq!(
+            get_set_orderbook_program_result(2u8).0,
+            Err(ProgramError::UninitializedAccount)
+        );
+        assert_eq!(
+            get_set_orderbook_program_result(3u8),
+            (
+                Err(ProgramError::from(SwapError::Unauthorized)),
+                serum_program_id
+            )
+        );
+        assert_eq!(
+            get_set_orderbook_program_result(4u8).0,
+            Err(ProgramError::MissingRequiredSignature)
+        );
+        assert_eq!(
+            get_set_orderbook_program_result(5u8),
+            (
+                Err(ProgramError::from(SwapError::InvalidSerumProgramId)),
+                serum_program_id
+            )
+        );
+    }
 
-        let deltafi_mint = Mint {
-            mint_authority: solana_program::program_option::COption::Some(Pubkey::new_unique()),
-            supply: 0u64,
-            decimals: 0u8,
-            is_initialized: true,
-            freeze_authority: solana_program::program_option::COption::Some(Pubkey::new_unique()),
-        };
+    fn get_set_exchange_rate_adapter_result(
+        adapter_type: u8,
+        option: u8,
+    ) -> (ProgramResult, ExchangeRateAdapterType, Pubkey) {
+        let mut accounts = Vec::new();
+        let program_id = Pubkey::new_unique();
+        let config_key = Pubkey::new_unique();
+        let pool_key = Pubkey::new_unique();
+        let admin_key = Pubkey::new_unique();
+        let stake_pool_key = Pubkey::new_unique();
+        let stake_pool_program_id = Pubkey::from_str(SPL_STAKE_POOL_PROGRAM_ID).unwrap();
+        let pool_mint = Pubkey::new_unique();
 
-        let mut config_lamport = 9_500_000_000u64;
-        let config_initial = ConfigInfo {
-            version: 0u8,
+        let config = ConfigInfo {
+            version: if option == 3u8 { 0u8 } else { 1u8 },
             bump_seed: 0u8,
-            admin_key,
-            deltafi_mint: deltafi_mint_key,
-            pyth_program_id: pyth_program_key,
-            fees: fees.clone(),
-            rewards: rewards.clone(),
-            deltafi_token: deltafi_token_key,
+            admin_key: if option == 4u8 { config_key } else { admin_key },
+            deltafi_mint: Pubkey::new_unique(),
+            pyth_program_id: Pubkey::new_unique(),
+            deltafi_token: Pubkey::new_unique(),
             ..ConfigInfo::default()
         };
+
+        let mut swap = SwapInfo {
+            is_initialized: option != 6u8,
+            config_key: if option == 7u8 { pool_key } else { config_key },
+            swap_type: if option == 8u8 {
+                SwapType::Normal
+            } else {
+                SwapType::Stable
+            },
+            token_a_mint: pool_mint,
+            ..Default::default()
+        };
+        swap.set_exchange_rate_adapter(ExchangeRateAdapterType::SplStakePool, stake_pool_key);
+
+        let mut lamports = 0u64;
         let mut config_data = [0u8; ConfigInfo::LEN];
-        config_initial.pack_into_slice(&mut config_data);
-        let config_info = AccountInfo::new(
+        config.pack_into_slice(&mut config_data);
+        accounts.push(AccountInfo::new(
             &config_key,
-            true,
             false,
-            &mut config_lamport,
+            false,
+            &mut lamports,
             &mut config_data,
             if option == 1u8 {
-                &admin_key
+                &config_key
             } else {
                 &program_id
             },
             false,
             0u64,
-        );
+        ));
 
-        let mut rent_lamport = 2_000_000_000u64;
-        let mut rent_data = [0u8; mem::size_of::<Rent>()];
-        let mut rent_info = AccountInfo::new(
-            &rent_key,
+        let mut lamports = 0u64;
+        let mut swap_data = [0u8; SwapInfo::LEN];
+        swap.pack_into_slice(&mut swap_data);
+        accounts.push(AccountInfo::new(
+            &pool_key,
             false,
             false,
-            &mut rent_lamport,
-            &mut rent_data,
-            &program_id,
+            &mut lamports,
+            &mut swap_data,
+            if option == 2u8 {
+                &pool_key
+            } else {
+                &program_id
+            },
             false,
             0u64,
-        );
-        assert!(rent_val.to_account_info(&mut rent_info).is_some());
+        ));
 
-        let mut admin_lamports = 0u64;
+        let mut lamports = 0u64;
         let mut admin_data = [0u8];
-        let admin_info = AccountInfo::new(
+        accounts.push(AccountInfo::new(
             &admin_key,
-            option != 2u8,
+            option != 5u8,
             false,
-            &mut admin_lamports,
+            &mut lamports,
             &mut admin_data,
-            &market_authority_key,
+            &program_id,
             false,
             0u64,
-        );
+        ));
 
-        let mut market_authority_lamports = 0u64;
-        let mut market_authority_data = [0u8];
-        let market_authority_info = AccountInfo::new(
-            &market_authority_key,
+        // SPL stake pool account with the pool mint at offset 162
+        let mut lamports = 0u64;
+        let mut stake_pool_data = vec![0u8; 300];
+        stake_pool_data[0] = 1u8;
+        if option != 9u8 {
+            stake_pool_data[162..194].copy_from_slice(pool_mint.as_ref());
+        }
+        accounts.push(AccountInfo::new(
+            &stake_pool_key,
             false,
             false,
-            &mut market_authority_lamports,
-            &mut market_authority_data,
-            &market_authority_owner,
+            &mut lamports,
+            &mut stake_pool_data,
+            &stake_pool_program_id,
             false,
             0u64,
-        );
+        ));
 
-        let mut packed_mint_data = [0u8; Mint::LEN];
-        assert!(Mint::pack(deltafi_mint, &mut packed_mint_data).is_ok());
+        let result = set_exchange_rate_adapter(&program_id, adapter_type, &accounts[..]);
+        let swap = SwapInfo::unpack_unchecked(&accounts[1].data.borrow()).unwrap();
 
-        let mut mint_lamports = 0u64;
-        let mint_info = AccountInfo::new(
-            &deltafi_mint_key,
-            false,
-            false,
-            &mut mint_lamports,
-            &mut packed_mint_data,
-            &token_program_key,
-            false,
-            0u64,
+        (
+            result,
+            swap.exchange_rate_adapter,
+            *swap.exchange_rate_account(),
+        )
+    }
+
+    #[test]
+    fn test_set_exchange_rate_adapter() {
+        let (result, adapter_type, rate_account) = get_set_exchange_rate_adapter_result(1u8, 0u8);
+        assert_eq!(result, Ok(()));
+        assert_eq!(adapter_type, ExchangeRateAdapterType::SplStakePool);
+        assert_ne!(rate_account, Pubkey::default());
+        assert_eq!(
+            get_set_exchange_rate_adapter_result(0u8, 0u8),
+            (Ok(()), ExchangeRateAdapterType::None, Pubkey::default())
+        );
+        assert_eq!(
+            get_set_exchange_rate_adapter_result(2u8, 0u8).0,
+            Err(ProgramError::from(SwapError::InvalidInput))
+        );
+        assert_eq!(
+            get_set_exchange_rate_adapter_result(1u8, 1u8).0,
+            Err(ProgramError::from(SwapError::InvalidAccountOwner))
+        );
+        assert_eq!(
+            get_set_exchange_rate_adapter_result(1u8, 2u8).0,
+            Err(ProgramError::from(SwapError::InvalidAccountOwner))
+        );
+        assert_eq!(
+            get_set_exchange_rate_adapter_result(1u8, 3u8).0,
+            Err(ProgramError::UninitializedAccount)
+        );
+        assert_eq!(
+            get_set_exchange_rate_adapter_result(1u8, 4u8).0,
+            Err(ProgramError::from(SwapError::Unauthorized))
+        );
+        assert_eq!(
+            get_set_exchange_rate_adapter_result(1u8, 5u8).0,
+            Err(ProgramError::MissingRequiredSignature)
+        );
+        assert_eq!(
+            get_set_exchange_rate_adapter_result(1u8, 6u8).0,
+            Err(ProgramError::UninitializedAccount)
         );
+        assert_eq!(
+            get_set_exchange_rate_adapter_result(1u8, 7u8).0,
+            Err(ProgramError::from(SwapError::InvalidMarketConfig))
+        );
+        assert_eq!(
+            get_set_exchange_rate_adapter_result(1u8, 8u8).0,
+            Err(ProgramError::from(SwapError::IncorrectSwapType))
+        );
+        assert_eq!(
+            get_set_exchange_rate_adapter_result(1u8, 9u8).0,
+            Err(ProgramError::from(SwapError::InvalidExchangeRateAccount))
+        );
+    }
 
-        let mut token_program_lamports = 0u64;
-        let mut token_program_data = [0u8];
-        let token_program_info = AccountInfo::new(
-            &token_program_key,
-            true,
+    fn get_set_oracle_params_result(
+        oracle_params: OracleParams,
+        option: u8,
+    ) -> (ProgramResult, OracleParams) {
+        let mut accounts = Vec::new();
+        let program_id = Pubkey::new_unique();
+        let config_key = Pubkey::new_unique();
+        let pool_key = Pubkey::new_unique();
+        let admin_key = Pubkey::new_unique();
+
+        let config = ConfigInfo {
+            version: if option == 3u8 { 0u8 } else { 1u8 },
+            bump_seed: 0u8,
+            admin_key: if option == 4u8 { config_key } else { admin_key },
+            deltafi_mint: Pubkey::new_unique(),
+            pyth_program_id: Pubkey::new_unique(),
+            deltafi_token: Pubkey::new_unique(),
+            ..ConfigInfo::default()
+        };
+
+        let swap = SwapInfo {
+            is_initialized: option != 6u8,
+            config_key: if option == 7u8 { pool_key } else { config_key },
+            ..Default::default()
+        };
+
+        let mut lamports = 0u64;
+        let mut config_data = [0u8; ConfigInfo::LEN];
+        config.pack_into_slice(&mut config_data);
+        accounts.push(AccountInfo::new(
+            &config_key,
             false,
-            &mut token_program_lamports,
-            &mut token_program_data,
-            &admin_key,
+            false,
+            &mut lamports,
+            &mut config_data,
+            if option == 1u8 {
+                &config_key
+            } else {
+                &program_id
+            },
             false,
             0u64,
-        );
+        ));
 
-        let mut pyth_program_lamports = 0u64;
-        let mut pyth_program_data = [0u8];
-        let pyth_program_info = AccountInfo::new(
-            &pyth_program_key,
-            true,
+        let mut lamports = 0u64;
+        let mut swap_data = [0u8; SwapInfo::LEN];
+        swap.pack_into_slice(&mut swap_data);
+        accounts.push(AccountInfo::new(
+            &pool_key,
             false,
-            &mut pyth_program_lamports,
-            &mut pyth_program_data,
-            &admin_key,
             false,
-            0u64,
-        );
-
-        let mut token_lamports = 0u64;
-        let mut deltafi_token_account_data = [0u8; 165];
-        let deltafi_token_account = spl_token::state::Account {
-            mint: if option == 4u8 {
-                Pubkey::new_unique()
-            } else {
-                deltafi_mint_key
-            },
-            owner: if option == 5u8 {
-                Pubkey::new_unique()
+            &mut lamports,
+            &mut swap_data,
+            if option == 2u8 {
+                &pool_key
             } else {
-                market_authority_key
+                &program_id
             },
-            amount: 0,
-            delegate: solana_program::program_option::COption::None,
-            state: spl_token::state::AccountState::Initialized,
-            is_native: solana_program::program_option::COption::None,
-            delegated_amount: 0,
-            close_authority: solana_program::program_option::COption::None,
-        };
-        deltafi_token_account.pack_into_slice(&mut deltafi_token_account_data);
-        let deltafi_token_info = AccountInfo::new(
-            &deltafi_token_key,
-            false,
-            false,
-            &mut token_lamports,
-            &mut deltafi_token_account_data,
-            &token_program_key,
             false,
             0u64,
-        );
+        ));
 
-        initialize(
-            &program_id,
-            &fees,
-            &rewards,
-            &[
-                config_info,
-                market_authority_info,
-                mint_info,
-                admin_info,
-                rent_info,
-                token_program_info,
-                pyth_program_info,
-                deltafi_token_info,
-            ],
-        )
+        let mut lamports = 0u64;
+        let mut admin_data = [0u8];
+        accounts.push(AccountInfo::new(
+            &admin_key,
+            option != 5u8,
+            false,
+            &mut lamports,
+            &mut admin_data,
+            &program_id,
+            false,
+            0u64,
+        ));
+
+        let result = set_oracle_params(&program_id, &oracle_params, &accounts[..]);
+        let oracle_params = SwapInfo::unpack_unchecked(&accounts[1].data.borrow())
+            .unwrap()
+            .oracle_params;
+
+        (result, oracle_params)
     }
 
     #[test]
-    fn test_initialize() {
-        assert_eq!(get_initialize_result(0u8), Ok(()));
+    fn test_set_oracle_params() {
+        let oracle_params = OracleParams {
+            stale_slots: 25u16,
+            max_confidence_bps: 500u16,
+            min_publishers: 5u8,
+        };
         assert_eq!(
-            get_initialize_result(1u8),
+            get_set_oracle_params_result(oracle_params, 0u8),
+            (Ok(()), oracle_params)
+        );
+        assert_eq!(
+            get_set_oracle_params_result(OracleParams::default(), 0u8),
+            (Ok(()), OracleParams::default())
+        );
+        assert_eq!(
+            get_set_oracle_params_result(
+                OracleParams {
+                    max_confidence_bps: 10_001u16,
+                    ..oracle_params
+                },
+                0u8
+            ),
+            (
+                Err(ProgramError::from(SwapError::InvalidInput)),
+                OracleParams::default()
+            )
+        );
+        assert_eq!(
+            get_set_oracle_params_result(oracle_params, 1u8).0,
             Err(ProgramError::from(SwapError::InvalidAccountOwner))
         );
         assert_eq!(
-            get_initialize_result(2u8),
-            Err(ProgramError::MissingRequiredSignature)
+            get_set_oracle_params_result(oracle_params, 2u8).0,
+            Err(ProgramError::from(SwapError::InvalidAccountOwner))
         );
         assert_eq!(
-            get_initialize_result(3u8),
-            Err(ProgramError::from(SwapError::InvalidProgramAddress))
+            get_set_oracle_params_result(oracle_params, 3u8).0,
+            Err(ProgramError::UninitializedAccount)
         );
         assert_eq!(
-            get_initialize_result(4u8),
-            Err(ProgramError::from(SwapError::IncorrectMint))
+            get_set_oracle_params_result(oracle_params, 4u8),
+            (
+                Err(ProgramError::from(SwapError::Unauthorized)),
+                OracleParams::default()
+            )
         );
         assert_eq!(
-            get_initialize_result(5u8),
-            Err(ProgramError::from(SwapError::InvalidOwner))
+            get_set_oracle_params_result(oracle_params, 5u8).0,
+            Err(ProgramError::MissingRequiredSignature)
         );
         assert_eq!(
-            get_initialize_result(6u8),
-            Err(ProgramError::from(SwapError::InvalidPythProgramId))
+            get_set_oracle_params_result(oracle_params, 6u8).0,
+            Err(ProgramError::UninitializedAccount)
         );
         assert_eq!(
-            get_initialize_result(7u8),
-            Err(ProgramError::IncorrectProgramId)
+            get_set_oracle_params_result(oracle_params, 7u8).0,
+            Err(ProgramError::from(SwapError::InvalidMarketConfig))
         );
     }
 
-    fn get_pause_result(option: u8) -> ProgramResult {
+    fn get_set_oracle_accounts_result(option: u8) -> (ProgramResult, SwapInfo, [Pubkey; 2]) {
         let mut accounts = Vec::new();
         let program_id = Pubkey::new_unique();
         let config_key = Pubkey::new_unique();
-        let swap_key = Pubkey::new_unique();
+        let pool_key = Pubkey::new_unique();
         let admin_key = Pubkey::new_unique();
+        let pyth_program_id = Pubkey::from_str(PYTH_PROGRAM_ID).unwrap();
+        let pyth_keys: Vec<Pubkey> = (0..4).map(|_| Pubkey::new_unique()).collect();
+        let serum_keys: Vec<Pubkey> = (0..3).map(|_| Pubkey::new_unique()).collect();
 
         let config = ConfigInfo {
             version: 1u8,
-            bump_seed: 0u8,
-            admin_key: if option == 3u8 { config_key } else { admin_key },
-            deltafi_mint: Pubkey::new_unique(),
-            pyth_program_id: Pubkey::new_unique(),
-            fees: Fees {
-                ..Default::default()
-            },
-            rewards: Rewards {
-                ..Default::default()
-            },
-            deltafi_token: Pubkey::new_unique(),
+            admin_key,
+            pyth_program_id,
             ..ConfigInfo::default()
         };
 
-        let mut swap = SwapInfo {
+        let swap = SwapInfo {
+            is_initialized: true,
+            swap_type: if option == 3u8 {
+                SwapType::Stable
+            } else {
+                SwapType::Normal
+            },
+            config_key,
+            oracle_priority_flags: if option >= 4u8 {
+                OraclePriorityFlag::SERUM_ONLY.bits()
+            } else {
+                OraclePriorityFlag::PYTH_ONLY.bits()
+            },
+            pyth_a: Pubkey::new_unique(),
+            pyth_b: Pubkey::new_unique(),
             ..Default::default()
         };
-        if option == 6u8 {
-            swap.config_key = swap_key;
-        } else {
-            swap.config_key = config_key;
-        }
-        if option == 5u8 {
-            swap.is_initialized = false;
-        } else {
-            swap.is_initialized = true;
-        }
 
         let mut lamports = 0u64;
         let mut config_data = [0u8; ConfigInfo::LEN];
@@ -789,11 +6205,7 @@ mod tests {
             false,
             &mut lamports,
             &mut config_data,
-            if option == 1u8 {
-                &config_key
-            } else {
-                &program_id
-            },
+            &program_id,
             false,
             0u64,
         ));
@@ -802,25 +6214,26 @@ mod tests {
         let mut swap_data = [0u8; SwapInfo::LEN];
         swap.pack_into_slice(&mut swap_data);
         accounts.push(AccountInfo::new(
-            &swap_key,
-            false,
+            &pool_key,
             false,
+            true,
             &mut lamports,
             &mut swap_data,
-            if option == 2u8 {
-                &swap_key
-            } else {
-                &program_id
-            },
+            &program_id,
             false,
             0u64,
         ));
 
+        let signer_key = if option == 1u8 {
+            Pubkey::new_unique()
+        } else {
+            admin_key
+        };
         let mut lamports = 0u64;
         let mut admin_data = [0u8];
         accounts.push(AccountInfo::new(
-            &admin_key,
-            option != 4u8,
+            &signer_key,
+            true,
             false,
             &mut lamports,
             &mut admin_data,
@@ -829,40 +6242,150 @@ mod tests {
             0u64,
         ));
 
-        pause(&program_id, &accounts[..])
+        // Products of the base and quote tokens pointing to their new price accounts
+        let mut products: Vec<pyth::Product> = [pyth_keys[1], pyth_keys[3]]
+            .iter()
+            .map(|price_key| pyth::Product {
+                magic: pyth::MAGIC,
+                ver: pyth::VERSION_2,
+                atype: pyth::AccountType::Product as u32,
+                size: 32u32,
+                px_acc: pyth::AccKey {
+                    val: if option == 2u8 {
+                        [0u8; 32]
+                    } else {
+                        price_key.to_bytes()
+                    },
+                },
+                attr: [0u8; pyth::PROD_ATTR_SIZE],
+            })
+            .collect();
+        let mut pyth_lamports = [0u64; 4];
+        let mut price_data = [[0u8; 1]; 2];
+        let (product_a, product_b) = products.split_at_mut(1);
+        let (price_a, price_b) = price_data.split_at_mut(1);
+        let pyth_data: Vec<&mut [u8]> = vec![
+            bytemuck::bytes_of_mut(&mut product_a[0]),
+            &mut price_a[0],
+            bytemuck::bytes_of_mut(&mut product_b[0]),
+            &mut price_b[0],
+        ];
+        for ((key, lamports), data) in pyth_keys
+            .iter()
+            .zip(pyth_lamports.iter_mut())
+            .zip(pyth_data)
+        {
+            accounts.push(AccountInfo::new(
+                key,
+                false,
+                false,
+                lamports,
+                data,
+                &pyth_program_id,
+                false,
+                0u64,
+            ));
+        }
+
+        // The serum market is not checked for a pyth only pool
+        let mut serum_lamports = [0u64; 3];
+        let mut serum_data = [[0u8; 1]; 3];
+        for ((key, lamports), data) in serum_keys
+            .iter()
+            .zip(serum_lamports.iter_mut())
+            .zip(serum_data.iter_mut())
+        {
+            accounts.push(AccountInfo::new(
+                key,
+                false,
+                false,
+                lamports,
+                data,
+                &program_id,
+                false,
+                0u64,
+            ));
+        }
+
+        // Moving the serum only pool to pyth only or to a pushed price, or keeping it serum
+        // only with serum market accounts of another program
+        let oracle_priority_flags = match option {
+            4u8 => Some(OraclePriorityFlag::PYTH_ONLY.bits()),
+            5u8 => Some(OraclePriorityFlag::SERUM_ONLY.bits()),
+            6u8 => Some(0b1000_0000u8),
+            7u8 => Some(OraclePriorityFlag::MANUAL.bits()),
+            _ => None,
+        };
+        let result = set_oracle_accounts(&program_id, oracle_priority_flags, &accounts[..]);
+        let swap = SwapInfo::unpack_unchecked(&accounts[1].data.borrow()).unwrap();
+        (result, swap, [pyth_keys[1], pyth_keys[3]])
     }
 
     #[test]
-    fn test_pause() {
-        assert!(get_pause_result(0u8).is_ok());
+    fn test_set_oracle_accounts() {
+        let (result, swap, [pyth_a, pyth_b]) = get_set_oracle_accounts_result(0u8);
+        assert_eq!(result, Ok(()));
+        assert_eq!(swap.pyth_a, pyth_a);
+        assert_eq!(swap.pyth_b, pyth_b);
+        assert_eq!(swap.serum_combined_address, Pubkey::default());
+
+        let (result, swap, [pyth_a, _]) = get_set_oracle_accounts_result(1u8);
+        assert_eq!(result, Err(ProgramError::from(SwapError::Unauthorized)));
+        assert_ne!(swap.pyth_a, pyth_a);
+        let (result, swap, [pyth_a, _]) = get_set_oracle_accounts_result(2u8);
         assert_eq!(
-            get_pause_result(1u8),
-            Err(ProgramError::from(SwapError::InvalidAccountOwner))
+            result,
+            Err(ProgramError::from(SwapError::InvalidPythConfig))
         );
+        assert_ne!(swap.pyth_a, pyth_a);
         assert_eq!(
-            get_pause_result(2u8),
-            Err(ProgramError::from(SwapError::InvalidAccountOwner))
+            get_set_oracle_accounts_result(3u8).0,
+            Err(ProgramError::from(SwapError::IncorrectSwapType))
         );
+    }
+
+    #[test]
+    fn test_set_oracle_priority() {
+        let (result, swap, [pyth_a, _]) = get_set_oracle_accounts_result(4u8);
+        assert_eq!(result, Ok(()));
+        assert_eq!(swap.pyth_a, pyth_a);
         assert_eq!(
-            get_pause_result(3u8),
-            Err(ProgramError::from(SwapError::Unauthorized))
+            swap.oracle_priority_flags,
+            OraclePriorityFlag::PYTH_ONLY.bits()
         );
+        let (result, swap, [pyth_a, _]) = get_set_oracle_accounts_result(5u8);
         assert_eq!(
-            get_pause_result(4u8),
-            Err(ProgramError::MissingRequiredSignature)
+            result,
+            Err(ProgramError::from(SwapError::InvalidSerumProgramId))
         );
+        assert_ne!(swap.pyth_a, pyth_a);
         assert_eq!(
-            get_pause_result(5u8),
-            Err(ProgramError::UninitializedAccount)
+            swap.oracle_priority_flags,
+            OraclePriorityFlag::SERUM_ONLY.bits()
         );
         assert_eq!(
-            get_pause_result(6u8),
-            Err(ProgramError::from(SwapError::InvalidMarketConfig))
+            get_set_oracle_accounts_result(6u8).0,
+            Err(ProgramError::from(SwapError::UnsupportedOraclePriority))
+        );
+
+        // A pushed price reads neither the pyth nor the serum accounts
+        let (result, swap, _) = get_set_oracle_accounts_result(7u8);
+        assert_eq!(result, Ok(()));
+        assert_eq!(
+            swap.oracle_priority_flags,
+            OraclePriorityFlag::MANUAL.bits()
         );
+        assert_eq!(swap.pyth_a, Pubkey::default());
+        assert_eq!(swap.pyth_b, Pubkey::default());
+        assert_eq!(swap.serum_combined_address, Pubkey::default());
     }
 
-    fn get_unpause_result(option: u8) -> ProgramResult {
-        let mut accounts = Vec::new();
+    fn get_set_price_setter_result(
+        price_setter: Pubkey,
+        max_change_bps: u16,
+        stale_slots: u64,
+        option: u8,
+    ) -> (ProgramResult, SwapInfo) {
         let program_id = Pubkey::new_unique();
         let config_key = Pubkey::new_unique();
         let swap_key = Pubkey::new_unique();
@@ -870,195 +6393,336 @@ mod tests {
 
         let config = ConfigInfo {
             version: 1u8,
-            bump_seed: 0u8,
-            admin_key: if option == 3u8 { config_key } else { admin_key },
-            deltafi_mint: Pubkey::new_unique(),
-            pyth_program_id: Pubkey::new_unique(),
-            fees: Fees {
-                ..Default::default()
-            },
-            rewards: Rewards {
-                ..Default::default()
+            admin_key,
+            ..ConfigInfo::default()
+        };
+
+        let mut config_lamports = 0u64;
+        let mut config_data = [0u8; ConfigInfo::LEN];
+        config.pack_into_slice(&mut config_data);
+        let config_info = AccountInfo::new(
+            &config_key,
+            false,
+            false,
+            &mut config_lamports,
+            &mut config_data,
+            &program_id,
+            false,
+            0u64,
+        );
+
+        let swap = SwapInfo {
+            is_initialized: true,
+            swap_type: if option == 2u8 {
+                SwapType::Stable
+            } else {
+                SwapType::Normal
             },
-            deltafi_token: Pubkey::new_unique(),
+            config_key,
+            oracle_priority_flags: OraclePriorityFlag::MANUAL.bits(),
+            price_setter: Pubkey::new_from_array([7u8; 32]),
+            manual_max_change_bps: 100,
+            manual_stale_slots: 50,
+            manual_price: Decimal::from(5u64),
+            manual_price_slot: 1_000,
+            ..SwapInfo::default()
+        };
+        let mut swap_lamports = 0u64;
+        let mut swap_data = [0u8; SwapInfo::LEN];
+        swap.pack_into_slice(&mut swap_data);
+        let swap_info = AccountInfo::new(
+            &swap_key,
+            false,
+            true,
+            &mut swap_lamports,
+            &mut swap_data,
+            &program_id,
+            false,
+            0u64,
+        );
+
+        let signer_key = if option == 1u8 {
+            Pubkey::new_unique()
+        } else {
+            admin_key
+        };
+        let mut admin_lamports = 0u64;
+        let mut admin_data = [0u8];
+        let admin_info = AccountInfo::new(
+            &signer_key,
+            true,
+            false,
+            &mut admin_lamports,
+            &mut admin_data,
+            &program_id,
+            false,
+            0u64,
+        );
+
+        let mut price_setter_lamports = 0u64;
+        let mut price_setter_data = [0u8];
+        let price_setter_info = AccountInfo::new(
+            &price_setter,
+            false,
+            false,
+            &mut price_setter_lamports,
+            &mut price_setter_data,
+            &program_id,
+            false,
+            0u64,
+        );
+
+        let accounts = [config_info, swap_info, admin_info, price_setter_info];
+        let result = set_price_setter(&program_id, max_change_bps, stale_slots, &accounts[..]);
+        let swap = SwapInfo::unpack(&accounts[1].data.borrow()).unwrap();
+        (result, swap)
+    }
+
+    #[test]
+    fn test_set_price_setter() {
+        // The same price setter keeps its pushed price
+        let price_setter = Pubkey::new_from_array([7u8; 32]);
+        let (result, swap) = get_set_price_setter_result(price_setter, 300u16, 150u64, 0u8);
+        assert!(result.is_ok());
+        assert_eq!(swap.manual_max_change_bps, 300u16);
+        assert_eq!(swap.manual_stale_slots, 150u64);
+        assert_eq!(swap.manual_price, Decimal::from(5u64));
+
+        // A new price setter starts from the last market price of the pool
+        let price_setter = Pubkey::new_unique();
+        let (result, swap) = get_set_price_setter_result(price_setter, 300u16, 150u64, 0u8);
+        assert!(result.is_ok());
+        assert_eq!(swap.price_setter, price_setter);
+        assert_eq!(swap.manual_price, Decimal::zero());
+        assert_eq!(swap.manual_price_slot, 0u64);
+
+        let (result, swap) = get_set_price_setter_result(Pubkey::default(), 0u16, 0u64, 0u8);
+        assert!(result.is_ok());
+        assert_eq!(swap.price_setter, Pubkey::default());
+
+        let (result, swap) = get_set_price_setter_result(price_setter, 300u16, 150u64, 1u8);
+        assert_eq!(result, Err(ProgramError::from(SwapError::Unauthorized)));
+        assert_eq!(swap.manual_max_change_bps, 100u16);
+        assert_eq!(
+            get_set_price_setter_result(price_setter, 300u16, 150u64, 2u8).0,
+            Err(ProgramError::from(SwapError::IncorrectSwapType))
+        );
+
+        for (max_change_bps, stale_slots) in [(0u16, 150u64), (10_001u16, 150u64), (300u16, 0u64)] {
+            assert_eq!(
+                get_set_price_setter_result(price_setter, max_change_bps, stale_slots, 0u8).0,
+                Err(ProgramError::from(SwapError::InvalidInput))
+            );
+        }
+    }
+
+    fn get_set_price_ema_window_result(window_slots: u64, option: u8) -> (ProgramResult, SwapInfo) {
+        let program_id = Pubkey::new_unique();
+        let config_key = Pubkey::new_unique();
+        let swap_key = Pubkey::new_unique();
+        let admin_key = Pubkey::new_unique();
+
+        let config = ConfigInfo {
+            version: 1u8,
+            admin_key,
             ..ConfigInfo::default()
         };
 
+        let mut config_lamports = 0u64;
+        let mut config_data = [0u8; ConfigInfo::LEN];
+        config.pack_into_slice(&mut config_data);
+        let config_info = AccountInfo::new(
+            &config_key,
+            false,
+            false,
+            &mut config_lamports,
+            &mut config_data,
+            &program_id,
+            false,
+            0u64,
+        );
+
         let mut swap = SwapInfo {
-            ..Default::default()
+            is_initialized: true,
+            swap_type: if option == 2u8 {
+                SwapType::Stable
+            } else {
+                SwapType::Normal
+            },
+            config_key,
+            ema_window_slots: 50,
+            ..SwapInfo::default()
+        };
+        swap.pool_state.ema_market_price = Decimal::from(5u64);
+        swap.pool_state.ema_slot = 1_000;
+        let mut swap_lamports = 0u64;
+        let mut swap_data = [0u8; SwapInfo::LEN];
+        swap.pack_into_slice(&mut swap_data);
+        let swap_info = AccountInfo::new(
+            &swap_key,
+            false,
+            true,
+            &mut swap_lamports,
+            &mut swap_data,
+            &program_id,
+            false,
+            0u64,
+        );
+
+        let signer_key = if option == 1u8 {
+            Pubkey::new_unique()
+        } else {
+            admin_key
+        };
+        let mut admin_lamports = 0u64;
+        let mut admin_data = [0u8];
+        let admin_info = AccountInfo::new(
+            &signer_key,
+            true,
+            false,
+            &mut admin_lamports,
+            &mut admin_data,
+            &program_id,
+            false,
+            0u64,
+        );
+
+        let accounts = [config_info, swap_info, admin_info];
+        let result = set_price_ema_window(&program_id, window_slots, &accounts[..]);
+        let swap = SwapInfo::unpack(&accounts[1].data.borrow()).unwrap();
+        (result, swap)
+    }
+
+    #[test]
+    fn test_set_price_ema_window() {
+        // The average restarts from the next oracle price
+        let (result, swap) = get_set_price_ema_window_result(150u64, 0u8);
+        assert!(result.is_ok());
+        assert_eq!(swap.ema_window_slots, 150u64);
+        assert_eq!(swap.pool_state.ema_market_price, Decimal::zero());
+
+        let (result, swap) = get_set_price_ema_window_result(0u64, 0u8);
+        assert!(result.is_ok());
+        assert_eq!(swap.ema_window_slots, 0u64);
+
+        let (result, swap) = get_set_price_ema_window_result(150u64, 1u8);
+        assert_eq!(result, Err(ProgramError::from(SwapError::Unauthorized)));
+        assert_eq!(swap.ema_window_slots, 50u64);
+        assert_eq!(swap.pool_state.ema_market_price, Decimal::from(5u64));
+        assert_eq!(
+            get_set_price_ema_window_result(150u64, 2u8).0,
+            Err(ProgramError::from(SwapError::IncorrectSwapType))
+        );
+    }
+
+    fn get_set_skim_surplus_result(skim_surplus: bool, option: u8) -> (ProgramResult, SwapInfo) {
+        let program_id = Pubkey::new_unique();
+        let config_key = Pubkey::new_unique();
+        let swap_key = Pubkey::new_unique();
+        let admin_key = Pubkey::new_unique();
+
+        let config = ConfigInfo {
+            version: 1u8,
+            admin_key,
+            ..ConfigInfo::default()
         };
-        if option == 6u8 {
-            swap.config_key = swap_key;
-        } else {
-            swap.config_key = config_key;
-        }
-        if option == 5u8 {
-            swap.is_initialized = false;
-        } else {
-            swap.is_initialized = true;
-        }
 
-        let mut lamports = 0u64;
+        let mut config_lamports = 0u64;
         let mut config_data = [0u8; ConfigInfo::LEN];
         config.pack_into_slice(&mut config_data);
-        accounts.push(AccountInfo::new(
+        let config_info = AccountInfo::new(
             &config_key,
             false,
             false,
-            &mut lamports,
+            &mut config_lamports,
             &mut config_data,
-            if option == 1u8 {
-                &config_key
-            } else {
-                &program_id
-            },
+            &program_id,
             false,
             0u64,
-        ));
+        );
 
-        let mut lamports = 0u64;
+        let swap = SwapInfo {
+            is_initialized: true,
+            config_key,
+            ..SwapInfo::default()
+        };
+        let mut swap_lamports = 0u64;
         let mut swap_data = [0u8; SwapInfo::LEN];
         swap.pack_into_slice(&mut swap_data);
-        accounts.push(AccountInfo::new(
+        let swap_info = AccountInfo::new(
             &swap_key,
             false,
-            false,
-            &mut lamports,
+            true,
+            &mut swap_lamports,
             &mut swap_data,
-            if option == 2u8 {
-                &swap_key
-            } else {
-                &program_id
-            },
+            &program_id,
             false,
             0u64,
-        ));
+        );
 
-        let mut lamports = 0u64;
+        let signer_key = if option == 1u8 {
+            Pubkey::new_unique()
+        } else {
+            admin_key
+        };
+        let mut admin_lamports = 0u64;
         let mut admin_data = [0u8];
-        accounts.push(AccountInfo::new(
-            &admin_key,
-            option != 4u8,
+        let admin_info = AccountInfo::new(
+            &signer_key,
+            true,
             false,
-            &mut lamports,
+            &mut admin_lamports,
             &mut admin_data,
             &program_id,
             false,
             0u64,
-        ));
+        );
 
-        unpause(&program_id, &accounts[..])
+        let accounts = [config_info, swap_info, admin_info];
+        let result = set_skim_surplus(&program_id, skim_surplus, &accounts[..]);
+        let swap = SwapInfo::unpack(&accounts[1].data.borrow()).unwrap();
+        (result, swap)
     }
 
     #[test]
-    fn test_unpause() {
-        assert!(get_unpause_result(0u8).is_ok());
-        assert_eq!(
-            get_unpause_result(1u8),
-            Err(ProgramError::from(SwapError::InvalidAccountOwner))
-        );
-        assert_eq!(
-            get_unpause_result(2u8),
-            Err(ProgramError::from(SwapError::InvalidAccountOwner))
-        );
-        assert_eq!(
-            get_unpause_result(3u8),
-            Err(ProgramError::from(SwapError::Unauthorized))
-        );
-        assert_eq!(
-            get_unpause_result(4u8),
-            Err(ProgramError::MissingRequiredSignature)
-        );
-        assert_eq!(
-            get_unpause_result(5u8),
-            Err(ProgramError::UninitializedAccount)
-        );
-        assert_eq!(
-            get_unpause_result(6u8),
-            Err(ProgramError::from(SwapError::InvalidMarketConfig))
-        );
+    fn test_set_skim_surplus() {
+        let (result, swap) = get_set_skim_surplus_result(true, 0u8);
+        assert!(result.is_ok());
+        assert!(swap.skim_surplus);
+
+        let (result, swap) = get_set_skim_surplus_result(true, 1u8);
+        assert_eq!(result, Err(ProgramError::from(SwapError::Unauthorized)));
+        assert!(!swap.skim_surplus);
     }
 
-    fn get_set_fee_account_result(option: u8) -> ProgramResult {
-        let mut accounts = Vec::new();
-        let nonce = 8u8;
-        let program_id = Pubkey::from_str("5NjW2CAV6MBQYxpL4oK2CESrpdj6tkcvxP3iigAgrHyR").unwrap();
-        let config_key = Pubkey::from_str("BuP3jEYfnTCfB4UqQk9L37k2vaXsNuVsbWxrYbGDmL6s").unwrap();
-        let swap_key = Pubkey::from_str("CWWiYh5Rpyf5rHZbzHYM6TT6FfcojTR2rKjr5M4BFa3y").unwrap();
-        let admin_key = Pubkey::from_str("CaR8CBoWip9oFygaVnonzPCKWgBaSQWwSC3jaYNAyNiK").unwrap();
-        let _authority_key =
-            Pubkey::create_program_address(&[swap_key.as_ref(), &[nonce]], &program_id);
-        assert!(_authority_key.is_ok());
-
-        let authority_key = if option == 7u8 {
-            Pubkey::new_unique()
-        } else {
-            _authority_key.unwrap()
-        };
-        let new_admin_fee_key = Pubkey::new_unique();
-        let token_program_key = Pubkey::new_unique();
+    fn get_set_market_maker_result(
+        market_maker_key: Pubkey,
+        index: u8,
+        option: u8,
+    ) -> (ProgramResult, [Pubkey; MAX_MARKET_MAKERS]) {
+        let program_id = Pubkey::new_unique();
+        let config_key = Pubkey::new_unique();
+        let admin_key = Pubkey::new_unique();
+        let existing_market_maker = Pubkey::new_from_array([9u8; 32]);
 
         let config = ConfigInfo {
-            version: 1u8,
+            version: if option == 2u8 { 0u8 } else { 1u8 },
             bump_seed: 0u8,
             admin_key: if option == 3u8 { config_key } else { admin_key },
             deltafi_mint: Pubkey::new_unique(),
             pyth_program_id: Pubkey::new_unique(),
-            fees: Fees {
-                ..Default::default()
-            },
-            rewards: Rewards {
-                ..Default::default()
-            },
             deltafi_token: Pubkey::new_unique(),
+            market_makers: [existing_market_maker, Pubkey::default()],
             ..ConfigInfo::default()
         };
 
-        let mut swap = SwapInfo {
-            ..Default::default()
-        };
-        if option == 6u8 {
-            swap.config_key = swap_key;
-        } else {
-            swap.config_key = config_key;
-        }
-        if option == 5u8 {
-            swap.is_initialized = false;
-        } else {
-            swap.is_initialized = true;
-        }
-        swap.token_a_mint = Pubkey::new_unique();
-        swap.token_b_mint = Pubkey::new_unique();
-        swap.nonce = nonce;
-
-        let mut account = Account {
-            ..Default::default()
-        };
-        account.owner = if option == 10u8 {
-            program_id
-        } else {
-            authority_key
-        };
-        account.mint = if option == 11u8 {
-            program_id
-        } else if option == 12u8 {
-            swap.token_b_mint
-        } else {
-            swap.token_a_mint
-        };
-        account.state = if option == 9u8 {
-            AccountState::Uninitialized
-        } else {
-            AccountState::Initialized
-        };
-
         let mut lamports = 0u64;
         let mut config_data = [0u8; ConfigInfo::LEN];
         config.pack_into_slice(&mut config_data);
-        accounts.push(AccountInfo::new(
+        let config_info = AccountInfo::new(
             &config_key,
             false,
-            false,
+            true,
             &mut lamports,
             &mut config_data,
             if option == 1u8 {
@@ -1068,180 +6732,109 @@ mod tests {
             },
             false,
             0u64,
-        ));
-
-        let mut lamports = 0u64;
-        let mut swap_data = [0u8; SwapInfo::LEN];
-        swap.pack_into_slice(&mut swap_data);
-        accounts.push(AccountInfo::new(
-            &swap_key,
-            false,
-            false,
-            &mut lamports,
-            &mut swap_data,
-            if option == 2u8 {
-                &swap_key
-            } else {
-                &program_id
-            },
-            false,
-            0u64,
-        ));
-
-        let mut lamports = 0u64;
-        let mut authority_data = [0u8];
-        accounts.push(AccountInfo::new(
-            &authority_key,
-            false,
-            false,
-            &mut lamports,
-            &mut authority_data,
-            &program_id,
-            false,
-            0u64,
-        ));
+        );
 
-        let mut lamports = 0u64;
+        let mut admin_lamports = 0u64;
         let mut admin_data = [0u8];
-        accounts.push(AccountInfo::new(
+        let admin_info = AccountInfo::new(
             &admin_key,
             option != 4u8,
             false,
-            &mut lamports,
+            &mut admin_lamports,
             &mut admin_data,
             &program_id,
             false,
             0u64,
-        ));
-
-        let mut lamports = 0u64;
-        let mut new_fee_account_data = [0u8; Account::LEN];
-        account.pack_into_slice(&mut new_fee_account_data);
-        accounts.push(AccountInfo::new(
-            &new_admin_fee_key,
-            false,
-            false,
-            &mut lamports,
-            &mut new_fee_account_data,
-            if option == 8u8 {
-                &program_id
-            } else {
-                &token_program_key
-            },
-            false,
-            0u64,
-        ));
+        );
 
-        let mut lamports = 0u64;
-        let mut token_swap_data = [0u8];
-        accounts.push(AccountInfo::new(
-            &token_program_key,
+        let mut market_maker_lamports = 0u64;
+        let mut market_maker_data = [0u8];
+        let market_maker_info = AccountInfo::new(
+            &market_maker_key,
             false,
             false,
-            &mut lamports,
-            &mut token_swap_data,
+            &mut market_maker_lamports,
+            &mut market_maker_data,
             &program_id,
             false,
             0u64,
-        ));
+        );
 
-        set_fee_account(&program_id, &accounts[..])
+        let accounts = [config_info, admin_info, market_maker_info];
+        let result = set_market_maker(&program_id, index, &accounts[..]);
+        let market_makers = ConfigInfo::unpack_unchecked(&accounts[0].data.borrow())
+            .unwrap()
+            .market_makers;
+
+        (result, market_makers)
     }
 
     #[test]
-    fn test_set_fee_account() {
-        assert!(get_set_fee_account_result(0u8).is_ok());
-        assert_eq!(
-            get_set_fee_account_result(1u8),
-            Err(ProgramError::from(SwapError::InvalidAccountOwner))
-        );
-        assert_eq!(
-            get_set_fee_account_result(2u8),
-            Err(ProgramError::from(SwapError::InvalidAccountOwner))
-        );
+    fn test_set_market_maker() {
+        let existing_market_maker = Pubkey::new_from_array([9u8; 32]);
+        let market_maker = Pubkey::new_unique();
         assert_eq!(
-            get_set_fee_account_result(3u8),
-            Err(ProgramError::from(SwapError::Unauthorized))
+            get_set_market_maker_result(market_maker, 1u8, 0u8),
+            (Ok(()), [existing_market_maker, market_maker])
         );
         assert_eq!(
-            get_set_fee_account_result(4u8),
-            Err(ProgramError::MissingRequiredSignature)
+            get_set_market_maker_result(Pubkey::default(), 0u8, 0u8),
+            (Ok(()), [Pubkey::default(), Pubkey::default()])
         );
         assert_eq!(
-            get_set_fee_account_result(5u8),
-            Err(ProgramError::UninitializedAccount)
+            get_set_market_maker_result(existing_market_maker, 0u8, 0u8),
+            (Ok(()), [existing_market_maker, Pubkey::default()])
         );
         assert_eq!(
-            get_set_fee_account_result(6u8),
-            Err(ProgramError::from(SwapError::InvalidMarketConfig))
+            get_set_market_maker_result(existing_market_maker, 1u8, 0u8),
+            (
+                Err(ProgramError::from(SwapError::InvalidMarketMaker)),
+                [existing_market_maker, Pubkey::default()]
+            )
         );
         assert_eq!(
-            get_set_fee_account_result(7u8),
-            Err(ProgramError::from(SwapError::InvalidProgramAddress))
+            get_set_market_maker_result(market_maker, MAX_MARKET_MAKERS as u8, 0u8).0,
+            Err(ProgramError::from(SwapError::InvalidInput))
         );
         assert_eq!(
-            get_set_fee_account_result(8u8),
-            Err(ProgramError::from(SwapError::IncorrectTokenProgramId))
+            get_set_market_maker_result(market_maker, 1u8, 1u8).0,
+            Err(ProgramError::from(SwapError::InvalidAccountOwner))
         );
         assert_eq!(
-            get_set_fee_account_result(9u8),
-            Err(ProgramError::from(SwapError::ExpectedAccount))
+            get_set_market_maker_result(market_maker, 1u8, 2u8).0,
+            Err(ProgramError::UninitializedAccount)
         );
         assert_eq!(
-            get_set_fee_account_result(10u8),
-            Err(ProgramError::from(SwapError::InvalidOwner))
+            get_set_market_maker_result(market_maker, 1u8, 3u8).0,
+            Err(ProgramError::from(SwapError::Unauthorized))
         );
         assert_eq!(
-            get_set_fee_account_result(11u8),
-            Err(ProgramError::from(SwapError::IncorrectMint))
+            get_set_market_maker_result(market_maker, 1u8, 4u8).0,
+            Err(ProgramError::MissingRequiredSignature)
         );
-        assert!(get_set_fee_account_result(12u8).is_ok());
     }
-
-    fn get_set_new_fees_result(new_fees: &Fees, option: u8) -> (ProgramResult, Fees) {
-        let mut accounts = Vec::new();
+
+    fn get_set_admin_role_result(role: u8, option: u8) -> (ProgramResult, ConfigInfo) {
         let program_id = Pubkey::new_unique();
         let config_key = Pubkey::new_unique();
-        let swap_key = Pubkey::new_unique();
         let admin_key = Pubkey::new_unique();
+        let role_key = Pubkey::new_from_array([9u8; 32]);
+        let pauser_key = Pubkey::new_unique();
 
         let config = ConfigInfo {
-            version: if option == 3u8 { 0u8 } else { 1u8 },
-            bump_seed: 0u8,
-            admin_key: if option == 4u8 { config_key } else { admin_key },
-            deltafi_mint: Pubkey::new_unique(),
-            pyth_program_id: Pubkey::new_unique(),
-            fees: Fees {
-                ..Default::default()
-            },
-            rewards: Rewards {
-                ..Default::default()
-            },
-            deltafi_token: Pubkey::new_unique(),
+            version: 1u8,
+            admin_key,
+            pauser_key,
             ..ConfigInfo::default()
         };
 
-        let mut swap = SwapInfo {
-            ..Default::default()
-        };
-        if option == 7u8 {
-            swap.config_key = swap_key;
-        } else {
-            swap.config_key = config_key;
-        }
-
-        swap.is_initialized = option != 6u8;
-        swap.fees = Fees {
-            ..Default::default()
-        };
-
         let mut lamports = 0u64;
         let mut config_data = [0u8; ConfigInfo::LEN];
         config.pack_into_slice(&mut config_data);
-        accounts.push(AccountInfo::new(
+        let config_info = AccountInfo::new(
             &config_key,
             false,
-            false,
+            true,
             &mut lamports,
             &mut config_data,
             if option == 1u8 {
@@ -1251,135 +6844,152 @@ mod tests {
             },
             false,
             0u64,
-        ));
+        );
 
-        let mut lamports = 0u64;
-        let mut swap_data = [0u8; SwapInfo::LEN];
-        swap.pack_into_slice(&mut swap_data);
-        accounts.push(AccountInfo::new(
-            &swap_key,
-            false,
-            false,
-            &mut lamports,
-            &mut swap_data,
+        let mut admin_lamports = 0u64;
+        let mut admin_data = [0u8];
+        let admin_info = AccountInfo::new(
             if option == 2u8 {
-                &swap_key
+                &pauser_key
             } else {
-                &program_id
+                &admin_key
             },
+            option != 3u8,
+            false,
+            &mut admin_lamports,
+            &mut admin_data,
+            &program_id,
             false,
             0u64,
-        ));
+        );
 
-        let mut lamports = 0u64;
-        let mut admin_data = [0u8];
-        accounts.push(AccountInfo::new(
-            &admin_key,
-            option != 5u8,
+        let mut role_lamports = 0u64;
+        let mut role_data = [0u8];
+        let role_info = AccountInfo::new(
+            &role_key,
             false,
-            &mut lamports,
-            &mut admin_data,
+            false,
+            &mut role_lamports,
+            &mut role_data,
             &program_id,
             false,
             0u64,
-        ));
+        );
 
-        let result = set_new_fees(&program_id, new_fees, &accounts[..]);
-        let result_swap = SwapInfo::unpack(&accounts[1].data.borrow());
+        let accounts = [config_info, admin_info, role_info];
+        let result = set_admin_role(&program_id, role, &accounts[..]);
+        let config = ConfigInfo::unpack_unchecked(&accounts[0].data.borrow()).unwrap();
 
-        (
-            result,
-            if let Ok(result_swap_content) = result_swap {
-                result_swap_content.fees
-            } else {
-                Fees {
-                    ..Default::default()
-                }
-            },
-        )
+        (result, config)
     }
 
     #[test]
-    fn test_set_new_fees() {
-        let test_fees = Fees {
-            is_initialized: true,
-            admin_trade_fee_numerator: 123_123_123u64,
-            admin_trade_fee_denominator: 40_000_000u64,
-            admin_withdraw_fee_numerator: 15_000_000u64,
-            admin_withdraw_fee_denominator: 3_000_000u64,
-            trade_fee_numerator: 24_000_000u64,
-            trade_fee_denominator: 31_000_000u64,
-            withdraw_fee_numerator: 5_000_000u64,
-            withdraw_fee_denominator: 91_000_000u64,
-        };
+    fn test_set_admin_role() {
+        let role_key = Pubkey::new_from_array([9u8; 32]);
+
+        let (result, config) = get_set_admin_role_result(AdminRole::FeeManager as u8, 0u8);
+        assert_eq!(result, Ok(()));
+        assert_eq!(config.fee_manager_key, role_key);
+        assert!(config.has_role(AdminRole::FeeManager, &role_key));
+        assert!(!config.has_role(AdminRole::Pauser, &role_key));
+
+        let (result, config) = get_set_admin_role_result(AdminRole::Pauser as u8, 0u8);
+        assert_eq!(result, Ok(()));
+        assert_eq!(config.pauser_key, role_key);
+
+        let (result, config) = get_set_admin_role_result(AdminRole::OracleManager as u8, 0u8);
+        assert_eq!(result, Ok(()));
+        assert_eq!(config.oracle_manager_key, role_key);
+
+        let (result, config) = get_set_admin_role_result(AdminRole::Guardian as u8, 0u8);
+        assert_eq!(result, Ok(()));
+        assert_eq!(config.guardian_key, role_key);
 
-        let ok_result = get_set_new_fees_result(&test_fees, 0u8);
-        assert!(ok_result.0.is_ok());
-        assert_eq!(ok_result.1, test_fees);
         assert_eq!(
-            get_set_new_fees_result(&test_fees, 1u8).0,
-            Err(ProgramError::from(SwapError::InvalidAccountOwner))
+            get_set_admin_role_result(AdminRole::SuperAdmin as u8, 0u8).0,
+            Err(ProgramError::from(SwapError::InvalidInput))
         );
         assert_eq!(
-            get_set_new_fees_result(&test_fees, 2u8).0,
-            Err(ProgramError::from(SwapError::InvalidAccountOwner))
+            get_set_admin_role_result(5u8, 0u8).0,
+            Err(ProgramError::from(SwapError::InvalidInput))
         );
         assert_eq!(
-            get_set_new_fees_result(&test_fees, 3u8).0,
-            Err(ProgramError::UninitializedAccount)
+            get_set_admin_role_result(AdminRole::Pauser as u8, 1u8).0,
+            Err(ProgramError::from(SwapError::InvalidAccountOwner))
         );
+        // delegated roles can't delegate
         assert_eq!(
-            get_set_new_fees_result(&test_fees, 4u8).0,
+            get_set_admin_role_result(AdminRole::Pauser as u8, 2u8).0,
             Err(ProgramError::from(SwapError::Unauthorized))
         );
         assert_eq!(
-            get_set_new_fees_result(&test_fees, 5u8).0,
+            get_set_admin_role_result(AdminRole::Pauser as u8, 3u8).0,
             Err(ProgramError::MissingRequiredSignature)
         );
-        assert_eq!(
-            get_set_new_fees_result(&test_fees, 6u8).0,
-            Err(ProgramError::UninitializedAccount)
-        );
-        assert_eq!(
-            get_set_new_fees_result(&test_fees, 7u8).0,
-            Err(ProgramError::from(SwapError::InvalidMarketConfig))
-        );
     }
 
-    fn get_set_new_rewards_result(new_rewards: &Rewards, option: u8) -> (ProgramResult, Rewards) {
+    fn get_rotate_fee_account_result(option: u8) -> (ProgramResult, bool) {
         let mut accounts = Vec::new();
-        let program_id = Pubkey::new_unique();
+        let nonce = 8u8;
+        let program_id = Pubkey::from_str("5NjW2CAV6MBQYxpL4oK2CESrpdj6tkcvxP3iigAgrHyR").unwrap();
         let config_key = Pubkey::new_unique();
-        let swap_key = Pubkey::new_unique();
+        let swap_key = Pubkey::from_str("CWWiYh5Rpyf5rHZbzHYM6TT6FfcojTR2rKjr5M4BFa3y").unwrap();
         let admin_key = Pubkey::new_unique();
+        let authority_key =
+            Pubkey::create_program_address(&[swap_key.as_ref(), &[nonce]], &program_id).unwrap();
+        let old_fee_key = Pubkey::new_unique();
+        let new_fee_key = if option == 5u8 {
+            old_fee_key
+        } else {
+            Pubkey::new_unique()
+        };
+        let destination_key = Pubkey::new_unique();
+        let token_program_key = spl_token::id();
 
         let config = ConfigInfo {
-            version: if option == 3u8 { 0u8 } else { 1u8 },
-            bump_seed: 0u8,
-            admin_key: if option == 4u8 { config_key } else { admin_key },
-            deltafi_mint: Pubkey::new_unique(),
-            pyth_program_id: Pubkey::new_unique(),
-            fees: Fees {
-                ..Default::default()
-            },
-            rewards: Rewards {
-                ..Default::default()
-            },
-            deltafi_token: Pubkey::new_unique(),
+            admin_key,
             ..ConfigInfo::default()
         };
 
         let mut swap = SwapInfo {
+            is_initialized: true,
+            config_key,
+            nonce,
+            token_a_mint: Pubkey::new_unique(),
+            token_b_mint: Pubkey::new_unique(),
             ..Default::default()
         };
-        if option == 7u8 {
-            swap.config_key = swap_key;
+        swap.admin_fee_key_a = if option == 1u8 {
+            Pubkey::new_unique()
         } else {
-            swap.config_key = config_key;
-        }
-
-        swap.is_initialized = option != 6u8;
-        swap.fees = Fees {
+            old_fee_key
+        };
+        swap.admin_fee_key_b = Pubkey::new_unique();
+
+        let old_fee_account = Account {
+            mint: swap.token_a_mint,
+            owner: authority_key,
+            amount: 1_000u64,
+            state: AccountState::Initialized,
+            close_authority: if option == 4u8 {
+                COption::Some(admin_key)
+            } else {
+                COption::None
+            },
+            ..Default::default()
+        };
+        let new_fee_account = Account {
+            mint: if option == 3u8 {
+                swap.token_b_mint
+            } else {
+                swap.token_a_mint
+            },
+            owner: if option == 2u8 {
+                admin_key
+            } else {
+                authority_key
+            },
+            state: AccountState::Initialized,
             ..Default::default()
         };
 
@@ -1392,11 +7002,7 @@ mod tests {
             false,
             &mut lamports,
             &mut config_data,
-            if option == 1u8 {
-                &config_key
-            } else {
-                &program_id
-            },
+            &program_id,
             false,
             0u64,
         ));
@@ -1407,14 +7013,23 @@ mod tests {
         accounts.push(AccountInfo::new(
             &swap_key,
             false,
-            false,
+            true,
             &mut lamports,
             &mut swap_data,
-            if option == 2u8 {
-                &swap_key
-            } else {
-                &program_id
-            },
+            &program_id,
+            false,
+            0u64,
+        ));
+
+        let mut lamports = 0u64;
+        let mut authority_data = [0u8];
+        accounts.push(AccountInfo::new(
+            &authority_key,
+            false,
+            false,
+            &mut lamports,
+            &mut authority_data,
+            &program_id,
             false,
             0u64,
         ));
@@ -1423,7 +7038,7 @@ mod tests {
         let mut admin_data = [0u8];
         accounts.push(AccountInfo::new(
             &admin_key,
-            option != 5u8,
+            option != 6u8,
             false,
             &mut lamports,
             &mut admin_data,
@@ -1432,103 +7047,119 @@ mod tests {
             0u64,
         ));
 
-        let result = set_new_rewards(&program_id, new_rewards, &accounts[..]);
-        let result_swap = SwapInfo::unpack(&accounts[1].data.borrow());
+        let mut lamports = 0u64;
+        let mut old_fee_account_data = [0u8; Account::LEN];
+        old_fee_account.pack_into_slice(&mut old_fee_account_data);
+        accounts.push(AccountInfo::new(
+            &old_fee_key,
+            false,
+            true,
+            &mut lamports,
+            &mut old_fee_account_data,
+            &token_program_key,
+            false,
+            0u64,
+        ));
 
-        (
-            result,
-            if let Ok(result_swap_content) = result_swap {
-                result_swap_content.rewards
-            } else {
-                Rewards {
-                    ..Default::default()
-                }
-            },
-        )
+        let mut lamports = 0u64;
+        let mut new_fee_account_data = [0u8; Account::LEN];
+        new_fee_account.pack_into_slice(&mut new_fee_account_data);
+        accounts.push(AccountInfo::new(
+            &new_fee_key,
+            false,
+            true,
+            &mut lamports,
+            &mut new_fee_account_data,
+            &token_program_key,
+            false,
+            0u64,
+        ));
+
+        let mut lamports = 0u64;
+        let mut destination_data = [0u8];
+        accounts.push(AccountInfo::new(
+            &destination_key,
+            false,
+            true,
+            &mut lamports,
+            &mut destination_data,
+            &program_id,
+            false,
+            0u64,
+        ));
+
+        let mut lamports = 0u64;
+        let mut token_program_data = [0u8];
+        accounts.push(AccountInfo::new(
+            &token_program_key,
+            false,
+            false,
+            &mut lamports,
+            &mut token_program_data,
+            &program_id,
+            true,
+            0u64,
+        ));
+
+        let result = rotate_fee_account(&program_id, &accounts[..]);
+        let swap = SwapInfo::unpack(&accounts[1].data.borrow()).unwrap();
+        (result, swap.admin_fee_key_a == new_fee_key)
     }
 
     #[test]
-    fn test_set_new_rewards() {
-        let test_rewards = Rewards {
-            is_initialized: true,
-            decimals: 9,
-            reserved: [0u8; 7],
-            trade_reward_numerator: 123_123_123u64,
-            trade_reward_denominator: 40_000_000u64,
-            trade_reward_cap: 15_000_000u64,
-        };
+    fn test_rotate_fee_account() {
+        assert_eq!(get_rotate_fee_account_result(0u8), (Ok(()), true));
 
-        let ok_result = get_set_new_rewards_result(&test_rewards, 0u8);
-        assert!(ok_result.0.is_ok());
-        assert_eq!(ok_result.1, test_rewards);
-        assert_eq!(
-            get_set_new_rewards_result(&test_rewards, 1u8).0,
-            Err(ProgramError::from(SwapError::InvalidAccountOwner))
-        );
         assert_eq!(
-            get_set_new_rewards_result(&test_rewards, 2u8).0,
-            Err(ProgramError::from(SwapError::InvalidAccountOwner))
+            get_rotate_fee_account_result(1u8).0,
+            Err(ProgramError::from(SwapError::InvalidAdmin))
         );
         assert_eq!(
-            get_set_new_rewards_result(&test_rewards, 3u8).0,
-            Err(ProgramError::UninitializedAccount)
+            get_rotate_fee_account_result(2u8).0,
+            Err(ProgramError::from(SwapError::InvalidOwner))
         );
         assert_eq!(
-            get_set_new_rewards_result(&test_rewards, 4u8).0,
-            Err(ProgramError::from(SwapError::Unauthorized))
+            get_rotate_fee_account_result(3u8).0,
+            Err(ProgramError::from(SwapError::IncorrectMint))
         );
         assert_eq!(
-            get_set_new_rewards_result(&test_rewards, 5u8).0,
-            Err(ProgramError::MissingRequiredSignature)
+            get_rotate_fee_account_result(4u8).0,
+            Err(ProgramError::from(SwapError::InvalidOwner))
         );
         assert_eq!(
-            get_set_new_rewards_result(&test_rewards, 6u8).0,
-            Err(ProgramError::UninitializedAccount)
+            get_rotate_fee_account_result(5u8).0,
+            Err(ProgramError::from(SwapError::InvalidInput))
         );
         assert_eq!(
-            get_set_new_rewards_result(&test_rewards, 7u8).0,
-            Err(ProgramError::from(SwapError::InvalidMarketConfig))
+            get_rotate_fee_account_result(6u8).0,
+            Err(ProgramError::MissingRequiredSignature)
         );
     }
 
-    fn get_set_farm_rewards_result(
-        farm_rewards: &FarmRewards,
-        option: u8,
-    ) -> (ProgramResult, FarmRewards) {
+    fn get_commit_initial_price_result(option: u8) -> (ProgramResult, Option<PriceCommitment>) {
         let mut accounts = Vec::new();
         let program_id = Pubkey::new_unique();
         let config_key = Pubkey::new_unique();
-        let farm_key = Pubkey::new_unique();
+        let swap_key = Pubkey::new_unique();
         let admin_key = Pubkey::new_unique();
+        let price_commitment_key = if option == 1u8 {
+            Pubkey::new_unique()
+        } else {
+            get_price_commitment_pubkey(&admin_key, &swap_key, &program_id).unwrap()
+        };
+        let clock_key = solana_program::sysvar::clock::id();
+        let rent_key = solana_program::sysvar::rent::id();
+        let price_hash = PriceCommitment::hash_price(1_000u128, &[7u8; 32]);
 
         let config = ConfigInfo {
-            version: if option == 3u8 { 0u8 } else { 1u8 },
-            bump_seed: 0u8,
-            admin_key: if option == 4u8 { config_key } else { admin_key },
-            deltafi_mint: Pubkey::new_unique(),
-            pyth_program_id: Pubkey::new_unique(),
-            fees: Fees {
-                ..Default::default()
-            },
-            rewards: Rewards {
-                ..Default::default()
-            },
-            deltafi_token: Pubkey::new_unique(),
+            admin_key,
             ..ConfigInfo::default()
         };
-
-        let mut farm = FarmInfo {
-            ..Default::default()
+        let rent_val = Rent::default();
+        let clock_val = Clock {
+            slot: 100u64,
+            ..Clock::default()
         };
-        if option == 7u8 {
-            farm.config_key = farm_key;
-        } else {
-            farm.config_key = config_key;
-        }
-
-        farm.is_initialized = option != 6u8;
-        farm.apr_numerator = farm_rewards.apr_numerator;
-        farm.apr_denominator = farm_rewards.apr_denominator;
 
         let mut lamports = 0u64;
         let mut config_data = [0u8; ConfigInfo::LEN];
@@ -1539,29 +7170,40 @@ mod tests {
             false,
             &mut lamports,
             &mut config_data,
-            if option == 1u8 {
-                &config_key
-            } else {
-                &program_id
-            },
+            &program_id,
+            false,
+            0u64,
+        ));
+
+        let mut price_commitment_lamports = rent_val.minimum_balance(PriceCommitment::LEN);
+        let mut price_commitment_data = [0u8; PriceCommitment::LEN];
+        accounts.push(AccountInfo::new(
+            &price_commitment_key,
+            false,
+            true,
+            &mut price_commitment_lamports,
+            &mut price_commitment_data,
+            &program_id,
             false,
             0u64,
         ));
 
         let mut lamports = 0u64;
-        let mut farm_data = [0u8; FarmInfo::LEN];
-        farm.pack_into_slice(&mut farm_data);
+        let mut swap_data = [0u8; SwapInfo::LEN];
+        if option == 3u8 {
+            SwapInfo {
+                is_initialized: true,
+                ..SwapInfo::default()
+            }
+            .pack_into_slice(&mut swap_data);
+        }
         accounts.push(AccountInfo::new(
-            &farm_key,
+            &swap_key,
             false,
             false,
             &mut lamports,
-            &mut farm_data,
-            if option == 2u8 {
-                &farm_key
-            } else {
-                &program_id
-            },
+            &mut swap_data,
+            &program_id,
             false,
             0u64,
         ));
@@ -1570,7 +7212,7 @@ mod tests {
         let mut admin_data = [0u8];
         accounts.push(AccountInfo::new(
             &admin_key,
-            option != 5u8,
+            option != 2u8,
             false,
             &mut lamports,
             &mut admin_data,
@@ -1579,99 +7221,88 @@ mod tests {
             0u64,
         ));
 
-        let result = set_farm_rewards(&program_id, farm_rewards, &accounts[..]);
-        let result_farm = FarmInfo::unpack(&accounts[1].data.borrow());
+        let mut lamports = 0u64;
+        let mut clock_data = [0u8; mem::size_of::<Clock>()];
+        accounts.push(AccountInfo::new(
+            &clock_key,
+            false,
+            false,
+            &mut lamports,
+            &mut clock_data,
+            &program_id,
+            false,
+            0u64,
+        ));
+        assert!(clock_val.to_account_info(&mut accounts[4]).is_some());
 
-        (
-            result,
-            if let Ok(result_farm_content) = result_farm {
-                FarmRewards {
-                    apr_numerator: result_farm_content.apr_numerator,
-                    apr_denominator: result_farm_content.apr_denominator,
-                }
-            } else {
-                FarmRewards {
-                    apr_numerator: 0u64,
-                    apr_denominator: 0u64,
-                }
-            },
-        )
+        let mut lamports = 0u64;
+        let mut rent_data = [0u8; mem::size_of::<Rent>()];
+        accounts.push(AccountInfo::new(
+            &rent_key,
+            false,
+            false,
+            &mut lamports,
+            &mut rent_data,
+            &program_id,
+            false,
+            0u64,
+        ));
+        assert!(rent_val.to_account_info(&mut accounts[5]).is_some());
+
+        let result = commit_initial_price(&program_id, price_hash, &accounts[..]);
+        let price_commitment = PriceCommitment::unpack(&accounts[1].data.borrow()).ok();
+        (result, price_commitment)
     }
 
     #[test]
-    fn test_set_farm_rewards() {
-        let test_staking_reward = FarmRewards {
-            apr_numerator: 222_222_222u64,
-            apr_denominator: 333_333_333u64,
-        };
-
-        let ok_result = get_set_farm_rewards_result(&test_staking_reward, 0u8);
-        assert!(ok_result.0.is_ok());
-        assert_eq!(ok_result.1, test_staking_reward);
+    fn test_commit_initial_price() {
+        let (result, price_commitment) = get_commit_initial_price_result(0u8);
+        assert_eq!(result, Ok(()));
+        let price_commitment = price_commitment.unwrap();
         assert_eq!(
-            get_set_farm_rewards_result(&test_staking_reward, 1u8).0,
-            Err(ProgramError::from(SwapError::InvalidAccountOwner))
+            price_commitment.price_hash,
+            PriceCommitment::hash_price(1_000u128, &[7u8; 32])
         );
+        assert_eq!(price_commitment.commit_slot, 100u64);
+
         assert_eq!(
-            get_set_farm_rewards_result(&test_staking_reward, 2u8).0,
+            get_commit_initial_price_result(1u8).0,
             Err(ProgramError::from(SwapError::InvalidAccountOwner))
         );
         assert_eq!(
-            get_set_farm_rewards_result(&test_staking_reward, 3u8).0,
-            Err(ProgramError::UninitializedAccount)
-        );
-        assert_eq!(
-            get_set_farm_rewards_result(&test_staking_reward, 4u8).0,
-            Err(ProgramError::from(SwapError::Unauthorized))
-        );
-        assert_eq!(
-            get_set_farm_rewards_result(&test_staking_reward, 5u8).0,
+            get_commit_initial_price_result(2u8).0,
             Err(ProgramError::MissingRequiredSignature)
         );
         assert_eq!(
-            get_set_farm_rewards_result(&test_staking_reward, 6u8).0,
-            Err(ProgramError::UninitializedAccount)
-        );
-        assert_eq!(
-            get_set_farm_rewards_result(&test_staking_reward, 7u8).0,
-            Err(ProgramError::from(SwapError::InvalidMarketConfig))
+            get_commit_initial_price_result(3u8).0,
+            Err(ProgramError::from(SwapError::AlreadyInUse))
         );
     }
 
-    fn get_set_slope_result(slope: u64, option: u8) -> (ProgramResult, u64) {
+    fn get_pegged_price_result(
+        token_swap: &SwapInfo,
+        slot: u64,
+        pegged_price: Option<u128>,
+    ) -> (ProgramResult, SwapInfo) {
         let mut accounts = Vec::new();
         let program_id = Pubkey::new_unique();
         let config_key = Pubkey::new_unique();
-        let pool_key = Pubkey::new_unique();
+        let swap_key = Pubkey::new_unique();
         let admin_key = Pubkey::new_unique();
+        let clock_key = solana_program::sysvar::clock::id();
 
         let config = ConfigInfo {
-            version: if option == 3u8 { 0u8 } else { 1u8 },
-            bump_seed: 0u8,
-            admin_key: if option == 4u8 { config_key } else { admin_key },
-            deltafi_mint: Pubkey::new_unique(),
-            pyth_program_id: Pubkey::new_unique(),
-            fees: Fees {
-                ..Default::default()
-            },
-            rewards: Rewards {
-                ..Default::default()
-            },
-            deltafi_token: Pubkey::new_unique(),
+            admin_key,
             ..ConfigInfo::default()
         };
-
-        let mut swap = SwapInfo {
-            ..Default::default()
+        let token_swap = SwapInfo {
+            config_key,
+            ..token_swap.clone()
+        };
+        let clock_val = Clock {
+            slot,
+            ..Clock::default()
         };
-        if option == 7u8 {
-            swap.config_key = pool_key;
-        } else {
-            swap.config_key = config_key;
-        }
-
-        swap.is_initialized = option != 6u8;
-        swap.pool_state.slope = Decimal::from(slope);
 
         let mut lamports = 0u64;
         let mut config_data = [0u8; ConfigInfo::LEN];
@@ -1682,29 +7313,21 @@ mod tests {
             false,
             &mut lamports,
             &mut config_data,
-            if option == 1u8 {
-                &config_key
-            } else {
-                &program_id
-            },
+            &program_id,
             false,
             0u64,
         ));
 
         let mut lamports = 0u64;
         let mut swap_data = [0u8; SwapInfo::LEN];
-        swap.pack_into_slice(&mut swap_data);
+        token_swap.pack_into_slice(&mut swap_data);
         accounts.push(AccountInfo::new(
-            &pool_key,
-            false,
+            &swap_key,
             false,
+            true,
             &mut lamports,
             &mut swap_data,
-            if option == 2u8 {
-                &pool_key
-            } else {
-                &program_id
-            },
+            &program_id,
             false,
             0u64,
         ));
@@ -1713,7 +7336,7 @@ mod tests {
         let mut admin_data = [0u8];
         accounts.push(AccountInfo::new(
             &admin_key,
-            option != 5u8,
+            true,
             false,
             &mut lamports,
             &mut admin_data,
@@ -1722,46 +7345,134 @@ mod tests {
             0u64,
         ));
 
-        let result = set_slope(&program_id, slope, &accounts[..]);
-        let result_pool = SwapInfo::unpack(&accounts[1].data.borrow());
+        let mut lamports = 0u64;
+        let mut clock_data = [0u8; mem::size_of::<Clock>()];
+        accounts.push(AccountInfo::new(
+            &clock_key,
+            false,
+            false,
+            &mut lamports,
+            &mut clock_data,
+            &program_id,
+            false,
+            0u64,
+        ));
+        assert!(clock_val.to_account_info(&mut accounts[3]).is_some());
 
-        (result, if result_pool.is_ok() { slope } else { 0u64 })
+        let result = match pegged_price {
+            Some(pegged_price) => commit_pegged_price(&program_id, pegged_price, &accounts[..]),
+            None => apply_pegged_price(&program_id, &accounts[..]),
+        };
+        let token_swap = SwapInfo::unpack(&accounts[1].data.borrow()).unwrap();
+        (result, token_swap)
     }
 
     #[test]
-    fn test_set_slope() {
-        let test_set_slope = 123_123_123u64;
+    fn test_pegged_price_timelock() {
+        let pegged_price = Decimal::from(2u64);
+        let token_swap = SwapInfo {
+            is_initialized: true,
+            swap_type: SwapType::Pegged,
+            token_a_decimals: 6,
+            token_b_decimals: 6,
+            ..SwapInfo::default()
+        };
 
-        let ok_result = get_set_slope_result(test_set_slope, 0u8);
-        assert!(ok_result.0.is_ok());
-        assert_eq!(ok_result.1, test_set_slope);
+        let normal_swap = SwapInfo {
+            swap_type: SwapType::Normal,
+            ..token_swap.clone()
+        };
         assert_eq!(
-            get_set_slope_result(test_set_slope, 1u8).0,
-            Err(ProgramError::from(SwapError::InvalidAccountOwner))
+            get_pegged_price_result(
+                &normal_swap,
+                100,
+                Some(pegged_price.to_scaled_val().unwrap())
+            )
+            .0,
+            Err(SwapError::IncorrectSwapType.into())
         );
         assert_eq!(
-            get_set_slope_result(test_set_slope, 2u8).0,
-            Err(ProgramError::from(SwapError::InvalidAccountOwner))
+            get_pegged_price_result(&token_swap, 100, Some(0)).0,
+            Err(SwapError::InvalidInput.into())
         );
         assert_eq!(
-            get_set_slope_result(test_set_slope, 3u8).0,
-            Err(ProgramError::UninitializedAccount)
+            get_pegged_price_result(&token_swap, 100, None).0,
+            Err(SwapError::InvalidInput.into())
         );
-        assert_eq!(
-            get_set_slope_result(test_set_slope, 4u8).0,
-            Err(ProgramError::from(SwapError::Unauthorized))
+
+        let (result, token_swap) = get_pegged_price_result(
+            &token_swap,
+            100,
+            Some(pegged_price.to_scaled_val().unwrap()),
         );
+        assert_eq!(result, Ok(()));
+        assert_eq!(token_swap.future_pegged_price, pegged_price);
         assert_eq!(
-            get_set_slope_result(test_set_slope, 5u8).0,
-            Err(ProgramError::MissingRequiredSignature)
+            token_swap.pegged_price_apply_slot,
+            100 + PEGGED_PRICE_TIMELOCK_SLOTS
         );
+
         assert_eq!(
-            get_set_slope_result(test_set_slope, 6u8).0,
-            Err(ProgramError::UninitializedAccount)
+            get_pegged_price_result(&token_swap, 99 + PEGGED_PRICE_TIMELOCK_SLOTS, None).0,
+            Err(SwapError::TimelockNotExpired.into())
+        );
+
+        let (result, token_swap) =
+            get_pegged_price_result(&token_swap, 100 + PEGGED_PRICE_TIMELOCK_SLOTS, None);
+        assert_eq!(result, Ok(()));
+        assert_eq!(token_swap.pool_state.last_market_price, pegged_price);
+        assert_eq!(token_swap.pool_state.market_price, pegged_price);
+        assert_eq!(token_swap.future_pegged_price, Decimal::zero());
+        assert_eq!(token_swap.pegged_price_apply_slot, 0);
+    }
+
+    #[test]
+    fn test_record_admin_op_unsigned_by_role() {
+        let program_id = Pubkey::new_unique();
+        let config_key = Pubkey::new_unique();
+        let caller_key = Pubkey::new_unique();
+        let config = ConfigInfo {
+            version: PROGRAM_VERSION,
+            admin_key: Pubkey::new_unique(),
+            admin_ops_epoch: 0,
+            admin_ops_count: 3,
+            max_admin_ops_per_epoch: 3,
+            ..ConfigInfo::default()
+        };
+        let mut config_lamports = 0u64;
+        let mut config_data = [0u8; ConfigInfo::LEN];
+        ConfigInfo::pack(config, &mut config_data).unwrap();
+        let config_info = AccountInfo::new(
+            &config_key,
+            false,
+            true,
+            &mut config_lamports,
+            &mut config_data,
+            &program_id,
+            false,
+            0u64,
+        );
+        let mut caller_lamports = 0u64;
+        let mut caller_data = [0u8];
+        let caller_info = AccountInfo::new(
+            &caller_key,
+            true,
+            false,
+            &mut caller_lamports,
+            &mut caller_data,
+            &program_id,
+            false,
+            0u64,
         );
+
+        // The caller holding no role is left to the instruction to reject, the count at the
+        // max is neither moved nor pausing the admin instructions
         assert_eq!(
-            get_set_slope_result(test_set_slope, 7u8).0,
-            Err(ProgramError::from(SwapError::InvalidMarketConfig))
+            record_admin_op(&program_id, &[config_info.clone(), caller_info]),
+            Ok(true)
         );
+        let config = ConfigInfo::unpack(&config_info.data.borrow()).unwrap();
+        assert_eq!(config.admin_ops_count, 3);
+        assert!(!config.is_admin_ops_paused);
     }
 }
@@ -0,0 +1,159 @@
+//! Command line of the cli, a command then its positional arguments and `--flag [value]`
+//! options in any order
+
+use deltafi_swap::math::Decimal;
+use solana_program::pubkey::Pubkey;
+use std::{
+    cell::RefCell,
+    collections::{HashMap, HashSet},
+    str::FromStr,
+};
+
+/// Parsed command line, every flag read by the command is recorded so a mistyped flag is
+/// reported instead of silently ignored
+pub struct Args {
+    command: String,
+    positionals: Vec<String>,
+    flags: HashMap<String, Option<String>>,
+    used: RefCell<HashSet<String>>,
+}
+
+impl Args {
+    /// Parse the arguments following the program name, a flag followed by another flag or
+    /// by nothing is a switch
+    pub fn parse(args: impl Iterator<Item = String>) -> Result<Self, String> {
+        let mut args = args.peekable();
+        let mut command = None;
+        let mut positionals = vec![];
+        let mut flags = HashMap::new();
+        while let Some(arg) = args.next() {
+            if let Some(name) = arg.strip_prefix("--") {
+                let value = match args.peek() {
+                    Some(next) if !next.starts_with("--") => args.next(),
+                    _ => None,
+                };
+                if flags.insert(name.to_string(), value).is_some() {
+                    return Err(format!("--{} is given twice", name));
+                }
+            } else if command.is_none() {
+                command = Some(arg);
+            } else {
+                positionals.push(arg);
+            }
+        }
+
+        Ok(Self {
+            command: command.unwrap_or_else(|| "help".to_string()),
+            positionals,
+            flags,
+            used: RefCell::new(HashSet::new()),
+        })
+    }
+
+    pub fn command(&self) -> &str {
+        &self.command
+    }
+
+    /// Positional argument at `index`, `name` is only used in the error
+    pub fn positional(&self, index: usize, name: &str) -> Result<&str, String> {
+        self.positionals
+            .get(index)
+            .map(String::as_str)
+            .ok_or_else(|| format!("missing <{}>", name))
+    }
+
+    /// Whether the switch `--name` is given
+    pub fn switch(&self, name: &str) -> Result<bool, String> {
+        self.used.borrow_mut().insert(name.to_string());
+        match self.flags.get(name) {
+            None => Ok(false),
+            Some(None) => Ok(true),
+            Some(Some(value)) => Err(format!("--{} takes no value, got {}", name, value)),
+        }
+    }
+
+    /// Value of `--name`, None if the flag is not given
+    pub fn value(&self, name: &str) -> Result<Option<&str>, String> {
+        self.used.borrow_mut().insert(name.to_string());
+        match self.flags.get(name) {
+            None => Ok(None),
+            Some(None) => Err(format!("--{} needs a value", name)),
+            Some(Some(value)) => Ok(Some(value)),
+        }
+    }
+
+    /// Value of `--name` parsed with `FromStr`
+    pub fn parse_value<T: FromStr>(&self, name: &str) -> Result<Option<T>, String>
+    where
+        T::Err: std::fmt::Debug,
+    {
+        self.value(name)?
+            .map(|value| {
+                value
+                    .parse()
+                    .map_err(|e| format!("invalid --{} {}: {:?}", name, value, e))
+            })
+            .transpose()
+    }
+
+    /// Value of `--name` parsed with `FromStr`, the flag must be given
+    pub fn required<T: FromStr>(&self, name: &str) -> Result<T, String>
+    where
+        T::Err: std::fmt::Debug,
+    {
+        self.parse_value(name)?
+            .ok_or_else(|| format!("missing --{}", name))
+    }
+
+    /// Pubkey of `--name`, the flag must be given
+    pub fn pubkey(&self, name: &str) -> Result<Pubkey, String> {
+        self.required(name)
+    }
+
+    /// Fraction `N/D` of `--name` as its numerator and denominator
+    pub fn fraction(&self, name: &str) -> Result<Option<(u64, u64)>, String> {
+        self.value(name)?
+            .map(|value| {
+                let invalid = || format!("invalid --{} {}, expected N/D", name, value);
+                let (numerator, denominator) = value.split_once('/').ok_or_else(invalid)?;
+                let numerator = numerator.parse().map_err(|_| invalid())?;
+                let denominator = denominator.parse().map_err(|_| invalid())?;
+                Ok((numerator, denominator))
+            })
+            .transpose()
+    }
+
+    /// Decimal of `--name` as its scaled value, e.g. `0.5` is `WAD / 2`
+    pub fn scaled_decimal(&self, name: &str) -> Result<Option<u128>, String> {
+        self.parse_value::<Decimal>(name)?
+            .map(|decimal| {
+                decimal
+                    .to_scaled_val()
+                    .map_err(|e| format!("invalid --{}: {}", name, e))
+            })
+            .transpose()
+    }
+
+    /// Fail on the flags the command did not read, called before sending anything
+    pub fn check_unused(&self) -> Result<(), String> {
+        let used = self.used.borrow();
+        let mut unused: Vec<&String> = self
+            .flags
+            .keys()
+            .filter(|name| !used.contains(*name))
+            .collect();
+        if unused.is_empty() {
+            return Ok(());
+        }
+        unused.sort();
+        Err(format!(
+            "unknown flags for {}: --{}",
+            self.command,
+            unused
+                .iter()
+                .map(|name| name.as_str())
+                .collect::<Vec<_>>()
+                .join(", --")
+        ))
+    }
+}
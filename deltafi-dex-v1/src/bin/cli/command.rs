@@ -0,0 +1,572 @@
+//! Commands of the cli, each one reads its flags, checks none is left over, then sends its
+//! transactions or prints the account
+
+use crate::args::Args;
+use deltafi_swap::{
+    instruction::{
+        farm_initialize, initialize, initialize_config, pause, set_decimals, set_new_fees,
+        set_slope, stable_initialize, unpause, FarmInitializeData, InitializeData,
+        StableInitializeData,
+    },
+    pyth::PYTH_PROGRAM_ID,
+    state::{ConfigInfo, FarmInfo, Fees, OraclePriorityFlag, Rewards, SwapInfo},
+};
+use serde::Serialize;
+use serde_json::Value;
+use solana_client::rpc_client::RpcClient;
+use solana_program::{
+    instruction::Instruction,
+    program_pack::{IsInitialized, Pack},
+    pubkey::Pubkey,
+    system_instruction,
+};
+use solana_sdk::{
+    commitment_config::CommitmentConfig,
+    signature::{read_keypair_file, Keypair, Signer},
+    transaction::Transaction,
+};
+use spl_token::{
+    instruction::{initialize_account, initialize_mint, set_authority, AuthorityType},
+    state::{Account as Token, Mint},
+};
+use std::str::FromStr;
+
+const DEFAULT_URL: &str = "http://localhost:8899";
+
+/// Cluster connection and signers shared by the commands
+pub struct Context {
+    rpc: RpcClient,
+    /// Only read by the commands sending transactions, `show-*` runs without a keypair
+    payer: Result<Keypair, String>,
+    admin_path: Option<String>,
+    program_id: Pubkey,
+}
+
+impl Context {
+    /// Connect with `--url`, pay with `--keypair`, defaulting to the solana cli ones
+    pub fn new(args: &Args) -> Result<Self, String> {
+        let url = args.value("url")?.unwrap_or(DEFAULT_URL);
+        let payer = match args.value("keypair")? {
+            Some(path) => read_keypair(path),
+            None => default_keypair_path().and_then(|path| read_keypair(&path)),
+        };
+        let program_id = args
+            .parse_value("program-id")?
+            .unwrap_or_else(deltafi_swap::id);
+
+        Ok(Self {
+            rpc: RpcClient::new_with_commitment(url.to_string(), CommitmentConfig::confirmed()),
+            payer,
+            admin_path: args.value("admin")?.map(str::to_string),
+            program_id,
+        })
+    }
+
+    fn payer(&self) -> Result<&Keypair, String> {
+        self.payer.as_ref().map_err(Clone::clone)
+    }
+
+    /// Admin of the config, `--admin` or the payer
+    fn admin(&self) -> Result<Keypair, String> {
+        match &self.admin_path {
+            Some(path) => read_keypair(path),
+            None => Ok(Keypair::from_bytes(&self.payer()?.to_bytes()).unwrap()),
+        }
+    }
+
+    fn account_data(&self, pubkey: &Pubkey) -> Result<Vec<u8>, String> {
+        self.rpc
+            .get_account(pubkey)
+            .map(|account| account.data)
+            .map_err(|e| format!("cannot fetch {}: {}", pubkey, e))
+    }
+
+    fn unpack<T: Pack + IsInitialized>(&self, pubkey: &Pubkey) -> Result<T, String> {
+        T::unpack(&self.account_data(pubkey)?)
+            .map_err(|e| format!("cannot unpack {}: {}", pubkey, e))
+    }
+
+    fn rent(&self, len: usize) -> Result<u64, String> {
+        self.rpc
+            .get_minimum_balance_for_rent_exemption(len)
+            .map_err(|e| e.to_string())
+    }
+
+    /// Instruction creating an account of `len` bytes owned by `owner`, rent exempt
+    fn create_account(
+        &self,
+        account: &Keypair,
+        len: usize,
+        owner: &Pubkey,
+    ) -> Result<Instruction, String> {
+        Ok(system_instruction::create_account(
+            &self.payer()?.pubkey(),
+            &account.pubkey(),
+            self.rent(len)?,
+            len as u64,
+            owner,
+        ))
+    }
+
+    /// Send the instructions in one transaction signed by the payer and `signers`
+    fn send(&self, instructions: &[Instruction], signers: &[&Keypair]) -> Result<(), String> {
+        let payer = self.payer()?;
+        let mut all_signers = vec![payer];
+        for signer in signers {
+            if all_signers.iter().all(|s| s.pubkey() != signer.pubkey()) {
+                all_signers.push(signer);
+            }
+        }
+        let blockhash = self.rpc.get_latest_blockhash().map_err(|e| e.to_string())?;
+        let transaction = Transaction::new_signed_with_payer(
+            instructions,
+            Some(&payer.pubkey()),
+            &all_signers,
+            blockhash,
+        );
+        let signature = self
+            .rpc
+            .send_and_confirm_transaction_with_spinner(&transaction)
+            .map_err(|e| format!("transaction failed: {}", e))?;
+        println!("signature: {}", signature);
+        Ok(())
+    }
+}
+
+fn default_keypair_path() -> Result<String, String> {
+    let home = std::env::var("HOME").map_err(|_| "HOME is not set, give --keypair".to_string())?;
+    Ok(format!("{}/.config/solana/id.json", home))
+}
+
+fn read_keypair(path: &str) -> Result<Keypair, String> {
+    read_keypair_file(path).map_err(|e| format!("cannot read keypair {}: {}", path, e))
+}
+
+/// Run the command of the command line
+pub fn run(args: &Args) -> Result<(), String> {
+    let context = Context::new(args)?;
+    match args.command() {
+        "create-config" => create_config(&context, args),
+        "init-pool" => init_pool(&context, args),
+        "set-fees" => update_fees(&context, args),
+        "set-slope" => update_slope(&context, args),
+        "set-decimals" => update_decimals(&context, args),
+        "pause" => set_paused(&context, args, true),
+        "unpause" => set_paused(&context, args, false),
+        "create-farm" => create_farm(&context, args),
+        "show-config" => show::<ConfigInfo>(&context, args),
+        "show-pool" => show::<SwapInfo>(&context, args),
+        "show-farm" => show::<FarmInfo>(&context, args),
+        command => Err(format!("unknown command {}, see deltafi-cli help", command)),
+    }
+}
+
+/// Override the fees given on the command line
+fn parse_fees(args: &Args, mut fees: Fees) -> Result<Fees, String> {
+    if let Some((numerator, denominator)) = args.fraction("trade-fee")? {
+        fees.trade_fee_numerator = numerator;
+        fees.trade_fee_denominator = denominator;
+    }
+    if let Some((numerator, denominator)) = args.fraction("admin-trade-fee")? {
+        fees.admin_trade_fee_numerator = numerator;
+        fees.admin_trade_fee_denominator = denominator;
+    }
+    if let Some((numerator, denominator)) = args.fraction("withdraw-fee")? {
+        fees.withdraw_fee_numerator = numerator;
+        fees.withdraw_fee_denominator = denominator;
+    }
+    if let Some((numerator, denominator)) = args.fraction("admin-withdraw-fee")? {
+        fees.admin_withdraw_fee_numerator = numerator;
+        fees.admin_withdraw_fee_denominator = denominator;
+    }
+    if let Some((numerator, denominator)) = args.fraction("flash-fee")? {
+        fees.flash_fee_numerator = numerator;
+        fees.flash_fee_denominator = denominator;
+    }
+    if let Some((numerator, denominator)) = args.fraction("treasury-fee")? {
+        fees.treasury_fee_numerator = numerator;
+        fees.treasury_fee_denominator = denominator;
+    }
+    Ok(fees)
+}
+
+fn create_config(context: &Context, args: &Args) -> Result<(), String> {
+    let deltafi_mint = args.pubkey("deltafi-mint")?;
+    let pyth_program_id = args
+        .parse_value("pyth-program-id")?
+        .unwrap_or_else(|| Pubkey::from_str(PYTH_PROGRAM_ID).unwrap());
+    // The fees left out are zero
+    let fees = parse_fees(
+        args,
+        Fees {
+            is_initialized: true,
+            admin_trade_fee_denominator: 1,
+            admin_withdraw_fee_denominator: 1,
+            trade_fee_denominator: 1,
+            withdraw_fee_denominator: 1,
+            flash_fee_denominator: 1,
+            treasury_fee_denominator: 1,
+            ..Fees::default()
+        },
+    )?;
+    let (trade_reward_numerator, trade_reward_denominator) = args
+        .fraction("trade-reward")?
+        .ok_or("missing --trade-reward")?;
+    let trade_reward_cap = args.required("trade-reward-cap")?;
+    let referral_fee_bps = args.parse_value("referral-fee-bps")?.unwrap_or_default();
+    args.check_unused()?;
+
+    let admin = context.admin()?;
+    let rewards = Rewards {
+        is_initialized: true,
+        decimals: context.unpack::<Mint>(&deltafi_mint)?.decimals,
+        referral_fee_bps,
+        trade_reward_numerator,
+        trade_reward_denominator,
+        trade_reward_cap,
+        ..Rewards::default()
+    };
+    let config = Keypair::new();
+    let (market_authority, _) =
+        Pubkey::find_program_address(&[config.pubkey().as_ref()], &context.program_id);
+    let deltafi_token = Keypair::new();
+
+    context.send(
+        &[
+            context.create_account(&deltafi_token, Token::LEN, &spl_token::id())?,
+            initialize_account(
+                &spl_token::id(),
+                &deltafi_token.pubkey(),
+                &deltafi_mint,
+                &market_authority,
+            )
+            .unwrap(),
+            context.create_account(&config, ConfigInfo::LEN, &context.program_id)?,
+            initialize_config(
+                context.program_id,
+                config.pubkey(),
+                market_authority,
+                deltafi_mint,
+                admin.pubkey(),
+                pyth_program_id,
+                fees,
+                rewards,
+                deltafi_token.pubkey(),
+            )
+            .unwrap(),
+        ],
+        &[&deltafi_token, &config, &admin],
+    )?;
+    println!("config: {}", config.pubkey());
+    println!("market authority: {}", market_authority);
+    println!("deltafi token: {}", deltafi_token.pubkey());
+    Ok(())
+}
+
+fn parse_oracle_priority(oracle: &str) -> Result<OraclePriorityFlag, String> {
+    match oracle {
+        "pyth" => Ok(OraclePriorityFlag::PYTH_ONLY),
+        "serum" => Ok(OraclePriorityFlag::SERUM_ONLY),
+        "pyth-with-serum-fallback" => Ok(OraclePriorityFlag::PYTH_WITH_SERUM_FALLBACK),
+        "manual" => Ok(OraclePriorityFlag::MANUAL),
+        "chainlink" => Ok(OraclePriorityFlag::CHAINLINK),
+        "pyth-pull" => Ok(OraclePriorityFlag::PYTH_PULL),
+        _ => Err(format!(
+            "invalid --oracle {}, expected pyth, serum, pyth-with-serum-fallback, manual, \
+             chainlink or pyth-pull",
+            oracle
+        )),
+    }
+}
+
+fn init_pool(context: &Context, args: &Args) -> Result<(), String> {
+    let config = args.pubkey("config")?;
+    let token_a = args.pubkey("token-a")?;
+    let token_b = args.pubkey("token-b")?;
+    let admin_fee_a = args.pubkey("admin-fee-a")?;
+    let admin_fee_b = args.pubkey("admin-fee-b")?;
+    let slope = args.scaled_decimal("slope")?.ok_or("missing --slope")? as u64;
+    let is_stable = args.switch("stable")?;
+    // Oracles of a normal pool, the ones left out are the default key
+    let oracle_priority = args
+        .value("oracle")?
+        .map(parse_oracle_priority)
+        .transpose()?
+        .unwrap_or(OraclePriorityFlag::PYTH_ONLY);
+    let oracle_key =
+        |name: &str| -> Result<Pubkey, String> { Ok(args.parse_value(name)?.unwrap_or_default()) };
+    let pyth_a_product = oracle_key("pyth-a-product")?;
+    let pyth_a = oracle_key("pyth-a")?;
+    let pyth_b_product = oracle_key("pyth-b-product")?;
+    let pyth_b = oracle_key("pyth-b")?;
+    let serum_market = oracle_key("serum-market")?;
+    let serum_bids = oracle_key("serum-bids")?;
+    let serum_asks = oracle_key("serum-asks")?;
+    let mid_price = args.scaled_decimal("mid-price")?.unwrap_or_default();
+    let quote_is_token_a = args.switch("quote-is-token-a")?;
+    args.check_unused()?;
+
+    let admin = context.admin()?;
+    let payer = context.payer()?.pubkey();
+    let token_a_account = context.unpack::<Token>(&token_a)?;
+    let token_b_account = context.unpack::<Token>(&token_b)?;
+    let token_a_decimals = context.unpack::<Mint>(&token_a_account.mint)?.decimals;
+    let token_b_decimals = context.unpack::<Mint>(&token_b_account.mint)?.decimals;
+
+    let swap = Keypair::new();
+    let (swap_authority, nonce) =
+        Pubkey::find_program_address(&[swap.pubkey().as_ref()], &context.program_id);
+    let pool_mint = Keypair::new();
+    let pool_token = Keypair::new();
+
+    // The pool mint and the pool token account of the payer first, the tokens only
+    // move to the swap authority in the transaction initializing the pool
+    context.send(
+        &[
+            context.create_account(&pool_mint, Mint::LEN, &spl_token::id())?,
+            initialize_mint(
+                &spl_token::id(),
+                &pool_mint.pubkey(),
+                &swap_authority,
+                None,
+                token_a_decimals,
+            )
+            .unwrap(),
+            context.create_account(&pool_token, Token::LEN, &spl_token::id())?,
+            initialize_account(
+                &spl_token::id(),
+                &pool_token.pubkey(),
+                &pool_mint.pubkey(),
+                &payer,
+            )
+            .unwrap(),
+        ],
+        &[&pool_mint, &pool_token],
+    )?;
+
+    let initialize_instruction = if is_stable {
+        stable_initialize(
+            context.program_id,
+            config,
+            swap.pubkey(),
+            swap_authority,
+            admin_fee_a,
+            admin_fee_b,
+            token_a,
+            token_b,
+            pool_mint.pubkey(),
+            pool_token.pubkey(),
+            admin.pubkey(),
+            StableInitializeData {
+                nonce,
+                slope,
+                token_a_decimals,
+                token_b_decimals,
+                token_a_amount: token_a_account.amount,
+                token_b_amount: token_b_account.amount,
+            },
+        )
+    } else {
+        initialize(
+            context.program_id,
+            config,
+            swap.pubkey(),
+            swap_authority,
+            admin_fee_a,
+            admin_fee_b,
+            token_a,
+            token_b,
+            pool_mint.pubkey(),
+            pool_token.pubkey(),
+            pyth_a_product,
+            pyth_a,
+            pyth_b_product,
+            pyth_b,
+            admin.pubkey(),
+            serum_market,
+            serum_bids,
+            serum_asks,
+            InitializeData {
+                nonce,
+                mid_price,
+                slope,
+                token_a_decimals,
+                token_b_decimals,
+                token_a_amount: token_a_account.amount,
+                token_b_amount: token_b_account.amount,
+                oracle_priority_flags: oracle_priority.bits(),
+                quote_is_token_a,
+            },
+        )
+    }
+    .unwrap();
+
+    let mut instructions = vec![];
+    for token in &[token_a, token_b] {
+        instructions.push(
+            set_authority(
+                &spl_token::id(),
+                token,
+                Some(&swap_authority),
+                AuthorityType::AccountOwner,
+                &payer,
+                &[],
+            )
+            .unwrap(),
+        );
+    }
+    instructions.push(context.create_account(&swap, SwapInfo::LEN, &context.program_id)?);
+    instructions.push(initialize_instruction);
+    context.send(&instructions, &[&swap, &admin])?;
+
+    println!("pool: {}", swap.pubkey());
+    println!("swap authority: {}", swap_authority);
+    println!("pool mint: {}", pool_mint.pubkey());
+    println!("pool token: {}", pool_token.pubkey());
+    Ok(())
+}
+
+fn update_fees(context: &Context, args: &Args) -> Result<(), String> {
+    let config = args.pubkey("config")?;
+    let swap = args.pubkey("pool")?;
+    let current_fees = context.unpack::<SwapInfo>(&swap)?.fees;
+    let fees = parse_fees(args, current_fees)?;
+    args.check_unused()?;
+
+    let admin = context.admin()?;
+    context.send(
+        &[set_new_fees(context.program_id, config, swap, admin.pubkey(), fees).unwrap()],
+        &[&admin],
+    )
+}
+
+fn update_slope(context: &Context, args: &Args) -> Result<(), String> {
+    let config = args.pubkey("config")?;
+    let swap = args.pubkey("pool")?;
+    let slope = args.scaled_decimal("slope")?.ok_or("missing --slope")? as u64;
+    args.check_unused()?;
+
+    let admin = context.admin()?;
+    context.send(
+        &[set_slope(context.program_id, config, swap, admin.pubkey(), slope).unwrap()],
+        &[&admin],
+    )
+}
+
+fn update_decimals(context: &Context, args: &Args) -> Result<(), String> {
+    let config = args.pubkey("config")?;
+    let swap = args.pubkey("pool")?;
+    let base_decimals = args.required("base-decimals")?;
+    let quote_decimals = args.required("quote-decimals")?;
+    args.check_unused()?;
+
+    let admin = context.admin()?;
+    context.send(
+        &[set_decimals(
+            context.program_id,
+            config,
+            swap,
+            admin.pubkey(),
+            base_decimals,
+            quote_decimals,
+        )
+        .unwrap()],
+        &[&admin],
+    )
+}
+
+fn set_paused(context: &Context, args: &Args, is_paused: bool) -> Result<(), String> {
+    let config = args.pubkey("config")?;
+    let swap = args.pubkey("pool")?;
+    args.check_unused()?;
+
+    let admin = context.admin()?;
+    let instruction = if is_paused {
+        pause(context.program_id, config, swap, admin.pubkey())
+    } else {
+        unpause(context.program_id, config, swap, admin.pubkey())
+    };
+    context.send(&[instruction.unwrap()], &[&admin])
+}
+
+fn create_farm(context: &Context, args: &Args) -> Result<(), String> {
+    let config = args.pubkey("config")?;
+    let swap = args.pubkey("pool")?;
+    let (fee_numerator, fee_denominator) = args.fraction("fee")?.ok_or("missing --fee")?;
+    let (rewards_numerator, rewards_denominator) =
+        args.fraction("rewards")?.ok_or("missing --rewards")?;
+    args.check_unused()?;
+
+    let admin = context.admin()?;
+    let pool_mint = context.unpack::<SwapInfo>(&swap)?.pool_mint;
+    let farm = Keypair::new();
+    let (farm_authority, bump_seed) =
+        Pubkey::find_program_address(&[farm.pubkey().as_ref()], &context.program_id);
+    let pool_token = Keypair::new();
+
+    context.send(
+        &[
+            context.create_account(&pool_token, Token::LEN, &spl_token::id())?,
+            initialize_account(
+                &spl_token::id(),
+                &pool_token.pubkey(),
+                &pool_mint,
+                &farm_authority,
+            )
+            .unwrap(),
+            context.create_account(&farm, FarmInfo::LEN, &context.program_id)?,
+            farm_initialize(
+                context.program_id,
+                config,
+                swap,
+                farm.pubkey(),
+                farm_authority,
+                pool_token.pubkey(),
+                admin.pubkey(),
+                FarmInitializeData {
+                    fee_numerator,
+                    fee_denominator,
+                    rewards_numerator,
+                    rewards_denominator,
+                    bump_seed,
+                },
+            )
+            .unwrap(),
+        ],
+        &[&pool_token, &farm, &admin],
+    )?;
+    println!("farm: {}", farm.pubkey());
+    println!("farm authority: {}", farm_authority);
+    println!("farm pool token: {}", pool_token.pubkey());
+    Ok(())
+}
+
+/// Replace the 32 byte arrays, the only ones of the account states being the keys, with
+/// their base58 form
+fn keys_to_base58(value: &mut Value) {
+    match value {
+        Value::Array(items) if items.len() == 32 && items.iter().all(Value::is_u64) => {
+            let bytes: Vec<u8> = items
+                .iter()
+                .map(|item| item.as_u64().unwrap() as u8)
+                .collect();
+            *value = Value::String(Pubkey::new(&bytes).to_string());
+        }
+        Value::Array(items) => items.iter_mut().for_each(keys_to_base58),
+        Value::Object(fields) => fields.values_mut().for_each(keys_to_base58),
+        _ => {}
+    }
+}
+
+/// Print the account state of the positional pubkey as JSON
+fn show<T: Pack + IsInitialized + Serialize>(context: &Context, args: &Args) -> Result<(), String> {
+    let pubkey = Pubkey::from_str(args.positional(0, "PUBKEY")?)
+        .map_err(|e| format!("invalid pubkey: {}", e))?;
+    args.check_unused()?;
+
+    let state = context.unpack::<T>(&pubkey)?;
+    let mut value = serde_json::to_value(&state).map_err(|e| e.to_string())?;
+    keys_to_base58(&mut value);
+    println!("{}", serde_json::to_string_pretty(&value).unwrap());
+    Ok(())
+}
@@ -0,0 +1,65 @@
+//! Admin and pool operations of the program from the command line, wrapping the
+//! instruction builders: create a config, initialize pools, update their parameters,
+//! create farms and print the on-chain state
+//!
+//! Usage: `deltafi-cli <command> [args] [--url <URL>] [--keypair <PATH>]`, see
+//! `deltafi-cli help` for the commands.
+
+mod args;
+mod command;
+
+use args::Args;
+use std::process;
+
+const USAGE: &str = "usage: deltafi-cli <command> [args] [options]
+
+Options:
+    --url <URL>             JSON RPC endpoint, http://localhost:8899 by default
+    --keypair <PATH>        payer, ~/.config/solana/id.json by default
+    --admin <PATH>          admin of the config, the payer by default
+    --program-id <PUBKEY>   deployed program, the program id of the crate by default
+
+Fees and rates are fractions N/D, slopes and prices decimals such as 0.5.
+
+Commands:
+    create-config --deltafi-mint <PUBKEY> --trade-reward <N/D> --trade-reward-cap <AMOUNT>
+        [--trade-fee <N/D>] [--admin-trade-fee <N/D>] [--withdraw-fee <N/D>]
+        [--admin-withdraw-fee <N/D>] [--flash-fee <N/D>] [--treasury-fee <N/D>]
+        [--referral-fee-bps <BPS>] [--pyth-program-id <PUBKEY>]
+    init-pool --config <PUBKEY> --token-a <PUBKEY> --token-b <PUBKEY>
+        --admin-fee-a <PUBKEY> --admin-fee-b <PUBKEY> --slope <DECIMAL> [--stable]
+        [--oracle pyth|serum|pyth-with-serum-fallback|manual|chainlink|pyth-pull]
+        [--pyth-a-product <PUBKEY>] [--pyth-a <PUBKEY>] [--pyth-b-product <PUBKEY>]
+        [--pyth-b <PUBKEY>] [--serum-market <PUBKEY>] [--serum-bids <PUBKEY>]
+        [--serum-asks <PUBKEY>] [--mid-price <DECIMAL>] [--quote-is-token-a]
+        the token accounts of the payer holding the initial deposit move to the pool,
+        the chainlink feeds or the pyth price updates of the tokens are given as --pyth-a
+        and --pyth-b
+    set-fees --config <PUBKEY> --pool <PUBKEY> [fee options of create-config]
+    set-slope --config <PUBKEY> --pool <PUBKEY> --slope <DECIMAL>
+    set-decimals --config <PUBKEY> --pool <PUBKEY> --base-decimals <N> --quote-decimals <N>
+    pause --config <PUBKEY> --pool <PUBKEY>
+    unpause --config <PUBKEY> --pool <PUBKEY>
+    create-farm --config <PUBKEY> --pool <PUBKEY> --fee <N/D> --rewards <N/D>
+    show-config <PUBKEY>
+    show-pool <PUBKEY>
+    show-farm <PUBKEY>";
+
+fn main() {
+    let args = match Args::parse(std::env::args().skip(1)) {
+        Ok(args) => args,
+        Err(e) => {
+            eprintln!("{}\n\n{}", e, USAGE);
+            process::exit(2);
+        }
+    };
+    if args.command() == "help" {
+        println!("{}", USAGE);
+        return;
+    }
+
+    if let Err(e) = command::run(&args) {
+        eprintln!("error: {}", e);
+        process::exit(1);
+    }
+}
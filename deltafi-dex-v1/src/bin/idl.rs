@@ -0,0 +1,11 @@
+//! Print the IDL of the program as JSON
+//!
+//! Usage: `cargo run --features idl --bin idl > deltafi_swap.json`
+
+fn main() {
+    let idl = deltafi_swap::idl::idl();
+    println!(
+        "{}",
+        serde_json::to_string_pretty(&idl).expect("IDL serializes to JSON")
+    );
+}
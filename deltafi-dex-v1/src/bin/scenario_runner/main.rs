@@ -0,0 +1,100 @@
+//! Regression harness replaying YAML scenarios of multi-instruction user flows on a
+//! `ProgramTest` bank and asserting the final balances
+//!
+//! Usage: `scenario-runner <scenario.yaml | directory>...`, run from the crate root
+//! so the test fixtures are found.
+
+mod runner;
+mod scenario;
+#[path = "../../../tests/utils/mod.rs"]
+mod utils;
+
+use scenario::Scenario;
+use std::{
+    fs,
+    path::{Path, PathBuf},
+    process,
+};
+
+fn scenario_paths(arg: &str) -> Result<Vec<PathBuf>, String> {
+    let path = Path::new(arg);
+    if !path.is_dir() {
+        return Ok(vec![path.to_path_buf()]);
+    }
+
+    let mut paths = vec![];
+    for entry in fs::read_dir(path).map_err(|e| format!("cannot read {}: {}", arg, e))? {
+        let path = entry.map_err(|e| e.to_string())?.path();
+        if matches!(
+            path.extension().and_then(|e| e.to_str()),
+            Some("yaml") | Some("yml")
+        ) {
+            paths.push(path);
+        }
+    }
+    paths.sort();
+    Ok(paths)
+}
+
+fn panic_message(panic: Box<dyn std::any::Any + Send>) -> String {
+    if let Some(message) = panic.downcast_ref::<&str>() {
+        message.to_string()
+    } else if let Some(message) = panic.downcast_ref::<String>() {
+        message.clone()
+    } else {
+        "panicked".to_string()
+    }
+}
+
+#[tokio::main]
+async fn main() {
+    let args: Vec<String> = std::env::args().skip(1).collect();
+    if args.is_empty() {
+        eprintln!("usage: scenario-runner <scenario.yaml | directory>...");
+        process::exit(2);
+    }
+
+    let mut paths = vec![];
+    for arg in &args {
+        match scenario_paths(arg) {
+            Ok(found) => paths.extend(found),
+            Err(e) => {
+                eprintln!("{}", e);
+                process::exit(2);
+            }
+        }
+    }
+
+    let mut failed = 0;
+    for path in &paths {
+        let scenario = match Scenario::load(path) {
+            Ok(scenario) => scenario,
+            Err(e) => {
+                println!("FAIL {}\n{}", path.display(), e);
+                failed += 1;
+                continue;
+            }
+        };
+
+        // A failing helper panics, the scenario runs in its own task so the others still run
+        let name = scenario.name.clone();
+        let result = match tokio::spawn(runner::run(scenario)).await {
+            Ok(result) => result,
+            Err(e) if e.is_panic() => Err(panic_message(e.into_panic())),
+            Err(e) => Err(e.to_string()),
+        };
+
+        match result {
+            Ok(()) => println!("PASS {}", name),
+            Err(e) => {
+                println!("FAIL {}\n{}", name, e);
+                failed += 1;
+            }
+        }
+    }
+
+    println!("{} passed, {} failed", paths.len() - failed, failed);
+    if failed > 0 {
+        process::exit(1);
+    }
+}
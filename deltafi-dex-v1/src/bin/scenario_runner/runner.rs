@@ -0,0 +1,476 @@
+//! Replay of a scenario on a `ProgramTest` bank
+
+use crate::{
+    scenario::{Expectation, Scenario, Step, Token},
+    utils::*,
+};
+
+use deltafi_swap::{
+    instruction::{farm_claim, swap, SwapData},
+    math::{Decimal, TryDiv},
+    processor::{get_referrer_data_pubkey, process},
+    state::SwapType,
+};
+
+use solana_program_test::*;
+use solana_sdk::{
+    pubkey::Pubkey,
+    signature::{Keypair, Signer},
+    transaction::Transaction,
+};
+use spl_token::instruction::approve;
+use std::collections::HashMap;
+
+struct Pool {
+    info: TestSwapInfo,
+    farm: Option<TestFarmPoolInfo>,
+}
+
+struct User {
+    owner: Keypair,
+    sol: Pubkey,
+    srm: Pubkey,
+    deltafi: Pubkey,
+    lp: HashMap<String, Pubkey>,
+    farm_users: HashMap<String, TestFarmUser>,
+    referrer: Option<(Pubkey, Pubkey)>,
+}
+
+impl User {
+    fn token(&self, token: Token, pool: Option<&String>) -> Pubkey {
+        match token {
+            Token::Sol => self.sol,
+            Token::Srm => self.srm,
+            Token::Deltafi => self.deltafi,
+            Token::Lp => self.lp[pool.unwrap()],
+        }
+    }
+}
+
+/// Run the scenario on a fresh bank, checking its expectations after the last step
+pub async fn run(scenario: Scenario) -> Result<(), String> {
+    let mut test = ProgramTest::new("deltafi_swap", deltafi_swap::id(), processor!(process));
+
+    let swap_config = add_swap_config(&mut test);
+    let sol_oracle = add_sol_oracle(&mut test);
+    let srm_oracle = add_srm_oracle(&mut test);
+    let srm_mint = add_srm_mint(&mut test);
+    let market_price = sol_oracle.price.try_div(srm_oracle.price).unwrap();
+
+    // The initial pool tokens go to a pool owner outside of the scenario users
+    let pool_owner = Keypair::new();
+    let admin_fee_owner = Keypair::new();
+    let swap_infos: Vec<TestSwapInfo> = scenario
+        .pools
+        .iter()
+        .map(|spec| {
+            add_swap_info(
+                SwapType::Normal,
+                &mut test,
+                &swap_config,
+                &pool_owner,
+                &admin_fee_owner,
+                AddSwapInfoArgs {
+                    token_a_mint: spl_token::native_mint::id(),
+                    token_b_mint: srm_mint.pubkey,
+                    token_a_amount: spec.token_a_amount,
+                    token_b_amount: spec.token_b_amount,
+                    oracle_a: sol_oracle.price_pubkey,
+                    oracle_b: srm_oracle.price_pubkey,
+                    market_price,
+                    slope: Decimal::one().try_div(2).unwrap(),
+                    last_market_price: market_price,
+                    last_valid_market_price_slot: 0,
+                    swap_out_limit_percentage: spec.swap_out_limit_percentage,
+                    ..AddSwapInfoArgs::default()
+                },
+            )
+        })
+        .collect();
+
+    let mut context = test.start_with_context().await;
+
+    let mut pools = HashMap::new();
+    for (spec, info) in scenario.pools.iter().zip(swap_infos) {
+        let farm = match &spec.farm {
+            Some(farm) => Some(
+                TestFarmPoolInfo::init(
+                    &mut context.banks_client,
+                    &swap_config,
+                    &info,
+                    &context.payer,
+                    farm.fee_numerator,
+                    farm.fee_denominator,
+                    farm.rewards_numerator,
+                    farm.rewards_denominator,
+                )
+                .await,
+            ),
+            None => None,
+        };
+        pools.insert(spec.name.clone(), Pool { info, farm });
+    }
+
+    let mut users = HashMap::new();
+    for spec in &scenario.users {
+        let owner = Keypair::new();
+        let sol = create_and_mint_to_token_account(
+            &mut context.banks_client,
+            spl_token::native_mint::id(),
+            None,
+            &context.payer,
+            owner.pubkey(),
+            spec.sol,
+        )
+        .await;
+        let srm = create_and_mint_to_token_account(
+            &mut context.banks_client,
+            srm_mint.pubkey,
+            Some(&srm_mint.authority),
+            &context.payer,
+            owner.pubkey(),
+            spec.srm,
+        )
+        .await;
+        let deltafi = create_and_mint_to_token_account(
+            &mut context.banks_client,
+            swap_config.deltafi_mint,
+            None,
+            &context.payer,
+            owner.pubkey(),
+            0,
+        )
+        .await;
+
+        let mut lp = HashMap::new();
+        let mut farm_users = HashMap::new();
+        for (name, pool) in &pools {
+            let pool_token = create_and_mint_to_token_account(
+                &mut context.banks_client,
+                pool.info.pool_mint,
+                None,
+                &context.payer,
+                owner.pubkey(),
+                0,
+            )
+            .await;
+            lp.insert(name.clone(), pool_token);
+
+            if let Some(farm) = &pool.farm {
+                let farm_user = TestFarmUser::init(
+                    &mut context.banks_client,
+                    swap_config.pubkey,
+                    farm.farm_pool_key,
+                    &owner,
+                    &context.payer,
+                )
+                .await;
+                farm_users.insert(name.clone(), farm_user);
+            }
+        }
+
+        users.insert(
+            spec.name.clone(),
+            User {
+                owner,
+                sol,
+                srm,
+                deltafi,
+                lp,
+                farm_users,
+                referrer: None,
+            },
+        );
+    }
+
+    // Referrers are set once every user has its DELTAFI account
+    for spec in &scenario.users {
+        let referrer = match &spec.referrer {
+            Some(referrer) => users[referrer].deltafi,
+            None => continue,
+        };
+        let user = &users[&spec.name];
+        let user_referrer_data_pubkey = get_referrer_data_pubkey(
+            &user.owner.pubkey(),
+            &swap_config.pubkey,
+            &deltafi_swap::id(),
+        )
+        .unwrap();
+        // Setting a referrer does not depend on the pool
+        pools
+            .values()
+            .next()
+            .unwrap()
+            .info
+            .set_referrer(
+                &mut context.banks_client,
+                &swap_config,
+                &user.owner,
+                user_referrer_data_pubkey,
+                referrer,
+                &context.payer,
+            )
+            .await;
+        users.get_mut(&spec.name).unwrap().referrer = Some((user_referrer_data_pubkey, referrer));
+    }
+
+    for (i, step) in scenario.steps.iter().enumerate() {
+        match step {
+            Step::Swap {
+                user,
+                pool,
+                sell,
+                amount_in,
+                minimum_amount_out,
+            } => {
+                let user = &users[user];
+                let pool = &pools[pool].info;
+                let (
+                    source,
+                    swap_source,
+                    source_mint,
+                    swap_destination,
+                    destination,
+                    destination_mint,
+                    admin_fee,
+                ) = if *sell == Token::Sol {
+                    (
+                        user.sol,
+                        pool.token_a,
+                        pool.token_a_mint,
+                        pool.token_b,
+                        user.srm,
+                        pool.token_b_mint,
+                        pool.admin_fee_b_key,
+                    )
+                } else {
+                    (
+                        user.srm,
+                        pool.token_b,
+                        pool.token_b_mint,
+                        pool.token_a,
+                        user.sol,
+                        pool.token_a_mint,
+                        pool.admin_fee_a_key,
+                    )
+                };
+
+                let user_transfer_authority = Keypair::new();
+                let mut transaction = Transaction::new_with_payer(
+                    &[
+                        approve(
+                            &spl_token::id(),
+                            &source,
+                            &user_transfer_authority.pubkey(),
+                            &user.owner.pubkey(),
+                            &[],
+                            *amount_in,
+                        )
+                        .unwrap(),
+                        swap(
+                            deltafi_swap::id(),
+                            swap_config.pubkey,
+                            pool.pubkey,
+                            swap_config.market_authority,
+                            pool.authority,
+                            user_transfer_authority.pubkey(),
+                            source,
+                            swap_source,
+                            source_mint,
+                            swap_destination,
+                            destination,
+                            destination_mint,
+                            user.deltafi,
+                            swap_config.deltafi_token,
+                            admin_fee,
+                            pool.oracle_a,
+                            pool.oracle_b,
+                            None,
+                            None,
+                            None,
+                            None,
+                            user.referrer.map(|(data, _)| data),
+                            user.referrer.map(|(_, token)| token),
+                            None,
+                            SwapData {
+                                amount_in: *amount_in,
+                                minimum_amount_out: *minimum_amount_out,
+                                deadline_slot: None,
+                                output_owner: None,
+                            },
+                        )
+                        .unwrap(),
+                    ],
+                    Some(&context.payer.pubkey()),
+                );
+                let recent_blockhash = context.banks_client.get_recent_blockhash().await.unwrap();
+                transaction.sign(
+                    &[&context.payer, &user.owner, &user_transfer_authority],
+                    recent_blockhash,
+                );
+                context
+                    .banks_client
+                    .process_transaction(transaction)
+                    .await
+                    .map_err(|e| format!("step {} swap failed: {}", i, e))?;
+            }
+            Step::Deposit {
+                user,
+                pool,
+                token_a_amount,
+                token_b_amount,
+                min_mint_amount,
+            } => {
+                let user = &users[user];
+                pools[pool]
+                    .info
+                    .deposit(
+                        SwapType::Normal,
+                        &mut context.banks_client,
+                        &user.owner,
+                        user.sol,
+                        user.srm,
+                        user.lp[pool],
+                        *token_a_amount,
+                        *token_b_amount,
+                        *min_mint_amount,
+                        &context.payer,
+                    )
+                    .await;
+            }
+            Step::Withdraw {
+                user,
+                pool,
+                pool_token_amount,
+                minimum_token_a_amount,
+                minimum_token_b_amount,
+            } => {
+                let user = &users[user];
+                pools[pool]
+                    .info
+                    .withdraw(
+                        SwapType::Normal,
+                        &mut context.banks_client,
+                        &user.owner,
+                        user.sol,
+                        user.srm,
+                        user.lp[pool],
+                        *pool_token_amount,
+                        *minimum_token_a_amount,
+                        *minimum_token_b_amount,
+                        &context.payer,
+                    )
+                    .await;
+            }
+            Step::FarmDeposit { user, pool, amount } => {
+                let user = &users[user];
+                let farm = pools[pool].farm.as_ref().unwrap();
+                user.farm_users[pool]
+                    .do_farm_deposit(
+                        &mut context.banks_client,
+                        &user.owner,
+                        user.lp[pool],
+                        farm.farm_pool_token,
+                        *amount,
+                        &context.payer,
+                    )
+                    .await;
+            }
+            Step::FarmWithdraw { user, pool, amount } => {
+                let user = &users[user];
+                let farm = pools[pool].farm.as_ref().unwrap();
+                user.farm_users[pool]
+                    .do_farm_withdraw(
+                        &mut context.banks_client,
+                        &user.owner,
+                        user.lp[pool],
+                        farm.farm_pool_token,
+                        farm.authority,
+                        *amount,
+                        &context.payer,
+                    )
+                    .await;
+            }
+            Step::FarmClaim { user, pool } => {
+                let user = &users[user];
+                let farm = pools[pool].farm.as_ref().unwrap();
+                let mut transaction = Transaction::new_with_payer(
+                    &[farm_claim(
+                        deltafi_swap::id(),
+                        swap_config.pubkey,
+                        farm.farm_pool_key,
+                        user.farm_users[pool].pubkey,
+                        user.owner.pubkey(),
+                        swap_config.market_authority,
+                        user.deltafi,
+                        swap_config.deltafi_token,
+                    )
+                    .unwrap()],
+                    Some(&context.payer.pubkey()),
+                );
+                let recent_blockhash = context.banks_client.get_recent_blockhash().await.unwrap();
+                transaction.sign(&[&context.payer, &user.owner], recent_blockhash);
+                context
+                    .banks_client
+                    .process_transaction(transaction)
+                    .await
+                    .map_err(|e| format!("step {} farm claim failed: {}", i, e))?;
+            }
+            Step::Warp { slots } => {
+                let slot = context.banks_client.get_root_slot().await.unwrap();
+                context
+                    .warp_to_slot(slot + slots)
+                    .map_err(|e| format!("step {} warp failed: {:?}", i, e))?;
+            }
+        }
+    }
+
+    let mut failures = vec![];
+    for expectation in &scenario.expect {
+        let Expectation {
+            user,
+            token,
+            pool,
+            balance,
+            min,
+            max,
+        } = expectation;
+        let account = users[user].token(*token, pool.as_ref());
+        let actual = get_token_balance(&mut context.banks_client, account).await;
+        if balance.map_or(false, |b| actual != b)
+            || min.map_or(false, |m| actual < m)
+            || max.map_or(false, |m| actual > m)
+        {
+            failures.push(format!(
+                "{} {:?}{}: balance {}, expected {}",
+                user,
+                token,
+                pool.as_ref()
+                    .map(|p| format!(" of {}", p))
+                    .unwrap_or_default(),
+                actual,
+                describe(expectation),
+            ));
+        }
+    }
+
+    if failures.is_empty() {
+        Ok(())
+    } else {
+        Err(failures.join("\n"))
+    }
+}
+
+fn describe(expectation: &Expectation) -> String {
+    let mut bounds = vec![];
+    if let Some(balance) = expectation.balance {
+        bounds.push(format!("= {}", balance));
+    }
+    if let Some(min) = expectation.min {
+        bounds.push(format!(">= {}", min));
+    }
+    if let Some(max) = expectation.max {
+        bounds.push(format!("<= {}", max));
+    }
+    bounds.join(" and ")
+}
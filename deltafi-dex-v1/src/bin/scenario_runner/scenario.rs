@@ -0,0 +1,321 @@
+//! YAML scenario file layout
+//!
+//! Every pool of a scenario is a SOL/SRM normal pool priced by the pyth fixtures,
+//! token a is SOL and token b is SRM.
+
+use serde::Deserialize;
+use std::{collections::HashSet, fs, path::Path};
+
+/// Scenario replayed by the runner
+#[derive(Debug, Deserialize)]
+#[serde(deny_unknown_fields)]
+pub struct Scenario {
+    /// Name of the scenario in the report
+    pub name: String,
+    /// Pools created before the validator starts
+    #[serde(default)]
+    pub pools: Vec<PoolSpec>,
+    /// Users funded after the validator starts
+    #[serde(default)]
+    pub users: Vec<UserSpec>,
+    /// Steps executed in order
+    #[serde(default)]
+    pub steps: Vec<Step>,
+    /// Balances checked after the last step
+    #[serde(default)]
+    pub expect: Vec<Expectation>,
+}
+
+/// SOL/SRM pool
+#[derive(Debug, Deserialize)]
+#[serde(deny_unknown_fields)]
+pub struct PoolSpec {
+    /// Name the steps refer to the pool by
+    pub name: String,
+    /// SOL reserve in lamports
+    pub token_a_amount: u64,
+    /// SRM reserve in the smallest SRM unit
+    pub token_b_amount: u64,
+    /// Max percentage of a reserve a single swap can take out
+    #[serde(default = "default_swap_out_limit_percentage")]
+    pub swap_out_limit_percentage: u8,
+    /// Farm of the pool token, no farm if not set
+    #[serde(default)]
+    pub farm: Option<FarmSpec>,
+}
+
+fn default_swap_out_limit_percentage() -> u8 {
+    10
+}
+
+/// Farm of a pool
+#[derive(Debug, Deserialize)]
+#[serde(deny_unknown_fields)]
+pub struct FarmSpec {
+    /// Fee numerator
+    pub fee_numerator: u64,
+    /// Fee denominator
+    pub fee_denominator: u64,
+    /// Rewards numerator
+    pub rewards_numerator: u64,
+    /// Rewards denominator
+    pub rewards_denominator: u64,
+}
+
+/// User with SOL, SRM, DELTAFI and pool token accounts
+#[derive(Debug, Deserialize)]
+#[serde(deny_unknown_fields)]
+pub struct UserSpec {
+    /// Name the steps refer to the user by
+    pub name: String,
+    /// Initial SOL balance in lamports
+    #[serde(default)]
+    pub sol: u64,
+    /// Initial SRM balance in the smallest SRM unit
+    #[serde(default)]
+    pub srm: u64,
+    /// User receiving the referral rewards of the swaps of this user
+    #[serde(default)]
+    pub referrer: Option<String>,
+}
+
+/// Token account of a user
+#[derive(Clone, Copy, Debug, Deserialize, PartialEq)]
+#[serde(rename_all = "snake_case")]
+pub enum Token {
+    /// SOL
+    Sol,
+    /// SRM
+    Srm,
+    /// DELTAFI rewards
+    Deltafi,
+    /// Pool token of a pool
+    Lp,
+}
+
+/// Step of a scenario
+#[derive(Debug, Deserialize)]
+#[serde(rename_all = "snake_case", deny_unknown_fields)]
+pub enum Step {
+    /// Swap SOL for SRM or SRM for SOL
+    Swap {
+        /// User swapping
+        user: String,
+        /// Pool swapped with
+        pool: String,
+        /// Token sold, `sol` or `srm`
+        sell: Token,
+        /// Amount sold
+        amount_in: u64,
+        /// Minimum amount bought
+        #[serde(default)]
+        minimum_amount_out: u64,
+    },
+    /// Deposit both tokens into a pool
+    Deposit {
+        /// User depositing
+        user: String,
+        /// Pool deposited into
+        pool: String,
+        /// SOL amount
+        token_a_amount: u64,
+        /// SRM amount
+        token_b_amount: u64,
+        /// Minimum pool token amount minted
+        #[serde(default)]
+        min_mint_amount: u64,
+    },
+    /// Withdraw both tokens from a pool
+    Withdraw {
+        /// User withdrawing
+        user: String,
+        /// Pool withdrawn from
+        pool: String,
+        /// Pool token amount burned
+        pool_token_amount: u64,
+        /// Minimum SOL amount
+        #[serde(default)]
+        minimum_token_a_amount: u64,
+        /// Minimum SRM amount
+        #[serde(default)]
+        minimum_token_b_amount: u64,
+    },
+    /// Deposit pool tokens into the farm of a pool
+    FarmDeposit {
+        /// User depositing
+        user: String,
+        /// Pool of the farm
+        pool: String,
+        /// Pool token amount
+        amount: u64,
+    },
+    /// Withdraw pool tokens from the farm of a pool
+    FarmWithdraw {
+        /// User withdrawing
+        user: String,
+        /// Pool of the farm
+        pool: String,
+        /// Pool token amount
+        amount: u64,
+    },
+    /// Claim the farm rewards into the DELTAFI account of the user
+    FarmClaim {
+        /// User claiming
+        user: String,
+        /// Pool of the farm
+        pool: String,
+    },
+    /// Move the bank forward, the oracle fixtures are not refreshed
+    Warp {
+        /// Number of slots
+        slots: u64,
+    },
+}
+
+/// Token balance of a user after the last step, either an exact balance or a range
+#[derive(Debug, Deserialize)]
+#[serde(deny_unknown_fields)]
+pub struct Expectation {
+    /// User of the token account
+    pub user: String,
+    /// Token of the account
+    pub token: Token,
+    /// Pool of the `lp` token
+    #[serde(default)]
+    pub pool: Option<String>,
+    /// Exact balance
+    #[serde(default)]
+    pub balance: Option<u64>,
+    /// Minimum balance
+    #[serde(default)]
+    pub min: Option<u64>,
+    /// Maximum balance
+    #[serde(default)]
+    pub max: Option<u64>,
+}
+
+impl Scenario {
+    /// Read and validate a scenario file
+    pub fn load(path: &Path) -> Result<Self, String> {
+        let file = fs::read_to_string(path)
+            .map_err(|e| format!("cannot read {}: {}", path.display(), e))?;
+        let scenario: Self = serde_yaml::from_str(&file)
+            .map_err(|e| format!("cannot parse {}: {}", path.display(), e))?;
+        scenario.validate()?;
+        Ok(scenario)
+    }
+
+    /// Check the names the steps and expectations refer to, before anything runs
+    fn validate(&self) -> Result<(), String> {
+        let pools = unique_names(self.pools.iter().map(|p| p.name.as_str()), "pool")?;
+        let users = unique_names(self.users.iter().map(|u| u.name.as_str()), "user")?;
+        let farms: HashSet<&str> = self
+            .pools
+            .iter()
+            .filter(|p| p.farm.is_some())
+            .map(|p| p.name.as_str())
+            .collect();
+
+        let user = |name: &str| {
+            if users.contains(name) {
+                Ok(())
+            } else {
+                Err(format!("unknown user {}", name))
+            }
+        };
+        let pool = |name: &str| {
+            if pools.contains(name) {
+                Ok(())
+            } else {
+                Err(format!("unknown pool {}", name))
+            }
+        };
+        let farm = |name: &str| {
+            if farms.contains(name) {
+                Ok(())
+            } else {
+                Err(format!("pool {} has no farm", name))
+            }
+        };
+
+        for spec in &self.users {
+            if let Some(referrer) = &spec.referrer {
+                user(referrer)?;
+                if pools.is_empty() {
+                    return Err("referrers need a pool".to_string());
+                }
+            }
+        }
+
+        for (i, step) in self.steps.iter().enumerate() {
+            match step {
+                Step::Swap {
+                    user: u,
+                    pool: p,
+                    sell,
+                    ..
+                } => {
+                    user(u)?;
+                    pool(p)?;
+                    if *sell != Token::Sol && *sell != Token::Srm {
+                        return Err(format!("step {}: only sol or srm can be sold", i));
+                    }
+                }
+                Step::Deposit {
+                    user: u, pool: p, ..
+                }
+                | Step::Withdraw {
+                    user: u, pool: p, ..
+                } => {
+                    user(u)?;
+                    pool(p)?;
+                }
+                Step::FarmDeposit {
+                    user: u, pool: p, ..
+                }
+                | Step::FarmWithdraw {
+                    user: u, pool: p, ..
+                }
+                | Step::FarmClaim { user: u, pool: p } => {
+                    user(u)?;
+                    farm(p)?;
+                }
+                Step::Warp { .. } => {}
+            }
+        }
+
+        for expectation in &self.expect {
+            user(&expectation.user)?;
+            match (expectation.token, &expectation.pool) {
+                (Token::Lp, Some(p)) => pool(p)?,
+                (Token::Lp, None) => return Err("lp expectation without a pool".to_string()),
+                (_, Some(_)) => return Err("pool set on a non lp expectation".to_string()),
+                (_, None) => {}
+            }
+            if expectation.balance.is_none()
+                && expectation.min.is_none()
+                && expectation.max.is_none()
+            {
+                return Err(format!(
+                    "expectation of {} sets no balance, min or max",
+                    expectation.user
+                ));
+            }
+        }
+
+        Ok(())
+    }
+}
+
+fn unique_names<'a>(
+    names: impl Iterator<Item = &'a str>,
+    kind: &str,
+) -> Result<HashSet<&'a str>, String> {
+    let mut set = HashSet::new();
+    for name in names {
+        if !set.insert(name) {
+            return Err(format!("duplicate {} {}", kind, name));
+        }
+    }
+    Ok(set)
+}
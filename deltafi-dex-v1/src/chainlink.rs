@@ -0,0 +1,253 @@
+//! Chainlink OCR2 price feeds, read from the transmissions account of a feed of the chainlink
+//! store program.
+//! Derived from https://github.com/smartcontractkit/chainlink-solana/blob/develop/contracts/programs/store/src/state.rs
+
+use std::{convert::TryFrom, str::FromStr};
+
+use arrayref::{array_ref, array_refs};
+use solana_program::{account_info::AccountInfo, program_error::ProgramError, pubkey::Pubkey};
+
+use crate::{
+    error::SwapError,
+    math::{Decimal, TryDiv},
+};
+
+/// Chainlink store program mainnet id, owner of the feed accounts
+pub const CHAINLINK_STORE_PROGRAM_ID: &str = "HEvSKofvBgfaexv23kMabbYqxasxU3mQ4ibBMEmJWHny";
+
+/// Anchor discriminator of the transmissions account, the first 8 bytes of
+/// sha256("account:Transmissions")
+pub const TRANSMISSIONS_DISCRIMINATOR: [u8; 8] = [96, 179, 69, 66, 128, 129, 73, 117];
+/// Layout version of the feeds read by the program
+pub const FEED_VERSION: u8 = 2;
+/// Bytes of the feed header, padded after its fields
+pub const HEADER_SIZE: usize = 192;
+/// Bytes of a transmission of the ring buffer
+pub const TRANSMISSION_SIZE: usize = 48;
+/// Max decimals of a feed answer read by the program
+pub const MAX_FEED_DECIMALS: u8 = 18;
+
+/// Latest round of a feed
+#[derive(Clone, Copy, Debug, Default, PartialEq)]
+pub struct Round {
+    /// Round id, 0 before the first transmission
+    pub round_id: u32,
+    /// Slot of the transmission
+    pub slot: u64,
+    /// Unix timestamp of the observations
+    pub timestamp: u32,
+    /// Answer of the round, in units of 10^-decimals
+    pub answer: i128,
+}
+
+/// Header and latest round of a feed
+#[derive(Clone, Copy, Debug, Default, PartialEq)]
+pub struct Feed {
+    /// Decimals of the answers
+    pub decimals: u8,
+    /// Latest round of the live transmissions
+    pub latest_round: Round,
+}
+
+impl Feed {
+    /// Unpacks the feed account, checked to be a transmissions account of the store program
+    pub fn load(feed_info: &AccountInfo) -> Result<Self, ProgramError> {
+        if *feed_info.owner != Pubkey::from_str(CHAINLINK_STORE_PROGRAM_ID).unwrap() {
+            return Err(SwapError::InvalidChainlinkFeed.into());
+        }
+        Self::unpack(&feed_info.try_borrow_data()?)
+    }
+
+    /// Unpacks the data of a transmissions account
+    pub fn unpack(data: &[u8]) -> Result<Self, ProgramError> {
+        if data.len() < 8 + HEADER_SIZE {
+            return Err(SwapError::InvalidChainlinkFeed.into());
+        }
+        let input = array_ref![data, 0, 8 + HEADER_SIZE];
+        #[allow(clippy::ptr_offset_with_cast)]
+        let (
+            discriminator,
+            version,
+            _state,
+            _owners,
+            _description,
+            decimals,
+            _flagging_threshold,
+            latest_round_id,
+            _granularity,
+            live_length,
+            live_cursor,
+            _,
+        ) = array_refs![input, 8, 1, 1, 96, 32, 1, 4, 4, 1, 4, 4, 44];
+        if *discriminator != TRANSMISSIONS_DISCRIMINATOR || version[0] != FEED_VERSION {
+            return Err(SwapError::InvalidChainlinkFeed.into());
+        }
+
+        let round_id = u32::from_le_bytes(*latest_round_id);
+        let live_length = u32::from_le_bytes(*live_length) as usize;
+        let live_cursor = u32::from_le_bytes(*live_cursor) as usize;
+        if round_id == 0 || live_length == 0 {
+            return Err(SwapError::InvalidChainlinkFeed.into());
+        }
+        // The cursor points past the latest transmission of the live ring buffer
+        let index = (live_cursor + live_length - 1) % live_length;
+        let offset = 8 + HEADER_SIZE + index * TRANSMISSION_SIZE;
+        if data.len() < offset + TRANSMISSION_SIZE {
+            return Err(SwapError::InvalidChainlinkFeed.into());
+        }
+        let transmission = array_ref![data, offset, TRANSMISSION_SIZE];
+        #[allow(clippy::ptr_offset_with_cast)]
+        let (slot, timestamp, _, answer, _) = array_refs![transmission, 8, 4, 4, 16, 16];
+
+        Ok(Self {
+            decimals: decimals[0],
+            latest_round: Round {
+                round_id,
+                slot: u64::from_le_bytes(*slot),
+                timestamp: u32::from_le_bytes(*timestamp),
+                answer: i128::from_le_bytes(*answer),
+            },
+        })
+    }
+
+    /// Answer of the latest round as a decimal, the answer must be positive
+    pub fn price(&self) -> Result<Decimal, ProgramError> {
+        if self.decimals > MAX_FEED_DECIMALS {
+            return Err(SwapError::InvalidChainlinkFeed.into());
+        }
+        let answer = u64::try_from(self.latest_round.answer)
+            .ok()
+            .filter(|answer| *answer > 0)
+            .ok_or(SwapError::InvalidChainlinkFeed)?;
+        Decimal::from(answer).try_div(10u64.pow(self.decimals as u32))
+    }
+}
+
+/// Builders of valid chainlink feed account data, to simulate oracle states without copying
+/// the bytes of live accounts
+#[cfg(any(test, feature = "test-utils"))]
+pub mod test_utils {
+    use super::*;
+
+    /// Latest round of a mock feed account
+    #[derive(Clone, Copy)]
+    pub struct MockFeed {
+        /// Answer of the latest round, in units of 10^-decimals
+        pub answer: i128,
+        /// Decimals of the answers
+        pub decimals: u8,
+        /// Slot of the latest round
+        pub slot: u64,
+        /// Length of the live ring buffer
+        pub live_length: u32,
+        /// Index of the latest round in the live ring buffer
+        pub latest_index: u32,
+    }
+
+    impl Default for MockFeed {
+        fn default() -> Self {
+            Self {
+                answer: 0,
+                decimals: 8,
+                slot: 0,
+                live_length: 4,
+                latest_index: 0,
+            }
+        }
+    }
+
+    impl MockFeed {
+        /// Data of the transmissions account of the feed
+        pub fn to_account_data(&self) -> Vec<u8> {
+            let mut data =
+                vec![0u8; 8 + HEADER_SIZE + self.live_length as usize * TRANSMISSION_SIZE];
+            data[..8].copy_from_slice(&TRANSMISSIONS_DISCRIMINATOR);
+            data[8] = FEED_VERSION;
+            data[8 + 2 + 96 + 32] = self.decimals;
+            let round_id = 1 + self.latest_index;
+            data[8 + 139 - 4..8 + 139].copy_from_slice(&round_id.to_le_bytes());
+            data[8 + 140..8 + 144].copy_from_slice(&self.live_length.to_le_bytes());
+            let live_cursor = (self.latest_index + 1) % self.live_length;
+            data[8 + 144..8 + 148].copy_from_slice(&live_cursor.to_le_bytes());
+
+            let offset = 8 + HEADER_SIZE + self.latest_index as usize * TRANSMISSION_SIZE;
+            data[offset..offset + 8].copy_from_slice(&self.slot.to_le_bytes());
+            data[offset + 16..offset + 32].copy_from_slice(&self.answer.to_le_bytes());
+            data
+        }
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+
+    #[test]
+    fn test_unpack_feed() {
+        // The latest round is at the end of the live ring buffer, the cursor wrapped around
+        let data = test_utils::MockFeed {
+            answer: 2_512_345_678,
+            decimals: 8,
+            slot: 42,
+            live_length: 4,
+            latest_index: 3,
+        }
+        .to_account_data();
+        let feed = Feed::unpack(&data).unwrap();
+        assert_eq!(feed.decimals, 8);
+        assert_eq!(feed.latest_round.round_id, 4);
+        assert_eq!(feed.latest_round.slot, 42);
+        assert_eq!(feed.latest_round.answer, 2_512_345_678);
+        assert_eq!(
+            feed.price().unwrap(),
+            Decimal::from_scaled_val(25_123_456_780_000_000_000)
+        );
+
+        let data = test_utils::MockFeed {
+            answer: 100,
+            decimals: 0,
+            latest_index: 0,
+            ..test_utils::MockFeed::default()
+        }
+        .to_account_data();
+        assert_eq!(
+            Feed::unpack(&data).unwrap().price().unwrap(),
+            Decimal::from(100u64)
+        );
+
+        let mut data = test_utils::MockFeed::default().to_account_data();
+        assert_eq!(
+            Feed::unpack(&data).unwrap().price(),
+            Err(SwapError::InvalidChainlinkFeed.into())
+        );
+        data[0] = 0;
+        assert_eq!(
+            Feed::unpack(&data),
+            Err(SwapError::InvalidChainlinkFeed.into())
+        );
+        assert_eq!(
+            Feed::unpack(&data[..8 + HEADER_SIZE - 1]),
+            Err(SwapError::InvalidChainlinkFeed.into())
+        );
+
+        let data = test_utils::MockFeed {
+            answer: -1,
+            ..test_utils::MockFeed::default()
+        }
+        .to_account_data();
+        assert_eq!(
+            Feed::unpack(&data).unwrap().price(),
+            Err(SwapError::InvalidChainlinkFeed.into())
+        );
+        let data = test_utils::MockFeed {
+            answer: 1,
+            decimals: MAX_FEED_DECIMALS + 1,
+            ..test_utils::MockFeed::default()
+        }
+        .to_account_data();
+        assert_eq!(
+            Feed::unpack(&data).unwrap().price(),
+            Err(SwapError::InvalidChainlinkFeed.into())
+        );
+    }
+}
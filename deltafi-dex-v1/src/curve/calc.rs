@@ -2,7 +2,7 @@
 
 use crate::{
     error::SwapError,
-    math::{Decimal, TryAdd, TryDiv, TryMul, TrySub},
+    math::{Decimal, TryAdd, TryDiv, TryDivRound, TryMul, TrySub},
 };
 use solana_program::program_error::ProgramError;
 
@@ -68,7 +68,7 @@ pub fn get_target_amount(
 ///
 /// # Return value
 ///
-/// target amount determined by the pricing function.
+/// target amount determined by the pricing function, rounded down against the trader.
 pub fn get_target_amount_reverse_direction(
     target_reserve: Decimal,
     current_reserve: Decimal,
@@ -142,7 +142,8 @@ pub fn get_target_amount_reverse_direction(
         adjusted_reserve.try_add(square_root)?
     };
 
-    let candidate_reserve = numerator.try_div(denominator)?;
+    // The reserve left rounds up so the amount out never gains the rounding dust
+    let candidate_reserve = numerator.try_div_ceil(denominator)?;
     if candidate_reserve > current_reserve {
         Ok(Decimal::zero())
     } else {
@@ -199,6 +200,61 @@ pub fn get_target_reserve(
     premium.try_mul(current_reserve)
 }
 
+/// Split an input between two pools to get the most total output out of them.
+///
+/// The output of a pool is concave in its input, so is the total output in the share of
+/// the first pool, which is found by a ternary search of `steps` steps. The search is
+/// bounded to fit the compute budget, its result is compared with sending the whole
+/// input to either pool.
+///
+/// # Arguments
+///
+/// * amount_in - input to split.
+/// * steps - number of steps of the search.
+/// * first_out - output of the first pool for its input.
+/// * second_out - output of the second pool for its input.
+///
+/// # Return value
+///
+/// share of the input sent to the first pool, the rest goes to the second pool.
+pub fn split_amount_in(
+    amount_in: u64,
+    steps: usize,
+    first_out: impl Fn(u64) -> u64,
+    second_out: impl Fn(u64) -> u64,
+) -> u64 {
+    let total_out =
+        |first_in: u64| first_out(first_in) as u128 + second_out(amount_in - first_in) as u128;
+
+    let mut low = 0u64;
+    let mut high = amount_in;
+    for _ in 0..steps {
+        if high - low < 3 {
+            break;
+        }
+        let third = (high - low) / 3;
+        let left = low + third;
+        let right = high - third;
+        if total_out(left) < total_out(right) {
+            low = left;
+        } else {
+            high = right;
+        }
+    }
+
+    // Trading a single pool is preferred on a tie, saving its transfers
+    let mut best_in = low + (high - low) / 2;
+    let mut best_out = total_out(best_in);
+    for &first_in in &[0, amount_in] {
+        let out = total_out(first_in);
+        if out >= best_out {
+            best_in = first_in;
+            best_out = out;
+        }
+    }
+    best_in
+}
+
 #[cfg(test)]
 mod tests {
     use super::*;
@@ -575,4 +631,48 @@ mod tests {
             Decimal::from(3_000_090u64)
         );
     }
+
+    #[test]
+    fn test_split_amount_in() {
+        // Constant product pools, x * y = k
+        let pool_out = |reserve_in: u64, reserve_out: u64| {
+            move |amount_in: u64| {
+                (reserve_out as u128 * amount_in as u128 / (reserve_in as u128 + amount_in as u128))
+                    as u64
+            }
+        };
+
+        // Same pools get the same share
+        let split = split_amount_in(
+            1_000_000,
+            32,
+            pool_out(10_000_000, 10_000_000),
+            pool_out(10_000_000, 10_000_000),
+        );
+        assert!((495_000..=505_000).contains(&split));
+
+        // The deeper pool gets the larger share, in the ratio of the reserves
+        let split = split_amount_in(
+            1_000_000,
+            32,
+            pool_out(30_000_000, 30_000_000),
+            pool_out(10_000_000, 10_000_000),
+        );
+        assert!((745_000..=755_000).contains(&split));
+
+        // A pool not quoting anything gets nothing
+        assert_eq!(
+            split_amount_in(1_000_000, 32, |_| 0, pool_out(10_000_000, 10_000_000)),
+            0
+        );
+        assert_eq!(
+            split_amount_in(1_000_000, 32, pool_out(10_000_000, 10_000_000), |_| 0),
+            1_000_000
+        );
+
+        // Tiny inputs
+        assert_eq!(split_amount_in(0, 32, |x| x, |x| x), 0);
+        let split = split_amount_in(2, 32, pool_out(100, 100), pool_out(100, 100));
+        assert!(split <= 2);
+    }
 }
@@ -2,9 +2,11 @@
 
 mod calc;
 mod pool;
+mod stable;
 
 pub use calc::*;
 pub use pool::*;
+pub use stable::*;
 
 #[cfg(test)]
 use crate::math::{Decimal, HALF_WAD};
@@ -23,9 +23,13 @@ use num::pow::checked_pow;
 #[cfg(feature = "fuzz")]
 use arbitrary::Arbitrary;
 
+#[cfg(feature = "serde")]
+use serde::{Deserialize, Serialize};
+
 /// Multiplier status enum
 #[repr(u8)]
 #[derive(Clone, Copy, PartialEq, Debug, Hash)]
+#[cfg_attr(feature = "serde", derive(Serialize, Deserialize))]
 pub enum Multiplier {
     /// multiplier = 1
     One,
@@ -57,6 +61,7 @@ impl TryFrom<u8> for Multiplier {
 /// PoolState struct
 #[repr(C)]
 #[derive(Clone, Debug, Default, PartialEq)]
+#[cfg_attr(feature = "serde", derive(Serialize, Deserialize))]
 pub struct PoolState {
     /// market price
     pub market_price: Decimal,
@@ -78,8 +83,45 @@ pub struct PoolState {
     pub last_market_price: Decimal,
     /// last valid market price slot
     pub last_valid_market_price_slot: u64,
+    /// base token LP fees collected since the last liquidity change, held apart from the reserve
+    pub base_fee_pending: u64,
+    /// quote token LP fees collected since the last liquidity change, held apart from the reserve
+    pub quote_fee_pending: u64,
+    /// base token LP fees collected since the pool creation
+    pub base_fee_total: u64,
+    /// quote token LP fees collected since the pool creation
+    pub quote_fee_total: u64,
+    /// Protocol share of the LP fees numerator
+    pub protocol_fee_numerator: u64,
+    /// Protocol share of the LP fees denominator
+    pub protocol_fee_denominator: u64,
+    /// base token protocol fees not claimed yet, held apart from the reserve
+    pub base_protocol_fee: u64,
+    /// quote token protocol fees not claimed yet, held apart from the reserve
+    pub quote_protocol_fee: u64,
+    /// Highest LP share price since the last market price change, in quote token per pool token
+    pub max_share_price: Decimal,
+    /// Moving average of the oracle price, packed at the end of the swap info as the pool
+    /// state layout is embedded in the legacy pools
+    pub ema_market_price: Decimal,
+    /// Oracle price slot of the last moving average update
+    pub ema_slot: u64,
 }
 
+/// Drop of the LP share price below its highest value tolerated by the share price floor,
+/// in basis points, for the rounding of the curve
+pub const SHARE_PRICE_FLOOR_TOLERANCE_BPS: u64 = 100;
+
+/// Bounty of the permissionless rebalance in basis points of the output at the market price
+pub const REBALANCE_BOUNTY_BPS: u64 = 10;
+
+/// Slots without a newer oracle price before a normal pool is open to the permissionless
+/// price refresh, about a minute
+pub const REFRESH_PRICE_INTERVAL_SLOTS: u64 = 150;
+
+/// DELTAFI reward of the permissionless price refresh, 0.01 DELTAFI
+pub const REFRESH_PRICE_REWARD: u64 = 10_000;
+
 /// Initialize pool state
 pub struct InitPoolStateParams {
     /// market price
@@ -109,6 +151,75 @@ pub enum SwapDirection {
     SellQuote,
 }
 
+impl TryFrom<u8> for SwapDirection {
+    type Error = ProgramError;
+
+    fn try_from(swap_direction: u8) -> Result<Self, Self::Error> {
+        match swap_direction {
+            0 => Ok(SwapDirection::SellBase),
+            1 => Ok(SwapDirection::SellQuote),
+            _ => Err(ProgramError::InvalidAccountData),
+        }
+    }
+}
+
+/// Policy applied when the oracle price jumps by more than the max step
+/// between two close slots
+#[repr(u8)]
+#[derive(Clone, Copy, Debug, PartialEq)]
+#[cfg_attr(feature = "serde", derive(Serialize, Deserialize))]
+pub enum PriceJumpPolicy {
+    /// reject the trade
+    Reject,
+    /// accept the new price
+    Accept,
+    /// clamp the new price to the max step from the last price
+    Clamp,
+}
+
+impl Default for PriceJumpPolicy {
+    fn default() -> Self {
+        Self::Reject
+    }
+}
+
+impl TryFrom<u8> for PriceJumpPolicy {
+    type Error = ProgramError;
+
+    fn try_from(policy: u8) -> Result<Self, Self::Error> {
+        match policy {
+            0 => Ok(PriceJumpPolicy::Reject),
+            1 => Ok(PriceJumpPolicy::Accept),
+            2 => Ok(PriceJumpPolicy::Clamp),
+            _ => Err(ProgramError::InvalidAccountData),
+        }
+    }
+}
+
+/// Check if the pyth and serum prices diverge more than max_price_divergence
+/// basis points of the pyth price, 0 means no limitation
+pub fn check_price_divergence(
+    pyth_price: Decimal,
+    serum_price: Decimal,
+    max_price_divergence: u16,
+) -> ProgramResult {
+    if max_price_divergence == 0u16 {
+        return Ok(());
+    }
+
+    let divergence = if pyth_price > serum_price {
+        pyth_price.try_sub(serum_price)?
+    } else {
+        serum_price.try_sub(pyth_price)?
+    };
+
+    if divergence.try_mul(10_000u64)? > pyth_price.try_mul(max_price_divergence as u64)? {
+        Err(SwapError::ExceededPriceDivergence.into())
+    } else {
+        Ok(())
+    }
+}
+
 impl PoolState {
     /// Create new pool state
     pub fn new(params: InitPoolStateParams) -> Self {
@@ -191,6 +302,7 @@ impl PoolState {
         quote_decimals: u8,
         market_price: Decimal,
     ) -> ProgramResult {
+        let last_market_price = self.market_price;
         self.market_price = match base_decimals.cmp(&quote_decimals) {
             Ordering::Greater => market_price.try_div(
                 checked_pow(10u64, (base_decimals - quote_decimals) as usize)
@@ -202,7 +314,14 @@ impl PoolState {
             ),
             Ordering::Equal => Ok(market_price),
         }?;
-        self.adjust_target()
+        self.adjust_target()?;
+
+        // The share price in quote token follows the market price, the floor restarts from
+        // the share price at the new market price.
+        if self.market_price != last_market_price {
+            self.max_share_price = self.share_price()?;
+        }
+        Ok(())
     }
 
     /// Update pool state to sell base token
@@ -411,7 +530,193 @@ impl PoolState {
         }
     }
 
-    /// Buy shares [round down]: deposit and calculate shares.
+    /// Price impact of a trade in basis points, the shortfall of the curve output
+    /// against the output at the market price
+    pub fn price_impact_bps(
+        &self,
+        amount_in: u64,
+        amount_out: u64,
+        swap_direction: SwapDirection,
+    ) -> Result<u64, ProgramError> {
+        if self.market_price == Decimal::zero() {
+            return Ok(0);
+        }
+        let market_out = match swap_direction {
+            SwapDirection::SellBase => Decimal::from(amount_in).try_mul(self.market_price)?,
+            SwapDirection::SellQuote => Decimal::from(amount_in).try_div(self.market_price)?,
+        };
+        let amount_out = Decimal::from(amount_out);
+        if market_out <= amount_out {
+            return Ok(0);
+        }
+        market_out
+            .try_sub(amount_out)?
+            .try_mul(10_000u64)?
+            .try_div(market_out)?
+            .try_floor_u64()
+    }
+
+    /// Marginal price of the curve in quote token per base token, the market price
+    /// moved by the slope toward the short side of the pool
+    pub fn mid_price(&self) -> Result<Decimal, ProgramError> {
+        let ratio = match self.multiplier {
+            Multiplier::One => return Ok(self.market_price),
+            Multiplier::AboveOne => self.base_target.try_div(self.base_reserve)?,
+            Multiplier::BelowOne => self.quote_target.try_div(self.quote_reserve)?,
+        };
+        let factor = Decimal::one()
+            .try_sub(self.slope)?
+            .try_add(ratio.try_mul(ratio)?.try_mul(self.slope)?)?;
+        match self.multiplier {
+            Multiplier::BelowOne => self.market_price.try_div(factor),
+            _ => self.market_price.try_mul(factor),
+        }
+    }
+
+    /// Relative deviation of a price from the market price, zero for a pool without
+    /// a market price
+    pub fn market_price_deviation(&self, price: Decimal) -> Result<Decimal, ProgramError> {
+        if self.market_price == Decimal::zero() {
+            return Ok(Decimal::zero());
+        }
+        let diff = if price > self.market_price {
+            price.try_sub(self.market_price)?
+        } else {
+            self.market_price.try_sub(price)?
+        };
+        diff.try_div(self.market_price)
+    }
+
+    /// Trade bringing the reserves back to the targets, the short token in and the long token
+    /// out at the market price. The output pays a bounty on top of the market price, never
+    /// more than the curve pays for the same input.
+    ///
+    /// # Arguments
+    ///
+    /// * max_amount_in - max amount of the short token to take in.
+    /// * bounty_bps - bounty in basis points of the output at the market price.
+    ///
+    /// # Return value
+    ///
+    /// swap direction, amount in, amount out.
+    pub fn rebalance_amounts(
+        &self,
+        max_amount_in: u64,
+        bounty_bps: u64,
+    ) -> Result<(SwapDirection, u64, u64), ProgramError> {
+        let (swap_direction, back_to_one_pay) = match self.multiplier {
+            Multiplier::One => return Err(SwapError::RebalanceNotNeeded.into()),
+            Multiplier::AboveOne => (
+                SwapDirection::SellBase,
+                self.base_target.try_sub(self.base_reserve)?,
+            ),
+            Multiplier::BelowOne => (
+                SwapDirection::SellQuote,
+                self.quote_target.try_sub(self.quote_reserve)?,
+            ),
+        };
+        let amount_in = back_to_one_pay.try_floor_u64()?.min(max_amount_in);
+        if amount_in == 0 {
+            return Err(SwapError::RebalanceNotNeeded.into());
+        }
+
+        let market_out = match swap_direction {
+            SwapDirection::SellBase => Decimal::from(amount_in).try_mul(self.market_price)?,
+            SwapDirection::SellQuote => Decimal::from(amount_in).try_div(self.market_price)?,
+        };
+        let amount_out = market_out
+            .try_mul(
+                10_000u64
+                    .checked_add(bounty_bps)
+                    .ok_or(SwapError::CalculationFailure)?,
+            )?
+            .try_div(10_000u64)?
+            .try_floor_u64()?
+            .min(self.get_out_amount(amount_in, swap_direction)?);
+        Ok((swap_direction, amount_in, amount_out))
+    }
+
+    /// Split a single-sided deposit: the portion of the input that should be swapped
+    /// so the remaining input and the swap output follow the reserve ratio.
+    ///
+    /// # Arguments
+    ///
+    /// * amount_in - single token amount to deposit.
+    /// * swap_direction - SellBase when depositing base token, SellQuote otherwise.
+    ///
+    /// # Return value
+    ///
+    /// input token amount to swap.
+    pub fn deposit_one_swap_amount(
+        &self,
+        amount_in: u64,
+        swap_direction: SwapDirection,
+    ) -> Result<u64, ProgramError> {
+        if self.base_reserve == Decimal::zero() || self.quote_reserve == Decimal::zero() {
+            return Err(SwapError::InsufficientFunds.into());
+        }
+
+        // Both reserves are valued in quote token at the market price.
+        let base_value = self.base_reserve.try_mul(self.market_price)?;
+        let total_value = base_value.try_add(self.quote_reserve)?;
+        let other_side_value = match swap_direction {
+            SwapDirection::SellBase => self.quote_reserve,
+            SwapDirection::SellQuote => base_value,
+        };
+
+        Decimal::from(amount_in)
+            .try_mul(other_side_value)?
+            .try_div(total_value)?
+            .try_floor_u64()
+    }
+
+    /// Buy shares with a single token once its swap part is applied to the pool.
+    ///
+    /// # Arguments
+    ///
+    /// * deposit_amount - input token amount left after the swap.
+    /// * swapped_amount - other side token amount received from the swap.
+    /// * swap_direction - SellBase when depositing base token, SellQuote otherwise.
+    ///
+    /// # Return value
+    ///
+    /// purchased shares, input token amount used by the deposit.
+    pub fn buy_shares_one_side(
+        &mut self,
+        deposit_amount: u64,
+        swapped_amount: u64,
+        swap_direction: SwapDirection,
+    ) -> Result<(u64, u64), ProgramError> {
+        let (shares, deposit_used, swapped_used) = match swap_direction {
+            SwapDirection::SellBase => self.buy_shares(deposit_amount, swapped_amount)?,
+            SwapDirection::SellQuote => {
+                let (shares, base_used, quote_used) =
+                    self.buy_shares(swapped_amount, deposit_amount)?;
+                (shares, quote_used, base_used)
+            }
+        };
+
+        // The swapped token never leaves the pool, the unused part stays in the reserve.
+        let swapped_left = Decimal::from(
+            swapped_amount
+                .checked_sub(swapped_used)
+                .ok_or(SwapError::CalculationFailure)?,
+        );
+        match swap_direction {
+            SwapDirection::SellBase => {
+                self.quote_reserve = self.quote_reserve.try_add(swapped_left)?
+            }
+            SwapDirection::SellQuote => {
+                self.base_reserve = self.base_reserve.try_add(swapped_left)?
+            }
+        }
+        self.adjust_target()?;
+
+        Ok((shares, deposit_used))
+    }
+
+    /// Buy shares [round down]: deposit and calculate shares. The amount of the token
+    /// matched to the ratio of the reserves rounds up, against the depositor.
     ///
     /// # Arguments
     ///
@@ -430,6 +735,7 @@ impl PoolState {
         if base_input == 0 || quote_input == 0 {
             return Err(SwapError::InsufficientFunds.into());
         }
+        self.settle_trade_fee()?;
 
         let (shares, base_output, quote_output) = if self.total_supply == 0 {
             // Use the base input amount to determine the initial share.
@@ -445,12 +751,12 @@ impl PoolState {
                 (
                     shares.try_floor_u64()?,
                     base_input,
-                    mint_ratio.try_mul(self.quote_reserve)?.try_floor_u64()?,
+                    mint_ratio.try_mul(self.quote_reserve)?.try_ceil_u64()?,
                 )
             } else {
                 (
                     shares.try_floor_u64()?,
-                    mint_ratio.try_mul(self.base_reserve)?.try_floor_u64()?,
+                    mint_ratio.try_mul(self.base_reserve)?.try_ceil_u64()?,
                     quote_input,
                 )
             }
@@ -494,6 +800,7 @@ impl PoolState {
         if self.total_supply < share_amount {
             return Err(SwapError::InsufficientFunds.into());
         }
+        self.settle_trade_fee()?;
 
         let base_balance = self.base_reserve;
         let quote_balance = self.quote_reserve;
@@ -522,23 +829,108 @@ impl PoolState {
         Ok((base_amount, quote_amount))
     }
 
-    /// Total value locked in the pool
+    /// Sell shares for a single token. The other side of the withdrawn liquidity stays in
+    /// the pool and is expected to be swapped into the requested token.
+    ///
+    /// # Arguments
+    ///
+    /// * share_amount - share amount to sell.
+    /// * swap_direction - SellQuote when withdrawing base token, SellBase otherwise.
+    ///
+    /// # Return value
+    ///
+    /// requested token amount, other side token amount to swap.
+    pub fn sell_shares_one_side(
+        &mut self,
+        share_amount: u64,
+        swap_direction: SwapDirection,
+    ) -> Result<(u64, u64), ProgramError> {
+        let (base_amount, quote_amount) = self.sell_shares(share_amount, 0, 0)?;
+        Ok(match swap_direction {
+            SwapDirection::SellQuote => (base_amount, quote_amount),
+            SwapDirection::SellBase => (quote_amount, base_amount),
+        })
+    }
+
+    /// Total value locked in the pool, including the pending LP fees
     pub fn tvl(&self, base_price: Decimal, quote_price: Decimal) -> Result<Decimal, ProgramError> {
         self.base_reserve
+            .try_add(Decimal::from(self.base_fee_pending))?
             .try_mul(base_price)?
-            .try_add(self.quote_reserve.try_mul(quote_price)?)
+            .try_add(
+                self.quote_reserve
+                    .try_add(Decimal::from(self.quote_fee_pending))?
+                    .try_mul(quote_price)?,
+            )
+    }
+
+    /// LP share price, value of the pool at the market price including the pending LP fees
+    /// per pool token, zero for a pool without supply
+    pub fn share_price(&self) -> Result<Decimal, ProgramError> {
+        if self.total_supply == 0 {
+            return Ok(Decimal::zero());
+        }
+        self.tvl(self.market_price, Decimal::one())?
+            .try_div(self.total_supply)
+    }
+
+    /// Virtual price of the pool token, the LP share price after the fee accrual in quote
+    /// token per pool token. It only drops with the market price, so an integrator marks the
+    /// pool tokens to market with it and takes a drop at the same price as a drain of the pool.
+    pub fn get_virtual_price(&self) -> Result<Decimal, ProgramError> {
+        self.share_price()
+    }
+
+    /// Check the LP share price did not drop below its highest value since the last market
+    /// price change, up to the tolerance, and raise the highest value.
+    ///
+    /// Called after every state transition but the fee withdrawals, so a curve or accounting
+    /// bug can't drain the liquidity providers.
+    pub fn check_share_price_floor(&mut self) -> ProgramResult {
+        if self.total_supply == 0 {
+            self.max_share_price = Decimal::zero();
+            return Ok(());
+        }
+        let share_price = self.share_price()?;
+        let floor = self
+            .max_share_price
+            .try_mul(10_000 - SHARE_PRICE_FLOOR_TOLERANCE_BPS)?
+            .try_div(10_000)?;
+        if share_price < floor {
+            return Err(SwapError::SharePriceBelowFloor.into());
+        }
+        if share_price > self.max_share_price {
+            self.max_share_price = share_price;
+        }
+        Ok(())
     }
 
     /// Check and update last market price and slot
+    ///
+    /// # Arguments
+    ///
+    /// * new_market_price - market price from the oracle.
+    /// * new_market_last_slot - slot of the oracle price.
+    /// * price_jump_policy - what to do when the price moves more than 1% within 10s.
+    ///
+    /// # Return value
+    ///
+    /// market price to use for the trade.
     pub fn check_and_update_market_price_and_slot(
         &mut self,
         new_market_price: Decimal,
         new_market_last_slot: u64,
-    ) -> ProgramResult {
+        price_jump_policy: PriceJumpPolicy,
+    ) -> Result<Decimal, ProgramError> {
         const SHORT_PYTH_PRICE_SLOTS: u64 = 25; // 10s
 
-        // check if this slot is with 10s from last swap and the price diff is more than 1%
-        if new_market_last_slot - self.last_valid_market_price_slot < SHORT_PYTH_PRICE_SLOTS {
+        let mut market_price = new_market_price;
+        // check if this slot is with 10s from last swap and the price diff is more than 1%,
+        // a price older than the last valid one is checked as well
+        if new_market_last_slot.saturating_sub(self.last_valid_market_price_slot)
+            < SHORT_PYTH_PRICE_SLOTS
+            && price_jump_policy != PriceJumpPolicy::Accept
+        {
             let price_diff = if new_market_price > self.last_market_price {
                 new_market_price.try_sub(self.last_market_price)
             } else {
@@ -546,31 +938,271 @@ impl PoolState {
             }?;
 
             if price_diff.try_mul(Decimal::from(100u64))? > self.last_market_price {
-                return Err(SwapError::UnstableMarketPrice.into());
+                if price_jump_policy == PriceJumpPolicy::Reject {
+                    return Err(SwapError::UnstableMarketPrice.into());
+                }
+
+                let max_step = self.last_market_price.try_div(100u64)?;
+                market_price = if new_market_price > self.last_market_price {
+                    self.last_market_price.try_add(max_step)?
+                } else {
+                    self.last_market_price.try_sub(max_step)?
+                };
             }
         }
 
-        self.last_market_price = new_market_price;
+        self.last_market_price = market_price;
         self.last_valid_market_price_slot = new_market_last_slot;
 
-        Ok(())
+        Ok(market_price)
+    }
+
+    /// Whether the last valid market price is old enough for the permissionless price refresh
+    pub fn needs_price_refresh(&self, current_slot: u64) -> bool {
+        current_slot.saturating_sub(self.last_valid_market_price_slot)
+            >= REFRESH_PRICE_INTERVAL_SLOTS
+    }
+
+    /// Update the moving average of the oracle price, the new price weighs the slots elapsed
+    /// since the last update over the window so a single price print moves it by a fraction.
+    ///
+    /// # Arguments
+    ///
+    /// * new_market_price - market price from the oracle, after the price jump policy.
+    /// * new_market_last_slot - slot of the oracle price.
+    /// * window_slots - slots for the average to fully follow the price, 0 turns it off.
+    ///
+    /// # Return value
+    ///
+    /// market price to reprice the curve with, the average or the oracle price if it is off.
+    pub fn update_ema_market_price(
+        &mut self,
+        new_market_price: Decimal,
+        new_market_last_slot: u64,
+        window_slots: u64,
+    ) -> Result<Decimal, ProgramError> {
+        if window_slots == 0 || self.ema_market_price == Decimal::zero() {
+            // The average restarts from the oracle price when it is turned on
+            self.ema_market_price = new_market_price;
+        } else {
+            let elapsed_slots = new_market_last_slot
+                .saturating_sub(self.ema_slot)
+                .min(window_slots);
+            let step = if new_market_price > self.ema_market_price {
+                new_market_price.try_sub(self.ema_market_price)?
+            } else {
+                self.ema_market_price.try_sub(new_market_price)?
+            }
+            .try_mul(elapsed_slots)?
+            .try_div(window_slots)?;
+            self.ema_market_price = if new_market_price > self.ema_market_price {
+                self.ema_market_price.try_add(step)?
+            } else {
+                self.ema_market_price.try_sub(step)?
+            };
+        }
+        self.ema_slot = self.ema_slot.max(new_market_last_slot);
+
+        if window_slots == 0 {
+            Ok(new_market_price)
+        } else {
+            Ok(self.ema_market_price)
+        }
+    }
+
+    /// Packs the moving average of the oracle price, kept apart from the pool state layout
+    pub fn pack_ema_into_slice(&self, output: &mut [u8; POOL_STATE_EMA_SIZE]) {
+        let (ema_market_price, ema_slot) = mut_array_refs![output, 16, 8];
+        pack_decimal(self.ema_market_price, ema_market_price);
+        *ema_slot = self.ema_slot.to_le_bytes();
+    }
+
+    /// Unpacks the moving average of the oracle price, kept apart from the pool state layout
+    pub fn unpack_ema_from_slice(&mut self, input: &[u8; POOL_STATE_EMA_SIZE]) {
+        let (ema_market_price, ema_slot) = array_refs![input, 16, 8];
+        self.ema_market_price = unpack_decimal(ema_market_price);
+        self.ema_slot = u64::from_le_bytes(*ema_slot);
     }
 
-    /// Collect trade trade fee.
+    /// Update pool state to swap, the LP part of the trade fee leaves the DESTINATION reserve
+    /// and is held as a pending fee.
+    ///
+    /// # Arguments
+    ///
+    /// * amount_in - SOURCE token amount.
+    /// * amount_out - DESTINATION token amount moving out of the pool.
+    /// * lp_fee - trade fee left to the liquidity providers.
+    /// * swap_direction - swap direction.
+    pub fn swap_with_fee(
+        &mut self,
+        amount_in: u64,
+        amount_out: u64,
+        lp_fee: u64,
+        swap_direction: SwapDirection,
+    ) -> ProgramResult {
+        let receive_amount = amount_out
+            .checked_add(lp_fee)
+            .ok_or(SwapError::CalculationFailure)?;
+        self.swap(amount_in, receive_amount, swap_direction)?;
+        match swap_direction {
+            SwapDirection::SellBase => self.collect_trade_fee(0, lp_fee),
+            SwapDirection::SellQuote => self.collect_trade_fee(lp_fee, 0),
+        }
+    }
+
+    /// Protocol share of the LP fee amount
+    pub fn protocol_fee(&self, fee_amount: u64) -> Result<u64, ProgramError> {
+        if self.protocol_fee_numerator == 0 {
+            return Ok(0);
+        }
+        fee_amount
+            .checked_mul(self.protocol_fee_numerator)
+            .ok_or(SwapError::CalculationFailure)?
+            .checked_div(self.protocol_fee_denominator)
+            .ok_or_else(|| SwapError::CalculationFailure.into())
+    }
+
+    /// Collect trade fee for the liquidity providers, it stays out of the reserves
+    /// until the next liquidity change. The protocol share is set aside until claimed.
     pub fn collect_trade_fee(&mut self, base_fee: u64, quote_fee: u64) -> ProgramResult {
-        self.base_reserve = self.base_reserve.try_add(Decimal::from(base_fee))?;
-        self.quote_reserve = self.quote_reserve.try_add(Decimal::from(quote_fee))?;
+        let base_protocol_fee = self.protocol_fee(base_fee)?;
+        let quote_protocol_fee = self.protocol_fee(quote_fee)?;
+        self.base_protocol_fee = self
+            .base_protocol_fee
+            .checked_add(base_protocol_fee)
+            .ok_or(SwapError::CalculationFailure)?;
+        self.quote_protocol_fee = self
+            .quote_protocol_fee
+            .checked_add(quote_protocol_fee)
+            .ok_or(SwapError::CalculationFailure)?;
+        let base_fee = base_fee
+            .checked_sub(base_protocol_fee)
+            .ok_or(SwapError::CalculationFailure)?;
+        let quote_fee = quote_fee
+            .checked_sub(quote_protocol_fee)
+            .ok_or(SwapError::CalculationFailure)?;
+
+        self.base_fee_pending = self
+            .base_fee_pending
+            .checked_add(base_fee)
+            .ok_or(SwapError::CalculationFailure)?;
+        self.quote_fee_pending = self
+            .quote_fee_pending
+            .checked_add(quote_fee)
+            .ok_or(SwapError::CalculationFailure)?;
+        self.base_fee_total = self
+            .base_fee_total
+            .checked_add(base_fee)
+            .ok_or(SwapError::CalculationFailure)?;
+        self.quote_fee_total = self
+            .quote_fee_total
+            .checked_add(quote_fee)
+            .ok_or(SwapError::CalculationFailure)?;
         Ok(())
     }
 
-    /// Check the reserve amount match the token amount in the pool.
+    /// Move the pending LP fees into the reserves, shares are priced against the whole pool.
+    pub fn settle_trade_fee(&mut self) -> ProgramResult {
+        self.base_reserve = self
+            .base_reserve
+            .try_add(Decimal::from(self.base_fee_pending))?;
+        self.quote_reserve = self
+            .quote_reserve
+            .try_add(Decimal::from(self.quote_fee_pending))?;
+        self.base_fee_pending = 0;
+        self.quote_fee_pending = 0;
+        Ok(())
+    }
+
+    /// Take the unclaimed protocol fees out of the pool state.
+    ///
+    /// # Return value
+    ///
+    /// (base protocol fee, quote protocol fee)
+    pub fn claim_protocol_fee(&mut self) -> (u64, u64) {
+        let claimed = (self.base_protocol_fee, self.quote_protocol_fee);
+        self.base_protocol_fee = 0;
+        self.quote_protocol_fee = 0;
+        claimed
+    }
+
+    /// Token amounts in the pool above the reserves, the pending fees and the protocol fees,
+    /// sent directly to the pool token accounts.
+    ///
+    /// # Return value
+    ///
+    /// (base surplus, quote surplus)
+    pub fn surplus_amounts(
+        &self,
+        base_token_amount: u64,
+        quote_token_amount: u64,
+    ) -> Result<(u64, u64), ProgramError> {
+        let base_accounted = self
+            .base_reserve
+            .try_add(Decimal::from(self.base_fee_pending))?
+            .try_add(Decimal::from(self.base_protocol_fee))?;
+        let quote_accounted = self
+            .quote_reserve
+            .try_add(Decimal::from(self.quote_fee_pending))?
+            .try_add(Decimal::from(self.quote_protocol_fee))?;
+        if base_accounted > Decimal::from(base_token_amount)
+            || quote_accounted > Decimal::from(quote_token_amount)
+        {
+            return Err(SwapError::InconsistentPoolState.into());
+        }
+
+        Ok((
+            Decimal::from(base_token_amount)
+                .try_sub(base_accounted)?
+                .try_floor_u64()?,
+            Decimal::from(quote_token_amount)
+                .try_sub(quote_accounted)?
+                .try_floor_u64()?,
+        ))
+    }
+
+    /// Credit the surplus of the pool token accounts to the protocol fees, so the tokens sent
+    /// directly to the pool are claimed with `ClaimProtocolFees` instead of staying out of the
+    /// accounting.
+    ///
+    /// # Return value
+    ///
+    /// (base surplus, quote surplus)
+    pub fn sync_reserve_amount(
+        &mut self,
+        base_token_amount: u64,
+        quote_token_amount: u64,
+    ) -> Result<(u64, u64), ProgramError> {
+        let (base_surplus, quote_surplus) =
+            self.surplus_amounts(base_token_amount, quote_token_amount)?;
+        self.base_protocol_fee = self
+            .base_protocol_fee
+            .checked_add(base_surplus)
+            .ok_or(SwapError::CalculationFailure)?;
+        self.quote_protocol_fee = self
+            .quote_protocol_fee
+            .checked_add(quote_surplus)
+            .ok_or(SwapError::CalculationFailure)?;
+        Ok((base_surplus, quote_surplus))
+    }
+
+    /// Check the reserve amount, the pending fees and the protocol fees match the token amount
+    /// in the pool.
     pub fn check_reserve_amount(
         &self,
         base_token_amount: u64,
         quote_token_amount: u64,
     ) -> ProgramResult {
-        if self.base_reserve > Decimal::from(base_token_amount)
-            || self.quote_reserve > Decimal::from(quote_token_amount)
+        if self
+            .base_reserve
+            .try_add(Decimal::from(self.base_fee_pending))?
+            .try_add(Decimal::from(self.base_protocol_fee))?
+            > Decimal::from(base_token_amount)
+            || self
+                .quote_reserve
+                .try_add(Decimal::from(self.quote_fee_pending))?
+                .try_add(Decimal::from(self.quote_protocol_fee))?
+                > Decimal::from(quote_token_amount)
         {
             return Err(SwapError::InconsistentPoolState.into());
         }
@@ -589,12 +1221,31 @@ impl PoolState {
         }
         Ok(())
     }
+
+    /// check the mint supply moved by exactly the same amount as the total supply in the pool
+    /// since the previous snapshot, so user burns outside the pool are tolerated but every
+    /// mint or burn done by the pool itself is accounted for.
+    pub fn check_mint_supply_change(
+        &self,
+        last_total_supply: u64,
+        last_mint_supply: u64,
+        mint_supply: u64,
+    ) -> ProgramResult {
+        if mint_supply as i128 - last_mint_supply as i128
+            != self.total_supply as i128 - last_total_supply as i128
+        {
+            return Err(SwapError::InconsistentMintSupply.into());
+        }
+        self.check_mint_supply(mint_supply)
+    }
 }
 
 impl Sealed for PoolState {}
 
 /// PoolState packed size
-pub const POOL_STATE_SIZE: usize = 129; // 16 + 16 + 16 + 16 + 16 + 16 + 8 + 1 + 16 + 8
+pub const POOL_STATE_SIZE: usize = 209; // 16 + 16 + 16 + 16 + 16 + 16 + 8 + 1 + 16 + 8 + 8 + 8 + 8 + 8 + 8 + 8 + 8 + 8 + 16
+/// Packed size of the moving average of the oracle price
+pub const POOL_STATE_EMA_SIZE: usize = 24; // 16 + 8
 impl Pack for PoolState {
     const LEN: usize = POOL_STATE_SIZE;
     fn pack_into_slice(&self, output: &mut [u8]) {
@@ -610,7 +1261,18 @@ impl Pack for PoolState {
             multiplier,
             last_market_price,
             last_valid_market_price_slot,
-        ) = mut_array_refs![output, 16, 16, 16, 16, 16, 16, 8, 1, 16, 8];
+            base_fee_pending,
+            quote_fee_pending,
+            base_fee_total,
+            quote_fee_total,
+            protocol_fee_numerator,
+            protocol_fee_denominator,
+            base_protocol_fee,
+            quote_protocol_fee,
+            max_share_price,
+        ) = mut_array_refs![
+            output, 16, 16, 16, 16, 16, 16, 8, 1, 16, 8, 8, 8, 8, 8, 8, 8, 8, 8, 16
+        ];
         pack_decimal(self.market_price, market_price);
         pack_decimal(self.slope, slope);
         pack_decimal(self.base_reserve, base_reserve);
@@ -621,6 +1283,15 @@ impl Pack for PoolState {
         multiplier[0] = self.multiplier as u8;
         pack_decimal(self.last_market_price, last_market_price);
         *last_valid_market_price_slot = self.last_valid_market_price_slot.to_le_bytes();
+        *base_fee_pending = self.base_fee_pending.to_le_bytes();
+        *quote_fee_pending = self.quote_fee_pending.to_le_bytes();
+        *base_fee_total = self.base_fee_total.to_le_bytes();
+        *quote_fee_total = self.quote_fee_total.to_le_bytes();
+        *protocol_fee_numerator = self.protocol_fee_numerator.to_le_bytes();
+        *protocol_fee_denominator = self.protocol_fee_denominator.to_le_bytes();
+        *base_protocol_fee = self.base_protocol_fee.to_le_bytes();
+        *quote_protocol_fee = self.quote_protocol_fee.to_le_bytes();
+        pack_decimal(self.max_share_price, max_share_price);
     }
 
     fn unpack_from_slice(input: &[u8]) -> Result<Self, ProgramError> {
@@ -637,7 +1308,16 @@ impl Pack for PoolState {
             multiplier,
             last_market_price,
             last_valid_market_price_slot,
-        ) = array_refs![input, 16, 16, 16, 16, 16, 16, 8, 1, 16, 8];
+            base_fee_pending,
+            quote_fee_pending,
+            base_fee_total,
+            quote_fee_total,
+            protocol_fee_numerator,
+            protocol_fee_denominator,
+            base_protocol_fee,
+            quote_protocol_fee,
+            max_share_price,
+        ) = array_refs![input, 16, 16, 16, 16, 16, 16, 8, 1, 16, 8, 8, 8, 8, 8, 8, 8, 8, 8, 16];
         Ok(Self {
             market_price: unpack_decimal(market_price),
             slope: unpack_decimal(slope),
@@ -649,6 +1329,16 @@ impl Pack for PoolState {
             multiplier: multiplier[0].try_into()?,
             last_market_price: unpack_decimal(last_market_price),
             last_valid_market_price_slot: u64::from_le_bytes(*last_valid_market_price_slot),
+            base_fee_pending: u64::from_le_bytes(*base_fee_pending),
+            quote_fee_pending: u64::from_le_bytes(*quote_fee_pending),
+            base_fee_total: u64::from_le_bytes(*base_fee_total),
+            quote_fee_total: u64::from_le_bytes(*quote_fee_total),
+            protocol_fee_numerator: u64::from_le_bytes(*protocol_fee_numerator),
+            protocol_fee_denominator: u64::from_le_bytes(*protocol_fee_denominator),
+            base_protocol_fee: u64::from_le_bytes(*base_protocol_fee),
+            quote_protocol_fee: u64::from_le_bytes(*quote_protocol_fee),
+            max_share_price: unpack_decimal(max_share_price),
+            ..Self::default()
         })
     }
 }
@@ -656,6 +1346,8 @@ impl Pack for PoolState {
 #[cfg(test)]
 mod tests {
     use super::*;
+    use crate::math::WAD;
+    use proptest::prelude::*;
 
     #[test]
     fn test_check_mint_supply() {
@@ -681,6 +1373,50 @@ mod tests {
         );
     }
 
+    #[test]
+    fn test_check_mint_supply_change() {
+        let pool_state = PoolState::new(InitPoolStateParams {
+            market_price: default_market_price(),
+            slope: default_slope(),
+            base_reserve: Decimal::from(100u64),
+            quote_reserve: Decimal::from(100u64),
+            total_supply: 1000,
+            last_market_price: default_market_price(),
+            last_valid_market_price_slot: 0,
+        });
+
+        // Initialization mints the whole total supply.
+        assert_eq!(pool_state.check_mint_supply_change(0, 0, 1000), Ok(()));
+
+        // Deposit of 100 shares after users burned 50 tokens by themselves.
+        assert_eq!(pool_state.check_mint_supply_change(900, 850, 950), Ok(()));
+
+        // Withdraw of 100 shares, the pool total supply shrinks by the same amount.
+        assert_eq!(
+            pool_state.check_mint_supply_change(1100, 1100, 1000),
+            Ok(())
+        );
+
+        // Minted more LP tokens than the shares added to the pool.
+        assert_eq!(
+            pool_state.check_mint_supply_change(900, 850, 951),
+            Err(SwapError::InconsistentMintSupply.into())
+        );
+
+        // Burned fewer LP tokens than the shares removed from the pool, e.g. withdraw fee
+        // shares left in circulation.
+        assert_eq!(
+            pool_state.check_mint_supply_change(1100, 1100, 1010),
+            Err(SwapError::InconsistentMintSupply.into())
+        );
+
+        // Deltas match but the mint supply still exceeds the pool total supply.
+        assert_eq!(
+            pool_state.check_mint_supply_change(0, 10, 1010),
+            Err(SwapError::InvalidSupply.into())
+        );
+    }
+
     #[test]
     fn test_check_reserve_amount() {
         let mut pool_state = PoolState::new(InitPoolStateParams {
@@ -711,6 +1447,65 @@ mod tests {
         );
     }
 
+    #[test]
+    fn test_needs_price_refresh() {
+        let pool_state = PoolState::new(InitPoolStateParams {
+            market_price: default_market_price(),
+            slope: default_slope(),
+            base_reserve: Decimal::from(100u64),
+            quote_reserve: Decimal::from(100u64),
+            total_supply: 0,
+            last_market_price: default_market_price(),
+            last_valid_market_price_slot: 1_000,
+        });
+        assert!(!pool_state.needs_price_refresh(1_000));
+        assert!(!pool_state.needs_price_refresh(1_000 + REFRESH_PRICE_INTERVAL_SLOTS - 1));
+        assert!(pool_state.needs_price_refresh(1_000 + REFRESH_PRICE_INTERVAL_SLOTS));
+        // An oracle slot ahead of the clock is never refreshed
+        assert!(!pool_state.needs_price_refresh(900));
+    }
+
+    #[test]
+    fn test_sync_reserve_amount() {
+        let mut pool_state = PoolState::new(InitPoolStateParams {
+            market_price: default_market_price(),
+            slope: default_slope(),
+            base_reserve: Decimal::from(100u64),
+            quote_reserve: Decimal::from(100u64),
+            total_supply: 0,
+            last_market_price: default_market_price(),
+            last_valid_market_price_slot: 0,
+        });
+        pool_state.base_fee_pending = 5;
+        pool_state.quote_protocol_fee = 3;
+
+        assert_eq!(pool_state.sync_reserve_amount(105u64, 103u64), Ok((0, 0)));
+        assert_eq!(pool_state.base_protocol_fee, 0);
+        assert_eq!(pool_state.quote_protocol_fee, 3);
+
+        // The donations are credited to the protocol fees
+        assert_eq!(pool_state.surplus_amounts(125u64, 110u64), Ok((20, 7)));
+        assert_eq!(pool_state.sync_reserve_amount(125u64, 110u64), Ok((20, 7)));
+        assert_eq!(pool_state.base_protocol_fee, 20);
+        assert_eq!(pool_state.quote_protocol_fee, 10);
+        assert_eq!(pool_state.base_reserve, Decimal::from(100u64));
+        assert_eq!(pool_state.quote_reserve, Decimal::from(100u64));
+        assert_eq!(pool_state.check_reserve_amount(125u64, 110u64), Ok(()));
+        assert_eq!(pool_state.sync_reserve_amount(125u64, 110u64), Ok((0, 0)));
+
+        // The fraction of the reserve is left to the pool
+        pool_state.base_reserve = Decimal::from(100u64)
+            .try_add(Decimal::from_percent(50))
+            .unwrap();
+        assert_eq!(pool_state.sync_reserve_amount(127u64, 110u64), Ok((1, 0)));
+        assert_eq!(pool_state.base_protocol_fee, 21);
+
+        assert_eq!(
+            pool_state.sync_reserve_amount(127u64, 109u64),
+            Err(SwapError::InconsistentPoolState.into())
+        );
+    }
+
     #[test]
     fn test_swap() {
         let mut pool_state = PoolState::new(InitPoolStateParams {
@@ -792,14 +1587,93 @@ mod tests {
 
         assert_eq!(
             pool_state.buy_shares(10_000, 10_000_000),
-            Ok((10000, 10000, 1_000_000))
+            Ok((10000, 10000, 1_000_001))
+        );
+
+        assert_eq!(pool_state.total_supply, 1_010_000);
+    }
+
+    #[test]
+    fn test_buy_shares_2() {
+        let mut pool_state = PoolState::new(InitPoolStateParams {
+            market_price: default_market_price(),
+            slope: default_slope(),
+            base_reserve: Decimal::zero(),
+            quote_reserve: Decimal::zero(),
+            total_supply: 0,
+            last_market_price: default_market_price(),
+            last_valid_market_price_slot: 0,
+        });
+
+        // The initial buy share should always use base token as shares.
+        assert_eq!(
+            pool_state.buy_shares(1_000_000_000, 100_000_000),
+            Ok((1_000_000_000, 1_000_000_000, 100_000_000))
+        );
+    }
+
+    #[test]
+    fn test_deposit_one() {
+        let mut pool_state = PoolState::new(InitPoolStateParams {
+            market_price: default_market_price(),
+            slope: default_slope(),
+            base_reserve: Decimal::zero(),
+            quote_reserve: Decimal::zero(),
+            total_supply: 0,
+            last_market_price: default_market_price(),
+            last_valid_market_price_slot: 0,
+        });
+
+        assert!(pool_state
+            .deposit_one_swap_amount(1_000, SwapDirection::SellBase)
+            .is_err());
+
+        pool_state.buy_shares(1_000_000, 100_000_000).unwrap();
+
+        // Balanced pool, half of the value is swapped to the other side.
+        let base_in = 10_000;
+        let swap_amount = pool_state
+            .deposit_one_swap_amount(base_in, SwapDirection::SellBase)
+            .unwrap();
+        assert_eq!(swap_amount, 5_000);
+
+        let quote_out = pool_state.quote_out_amount(swap_amount).unwrap();
+        pool_state
+            .swap(swap_amount, quote_out, SwapDirection::SellBase)
+            .unwrap();
+        let (shares, base_used) = pool_state
+            .buy_shares_one_side(base_in - swap_amount, quote_out, SwapDirection::SellBase)
+            .unwrap();
+        assert!(base_used <= base_in - swap_amount);
+        assert!(shares > 0 && shares < base_in);
+        assert_eq!(pool_state.total_supply, 1_000_000 + shares);
+
+        // Every swapped token is kept in the pool reserve.
+        assert_eq!(pool_state.quote_reserve, Decimal::from(100_000_000u64));
+
+        let quote_in = 1_000_000;
+        let swap_amount = pool_state
+            .deposit_one_swap_amount(quote_in, SwapDirection::SellQuote)
+            .unwrap();
+        let base_out = pool_state.base_out_amount(swap_amount).unwrap();
+        pool_state
+            .swap(swap_amount, base_out, SwapDirection::SellQuote)
+            .unwrap();
+        let (shares, quote_used) = pool_state
+            .buy_shares_one_side(quote_in - swap_amount, base_out, SwapDirection::SellQuote)
+            .unwrap();
+        assert!(quote_used <= quote_in - swap_amount);
+        assert!(shares > 0);
+
+        // Nothing left to deposit after the swap.
+        assert_eq!(
+            pool_state.buy_shares_one_side(0, 100, SwapDirection::SellBase),
+            Err(SwapError::InsufficientFunds.into())
         );
-
-        assert_eq!(pool_state.total_supply, 1_010_000);
     }
 
     #[test]
-    fn test_buy_shares_2() {
+    fn test_withdraw_one() {
         let mut pool_state = PoolState::new(InitPoolStateParams {
             market_price: default_market_price(),
             slope: default_slope(),
@@ -810,10 +1684,39 @@ mod tests {
             last_valid_market_price_slot: 0,
         });
 
-        // The initial buy share should always use base token as shares.
+        assert!(pool_state
+            .sell_shares_one_side(10, SwapDirection::SellQuote)
+            .is_err());
+
+        pool_state.buy_shares(1_000_000, 100_000_000).unwrap();
+
+        // Withdraw base, the quote part is swapped back into the pool.
+        let (base_amount, quote_swap_amount) = pool_state
+            .sell_shares_one_side(10_000, SwapDirection::SellQuote)
+            .unwrap();
+        assert_eq!((base_amount, quote_swap_amount), (10_000, 1_000_000));
+        assert_eq!(pool_state.total_supply, 990_000);
+
+        let base_out = pool_state.base_out_amount(quote_swap_amount).unwrap();
+        assert!(base_out > 0 && base_out < 10_000);
+        pool_state
+            .swap(quote_swap_amount, base_out, SwapDirection::SellQuote)
+            .unwrap();
+        assert_eq!(pool_state.quote_reserve, Decimal::from(100_000_000u64));
         assert_eq!(
-            pool_state.buy_shares(1_000_000_000, 100_000_000),
-            Ok((1_000_000_000, 1_000_000_000, 100_000_000))
+            pool_state.base_reserve,
+            Decimal::from(1_000_000u64 - base_amount - base_out)
+        );
+
+        // Withdraw quote, the base part is swapped back into the pool.
+        let (quote_amount, base_swap_amount) = pool_state
+            .sell_shares_one_side(10_000, SwapDirection::SellBase)
+            .unwrap();
+        assert!(quote_amount > 0 && base_swap_amount > 0);
+
+        assert_eq!(
+            pool_state.sell_shares_one_side(1_000_000, SwapDirection::SellBase),
+            Err(SwapError::InsufficientFunds.into())
         );
     }
 
@@ -880,19 +1783,172 @@ mod tests {
         });
 
         assert!(pool_state
-            .check_and_update_market_price_and_slot(Decimal::from(100u64), 6u64)
+            .check_and_update_market_price_and_slot(
+                Decimal::from(100u64),
+                6u64,
+                PriceJumpPolicy::Reject
+            )
             .is_err());
         assert!(pool_state
-            .check_and_update_market_price_and_slot(Decimal::from(1000u64), 26u64)
+            .check_and_update_market_price_and_slot(
+                Decimal::from(1000u64),
+                26u64,
+                PriceJumpPolicy::Reject
+            )
             .is_ok());
         assert!(pool_state
-            .check_and_update_market_price_and_slot(Decimal::from(1001u64), 50u64)
+            .check_and_update_market_price_and_slot(
+                Decimal::from(1001u64),
+                50u64,
+                PriceJumpPolicy::Reject
+            )
             .is_ok());
+
+        // A large jump within 10s is taken as is.
+        assert_eq!(
+            pool_state.check_and_update_market_price_and_slot(
+                Decimal::from(500u64),
+                60u64,
+                PriceJumpPolicy::Accept
+            ),
+            Ok(Decimal::from(500u64))
+        );
+        assert_eq!(pool_state.last_market_price, Decimal::from(500u64));
+
+        // A large jump within 10s is limited to 1% of the last price.
+        assert_eq!(
+            pool_state.check_and_update_market_price_and_slot(
+                Decimal::from(600u64),
+                70u64,
+                PriceJumpPolicy::Clamp
+            ),
+            Ok(Decimal::from(505u64))
+        );
+        assert_eq!(
+            pool_state.check_and_update_market_price_and_slot(
+                Decimal::from(400u64),
+                80u64,
+                PriceJumpPolicy::Clamp
+            ),
+            Ok(Decimal::from(505u64)
+                .try_sub(Decimal::from(505u64).try_div(100u64).unwrap())
+                .unwrap())
+        );
+        assert_eq!(pool_state.last_valid_market_price_slot, 80u64);
+
+        // A small move is not clamped.
+        assert_eq!(
+            pool_state.check_and_update_market_price_and_slot(
+                Decimal::from(500u64),
+                90u64,
+                PriceJumpPolicy::Clamp
+            ),
+            Ok(Decimal::from(500u64))
+        );
+    }
+
+    #[test]
+    fn test_update_ema_market_price() {
+        let mut pool_state = PoolState::default();
+
+        // The first price starts the average
+        assert_eq!(
+            pool_state.update_ema_market_price(Decimal::from(100u64), 1_000u64, 100u64),
+            Ok(Decimal::from(100u64))
+        );
+        assert_eq!(pool_state.ema_slot, 1_000u64);
+
+        // A spike moves the average by the slots elapsed over the window
+        assert_eq!(
+            pool_state.update_ema_market_price(Decimal::from(200u64), 1_010u64, 100u64),
+            Ok(Decimal::from(110u64))
+        );
+        assert_eq!(
+            pool_state.update_ema_market_price(Decimal::from(10u64), 1_060u64, 100u64),
+            Ok(Decimal::from(60u64))
+        );
+        // More prints in the same slot leave it as it is
+        assert_eq!(
+            pool_state.update_ema_market_price(Decimal::from(1_000u64), 1_060u64, 100u64),
+            Ok(Decimal::from(60u64))
+        );
+        // An older price does not move it back
+        assert_eq!(
+            pool_state.update_ema_market_price(Decimal::from(90u64), 1_050u64, 100u64),
+            Ok(Decimal::from(60u64))
+        );
+        assert_eq!(pool_state.ema_slot, 1_060u64);
+        // The average follows the price once a window elapsed
+        assert_eq!(
+            pool_state.update_ema_market_price(Decimal::from(90u64), 1_500u64, 100u64),
+            Ok(Decimal::from(90u64))
+        );
+
+        // The oracle price is used as is without a window, the average keeps up with it
+        assert_eq!(
+            pool_state.update_ema_market_price(Decimal::from(120u64), 1_501u64, 0u64),
+            Ok(Decimal::from(120u64))
+        );
+        assert_eq!(pool_state.ema_market_price, Decimal::from(120u64));
+
+        let mut packed = [0u8; POOL_STATE_EMA_SIZE];
+        pool_state.pack_ema_into_slice(&mut packed);
+        let mut unpacked = PoolState::default();
+        unpacked.unpack_ema_from_slice(&packed);
+        assert_eq!(unpacked.ema_market_price, Decimal::from(120u64));
+        assert_eq!(unpacked.ema_slot, 1_501u64);
+    }
+
+    #[test]
+    fn test_price_jump_policy_try_from() {
+        assert_eq!(PriceJumpPolicy::try_from(0u8), Ok(PriceJumpPolicy::Reject));
+        assert_eq!(PriceJumpPolicy::try_from(1u8), Ok(PriceJumpPolicy::Accept));
+        assert_eq!(PriceJumpPolicy::try_from(2u8), Ok(PriceJumpPolicy::Clamp));
+        assert_eq!(
+            PriceJumpPolicy::try_from(3u8),
+            Err(ProgramError::InvalidAccountData)
+        );
+        assert_eq!(PriceJumpPolicy::default(), PriceJumpPolicy::Reject);
+    }
+
+    #[test]
+    fn test_swap_direction_try_from() {
+        assert_eq!(SwapDirection::try_from(0u8), Ok(SwapDirection::SellBase));
+        assert_eq!(SwapDirection::try_from(1u8), Ok(SwapDirection::SellQuote));
+        assert_eq!(
+            SwapDirection::try_from(2u8),
+            Err(ProgramError::InvalidAccountData)
+        );
+    }
+
+    #[test]
+    fn test_check_price_divergence() {
+        let pyth_price = Decimal::from(100u64);
+        assert_eq!(
+            check_price_divergence(pyth_price, Decimal::from(101u64), 100u16),
+            Ok(())
+        );
+        assert_eq!(
+            check_price_divergence(pyth_price, Decimal::from(99u64), 100u16),
+            Ok(())
+        );
+        assert_eq!(
+            check_price_divergence(pyth_price, Decimal::from(102u64), 100u16),
+            Err(SwapError::ExceededPriceDivergence.into())
+        );
+        assert_eq!(
+            check_price_divergence(pyth_price, Decimal::from(98u64), 100u16),
+            Err(SwapError::ExceededPriceDivergence.into())
+        );
+        assert_eq!(
+            check_price_divergence(pyth_price, Decimal::from(200u64), 0u16),
+            Ok(())
+        );
     }
 
     #[test]
     fn test_packing_pool() {
-        let pool_state = PoolState::new(InitPoolStateParams {
+        let mut pool_state = PoolState::new(InitPoolStateParams {
             market_price: default_market_price(),
             slope: default_slope(),
             base_reserve: Decimal::from(1_000_000u64),
@@ -901,6 +1957,9 @@ mod tests {
             last_market_price: default_market_price(),
             last_valid_market_price_slot: 0,
         });
+        pool_state.protocol_fee_numerator = 1;
+        pool_state.protocol_fee_denominator = 4;
+        pool_state.collect_trade_fee(100, 200).unwrap();
 
         let mut packed = [0u8; PoolState::LEN];
         PoolState::pack_into_slice(&pool_state, &mut packed);
@@ -908,6 +1967,164 @@ mod tests {
         assert_eq!(pool_state, unpacked);
     }
 
+    #[test]
+    fn test_collect_trade_fee() {
+        let mut pool_state = PoolState::new(InitPoolStateParams {
+            market_price: default_market_price(),
+            slope: default_slope(),
+            base_reserve: Decimal::zero(),
+            quote_reserve: Decimal::zero(),
+            total_supply: 0,
+            last_market_price: default_market_price(),
+            last_valid_market_price_slot: 0,
+        });
+        pool_state.buy_shares(1_000_000, 100_000_000).unwrap();
+
+        // 1_000 quote token leaves the quote reserve as the LP fee
+        pool_state
+            .swap_with_fee(10_000, 990_000, 1_000, SwapDirection::SellBase)
+            .unwrap();
+        assert_eq!(pool_state.base_reserve, Decimal::from(1_010_000u64));
+        assert_eq!(pool_state.quote_reserve, Decimal::from(99_009_000u64));
+        assert_eq!(pool_state.base_fee_pending, 0);
+        assert_eq!(pool_state.quote_fee_pending, 1_000);
+        assert_eq!(
+            pool_state.check_reserve_amount(1_010_000, 99_010_000),
+            Ok(())
+        );
+        assert_eq!(
+            pool_state.check_reserve_amount(1_010_000, 99_009_999),
+            Err(SwapError::InconsistentPoolState.into())
+        );
+
+        pool_state
+            .swap_with_fee(1_000_000, 9_000, 10, SwapDirection::SellQuote)
+            .unwrap();
+        assert_eq!(pool_state.base_reserve, Decimal::from(1_000_990u64));
+        assert_eq!(pool_state.base_fee_pending, 10);
+        assert_eq!(pool_state.base_fee_total, 10);
+        assert_eq!(pool_state.quote_fee_total, 1_000);
+
+        // Liquidity changes move the pending fees into the reserves, the totals are kept
+        pool_state.sell_shares(0, 0, 0).unwrap();
+        assert_eq!(pool_state.base_reserve, Decimal::from(1_001_000u64));
+        assert_eq!(pool_state.quote_reserve, Decimal::from(100_010_000u64));
+        assert_eq!(pool_state.base_fee_pending, 0);
+        assert_eq!(pool_state.quote_fee_pending, 0);
+        assert_eq!(pool_state.base_fee_total, 10);
+        assert_eq!(pool_state.quote_fee_total, 1_000);
+    }
+
+    #[test]
+    fn test_share_price_floor() {
+        let mut pool_state = PoolState::new(InitPoolStateParams {
+            market_price: Decimal::from(100u64),
+            slope: default_slope(),
+            base_reserve: Decimal::from(1_000_000u64),
+            quote_reserve: Decimal::from(100_000_000u64),
+            total_supply: 1_000_000,
+            last_market_price: Decimal::from(100u64),
+            last_valid_market_price_slot: 0,
+        });
+        assert_eq!(pool_state.share_price(), Ok(Decimal::from(200u64)));
+        assert_eq!(pool_state.check_share_price_floor(), Ok(()));
+        assert_eq!(pool_state.max_share_price, Decimal::from(200u64));
+
+        // The LP fee raises the share price
+        pool_state
+            .swap_with_fee(10_000, 990_000, 1_000, SwapDirection::SellBase)
+            .unwrap();
+        assert_eq!(pool_state.check_share_price_floor(), Ok(()));
+        let max_share_price = pool_state.max_share_price;
+        assert!(max_share_price > Decimal::from(200u64));
+
+        // A drop within the tolerance is let through, the highest share price is kept
+        pool_state.quote_reserve = pool_state
+            .quote_reserve
+            .try_sub(Decimal::from(1_000_000u64))
+            .unwrap();
+        assert_eq!(pool_state.check_share_price_floor(), Ok(()));
+        assert_eq!(pool_state.max_share_price, max_share_price);
+
+        // A 2% drain of the pool value is rejected
+        pool_state.quote_reserve = pool_state
+            .quote_reserve
+            .try_sub(Decimal::from(3_000_000u64))
+            .unwrap();
+        assert_eq!(
+            pool_state.check_share_price_floor(),
+            Err(SwapError::SharePriceBelowFloor.into())
+        );
+
+        // A new market price restarts the floor from the share price at that price
+        pool_state
+            .set_market_price(6, 6, Decimal::from(50u64))
+            .unwrap();
+        assert_eq!(
+            pool_state.max_share_price,
+            pool_state.share_price().unwrap()
+        );
+        assert!(pool_state.max_share_price < max_share_price);
+        assert_eq!(pool_state.check_share_price_floor(), Ok(()));
+
+        // The same market price keeps the floor
+        pool_state
+            .set_market_price(6, 6, Decimal::from(50u64))
+            .unwrap();
+        pool_state.base_reserve = pool_state
+            .base_reserve
+            .try_sub(Decimal::from(100_000u64))
+            .unwrap();
+        assert_eq!(
+            pool_state.check_share_price_floor(),
+            Err(SwapError::SharePriceBelowFloor.into())
+        );
+    }
+
+    #[test]
+    fn test_collect_protocol_fee() {
+        let mut pool_state = PoolState::new(InitPoolStateParams {
+            market_price: default_market_price(),
+            slope: default_slope(),
+            base_reserve: Decimal::zero(),
+            quote_reserve: Decimal::zero(),
+            total_supply: 0,
+            last_market_price: default_market_price(),
+            last_valid_market_price_slot: 0,
+        });
+        pool_state.buy_shares(1_000_000, 100_000_000).unwrap();
+        pool_state.protocol_fee_numerator = 1;
+        pool_state.protocol_fee_denominator = 4;
+
+        // A quarter of the 1_000 quote token LP fee is set aside for the protocol
+        pool_state
+            .swap_with_fee(10_000, 990_000, 1_000, SwapDirection::SellBase)
+            .unwrap();
+        assert_eq!(pool_state.quote_fee_pending, 750);
+        assert_eq!(pool_state.quote_fee_total, 750);
+        assert_eq!(pool_state.quote_protocol_fee, 250);
+        assert_eq!(
+            pool_state.check_reserve_amount(1_010_000, 99_010_000),
+            Ok(())
+        );
+        assert_eq!(
+            pool_state.check_reserve_amount(1_010_000, 99_009_999),
+            Err(SwapError::InconsistentPoolState.into())
+        );
+
+        // The protocol fees stay out of the reserves on liquidity changes
+        pool_state.sell_shares(0, 0, 0).unwrap();
+        assert_eq!(pool_state.quote_reserve, Decimal::from(99_009_750u64));
+        assert_eq!(pool_state.quote_protocol_fee, 250);
+
+        assert_eq!(pool_state.claim_protocol_fee(), (0, 250));
+        assert_eq!(pool_state.quote_protocol_fee, 0);
+        assert_eq!(
+            pool_state.check_reserve_amount(1_010_000, 99_009_750),
+            Ok(())
+        );
+    }
+
     #[test]
     fn test_get_out_amount_balanced_pool() {
         let mut pool_state = PoolState::new(InitPoolStateParams {
@@ -1520,4 +2737,360 @@ mod tests {
             .unwrap();
         assert_eq!(pool_state.market_price, Decimal::from(100u64));
     }
+
+    #[test]
+    fn test_price_impact_bps() {
+        let mut pool_state = PoolState::new(InitPoolStateParams {
+            market_price: default_market_price(),
+            slope: default_slope(),
+            base_reserve: Decimal::zero(),
+            quote_reserve: Decimal::zero(),
+            total_supply: 0,
+            last_market_price: default_market_price(),
+            last_valid_market_price_slot: 0,
+        });
+
+        // 1_000 base is worth 100_000 quote at the market price of 100
+        assert_eq!(
+            pool_state.price_impact_bps(1_000, 99_000, SwapDirection::SellBase),
+            Ok(100)
+        );
+        assert_eq!(
+            pool_state.price_impact_bps(100_000, 995, SwapDirection::SellQuote),
+            Ok(50)
+        );
+        assert_eq!(
+            pool_state.price_impact_bps(1_000, 100_500, SwapDirection::SellBase),
+            Ok(0)
+        );
+
+        pool_state.market_price = Decimal::zero();
+        assert_eq!(
+            pool_state.price_impact_bps(1_000, 99_000, SwapDirection::SellBase),
+            Ok(0)
+        );
+    }
+
+    #[test]
+    fn test_mid_price() {
+        let mut pool_state = PoolState::new(InitPoolStateParams {
+            market_price: default_market_price(),
+            slope: default_slope(),
+            base_reserve: Decimal::from(1_000_000u64),
+            quote_reserve: Decimal::from(100_000_000u64),
+            total_supply: 1_000_000,
+            last_market_price: default_market_price(),
+            last_valid_market_price_slot: 0,
+        });
+        pool_state.adjust_target().unwrap();
+        assert_eq!(pool_state.multiplier, Multiplier::One);
+        let mid_price = pool_state.mid_price().unwrap();
+        assert_eq!(mid_price, default_market_price());
+        assert_eq!(
+            pool_state.market_price_deviation(mid_price),
+            Ok(Decimal::zero())
+        );
+
+        // quote token short against the new market price, the curve prices base lower
+        pool_state
+            .set_market_price(6, 6, Decimal::from(110u64))
+            .unwrap();
+        assert_eq!(pool_state.multiplier, Multiplier::BelowOne);
+        let mid_price = pool_state.mid_price().unwrap();
+        assert!(mid_price < Decimal::from(110u64));
+        let deviation = pool_state.market_price_deviation(mid_price).unwrap();
+        assert!(deviation > Decimal::zero());
+        assert!(deviation < Decimal::from_percent(10));
+        // the last mid price deviates by the market price move
+        assert_eq!(
+            pool_state.market_price_deviation(default_market_price()),
+            Decimal::from(10u64).try_div(110u64)
+        );
+
+        // base token short against the new market price, the curve prices base higher
+        pool_state
+            .set_market_price(6, 6, Decimal::from(90u64))
+            .unwrap();
+        assert_eq!(pool_state.multiplier, Multiplier::AboveOne);
+        let mid_price = pool_state.mid_price().unwrap();
+        assert!(mid_price > Decimal::from(90u64));
+        assert!(pool_state.market_price_deviation(mid_price).unwrap() > Decimal::zero());
+
+        pool_state.market_price = Decimal::zero();
+        assert_eq!(
+            pool_state.market_price_deviation(mid_price),
+            Ok(Decimal::zero())
+        );
+    }
+
+    #[test]
+    fn test_rebalance_amounts() {
+        let mut pool_state = PoolState::new(InitPoolStateParams {
+            market_price: default_market_price(),
+            slope: default_slope(),
+            base_reserve: Decimal::from(1_000_000u64),
+            quote_reserve: Decimal::from(100_000_000u64),
+            total_supply: 1_000_000,
+            last_market_price: default_market_price(),
+            last_valid_market_price_slot: 0,
+        });
+        pool_state.adjust_target().unwrap();
+        assert_eq!(
+            pool_state.rebalance_amounts(u64::MAX, 10),
+            Err(SwapError::RebalanceNotNeeded.into())
+        );
+
+        // base token short against the new market price, the rebalance sells base to the pool
+        pool_state
+            .set_market_price(6, 6, Decimal::from(90u64))
+            .unwrap();
+        assert_eq!(pool_state.multiplier, Multiplier::AboveOne);
+        let deviation = pool_state
+            .market_price_deviation(pool_state.mid_price().unwrap())
+            .unwrap();
+
+        // the output is the market price plus the bounty, below the curve output
+        assert_eq!(
+            pool_state.rebalance_amounts(1_000, 10),
+            Ok((SwapDirection::SellBase, 1_000, 90_090))
+        );
+        assert!(pool_state.quote_out_amount(1_000).unwrap() > 90_090);
+
+        let (swap_direction, amount_in, amount_out) =
+            pool_state.rebalance_amounts(u64::MAX, 10).unwrap();
+        assert_eq!(swap_direction, SwapDirection::SellBase);
+        assert_eq!(
+            amount_in,
+            pool_state
+                .base_target
+                .try_sub(pool_state.base_reserve)
+                .unwrap()
+                .try_floor_u64()
+                .unwrap()
+        );
+        pool_state
+            .swap(amount_in, amount_out, swap_direction)
+            .unwrap();
+        let rebalanced_deviation = pool_state
+            .market_price_deviation(pool_state.mid_price().unwrap())
+            .unwrap();
+        assert!(rebalanced_deviation.try_mul(10u64).unwrap() < deviation);
+    }
+
+    proptest! {
+        #[test]
+        fn test_pool_state_arbitrary_inputs_never_panic(
+            market_price in 1..=u64::MAX,
+            slope in 0..=WAD,
+            base_reserve in 0..=u64::MAX,
+            quote_reserve in 0..=u64::MAX,
+            total_supply in 0..=u64::MAX,
+            amount in 0..=u64::MAX,
+            sell_base in any::<bool>(),
+        ) {
+            let mut pool_state = PoolState::new(InitPoolStateParams {
+                market_price: Decimal::from_scaled_val(market_price.into()),
+                slope: Decimal::from_scaled_val(slope.into()),
+                base_reserve: Decimal::from(base_reserve),
+                quote_reserve: Decimal::from(quote_reserve),
+                total_supply,
+                last_market_price: Decimal::from_scaled_val(market_price.into()),
+                last_valid_market_price_slot: 0,
+            });
+            // Every calculation fails with an error rather than a panic
+            let _ = pool_state.adjust_target();
+            let swap_direction = if sell_base {
+                SwapDirection::SellBase
+            } else {
+                SwapDirection::SellQuote
+            };
+            if let Ok(amount_out) = pool_state.get_out_amount(amount, swap_direction) {
+                let lp_fee = amount_out / 10;
+                let _ = pool_state.clone().swap_with_fee(
+                    amount,
+                    amount_out - lp_fee,
+                    lp_fee,
+                    swap_direction,
+                );
+            }
+            let _ = pool_state.clone().buy_shares(amount, amount);
+            let _ = pool_state.clone().sell_shares(amount, 0, 0);
+            let _ = pool_state.check_and_update_market_price_and_slot(
+                Decimal::from_scaled_val(market_price.into()),
+                0,
+                PriceJumpPolicy::Reject,
+            );
+        }
+
+        #[test]
+        fn test_deposit_withdraw_never_drains_reserves(
+            market_price in 1_000_000..=1_000_000_000_000_000u64,
+            slope in 0..=WAD,
+            base_reserve in 1..=1_000_000_000_000_000u64,
+            quote_reserve in 1..=1_000_000_000_000_000u64,
+            total_supply in 1..=1_000_000_000_000_000u64,
+            base_input in 1..=1_000_000_000_000u64,
+            quote_input in 1..=1_000_000_000_000u64,
+        ) {
+            let mut pool_state = PoolState::new(InitPoolStateParams {
+                market_price: Decimal::from_scaled_val(market_price.into()),
+                slope: Decimal::from_scaled_val(slope.into()),
+                base_reserve: Decimal::from(base_reserve),
+                quote_reserve: Decimal::from(quote_reserve),
+                total_supply,
+                last_market_price: Decimal::from_scaled_val(market_price.into()),
+                last_valid_market_price_slot: 0,
+            });
+            prop_assume!(pool_state.adjust_target().is_ok());
+
+            // Withdrawing the shares of a deposit never returns more than the deposit, the
+            // rounding dust stays with the pool
+            if let Ok((shares, base_used, quote_used)) =
+                pool_state.buy_shares(base_input, quote_input)
+            {
+                if let Ok((base_out, quote_out)) = pool_state.sell_shares(shares, 0, 0) {
+                    prop_assert!(base_out <= base_used);
+                    prop_assert!(quote_out <= quote_used);
+                    prop_assert!(pool_state.base_reserve >= Decimal::from(base_reserve));
+                    prop_assert!(pool_state.quote_reserve >= Decimal::from(quote_reserve));
+                    prop_assert_eq!(pool_state.total_supply, total_supply);
+                }
+            }
+        }
+
+        #[test]
+        fn test_pool_state_operation_sequence_invariants(
+            market_price in 1_000_000_000..=1_000_000_000_000_000u64,
+            slope in 10_000_000_000..=WAD,
+            base_reserve in 1_000_000..=1_000_000_000_000u64,
+            operations in prop::collection::vec(
+                (
+                    0..3u8,
+                    1..=10_000_000_000_000u64,
+                    1..=10_000_000_000_000u64,
+                    any::<bool>(),
+                ),
+                1..16,
+            ),
+        ) {
+            // A balanced pool at the market price
+            let market_price = Decimal::from_scaled_val(market_price.into());
+            let quote_reserve = Decimal::from(base_reserve)
+                .try_mul(market_price)
+                .unwrap()
+                .try_floor_u64()
+                .unwrap();
+            prop_assume!(quote_reserve > 0);
+            let mut pool_state = PoolState::new(InitPoolStateParams {
+                market_price,
+                slope: Decimal::from_scaled_val(slope.into()),
+                base_reserve: Decimal::from(base_reserve),
+                quote_reserve: Decimal::from(quote_reserve),
+                total_supply: base_reserve,
+                last_market_price: market_price,
+                last_valid_market_price_slot: 0,
+            });
+            prop_assume!(pool_state.adjust_target().is_ok());
+            let share_price_floor = pool_state
+                .share_price()
+                .unwrap()
+                .try_mul(10_000 - SHARE_PRICE_FLOOR_TOLERANCE_BPS)
+                .unwrap()
+                .try_div(10_000)
+                .unwrap();
+
+            // Token amounts held by the pool, moved by the operations that succeed
+            let mut base_amount = base_reserve;
+            let mut quote_amount = quote_reserve;
+            for (operation, amount, other_amount, sell_base) in operations {
+                let mut next_state = pool_state.clone();
+                let (next_base_amount, next_quote_amount) = match operation {
+                    0 => {
+                        let swap_direction = if sell_base {
+                            SwapDirection::SellBase
+                        } else {
+                            SwapDirection::SellQuote
+                        };
+                        let amount_out =
+                            match next_state.get_out_amount(amount, swap_direction) {
+                                Ok(amount_out) => amount_out,
+                                Err(_) => continue,
+                            };
+                        if next_state.swap(amount, amount_out, swap_direction).is_err() {
+                            continue;
+                        }
+                        match swap_direction {
+                            SwapDirection::SellBase => (
+                                base_amount.checked_add(amount),
+                                quote_amount.checked_sub(amount_out),
+                            ),
+                            SwapDirection::SellQuote => (
+                                base_amount.checked_sub(amount_out),
+                                quote_amount.checked_add(amount),
+                            ),
+                        }
+                    }
+                    1 => {
+                        let (shares, base_used, quote_used) =
+                            match next_state.buy_shares(amount, other_amount) {
+                                Ok(bought) => bought,
+                                Err(_) => continue,
+                            };
+                        // Selling the shares right back loses at most the worth of a pool
+                        // token and the truncation of the mint ratio
+                        if let Ok((base_out, quote_out)) =
+                            next_state.clone().sell_shares(shares, 0, 0)
+                        {
+                            let tolerance = |reserve: Decimal| {
+                                let share_worth = reserve
+                                    .try_div(next_state.total_supply)
+                                    .unwrap()
+                                    .try_floor_u64()
+                                    .unwrap();
+                                let truncation =
+                                    reserve.try_div(WAD).unwrap().try_floor_u64().unwrap();
+                                share_worth + truncation + 2
+                            };
+                            prop_assert!(base_out <= base_used);
+                            prop_assert!(quote_out <= quote_used);
+                            prop_assert!(
+                                base_used - base_out <= tolerance(next_state.base_reserve)
+                            );
+                            prop_assert!(
+                                quote_used - quote_out <= tolerance(next_state.quote_reserve)
+                            );
+                        }
+                        (
+                            base_amount.checked_add(base_used),
+                            quote_amount.checked_add(quote_used),
+                        )
+                    }
+                    _ => {
+                        if next_state.total_supply <= 1 {
+                            continue;
+                        }
+                        let shares = 1 + amount % (next_state.total_supply - 1);
+                        let (base_out, quote_out) = match next_state.sell_shares(shares, 0, 0) {
+                            Ok(sold) => sold,
+                            Err(_) => continue,
+                        };
+                        (
+                            base_amount.checked_sub(base_out),
+                            quote_amount.checked_sub(quote_out),
+                        )
+                    }
+                };
+
+                // The reserves never go negative and always match the tokens held
+                base_amount = next_base_amount.unwrap();
+                quote_amount = next_quote_amount.unwrap();
+                pool_state = next_state;
+                prop_assert_eq!(pool_state.base_reserve, Decimal::from(base_amount));
+                prop_assert_eq!(pool_state.quote_reserve, Decimal::from(quote_amount));
+
+                // The value of a pool token never drops below the share price floor
+                prop_assert!(pool_state.share_price().unwrap() >= share_price_floor);
+            }
+        }
+    }
 }
@@ -0,0 +1,241 @@
+//! StableSwap invariant of the stable pools of more than two tokens
+//!
+//! A * n^n * sum(x_i) + D = A * D * n^n + D^(n+1) / (n^n * prod(x_i))
+//!
+//! where the balances x_i are normalized to the same decimals, A is the amplification
+//! coefficient and D the invariant, the total balance of the pool when it is balanced.
+
+use std::convert::TryFrom;
+
+use solana_program::program_error::ProgramError;
+
+use crate::{error::SwapError, math::U192};
+
+/// Max iterations of the Newton method converging to the invariant or to a balance
+const MAX_ITERATIONS: usize = 256;
+
+/// Min amplification coefficient, the curve is constant product at 0
+pub const MIN_AMP: u64 = 1;
+/// Max amplification coefficient, the curve is about constant sum above
+pub const MAX_AMP: u64 = 1_000_000;
+
+fn checked_mul_div(a: U192, b: U192, c: U192) -> Result<U192, ProgramError> {
+    a.checked_mul(b)
+        .and_then(|value| value.checked_div(c))
+        .ok_or_else(|| SwapError::CalculationFailure.into())
+}
+
+fn to_u128(value: U192) -> Result<u128, ProgramError> {
+    if value > U192::from(u128::MAX) {
+        return Err(SwapError::CalculationFailure.into());
+    }
+    Ok(value.as_u128())
+}
+
+/// A * n^n of the amplification coefficient for n tokens
+fn amp_n_pow_n(amp: u64, token_count: usize) -> Result<U192, ProgramError> {
+    if !(MIN_AMP..=MAX_AMP).contains(&amp) {
+        return Err(SwapError::InvalidAmp.into());
+    }
+    let n = u32::try_from(token_count).map_err(|_| SwapError::CalculationFailure)?;
+    (token_count as u128)
+        .checked_pow(n)
+        .and_then(|n_pow_n| n_pow_n.checked_mul(amp as u128))
+        .map(U192::from)
+        .ok_or_else(|| SwapError::CalculationFailure.into())
+}
+
+/// Compute the invariant D of the normalized balances.
+///
+/// # Arguments
+///
+/// * amp - amplification coefficient.
+/// * balances - balances of the tokens, normalized to the same decimals.
+///
+/// # Return value
+///
+/// invariant D, zero for an empty pool.
+pub fn compute_stable_invariant(amp: u64, balances: &[u128]) -> Result<u128, ProgramError> {
+    let ann = amp_n_pow_n(amp, balances.len())?;
+    let n = U192::from(balances.len());
+    let sum = balances
+        .iter()
+        .try_fold(U192::zero(), |sum, balance| {
+            sum.checked_add(U192::from(*balance))
+        })
+        .ok_or(SwapError::CalculationFailure)?;
+    if sum.is_zero() {
+        return Ok(0);
+    }
+
+    let mut d = sum;
+    for _ in 0..MAX_ITERATIONS {
+        // D^(n+1) / (n^n * prod(x_i))
+        let mut d_p = d;
+        for balance in balances {
+            let denominator = U192::from(*balance)
+                .checked_mul(n)
+                .filter(|denominator| !denominator.is_zero())
+                .ok_or(SwapError::CalculationFailure)?;
+            d_p = checked_mul_div(d_p, d, denominator)?;
+        }
+        let previous_d = d;
+        // D = (Ann * S + D_P * n) * D / ((Ann - 1) * D + (n + 1) * D_P)
+        let numerator = ann
+            .checked_mul(sum)
+            .and_then(|value| value.checked_add(d_p.checked_mul(n)?))
+            .ok_or(SwapError::CalculationFailure)?;
+        let denominator = (ann - U192::one())
+            .checked_mul(d)
+            .and_then(|value| value.checked_add(d_p.checked_mul(n + U192::one())?))
+            .ok_or(SwapError::CalculationFailure)?;
+        d = checked_mul_div(numerator, d, denominator)?;
+
+        let difference = if d > previous_d {
+            d - previous_d
+        } else {
+            previous_d - d
+        };
+        if difference <= U192::one() {
+            return to_u128(d);
+        }
+    }
+    Err(SwapError::CalculationFailure.into())
+}
+
+/// Compute the balance of a token keeping the invariant D with the other balances.
+///
+/// # Arguments
+///
+/// * amp - amplification coefficient.
+/// * balances - balances of the tokens, normalized to the same decimals. The balance of
+///   the token computed is ignored.
+/// * index - index of the token to compute the balance of.
+/// * d - invariant to keep.
+///
+/// # Return value
+///
+/// normalized balance of the token.
+pub fn compute_stable_balance(
+    amp: u64,
+    balances: &[u128],
+    index: usize,
+    d: u128,
+) -> Result<u128, ProgramError> {
+    if index >= balances.len() {
+        return Err(SwapError::CalculationFailure.into());
+    }
+    let ann = amp_n_pow_n(amp, balances.len())?;
+    let n = U192::from(balances.len());
+    let d = U192::from(d);
+
+    // c = D^(n+1) / (n^n * prod(x_k) * Ann), b = S + D / Ann over the other tokens k
+    let mut c = d;
+    let mut sum = U192::zero();
+    for (k, balance) in balances.iter().enumerate() {
+        if k == index {
+            continue;
+        }
+        let balance = U192::from(*balance);
+        sum = sum
+            .checked_add(balance)
+            .ok_or(SwapError::CalculationFailure)?;
+        let denominator = balance
+            .checked_mul(n)
+            .filter(|denominator| !denominator.is_zero())
+            .ok_or(SwapError::CalculationFailure)?;
+        c = checked_mul_div(c, d, denominator)?;
+    }
+    c = checked_mul_div(
+        c,
+        d,
+        ann.checked_mul(n).ok_or(SwapError::CalculationFailure)?,
+    )?;
+    let b = sum
+        .checked_add(d / ann)
+        .ok_or(SwapError::CalculationFailure)?;
+
+    // y = (y^2 + c) / (2y + b - D)
+    let mut y = d;
+    for _ in 0..MAX_ITERATIONS {
+        let previous_y = y;
+        let numerator = y
+            .checked_mul(y)
+            .and_then(|value| value.checked_add(c))
+            .ok_or(SwapError::CalculationFailure)?;
+        let denominator = y
+            .checked_mul(U192::from(2u8))
+            .and_then(|value| value.checked_add(b))
+            .and_then(|value| value.checked_sub(d))
+            .filter(|denominator| !denominator.is_zero())
+            .ok_or(SwapError::CalculationFailure)?;
+        y = numerator / denominator;
+
+        let difference = if y > previous_y {
+            y - previous_y
+        } else {
+            previous_y - y
+        };
+        if difference <= U192::one() {
+            return to_u128(y);
+        }
+    }
+    Err(SwapError::CalculationFailure.into())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_stable_invariant() {
+        // A balanced pool is worth the sum of its balances
+        let balances = [1_000_000_000u128; 3];
+        assert_eq!(
+            compute_stable_invariant(100, &balances).unwrap(),
+            3_000_000_000
+        );
+        assert_eq!(compute_stable_invariant(100, &[0, 0, 0]).unwrap(), 0);
+
+        // An imbalanced pool is worth less than the sum, more so at a low amplification
+        let balances = [1_000_000_000u128, 2_000_000_000, 3_000_000_000];
+        let high_amp = compute_stable_invariant(1_000, &balances).unwrap();
+        let low_amp = compute_stable_invariant(10, &balances).unwrap();
+        assert!(high_amp < 6_000_000_000);
+        assert!(low_amp < high_amp);
+
+        assert_eq!(
+            compute_stable_invariant(0, &balances).unwrap_err(),
+            SwapError::InvalidAmp.into()
+        );
+        assert_eq!(
+            compute_stable_invariant(100, &[1_000, 0, 1_000]).unwrap_err(),
+            SwapError::CalculationFailure.into()
+        );
+    }
+
+    #[test]
+    fn test_stable_balance() {
+        let amp = 100;
+        let balances = [1_000_000_000u128; 3];
+        let d = compute_stable_invariant(amp, &balances).unwrap();
+        let balance = compute_stable_balance(amp, &balances, 1, d).unwrap();
+        assert!(balance >= balances[1] - 1 && balance <= balances[1] + 1);
+
+        // Close to 1:1 near the balance of the pool
+        let new_balances = [1_001_000_000u128, 0, 1_000_000_000];
+        let balance = compute_stable_balance(amp, &new_balances, 1, d).unwrap();
+        let amount_out = balances[1] - balance;
+        assert!(amount_out < 1_000_000 && amount_out > 999_000);
+
+        // The invariant is kept
+        let swapped = [1_001_000_000u128, balance, 1_000_000_000];
+        let new_d = compute_stable_invariant(amp, &swapped).unwrap();
+        assert!(new_d >= d - 2 && new_d <= d + 2);
+
+        assert_eq!(
+            compute_stable_balance(amp, &balances, 3, d).unwrap_err(),
+            SwapError::CalculationFailure.into()
+        );
+    }
+}
@@ -0,0 +1,275 @@
+//! Decoder of the raw instructions of the program, for explorers and indexers labeling the
+//! DeltaFi transactions. The data is unpacked into the typed instruction enums and the
+//! accounts are named after the account lists of the [IDL](../idl/index.html).
+
+use crate::{
+    idl::{idl, IdlInstruction},
+    instruction::{
+        AdminInstruction, FarmInstruction, InstructionType, RouterInstruction,
+        StableSwapInstruction, SwapInstruction,
+    },
+};
+use solana_program::{program_error::ProgramError, pubkey::Pubkey};
+
+/// Instruction unpacked from its data
+#[derive(Debug, PartialEq)]
+pub enum DecodedInstruction {
+    /// Admin instruction
+    Admin(AdminInstruction),
+    /// Swap instruction of a normal pool
+    Swap(SwapInstruction),
+    /// Swap instruction of a stable or pegged pool
+    StableSwap(StableSwapInstruction),
+    /// Farm instruction
+    Farm(FarmInstruction),
+    /// Multi-hop router instruction
+    Router(RouterInstruction),
+}
+
+/// Account of an instruction with its role
+#[derive(Clone, Debug, PartialEq)]
+pub struct ParsedAccount {
+    /// Account name in the IDL, e.g. `swapAuthority`
+    pub name: &'static str,
+    /// Account key
+    pub pubkey: Pubkey,
+    /// Writable account
+    pub is_mut: bool,
+    /// Signer account
+    pub is_signer: bool,
+}
+
+/// Instruction decoded with its accounts
+#[derive(Debug, PartialEq)]
+pub struct ParsedInstruction {
+    /// Instruction name in the IDL, e.g. `stableSwapSwap`
+    pub name: String,
+    /// Unpacked instruction
+    pub instruction: DecodedInstruction,
+    /// Accounts named in the order of the IDL. The optional accounts are named by their
+    /// position, an instruction leaving out an optional account but passing a later one has
+    /// the later one named after the account left out.
+    pub accounts: Vec<ParsedAccount>,
+    /// Accounts after the account list of the IDL, like the pools of a route or the
+    /// accounts of a flash swap callback
+    pub remaining_accounts: Vec<Pubkey>,
+}
+
+/// Decoder holding the account tables of the IDL, built once to decode many instructions
+pub struct InstructionDecoder {
+    instructions: Vec<IdlInstruction>,
+}
+
+impl Default for InstructionDecoder {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+impl InstructionDecoder {
+    /// Decoder of the instructions of the IDL
+    pub fn new() -> Self {
+        Self {
+            instructions: idl().instructions,
+        }
+    }
+
+    /// Decode the data and the accounts of an instruction of the program
+    pub fn decode(
+        &self,
+        data: &[u8],
+        accounts: &[Pubkey],
+    ) -> Result<ParsedInstruction, ProgramError> {
+        let instruction = match InstructionType::check(data) {
+            Some(InstructionType::Admin) => {
+                DecodedInstruction::Admin(AdminInstruction::unpack(data)?)
+            }
+            Some(InstructionType::Swap) => DecodedInstruction::Swap(SwapInstruction::unpack(data)?),
+            Some(InstructionType::StableSwap) => {
+                DecodedInstruction::StableSwap(StableSwapInstruction::unpack(data)?)
+            }
+            Some(InstructionType::Farm) => DecodedInstruction::Farm(FarmInstruction::unpack(data)?),
+            Some(InstructionType::Router) => {
+                DecodedInstruction::Router(RouterInstruction::unpack(data)?)
+            }
+            None => return Err(ProgramError::InvalidInstructionData),
+        };
+        let idl_instruction = self
+            .instructions
+            .iter()
+            .find(|instruction| instruction.discriminant.value == data[0])
+            .ok_or(ProgramError::InvalidInstructionData)?;
+
+        let required_accounts = idl_instruction
+            .accounts
+            .iter()
+            .filter(|account| !account.is_optional)
+            .count();
+        if accounts.len() < required_accounts {
+            return Err(ProgramError::NotEnoughAccountKeys);
+        }
+        let named_accounts = accounts.len().min(idl_instruction.accounts.len());
+
+        Ok(ParsedInstruction {
+            name: idl_instruction.name.clone(),
+            instruction,
+            accounts: idl_instruction
+                .accounts
+                .iter()
+                .zip(accounts)
+                .map(|(account, pubkey)| ParsedAccount {
+                    name: account.name,
+                    pubkey: *pubkey,
+                    is_mut: account.is_mut,
+                    is_signer: account.is_signer,
+                })
+                .collect(),
+            remaining_accounts: accounts[named_accounts..].to_vec(),
+        })
+    }
+}
+
+/// Decode the data and the accounts of an instruction of the program, see
+/// [InstructionDecoder](struct.InstructionDecoder.html) to decode many instructions
+pub fn decode_instruction(
+    data: &[u8],
+    accounts: &[Pubkey],
+) -> Result<ParsedInstruction, ProgramError> {
+    InstructionDecoder::new().decode(data, accounts)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::instruction::{farm_claim, pause, swap, SwapData};
+    use solana_program::instruction::Instruction;
+
+    fn decode(instruction: &Instruction) -> ParsedInstruction {
+        let accounts: Vec<Pubkey> = instruction.accounts.iter().map(|a| a.pubkey).collect();
+        decode_instruction(&instruction.data, &accounts).unwrap()
+    }
+
+    fn assert_accounts_match(parsed: &ParsedInstruction, instruction: &Instruction) {
+        for (account, meta) in parsed.accounts.iter().zip(instruction.accounts.iter()) {
+            assert_eq!(account.pubkey, meta.pubkey, "{}", account.name);
+            assert_eq!(account.is_mut, meta.is_writable, "{}", account.name);
+            assert_eq!(account.is_signer, meta.is_signer, "{}", account.name);
+        }
+    }
+
+    #[test]
+    fn test_decode_swap() {
+        let keys: Vec<Pubkey> = (0..16).map(|_| Pubkey::new_unique()).collect();
+        let swap_data = SwapData {
+            amount_in: 1_000,
+            minimum_amount_out: 900,
+            deadline_slot: Some(42),
+            output_owner: None,
+        };
+        let instruction = swap(
+            crate::id(),
+            keys[0],
+            keys[1],
+            keys[2],
+            keys[3],
+            keys[4],
+            keys[5],
+            keys[6],
+            keys[7],
+            keys[8],
+            keys[9],
+            keys[10],
+            keys[11],
+            keys[12],
+            keys[13],
+            keys[14],
+            keys[15],
+            None,
+            None,
+            None,
+            None,
+            None,
+            None,
+            None,
+            swap_data.clone(),
+        )
+        .unwrap();
+
+        let parsed = decode(&instruction);
+        assert_eq!(parsed.name, "swap");
+        assert_eq!(
+            parsed.instruction,
+            DecodedInstruction::Swap(SwapInstruction::Swap(swap_data))
+        );
+        assert_eq!(parsed.accounts.len(), instruction.accounts.len());
+        assert_eq!(parsed.accounts[3].name, "swapAuthority");
+        assert_eq!(parsed.accounts[16].name, "tokenProgram");
+        assert_eq!(parsed.accounts[16].pubkey, spl_token::id());
+        assert_accounts_match(&parsed, &instruction);
+        assert!(parsed.remaining_accounts.is_empty());
+    }
+
+    #[test]
+    fn test_decode_admin_and_farm() {
+        let keys: Vec<Pubkey> = (0..7).map(|_| Pubkey::new_unique()).collect();
+        let instruction = pause(crate::id(), keys[0], keys[1], keys[2]).unwrap();
+        let parsed = decode(&instruction);
+        assert_eq!(
+            parsed.instruction,
+            DecodedInstruction::Admin(AdminInstruction::Pause)
+        );
+        let names: Vec<_> = parsed.accounts.iter().map(|a| a.name).collect();
+        assert_eq!(names, vec!["config", "swap", "admin"]);
+        assert_accounts_match(&parsed, &instruction);
+
+        let instruction = farm_claim(
+            crate::id(),
+            keys[0],
+            keys[1],
+            keys[2],
+            keys[3],
+            keys[4],
+            keys[5],
+            keys[6],
+        )
+        .unwrap();
+        let parsed = decode(&instruction);
+        assert_eq!(
+            parsed.instruction,
+            DecodedInstruction::Farm(FarmInstruction::Claim)
+        );
+        assert_accounts_match(&parsed, &instruction);
+    }
+
+    #[test]
+    fn test_decode_remaining_accounts() {
+        let keys: Vec<Pubkey> = (0..4).map(|_| Pubkey::new_unique()).collect();
+        let instruction = pause(crate::id(), keys[0], keys[1], keys[2]).unwrap();
+        let mut accounts: Vec<Pubkey> = instruction.accounts.iter().map(|a| a.pubkey).collect();
+        accounts.push(keys[3]);
+        let parsed = decode_instruction(&instruction.data, &accounts).unwrap();
+        assert_eq!(parsed.accounts.len(), 3);
+        assert_eq!(parsed.remaining_accounts, vec![keys[3]]);
+    }
+
+    #[test]
+    fn test_decode_invalid() {
+        assert_eq!(
+            decode_instruction(&[], &[]),
+            Err(ProgramError::InvalidInstructionData)
+        );
+        assert_eq!(
+            decode_instruction(&[255], &[]),
+            Err(ProgramError::InvalidInstructionData)
+        );
+        // Swap without its args
+        assert!(decode_instruction(&[1], &[]).is_err());
+
+        let keys: Vec<Pubkey> = (0..3).map(|_| Pubkey::new_unique()).collect();
+        let instruction = pause(crate::id(), keys[0], keys[1], keys[2]).unwrap();
+        assert_eq!(
+            decode_instruction(&instruction.data, &keys[..2]),
+            Err(ProgramError::NotEnoughAccountKeys)
+        );
+    }
+}
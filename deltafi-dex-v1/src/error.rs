@@ -236,6 +236,168 @@ pub enum SwapError {
     /// Invalid Serum market mint address
     #[error("InvalidSerumMarketMintAddress")]
     InvalidSerumMarketMintAddress,
+    /// Pool mint decimals do not match the base token decimals
+    #[error("InvalidPoolMintDecimals")]
+    InvalidPoolMintDecimals,
+    /// Pool mint supply changed by a different amount than the pool total supply
+    #[error("InconsistentMintSupply")]
+    InconsistentMintSupply,
+    /// Invalid reward split recipients or percentages
+    #[error("InvalidRewardSplit")]
+    InvalidRewardSplit,
+    /// Pyth and Serum prices diverge more than the pool allows
+    #[error("ExceededPriceDivergence")]
+    ExceededPriceDivergence,
+    /// Exchange rate account does not match the adapter of the pool
+    #[error("InvalidExchangeRateAccount")]
+    InvalidExchangeRateAccount,
+    /// Exchange rate account is not updated in the current epoch
+    #[error("StaleExchangeRate")]
+    StaleExchangeRate,
+    /// Market maker is not on the config allowlist
+    #[error("InvalidMarketMaker")]
+    InvalidMarketMaker,
+    /// Quote is filled after its expiry slot
+    #[error("QuoteExpired")]
+    QuoteExpired,
+    /// Fill amount exceeds the remaining quote amount
+    #[error("ExceededQuoteAmount")]
+    ExceededQuoteAmount,
+    /// Quote price is not better than the pool price
+    #[error("QuoteWorseThanPool")]
+    QuoteWorseThanPool,
+    /// Pool is locked by a flash swap in progress
+    #[error("ReentrancyLocked")]
+    ReentrancyLocked,
+    /// Flash swap is not repaid with its fee
+    #[error("InsufficientFlashRepayment")]
+    InsufficientFlashRepayment,
+    /// Transaction executed after its deadline slot
+    #[error("TransactionExpired")]
+    TransactionExpired,
+    /// Revealed initial price does not match the committed hash
+    #[error("InvalidPriceCommitment")]
+    InvalidPriceCommitment,
+    /// Committed change applied before its timelock is over
+    #[error("TimelockNotExpired")]
+    TimelockNotExpired,
+    /// Swap output sent to a token account not owned by a signer
+    #[error("InvalidDestinationOwner")]
+    InvalidDestinationOwner,
+    /// Pool closed while it is not paused or still has liquidity
+    #[error("PoolNotClosable")]
+    PoolNotClosable,
+    /// Oracle account not registered in the feed registry for the mint
+    #[error("InvalidOracleFeed")]
+    InvalidOracleFeed,
+    /// Swap output owner not passed to the program invoking the swap
+    #[error("InvalidSwapOutputOwner")]
+    InvalidSwapOutputOwner,
+    /// Price cache account not derived from the pool or relayer not allowed to update it
+    #[error("InvalidPriceCache")]
+    InvalidPriceCache,
+    /// LP share price dropped below its highest value
+    #[error("SharePriceBelowFloor")]
+    SharePriceBelowFloor,
+    /// Scratch account not derived from the config and mint or not held by the signer
+    #[error("InvalidScratchAccount")]
+    InvalidScratchAccount,
+    /// Scratch account is held by another signer
+    #[error("ScratchAccountInUse")]
+    ScratchAccountInUse,
+    /// Pool is not at the program address of its config, mints and swap type
+    #[error("InvalidPoolAddress")]
+    InvalidPoolAddress,
+    /// Admin instructions exceeded the max of the epoch and are paused until the guardian co-signs
+    #[error("AdminOpsPaused")]
+    AdminOpsPaused,
+    /// Fee discount account or tiers are invalid
+    #[error("InvalidFeeDiscount")]
+    InvalidFeeDiscount,
+    /// Treasury token account does not belong to the config treasury
+    #[error("InvalidTreasury")]
+    InvalidTreasury,
+    /// Referrer updated again before the cooldown has passed
+    #[error("ReferrerUpdateCooldown")]
+    ReferrerUpdateCooldown,
+    /// Referrer stats do not belong to the config and referrer token account
+    #[error("InvalidReferrerStats")]
+    InvalidReferrerStats,
+    /// Farm reward token is duplicated, out of range or its accounts do not match the farm
+    #[error("InvalidFarmRewardToken")]
+    InvalidFarmRewardToken,
+    /// Farm position is locked until its unlock timestamp
+    #[error("FarmPositionLocked")]
+    FarmPositionLocked,
+    /// Farm lock tier is out of range or the farm user account predates the lock tiers
+    #[error("InvalidFarmLockTier")]
+    InvalidFarmLockTier,
+    /// Farm position receiving a transfer still has a stake or rewards
+    #[error("FarmPositionNotEmpty")]
+    FarmPositionNotEmpty,
+    /// Amplification coefficient of a stable pool is out of range
+    #[error("InvalidAmp")]
+    InvalidAmp,
+    /// Pool is off for the rebalance or within its threshold of the oracle price
+    #[error("RebalanceNotNeeded")]
+    RebalanceNotNeeded,
+    /// Swap price deviates from the circuit breaker reference price by more than the max
+    #[error("CircuitBreakerTripped")]
+    CircuitBreakerTripped,
+    /// Deposit mints the pool tokens above the deposit cap of the pool
+    #[error("DepositCapExceeded")]
+    DepositCapExceeded,
+    /// User has no whitelist entry on the whitelist-gated pool
+    #[error("NotWhitelisted")]
+    NotWhitelisted,
+    /// Whitelist entry is not at the program address of the pool and the user
+    #[error("InvalidWhitelistEntry")]
+    InvalidWhitelistEntry,
+    /// Liquidity lock is not at the program address of the pool and the owner, or the lock
+    /// brings its cliff forward
+    #[error("InvalidLiquidityLock")]
+    InvalidLiquidityLock,
+    /// Pool tokens of the liquidity lock are locked until its cliff
+    #[error("LiquidityLocked")]
+    LiquidityLocked,
+    /// The swap output and the fees paid out of the pool overflow
+    #[error("SwapOutflowOverflow")]
+    SwapOutflowOverflow,
+    /// The admin fee exceeds the trade fee it is taken from
+    #[error("LpFeeUnderflow")]
+    LpFeeUnderflow,
+    /// The withdraw fee exceeds the withdrawn amount
+    #[error("WithdrawFeeUnderflow")]
+    WithdrawFeeUnderflow,
+    /// Temporary wSOL account is not at the program address of the user, or not the native
+    /// SOL side of the wrapped instruction
+    #[error("InvalidNativeSolAccount")]
+    InvalidNativeSolAccount,
+    /// The market price of a MANUAL oracle pool was never pushed or is stale
+    #[error("StaleManualPrice")]
+    StaleManualPrice,
+    /// The pushed market price changes by more than the max change from the previous one
+    #[error("ManualPriceChangeTooLarge")]
+    ManualPriceChangeTooLarge,
+    /// Chainlink feed is not a transmissions account of the store program or has no valid
+    /// latest round
+    #[error("InvalidChainlinkFeed")]
+    InvalidChainlinkFeed,
+    /// Latest round of a chainlink feed is stale
+    #[error("StaleChainlinkPrice")]
+    StaleChainlinkPrice,
+    /// Pyth price update is not a fully verified update account of the receiver program
+    #[error("InvalidPythPriceUpdate")]
+    InvalidPythPriceUpdate,
+    /// Pyth price update is for another feed than the one of the pool
+    #[error("PythFeedIdMismatch")]
+    PythFeedIdMismatch,
+    /// Pool market price is refreshed within the interval or the oracle has no newer price
+    #[error("PriceRefreshNotNeeded")]
+    PriceRefreshNotNeeded,
+    /// Farm user account predates the compound delegates
+    #[error("InvalidCompoundDelegate")]
+    InvalidCompoundDelegate,
 }
 impl From<SwapError> for ProgramError {
     fn from(e: SwapError) -> Self {
@@ -363,6 +525,143 @@ impl PrintProgramError for SwapError {
             SwapError::InvalidSerumMarketMintAddress => {
                 msg!("Error: Invalid Serum Market Mint Address")
             }
+            SwapError::InvalidPoolMintDecimals => {
+                msg!("Error: Pool mint decimals must match the base token decimals")
+            }
+            SwapError::InconsistentMintSupply => {
+                msg!("Error: Pool mint supply change does not match the pool total supply change")
+            }
+            SwapError::InvalidRewardSplit => msg!("Error: Invalid reward split"),
+            SwapError::ExceededPriceDivergence => {
+                msg!("Error: Pyth and Serum prices diverge too much")
+            }
+            SwapError::InvalidExchangeRateAccount => msg!("Error: Invalid exchange rate account"),
+            SwapError::StaleExchangeRate => msg!("Error: Stale exchange rate"),
+            SwapError::InvalidMarketMaker => msg!("Error: Invalid market maker"),
+            SwapError::QuoteExpired => msg!("Error: Quote expired"),
+            SwapError::ExceededQuoteAmount => msg!("Error: Exceeded quote amount"),
+            SwapError::QuoteWorseThanPool => msg!("Error: Quote is worse than the pool price"),
+            SwapError::ReentrancyLocked => msg!("Error: Pool is locked by a flash swap"),
+            SwapError::InsufficientFlashRepayment => {
+                msg!("Error: Flash swap is not repaid with its fee")
+            }
+            SwapError::TransactionExpired => msg!("Error: Transaction expired"),
+            SwapError::InvalidPriceCommitment => {
+                msg!("Error: Revealed price does not match the commitment")
+            }
+            SwapError::TimelockNotExpired => msg!("Error: Timelock has not expired"),
+            SwapError::InvalidDestinationOwner => {
+                msg!("Error: Destination token account is not owned by a signer")
+            }
+            SwapError::PoolNotClosable => {
+                msg!("Error: Pool must be paused and have no liquidity to be closed")
+            }
+            SwapError::InvalidOracleFeed => {
+                msg!("Error: Oracle account is not registered for the mint")
+            }
+            SwapError::InvalidSwapOutputOwner => {
+                msg!("Error: Output owner is not an account of the calling program")
+            }
+            SwapError::InvalidPriceCache => {
+                msg!("Error: Price cache or relayer is not the one of the pool")
+            }
+            SwapError::SharePriceBelowFloor => {
+                msg!("Error: LP share price dropped below its highest value")
+            }
+            SwapError::InvalidScratchAccount => {
+                msg!("Error: Scratch account is not the one of the mint or not held by the signer")
+            }
+            SwapError::ScratchAccountInUse => {
+                msg!("Error: Scratch account is held by another signer")
+            }
+            SwapError::InvalidPoolAddress => {
+                msg!("Error: Pool is not at the program address of its config, mints and swap type")
+            }
+            SwapError::AdminOpsPaused => {
+                msg!("Error: Admin instructions are paused until the guardian co-signs")
+            }
+            SwapError::InvalidFeeDiscount => {
+                msg!("Error: Fee discount account or tiers are invalid")
+            }
+            SwapError::InvalidTreasury => {
+                msg!("Error: Treasury token account does not belong to the config treasury")
+            }
+            SwapError::ReferrerUpdateCooldown => {
+                msg!("Error: Referrer updated again before the cooldown has passed")
+            }
+            SwapError::InvalidReferrerStats => {
+                msg!("Error: Referrer stats do not belong to the config and referrer token account")
+            }
+            SwapError::InvalidFarmRewardToken => {
+                msg!("Error: Farm reward token is duplicated, out of range or its accounts do not match the farm")
+            }
+            SwapError::FarmPositionLocked => {
+                msg!("Error: Farm position is locked until its unlock timestamp")
+            }
+            SwapError::InvalidFarmLockTier => {
+                msg!("Error: Farm lock tier is out of range or the farm user account predates the lock tiers")
+            }
+            SwapError::FarmPositionNotEmpty => {
+                msg!("Error: Farm position receiving a transfer still has a stake or rewards")
+            }
+            SwapError::InvalidAmp => {
+                msg!("Error: Amplification coefficient of a stable pool is out of range")
+            }
+            SwapError::RebalanceNotNeeded => {
+                msg!("Error: Pool rebalance is off or the pool is within the threshold of the oracle price")
+            }
+            SwapError::CircuitBreakerTripped => {
+                msg!("Error: Swap price deviates from the circuit breaker reference price by more than the max")
+            }
+            SwapError::DepositCapExceeded => {
+                msg!("Error: Deposit mints the pool tokens above the deposit cap of the pool")
+            }
+            SwapError::NotWhitelisted => {
+                msg!("Error: User has no whitelist entry on the whitelist-gated pool")
+            }
+            SwapError::InvalidWhitelistEntry => msg!(
+                "Error: Whitelist entry is not at the program address of the pool and the user"
+            ),
+            SwapError::InvalidLiquidityLock => {
+                msg!("Error: Liquidity lock is invalid or brings its cliff forward")
+            }
+            SwapError::LiquidityLocked => {
+                msg!("Error: Pool tokens of the liquidity lock are locked until its cliff")
+            }
+            SwapError::SwapOutflowOverflow => {
+                msg!("Error: Swap output and the fees paid out of the pool overflow")
+            }
+            SwapError::LpFeeUnderflow => {
+                msg!("Error: Admin fee exceeds the trade fee it is taken from")
+            }
+            SwapError::WithdrawFeeUnderflow => {
+                msg!("Error: Withdraw fee exceeds the withdrawn amount")
+            }
+            SwapError::InvalidNativeSolAccount => {
+                msg!("Error: Temporary wSOL account is invalid or not the native SOL side")
+            }
+            SwapError::StaleManualPrice => {
+                msg!("Error: Market price of the manual oracle was never pushed or is stale")
+            }
+            SwapError::ManualPriceChangeTooLarge => {
+                msg!("Error: Pushed market price changes by more than the max change per update")
+            }
+            SwapError::InvalidChainlinkFeed => {
+                msg!("Error: Chainlink feed account is invalid or has no valid latest round")
+            }
+            SwapError::StaleChainlinkPrice => msg!("Error: Stale chainlink price"),
+            SwapError::InvalidPythPriceUpdate => {
+                msg!("Error: Pyth price update account is invalid or not fully verified")
+            }
+            SwapError::PythFeedIdMismatch => {
+                msg!("Error: Pyth price update feed id does not match the pool feed")
+            }
+            SwapError::PriceRefreshNotNeeded => {
+                msg!("Error: Pool market price is recent or the oracle has no newer price")
+            }
+            SwapError::InvalidCompoundDelegate => {
+                msg!("Error: Farm user account predates the compound delegates")
+            }
         }
     }
 }
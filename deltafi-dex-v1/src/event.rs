@@ -0,0 +1,267 @@
+//! Structured events logged with `sol_log_data` for indexers
+//!
+//! Every event is logged as a single data field laid out as
+//! `[EVENT_VERSION, event type, event fields...]`, integers in little endian
+//! and prices as the raw scaled value of `Decimal`.
+
+use arrayref::{array_mut_ref, mut_array_refs};
+use solana_program::{
+    log::sol_log_data,
+    pubkey::{Pubkey, PUBKEY_BYTES},
+};
+
+use crate::{curve::SwapDirection, math::Decimal, state::pack_decimal};
+
+/// Version of the event layouts, bumped on any layout change
+pub const EVENT_VERSION: u8 = 1;
+
+/// Event types, the second byte of an event
+#[repr(u8)]
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub enum EventType {
+    /// Swap of a normal or stable pool
+    Swap = 0,
+    /// Deposit into a pool
+    Deposit = 1,
+    /// Withdrawal from a pool
+    Withdraw = 2,
+    /// Deposit into a farm
+    FarmDeposit = 3,
+    /// Withdrawal from a farm
+    FarmWithdraw = 4,
+    /// Claim of farm rewards
+    FarmClaim = 5,
+    /// Farm rewards compounded into the farm position
+    FarmCompound = 6,
+}
+
+/// Event logged by the program
+pub trait Event {
+    /// Length of the event fields
+    const LEN: usize;
+
+    /// Type of the event
+    fn event_type(&self) -> EventType;
+
+    /// Pack the event fields into the slice
+    fn pack_into_slice(&self, dst: &mut [u8]);
+
+    /// Serialize the event with its version and type header
+    fn to_vec(&self) -> Vec<u8> {
+        let mut data = vec![0u8; 2 + Self::LEN];
+        data[0] = EVENT_VERSION;
+        data[1] = self.event_type() as u8;
+        self.pack_into_slice(&mut data[2..]);
+        data
+    }
+
+    /// Log the event
+    fn emit(&self) {
+        sol_log_data(&[&self.to_vec()]);
+    }
+}
+
+/// Swap event, fees are in the destination token
+#[derive(Clone, Debug, PartialEq)]
+pub struct SwapEvent {
+    /// Swap pubkey
+    pub swap: Pubkey,
+    /// User transfer authority pubkey
+    pub user: Pubkey,
+    /// Swap direction
+    pub direction: SwapDirection,
+    /// Amount of the source token sold
+    pub amount_in: u64,
+    /// Amount of the destination token received by the user
+    pub amount_out: u64,
+    /// Trade fee
+    pub trade_fee: u64,
+    /// Admin part of the trade fee
+    pub admin_fee: u64,
+    /// Market price the swap was priced at
+    pub market_price: Decimal,
+}
+
+const SWAP_EVENT_SIZE: usize = PUBKEY_BYTES * 2 + 1 + 8 * 4 + 16;
+
+impl Event for SwapEvent {
+    const LEN: usize = SWAP_EVENT_SIZE;
+
+    fn event_type(&self) -> EventType {
+        EventType::Swap
+    }
+
+    fn pack_into_slice(&self, dst: &mut [u8]) {
+        let output = array_mut_ref![dst, 0, SWAP_EVENT_SIZE];
+        #[allow(clippy::ptr_offset_with_cast)]
+        let (swap, user, direction, amount_in, amount_out, trade_fee, admin_fee, market_price) =
+            mut_array_refs![output, PUBKEY_BYTES, PUBKEY_BYTES, 1, 8, 8, 8, 8, 16];
+        swap.copy_from_slice(self.swap.as_ref());
+        user.copy_from_slice(self.user.as_ref());
+        direction[0] = self.direction as u8;
+        *amount_in = self.amount_in.to_le_bytes();
+        *amount_out = self.amount_out.to_le_bytes();
+        *trade_fee = self.trade_fee.to_le_bytes();
+        *admin_fee = self.admin_fee.to_le_bytes();
+        pack_decimal(self.market_price, market_price);
+    }
+}
+
+/// Deposit or withdrawal event of a pool, the fees are the trade fees of the swapped part
+/// of single sided liquidity and the withdraw fees
+#[derive(Clone, Debug, PartialEq)]
+pub struct LiquidityEvent {
+    /// `EventType::Deposit` or `EventType::Withdraw`
+    pub event_type: EventType,
+    /// Swap pubkey
+    pub swap: Pubkey,
+    /// User transfer authority pubkey
+    pub user: Pubkey,
+    /// Amount of token a deposited or withdrawn
+    pub token_a_amount: u64,
+    /// Amount of token b deposited or withdrawn
+    pub token_b_amount: u64,
+    /// Amount of pool token minted or burned
+    pub pool_token_amount: u64,
+    /// Fee in token a
+    pub fee_a: u64,
+    /// Fee in token b
+    pub fee_b: u64,
+    /// Admin part of the fee in token a
+    pub admin_fee_a: u64,
+    /// Admin part of the fee in token b
+    pub admin_fee_b: u64,
+    /// Market price of the pool
+    pub market_price: Decimal,
+}
+
+const LIQUIDITY_EVENT_SIZE: usize = PUBKEY_BYTES * 2 + 8 * 7 + 16;
+
+impl Event for LiquidityEvent {
+    const LEN: usize = LIQUIDITY_EVENT_SIZE;
+
+    fn event_type(&self) -> EventType {
+        self.event_type
+    }
+
+    fn pack_into_slice(&self, dst: &mut [u8]) {
+        let output = array_mut_ref![dst, 0, LIQUIDITY_EVENT_SIZE];
+        #[allow(clippy::ptr_offset_with_cast)]
+        let (
+            swap,
+            user,
+            token_a_amount,
+            token_b_amount,
+            pool_token_amount,
+            fee_a,
+            fee_b,
+            admin_fee_a,
+            admin_fee_b,
+            market_price,
+        ) = mut_array_refs![output, PUBKEY_BYTES, PUBKEY_BYTES, 8, 8, 8, 8, 8, 8, 8, 16];
+        swap.copy_from_slice(self.swap.as_ref());
+        user.copy_from_slice(self.user.as_ref());
+        *token_a_amount = self.token_a_amount.to_le_bytes();
+        *token_b_amount = self.token_b_amount.to_le_bytes();
+        *pool_token_amount = self.pool_token_amount.to_le_bytes();
+        *fee_a = self.fee_a.to_le_bytes();
+        *fee_b = self.fee_b.to_le_bytes();
+        *admin_fee_a = self.admin_fee_a.to_le_bytes();
+        *admin_fee_b = self.admin_fee_b.to_le_bytes();
+        pack_decimal(self.market_price, market_price);
+    }
+}
+
+/// Farm deposit, withdrawal, claim or compound event, the amount is the pool token amount
+/// or the claimed reward amount, the pool tokens staked for a compound
+#[derive(Clone, Debug, PartialEq)]
+pub struct FarmEvent {
+    /// `EventType::FarmDeposit`, `EventType::FarmWithdraw`, `EventType::FarmClaim` or
+    /// `EventType::FarmCompound`
+    pub event_type: EventType,
+    /// Farm pool pubkey
+    pub farm_pool: Pubkey,
+    /// Farm user pubkey
+    pub farm_user: Pubkey,
+    /// Amount of the operation
+    pub amount: u64,
+}
+
+const FARM_EVENT_SIZE: usize = PUBKEY_BYTES * 2 + 8;
+
+impl Event for FarmEvent {
+    const LEN: usize = FARM_EVENT_SIZE;
+
+    fn event_type(&self) -> EventType {
+        self.event_type
+    }
+
+    fn pack_into_slice(&self, dst: &mut [u8]) {
+        let output = array_mut_ref![dst, 0, FARM_EVENT_SIZE];
+        #[allow(clippy::ptr_offset_with_cast)]
+        let (farm_pool, farm_user, amount) = mut_array_refs![output, PUBKEY_BYTES, PUBKEY_BYTES, 8];
+        farm_pool.copy_from_slice(self.farm_pool.as_ref());
+        farm_user.copy_from_slice(self.farm_user.as_ref());
+        *amount = self.amount.to_le_bytes();
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    use crate::state::unpack_decimal;
+    use arrayref::{array_ref, array_refs};
+
+    #[test]
+    fn test_swap_event_layout() {
+        let event = SwapEvent {
+            swap: Pubkey::new_unique(),
+            user: Pubkey::new_unique(),
+            direction: SwapDirection::SellQuote,
+            amount_in: 1_000,
+            amount_out: 990,
+            trade_fee: 10,
+            admin_fee: 2,
+            market_price: Decimal::from(3u64),
+        };
+
+        let data = event.to_vec();
+        assert_eq!(data.len(), 2 + SWAP_EVENT_SIZE);
+        assert_eq!(data[0], EVENT_VERSION);
+        assert_eq!(data[1], EventType::Swap as u8);
+
+        let input = array_ref![data, 2, SWAP_EVENT_SIZE];
+        #[allow(clippy::ptr_offset_with_cast)]
+        let (swap, user, direction, amount_in, amount_out, trade_fee, admin_fee, market_price) =
+            array_refs![input, PUBKEY_BYTES, PUBKEY_BYTES, 1, 8, 8, 8, 8, 16];
+        assert_eq!(Pubkey::new_from_array(*swap), event.swap);
+        assert_eq!(Pubkey::new_from_array(*user), event.user);
+        assert_eq!(direction[0], SwapDirection::SellQuote as u8);
+        assert_eq!(u64::from_le_bytes(*amount_in), event.amount_in);
+        assert_eq!(u64::from_le_bytes(*amount_out), event.amount_out);
+        assert_eq!(u64::from_le_bytes(*trade_fee), event.trade_fee);
+        assert_eq!(u64::from_le_bytes(*admin_fee), event.admin_fee);
+        assert_eq!(unpack_decimal(market_price), event.market_price);
+    }
+
+    #[test]
+    fn test_farm_event_layout() {
+        let event = FarmEvent {
+            event_type: EventType::FarmClaim,
+            farm_pool: Pubkey::new_unique(),
+            farm_user: Pubkey::new_unique(),
+            amount: 42,
+        };
+
+        let data = event.to_vec();
+        assert_eq!(data.len(), 2 + FARM_EVENT_SIZE);
+        assert_eq!(data[1], EventType::FarmClaim as u8);
+        assert_eq!(&data[2..2 + PUBKEY_BYTES], event.farm_pool.as_ref());
+        assert_eq!(
+            &data[2 + PUBKEY_BYTES..2 + PUBKEY_BYTES * 2],
+            event.farm_user.as_ref()
+        );
+        assert_eq!(&data[2 + PUBKEY_BYTES * 2..], &42u64.to_le_bytes()[..]);
+    }
+}
@@ -0,0 +1,2185 @@
+//! Interface description of the program in the shank IDL format, so wallets and explorers
+//! can decode the DeltaFi instructions and errors.
+//!
+//! The instructions are tagged with a single `u8` discriminant followed by the packed
+//! little-endian args, without any borsh length prefix or option tag:
+//! - an `optional` arg is left out of the data when not set, it is always the last one or
+//!   followed by optional args only
+//! - the swap `deadlineSlot` is packed as `u64::MAX` in front of an `outputOwner` set without
+//!   deadline
+//! - a `bytes` arg takes the rest of the instruction data
+//!
+//! Accounts listed after the optional ones, like the pools of a route or the accounts of a
+//! flash swap callback, are described in the docs of the instruction.
+
+use crate::{
+    error::SwapError,
+    state::{MAX_REWARD_RECIPIENTS, TRI_POOL_TOKENS},
+};
+use num_traits::FromPrimitive;
+use serde::Serialize;
+
+/// Program IDL
+#[derive(Debug, Serialize)]
+pub struct Idl {
+    /// Crate version
+    pub version: String,
+    /// Program name
+    pub name: String,
+    /// Instructions of the program
+    pub instructions: Vec<IdlInstruction>,
+    /// Custom errors of the program
+    pub errors: Vec<IdlError>,
+    /// IDL metadata
+    pub metadata: IdlMetadata,
+}
+
+/// Instruction of the IDL
+#[derive(Debug, Serialize)]
+pub struct IdlInstruction {
+    /// Instruction name, the variant name prefixed by its instruction set
+    pub name: String,
+    /// Variant of the instruction enum
+    #[serde(skip)]
+    pub variant: &'static str,
+    /// Notes on the accounts not covered by the account list
+    #[serde(skip_serializing_if = "Vec::is_empty")]
+    pub docs: Vec<&'static str>,
+    /// Accounts in order
+    pub accounts: Vec<IdlAccount>,
+    /// Args packed after the discriminant in order
+    pub args: Vec<IdlField>,
+    /// Instruction tag
+    pub discriminant: IdlDiscriminant,
+}
+
+/// Account of an instruction
+#[derive(Debug, Serialize)]
+#[serde(rename_all = "camelCase")]
+pub struct IdlAccount {
+    /// Account name
+    pub name: &'static str,
+    /// Writable account
+    pub is_mut: bool,
+    /// Signer account
+    pub is_signer: bool,
+    /// Account left out when not needed
+    #[serde(skip_serializing_if = "std::ops::Not::not")]
+    pub is_optional: bool,
+}
+
+/// Arg of an instruction
+#[derive(Debug, Serialize)]
+pub struct IdlField {
+    /// Arg name
+    pub name: &'static str,
+    /// Arg type
+    #[serde(rename = "type")]
+    pub ty: IdlType,
+    /// Arg left out of the data when not set
+    #[serde(skip_serializing_if = "std::ops::Not::not")]
+    pub optional: bool,
+}
+
+/// Type of an arg
+#[derive(Debug, Serialize)]
+#[serde(untagged)]
+pub enum IdlType {
+    /// `bool`, `u8`, `u16`, `u64`, `u128`, `publicKey` or `bytes`
+    Primitive(&'static str),
+    /// Fixed size array
+    Array {
+        /// Element type and length
+        array: (Box<IdlType>, usize),
+    },
+}
+
+/// Instruction tag
+#[derive(Debug, Serialize)]
+pub struct IdlDiscriminant {
+    /// Tag type, always `u8`
+    #[serde(rename = "type")]
+    pub ty: &'static str,
+    /// Tag value
+    pub value: u8,
+}
+
+/// Custom error of the program
+#[derive(Debug, Serialize)]
+pub struct IdlError {
+    /// Custom error code
+    pub code: u32,
+    /// Error name
+    pub name: String,
+    /// Error message
+    pub msg: String,
+}
+
+/// IDL metadata
+#[derive(Debug, Serialize)]
+pub struct IdlMetadata {
+    /// Generator of the IDL
+    pub origin: &'static str,
+    /// Program id
+    pub address: String,
+}
+
+impl IdlAccount {
+    fn optional(mut self) -> Self {
+        self.is_optional = true;
+        self
+    }
+}
+
+impl IdlField {
+    fn optional(mut self) -> Self {
+        self.optional = true;
+        self
+    }
+}
+
+fn account(name: &'static str, is_mut: bool, is_signer: bool) -> IdlAccount {
+    IdlAccount {
+        name,
+        is_mut,
+        is_signer,
+        is_optional: false,
+    }
+}
+
+fn readonly(name: &'static str) -> IdlAccount {
+    account(name, false, false)
+}
+
+fn writable(name: &'static str) -> IdlAccount {
+    account(name, true, false)
+}
+
+fn signer(name: &'static str) -> IdlAccount {
+    account(name, false, true)
+}
+
+fn writable_signer(name: &'static str) -> IdlAccount {
+    account(name, true, true)
+}
+
+fn arg(name: &'static str, ty: &'static str) -> IdlField {
+    IdlField {
+        name,
+        ty: IdlType::Primitive(ty),
+        optional: false,
+    }
+}
+
+fn array_arg(name: &'static str, ty: &'static str, len: usize) -> IdlField {
+    IdlField {
+        name,
+        ty: IdlType::Array {
+            array: (Box::new(IdlType::Primitive(ty)), len),
+        },
+        optional: false,
+    }
+}
+
+fn instruction(
+    prefix: &str,
+    variant: &'static str,
+    discriminant: u8,
+    accounts: Vec<IdlAccount>,
+    args: Vec<IdlField>,
+) -> IdlInstruction {
+    let name = format!("{}{}", prefix, variant);
+    let mut chars = name.chars();
+    let name = match chars.next() {
+        Some(first) => first.to_lowercase().chain(chars).collect(),
+        None => name,
+    };
+    IdlInstruction {
+        name,
+        variant,
+        docs: vec![],
+        accounts,
+        args,
+        discriminant: IdlDiscriminant {
+            ty: "u8",
+            value: discriminant,
+        },
+    }
+}
+
+impl IdlInstruction {
+    fn docs(mut self, docs: &[&'static str]) -> Self {
+        self.docs = docs.to_vec();
+        self
+    }
+}
+
+fn swap_args() -> Vec<IdlField> {
+    vec![
+        arg("amountIn", "u64"),
+        arg("minimumAmountOut", "u64"),
+        arg("deadlineSlot", "u64").optional(),
+        arg("outputOwner", "publicKey").optional(),
+    ]
+}
+
+fn deposit_args() -> Vec<IdlField> {
+    vec![
+        arg("tokenAAmount", "u64"),
+        arg("tokenBAmount", "u64"),
+        arg("minMintAmount", "u64"),
+        arg("deadlineSlot", "u64").optional(),
+    ]
+}
+
+fn withdraw_args() -> Vec<IdlField> {
+    vec![
+        arg("poolTokenAmount", "u64"),
+        arg("minimumTokenAAmount", "u64"),
+        arg("minimumTokenBAmount", "u64"),
+        arg("deadlineSlot", "u64").optional(),
+    ]
+}
+
+fn deposit_one_args() -> Vec<IdlField> {
+    vec![
+        arg("amountIn", "u64"),
+        arg("minMintAmount", "u64"),
+        arg("deadlineSlot", "u64").optional(),
+    ]
+}
+
+fn withdraw_one_args() -> Vec<IdlField> {
+    vec![
+        arg("poolTokenAmount", "u64"),
+        arg("minimumAmountOut", "u64"),
+        arg("tokenSide", "u8"),
+        arg("deadlineSlot", "u64").optional(),
+    ]
+}
+
+fn initialize_args() -> Vec<IdlField> {
+    vec![
+        arg("nonce", "u8"),
+        arg("slope", "u64"),
+        arg("midPrice", "u128"),
+        arg("tokenADecimals", "u8"),
+        arg("tokenBDecimals", "u8"),
+        arg("tokenAAmount", "u64"),
+        arg("tokenBAmount", "u64"),
+        arg("oraclePriorityFlags", "u8"),
+        arg("quoteIsTokenA", "bool"),
+    ]
+}
+
+fn stable_initialize_args() -> Vec<IdlField> {
+    vec![
+        arg("nonce", "u8"),
+        arg("slope", "u64"),
+        arg("tokenADecimals", "u8"),
+        arg("tokenBDecimals", "u8"),
+        arg("tokenAAmount", "u64"),
+        arg("tokenBAmount", "u64"),
+    ]
+}
+
+fn fees_args() -> Vec<IdlField> {
+    vec![
+        arg("feesIsInitialized", "bool"),
+        arg("adminTradeFeeNumerator", "u64"),
+        arg("adminTradeFeeDenominator", "u64"),
+        arg("adminWithdrawFeeNumerator", "u64"),
+        arg("adminWithdrawFeeDenominator", "u64"),
+        arg("tradeFeeNumerator", "u64"),
+        arg("tradeFeeDenominator", "u64"),
+        arg("withdrawFeeNumerator", "u64"),
+        arg("withdrawFeeDenominator", "u64"),
+        arg("flashFeeNumerator", "u64"),
+        arg("flashFeeDenominator", "u64"),
+        arg("dynamicFeeFactor", "u8"),
+        arg("maxDynamicFeeMultiplier", "u8"),
+        arg("treasuryFeeNumerator", "u64"),
+        arg("treasuryFeeDenominator", "u64"),
+    ]
+}
+
+fn fee_discount_tiers_args() -> Vec<IdlField> {
+    vec![
+        arg("tier0MinStakedAmount", "u64"),
+        arg("tier0DiscountBps", "u16"),
+        arg("tier1MinStakedAmount", "u64"),
+        arg("tier1DiscountBps", "u16"),
+        arg("tier2MinStakedAmount", "u64"),
+        arg("tier2DiscountBps", "u16"),
+        arg("tier3MinStakedAmount", "u64"),
+        arg("tier3DiscountBps", "u16"),
+    ]
+}
+
+fn rewards_args() -> Vec<IdlField> {
+    vec![
+        arg("rewardsIsInitialized", "bool"),
+        arg("decimals", "u8"),
+        arg("referralFeeBps", "u16"),
+        array_arg("reserved", "u8", 5),
+        arg("tradeRewardNumerator", "u64"),
+        arg("tradeRewardDenominator", "u64"),
+        arg("tradeRewardCap", "u64"),
+    ]
+}
+
+fn pool_admin_accounts(admin: &'static str) -> Vec<IdlAccount> {
+    vec![readonly("config"), writable("swap"), signer(admin)]
+}
+
+fn initialize_accounts() -> Vec<IdlAccount> {
+    vec![
+        readonly("config"),
+        writable("swap"),
+        readonly("swapAuthority"),
+        readonly("adminFeeA"),
+        readonly("adminFeeB"),
+        readonly("tokenA"),
+        readonly("tokenB"),
+        writable("poolMint"),
+        writable("destination"),
+        readonly("pythProductA"),
+        readonly("pythPriceA"),
+        readonly("pythProductB"),
+        readonly("pythPriceB"),
+        signer("admin"),
+        readonly("serumMarket"),
+        readonly("serumBids"),
+        readonly("serumAsks"),
+        readonly("clock"),
+        readonly("rent"),
+        readonly("tokenProgram"),
+    ]
+}
+
+fn stable_initialize_accounts() -> Vec<IdlAccount> {
+    vec![
+        readonly("config"),
+        writable("swap"),
+        readonly("swapAuthority"),
+        readonly("adminFeeA"),
+        readonly("adminFeeB"),
+        readonly("tokenA"),
+        readonly("tokenB"),
+        writable("poolMint"),
+        writable("destination"),
+        signer("admin"),
+        readonly("rent"),
+        readonly("tokenProgram"),
+    ]
+}
+
+/// Accounts of the `InitializeV2` instructions, the swap is created by the program at its
+/// program address and paid by the admin, with the system program following the token program
+fn initialize_v2_accounts(accounts: Vec<IdlAccount>) -> Vec<IdlAccount> {
+    accounts
+        .into_iter()
+        .flat_map(|account| match account.name {
+            "swap" => vec![writable("swap")],
+            "admin" => vec![writable_signer("admin")],
+            "tokenProgram" => vec![account, readonly("systemProgram")],
+            _ => vec![account],
+        })
+        .collect()
+}
+
+fn admin_instructions() -> Vec<IdlInstruction> {
+    let ix = |variant, discriminant, accounts, args| {
+        instruction("Admin", variant, discriminant, accounts, args)
+    };
+    vec![
+        ix(
+            "Initialize",
+            100,
+            vec![
+                writable("config"),
+                readonly("marketAuthority"),
+                readonly("deltafiMint"),
+                signer("admin"),
+                readonly("rent"),
+                readonly("tokenProgram"),
+                readonly("pythProgram"),
+                readonly("deltafiToken"),
+            ],
+            fees_args().into_iter().chain(rewards_args()).collect(),
+        ),
+        ix("Pause", 101, pool_admin_accounts("admin"), vec![]),
+        ix("Unpause", 102, pool_admin_accounts("admin"), vec![]),
+        ix(
+            "SetFeeAccount",
+            103,
+            vec![
+                readonly("config"),
+                writable("swap"),
+                readonly("swapAuthority"),
+                signer("admin"),
+                readonly("newFeeAccount"),
+                readonly("tokenProgram"),
+            ],
+            vec![],
+        ),
+        ix(
+            "CommitNewAdmin",
+            104,
+            vec![
+                writable("config"),
+                signer("admin"),
+                writable("deltafiMint"),
+                readonly("tokenProgram"),
+            ],
+            vec![arg("newAdminKey", "publicKey")],
+        ),
+        ix("SetNewFees", 105, pool_admin_accounts("admin"), fees_args()),
+        ix(
+            "SetNewRewards",
+            106,
+            pool_admin_accounts("admin"),
+            rewards_args(),
+        ),
+        ix(
+            "SetFarmRewards",
+            107,
+            vec![readonly("config"), writable("farmPool"), signer("admin")],
+            vec![arg("aprNumerator", "u64"), arg("aprDenominator", "u64")],
+        ),
+        ix(
+            "SetSlope",
+            108,
+            pool_admin_accounts("admin"),
+            vec![arg("slope", "u64")],
+        ),
+        ix(
+            "SetDecimals",
+            109,
+            pool_admin_accounts("admin"),
+            vec![arg("baseDecimals", "u8"), arg("quoteDecimals", "u8")],
+        ),
+        ix(
+            "SetSwapLimit",
+            110,
+            pool_admin_accounts("admin"),
+            vec![arg("swapOutLimitPercentage", "u8")],
+        ),
+        ix(
+            "SetPriceJumpPolicy",
+            111,
+            pool_admin_accounts("admin"),
+            vec![arg("priceJumpPolicy", "u8")],
+        ),
+        ix(
+            "SetMaxPriceDivergence",
+            112,
+            pool_admin_accounts("admin"),
+            vec![arg("maxPriceDivergence", "u16")],
+        ),
+        ix(
+            "SetOrderbookProgram",
+            113,
+            vec![
+                writable("config"),
+                signer("admin"),
+                readonly("orderbookProgram"),
+            ],
+            vec![],
+        ),
+        ix(
+            "SetExchangeRateAdapter",
+            114,
+            vec![
+                readonly("config"),
+                writable("swap"),
+                signer("admin"),
+                readonly("exchangeRate").optional(),
+            ],
+            vec![arg("adapterType", "u8")],
+        ),
+        ix(
+            "SetOracleParams",
+            115,
+            pool_admin_accounts("admin"),
+            vec![
+                arg("staleSlots", "u16"),
+                arg("maxConfidenceBps", "u16"),
+                arg("minPublishers", "u8"),
+            ],
+        ),
+        ix(
+            "SetMarketMaker",
+            116,
+            vec![writable("config"), signer("admin"), readonly("marketMaker")],
+            vec![arg("index", "u8")],
+        ),
+        ix(
+            "RotateFeeAccount",
+            117,
+            vec![
+                readonly("config"),
+                writable("swap"),
+                readonly("swapAuthority"),
+                signer("admin"),
+                writable("feeAccount"),
+                writable("newFeeAccount"),
+                writable("lamportsDestination"),
+                readonly("tokenProgram"),
+            ],
+            vec![],
+        ),
+        ix(
+            "CommitInitialPrice",
+            118,
+            vec![
+                readonly("config"),
+                writable("priceCommitment"),
+                readonly("swap"),
+                signer("admin"),
+                readonly("clock"),
+                readonly("rent"),
+            ],
+            vec![array_arg("priceHash", "u8", 32)],
+        ),
+        ix(
+            "CommitPeggedPrice",
+            119,
+            vec![
+                readonly("config"),
+                writable("swap"),
+                signer("admin"),
+                readonly("clock"),
+            ],
+            vec![arg("peggedPrice", "u128")],
+        ),
+        ix(
+            "ApplyPeggedPrice",
+            120,
+            vec![
+                readonly("config"),
+                writable("swap"),
+                signer("admin"),
+                readonly("clock"),
+            ],
+            vec![],
+        ),
+        ix(
+            "SetAdminRole",
+            121,
+            vec![writable("config"), signer("admin"), readonly("delegate")],
+            vec![arg("role", "u8")],
+        ),
+        ix(
+            "PauseAll",
+            122,
+            vec![readonly("config"), signer("admin")],
+            vec![],
+        )
+        .docs(&["Followed by the writable token swap accounts of the pools to pause"]),
+        ix(
+            "SetStrictDestinationOwner",
+            123,
+            pool_admin_accounts("admin"),
+            vec![arg("strictDestinationOwner", "bool")],
+        ),
+        ix(
+            "EnableFarmUserRegistry",
+            124,
+            vec![readonly("config"), writable("farmPool"), signer("admin")],
+            vec![],
+        ),
+        ix(
+            "SetPauseFlags",
+            125,
+            pool_admin_accounts("admin"),
+            vec![arg("pauseFlags", "u8")],
+        ),
+        ix(
+            "ClaimProtocolFees",
+            126,
+            vec![
+                readonly("config"),
+                writable("swap"),
+                readonly("swapAuthority"),
+                signer("admin"),
+                writable("tokenA"),
+                writable("tokenB"),
+                writable("adminFeeA"),
+                writable("adminFeeB"),
+                readonly("tokenProgram"),
+            ],
+            vec![],
+        ),
+        ix(
+            "SetProtocolFee",
+            127,
+            pool_admin_accounts("admin"),
+            vec![arg("numerator", "u64"), arg("denominator", "u64")],
+        ),
+        ix(
+            "SetPoolAuthority",
+            128,
+            vec![
+                readonly("config"),
+                readonly("swap"),
+                readonly("swapAuthority"),
+                signer("admin"),
+                writable("account"),
+                readonly("newAuthority"),
+                readonly("tokenProgram"),
+            ],
+            vec![arg("authorityType", "u8")],
+        ),
+        ix(
+            "SetMarketAuthority",
+            129,
+            vec![
+                readonly("config"),
+                readonly("marketAuthority"),
+                signer("admin"),
+                writable("account"),
+                readonly("newAuthority"),
+                readonly("tokenProgram"),
+            ],
+            vec![arg("authorityType", "u8")],
+        ),
+        ix(
+            "ClosePool",
+            130,
+            vec![
+                readonly("config"),
+                writable("swap"),
+                readonly("swapAuthority"),
+                writable_signer("admin"),
+                writable("poolMint"),
+                writable("tokenA"),
+                writable("tokenB"),
+                writable("destinationA"),
+                writable("destinationB"),
+                readonly("tokenProgram"),
+            ],
+            vec![],
+        ),
+        ix(
+            "SetOracleFeed",
+            131,
+            vec![
+                readonly("config"),
+                writable("oracleFeed"),
+                readonly("mint"),
+                readonly("pythPrice"),
+                readonly("serumMarket"),
+                writable_signer("admin"),
+                readonly("rent"),
+                readonly("systemProgram"),
+            ],
+            vec![],
+        ),
+        ix(
+            "EnableOracleFeedRegistry",
+            132,
+            vec![writable("config"), signer("admin")],
+            vec![],
+        ),
+        ix(
+            "SetPriceCache",
+            133,
+            vec![
+                readonly("config"),
+                readonly("swap"),
+                writable("priceCache"),
+                readonly("relayer"),
+                writable_signer("admin"),
+                readonly("rent"),
+                readonly("systemProgram"),
+            ],
+            vec![],
+        ),
+        ix(
+            "CreateScratchAccount",
+            134,
+            vec![
+                readonly("config"),
+                writable("scratchAccount"),
+                writable("scratchToken"),
+                readonly("mint"),
+                writable_signer("admin"),
+                readonly("rent"),
+                readonly("systemProgram"),
+                readonly("tokenProgram"),
+            ],
+            vec![arg("index", "u8")],
+        ),
+        ix(
+            "SetAdminOpsLimit",
+            135,
+            vec![writable("config"), signer("guardian")],
+            vec![arg("maxAdminOpsPerEpoch", "u16")],
+        )
+        .docs(&["While the max is set, the admin instructions take the config writable"]),
+        ix(
+            "SetFeeDiscountTiers",
+            136,
+            vec![
+                readonly("config"),
+                writable("feeDiscountTiers"),
+                writable_signer("admin"),
+                readonly("rent"),
+                readonly("systemProgram"),
+            ],
+            fee_discount_tiers_args(),
+        ),
+        ix(
+            "SetProtocolFeeSwitch",
+            137,
+            vec![writable("config"), signer("admin"), readonly("treasury")],
+            vec![arg("isProtocolFeeEnabled", "bool")],
+        ),
+        ix(
+            "SyncPoolFromConfig",
+            138,
+            vec![readonly("config"), signer("admin")],
+            vec![arg("includePinned", "bool")],
+        )
+        .docs(&["Followed by the writable token swap accounts of the pools to sync"]),
+        ix(
+            "SetConfigFees",
+            139,
+            vec![writable("config"), signer("admin")],
+            fees_args().into_iter().chain(rewards_args()).collect(),
+        ),
+        ix(
+            "BatchSetFees",
+            140,
+            vec![readonly("config"), signer("admin")],
+            fees_args(),
+        )
+        .docs(&["Followed by the writable token swap accounts of the pools to update"]),
+        ix(
+            "BatchPause",
+            141,
+            vec![readonly("config"), signer("admin")],
+            vec![],
+        )
+        .docs(&["Followed by the writable token swap accounts of the pools to pause"]),
+        ix(
+            "AddFarmRewardToken",
+            142,
+            vec![
+                readonly("config"),
+                writable("farmPool"),
+                writable("farmRewardTokens"),
+                readonly("vault"),
+                writable_signer("admin"),
+                readonly("rent"),
+                readonly("systemProgram"),
+                readonly("tokenProgram"),
+            ],
+            vec![arg("rewardPerSlot", "u64")],
+        ),
+        ix(
+            "SetFarmRewardRate",
+            143,
+            vec![
+                readonly("config"),
+                readonly("farmPool"),
+                writable("farmRewardTokens"),
+                signer("admin"),
+            ],
+            vec![arg("index", "u8"), arg("rewardPerSlot", "u64")],
+        ),
+        ix(
+            "SetFarmEmissionRate",
+            144,
+            vec![readonly("config"), writable("farmPool"), signer("admin")],
+            vec![arg("rewardPerSlot", "u64")],
+        ),
+        ix(
+            "FundStakingRewards",
+            145,
+            vec![
+                readonly("config"),
+                readonly("farmPool"),
+                readonly("marketAuthority"),
+                writable("rewardReserve"),
+                writable("source"),
+                signer("admin"),
+                readonly("tokenProgram"),
+            ],
+            vec![arg("amount", "u64")],
+        ),
+        ix(
+            "SetRebalanceThreshold",
+            146,
+            pool_admin_accounts("admin"),
+            vec![arg("rebalanceThresholdBps", "u16")],
+        )
+        .docs(&["0 turns the permissionless rebalance of the pool off"]),
+        ix(
+            "SetCircuitBreaker",
+            147,
+            pool_admin_accounts("admin"),
+            vec![arg("maxDeviationBps", "u16"), arg("windowSlots", "u64")],
+        )
+        .docs(&["Resuming the swaps with SetPauseFlags clears the trip"]),
+        ix(
+            "SetDepositCap",
+            148,
+            pool_admin_accounts("admin"),
+            vec![arg("maxTotalSupply", "u64")],
+        )
+        .docs(&["0 removes the cap on the pool token supply"]),
+        ix(
+            "SetWhitelistEnabled",
+            149,
+            pool_admin_accounts("admin"),
+            vec![arg("whitelistEnabled", "bool")],
+        )
+        .docs(&["Swaps and deposits of a whitelist-gated pool require a whitelist entry"]),
+        ix(
+            "AddWhitelistEntry",
+            150,
+            vec![
+                readonly("config"),
+                readonly("swap"),
+                writable("whitelistEntry"),
+                readonly("user"),
+                writable_signer("admin"),
+                readonly("rent"),
+                readonly("systemProgram"),
+            ],
+            vec![],
+        ),
+        ix(
+            "RemoveWhitelistEntry",
+            151,
+            vec![
+                readonly("config"),
+                readonly("swap"),
+                writable("whitelistEntry"),
+                writable_signer("admin"),
+            ],
+            vec![],
+        ),
+        ix(
+            "MigrateSwapInfo",
+            152,
+            vec![
+                readonly("config"),
+                writable("swap"),
+                writable_signer("admin"),
+                readonly("rent"),
+                readonly("systemProgram"),
+            ],
+            vec![],
+        )
+        .docs(&["Reallocates a legacy pool to the current length, the admin pays the rent"]),
+        ix(
+            "MigrateConfigInfo",
+            153,
+            vec![
+                writable("config"),
+                writable_signer("admin"),
+                readonly("rent"),
+                readonly("systemProgram"),
+            ],
+            vec![],
+        )
+        .docs(&["Reallocates a legacy market config to the current length, migrated first"]),
+        ix(
+            "MigrateFarmInfo",
+            154,
+            vec![
+                readonly("config"),
+                writable("farmPool"),
+                writable_signer("admin"),
+                readonly("rent"),
+                readonly("systemProgram"),
+            ],
+            vec![],
+        )
+        .docs(&["Reallocates a legacy farm pool to the current length, the admin pays the rent"]),
+        ix("SetOracleAccounts", 155, oracle_accounts("admin"), vec![]).docs(&[
+            "Replaces the pyth price accounts and the serum market of a normal pool, checked as on initialization",
+        ]),
+        ix(
+            "SetOraclePriority",
+            156,
+            oracle_accounts("admin"),
+            vec![arg("oraclePriorityFlags", "u8")],
+        )
+        .docs(&[
+            "Sets the oracle priority flags of a normal pool with the oracle accounts of the new priority",
+        ]),
+        ix(
+            "SetPriceSetter",
+            157,
+            vec![
+                readonly("config"),
+                writable("swap"),
+                signer("admin"),
+                readonly("priceSetter"),
+            ],
+            vec![arg("maxChangeBps", "u16"), arg("staleSlots", "u64")],
+        )
+        .docs(&[
+            "Sets the price setter of a MANUAL oracle pool, a new price setter clears the pushed price",
+        ]),
+        ix(
+            "SetPriceEmaWindow",
+            158,
+            pool_admin_accounts("admin"),
+            vec![arg("windowSlots", "u64")],
+        )
+        .docs(&["0 reprices the pool at the oracle price rather than its moving average"]),
+        ix(
+            "SetSkimSurplus",
+            159,
+            pool_admin_accounts("admin"),
+            vec![arg("skimSurplus", "bool")],
+        )
+        .docs(&[
+            "The swaps transfer the surplus of the pool destination token account to its admin fee account",
+        ]),
+    ]
+}
+
+fn oracle_accounts(admin: &'static str) -> Vec<IdlAccount> {
+    let mut accounts = pool_admin_accounts(admin);
+    accounts.extend(vec![
+        readonly("pythProductA"),
+        readonly("pythPriceA"),
+        readonly("pythProductB"),
+        readonly("pythPriceB"),
+        readonly("serumMarket"),
+        readonly("serumBids"),
+        readonly("serumAsks"),
+        readonly("oracleFeedA").optional(),
+        readonly("oracleFeedB").optional(),
+    ]);
+    accounts
+}
+
+fn swap_v2_accounts() -> Vec<IdlAccount> {
+    vec![
+        readonly("config"),
+        writable("swap"),
+        readonly("marketAuthority"),
+        readonly("swapAuthority"),
+        signer("userTransferAuthority"),
+        writable("source"),
+        writable("swapSource"),
+        writable("swapDestination"),
+        writable("destination"),
+        writable("rewardToken"),
+        writable("sourceRewardToken"),
+        writable("adminFeeDestination"),
+        readonly("pythPriceA"),
+        readonly("pythPriceB"),
+        readonly("serumMarket"),
+        readonly("serumBids"),
+        readonly("serumAsks"),
+        readonly("tokenProgram"),
+        readonly("priceCache").optional(),
+        readonly("feeDiscount").optional(),
+        readonly("feeDiscountTiers").optional(),
+        writable("treasuryToken").optional(),
+        readonly("instructions").optional(),
+        readonly("userReferrerData").optional(),
+        writable("referrerToken").optional(),
+        writable("referrerFeeToken").optional(),
+        writable("referrerStats").optional(),
+    ]
+}
+
+/// Accounts of a native SOL instruction, the temporary wSOL account funded by the user
+/// transfer authority is followed by the accounts of the wrapped swap or deposit
+fn with_native_sol(accounts: Vec<IdlAccount>) -> Vec<IdlAccount> {
+    vec![
+        writable("nativeSol"),
+        readonly("nativeMint"),
+        readonly("rent"),
+        readonly("systemProgram"),
+        readonly("nativeSolTokenProgram"),
+    ]
+    .into_iter()
+    .chain(accounts.into_iter().map(|account| {
+        if account.name == "userTransferAuthority" {
+            writable_signer(account.name)
+        } else {
+            account
+        }
+    }))
+    .collect()
+}
+
+fn swap_instructions() -> Vec<IdlInstruction> {
+    let ix = |variant, discriminant, accounts, args| {
+        instruction("", variant, discriminant, accounts, args)
+    };
+    vec![
+        ix(
+            "Initialize",
+            0,
+            initialize_accounts()
+                .into_iter()
+                .chain(vec![
+                    readonly("oracleFeedA").optional(),
+                    readonly("oracleFeedB").optional(),
+                ])
+                .collect(),
+            initialize_args(),
+        ),
+        ix(
+            "Swap",
+            1,
+            vec![
+                readonly("config"),
+                writable("swap"),
+                readonly("marketAuthority"),
+                readonly("swapAuthority"),
+                signer("userTransferAuthority"),
+                writable("source"),
+                writable("swapSource"),
+                readonly("sourceMint"),
+                writable("swapDestination"),
+                writable("destination"),
+                readonly("destinationMint"),
+                writable("rewardToken"),
+                writable("sourceRewardToken"),
+                writable("adminFeeDestination"),
+                readonly("pythPriceA"),
+                readonly("pythPriceB"),
+                readonly("tokenProgram"),
+                readonly("priceCache").optional(),
+                readonly("feeDiscount").optional(),
+                readonly("feeDiscountTiers").optional(),
+                writable("treasuryToken").optional(),
+                readonly("instructions").optional(),
+                readonly("userReferrerData").optional(),
+                writable("referrerToken").optional(),
+                writable("referrerStats").optional(),
+            ],
+            swap_args(),
+        ),
+        ix(
+            "Deposit",
+            2,
+            vec![
+                readonly("swap"),
+                readonly("swapAuthority"),
+                signer("userTransferAuthority"),
+                writable("sourceA"),
+                writable("sourceB"),
+                writable("tokenA"),
+                writable("tokenB"),
+                writable("poolMint"),
+                writable("destination"),
+                readonly("pythPriceA"),
+                readonly("pythPriceB"),
+                readonly("clock"),
+                readonly("tokenProgram"),
+            ],
+            deposit_args(),
+        ),
+        ix(
+            "Withdraw",
+            3,
+            vec![
+                readonly("swap"),
+                readonly("swapAuthority"),
+                signer("userTransferAuthority"),
+                writable("poolMint"),
+                writable("source"),
+                writable("tokenA"),
+                writable("tokenB"),
+                writable("destinationA"),
+                writable("destinationB"),
+                writable("adminFeeA"),
+                writable("adminFeeB"),
+                readonly("pythPriceA"),
+                readonly("pythPriceB"),
+                readonly("clock"),
+                readonly("tokenProgram"),
+            ],
+            withdraw_args(),
+        ),
+        ix(
+            "SetReferrer",
+            4,
+            vec![
+                readonly("config"),
+                signer("owner"),
+                writable("userReferrerData"),
+                readonly("referrerToken"),
+                readonly("rent"),
+                readonly("tokenProgram"),
+            ],
+            vec![],
+        ),
+        ix(
+            "SwapV2",
+            5,
+            swap_v2_accounts(),
+            swap_args(),
+        ),
+        ix(
+            "DepositOne",
+            6,
+            vec![
+                readonly("config"),
+                writable("swap"),
+                readonly("swapAuthority"),
+                signer("userTransferAuthority"),
+                writable("source"),
+                writable("tokenA"),
+                writable("tokenB"),
+                writable("adminFeeDestination"),
+                writable("poolMint"),
+                writable("destination"),
+                readonly("pythPriceA"),
+                readonly("pythPriceB"),
+                readonly("serumMarket"),
+                readonly("serumBids"),
+                readonly("serumAsks"),
+                readonly("tokenProgram"),
+            ],
+            deposit_one_args(),
+        ),
+        ix(
+            "WithdrawOne",
+            7,
+            vec![
+                readonly("config"),
+                writable("swap"),
+                readonly("swapAuthority"),
+                signer("userTransferAuthority"),
+                writable("poolMint"),
+                writable("source"),
+                writable("tokenA"),
+                writable("tokenB"),
+                writable("destination"),
+                writable("adminFeeDestination"),
+                readonly("pythPriceA"),
+                readonly("pythPriceB"),
+                readonly("serumMarket"),
+                readonly("serumBids"),
+                readonly("serumAsks"),
+                readonly("tokenProgram"),
+            ],
+            withdraw_one_args(),
+        ),
+        ix(
+            "PostQuote",
+            8,
+            vec![
+                readonly("config"),
+                readonly("swap"),
+                writable("quote"),
+                signer("marketMaker"),
+                readonly("inventoryA"),
+                readonly("inventoryB"),
+                readonly("clock"),
+                readonly("rent"),
+                readonly("tokenProgram"),
+            ],
+            vec![
+                arg("swapDirection", "u8"),
+                arg("price", "u128"),
+                arg("maxAmountIn", "u64"),
+                arg("expirySlot", "u64"),
+            ],
+        ),
+        ix(
+            "FillQuote",
+            9,
+            vec![
+                readonly("config"),
+                readonly("swap"),
+                readonly("marketAuthority"),
+                readonly("swapAuthority"),
+                writable("quote"),
+                signer("userTransferAuthority"),
+                writable("source"),
+                writable("destination"),
+                writable("inventorySource"),
+                writable("inventoryDestination"),
+                writable("adminFeeDestination"),
+                writable("rewardToken"),
+                writable("sourceRewardToken"),
+                readonly("clock"),
+                readonly("tokenProgram"),
+            ],
+            swap_args(),
+        ),
+        ix(
+            "FlashSwap",
+            17,
+            vec![
+                readonly("config"),
+                writable("swap"),
+                readonly("swapAuthority"),
+                writable("tokenA"),
+                writable("tokenB"),
+                writable("destinationA"),
+                writable("destinationB"),
+                writable("adminFeeA"),
+                writable("adminFeeB"),
+                readonly("tokenProgram"),
+                writable("treasuryTokenA").optional(),
+                writable("treasuryTokenB").optional(),
+                readonly("callbackProgram"),
+            ],
+            vec![
+                arg("amountA", "u64"),
+                arg("amountB", "u64"),
+                arg("deadlineSlot", "u64"),
+                arg("callbackData", "bytes").optional(),
+            ],
+        )
+        .docs(&[
+            "The deadline slot is u64::MAX for no deadline",
+            "Followed by the accounts passed to the callback program",
+        ]),
+        ix(
+            "RevealInitialize",
+            18,
+            initialize_accounts()
+                .into_iter()
+                .map(|account| match account.name {
+                    "admin" => writable_signer("admin"),
+                    _ => account,
+                })
+                .chain(vec![
+                    writable("priceCommitment"),
+                    readonly("oracleFeedA").optional(),
+                    readonly("oracleFeedB").optional(),
+                ])
+                .collect(),
+            initialize_args()
+                .into_iter()
+                .chain(vec![array_arg("salt", "u8", 32)])
+                .collect(),
+        ),
+        ix(
+            "InitializeV2",
+            35,
+            initialize_v2_accounts(initialize_accounts())
+                .into_iter()
+                .chain(vec![
+                    readonly("oracleFeedA").optional(),
+                    readonly("oracleFeedB").optional(),
+                ])
+                .collect(),
+            initialize_args(),
+        ),
+        ix(
+            "GetQuote",
+            31,
+            vec![
+                readonly("config"),
+                readonly("swap"),
+                readonly("pythPriceA").optional(),
+                readonly("pythPriceB").optional(),
+                readonly("serumMarket").optional(),
+                readonly("serumBids").optional(),
+                readonly("serumAsks").optional(),
+            ],
+            vec![arg("amountIn", "u64"), arg("swapDirection", "u8")],
+        )
+        .docs(&["Stable pools with an exchange rate adapter take the exchange rate account instead of the price accounts"]),
+        ix(
+            "UpdatePriceCache",
+            32,
+            vec![
+                readonly("swap"),
+                writable("priceCache"),
+                signer("relayer"),
+                readonly("pythPriceA"),
+                readonly("pythPriceB"),
+            ],
+            vec![],
+        ),
+        ix(
+            "AcquireScratch",
+            33,
+            vec![
+                writable("scratchAccount"),
+                writable("scratchToken"),
+                signer("holder"),
+                readonly("tokenProgram"),
+            ],
+            vec![],
+        )
+        .docs(&["Must be released with ReleaseScratch in the same transaction"]),
+        ix(
+            "ReleaseScratch",
+            34,
+            vec![
+                writable("scratchAccount"),
+                writable("scratchToken"),
+                signer("holder"),
+                writable("destination"),
+                readonly("tokenProgram"),
+            ],
+            vec![],
+        ),
+        ix(
+            "StakeFeeDiscount",
+            36,
+            vec![
+                readonly("config"),
+                writable("feeDiscount"),
+                writable("feeDiscountToken"),
+                writable("source"),
+                writable_signer("owner"),
+                readonly("deltafiMint"),
+                readonly("rent"),
+                readonly("systemProgram"),
+                readonly("tokenProgram"),
+            ],
+            vec![arg("amount", "u64")],
+        )
+        .docs(&["The discount of the staked amount applies to the swaps from the next slot"]),
+        ix(
+            "UnstakeFeeDiscount",
+            37,
+            vec![
+                writable("feeDiscount"),
+                writable("feeDiscountToken"),
+                signer("owner"),
+                writable("destination"),
+                readonly("tokenProgram"),
+            ],
+            vec![arg("amount", "u64")],
+        ),
+        ix(
+            "UpdateReferrer",
+            38,
+            vec![
+                readonly("config"),
+                signer("owner"),
+                writable("userReferrerData"),
+                readonly("referrerToken"),
+                readonly("tokenProgram"),
+            ],
+            vec![],
+        )
+        .docs(&["Passing the dummy referrer clears the referrer"]),
+        ix(
+            "InitReferrerStats",
+            39,
+            vec![
+                readonly("config"),
+                readonly("referrerToken"),
+                writable("referrerStats"),
+                writable_signer("payer"),
+                readonly("rent"),
+                readonly("systemProgram"),
+                readonly("tokenProgram"),
+            ],
+            vec![],
+        ),
+        ix(
+            "Rebalance",
+            51,
+            vec![
+                readonly("config"),
+                writable("swap"),
+                readonly("swapAuthority"),
+                signer("userTransferAuthority"),
+                writable("source"),
+                writable("destination"),
+                writable("tokenA"),
+                writable("tokenB"),
+                readonly("pythPriceA"),
+                readonly("pythPriceB"),
+                readonly("serumMarket"),
+                readonly("serumBids"),
+                readonly("serumAsks"),
+                readonly("tokenProgram"),
+            ],
+            vec![
+                arg("maxAmountIn", "u64"),
+                arg("minimumAmountOut", "u64"),
+                arg("deadlineSlot", "u64").optional(),
+            ],
+        )
+        .docs(&["Pays the short token of the pool in and the long token out at the oracle price"]),
+        ix(
+            "LockLiquidity",
+            52,
+            vec![
+                readonly("swap"),
+                writable("liquidityLock"),
+                writable("liquidityLockToken"),
+                writable("source"),
+                writable_signer("owner"),
+                readonly("poolMint"),
+                readonly("rent"),
+                readonly("systemProgram"),
+                readonly("tokenProgram"),
+            ],
+            vec![arg("amount", "u64"), arg("unlockTs", "i64")],
+        )
+        .docs(&["Later locks add to the locked amount and cannot bring the cliff forward"]),
+        ix(
+            "UnlockLiquidity",
+            53,
+            vec![
+                writable("liquidityLock"),
+                writable("liquidityLockToken"),
+                signer("owner"),
+                writable("destination"),
+                readonly("tokenProgram"),
+            ],
+            vec![],
+        ),
+        ix(
+            "SwapNativeSol",
+            54,
+            with_native_sol(swap_v2_accounts()),
+            swap_args(),
+        )
+        .docs(&[
+            "The temporary wSOL account is the source or the destination of the swap, the output owner is not supported",
+        ]),
+        ix(
+            "DepositNativeSol",
+            55,
+            with_native_sol(vec![
+                readonly("swap"),
+                readonly("swapAuthority"),
+                signer("userTransferAuthority"),
+                writable("sourceA"),
+                writable("sourceB"),
+                writable("tokenA"),
+                writable("tokenB"),
+                writable("poolMint"),
+                writable("destination"),
+                readonly("tokenProgram"),
+            ]),
+            deposit_args(),
+        )
+        .docs(&["The temporary wSOL account is the source of one of the tokens"]),
+        ix("SwapPartialFill", 56, swap_v2_accounts(), swap_args()).docs(&[
+            "Sets the filled amountIn and amountOut as the return data, the unused input stays in the source account",
+        ]),
+        ix(
+            "SetManualPrice",
+            57,
+            vec![writable("swap"), signer("priceSetter")],
+            vec![arg("marketPrice", "u128")],
+        )
+        .docs(&[
+            "Pushes the market price of a MANUAL oracle pool in the conventional order of the pair, scaled by 10^18",
+        ]),
+        ix(
+            "Sync",
+            58,
+            vec![
+                writable("swap"),
+                readonly("tokenA"),
+                readonly("tokenB"),
+                readonly("tokenProgram"),
+            ],
+            vec![],
+        )
+        .docs(&[
+            "Credits the tokens sent directly to the pool token accounts to the protocol fees of the pool",
+        ]),
+        ix(
+            "RefreshPrice",
+            59,
+            vec![
+                readonly("config"),
+                writable("swap"),
+                readonly("marketAuthority"),
+                writable("rewardToken"),
+                writable("sourceRewardToken"),
+                readonly("pythPriceA"),
+                readonly("pythPriceB"),
+                readonly("serumMarket"),
+                readonly("serumBids"),
+                readonly("serumAsks"),
+                readonly("tokenProgram"),
+            ],
+            vec![],
+        )
+        .docs(&[
+            "Refreshes the market price of a quiet normal pool and pays the caller a DELTAFI reward for a newer oracle price",
+        ]),
+    ]
+}
+
+fn stable_swap_instructions() -> Vec<IdlInstruction> {
+    let ix = |variant, discriminant, accounts, args| {
+        instruction("Stable", variant, discriminant, accounts, args)
+    };
+    vec![
+        ix(
+            "Initialize",
+            10,
+            stable_initialize_accounts(),
+            stable_initialize_args(),
+        ),
+        ix(
+            "Swap",
+            11,
+            vec![
+                readonly("config"),
+                writable("swap"),
+                readonly("marketAuthority"),
+                readonly("swapAuthority"),
+                signer("userTransferAuthority"),
+                writable("source"),
+                writable("swapSource"),
+                readonly("sourceMint"),
+                writable("swapDestination"),
+                writable("destination"),
+                readonly("destinationMint"),
+                writable("rewardToken"),
+                writable("sourceRewardToken"),
+                writable("adminFeeDestination"),
+                readonly("tokenProgram"),
+                readonly("exchangeRate").optional(),
+                writable("treasuryToken").optional(),
+                readonly("instructions").optional(),
+                readonly("userReferrerData").optional(),
+                writable("referrerToken").optional(),
+                writable("referrerStats").optional(),
+            ],
+            swap_args(),
+        ),
+        ix(
+            "Deposit",
+            12,
+            vec![
+                readonly("swap"),
+                readonly("swapAuthority"),
+                signer("userTransferAuthority"),
+                writable("sourceA"),
+                writable("sourceB"),
+                writable("tokenA"),
+                writable("tokenB"),
+                writable("poolMint"),
+                writable("destination"),
+                readonly("tokenProgram"),
+            ],
+            deposit_args(),
+        ),
+        ix(
+            "Withdraw",
+            13,
+            vec![
+                readonly("swap"),
+                readonly("swapAuthority"),
+                signer("userTransferAuthority"),
+                writable("poolMint"),
+                writable("source"),
+                writable("tokenA"),
+                writable("tokenB"),
+                writable("destinationA"),
+                writable("destinationB"),
+                writable("adminFeeA"),
+                writable("adminFeeB"),
+                readonly("tokenProgram"),
+            ],
+            withdraw_args(),
+        ),
+        ix(
+            "SwapV2",
+            14,
+            vec![
+                readonly("config"),
+                writable("swap"),
+                readonly("marketAuthority"),
+                readonly("swapAuthority"),
+                signer("userTransferAuthority"),
+                writable("source"),
+                writable("swapSource"),
+                writable("swapDestination"),
+                writable("destination"),
+                writable("rewardToken"),
+                writable("sourceRewardToken"),
+                writable("adminFeeDestination"),
+                readonly("tokenProgram"),
+                readonly("exchangeRate").optional(),
+                writable("treasuryToken").optional(),
+                readonly("instructions").optional(),
+                readonly("userReferrerData").optional(),
+                writable("referrerToken").optional(),
+                writable("referrerStats").optional(),
+            ],
+            swap_args(),
+        ),
+        ix(
+            "DepositOne",
+            15,
+            vec![
+                readonly("config"),
+                writable("swap"),
+                readonly("swapAuthority"),
+                signer("userTransferAuthority"),
+                writable("source"),
+                writable("tokenA"),
+                writable("tokenB"),
+                writable("adminFeeDestination"),
+                writable("poolMint"),
+                writable("destination"),
+                readonly("exchangeRate").optional(),
+                readonly("tokenProgram"),
+            ],
+            deposit_one_args(),
+        ),
+        ix(
+            "WithdrawOne",
+            16,
+            vec![
+                readonly("config"),
+                writable("swap"),
+                readonly("swapAuthority"),
+                signer("userTransferAuthority"),
+                writable("poolMint"),
+                writable("source"),
+                writable("tokenA"),
+                writable("tokenB"),
+                writable("destination"),
+                writable("adminFeeDestination"),
+                readonly("exchangeRate").optional(),
+                readonly("tokenProgram"),
+            ],
+            withdraw_one_args(),
+        ),
+        ix(
+            "PeggedInitialize",
+            19,
+            vec![
+                readonly("config"),
+                writable("swap"),
+                readonly("swapAuthority"),
+                readonly("adminFeeA"),
+                readonly("adminFeeB"),
+                readonly("tokenA"),
+                readonly("tokenB"),
+                writable("poolMint"),
+                writable("destination"),
+                signer("admin"),
+                readonly("rent"),
+                readonly("tokenProgram"),
+            ],
+            vec![
+                arg("nonce", "u8"),
+                arg("peggedPrice", "u128"),
+                arg("tokenADecimals", "u8"),
+                arg("tokenBDecimals", "u8"),
+                arg("tokenAAmount", "u64"),
+                arg("tokenBAmount", "u64"),
+            ],
+        ),
+        ix(
+            "InitializeV2",
+            28,
+            initialize_v2_accounts(stable_initialize_accounts()),
+            stable_initialize_args(),
+        ),
+        ix(
+            "TriInitialize",
+            47,
+            vec![
+                readonly("config"),
+                writable_signer("triSwap"),
+                readonly("swapAuthority"),
+                readonly("adminFee0"),
+                readonly("adminFee1"),
+                readonly("adminFee2"),
+                readonly("token0"),
+                readonly("token1"),
+                readonly("token2"),
+                writable("poolMint"),
+                writable("destination"),
+                signer("admin"),
+                readonly("rent"),
+                readonly("tokenProgram"),
+            ],
+            vec![
+                arg("nonce", "u8"),
+                arg("amp", "u64"),
+                array_arg("tokenDecimals", "u8", TRI_POOL_TOKENS),
+            ],
+        )
+        .docs(&["The token accounts hold the initial liquidity, minted 1:1 with the invariant"]),
+        ix(
+            "TriSwap",
+            48,
+            vec![
+                readonly("config"),
+                writable("triSwap"),
+                readonly("swapAuthority"),
+                signer("userTransferAuthority"),
+                writable("source"),
+                writable("swapSource"),
+                writable("swapDestination"),
+                writable("destination"),
+                writable("adminFeeDestination"),
+                readonly("tokenProgram"),
+                writable("treasuryToken").optional(),
+            ],
+            vec![
+                arg("amountIn", "u64"),
+                arg("minimumAmountOut", "u64"),
+                arg("deadlineSlot", "u64").optional(),
+            ],
+        ),
+        ix(
+            "TriDeposit",
+            49,
+            vec![
+                readonly("config"),
+                writable("triSwap"),
+                readonly("swapAuthority"),
+                signer("userTransferAuthority"),
+                writable("source0"),
+                writable("source1"),
+                writable("source2"),
+                writable("token0"),
+                writable("token1"),
+                writable("token2"),
+                writable("poolMint"),
+                writable("destination"),
+                readonly("tokenProgram"),
+            ],
+            vec![
+                array_arg("tokenAmounts", "u64", TRI_POOL_TOKENS),
+                arg("minMintAmount", "u64"),
+                arg("deadlineSlot", "u64").optional(),
+            ],
+        ),
+        ix(
+            "TriWithdraw",
+            50,
+            vec![
+                readonly("config"),
+                writable("triSwap"),
+                readonly("swapAuthority"),
+                signer("userTransferAuthority"),
+                writable("poolMint"),
+                writable("source"),
+                writable("token0"),
+                writable("token1"),
+                writable("token2"),
+                writable("destination0"),
+                writable("destination1"),
+                writable("destination2"),
+                writable("adminFee0"),
+                writable("adminFee1"),
+                writable("adminFee2"),
+                readonly("tokenProgram"),
+            ],
+            vec![
+                arg("poolTokenAmount", "u64"),
+                array_arg("minimumTokenAmounts", "u64", TRI_POOL_TOKENS),
+                arg("deadlineSlot", "u64").optional(),
+            ],
+        ),
+    ]
+}
+
+fn farm_instructions() -> Vec<IdlInstruction> {
+    let ix = |variant, discriminant, accounts, args| {
+        instruction("Farm", variant, discriminant, accounts, args)
+    };
+    vec![
+        ix(
+            "Initialize",
+            20,
+            vec![
+                readonly("config"),
+                readonly("swap"),
+                writable_signer("farmPool"),
+                readonly("swapAuthority"),
+                readonly("poolToken"),
+                signer("admin"),
+                readonly("rent"),
+            ],
+            vec![
+                arg("feeNumerator", "u64"),
+                arg("feeDenominator", "u64"),
+                arg("rewardsNumerator", "u64"),
+                arg("rewardsDenominator", "u64"),
+                arg("bumpSeed", "u8"),
+            ],
+        ),
+        ix(
+            "InitializeFarmUser",
+            21,
+            vec![
+                readonly("config"),
+                readonly("farmPool"),
+                writable("farmUser"),
+                signer("farmOwner"),
+                readonly("rent"),
+                writable("registryPage").optional(),
+                writable_signer("payer").optional(),
+                readonly("systemProgram").optional(),
+            ],
+            vec![],
+        )
+        .docs(&["The farm pool is writable if the farm user registry is enabled"]),
+        ix(
+            "Claim",
+            22,
+            vec![
+                readonly("config"),
+                readonly("farmPool"),
+                writable("farmUser"),
+                signer("farmOwner"),
+                readonly("marketAuthority"),
+                writable("claimDestination"),
+                writable("claimSource"),
+                readonly("tokenProgram"),
+            ],
+            vec![],
+        )
+        .docs(&[
+            "Followed by the reward split and the reward token accounts of its recipients if the farm user has a reward split",
+            "Followed by the user referrer data and the referrer token account for the referral reward",
+        ]),
+        ix(
+            "Refresh",
+            23,
+            vec![readonly("config"), writable("farmPool")],
+            vec![],
+        )
+        .docs(&["Followed by the writable farm users of the farm pool"]),
+        ix(
+            "Deposit",
+            24,
+            vec![
+                readonly("config"),
+                writable("farmPool"),
+                signer("userTransferAuthority"),
+                writable("source"),
+                writable("destination"),
+                writable("farmUser"),
+                signer("farmOwner"),
+                readonly("tokenProgram"),
+            ],
+            vec![arg("amount", "u64"), arg("lockTier", "u8").optional()],
+        )
+        .docs(&[
+            "Followed by the writable farm reward tokens and farm user reward tokens if the farm emits partner reward tokens",
+            "A lock tier locks the whole farm position until its unlock timestamp, boosting its rewards",
+        ]),
+        ix(
+            "Withdraw",
+            25,
+            vec![
+                readonly("config"),
+                writable("farmPool"),
+                writable("farmUser"),
+                readonly("authority"),
+                writable("source"),
+                writable("destination"),
+                signer("farmOwner"),
+                readonly("tokenProgram"),
+            ],
+            vec![arg("amount", "u64")],
+        )
+        .docs(&[
+            "Followed by the writable farm reward tokens and farm user reward tokens if the farm emits partner reward tokens",
+            "Then by the writable admin token account of the pool mint receiving the withdraw fee if the farm has a fee",
+        ]),
+        ix(
+            "SetRewardSplit",
+            26,
+            vec![
+                readonly("config"),
+                readonly("farmPool"),
+                writable("farmUser"),
+                signer("farmOwner"),
+                writable("rewardSplit"),
+                readonly("rent"),
+            ],
+            vec![array_arg("percentages", "u8", MAX_REWARD_RECIPIENTS)],
+        )
+        .docs(&["Followed by the reward token accounts of the recipients"]),
+        ix(
+            "Checkpoint",
+            27,
+            vec![readonly("config"), readonly("farmPool")],
+            vec![],
+        )
+        .docs(&["Followed by the writable farm users of the farm pool"]),
+        ix(
+            "FundRewardToken",
+            40,
+            vec![
+                readonly("config"),
+                readonly("farmPool"),
+                writable("farmRewardTokens"),
+                writable("vault"),
+                writable("source"),
+                signer("userTransferAuthority"),
+                readonly("tokenProgram"),
+            ],
+            vec![arg("index", "u8"), arg("amount", "u64")],
+        ),
+        ix(
+            "InitializeFarmUserRewardTokens",
+            41,
+            vec![
+                readonly("config"),
+                readonly("farmPool"),
+                readonly("farmUser"),
+                writable("farmUserRewardTokens"),
+                writable_signer("farmOwner"),
+                readonly("rent"),
+                readonly("systemProgram"),
+            ],
+            vec![],
+        ),
+        ix(
+            "ClaimRewardTokens",
+            42,
+            vec![
+                readonly("config"),
+                readonly("farmPool"),
+                readonly("farmUser"),
+                writable("farmRewardTokens"),
+                writable("farmUserRewardTokens"),
+                signer("farmOwner"),
+                readonly("authority"),
+                readonly("tokenProgram"),
+            ],
+            vec![],
+        )
+        .docs(&["Followed by the writable vault and destination token accounts of each reward token in use"]),
+        ix(
+            "Compound",
+            43,
+            vec![
+                readonly("config"),
+                writable("farmPool"),
+                writable("farmUser"),
+                signer("compoundAuthority"),
+                readonly("marketAuthority"),
+                writable("claimSource"),
+                writable("swap"),
+                readonly("swapAuthority"),
+                writable("tokenA"),
+                writable("tokenB"),
+                writable("adminFeeDestination"),
+                writable("poolMint"),
+                writable("farmPoolToken"),
+            ],
+            vec![arg("minMintAmount", "u64")],
+        )
+        .docs(&[
+            "Followed by the market price accounts of the swap as in DepositOne of its swap type, then the token program",
+            "Then by the writable farm reward tokens and farm user reward tokens if the farm emits partner reward tokens",
+        ]),
+        ix(
+            "TransferPosition",
+            44,
+            vec![
+                readonly("config"),
+                readonly("farmPool"),
+                writable("sourceFarmUser"),
+                signer("sourceOwner"),
+                writable("destinationFarmUser"),
+            ],
+            vec![],
+        )
+        .docs(&["Followed by the writable farm reward tokens, source and destination farm user reward tokens if the farm emits partner reward tokens"]),
+        ix(
+            "ClaimMulti",
+            45,
+            vec![
+                readonly("config"),
+                signer("farmOwner"),
+                readonly("marketAuthority"),
+                writable("claimDestination"),
+                writable("claimSource"),
+                readonly("tokenProgram"),
+            ],
+            vec![arg("farmCount", "u8")],
+        )
+        .docs(&[
+            "Followed by the farm pool and the writable farm user of each of the farms",
+            "Followed by the user referrer data and the referrer token account for the referral reward",
+        ]),
+        ix(
+            "InitializeStaking",
+            46,
+            vec![
+                readonly("config"),
+                writable_signer("farmPool"),
+                readonly("farmAuthority"),
+                readonly("poolToken"),
+                signer("admin"),
+                readonly("rent"),
+            ],
+            vec![
+                arg("feeNumerator", "u64"),
+                arg("feeDenominator", "u64"),
+                arg("rewardsNumerator", "u64"),
+                arg("rewardsDenominator", "u64"),
+                arg("bumpSeed", "u8"),
+            ],
+        ),
+        ix(
+            "SetCompoundDelegate",
+            60,
+            vec![
+                readonly("config"),
+                readonly("farmPool"),
+                writable("farmUser"),
+                signer("farmOwner"),
+            ],
+            vec![arg("compoundDelegate", "publicKey")],
+        ),
+    ]
+}
+
+fn router_instructions() -> Vec<IdlInstruction> {
+    vec![
+        instruction("", "Route", 30, router_accounts(), swap_args()).docs(&[
+            "Followed for each pool of the route by the writable token swap, the swap authority, the writable token accounts to swap INTO and FROM, the writable admin fee account, the optional writable treasury and referrer token accounts of the mint swapped FROM and the price accounts of the pool",
+        ]),
+        instruction("", "SmartSwap", 29, router_accounts(), swap_args()).docs(&[
+            "Splits the input between the normal pool and the stable pool of the pair, followed by the accounts of the normal pool then the stable pool, listed as for a route",
+        ]),
+    ]
+}
+
+fn router_accounts() -> Vec<IdlAccount> {
+    vec![
+        readonly("config"),
+        readonly("marketAuthority"),
+        signer("userTransferAuthority"),
+        writable("source"),
+        writable("destination"),
+        writable("rewardToken"),
+        writable("sourceRewardToken"),
+        readonly("tokenProgram"),
+        readonly("feeDiscount").optional(),
+        readonly("feeDiscountTiers").optional(),
+        readonly("userReferrerData").optional(),
+        writable("referrerToken").optional(),
+    ]
+}
+
+fn errors() -> Vec<IdlError> {
+    (0..)
+        .map(SwapError::from_u32)
+        .take_while(Option::is_some)
+        .flatten()
+        .enumerate()
+        .map(|(code, error)| IdlError {
+            code: code as u32,
+            name: format!("{:?}", error),
+            msg: error.to_string(),
+        })
+        .collect()
+}
+
+/// Build the IDL of the program
+pub fn idl() -> Idl {
+    Idl {
+        version: env!("CARGO_PKG_VERSION").to_string(),
+        name: "deltafi_swap".to_string(),
+        instructions: swap_instructions()
+            .into_iter()
+            .chain(stable_swap_instructions())
+            .chain(farm_instructions())
+            .chain(router_instructions())
+            .chain(admin_instructions())
+            .collect(),
+        errors: errors(),
+        metadata: IdlMetadata {
+            origin: "shank",
+            address: crate::id().to_string(),
+        },
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::instruction::{
+        AdminInstruction, FarmInstruction, InstructionType, RouterInstruction,
+        StableSwapInstruction, SwapInstruction,
+    };
+
+    fn size(ty: &IdlType) -> usize {
+        match ty {
+            IdlType::Primitive("bool") | IdlType::Primitive("u8") => 1,
+            IdlType::Primitive("u16") => 2,
+            IdlType::Primitive("u64") | IdlType::Primitive("i64") => 8,
+            IdlType::Primitive("u128") => 16,
+            IdlType::Primitive("publicKey") => 32,
+            IdlType::Primitive(ty) => panic!("no fixed size for {}", ty),
+            IdlType::Array { array: (ty, len) } => size(ty) * len,
+        }
+    }
+
+    fn unpack_debug(input: &[u8]) -> String {
+        match InstructionType::check(input).unwrap() {
+            InstructionType::Admin => format!("{:?}", AdminInstruction::unpack(input).unwrap()),
+            InstructionType::Swap => format!("{:?}", SwapInstruction::unpack(input).unwrap()),
+            InstructionType::StableSwap => {
+                format!("{:?}", StableSwapInstruction::unpack(input).unwrap())
+            }
+            InstructionType::Farm => format!("{:?}", FarmInstruction::unpack(input).unwrap()),
+            InstructionType::Router => format!("{:?}", RouterInstruction::unpack(input).unwrap()),
+        }
+    }
+
+    #[test]
+    fn test_idl_instructions_unpack() {
+        let idl = idl();
+        for instruction in idl.instructions.iter() {
+            let mut input = vec![instruction.discriminant.value];
+            for arg in instruction.args.iter().filter(|arg| !arg.optional) {
+                input.resize(input.len() + size(&arg.ty), 0);
+            }
+            let debug = unpack_debug(&input);
+            let variant = debug.split('(').next().unwrap();
+            assert_eq!(variant, instruction.variant, "{}", instruction.name);
+        }
+
+        for tag in 0..=u8::MAX {
+            if InstructionType::check(&[tag]).is_some() {
+                assert_eq!(
+                    idl.instructions
+                        .iter()
+                        .filter(|instruction| instruction.discriminant.value == tag)
+                        .count(),
+                    1,
+                    "tag {}",
+                    tag
+                );
+            }
+        }
+
+        let mut names: Vec<_> = idl.instructions.iter().map(|i| &i.name).collect();
+        names.sort();
+        names.dedup();
+        assert_eq!(names.len(), idl.instructions.len());
+    }
+
+    #[test]
+    fn test_idl_errors() {
+        let errors = idl().errors;
+        assert_eq!(errors[0].name, "AlreadyInUse");
+        for error in errors.iter() {
+            assert_eq!(
+                SwapError::from_u32(error.code).map(|e| format!("{:?}", e)),
+                Some(error.name.clone())
+            );
+        }
+        assert!(SwapError::from_u32(errors.len() as u32).is_none());
+    }
+}
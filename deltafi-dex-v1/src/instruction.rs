@@ -2,19 +2,26 @@
 
 #![allow(clippy::too_many_arguments)]
 
-use std::{convert::TryInto, mem::size_of};
+use std::{
+    convert::{TryFrom, TryInto},
+    mem::size_of,
+};
 
 use solana_program::{
     instruction::{AccountMeta, Instruction},
     program_error::ProgramError,
     program_pack::Pack,
     pubkey::{Pubkey, PUBKEY_BYTES},
-    sysvar::{clock, rent},
+    system_program,
+    sysvar::{self, clock, rent},
 };
 
 use crate::{
     error::SwapError,
-    state::{Fees, Rewards},
+    state::{
+        FeeDiscountTier, Fees, OracleParams, Rewards, MAX_FEE_DISCOUNT_TIERS,
+        MAX_REWARD_RECIPIENTS, TRI_POOL_TOKENS,
+    },
 };
 
 #[cfg(feature = "fuzz")]
@@ -31,6 +38,8 @@ pub enum InstructionType {
     StableSwap,
     /// Farm
     Farm,
+    /// Multi-hop router
+    Router,
 }
 
 impl InstructionType {
@@ -38,10 +47,11 @@ impl InstructionType {
     pub fn check(input: &[u8]) -> Option<Self> {
         let (&tag, _rest) = input.split_first()?;
         match tag {
-            100..=110 => Some(Self::Admin),
-            0..=5 => Some(Self::Swap),
-            10..=14 => Some(Self::StableSwap),
-            20..=25 => Some(Self::Farm),
+            100..=159 => Some(Self::Admin),
+            0..=9 | 17 | 18 | 31..=39 | 51..=59 => Some(Self::Swap),
+            10..=16 | 19 | 28 | 47..=50 => Some(Self::StableSwap),
+            20..=27 | 40..=46 | 60 => Some(Self::Farm),
+            29 | 30 => Some(Self::Router),
             _ => None,
         }
     }
@@ -56,7 +66,7 @@ pub struct InitializeData {
     pub nonce: u8,
     /// Slope variable - real value * 10**18, 0 <= slope <= 1
     pub slope: u64,
-    /// mid price
+    /// mid price, quote per base in the conventional order of the pair
     pub mid_price: u128,
     /// token a decimals
     pub token_a_decimals: u8,
@@ -68,6 +78,27 @@ pub struct InitializeData {
     pub token_b_amount: u64,
     /// oracle priority flags
     pub oracle_priority_flags: u8,
+    /// token A is the quote token of the pair, the serum market is then the one of token B
+    /// in token A and the mid price is the price of token B in token A
+    pub quote_is_token_a: bool,
+}
+
+/// Pegged swap initialize data
+#[repr(C)]
+#[derive(Debug, PartialEq)]
+pub struct PeggedInitializeData {
+    /// Nonce used to create valid program address
+    pub nonce: u8,
+    /// constant price of the base token in quote token - real value * 10**18
+    pub pegged_price: u128,
+    /// token a decimals
+    pub token_a_decimals: u8,
+    /// token b decimals
+    pub token_b_decimals: u8,
+    /// token a amount
+    pub token_a_amount: u64,
+    /// token b amount
+    pub token_b_amount: u64,
 }
 
 /// Stable swap initialize data
@@ -97,9 +128,15 @@ pub struct SwapData {
     pub amount_in: u64,
     /// Minimum amount of DESTINATION token to output, prevents excessive slippage
     pub minimum_amount_out: u64,
+    /// Last slot the transaction can be executed, prevents stale transactions landing late
+    pub deadline_slot: Option<u64>,
+    /// Owner of the DESTINATION token account, a program address of the program invoking the
+    /// swap, lets the output go to the calling program without a signature of the owner
+    pub output_owner: Option<Pubkey>,
 }
 
 /// Deposit instruction data
+#[cfg_attr(feature = "fuzz", derive(Arbitrary))]
 #[repr(C)]
 #[derive(Clone, Debug, PartialEq)]
 pub struct DepositData {
@@ -109,9 +146,24 @@ pub struct DepositData {
     pub token_b_amount: u64,
     /// Minimum LP tokens to mint, prevents excessive slippage
     pub min_mint_amount: u64,
+    /// Last slot the transaction can be executed, prevents stale transactions landing late
+    pub deadline_slot: Option<u64>,
+}
+
+/// Single-sided deposit instruction data
+#[repr(C)]
+#[derive(Clone, Debug, PartialEq)]
+pub struct DepositOneData {
+    /// SOURCE token amount to deposit, part of it is swapped to the other side
+    pub amount_in: u64,
+    /// Minimum LP tokens to mint, prevents excessive slippage
+    pub min_mint_amount: u64,
+    /// Last slot the transaction can be executed, prevents stale transactions landing late
+    pub deadline_slot: Option<u64>,
 }
 
 /// Withdraw instruction data
+#[cfg_attr(feature = "fuzz", derive(Arbitrary))]
 #[repr(C)]
 #[derive(Clone, Debug, PartialEq)]
 pub struct WithdrawData {
@@ -122,6 +174,161 @@ pub struct WithdrawData {
     pub minimum_token_a_amount: u64,
     /// Minimum amount of token B to receive, prevents excessive slippage
     pub minimum_token_b_amount: u64,
+    /// Last slot the transaction can be executed, prevents stale transactions landing late
+    pub deadline_slot: Option<u64>,
+}
+
+/// Single-sided withdraw instruction data
+#[repr(C)]
+#[derive(Clone, Debug, PartialEq)]
+pub struct WithdrawOneData {
+    /// Amount of pool tokens to burn. The other side of the withdrawn liquidity
+    /// is swapped into the requested token.
+    pub pool_token_amount: u64,
+    /// Minimum amount of the requested token to receive, prevents excessive slippage
+    pub minimum_amount_out: u64,
+    /// Token to receive, 0 for token A and 1 for token B
+    pub token_side: u8,
+    /// Last slot the transaction can be executed, prevents stale transactions landing late
+    pub deadline_slot: Option<u64>,
+}
+
+/// Rebalance instruction data
+#[repr(C)]
+#[derive(Clone, Debug, PartialEq)]
+pub struct RebalanceData {
+    /// Max amount of the short token to pay in, the rebalance stops at the pool targets
+    pub max_amount_in: u64,
+    /// Minimum amount of the long token to receive, prevents a late oracle price move
+    pub minimum_amount_out: u64,
+    /// Last slot the transaction can be executed, prevents stale transactions landing late
+    pub deadline_slot: Option<u64>,
+}
+
+/// LockLiquidity instruction data
+#[repr(C)]
+#[derive(Clone, Debug, PartialEq)]
+pub struct LockLiquidityData {
+    /// Amount of pool tokens to lock
+    pub amount: u64,
+    /// Cliff timestamp of the lock, not earlier than the cliff of the pool tokens locked before
+    pub unlock_ts: i64,
+}
+
+/// Market maker quote instruction data
+#[repr(C)]
+#[derive(Clone, Debug, PartialEq)]
+pub struct QuoteData {
+    /// Direction of the user trade filling the quote, 0 for sell base and 1 for sell quote
+    pub swap_direction: u8,
+    /// Price of the base token in quote token - real value * 10**18
+    pub price: u128,
+    /// Max amount of SOURCE token the quote takes in, 0 cancels the quote
+    pub max_amount_in: u64,
+    /// Last slot the quote can be filled
+    pub expiry_slot: u64,
+}
+
+/// Flash swap instruction data
+#[cfg_attr(feature = "fuzz", derive(Arbitrary))]
+#[repr(C)]
+#[derive(Clone, Debug, PartialEq)]
+pub struct FlashSwapData {
+    /// Amount of base token to borrow
+    pub amount_a: u64,
+    /// Amount of quote token to borrow
+    pub amount_b: u64,
+    /// Last slot the transaction can be executed, packed as `u64::MAX` when unset since the
+    /// callback data follows it
+    pub deadline_slot: Option<u64>,
+    /// Instruction data passed to the callback program
+    pub callback_data: Vec<u8>,
+}
+
+/// Quote instruction data
+#[repr(C)]
+#[derive(Clone, Debug, PartialEq)]
+pub struct GetQuoteData {
+    /// SOURCE amount to quote
+    pub amount_in: u64,
+    /// 0 for sell base and 1 for sell quote
+    pub swap_direction: u8,
+}
+
+/// Quote of a swap, set as the return data of `GetQuote`
+#[repr(C)]
+#[derive(Clone, Debug, PartialEq)]
+pub struct SwapQuote {
+    /// DESTINATION amount the user would receive
+    pub amount_out: u64,
+    /// Trade fee in DESTINATION token
+    pub trade_fee: u64,
+    /// Admin part of the trade fee
+    pub admin_fee: u64,
+    /// Shortfall of the curve output against the market price, in basis points
+    pub price_impact_bps: u64,
+    /// Market price of the pool as quote per base in the conventional order of the pair -
+    /// real value * 10**18, adjusted to the token decimals
+    pub market_price: u128,
+}
+
+impl SwapQuote {
+    /// Packs the quote into the return data
+    pub fn pack(&self) -> Vec<u8> {
+        let mut buf = Vec::with_capacity(48);
+        buf.extend_from_slice(&self.amount_out.to_le_bytes());
+        buf.extend_from_slice(&self.trade_fee.to_le_bytes());
+        buf.extend_from_slice(&self.admin_fee.to_le_bytes());
+        buf.extend_from_slice(&self.price_impact_bps.to_le_bytes());
+        buf.extend_from_slice(&self.market_price.to_le_bytes());
+        buf
+    }
+
+    /// Unpacks the return data of a `GetQuote` CPI
+    pub fn unpack(input: &[u8]) -> Result<Self, ProgramError> {
+        let (amount_out, rest) = unpack_u64(input)?;
+        let (trade_fee, rest) = unpack_u64(rest)?;
+        let (admin_fee, rest) = unpack_u64(rest)?;
+        let (price_impact_bps, rest) = unpack_u64(rest)?;
+        let (market_price, _) = unpack_u128(rest)?;
+        Ok(Self {
+            amount_out,
+            trade_fee,
+            admin_fee,
+            price_impact_bps,
+            market_price,
+        })
+    }
+}
+
+/// Amounts of a partial fill swap, set as the return data of `SwapPartialFill`
+#[repr(C)]
+#[derive(Clone, Debug, PartialEq)]
+pub struct SwapFill {
+    /// SOURCE amount taken in, the rest of the requested input stays with the user
+    pub amount_in: u64,
+    /// DESTINATION amount the user received
+    pub amount_out: u64,
+}
+
+impl SwapFill {
+    /// Packs the fill into the return data
+    pub fn pack(&self) -> Vec<u8> {
+        let mut buf = Vec::with_capacity(16);
+        buf.extend_from_slice(&self.amount_in.to_le_bytes());
+        buf.extend_from_slice(&self.amount_out.to_le_bytes());
+        buf
+    }
+
+    /// Unpacks the return data of a `SwapPartialFill` CPI
+    pub fn unpack(input: &[u8]) -> Result<Self, ProgramError> {
+        let (amount_in, rest) = unpack_u64(input)?;
+        let (amount_out, _) = unpack_u64(rest)?;
+        Ok(Self {
+            amount_in,
+            amount_out,
+        })
+    }
 }
 
 /// ADMIN INSTRUCTION PARAMS
@@ -172,20 +379,20 @@ pub enum AdminInstruction {
     ///
     ///   0. `[]` Market config
     ///   1. `[writable]` token_swap account of the pool
-    ///   2. `[signer]` admin account
+    ///   2. `[signer]` admin or pauser account
     Pause,
     /// Resume pool
     ///
     ///   0. `[]` Market config
     ///   1. `[writable]` token_swap account of the pool
-    ///   2. `[signer]` admin account
+    ///   2. `[signer]` admin or pauser account
     Unpause,
     /// Set fee account to the pool
     ///
     ///   0. `[]` Market config
     ///   1. `[writable]` token_swap account of the pool
     ///   2. `[]` $authority derived from `create_program_address(&[token_swap acc])`
-    ///   3. `[signer]` admin account
+    ///   3. `[signer]` admin or fee manager account
     ///   4. `[]` new fee account that matches one of both
     ///   5. `[]` token_program_id
     SetFeeAccount,
@@ -196,24 +403,24 @@ pub enum AdminInstruction {
     ///   2. `[writable]` deltafi mint account that'd change freeze_authority
     ///   3. `[]` token_program_id
     CommitNewAdmin(CommitNewAdmin),
-    /// Set new fees to the pool
+    /// Set new fees to the pool, pinning its fees and rewards to custom values
     ///
     ///   1. `[]` market config
     ///   2. `[writable]` token_swap account of the pool
-    ///   3. `[signer]` admin account
+    ///   3. `[signer]` admin or fee manager account
     SetNewFees(Fees),
-    /// Set new rewards to the pool
+    /// Set new rewards to the pool, pinning its fees and rewards to custom values
     ///
     ///   1. `[]` market config
     ///   2. `[writable]` token_swap account of the pool
-    ///   3. `[signer]` admin account
+    ///   3. `[signer]` admin or fee manager account
     /// TODO: rename to trade rewards
     SetNewRewards(Rewards),
     /// Set new staking rewards ratio to the stake pool
     ///
     ///   0. `[]` Market config
     ///   1. `[writable]` farm pool account
-    ///   2. `[signer]` admin account
+    ///   2. `[signer]` admin or fee manager account
     SetFarmRewards(FarmRewards),
     /// Set new staking rewards ratio to the stake pool
     ///
@@ -233,6 +440,424 @@ pub enum AdminInstruction {
     ///   1. `[writable]` token_swap account of the pool
     ///   2. `[signer]` admin account
     SetSwapLimit(u8),
+    /// Set the policy applied on large oracle price jumps
+    ///
+    ///   0. `[]` Market config
+    ///   1. `[writable]` token_swap account of the pool
+    ///   2. `[signer]` admin or oracle manager account
+    SetPriceJumpPolicy(u8),
+    /// Set the max divergence in basis points between pyth and serum prices
+    ///
+    ///   0. `[]` Market config
+    ///   1. `[writable]` token_swap account of the pool
+    ///   2. `[signer]` admin or oracle manager account
+    SetMaxPriceDivergence(u16),
+    /// Set the orderbook program accepted for serum market prices
+    ///
+    ///   0. `[writable]` market config
+    ///   1. `[signer]` admin account
+    ///   2. `[]` orderbook program id
+    SetOrderbookProgram,
+    /// Set the exchange rate adapter pricing a stable pool
+    ///
+    ///   0. `[]` Market config
+    ///   1. `[writable]` token_swap account of the pool
+    ///   2. `[signer]` admin or oracle manager account
+    ///   3. `[]` optional: exchange rate account, required unless the adapter is reset to none
+    SetExchangeRateAdapter(u8),
+    /// Set the pyth staleness, confidence and publisher thresholds of the pool
+    ///
+    ///   0. `[]` Market config
+    ///   1. `[writable]` token_swap account of the pool
+    ///   2. `[signer]` admin or oracle manager account
+    SetOracleParams(OracleParams),
+    /// Set the market maker allowed to post quotes at the allowlist index
+    ///
+    ///   0. `[writable]` market config
+    ///   1. `[signer]` admin account
+    ///   2. `[]` market maker, the default pubkey removes the market maker at the index
+    SetMarketMaker(u8),
+    /// Replace a fee account of the pool, the balance left in the old fee account is swept
+    /// into the new one and the old account is closed in the same instruction
+    ///
+    ///   0. `[]` Market config
+    ///   1. `[writable]` token_swap account of the pool
+    ///   2. `[]` $authority derived from `create_program_address(&[token_swap acc])`
+    ///   3. `[signer]` admin or fee manager account
+    ///   4. `[writable]` current fee account of the pool
+    ///   5. `[writable]` new fee account with the same mint
+    ///   6. `[writable]` lamports destination of the closed fee account
+    ///   7. `[]` token_program_id
+    RotateFeeAccount,
+    /// Commit the hash of the initial mid price and a salt of a pool to be initialized with
+    /// `RevealInitialize`, keeping the seed price of a launch out of the mempool until then
+    ///
+    ///   0. `[]` Market config
+    ///   1. `[writable]` price commitment account, derived from the admin and the token_swap
+    ///   2. `[]` token_swap account of the pool to be initialized
+    ///   3. `[signer]` admin account
+    ///   4. `[]` Clock sysvar
+    ///   5. `[]` Rent sysvar
+    CommitInitialPrice([u8; 32]),
+    /// Commit a new constant price of a pegged pool, applied with `ApplyPeggedPrice` once
+    /// the timelock is over. A new commitment replaces the pending one.
+    ///
+    ///   0. `[]` Market config
+    ///   1. `[writable]` token_swap account of the pegged pool
+    ///   2. `[signer]` admin or oracle manager account
+    ///   3. `[]` Clock sysvar
+    CommitPeggedPrice(u128),
+    /// Apply the pegged price committed with `CommitPeggedPrice`
+    ///
+    ///   0. `[]` Market config
+    ///   1. `[writable]` token_swap account of the pegged pool
+    ///   2. `[signer]` admin or oracle manager account
+    ///   3. `[]` Clock sysvar
+    ApplyPeggedPrice,
+    /// Delegate an admin role to a key, the default pubkey revokes the role
+    ///
+    ///   0. `[writable]` market config
+    ///   1. `[signer]` admin account
+    ///   2. `[]` key delegated the role
+    SetAdminRole(u8),
+    /// Pause every pool of the config passed to the instruction, for incident response.
+    /// Pools are unpaused one by one with `Unpause`.
+    ///
+    ///   0. `[]` Market config
+    ///   1. `[signer]` admin or guardian account
+    ///   2. ..2+N `[writable]` token_swap accounts of the pools to pause
+    PauseAll,
+    /// Require swaps of the pool to send the output to a token account owned by a signer of
+    /// the transaction, the user transfer authority or an extra signer appended to the accounts
+    ///
+    ///   0. `[]` Market config
+    ///   1. `[writable]` token_swap account of the pool
+    ///   2. `[signer]` admin account
+    SetStrictDestinationOwner(bool),
+    /// Register the farm users of the farm pool initialized from now on in the paged farm
+    /// user registry. The registry can't be turned off, so the pages stay in sync with the count.
+    ///
+    ///   0. `[]` Market config
+    ///   1. `[writable]` farm pool account
+    ///   2. `[signer]` admin account
+    EnableFarmUserRegistry,
+    /// Pause swaps, deposits or withdrawals of the pool independently, the `PauseFlag` bits
+    /// set are paused and the others resumed. `Pause` still stops every operation.
+    ///
+    ///   0. `[]` Market config
+    ///   1. `[writable]` token_swap account of the pool
+    ///   2. `[signer]` admin or pauser account
+    SetPauseFlags(u8),
+    /// Transfer the protocol fees accrued in the pool state to the fee accounts of the pool
+    ///
+    ///   0. `[]` Market config
+    ///   1. `[writable]` token_swap account of the pool
+    ///   2. `[]` $authority derived from `create_program_address(&[token_swap acc])`
+    ///   3. `[signer]` admin or fee manager account
+    ///   4. `[writable]` token_a Base Account of the pool
+    ///   5. `[writable]` token_b Quote Account of the pool
+    ///   6. `[writable]` token_a fee account of the pool
+    ///   7. `[writable]` token_b fee account of the pool
+    ///   8. `[]` token_program_id
+    ClaimProtocolFees,
+    /// Set the protocol share of the LP fees as numerator and denominator, it is kept in the
+    /// pool until claimed with `ClaimProtocolFees`
+    ///
+    ///   0. `[]` Market config
+    ///   1. `[writable]` token_swap account of the pool
+    ///   2. `[signer]` admin or fee manager account
+    SetProtocolFee(u64, u64),
+    /// Set an authority of a token account or mint held by the pool authority, signed with
+    /// the pool authority seeds, for planned authority migrations. The data is the spl_token
+    /// `AuthorityType` index.
+    ///
+    ///   0. `[]` Market config
+    ///   1. `[]` token_swap account of the pool
+    ///   2. `[]` $authority derived from `create_program_address(&[token_swap acc])`
+    ///   3. `[signer]` admin account
+    ///   4. `[writable]` token account or mint to update
+    ///   5. `[]` new authority
+    ///   6. `[]` token_program_id
+    SetPoolAuthority(u8),
+    /// Set an authority of a token account or mint held by the market authority, signed with
+    /// the market authority seeds, for planned authority migrations. The data is the spl_token
+    /// `AuthorityType` index.
+    ///
+    ///   0. `[]` Market config
+    ///   1. `[]` market authority derived from `create_program_address(&[config acc])`
+    ///   2. `[signer]` admin account
+    ///   3. `[writable]` token account or mint to update
+    ///   4. `[]` new authority
+    ///   5. `[]` token_program_id
+    SetMarketAuthority(u8),
+    /// Close a paused pool without liquidity. The tokens left in the pool are swept to the
+    /// destinations, the pool token accounts are closed, the mint authority of the pool mint
+    /// is revoked and the rent of the pool goes back to the admin. The admin fee accounts are
+    /// owned by the fee owner and left alone.
+    ///
+    ///   0. `[]` Market config
+    ///   1. `[writable]` token_swap account of the pool
+    ///   2. `[]` $authority derived from `create_program_address(&[token_swap acc])`
+    ///   3. `[writable, signer]` admin account, receives the rent
+    ///   4. `[writable]` Pool token mint
+    ///   5. `[writable]` token_a Base Account of the pool
+    ///   6. `[writable]` token_b Quote Account of the pool
+    ///   7. `[writable]` token_a destination of the tokens left
+    ///   8. `[writable]` token_b destination of the tokens left
+    ///   9. `[]` token_program_id
+    ClosePool,
+    /// Register the oracle accounts of a token mint in the oracle feed registry of the config,
+    /// the registry entry is created on the first call. Default pubkey for a feed not available.
+    ///
+    ///   0. `[]` Market config
+    ///   1. `[writable]` oracle feed account derived from `find_oracle_feed_address`
+    ///   2. `[]` token mint
+    ///   3. `[]` pyth price account of the mint
+    ///   4. `[]` serum market with the mint as base token
+    ///   5. `[writable, signer]` admin or oracle manager account, pays for the oracle feed
+    ///   6. `[]` rent sysvar
+    ///   7. `[]` system program
+    SetOracleFeed,
+    /// Initialize the pools of the config with the oracle accounts registered in the oracle
+    /// feed registry only. The registry can't be turned off once enabled.
+    ///
+    ///   0. `[writable]` Market config
+    ///   1. `[signer]` admin account
+    EnableOracleFeedRegistry,
+    /// Set the relayer of the price cache of a pool, the price cache is created on the first
+    /// call. The cached price is cleared until the new relayer updates it.
+    ///
+    ///   0. `[]` Market config
+    ///   1. `[]` token-swap
+    ///   2. `[writable]` price cache account derived from `find_price_cache_address`
+    ///   3. `[]` relayer account
+    ///   4. `[writable, signer]` admin or oracle manager account, pays for the price cache
+    ///   5. `[]` rent sysvar
+    ///   6. `[]` system program
+    SetPriceCache,
+    /// Create the scratch account of a mint at the index, with its token account. Scratch
+    /// accounts are held with `AcquireScratch` and `ReleaseScratch` for intermediate custody.
+    ///
+    ///   0. `[]` Market config
+    ///   1. `[writable]` scratch account derived from `find_scratch_account_address`
+    ///   2. `[writable]` scratch token account derived from `find_scratch_token_address`
+    ///   3. `[]` token mint
+    ///   4. `[writable, signer]` admin account, pays for the scratch accounts
+    ///   5. `[]` rent sysvar
+    ///   6. `[]` system program
+    ///   7. `[]` token_program_id
+    CreateScratchAccount(u8),
+    /// Set the max admin instructions per epoch, 0 to remove the limit. Only the guardian key
+    /// can sign it, it resumes the admin instructions paused after the max was exceeded.
+    /// While the max is set, the admin instructions take the market config writable to count
+    /// them, and the ones paused are executed when co-signed by the guardian.
+    ///
+    ///   0. `[writable]` Market config
+    ///   1. `[signer]` guardian account
+    SetAdminOpsLimit(u16),
+    /// Set the trade fee discount tiers of the DELTAFI stakers, the tiers account is created
+    /// on the first call
+    ///
+    ///   0. `[]` Market config
+    ///   1. `[writable]` fee discount tiers derived from `find_fee_discount_tiers_address`
+    ///   2. `[writable, signer]` fee manager account, pays for the tiers account
+    ///   3. `[]` rent sysvar
+    ///   4. `[]` system program
+    SetFeeDiscountTiers([FeeDiscountTier; MAX_FEE_DISCOUNT_TIERS]),
+    /// Turn on or off the protocol fee switch of the config, swaps then send the protocol share
+    /// of the trade fee to a token account of the treasury owner. Turning it on sets the owner.
+    ///
+    ///   0. `[writable]` Market config
+    ///   1. `[signer]` admin account
+    ///   2. `[]` treasury owner, only read when turning the switch on
+    SetProtocolFeeSwitch(bool),
+    /// Copy the default fees and rewards of the config to the pools, skipping the pools pinned
+    /// to custom values. With the data set, the pinned pools are synced and unpinned too.
+    ///
+    ///   0. `[]` Market config
+    ///   1. `[signer]` admin or fee manager account
+    ///   2. ..2+N `[writable]` token_swap accounts of the pools to sync
+    SyncPoolFromConfig(bool),
+    /// Set the default fees and rewards of the config, the pools are updated by
+    /// `SyncPoolFromConfig`
+    ///
+    ///   0. `[writable]` Market config
+    ///   1. `[signer]` admin or fee manager account
+    SetConfigFees(AdminInitializeData),
+    /// Set the same new fees to many pools in one instruction, pinning their fees and rewards
+    /// to custom values
+    ///
+    ///   0. `[]` Market config
+    ///   1. `[signer]` admin or fee manager account
+    ///   2. ..2+N `[writable]` token_swap accounts of the pools to update
+    BatchSetFees(Fees),
+    /// Pause many pools in one instruction
+    ///
+    ///   0. `[]` Market config
+    ///   1. `[signer]` admin or pauser account
+    ///   2. ..2+N `[writable]` token_swap accounts of the pools to pause
+    BatchPause,
+    /// Add a partner reward token to a farm emitting the reward per slot, the farm reward
+    /// tokens account is created on the first call
+    ///
+    ///   0. `[]` Market config
+    ///   1. `[writable]` farm pool account
+    ///   2. `[writable]` farm reward tokens derived from `find_farm_reward_tokens_address`
+    ///   3. `[]` reward token vault, owned by the farm authority
+    ///   4. `[writable, signer]` admin or fee manager account, pays for the farm reward tokens
+    ///   5. `[]` rent sysvar
+    ///   6. `[]` system program
+    ///   7. `[]` token program
+    AddFarmRewardToken(u64),
+    /// Set the reward per slot of a partner reward token of a farm, from the index of the
+    /// reward token
+    ///
+    ///   0. `[]` Market config
+    ///   1. `[]` farm pool account
+    ///   2. `[writable]` farm reward tokens
+    ///   3. `[signer]` admin or fee manager account
+    SetFarmRewardRate(u8, u64),
+    /// Set the reward emission per slot of a farm, the first call switches the farm
+    /// from the apr model to the emission
+    ///
+    ///   0. `[]` Market config
+    ///   1. `[writable]` farm pool account
+    ///   2. `[signer]` admin or fee manager account
+    SetFarmEmissionRate(u64),
+    /// Fund the DELTAFI reward reserve of the market authority for a staking pool
+    ///
+    ///   0. `[]` Market config
+    ///   1. `[]` staking pool, a farm pool of the DELTAFI mint
+    ///   2. `[]` market authority
+    ///   3. `[writable]` DELTAFI reward reserve of the market authority
+    ///   4. `[writable]` DELTAFI source account
+    ///   5. `[signer]` admin or fee manager account, transfer authority of the source
+    ///   6. `[]` token program id
+    FundStakingRewards(u64),
+    /// Set the min deviation in basis points of the pool mid price from the oracle price for
+    /// the permissionless rebalance of the pool, 0 turns the rebalance off
+    ///
+    ///   0. `[]` Market config
+    ///   1. `[writable]` token_swap account of the pool
+    ///   2. `[signer]` admin or fee manager account
+    SetRebalanceThreshold(u16),
+    /// Set the circuit breaker of the pool, failing the swaps executing at a price deviating
+    /// by more than the max deviation in basis points from the market price at the start of
+    /// the window of slots. A max deviation of 0 turns the circuit breaker off.
+    ///
+    ///   0. `[]` Market config
+    ///   1. `[writable]` token_swap account of the pool
+    ///   2. `[signer]` admin account
+    SetCircuitBreaker(u16, u64),
+    /// Cap the total supply of the pool tokens the deposits can mint up to, launching the pool
+    /// in guarded mode. A cap of 0 removes it, withdrawals are never capped.
+    ///
+    ///   0. `[]` Market config
+    ///   1. `[writable]` token_swap account of the pool
+    ///   2. `[signer]` admin or fee manager account
+    SetDepositCap(u64),
+    /// Turn on or off the whitelist of the pool, swaps and deposits on a whitelist-gated pool
+    /// require a whitelist entry of the user. Routes, flash swaps and quote fills are rejected.
+    ///
+    ///   0. `[]` Market config
+    ///   1. `[writable]` token_swap account of the pool
+    ///   2. `[signer]` admin account
+    SetWhitelistEnabled(bool),
+    /// Create the whitelist entry of a user on the pool
+    ///
+    ///   0. `[]` Market config
+    ///   1. `[]` token_swap account of the pool
+    ///   2. `[writable]` whitelist entry derived from `find_whitelist_entry_address`
+    ///   3. `[]` whitelisted user
+    ///   4. `[writable, signer]` admin account, pays for the whitelist entry
+    ///   5. `[]` rent sysvar
+    ///   6. `[]` system program
+    AddWhitelistEntry,
+    /// Close the whitelist entry of a user on the pool, the rent goes back to the admin
+    ///
+    ///   0. `[]` Market config
+    ///   1. `[]` token_swap account of the pool
+    ///   2. `[writable]` whitelist entry
+    ///   3. `[writable, signer]` admin account
+    RemoveWhitelistEntry,
+    /// Upgrade a pool to the current layout version. A pool created before the last growth of
+    /// the layout is rejected by the other instructions until migrated, it is reallocated to
+    /// the current length with the new fields off and the admin paying the rent of the growth.
+    ///
+    ///   0. `[]` Market config
+    ///   1. `[writable]` token_swap account of the pool
+    ///   2. `[writable, signer]` admin account
+    ///   3. `[]` rent sysvar
+    ///   4. `[]` system program
+    MigrateSwapInfo,
+    /// Reallocate a market config created before the reserved bytes were padded for the
+    /// future fields to the current length, the admin paying the rent of the growth. The
+    /// config is migrated before the pools and farms of the market.
+    ///
+    ///   0. `[writable]` Market config
+    ///   1. `[writable, signer]` admin account
+    ///   2. `[]` rent sysvar
+    ///   3. `[]` system program
+    MigrateConfigInfo,
+    /// Reallocate a farm pool created before the reserved bytes were padded for the future
+    /// fields to the current length, the admin paying the rent of the growth.
+    ///
+    ///   0. `[]` Market config
+    ///   1. `[writable]` farm pool account
+    ///   2. `[writable, signer]` admin account
+    ///   3. `[]` rent sysvar
+    ///   4. `[]` system program
+    MigrateFarmInfo,
+    /// Replace the pyth price accounts and the serum market of a normal pool, e.g. after a
+    /// pyth feed migration or a serum market deprecation. The new accounts are checked as on
+    /// initialization for the oracle priority of the pool.
+    ///
+    ///   0. `[]` Market config
+    ///   1. `[writable]` token_swap account of the pool
+    ///   2. `[signer]` admin or oracle manager account
+    ///   3. `[]` base token product from pyth network.
+    ///   4. `[]` base token price from pyth network.
+    ///   5. `[]` quote token product from pyth network.
+    ///   6. `[]` quote token price from pyth network.
+    ///   7. `[]` serum market account
+    ///   8. `[]` serum bids orderbook account
+    ///   9. `[]` serum asks orderbook account
+    ///
+    ///   When the oracle feed registry of the config is enabled, the accounts end with:
+    ///
+    ///   10. `[]` oracle feed of the base token mint.
+    ///   11. `[]` oracle feed of the quote token mint.
+    SetOracleAccounts,
+    /// Set the oracle priority flags of a normal pool with the oracle accounts of the new
+    /// priority, e.g. to move a pool from serum only to pyth only as the liquidity migrates.
+    /// The accounts are those of [SetOracleAccounts](enum.AdminInstruction.html#variant.SetOracleAccounts),
+    /// checked for the new priority.
+    SetOraclePriority(u8),
+    /// Set the price setter of a MANUAL oracle pool with the max change in basis points of a
+    /// pushed price from the previous one and the slots after which the pushed price is
+    /// stale. A new price setter clears the pushed price, the default pubkey removes it.
+    ///
+    ///   0. `[]` Market config
+    ///   1. `[writable]` token_swap account of the pool
+    ///   2. `[signer]` admin or oracle manager account
+    ///   3. `[]` price setter account
+    SetPriceSetter(u16, u64),
+    /// Set the window in slots of the moving average of the oracle price of a normal pool,
+    /// the curve is repriced at the average so a single oracle print moves it by a fraction
+    /// of the slots elapsed over the window. A window of 0 reprices at the oracle price.
+    ///
+    ///   0. `[]` Market config
+    ///   1. `[writable]` token_swap account of the pool
+    ///   2. `[signer]` admin or oracle manager account
+    SetPriceEmaWindow(u64),
+    /// Turn on or off the skim of the pool surplus, the swaps then transfer the tokens sent
+    /// directly to the pool DESTINATION token account to its admin fee account and credit the
+    /// ones sent to the SOURCE token account to the protocol fees before trading
+    ///
+    ///   0. `[]` Market config
+    ///   1. `[writable]` token_swap account of the pool
+    ///   2. `[signer]` admin or fee manager account
+    SetSkimSurplus(bool),
 }
 
 impl AdminInstruction {
@@ -285,46 +910,234 @@ impl AdminInstruction {
                 let (swap_out_limit_percentage, _) = unpack_u8(rest)?;
                 Self::SetSwapLimit(swap_out_limit_percentage)
             }
-            _ => return Err(SwapError::InvalidInstruction.into()),
-        })
-    }
-
-    /// Packs a [AdminInstruction](enum.AdminInstruciton.html) into a byte buffer.
-    pub fn pack(&self) -> Vec<u8> {
-        let mut buf = Vec::with_capacity(size_of::<Self>());
-        match &*self {
-            Self::Initialize(AdminInitializeData { fees, rewards }) => {
-                buf.push(100);
-                let mut fees_slice = [0u8; Fees::LEN];
-                Pack::pack_into_slice(fees, &mut fees_slice[..]);
-                buf.extend_from_slice(&fees_slice);
-                let mut rewards_slice = [0u8; Rewards::LEN];
-                Pack::pack_into_slice(rewards, &mut rewards_slice[..]);
-                buf.extend_from_slice(&rewards_slice);
+            111 => {
+                let (price_jump_policy, _) = unpack_u8(rest)?;
+                Self::SetPriceJumpPolicy(price_jump_policy)
             }
-            Self::Pause => buf.push(101),
-            Self::Unpause => buf.push(102),
-            Self::SetFeeAccount => buf.push(103),
-            Self::CommitNewAdmin(CommitNewAdmin { new_admin_key }) => {
-                buf.push(104);
-                buf.extend_from_slice(new_admin_key.as_ref());
+            112 => {
+                let (max_price_divergence, _) = unpack_u16(rest)?;
+                Self::SetMaxPriceDivergence(max_price_divergence)
             }
-            Self::SetNewFees(fees) => {
-                buf.push(105);
-                let mut fees_slice = [0u8; Fees::LEN];
-                Pack::pack_into_slice(fees, &mut fees_slice[..]);
-                buf.extend_from_slice(&fees_slice);
+            113 => Self::SetOrderbookProgram,
+            114 => {
+                let (adapter_type, _) = unpack_u8(rest)?;
+                Self::SetExchangeRateAdapter(adapter_type)
             }
-            Self::SetNewRewards(rewards) => {
-                buf.push(106);
-                let mut rewards_slice = [0u8; Rewards::LEN];
-                Pack::pack_into_slice(rewards, &mut rewards_slice[..]);
-                buf.extend_from_slice(&rewards_slice);
+            115 => {
+                let (stale_slots, rest) = unpack_u16(rest)?;
+                let (max_confidence_bps, rest) = unpack_u16(rest)?;
+                let (min_publishers, _) = unpack_u8(rest)?;
+                Self::SetOracleParams(OracleParams {
+                    stale_slots,
+                    max_confidence_bps,
+                    min_publishers,
+                })
             }
-            Self::SetFarmRewards(FarmRewards {
-                apr_numerator,
-                apr_denominator,
-            }) => {
+            116 => {
+                let (index, _) = unpack_u8(rest)?;
+                Self::SetMarketMaker(index)
+            }
+            117 => Self::RotateFeeAccount,
+            118 => {
+                let (price_hash, _) = unpack_bytes32(rest)?;
+                Self::CommitInitialPrice(price_hash)
+            }
+            119 => {
+                let (pegged_price, _) = unpack_u128(rest)?;
+                Self::CommitPeggedPrice(pegged_price)
+            }
+            120 => Self::ApplyPeggedPrice,
+            121 => {
+                let (role, _) = unpack_u8(rest)?;
+                Self::SetAdminRole(role)
+            }
+            122 => Self::PauseAll,
+            123 => {
+                let (strict_destination_owner, _) = unpack_u8(rest)?;
+                Self::SetStrictDestinationOwner(match strict_destination_owner {
+                    0 => false,
+                    1 => true,
+                    _ => return Err(SwapError::InvalidInstruction.into()),
+                })
+            }
+            124 => Self::EnableFarmUserRegistry,
+            125 => {
+                let (pause_flags, _) = unpack_u8(rest)?;
+                Self::SetPauseFlags(pause_flags)
+            }
+            126 => Self::ClaimProtocolFees,
+            127 => {
+                let (numerator, rest) = unpack_u64(rest)?;
+                let (denominator, _) = unpack_u64(rest)?;
+                Self::SetProtocolFee(numerator, denominator)
+            }
+            128 => {
+                let (authority_type, _) = unpack_u8(rest)?;
+                Self::SetPoolAuthority(authority_type)
+            }
+            129 => {
+                let (authority_type, _) = unpack_u8(rest)?;
+                Self::SetMarketAuthority(authority_type)
+            }
+            130 => Self::ClosePool,
+            131 => Self::SetOracleFeed,
+            132 => Self::EnableOracleFeedRegistry,
+            133 => Self::SetPriceCache,
+            134 => {
+                let (index, _) = unpack_u8(rest)?;
+                Self::CreateScratchAccount(index)
+            }
+            135 => {
+                let (max_admin_ops_per_epoch, _) = unpack_u16(rest)?;
+                Self::SetAdminOpsLimit(max_admin_ops_per_epoch)
+            }
+            136 => {
+                let mut tiers = [FeeDiscountTier::default(); MAX_FEE_DISCOUNT_TIERS];
+                let mut rest = rest;
+                for tier in tiers.iter_mut() {
+                    let (min_staked_amount, next) = unpack_u64(rest)?;
+                    let (discount_bps, next) = unpack_u16(next)?;
+                    *tier = FeeDiscountTier {
+                        min_staked_amount,
+                        discount_bps,
+                    };
+                    rest = next;
+                }
+                Self::SetFeeDiscountTiers(tiers)
+            }
+            137 => {
+                let (is_protocol_fee_enabled, _) = unpack_u8(rest)?;
+                Self::SetProtocolFeeSwitch(match is_protocol_fee_enabled {
+                    0 => false,
+                    1 => true,
+                    _ => return Err(SwapError::InvalidInstruction.into()),
+                })
+            }
+            138 => {
+                let (include_pinned, _) = unpack_u8(rest)?;
+                Self::SyncPoolFromConfig(match include_pinned {
+                    0 => false,
+                    1 => true,
+                    _ => return Err(SwapError::InvalidInstruction.into()),
+                })
+            }
+            139 => {
+                let (fees, rest) = rest.split_at(Fees::LEN);
+                let fees = Fees::unpack_unchecked(fees)?;
+                let (rewards, _rest) = rest.split_at(Rewards::LEN);
+                let rewards = Rewards::unpack_unchecked(rewards)?;
+                Self::SetConfigFees(AdminInitializeData { fees, rewards })
+            }
+            140 => {
+                let fees = Fees::unpack_unchecked(rest)?;
+                Self::BatchSetFees(fees)
+            }
+            141 => Self::BatchPause,
+            142 => {
+                let (reward_per_slot, _) = unpack_u64(rest)?;
+                Self::AddFarmRewardToken(reward_per_slot)
+            }
+            143 => {
+                let (index, rest) = unpack_u8(rest)?;
+                let (reward_per_slot, _) = unpack_u64(rest)?;
+                Self::SetFarmRewardRate(index, reward_per_slot)
+            }
+            144 => {
+                let (reward_per_slot, _) = unpack_u64(rest)?;
+                Self::SetFarmEmissionRate(reward_per_slot)
+            }
+            145 => {
+                let (amount, _) = unpack_u64(rest)?;
+                Self::FundStakingRewards(amount)
+            }
+            146 => {
+                let (rebalance_threshold_bps, _) = unpack_u16(rest)?;
+                Self::SetRebalanceThreshold(rebalance_threshold_bps)
+            }
+            147 => {
+                let (max_deviation_bps, rest) = unpack_u16(rest)?;
+                let (window_slots, _) = unpack_u64(rest)?;
+                Self::SetCircuitBreaker(max_deviation_bps, window_slots)
+            }
+            148 => {
+                let (max_total_supply, _) = unpack_u64(rest)?;
+                Self::SetDepositCap(max_total_supply)
+            }
+            149 => {
+                let (whitelist_enabled, _) = unpack_u8(rest)?;
+                Self::SetWhitelistEnabled(match whitelist_enabled {
+                    0 => false,
+                    1 => true,
+                    _ => return Err(SwapError::InvalidInstruction.into()),
+                })
+            }
+            150 => Self::AddWhitelistEntry,
+            151 => Self::RemoveWhitelistEntry,
+            152 => Self::MigrateSwapInfo,
+            153 => Self::MigrateConfigInfo,
+            154 => Self::MigrateFarmInfo,
+            155 => Self::SetOracleAccounts,
+            156 => {
+                let (oracle_priority_flags, _) = unpack_u8(rest)?;
+                Self::SetOraclePriority(oracle_priority_flags)
+            }
+            157 => {
+                let (max_change_bps, rest) = unpack_u16(rest)?;
+                let (stale_slots, _) = unpack_u64(rest)?;
+                Self::SetPriceSetter(max_change_bps, stale_slots)
+            }
+            158 => {
+                let (window_slots, _) = unpack_u64(rest)?;
+                Self::SetPriceEmaWindow(window_slots)
+            }
+            159 => {
+                let (skim_surplus, _) = unpack_u8(rest)?;
+                Self::SetSkimSurplus(match skim_surplus {
+                    0 => false,
+                    1 => true,
+                    _ => return Err(SwapError::InvalidInstruction.into()),
+                })
+            }
+            _ => return Err(SwapError::InvalidInstruction.into()),
+        })
+    }
+
+    /// Packs a [AdminInstruction](enum.AdminInstruciton.html) into a byte buffer.
+    pub fn pack(&self) -> Vec<u8> {
+        let mut buf = Vec::with_capacity(size_of::<Self>());
+        match &*self {
+            Self::Initialize(AdminInitializeData { fees, rewards }) => {
+                buf.push(100);
+                let mut fees_slice = [0u8; Fees::LEN];
+                Pack::pack_into_slice(fees, &mut fees_slice[..]);
+                buf.extend_from_slice(&fees_slice);
+                let mut rewards_slice = [0u8; Rewards::LEN];
+                Pack::pack_into_slice(rewards, &mut rewards_slice[..]);
+                buf.extend_from_slice(&rewards_slice);
+            }
+            Self::Pause => buf.push(101),
+            Self::Unpause => buf.push(102),
+            Self::SetFeeAccount => buf.push(103),
+            Self::CommitNewAdmin(CommitNewAdmin { new_admin_key }) => {
+                buf.push(104);
+                buf.extend_from_slice(new_admin_key.as_ref());
+            }
+            Self::SetNewFees(fees) => {
+                buf.push(105);
+                let mut fees_slice = [0u8; Fees::LEN];
+                Pack::pack_into_slice(fees, &mut fees_slice[..]);
+                buf.extend_from_slice(&fees_slice);
+            }
+            Self::SetNewRewards(rewards) => {
+                buf.push(106);
+                let mut rewards_slice = [0u8; Rewards::LEN];
+                Pack::pack_into_slice(rewards, &mut rewards_slice[..]);
+                buf.extend_from_slice(&rewards_slice);
+            }
+            Self::SetFarmRewards(FarmRewards {
+                apr_numerator,
+                apr_denominator,
+            }) => {
                 buf.push(107);
                 buf.extend_from_slice(&apr_numerator.to_le_bytes());
                 buf.extend_from_slice(&apr_denominator.to_le_bytes());
@@ -342,6 +1155,189 @@ impl AdminInstruction {
                 buf.push(110);
                 buf.extend_from_slice(&swap_out_limit_percentage.to_le_bytes());
             }
+            Self::SetPriceJumpPolicy(price_jump_policy) => {
+                buf.push(111);
+                buf.extend_from_slice(&price_jump_policy.to_le_bytes());
+            }
+            Self::SetMaxPriceDivergence(max_price_divergence) => {
+                buf.push(112);
+                buf.extend_from_slice(&max_price_divergence.to_le_bytes());
+            }
+            Self::SetOrderbookProgram => buf.push(113),
+            Self::SetExchangeRateAdapter(adapter_type) => {
+                buf.push(114);
+                buf.extend_from_slice(&adapter_type.to_le_bytes());
+            }
+            Self::SetOracleParams(OracleParams {
+                stale_slots,
+                max_confidence_bps,
+                min_publishers,
+            }) => {
+                buf.push(115);
+                buf.extend_from_slice(&stale_slots.to_le_bytes());
+                buf.extend_from_slice(&max_confidence_bps.to_le_bytes());
+                buf.extend_from_slice(&min_publishers.to_le_bytes());
+            }
+            Self::SetMarketMaker(index) => {
+                buf.push(116);
+                buf.extend_from_slice(&index.to_le_bytes());
+            }
+            Self::RotateFeeAccount => {
+                buf.push(117);
+            }
+            Self::CommitInitialPrice(price_hash) => {
+                buf.push(118);
+                buf.extend_from_slice(price_hash);
+            }
+            Self::CommitPeggedPrice(pegged_price) => {
+                buf.push(119);
+                buf.extend_from_slice(&pegged_price.to_le_bytes());
+            }
+            Self::ApplyPeggedPrice => {
+                buf.push(120);
+            }
+            Self::SetAdminRole(role) => {
+                buf.push(121);
+                buf.extend_from_slice(&role.to_le_bytes());
+            }
+            Self::PauseAll => {
+                buf.push(122);
+            }
+            Self::SetStrictDestinationOwner(strict_destination_owner) => {
+                buf.push(123);
+                buf.push(*strict_destination_owner as u8);
+            }
+            Self::EnableFarmUserRegistry => {
+                buf.push(124);
+            }
+            Self::SetPauseFlags(pause_flags) => {
+                buf.push(125);
+                buf.extend_from_slice(&pause_flags.to_le_bytes());
+            }
+            Self::ClaimProtocolFees => {
+                buf.push(126);
+            }
+            Self::SetProtocolFee(numerator, denominator) => {
+                buf.push(127);
+                buf.extend_from_slice(&numerator.to_le_bytes());
+                buf.extend_from_slice(&denominator.to_le_bytes());
+            }
+            Self::SetPoolAuthority(authority_type) => {
+                buf.push(128);
+                buf.extend_from_slice(&authority_type.to_le_bytes());
+            }
+            Self::SetMarketAuthority(authority_type) => {
+                buf.push(129);
+                buf.extend_from_slice(&authority_type.to_le_bytes());
+            }
+            Self::ClosePool => {
+                buf.push(130);
+            }
+            Self::SetOracleFeed => {
+                buf.push(131);
+            }
+            Self::EnableOracleFeedRegistry => {
+                buf.push(132);
+            }
+            Self::SetPriceCache => {
+                buf.push(133);
+            }
+            Self::CreateScratchAccount(index) => {
+                buf.push(134);
+                buf.push(*index);
+            }
+            Self::SetAdminOpsLimit(max_admin_ops_per_epoch) => {
+                buf.push(135);
+                buf.extend_from_slice(&max_admin_ops_per_epoch.to_le_bytes());
+            }
+            Self::SetFeeDiscountTiers(ref tiers) => {
+                buf.push(136);
+                for tier in tiers.iter() {
+                    buf.extend_from_slice(&tier.min_staked_amount.to_le_bytes());
+                    buf.extend_from_slice(&tier.discount_bps.to_le_bytes());
+                }
+            }
+            Self::SetProtocolFeeSwitch(is_protocol_fee_enabled) => {
+                buf.push(137);
+                buf.push(*is_protocol_fee_enabled as u8);
+            }
+            Self::SyncPoolFromConfig(include_pinned) => {
+                buf.push(138);
+                buf.push(*include_pinned as u8);
+            }
+            Self::SetConfigFees(AdminInitializeData { fees, rewards }) => {
+                buf.push(139);
+                let mut fees_slice = [0u8; Fees::LEN];
+                Pack::pack_into_slice(fees, &mut fees_slice[..]);
+                buf.extend_from_slice(&fees_slice);
+                let mut rewards_slice = [0u8; Rewards::LEN];
+                Pack::pack_into_slice(rewards, &mut rewards_slice[..]);
+                buf.extend_from_slice(&rewards_slice);
+            }
+            Self::BatchSetFees(fees) => {
+                buf.push(140);
+                let mut fees_slice = [0u8; Fees::LEN];
+                Pack::pack_into_slice(fees, &mut fees_slice[..]);
+                buf.extend_from_slice(&fees_slice);
+            }
+            Self::BatchPause => buf.push(141),
+            Self::AddFarmRewardToken(reward_per_slot) => {
+                buf.push(142);
+                buf.extend_from_slice(&reward_per_slot.to_le_bytes());
+            }
+            Self::SetFarmRewardRate(index, reward_per_slot) => {
+                buf.push(143);
+                buf.push(*index);
+                buf.extend_from_slice(&reward_per_slot.to_le_bytes());
+            }
+            Self::SetFarmEmissionRate(reward_per_slot) => {
+                buf.push(144);
+                buf.extend_from_slice(&reward_per_slot.to_le_bytes());
+            }
+            Self::FundStakingRewards(amount) => {
+                buf.push(145);
+                buf.extend_from_slice(&amount.to_le_bytes());
+            }
+            Self::SetRebalanceThreshold(rebalance_threshold_bps) => {
+                buf.push(146);
+                buf.extend_from_slice(&rebalance_threshold_bps.to_le_bytes());
+            }
+            Self::SetCircuitBreaker(max_deviation_bps, window_slots) => {
+                buf.push(147);
+                buf.extend_from_slice(&max_deviation_bps.to_le_bytes());
+                buf.extend_from_slice(&window_slots.to_le_bytes());
+            }
+            Self::SetDepositCap(max_total_supply) => {
+                buf.push(148);
+                buf.extend_from_slice(&max_total_supply.to_le_bytes());
+            }
+            Self::SetWhitelistEnabled(whitelist_enabled) => {
+                buf.push(149);
+                buf.push(*whitelist_enabled as u8);
+            }
+            Self::AddWhitelistEntry => buf.push(150),
+            Self::RemoveWhitelistEntry => buf.push(151),
+            Self::MigrateSwapInfo => buf.push(152),
+            Self::MigrateConfigInfo => buf.push(153),
+            Self::MigrateFarmInfo => buf.push(154),
+            Self::SetOracleAccounts => buf.push(155),
+            Self::SetOraclePriority(oracle_priority_flags) => {
+                buf.push(156);
+                buf.push(*oracle_priority_flags);
+            }
+            Self::SetPriceSetter(max_change_bps, stale_slots) => {
+                buf.push(157);
+                buf.extend_from_slice(&max_change_bps.to_le_bytes());
+                buf.extend_from_slice(&stale_slots.to_le_bytes());
+            }
+            Self::SetPriceEmaWindow(window_slots) => {
+                buf.push(158);
+                buf.extend_from_slice(&window_slots.to_le_bytes());
+            }
+            Self::SetSkimSurplus(skim_surplus) => {
+                buf.push(159);
+                buf.push(*skim_surplus as u8);
+            }
         }
         buf
     }
@@ -613,309 +1609,65 @@ pub fn set_swap_limit(
     })
 }
 
-/// Instructions supported by the pool SwapInfo program.
-#[repr(C)]
-#[derive(Debug, PartialEq)]
-pub enum SwapInstruction {
-    ///   Initializes a new SwapInfo.
-    ///
-    ///   0. `[]` market config.
-    ///   1. `[writable]` new token-swap to create.
-    ///   2. `[]` $swap_authority.
-    ///   3. `[]` admin fee account for base token.
-    ///   4. `[]` admin fee account for quote token.
-    ///   5. `[]` base token account. Must be non zero, owned by $swap_authority.
-    ///   6. `[]` quote token account. Must be non zero, owned by $swap_authority.
-    ///   7. `[writable]` pool mint account mint by $swap_authority.
-    ///   8. `[writable]` pool token account owned by user.
-    ///   9. `[]` base token product from pyth network.
-    ///   10. `[]` base token price from pyth network.
-    ///   11. `[]` quote token product from pyth network.
-    ///   12. `[]` quote token price from pyth network.
-    ///   13. `[]` admin account.
-    ///   14. '[]' serum market account
-    ///   15. '[]' serum bids orderbook account
-    ///   16. '[]' serum asks orderbook account
-    Initialize(InitializeData),
+/// Creates `set_price_jump_policy` instruction
+pub fn set_price_jump_policy(
+    program_id: Pubkey,
+    config_pubkey: Pubkey,
+    swap_pubkey: Pubkey,
+    admin_pubkey: Pubkey,
+    price_jump_policy: u8,
+) -> Result<Instruction, ProgramError> {
+    let data = AdminInstruction::SetPriceJumpPolicy(price_jump_policy).pack();
 
-    ///   Swap the tokens in the pool.
-    ///
-    ///   0. `[]` market config.
-    ///   1. `[writable]` token-swap.
-    ///   2. `[]` $market_authority to mint deltafi token.
-    ///   3. `[]` $swap_authority.
-    ///   4. `[signer]` $user_transfer_authority
-    ///   5. `[writable]` SOURCE(base|quote) account, transferable by $user_transfer_authority.
-    ///   6. `[writable]` (base|quote) token account to swap INTO. Must be the SOURCE token.
-    ///   7. `[]` Mint account that provides mint info including decimals of the SOURCE token,
-    ///   8. `[writable]` (base|quote) token account to swap FROM. Must be the DESTINATION token.
-    ///   9. `[writable]` DESTINATION(base|quote) account owned by user.
-    ///   10. `[]` mint account that provides mint info including decimals of the DESTINATION token.
-    ///   11. `[writable]` rewards(DELTAFI) token account owned by user.
-    ///   12. `[writable]` rewards(DELTAFI) source deltafi token account to issue reward.
-    ///   13. `[writable]` (base|quote) admin fee account. Must have same mint as DESTINATION token.
-    ///   14. `[]` base token price from pyth network.
-    ///   15. `[]` quote token price from pyth network.
-    ///   16. `[]` token program id.
-    ///   17. `[]` optional: user referrer data account.
-    ///   18. `[writable]` optional: referrer token account.
-    Swap(SwapData),
+    let accounts = vec![
+        AccountMeta::new_readonly(config_pubkey, false),
+        AccountMeta::new(swap_pubkey, false),
+        AccountMeta::new_readonly(admin_pubkey, true),
+    ];
 
-    ///   Deposit some tokens into the pool.  The output is a "pool" token representing ownership
-    ///   into the pool. Inputs are converted to the current ratio.
-    ///
-    ///   0. `[]` token-swap.
-    ///   1. `[]` $swap_authority.
-    ///   2. `[signer]` $user_transfer_authority.
-    ///   3. `[writable]` base token account to deposit FROM, transferable by $user_transfer_authority.
-    ///   4. `[writable]` quote token account to deposit FROM, transferable by $user_transfer_authority.
-    ///   5. `[writable]` base token account to deposit INTO.
-    ///   6. `[writable]` quote token account to deposit INTO.
-    ///   7. `[writable]` pool mint account, mint by $swap_authority.
-    ///   8. `[writable]` pool token account owned by user.
-    ///   9. `[]` base token price from pyth network.
-    ///   10. `[]` quote token price from pyth network.
-    ///   11. `[]` clock sysvar.
-    ///   12. `[]` token program id.
-    Deposit(DepositData),
+    Ok(Instruction {
+        program_id,
+        accounts,
+        data,
+    })
+}
 
-    ///   Withdraw tokens from the pool at the current ratio.
-    ///
-    ///   0. `[]` token-swap.
-    ///   1. `[]` $swap_authority.
-    ///   2. `[signer]` $user_transfer_authority.
-    ///   3. `[writable]` pool mint account, mint by $swap_autority.
-    ///   4. `[writable]` SOURCE pool token account, transferable by $user_transfer_authority.
-    ///   5. `[writable]` base token account to withdraw FROM.
-    ///   6. `[writable]` quote token account to withdraw FROM.
-    ///   7. `[writable]` base token account to withdraw INTO.
-    ///   8. `[writable]` quote token account to withdraw INTO.
-    ///   9. `[writable]` admin fee account for base token.
-    ///   10. `[writable]` admin fee account for quote token.
-    ///   11. `[]` base token price from pyth network.
-    ///   12. `[]` quote token price from pyth network.
-    ///   13. `[]` clock sysvar.
-    ///   14. `[]` token program id.
-    Withdraw(WithdrawData),
+/// Creates `set_max_price_divergence` instruction
+pub fn set_max_price_divergence(
+    program_id: Pubkey,
+    config_pubkey: Pubkey,
+    swap_pubkey: Pubkey,
+    admin_pubkey: Pubkey,
+    max_price_divergence: u16,
+) -> Result<Instruction, ProgramError> {
+    let data = AdminInstruction::SetMaxPriceDivergence(max_price_divergence).pack();
 
-    ///   Withdraw tokens from the pool at the current ratio.
-    ///
-    ///   0. `[]` market config.
-    ///   1. `[signer]` user.
-    ///   2. `[writable]` user referrer data account
-    ///   3. `[]` referrer token address.
-    ///   4. `[]` rent sysvar.
-    ///   5. `[]` token program id.
-    SetReferrer,
+    let accounts = vec![
+        AccountMeta::new_readonly(config_pubkey, false),
+        AccountMeta::new(swap_pubkey, false),
+        AccountMeta::new_readonly(admin_pubkey, true),
+    ];
 
-    ///   Swap the tokens in the pool (removed src and dest mint accounts).
-    ///
-    ///   0. `[]` market config.
-    ///   1. `[writable]` token-swap.
-    ///   2. `[]` $market_authority to mint deltafi token.
-    ///   3. `[]` $swap_authority.
-    ///   4. `[signer]` $user_transfer_authority
-    ///   5. `[writable]` SOURCE(base|quote) account, transferable by $user_transfer_authority.
-    ///   6. `[writable]` (base|quote) token account to swap INTO. Must be the SOURCE token.
-    ///   7. `[writable]` (base|quote) token account to swap FROM. Must be the DESTINATION token.
-    ///   8. `[writable]` DESTINATION(base|quote) account owned by user.
-    ///   9. `[writable]` rewards(DELTAFI) token account owned by user.
-    ///   10. `[writable]` rewards(DELTAFI) source deltafi token account to issue reward.
-    ///   11. `[writable]` (base|quote) admin fee account. Must have same mint as DESTINATION token.
-    ///   12. `[]` base token price from pyth network.
-    ///   13. `[]` quote token price from pyth network.
-    ///   14. '[]' serum market account
-    ///   15. '[]' serum bids orderbook account
-    ///   16. '[]' serum asks orderbook account
-    ///   17. `[]` token program id.
-    ///   18. `[]` optional: user referrer data account.
-    ///   19. `[writable]` optional: referrer token account.
-    SwapV2(SwapData),
-}
-
-impl SwapInstruction {
-    /// Unpacks a byte buffer into a [SwapInstruction](enum.SwapInstruction.html).
-    pub fn unpack(input: &[u8]) -> Result<Self, ProgramError> {
-        let (&tag, rest) = input
-            .split_first()
-            .ok_or(SwapError::InstructionUnpackError)?;
-        Ok(match tag {
-            0 => {
-                let (&nonce, rest) = rest
-                    .split_first()
-                    .ok_or(SwapError::InstructionUnpackError)?;
-                let (slope, rest) = unpack_u64(rest)?;
-                let (mid_price, rest) = unpack_u128(rest)?;
-                let (token_a_decimals, rest) = unpack_u8(rest)?;
-                let (token_b_decimals, rest) = unpack_u8(rest)?;
-                let (token_a_amount, rest) = unpack_u64(rest)?;
-                let (token_b_amount, rest) = unpack_u64(rest)?;
-                let (oracle_priority_flags, _) = unpack_u8(rest)?;
-                Self::Initialize(InitializeData {
-                    nonce,
-                    slope,
-                    mid_price,
-                    token_a_decimals,
-                    token_b_decimals,
-                    token_a_amount,
-                    token_b_amount,
-                    oracle_priority_flags,
-                })
-            }
-            1 => {
-                let (amount_in, rest) = unpack_u64(rest)?;
-                let (minimum_amount_out, _) = unpack_u64(rest)?;
-                Self::Swap(SwapData {
-                    amount_in,
-                    minimum_amount_out,
-                })
-            }
-            2 => {
-                let (token_a_amount, rest) = unpack_u64(rest)?;
-                let (token_b_amount, rest) = unpack_u64(rest)?;
-                let (min_mint_amount, _) = unpack_u64(rest)?;
-                Self::Deposit(DepositData {
-                    token_a_amount,
-                    token_b_amount,
-                    min_mint_amount,
-                })
-            }
-            3 => {
-                let (pool_token_amount, rest) = unpack_u64(rest)?;
-                let (minimum_token_a_amount, rest) = unpack_u64(rest)?;
-                let (minimum_token_b_amount, _) = unpack_u64(rest)?;
-                Self::Withdraw(WithdrawData {
-                    pool_token_amount,
-                    minimum_token_a_amount,
-                    minimum_token_b_amount,
-                })
-            }
-            4 => Self::SetReferrer,
-            5 => {
-                let (amount_in, rest) = unpack_u64(rest)?;
-                let (minimum_amount_out, _) = unpack_u64(rest)?;
-                Self::SwapV2(SwapData {
-                    amount_in,
-                    minimum_amount_out,
-                })
-            }
-            _ => return Err(SwapError::InvalidInstruction.into()),
-        })
-    }
-
-    /// Packs a [SwapInstruction](enum.SwapInstruction.html) into a byte buffer.
-    pub fn pack(&self) -> Vec<u8> {
-        let mut buf = Vec::with_capacity(size_of::<Self>());
-        match *self {
-            Self::Initialize(InitializeData {
-                nonce,
-                slope,
-                mid_price,
-                token_a_decimals,
-                token_b_decimals,
-                token_a_amount,
-                token_b_amount,
-                oracle_priority_flags,
-            }) => {
-                buf.push(0);
-                buf.push(nonce);
-                buf.extend_from_slice(&slope.to_le_bytes());
-                buf.extend_from_slice(&mid_price.to_le_bytes());
-                buf.push(token_a_decimals);
-                buf.push(token_b_decimals);
-                buf.extend_from_slice(&token_a_amount.to_le_bytes());
-                buf.extend_from_slice(&token_b_amount.to_le_bytes());
-                buf.extend_from_slice(&oracle_priority_flags.to_le_bytes());
-            }
-            Self::Swap(SwapData {
-                amount_in,
-                minimum_amount_out,
-            }) => {
-                buf.push(1);
-                buf.extend_from_slice(&amount_in.to_le_bytes());
-                buf.extend_from_slice(&minimum_amount_out.to_le_bytes());
-            }
-            Self::Deposit(DepositData {
-                token_a_amount,
-                token_b_amount,
-                min_mint_amount,
-            }) => {
-                buf.push(2);
-                buf.extend_from_slice(&token_a_amount.to_le_bytes());
-                buf.extend_from_slice(&token_b_amount.to_le_bytes());
-                buf.extend_from_slice(&min_mint_amount.to_le_bytes());
-            }
-            Self::Withdraw(WithdrawData {
-                pool_token_amount,
-                minimum_token_a_amount,
-                minimum_token_b_amount,
-            }) => {
-                buf.push(3);
-                buf.extend_from_slice(&pool_token_amount.to_le_bytes());
-                buf.extend_from_slice(&minimum_token_a_amount.to_le_bytes());
-                buf.extend_from_slice(&minimum_token_b_amount.to_le_bytes());
-            }
-            Self::SetReferrer => {
-                buf.push(4);
-            }
-            Self::SwapV2(SwapData {
-                amount_in,
-                minimum_amount_out,
-            }) => {
-                buf.push(5);
-                buf.extend_from_slice(&amount_in.to_le_bytes());
-                buf.extend_from_slice(&minimum_amount_out.to_le_bytes());
-            }
-        }
-        buf
-    }
+    Ok(Instruction {
+        program_id,
+        accounts,
+        data,
+    })
 }
 
-/// Creates an 'initialize' instruction.
-pub fn initialize(
+/// Creates `set_orderbook_program` instruction
+pub fn set_orderbook_program(
     program_id: Pubkey,
     config_pubkey: Pubkey,
-    swap_pubkey: Pubkey,
-    authority_pubkey: Pubkey,
-    admin_fee_a_pubkey: Pubkey,
-    admin_fee_b_pubkey: Pubkey,
-    token_a_pubkey: Pubkey,
-    token_b_pubkey: Pubkey,
-    pool_mint_pubkey: Pubkey,
-    destination_pubkey: Pubkey,
-    pyth_a_product_pubkey: Pubkey,
-    pyth_a_pubkey: Pubkey,
-    pyth_b_product_pubkey: Pubkey,
-    pyth_b_pubkey: Pubkey,
     admin_pubkey: Pubkey,
-    serum_market_pubkey: Pubkey,
-    serum_bids_pubkey: Pubkey,
-    serum_asks_pubkey: Pubkey,
-    init_data: InitializeData,
+    orderbook_program_id: Pubkey,
 ) -> Result<Instruction, ProgramError> {
-    let data = SwapInstruction::Initialize(init_data).pack();
+    let data = AdminInstruction::SetOrderbookProgram.pack();
 
     let accounts = vec![
-        AccountMeta::new_readonly(config_pubkey, false),
-        AccountMeta::new(swap_pubkey, true),
-        AccountMeta::new_readonly(authority_pubkey, false),
-        AccountMeta::new_readonly(admin_fee_a_pubkey, false),
-        AccountMeta::new_readonly(admin_fee_b_pubkey, false),
-        AccountMeta::new_readonly(token_a_pubkey, false),
-        AccountMeta::new_readonly(token_b_pubkey, false),
-        AccountMeta::new(pool_mint_pubkey, false),
-        AccountMeta::new(destination_pubkey, false),
-        AccountMeta::new_readonly(pyth_a_product_pubkey, false),
-        AccountMeta::new_readonly(pyth_a_pubkey, false),
-        AccountMeta::new_readonly(pyth_b_product_pubkey, false),
-        AccountMeta::new_readonly(pyth_b_pubkey, false),
+        AccountMeta::new(config_pubkey, false),
         AccountMeta::new_readonly(admin_pubkey, true),
-        AccountMeta::new_readonly(serum_market_pubkey, false),
-        AccountMeta::new_readonly(serum_bids_pubkey, false),
-        AccountMeta::new_readonly(serum_asks_pubkey, false),
-        AccountMeta::new_readonly(clock::id(), false),
-        AccountMeta::new_readonly(rent::id(), false),
-        AccountMeta::new_readonly(spl_token::id(), false),
+        AccountMeta::new_readonly(orderbook_program_id, false),
     ];
 
     Ok(Instruction {
@@ -925,24 +1677,25 @@ pub fn initialize(
     })
 }
 
-/// Creates a 'SetReferrer' instruction.
-pub fn set_referrer(
+/// Creates `set_exchange_rate_adapter` instruction
+pub fn set_exchange_rate_adapter(
     program_id: Pubkey,
     config_pubkey: Pubkey,
-    user_pubkey: Pubkey,
-    user_referrer_data_pubkey: Pubkey,
-    referrer_token_pubkey: Pubkey,
+    swap_pubkey: Pubkey,
+    admin_pubkey: Pubkey,
+    exchange_rate_pubkey: Option<Pubkey>,
+    adapter_type: u8,
 ) -> Result<Instruction, ProgramError> {
-    let data = SwapInstruction::SetReferrer.pack();
+    let data = AdminInstruction::SetExchangeRateAdapter(adapter_type).pack();
 
-    let accounts = vec![
+    let mut accounts = vec![
         AccountMeta::new_readonly(config_pubkey, false),
-        AccountMeta::new_readonly(user_pubkey, true),
-        AccountMeta::new(user_referrer_data_pubkey, false),
-        AccountMeta::new_readonly(referrer_token_pubkey, false),
-        AccountMeta::new_readonly(rent::id(), false),
-        AccountMeta::new_readonly(spl_token::id(), false),
+        AccountMeta::new(swap_pubkey, false),
+        AccountMeta::new_readonly(admin_pubkey, true),
     ];
+    if let Some(exchange_rate_pubkey) = exchange_rate_pubkey {
+        accounts.push(AccountMeta::new_readonly(exchange_rate_pubkey, false));
+    }
 
     Ok(Instruction {
         program_id,
@@ -951,58 +1704,22 @@ pub fn set_referrer(
     })
 }
 
-/// Creates a 'swap' instruction.
-pub fn swap(
+/// Creates `set_oracle_params` instruction
+pub fn set_oracle_params(
     program_id: Pubkey,
     config_pubkey: Pubkey,
     swap_pubkey: Pubkey,
-    market_authority_pubkey: Pubkey,
-    swap_authority_pubkey: Pubkey,
-    user_transfer_authority_pubkey: Pubkey,
-    source_pubkey: Pubkey,
-    swap_source_pubkey: Pubkey,
-    source_mint_pubkey: Pubkey,
-    swap_destination_pubkey: Pubkey,
-    destination_pubkey: Pubkey,
-    destination_mint_pubkey: Pubkey,
-    reward_token_pubkey: Pubkey,
-    source_reward_token_pubkey: Pubkey,
-    admin_fee_destination_pubkey: Pubkey,
-    pyth_a_pubkey: Pubkey,
-    pyth_b_pubkey: Pubkey,
-    user_referrer_data_pubkey: Option<Pubkey>,
-    referrer_token_pubkey: Option<Pubkey>,
-    swap_data: SwapData,
+    admin_pubkey: Pubkey,
+    oracle_params: OracleParams,
 ) -> Result<Instruction, ProgramError> {
-    let data = SwapInstruction::Swap(swap_data).pack();
+    let data = AdminInstruction::SetOracleParams(oracle_params).pack();
 
-    let mut accounts = vec![
+    let accounts = vec![
         AccountMeta::new_readonly(config_pubkey, false),
         AccountMeta::new(swap_pubkey, false),
-        AccountMeta::new_readonly(market_authority_pubkey, false),
-        AccountMeta::new_readonly(swap_authority_pubkey, false),
-        AccountMeta::new_readonly(user_transfer_authority_pubkey, true),
-        AccountMeta::new(source_pubkey, false),
-        AccountMeta::new(swap_source_pubkey, false),
-        AccountMeta::new_readonly(source_mint_pubkey, false),
-        AccountMeta::new(swap_destination_pubkey, false),
-        AccountMeta::new(destination_pubkey, false),
-        AccountMeta::new_readonly(destination_mint_pubkey, false),
-        AccountMeta::new(reward_token_pubkey, false),
-        AccountMeta::new(source_reward_token_pubkey, false),
-        AccountMeta::new(admin_fee_destination_pubkey, false),
-        AccountMeta::new_readonly(pyth_a_pubkey, false),
-        AccountMeta::new_readonly(pyth_b_pubkey, false),
-        AccountMeta::new_readonly(spl_token::id(), false),
+        AccountMeta::new_readonly(admin_pubkey, true),
     ];
 
-    if let Some(user_referrer_data_pubkey) = user_referrer_data_pubkey {
-        accounts.extend_from_slice(&[
-            AccountMeta::new_readonly(user_referrer_data_pubkey, false),
-            AccountMeta::new(referrer_token_pubkey.unwrap(), false),
-        ]);
-    }
-
     Ok(Instruction {
         program_id,
         accounts,
@@ -1010,60 +1727,22 @@ pub fn swap(
     })
 }
 
-/// Creates a 'swapV2' instruction.
-pub fn swap_v2(
+/// Creates `set_market_maker` instruction
+pub fn set_market_maker(
     program_id: Pubkey,
     config_pubkey: Pubkey,
-    swap_pubkey: Pubkey,
-    market_authority_pubkey: Pubkey,
-    swap_authority_pubkey: Pubkey,
-    user_transfer_authority_pubkey: Pubkey,
-    source_pubkey: Pubkey,
-    swap_source_pubkey: Pubkey,
-    swap_destination_pubkey: Pubkey,
-    destination_pubkey: Pubkey,
-    reward_token_pubkey: Pubkey,
-    source_reward_token_pubkey: Pubkey,
-    admin_fee_destination_pubkey: Pubkey,
-    pyth_a_pubkey: Pubkey,
-    pyth_b_pubkey: Pubkey,
-    serum_market_pubkey: Pubkey,
-    serum_bids_pubkey: Pubkey,
-    serum_asks_pubkey: Pubkey,
-    user_referrer_data_pubkey: Option<Pubkey>,
-    referrer_token_pubkey: Option<Pubkey>,
-    swap_data: SwapData,
+    admin_pubkey: Pubkey,
+    market_maker_pubkey: Pubkey,
+    index: u8,
 ) -> Result<Instruction, ProgramError> {
-    let data = SwapInstruction::SwapV2(swap_data).pack();
+    let data = AdminInstruction::SetMarketMaker(index).pack();
 
-    let mut accounts = vec![
-        AccountMeta::new_readonly(config_pubkey, false),
-        AccountMeta::new(swap_pubkey, false),
-        AccountMeta::new_readonly(market_authority_pubkey, false),
-        AccountMeta::new_readonly(swap_authority_pubkey, false),
-        AccountMeta::new_readonly(user_transfer_authority_pubkey, true),
-        AccountMeta::new(source_pubkey, false),
-        AccountMeta::new(swap_source_pubkey, false),
-        AccountMeta::new(swap_destination_pubkey, false),
-        AccountMeta::new(destination_pubkey, false),
-        AccountMeta::new(reward_token_pubkey, false),
-        AccountMeta::new(source_reward_token_pubkey, false),
-        AccountMeta::new(admin_fee_destination_pubkey, false),
-        AccountMeta::new_readonly(pyth_a_pubkey, false),
-        AccountMeta::new_readonly(pyth_b_pubkey, false),
-        AccountMeta::new_readonly(serum_market_pubkey, false),
-        AccountMeta::new_readonly(serum_bids_pubkey, false),
-        AccountMeta::new_readonly(serum_asks_pubkey, false),
-        AccountMeta::new_readonly(spl_token::id(), false),
+    let accounts = vec![
+        AccountMeta::new(config_pubkey, false),
+        AccountMeta::new_readonly(admin_pubkey, true),
+        AccountMeta::new_readonly(market_maker_pubkey, false),
     ];
 
-    if let Some(user_referrer_data_pubkey) = user_referrer_data_pubkey {
-        accounts.extend_from_slice(&[
-            AccountMeta::new_readonly(user_referrer_data_pubkey, false),
-            AccountMeta::new(referrer_token_pubkey.unwrap(), false),
-        ]);
-    }
-
     Ok(Instruction {
         program_id,
         accounts,
@@ -1071,31 +1750,26 @@ pub fn swap_v2(
     })
 }
 
-/// Creates a 'deposit' instruction.
-pub fn deposit(
+/// Creates `rotate_fee_account` instruction
+pub fn rotate_fee_account(
     program_id: Pubkey,
+    config_pubkey: Pubkey,
     swap_pubkey: Pubkey,
     authority_pubkey: Pubkey,
-    user_transfer_authority_pubkey: Pubkey,
-    deposit_token_a_pubkey: Pubkey,
-    deposit_token_b_pubkey: Pubkey,
-    swap_token_a_pubkey: Pubkey,
-    swap_token_b_pubkey: Pubkey,
-    pool_mint_pubkey: Pubkey,
+    admin_pubkey: Pubkey,
+    old_fee_account_pubkey: Pubkey,
+    new_fee_account_pubkey: Pubkey,
     destination_pubkey: Pubkey,
-    deposit_data: DepositData,
 ) -> Result<Instruction, ProgramError> {
-    let data = SwapInstruction::Deposit(deposit_data).pack();
+    let data = AdminInstruction::RotateFeeAccount.pack();
 
     let accounts = vec![
+        AccountMeta::new_readonly(config_pubkey, false),
         AccountMeta::new(swap_pubkey, false),
         AccountMeta::new_readonly(authority_pubkey, false),
-        AccountMeta::new_readonly(user_transfer_authority_pubkey, true),
-        AccountMeta::new(deposit_token_a_pubkey, false),
-        AccountMeta::new(deposit_token_b_pubkey, false),
-        AccountMeta::new(swap_token_a_pubkey, false),
-        AccountMeta::new(swap_token_b_pubkey, false),
-        AccountMeta::new(pool_mint_pubkey, false),
+        AccountMeta::new_readonly(admin_pubkey, true),
+        AccountMeta::new(old_fee_account_pubkey, false),
+        AccountMeta::new(new_fee_account_pubkey, false),
         AccountMeta::new(destination_pubkey, false),
         AccountMeta::new_readonly(spl_token::id(), false),
     ];
@@ -1107,34 +1781,196 @@ pub fn deposit(
     })
 }
 
-/// Creates a 'withdraw' instruction.
-pub fn withdraw(
+/// Creates a 'CommitInitialPrice' instruction.
+pub fn commit_initial_price(
+    program_id: Pubkey,
+    config_pubkey: Pubkey,
+    price_commitment_pubkey: Pubkey,
+    swap_pubkey: Pubkey,
+    admin_pubkey: Pubkey,
+    price_hash: [u8; 32],
+) -> Result<Instruction, ProgramError> {
+    let data = AdminInstruction::CommitInitialPrice(price_hash).pack();
+
+    let accounts = vec![
+        AccountMeta::new_readonly(config_pubkey, false),
+        AccountMeta::new(price_commitment_pubkey, false),
+        AccountMeta::new_readonly(swap_pubkey, false),
+        AccountMeta::new_readonly(admin_pubkey, true),
+        AccountMeta::new_readonly(clock::id(), false),
+        AccountMeta::new_readonly(rent::id(), false),
+    ];
+
+    Ok(Instruction {
+        program_id,
+        accounts,
+        data,
+    })
+}
+
+/// Creates a 'CommitPeggedPrice' instruction.
+pub fn commit_pegged_price(
+    program_id: Pubkey,
+    config_pubkey: Pubkey,
+    swap_pubkey: Pubkey,
+    admin_pubkey: Pubkey,
+    pegged_price: u128,
+) -> Result<Instruction, ProgramError> {
+    let data = AdminInstruction::CommitPeggedPrice(pegged_price).pack();
+
+    let accounts = vec![
+        AccountMeta::new_readonly(config_pubkey, false),
+        AccountMeta::new(swap_pubkey, false),
+        AccountMeta::new_readonly(admin_pubkey, true),
+        AccountMeta::new_readonly(clock::id(), false),
+    ];
+
+    Ok(Instruction {
+        program_id,
+        accounts,
+        data,
+    })
+}
+
+/// Creates an 'ApplyPeggedPrice' instruction.
+pub fn apply_pegged_price(
+    program_id: Pubkey,
+    config_pubkey: Pubkey,
+    swap_pubkey: Pubkey,
+    admin_pubkey: Pubkey,
+) -> Result<Instruction, ProgramError> {
+    let data = AdminInstruction::ApplyPeggedPrice.pack();
+
+    let accounts = vec![
+        AccountMeta::new_readonly(config_pubkey, false),
+        AccountMeta::new(swap_pubkey, false),
+        AccountMeta::new_readonly(admin_pubkey, true),
+        AccountMeta::new_readonly(clock::id(), false),
+    ];
+
+    Ok(Instruction {
+        program_id,
+        accounts,
+        data,
+    })
+}
+
+/// Creates a 'SetAdminRole' instruction.
+pub fn set_admin_role(
+    program_id: Pubkey,
+    config_pubkey: Pubkey,
+    admin_pubkey: Pubkey,
+    role_pubkey: Pubkey,
+    role: u8,
+) -> Result<Instruction, ProgramError> {
+    let data = AdminInstruction::SetAdminRole(role).pack();
+
+    let accounts = vec![
+        AccountMeta::new(config_pubkey, false),
+        AccountMeta::new_readonly(admin_pubkey, true),
+        AccountMeta::new_readonly(role_pubkey, false),
+    ];
+
+    Ok(Instruction {
+        program_id,
+        accounts,
+        data,
+    })
+}
+
+/// Creates a 'PauseAll' instruction.
+pub fn pause_all(
+    program_id: Pubkey,
+    config_pubkey: Pubkey,
+    guardian_pubkey: Pubkey,
+    swap_pubkeys: &[Pubkey],
+) -> Result<Instruction, ProgramError> {
+    let data = AdminInstruction::PauseAll.pack();
+
+    let mut accounts = vec![
+        AccountMeta::new_readonly(config_pubkey, false),
+        AccountMeta::new_readonly(guardian_pubkey, true),
+    ];
+    accounts.extend(
+        swap_pubkeys
+            .iter()
+            .map(|swap_pubkey| AccountMeta::new(*swap_pubkey, false)),
+    );
+
+    Ok(Instruction {
+        program_id,
+        accounts,
+        data,
+    })
+}
+
+/// Creates a 'SetStrictDestinationOwner' instruction.
+pub fn set_strict_destination_owner(
+    program_id: Pubkey,
+    config_pubkey: Pubkey,
+    swap_pubkey: Pubkey,
+    admin_pubkey: Pubkey,
+    strict_destination_owner: bool,
+) -> Result<Instruction, ProgramError> {
+    let data = AdminInstruction::SetStrictDestinationOwner(strict_destination_owner).pack();
+
+    let accounts = vec![
+        AccountMeta::new_readonly(config_pubkey, false),
+        AccountMeta::new(swap_pubkey, false),
+        AccountMeta::new_readonly(admin_pubkey, true),
+    ];
+
+    Ok(Instruction {
+        program_id,
+        accounts,
+        data,
+    })
+}
+
+/// Creates a 'SetPauseFlags' instruction.
+pub fn set_pause_flags(
+    program_id: Pubkey,
+    config_pubkey: Pubkey,
+    swap_pubkey: Pubkey,
+    admin_pubkey: Pubkey,
+    pause_flags: u8,
+) -> Result<Instruction, ProgramError> {
+    let data = AdminInstruction::SetPauseFlags(pause_flags).pack();
+
+    let accounts = vec![
+        AccountMeta::new_readonly(config_pubkey, false),
+        AccountMeta::new(swap_pubkey, false),
+        AccountMeta::new_readonly(admin_pubkey, true),
+    ];
+
+    Ok(Instruction {
+        program_id,
+        accounts,
+        data,
+    })
+}
+
+/// Creates a 'ClaimProtocolFees' instruction.
+pub fn claim_protocol_fees(
     program_id: Pubkey,
+    config_pubkey: Pubkey,
     swap_pubkey: Pubkey,
     authority_pubkey: Pubkey,
-    user_transfer_authority_pubkey: Pubkey,
-    pool_mint_pubkey: Pubkey,
-    source_pubkey: Pubkey,
-    swap_token_a_pubkey: Pubkey,
-    swap_token_b_pubkey: Pubkey,
-    destination_token_a_pubkey: Pubkey,
-    destination_token_b_pubkey: Pubkey,
+    admin_pubkey: Pubkey,
+    token_a_pubkey: Pubkey,
+    token_b_pubkey: Pubkey,
     admin_fee_a_pubkey: Pubkey,
     admin_fee_b_pubkey: Pubkey,
-    withdraw_data: WithdrawData,
 ) -> Result<Instruction, ProgramError> {
-    let data = SwapInstruction::Withdraw(withdraw_data).pack();
+    let data = AdminInstruction::ClaimProtocolFees.pack();
 
     let accounts = vec![
+        AccountMeta::new_readonly(config_pubkey, false),
         AccountMeta::new(swap_pubkey, false),
         AccountMeta::new_readonly(authority_pubkey, false),
-        AccountMeta::new_readonly(user_transfer_authority_pubkey, true),
-        AccountMeta::new(pool_mint_pubkey, false),
-        AccountMeta::new(source_pubkey, false),
-        AccountMeta::new(swap_token_a_pubkey, false),
-        AccountMeta::new(swap_token_b_pubkey, false),
-        AccountMeta::new(destination_token_a_pubkey, false),
-        AccountMeta::new(destination_token_b_pubkey, false),
+        AccountMeta::new_readonly(admin_pubkey, true),
+        AccountMeta::new(token_a_pubkey, false),
+        AccountMeta::new(token_b_pubkey, false),
         AccountMeta::new(admin_fee_a_pubkey, false),
         AccountMeta::new(admin_fee_b_pubkey, false),
         AccountMeta::new_readonly(spl_token::id(), false),
@@ -1147,252 +1983,50 @@ pub fn withdraw(
     })
 }
 
-/// Swap instructions for stablecoins pool
-#[repr(C)]
-#[derive(Debug, PartialEq)]
-pub enum StableSwapInstruction {
-    ///   Initializes a new stable swap pool.
-    ///
-    ///   0. `[]` market config.
-    ///   1. `[writable]` new stable-swap to create.
-    ///   1. `[]` $swap_authority.
-    ///   3. `[]` admin fee account for base token.
-    ///   4. `[]` admin fee account for quote token.
-    ///   5. `[]` base token account. Must be non zero, owned by $swap_authority.
-    ///   6. `[]` quote token account. Must be non zero, owned by $swap_authority.
-    ///   7. `[writable]` pool mint account mint by $swap_authority.
-    ///   8. `[writable]` pool token account owned by user.
-    ///   9. `[]` rent sysvar.
-    ///   10. `[]` token program id.
-    Initialize(StableInitializeData),
-
-    ///   Swap the tokens in the pool.
-    ///
-    ///   0. `[]` market config.
-    ///   1. `[writable]` stable-swap.
-    ///   2. `[]` $market_authority to mint deltafi token.
-    ///   3. `[]` $swap_authority.
-    ///   4. `[signer]` $user_transfer_authority
-    ///   5. `[writable]` SOURCE(base|quote) account, transferable by $user_transfer_authority.
-    ///   6. `[writable]` (base|quote) token account to swap INTO. Must be the SOURCE token.
-    ///   7.  `[]` Mint account of SOURCE token
-    ///   8. `[writable]` (base|quote) token account to swap FROM. Must be the DESTINATION token.
-    ///   9. `[writable]` DESTINATION(base|quote) account owned by user.
-    ///   10. `[]` Mint account of DESITINATION token
-    ///   11. `[writable]` rewards(DELTAFI) token account owned by user.
-    ///   12. `[writable]` rewards(DELTAFI) source deltafi token account to issue reward.
-    ///   13. `[writable]` (base|quote) admin fee account. Must have same mint as DESTINATION token.
-    ///   14. `[]` token program id.
-    ///   15. `[]` optional: user referrer data account.
-    ///   16. `[writable]` optional: referrer token account.
-    Swap(SwapData),
+/// Creates a 'SetProtocolFee' instruction.
+pub fn set_protocol_fee(
+    program_id: Pubkey,
+    config_pubkey: Pubkey,
+    swap_pubkey: Pubkey,
+    admin_pubkey: Pubkey,
+    numerator: u64,
+    denominator: u64,
+) -> Result<Instruction, ProgramError> {
+    let data = AdminInstruction::SetProtocolFee(numerator, denominator).pack();
 
-    ///   Deposit some tokens into the pool.  The output is a "pool" token representing ownership
-    ///   into the pool. Inputs are converted to the current ratio.
-    ///
-    ///   0. `[]` stable-swap.
-    ///   1. `[]` $swap_authority.
-    ///   2. `[signer]` $user_transfer_authority.
-    ///   3. `[writable]` base token account to deposit FROM, transferable by $user_transfer_authority.
-    ///   4. `[writable]` quote token account to deposit FROM, transferable by $user_transfer_authority.
-    ///   5. `[writable]` base token account to deposit INTO.
-    ///   6. `[writable]` quote token account to deposit INTO.
-    ///   7. `[writable]` pool mint account, mint by $swap_authority.
-    ///   8. `[writable]` pool token account owned by user.
-    ///   10. `[]` token program id.
-    Deposit(DepositData),
-
-    ///   Withdraw tokens from the pool at the current ratio.
-    ///
-    ///   0. `[]` stable-swap.
-    ///   1. `[]` $swap_authority.
-    ///   2. `[signer]` $user_transfer_authority.
-    ///   3. `[writable]` pool mint account, mint by $swap_autority.
-    ///   4. `[writable]` SOURCE pool token account, transferable by $user_transfer_authority.
-    ///   5. `[writable]` base token account to withdraw FROM.
-    ///   6. `[writable]` quote token account to withdraw FROM.
-    ///   7. `[writable]` base token account to withdraw INTO.
-    ///   8. `[writable]` quote token account to withdraw INTO.
-    ///   9. `[writable]` admin fee account for base token.
-    ///   10. `[writable]` admin fee account for quote token.
-    ///   12. `[]` token program id.
-    Withdraw(WithdrawData),
-
-    ///   Swap the tokens in the pool (removed src and dest mint accounts).
-    ///
-    ///   0. `[]` market config.
-    ///   1. `[writable]` stable-swap.
-    ///   2. `[]` $market_authority to mint deltafi token.
-    ///   3. `[]` $swap_authority.
-    ///   4. `[signer]` $user_transfer_authority
-    ///   5. `[writable]` SOURCE(base|quote) account, transferable by $user_transfer_authority.
-    ///   6. `[writable]` (base|quote) token account to swap INTO. Must be the SOURCE token.
-    ///   7. `[writable]` (base|quote) token account to swap FROM. Must be the DESTINATION token.
-    ///   8. `[writable]` DESTINATION(base|quote) account owned by user.
-    ///   9. `[writable]` rewards(DELTAFI) token account owned by user.
-    ///   10. `[writable]` rewards(DELTAFI) source deltafi token account to issue reward.
-    ///   11. `[writable]` (base|quote) admin fee account. Must have same mint as DESTINATION token.
-    ///   12. `[]` token program id.
-    ///   13. `[]` optional: user referrer data account.
-    ///   14. `[writable]` optional: referrer token account.
-    SwapV2(SwapData),
-}
-
-impl StableSwapInstruction {
-    /// Unpacks a byte buffer into a [StableSwapInstruction](enum.StableSwapInstruction.html).
-    pub fn unpack(input: &[u8]) -> Result<Self, ProgramError> {
-        let (&tag, rest) = input
-            .split_first()
-            .ok_or(SwapError::InstructionUnpackError)?;
-        Ok(match tag {
-            10 => {
-                let (&nonce, rest) = rest
-                    .split_first()
-                    .ok_or(SwapError::InstructionUnpackError)?;
-                let (slope, rest) = unpack_u64(rest)?;
-                let (token_a_decimals, rest) = unpack_u8(rest)?;
-                let (token_b_decimals, rest) = unpack_u8(rest)?;
-                let (token_a_amount, rest) = unpack_u64(rest)?;
-                let (token_b_amount, _) = unpack_u64(rest)?;
-                Self::Initialize(StableInitializeData {
-                    nonce,
-                    slope,
-                    token_a_decimals,
-                    token_b_decimals,
-                    token_a_amount,
-                    token_b_amount,
-                })
-            }
-            11 => {
-                let (amount_in, rest) = unpack_u64(rest)?;
-                let (minimum_amount_out, _) = unpack_u64(rest)?;
-                Self::Swap(SwapData {
-                    amount_in,
-                    minimum_amount_out,
-                })
-            }
-            12 => {
-                let (token_a_amount, rest) = unpack_u64(rest)?;
-                let (token_b_amount, rest) = unpack_u64(rest)?;
-                let (min_mint_amount, _) = unpack_u64(rest)?;
-                Self::Deposit(DepositData {
-                    token_a_amount,
-                    token_b_amount,
-                    min_mint_amount,
-                })
-            }
-            13 => {
-                let (pool_token_amount, rest) = unpack_u64(rest)?;
-                let (minimum_token_a_amount, rest) = unpack_u64(rest)?;
-                let (minimum_token_b_amount, _) = unpack_u64(rest)?;
-                Self::Withdraw(WithdrawData {
-                    pool_token_amount,
-                    minimum_token_a_amount,
-                    minimum_token_b_amount,
-                })
-            }
-            14 => {
-                let (amount_in, rest) = unpack_u64(rest)?;
-                let (minimum_amount_out, _) = unpack_u64(rest)?;
-                Self::SwapV2(SwapData {
-                    amount_in,
-                    minimum_amount_out,
-                })
-            }
-            _ => return Err(SwapError::InvalidInstruction.into()),
-        })
-    }
+    let accounts = vec![
+        AccountMeta::new_readonly(config_pubkey, false),
+        AccountMeta::new(swap_pubkey, false),
+        AccountMeta::new_readonly(admin_pubkey, true),
+    ];
 
-    /// Packs a [SwapInstruction](enum.SwapInstruction.html) into a byte buffer.
-    pub fn pack(&self) -> Vec<u8> {
-        let mut buf = Vec::with_capacity(size_of::<Self>());
-        match *self {
-            Self::Initialize(StableInitializeData {
-                nonce,
-                slope,
-                token_a_decimals,
-                token_b_decimals,
-                token_a_amount,
-                token_b_amount,
-            }) => {
-                buf.push(10);
-                buf.extend_from_slice(&nonce.to_le_bytes());
-                buf.extend_from_slice(&slope.to_le_bytes());
-                buf.push(token_a_decimals);
-                buf.push(token_b_decimals);
-                buf.extend_from_slice(&token_a_amount.to_le_bytes());
-                buf.extend_from_slice(&token_b_amount.to_le_bytes());
-            }
-            Self::Swap(SwapData {
-                amount_in,
-                minimum_amount_out,
-            }) => {
-                buf.push(11);
-                buf.extend_from_slice(&amount_in.to_le_bytes());
-                buf.extend_from_slice(&minimum_amount_out.to_le_bytes());
-            }
-            Self::Deposit(DepositData {
-                token_a_amount,
-                token_b_amount,
-                min_mint_amount,
-            }) => {
-                buf.push(12);
-                buf.extend_from_slice(&token_a_amount.to_le_bytes());
-                buf.extend_from_slice(&token_b_amount.to_le_bytes());
-                buf.extend_from_slice(&min_mint_amount.to_le_bytes());
-            }
-            Self::Withdraw(WithdrawData {
-                pool_token_amount,
-                minimum_token_a_amount,
-                minimum_token_b_amount,
-            }) => {
-                buf.push(13);
-                buf.extend_from_slice(&pool_token_amount.to_le_bytes());
-                buf.extend_from_slice(&minimum_token_a_amount.to_le_bytes());
-                buf.extend_from_slice(&minimum_token_b_amount.to_le_bytes());
-            }
-            Self::SwapV2(SwapData {
-                amount_in,
-                minimum_amount_out,
-            }) => {
-                buf.push(14);
-                buf.extend_from_slice(&amount_in.to_le_bytes());
-                buf.extend_from_slice(&minimum_amount_out.to_le_bytes());
-            }
-        }
-        buf
-    }
+    Ok(Instruction {
+        program_id,
+        accounts,
+        data,
+    })
 }
 
-/// Creates 'stable_initialize' instruction.
-pub fn stable_initialize(
+/// Creates a 'SetPoolAuthority' instruction.
+pub fn set_pool_authority(
     program_id: Pubkey,
     config_pubkey: Pubkey,
     swap_pubkey: Pubkey,
     authority_pubkey: Pubkey,
-    admin_fee_a_pubkey: Pubkey,
-    admin_fee_b_pubkey: Pubkey,
-    token_a_pubkey: Pubkey,
-    token_b_pubkey: Pubkey,
-    pool_mint_pubkey: Pubkey,
-    destination_pubkey: Pubkey,
     admin_pubkey: Pubkey,
-    init_data: StableInitializeData,
+    account_pubkey: Pubkey,
+    new_authority_pubkey: Pubkey,
+    authority_type: u8,
 ) -> Result<Instruction, ProgramError> {
-    let data = StableSwapInstruction::Initialize(init_data).pack();
+    let data = AdminInstruction::SetPoolAuthority(authority_type).pack();
 
     let accounts = vec![
         AccountMeta::new_readonly(config_pubkey, false),
-        AccountMeta::new(swap_pubkey, true),
+        AccountMeta::new_readonly(swap_pubkey, false),
         AccountMeta::new_readonly(authority_pubkey, false),
-        AccountMeta::new_readonly(admin_fee_a_pubkey, false),
-        AccountMeta::new_readonly(admin_fee_b_pubkey, false),
-        AccountMeta::new_readonly(token_a_pubkey, false),
-        AccountMeta::new_readonly(token_b_pubkey, false),
-        AccountMeta::new(pool_mint_pubkey, false),
-        AccountMeta::new(destination_pubkey, false),
         AccountMeta::new_readonly(admin_pubkey, true),
-        AccountMeta::new_readonly(rent::id(), false),
+        AccountMeta::new(account_pubkey, false),
+        AccountMeta::new_readonly(new_authority_pubkey, false),
         AccountMeta::new_readonly(spl_token::id(), false),
     ];
 
@@ -1403,54 +2037,27 @@ pub fn stable_initialize(
     })
 }
 
-/// Creates a 'stable_swap' instruction.
-pub fn stable_swap(
+/// Creates a 'SetMarketAuthority' instruction.
+pub fn set_market_authority(
     program_id: Pubkey,
     config_pubkey: Pubkey,
-    swap_pubkey: Pubkey,
     market_authority_pubkey: Pubkey,
-    swap_authority_pubkey: Pubkey,
-    user_transfer_authority_pubkey: Pubkey,
-    source_pubkey: Pubkey,
-    swap_source_pubkey: Pubkey,
-    source_mint_info: Pubkey,
-    swap_destination_pubkey: Pubkey,
-    destination_pubkey: Pubkey,
-    destination_mint_info: Pubkey,
-    reward_token_pubkey: Pubkey,
-    source_reward_token_pubkey: Pubkey,
-    admin_fee_destination_pubkey: Pubkey,
-    user_referrer_data_pubkey: Option<Pubkey>,
-    referrer_token_pubkey: Option<Pubkey>,
-    swap_data: SwapData,
+    admin_pubkey: Pubkey,
+    account_pubkey: Pubkey,
+    new_authority_pubkey: Pubkey,
+    authority_type: u8,
 ) -> Result<Instruction, ProgramError> {
-    let data = StableSwapInstruction::Swap(swap_data).pack();
+    let data = AdminInstruction::SetMarketAuthority(authority_type).pack();
 
-    let mut accounts = vec![
+    let accounts = vec![
         AccountMeta::new_readonly(config_pubkey, false),
-        AccountMeta::new(swap_pubkey, false),
         AccountMeta::new_readonly(market_authority_pubkey, false),
-        AccountMeta::new_readonly(swap_authority_pubkey, false),
-        AccountMeta::new_readonly(user_transfer_authority_pubkey, true),
-        AccountMeta::new(source_pubkey, false),
-        AccountMeta::new(swap_source_pubkey, false),
-        AccountMeta::new_readonly(source_mint_info, false),
-        AccountMeta::new(swap_destination_pubkey, false),
-        AccountMeta::new(destination_pubkey, false),
-        AccountMeta::new_readonly(destination_mint_info, false),
-        AccountMeta::new(reward_token_pubkey, false),
-        AccountMeta::new(source_reward_token_pubkey, false),
-        AccountMeta::new(admin_fee_destination_pubkey, false),
+        AccountMeta::new_readonly(admin_pubkey, true),
+        AccountMeta::new(account_pubkey, false),
+        AccountMeta::new_readonly(new_authority_pubkey, false),
         AccountMeta::new_readonly(spl_token::id(), false),
     ];
 
-    if let Some(user_referrer_data_pubkey) = user_referrer_data_pubkey {
-        accounts.extend_from_slice(&[
-            AccountMeta::new_readonly(user_referrer_data_pubkey, false),
-            AccountMeta::new(referrer_token_pubkey.unwrap(), false),
-        ]);
-    }
-
     Ok(Instruction {
         program_id,
         accounts,
@@ -1458,50 +2065,34 @@ pub fn stable_swap(
     })
 }
 
-/// Creates a 'stable_swap_v2' instruction.
-pub fn stable_swap_v2(
+/// Creates a 'ClosePool' instruction.
+pub fn close_pool(
     program_id: Pubkey,
     config_pubkey: Pubkey,
     swap_pubkey: Pubkey,
-    market_authority_pubkey: Pubkey,
-    swap_authority_pubkey: Pubkey,
-    user_transfer_authority_pubkey: Pubkey,
-    source_pubkey: Pubkey,
-    swap_source_pubkey: Pubkey,
-    swap_destination_pubkey: Pubkey,
-    destination_pubkey: Pubkey,
-    reward_token_pubkey: Pubkey,
-    source_reward_token_pubkey: Pubkey,
-    admin_fee_destination_pubkey: Pubkey,
-    user_referrer_data_pubkey: Option<Pubkey>,
-    referrer_token_pubkey: Option<Pubkey>,
-    swap_data: SwapData,
+    authority_pubkey: Pubkey,
+    admin_pubkey: Pubkey,
+    pool_mint_pubkey: Pubkey,
+    token_a_pubkey: Pubkey,
+    token_b_pubkey: Pubkey,
+    destination_a_pubkey: Pubkey,
+    destination_b_pubkey: Pubkey,
 ) -> Result<Instruction, ProgramError> {
-    let data = StableSwapInstruction::SwapV2(swap_data).pack();
+    let data = AdminInstruction::ClosePool.pack();
 
-    let mut accounts = vec![
+    let accounts = vec![
         AccountMeta::new_readonly(config_pubkey, false),
         AccountMeta::new(swap_pubkey, false),
-        AccountMeta::new_readonly(market_authority_pubkey, false),
-        AccountMeta::new_readonly(swap_authority_pubkey, false),
-        AccountMeta::new_readonly(user_transfer_authority_pubkey, true),
-        AccountMeta::new(source_pubkey, false),
-        AccountMeta::new(swap_source_pubkey, false),
-        AccountMeta::new(swap_destination_pubkey, false),
-        AccountMeta::new(destination_pubkey, false),
-        AccountMeta::new(reward_token_pubkey, false),
-        AccountMeta::new(source_reward_token_pubkey, false),
-        AccountMeta::new(admin_fee_destination_pubkey, false),
+        AccountMeta::new_readonly(authority_pubkey, false),
+        AccountMeta::new(admin_pubkey, true),
+        AccountMeta::new(pool_mint_pubkey, false),
+        AccountMeta::new(token_a_pubkey, false),
+        AccountMeta::new(token_b_pubkey, false),
+        AccountMeta::new(destination_a_pubkey, false),
+        AccountMeta::new(destination_b_pubkey, false),
         AccountMeta::new_readonly(spl_token::id(), false),
     ];
 
-    if let Some(user_referrer_data_pubkey) = user_referrer_data_pubkey {
-        accounts.extend_from_slice(&[
-            AccountMeta::new_readonly(user_referrer_data_pubkey, false),
-            AccountMeta::new(referrer_token_pubkey.unwrap(), false),
-        ]);
-    }
-
     Ok(Instruction {
         program_id,
         accounts,
@@ -1509,35 +2100,27 @@ pub fn stable_swap_v2(
     })
 }
 
-/// Creates a 'stable_deposit' instruction.
-pub fn stable_deposit(
+/// Creates a 'SetOracleFeed' instruction.
+pub fn set_oracle_feed(
     program_id: Pubkey,
-    swap_pubkey: Pubkey,
-    authority_pubkey: Pubkey,
-    user_transfer_authority_pubkey: Pubkey,
-    deposit_token_a_pubkey: Pubkey,
-    deposit_token_b_pubkey: Pubkey,
-    swap_token_a_pubkey: Pubkey,
-    swap_token_b_pubkey: Pubkey,
-    pool_mint_pubkey: Pubkey,
-    destination_pubkey: Pubkey,
-    deposit_data: DepositData,
+    config_pubkey: Pubkey,
+    oracle_feed_pubkey: Pubkey,
+    mint_pubkey: Pubkey,
+    pyth_price_pubkey: Pubkey,
+    serum_market_pubkey: Pubkey,
+    admin_pubkey: Pubkey,
 ) -> Result<Instruction, ProgramError> {
-    let data = StableSwapInstruction::Deposit(deposit_data).pack();
+    let data = AdminInstruction::SetOracleFeed.pack();
 
     let accounts = vec![
-        AccountMeta::new(swap_pubkey, false),
-        AccountMeta::new_readonly(authority_pubkey, false),
-        AccountMeta::new_readonly(user_transfer_authority_pubkey, true),
-        AccountMeta::new(deposit_token_a_pubkey, false),
-        AccountMeta::new(deposit_token_b_pubkey, false),
-        AccountMeta::new(swap_token_a_pubkey, false),
-        AccountMeta::new(swap_token_b_pubkey, false),
-        AccountMeta::new(pool_mint_pubkey, false),
-        AccountMeta::new(destination_pubkey, false),
-        AccountMeta::new_readonly(spl_token::id(), false),
-        AccountMeta::new_readonly(spl_token::id(), false),
-        AccountMeta::new_readonly(spl_token::id(), false),
+        AccountMeta::new_readonly(config_pubkey, false),
+        AccountMeta::new(oracle_feed_pubkey, false),
+        AccountMeta::new_readonly(mint_pubkey, false),
+        AccountMeta::new_readonly(pyth_price_pubkey, false),
+        AccountMeta::new_readonly(serum_market_pubkey, false),
+        AccountMeta::new(admin_pubkey, true),
+        AccountMeta::new_readonly(rent::id(), false),
+        AccountMeta::new_readonly(system_program::id(), false),
     ];
 
     Ok(Instruction {
@@ -1547,37 +2130,17 @@ pub fn stable_deposit(
     })
 }
 
-/// Creates a 'stable_withdraw' instruction.
-pub fn stable_withdraw(
+/// Creates a 'EnableOracleFeedRegistry' instruction.
+pub fn enable_oracle_feed_registry(
     program_id: Pubkey,
-    swap_pubkey: Pubkey,
-    authority_pubkey: Pubkey,
-    user_transfer_authority_pubkey: Pubkey,
-    pool_mint_pubkey: Pubkey,
-    source_pubkey: Pubkey,
-    swap_token_a_pubkey: Pubkey,
-    swap_token_b_pubkey: Pubkey,
-    destination_token_a_pubkey: Pubkey,
-    destination_token_b_pubkey: Pubkey,
-    admin_fee_a_pubkey: Pubkey,
-    admin_fee_b_pubkey: Pubkey,
-    withdraw_data: WithdrawData,
+    config_pubkey: Pubkey,
+    admin_pubkey: Pubkey,
 ) -> Result<Instruction, ProgramError> {
-    let data = StableSwapInstruction::Withdraw(withdraw_data).pack();
+    let data = AdminInstruction::EnableOracleFeedRegistry.pack();
 
     let accounts = vec![
-        AccountMeta::new(swap_pubkey, false),
-        AccountMeta::new_readonly(authority_pubkey, false),
-        AccountMeta::new_readonly(user_transfer_authority_pubkey, true),
-        AccountMeta::new(pool_mint_pubkey, false),
-        AccountMeta::new(source_pubkey, false),
-        AccountMeta::new(swap_token_a_pubkey, false),
-        AccountMeta::new(swap_token_b_pubkey, false),
-        AccountMeta::new(destination_token_a_pubkey, false),
-        AccountMeta::new(destination_token_b_pubkey, false),
-        AccountMeta::new(admin_fee_a_pubkey, false),
-        AccountMeta::new(admin_fee_b_pubkey, false),
-        AccountMeta::new_readonly(spl_token::id(), false),
+        AccountMeta::new(config_pubkey, false),
+        AccountMeta::new_readonly(admin_pubkey, true),
     ];
 
     Ok(Instruction {
@@ -1587,274 +2150,125 @@ pub fn stable_withdraw(
     })
 }
 
-/// FARM INSTRUNCTION DATA
-/// Initialize instruction data
-#[repr(C)]
-#[derive(Debug, PartialEq)]
-pub struct FarmInitializeData {
-    /// Withdraw fee numerator
-    pub fee_numerator: u64,
-    /// Withdraw fee denominator
-    pub fee_denominator: u64,
-    /// Rewards numerator
-    pub rewards_numerator: u64,
-    /// Rewards denominator
-    pub rewards_denominator: u64,
-    /// Bump seed
-    pub bump_seed: u8,
-}
-
-/// Farm deposit instruction data
-#[cfg_attr(feature = "fuzz", derive(Arbitrary, Clone))]
-#[repr(C)]
-#[derive(Debug, PartialEq)]
-pub struct FarmDepositData {
-    /// Amount to stake
-    pub amount: u64,
-}
-
-/// Farm withdraw instruction data
-#[cfg_attr(feature = "fuzz", derive(Arbitrary, Clone))]
-#[repr(C)]
-#[derive(Debug, PartialEq)]
-pub struct FarmWithdrawData {
-    /// Amount to withdraw
-    pub amount: u64,
-}
-
-/// Instructions supported by the pool FarmInfo program.
-#[repr(C)]
-#[derive(Debug, PartialEq)]
-pub enum FarmInstruction {
-    /// Intialize farm info
-    Initialize(FarmInitializeData),
-    /// Initialize farm user
-    InitializeFarmUser,
-    /// Farm claim
-    Claim,
-    /// Farm refresh
-    Refresh,
-    /// Farm deposit
-    Deposit(FarmDepositData),
-    /// Farm withdraw
-    Withdraw(FarmWithdrawData),
-}
-
-impl FarmInstruction {
-    /// Unpacks a byte buffer into a [FarmInstruction](enum.FarmInstruction.html).
-    pub fn unpack(input: &[u8]) -> Result<Self, ProgramError> {
-        let (&tag, rest) = input
-            .split_first()
-            .ok_or(ProgramError::InvalidInstructionData)?;
-        Ok(match tag {
-            20 => {
-                let (fee_numerator, rest) = unpack_u64(rest)?;
-                let (fee_denominator, rest) = unpack_u64(rest)?;
-                let (rewards_numerator, rest) = unpack_u64(rest)?;
-                let (rewards_denominator, rest) = unpack_u64(rest)?;
-                let (bump_seed, _) = unpack_u8(rest)?;
-
-                Self::Initialize(FarmInitializeData {
-                    fee_numerator,
-                    fee_denominator,
-                    rewards_numerator,
-                    rewards_denominator,
-                    bump_seed,
-                })
-            }
-            21 => Self::InitializeFarmUser,
-            22 => Self::Claim,
-            23 => Self::Refresh,
-            24 => {
-                let (amount, _) = unpack_u64(rest)?;
-                Self::Deposit(FarmDepositData { amount })
-            }
-            25 => {
-                let (amount, _) = unpack_u64(rest)?;
-                Self::Withdraw(FarmWithdrawData { amount })
-            }
-            _ => return Err(ProgramError::InvalidInstructionData),
-        })
-    }
-
-    /// Packs a [FarmInstruction](enum.FarmInstruction.html) into a byte buffer
-    pub fn pack(&self) -> Vec<u8> {
-        let mut buf = Vec::with_capacity(size_of::<Self>());
-        match *self {
-            Self::Initialize(FarmInitializeData {
-                fee_numerator,
-                fee_denominator,
-                rewards_numerator,
-                rewards_denominator,
-                bump_seed,
-            }) => {
-                buf.push(20);
-                buf.extend_from_slice(&fee_numerator.to_le_bytes());
-                buf.extend_from_slice(&fee_denominator.to_le_bytes());
-                buf.extend_from_slice(&rewards_numerator.to_le_bytes());
-                buf.extend_from_slice(&rewards_denominator.to_le_bytes());
-                buf.extend_from_slice(&bump_seed.to_le_bytes());
-            }
-            Self::InitializeFarmUser => {
-                buf.push(21);
-            }
-            Self::Claim => buf.push(22),
-            Self::Refresh => buf.push(23),
-            Self::Deposit(FarmDepositData { amount }) => {
-                buf.push(24);
-                buf.extend_from_slice(&amount.to_le_bytes());
-            }
-            Self::Withdraw(FarmWithdrawData { amount }) => {
-                buf.push(25);
-                buf.extend_from_slice(&amount.to_le_bytes());
-            }
-        }
-
-        buf
-    }
-}
-
-/// Creates `FarmInitialize` instruction
-pub fn farm_initialize(
+/// Creates a 'EnableFarmUserRegistry' instruction.
+pub fn enable_farm_user_registry(
     program_id: Pubkey,
-    config_info_pubkey: Pubkey,
-    swap_info_pubkey: Pubkey,
+    config_pubkey: Pubkey,
     farm_pool_pubkey: Pubkey,
-    authority_pubkey: Pubkey,
-    pool_token_pubkey: Pubkey,
     admin_pubkey: Pubkey,
-    init_data: FarmInitializeData,
 ) -> Result<Instruction, ProgramError> {
-    let data = FarmInstruction::Initialize(init_data).pack();
+    let data = AdminInstruction::EnableFarmUserRegistry.pack();
 
     let accounts = vec![
-        AccountMeta::new_readonly(config_info_pubkey, false),
-        AccountMeta::new_readonly(swap_info_pubkey, false),
-        AccountMeta::new(farm_pool_pubkey, true),
-        AccountMeta::new_readonly(authority_pubkey, false),
-        AccountMeta::new_readonly(pool_token_pubkey, false),
+        AccountMeta::new_readonly(config_pubkey, false),
+        AccountMeta::new(farm_pool_pubkey, false),
         AccountMeta::new_readonly(admin_pubkey, true),
-        AccountMeta::new_readonly(rent::id(), false),
     ];
 
     Ok(Instruction {
         program_id,
-        data,
         accounts,
+        data,
     })
 }
 
-/// Creates `FarmUserInitialize` instruction
-pub fn farm_user_initialize(
+/// Creates a 'SetPriceCache' instruction.
+pub fn set_price_cache(
     program_id: Pubkey,
-    config_info_pubkey: Pubkey,
-    farm_pool_pubkey: Pubkey,
-    farm_user_pubkey: Pubkey,
-    farm_owner_pubkey: Pubkey,
+    config_pubkey: Pubkey,
+    swap_pubkey: Pubkey,
+    price_cache_pubkey: Pubkey,
+    relayer_pubkey: Pubkey,
+    admin_pubkey: Pubkey,
 ) -> Result<Instruction, ProgramError> {
-    let data = FarmInstruction::InitializeFarmUser.pack();
+    let data = AdminInstruction::SetPriceCache.pack();
 
     let accounts = vec![
-        AccountMeta::new_readonly(config_info_pubkey, false),
-        AccountMeta::new_readonly(farm_pool_pubkey, false),
-        AccountMeta::new(farm_user_pubkey, false),
-        AccountMeta::new_readonly(farm_owner_pubkey, true),
+        AccountMeta::new_readonly(config_pubkey, false),
+        AccountMeta::new_readonly(swap_pubkey, false),
+        AccountMeta::new(price_cache_pubkey, false),
+        AccountMeta::new_readonly(relayer_pubkey, false),
+        AccountMeta::new(admin_pubkey, true),
         AccountMeta::new_readonly(rent::id(), false),
+        AccountMeta::new_readonly(system_program::id(), false),
     ];
 
     Ok(Instruction {
         program_id,
-        data,
         accounts,
+        data,
     })
 }
 
-/// Creates `FarmDeposit` instruction
-pub fn farm_deposit(
+/// Creates a 'CreateScratchAccount' instruction.
+pub fn create_scratch_account(
     program_id: Pubkey,
-    config_key: Pubkey,
-    farm_pool_pubkey: Pubkey,
-    user_transfer_authority_pubkey: Pubkey,
-    source_pubkey: Pubkey,
-    destination_pubkey: Pubkey,
-    farm_user_pubkey: Pubkey,
-    farm_owner_pubkey: Pubkey,
-    deposit_data: FarmDepositData,
+    config_pubkey: Pubkey,
+    scratch_account_pubkey: Pubkey,
+    scratch_token_pubkey: Pubkey,
+    mint_pubkey: Pubkey,
+    admin_pubkey: Pubkey,
+    index: u8,
 ) -> Result<Instruction, ProgramError> {
-    let data = FarmInstruction::Deposit(deposit_data).pack();
+    let data = AdminInstruction::CreateScratchAccount(index).pack();
 
     let accounts = vec![
-        AccountMeta::new_readonly(config_key, false),
-        AccountMeta::new(farm_pool_pubkey, false),
-        AccountMeta::new_readonly(user_transfer_authority_pubkey, true),
-        AccountMeta::new(source_pubkey, false),
-        AccountMeta::new(destination_pubkey, false),
-        AccountMeta::new(farm_user_pubkey, false),
-        AccountMeta::new_readonly(farm_owner_pubkey, true),
+        AccountMeta::new_readonly(config_pubkey, false),
+        AccountMeta::new(scratch_account_pubkey, false),
+        AccountMeta::new(scratch_token_pubkey, false),
+        AccountMeta::new_readonly(mint_pubkey, false),
+        AccountMeta::new(admin_pubkey, true),
+        AccountMeta::new_readonly(rent::id(), false),
+        AccountMeta::new_readonly(system_program::id(), false),
         AccountMeta::new_readonly(spl_token::id(), false),
     ];
 
     Ok(Instruction {
         program_id,
-        data,
         accounts,
+        data,
     })
 }
 
-/// Creates `FarmWithdraw` instruction
-pub fn farm_withdraw(
+/// Creates a 'SetAdminOpsLimit' instruction.
+pub fn set_admin_ops_limit(
     program_id: Pubkey,
-    config_key: Pubkey,
-    farm_pool_pubkey: Pubkey,
-    farm_user_pubkey: Pubkey,
-    authority_pubkey: Pubkey,
-    source_pubkey: Pubkey,
-    destination_pubkey: Pubkey,
-    farm_owner_pubkey: Pubkey,
-    withdraw_data: FarmWithdrawData,
+    config_pubkey: Pubkey,
+    guardian_pubkey: Pubkey,
+    max_admin_ops_per_epoch: u16,
 ) -> Result<Instruction, ProgramError> {
-    let data = FarmInstruction::Withdraw(withdraw_data).pack();
+    let data = AdminInstruction::SetAdminOpsLimit(max_admin_ops_per_epoch).pack();
 
     let accounts = vec![
-        AccountMeta::new_readonly(config_key, false),
-        AccountMeta::new(farm_pool_pubkey, false),
-        AccountMeta::new(farm_user_pubkey, false),
-        AccountMeta::new_readonly(authority_pubkey, false),
-        AccountMeta::new(source_pubkey, false),
-        AccountMeta::new(destination_pubkey, false),
-        AccountMeta::new_readonly(farm_owner_pubkey, true),
-        AccountMeta::new_readonly(spl_token::id(), false),
+        AccountMeta::new(config_pubkey, false),
+        AccountMeta::new_readonly(guardian_pubkey, true),
     ];
 
     Ok(Instruction {
         program_id,
-        data,
         accounts,
+        data,
     })
 }
 
-/// Creates farm claim instruction
-pub fn farm_claim(
+/// Creates a 'SetFeeDiscountTiers' instruction.
+pub fn set_fee_discount_tiers(
     program_id: Pubkey,
     config_pubkey: Pubkey,
-    farm_pool_pubkey: Pubkey,
-    farm_user_pubkey: Pubkey,
-    farm_owner_pubkey: Pubkey,
-    market_authority_pubkey: Pubkey,
-    claim_destination_pubkey: Pubkey,
-    claim_source_pubkey: Pubkey,
+    fee_discount_tiers_pubkey: Pubkey,
+    fee_manager_pubkey: Pubkey,
+    tiers: [FeeDiscountTier; MAX_FEE_DISCOUNT_TIERS],
 ) -> Result<Instruction, ProgramError> {
-    let data = FarmInstruction::Claim.pack();
+    let data = AdminInstruction::SetFeeDiscountTiers(tiers).pack();
+
     let accounts = vec![
         AccountMeta::new_readonly(config_pubkey, false),
-        AccountMeta::new_readonly(farm_pool_pubkey, false),
-        AccountMeta::new(farm_user_pubkey, false),
-        AccountMeta::new_readonly(farm_owner_pubkey, true),
-        AccountMeta::new_readonly(market_authority_pubkey, false),
-        AccountMeta::new(claim_destination_pubkey, false),
-        AccountMeta::new(claim_source_pubkey, false),
-        AccountMeta::new_readonly(spl_token::id(), false),
+        AccountMeta::new(fee_discount_tiers_pubkey, false),
+        AccountMeta::new(fee_manager_pubkey, true),
+        AccountMeta::new_readonly(rent::id(), false),
+        AccountMeta::new_readonly(system_program::id(), false),
     ];
+
     Ok(Instruction {
         program_id,
         accounts,
@@ -1862,358 +2276,6530 @@ pub fn farm_claim(
     })
 }
 
-fn unpack_u128(input: &[u8]) -> Result<(u128, &[u8]), ProgramError> {
-    if input.len() < 16 {
-        return Err(SwapError::InstructionUnpackError.into());
-    }
-    let (amount, rest) = input.split_at(16);
-    let amount = amount
-        .get(..16)
-        .and_then(|slice| slice.try_into().ok())
-        .map(u128::from_le_bytes)
-        .ok_or(SwapError::InstructionUnpackError)?;
-    Ok((amount, rest))
+/// Creates a 'SyncPoolFromConfig' instruction.
+pub fn sync_pool_from_config(
+    program_id: Pubkey,
+    config_pubkey: Pubkey,
+    admin_pubkey: Pubkey,
+    swap_pubkeys: &[Pubkey],
+    include_pinned: bool,
+) -> Result<Instruction, ProgramError> {
+    let data = AdminInstruction::SyncPoolFromConfig(include_pinned).pack();
+
+    let mut accounts = vec![
+        AccountMeta::new_readonly(config_pubkey, false),
+        AccountMeta::new_readonly(admin_pubkey, true),
+    ];
+    accounts.extend(
+        swap_pubkeys
+            .iter()
+            .map(|swap_pubkey| AccountMeta::new(*swap_pubkey, false)),
+    );
+
+    Ok(Instruction {
+        program_id,
+        accounts,
+        data,
+    })
 }
 
-#[allow(dead_code)]
-fn unpack_i64(input: &[u8]) -> Result<(i64, &[u8]), ProgramError> {
-    if input.len() < 8 {
-        return Err(SwapError::InstructionUnpackError.into());
-    }
-    let (amount, rest) = input.split_at(8);
-    let amount = amount
-        .get(..8)
-        .and_then(|slice| slice.try_into().ok())
-        .map(i64::from_le_bytes)
-        .ok_or(SwapError::InstructionUnpackError)?;
-    Ok((amount, rest))
+/// Creates a 'SetConfigFees' instruction.
+pub fn set_config_fees(
+    program_id: Pubkey,
+    config_pubkey: Pubkey,
+    admin_pubkey: Pubkey,
+    fees: Fees,
+    rewards: Rewards,
+) -> Result<Instruction, ProgramError> {
+    let data = AdminInstruction::SetConfigFees(AdminInitializeData { fees, rewards }).pack();
+
+    let accounts = vec![
+        AccountMeta::new(config_pubkey, false),
+        AccountMeta::new_readonly(admin_pubkey, true),
+    ];
+
+    Ok(Instruction {
+        program_id,
+        accounts,
+        data,
+    })
 }
 
-fn unpack_u64(input: &[u8]) -> Result<(u64, &[u8]), ProgramError> {
-    if input.len() < 8 {
-        return Err(SwapError::InstructionUnpackError.into());
-    }
-    let (amount, rest) = input.split_at(8);
-    let amount = amount
-        .get(..8)
-        .and_then(|slice| slice.try_into().ok())
-        .map(u64::from_le_bytes)
-        .ok_or(SwapError::InstructionUnpackError)?;
-    Ok((amount, rest))
+/// Creates a 'BatchSetFees' instruction.
+pub fn batch_set_fees(
+    program_id: Pubkey,
+    config_pubkey: Pubkey,
+    admin_pubkey: Pubkey,
+    swap_pubkeys: &[Pubkey],
+    fees: Fees,
+) -> Result<Instruction, ProgramError> {
+    let data = AdminInstruction::BatchSetFees(fees).pack();
+
+    let mut accounts = vec![
+        AccountMeta::new_readonly(config_pubkey, false),
+        AccountMeta::new_readonly(admin_pubkey, true),
+    ];
+    accounts.extend(
+        swap_pubkeys
+            .iter()
+            .map(|swap_pubkey| AccountMeta::new(*swap_pubkey, false)),
+    );
+
+    Ok(Instruction {
+        program_id,
+        accounts,
+        data,
+    })
 }
 
-fn unpack_u8(input: &[u8]) -> Result<(u8, &[u8]), ProgramError> {
-    if input.is_empty() {
-        return Err(SwapError::InstructionUnpackError.into());
-    }
-    let (bytes, rest) = input.split_at(1);
-    let value = bytes
-        .get(..1)
-        .and_then(|slice| slice.try_into().ok())
-        .map(u8::from_le_bytes)
-        .ok_or(SwapError::InstructionUnpackError)?;
-    Ok((value, rest))
+/// Creates a 'BatchPause' instruction.
+pub fn batch_pause(
+    program_id: Pubkey,
+    config_pubkey: Pubkey,
+    admin_pubkey: Pubkey,
+    swap_pubkeys: &[Pubkey],
+) -> Result<Instruction, ProgramError> {
+    let data = AdminInstruction::BatchPause.pack();
+
+    let mut accounts = vec![
+        AccountMeta::new_readonly(config_pubkey, false),
+        AccountMeta::new_readonly(admin_pubkey, true),
+    ];
+    accounts.extend(
+        swap_pubkeys
+            .iter()
+            .map(|swap_pubkey| AccountMeta::new(*swap_pubkey, false)),
+    );
+
+    Ok(Instruction {
+        program_id,
+        accounts,
+        data,
+    })
 }
 
-#[allow(dead_code)]
-fn unpack_bytes32(input: &[u8]) -> Result<(&[u8; 32], &[u8]), ProgramError> {
-    if input.len() < 32 {
-        return Err(SwapError::InstructionUnpackError.into());
-    }
-    let (bytes, rest) = input.split_at(32);
-    Ok((
-        bytes
-            .try_into()
-            .map_err(|_| SwapError::InstructionUnpackError)?,
-        rest,
-    ))
+/// Creates an 'AddFarmRewardToken' instruction.
+pub fn add_farm_reward_token(
+    program_id: Pubkey,
+    config_pubkey: Pubkey,
+    farm_pool_pubkey: Pubkey,
+    farm_reward_tokens_pubkey: Pubkey,
+    vault_pubkey: Pubkey,
+    admin_pubkey: Pubkey,
+    reward_per_slot: u64,
+) -> Result<Instruction, ProgramError> {
+    let data = AdminInstruction::AddFarmRewardToken(reward_per_slot).pack();
+
+    let accounts = vec![
+        AccountMeta::new_readonly(config_pubkey, false),
+        AccountMeta::new(farm_pool_pubkey, false),
+        AccountMeta::new(farm_reward_tokens_pubkey, false),
+        AccountMeta::new_readonly(vault_pubkey, false),
+        AccountMeta::new(admin_pubkey, true),
+        AccountMeta::new_readonly(rent::id(), false),
+        AccountMeta::new_readonly(system_program::id(), false),
+        AccountMeta::new_readonly(spl_token::id(), false),
+    ];
+
+    Ok(Instruction {
+        program_id,
+        accounts,
+        data,
+    })
 }
 
-#[allow(dead_code)]
-fn unpack_pubkey(input: &[u8]) -> Result<(Pubkey, &[u8]), ProgramError> {
-    if input.len() < PUBKEY_BYTES {
-        return Err(SwapError::InstructionUnpackError.into());
-    }
-    let (key, rest) = input.split_at(PUBKEY_BYTES);
-    let pk = Pubkey::new(key);
-    Ok((pk, rest))
+/// Creates a 'SetFarmRewardRate' instruction.
+pub fn set_farm_reward_rate(
+    program_id: Pubkey,
+    config_pubkey: Pubkey,
+    farm_pool_pubkey: Pubkey,
+    farm_reward_tokens_pubkey: Pubkey,
+    admin_pubkey: Pubkey,
+    index: u8,
+    reward_per_slot: u64,
+) -> Result<Instruction, ProgramError> {
+    let data = AdminInstruction::SetFarmRewardRate(index, reward_per_slot).pack();
+
+    let accounts = vec![
+        AccountMeta::new_readonly(config_pubkey, false),
+        AccountMeta::new_readonly(farm_pool_pubkey, false),
+        AccountMeta::new(farm_reward_tokens_pubkey, false),
+        AccountMeta::new_readonly(admin_pubkey, true),
+    ];
+
+    Ok(Instruction {
+        program_id,
+        accounts,
+        data,
+    })
 }
 
-#[cfg(test)]
-mod tests {
-    use super::*;
-    use crate::{
-        curve::{default_market_price, default_slope},
-        state::{DEFAULT_TEST_FEES, DEFAULT_TEST_REWARDS},
-    };
+/// Creates a 'SetFarmEmissionRate' instruction.
+pub fn set_farm_emission_rate(
+    program_id: Pubkey,
+    config_pubkey: Pubkey,
+    farm_pool_pubkey: Pubkey,
+    admin_pubkey: Pubkey,
+    reward_per_slot: u64,
+) -> Result<Instruction, ProgramError> {
+    let data = AdminInstruction::SetFarmEmissionRate(reward_per_slot).pack();
 
-    #[test]
-    fn test_instruction_type_check() {
-        assert!(matches!(
-            InstructionType::check(&[101u8, 1u8]),
-            Some(InstructionType::Admin)
-        ));
-        assert!(matches!(
-            InstructionType::check(&[1u8, 1u8]),
-            Some(InstructionType::Swap)
-        ));
-        assert!(matches!(
-            InstructionType::check(&[12u8, 1u8]),
-            Some(InstructionType::StableSwap)
-        ));
-        assert!(matches!(
-            InstructionType::check(&[21u8, 1u8]),
-            Some(InstructionType::Farm)
-        ));
-        assert!(InstructionType::check(&[15u8, 1u8]).is_none());
-    }
+    let accounts = vec![
+        AccountMeta::new_readonly(config_pubkey, false),
+        AccountMeta::new(farm_pool_pubkey, false),
+        AccountMeta::new_readonly(admin_pubkey, true),
+    ];
 
-    #[test]
-    fn test_pack_admin_init_config() {
-        let fees = DEFAULT_TEST_FEES;
-        let rewards = DEFAULT_TEST_REWARDS;
-        let check = AdminInstruction::Initialize(AdminInitializeData {
-            fees: fees.clone(),
-            rewards: rewards.clone(),
-        });
-        let packed = check.pack();
-        let mut expect = vec![100];
-        let is_initialized = vec![1, fees.is_initialized as u8];
-        expect.extend_from_slice(&is_initialized[0].to_le_bytes());
-        expect.extend_from_slice(&fees.admin_trade_fee_numerator.to_le_bytes());
-        expect.extend_from_slice(&fees.admin_trade_fee_denominator.to_le_bytes());
-        expect.extend_from_slice(&fees.admin_withdraw_fee_numerator.to_le_bytes());
-        expect.extend_from_slice(&fees.admin_withdraw_fee_denominator.to_le_bytes());
-        expect.extend_from_slice(&fees.trade_fee_numerator.to_le_bytes());
-        expect.extend_from_slice(&fees.trade_fee_denominator.to_le_bytes());
-        expect.extend_from_slice(&fees.withdraw_fee_numerator.to_le_bytes());
-        expect.extend_from_slice(&fees.withdraw_fee_denominator.to_le_bytes());
-        let is_initialized = vec![1, rewards.is_initialized as u8];
-        expect.extend_from_slice(&is_initialized[0].to_le_bytes());
-        expect.extend_from_slice(&rewards.decimals.to_le_bytes());
-        expect.extend_from_slice(&rewards.reserved);
-        expect.extend_from_slice(&rewards.trade_reward_numerator.to_le_bytes());
-        expect.extend_from_slice(&rewards.trade_reward_denominator.to_le_bytes());
-        expect.extend_from_slice(&rewards.trade_reward_cap.to_le_bytes());
-        assert_eq!(packed, expect);
-        let unpacked = AdminInstruction::unpack(&expect).unwrap();
-        assert_eq!(unpacked, check);
-    }
+    Ok(Instruction {
+        program_id,
+        accounts,
+        data,
+    })
+}
+
+/// Creates a 'FundStakingRewards' instruction.
+pub fn fund_staking_rewards(
+    program_id: Pubkey,
+    config_pubkey: Pubkey,
+    farm_pool_pubkey: Pubkey,
+    market_authority_pubkey: Pubkey,
+    reward_reserve_pubkey: Pubkey,
+    source_pubkey: Pubkey,
+    admin_pubkey: Pubkey,
+    amount: u64,
+) -> Result<Instruction, ProgramError> {
+    let data = AdminInstruction::FundStakingRewards(amount).pack();
+
+    let accounts = vec![
+        AccountMeta::new_readonly(config_pubkey, false),
+        AccountMeta::new_readonly(farm_pool_pubkey, false),
+        AccountMeta::new_readonly(market_authority_pubkey, false),
+        AccountMeta::new(reward_reserve_pubkey, false),
+        AccountMeta::new(source_pubkey, false),
+        AccountMeta::new_readonly(admin_pubkey, true),
+        AccountMeta::new_readonly(spl_token::id(), false),
+    ];
+
+    Ok(Instruction {
+        program_id,
+        accounts,
+        data,
+    })
+}
+
+/// Creates a 'SetRebalanceThreshold' instruction.
+pub fn set_rebalance_threshold(
+    program_id: Pubkey,
+    config_pubkey: Pubkey,
+    swap_pubkey: Pubkey,
+    admin_pubkey: Pubkey,
+    rebalance_threshold_bps: u16,
+) -> Result<Instruction, ProgramError> {
+    let data = AdminInstruction::SetRebalanceThreshold(rebalance_threshold_bps).pack();
+
+    let accounts = vec![
+        AccountMeta::new_readonly(config_pubkey, false),
+        AccountMeta::new(swap_pubkey, false),
+        AccountMeta::new_readonly(admin_pubkey, true),
+    ];
+
+    Ok(Instruction {
+        program_id,
+        accounts,
+        data,
+    })
+}
+
+/// Creates a 'SetCircuitBreaker' instruction.
+pub fn set_circuit_breaker(
+    program_id: Pubkey,
+    config_pubkey: Pubkey,
+    swap_pubkey: Pubkey,
+    admin_pubkey: Pubkey,
+    max_deviation_bps: u16,
+    window_slots: u64,
+) -> Result<Instruction, ProgramError> {
+    let data = AdminInstruction::SetCircuitBreaker(max_deviation_bps, window_slots).pack();
+
+    let accounts = vec![
+        AccountMeta::new_readonly(config_pubkey, false),
+        AccountMeta::new(swap_pubkey, false),
+        AccountMeta::new_readonly(admin_pubkey, true),
+    ];
+
+    Ok(Instruction {
+        program_id,
+        accounts,
+        data,
+    })
+}
+
+/// Creates a 'SetDepositCap' instruction.
+pub fn set_deposit_cap(
+    program_id: Pubkey,
+    config_pubkey: Pubkey,
+    swap_pubkey: Pubkey,
+    admin_pubkey: Pubkey,
+    max_total_supply: u64,
+) -> Result<Instruction, ProgramError> {
+    let data = AdminInstruction::SetDepositCap(max_total_supply).pack();
+
+    let accounts = vec![
+        AccountMeta::new_readonly(config_pubkey, false),
+        AccountMeta::new(swap_pubkey, false),
+        AccountMeta::new_readonly(admin_pubkey, true),
+    ];
+
+    Ok(Instruction {
+        program_id,
+        accounts,
+        data,
+    })
+}
+
+/// Creates a 'SetWhitelistEnabled' instruction.
+pub fn set_whitelist_enabled(
+    program_id: Pubkey,
+    config_pubkey: Pubkey,
+    swap_pubkey: Pubkey,
+    admin_pubkey: Pubkey,
+    whitelist_enabled: bool,
+) -> Result<Instruction, ProgramError> {
+    let data = AdminInstruction::SetWhitelistEnabled(whitelist_enabled).pack();
+
+    let accounts = vec![
+        AccountMeta::new_readonly(config_pubkey, false),
+        AccountMeta::new(swap_pubkey, false),
+        AccountMeta::new_readonly(admin_pubkey, true),
+    ];
+
+    Ok(Instruction {
+        program_id,
+        accounts,
+        data,
+    })
+}
+
+/// Creates an 'AddWhitelistEntry' instruction.
+pub fn add_whitelist_entry(
+    program_id: Pubkey,
+    config_pubkey: Pubkey,
+    swap_pubkey: Pubkey,
+    whitelist_entry_pubkey: Pubkey,
+    user_pubkey: Pubkey,
+    admin_pubkey: Pubkey,
+) -> Result<Instruction, ProgramError> {
+    let data = AdminInstruction::AddWhitelistEntry.pack();
+
+    let accounts = vec![
+        AccountMeta::new_readonly(config_pubkey, false),
+        AccountMeta::new_readonly(swap_pubkey, false),
+        AccountMeta::new(whitelist_entry_pubkey, false),
+        AccountMeta::new_readonly(user_pubkey, false),
+        AccountMeta::new(admin_pubkey, true),
+        AccountMeta::new_readonly(rent::id(), false),
+        AccountMeta::new_readonly(system_program::id(), false),
+    ];
+
+    Ok(Instruction {
+        program_id,
+        accounts,
+        data,
+    })
+}
+
+/// Creates a 'RemoveWhitelistEntry' instruction.
+pub fn remove_whitelist_entry(
+    program_id: Pubkey,
+    config_pubkey: Pubkey,
+    swap_pubkey: Pubkey,
+    whitelist_entry_pubkey: Pubkey,
+    admin_pubkey: Pubkey,
+) -> Result<Instruction, ProgramError> {
+    let data = AdminInstruction::RemoveWhitelistEntry.pack();
+
+    let accounts = vec![
+        AccountMeta::new_readonly(config_pubkey, false),
+        AccountMeta::new_readonly(swap_pubkey, false),
+        AccountMeta::new(whitelist_entry_pubkey, false),
+        AccountMeta::new(admin_pubkey, true),
+    ];
+
+    Ok(Instruction {
+        program_id,
+        accounts,
+        data,
+    })
+}
+
+/// Creates a 'MigrateSwapInfo' instruction.
+pub fn migrate_swap_info(
+    program_id: Pubkey,
+    config_pubkey: Pubkey,
+    swap_pubkey: Pubkey,
+    admin_pubkey: Pubkey,
+) -> Result<Instruction, ProgramError> {
+    let data = AdminInstruction::MigrateSwapInfo.pack();
+
+    let accounts = vec![
+        AccountMeta::new_readonly(config_pubkey, false),
+        AccountMeta::new(swap_pubkey, false),
+        AccountMeta::new(admin_pubkey, true),
+        AccountMeta::new_readonly(rent::id(), false),
+        AccountMeta::new_readonly(system_program::id(), false),
+    ];
+
+    Ok(Instruction {
+        program_id,
+        accounts,
+        data,
+    })
+}
+
+/// Creates a 'MigrateConfigInfo' instruction.
+pub fn migrate_config_info(
+    program_id: Pubkey,
+    config_pubkey: Pubkey,
+    admin_pubkey: Pubkey,
+) -> Result<Instruction, ProgramError> {
+    let data = AdminInstruction::MigrateConfigInfo.pack();
+
+    let accounts = vec![
+        AccountMeta::new(config_pubkey, false),
+        AccountMeta::new(admin_pubkey, true),
+        AccountMeta::new_readonly(rent::id(), false),
+        AccountMeta::new_readonly(system_program::id(), false),
+    ];
+
+    Ok(Instruction {
+        program_id,
+        accounts,
+        data,
+    })
+}
+
+/// Creates a 'MigrateFarmInfo' instruction.
+pub fn migrate_farm_info(
+    program_id: Pubkey,
+    config_pubkey: Pubkey,
+    farm_pool_pubkey: Pubkey,
+    admin_pubkey: Pubkey,
+) -> Result<Instruction, ProgramError> {
+    let data = AdminInstruction::MigrateFarmInfo.pack();
+
+    let accounts = vec![
+        AccountMeta::new_readonly(config_pubkey, false),
+        AccountMeta::new(farm_pool_pubkey, false),
+        AccountMeta::new(admin_pubkey, true),
+        AccountMeta::new_readonly(rent::id(), false),
+        AccountMeta::new_readonly(system_program::id(), false),
+    ];
+
+    Ok(Instruction {
+        program_id,
+        accounts,
+        data,
+    })
+}
+
+/// Creates a 'SetOracleAccounts' instruction.
+pub fn set_oracle_accounts(
+    program_id: Pubkey,
+    config_pubkey: Pubkey,
+    swap_pubkey: Pubkey,
+    admin_pubkey: Pubkey,
+    pyth_a_product_pubkey: Pubkey,
+    pyth_a_pubkey: Pubkey,
+    pyth_b_product_pubkey: Pubkey,
+    pyth_b_pubkey: Pubkey,
+    serum_market_pubkey: Pubkey,
+    serum_bids_pubkey: Pubkey,
+    serum_asks_pubkey: Pubkey,
+) -> Result<Instruction, ProgramError> {
+    let data = AdminInstruction::SetOracleAccounts.pack();
+
+    let accounts = vec![
+        AccountMeta::new_readonly(config_pubkey, false),
+        AccountMeta::new(swap_pubkey, false),
+        AccountMeta::new_readonly(admin_pubkey, true),
+        AccountMeta::new_readonly(pyth_a_product_pubkey, false),
+        AccountMeta::new_readonly(pyth_a_pubkey, false),
+        AccountMeta::new_readonly(pyth_b_product_pubkey, false),
+        AccountMeta::new_readonly(pyth_b_pubkey, false),
+        AccountMeta::new_readonly(serum_market_pubkey, false),
+        AccountMeta::new_readonly(serum_bids_pubkey, false),
+        AccountMeta::new_readonly(serum_asks_pubkey, false),
+    ];
+
+    Ok(Instruction {
+        program_id,
+        accounts,
+        data,
+    })
+}
+
+/// Creates a 'SetOraclePriority' instruction.
+pub fn set_oracle_priority(
+    program_id: Pubkey,
+    config_pubkey: Pubkey,
+    swap_pubkey: Pubkey,
+    admin_pubkey: Pubkey,
+    pyth_a_product_pubkey: Pubkey,
+    pyth_a_pubkey: Pubkey,
+    pyth_b_product_pubkey: Pubkey,
+    pyth_b_pubkey: Pubkey,
+    serum_market_pubkey: Pubkey,
+    serum_bids_pubkey: Pubkey,
+    serum_asks_pubkey: Pubkey,
+    oracle_priority_flags: u8,
+) -> Result<Instruction, ProgramError> {
+    let mut instruction = set_oracle_accounts(
+        program_id,
+        config_pubkey,
+        swap_pubkey,
+        admin_pubkey,
+        pyth_a_product_pubkey,
+        pyth_a_pubkey,
+        pyth_b_product_pubkey,
+        pyth_b_pubkey,
+        serum_market_pubkey,
+        serum_bids_pubkey,
+        serum_asks_pubkey,
+    )?;
+    instruction.data = AdminInstruction::SetOraclePriority(oracle_priority_flags).pack();
+    Ok(instruction)
+}
+
+/// Creates a 'SetPriceSetter' instruction.
+pub fn set_price_setter(
+    program_id: Pubkey,
+    config_pubkey: Pubkey,
+    swap_pubkey: Pubkey,
+    admin_pubkey: Pubkey,
+    price_setter_pubkey: Pubkey,
+    max_change_bps: u16,
+    stale_slots: u64,
+) -> Result<Instruction, ProgramError> {
+    let data = AdminInstruction::SetPriceSetter(max_change_bps, stale_slots).pack();
+
+    let accounts = vec![
+        AccountMeta::new_readonly(config_pubkey, false),
+        AccountMeta::new(swap_pubkey, false),
+        AccountMeta::new_readonly(admin_pubkey, true),
+        AccountMeta::new_readonly(price_setter_pubkey, false),
+    ];
+
+    Ok(Instruction {
+        program_id,
+        accounts,
+        data,
+    })
+}
+
+/// Creates a 'SetPriceEmaWindow' instruction.
+pub fn set_price_ema_window(
+    program_id: Pubkey,
+    config_pubkey: Pubkey,
+    swap_pubkey: Pubkey,
+    admin_pubkey: Pubkey,
+    window_slots: u64,
+) -> Result<Instruction, ProgramError> {
+    let data = AdminInstruction::SetPriceEmaWindow(window_slots).pack();
+
+    let accounts = vec![
+        AccountMeta::new_readonly(config_pubkey, false),
+        AccountMeta::new(swap_pubkey, false),
+        AccountMeta::new_readonly(admin_pubkey, true),
+    ];
+
+    Ok(Instruction {
+        program_id,
+        accounts,
+        data,
+    })
+}
+
+/// Creates a 'SetSkimSurplus' instruction.
+pub fn set_skim_surplus(
+    program_id: Pubkey,
+    config_pubkey: Pubkey,
+    swap_pubkey: Pubkey,
+    admin_pubkey: Pubkey,
+    skim_surplus: bool,
+) -> Result<Instruction, ProgramError> {
+    let data = AdminInstruction::SetSkimSurplus(skim_surplus).pack();
+
+    let accounts = vec![
+        AccountMeta::new_readonly(config_pubkey, false),
+        AccountMeta::new(swap_pubkey, false),
+        AccountMeta::new_readonly(admin_pubkey, true),
+    ];
+
+    Ok(Instruction {
+        program_id,
+        accounts,
+        data,
+    })
+}
+
+/// Creates a 'SetProtocolFeeSwitch' instruction.
+pub fn set_protocol_fee_switch(
+    program_id: Pubkey,
+    config_pubkey: Pubkey,
+    admin_pubkey: Pubkey,
+    treasury_pubkey: Pubkey,
+    is_protocol_fee_enabled: bool,
+) -> Result<Instruction, ProgramError> {
+    let data = AdminInstruction::SetProtocolFeeSwitch(is_protocol_fee_enabled).pack();
+
+    let accounts = vec![
+        AccountMeta::new(config_pubkey, false),
+        AccountMeta::new_readonly(admin_pubkey, true),
+        AccountMeta::new_readonly(treasury_pubkey, false),
+    ];
+
+    Ok(Instruction {
+        program_id,
+        accounts,
+        data,
+    })
+}
+
+/// Instructions supported by the pool SwapInfo program.
+#[repr(C)]
+#[derive(Debug, PartialEq)]
+pub enum SwapInstruction {
+    ///   Initializes a new SwapInfo.
+    ///
+    ///   0. `[]` market config.
+    ///   1. `[writable]` new token-swap to create.
+    ///   2. `[]` $swap_authority.
+    ///   3. `[]` admin fee account for base token.
+    ///   4. `[]` admin fee account for quote token.
+    ///   5. `[]` base token account. Must be non zero, owned by $swap_authority.
+    ///   6. `[]` quote token account. Must be non zero, owned by $swap_authority.
+    ///   7. `[writable]` pool mint account mint by $swap_authority.
+    ///   8. `[writable]` pool token account owned by user.
+    ///   9. `[]` base token product from pyth network.
+    ///   10. `[]` base token price from pyth network.
+    ///   11. `[]` quote token product from pyth network.
+    ///   12. `[]` quote token price from pyth network.
+    ///   13. `[signer]` admin account.
+    ///   14. '[]' serum market account
+    ///   15. '[]' serum bids orderbook account
+    ///   16. '[]' serum asks orderbook account
+    ///   17. `[]` clock sysvar.
+    ///   18. `[]` rent sysvar.
+    ///   19. `[]` token program id.
+    ///
+    ///   When the oracle feed registry of the config is enabled, the accounts end with:
+    ///
+    ///   20. `[]` oracle feed of the base token mint.
+    ///   21. `[]` oracle feed of the quote token mint.
+    Initialize(InitializeData),
+
+    ///   Swap the tokens in the pool.
+    ///
+    ///   Only the pools priced from their pyth price accounts swap with it, the pools on the
+    ///   other oracles swap with SwapV2.
+    ///
+    ///   On a whitelist-gated pool the whitelist entry of $user_transfer_authority follows the
+    ///   token program id, ahead of the optional accounts.
+    ///
+    ///   0. `[]` market config.
+    ///   1. `[writable]` token-swap.
+    ///   2. `[]` $market_authority to mint deltafi token.
+    ///   3. `[]` $swap_authority.
+    ///   4. `[signer]` $user_transfer_authority
+    ///   5. `[writable]` SOURCE(base|quote) account, transferable by $user_transfer_authority.
+    ///   6. `[writable]` (base|quote) token account to swap INTO. Must be the SOURCE token.
+    ///   7. `[]` Mint account that provides mint info including decimals of the SOURCE token,
+    ///   8. `[writable]` (base|quote) token account to swap FROM. Must be the DESTINATION token.
+    ///   9. `[writable]` DESTINATION(base|quote) account owned by user.
+    ///   10. `[]` mint account that provides mint info including decimals of the DESTINATION token.
+    ///   11. `[writable]` rewards(DELTAFI) token account owned by user.
+    ///   12. `[writable]` rewards(DELTAFI) source deltafi token account to issue reward.
+    ///   13. `[writable]` (base|quote) admin fee account. Must have same mint as DESTINATION token.
+    ///   14. `[]` base token price from pyth network.
+    ///   15. `[]` quote token price from pyth network.
+    ///   16. `[]` token program id.
+    ///   17. `[]` optional: price cache of the pool, read instead of the pyth prices when fresh.
+    ///   18. `[]` optional: fee discount account of $user_transfer_authority, discounting the
+    ///       trade fee by the tier of its staked DELTAFI.
+    ///   19. `[]` optional: fee discount tiers of the market config, required with 18.
+    ///   20. `[writable]` optional: treasury token account of the DESTINATION mint owned by
+    ///       the config treasury, required while the protocol fee switch is on.
+    ///   21. `[]` optional: instructions sysvar, required if the output owner is set.
+    ///   22. `[]` optional: user referrer data account.
+    ///   23. `[writable]` optional: referrer token account.
+    ///   24. `[writable]` optional: referrer stats of the referrer token account, paying the
+    ///       referral reward at the tier of its referred volume instead of the base tier.
+    Swap(SwapData),
+
+    ///   Deposit some tokens into the pool.  The output is a "pool" token representing ownership
+    ///   into the pool. Inputs are converted to the current ratio.
+    ///
+    ///   On a whitelist-gated pool the whitelist entry of $user_transfer_authority follows the
+    ///   token program id, ahead of the optional accounts.
+    ///
+    ///   0. `[]` token-swap.
+    ///   1. `[]` $swap_authority.
+    ///   2. `[signer]` $user_transfer_authority.
+    ///   3. `[writable]` base token account to deposit FROM, transferable by $user_transfer_authority.
+    ///   4. `[writable]` quote token account to deposit FROM, transferable by $user_transfer_authority.
+    ///   5. `[writable]` base token account to deposit INTO.
+    ///   6. `[writable]` quote token account to deposit INTO.
+    ///   7. `[writable]` pool mint account, mint by $swap_authority.
+    ///   8. `[writable]` pool token account owned by user.
+    ///   9. `[]` base token price from pyth network.
+    ///   10. `[]` quote token price from pyth network.
+    ///   11. `[]` clock sysvar.
+    ///   12. `[]` token program id.
+    Deposit(DepositData),
+
+    ///   Withdraw tokens from the pool at the current ratio.
+    ///
+    ///   0. `[]` token-swap.
+    ///   1. `[]` $swap_authority.
+    ///   2. `[signer]` $user_transfer_authority.
+    ///   3. `[writable]` pool mint account, mint by $swap_autority.
+    ///   4. `[writable]` SOURCE pool token account, transferable by $user_transfer_authority.
+    ///   5. `[writable]` base token account to withdraw FROM.
+    ///   6. `[writable]` quote token account to withdraw FROM.
+    ///   7. `[writable]` base token account to withdraw INTO.
+    ///   8. `[writable]` quote token account to withdraw INTO.
+    ///   9. `[writable]` admin fee account for base token.
+    ///   10. `[writable]` admin fee account for quote token.
+    ///   11. `[]` base token price from pyth network.
+    ///   12. `[]` quote token price from pyth network.
+    ///   13. `[]` clock sysvar.
+    ///   14. `[]` token program id.
+    Withdraw(WithdrawData),
+
+    ///   Withdraw tokens from the pool at the current ratio.
+    ///
+    ///   0. `[]` market config.
+    ///   1. `[signer]` user.
+    ///   2. `[writable]` user referrer data account
+    ///   3. `[]` referrer token address.
+    ///   4. `[]` rent sysvar.
+    ///   5. `[]` token program id.
+    SetReferrer,
+
+    ///   Swap the tokens in the pool (removed src and dest mint accounts).
+    ///
+    ///   On a whitelist-gated pool the whitelist entry of $user_transfer_authority follows the
+    ///   token program id, ahead of the optional accounts.
+    ///
+    ///   0. `[]` market config.
+    ///   1. `[writable]` token-swap.
+    ///   2. `[]` $market_authority to mint deltafi token.
+    ///   3. `[]` $swap_authority.
+    ///   4. `[signer]` $user_transfer_authority
+    ///   5. `[writable]` SOURCE(base|quote) account, transferable by $user_transfer_authority.
+    ///   6. `[writable]` (base|quote) token account to swap INTO. Must be the SOURCE token.
+    ///   7. `[writable]` (base|quote) token account to swap FROM. Must be the DESTINATION token.
+    ///   8. `[writable]` DESTINATION(base|quote) account owned by user.
+    ///   9. `[writable]` rewards(DELTAFI) token account owned by user.
+    ///   10. `[writable]` rewards(DELTAFI) source deltafi token account to issue reward.
+    ///   11. `[writable]` (base|quote) admin fee account. Must have same mint as DESTINATION token.
+    ///   12. `[]` base token price from pyth network.
+    ///   13. `[]` quote token price from pyth network.
+    ///   14. '[]' serum market account
+    ///   15. '[]' serum bids orderbook account
+    ///   16. '[]' serum asks orderbook account
+    ///   17. `[]` token program id.
+    ///   18. `[]` optional: price cache of the pool, read instead of the pyth prices when fresh
+    ///       on pools priced by pyth only.
+    ///   19. `[]` optional: fee discount account of $user_transfer_authority, discounting the
+    ///       trade fee by the tier of its staked DELTAFI.
+    ///   20. `[]` optional: fee discount tiers of the market config, required with 19.
+    ///   21. `[writable]` optional: treasury token account of the DESTINATION mint owned by
+    ///       the config treasury, required while the protocol fee switch is on.
+    ///   22. `[]` optional: instructions sysvar, required if the output owner is set.
+    ///   23. `[]` optional: user referrer data account.
+    ///   24. `[writable]` optional: referrer token account.
+    ///   25. `[writable]` optional: token account of the DESTINATION mint owned by the referrer,
+    ///       required with a non-dummy referrer while the referral fee share of the pool is set.
+    ///   26. `[writable]` optional: referrer stats of the referrer token account, paying the
+    ///       referral reward at the tier of its referred volume instead of the base tier.
+    SwapV2(SwapData),
+
+    ///   Deposit a single token into the pool. Part of the input is swapped to the other
+    ///   side at the oracle price and the rest is deposited with the swap output.
+    ///
+    ///   On a whitelist-gated pool the whitelist entry of $user_transfer_authority follows the
+    ///   token program id, ahead of the optional accounts.
+    ///
+    ///   0. `[]` market config.
+    ///   1. `[writable]` token-swap.
+    ///   2. `[]` $swap_authority.
+    ///   3. `[signer]` $user_transfer_authority.
+    ///   4. `[writable]` SOURCE(base|quote) account, transferable by $user_transfer_authority.
+    ///   5. `[writable]` base token account to deposit INTO.
+    ///   6. `[writable]` quote token account to deposit INTO.
+    ///   7. `[writable]` (base|quote) admin fee account. Must be the other side of SOURCE token.
+    ///   8. `[writable]` pool mint account, mint by $swap_authority.
+    ///   9. `[writable]` pool token account owned by user.
+    ///   10. `[]` base token price from pyth network.
+    ///   11. `[]` quote token price from pyth network.
+    ///   12. '[]' serum market account
+    ///   13. '[]' serum bids orderbook account
+    ///   14. '[]' serum asks orderbook account
+    ///   15. `[]` token program id.
+    DepositOne(DepositOneData),
+
+    ///   Withdraw a single token from the pool. The other side of the withdrawn liquidity
+    ///   is swapped into the requested token at the oracle price.
+    ///
+    ///   0. `[]` market config.
+    ///   1. `[writable]` token-swap.
+    ///   2. `[]` $swap_authority.
+    ///   3. `[signer]` $user_transfer_authority.
+    ///   4. `[writable]` pool mint account, $swap_authority can burn.
+    ///   5. `[writable]` SOURCE pool account, amount is transferable by $user_transfer_authority.
+    ///   6. `[writable]` base token account to withdraw FROM.
+    ///   7. `[writable]` quote token account to withdraw FROM.
+    ///   8. `[writable]` (base|quote) user account to credit, matching `token_side`.
+    ///   9. `[writable]` (base|quote) admin fee account, matching `token_side`.
+    ///   10. `[]` base token price from pyth network.
+    ///   11. `[]` quote token price from pyth network.
+    ///   12. '[]' serum market account
+    ///   13. '[]' serum bids orderbook account
+    ///   14. '[]' serum asks orderbook account
+    ///   15. `[]` token program id.
+    WithdrawOne(WithdrawOneData),
+
+    ///   Post or replace the quote of an approved market maker. The market maker approves
+    ///   $swap_authority as the delegate of its inventory accounts to settle the fills.
+    ///
+    ///   0. `[]` market config.
+    ///   1. `[]` token-swap.
+    ///   2. `[writable]` quote account, created with seed from the market maker and token-swap.
+    ///   3. `[signer]` market maker on the config allowlist.
+    ///   4. `[]` base token inventory account owned by the market maker.
+    ///   5. `[]` quote token inventory account owned by the market maker.
+    ///   6. `[]` clock sysvar.
+    ///   7. `[]` rent sysvar.
+    ///   8. `[]` token program id.
+    PostQuote(QuoteData),
+
+    ///   Fill the quote of a market maker, settled from the market maker inventory.
+    ///
+    ///   0. `[]` market config.
+    ///   1. `[]` token-swap.
+    ///   2. `[]` $market_authority to issue deltafi token.
+    ///   3. `[]` $swap_authority, delegate of the market maker inventory accounts.
+    ///   4. `[writable]` quote account.
+    ///   5. `[signer]` $user_transfer_authority
+    ///   6. `[writable]` SOURCE(base|quote) account, transferable by $user_transfer_authority.
+    ///   7. `[writable]` DESTINATION(base|quote) account owned by user.
+    ///   8. `[writable]` market maker inventory account to swap INTO. Must be the SOURCE token.
+    ///   9. `[writable]` market maker inventory account to swap FROM. Must be the DESTINATION token.
+    ///   10. `[writable]` (base|quote) admin fee account. Must have same mint as DESTINATION token.
+    ///   11. `[writable]` rewards(DELTAFI) token account owned by user.
+    ///   12. `[writable]` rewards(DELTAFI) source deltafi token account to issue reward.
+    ///   13. `[]` clock sysvar.
+    ///   14. `[]` token program id.
+    FillQuote(SwapData),
+
+    ///   Lend pool tokens to the caller and invoke its callback program, the callback must
+    ///   repay the borrowed amounts plus the flash fee into the pool token accounts before
+    ///   it returns. The pool is locked against any other instruction during the callback.
+    ///
+    ///   0. `[]` market config.
+    ///   1. `[writable]` token-swap.
+    ///   2. `[]` $swap_authority.
+    ///   3. `[writable]` base token account of the pool.
+    ///   4. `[writable]` quote token account of the pool.
+    ///   5. `[writable]` base token account receiving the borrowed base token.
+    ///   6. `[writable]` quote token account receiving the borrowed quote token.
+    ///   7. `[writable]` base admin fee account.
+    ///   8. `[writable]` quote admin fee account.
+    ///   9. `[]` token program id.
+    ///   10. `[writable]` optional: treasury token account of the base mint owned by the
+    ///       config treasury, required while the protocol fee switch is on.
+    ///   11. `[writable]` optional: treasury token account of the quote mint, required with 10.
+    ///   12. `[]` callback program id.
+    ///   13. ..13+N `[]` accounts passed to the callback program, with their signer and
+    ///       writable flags.
+    FlashSwap(FlashSwapData),
+    /// Initializes a new swap with the mid price committed by the admin in
+    /// `CommitInitialPrice`, the price is only accepted if it hashes with the salt to the
+    /// commitment. The accounts are the ones of `Initialize` with the admin writable and:
+    ///
+    ///   20. `[writable]` price commitment account, closed to the admin after the reveal.
+    ///   21. `[]` oracle feed of the base token mint, if the oracle feed registry is enabled.
+    ///   22. `[]` oracle feed of the quote token mint, if the oracle feed registry is enabled.
+    RevealInitialize(InitializeData, [u8; 32]),
+
+    ///   Quote a swap of a normal or stable pool without moving any token, the quote is set
+    ///   as the return data of the instruction as a packed `SwapQuote`.
+    ///
+    ///   0. `[]` market config.
+    ///   1. `[]` token-swap.
+    ///   2. `[]` base token price from pyth network, for normal pools.
+    ///   3. `[]` quote token price from pyth network, for normal pools.
+    ///   4. '[]' serum market account, for normal pools.
+    ///   5. '[]' serum bids orderbook account, for normal pools.
+    ///   6. '[]' serum asks orderbook account, for normal pools.
+    ///
+    ///   Stable pools with an exchange rate adapter take the exchange rate account instead.
+    GetQuote(GetQuoteData),
+
+    ///   Update the price cache of a normal pool with the market price read from its pyth
+    ///   price accounts, signed by the relayer of the price cache.
+    ///
+    ///   0. `[]` token-swap.
+    ///   1. `[writable]` price cache of the pool.
+    ///   2. `[signer]` relayer of the price cache.
+    ///   3. `[]` base token price from pyth network.
+    ///   4. `[]` quote token price from pyth network.
+    UpdatePriceCache,
+
+    ///   Hold a free scratch account, its token account is delegated to the holder so it can
+    ///   be used as the SOURCE or DESTINATION of the instructions of the transaction. It must
+    ///   be released with `ReleaseScratch` in the same transaction.
+    ///
+    ///   0. `[writable]` scratch account.
+    ///   1. `[writable]` scratch token account.
+    ///   2. `[signer]` holder.
+    ///   3. `[]` token program id.
+    AcquireScratch,
+
+    ///   Release a scratch account, the tokens left in custody are sent to the destination
+    ///   and the delegation of the holder is revoked.
+    ///
+    ///   0. `[writable]` scratch account.
+    ///   1. `[writable]` scratch token account.
+    ///   2. `[signer]` holder.
+    ///   3. `[writable]` destination token account of the tokens left.
+    ///   4. `[]` token program id.
+    ReleaseScratch,
+
+    ///   Initializes a new swap at the program address derived from `find_swap_address` with
+    ///   the mints of the base and quote token accounts, so a market has a single normal pool
+    ///   of a pair. The accounts are the ones of `Initialize` with the swap not signing and the
+    ///   admin writable to pay for the swap account, with the system program following the
+    ///   token program:
+    ///
+    ///   20. `[]` system program.
+    ///   21. `[]` oracle feed of the base token mint, if the oracle feed registry is enabled.
+    ///   22. `[]` oracle feed of the quote token mint, if the oracle feed registry is enabled.
+    InitializeV2(InitializeData),
+
+    ///   Stake DELTAFI in the fee discount account of the owner, the fee discount account and
+    ///   its token account are created on the first stake. The discount of the staked amount
+    ///   applies to the swaps from the next slot.
+    ///
+    ///   0. `[]` market config.
+    ///   1. `[writable]` fee discount account derived from `find_fee_discount_address`.
+    ///   2. `[writable]` fee discount token account derived from
+    ///      `find_fee_discount_token_address`.
+    ///   3. `[writable]` SOURCE DELTAFI token account, transferable by the owner.
+    ///   4. `[writable, signer]` owner, pays for the fee discount accounts.
+    ///   5. `[]` DELTAFI mint.
+    ///   6. `[]` rent sysvar.
+    ///   7. `[]` system program.
+    ///   8. `[]` token program id.
+    StakeFeeDiscount(u64),
+
+    ///   Unstake DELTAFI from the fee discount account of the owner.
+    ///
+    ///   0. `[writable]` fee discount account.
+    ///   1. `[writable]` fee discount token account.
+    ///   2. `[signer]` owner.
+    ///   3. `[writable]` DESTINATION DELTAFI token account.
+    ///   4. `[]` token program id.
+    UnstakeFeeDiscount(u64),
+
+    ///   Change the referrer set by `SetReferrer` once the referrer cooldown has passed, the
+    ///   dummy referrer clears it.
+    ///
+    ///   0. `[]` market config.
+    ///   1. `[signer]` user.
+    ///   2. `[writable]` user referrer data account
+    ///   3. `[]` new referrer token address.
+    ///   4. `[]` token program id.
+    UpdateReferrer,
+
+    ///   Create the referral stats of a referrer DELTAFI token account, tracking its referred
+    ///   volume for the referral reward tiers of the swaps passing them after the referrer.
+    ///
+    ///   0. `[]` market config.
+    ///   1. `[]` referrer DELTAFI token account.
+    ///   2. `[writable]` referrer stats, at the program address of the config and referrer.
+    ///   3. `[writable, signer]` payer.
+    ///   4. `[]` rent sysvar.
+    ///   5. `[]` system program.
+    ///   6. `[]` token program id.
+    InitReferrerStats,
+
+    ///   Trade the reserves of a normal pool drifted from the oracle price back to the targets,
+    ///   open to anyone once the mid price deviates from the oracle price by the rebalance
+    ///   threshold of the pool. The caller pays the short token and receives the long token at
+    ///   the oracle price plus a bounty of `REBALANCE_BOUNTY_BPS`, capped at the output of a swap.
+    ///
+    ///   0. `[]` market config.
+    ///   1. `[writable]` token swap.
+    ///   2. `[]` $swap_authority.
+    ///   3. `[signer]` $user_transfer_authority.
+    ///   4. `[writable]` SOURCE account of the short token, transferable by $user_transfer_authority.
+    ///   5. `[writable]` DESTINATION account of the long token.
+    ///   6. `[writable]` token A account of the pool.
+    ///   7. `[writable]` token B account of the pool.
+    ///   8. `[]` pyth price account of token A.
+    ///   9. `[]` pyth price account of token B.
+    ///   10. `[]` serum market account.
+    ///   11. `[]` serum bids account.
+    ///   12. `[]` serum asks account.
+    ///   13. `[]` token program id.
+    Rebalance(RebalanceData),
+
+    ///   Lock pool tokens of the owner in its liquidity lock on the pool until the cliff, so a
+    ///   pool creator proves its liquidity is locked. The liquidity lock and its token account
+    ///   are created on the first lock, the next locks add to the locked amount and can only
+    ///   push the cliff back.
+    ///
+    ///   0. `[]` token swap.
+    ///   1. `[writable]` liquidity lock derived from `find_liquidity_lock_address`.
+    ///   2. `[writable]` liquidity lock token account derived from
+    ///      `find_liquidity_lock_token_address`.
+    ///   3. `[writable]` SOURCE pool token account, transferable by the owner.
+    ///   4. `[writable, signer]` owner, pays for the liquidity lock accounts.
+    ///   5. `[]` pool mint.
+    ///   6. `[]` rent sysvar.
+    ///   7. `[]` system program.
+    ///   8. `[]` token program id.
+    LockLiquidity(LockLiquidityData),
+
+    ///   Withdraw all the locked pool tokens of the owner past the cliff of its liquidity lock.
+    ///
+    ///   0. `[writable]` liquidity lock.
+    ///   1. `[writable]` liquidity lock token account.
+    ///   2. `[signer]` owner.
+    ///   3. `[writable]` DESTINATION pool token account.
+    ///   4. `[]` token program id.
+    UnlockLiquidity,
+
+    ///   Swap native SOL in or out of a normal pool through a temporary wSOL account of
+    ///   $user_transfer_authority, created for the swap and closed back to the user after it.
+    ///   The `amount_in` lamports are wrapped into it as the SOURCE, or the output is unwrapped
+    ///   from it as the DESTINATION. The output owner is not supported.
+    ///
+    ///   0. `[writable]` temporary wSOL account derived from `find_native_sol_address`.
+    ///   1. `[]` native mint.
+    ///   2. `[]` rent sysvar.
+    ///   3. `[]` system program.
+    ///   4. `[]` token program id.
+    ///   5. ..5+N the accounts of `SwapV2`, with $user_transfer_authority writable to fund the
+    ///      wSOL account and the wSOL account as the SOURCE or DESTINATION account.
+    SwapNativeSol(SwapData),
+
+    ///   Deposit native SOL into a normal pool through a temporary wSOL account of
+    ///   $user_transfer_authority, created for the deposit and closed back to the user after
+    ///   it with the lamports not deposited.
+    ///
+    ///   0. `[writable]` temporary wSOL account derived from `find_native_sol_address`.
+    ///   1. `[]` native mint.
+    ///   2. `[]` rent sysvar.
+    ///   3. `[]` system program.
+    ///   4. `[]` token program id.
+    ///   5. ..5+N the accounts of `Deposit`, with $user_transfer_authority writable to fund the
+    ///      wSOL account and the wSOL account as the base or quote token account to deposit
+    ///      FROM.
+    DepositNativeSol(DepositData),
+
+    ///   Swap the tokens in a normal pool, filling as much of `amount_in` as the swap out limit
+    ///   and the circuit breaker band of the pool allow instead of failing. The unused input
+    ///   stays in the SOURCE account and `minimum_amount_out` is scaled down with the filled
+    ///   input. The filled amounts are set as the return data of the instruction as a packed
+    ///   `SwapFill`.
+    ///
+    ///   The accounts are the ones of `SwapV2`.
+    SwapPartialFill(SwapData),
+
+    ///   Push the market price of a MANUAL oracle pool, quoted in the conventional order of the
+    ///   pair like the initial mid price and scaled by 10^18. The price moves by at most the
+    ///   max change of the pool from the previous one and goes stale after the stale slots of
+    ///   the pool, the swaps fail until the next push.
+    ///
+    ///   0. `[writable]` token-swap.
+    ///   1. `[signer]` price setter of the pool.
+    SetManualPrice(u128),
+
+    ///   Reconcile the pool state with the balances of the pool token accounts, open to anyone.
+    ///   The tokens sent directly to the pool above its reserves and fees are credited to the
+    ///   protocol fees of the pool, claimed by the fee manager with `ClaimProtocolFees`.
+    ///
+    ///   0. `[writable]` token-swap.
+    ///   1. `[]` token A account of the pool.
+    ///   2. `[]` token B account of the pool.
+    ///   3. `[]` token program id.
+    Sync,
+
+    ///   Refresh the market price of a normal pool from its oracle without trading, open to
+    ///   anyone once the pool has gone `REFRESH_PRICE_INTERVAL_SLOTS` without a newer oracle
+    ///   price. The caller is paid `REFRESH_PRICE_REWARD` DELTAFI when the oracle has a newer
+    ///   price than the pool.
+    ///
+    ///   0. `[]` market config.
+    ///   1. `[writable]` token swap.
+    ///   2. `[]` market authority.
+    ///   3. `[writable]` DELTAFI token account of the caller to receive the reward.
+    ///   4. `[writable]` DELTAFI token account of the market authority to pay the reward from.
+    ///   5. `[]` pyth price account of token A.
+    ///   6. `[]` pyth price account of token B.
+    ///   7. `[]` serum market account.
+    ///   8. `[]` serum bids account.
+    ///   9. `[]` serum asks account.
+    ///   10. `[]` token program id.
+    RefreshPrice,
+}
+
+impl SwapInstruction {
+    /// Unpacks a byte buffer into a [SwapInstruction](enum.SwapInstruction.html).
+    pub fn unpack(input: &[u8]) -> Result<Self, ProgramError> {
+        let (&tag, rest) = input
+            .split_first()
+            .ok_or(SwapError::InstructionUnpackError)?;
+        Ok(match tag {
+            0 => {
+                let (init_data, _) = unpack_initialize_data(rest)?;
+                Self::Initialize(init_data)
+            }
+            1 => {
+                let (amount_in, rest) = unpack_u64(rest)?;
+                let (minimum_amount_out, rest) = unpack_u64(rest)?;
+                let (deadline_slot, output_owner) = unpack_swap_options(rest)?;
+                Self::Swap(SwapData {
+                    amount_in,
+                    minimum_amount_out,
+                    deadline_slot,
+                    output_owner,
+                })
+            }
+            2 => {
+                let (token_a_amount, rest) = unpack_u64(rest)?;
+                let (token_b_amount, rest) = unpack_u64(rest)?;
+                let (min_mint_amount, rest) = unpack_u64(rest)?;
+                let deadline_slot = unpack_deadline_slot(rest)?;
+                Self::Deposit(DepositData {
+                    token_a_amount,
+                    token_b_amount,
+                    min_mint_amount,
+                    deadline_slot,
+                })
+            }
+            3 => {
+                let (pool_token_amount, rest) = unpack_u64(rest)?;
+                let (minimum_token_a_amount, rest) = unpack_u64(rest)?;
+                let (minimum_token_b_amount, rest) = unpack_u64(rest)?;
+                let deadline_slot = unpack_deadline_slot(rest)?;
+                Self::Withdraw(WithdrawData {
+                    pool_token_amount,
+                    minimum_token_a_amount,
+                    minimum_token_b_amount,
+                    deadline_slot,
+                })
+            }
+            4 => Self::SetReferrer,
+            5 => {
+                let (amount_in, rest) = unpack_u64(rest)?;
+                let (minimum_amount_out, rest) = unpack_u64(rest)?;
+                let (deadline_slot, output_owner) = unpack_swap_options(rest)?;
+                Self::SwapV2(SwapData {
+                    amount_in,
+                    minimum_amount_out,
+                    deadline_slot,
+                    output_owner,
+                })
+            }
+            6 => {
+                let (amount_in, rest) = unpack_u64(rest)?;
+                let (min_mint_amount, rest) = unpack_u64(rest)?;
+                let deadline_slot = unpack_deadline_slot(rest)?;
+                Self::DepositOne(DepositOneData {
+                    amount_in,
+                    min_mint_amount,
+                    deadline_slot,
+                })
+            }
+            7 => {
+                let (pool_token_amount, rest) = unpack_u64(rest)?;
+                let (minimum_amount_out, rest) = unpack_u64(rest)?;
+                let (token_side, rest) = unpack_u8(rest)?;
+                let deadline_slot = unpack_deadline_slot(rest)?;
+                Self::WithdrawOne(WithdrawOneData {
+                    pool_token_amount,
+                    minimum_amount_out,
+                    token_side,
+                    deadline_slot,
+                })
+            }
+            8 => {
+                let (swap_direction, rest) = unpack_u8(rest)?;
+                let (price, rest) = unpack_u128(rest)?;
+                let (max_amount_in, rest) = unpack_u64(rest)?;
+                let (expiry_slot, _) = unpack_u64(rest)?;
+                Self::PostQuote(QuoteData {
+                    swap_direction,
+                    price,
+                    max_amount_in,
+                    expiry_slot,
+                })
+            }
+            9 => {
+                let (amount_in, rest) = unpack_u64(rest)?;
+                let (minimum_amount_out, rest) = unpack_u64(rest)?;
+                let (deadline_slot, output_owner) = unpack_swap_options(rest)?;
+                Self::FillQuote(SwapData {
+                    amount_in,
+                    minimum_amount_out,
+                    deadline_slot,
+                    output_owner,
+                })
+            }
+            17 => {
+                let (amount_a, rest) = unpack_u64(rest)?;
+                let (amount_b, rest) = unpack_u64(rest)?;
+                let (deadline_slot, rest) = unpack_u64(rest)?;
+                Self::FlashSwap(FlashSwapData {
+                    amount_a,
+                    amount_b,
+                    deadline_slot: if deadline_slot == u64::MAX {
+                        None
+                    } else {
+                        Some(deadline_slot)
+                    },
+                    callback_data: rest.to_vec(),
+                })
+            }
+            18 => {
+                let (init_data, rest) = unpack_initialize_data(rest)?;
+                let (salt, _) = unpack_bytes32(rest)?;
+                Self::RevealInitialize(init_data, salt)
+            }
+            31 => {
+                let (amount_in, rest) = unpack_u64(rest)?;
+                let (swap_direction, _) = unpack_u8(rest)?;
+                Self::GetQuote(GetQuoteData {
+                    amount_in,
+                    swap_direction,
+                })
+            }
+            32 => Self::UpdatePriceCache,
+            33 => Self::AcquireScratch,
+            34 => Self::ReleaseScratch,
+            35 => {
+                let (init_data, _) = unpack_initialize_data(rest)?;
+                Self::InitializeV2(init_data)
+            }
+            36 => {
+                let (amount, _) = unpack_u64(rest)?;
+                Self::StakeFeeDiscount(amount)
+            }
+            37 => {
+                let (amount, _) = unpack_u64(rest)?;
+                Self::UnstakeFeeDiscount(amount)
+            }
+            38 => Self::UpdateReferrer,
+            39 => Self::InitReferrerStats,
+            51 => {
+                let (max_amount_in, rest) = unpack_u64(rest)?;
+                let (minimum_amount_out, rest) = unpack_u64(rest)?;
+                let deadline_slot = unpack_deadline_slot(rest)?;
+                Self::Rebalance(RebalanceData {
+                    max_amount_in,
+                    minimum_amount_out,
+                    deadline_slot,
+                })
+            }
+            52 => {
+                let (amount, rest) = unpack_u64(rest)?;
+                let (unlock_ts, _) = unpack_i64(rest)?;
+                Self::LockLiquidity(LockLiquidityData { amount, unlock_ts })
+            }
+            53 => Self::UnlockLiquidity,
+            54 => {
+                let (amount_in, rest) = unpack_u64(rest)?;
+                let (minimum_amount_out, rest) = unpack_u64(rest)?;
+                let (deadline_slot, output_owner) = unpack_swap_options(rest)?;
+                Self::SwapNativeSol(SwapData {
+                    amount_in,
+                    minimum_amount_out,
+                    deadline_slot,
+                    output_owner,
+                })
+            }
+            55 => {
+                let (token_a_amount, rest) = unpack_u64(rest)?;
+                let (token_b_amount, rest) = unpack_u64(rest)?;
+                let (min_mint_amount, rest) = unpack_u64(rest)?;
+                let deadline_slot = unpack_deadline_slot(rest)?;
+                Self::DepositNativeSol(DepositData {
+                    token_a_amount,
+                    token_b_amount,
+                    min_mint_amount,
+                    deadline_slot,
+                })
+            }
+            56 => {
+                let (amount_in, rest) = unpack_u64(rest)?;
+                let (minimum_amount_out, rest) = unpack_u64(rest)?;
+                let (deadline_slot, output_owner) = unpack_swap_options(rest)?;
+                Self::SwapPartialFill(SwapData {
+                    amount_in,
+                    minimum_amount_out,
+                    deadline_slot,
+                    output_owner,
+                })
+            }
+            57 => {
+                let (market_price, _) = unpack_u128(rest)?;
+                Self::SetManualPrice(market_price)
+            }
+            58 => Self::Sync,
+            59 => Self::RefreshPrice,
+            _ => return Err(SwapError::InvalidInstruction.into()),
+        })
+    }
+
+    /// Packs a [SwapInstruction](enum.SwapInstruction.html) into a byte buffer.
+    pub fn pack(&self) -> Vec<u8> {
+        let mut buf = Vec::with_capacity(size_of::<Self>());
+        match *self {
+            Self::Initialize(ref init_data) => {
+                buf.push(0);
+                pack_initialize_data(init_data, &mut buf);
+            }
+            Self::Swap(SwapData {
+                amount_in,
+                minimum_amount_out,
+                deadline_slot,
+                output_owner,
+            }) => {
+                buf.push(1);
+                buf.extend_from_slice(&amount_in.to_le_bytes());
+                buf.extend_from_slice(&minimum_amount_out.to_le_bytes());
+                pack_swap_options(deadline_slot, output_owner, &mut buf);
+            }
+            Self::Deposit(DepositData {
+                token_a_amount,
+                token_b_amount,
+                min_mint_amount,
+                deadline_slot,
+            }) => {
+                buf.push(2);
+                buf.extend_from_slice(&token_a_amount.to_le_bytes());
+                buf.extend_from_slice(&token_b_amount.to_le_bytes());
+                buf.extend_from_slice(&min_mint_amount.to_le_bytes());
+                pack_deadline_slot(deadline_slot, &mut buf);
+            }
+            Self::Withdraw(WithdrawData {
+                pool_token_amount,
+                minimum_token_a_amount,
+                minimum_token_b_amount,
+                deadline_slot,
+            }) => {
+                buf.push(3);
+                buf.extend_from_slice(&pool_token_amount.to_le_bytes());
+                buf.extend_from_slice(&minimum_token_a_amount.to_le_bytes());
+                buf.extend_from_slice(&minimum_token_b_amount.to_le_bytes());
+                pack_deadline_slot(deadline_slot, &mut buf);
+            }
+            Self::SetReferrer => {
+                buf.push(4);
+            }
+            Self::SwapV2(SwapData {
+                amount_in,
+                minimum_amount_out,
+                deadline_slot,
+                output_owner,
+            }) => {
+                buf.push(5);
+                buf.extend_from_slice(&amount_in.to_le_bytes());
+                buf.extend_from_slice(&minimum_amount_out.to_le_bytes());
+                pack_swap_options(deadline_slot, output_owner, &mut buf);
+            }
+            Self::DepositOne(DepositOneData {
+                amount_in,
+                min_mint_amount,
+                deadline_slot,
+            }) => {
+                buf.push(6);
+                buf.extend_from_slice(&amount_in.to_le_bytes());
+                buf.extend_from_slice(&min_mint_amount.to_le_bytes());
+                pack_deadline_slot(deadline_slot, &mut buf);
+            }
+            Self::WithdrawOne(WithdrawOneData {
+                pool_token_amount,
+                minimum_amount_out,
+                token_side,
+                deadline_slot,
+            }) => {
+                buf.push(7);
+                buf.extend_from_slice(&pool_token_amount.to_le_bytes());
+                buf.extend_from_slice(&minimum_amount_out.to_le_bytes());
+                buf.push(token_side);
+                pack_deadline_slot(deadline_slot, &mut buf);
+            }
+            Self::PostQuote(QuoteData {
+                swap_direction,
+                price,
+                max_amount_in,
+                expiry_slot,
+            }) => {
+                buf.push(8);
+                buf.push(swap_direction);
+                buf.extend_from_slice(&price.to_le_bytes());
+                buf.extend_from_slice(&max_amount_in.to_le_bytes());
+                buf.extend_from_slice(&expiry_slot.to_le_bytes());
+            }
+            Self::FillQuote(SwapData {
+                amount_in,
+                minimum_amount_out,
+                deadline_slot,
+                output_owner,
+            }) => {
+                buf.push(9);
+                buf.extend_from_slice(&amount_in.to_le_bytes());
+                buf.extend_from_slice(&minimum_amount_out.to_le_bytes());
+                pack_swap_options(deadline_slot, output_owner, &mut buf);
+            }
+            Self::FlashSwap(FlashSwapData {
+                amount_a,
+                amount_b,
+                deadline_slot,
+                ref callback_data,
+            }) => {
+                buf.push(17);
+                buf.extend_from_slice(&amount_a.to_le_bytes());
+                buf.extend_from_slice(&amount_b.to_le_bytes());
+                buf.extend_from_slice(&deadline_slot.unwrap_or(u64::MAX).to_le_bytes());
+                buf.extend_from_slice(callback_data);
+            }
+            Self::RevealInitialize(ref init_data, salt) => {
+                buf.push(18);
+                pack_initialize_data(init_data, &mut buf);
+                buf.extend_from_slice(&salt);
+            }
+            Self::GetQuote(GetQuoteData {
+                amount_in,
+                swap_direction,
+            }) => {
+                buf.push(31);
+                buf.extend_from_slice(&amount_in.to_le_bytes());
+                buf.push(swap_direction);
+            }
+            Self::UpdatePriceCache => {
+                buf.push(32);
+            }
+            Self::AcquireScratch => {
+                buf.push(33);
+            }
+            Self::ReleaseScratch => {
+                buf.push(34);
+            }
+            Self::InitializeV2(ref init_data) => {
+                buf.push(35);
+                pack_initialize_data(init_data, &mut buf);
+            }
+            Self::StakeFeeDiscount(amount) => {
+                buf.push(36);
+                buf.extend_from_slice(&amount.to_le_bytes());
+            }
+            Self::UnstakeFeeDiscount(amount) => {
+                buf.push(37);
+                buf.extend_from_slice(&amount.to_le_bytes());
+            }
+            Self::UpdateReferrer => buf.push(38),
+            Self::InitReferrerStats => buf.push(39),
+            Self::Rebalance(RebalanceData {
+                max_amount_in,
+                minimum_amount_out,
+                deadline_slot,
+            }) => {
+                buf.push(51);
+                buf.extend_from_slice(&max_amount_in.to_le_bytes());
+                buf.extend_from_slice(&minimum_amount_out.to_le_bytes());
+                pack_deadline_slot(deadline_slot, &mut buf);
+            }
+            Self::LockLiquidity(LockLiquidityData { amount, unlock_ts }) => {
+                buf.push(52);
+                buf.extend_from_slice(&amount.to_le_bytes());
+                buf.extend_from_slice(&unlock_ts.to_le_bytes());
+            }
+            Self::UnlockLiquidity => buf.push(53),
+            Self::SwapNativeSol(SwapData {
+                amount_in,
+                minimum_amount_out,
+                deadline_slot,
+                output_owner,
+            }) => {
+                buf.push(54);
+                buf.extend_from_slice(&amount_in.to_le_bytes());
+                buf.extend_from_slice(&minimum_amount_out.to_le_bytes());
+                pack_swap_options(deadline_slot, output_owner, &mut buf);
+            }
+            Self::DepositNativeSol(DepositData {
+                token_a_amount,
+                token_b_amount,
+                min_mint_amount,
+                deadline_slot,
+            }) => {
+                buf.push(55);
+                buf.extend_from_slice(&token_a_amount.to_le_bytes());
+                buf.extend_from_slice(&token_b_amount.to_le_bytes());
+                buf.extend_from_slice(&min_mint_amount.to_le_bytes());
+                pack_deadline_slot(deadline_slot, &mut buf);
+            }
+            Self::SwapPartialFill(SwapData {
+                amount_in,
+                minimum_amount_out,
+                deadline_slot,
+                output_owner,
+            }) => {
+                buf.push(56);
+                buf.extend_from_slice(&amount_in.to_le_bytes());
+                buf.extend_from_slice(&minimum_amount_out.to_le_bytes());
+                pack_swap_options(deadline_slot, output_owner, &mut buf);
+            }
+            Self::SetManualPrice(market_price) => {
+                buf.push(57);
+                buf.extend_from_slice(&market_price.to_le_bytes());
+            }
+            Self::Sync => buf.push(58),
+            Self::RefreshPrice => buf.push(59),
+        }
+        buf
+    }
+}
+
+/// Creates an 'initialize' instruction.
+pub fn initialize(
+    program_id: Pubkey,
+    config_pubkey: Pubkey,
+    swap_pubkey: Pubkey,
+    authority_pubkey: Pubkey,
+    admin_fee_a_pubkey: Pubkey,
+    admin_fee_b_pubkey: Pubkey,
+    token_a_pubkey: Pubkey,
+    token_b_pubkey: Pubkey,
+    pool_mint_pubkey: Pubkey,
+    destination_pubkey: Pubkey,
+    pyth_a_product_pubkey: Pubkey,
+    pyth_a_pubkey: Pubkey,
+    pyth_b_product_pubkey: Pubkey,
+    pyth_b_pubkey: Pubkey,
+    admin_pubkey: Pubkey,
+    serum_market_pubkey: Pubkey,
+    serum_bids_pubkey: Pubkey,
+    serum_asks_pubkey: Pubkey,
+    init_data: InitializeData,
+) -> Result<Instruction, ProgramError> {
+    let data = SwapInstruction::Initialize(init_data).pack();
+
+    let accounts = vec![
+        AccountMeta::new_readonly(config_pubkey, false),
+        AccountMeta::new(swap_pubkey, true),
+        AccountMeta::new_readonly(authority_pubkey, false),
+        AccountMeta::new_readonly(admin_fee_a_pubkey, false),
+        AccountMeta::new_readonly(admin_fee_b_pubkey, false),
+        AccountMeta::new_readonly(token_a_pubkey, false),
+        AccountMeta::new_readonly(token_b_pubkey, false),
+        AccountMeta::new(pool_mint_pubkey, false),
+        AccountMeta::new(destination_pubkey, false),
+        AccountMeta::new_readonly(pyth_a_product_pubkey, false),
+        AccountMeta::new_readonly(pyth_a_pubkey, false),
+        AccountMeta::new_readonly(pyth_b_product_pubkey, false),
+        AccountMeta::new_readonly(pyth_b_pubkey, false),
+        AccountMeta::new_readonly(admin_pubkey, true),
+        AccountMeta::new_readonly(serum_market_pubkey, false),
+        AccountMeta::new_readonly(serum_bids_pubkey, false),
+        AccountMeta::new_readonly(serum_asks_pubkey, false),
+        AccountMeta::new_readonly(clock::id(), false),
+        AccountMeta::new_readonly(rent::id(), false),
+        AccountMeta::new_readonly(spl_token::id(), false),
+    ];
+
+    Ok(Instruction {
+        program_id,
+        accounts,
+        data,
+    })
+}
+
+/// Creates an 'InitializeV2' instruction, the swap is derived from `find_swap_address`.
+pub fn initialize_v2(
+    program_id: Pubkey,
+    config_pubkey: Pubkey,
+    swap_pubkey: Pubkey,
+    authority_pubkey: Pubkey,
+    admin_fee_a_pubkey: Pubkey,
+    admin_fee_b_pubkey: Pubkey,
+    token_a_pubkey: Pubkey,
+    token_b_pubkey: Pubkey,
+    pool_mint_pubkey: Pubkey,
+    destination_pubkey: Pubkey,
+    pyth_a_product_pubkey: Pubkey,
+    pyth_a_pubkey: Pubkey,
+    pyth_b_product_pubkey: Pubkey,
+    pyth_b_pubkey: Pubkey,
+    admin_pubkey: Pubkey,
+    serum_market_pubkey: Pubkey,
+    serum_bids_pubkey: Pubkey,
+    serum_asks_pubkey: Pubkey,
+    init_data: InitializeData,
+) -> Result<Instruction, ProgramError> {
+    let data = SwapInstruction::InitializeV2(init_data).pack();
+
+    let accounts = vec![
+        AccountMeta::new_readonly(config_pubkey, false),
+        AccountMeta::new(swap_pubkey, false),
+        AccountMeta::new_readonly(authority_pubkey, false),
+        AccountMeta::new_readonly(admin_fee_a_pubkey, false),
+        AccountMeta::new_readonly(admin_fee_b_pubkey, false),
+        AccountMeta::new_readonly(token_a_pubkey, false),
+        AccountMeta::new_readonly(token_b_pubkey, false),
+        AccountMeta::new(pool_mint_pubkey, false),
+        AccountMeta::new(destination_pubkey, false),
+        AccountMeta::new_readonly(pyth_a_product_pubkey, false),
+        AccountMeta::new_readonly(pyth_a_pubkey, false),
+        AccountMeta::new_readonly(pyth_b_product_pubkey, false),
+        AccountMeta::new_readonly(pyth_b_pubkey, false),
+        AccountMeta::new(admin_pubkey, true),
+        AccountMeta::new_readonly(serum_market_pubkey, false),
+        AccountMeta::new_readonly(serum_bids_pubkey, false),
+        AccountMeta::new_readonly(serum_asks_pubkey, false),
+        AccountMeta::new_readonly(clock::id(), false),
+        AccountMeta::new_readonly(rent::id(), false),
+        AccountMeta::new_readonly(spl_token::id(), false),
+        AccountMeta::new_readonly(system_program::id(), false),
+    ];
+
+    Ok(Instruction {
+        program_id,
+        accounts,
+        data,
+    })
+}
+
+/// Creates a 'RevealInitialize' instruction.
+pub fn reveal_initialize(
+    program_id: Pubkey,
+    config_pubkey: Pubkey,
+    swap_pubkey: Pubkey,
+    authority_pubkey: Pubkey,
+    admin_fee_a_pubkey: Pubkey,
+    admin_fee_b_pubkey: Pubkey,
+    token_a_pubkey: Pubkey,
+    token_b_pubkey: Pubkey,
+    pool_mint_pubkey: Pubkey,
+    destination_pubkey: Pubkey,
+    pyth_a_product_pubkey: Pubkey,
+    pyth_a_pubkey: Pubkey,
+    pyth_b_product_pubkey: Pubkey,
+    pyth_b_pubkey: Pubkey,
+    admin_pubkey: Pubkey,
+    serum_market_pubkey: Pubkey,
+    serum_bids_pubkey: Pubkey,
+    serum_asks_pubkey: Pubkey,
+    price_commitment_pubkey: Pubkey,
+    init_data: InitializeData,
+    salt: [u8; 32],
+) -> Result<Instruction, ProgramError> {
+    let data = SwapInstruction::RevealInitialize(init_data, salt).pack();
+
+    let accounts = vec![
+        AccountMeta::new_readonly(config_pubkey, false),
+        AccountMeta::new(swap_pubkey, true),
+        AccountMeta::new_readonly(authority_pubkey, false),
+        AccountMeta::new_readonly(admin_fee_a_pubkey, false),
+        AccountMeta::new_readonly(admin_fee_b_pubkey, false),
+        AccountMeta::new_readonly(token_a_pubkey, false),
+        AccountMeta::new_readonly(token_b_pubkey, false),
+        AccountMeta::new(pool_mint_pubkey, false),
+        AccountMeta::new(destination_pubkey, false),
+        AccountMeta::new_readonly(pyth_a_product_pubkey, false),
+        AccountMeta::new_readonly(pyth_a_pubkey, false),
+        AccountMeta::new_readonly(pyth_b_product_pubkey, false),
+        AccountMeta::new_readonly(pyth_b_pubkey, false),
+        AccountMeta::new(admin_pubkey, true),
+        AccountMeta::new_readonly(serum_market_pubkey, false),
+        AccountMeta::new_readonly(serum_bids_pubkey, false),
+        AccountMeta::new_readonly(serum_asks_pubkey, false),
+        AccountMeta::new_readonly(clock::id(), false),
+        AccountMeta::new_readonly(rent::id(), false),
+        AccountMeta::new_readonly(spl_token::id(), false),
+        AccountMeta::new(price_commitment_pubkey, false),
+    ];
+
+    Ok(Instruction {
+        program_id,
+        accounts,
+        data,
+    })
+}
+
+/// Creates a 'SetReferrer' instruction.
+pub fn set_referrer(
+    program_id: Pubkey,
+    config_pubkey: Pubkey,
+    user_pubkey: Pubkey,
+    user_referrer_data_pubkey: Pubkey,
+    referrer_token_pubkey: Pubkey,
+) -> Result<Instruction, ProgramError> {
+    let data = SwapInstruction::SetReferrer.pack();
+
+    let accounts = vec![
+        AccountMeta::new_readonly(config_pubkey, false),
+        AccountMeta::new_readonly(user_pubkey, true),
+        AccountMeta::new(user_referrer_data_pubkey, false),
+        AccountMeta::new_readonly(referrer_token_pubkey, false),
+        AccountMeta::new_readonly(rent::id(), false),
+        AccountMeta::new_readonly(spl_token::id(), false),
+    ];
+
+    Ok(Instruction {
+        program_id,
+        accounts,
+        data,
+    })
+}
+
+/// Creates an 'UpdateReferrer' instruction.
+pub fn update_referrer(
+    program_id: Pubkey,
+    config_pubkey: Pubkey,
+    user_pubkey: Pubkey,
+    user_referrer_data_pubkey: Pubkey,
+    referrer_token_pubkey: Pubkey,
+) -> Result<Instruction, ProgramError> {
+    let data = SwapInstruction::UpdateReferrer.pack();
+
+    let accounts = vec![
+        AccountMeta::new_readonly(config_pubkey, false),
+        AccountMeta::new_readonly(user_pubkey, true),
+        AccountMeta::new(user_referrer_data_pubkey, false),
+        AccountMeta::new_readonly(referrer_token_pubkey, false),
+        AccountMeta::new_readonly(spl_token::id(), false),
+    ];
+
+    Ok(Instruction {
+        program_id,
+        accounts,
+        data,
+    })
+}
+
+/// Creates an 'InitReferrerStats' instruction.
+pub fn init_referrer_stats(
+    program_id: Pubkey,
+    config_pubkey: Pubkey,
+    referrer_token_pubkey: Pubkey,
+    referrer_stats_pubkey: Pubkey,
+    payer_pubkey: Pubkey,
+) -> Result<Instruction, ProgramError> {
+    let data = SwapInstruction::InitReferrerStats.pack();
+
+    let accounts = vec![
+        AccountMeta::new_readonly(config_pubkey, false),
+        AccountMeta::new_readonly(referrer_token_pubkey, false),
+        AccountMeta::new(referrer_stats_pubkey, false),
+        AccountMeta::new(payer_pubkey, true),
+        AccountMeta::new_readonly(rent::id(), false),
+        AccountMeta::new_readonly(system_program::id(), false),
+        AccountMeta::new_readonly(spl_token::id(), false),
+    ];
+
+    Ok(Instruction {
+        program_id,
+        accounts,
+        data,
+    })
+}
+
+/// Creates a 'swap' instruction.
+pub fn swap(
+    program_id: Pubkey,
+    config_pubkey: Pubkey,
+    swap_pubkey: Pubkey,
+    market_authority_pubkey: Pubkey,
+    swap_authority_pubkey: Pubkey,
+    user_transfer_authority_pubkey: Pubkey,
+    source_pubkey: Pubkey,
+    swap_source_pubkey: Pubkey,
+    source_mint_pubkey: Pubkey,
+    swap_destination_pubkey: Pubkey,
+    destination_pubkey: Pubkey,
+    destination_mint_pubkey: Pubkey,
+    reward_token_pubkey: Pubkey,
+    source_reward_token_pubkey: Pubkey,
+    admin_fee_destination_pubkey: Pubkey,
+    pyth_a_pubkey: Pubkey,
+    pyth_b_pubkey: Pubkey,
+    price_cache_pubkey: Option<Pubkey>,
+    fee_discount_pubkey: Option<Pubkey>,
+    fee_discount_tiers_pubkey: Option<Pubkey>,
+    treasury_token_pubkey: Option<Pubkey>,
+    user_referrer_data_pubkey: Option<Pubkey>,
+    referrer_token_pubkey: Option<Pubkey>,
+    referrer_stats_pubkey: Option<Pubkey>,
+    swap_data: SwapData,
+) -> Result<Instruction, ProgramError> {
+    let has_output_owner = swap_data.output_owner.is_some();
+    let data = SwapInstruction::Swap(swap_data).pack();
+
+    let mut accounts = vec![
+        AccountMeta::new_readonly(config_pubkey, false),
+        AccountMeta::new(swap_pubkey, false),
+        AccountMeta::new_readonly(market_authority_pubkey, false),
+        AccountMeta::new_readonly(swap_authority_pubkey, false),
+        AccountMeta::new_readonly(user_transfer_authority_pubkey, true),
+        AccountMeta::new(source_pubkey, false),
+        AccountMeta::new(swap_source_pubkey, false),
+        AccountMeta::new_readonly(source_mint_pubkey, false),
+        AccountMeta::new(swap_destination_pubkey, false),
+        AccountMeta::new(destination_pubkey, false),
+        AccountMeta::new_readonly(destination_mint_pubkey, false),
+        AccountMeta::new(reward_token_pubkey, false),
+        AccountMeta::new(source_reward_token_pubkey, false),
+        AccountMeta::new(admin_fee_destination_pubkey, false),
+        AccountMeta::new_readonly(pyth_a_pubkey, false),
+        AccountMeta::new_readonly(pyth_b_pubkey, false),
+        AccountMeta::new_readonly(spl_token::id(), false),
+    ];
+
+    if let Some(price_cache_pubkey) = price_cache_pubkey {
+        accounts.push(AccountMeta::new_readonly(price_cache_pubkey, false));
+    }
+
+    if let Some(fee_discount_pubkey) = fee_discount_pubkey {
+        accounts.extend_from_slice(&[
+            AccountMeta::new_readonly(fee_discount_pubkey, false),
+            AccountMeta::new_readonly(fee_discount_tiers_pubkey.unwrap(), false),
+        ]);
+    }
+
+    if let Some(treasury_token_pubkey) = treasury_token_pubkey {
+        accounts.push(AccountMeta::new(treasury_token_pubkey, false));
+    }
+
+    if has_output_owner {
+        accounts.push(AccountMeta::new_readonly(sysvar::instructions::id(), false));
+    }
+
+    if let Some(user_referrer_data_pubkey) = user_referrer_data_pubkey {
+        accounts.extend_from_slice(&[
+            AccountMeta::new_readonly(user_referrer_data_pubkey, false),
+            AccountMeta::new(referrer_token_pubkey.unwrap(), false),
+        ]);
+    }
+
+    if let Some(referrer_stats_pubkey) = referrer_stats_pubkey {
+        accounts.push(AccountMeta::new(referrer_stats_pubkey, false));
+    }
+
+    Ok(Instruction {
+        program_id,
+        accounts,
+        data,
+    })
+}
+
+/// Creates a 'swapV2' instruction.
+pub fn swap_v2(
+    program_id: Pubkey,
+    config_pubkey: Pubkey,
+    swap_pubkey: Pubkey,
+    market_authority_pubkey: Pubkey,
+    swap_authority_pubkey: Pubkey,
+    user_transfer_authority_pubkey: Pubkey,
+    source_pubkey: Pubkey,
+    swap_source_pubkey: Pubkey,
+    swap_destination_pubkey: Pubkey,
+    destination_pubkey: Pubkey,
+    reward_token_pubkey: Pubkey,
+    source_reward_token_pubkey: Pubkey,
+    admin_fee_destination_pubkey: Pubkey,
+    pyth_a_pubkey: Pubkey,
+    pyth_b_pubkey: Pubkey,
+    serum_market_pubkey: Pubkey,
+    serum_bids_pubkey: Pubkey,
+    serum_asks_pubkey: Pubkey,
+    price_cache_pubkey: Option<Pubkey>,
+    fee_discount_pubkey: Option<Pubkey>,
+    fee_discount_tiers_pubkey: Option<Pubkey>,
+    treasury_token_pubkey: Option<Pubkey>,
+    user_referrer_data_pubkey: Option<Pubkey>,
+    referrer_token_pubkey: Option<Pubkey>,
+    referrer_fee_token_pubkey: Option<Pubkey>,
+    referrer_stats_pubkey: Option<Pubkey>,
+    swap_data: SwapData,
+) -> Result<Instruction, ProgramError> {
+    let has_output_owner = swap_data.output_owner.is_some();
+    let data = SwapInstruction::SwapV2(swap_data).pack();
+
+    let mut accounts = vec![
+        AccountMeta::new_readonly(config_pubkey, false),
+        AccountMeta::new(swap_pubkey, false),
+        AccountMeta::new_readonly(market_authority_pubkey, false),
+        AccountMeta::new_readonly(swap_authority_pubkey, false),
+        AccountMeta::new_readonly(user_transfer_authority_pubkey, true),
+        AccountMeta::new(source_pubkey, false),
+        AccountMeta::new(swap_source_pubkey, false),
+        AccountMeta::new(swap_destination_pubkey, false),
+        AccountMeta::new(destination_pubkey, false),
+        AccountMeta::new(reward_token_pubkey, false),
+        AccountMeta::new(source_reward_token_pubkey, false),
+        AccountMeta::new(admin_fee_destination_pubkey, false),
+        AccountMeta::new_readonly(pyth_a_pubkey, false),
+        AccountMeta::new_readonly(pyth_b_pubkey, false),
+        AccountMeta::new_readonly(serum_market_pubkey, false),
+        AccountMeta::new_readonly(serum_bids_pubkey, false),
+        AccountMeta::new_readonly(serum_asks_pubkey, false),
+        AccountMeta::new_readonly(spl_token::id(), false),
+    ];
+
+    if let Some(price_cache_pubkey) = price_cache_pubkey {
+        accounts.push(AccountMeta::new_readonly(price_cache_pubkey, false));
+    }
+
+    if let Some(fee_discount_pubkey) = fee_discount_pubkey {
+        accounts.extend_from_slice(&[
+            AccountMeta::new_readonly(fee_discount_pubkey, false),
+            AccountMeta::new_readonly(fee_discount_tiers_pubkey.unwrap(), false),
+        ]);
+    }
+
+    if let Some(treasury_token_pubkey) = treasury_token_pubkey {
+        accounts.push(AccountMeta::new(treasury_token_pubkey, false));
+    }
+
+    if has_output_owner {
+        accounts.push(AccountMeta::new_readonly(sysvar::instructions::id(), false));
+    }
+
+    if let Some(user_referrer_data_pubkey) = user_referrer_data_pubkey {
+        accounts.extend_from_slice(&[
+            AccountMeta::new_readonly(user_referrer_data_pubkey, false),
+            AccountMeta::new(referrer_token_pubkey.unwrap(), false),
+        ]);
+    }
+
+    if let Some(referrer_fee_token_pubkey) = referrer_fee_token_pubkey {
+        accounts.push(AccountMeta::new(referrer_fee_token_pubkey, false));
+    }
+
+    if let Some(referrer_stats_pubkey) = referrer_stats_pubkey {
+        accounts.push(AccountMeta::new(referrer_stats_pubkey, false));
+    }
+
+    Ok(Instruction {
+        program_id,
+        accounts,
+        data,
+    })
+}
+
+/// Creates a 'deposit' instruction.
+pub fn deposit(
+    program_id: Pubkey,
+    swap_pubkey: Pubkey,
+    authority_pubkey: Pubkey,
+    user_transfer_authority_pubkey: Pubkey,
+    deposit_token_a_pubkey: Pubkey,
+    deposit_token_b_pubkey: Pubkey,
+    swap_token_a_pubkey: Pubkey,
+    swap_token_b_pubkey: Pubkey,
+    pool_mint_pubkey: Pubkey,
+    destination_pubkey: Pubkey,
+    deposit_data: DepositData,
+) -> Result<Instruction, ProgramError> {
+    let data = SwapInstruction::Deposit(deposit_data).pack();
+
+    let accounts = vec![
+        AccountMeta::new(swap_pubkey, false),
+        AccountMeta::new_readonly(authority_pubkey, false),
+        AccountMeta::new_readonly(user_transfer_authority_pubkey, true),
+        AccountMeta::new(deposit_token_a_pubkey, false),
+        AccountMeta::new(deposit_token_b_pubkey, false),
+        AccountMeta::new(swap_token_a_pubkey, false),
+        AccountMeta::new(swap_token_b_pubkey, false),
+        AccountMeta::new(pool_mint_pubkey, false),
+        AccountMeta::new(destination_pubkey, false),
+        AccountMeta::new_readonly(spl_token::id(), false),
+    ];
+
+    Ok(Instruction {
+        program_id,
+        accounts,
+        data,
+    })
+}
+
+/// Creates a 'withdraw' instruction.
+pub fn withdraw(
+    program_id: Pubkey,
+    swap_pubkey: Pubkey,
+    authority_pubkey: Pubkey,
+    user_transfer_authority_pubkey: Pubkey,
+    pool_mint_pubkey: Pubkey,
+    source_pubkey: Pubkey,
+    swap_token_a_pubkey: Pubkey,
+    swap_token_b_pubkey: Pubkey,
+    destination_token_a_pubkey: Pubkey,
+    destination_token_b_pubkey: Pubkey,
+    admin_fee_a_pubkey: Pubkey,
+    admin_fee_b_pubkey: Pubkey,
+    withdraw_data: WithdrawData,
+) -> Result<Instruction, ProgramError> {
+    let data = SwapInstruction::Withdraw(withdraw_data).pack();
+
+    let accounts = vec![
+        AccountMeta::new(swap_pubkey, false),
+        AccountMeta::new_readonly(authority_pubkey, false),
+        AccountMeta::new_readonly(user_transfer_authority_pubkey, true),
+        AccountMeta::new(pool_mint_pubkey, false),
+        AccountMeta::new(source_pubkey, false),
+        AccountMeta::new(swap_token_a_pubkey, false),
+        AccountMeta::new(swap_token_b_pubkey, false),
+        AccountMeta::new(destination_token_a_pubkey, false),
+        AccountMeta::new(destination_token_b_pubkey, false),
+        AccountMeta::new(admin_fee_a_pubkey, false),
+        AccountMeta::new(admin_fee_b_pubkey, false),
+        AccountMeta::new_readonly(spl_token::id(), false),
+    ];
+
+    Ok(Instruction {
+        program_id,
+        accounts,
+        data,
+    })
+}
+
+/// Creates a 'deposit_one' instruction.
+pub fn deposit_one(
+    program_id: Pubkey,
+    config_pubkey: Pubkey,
+    swap_pubkey: Pubkey,
+    authority_pubkey: Pubkey,
+    user_transfer_authority_pubkey: Pubkey,
+    source_pubkey: Pubkey,
+    swap_token_a_pubkey: Pubkey,
+    swap_token_b_pubkey: Pubkey,
+    admin_fee_destination_pubkey: Pubkey,
+    pool_mint_pubkey: Pubkey,
+    destination_pubkey: Pubkey,
+    pyth_a_pubkey: Pubkey,
+    pyth_b_pubkey: Pubkey,
+    serum_market_pubkey: Pubkey,
+    serum_bids_pubkey: Pubkey,
+    serum_asks_pubkey: Pubkey,
+    deposit_data: DepositOneData,
+) -> Result<Instruction, ProgramError> {
+    let data = SwapInstruction::DepositOne(deposit_data).pack();
+
+    let accounts = vec![
+        AccountMeta::new_readonly(config_pubkey, false),
+        AccountMeta::new(swap_pubkey, false),
+        AccountMeta::new_readonly(authority_pubkey, false),
+        AccountMeta::new_readonly(user_transfer_authority_pubkey, true),
+        AccountMeta::new(source_pubkey, false),
+        AccountMeta::new(swap_token_a_pubkey, false),
+        AccountMeta::new(swap_token_b_pubkey, false),
+        AccountMeta::new(admin_fee_destination_pubkey, false),
+        AccountMeta::new(pool_mint_pubkey, false),
+        AccountMeta::new(destination_pubkey, false),
+        AccountMeta::new_readonly(pyth_a_pubkey, false),
+        AccountMeta::new_readonly(pyth_b_pubkey, false),
+        AccountMeta::new_readonly(serum_market_pubkey, false),
+        AccountMeta::new_readonly(serum_bids_pubkey, false),
+        AccountMeta::new_readonly(serum_asks_pubkey, false),
+        AccountMeta::new_readonly(spl_token::id(), false),
+    ];
+
+    Ok(Instruction {
+        program_id,
+        accounts,
+        data,
+    })
+}
+
+/// Creates a 'rebalance' instruction.
+pub fn rebalance(
+    program_id: Pubkey,
+    config_pubkey: Pubkey,
+    swap_pubkey: Pubkey,
+    authority_pubkey: Pubkey,
+    user_transfer_authority_pubkey: Pubkey,
+    source_pubkey: Pubkey,
+    destination_pubkey: Pubkey,
+    swap_token_a_pubkey: Pubkey,
+    swap_token_b_pubkey: Pubkey,
+    pyth_a_pubkey: Pubkey,
+    pyth_b_pubkey: Pubkey,
+    serum_market_pubkey: Pubkey,
+    serum_bids_pubkey: Pubkey,
+    serum_asks_pubkey: Pubkey,
+    rebalance_data: RebalanceData,
+) -> Result<Instruction, ProgramError> {
+    let data = SwapInstruction::Rebalance(rebalance_data).pack();
+
+    let accounts = vec![
+        AccountMeta::new_readonly(config_pubkey, false),
+        AccountMeta::new(swap_pubkey, false),
+        AccountMeta::new_readonly(authority_pubkey, false),
+        AccountMeta::new_readonly(user_transfer_authority_pubkey, true),
+        AccountMeta::new(source_pubkey, false),
+        AccountMeta::new(destination_pubkey, false),
+        AccountMeta::new(swap_token_a_pubkey, false),
+        AccountMeta::new(swap_token_b_pubkey, false),
+        AccountMeta::new_readonly(pyth_a_pubkey, false),
+        AccountMeta::new_readonly(pyth_b_pubkey, false),
+        AccountMeta::new_readonly(serum_market_pubkey, false),
+        AccountMeta::new_readonly(serum_bids_pubkey, false),
+        AccountMeta::new_readonly(serum_asks_pubkey, false),
+        AccountMeta::new_readonly(spl_token::id(), false),
+    ];
+
+    Ok(Instruction {
+        program_id,
+        accounts,
+        data,
+    })
+}
+
+/// Creates a 'withdraw_one' instruction.
+pub fn withdraw_one(
+    program_id: Pubkey,
+    config_pubkey: Pubkey,
+    swap_pubkey: Pubkey,
+    authority_pubkey: Pubkey,
+    user_transfer_authority_pubkey: Pubkey,
+    pool_mint_pubkey: Pubkey,
+    source_pubkey: Pubkey,
+    swap_token_a_pubkey: Pubkey,
+    swap_token_b_pubkey: Pubkey,
+    destination_pubkey: Pubkey,
+    admin_fee_destination_pubkey: Pubkey,
+    pyth_a_pubkey: Pubkey,
+    pyth_b_pubkey: Pubkey,
+    serum_market_pubkey: Pubkey,
+    serum_bids_pubkey: Pubkey,
+    serum_asks_pubkey: Pubkey,
+    withdraw_data: WithdrawOneData,
+) -> Result<Instruction, ProgramError> {
+    let data = SwapInstruction::WithdrawOne(withdraw_data).pack();
+
+    let accounts = vec![
+        AccountMeta::new_readonly(config_pubkey, false),
+        AccountMeta::new(swap_pubkey, false),
+        AccountMeta::new_readonly(authority_pubkey, false),
+        AccountMeta::new_readonly(user_transfer_authority_pubkey, true),
+        AccountMeta::new(pool_mint_pubkey, false),
+        AccountMeta::new(source_pubkey, false),
+        AccountMeta::new(swap_token_a_pubkey, false),
+        AccountMeta::new(swap_token_b_pubkey, false),
+        AccountMeta::new(destination_pubkey, false),
+        AccountMeta::new(admin_fee_destination_pubkey, false),
+        AccountMeta::new_readonly(pyth_a_pubkey, false),
+        AccountMeta::new_readonly(pyth_b_pubkey, false),
+        AccountMeta::new_readonly(serum_market_pubkey, false),
+        AccountMeta::new_readonly(serum_bids_pubkey, false),
+        AccountMeta::new_readonly(serum_asks_pubkey, false),
+        AccountMeta::new_readonly(spl_token::id(), false),
+    ];
+
+    Ok(Instruction {
+        program_id,
+        accounts,
+        data,
+    })
+}
+
+/// Creates a 'post_quote' instruction.
+pub fn post_quote(
+    program_id: Pubkey,
+    config_pubkey: Pubkey,
+    swap_pubkey: Pubkey,
+    quote_pubkey: Pubkey,
+    market_maker_pubkey: Pubkey,
+    market_maker_token_a_pubkey: Pubkey,
+    market_maker_token_b_pubkey: Pubkey,
+    quote_data: QuoteData,
+) -> Result<Instruction, ProgramError> {
+    let data = SwapInstruction::PostQuote(quote_data).pack();
+
+    let accounts = vec![
+        AccountMeta::new_readonly(config_pubkey, false),
+        AccountMeta::new_readonly(swap_pubkey, false),
+        AccountMeta::new(quote_pubkey, false),
+        AccountMeta::new_readonly(market_maker_pubkey, true),
+        AccountMeta::new_readonly(market_maker_token_a_pubkey, false),
+        AccountMeta::new_readonly(market_maker_token_b_pubkey, false),
+        AccountMeta::new_readonly(clock::id(), false),
+        AccountMeta::new_readonly(rent::id(), false),
+        AccountMeta::new_readonly(spl_token::id(), false),
+    ];
+
+    Ok(Instruction {
+        program_id,
+        accounts,
+        data,
+    })
+}
+
+/// Creates a 'fill_quote' instruction.
+pub fn fill_quote(
+    program_id: Pubkey,
+    config_pubkey: Pubkey,
+    swap_pubkey: Pubkey,
+    market_authority_pubkey: Pubkey,
+    swap_authority_pubkey: Pubkey,
+    quote_pubkey: Pubkey,
+    user_transfer_authority_pubkey: Pubkey,
+    source_pubkey: Pubkey,
+    destination_pubkey: Pubkey,
+    market_maker_source_pubkey: Pubkey,
+    market_maker_destination_pubkey: Pubkey,
+    admin_fee_destination_pubkey: Pubkey,
+    reward_token_pubkey: Pubkey,
+    source_reward_token_pubkey: Pubkey,
+    swap_data: SwapData,
+) -> Result<Instruction, ProgramError> {
+    let data = SwapInstruction::FillQuote(swap_data).pack();
+
+    let accounts = vec![
+        AccountMeta::new_readonly(config_pubkey, false),
+        AccountMeta::new_readonly(swap_pubkey, false),
+        AccountMeta::new_readonly(market_authority_pubkey, false),
+        AccountMeta::new_readonly(swap_authority_pubkey, false),
+        AccountMeta::new(quote_pubkey, false),
+        AccountMeta::new_readonly(user_transfer_authority_pubkey, true),
+        AccountMeta::new(source_pubkey, false),
+        AccountMeta::new(destination_pubkey, false),
+        AccountMeta::new(market_maker_source_pubkey, false),
+        AccountMeta::new(market_maker_destination_pubkey, false),
+        AccountMeta::new(admin_fee_destination_pubkey, false),
+        AccountMeta::new(reward_token_pubkey, false),
+        AccountMeta::new(source_reward_token_pubkey, false),
+        AccountMeta::new_readonly(clock::id(), false),
+        AccountMeta::new_readonly(spl_token::id(), false),
+    ];
+
+    Ok(Instruction {
+        program_id,
+        accounts,
+        data,
+    })
+}
+
+/// Creates a 'get_quote' instruction, the oracle accounts are the price accounts of the pool.
+pub fn get_quote(
+    program_id: Pubkey,
+    config_pubkey: Pubkey,
+    swap_pubkey: Pubkey,
+    oracle_pubkeys: Vec<Pubkey>,
+    get_quote_data: GetQuoteData,
+) -> Result<Instruction, ProgramError> {
+    let data = SwapInstruction::GetQuote(get_quote_data).pack();
+
+    let mut accounts = vec![
+        AccountMeta::new_readonly(config_pubkey, false),
+        AccountMeta::new_readonly(swap_pubkey, false),
+    ];
+    accounts.extend(
+        oracle_pubkeys
+            .into_iter()
+            .map(|pubkey| AccountMeta::new_readonly(pubkey, false)),
+    );
+
+    Ok(Instruction {
+        program_id,
+        accounts,
+        data,
+    })
+}
+
+/// Creates an 'update_price_cache' instruction.
+pub fn update_price_cache(
+    program_id: Pubkey,
+    swap_pubkey: Pubkey,
+    price_cache_pubkey: Pubkey,
+    relayer_pubkey: Pubkey,
+    pyth_a_pubkey: Pubkey,
+    pyth_b_pubkey: Pubkey,
+) -> Result<Instruction, ProgramError> {
+    let data = SwapInstruction::UpdatePriceCache.pack();
+
+    let accounts = vec![
+        AccountMeta::new_readonly(swap_pubkey, false),
+        AccountMeta::new(price_cache_pubkey, false),
+        AccountMeta::new_readonly(relayer_pubkey, true),
+        AccountMeta::new_readonly(pyth_a_pubkey, false),
+        AccountMeta::new_readonly(pyth_b_pubkey, false),
+    ];
+
+    Ok(Instruction {
+        program_id,
+        accounts,
+        data,
+    })
+}
+
+/// Creates a 'SetManualPrice' instruction.
+pub fn set_manual_price(
+    program_id: Pubkey,
+    swap_pubkey: Pubkey,
+    price_setter_pubkey: Pubkey,
+    market_price: u128,
+) -> Result<Instruction, ProgramError> {
+    let data = SwapInstruction::SetManualPrice(market_price).pack();
+
+    let accounts = vec![
+        AccountMeta::new(swap_pubkey, false),
+        AccountMeta::new_readonly(price_setter_pubkey, true),
+    ];
+
+    Ok(Instruction {
+        program_id,
+        accounts,
+        data,
+    })
+}
+
+/// Creates a 'Sync' instruction.
+pub fn sync(
+    program_id: Pubkey,
+    swap_pubkey: Pubkey,
+    token_a_pubkey: Pubkey,
+    token_b_pubkey: Pubkey,
+) -> Result<Instruction, ProgramError> {
+    let data = SwapInstruction::Sync.pack();
+
+    let accounts = vec![
+        AccountMeta::new(swap_pubkey, false),
+        AccountMeta::new_readonly(token_a_pubkey, false),
+        AccountMeta::new_readonly(token_b_pubkey, false),
+        AccountMeta::new_readonly(spl_token::id(), false),
+    ];
+
+    Ok(Instruction {
+        program_id,
+        accounts,
+        data,
+    })
+}
+
+/// Creates a 'RefreshPrice' instruction.
+pub fn refresh_price(
+    program_id: Pubkey,
+    config_pubkey: Pubkey,
+    swap_pubkey: Pubkey,
+    market_authority_pubkey: Pubkey,
+    reward_token_pubkey: Pubkey,
+    source_reward_token_pubkey: Pubkey,
+    pyth_a_pubkey: Pubkey,
+    pyth_b_pubkey: Pubkey,
+    serum_market_pubkey: Pubkey,
+    serum_bids_pubkey: Pubkey,
+    serum_asks_pubkey: Pubkey,
+) -> Result<Instruction, ProgramError> {
+    let data = SwapInstruction::RefreshPrice.pack();
+
+    let accounts = vec![
+        AccountMeta::new_readonly(config_pubkey, false),
+        AccountMeta::new(swap_pubkey, false),
+        AccountMeta::new_readonly(market_authority_pubkey, false),
+        AccountMeta::new(reward_token_pubkey, false),
+        AccountMeta::new(source_reward_token_pubkey, false),
+        AccountMeta::new_readonly(pyth_a_pubkey, false),
+        AccountMeta::new_readonly(pyth_b_pubkey, false),
+        AccountMeta::new_readonly(serum_market_pubkey, false),
+        AccountMeta::new_readonly(serum_bids_pubkey, false),
+        AccountMeta::new_readonly(serum_asks_pubkey, false),
+        AccountMeta::new_readonly(spl_token::id(), false),
+    ];
+
+    Ok(Instruction {
+        program_id,
+        accounts,
+        data,
+    })
+}
+
+/// Creates an 'AcquireScratch' instruction.
+pub fn acquire_scratch(
+    program_id: Pubkey,
+    scratch_account_pubkey: Pubkey,
+    scratch_token_pubkey: Pubkey,
+    holder_pubkey: Pubkey,
+) -> Result<Instruction, ProgramError> {
+    let data = SwapInstruction::AcquireScratch.pack();
+
+    let accounts = vec![
+        AccountMeta::new(scratch_account_pubkey, false),
+        AccountMeta::new(scratch_token_pubkey, false),
+        AccountMeta::new_readonly(holder_pubkey, true),
+        AccountMeta::new_readonly(spl_token::id(), false),
+    ];
+
+    Ok(Instruction {
+        program_id,
+        accounts,
+        data,
+    })
+}
+
+/// Creates a 'ReleaseScratch' instruction.
+pub fn release_scratch(
+    program_id: Pubkey,
+    scratch_account_pubkey: Pubkey,
+    scratch_token_pubkey: Pubkey,
+    holder_pubkey: Pubkey,
+    destination_pubkey: Pubkey,
+) -> Result<Instruction, ProgramError> {
+    let data = SwapInstruction::ReleaseScratch.pack();
+
+    let accounts = vec![
+        AccountMeta::new(scratch_account_pubkey, false),
+        AccountMeta::new(scratch_token_pubkey, false),
+        AccountMeta::new_readonly(holder_pubkey, true),
+        AccountMeta::new(destination_pubkey, false),
+        AccountMeta::new_readonly(spl_token::id(), false),
+    ];
+
+    Ok(Instruction {
+        program_id,
+        accounts,
+        data,
+    })
+}
+
+/// Creates a 'StakeFeeDiscount' instruction.
+pub fn stake_fee_discount(
+    program_id: Pubkey,
+    config_pubkey: Pubkey,
+    fee_discount_pubkey: Pubkey,
+    fee_discount_token_pubkey: Pubkey,
+    source_pubkey: Pubkey,
+    owner_pubkey: Pubkey,
+    deltafi_mint_pubkey: Pubkey,
+    amount: u64,
+) -> Result<Instruction, ProgramError> {
+    let data = SwapInstruction::StakeFeeDiscount(amount).pack();
+
+    let accounts = vec![
+        AccountMeta::new_readonly(config_pubkey, false),
+        AccountMeta::new(fee_discount_pubkey, false),
+        AccountMeta::new(fee_discount_token_pubkey, false),
+        AccountMeta::new(source_pubkey, false),
+        AccountMeta::new(owner_pubkey, true),
+        AccountMeta::new_readonly(deltafi_mint_pubkey, false),
+        AccountMeta::new_readonly(rent::id(), false),
+        AccountMeta::new_readonly(system_program::id(), false),
+        AccountMeta::new_readonly(spl_token::id(), false),
+    ];
+
+    Ok(Instruction {
+        program_id,
+        accounts,
+        data,
+    })
+}
+
+/// Creates an 'UnstakeFeeDiscount' instruction.
+pub fn unstake_fee_discount(
+    program_id: Pubkey,
+    fee_discount_pubkey: Pubkey,
+    fee_discount_token_pubkey: Pubkey,
+    owner_pubkey: Pubkey,
+    destination_pubkey: Pubkey,
+    amount: u64,
+) -> Result<Instruction, ProgramError> {
+    let data = SwapInstruction::UnstakeFeeDiscount(amount).pack();
+
+    let accounts = vec![
+        AccountMeta::new(fee_discount_pubkey, false),
+        AccountMeta::new(fee_discount_token_pubkey, false),
+        AccountMeta::new_readonly(owner_pubkey, true),
+        AccountMeta::new(destination_pubkey, false),
+        AccountMeta::new_readonly(spl_token::id(), false),
+    ];
+
+    Ok(Instruction {
+        program_id,
+        accounts,
+        data,
+    })
+}
+
+/// Creates a 'LockLiquidity' instruction.
+pub fn lock_liquidity(
+    program_id: Pubkey,
+    swap_pubkey: Pubkey,
+    liquidity_lock_pubkey: Pubkey,
+    liquidity_lock_token_pubkey: Pubkey,
+    source_pubkey: Pubkey,
+    owner_pubkey: Pubkey,
+    pool_mint_pubkey: Pubkey,
+    lock_liquidity_data: LockLiquidityData,
+) -> Result<Instruction, ProgramError> {
+    let data = SwapInstruction::LockLiquidity(lock_liquidity_data).pack();
+
+    let accounts = vec![
+        AccountMeta::new_readonly(swap_pubkey, false),
+        AccountMeta::new(liquidity_lock_pubkey, false),
+        AccountMeta::new(liquidity_lock_token_pubkey, false),
+        AccountMeta::new(source_pubkey, false),
+        AccountMeta::new(owner_pubkey, true),
+        AccountMeta::new_readonly(pool_mint_pubkey, false),
+        AccountMeta::new_readonly(rent::id(), false),
+        AccountMeta::new_readonly(system_program::id(), false),
+        AccountMeta::new_readonly(spl_token::id(), false),
+    ];
+
+    Ok(Instruction {
+        program_id,
+        accounts,
+        data,
+    })
+}
+
+/// Creates an 'UnlockLiquidity' instruction.
+pub fn unlock_liquidity(
+    program_id: Pubkey,
+    liquidity_lock_pubkey: Pubkey,
+    liquidity_lock_token_pubkey: Pubkey,
+    owner_pubkey: Pubkey,
+    destination_pubkey: Pubkey,
+) -> Result<Instruction, ProgramError> {
+    let data = SwapInstruction::UnlockLiquidity.pack();
+
+    let accounts = vec![
+        AccountMeta::new(liquidity_lock_pubkey, false),
+        AccountMeta::new(liquidity_lock_token_pubkey, false),
+        AccountMeta::new_readonly(owner_pubkey, true),
+        AccountMeta::new(destination_pubkey, false),
+        AccountMeta::new_readonly(spl_token::id(), false),
+    ];
+
+    Ok(Instruction {
+        program_id,
+        accounts,
+        data,
+    })
+}
+
+/// Turns a 'SwapV2' instruction into a 'SwapPartialFill' instruction with the same accounts
+pub fn with_partial_fill(mut instruction: Instruction) -> Result<Instruction, ProgramError> {
+    instruction.data = match SwapInstruction::unpack(&instruction.data)? {
+        SwapInstruction::SwapV2(swap_data) => SwapInstruction::SwapPartialFill(swap_data).pack(),
+        _ => return Err(SwapError::InvalidInstruction.into()),
+    };
+    Ok(instruction)
+}
+
+/// Turns a 'SwapV2' or a 'Deposit' instruction into its native SOL variant, the temporary wSOL
+/// account must already be its SOURCE or DESTINATION account.
+pub fn with_native_sol(
+    instruction: Instruction,
+    native_sol_pubkey: Pubkey,
+) -> Result<Instruction, ProgramError> {
+    let (data, user_transfer_authority_index) = match SwapInstruction::unpack(&instruction.data)? {
+        SwapInstruction::SwapV2(swap_data) => (SwapInstruction::SwapNativeSol(swap_data).pack(), 4),
+        SwapInstruction::Deposit(deposit_data) => {
+            (SwapInstruction::DepositNativeSol(deposit_data).pack(), 2)
+        }
+        _ => return Err(SwapError::InvalidInstruction.into()),
+    };
+
+    let mut accounts = vec![
+        AccountMeta::new(native_sol_pubkey, false),
+        AccountMeta::new_readonly(spl_token::native_mint::id(), false),
+        AccountMeta::new_readonly(rent::id(), false),
+        AccountMeta::new_readonly(system_program::id(), false),
+        AccountMeta::new_readonly(spl_token::id(), false),
+    ];
+    let mut wrapped_accounts = instruction.accounts;
+    wrapped_accounts
+        .get_mut(user_transfer_authority_index)
+        .ok_or(ProgramError::NotEnoughAccountKeys)?
+        .is_writable = true;
+    accounts.extend(wrapped_accounts);
+
+    Ok(Instruction {
+        program_id: instruction.program_id,
+        accounts,
+        data,
+    })
+}
+
+/// Creates a 'flash_swap' instruction.
+pub fn flash_swap(
+    program_id: Pubkey,
+    config_pubkey: Pubkey,
+    swap_pubkey: Pubkey,
+    swap_authority_pubkey: Pubkey,
+    swap_token_a_pubkey: Pubkey,
+    swap_token_b_pubkey: Pubkey,
+    destination_token_a_pubkey: Pubkey,
+    destination_token_b_pubkey: Pubkey,
+    admin_fee_a_pubkey: Pubkey,
+    admin_fee_b_pubkey: Pubkey,
+    treasury_token_pubkeys: Option<(Pubkey, Pubkey)>,
+    callback_program_id: Pubkey,
+    callback_accounts: Vec<AccountMeta>,
+    flash_swap_data: FlashSwapData,
+) -> Result<Instruction, ProgramError> {
+    let data = SwapInstruction::FlashSwap(flash_swap_data).pack();
+
+    let mut accounts = vec![
+        AccountMeta::new_readonly(config_pubkey, false),
+        AccountMeta::new(swap_pubkey, false),
+        AccountMeta::new_readonly(swap_authority_pubkey, false),
+        AccountMeta::new(swap_token_a_pubkey, false),
+        AccountMeta::new(swap_token_b_pubkey, false),
+        AccountMeta::new(destination_token_a_pubkey, false),
+        AccountMeta::new(destination_token_b_pubkey, false),
+        AccountMeta::new(admin_fee_a_pubkey, false),
+        AccountMeta::new(admin_fee_b_pubkey, false),
+        AccountMeta::new_readonly(spl_token::id(), false),
+    ];
+    if let Some((treasury_token_a_pubkey, treasury_token_b_pubkey)) = treasury_token_pubkeys {
+        accounts.push(AccountMeta::new(treasury_token_a_pubkey, false));
+        accounts.push(AccountMeta::new(treasury_token_b_pubkey, false));
+    }
+    accounts.push(AccountMeta::new_readonly(callback_program_id, false));
+    accounts.extend(callback_accounts);
+
+    Ok(Instruction {
+        program_id,
+        accounts,
+        data,
+    })
+}
+
+/// Tri pool initialize data
+#[repr(C)]
+#[derive(Clone, Debug, PartialEq)]
+pub struct TriInitializeData {
+    /// Nonce used to create valid program address
+    pub nonce: u8,
+    /// Amplification coefficient of the StableSwap invariant
+    pub amp: u64,
+    /// Decimals of the three tokens, in the order of the token accounts
+    pub token_decimals: [u8; TRI_POOL_TOKENS],
+}
+
+/// Tri pool swap instruction data
+#[repr(C)]
+#[derive(Clone, Debug, PartialEq)]
+pub struct TriSwapData {
+    /// SOURCE amount to transfer, output to DESTINATION is based on the StableSwap invariant
+    pub amount_in: u64,
+    /// Minimum amount of DESTINATION token to output, prevents excessive slippage
+    pub minimum_amount_out: u64,
+    /// Last slot the transaction can be executed, prevents stale transactions landing late
+    pub deadline_slot: Option<u64>,
+}
+
+/// Tri pool deposit instruction data
+#[repr(C)]
+#[derive(Clone, Debug, PartialEq)]
+pub struct TriDepositData {
+    /// Amounts of the three tokens to deposit
+    pub token_amounts: [u64; TRI_POOL_TOKENS],
+    /// Minimum LP tokens to mint, prevents excessive slippage
+    pub min_mint_amount: u64,
+    /// Last slot the transaction can be executed, prevents stale transactions landing late
+    pub deadline_slot: Option<u64>,
+}
+
+/// Tri pool withdraw instruction data
+#[repr(C)]
+#[derive(Clone, Debug, PartialEq)]
+pub struct TriWithdrawData {
+    /// Amount of pool tokens to burn, paid out in the three tokens at the ratio of the pool
+    pub pool_token_amount: u64,
+    /// Minimum amounts of the three tokens to receive, prevents excessive slippage
+    pub minimum_token_amounts: [u64; TRI_POOL_TOKENS],
+    /// Last slot the transaction can be executed, prevents stale transactions landing late
+    pub deadline_slot: Option<u64>,
+}
+
+/// Swap instructions for stablecoins pool
+#[repr(C)]
+#[derive(Debug, PartialEq)]
+pub enum StableSwapInstruction {
+    ///   Initializes a new stable swap pool.
+    ///
+    ///   0. `[]` market config.
+    ///   1. `[writable]` new stable-swap to create.
+    ///   1. `[]` $swap_authority.
+    ///   3. `[]` admin fee account for base token.
+    ///   4. `[]` admin fee account for quote token.
+    ///   5. `[]` base token account. Must be non zero, owned by $swap_authority.
+    ///   6. `[]` quote token account. Must be non zero, owned by $swap_authority.
+    ///   7. `[writable]` pool mint account mint by $swap_authority.
+    ///   8. `[writable]` pool token account owned by user.
+    ///   9. `[signer]` admin account.
+    ///   10. `[]` rent sysvar.
+    ///   11. `[]` token program id.
+    Initialize(StableInitializeData),
+
+    ///   Swap the tokens in the pool.
+    ///
+    ///   On a whitelist-gated pool the whitelist entry of $user_transfer_authority follows the
+    ///   token program id, ahead of the optional accounts.
+    ///
+    ///   0. `[]` market config.
+    ///   1. `[writable]` stable-swap.
+    ///   2. `[]` $market_authority to mint deltafi token.
+    ///   3. `[]` $swap_authority.
+    ///   4. `[signer]` $user_transfer_authority
+    ///   5. `[writable]` SOURCE(base|quote) account, transferable by $user_transfer_authority.
+    ///   6. `[writable]` (base|quote) token account to swap INTO. Must be the SOURCE token.
+    ///   7.  `[]` Mint account of SOURCE token
+    ///   8. `[writable]` (base|quote) token account to swap FROM. Must be the DESTINATION token.
+    ///   9. `[writable]` DESTINATION(base|quote) account owned by user.
+    ///   10. `[]` Mint account of DESITINATION token
+    ///   11. `[writable]` rewards(DELTAFI) token account owned by user.
+    ///   12. `[writable]` rewards(DELTAFI) source deltafi token account to issue reward.
+    ///   13. `[writable]` (base|quote) admin fee account. Must have same mint as DESTINATION token.
+    ///   14. `[]` token program id.
+    ///   15. `[]` optional: exchange rate account, required if the pool has an exchange rate adapter.
+    ///   16. `[writable]` optional: treasury token account of the DESTINATION mint owned by
+    ///       the config treasury, required while the protocol fee switch is on.
+    ///   17. `[]` optional: instructions sysvar, required if the output owner is set.
+    ///   18. `[]` optional: user referrer data account.
+    ///   19. `[writable]` optional: referrer token account.
+    ///   20. `[writable]` optional: referrer stats of the referrer token account, paying the
+    ///       referral reward at the tier of its referred volume instead of the base tier.
+    Swap(SwapData),
+
+    ///   Deposit some tokens into the pool.  The output is a "pool" token representing ownership
+    ///   into the pool. Inputs are converted to the current ratio.
+    ///
+    ///   On a whitelist-gated pool the whitelist entry of $user_transfer_authority follows the
+    ///   token program id, ahead of the optional accounts.
+    ///
+    ///   0. `[]` stable-swap.
+    ///   1. `[]` $swap_authority.
+    ///   2. `[signer]` $user_transfer_authority.
+    ///   3. `[writable]` base token account to deposit FROM, transferable by $user_transfer_authority.
+    ///   4. `[writable]` quote token account to deposit FROM, transferable by $user_transfer_authority.
+    ///   5. `[writable]` base token account to deposit INTO.
+    ///   6. `[writable]` quote token account to deposit INTO.
+    ///   7. `[writable]` pool mint account, mint by $swap_authority.
+    ///   8. `[writable]` pool token account owned by user.
+    ///   10. `[]` token program id.
+    Deposit(DepositData),
+
+    ///   Withdraw tokens from the pool at the current ratio.
+    ///
+    ///   0. `[]` stable-swap.
+    ///   1. `[]` $swap_authority.
+    ///   2. `[signer]` $user_transfer_authority.
+    ///   3. `[writable]` pool mint account, mint by $swap_autority.
+    ///   4. `[writable]` SOURCE pool token account, transferable by $user_transfer_authority.
+    ///   5. `[writable]` base token account to withdraw FROM.
+    ///   6. `[writable]` quote token account to withdraw FROM.
+    ///   7. `[writable]` base token account to withdraw INTO.
+    ///   8. `[writable]` quote token account to withdraw INTO.
+    ///   9. `[writable]` admin fee account for base token.
+    ///   10. `[writable]` admin fee account for quote token.
+    ///   12. `[]` token program id.
+    Withdraw(WithdrawData),
+
+    ///   Swap the tokens in the pool (removed src and dest mint accounts).
+    ///
+    ///   On a whitelist-gated pool the whitelist entry of $user_transfer_authority follows the
+    ///   token program id, ahead of the optional accounts.
+    ///
+    ///   0. `[]` market config.
+    ///   1. `[writable]` stable-swap.
+    ///   2. `[]` $market_authority to mint deltafi token.
+    ///   3. `[]` $swap_authority.
+    ///   4. `[signer]` $user_transfer_authority
+    ///   5. `[writable]` SOURCE(base|quote) account, transferable by $user_transfer_authority.
+    ///   6. `[writable]` (base|quote) token account to swap INTO. Must be the SOURCE token.
+    ///   7. `[writable]` (base|quote) token account to swap FROM. Must be the DESTINATION token.
+    ///   8. `[writable]` DESTINATION(base|quote) account owned by user.
+    ///   9. `[writable]` rewards(DELTAFI) token account owned by user.
+    ///   10. `[writable]` rewards(DELTAFI) source deltafi token account to issue reward.
+    ///   11. `[writable]` (base|quote) admin fee account. Must have same mint as DESTINATION token.
+    ///   12. `[]` token program id.
+    ///   13. `[]` optional: exchange rate account, required if the pool has an exchange rate adapter.
+    ///   14. `[writable]` optional: treasury token account of the DESTINATION mint owned by
+    ///       the config treasury, required while the protocol fee switch is on.
+    ///   15. `[]` optional: instructions sysvar, required if the output owner is set.
+    ///   16. `[]` optional: user referrer data account.
+    ///   17. `[writable]` optional: referrer token account.
+    ///   18. `[writable]` optional: referrer stats of the referrer token account, paying the
+    ///       referral reward at the tier of its referred volume instead of the base tier.
+    SwapV2(SwapData),
+
+    ///   Deposit a single token into the pool. Part of the input is swapped to the other
+    ///   side at the pool price and the rest is deposited with the swap output.
+    ///
+    ///   On a whitelist-gated pool the whitelist entry of $user_transfer_authority follows the
+    ///   token program id, ahead of the optional accounts.
+    ///
+    ///   0. `[]` market config.
+    ///   1. `[writable]` stable-swap.
+    ///   2. `[]` $swap_authority.
+    ///   3. `[signer]` $user_transfer_authority.
+    ///   4. `[writable]` SOURCE(base|quote) account, transferable by $user_transfer_authority.
+    ///   5. `[writable]` base token account to deposit INTO.
+    ///   6. `[writable]` quote token account to deposit INTO.
+    ///   7. `[writable]` (base|quote) admin fee account. Must be the other side of SOURCE token.
+    ///   8. `[writable]` pool mint account, mint by $swap_authority.
+    ///   9. `[writable]` pool token account owned by user.
+    ///   10. `[]` optional: exchange rate account, required if the pool has an exchange rate adapter.
+    ///   11. `[]` token program id.
+    DepositOne(DepositOneData),
+
+    ///   Withdraw a single token from the pool. The other side of the withdrawn liquidity
+    ///   is swapped into the requested token at the pool price.
+    ///
+    ///   0. `[]` market config.
+    ///   1. `[writable]` stable-swap.
+    ///   2. `[]` $swap_authority.
+    ///   3. `[signer]` $user_transfer_authority.
+    ///   4. `[writable]` pool mint account, $swap_authority can burn.
+    ///   5. `[writable]` SOURCE pool account, amount is transferable by $user_transfer_authority.
+    ///   6. `[writable]` base token account to withdraw FROM.
+    ///   7. `[writable]` quote token account to withdraw FROM.
+    ///   8. `[writable]` (base|quote) user account to credit, matching `token_side`.
+    ///   9. `[writable]` (base|quote) admin fee account, matching `token_side`.
+    ///   10. `[]` optional: exchange rate account, required if the pool has an exchange rate adapter.
+    ///   11. `[]` token program id.
+    WithdrawOne(WithdrawOneData),
+
+    ///   Initializes a new pegged pool trading at a constant price without any oracle, the
+    ///   pool is then traded with the stable swap instructions.
+    ///
+    ///   0. `[]` market config.
+    ///   1. `[writable]` new pegged swap to create.
+    ///   2. `[]` $swap_authority.
+    ///   3. `[]` admin fee account for base token.
+    ///   4. `[]` admin fee account for quote token.
+    ///   5. `[]` base token account. Must be non zero, owned by $swap_authority.
+    ///   6. `[]` quote token account. Must be non zero, owned by $swap_authority.
+    ///   7. `[writable]` pool mint account mint by $swap_authority.
+    ///   8. `[writable]` pool token account owned by user.
+    ///   9. `[signer]` admin account.
+    ///   10. `[]` rent sysvar.
+    ///   11. `[]` token program id.
+    PeggedInitialize(PeggedInitializeData),
+
+    ///   Initializes a new stable swap pool at the program address derived from
+    ///   `find_swap_address` with the mints of the base and quote token accounts. The accounts
+    ///   are the ones of `Initialize` with the swap not signing and the admin writable to pay
+    ///   for the swap account, followed by:
+    ///
+    ///   12. `[]` system program.
+    InitializeV2(StableInitializeData),
+
+    ///   Initializes a new tri pool of three stable tokens traded on the StableSwap invariant.
+    ///   The token accounts hold the initial liquidity, the pool tokens minted are the
+    ///   invariant of the initial balances at the decimals of the pool mint, the most decimals
+    ///   of the tokens.
+    ///
+    ///   0. `[]` market config.
+    ///   1. `[writable, signer]` new tri pool to create.
+    ///   2. `[]` $swap_authority.
+    ///   3..6. `[]` admin fee accounts of the three tokens.
+    ///   6..9. `[]` token accounts of the three tokens. Must be non zero, owned by $swap_authority.
+    ///   9. `[writable]` pool mint account mint by $swap_authority.
+    ///   10. `[writable]` pool token account owned by user.
+    ///   11. `[signer]` admin account.
+    ///   12. `[]` rent sysvar.
+    ///   13. `[]` token program id.
+    TriInitialize(TriInitializeData),
+
+    ///   Swap one token of a tri pool for another.
+    ///
+    ///   0. `[]` market config.
+    ///   1. `[writable]` tri pool.
+    ///   2. `[]` $swap_authority.
+    ///   3. `[signer]` $user_transfer_authority.
+    ///   4. `[writable]` SOURCE account, transferable by $user_transfer_authority.
+    ///   5. `[writable]` pool token account to swap INTO. Must be the SOURCE token.
+    ///   6. `[writable]` pool token account to swap FROM. Must be the DESTINATION token.
+    ///   7. `[writable]` DESTINATION account owned by user.
+    ///   8. `[writable]` admin fee account of the DESTINATION token.
+    ///   9. `[]` token program id.
+    ///   10. `[writable]` optional: treasury token account of the DESTINATION mint owned by
+    ///       the config treasury, required while the protocol fee switch is on.
+    TriSwap(TriSwapData),
+
+    ///   Deposit the three tokens into a tri pool. Amounts off the ratio of the pool pay an
+    ///   imbalance fee.
+    ///
+    ///   0. `[]` market config.
+    ///   1. `[writable]` tri pool.
+    ///   2. `[]` $swap_authority.
+    ///   3. `[signer]` $user_transfer_authority.
+    ///   4..7. `[writable]` token accounts to deposit FROM, transferable by $user_transfer_authority.
+    ///   7..10. `[writable]` pool token accounts to deposit INTO.
+    ///   10. `[writable]` pool mint account, mint by $swap_authority.
+    ///   11. `[writable]` pool token account owned by user.
+    ///   12. `[]` token program id.
+    TriDeposit(TriDepositData),
+
+    ///   Withdraw the three tokens from a tri pool at the ratio of the pool.
+    ///
+    ///   0. `[]` market config.
+    ///   1. `[writable]` tri pool.
+    ///   2. `[]` $swap_authority.
+    ///   3. `[signer]` $user_transfer_authority.
+    ///   4. `[writable]` pool mint account, $swap_authority can burn.
+    ///   5. `[writable]` SOURCE pool token account, transferable by $user_transfer_authority.
+    ///   6..9. `[writable]` pool token accounts to withdraw FROM.
+    ///   9..12. `[writable]` token accounts to withdraw INTO.
+    ///   12..15. `[writable]` admin fee accounts of the three tokens.
+    ///   15. `[]` token program id.
+    TriWithdraw(TriWithdrawData),
+}
+
+impl StableSwapInstruction {
+    /// Unpacks a byte buffer into a [StableSwapInstruction](enum.StableSwapInstruction.html).
+    pub fn unpack(input: &[u8]) -> Result<Self, ProgramError> {
+        let (&tag, rest) = input
+            .split_first()
+            .ok_or(SwapError::InstructionUnpackError)?;
+        Ok(match tag {
+            10 => Self::Initialize(unpack_stable_initialize_data(rest)?),
+            11 => {
+                let (amount_in, rest) = unpack_u64(rest)?;
+                let (minimum_amount_out, rest) = unpack_u64(rest)?;
+                let (deadline_slot, output_owner) = unpack_swap_options(rest)?;
+                Self::Swap(SwapData {
+                    amount_in,
+                    minimum_amount_out,
+                    deadline_slot,
+                    output_owner,
+                })
+            }
+            12 => {
+                let (token_a_amount, rest) = unpack_u64(rest)?;
+                let (token_b_amount, rest) = unpack_u64(rest)?;
+                let (min_mint_amount, rest) = unpack_u64(rest)?;
+                let deadline_slot = unpack_deadline_slot(rest)?;
+                Self::Deposit(DepositData {
+                    token_a_amount,
+                    token_b_amount,
+                    min_mint_amount,
+                    deadline_slot,
+                })
+            }
+            13 => {
+                let (pool_token_amount, rest) = unpack_u64(rest)?;
+                let (minimum_token_a_amount, rest) = unpack_u64(rest)?;
+                let (minimum_token_b_amount, rest) = unpack_u64(rest)?;
+                let deadline_slot = unpack_deadline_slot(rest)?;
+                Self::Withdraw(WithdrawData {
+                    pool_token_amount,
+                    minimum_token_a_amount,
+                    minimum_token_b_amount,
+                    deadline_slot,
+                })
+            }
+            14 => {
+                let (amount_in, rest) = unpack_u64(rest)?;
+                let (minimum_amount_out, rest) = unpack_u64(rest)?;
+                let (deadline_slot, output_owner) = unpack_swap_options(rest)?;
+                Self::SwapV2(SwapData {
+                    amount_in,
+                    minimum_amount_out,
+                    deadline_slot,
+                    output_owner,
+                })
+            }
+            15 => {
+                let (amount_in, rest) = unpack_u64(rest)?;
+                let (min_mint_amount, rest) = unpack_u64(rest)?;
+                let deadline_slot = unpack_deadline_slot(rest)?;
+                Self::DepositOne(DepositOneData {
+                    amount_in,
+                    min_mint_amount,
+                    deadline_slot,
+                })
+            }
+            16 => {
+                let (pool_token_amount, rest) = unpack_u64(rest)?;
+                let (minimum_amount_out, rest) = unpack_u64(rest)?;
+                let (token_side, rest) = unpack_u8(rest)?;
+                let deadline_slot = unpack_deadline_slot(rest)?;
+                Self::WithdrawOne(WithdrawOneData {
+                    pool_token_amount,
+                    minimum_amount_out,
+                    token_side,
+                    deadline_slot,
+                })
+            }
+            19 => {
+                let (nonce, rest) = unpack_u8(rest)?;
+                let (pegged_price, rest) = unpack_u128(rest)?;
+                let (token_a_decimals, rest) = unpack_u8(rest)?;
+                let (token_b_decimals, rest) = unpack_u8(rest)?;
+                let (token_a_amount, rest) = unpack_u64(rest)?;
+                let (token_b_amount, _) = unpack_u64(rest)?;
+                Self::PeggedInitialize(PeggedInitializeData {
+                    nonce,
+                    pegged_price,
+                    token_a_decimals,
+                    token_b_decimals,
+                    token_a_amount,
+                    token_b_amount,
+                })
+            }
+            28 => Self::InitializeV2(unpack_stable_initialize_data(rest)?),
+            47 => {
+                let (nonce, rest) = unpack_u8(rest)?;
+                let (amp, rest) = unpack_u64(rest)?;
+                let mut token_decimals = [0u8; TRI_POOL_TOKENS];
+                let mut rest = rest;
+                for decimals in token_decimals.iter_mut() {
+                    let (value, next) = unpack_u8(rest)?;
+                    *decimals = value;
+                    rest = next;
+                }
+                Self::TriInitialize(TriInitializeData {
+                    nonce,
+                    amp,
+                    token_decimals,
+                })
+            }
+            48 => {
+                let (amount_in, rest) = unpack_u64(rest)?;
+                let (minimum_amount_out, rest) = unpack_u64(rest)?;
+                let deadline_slot = unpack_deadline_slot(rest)?;
+                Self::TriSwap(TriSwapData {
+                    amount_in,
+                    minimum_amount_out,
+                    deadline_slot,
+                })
+            }
+            49 => {
+                let (token_amounts, rest) = unpack_tri_amounts(rest)?;
+                let (min_mint_amount, rest) = unpack_u64(rest)?;
+                let deadline_slot = unpack_deadline_slot(rest)?;
+                Self::TriDeposit(TriDepositData {
+                    token_amounts,
+                    min_mint_amount,
+                    deadline_slot,
+                })
+            }
+            50 => {
+                let (pool_token_amount, rest) = unpack_u64(rest)?;
+                let (minimum_token_amounts, rest) = unpack_tri_amounts(rest)?;
+                let deadline_slot = unpack_deadline_slot(rest)?;
+                Self::TriWithdraw(TriWithdrawData {
+                    pool_token_amount,
+                    minimum_token_amounts,
+                    deadline_slot,
+                })
+            }
+            _ => return Err(SwapError::InvalidInstruction.into()),
+        })
+    }
+
+    /// Packs a [SwapInstruction](enum.SwapInstruction.html) into a byte buffer.
+    pub fn pack(&self) -> Vec<u8> {
+        let mut buf = Vec::with_capacity(size_of::<Self>());
+        match *self {
+            Self::Initialize(ref init_data) => {
+                buf.push(10);
+                pack_stable_initialize_data(init_data, &mut buf);
+            }
+            Self::Swap(SwapData {
+                amount_in,
+                minimum_amount_out,
+                deadline_slot,
+                output_owner,
+            }) => {
+                buf.push(11);
+                buf.extend_from_slice(&amount_in.to_le_bytes());
+                buf.extend_from_slice(&minimum_amount_out.to_le_bytes());
+                pack_swap_options(deadline_slot, output_owner, &mut buf);
+            }
+            Self::Deposit(DepositData {
+                token_a_amount,
+                token_b_amount,
+                min_mint_amount,
+                deadline_slot,
+            }) => {
+                buf.push(12);
+                buf.extend_from_slice(&token_a_amount.to_le_bytes());
+                buf.extend_from_slice(&token_b_amount.to_le_bytes());
+                buf.extend_from_slice(&min_mint_amount.to_le_bytes());
+                pack_deadline_slot(deadline_slot, &mut buf);
+            }
+            Self::Withdraw(WithdrawData {
+                pool_token_amount,
+                minimum_token_a_amount,
+                minimum_token_b_amount,
+                deadline_slot,
+            }) => {
+                buf.push(13);
+                buf.extend_from_slice(&pool_token_amount.to_le_bytes());
+                buf.extend_from_slice(&minimum_token_a_amount.to_le_bytes());
+                buf.extend_from_slice(&minimum_token_b_amount.to_le_bytes());
+                pack_deadline_slot(deadline_slot, &mut buf);
+            }
+            Self::SwapV2(SwapData {
+                amount_in,
+                minimum_amount_out,
+                deadline_slot,
+                output_owner,
+            }) => {
+                buf.push(14);
+                buf.extend_from_slice(&amount_in.to_le_bytes());
+                buf.extend_from_slice(&minimum_amount_out.to_le_bytes());
+                pack_swap_options(deadline_slot, output_owner, &mut buf);
+            }
+            Self::DepositOne(DepositOneData {
+                amount_in,
+                min_mint_amount,
+                deadline_slot,
+            }) => {
+                buf.push(15);
+                buf.extend_from_slice(&amount_in.to_le_bytes());
+                buf.extend_from_slice(&min_mint_amount.to_le_bytes());
+                pack_deadline_slot(deadline_slot, &mut buf);
+            }
+            Self::WithdrawOne(WithdrawOneData {
+                pool_token_amount,
+                minimum_amount_out,
+                token_side,
+                deadline_slot,
+            }) => {
+                buf.push(16);
+                buf.extend_from_slice(&pool_token_amount.to_le_bytes());
+                buf.extend_from_slice(&minimum_amount_out.to_le_bytes());
+                buf.push(token_side);
+                pack_deadline_slot(deadline_slot, &mut buf);
+            }
+            Self::PeggedInitialize(PeggedInitializeData {
+                nonce,
+                pegged_price,
+                token_a_decimals,
+                token_b_decimals,
+                token_a_amount,
+                token_b_amount,
+            }) => {
+                buf.push(19);
+                buf.push(nonce);
+                buf.extend_from_slice(&pegged_price.to_le_bytes());
+                buf.push(token_a_decimals);
+                buf.push(token_b_decimals);
+                buf.extend_from_slice(&token_a_amount.to_le_bytes());
+                buf.extend_from_slice(&token_b_amount.to_le_bytes());
+            }
+            Self::InitializeV2(ref init_data) => {
+                buf.push(28);
+                pack_stable_initialize_data(init_data, &mut buf);
+            }
+            Self::TriInitialize(TriInitializeData {
+                nonce,
+                amp,
+                token_decimals,
+            }) => {
+                buf.push(47);
+                buf.push(nonce);
+                buf.extend_from_slice(&amp.to_le_bytes());
+                buf.extend_from_slice(&token_decimals);
+            }
+            Self::TriSwap(TriSwapData {
+                amount_in,
+                minimum_amount_out,
+                deadline_slot,
+            }) => {
+                buf.push(48);
+                buf.extend_from_slice(&amount_in.to_le_bytes());
+                buf.extend_from_slice(&minimum_amount_out.to_le_bytes());
+                pack_deadline_slot(deadline_slot, &mut buf);
+            }
+            Self::TriDeposit(TriDepositData {
+                token_amounts,
+                min_mint_amount,
+                deadline_slot,
+            }) => {
+                buf.push(49);
+                pack_tri_amounts(&token_amounts, &mut buf);
+                buf.extend_from_slice(&min_mint_amount.to_le_bytes());
+                pack_deadline_slot(deadline_slot, &mut buf);
+            }
+            Self::TriWithdraw(TriWithdrawData {
+                pool_token_amount,
+                minimum_token_amounts,
+                deadline_slot,
+            }) => {
+                buf.push(50);
+                buf.extend_from_slice(&pool_token_amount.to_le_bytes());
+                pack_tri_amounts(&minimum_token_amounts, &mut buf);
+                pack_deadline_slot(deadline_slot, &mut buf);
+            }
+        }
+        buf
+    }
+}
+
+/// Creates 'stable_initialize' instruction.
+pub fn stable_initialize(
+    program_id: Pubkey,
+    config_pubkey: Pubkey,
+    swap_pubkey: Pubkey,
+    authority_pubkey: Pubkey,
+    admin_fee_a_pubkey: Pubkey,
+    admin_fee_b_pubkey: Pubkey,
+    token_a_pubkey: Pubkey,
+    token_b_pubkey: Pubkey,
+    pool_mint_pubkey: Pubkey,
+    destination_pubkey: Pubkey,
+    admin_pubkey: Pubkey,
+    init_data: StableInitializeData,
+) -> Result<Instruction, ProgramError> {
+    let data = StableSwapInstruction::Initialize(init_data).pack();
+
+    let accounts = vec![
+        AccountMeta::new_readonly(config_pubkey, false),
+        AccountMeta::new(swap_pubkey, true),
+        AccountMeta::new_readonly(authority_pubkey, false),
+        AccountMeta::new_readonly(admin_fee_a_pubkey, false),
+        AccountMeta::new_readonly(admin_fee_b_pubkey, false),
+        AccountMeta::new_readonly(token_a_pubkey, false),
+        AccountMeta::new_readonly(token_b_pubkey, false),
+        AccountMeta::new(pool_mint_pubkey, false),
+        AccountMeta::new(destination_pubkey, false),
+        AccountMeta::new_readonly(admin_pubkey, true),
+        AccountMeta::new_readonly(rent::id(), false),
+        AccountMeta::new_readonly(spl_token::id(), false),
+    ];
+
+    Ok(Instruction {
+        program_id,
+        accounts,
+        data,
+    })
+}
+
+/// Creates 'stable_initialize_v2' instruction, the swap is derived from `find_swap_address`.
+pub fn stable_initialize_v2(
+    program_id: Pubkey,
+    config_pubkey: Pubkey,
+    swap_pubkey: Pubkey,
+    authority_pubkey: Pubkey,
+    admin_fee_a_pubkey: Pubkey,
+    admin_fee_b_pubkey: Pubkey,
+    token_a_pubkey: Pubkey,
+    token_b_pubkey: Pubkey,
+    pool_mint_pubkey: Pubkey,
+    destination_pubkey: Pubkey,
+    admin_pubkey: Pubkey,
+    init_data: StableInitializeData,
+) -> Result<Instruction, ProgramError> {
+    let data = StableSwapInstruction::InitializeV2(init_data).pack();
+
+    let accounts = vec![
+        AccountMeta::new_readonly(config_pubkey, false),
+        AccountMeta::new(swap_pubkey, false),
+        AccountMeta::new_readonly(authority_pubkey, false),
+        AccountMeta::new_readonly(admin_fee_a_pubkey, false),
+        AccountMeta::new_readonly(admin_fee_b_pubkey, false),
+        AccountMeta::new_readonly(token_a_pubkey, false),
+        AccountMeta::new_readonly(token_b_pubkey, false),
+        AccountMeta::new(pool_mint_pubkey, false),
+        AccountMeta::new(destination_pubkey, false),
+        AccountMeta::new(admin_pubkey, true),
+        AccountMeta::new_readonly(rent::id(), false),
+        AccountMeta::new_readonly(spl_token::id(), false),
+        AccountMeta::new_readonly(system_program::id(), false),
+    ];
+
+    Ok(Instruction {
+        program_id,
+        accounts,
+        data,
+    })
+}
+
+/// Creates 'pegged_initialize' instruction.
+pub fn pegged_initialize(
+    program_id: Pubkey,
+    config_pubkey: Pubkey,
+    swap_pubkey: Pubkey,
+    authority_pubkey: Pubkey,
+    admin_fee_a_pubkey: Pubkey,
+    admin_fee_b_pubkey: Pubkey,
+    token_a_pubkey: Pubkey,
+    token_b_pubkey: Pubkey,
+    pool_mint_pubkey: Pubkey,
+    destination_pubkey: Pubkey,
+    admin_pubkey: Pubkey,
+    init_data: PeggedInitializeData,
+) -> Result<Instruction, ProgramError> {
+    let data = StableSwapInstruction::PeggedInitialize(init_data).pack();
+
+    let accounts = vec![
+        AccountMeta::new_readonly(config_pubkey, false),
+        AccountMeta::new(swap_pubkey, true),
+        AccountMeta::new_readonly(authority_pubkey, false),
+        AccountMeta::new_readonly(admin_fee_a_pubkey, false),
+        AccountMeta::new_readonly(admin_fee_b_pubkey, false),
+        AccountMeta::new_readonly(token_a_pubkey, false),
+        AccountMeta::new_readonly(token_b_pubkey, false),
+        AccountMeta::new(pool_mint_pubkey, false),
+        AccountMeta::new(destination_pubkey, false),
+        AccountMeta::new_readonly(admin_pubkey, true),
+        AccountMeta::new_readonly(rent::id(), false),
+        AccountMeta::new_readonly(spl_token::id(), false),
+    ];
+
+    Ok(Instruction {
+        program_id,
+        accounts,
+        data,
+    })
+}
+
+/// Creates a 'stable_swap' instruction.
+pub fn stable_swap(
+    program_id: Pubkey,
+    config_pubkey: Pubkey,
+    swap_pubkey: Pubkey,
+    market_authority_pubkey: Pubkey,
+    swap_authority_pubkey: Pubkey,
+    user_transfer_authority_pubkey: Pubkey,
+    source_pubkey: Pubkey,
+    swap_source_pubkey: Pubkey,
+    source_mint_info: Pubkey,
+    swap_destination_pubkey: Pubkey,
+    destination_pubkey: Pubkey,
+    destination_mint_info: Pubkey,
+    reward_token_pubkey: Pubkey,
+    source_reward_token_pubkey: Pubkey,
+    admin_fee_destination_pubkey: Pubkey,
+    exchange_rate_pubkey: Option<Pubkey>,
+    treasury_token_pubkey: Option<Pubkey>,
+    user_referrer_data_pubkey: Option<Pubkey>,
+    referrer_token_pubkey: Option<Pubkey>,
+    referrer_stats_pubkey: Option<Pubkey>,
+    swap_data: SwapData,
+) -> Result<Instruction, ProgramError> {
+    let has_output_owner = swap_data.output_owner.is_some();
+    let data = StableSwapInstruction::Swap(swap_data).pack();
+
+    let mut accounts = vec![
+        AccountMeta::new_readonly(config_pubkey, false),
+        AccountMeta::new(swap_pubkey, false),
+        AccountMeta::new_readonly(market_authority_pubkey, false),
+        AccountMeta::new_readonly(swap_authority_pubkey, false),
+        AccountMeta::new_readonly(user_transfer_authority_pubkey, true),
+        AccountMeta::new(source_pubkey, false),
+        AccountMeta::new(swap_source_pubkey, false),
+        AccountMeta::new_readonly(source_mint_info, false),
+        AccountMeta::new(swap_destination_pubkey, false),
+        AccountMeta::new(destination_pubkey, false),
+        AccountMeta::new_readonly(destination_mint_info, false),
+        AccountMeta::new(reward_token_pubkey, false),
+        AccountMeta::new(source_reward_token_pubkey, false),
+        AccountMeta::new(admin_fee_destination_pubkey, false),
+        AccountMeta::new_readonly(spl_token::id(), false),
+    ];
+
+    if let Some(exchange_rate_pubkey) = exchange_rate_pubkey {
+        accounts.push(AccountMeta::new_readonly(exchange_rate_pubkey, false));
+    }
+
+    if let Some(treasury_token_pubkey) = treasury_token_pubkey {
+        accounts.push(AccountMeta::new(treasury_token_pubkey, false));
+    }
+
+    if has_output_owner {
+        accounts.push(AccountMeta::new_readonly(sysvar::instructions::id(), false));
+    }
+
+    if let Some(user_referrer_data_pubkey) = user_referrer_data_pubkey {
+        accounts.extend_from_slice(&[
+            AccountMeta::new_readonly(user_referrer_data_pubkey, false),
+            AccountMeta::new(referrer_token_pubkey.unwrap(), false),
+        ]);
+    }
+
+    if let Some(referrer_stats_pubkey) = referrer_stats_pubkey {
+        accounts.push(AccountMeta::new(referrer_stats_pubkey, false));
+    }
+
+    Ok(Instruction {
+        program_id,
+        accounts,
+        data,
+    })
+}
+
+/// Creates a 'stable_swap_v2' instruction.
+pub fn stable_swap_v2(
+    program_id: Pubkey,
+    config_pubkey: Pubkey,
+    swap_pubkey: Pubkey,
+    market_authority_pubkey: Pubkey,
+    swap_authority_pubkey: Pubkey,
+    user_transfer_authority_pubkey: Pubkey,
+    source_pubkey: Pubkey,
+    swap_source_pubkey: Pubkey,
+    swap_destination_pubkey: Pubkey,
+    destination_pubkey: Pubkey,
+    reward_token_pubkey: Pubkey,
+    source_reward_token_pubkey: Pubkey,
+    admin_fee_destination_pubkey: Pubkey,
+    exchange_rate_pubkey: Option<Pubkey>,
+    treasury_token_pubkey: Option<Pubkey>,
+    user_referrer_data_pubkey: Option<Pubkey>,
+    referrer_token_pubkey: Option<Pubkey>,
+    referrer_stats_pubkey: Option<Pubkey>,
+    swap_data: SwapData,
+) -> Result<Instruction, ProgramError> {
+    let has_output_owner = swap_data.output_owner.is_some();
+    let data = StableSwapInstruction::SwapV2(swap_data).pack();
+
+    let mut accounts = vec![
+        AccountMeta::new_readonly(config_pubkey, false),
+        AccountMeta::new(swap_pubkey, false),
+        AccountMeta::new_readonly(market_authority_pubkey, false),
+        AccountMeta::new_readonly(swap_authority_pubkey, false),
+        AccountMeta::new_readonly(user_transfer_authority_pubkey, true),
+        AccountMeta::new(source_pubkey, false),
+        AccountMeta::new(swap_source_pubkey, false),
+        AccountMeta::new(swap_destination_pubkey, false),
+        AccountMeta::new(destination_pubkey, false),
+        AccountMeta::new(reward_token_pubkey, false),
+        AccountMeta::new(source_reward_token_pubkey, false),
+        AccountMeta::new(admin_fee_destination_pubkey, false),
+        AccountMeta::new_readonly(spl_token::id(), false),
+    ];
+
+    if let Some(exchange_rate_pubkey) = exchange_rate_pubkey {
+        accounts.push(AccountMeta::new_readonly(exchange_rate_pubkey, false));
+    }
+
+    if let Some(treasury_token_pubkey) = treasury_token_pubkey {
+        accounts.push(AccountMeta::new(treasury_token_pubkey, false));
+    }
+
+    if has_output_owner {
+        accounts.push(AccountMeta::new_readonly(sysvar::instructions::id(), false));
+    }
+
+    if let Some(user_referrer_data_pubkey) = user_referrer_data_pubkey {
+        accounts.extend_from_slice(&[
+            AccountMeta::new_readonly(user_referrer_data_pubkey, false),
+            AccountMeta::new(referrer_token_pubkey.unwrap(), false),
+        ]);
+    }
+
+    if let Some(referrer_stats_pubkey) = referrer_stats_pubkey {
+        accounts.push(AccountMeta::new(referrer_stats_pubkey, false));
+    }
+
+    Ok(Instruction {
+        program_id,
+        accounts,
+        data,
+    })
+}
+
+/// Creates a 'stable_deposit' instruction.
+pub fn stable_deposit(
+    program_id: Pubkey,
+    swap_pubkey: Pubkey,
+    authority_pubkey: Pubkey,
+    user_transfer_authority_pubkey: Pubkey,
+    deposit_token_a_pubkey: Pubkey,
+    deposit_token_b_pubkey: Pubkey,
+    swap_token_a_pubkey: Pubkey,
+    swap_token_b_pubkey: Pubkey,
+    pool_mint_pubkey: Pubkey,
+    destination_pubkey: Pubkey,
+    deposit_data: DepositData,
+) -> Result<Instruction, ProgramError> {
+    let data = StableSwapInstruction::Deposit(deposit_data).pack();
+
+    let accounts = vec![
+        AccountMeta::new(swap_pubkey, false),
+        AccountMeta::new_readonly(authority_pubkey, false),
+        AccountMeta::new_readonly(user_transfer_authority_pubkey, true),
+        AccountMeta::new(deposit_token_a_pubkey, false),
+        AccountMeta::new(deposit_token_b_pubkey, false),
+        AccountMeta::new(swap_token_a_pubkey, false),
+        AccountMeta::new(swap_token_b_pubkey, false),
+        AccountMeta::new(pool_mint_pubkey, false),
+        AccountMeta::new(destination_pubkey, false),
+        AccountMeta::new_readonly(spl_token::id(), false),
+        AccountMeta::new_readonly(spl_token::id(), false),
+        AccountMeta::new_readonly(spl_token::id(), false),
+    ];
+
+    Ok(Instruction {
+        program_id,
+        accounts,
+        data,
+    })
+}
+
+/// Creates a 'stable_withdraw' instruction.
+pub fn stable_withdraw(
+    program_id: Pubkey,
+    swap_pubkey: Pubkey,
+    authority_pubkey: Pubkey,
+    user_transfer_authority_pubkey: Pubkey,
+    pool_mint_pubkey: Pubkey,
+    source_pubkey: Pubkey,
+    swap_token_a_pubkey: Pubkey,
+    swap_token_b_pubkey: Pubkey,
+    destination_token_a_pubkey: Pubkey,
+    destination_token_b_pubkey: Pubkey,
+    admin_fee_a_pubkey: Pubkey,
+    admin_fee_b_pubkey: Pubkey,
+    withdraw_data: WithdrawData,
+) -> Result<Instruction, ProgramError> {
+    let data = StableSwapInstruction::Withdraw(withdraw_data).pack();
+
+    let accounts = vec![
+        AccountMeta::new(swap_pubkey, false),
+        AccountMeta::new_readonly(authority_pubkey, false),
+        AccountMeta::new_readonly(user_transfer_authority_pubkey, true),
+        AccountMeta::new(pool_mint_pubkey, false),
+        AccountMeta::new(source_pubkey, false),
+        AccountMeta::new(swap_token_a_pubkey, false),
+        AccountMeta::new(swap_token_b_pubkey, false),
+        AccountMeta::new(destination_token_a_pubkey, false),
+        AccountMeta::new(destination_token_b_pubkey, false),
+        AccountMeta::new(admin_fee_a_pubkey, false),
+        AccountMeta::new(admin_fee_b_pubkey, false),
+        AccountMeta::new_readonly(spl_token::id(), false),
+    ];
+
+    Ok(Instruction {
+        program_id,
+        accounts,
+        data,
+    })
+}
+
+/// Creates a 'stable_deposit_one' instruction.
+pub fn stable_deposit_one(
+    program_id: Pubkey,
+    config_pubkey: Pubkey,
+    swap_pubkey: Pubkey,
+    authority_pubkey: Pubkey,
+    user_transfer_authority_pubkey: Pubkey,
+    source_pubkey: Pubkey,
+    swap_token_a_pubkey: Pubkey,
+    swap_token_b_pubkey: Pubkey,
+    admin_fee_destination_pubkey: Pubkey,
+    pool_mint_pubkey: Pubkey,
+    destination_pubkey: Pubkey,
+    exchange_rate_pubkey: Option<Pubkey>,
+    deposit_data: DepositOneData,
+) -> Result<Instruction, ProgramError> {
+    let data = StableSwapInstruction::DepositOne(deposit_data).pack();
+
+    let mut accounts = vec![
+        AccountMeta::new_readonly(config_pubkey, false),
+        AccountMeta::new(swap_pubkey, false),
+        AccountMeta::new_readonly(authority_pubkey, false),
+        AccountMeta::new_readonly(user_transfer_authority_pubkey, true),
+        AccountMeta::new(source_pubkey, false),
+        AccountMeta::new(swap_token_a_pubkey, false),
+        AccountMeta::new(swap_token_b_pubkey, false),
+        AccountMeta::new(admin_fee_destination_pubkey, false),
+        AccountMeta::new(pool_mint_pubkey, false),
+        AccountMeta::new(destination_pubkey, false),
+    ];
+    if let Some(exchange_rate_pubkey) = exchange_rate_pubkey {
+        accounts.push(AccountMeta::new_readonly(exchange_rate_pubkey, false));
+    }
+    accounts.push(AccountMeta::new_readonly(spl_token::id(), false));
+
+    Ok(Instruction {
+        program_id,
+        accounts,
+        data,
+    })
+}
+
+/// Creates a 'stable_withdraw_one' instruction.
+pub fn stable_withdraw_one(
+    program_id: Pubkey,
+    config_pubkey: Pubkey,
+    swap_pubkey: Pubkey,
+    authority_pubkey: Pubkey,
+    user_transfer_authority_pubkey: Pubkey,
+    pool_mint_pubkey: Pubkey,
+    source_pubkey: Pubkey,
+    swap_token_a_pubkey: Pubkey,
+    swap_token_b_pubkey: Pubkey,
+    destination_pubkey: Pubkey,
+    admin_fee_destination_pubkey: Pubkey,
+    exchange_rate_pubkey: Option<Pubkey>,
+    withdraw_data: WithdrawOneData,
+) -> Result<Instruction, ProgramError> {
+    let data = StableSwapInstruction::WithdrawOne(withdraw_data).pack();
+
+    let mut accounts = vec![
+        AccountMeta::new_readonly(config_pubkey, false),
+        AccountMeta::new(swap_pubkey, false),
+        AccountMeta::new_readonly(authority_pubkey, false),
+        AccountMeta::new_readonly(user_transfer_authority_pubkey, true),
+        AccountMeta::new(pool_mint_pubkey, false),
+        AccountMeta::new(source_pubkey, false),
+        AccountMeta::new(swap_token_a_pubkey, false),
+        AccountMeta::new(swap_token_b_pubkey, false),
+        AccountMeta::new(destination_pubkey, false),
+        AccountMeta::new(admin_fee_destination_pubkey, false),
+    ];
+    if let Some(exchange_rate_pubkey) = exchange_rate_pubkey {
+        accounts.push(AccountMeta::new_readonly(exchange_rate_pubkey, false));
+    }
+    accounts.push(AccountMeta::new_readonly(spl_token::id(), false));
+
+    Ok(Instruction {
+        program_id,
+        accounts,
+        data,
+    })
+}
+
+/// Creates a 'tri_initialize' instruction.
+pub fn tri_initialize(
+    program_id: Pubkey,
+    config_pubkey: Pubkey,
+    tri_swap_pubkey: Pubkey,
+    authority_pubkey: Pubkey,
+    admin_fee_pubkeys: [Pubkey; TRI_POOL_TOKENS],
+    token_pubkeys: [Pubkey; TRI_POOL_TOKENS],
+    pool_mint_pubkey: Pubkey,
+    destination_pubkey: Pubkey,
+    admin_pubkey: Pubkey,
+    init_data: TriInitializeData,
+) -> Result<Instruction, ProgramError> {
+    let data = StableSwapInstruction::TriInitialize(init_data).pack();
+
+    let mut accounts = vec![
+        AccountMeta::new_readonly(config_pubkey, false),
+        AccountMeta::new(tri_swap_pubkey, true),
+        AccountMeta::new_readonly(authority_pubkey, false),
+    ];
+    for admin_fee_pubkey in admin_fee_pubkeys.iter() {
+        accounts.push(AccountMeta::new_readonly(*admin_fee_pubkey, false));
+    }
+    for token_pubkey in token_pubkeys.iter() {
+        accounts.push(AccountMeta::new_readonly(*token_pubkey, false));
+    }
+    accounts.extend(vec![
+        AccountMeta::new(pool_mint_pubkey, false),
+        AccountMeta::new(destination_pubkey, false),
+        AccountMeta::new_readonly(admin_pubkey, true),
+        AccountMeta::new_readonly(rent::id(), false),
+        AccountMeta::new_readonly(spl_token::id(), false),
+    ]);
+
+    Ok(Instruction {
+        program_id,
+        accounts,
+        data,
+    })
+}
+
+/// Creates a 'tri_swap' instruction.
+pub fn tri_swap(
+    program_id: Pubkey,
+    config_pubkey: Pubkey,
+    tri_swap_pubkey: Pubkey,
+    authority_pubkey: Pubkey,
+    user_transfer_authority_pubkey: Pubkey,
+    source_pubkey: Pubkey,
+    swap_source_pubkey: Pubkey,
+    swap_destination_pubkey: Pubkey,
+    destination_pubkey: Pubkey,
+    admin_fee_destination_pubkey: Pubkey,
+    treasury_token_pubkey: Option<Pubkey>,
+    swap_data: TriSwapData,
+) -> Result<Instruction, ProgramError> {
+    let data = StableSwapInstruction::TriSwap(swap_data).pack();
+
+    let mut accounts = vec![
+        AccountMeta::new_readonly(config_pubkey, false),
+        AccountMeta::new(tri_swap_pubkey, false),
+        AccountMeta::new_readonly(authority_pubkey, false),
+        AccountMeta::new_readonly(user_transfer_authority_pubkey, true),
+        AccountMeta::new(source_pubkey, false),
+        AccountMeta::new(swap_source_pubkey, false),
+        AccountMeta::new(swap_destination_pubkey, false),
+        AccountMeta::new(destination_pubkey, false),
+        AccountMeta::new(admin_fee_destination_pubkey, false),
+        AccountMeta::new_readonly(spl_token::id(), false),
+    ];
+    if let Some(treasury_token_pubkey) = treasury_token_pubkey {
+        accounts.push(AccountMeta::new(treasury_token_pubkey, false));
+    }
+
+    Ok(Instruction {
+        program_id,
+        accounts,
+        data,
+    })
+}
+
+/// Creates a 'tri_deposit' instruction.
+pub fn tri_deposit(
+    program_id: Pubkey,
+    config_pubkey: Pubkey,
+    tri_swap_pubkey: Pubkey,
+    authority_pubkey: Pubkey,
+    user_transfer_authority_pubkey: Pubkey,
+    source_pubkeys: [Pubkey; TRI_POOL_TOKENS],
+    token_pubkeys: [Pubkey; TRI_POOL_TOKENS],
+    pool_mint_pubkey: Pubkey,
+    destination_pubkey: Pubkey,
+    deposit_data: TriDepositData,
+) -> Result<Instruction, ProgramError> {
+    let data = StableSwapInstruction::TriDeposit(deposit_data).pack();
+
+    let mut accounts = vec![
+        AccountMeta::new_readonly(config_pubkey, false),
+        AccountMeta::new(tri_swap_pubkey, false),
+        AccountMeta::new_readonly(authority_pubkey, false),
+        AccountMeta::new_readonly(user_transfer_authority_pubkey, true),
+    ];
+    for pubkey in source_pubkeys.iter().chain(token_pubkeys.iter()) {
+        accounts.push(AccountMeta::new(*pubkey, false));
+    }
+    accounts.extend(vec![
+        AccountMeta::new(pool_mint_pubkey, false),
+        AccountMeta::new(destination_pubkey, false),
+        AccountMeta::new_readonly(spl_token::id(), false),
+    ]);
+
+    Ok(Instruction {
+        program_id,
+        accounts,
+        data,
+    })
+}
+
+/// Creates a 'tri_withdraw' instruction.
+pub fn tri_withdraw(
+    program_id: Pubkey,
+    config_pubkey: Pubkey,
+    tri_swap_pubkey: Pubkey,
+    authority_pubkey: Pubkey,
+    user_transfer_authority_pubkey: Pubkey,
+    pool_mint_pubkey: Pubkey,
+    source_pubkey: Pubkey,
+    token_pubkeys: [Pubkey; TRI_POOL_TOKENS],
+    destination_pubkeys: [Pubkey; TRI_POOL_TOKENS],
+    admin_fee_pubkeys: [Pubkey; TRI_POOL_TOKENS],
+    withdraw_data: TriWithdrawData,
+) -> Result<Instruction, ProgramError> {
+    let data = StableSwapInstruction::TriWithdraw(withdraw_data).pack();
+
+    let mut accounts = vec![
+        AccountMeta::new_readonly(config_pubkey, false),
+        AccountMeta::new(tri_swap_pubkey, false),
+        AccountMeta::new_readonly(authority_pubkey, false),
+        AccountMeta::new_readonly(user_transfer_authority_pubkey, true),
+        AccountMeta::new(pool_mint_pubkey, false),
+        AccountMeta::new(source_pubkey, false),
+    ];
+    for pubkey in token_pubkeys
+        .iter()
+        .chain(destination_pubkeys.iter())
+        .chain(admin_fee_pubkeys.iter())
+    {
+        accounts.push(AccountMeta::new(*pubkey, false));
+    }
+    accounts.push(AccountMeta::new_readonly(spl_token::id(), false));
+
+    Ok(Instruction {
+        program_id,
+        accounts,
+        data,
+    })
+}
+
+/// Max number of pools traded by a route
+pub const MAX_ROUTE_HOPS: usize = 3;
+
+/// Steps of the search splitting the input of a smart swap between its pools, bounded by
+/// the compute budget of the curve quotes
+pub const SMART_SWAP_SEARCH_STEPS: usize = 12;
+
+/// Instructions trading across several pools
+#[repr(C)]
+#[derive(Debug, PartialEq)]
+pub enum RouterInstruction {
+    ///   Swap through up to 3 normal or stable pools in sequence, e.g. A -> B -> C.
+    ///   The output of each pool goes straight into the next pool, only the output of
+    ///   the last pool is checked against `minimum_amount_out`.
+    ///
+    ///   0. `[]` market config.
+    ///   1. `[]` $market_authority to issue deltafi token.
+    ///   2. `[signer]` $user_transfer_authority
+    ///   3. `[writable]` SOURCE account of the first pool, transferable by $user_transfer_authority.
+    ///   4. `[writable]` DESTINATION account of the last pool owned by user.
+    ///   5. `[writable]` rewards(DELTAFI) token account owned by user.
+    ///   6. `[writable]` rewards(DELTAFI) source deltafi token account to issue reward.
+    ///   7. `[]` token program id.
+    ///   8. `[]` optional: fee discount account of $user_transfer_authority.
+    ///   9. `[]` optional: fee discount tiers of the config, required with 8.
+    ///   10. `[]` optional: user referrer data account of the SOURCE owner.
+    ///   11. `[writable]` optional: referrer token account, required with 10.
+    ///   For each pool of the route, charging the fees of its own swap instruction:
+    ///   0. `[writable]` token-swap.
+    ///   1. `[]` $swap_authority.
+    ///   2. `[writable]` (base|quote) token account to swap INTO.
+    ///   3. `[writable]` (base|quote) token account to swap FROM.
+    ///   4. `[writable]` (base|quote) admin fee account. Must have same mint as the token swapped FROM.
+    ///   5. `[writable]` optional: treasury token account of the mint swapped FROM owned by the
+    ///      config treasury, required while the protocol fee switch is on.
+    ///   6. `[writable]` optional: referrer token account of the mint swapped FROM owned by the
+    ///      referrer, required for a normal pool paying a referral fee on a referred route.
+    ///   7. ..7+N `[]` price accounts: pyth price of base and quote tokens, serum market, bids and
+    ///      asks for a normal pool, the exchange rate account for a stable pool with an exchange
+    ///      rate adapter, none otherwise.
+    Route(SwapData),
+
+    ///   Swap through the normal pool and the stable pool of the same pair at once, e.g. the
+    ///   USDC/USDT pools. The input is split between the two pools to get the most output
+    ///   out of their curves, only the total output is checked against `minimum_amount_out`.
+    ///
+    ///   0. `[]` market config.
+    ///   1. `[]` $market_authority to issue deltafi token.
+    ///   2. `[signer]` $user_transfer_authority
+    ///   3. `[writable]` SOURCE account, transferable by $user_transfer_authority.
+    ///   4. `[writable]` DESTINATION account owned by user.
+    ///   5. `[writable]` rewards(DELTAFI) token account owned by user.
+    ///   6. `[writable]` rewards(DELTAFI) source deltafi token account to issue reward.
+    ///   7. `[]` token program id.
+    ///   8. `[]` optional: fee discount account of $user_transfer_authority.
+    ///   9. `[]` optional: fee discount tiers of the config, required with 8.
+    ///   10. `[]` optional: user referrer data account of the SOURCE owner.
+    ///   11. `[writable]` optional: referrer token account, required with 10.
+    ///   For the normal pool, then the stable pool, the pool accounts of
+    ///   [Route](enum.RouterInstruction.html#variant.Route).
+    SmartSwap(SwapData),
+}
+
+impl RouterInstruction {
+    /// Unpacks a byte buffer into a [RouterInstruction](enum.RouterInstruction.html).
+    pub fn unpack(input: &[u8]) -> Result<Self, ProgramError> {
+        let (&tag, rest) = input
+            .split_first()
+            .ok_or(SwapError::InstructionUnpackError)?;
+        Ok(match tag {
+            30 => {
+                let (amount_in, rest) = unpack_u64(rest)?;
+                let (minimum_amount_out, rest) = unpack_u64(rest)?;
+                let (deadline_slot, output_owner) = unpack_swap_options(rest)?;
+                Self::Route(SwapData {
+                    amount_in,
+                    minimum_amount_out,
+                    deadline_slot,
+                    output_owner,
+                })
+            }
+            29 => {
+                let (amount_in, rest) = unpack_u64(rest)?;
+                let (minimum_amount_out, rest) = unpack_u64(rest)?;
+                let (deadline_slot, output_owner) = unpack_swap_options(rest)?;
+                Self::SmartSwap(SwapData {
+                    amount_in,
+                    minimum_amount_out,
+                    deadline_slot,
+                    output_owner,
+                })
+            }
+            _ => return Err(SwapError::InvalidInstruction.into()),
+        })
+    }
+
+    /// Packs a [RouterInstruction](enum.RouterInstruction.html) into a byte buffer.
+    pub fn pack(&self) -> Vec<u8> {
+        let mut buf = Vec::with_capacity(size_of::<Self>());
+        match *self {
+            Self::Route(SwapData {
+                amount_in,
+                minimum_amount_out,
+                deadline_slot,
+                output_owner,
+            }) => {
+                buf.push(30);
+                buf.extend_from_slice(&amount_in.to_le_bytes());
+                buf.extend_from_slice(&minimum_amount_out.to_le_bytes());
+                pack_swap_options(deadline_slot, output_owner, &mut buf);
+            }
+            Self::SmartSwap(SwapData {
+                amount_in,
+                minimum_amount_out,
+                deadline_slot,
+                output_owner,
+            }) => {
+                buf.push(29);
+                buf.extend_from_slice(&amount_in.to_le_bytes());
+                buf.extend_from_slice(&minimum_amount_out.to_le_bytes());
+                pack_swap_options(deadline_slot, output_owner, &mut buf);
+            }
+        }
+        buf
+    }
+}
+
+/// Accounts of a pool traded by a route
+#[derive(Clone, Debug, PartialEq)]
+pub struct RouteHop {
+    /// Token swap of the pool
+    pub swap_pubkey: Pubkey,
+    /// Swap authority of the pool
+    pub swap_authority_pubkey: Pubkey,
+    /// Pool token account to swap INTO
+    pub swap_source_pubkey: Pubkey,
+    /// Pool token account to swap FROM
+    pub swap_destination_pubkey: Pubkey,
+    /// Admin fee account with the mint of the token swapped FROM
+    pub admin_fee_destination_pubkey: Pubkey,
+    /// Treasury token account with the mint of the token swapped FROM, while the protocol fee
+    /// switch is on
+    pub treasury_token_pubkey: Option<Pubkey>,
+    /// Referrer token account with the mint of the token swapped FROM, on a referred route
+    /// through a normal pool paying a referral fee
+    pub referrer_fee_token_pubkey: Option<Pubkey>,
+    /// Price accounts of the pool, see [RouterInstruction::Route](enum.RouterInstruction.html)
+    pub price_pubkeys: Vec<Pubkey>,
+}
+
+/// Creates a 'route' instruction.
+pub fn route(
+    program_id: Pubkey,
+    config_pubkey: Pubkey,
+    market_authority_pubkey: Pubkey,
+    user_transfer_authority_pubkey: Pubkey,
+    source_pubkey: Pubkey,
+    destination_pubkey: Pubkey,
+    reward_token_pubkey: Pubkey,
+    source_reward_token_pubkey: Pubkey,
+    fee_discount_pubkey: Option<Pubkey>,
+    fee_discount_tiers_pubkey: Option<Pubkey>,
+    user_referrer_data_pubkey: Option<Pubkey>,
+    referrer_token_pubkey: Option<Pubkey>,
+    hops: &[RouteHop],
+    swap_data: SwapData,
+) -> Result<Instruction, ProgramError> {
+    if hops.is_empty() || hops.len() > MAX_ROUTE_HOPS {
+        return Err(SwapError::InvalidInput.into());
+    }
+    let data = RouterInstruction::Route(swap_data).pack();
+
+    let accounts = router_accounts(
+        config_pubkey,
+        market_authority_pubkey,
+        user_transfer_authority_pubkey,
+        source_pubkey,
+        destination_pubkey,
+        reward_token_pubkey,
+        source_reward_token_pubkey,
+        fee_discount_pubkey,
+        fee_discount_tiers_pubkey,
+        user_referrer_data_pubkey,
+        referrer_token_pubkey,
+        hops,
+    );
+
+    Ok(Instruction {
+        program_id,
+        accounts,
+        data,
+    })
+}
+
+/// Creates a 'smart_swap' instruction.
+pub fn smart_swap(
+    program_id: Pubkey,
+    config_pubkey: Pubkey,
+    market_authority_pubkey: Pubkey,
+    user_transfer_authority_pubkey: Pubkey,
+    source_pubkey: Pubkey,
+    destination_pubkey: Pubkey,
+    reward_token_pubkey: Pubkey,
+    source_reward_token_pubkey: Pubkey,
+    fee_discount_pubkey: Option<Pubkey>,
+    fee_discount_tiers_pubkey: Option<Pubkey>,
+    user_referrer_data_pubkey: Option<Pubkey>,
+    referrer_token_pubkey: Option<Pubkey>,
+    normal_pool: RouteHop,
+    stable_pool: RouteHop,
+    swap_data: SwapData,
+) -> Result<Instruction, ProgramError> {
+    if normal_pool.swap_pubkey == stable_pool.swap_pubkey {
+        return Err(SwapError::InvalidInput.into());
+    }
+    let data = RouterInstruction::SmartSwap(swap_data).pack();
+
+    let accounts = router_accounts(
+        config_pubkey,
+        market_authority_pubkey,
+        user_transfer_authority_pubkey,
+        source_pubkey,
+        destination_pubkey,
+        reward_token_pubkey,
+        source_reward_token_pubkey,
+        fee_discount_pubkey,
+        fee_discount_tiers_pubkey,
+        user_referrer_data_pubkey,
+        referrer_token_pubkey,
+        &[normal_pool, stable_pool],
+    );
+
+    Ok(Instruction {
+        program_id,
+        accounts,
+        data,
+    })
+}
+
+fn router_accounts(
+    config_pubkey: Pubkey,
+    market_authority_pubkey: Pubkey,
+    user_transfer_authority_pubkey: Pubkey,
+    source_pubkey: Pubkey,
+    destination_pubkey: Pubkey,
+    reward_token_pubkey: Pubkey,
+    source_reward_token_pubkey: Pubkey,
+    fee_discount_pubkey: Option<Pubkey>,
+    fee_discount_tiers_pubkey: Option<Pubkey>,
+    user_referrer_data_pubkey: Option<Pubkey>,
+    referrer_token_pubkey: Option<Pubkey>,
+    hops: &[RouteHop],
+) -> Vec<AccountMeta> {
+    let mut accounts = vec![
+        AccountMeta::new_readonly(config_pubkey, false),
+        AccountMeta::new_readonly(market_authority_pubkey, false),
+        AccountMeta::new_readonly(user_transfer_authority_pubkey, true),
+        AccountMeta::new(source_pubkey, false),
+        AccountMeta::new(destination_pubkey, false),
+        AccountMeta::new(reward_token_pubkey, false),
+        AccountMeta::new(source_reward_token_pubkey, false),
+        AccountMeta::new_readonly(spl_token::id(), false),
+    ];
+    if let Some(fee_discount_pubkey) = fee_discount_pubkey {
+        accounts.extend_from_slice(&[
+            AccountMeta::new_readonly(fee_discount_pubkey, false),
+            AccountMeta::new_readonly(fee_discount_tiers_pubkey.unwrap(), false),
+        ]);
+    }
+    if let Some(user_referrer_data_pubkey) = user_referrer_data_pubkey {
+        accounts.extend_from_slice(&[
+            AccountMeta::new_readonly(user_referrer_data_pubkey, false),
+            AccountMeta::new(referrer_token_pubkey.unwrap(), false),
+        ]);
+    }
+    for hop in hops {
+        accounts.extend_from_slice(&[
+            AccountMeta::new(hop.swap_pubkey, false),
+            AccountMeta::new_readonly(hop.swap_authority_pubkey, false),
+            AccountMeta::new(hop.swap_source_pubkey, false),
+            AccountMeta::new(hop.swap_destination_pubkey, false),
+            AccountMeta::new(hop.admin_fee_destination_pubkey, false),
+        ]);
+        if let Some(treasury_token_pubkey) = hop.treasury_token_pubkey {
+            accounts.push(AccountMeta::new(treasury_token_pubkey, false));
+        }
+        if let Some(referrer_fee_token_pubkey) = hop.referrer_fee_token_pubkey {
+            accounts.push(AccountMeta::new(referrer_fee_token_pubkey, false));
+        }
+        for price_pubkey in &hop.price_pubkeys {
+            accounts.push(AccountMeta::new_readonly(*price_pubkey, false));
+        }
+    }
+    accounts
+}
+
+/// FARM INSTRUNCTION DATA
+/// Initialize instruction data
+#[repr(C)]
+#[derive(Debug, PartialEq)]
+pub struct FarmInitializeData {
+    /// Withdraw fee numerator
+    pub fee_numerator: u64,
+    /// Withdraw fee denominator
+    pub fee_denominator: u64,
+    /// Rewards numerator
+    pub rewards_numerator: u64,
+    /// Rewards denominator
+    pub rewards_denominator: u64,
+    /// Bump seed
+    pub bump_seed: u8,
+}
+
+/// Farm deposit instruction data
+#[cfg_attr(feature = "fuzz", derive(Arbitrary, Clone))]
+#[repr(C)]
+#[derive(Debug, PartialEq)]
+pub struct FarmDepositData {
+    /// Amount to stake
+    pub amount: u64,
+    /// Index in `FARM_LOCK_TIERS` to lock the position in, 0 does not lock, only packed when set
+    pub lock_tier: u8,
+}
+
+/// Farm withdraw instruction data
+#[cfg_attr(feature = "fuzz", derive(Arbitrary, Clone))]
+#[repr(C)]
+#[derive(Debug, PartialEq)]
+pub struct FarmWithdrawData {
+    /// Amount to withdraw
+    pub amount: u64,
+}
+
+/// Farm fund reward token instruction data
+#[repr(C)]
+#[derive(Debug, PartialEq)]
+pub struct FarmFundRewardTokenData {
+    /// Index of the reward token in the farm reward tokens
+    pub index: u8,
+    /// Amount of reward tokens to fund
+    pub amount: u64,
+}
+
+/// Farm compound instruction data
+#[repr(C)]
+#[derive(Debug, PartialEq)]
+pub struct FarmCompoundData {
+    /// Minimum pool tokens minted from the rewards and staked
+    pub min_mint_amount: u64,
+}
+
+/// Farm set compound delegate instruction data
+#[repr(C)]
+#[derive(Debug, PartialEq)]
+pub struct FarmCompoundDelegateData {
+    /// Authority allowed to compound besides the owner, default pubkey to remove it
+    pub compound_delegate: Pubkey,
+}
+
+/// Farm claim multi instruction data
+#[repr(C)]
+#[derive(Debug, PartialEq)]
+pub struct FarmClaimMultiData {
+    /// Number of farm pool and farm user pairs to claim
+    pub farm_count: u8,
+}
+
+/// Farm reward split instruction data
+#[repr(C)]
+#[derive(Debug, PartialEq)]
+pub struct FarmRewardSplitData {
+    /// Percentage of the claimed rewards for each recipient, 0 for unused slots
+    pub percentages: [u8; MAX_REWARD_RECIPIENTS],
+}
+
+/// Instructions supported by the pool FarmInfo program.
+#[repr(C)]
+#[derive(Debug, PartialEq)]
+pub enum FarmInstruction {
+    /// Intialize farm info
+    Initialize(FarmInitializeData),
+    /// Initialize farm user
+    ///
+    ///   0. `[]` market config.
+    ///   1. `[]` farm pool, writable if the farm user registry is enabled.
+    ///   2. `[writable]` farm user, derived from the owner and the farm pool.
+    ///   3. `[signer]` farm user owner.
+    ///   4. `[]` Rent sysvar.
+    ///   Required if the farm user registry of the farm pool is enabled:
+    ///   5. `[writable]` registry page of the farm user, derived from the farm pool and
+    ///      `farm_user_count / FARM_USER_REGISTRY_PAGE_SIZE`
+    ///   6. `[writable, signer]` payer of the registry page, only charged when a page is opened
+    ///   7. `[]` System program
+    InitializeFarmUser,
+    /// Farm claim
+    Claim,
+    /// Refresh the farm accounting, permissionless: the farm emission accumulator is brought to
+    /// the current slot and the rewards of the farm users accrued, no tokens are transferred.
+    ///
+    ///   0. `[]` market config.
+    ///   1. `[writable]` farm pool.
+    ///   2. ..2+N `[writable]` N farm users of the farm pool.
+    Refresh,
+    /// Farm deposit, followed by the writable farm reward tokens and farm user reward tokens when
+    /// the farm emits partner reward tokens
+    Deposit(FarmDepositData),
+    /// Farm withdraw, followed by the writable farm reward tokens and farm user reward tokens when
+    /// the farm emits partner reward tokens, then by the writable admin token account of the pool
+    /// mint receiving the withdraw fee when the farm has a fee
+    Withdraw(FarmWithdrawData),
+    /// Set the recipients sharing the farm user rewards at claim, no recipients removes the split
+    ///
+    ///   0. `[]` market config.
+    ///   1. `[]` farm pool.
+    ///   2. `[writable]` farm user.
+    ///   3. `[signer]` farm owner.
+    ///   4. `[writable]` reward split account, owned by the program.
+    ///   5. `[]` rent sysvar.
+    ///   6. ..6+N `[]` N reward token accounts of the recipients, up to 4.
+    SetRewardSplit(FarmRewardSplitData),
+    /// Checkpoint the rewards of farm users at the current APR, no tokens are transferred.
+    /// Cranked before the farm APR changes so the new APR is not applied retroactively.
+    ///
+    ///   0. `[]` market config.
+    ///   1. `[]` farm pool.
+    ///   2. ..2+N `[writable]` N farm users of the farm pool.
+    Checkpoint,
+    /// Fund a partner reward token of the farm, the funded tokens are emitted at its rate
+    ///
+    ///   0. `[]` market config.
+    ///   1. `[]` farm pool.
+    ///   2. `[writable]` farm reward tokens, derived from the farm pool.
+    ///   3. `[writable]` reward token vault.
+    ///   4. `[writable]` source token account of the reward token.
+    ///   5. `[signer]` user transfer authority.
+    ///   6. `[]` token program id.
+    FundRewardToken(FarmFundRewardTokenData),
+    /// Initialize the partner reward tokens owed to a farm user, required to deposit and
+    /// withdraw once the farm emits partner reward tokens
+    ///
+    ///   0. `[]` market config.
+    ///   1. `[]` farm pool.
+    ///   2. `[]` farm user.
+    ///   3. `[writable]` farm user reward tokens, derived from the farm user.
+    ///   4. `[writable, signer]` farm owner, pays for the account.
+    ///   5. `[]` rent sysvar.
+    ///   6. `[]` system program.
+    InitializeFarmUserRewardTokens,
+    /// Claim the partner reward tokens owed to a farm user
+    ///
+    ///   0. `[]` market config.
+    ///   1. `[]` farm pool.
+    ///   2. `[]` farm user.
+    ///   3. `[writable]` farm reward tokens, derived from the farm pool.
+    ///   4. `[writable]` farm user reward tokens, derived from the farm user.
+    ///   5. `[signer]` farm owner.
+    ///   6. `[]` farm authority.
+    ///   7. `[]` token program id.
+    ///   8. ..8+2N `[writable]` vault and destination token account of each of the N reward
+    ///      tokens in use, in the order of the farm reward tokens.
+    ClaimRewardTokens,
+    /// Compound the DELTAFI rewards owed to a farm user, signed by the farm owner or its
+    /// compound delegate. The rewards are deposited single sided into the swap of the farm pool
+    /// mint, which must have a DELTAFI side, and the minted pool tokens are staked into the
+    /// position. Rewards the pool does not take stay owed. Farm users with a reward split cannot
+    /// compound.
+    ///
+    ///   0. `[]` market config.
+    ///   1. `[writable]` farm pool.
+    ///   2. `[writable]` farm user.
+    ///   3. `[signer]` farm owner or compound delegate of the farm user.
+    ///   4. `[]` market authority.
+    ///   5. `[writable]` DELTAFI reward token account of the market authority.
+    ///   6. `[writable]` token-swap of the farm pool mint.
+    ///   7. `[]` swap authority.
+    ///   8. `[writable]` base token account of the swap.
+    ///   9. `[writable]` quote token account of the swap.
+    ///   10. `[writable]` admin fee account of the non-DELTAFI side of the swap.
+    ///   11. `[writable]` pool mint account.
+    ///   12. `[writable]` pool token account of the farm pool.
+    ///   13. .. `[]` market price accounts of the swap, as in `DepositOne` of its swap type.
+    ///   14. `[]` token program id.
+    ///   15. `[writable]` optional: farm reward tokens, then farm user reward tokens, required
+    ///       if the farm emits partner reward tokens.
+    Compound(FarmCompoundData),
+    /// Move the stake, lock and rewards of a farm user to the empty farm user of another owner
+    /// of the same farm, to migrate wallets without unstaking. Partner rewards accrued before
+    /// the transfer stay claimable by the source owner.
+    ///
+    ///   0. `[]` market config.
+    ///   1. `[]` farm pool.
+    ///   2. `[writable]` source farm user.
+    ///   3. `[signer]` source farm owner.
+    ///   4. `[writable]` destination farm user, initialized by its owner.
+    ///   5. `[writable]` optional: farm reward tokens, then source and destination farm user
+    ///      reward tokens, required if the farm emits partner reward tokens.
+    TransferPosition,
+    /// Claim the DELTAFI rewards of the farm users of an owner across several farms, paid in
+    /// one transfer from the claim source. Farm users with a reward split cannot claim here.
+    ///
+    ///   0. `[]` market config.
+    ///   1. `[signer]` farm owner.
+    ///   2. `[]` market authority.
+    ///   3. `[writable]` claim destination.
+    ///   4. `[writable]` claim source.
+    ///   5. `[]` token program id.
+    ///   6. ..6+2N `[]` farm pool, then `[writable]` farm user of each of the N farms.
+    ///   6+2N. `[]` optional: user referrer data.
+    ///   7+2N. `[writable]` optional: referrer token account.
+    ClaimMulti(FarmClaimMultiData),
+    /// Initialize a staking pool, a farm pool staking DELTAFI itself. Its users deposit,
+    /// withdraw and claim as in any farm pool.
+    ///
+    ///   0. `[]` market config.
+    ///   1. `[writable, signer]` staking pool.
+    ///   2. `[]` farm authority, derived from the staking pool.
+    ///   3. `[]` DELTAFI token account of the farm authority holding the stake.
+    ///   4. `[signer]` admin.
+    ///   5. `[]` rent sysvar.
+    InitializeStaking(FarmInitializeData),
+    /// Set the authority allowed to compound the farm user besides its owner, the default
+    /// pubkey removes it. Farm users created before the padding have no room for a delegate.
+    ///
+    ///   0. `[]` market config.
+    ///   1. `[]` farm pool.
+    ///   2. `[writable]` farm user.
+    ///   3. `[signer]` farm owner.
+    SetCompoundDelegate(FarmCompoundDelegateData),
+}
+
+impl FarmInstruction {
+    /// Unpacks a byte buffer into a [FarmInstruction](enum.FarmInstruction.html).
+    pub fn unpack(input: &[u8]) -> Result<Self, ProgramError> {
+        let (&tag, rest) = input
+            .split_first()
+            .ok_or(ProgramError::InvalidInstructionData)?;
+        Ok(match tag {
+            20 => Self::Initialize(unpack_farm_initialize_data(rest)?),
+            21 => Self::InitializeFarmUser,
+            22 => Self::Claim,
+            23 => Self::Refresh,
+            24 => {
+                let (amount, rest) = unpack_u64(rest)?;
+                let lock_tier = if rest.is_empty() {
+                    0
+                } else {
+                    unpack_u8(rest)?.0
+                };
+                Self::Deposit(FarmDepositData { amount, lock_tier })
+            }
+            25 => {
+                let (amount, _) = unpack_u64(rest)?;
+                Self::Withdraw(FarmWithdrawData { amount })
+            }
+            26 => {
+                let mut percentages = [0u8; MAX_REWARD_RECIPIENTS];
+                let mut rest = rest;
+                for percentage in percentages.iter_mut() {
+                    let (value, next) = unpack_u8(rest)?;
+                    *percentage = value;
+                    rest = next;
+                }
+                Self::SetRewardSplit(FarmRewardSplitData { percentages })
+            }
+            27 => Self::Checkpoint,
+            40 => {
+                let (index, rest) = unpack_u8(rest)?;
+                let (amount, _) = unpack_u64(rest)?;
+                Self::FundRewardToken(FarmFundRewardTokenData { index, amount })
+            }
+            41 => Self::InitializeFarmUserRewardTokens,
+            42 => Self::ClaimRewardTokens,
+            43 => {
+                let (min_mint_amount, _) = unpack_u64(rest)?;
+                Self::Compound(FarmCompoundData { min_mint_amount })
+            }
+            44 => Self::TransferPosition,
+            45 => {
+                let (farm_count, _) = unpack_u8(rest)?;
+                Self::ClaimMulti(FarmClaimMultiData { farm_count })
+            }
+            46 => Self::InitializeStaking(unpack_farm_initialize_data(rest)?),
+            60 => {
+                let (compound_delegate, _) = unpack_pubkey(rest)?;
+                Self::SetCompoundDelegate(FarmCompoundDelegateData { compound_delegate })
+            }
+            _ => return Err(ProgramError::InvalidInstructionData),
+        })
+    }
+
+    /// Packs a [FarmInstruction](enum.FarmInstruction.html) into a byte buffer
+    pub fn pack(&self) -> Vec<u8> {
+        let mut buf = Vec::with_capacity(size_of::<Self>());
+        match *self {
+            Self::Initialize(ref init_data) => {
+                buf.push(20);
+                pack_farm_initialize_data(init_data, &mut buf);
+            }
+            Self::InitializeFarmUser => {
+                buf.push(21);
+            }
+            Self::Claim => buf.push(22),
+            Self::Refresh => buf.push(23),
+            Self::Deposit(FarmDepositData { amount, lock_tier }) => {
+                buf.push(24);
+                buf.extend_from_slice(&amount.to_le_bytes());
+                if lock_tier != 0 {
+                    buf.push(lock_tier);
+                }
+            }
+            Self::Withdraw(FarmWithdrawData { amount }) => {
+                buf.push(25);
+                buf.extend_from_slice(&amount.to_le_bytes());
+            }
+            Self::SetRewardSplit(FarmRewardSplitData { percentages }) => {
+                buf.push(26);
+                buf.extend_from_slice(&percentages);
+            }
+            Self::Checkpoint => buf.push(27),
+            Self::FundRewardToken(FarmFundRewardTokenData { index, amount }) => {
+                buf.push(40);
+                buf.push(index);
+                buf.extend_from_slice(&amount.to_le_bytes());
+            }
+            Self::InitializeFarmUserRewardTokens => buf.push(41),
+            Self::ClaimRewardTokens => buf.push(42),
+            Self::Compound(FarmCompoundData { min_mint_amount }) => {
+                buf.push(43);
+                buf.extend_from_slice(&min_mint_amount.to_le_bytes());
+            }
+            Self::TransferPosition => buf.push(44),
+            Self::ClaimMulti(FarmClaimMultiData { farm_count }) => {
+                buf.push(45);
+                buf.push(farm_count);
+            }
+            Self::InitializeStaking(ref init_data) => {
+                buf.push(46);
+                pack_farm_initialize_data(init_data, &mut buf);
+            }
+            Self::SetCompoundDelegate(FarmCompoundDelegateData { compound_delegate }) => {
+                buf.push(60);
+                buf.extend_from_slice(compound_delegate.as_ref());
+            }
+        }
+
+        buf
+    }
+}
+
+/// Creates `FarmInitialize` instruction
+pub fn farm_initialize(
+    program_id: Pubkey,
+    config_info_pubkey: Pubkey,
+    swap_info_pubkey: Pubkey,
+    farm_pool_pubkey: Pubkey,
+    authority_pubkey: Pubkey,
+    pool_token_pubkey: Pubkey,
+    admin_pubkey: Pubkey,
+    init_data: FarmInitializeData,
+) -> Result<Instruction, ProgramError> {
+    let data = FarmInstruction::Initialize(init_data).pack();
+
+    let accounts = vec![
+        AccountMeta::new_readonly(config_info_pubkey, false),
+        AccountMeta::new_readonly(swap_info_pubkey, false),
+        AccountMeta::new(farm_pool_pubkey, true),
+        AccountMeta::new_readonly(authority_pubkey, false),
+        AccountMeta::new_readonly(pool_token_pubkey, false),
+        AccountMeta::new_readonly(admin_pubkey, true),
+        AccountMeta::new_readonly(rent::id(), false),
+    ];
+
+    Ok(Instruction {
+        program_id,
+        data,
+        accounts,
+    })
+}
+
+/// Creates `FarmInitializeStaking` instruction
+pub fn farm_initialize_staking(
+    program_id: Pubkey,
+    config_info_pubkey: Pubkey,
+    farm_pool_pubkey: Pubkey,
+    authority_pubkey: Pubkey,
+    pool_token_pubkey: Pubkey,
+    admin_pubkey: Pubkey,
+    init_data: FarmInitializeData,
+) -> Result<Instruction, ProgramError> {
+    let data = FarmInstruction::InitializeStaking(init_data).pack();
+
+    let accounts = vec![
+        AccountMeta::new_readonly(config_info_pubkey, false),
+        AccountMeta::new(farm_pool_pubkey, true),
+        AccountMeta::new_readonly(authority_pubkey, false),
+        AccountMeta::new_readonly(pool_token_pubkey, false),
+        AccountMeta::new_readonly(admin_pubkey, true),
+        AccountMeta::new_readonly(rent::id(), false),
+    ];
+
+    Ok(Instruction {
+        program_id,
+        data,
+        accounts,
+    })
+}
+
+/// Creates `FarmUserInitialize` instruction for a farm pool with the farm user registry
+pub fn farm_user_initialize_with_registry(
+    program_id: Pubkey,
+    config_info_pubkey: Pubkey,
+    farm_pool_pubkey: Pubkey,
+    farm_user_pubkey: Pubkey,
+    farm_owner_pubkey: Pubkey,
+    registry_pubkey: Pubkey,
+    payer_pubkey: Pubkey,
+) -> Result<Instruction, ProgramError> {
+    let data = FarmInstruction::InitializeFarmUser.pack();
+
+    let accounts = vec![
+        AccountMeta::new_readonly(config_info_pubkey, false),
+        AccountMeta::new(farm_pool_pubkey, false),
+        AccountMeta::new(farm_user_pubkey, false),
+        AccountMeta::new_readonly(farm_owner_pubkey, true),
+        AccountMeta::new_readonly(rent::id(), false),
+        AccountMeta::new(registry_pubkey, false),
+        AccountMeta::new(payer_pubkey, true),
+        AccountMeta::new_readonly(system_program::id(), false),
+    ];
+
+    Ok(Instruction {
+        program_id,
+        data,
+        accounts,
+    })
+}
+
+/// Creates `FarmUserInitialize` instruction
+pub fn farm_user_initialize(
+    program_id: Pubkey,
+    config_info_pubkey: Pubkey,
+    farm_pool_pubkey: Pubkey,
+    farm_user_pubkey: Pubkey,
+    farm_owner_pubkey: Pubkey,
+) -> Result<Instruction, ProgramError> {
+    let data = FarmInstruction::InitializeFarmUser.pack();
+
+    let accounts = vec![
+        AccountMeta::new_readonly(config_info_pubkey, false),
+        AccountMeta::new_readonly(farm_pool_pubkey, false),
+        AccountMeta::new(farm_user_pubkey, false),
+        AccountMeta::new_readonly(farm_owner_pubkey, true),
+        AccountMeta::new_readonly(rent::id(), false),
+    ];
+
+    Ok(Instruction {
+        program_id,
+        data,
+        accounts,
+    })
+}
+
+/// Creates `FarmDeposit` instruction
+pub fn farm_deposit(
+    program_id: Pubkey,
+    config_key: Pubkey,
+    farm_pool_pubkey: Pubkey,
+    user_transfer_authority_pubkey: Pubkey,
+    source_pubkey: Pubkey,
+    destination_pubkey: Pubkey,
+    farm_user_pubkey: Pubkey,
+    farm_owner_pubkey: Pubkey,
+    deposit_data: FarmDepositData,
+) -> Result<Instruction, ProgramError> {
+    let data = FarmInstruction::Deposit(deposit_data).pack();
+
+    let accounts = vec![
+        AccountMeta::new_readonly(config_key, false),
+        AccountMeta::new(farm_pool_pubkey, false),
+        AccountMeta::new_readonly(user_transfer_authority_pubkey, true),
+        AccountMeta::new(source_pubkey, false),
+        AccountMeta::new(destination_pubkey, false),
+        AccountMeta::new(farm_user_pubkey, false),
+        AccountMeta::new_readonly(farm_owner_pubkey, true),
+        AccountMeta::new_readonly(spl_token::id(), false),
+    ];
+
+    Ok(Instruction {
+        program_id,
+        data,
+        accounts,
+    })
+}
+
+/// Creates `FarmWithdraw` instruction
+pub fn farm_withdraw(
+    program_id: Pubkey,
+    config_key: Pubkey,
+    farm_pool_pubkey: Pubkey,
+    farm_user_pubkey: Pubkey,
+    authority_pubkey: Pubkey,
+    source_pubkey: Pubkey,
+    destination_pubkey: Pubkey,
+    farm_owner_pubkey: Pubkey,
+    withdraw_data: FarmWithdrawData,
+) -> Result<Instruction, ProgramError> {
+    let data = FarmInstruction::Withdraw(withdraw_data).pack();
+
+    let accounts = vec![
+        AccountMeta::new_readonly(config_key, false),
+        AccountMeta::new(farm_pool_pubkey, false),
+        AccountMeta::new(farm_user_pubkey, false),
+        AccountMeta::new_readonly(authority_pubkey, false),
+        AccountMeta::new(source_pubkey, false),
+        AccountMeta::new(destination_pubkey, false),
+        AccountMeta::new_readonly(farm_owner_pubkey, true),
+        AccountMeta::new_readonly(spl_token::id(), false),
+    ];
+
+    Ok(Instruction {
+        program_id,
+        data,
+        accounts,
+    })
+}
+
+/// Creates farm claim instruction
+pub fn farm_claim(
+    program_id: Pubkey,
+    config_pubkey: Pubkey,
+    farm_pool_pubkey: Pubkey,
+    farm_user_pubkey: Pubkey,
+    farm_owner_pubkey: Pubkey,
+    market_authority_pubkey: Pubkey,
+    claim_destination_pubkey: Pubkey,
+    claim_source_pubkey: Pubkey,
+) -> Result<Instruction, ProgramError> {
+    let data = FarmInstruction::Claim.pack();
+    let accounts = vec![
+        AccountMeta::new_readonly(config_pubkey, false),
+        AccountMeta::new_readonly(farm_pool_pubkey, false),
+        AccountMeta::new(farm_user_pubkey, false),
+        AccountMeta::new_readonly(farm_owner_pubkey, true),
+        AccountMeta::new_readonly(market_authority_pubkey, false),
+        AccountMeta::new(claim_destination_pubkey, false),
+        AccountMeta::new(claim_source_pubkey, false),
+        AccountMeta::new_readonly(spl_token::id(), false),
+    ];
+    Ok(Instruction {
+        program_id,
+        accounts,
+        data,
+    })
+}
+
+/// Creates farm set reward split instruction
+pub fn farm_set_reward_split(
+    program_id: Pubkey,
+    config_pubkey: Pubkey,
+    farm_pool_pubkey: Pubkey,
+    farm_user_pubkey: Pubkey,
+    farm_owner_pubkey: Pubkey,
+    reward_split_pubkey: Pubkey,
+    recipient_pubkeys: &[Pubkey],
+    split_data: FarmRewardSplitData,
+) -> Result<Instruction, ProgramError> {
+    let data = FarmInstruction::SetRewardSplit(split_data).pack();
+    let mut accounts = vec![
+        AccountMeta::new_readonly(config_pubkey, false),
+        AccountMeta::new_readonly(farm_pool_pubkey, false),
+        AccountMeta::new(farm_user_pubkey, false),
+        AccountMeta::new_readonly(farm_owner_pubkey, true),
+        AccountMeta::new(reward_split_pubkey, false),
+        AccountMeta::new_readonly(rent::id(), false),
+    ];
+    for recipient_pubkey in recipient_pubkeys {
+        accounts.push(AccountMeta::new_readonly(*recipient_pubkey, false));
+    }
+    Ok(Instruction {
+        program_id,
+        accounts,
+        data,
+    })
+}
+
+/// Creates `FarmCheckpoint` instruction
+pub fn farm_checkpoint(
+    program_id: Pubkey,
+    config_pubkey: Pubkey,
+    farm_pool_pubkey: Pubkey,
+    farm_user_pubkeys: &[Pubkey],
+) -> Result<Instruction, ProgramError> {
+    let data = FarmInstruction::Checkpoint.pack();
+    let mut accounts = vec![
+        AccountMeta::new_readonly(config_pubkey, false),
+        AccountMeta::new_readonly(farm_pool_pubkey, false),
+    ];
+    for farm_user_pubkey in farm_user_pubkeys {
+        accounts.push(AccountMeta::new(*farm_user_pubkey, false));
+    }
+    Ok(Instruction {
+        program_id,
+        accounts,
+        data,
+    })
+}
+
+/// Creates `FarmRefresh` instruction
+pub fn farm_refresh(
+    program_id: Pubkey,
+    config_pubkey: Pubkey,
+    farm_pool_pubkey: Pubkey,
+    farm_user_pubkeys: &[Pubkey],
+) -> Result<Instruction, ProgramError> {
+    let data = FarmInstruction::Refresh.pack();
+    let mut accounts = vec![
+        AccountMeta::new_readonly(config_pubkey, false),
+        AccountMeta::new(farm_pool_pubkey, false),
+    ];
+    for farm_user_pubkey in farm_user_pubkeys {
+        accounts.push(AccountMeta::new(*farm_user_pubkey, false));
+    }
+    Ok(Instruction {
+        program_id,
+        accounts,
+        data,
+    })
+}
+
+/// Appends the partner reward token accounts of a farm user to a farm deposit or withdraw
+/// instruction, required once the farm emits partner reward tokens
+pub fn with_farm_reward_tokens(
+    mut instruction: Instruction,
+    farm_reward_tokens_pubkey: Pubkey,
+    farm_user_reward_tokens_pubkey: Pubkey,
+) -> Instruction {
+    instruction
+        .accounts
+        .push(AccountMeta::new(farm_reward_tokens_pubkey, false));
+    instruction
+        .accounts
+        .push(AccountMeta::new(farm_user_reward_tokens_pubkey, false));
+    instruction
+}
+
+/// Appends the admin token account receiving the withdraw fee to a farm withdraw instruction,
+/// required when the farm has a fee, after the partner reward token accounts
+pub fn with_farm_withdraw_fee(
+    mut instruction: Instruction,
+    fee_destination_pubkey: Pubkey,
+) -> Instruction {
+    instruction
+        .accounts
+        .push(AccountMeta::new(fee_destination_pubkey, false));
+    instruction
+}
+
+/// Creates `FarmFundRewardToken` instruction
+pub fn farm_fund_reward_token(
+    program_id: Pubkey,
+    config_pubkey: Pubkey,
+    farm_pool_pubkey: Pubkey,
+    farm_reward_tokens_pubkey: Pubkey,
+    vault_pubkey: Pubkey,
+    source_pubkey: Pubkey,
+    user_transfer_authority_pubkey: Pubkey,
+    fund_data: FarmFundRewardTokenData,
+) -> Result<Instruction, ProgramError> {
+    let data = FarmInstruction::FundRewardToken(fund_data).pack();
+    let accounts = vec![
+        AccountMeta::new_readonly(config_pubkey, false),
+        AccountMeta::new_readonly(farm_pool_pubkey, false),
+        AccountMeta::new(farm_reward_tokens_pubkey, false),
+        AccountMeta::new(vault_pubkey, false),
+        AccountMeta::new(source_pubkey, false),
+        AccountMeta::new_readonly(user_transfer_authority_pubkey, true),
+        AccountMeta::new_readonly(spl_token::id(), false),
+    ];
+    Ok(Instruction {
+        program_id,
+        accounts,
+        data,
+    })
+}
+
+/// Creates `FarmInitializeFarmUserRewardTokens` instruction
+pub fn farm_user_reward_tokens_initialize(
+    program_id: Pubkey,
+    config_pubkey: Pubkey,
+    farm_pool_pubkey: Pubkey,
+    farm_user_pubkey: Pubkey,
+    farm_user_reward_tokens_pubkey: Pubkey,
+    farm_owner_pubkey: Pubkey,
+) -> Result<Instruction, ProgramError> {
+    let data = FarmInstruction::InitializeFarmUserRewardTokens.pack();
+    let accounts = vec![
+        AccountMeta::new_readonly(config_pubkey, false),
+        AccountMeta::new_readonly(farm_pool_pubkey, false),
+        AccountMeta::new_readonly(farm_user_pubkey, false),
+        AccountMeta::new(farm_user_reward_tokens_pubkey, false),
+        AccountMeta::new(farm_owner_pubkey, true),
+        AccountMeta::new_readonly(rent::id(), false),
+        AccountMeta::new_readonly(system_program::id(), false),
+    ];
+    Ok(Instruction {
+        program_id,
+        accounts,
+        data,
+    })
+}
+
+/// Creates `FarmClaimRewardTokens` instruction, with the vault and destination of each
+/// reward token in use
+pub fn farm_claim_reward_tokens(
+    program_id: Pubkey,
+    config_pubkey: Pubkey,
+    farm_pool_pubkey: Pubkey,
+    farm_user_pubkey: Pubkey,
+    farm_reward_tokens_pubkey: Pubkey,
+    farm_user_reward_tokens_pubkey: Pubkey,
+    farm_owner_pubkey: Pubkey,
+    authority_pubkey: Pubkey,
+    vault_destination_pubkeys: &[(Pubkey, Pubkey)],
+) -> Result<Instruction, ProgramError> {
+    let data = FarmInstruction::ClaimRewardTokens.pack();
+    let mut accounts = vec![
+        AccountMeta::new_readonly(config_pubkey, false),
+        AccountMeta::new_readonly(farm_pool_pubkey, false),
+        AccountMeta::new_readonly(farm_user_pubkey, false),
+        AccountMeta::new(farm_reward_tokens_pubkey, false),
+        AccountMeta::new(farm_user_reward_tokens_pubkey, false),
+        AccountMeta::new_readonly(farm_owner_pubkey, true),
+        AccountMeta::new_readonly(authority_pubkey, false),
+        AccountMeta::new_readonly(spl_token::id(), false),
+    ];
+    for (vault_pubkey, destination_pubkey) in vault_destination_pubkeys {
+        accounts.push(AccountMeta::new(*vault_pubkey, false));
+        accounts.push(AccountMeta::new(*destination_pubkey, false));
+    }
+    Ok(Instruction {
+        program_id,
+        accounts,
+        data,
+    })
+}
+
+/// Creates `FarmCompound` instruction, with the market price accounts of the swap
+pub fn farm_compound(
+    program_id: Pubkey,
+    config_pubkey: Pubkey,
+    farm_pool_pubkey: Pubkey,
+    farm_user_pubkey: Pubkey,
+    compound_authority_pubkey: Pubkey,
+    market_authority_pubkey: Pubkey,
+    claim_source_pubkey: Pubkey,
+    swap_pubkey: Pubkey,
+    swap_authority_pubkey: Pubkey,
+    swap_token_a_pubkey: Pubkey,
+    swap_token_b_pubkey: Pubkey,
+    admin_fee_destination_pubkey: Pubkey,
+    pool_mint_pubkey: Pubkey,
+    farm_pool_token_pubkey: Pubkey,
+    price_pubkeys: &[Pubkey],
+    compound_data: FarmCompoundData,
+) -> Result<Instruction, ProgramError> {
+    let data = FarmInstruction::Compound(compound_data).pack();
+    let mut accounts = vec![
+        AccountMeta::new_readonly(config_pubkey, false),
+        AccountMeta::new(farm_pool_pubkey, false),
+        AccountMeta::new(farm_user_pubkey, false),
+        AccountMeta::new_readonly(compound_authority_pubkey, true),
+        AccountMeta::new_readonly(market_authority_pubkey, false),
+        AccountMeta::new(claim_source_pubkey, false),
+        AccountMeta::new(swap_pubkey, false),
+        AccountMeta::new_readonly(swap_authority_pubkey, false),
+        AccountMeta::new(swap_token_a_pubkey, false),
+        AccountMeta::new(swap_token_b_pubkey, false),
+        AccountMeta::new(admin_fee_destination_pubkey, false),
+        AccountMeta::new(pool_mint_pubkey, false),
+        AccountMeta::new(farm_pool_token_pubkey, false),
+    ];
+    for price_pubkey in price_pubkeys {
+        accounts.push(AccountMeta::new_readonly(*price_pubkey, false));
+    }
+    accounts.push(AccountMeta::new_readonly(spl_token::id(), false));
+    Ok(Instruction {
+        program_id,
+        accounts,
+        data,
+    })
+}
+
+/// Creates `FarmTransferPosition` instruction
+pub fn farm_transfer_position(
+    program_id: Pubkey,
+    config_pubkey: Pubkey,
+    farm_pool_pubkey: Pubkey,
+    source_farm_user_pubkey: Pubkey,
+    source_owner_pubkey: Pubkey,
+    destination_farm_user_pubkey: Pubkey,
+) -> Result<Instruction, ProgramError> {
+    let data = FarmInstruction::TransferPosition.pack();
+    let accounts = vec![
+        AccountMeta::new_readonly(config_pubkey, false),
+        AccountMeta::new_readonly(farm_pool_pubkey, false),
+        AccountMeta::new(source_farm_user_pubkey, false),
+        AccountMeta::new_readonly(source_owner_pubkey, true),
+        AccountMeta::new(destination_farm_user_pubkey, false),
+    ];
+    Ok(Instruction {
+        program_id,
+        accounts,
+        data,
+    })
+}
+
+/// Creates `FarmClaimMulti` instruction, with the farm pool and farm user of each farm
+pub fn farm_claim_multi(
+    program_id: Pubkey,
+    config_pubkey: Pubkey,
+    farm_owner_pubkey: Pubkey,
+    market_authority_pubkey: Pubkey,
+    claim_destination_pubkey: Pubkey,
+    claim_source_pubkey: Pubkey,
+    farm_pubkeys: &[(Pubkey, Pubkey)],
+    referral_pubkeys: Option<(Pubkey, Pubkey)>,
+) -> Result<Instruction, ProgramError> {
+    let farm_count = u8::try_from(farm_pubkeys.len()).map_err(|_| SwapError::InvalidInput)?;
+    let data = FarmInstruction::ClaimMulti(FarmClaimMultiData { farm_count }).pack();
+    let mut accounts = vec![
+        AccountMeta::new_readonly(config_pubkey, false),
+        AccountMeta::new_readonly(farm_owner_pubkey, true),
+        AccountMeta::new_readonly(market_authority_pubkey, false),
+        AccountMeta::new(claim_destination_pubkey, false),
+        AccountMeta::new(claim_source_pubkey, false),
+        AccountMeta::new_readonly(spl_token::id(), false),
+    ];
+    for (farm_pool_pubkey, farm_user_pubkey) in farm_pubkeys {
+        accounts.push(AccountMeta::new_readonly(*farm_pool_pubkey, false));
+        accounts.push(AccountMeta::new(*farm_user_pubkey, false));
+    }
+    if let Some((user_referrer_data_pubkey, referrer_token_pubkey)) = referral_pubkeys {
+        accounts.push(AccountMeta::new_readonly(user_referrer_data_pubkey, false));
+        accounts.push(AccountMeta::new(referrer_token_pubkey, false));
+    }
+    Ok(Instruction {
+        program_id,
+        accounts,
+        data,
+    })
+}
+
+/// Creates `FarmSetCompoundDelegate` instruction
+pub fn farm_set_compound_delegate(
+    program_id: Pubkey,
+    config_pubkey: Pubkey,
+    farm_pool_pubkey: Pubkey,
+    farm_user_pubkey: Pubkey,
+    farm_owner_pubkey: Pubkey,
+    compound_delegate: Pubkey,
+) -> Result<Instruction, ProgramError> {
+    let data =
+        FarmInstruction::SetCompoundDelegate(FarmCompoundDelegateData { compound_delegate }).pack();
+    let accounts = vec![
+        AccountMeta::new_readonly(config_pubkey, false),
+        AccountMeta::new_readonly(farm_pool_pubkey, false),
+        AccountMeta::new(farm_user_pubkey, false),
+        AccountMeta::new_readonly(farm_owner_pubkey, true),
+    ];
+    Ok(Instruction {
+        program_id,
+        accounts,
+        data,
+    })
+}
+
+fn unpack_farm_initialize_data(input: &[u8]) -> Result<FarmInitializeData, ProgramError> {
+    let (fee_numerator, rest) = unpack_u64(input)?;
+    let (fee_denominator, rest) = unpack_u64(rest)?;
+    let (rewards_numerator, rest) = unpack_u64(rest)?;
+    let (rewards_denominator, rest) = unpack_u64(rest)?;
+    let (bump_seed, _) = unpack_u8(rest)?;
+    Ok(FarmInitializeData {
+        fee_numerator,
+        fee_denominator,
+        rewards_numerator,
+        rewards_denominator,
+        bump_seed,
+    })
+}
+
+fn pack_farm_initialize_data(init_data: &FarmInitializeData, buf: &mut Vec<u8>) {
+    buf.extend_from_slice(&init_data.fee_numerator.to_le_bytes());
+    buf.extend_from_slice(&init_data.fee_denominator.to_le_bytes());
+    buf.extend_from_slice(&init_data.rewards_numerator.to_le_bytes());
+    buf.extend_from_slice(&init_data.rewards_denominator.to_le_bytes());
+    buf.push(init_data.bump_seed);
+}
+
+fn unpack_initialize_data(input: &[u8]) -> Result<(InitializeData, &[u8]), ProgramError> {
+    let (nonce, rest) = unpack_u8(input)?;
+    let (slope, rest) = unpack_u64(rest)?;
+    let (mid_price, rest) = unpack_u128(rest)?;
+    let (token_a_decimals, rest) = unpack_u8(rest)?;
+    let (token_b_decimals, rest) = unpack_u8(rest)?;
+    let (token_a_amount, rest) = unpack_u64(rest)?;
+    let (token_b_amount, rest) = unpack_u64(rest)?;
+    let (oracle_priority_flags, rest) = unpack_u8(rest)?;
+    let (quote_is_token_a, rest) = unpack_u8(rest)?;
+    let quote_is_token_a = match quote_is_token_a {
+        0 => false,
+        1 => true,
+        _ => return Err(SwapError::InvalidInstruction.into()),
+    };
+    Ok((
+        InitializeData {
+            nonce,
+            slope,
+            mid_price,
+            token_a_decimals,
+            token_b_decimals,
+            token_a_amount,
+            token_b_amount,
+            oracle_priority_flags,
+            quote_is_token_a,
+        },
+        rest,
+    ))
+}
+
+fn pack_initialize_data(init_data: &InitializeData, buf: &mut Vec<u8>) {
+    buf.push(init_data.nonce);
+    buf.extend_from_slice(&init_data.slope.to_le_bytes());
+    buf.extend_from_slice(&init_data.mid_price.to_le_bytes());
+    buf.push(init_data.token_a_decimals);
+    buf.push(init_data.token_b_decimals);
+    buf.extend_from_slice(&init_data.token_a_amount.to_le_bytes());
+    buf.extend_from_slice(&init_data.token_b_amount.to_le_bytes());
+    buf.push(init_data.oracle_priority_flags);
+    buf.push(init_data.quote_is_token_a as u8);
+}
+
+fn unpack_stable_initialize_data(input: &[u8]) -> Result<StableInitializeData, ProgramError> {
+    let (&nonce, rest) = input
+        .split_first()
+        .ok_or(SwapError::InstructionUnpackError)?;
+    let (slope, rest) = unpack_u64(rest)?;
+    let (token_a_decimals, rest) = unpack_u8(rest)?;
+    let (token_b_decimals, rest) = unpack_u8(rest)?;
+    let (token_a_amount, rest) = unpack_u64(rest)?;
+    let (token_b_amount, _) = unpack_u64(rest)?;
+    Ok(StableInitializeData {
+        nonce,
+        slope,
+        token_a_decimals,
+        token_b_decimals,
+        token_a_amount,
+        token_b_amount,
+    })
+}
+
+fn pack_stable_initialize_data(init_data: &StableInitializeData, buf: &mut Vec<u8>) {
+    buf.extend_from_slice(&init_data.nonce.to_le_bytes());
+    buf.extend_from_slice(&init_data.slope.to_le_bytes());
+    buf.push(init_data.token_a_decimals);
+    buf.push(init_data.token_b_decimals);
+    buf.extend_from_slice(&init_data.token_a_amount.to_le_bytes());
+    buf.extend_from_slice(&init_data.token_b_amount.to_le_bytes());
+}
+
+fn unpack_tri_amounts(input: &[u8]) -> Result<([u64; TRI_POOL_TOKENS], &[u8]), ProgramError> {
+    let mut amounts = [0u64; TRI_POOL_TOKENS];
+    let mut rest = input;
+    for amount in amounts.iter_mut() {
+        let (value, next) = unpack_u64(rest)?;
+        *amount = value;
+        rest = next;
+    }
+    Ok((amounts, rest))
+}
+
+fn pack_tri_amounts(amounts: &[u64; TRI_POOL_TOKENS], buf: &mut Vec<u8>) {
+    for amount in amounts {
+        buf.extend_from_slice(&amount.to_le_bytes());
+    }
+}
+
+fn unpack_deadline_slot(input: &[u8]) -> Result<Option<u64>, ProgramError> {
+    if input.is_empty() {
+        return Ok(None);
+    }
+    let (deadline_slot, _) = unpack_u64(input)?;
+    Ok(Some(deadline_slot))
+}
+
+fn pack_deadline_slot(deadline_slot: Option<u64>, buf: &mut Vec<u8>) {
+    if let Some(deadline_slot) = deadline_slot {
+        buf.extend_from_slice(&deadline_slot.to_le_bytes());
+    }
+}
+
+/// Unpack the optional deadline slot and output owner of a swap, a deadline slot of
+/// `u64::MAX` is packed in front of an output owner set without deadline
+fn unpack_swap_options(input: &[u8]) -> Result<(Option<u64>, Option<Pubkey>), ProgramError> {
+    if input.is_empty() {
+        return Ok((None, None));
+    }
+    let (deadline_slot, rest) = unpack_u64(input)?;
+    if rest.is_empty() {
+        return Ok((Some(deadline_slot), None));
+    }
+    let (output_owner, _) = unpack_pubkey(rest)?;
+    let deadline_slot = if deadline_slot == u64::MAX {
+        None
+    } else {
+        Some(deadline_slot)
+    };
+    Ok((deadline_slot, Some(output_owner)))
+}
+
+fn pack_swap_options(deadline_slot: Option<u64>, output_owner: Option<Pubkey>, buf: &mut Vec<u8>) {
+    match output_owner {
+        Some(output_owner) => {
+            buf.extend_from_slice(&deadline_slot.unwrap_or(u64::MAX).to_le_bytes());
+            buf.extend_from_slice(output_owner.as_ref());
+        }
+        None => pack_deadline_slot(deadline_slot, buf),
+    }
+}
+
+fn unpack_u128(input: &[u8]) -> Result<(u128, &[u8]), ProgramError> {
+    if input.len() < 16 {
+        return Err(SwapError::InstructionUnpackError.into());
+    }
+    let (amount, rest) = input.split_at(16);
+    let amount = amount
+        .get(..16)
+        .and_then(|slice| slice.try_into().ok())
+        .map(u128::from_le_bytes)
+        .ok_or(SwapError::InstructionUnpackError)?;
+    Ok((amount, rest))
+}
+
+fn unpack_i64(input: &[u8]) -> Result<(i64, &[u8]), ProgramError> {
+    if input.len() < 8 {
+        return Err(SwapError::InstructionUnpackError.into());
+    }
+    let (amount, rest) = input.split_at(8);
+    let amount = amount
+        .get(..8)
+        .and_then(|slice| slice.try_into().ok())
+        .map(i64::from_le_bytes)
+        .ok_or(SwapError::InstructionUnpackError)?;
+    Ok((amount, rest))
+}
+
+fn unpack_u64(input: &[u8]) -> Result<(u64, &[u8]), ProgramError> {
+    if input.len() < 8 {
+        return Err(SwapError::InstructionUnpackError.into());
+    }
+    let (amount, rest) = input.split_at(8);
+    let amount = amount
+        .get(..8)
+        .and_then(|slice| slice.try_into().ok())
+        .map(u64::from_le_bytes)
+        .ok_or(SwapError::InstructionUnpackError)?;
+    Ok((amount, rest))
+}
+
+fn unpack_u16(input: &[u8]) -> Result<(u16, &[u8]), ProgramError> {
+    if input.len() < 2 {
+        return Err(SwapError::InstructionUnpackError.into());
+    }
+    let (value, rest) = input.split_at(2);
+    let value = value
+        .get(..2)
+        .and_then(|slice| slice.try_into().ok())
+        .map(u16::from_le_bytes)
+        .ok_or(SwapError::InstructionUnpackError)?;
+    Ok((value, rest))
+}
+
+fn unpack_u8(input: &[u8]) -> Result<(u8, &[u8]), ProgramError> {
+    if input.is_empty() {
+        return Err(SwapError::InstructionUnpackError.into());
+    }
+    let (bytes, rest) = input.split_at(1);
+    let value = bytes
+        .get(..1)
+        .and_then(|slice| slice.try_into().ok())
+        .map(u8::from_le_bytes)
+        .ok_or(SwapError::InstructionUnpackError)?;
+    Ok((value, rest))
+}
+
+#[allow(dead_code)]
+fn unpack_bytes32(input: &[u8]) -> Result<(&[u8; 32], &[u8]), ProgramError> {
+    if input.len() < 32 {
+        return Err(SwapError::InstructionUnpackError.into());
+    }
+    let (bytes, rest) = input.split_at(32);
+    Ok((
+        bytes
+            .try_into()
+            .map_err(|_| SwapError::InstructionUnpackError)?,
+        rest,
+    ))
+}
+
+fn unpack_pubkey(input: &[u8]) -> Result<(Pubkey, &[u8]), ProgramError> {
+    if input.len() < PUBKEY_BYTES {
+        return Err(SwapError::InstructionUnpackError.into());
+    }
+    let (key, rest) = input.split_at(PUBKEY_BYTES);
+    let pk = Pubkey::new(key);
+    Ok((pk, rest))
+}
+
+fn unpack_bytes32(input: &[u8]) -> Result<([u8; 32], &[u8]), ProgramError> {
+    if input.len() < 32 {
+        return Err(SwapError::InstructionUnpackError.into());
+    }
+    let (bytes, rest) = input.split_at(32);
+    let bytes = bytes
+        .try_into()
+        .map_err(|_| SwapError::InstructionUnpackError)?;
+    Ok((bytes, rest))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::{
+        curve::{default_market_price, default_slope},
+        state::{DEFAULT_TEST_FEES, DEFAULT_TEST_REWARDS},
+    };
+
+    #[test]
+    fn test_instruction_type_check() {
+        assert!(matches!(
+            InstructionType::check(&[101u8, 1u8]),
+            Some(InstructionType::Admin)
+        ));
+        assert!(matches!(
+            InstructionType::check(&[1u8, 1u8]),
+            Some(InstructionType::Swap)
+        ));
+        assert!(matches!(
+            InstructionType::check(&[12u8, 1u8]),
+            Some(InstructionType::StableSwap)
+        ));
+        assert!(matches!(
+            InstructionType::check(&[21u8, 1u8]),
+            Some(InstructionType::Farm)
+        ));
+        assert!(matches!(
+            InstructionType::check(&[30u8, 1u8]),
+            Some(InstructionType::Router)
+        ));
+        assert!(matches!(
+            InstructionType::check(&[28u8, 1u8]),
+            Some(InstructionType::StableSwap)
+        ));
+        assert!(InstructionType::check(&[99u8, 1u8]).is_none());
+    }
+
+    #[test]
+    fn test_pack_admin_init_config() {
+        let fees = DEFAULT_TEST_FEES;
+        let rewards = DEFAULT_TEST_REWARDS;
+        let check = AdminInstruction::Initialize(AdminInitializeData {
+            fees: fees.clone(),
+            rewards: rewards.clone(),
+        });
+        let packed = check.pack();
+        let mut expect = vec![100];
+        let is_initialized = vec![1, fees.is_initialized as u8];
+        expect.extend_from_slice(&is_initialized[0].to_le_bytes());
+        expect.extend_from_slice(&fees.admin_trade_fee_numerator.to_le_bytes());
+        expect.extend_from_slice(&fees.admin_trade_fee_denominator.to_le_bytes());
+        expect.extend_from_slice(&fees.admin_withdraw_fee_numerator.to_le_bytes());
+        expect.extend_from_slice(&fees.admin_withdraw_fee_denominator.to_le_bytes());
+        expect.extend_from_slice(&fees.trade_fee_numerator.to_le_bytes());
+        expect.extend_from_slice(&fees.trade_fee_denominator.to_le_bytes());
+        expect.extend_from_slice(&fees.withdraw_fee_numerator.to_le_bytes());
+        expect.extend_from_slice(&fees.withdraw_fee_denominator.to_le_bytes());
+        expect.extend_from_slice(&fees.flash_fee_numerator.to_le_bytes());
+        expect.extend_from_slice(&fees.flash_fee_denominator.to_le_bytes());
+        expect.extend_from_slice(&fees.dynamic_fee_factor.to_le_bytes());
+        expect.extend_from_slice(&fees.max_dynamic_fee_multiplier.to_le_bytes());
+        expect.extend_from_slice(&fees.treasury_fee_numerator.to_le_bytes());
+        expect.extend_from_slice(&fees.treasury_fee_denominator.to_le_bytes());
+        let is_initialized = vec![1, rewards.is_initialized as u8];
+        expect.extend_from_slice(&is_initialized[0].to_le_bytes());
+        expect.extend_from_slice(&rewards.decimals.to_le_bytes());
+        expect.extend_from_slice(&rewards.referral_fee_bps.to_le_bytes());
+        expect.extend_from_slice(&rewards.reserved);
+        expect.extend_from_slice(&rewards.trade_reward_numerator.to_le_bytes());
+        expect.extend_from_slice(&rewards.trade_reward_denominator.to_le_bytes());
+        expect.extend_from_slice(&rewards.trade_reward_cap.to_le_bytes());
+        assert_eq!(packed, expect);
+        let unpacked = AdminInstruction::unpack(&expect).unwrap();
+        assert_eq!(unpacked, check);
+    }
+
+    #[test]
+    fn test_pack_admin_set_new_fees() {
+        let fees = DEFAULT_TEST_FEES;
+        let check = AdminInstruction::SetNewFees(fees.clone());
+        let packed = check.pack();
+        let mut expect = vec![105];
+        let is_initialized = vec![1, fees.is_initialized as u8];
+        expect.extend_from_slice(&is_initialized[0].to_le_bytes());
+        expect.extend_from_slice(&fees.admin_trade_fee_numerator.to_le_bytes());
+        expect.extend_from_slice(&fees.admin_trade_fee_denominator.to_le_bytes());
+        expect.extend_from_slice(&fees.admin_withdraw_fee_numerator.to_le_bytes());
+        expect.extend_from_slice(&fees.admin_withdraw_fee_denominator.to_le_bytes());
+        expect.extend_from_slice(&fees.trade_fee_numerator.to_le_bytes());
+        expect.extend_from_slice(&fees.trade_fee_denominator.to_le_bytes());
+        expect.extend_from_slice(&fees.withdraw_fee_numerator.to_le_bytes());
+        expect.extend_from_slice(&fees.withdraw_fee_denominator.to_le_bytes());
+        expect.extend_from_slice(&fees.flash_fee_numerator.to_le_bytes());
+        expect.extend_from_slice(&fees.flash_fee_denominator.to_le_bytes());
+        expect.extend_from_slice(&fees.dynamic_fee_factor.to_le_bytes());
+        expect.extend_from_slice(&fees.max_dynamic_fee_multiplier.to_le_bytes());
+        expect.extend_from_slice(&fees.treasury_fee_numerator.to_le_bytes());
+        expect.extend_from_slice(&fees.treasury_fee_denominator.to_le_bytes());
+        assert_eq!(packed, expect);
+        let unpacked = AdminInstruction::unpack(&expect).unwrap();
+        assert_eq!(unpacked, check);
+    }
+
+    #[test]
+    fn test_pack_admin_set_new_rewards() {
+        let rewards = DEFAULT_TEST_REWARDS;
+        let check = AdminInstruction::SetNewRewards(rewards.clone());
+        let packed = check.pack();
+        let mut expect = vec![106];
+        let is_initialized = vec![1, rewards.is_initialized as u8];
+        expect.extend_from_slice(&is_initialized[0].to_le_bytes());
+        expect.extend_from_slice(&rewards.decimals.to_le_bytes());
+        expect.extend_from_slice(&rewards.referral_fee_bps.to_le_bytes());
+        expect.extend_from_slice(&rewards.reserved);
+        expect.extend_from_slice(&rewards.trade_reward_numerator.to_le_bytes());
+        expect.extend_from_slice(&rewards.trade_reward_denominator.to_le_bytes());
+        expect.extend_from_slice(&rewards.trade_reward_cap.to_le_bytes());
+        assert_eq!(packed, expect);
+        let unpacked = AdminInstruction::unpack(&expect).unwrap();
+        assert_eq!(unpacked, check);
+    }
+
+    #[test]
+    fn test_pack_set_farm_rewards() {
+        let farm_rewards = FarmRewards {
+            apr_numerator: 12,
+            apr_denominator: 100,
+        };
+        let check = AdminInstruction::SetFarmRewards(farm_rewards.clone());
+        let packed = check.pack();
+        let mut expect = vec![107];
+        expect.extend_from_slice(&farm_rewards.apr_numerator.to_le_bytes());
+        expect.extend_from_slice(&farm_rewards.apr_denominator.to_le_bytes());
+        assert_eq!(packed, expect);
+        let unpacked = AdminInstruction::unpack(&expect).unwrap();
+        assert_eq!(unpacked, check);
+    }
+
+    #[test]
+    fn test_pack_set_slope() {
+        let slope = 123_123_123u64;
+        let check = AdminInstruction::SetSlope(slope);
+        let packed = check.pack();
+        let mut expect = vec![108];
+        expect.extend_from_slice(&slope.to_le_bytes());
+        assert_eq!(packed, expect);
+        let unpacked = AdminInstruction::unpack(&expect).unwrap();
+        assert_eq!(unpacked, check);
+    }
+
+    #[test]
+    fn test_pack_set_decimals() {
+        let base_decimals = 9u8;
+        let quote_decimals = 6u8;
+        let check = AdminInstruction::SetDecimals(base_decimals, quote_decimals);
+        let packed = check.pack();
+        let mut expect = vec![109];
+        expect.extend_from_slice(&base_decimals.to_le_bytes());
+        expect.extend_from_slice(&quote_decimals.to_le_bytes());
+        assert_eq!(packed, expect);
+        let unpacked = AdminInstruction::unpack(&expect).unwrap();
+        assert_eq!(unpacked, check);
+    }
+
+    #[test]
+    fn test_pack_set_swap_limit() {
+        let swap_out_limit_percentage = 10u8;
+        let check = AdminInstruction::SetSwapLimit(swap_out_limit_percentage);
+        let packed = check.pack();
+        let mut expect = vec![110];
+        expect.extend_from_slice(&swap_out_limit_percentage.to_le_bytes());
+        assert_eq!(packed, expect);
+        let unpacked = AdminInstruction::unpack(&expect).unwrap();
+        assert_eq!(unpacked, check);
+    }
+
+    #[test]
+    fn test_pack_set_price_jump_policy() {
+        let price_jump_policy = 2u8;
+        let check = AdminInstruction::SetPriceJumpPolicy(price_jump_policy);
+        let packed = check.pack();
+        let mut expect = vec![111];
+        expect.extend_from_slice(&price_jump_policy.to_le_bytes());
+        assert_eq!(packed, expect);
+        let unpacked = AdminInstruction::unpack(&expect).unwrap();
+        assert_eq!(unpacked, check);
+    }
+
+    #[test]
+    fn test_pack_set_max_price_divergence() {
+        let max_price_divergence = 150u16;
+        let check = AdminInstruction::SetMaxPriceDivergence(max_price_divergence);
+        let packed = check.pack();
+        let mut expect = vec![112];
+        expect.extend_from_slice(&max_price_divergence.to_le_bytes());
+        assert_eq!(packed, expect);
+        let unpacked = AdminInstruction::unpack(&expect).unwrap();
+        assert_eq!(unpacked, check);
+
+        assert_eq!(
+            AdminInstruction::unpack(&[112, 1]),
+            Err(SwapError::InstructionUnpackError.into())
+        );
+    }
+
+    #[test]
+    fn test_pack_set_orderbook_program() {
+        let check = AdminInstruction::SetOrderbookProgram;
+        let packed = check.pack();
+        let expect = vec![113];
+        assert_eq!(packed, expect);
+        let unpacked = AdminInstruction::unpack(&expect).unwrap();
+        assert_eq!(unpacked, check);
+    }
+
+    #[test]
+    fn test_pack_set_exchange_rate_adapter() {
+        let check = AdminInstruction::SetExchangeRateAdapter(1u8);
+        let packed = check.pack();
+        let expect = vec![114, 1];
+        assert_eq!(packed, expect);
+        let unpacked = AdminInstruction::unpack(&expect).unwrap();
+        assert_eq!(unpacked, check);
+    }
+
+    #[test]
+    fn test_pack_set_oracle_params() {
+        let oracle_params = OracleParams {
+            stale_slots: 25u16,
+            max_confidence_bps: 500u16,
+            min_publishers: 5u8,
+        };
+        let check = AdminInstruction::SetOracleParams(oracle_params);
+        let packed = check.pack();
+        let mut expect = vec![115];
+        expect.extend_from_slice(&oracle_params.stale_slots.to_le_bytes());
+        expect.extend_from_slice(&oracle_params.max_confidence_bps.to_le_bytes());
+        expect.push(oracle_params.min_publishers);
+        assert_eq!(packed, expect);
+        let unpacked = AdminInstruction::unpack(&expect).unwrap();
+        assert_eq!(unpacked, check);
+    }
+
+    #[test]
+    fn test_pack_set_market_maker() {
+        let index = 1u8;
+        let check = AdminInstruction::SetMarketMaker(index);
+        let packed = check.pack();
+        let expect = vec![116, index];
+        assert_eq!(packed, expect);
+        let unpacked = AdminInstruction::unpack(&expect).unwrap();
+        assert_eq!(unpacked, check);
+    }
+
+    #[test]
+    fn test_pack_rotate_fee_account() {
+        let check = AdminInstruction::RotateFeeAccount;
+        let packed = check.pack();
+        let expect = vec![117];
+        assert_eq!(packed, expect);
+        let unpacked = AdminInstruction::unpack(&expect).unwrap();
+        assert_eq!(unpacked, check);
+        assert!(matches!(
+            InstructionType::check(&expect),
+            Some(InstructionType::Admin)
+        ));
+    }
+
+    #[test]
+    fn test_pack_commit_initial_price() {
+        let price_hash = [3u8; 32];
+        let check = AdminInstruction::CommitInitialPrice(price_hash);
+        let packed = check.pack();
+        let mut expect = vec![118];
+        expect.extend_from_slice(&price_hash);
+        assert_eq!(packed, expect);
+        let unpacked = AdminInstruction::unpack(&expect).unwrap();
+        assert_eq!(unpacked, check);
+        assert!(matches!(
+            InstructionType::check(&expect),
+            Some(InstructionType::Admin)
+        ));
+
+        assert_eq!(
+            AdminInstruction::unpack(&expect[..32]).unwrap_err(),
+            SwapError::InstructionUnpackError.into()
+        );
+    }
+
+    #[test]
+    fn test_pack_pegged_price() {
+        let pegged_price = 1_000_000_000_000_000_000u128;
+        let check = AdminInstruction::CommitPeggedPrice(pegged_price);
+        let packed = check.pack();
+        let mut expect = vec![119];
+        expect.extend_from_slice(&pegged_price.to_le_bytes());
+        assert_eq!(packed, expect);
+        let unpacked = AdminInstruction::unpack(&expect).unwrap();
+        assert_eq!(unpacked, check);
+
+        let check = AdminInstruction::ApplyPeggedPrice;
+        let packed = check.pack();
+        let expect = vec![120];
+        assert_eq!(packed, expect);
+        let unpacked = AdminInstruction::unpack(&expect).unwrap();
+        assert_eq!(unpacked, check);
+        assert!(matches!(
+            InstructionType::check(&expect),
+            Some(InstructionType::Admin)
+        ));
+    }
+
+    #[test]
+    fn test_pack_set_admin_role() {
+        let role = 2u8;
+        let check = AdminInstruction::SetAdminRole(role);
+        let packed = check.pack();
+        let expect = vec![121, role];
+        assert_eq!(packed, expect);
+        let unpacked = AdminInstruction::unpack(&expect).unwrap();
+        assert_eq!(unpacked, check);
+        assert!(matches!(
+            InstructionType::check(&expect),
+            Some(InstructionType::Admin)
+        ));
+    }
+
+    #[test]
+    fn test_pack_pause_all() {
+        let check = AdminInstruction::PauseAll;
+        let packed = check.pack();
+        let expect = vec![122];
+        assert_eq!(packed, expect);
+        let unpacked = AdminInstruction::unpack(&expect).unwrap();
+        assert_eq!(unpacked, check);
+        assert!(matches!(
+            InstructionType::check(&expect),
+            Some(InstructionType::Admin)
+        ));
+    }
+
+    #[test]
+    fn test_pack_set_strict_destination_owner() {
+        let check = AdminInstruction::SetStrictDestinationOwner(true);
+        let packed = check.pack();
+        let expect = vec![123, 1];
+        assert_eq!(packed, expect);
+        let unpacked = AdminInstruction::unpack(&expect).unwrap();
+        assert_eq!(unpacked, check);
+        assert_eq!(
+            AdminInstruction::unpack(&[123, 0]).unwrap(),
+            AdminInstruction::SetStrictDestinationOwner(false)
+        );
+        assert_eq!(
+            AdminInstruction::unpack(&[123, 2]).unwrap_err(),
+            SwapError::InvalidInstruction.into()
+        );
+    }
+
+    #[test]
+    fn test_pack_set_pause_flags() {
+        let check = AdminInstruction::SetPauseFlags(0b101);
+        let packed = check.pack();
+        let expect = vec![125, 0b101];
+        assert_eq!(packed, expect);
+        let unpacked = AdminInstruction::unpack(&expect).unwrap();
+        assert_eq!(unpacked, check);
+        assert!(matches!(
+            InstructionType::check(&expect),
+            Some(InstructionType::Admin)
+        ));
+    }
+
+    #[test]
+    fn test_pack_claim_protocol_fees() {
+        let check = AdminInstruction::ClaimProtocolFees;
+        let packed = check.pack();
+        let expect = vec![126];
+        assert_eq!(packed, expect);
+        let unpacked = AdminInstruction::unpack(&expect).unwrap();
+        assert_eq!(unpacked, check);
+    }
+
+    #[test]
+    fn test_pack_set_protocol_fee() {
+        let numerator = 1u64;
+        let denominator = 5u64;
+        let check = AdminInstruction::SetProtocolFee(numerator, denominator);
+        let packed = check.pack();
+        let mut expect = vec![127];
+        expect.extend_from_slice(&numerator.to_le_bytes());
+        expect.extend_from_slice(&denominator.to_le_bytes());
+        assert_eq!(packed, expect);
+        let unpacked = AdminInstruction::unpack(&expect).unwrap();
+        assert_eq!(unpacked, check);
+        assert!(matches!(
+            InstructionType::check(&expect),
+            Some(InstructionType::Admin)
+        ));
+    }
+
+    #[test]
+    fn test_pack_set_pool_authority() {
+        let check = AdminInstruction::SetPoolAuthority(2);
+        let packed = check.pack();
+        let expect = vec![128, 2];
+        assert_eq!(packed, expect);
+        let unpacked = AdminInstruction::unpack(&expect).unwrap();
+        assert_eq!(unpacked, check);
+    }
+
+    #[test]
+    fn test_pack_set_market_authority() {
+        let check = AdminInstruction::SetMarketAuthority(2);
+        let packed = check.pack();
+        let expect = vec![129, 2];
+        assert_eq!(packed, expect);
+        let unpacked = AdminInstruction::unpack(&expect).unwrap();
+        assert_eq!(unpacked, check);
+        assert!(matches!(
+            InstructionType::check(&expect),
+            Some(InstructionType::Admin)
+        ));
+    }
+
+    #[test]
+    fn test_pack_close_pool() {
+        let check = AdminInstruction::ClosePool;
+        let packed = check.pack();
+        let expect = vec![130];
+        assert_eq!(packed, expect);
+        let unpacked = AdminInstruction::unpack(&expect).unwrap();
+        assert_eq!(unpacked, check);
+        assert!(matches!(
+            InstructionType::check(&expect),
+            Some(InstructionType::Admin)
+        ));
+    }
+
+    #[test]
+    fn test_pack_set_oracle_feed() {
+        let check = AdminInstruction::SetOracleFeed;
+        let packed = check.pack();
+        let expect = vec![131];
+        assert_eq!(packed, expect);
+        let unpacked = AdminInstruction::unpack(&expect).unwrap();
+        assert_eq!(unpacked, check);
+    }
+
+    #[test]
+    fn test_pack_enable_oracle_feed_registry() {
+        let check = AdminInstruction::EnableOracleFeedRegistry;
+        let packed = check.pack();
+        let expect = vec![132];
+        assert_eq!(packed, expect);
+        let unpacked = AdminInstruction::unpack(&expect).unwrap();
+        assert_eq!(unpacked, check);
+        assert!(matches!(
+            InstructionType::check(&expect),
+            Some(InstructionType::Admin)
+        ));
+    }
+
+    #[test]
+    fn test_pack_set_price_cache() {
+        let check = AdminInstruction::SetPriceCache;
+        let packed = check.pack();
+        let expect = vec![133];
+        assert_eq!(packed, expect);
+        let unpacked = AdminInstruction::unpack(&expect).unwrap();
+        assert_eq!(unpacked, check);
+        assert!(matches!(
+            InstructionType::check(&expect),
+            Some(InstructionType::Admin)
+        ));
+    }
+
+    #[test]
+    fn test_pack_create_scratch_account() {
+        let check = AdminInstruction::CreateScratchAccount(3);
+        let packed = check.pack();
+        let expect = vec![134, 3];
+        assert_eq!(packed, expect);
+        let unpacked = AdminInstruction::unpack(&expect).unwrap();
+        assert_eq!(unpacked, check);
+        assert!(matches!(
+            InstructionType::check(&expect),
+            Some(InstructionType::Admin)
+        ));
+    }
+
+    #[test]
+    fn test_pack_set_protocol_fee_switch() {
+        let check = AdminInstruction::SetProtocolFeeSwitch(true);
+        let packed = check.pack();
+        let expect = vec![137, 1];
+        assert_eq!(packed, expect);
+        let unpacked = AdminInstruction::unpack(&expect).unwrap();
+        assert_eq!(unpacked, check);
+        assert_eq!(
+            AdminInstruction::unpack(&[137, 0]).unwrap(),
+            AdminInstruction::SetProtocolFeeSwitch(false)
+        );
+        assert_eq!(
+            AdminInstruction::unpack(&[137, 2]).unwrap_err(),
+            SwapError::InvalidInstruction.into()
+        );
+        assert!(matches!(
+            InstructionType::check(&expect),
+            Some(InstructionType::Admin)
+        ));
+    }
+
+    #[test]
+    fn test_pack_sync_pool_from_config() {
+        let check = AdminInstruction::SyncPoolFromConfig(true);
+        let packed = check.pack();
+        let expect = vec![138, 1];
+        assert_eq!(packed, expect);
+        let unpacked = AdminInstruction::unpack(&expect).unwrap();
+        assert_eq!(unpacked, check);
+        assert_eq!(
+            AdminInstruction::unpack(&[138, 0]).unwrap(),
+            AdminInstruction::SyncPoolFromConfig(false)
+        );
+        assert_eq!(
+            AdminInstruction::unpack(&[138, 2]).unwrap_err(),
+            SwapError::InvalidInstruction.into()
+        );
+        assert!(matches!(
+            InstructionType::check(&expect),
+            Some(InstructionType::Admin)
+        ));
+    }
+
+    #[test]
+    fn test_pack_set_config_fees() {
+        let fees = DEFAULT_TEST_FEES;
+        let rewards = DEFAULT_TEST_REWARDS;
+        let check = AdminInstruction::SetConfigFees(AdminInitializeData {
+            fees: fees.clone(),
+            rewards: rewards.clone(),
+        });
+        let packed = check.pack();
+        let mut expect = vec![139];
+        let mut fees_slice = [0u8; Fees::LEN];
+        fees.pack_into_slice(&mut fees_slice);
+        expect.extend_from_slice(&fees_slice);
+        let mut rewards_slice = [0u8; Rewards::LEN];
+        rewards.pack_into_slice(&mut rewards_slice);
+        expect.extend_from_slice(&rewards_slice);
+        assert_eq!(packed, expect);
+        let unpacked = AdminInstruction::unpack(&expect).unwrap();
+        assert_eq!(unpacked, check);
+        assert!(matches!(
+            InstructionType::check(&expect),
+            Some(InstructionType::Admin)
+        ));
+    }
+
+    #[test]
+    fn test_pack_batch_set_fees() {
+        let fees = DEFAULT_TEST_FEES;
+        let check = AdminInstruction::BatchSetFees(fees.clone());
+        let packed = check.pack();
+        let mut expect = vec![140];
+        let mut fees_slice = [0u8; Fees::LEN];
+        fees.pack_into_slice(&mut fees_slice);
+        expect.extend_from_slice(&fees_slice);
+        assert_eq!(packed, expect);
+        let unpacked = AdminInstruction::unpack(&expect).unwrap();
+        assert_eq!(unpacked, check);
+        assert!(matches!(
+            InstructionType::check(&expect),
+            Some(InstructionType::Admin)
+        ));
+    }
+
+    #[test]
+    fn test_pack_farm_reward_token_admin() {
+        let check = AdminInstruction::AddFarmRewardToken(1_000);
+        let packed = check.pack();
+        let mut expect = vec![142];
+        expect.extend_from_slice(&1_000u64.to_le_bytes());
+        assert_eq!(packed, expect);
+        let unpacked = AdminInstruction::unpack(&expect).unwrap();
+        assert_eq!(unpacked, check);
+
+        let check = AdminInstruction::SetFarmRewardRate(1, 2_000);
+        let packed = check.pack();
+        let mut expect = vec![143, 1];
+        expect.extend_from_slice(&2_000u64.to_le_bytes());
+        assert_eq!(packed, expect);
+        let unpacked = AdminInstruction::unpack(&expect).unwrap();
+        assert_eq!(unpacked, check);
+        assert!(matches!(
+            InstructionType::check(&expect),
+            Some(InstructionType::Admin)
+        ));
+    }
+
+    #[test]
+    fn test_pack_set_farm_emission_rate() {
+        let check = AdminInstruction::SetFarmEmissionRate(3_000);
+        let packed = check.pack();
+        let mut expect = vec![144];
+        expect.extend_from_slice(&3_000u64.to_le_bytes());
+        assert_eq!(packed, expect);
+        let unpacked = AdminInstruction::unpack(&expect).unwrap();
+        assert_eq!(unpacked, check);
+        assert!(matches!(
+            InstructionType::check(&expect),
+            Some(InstructionType::Admin)
+        ));
+    }
+
+    #[test]
+    fn test_pack_fund_staking_rewards() {
+        let check = AdminInstruction::FundStakingRewards(5_000);
+        let packed = check.pack();
+        let mut expect = vec![145];
+        expect.extend_from_slice(&5_000u64.to_le_bytes());
+        assert_eq!(packed, expect);
+        let unpacked = AdminInstruction::unpack(&expect).unwrap();
+        assert_eq!(unpacked, check);
+        assert!(matches!(
+            InstructionType::check(&expect),
+            Some(InstructionType::Admin)
+        ));
+    }
+
+    #[test]
+    fn test_pack_set_rebalance_threshold() {
+        let check = AdminInstruction::SetRebalanceThreshold(50);
+        let packed = check.pack();
+        let mut expect = vec![146];
+        expect.extend_from_slice(&50u16.to_le_bytes());
+        assert_eq!(packed, expect);
+        let unpacked = AdminInstruction::unpack(&expect).unwrap();
+        assert_eq!(unpacked, check);
+        assert!(matches!(
+            InstructionType::check(&expect),
+            Some(InstructionType::Admin)
+        ));
+    }
+
+    #[test]
+    fn test_pack_set_circuit_breaker() {
+        let check = AdminInstruction::SetCircuitBreaker(500, 150);
+        let packed = check.pack();
+        let mut expect = vec![147];
+        expect.extend_from_slice(&500u16.to_le_bytes());
+        expect.extend_from_slice(&150u64.to_le_bytes());
+        assert_eq!(packed, expect);
+        let unpacked = AdminInstruction::unpack(&expect).unwrap();
+        assert_eq!(unpacked, check);
+        assert!(matches!(
+            InstructionType::check(&expect),
+            Some(InstructionType::Admin)
+        ));
+    }
+
+    #[test]
+    fn test_pack_set_deposit_cap() {
+        let max_total_supply = 1_000_000_000u64;
+        let check = AdminInstruction::SetDepositCap(max_total_supply);
+        let packed = check.pack();
+        let mut expect = vec![148];
+        expect.extend_from_slice(&max_total_supply.to_le_bytes());
+        assert_eq!(packed, expect);
+        let unpacked = AdminInstruction::unpack(&expect).unwrap();
+        assert_eq!(unpacked, check);
+        assert!(matches!(
+            InstructionType::check(&expect),
+            Some(InstructionType::Admin)
+        ));
+    }
+
+    #[test]
+    fn test_pack_whitelist() {
+        let check = AdminInstruction::SetWhitelistEnabled(true);
+        let packed = check.pack();
+        let expect = vec![149, 1];
+        assert_eq!(packed, expect);
+        let unpacked = AdminInstruction::unpack(&expect).unwrap();
+        assert_eq!(unpacked, check);
+        assert_eq!(
+            AdminInstruction::unpack(&[149, 2]).unwrap_err(),
+            SwapError::InvalidInstruction.into()
+        );
+
+        let check = AdminInstruction::AddWhitelistEntry;
+        let expect = vec![150];
+        assert_eq!(check.pack(), expect);
+        assert_eq!(AdminInstruction::unpack(&expect).unwrap(), check);
+
+        let check = AdminInstruction::RemoveWhitelistEntry;
+        let expect = vec![151];
+        assert_eq!(check.pack(), expect);
+        assert_eq!(AdminInstruction::unpack(&expect).unwrap(), check);
+        assert!(matches!(
+            InstructionType::check(&expect),
+            Some(InstructionType::Admin)
+        ));
+    }
+
+    #[test]
+    fn test_pack_migrate_swap_info() {
+        let check = AdminInstruction::MigrateSwapInfo;
+        let expect = vec![152];
+        assert_eq!(check.pack(), expect);
+        assert_eq!(AdminInstruction::unpack(&expect).unwrap(), check);
+        assert!(matches!(
+            InstructionType::check(&expect),
+            Some(InstructionType::Admin)
+        ));
+    }
+
+    #[test]
+    fn test_pack_migrate_config_farm_info() {
+        let check = AdminInstruction::MigrateConfigInfo;
+        let expect = vec![153];
+        assert_eq!(check.pack(), expect);
+        assert_eq!(AdminInstruction::unpack(&expect).unwrap(), check);
+
+        let check = AdminInstruction::MigrateFarmInfo;
+        let expect = vec![154];
+        assert_eq!(check.pack(), expect);
+        assert_eq!(AdminInstruction::unpack(&expect).unwrap(), check);
+        assert!(matches!(
+            InstructionType::check(&expect),
+            Some(InstructionType::Admin)
+        ));
+    }
+
+    #[test]
+    fn test_pack_set_oracle_accounts() {
+        let check = AdminInstruction::SetOracleAccounts;
+        let expect = vec![155];
+        assert_eq!(check.pack(), expect);
+        assert_eq!(AdminInstruction::unpack(&expect).unwrap(), check);
+        assert!(matches!(
+            InstructionType::check(&expect),
+            Some(InstructionType::Admin)
+        ));
+
+        let check = AdminInstruction::SetOraclePriority(1u8);
+        let expect = vec![156, 1];
+        assert_eq!(check.pack(), expect);
+        assert_eq!(AdminInstruction::unpack(&expect).unwrap(), check);
+        assert!(AdminInstruction::unpack(&[156]).is_err());
+    }
+
+    #[test]
+    fn test_pack_set_price_setter() {
+        let check = AdminInstruction::SetPriceSetter(300, 1_500);
+        let mut expect = vec![157];
+        expect.extend_from_slice(&300u16.to_le_bytes());
+        expect.extend_from_slice(&1_500u64.to_le_bytes());
+        assert_eq!(check.pack(), expect);
+        assert_eq!(AdminInstruction::unpack(&expect).unwrap(), check);
+        assert!(matches!(
+            InstructionType::check(&expect),
+            Some(InstructionType::Admin)
+        ));
+    }
+
+    #[test]
+    fn test_pack_set_price_ema_window() {
+        let check = AdminInstruction::SetPriceEmaWindow(75);
+        let mut expect = vec![158];
+        expect.extend_from_slice(&75u64.to_le_bytes());
+        assert_eq!(check.pack(), expect);
+        assert_eq!(AdminInstruction::unpack(&expect).unwrap(), check);
+        assert!(AdminInstruction::unpack(&[158]).is_err());
+    }
+
+    #[test]
+    fn test_pack_set_skim_surplus() {
+        let check = AdminInstruction::SetSkimSurplus(true);
+        let expect = vec![159, 1];
+        assert_eq!(check.pack(), expect);
+        assert_eq!(AdminInstruction::unpack(&expect).unwrap(), check);
+        assert_eq!(
+            AdminInstruction::unpack(&[159, 2]).unwrap_err(),
+            SwapError::InvalidInstruction.into()
+        );
+        assert!(matches!(
+            InstructionType::check(&expect),
+            Some(InstructionType::Admin)
+        ));
+    }
+
+    #[test]
+    fn test_pack_batch_pause() {
+        let check = AdminInstruction::BatchPause;
+        let packed = check.pack();
+        let expect = vec![141];
+        assert_eq!(packed, expect);
+        let unpacked = AdminInstruction::unpack(&expect).unwrap();
+        assert_eq!(unpacked, check);
+        assert!(matches!(
+            InstructionType::check(&expect),
+            Some(InstructionType::Admin)
+        ));
+    }
+
+    #[test]
+    fn test_pack_set_admin_ops_limit() {
+        let check = AdminInstruction::SetAdminOpsLimit(300);
+        let packed = check.pack();
+        let mut expect = vec![135];
+        expect.extend_from_slice(&300u16.to_le_bytes());
+        assert_eq!(packed, expect);
+        let unpacked = AdminInstruction::unpack(&expect).unwrap();
+        assert_eq!(unpacked, check);
+        assert!(matches!(
+            InstructionType::check(&expect),
+            Some(InstructionType::Admin)
+        ));
+    }
+
+    #[test]
+    fn test_pack_set_fee_discount_tiers() {
+        let mut tiers = [FeeDiscountTier::default(); MAX_FEE_DISCOUNT_TIERS];
+        tiers[0] = FeeDiscountTier {
+            min_staked_amount: 1_000,
+            discount_bps: 500,
+        };
+        let check = AdminInstruction::SetFeeDiscountTiers(tiers);
+        let packed = check.pack();
+        let mut expect = vec![136];
+        expect.extend_from_slice(&1_000u64.to_le_bytes());
+        expect.extend_from_slice(&500u16.to_le_bytes());
+        expect.extend_from_slice(&[0u8; 10 * (MAX_FEE_DISCOUNT_TIERS - 1)]);
+        assert_eq!(packed, expect);
+        let unpacked = AdminInstruction::unpack(&expect).unwrap();
+        assert_eq!(unpacked, check);
+        assert!(matches!(
+            InstructionType::check(&expect),
+            Some(InstructionType::Admin)
+        ));
+    }
+
+    #[test]
+    fn test_pack_enable_farm_user_registry() {
+        let check = AdminInstruction::EnableFarmUserRegistry;
+        let packed = check.pack();
+        let expect = vec![124];
+        assert_eq!(packed, expect);
+        let unpacked = AdminInstruction::unpack(&expect).unwrap();
+        assert_eq!(unpacked, check);
+        assert!(matches!(
+            InstructionType::check(&expect),
+            Some(InstructionType::Admin)
+        ));
+    }
 
     #[test]
-    fn test_pack_admin_set_new_fees() {
-        let fees = DEFAULT_TEST_FEES;
-        let check = AdminInstruction::SetNewFees(fees.clone());
+    fn test_pack_admin_pause() {
+        let check = AdminInstruction::Pause;
         let packed = check.pack();
-        let mut expect = vec![105];
-        let is_initialized = vec![1, fees.is_initialized as u8];
-        expect.extend_from_slice(&is_initialized[0].to_le_bytes());
-        expect.extend_from_slice(&fees.admin_trade_fee_numerator.to_le_bytes());
-        expect.extend_from_slice(&fees.admin_trade_fee_denominator.to_le_bytes());
-        expect.extend_from_slice(&fees.admin_withdraw_fee_numerator.to_le_bytes());
-        expect.extend_from_slice(&fees.admin_withdraw_fee_denominator.to_le_bytes());
-        expect.extend_from_slice(&fees.trade_fee_numerator.to_le_bytes());
-        expect.extend_from_slice(&fees.trade_fee_denominator.to_le_bytes());
-        expect.extend_from_slice(&fees.withdraw_fee_numerator.to_le_bytes());
-        expect.extend_from_slice(&fees.withdraw_fee_denominator.to_le_bytes());
+        let expect = vec![101];
         assert_eq!(packed, expect);
         let unpacked = AdminInstruction::unpack(&expect).unwrap();
         assert_eq!(unpacked, check);
     }
 
     #[test]
-    fn test_pack_admin_set_new_rewards() {
-        let rewards = DEFAULT_TEST_REWARDS;
-        let check = AdminInstruction::SetNewRewards(rewards.clone());
+    fn test_pack_admin_unpause() {
+        let check = AdminInstruction::Unpause;
         let packed = check.pack();
-        let mut expect = vec![106];
-        let is_initialized = vec![1, rewards.is_initialized as u8];
-        expect.extend_from_slice(&is_initialized[0].to_le_bytes());
-        expect.extend_from_slice(&rewards.decimals.to_le_bytes());
-        expect.extend_from_slice(&rewards.reserved);
-        expect.extend_from_slice(&rewards.trade_reward_numerator.to_le_bytes());
-        expect.extend_from_slice(&rewards.trade_reward_denominator.to_le_bytes());
-        expect.extend_from_slice(&rewards.trade_reward_cap.to_le_bytes());
+        let expect = vec![102];
+        assert_eq!(packed, expect);
+        let unpacked = AdminInstruction::unpack(&expect).unwrap();
+        assert_eq!(unpacked, check);
+    }
+
+    #[test]
+    fn test_pack_admin_set_fee_account() {
+        let check = AdminInstruction::SetFeeAccount;
+        let packed = check.pack();
+        let expect = vec![103];
         assert_eq!(packed, expect);
         let unpacked = AdminInstruction::unpack(&expect).unwrap();
         assert_eq!(unpacked, check);
     }
 
-    #[test]
-    fn test_pack_set_farm_rewards() {
-        let farm_rewards = FarmRewards {
-            apr_numerator: 12,
-            apr_denominator: 100,
-        };
-        let check = AdminInstruction::SetFarmRewards(farm_rewards.clone());
-        let packed = check.pack();
-        let mut expect = vec![107];
-        expect.extend_from_slice(&farm_rewards.apr_numerator.to_le_bytes());
-        expect.extend_from_slice(&farm_rewards.apr_denominator.to_le_bytes());
-        assert_eq!(packed, expect);
-        let unpacked = AdminInstruction::unpack(&expect).unwrap();
-        assert_eq!(unpacked, check);
+    #[test]
+    fn test_pack_admin_error() {
+        let result = AdminInstruction::unpack(&[111, 2]);
+        let expect = Err(ProgramError::from(SwapError::InvalidInstruction));
+        assert_eq!(expect, result);
+    }
+
+    #[test]
+    fn test_initialize_config() {
+        let program_id = Pubkey::new_unique();
+        let config_pubkey = Pubkey::new_unique();
+        let market_authority_pubkey = Pubkey::new_unique();
+        let deltafi_mint_pubkey = Pubkey::new_unique();
+        let admin_pubkey = Pubkey::new_unique();
+        let pyth_program_id = Pubkey::new_unique();
+        let fees = DEFAULT_TEST_FEES;
+        let rewards = DEFAULT_TEST_REWARDS;
+        let deltafi_token_pubkey = Pubkey::new_unique();
+
+        let result = initialize_config(
+            program_id,
+            config_pubkey,
+            market_authority_pubkey,
+            deltafi_mint_pubkey,
+            admin_pubkey,
+            pyth_program_id,
+            fees.clone(),
+            rewards.clone(),
+            deltafi_token_pubkey,
+        );
+
+        assert!(result.is_ok());
+
+        let mut expected_data = vec![100];
+        let is_initialized = vec![1, fees.is_initialized as u8];
+        expected_data.extend_from_slice(&is_initialized[0].to_le_bytes());
+        expected_data.extend_from_slice(&fees.admin_trade_fee_numerator.to_le_bytes());
+        expected_data.extend_from_slice(&fees.admin_trade_fee_denominator.to_le_bytes());
+        expected_data.extend_from_slice(&fees.admin_withdraw_fee_numerator.to_le_bytes());
+        expected_data.extend_from_slice(&fees.admin_withdraw_fee_denominator.to_le_bytes());
+        expected_data.extend_from_slice(&fees.trade_fee_numerator.to_le_bytes());
+        expected_data.extend_from_slice(&fees.trade_fee_denominator.to_le_bytes());
+        expected_data.extend_from_slice(&fees.withdraw_fee_numerator.to_le_bytes());
+        expected_data.extend_from_slice(&fees.withdraw_fee_denominator.to_le_bytes());
+        expected_data.extend_from_slice(&fees.flash_fee_numerator.to_le_bytes());
+        expected_data.extend_from_slice(&fees.flash_fee_denominator.to_le_bytes());
+        expected_data.extend_from_slice(&fees.dynamic_fee_factor.to_le_bytes());
+        expected_data.extend_from_slice(&fees.max_dynamic_fee_multiplier.to_le_bytes());
+        expected_data.extend_from_slice(&fees.treasury_fee_numerator.to_le_bytes());
+        expected_data.extend_from_slice(&fees.treasury_fee_denominator.to_le_bytes());
+        let is_initialized = vec![1, rewards.is_initialized as u8];
+        expected_data.extend_from_slice(&is_initialized[0].to_le_bytes());
+        expected_data.extend_from_slice(&rewards.decimals.to_le_bytes());
+        expected_data.extend_from_slice(&rewards.referral_fee_bps.to_le_bytes());
+        expected_data.extend_from_slice(&rewards.reserved);
+        expected_data.extend_from_slice(&rewards.trade_reward_numerator.to_le_bytes());
+        expected_data.extend_from_slice(&rewards.trade_reward_denominator.to_le_bytes());
+        expected_data.extend_from_slice(&rewards.trade_reward_cap.to_le_bytes());
+
+        let expected_account = vec![
+            AccountMeta {
+                pubkey: config_pubkey,
+                is_signer: true,
+                is_writable: true,
+            },
+            AccountMeta {
+                pubkey: market_authority_pubkey,
+                is_signer: false,
+                is_writable: false,
+            },
+            AccountMeta {
+                pubkey: deltafi_mint_pubkey,
+                is_signer: false,
+                is_writable: false,
+            },
+            AccountMeta {
+                pubkey: admin_pubkey,
+                is_signer: true,
+                is_writable: false,
+            },
+            AccountMeta {
+                pubkey: rent::id(),
+                is_signer: false,
+                is_writable: false,
+            },
+            AccountMeta {
+                pubkey: spl_token::id(),
+                is_signer: false,
+                is_writable: false,
+            },
+            AccountMeta {
+                pubkey: pyth_program_id,
+                is_signer: false,
+                is_writable: false,
+            },
+            AccountMeta {
+                pubkey: deltafi_token_pubkey,
+                is_signer: false,
+                is_writable: false,
+            },
+        ];
+
+        assert_eq!(result.as_ref().unwrap().data, expected_data);
+        assert_eq!(result.as_ref().unwrap().accounts, expected_account);
+    }
+
+    #[test]
+    fn test_pause() {
+        let program_id = Pubkey::new_unique();
+        let config_pubkey = Pubkey::new_unique();
+        let swap_pubkey = Pubkey::new_unique();
+        let admin_pubkey = Pubkey::new_unique();
+
+        let result = pause(program_id, config_pubkey, swap_pubkey, admin_pubkey);
+        let expected_data = vec![101];
+
+        let expected_account = vec![
+            AccountMeta {
+                pubkey: config_pubkey,
+                is_signer: false,
+                is_writable: false,
+            },
+            AccountMeta {
+                pubkey: swap_pubkey,
+                is_signer: false,
+                is_writable: true,
+            },
+            AccountMeta {
+                pubkey: admin_pubkey,
+                is_signer: true,
+                is_writable: false,
+            },
+        ];
+
+        assert_eq!(result.as_ref().unwrap().data, expected_data);
+        assert_eq!(result.as_ref().unwrap().accounts, expected_account);
+    }
+
+    #[test]
+    fn test_unpause() {
+        let program_id = Pubkey::new_unique();
+        let config_pubkey = Pubkey::new_unique();
+        let swap_pubkey = Pubkey::new_unique();
+        let admin_pubkey = Pubkey::new_unique();
+
+        let result = unpause(program_id, config_pubkey, swap_pubkey, admin_pubkey);
+        let expected_data = vec![102];
+
+        let expected_account = vec![
+            AccountMeta {
+                pubkey: config_pubkey,
+                is_signer: false,
+                is_writable: false,
+            },
+            AccountMeta {
+                pubkey: swap_pubkey,
+                is_signer: false,
+                is_writable: true,
+            },
+            AccountMeta {
+                pubkey: admin_pubkey,
+                is_signer: true,
+                is_writable: false,
+            },
+        ];
+
+        assert_eq!(result.as_ref().unwrap().data, expected_data);
+        assert_eq!(result.as_ref().unwrap().accounts, expected_account);
+    }
+
+    #[test]
+    fn test_set_fee_account() {
+        let program_id = Pubkey::new_unique();
+        let config_pubkey = Pubkey::new_unique();
+        let swap_pubkey = Pubkey::new_unique();
+        let authority_pubkey = Pubkey::new_unique();
+        let admin_pubkey = Pubkey::new_unique();
+        let new_fee_account_pubkey = Pubkey::new_unique();
+
+        let result = set_fee_account(
+            program_id,
+            config_pubkey,
+            swap_pubkey,
+            authority_pubkey,
+            admin_pubkey,
+            new_fee_account_pubkey,
+        );
+
+        let expected_data = vec![103];
+
+        let expected_account = vec![
+            AccountMeta {
+                pubkey: config_pubkey,
+                is_signer: false,
+                is_writable: false,
+            },
+            AccountMeta {
+                pubkey: swap_pubkey,
+                is_signer: false,
+                is_writable: true,
+            },
+            AccountMeta {
+                pubkey: authority_pubkey,
+                is_signer: false,
+                is_writable: false,
+            },
+            AccountMeta {
+                pubkey: admin_pubkey,
+                is_signer: true,
+                is_writable: false,
+            },
+            AccountMeta {
+                pubkey: new_fee_account_pubkey,
+                is_signer: false,
+                is_writable: false,
+            },
+            AccountMeta {
+                pubkey: spl_token::id(),
+                is_signer: false,
+                is_writable: false,
+            },
+        ];
+
+        assert_eq!(result.as_ref().unwrap().data, expected_data);
+        assert_eq!(result.as_ref().unwrap().accounts, expected_account);
     }
 
     #[test]
-    fn test_pack_set_slope() {
-        let slope = 123_123_123u64;
-        let check = AdminInstruction::SetSlope(slope);
-        let packed = check.pack();
-        let mut expect = vec![108];
-        expect.extend_from_slice(&slope.to_le_bytes());
-        assert_eq!(packed, expect);
-        let unpacked = AdminInstruction::unpack(&expect).unwrap();
-        assert_eq!(unpacked, check);
-    }
+    fn test_commit_new_admin() {
+        let program_id = Pubkey::new_unique();
+        let config_pubkey = Pubkey::new_unique();
+        let admin_pubkey = Pubkey::new_unique();
+        let deltafi_mint_pubkey = Pubkey::new_unique();
+        let new_admin_key = Pubkey::new_unique();
 
-    #[test]
-    fn test_pack_set_decimals() {
-        let base_decimals = 9u8;
-        let quote_decimals = 6u8;
-        let check = AdminInstruction::SetDecimals(base_decimals, quote_decimals);
-        let packed = check.pack();
-        let mut expect = vec![109];
-        expect.extend_from_slice(&base_decimals.to_le_bytes());
-        expect.extend_from_slice(&quote_decimals.to_le_bytes());
-        assert_eq!(packed, expect);
-        let unpacked = AdminInstruction::unpack(&expect).unwrap();
-        assert_eq!(unpacked, check);
-    }
+        let result = commit_new_admin(
+            program_id,
+            config_pubkey,
+            admin_pubkey,
+            deltafi_mint_pubkey,
+            new_admin_key,
+        );
 
-    #[test]
-    fn test_pack_set_swap_limit() {
-        let swap_out_limit_percentage = 10u8;
-        let check = AdminInstruction::SetSwapLimit(swap_out_limit_percentage);
-        let packed = check.pack();
-        let mut expect = vec![110];
-        expect.extend_from_slice(&swap_out_limit_percentage.to_le_bytes());
-        assert_eq!(packed, expect);
-        let unpacked = AdminInstruction::unpack(&expect).unwrap();
-        assert_eq!(unpacked, check);
-    }
+        let mut expected_data = vec![104];
+        expected_data.extend_from_slice(new_admin_key.as_ref());
 
-    #[test]
-    fn test_pack_admin_pause() {
-        let check = AdminInstruction::Pause;
-        let packed = check.pack();
-        let expect = vec![101];
-        assert_eq!(packed, expect);
-        let unpacked = AdminInstruction::unpack(&expect).unwrap();
-        assert_eq!(unpacked, check);
-    }
+        let expected_account = vec![
+            AccountMeta {
+                pubkey: config_pubkey,
+                is_signer: false,
+                is_writable: true,
+            },
+            AccountMeta {
+                pubkey: admin_pubkey,
+                is_signer: true,
+                is_writable: false,
+            },
+            AccountMeta {
+                pubkey: deltafi_mint_pubkey,
+                is_signer: false,
+                is_writable: true,
+            },
+            AccountMeta {
+                pubkey: spl_token::id(),
+                is_signer: false,
+                is_writable: false,
+            },
+        ];
 
-    #[test]
-    fn test_pack_admin_unpause() {
-        let check = AdminInstruction::Unpause;
-        let packed = check.pack();
-        let expect = vec![102];
-        assert_eq!(packed, expect);
-        let unpacked = AdminInstruction::unpack(&expect).unwrap();
-        assert_eq!(unpacked, check);
+        assert_eq!(result.as_ref().unwrap().data, expected_data);
+        assert_eq!(result.as_ref().unwrap().accounts, expected_account);
     }
 
     #[test]
-    fn test_pack_admin_set_fee_account() {
-        let check = AdminInstruction::SetFeeAccount;
-        let packed = check.pack();
-        let expect = vec![103];
-        assert_eq!(packed, expect);
-        let unpacked = AdminInstruction::unpack(&expect).unwrap();
-        assert_eq!(unpacked, check);
-    }
+    fn test_set_new_fees() {
+        let program_id = Pubkey::new_unique();
+        let config_pubkey = Pubkey::new_unique();
+        let swap_pubkey = Pubkey::new_unique();
+        let admin_pubkey = Pubkey::new_unique();
+        let new_fees = DEFAULT_TEST_FEES;
 
-    #[test]
-    fn test_pack_admin_error() {
-        let result = AdminInstruction::unpack(&[111, 2]);
-        let expect = Err(ProgramError::from(SwapError::InvalidInstruction));
-        assert_eq!(expect, result);
+        let result = set_new_fees(
+            program_id,
+            config_pubkey,
+            swap_pubkey,
+            admin_pubkey,
+            new_fees.clone(),
+        );
+
+        let mut expected_data = vec![105];
+        let is_initialized = vec![1, new_fees.is_initialized as u8];
+        expected_data.extend_from_slice(&is_initialized[0].to_le_bytes());
+        expected_data.extend_from_slice(&new_fees.admin_trade_fee_numerator.to_le_bytes());
+        expected_data.extend_from_slice(&new_fees.admin_trade_fee_denominator.to_le_bytes());
+        expected_data.extend_from_slice(&new_fees.admin_withdraw_fee_numerator.to_le_bytes());
+        expected_data.extend_from_slice(&new_fees.admin_withdraw_fee_denominator.to_le_bytes());
+        expected_data.extend_from_slice(&new_fees.trade_fee_numerator.to_le_bytes());
+        expected_data.extend_from_slice(&new_fees.trade_fee_denominator.to_le_bytes());
+        expected_data.extend_from_slice(&new_fees.withdraw_fee_numerator.to_le_bytes());
+        expected_data.extend_from_slice(&new_fees.withdraw_fee_denominator.to_le_bytes());
+        expected_data.extend_from_slice(&new_fees.flash_fee_numerator.to_le_bytes());
+        expected_data.extend_from_slice(&new_fees.flash_fee_denominator.to_le_bytes());
+        expected_data.extend_from_slice(&new_fees.dynamic_fee_factor.to_le_bytes());
+        expected_data.extend_from_slice(&new_fees.max_dynamic_fee_multiplier.to_le_bytes());
+        expected_data.extend_from_slice(&new_fees.treasury_fee_numerator.to_le_bytes());
+        expected_data.extend_from_slice(&new_fees.treasury_fee_denominator.to_le_bytes());
+
+        let expected_account = vec![
+            AccountMeta {
+                pubkey: config_pubkey,
+                is_signer: false,
+                is_writable: false,
+            },
+            AccountMeta {
+                pubkey: swap_pubkey,
+                is_signer: false,
+                is_writable: true,
+            },
+            AccountMeta {
+                pubkey: admin_pubkey,
+                is_signer: true,
+                is_writable: false,
+            },
+        ];
+
+        assert_eq!(result.as_ref().unwrap().data, expected_data);
+        assert_eq!(result.as_ref().unwrap().accounts, expected_account);
     }
 
     #[test]
-    fn test_initialize_config() {
+    fn test_set_new_rewards() {
         let program_id = Pubkey::new_unique();
         let config_pubkey = Pubkey::new_unique();
-        let market_authority_pubkey = Pubkey::new_unique();
-        let deltafi_mint_pubkey = Pubkey::new_unique();
+        let swap_pubkey = Pubkey::new_unique();
         let admin_pubkey = Pubkey::new_unique();
-        let pyth_program_id = Pubkey::new_unique();
-        let fees = DEFAULT_TEST_FEES;
-        let rewards = DEFAULT_TEST_REWARDS;
-        let deltafi_token_pubkey = Pubkey::new_unique();
+        let new_rewards = DEFAULT_TEST_REWARDS;
 
-        let result = initialize_config(
+        let result = set_new_rewards(
             program_id,
             config_pubkey,
-            market_authority_pubkey,
-            deltafi_mint_pubkey,
+            swap_pubkey,
             admin_pubkey,
-            pyth_program_id,
-            fees.clone(),
-            rewards.clone(),
-            deltafi_token_pubkey,
+            new_rewards.clone(),
         );
 
-        assert!(result.is_ok());
-
-        let mut expected_data = vec![100];
-        let is_initialized = vec![1, fees.is_initialized as u8];
-        expected_data.extend_from_slice(&is_initialized[0].to_le_bytes());
-        expected_data.extend_from_slice(&fees.admin_trade_fee_numerator.to_le_bytes());
-        expected_data.extend_from_slice(&fees.admin_trade_fee_denominator.to_le_bytes());
-        expected_data.extend_from_slice(&fees.admin_withdraw_fee_numerator.to_le_bytes());
-        expected_data.extend_from_slice(&fees.admin_withdraw_fee_denominator.to_le_bytes());
-        expected_data.extend_from_slice(&fees.trade_fee_numerator.to_le_bytes());
-        expected_data.extend_from_slice(&fees.trade_fee_denominator.to_le_bytes());
-        expected_data.extend_from_slice(&fees.withdraw_fee_numerator.to_le_bytes());
-        expected_data.extend_from_slice(&fees.withdraw_fee_denominator.to_le_bytes());
-        let is_initialized = vec![1, rewards.is_initialized as u8];
+        let mut expected_data = vec![106];
+        let is_initialized = vec![1, new_rewards.is_initialized as u8];
         expected_data.extend_from_slice(&is_initialized[0].to_le_bytes());
-        expected_data.extend_from_slice(&rewards.decimals.to_le_bytes());
-        expected_data.extend_from_slice(&rewards.reserved);
-        expected_data.extend_from_slice(&rewards.trade_reward_numerator.to_le_bytes());
-        expected_data.extend_from_slice(&rewards.trade_reward_denominator.to_le_bytes());
-        expected_data.extend_from_slice(&rewards.trade_reward_cap.to_le_bytes());
+        expected_data.extend_from_slice(&new_rewards.decimals.to_le_bytes());
+        expected_data.extend_from_slice(&new_rewards.referral_fee_bps.to_le_bytes());
+        expected_data.extend_from_slice(&new_rewards.reserved);
+        expected_data.extend_from_slice(&new_rewards.trade_reward_numerator.to_le_bytes());
+        expected_data.extend_from_slice(&new_rewards.trade_reward_denominator.to_le_bytes());
+        expected_data.extend_from_slice(&new_rewards.trade_reward_cap.to_le_bytes());
 
         let expected_account = vec![
             AccountMeta {
                 pubkey: config_pubkey,
-                is_signer: true,
+                is_signer: false,
+                is_writable: false,
+            },
+            AccountMeta {
+                pubkey: swap_pubkey,
+                is_signer: false,
                 is_writable: true,
             },
             AccountMeta {
-                pubkey: market_authority_pubkey,
+                pubkey: admin_pubkey,
+                is_signer: true,
+                is_writable: false,
+            },
+        ];
+
+        assert_eq!(result.as_ref().unwrap().data, expected_data);
+        assert_eq!(result.as_ref().unwrap().accounts, expected_account);
+    }
+
+    #[test]
+    fn test_set_farm_rewards() {
+        let program_id = Pubkey::new_unique();
+        let config_pubkey = Pubkey::new_unique();
+        let farm_pool_info = Pubkey::new_unique();
+        let admin_pubkey = Pubkey::new_unique();
+        let new_rewards = FarmRewards {
+            apr_numerator: DEFAULT_TEST_REWARDS.trade_reward_numerator,
+            apr_denominator: DEFAULT_TEST_REWARDS.trade_reward_denominator,
+        };
+
+        let result = set_farm_rewards(
+            program_id,
+            config_pubkey,
+            farm_pool_info,
+            admin_pubkey,
+            new_rewards.clone(),
+        );
+
+        let mut expected_data = vec![107];
+        expected_data.extend_from_slice(&new_rewards.apr_numerator.to_le_bytes());
+        expected_data.extend_from_slice(&new_rewards.apr_denominator.to_le_bytes());
+
+        let expected_account = vec![
+            AccountMeta {
+                pubkey: config_pubkey,
                 is_signer: false,
                 is_writable: false,
             },
             AccountMeta {
-                pubkey: deltafi_mint_pubkey,
+                pubkey: farm_pool_info,
                 is_signer: false,
-                is_writable: false,
+                is_writable: true,
             },
             AccountMeta {
                 pubkey: admin_pubkey,
                 is_signer: true,
                 is_writable: false,
             },
+        ];
+
+        assert_eq!(result.as_ref().unwrap().data, expected_data);
+        assert_eq!(result.as_ref().unwrap().accounts, expected_account);
+    }
+
+    #[test]
+    fn test_set_slope() {
+        let program_id = Pubkey::new_unique();
+        let config_pubkey = Pubkey::new_unique();
+        let pool_info = Pubkey::new_unique();
+        let admin_pubkey = Pubkey::new_unique();
+        let slope = 123_123_123u64;
+
+        let result = set_slope(program_id, config_pubkey, pool_info, admin_pubkey, slope);
+
+        let mut expected_data = vec![108];
+        expected_data.extend_from_slice(&slope.to_le_bytes());
+
+        let expected_account = vec![
             AccountMeta {
-                pubkey: rent::id(),
-                is_signer: false,
-                is_writable: false,
-            },
-            AccountMeta {
-                pubkey: spl_token::id(),
+                pubkey: config_pubkey,
                 is_signer: false,
                 is_writable: false,
             },
             AccountMeta {
-                pubkey: pyth_program_id,
+                pubkey: pool_info,
                 is_signer: false,
-                is_writable: false,
+                is_writable: true,
             },
             AccountMeta {
-                pubkey: deltafi_token_pubkey,
-                is_signer: false,
+                pubkey: admin_pubkey,
+                is_signer: true,
                 is_writable: false,
             },
         ];
@@ -2223,14 +8809,26 @@ mod tests {
     }
 
     #[test]
-    fn test_pause() {
+    fn test_set_decimals() {
         let program_id = Pubkey::new_unique();
         let config_pubkey = Pubkey::new_unique();
-        let swap_pubkey = Pubkey::new_unique();
+        let pool_info = Pubkey::new_unique();
         let admin_pubkey = Pubkey::new_unique();
+        let token_a_decimals = 6u8;
+        let token_b_decimals = 9u8;
 
-        let result = pause(program_id, config_pubkey, swap_pubkey, admin_pubkey);
-        let expected_data = vec![101];
+        let result = set_decimals(
+            program_id,
+            config_pubkey,
+            pool_info,
+            admin_pubkey,
+            token_a_decimals,
+            token_b_decimals,
+        );
+
+        let mut expected_data = vec![109];
+        expected_data.extend_from_slice(&token_a_decimals.to_le_bytes());
+        expected_data.extend_from_slice(&token_b_decimals.to_le_bytes());
 
         let expected_account = vec![
             AccountMeta {
@@ -2239,7 +8837,7 @@ mod tests {
                 is_writable: false,
             },
             AccountMeta {
-                pubkey: swap_pubkey,
+                pubkey: pool_info,
                 is_signer: false,
                 is_writable: true,
             },
@@ -2255,14 +8853,23 @@ mod tests {
     }
 
     #[test]
-    fn test_unpause() {
+    fn test_set_swap_limit() {
         let program_id = Pubkey::new_unique();
         let config_pubkey = Pubkey::new_unique();
-        let swap_pubkey = Pubkey::new_unique();
+        let pool_info = Pubkey::new_unique();
         let admin_pubkey = Pubkey::new_unique();
+        let swap_out_limit_percentage = 10u8;
 
-        let result = unpause(program_id, config_pubkey, swap_pubkey, admin_pubkey);
-        let expected_data = vec![102];
+        let result = set_swap_limit(
+            program_id,
+            config_pubkey,
+            pool_info,
+            admin_pubkey,
+            swap_out_limit_percentage,
+        );
+
+        let mut expected_data = vec![110];
+        expected_data.extend_from_slice(&swap_out_limit_percentage.to_le_bytes());
 
         let expected_account = vec![
             AccountMeta {
@@ -2271,7 +8878,7 @@ mod tests {
                 is_writable: false,
             },
             AccountMeta {
-                pubkey: swap_pubkey,
+                pubkey: pool_info,
                 is_signer: false,
                 is_writable: true,
             },
@@ -2287,24 +8894,23 @@ mod tests {
     }
 
     #[test]
-    fn test_set_fee_account() {
+    fn test_set_price_jump_policy() {
         let program_id = Pubkey::new_unique();
         let config_pubkey = Pubkey::new_unique();
-        let swap_pubkey = Pubkey::new_unique();
-        let authority_pubkey = Pubkey::new_unique();
+        let pool_info = Pubkey::new_unique();
         let admin_pubkey = Pubkey::new_unique();
-        let new_fee_account_pubkey = Pubkey::new_unique();
+        let price_jump_policy = 1u8;
 
-        let result = set_fee_account(
+        let result = set_price_jump_policy(
             program_id,
             config_pubkey,
-            swap_pubkey,
-            authority_pubkey,
+            pool_info,
             admin_pubkey,
-            new_fee_account_pubkey,
+            price_jump_policy,
         );
 
-        let expected_data = vec![103];
+        let mut expected_data = vec![111];
+        expected_data.extend_from_slice(&price_jump_policy.to_le_bytes());
 
         let expected_account = vec![
             AccountMeta {
@@ -2313,28 +8919,54 @@ mod tests {
                 is_writable: false,
             },
             AccountMeta {
-                pubkey: swap_pubkey,
+                pubkey: pool_info,
                 is_signer: false,
                 is_writable: true,
             },
-            AccountMeta {
-                pubkey: authority_pubkey,
-                is_signer: false,
-                is_writable: false,
-            },
             AccountMeta {
                 pubkey: admin_pubkey,
                 is_signer: true,
                 is_writable: false,
             },
+        ];
+
+        assert_eq!(result.as_ref().unwrap().data, expected_data);
+        assert_eq!(result.as_ref().unwrap().accounts, expected_account);
+    }
+
+    #[test]
+    fn test_set_max_price_divergence() {
+        let program_id = Pubkey::new_unique();
+        let config_pubkey = Pubkey::new_unique();
+        let pool_info = Pubkey::new_unique();
+        let admin_pubkey = Pubkey::new_unique();
+        let max_price_divergence = 200u16;
+
+        let result = set_max_price_divergence(
+            program_id,
+            config_pubkey,
+            pool_info,
+            admin_pubkey,
+            max_price_divergence,
+        );
+
+        let mut expected_data = vec![112];
+        expected_data.extend_from_slice(&max_price_divergence.to_le_bytes());
+
+        let expected_account = vec![
             AccountMeta {
-                pubkey: new_fee_account_pubkey,
+                pubkey: config_pubkey,
                 is_signer: false,
                 is_writable: false,
             },
             AccountMeta {
-                pubkey: spl_token::id(),
+                pubkey: pool_info,
                 is_signer: false,
+                is_writable: true,
+            },
+            AccountMeta {
+                pubkey: admin_pubkey,
+                is_signer: true,
                 is_writable: false,
             },
         ];
@@ -2344,23 +8976,20 @@ mod tests {
     }
 
     #[test]
-    fn test_commit_new_admin() {
+    fn test_set_orderbook_program() {
         let program_id = Pubkey::new_unique();
         let config_pubkey = Pubkey::new_unique();
         let admin_pubkey = Pubkey::new_unique();
-        let deltafi_mint_pubkey = Pubkey::new_unique();
-        let new_admin_key = Pubkey::new_unique();
+        let orderbook_program_id = Pubkey::new_unique();
 
-        let result = commit_new_admin(
+        let result = set_orderbook_program(
             program_id,
             config_pubkey,
             admin_pubkey,
-            deltafi_mint_pubkey,
-            new_admin_key,
+            orderbook_program_id,
         );
 
-        let mut expected_data = vec![104];
-        expected_data.extend_from_slice(new_admin_key.as_ref());
+        let expected_data = vec![113];
 
         let expected_account = vec![
             AccountMeta {
@@ -2374,12 +9003,7 @@ mod tests {
                 is_writable: false,
             },
             AccountMeta {
-                pubkey: deltafi_mint_pubkey,
-                is_signer: false,
-                is_writable: true,
-            },
-            AccountMeta {
-                pubkey: spl_token::id(),
+                pubkey: orderbook_program_id,
                 is_signer: false,
                 is_writable: false,
             },
@@ -2390,34 +9014,25 @@ mod tests {
     }
 
     #[test]
-    fn test_set_new_fees() {
+    fn test_set_exchange_rate_adapter() {
         let program_id = Pubkey::new_unique();
         let config_pubkey = Pubkey::new_unique();
         let swap_pubkey = Pubkey::new_unique();
         let admin_pubkey = Pubkey::new_unique();
-        let new_fees = DEFAULT_TEST_FEES;
+        let exchange_rate_pubkey = Pubkey::new_unique();
 
-        let result = set_new_fees(
+        let result = set_exchange_rate_adapter(
             program_id,
             config_pubkey,
             swap_pubkey,
             admin_pubkey,
-            new_fees.clone(),
+            Some(exchange_rate_pubkey),
+            1u8,
         );
 
-        let mut expected_data = vec![105];
-        let is_initialized = vec![1, new_fees.is_initialized as u8];
-        expected_data.extend_from_slice(&is_initialized[0].to_le_bytes());
-        expected_data.extend_from_slice(&new_fees.admin_trade_fee_numerator.to_le_bytes());
-        expected_data.extend_from_slice(&new_fees.admin_trade_fee_denominator.to_le_bytes());
-        expected_data.extend_from_slice(&new_fees.admin_withdraw_fee_numerator.to_le_bytes());
-        expected_data.extend_from_slice(&new_fees.admin_withdraw_fee_denominator.to_le_bytes());
-        expected_data.extend_from_slice(&new_fees.trade_fee_numerator.to_le_bytes());
-        expected_data.extend_from_slice(&new_fees.trade_fee_denominator.to_le_bytes());
-        expected_data.extend_from_slice(&new_fees.withdraw_fee_numerator.to_le_bytes());
-        expected_data.extend_from_slice(&new_fees.withdraw_fee_denominator.to_le_bytes());
+        let expected_data = vec![114, 1];
 
-        let expected_account = vec![
+        let mut expected_account = vec![
             AccountMeta {
                 pubkey: config_pubkey,
                 is_signer: false,
@@ -2433,36 +9048,54 @@ mod tests {
                 is_signer: true,
                 is_writable: false,
             },
+            AccountMeta {
+                pubkey: exchange_rate_pubkey,
+                is_signer: false,
+                is_writable: false,
+            },
         ];
 
         assert_eq!(result.as_ref().unwrap().data, expected_data);
         assert_eq!(result.as_ref().unwrap().accounts, expected_account);
+
+        let result = set_exchange_rate_adapter(
+            program_id,
+            config_pubkey,
+            swap_pubkey,
+            admin_pubkey,
+            None,
+            0u8,
+        );
+        expected_account.pop();
+
+        assert_eq!(result.as_ref().unwrap().data, vec![114, 0]);
+        assert_eq!(result.as_ref().unwrap().accounts, expected_account);
     }
 
     #[test]
-    fn test_set_new_rewards() {
+    fn test_set_oracle_params() {
         let program_id = Pubkey::new_unique();
         let config_pubkey = Pubkey::new_unique();
         let swap_pubkey = Pubkey::new_unique();
         let admin_pubkey = Pubkey::new_unique();
-        let new_rewards = DEFAULT_TEST_REWARDS;
+        let oracle_params = OracleParams {
+            stale_slots: 25u16,
+            max_confidence_bps: 500u16,
+            min_publishers: 5u8,
+        };
 
-        let result = set_new_rewards(
+        let result = set_oracle_params(
             program_id,
             config_pubkey,
             swap_pubkey,
             admin_pubkey,
-            new_rewards.clone(),
+            oracle_params,
         );
 
-        let mut expected_data = vec![106];
-        let is_initialized = vec![1, new_rewards.is_initialized as u8];
-        expected_data.extend_from_slice(&is_initialized[0].to_le_bytes());
-        expected_data.extend_from_slice(&new_rewards.decimals.to_le_bytes());
-        expected_data.extend_from_slice(&new_rewards.reserved);
-        expected_data.extend_from_slice(&new_rewards.trade_reward_numerator.to_le_bytes());
-        expected_data.extend_from_slice(&new_rewards.trade_reward_denominator.to_le_bytes());
-        expected_data.extend_from_slice(&new_rewards.trade_reward_cap.to_le_bytes());
+        let mut expected_data = vec![115];
+        expected_data.extend_from_slice(&oracle_params.stale_slots.to_le_bytes());
+        expected_data.extend_from_slice(&oracle_params.max_confidence_bps.to_le_bytes());
+        expected_data.push(oracle_params.min_publishers);
 
         let expected_account = vec![
             AccountMeta {
@@ -2487,44 +9120,106 @@ mod tests {
     }
 
     #[test]
-    fn test_set_farm_rewards() {
+    fn test_set_market_maker() {
         let program_id = Pubkey::new_unique();
         let config_pubkey = Pubkey::new_unique();
-        let farm_pool_info = Pubkey::new_unique();
         let admin_pubkey = Pubkey::new_unique();
-        let new_rewards = FarmRewards {
-            apr_numerator: DEFAULT_TEST_REWARDS.trade_reward_numerator,
-            apr_denominator: DEFAULT_TEST_REWARDS.trade_reward_denominator,
-        };
+        let market_maker_pubkey = Pubkey::new_unique();
+        let index = 1u8;
 
-        let result = set_farm_rewards(
+        let result = set_market_maker(
             program_id,
             config_pubkey,
-            farm_pool_info,
             admin_pubkey,
-            new_rewards.clone(),
+            market_maker_pubkey,
+            index,
         );
 
-        let mut expected_data = vec![107];
-        expected_data.extend_from_slice(&new_rewards.apr_numerator.to_le_bytes());
-        expected_data.extend_from_slice(&new_rewards.apr_denominator.to_le_bytes());
+        let expected_data = vec![116, index];
+        let expected_account = vec![
+            AccountMeta::new(config_pubkey, false),
+            AccountMeta::new_readonly(admin_pubkey, true),
+            AccountMeta::new_readonly(market_maker_pubkey, false),
+        ];
+
+        assert_eq!(result.as_ref().unwrap().data, expected_data);
+        assert_eq!(result.as_ref().unwrap().accounts, expected_account);
+    }
+
+    #[test]
+    fn test_set_admin_role() {
+        let program_id = Pubkey::new_unique();
+        let config_pubkey = Pubkey::new_unique();
+        let admin_pubkey = Pubkey::new_unique();
+        let role_pubkey = Pubkey::new_unique();
+        let role = 2u8;
+
+        let result = set_admin_role(program_id, config_pubkey, admin_pubkey, role_pubkey, role);
 
+        let expected_data = vec![121, role];
         let expected_account = vec![
-            AccountMeta {
-                pubkey: config_pubkey,
-                is_signer: false,
-                is_writable: false,
-            },
-            AccountMeta {
-                pubkey: farm_pool_info,
-                is_signer: false,
-                is_writable: true,
-            },
-            AccountMeta {
-                pubkey: admin_pubkey,
-                is_signer: true,
-                is_writable: false,
-            },
+            AccountMeta::new(config_pubkey, false),
+            AccountMeta::new_readonly(admin_pubkey, true),
+            AccountMeta::new_readonly(role_pubkey, false),
+        ];
+
+        assert_eq!(result.as_ref().unwrap().data, expected_data);
+        assert_eq!(result.as_ref().unwrap().accounts, expected_account);
+    }
+
+    #[test]
+    fn test_pause_all() {
+        let program_id = Pubkey::new_unique();
+        let config_pubkey = Pubkey::new_unique();
+        let guardian_pubkey = Pubkey::new_unique();
+        let swap_pubkeys = [Pubkey::new_unique(), Pubkey::new_unique()];
+
+        let result = pause_all(program_id, config_pubkey, guardian_pubkey, &swap_pubkeys);
+
+        let expected_data = vec![122];
+        let expected_account = vec![
+            AccountMeta::new_readonly(config_pubkey, false),
+            AccountMeta::new_readonly(guardian_pubkey, true),
+            AccountMeta::new(swap_pubkeys[0], false),
+            AccountMeta::new(swap_pubkeys[1], false),
+        ];
+
+        assert_eq!(result.as_ref().unwrap().data, expected_data);
+        assert_eq!(result.as_ref().unwrap().accounts, expected_account);
+    }
+
+    #[test]
+    fn test_rotate_fee_account() {
+        let program_id = Pubkey::new_unique();
+        let config_pubkey = Pubkey::new_unique();
+        let swap_pubkey = Pubkey::new_unique();
+        let authority_pubkey = Pubkey::new_unique();
+        let admin_pubkey = Pubkey::new_unique();
+        let old_fee_account_pubkey = Pubkey::new_unique();
+        let new_fee_account_pubkey = Pubkey::new_unique();
+        let destination_pubkey = Pubkey::new_unique();
+
+        let result = rotate_fee_account(
+            program_id,
+            config_pubkey,
+            swap_pubkey,
+            authority_pubkey,
+            admin_pubkey,
+            old_fee_account_pubkey,
+            new_fee_account_pubkey,
+            destination_pubkey,
+        );
+
+        let expected_data = vec![117];
+        let expected_account = vec![
+            AccountMeta::new_readonly(config_pubkey, false),
+            AccountMeta::new(swap_pubkey, false),
+            AccountMeta::new_readonly(authority_pubkey, false),
+            AccountMeta::new_readonly(admin_pubkey, true),
+            AccountMeta::new(old_fee_account_pubkey, false),
+            AccountMeta::new(new_fee_account_pubkey, false),
+            AccountMeta::new(destination_pubkey, false),
+            AccountMeta::new_readonly(spl_token::id(), false),
         ];
 
         assert_eq!(result.as_ref().unwrap().data, expected_data);
@@ -2532,34 +9227,32 @@ mod tests {
     }
 
     #[test]
-    fn test_set_slope() {
+    fn test_commit_initial_price() {
         let program_id = Pubkey::new_unique();
         let config_pubkey = Pubkey::new_unique();
-        let pool_info = Pubkey::new_unique();
+        let price_commitment_pubkey = Pubkey::new_unique();
+        let swap_pubkey = Pubkey::new_unique();
         let admin_pubkey = Pubkey::new_unique();
-        let slope = 123_123_123u64;
-
-        let result = set_slope(program_id, config_pubkey, pool_info, admin_pubkey, slope);
+        let price_hash = [3u8; 32];
 
-        let mut expected_data = vec![108];
-        expected_data.extend_from_slice(&slope.to_le_bytes());
+        let result = commit_initial_price(
+            program_id,
+            config_pubkey,
+            price_commitment_pubkey,
+            swap_pubkey,
+            admin_pubkey,
+            price_hash,
+        );
 
+        let mut expected_data = vec![118];
+        expected_data.extend_from_slice(&price_hash);
         let expected_account = vec![
-            AccountMeta {
-                pubkey: config_pubkey,
-                is_signer: false,
-                is_writable: false,
-            },
-            AccountMeta {
-                pubkey: pool_info,
-                is_signer: false,
-                is_writable: true,
-            },
-            AccountMeta {
-                pubkey: admin_pubkey,
-                is_signer: true,
-                is_writable: false,
-            },
+            AccountMeta::new_readonly(config_pubkey, false),
+            AccountMeta::new(price_commitment_pubkey, false),
+            AccountMeta::new_readonly(swap_pubkey, false),
+            AccountMeta::new_readonly(admin_pubkey, true),
+            AccountMeta::new_readonly(clock::id(), false),
+            AccountMeta::new_readonly(rent::id(), false),
         ];
 
         assert_eq!(result.as_ref().unwrap().data, expected_data);
@@ -2567,177 +9260,879 @@ mod tests {
     }
 
     #[test]
-    fn test_set_decimals() {
-        let program_id = Pubkey::new_unique();
-        let config_pubkey = Pubkey::new_unique();
-        let pool_info = Pubkey::new_unique();
-        let admin_pubkey = Pubkey::new_unique();
-        let token_a_decimals = 6u8;
+    fn test_pack_swap_initialization() {
+        let nonce: u8 = 255;
+        let slope: u64 = default_slope().to_scaled_val().unwrap().try_into().unwrap();
+        let mid_price = default_market_price().to_scaled_val().unwrap();
+        let token_a_decimals = 9u8;
         let token_b_decimals = 9u8;
-
-        let result = set_decimals(
-            program_id,
-            config_pubkey,
-            pool_info,
-            admin_pubkey,
+        let token_a_amount = 1000u64;
+        let token_b_amount = 2000u64;
+        let oracle_priority_flags = 0u8;
+        let quote_is_token_a = true;
+        let check = SwapInstruction::Initialize(InitializeData {
+            nonce,
+            slope,
+            mid_price,
             token_a_decimals,
             token_b_decimals,
+            token_a_amount,
+            token_b_amount,
+            oracle_priority_flags,
+            quote_is_token_a,
+        });
+        let packed = check.pack();
+        let mut expect = vec![0];
+        expect.extend_from_slice(&nonce.to_le_bytes());
+        expect.extend_from_slice(&slope.to_le_bytes());
+        expect.extend_from_slice(&mid_price.to_le_bytes());
+        expect.extend_from_slice(&token_a_decimals.to_le_bytes());
+        expect.extend_from_slice(&token_b_decimals.to_le_bytes());
+        expect.extend_from_slice(&token_a_amount.to_le_bytes());
+        expect.extend_from_slice(&token_b_amount.to_le_bytes());
+        expect.extend_from_slice(&oracle_priority_flags.to_le_bytes());
+        expect.push(quote_is_token_a as u8);
+        assert_eq!(packed, expect);
+        let unpacked = SwapInstruction::unpack(&expect).unwrap();
+        assert_eq!(unpacked, check);
+
+        *expect.last_mut().unwrap() = 2;
+        assert_eq!(
+            SwapInstruction::unpack(&expect),
+            Err(SwapError::InvalidInstruction.into())
         );
+    }
 
-        let mut expected_data = vec![109];
-        expected_data.extend_from_slice(&token_a_decimals.to_le_bytes());
-        expected_data.extend_from_slice(&token_b_decimals.to_le_bytes());
+    #[test]
+    fn test_pack_initialize_v2() {
+        let nonce: u8 = 255;
+        let slope: u64 = default_slope().to_scaled_val().unwrap().try_into().unwrap();
+        let mid_price = default_market_price().to_scaled_val().unwrap();
+        let token_a_decimals = 9u8;
+        let token_b_decimals = 6u8;
+        let token_a_amount = 1000u64;
+        let token_b_amount = 2000u64;
+        let oracle_priority_flags = 0u8;
+        let check = SwapInstruction::InitializeV2(InitializeData {
+            nonce,
+            slope,
+            mid_price,
+            token_a_decimals,
+            token_b_decimals,
+            token_a_amount,
+            token_b_amount,
+            oracle_priority_flags,
+            quote_is_token_a: true,
+        });
+        let packed = check.pack();
+        let mut expect = vec![35];
+        expect.extend_from_slice(&nonce.to_le_bytes());
+        expect.extend_from_slice(&slope.to_le_bytes());
+        expect.extend_from_slice(&mid_price.to_le_bytes());
+        expect.extend_from_slice(&token_a_decimals.to_le_bytes());
+        expect.extend_from_slice(&token_b_decimals.to_le_bytes());
+        expect.extend_from_slice(&token_a_amount.to_le_bytes());
+        expect.extend_from_slice(&token_b_amount.to_le_bytes());
+        expect.extend_from_slice(&oracle_priority_flags.to_le_bytes());
+        expect.push(1);
+        assert_eq!(packed, expect);
+        let unpacked = SwapInstruction::unpack(&expect).unwrap();
+        assert_eq!(unpacked, check);
+        assert!(matches!(
+            InstructionType::check(&expect),
+            Some(InstructionType::Swap)
+        ));
+    }
 
-        let expected_account = vec![
-            AccountMeta {
-                pubkey: config_pubkey,
-                is_signer: false,
-                is_writable: false,
-            },
-            AccountMeta {
-                pubkey: pool_info,
-                is_signer: false,
-                is_writable: true,
-            },
-            AccountMeta {
-                pubkey: admin_pubkey,
-                is_signer: true,
-                is_writable: false,
+    #[test]
+    fn test_pack_stable_initialize_v2() {
+        let nonce: u8 = 255;
+        let slope: u64 = default_slope().to_scaled_val().unwrap().try_into().unwrap();
+        let token_a_decimals = 6u8;
+        let token_b_decimals = 6u8;
+        let token_a_amount = 1000u64;
+        let token_b_amount = 2000u64;
+        let check = StableSwapInstruction::InitializeV2(StableInitializeData {
+            nonce,
+            slope,
+            token_a_decimals,
+            token_b_decimals,
+            token_a_amount,
+            token_b_amount,
+        });
+        let packed = check.pack();
+        let mut expect = vec![28];
+        expect.extend_from_slice(&nonce.to_le_bytes());
+        expect.extend_from_slice(&slope.to_le_bytes());
+        expect.extend_from_slice(&token_a_decimals.to_le_bytes());
+        expect.extend_from_slice(&token_b_decimals.to_le_bytes());
+        expect.extend_from_slice(&token_a_amount.to_le_bytes());
+        expect.extend_from_slice(&token_b_amount.to_le_bytes());
+        assert_eq!(packed, expect);
+        let unpacked = StableSwapInstruction::unpack(&expect).unwrap();
+        assert_eq!(unpacked, check);
+        assert!(matches!(
+            InstructionType::check(&expect),
+            Some(InstructionType::StableSwap)
+        ));
+    }
+
+    #[test]
+    fn test_pack_reveal_initialize() {
+        let nonce: u8 = 255;
+        let slope: u64 = default_slope().to_scaled_val().unwrap().try_into().unwrap();
+        let mid_price = default_market_price().to_scaled_val().unwrap();
+        let token_a_decimals = 9u8;
+        let token_b_decimals = 6u8;
+        let token_a_amount = 1000u64;
+        let token_b_amount = 2000u64;
+        let oracle_priority_flags = 0u8;
+        let salt = [5u8; 32];
+        let check = SwapInstruction::RevealInitialize(
+            InitializeData {
+                nonce,
+                slope,
+                mid_price,
+                token_a_decimals,
+                token_b_decimals,
+                token_a_amount,
+                token_b_amount,
+                oracle_priority_flags,
+                quote_is_token_a: false,
             },
-        ];
+            salt,
+        );
+        let packed = check.pack();
+        let mut expect = vec![18];
+        expect.extend_from_slice(&nonce.to_le_bytes());
+        expect.extend_from_slice(&slope.to_le_bytes());
+        expect.extend_from_slice(&mid_price.to_le_bytes());
+        expect.extend_from_slice(&token_a_decimals.to_le_bytes());
+        expect.extend_from_slice(&token_b_decimals.to_le_bytes());
+        expect.extend_from_slice(&token_a_amount.to_le_bytes());
+        expect.extend_from_slice(&token_b_amount.to_le_bytes());
+        expect.extend_from_slice(&oracle_priority_flags.to_le_bytes());
+        expect.push(0);
+        expect.extend_from_slice(&salt);
+        assert_eq!(packed, expect);
+        let unpacked = SwapInstruction::unpack(&expect).unwrap();
+        assert_eq!(unpacked, check);
+        assert!(matches!(
+            InstructionType::check(&expect),
+            Some(InstructionType::Swap)
+        ));
 
-        assert_eq!(result.as_ref().unwrap().data, expected_data);
-        assert_eq!(result.as_ref().unwrap().accounts, expected_account);
+        assert_eq!(
+            SwapInstruction::unpack(&expect[..expect.len() - 1]).unwrap_err(),
+            SwapError::InstructionUnpackError.into()
+        );
+    }
+
+    #[test]
+    fn test_pack_swap() {
+        let amount_in: u64 = 1_000_000;
+        let minimum_amount_out: u64 = 500_000;
+        let check = SwapInstruction::Swap(SwapData {
+            amount_in,
+            minimum_amount_out,
+            deadline_slot: None,
+            output_owner: None,
+        });
+        let packed = check.pack();
+        let mut expect = vec![1];
+        expect.extend_from_slice(&amount_in.to_le_bytes());
+        expect.extend_from_slice(&minimum_amount_out.to_le_bytes());
+        assert_eq!(packed, expect);
+        let unpacked = SwapInstruction::unpack(&expect).unwrap();
+        assert_eq!(unpacked, check);
+
+        let deadline_slot: u64 = 150_000;
+        let check = SwapInstruction::Swap(SwapData {
+            amount_in,
+            minimum_amount_out,
+            deadline_slot: Some(deadline_slot),
+            output_owner: None,
+        });
+        let packed = check.pack();
+        expect.extend_from_slice(&deadline_slot.to_le_bytes());
+        assert_eq!(packed, expect);
+        let unpacked = SwapInstruction::unpack(&expect).unwrap();
+        assert_eq!(unpacked, check);
+
+        // a truncated deadline slot is rejected
+        expect.pop();
+        assert_eq!(
+            SwapInstruction::unpack(&expect),
+            Err(SwapError::InstructionUnpackError.into())
+        );
+    }
+
+    #[test]
+    fn test_pack_swap_output_owner() {
+        let amount_in: u64 = 1_000_000;
+        let minimum_amount_out: u64 = 500_000;
+        let output_owner = Pubkey::new_unique();
+        let check = SwapInstruction::SwapV2(SwapData {
+            amount_in,
+            minimum_amount_out,
+            deadline_slot: None,
+            output_owner: Some(output_owner),
+        });
+        let packed = check.pack();
+        let mut expect = vec![5];
+        expect.extend_from_slice(&amount_in.to_le_bytes());
+        expect.extend_from_slice(&minimum_amount_out.to_le_bytes());
+        expect.extend_from_slice(&u64::MAX.to_le_bytes());
+        expect.extend_from_slice(output_owner.as_ref());
+        assert_eq!(packed, expect);
+        let unpacked = SwapInstruction::unpack(&expect).unwrap();
+        assert_eq!(unpacked, check);
+
+        let deadline_slot: u64 = 150_000;
+        let check = StableSwapInstruction::Swap(SwapData {
+            amount_in,
+            minimum_amount_out,
+            deadline_slot: Some(deadline_slot),
+            output_owner: Some(output_owner),
+        });
+        let packed = check.pack();
+        let mut expect = vec![11];
+        expect.extend_from_slice(&amount_in.to_le_bytes());
+        expect.extend_from_slice(&minimum_amount_out.to_le_bytes());
+        expect.extend_from_slice(&deadline_slot.to_le_bytes());
+        expect.extend_from_slice(output_owner.as_ref());
+        assert_eq!(packed, expect);
+        let unpacked = StableSwapInstruction::unpack(&expect).unwrap();
+        assert_eq!(unpacked, check);
+
+        // a truncated output owner is rejected
+        expect.pop();
+        assert_eq!(
+            StableSwapInstruction::unpack(&expect),
+            Err(SwapError::InstructionUnpackError.into())
+        );
+    }
+
+    #[test]
+    fn test_pack_swap_deposit() {
+        let token_a_amount: u64 = 1_000_000;
+        let token_b_amount: u64 = 500_000;
+        let min_mint_amount: u64 = 500_000;
+        let deadline_slot: u64 = 150_000;
+        let check = SwapInstruction::Deposit(DepositData {
+            token_a_amount,
+            token_b_amount,
+            min_mint_amount,
+            deadline_slot: Some(deadline_slot),
+        });
+        let packed = check.pack();
+        let mut expect = vec![2];
+        expect.extend_from_slice(&token_a_amount.to_le_bytes());
+        expect.extend_from_slice(&token_b_amount.to_le_bytes());
+        expect.extend_from_slice(&min_mint_amount.to_le_bytes());
+        expect.extend_from_slice(&deadline_slot.to_le_bytes());
+        assert_eq!(packed, expect);
+        let unpacked = SwapInstruction::unpack(&expect).unwrap();
+        assert_eq!(unpacked, check);
     }
 
     #[test]
-    fn test_set_swap_limit() {
-        let program_id = Pubkey::new_unique();
-        let config_pubkey = Pubkey::new_unique();
-        let pool_info = Pubkey::new_unique();
-        let admin_pubkey = Pubkey::new_unique();
-        let swap_out_limit_percentage = 10u8;
+    fn test_pack_swap_withdraw() {
+        let minimum_token_a_amount: u64 = 1_000_000;
+        let minimum_token_b_amount: u64 = 500_000;
+        let pool_token_amount: u64 = 500_000;
+        let deadline_slot: u64 = 150_000;
+        let check = SwapInstruction::Withdraw(WithdrawData {
+            pool_token_amount,
+            minimum_token_a_amount,
+            minimum_token_b_amount,
+            deadline_slot: Some(deadline_slot),
+        });
+        let packed = check.pack();
+        let mut expect = vec![3];
+        expect.extend_from_slice(&pool_token_amount.to_le_bytes());
+        expect.extend_from_slice(&minimum_token_a_amount.to_le_bytes());
+        expect.extend_from_slice(&minimum_token_b_amount.to_le_bytes());
+        expect.extend_from_slice(&deadline_slot.to_le_bytes());
+        assert_eq!(packed, expect);
+        let unpacked = SwapInstruction::unpack(&expect).unwrap();
+        assert_eq!(unpacked, check);
+    }
 
-        let result = set_swap_limit(
-            program_id,
-            config_pubkey,
-            pool_info,
-            admin_pubkey,
-            swap_out_limit_percentage,
-        );
+    #[test]
+    fn test_pack_swap_deposit_one() {
+        let amount_in: u64 = 1_000_000;
+        let min_mint_amount: u64 = 500_000;
+        let deadline_slot: u64 = 150_000;
+        let check = SwapInstruction::DepositOne(DepositOneData {
+            amount_in,
+            min_mint_amount,
+            deadline_slot: Some(deadline_slot),
+        });
+        let packed = check.pack();
+        let mut expect = vec![6];
+        expect.extend_from_slice(&amount_in.to_le_bytes());
+        expect.extend_from_slice(&min_mint_amount.to_le_bytes());
+        expect.extend_from_slice(&deadline_slot.to_le_bytes());
+        assert_eq!(packed, expect);
+        let unpacked = SwapInstruction::unpack(&expect).unwrap();
+        assert_eq!(unpacked, check);
 
-        let mut expected_data = vec![110];
-        expected_data.extend_from_slice(&swap_out_limit_percentage.to_le_bytes());
+        // The deposit one packed before the deadline has none
+        let check = StableSwapInstruction::DepositOne(DepositOneData {
+            amount_in,
+            min_mint_amount,
+            deadline_slot: None,
+        });
+        let packed = check.pack();
+        expect[0] = 15;
+        expect.truncate(expect.len() - 8);
+        assert_eq!(packed, expect);
+        let unpacked = StableSwapInstruction::unpack(&expect).unwrap();
+        assert_eq!(unpacked, check);
+    }
 
-        let expected_account = vec![
-            AccountMeta {
-                pubkey: config_pubkey,
-                is_signer: false,
-                is_writable: false,
-            },
-            AccountMeta {
-                pubkey: pool_info,
-                is_signer: false,
-                is_writable: true,
-            },
-            AccountMeta {
-                pubkey: admin_pubkey,
-                is_signer: true,
-                is_writable: false,
-            },
-        ];
+    #[test]
+    fn test_pack_swap_withdraw_one() {
+        let pool_token_amount: u64 = 1_000_000;
+        let minimum_amount_out: u64 = 500_000;
+        let token_side: u8 = 1;
+        let deadline_slot: u64 = 150_000;
+        let check = SwapInstruction::WithdrawOne(WithdrawOneData {
+            pool_token_amount,
+            minimum_amount_out,
+            token_side,
+            deadline_slot: Some(deadline_slot),
+        });
+        let packed = check.pack();
+        let mut expect = vec![7];
+        expect.extend_from_slice(&pool_token_amount.to_le_bytes());
+        expect.extend_from_slice(&minimum_amount_out.to_le_bytes());
+        expect.push(token_side);
+        expect.extend_from_slice(&deadline_slot.to_le_bytes());
+        assert_eq!(packed, expect);
+        let unpacked = SwapInstruction::unpack(&expect).unwrap();
+        assert_eq!(unpacked, check);
 
-        assert_eq!(result.as_ref().unwrap().data, expected_data);
-        assert_eq!(result.as_ref().unwrap().accounts, expected_account);
+        // The withdraw one packed before the deadline has none
+        let check = StableSwapInstruction::WithdrawOne(WithdrawOneData {
+            pool_token_amount,
+            minimum_amount_out,
+            token_side,
+            deadline_slot: None,
+        });
+        let packed = check.pack();
+        expect[0] = 16;
+        expect.truncate(expect.len() - 8);
+        assert_eq!(packed, expect);
+        let unpacked = StableSwapInstruction::unpack(&expect).unwrap();
+        assert_eq!(unpacked, check);
     }
 
     #[test]
-    fn test_pack_swap_initialization() {
+    fn test_pack_pegged_initialization() {
         let nonce: u8 = 255;
-        let slope: u64 = default_slope().to_scaled_val().unwrap().try_into().unwrap();
-        let mid_price = default_market_price().to_scaled_val().unwrap();
-        let token_a_decimals = 9u8;
-        let token_b_decimals = 9u8;
+        let pegged_price: u128 = 1_000_000_000_000_000_000;
+        let token_a_decimals = 8u8;
+        let token_b_decimals = 6u8;
         let token_a_amount = 1000u64;
         let token_b_amount = 2000u64;
-        let oracle_priority_flags = 0u8;
-        let check = SwapInstruction::Initialize(InitializeData {
+        let check = StableSwapInstruction::PeggedInitialize(PeggedInitializeData {
             nonce,
-            slope,
-            mid_price,
+            pegged_price,
             token_a_decimals,
             token_b_decimals,
             token_a_amount,
             token_b_amount,
-            oracle_priority_flags,
         });
         let packed = check.pack();
-        let mut expect = vec![0];
-        expect.extend_from_slice(&nonce.to_le_bytes());
-        expect.extend_from_slice(&slope.to_le_bytes());
-        expect.extend_from_slice(&mid_price.to_le_bytes());
-        expect.extend_from_slice(&token_a_decimals.to_le_bytes());
-        expect.extend_from_slice(&token_b_decimals.to_le_bytes());
+        let mut expect = vec![19, nonce];
+        expect.extend_from_slice(&pegged_price.to_le_bytes());
+        expect.push(token_a_decimals);
+        expect.push(token_b_decimals);
         expect.extend_from_slice(&token_a_amount.to_le_bytes());
         expect.extend_from_slice(&token_b_amount.to_le_bytes());
-        expect.extend_from_slice(&oracle_priority_flags.to_le_bytes());
+        assert_eq!(packed, expect);
+        let unpacked = StableSwapInstruction::unpack(&expect).unwrap();
+        assert_eq!(unpacked, check);
+        assert!(matches!(
+            InstructionType::check(&expect),
+            Some(InstructionType::StableSwap)
+        ));
+    }
+
+    #[test]
+    fn test_pack_tri_pool() {
+        let nonce: u8 = 255;
+        let amp = 100u64;
+        let token_decimals = [6u8, 6, 9];
+        let check = StableSwapInstruction::TriInitialize(TriInitializeData {
+            nonce,
+            amp,
+            token_decimals,
+        });
+        let packed = check.pack();
+        let mut expect = vec![47, nonce];
+        expect.extend_from_slice(&amp.to_le_bytes());
+        expect.extend_from_slice(&token_decimals);
+        assert_eq!(packed, expect);
+        let unpacked = StableSwapInstruction::unpack(&expect).unwrap();
+        assert_eq!(unpacked, check);
+        assert!(matches!(
+            InstructionType::check(&expect),
+            Some(InstructionType::StableSwap)
+        ));
+
+        let amount_in = 1_000_000u64;
+        let minimum_amount_out = 999_000_000u64;
+        let deadline_slot = 150_000u64;
+        let check = StableSwapInstruction::TriSwap(TriSwapData {
+            amount_in,
+            minimum_amount_out,
+            deadline_slot: Some(deadline_slot),
+        });
+        let packed = check.pack();
+        let mut expect = vec![48];
+        expect.extend_from_slice(&amount_in.to_le_bytes());
+        expect.extend_from_slice(&minimum_amount_out.to_le_bytes());
+        expect.extend_from_slice(&deadline_slot.to_le_bytes());
+        assert_eq!(packed, expect);
+        let unpacked = StableSwapInstruction::unpack(&expect).unwrap();
+        assert_eq!(unpacked, check);
+
+        let token_amounts = [1_000u64, 2_000, 3_000_000];
+        let min_mint_amount = 5_000_000u64;
+        let check = StableSwapInstruction::TriDeposit(TriDepositData {
+            token_amounts,
+            min_mint_amount,
+            deadline_slot: None,
+        });
+        let packed = check.pack();
+        let mut expect = vec![49];
+        for amount in token_amounts.iter() {
+            expect.extend_from_slice(&amount.to_le_bytes());
+        }
+        expect.extend_from_slice(&min_mint_amount.to_le_bytes());
+        assert_eq!(packed, expect);
+        let unpacked = StableSwapInstruction::unpack(&expect).unwrap();
+        assert_eq!(unpacked, check);
+
+        let pool_token_amount = 5_000_000u64;
+        let check = StableSwapInstruction::TriWithdraw(TriWithdrawData {
+            pool_token_amount,
+            minimum_token_amounts: token_amounts,
+            deadline_slot: Some(deadline_slot),
+        });
+        let packed = check.pack();
+        let mut expect = vec![50];
+        expect.extend_from_slice(&pool_token_amount.to_le_bytes());
+        for amount in token_amounts.iter() {
+            expect.extend_from_slice(&amount.to_le_bytes());
+        }
+        expect.extend_from_slice(&deadline_slot.to_le_bytes());
+        assert_eq!(packed, expect);
+        let unpacked = StableSwapInstruction::unpack(&expect).unwrap();
+        assert_eq!(unpacked, check);
+        assert!(matches!(
+            InstructionType::check(&expect),
+            Some(InstructionType::StableSwap)
+        ));
+    }
+
+    #[test]
+    fn test_pack_post_quote() {
+        let swap_direction: u8 = 1;
+        let price: u128 = 35_000_000_000_000_000_000;
+        let max_amount_in: u64 = 1_000_000;
+        let expiry_slot: u64 = 150_000;
+        let check = SwapInstruction::PostQuote(QuoteData {
+            swap_direction,
+            price,
+            max_amount_in,
+            expiry_slot,
+        });
+        let packed = check.pack();
+        let mut expect = vec![8, swap_direction];
+        expect.extend_from_slice(&price.to_le_bytes());
+        expect.extend_from_slice(&max_amount_in.to_le_bytes());
+        expect.extend_from_slice(&expiry_slot.to_le_bytes());
+        assert_eq!(packed, expect);
+        let unpacked = SwapInstruction::unpack(&expect).unwrap();
+        assert_eq!(unpacked, check);
+    }
+
+    #[test]
+    fn test_pack_fill_quote() {
+        let amount_in: u64 = 1_000_000;
+        let minimum_amount_out: u64 = 500_000;
+        let check = SwapInstruction::FillQuote(SwapData {
+            amount_in,
+            minimum_amount_out,
+            deadline_slot: None,
+            output_owner: None,
+        });
+        let packed = check.pack();
+        let mut expect = vec![9];
+        expect.extend_from_slice(&amount_in.to_le_bytes());
+        expect.extend_from_slice(&minimum_amount_out.to_le_bytes());
+        assert_eq!(packed, expect);
+        let unpacked = SwapInstruction::unpack(&expect).unwrap();
+        assert_eq!(unpacked, check);
+    }
+
+    #[test]
+    fn test_pack_get_quote() {
+        let amount_in: u64 = 1_000_000;
+        let swap_direction: u8 = 1;
+        let check = SwapInstruction::GetQuote(GetQuoteData {
+            amount_in,
+            swap_direction,
+        });
+        let packed = check.pack();
+        let mut expect = vec![31];
+        expect.extend_from_slice(&amount_in.to_le_bytes());
+        expect.push(swap_direction);
+        assert_eq!(packed, expect);
+        let unpacked = SwapInstruction::unpack(&expect).unwrap();
+        assert_eq!(unpacked, check);
+        assert!(matches!(
+            InstructionType::check(&expect),
+            Some(InstructionType::Swap)
+        ));
+    }
+
+    #[test]
+    fn test_pack_update_price_cache() {
+        let check = SwapInstruction::UpdatePriceCache;
+        let packed = check.pack();
+        let expect = vec![32];
+        assert_eq!(packed, expect);
+        let unpacked = SwapInstruction::unpack(&expect).unwrap();
+        assert_eq!(unpacked, check);
+        assert!(matches!(
+            InstructionType::check(&expect),
+            Some(InstructionType::Swap)
+        ));
+    }
+
+    #[test]
+    fn test_pack_scratch() {
+        for (check, tag) in &[
+            (SwapInstruction::AcquireScratch, 33u8),
+            (SwapInstruction::ReleaseScratch, 34u8),
+        ] {
+            let packed = check.pack();
+            let expect = vec![*tag];
+            assert_eq!(packed, expect);
+            let unpacked = SwapInstruction::unpack(&expect).unwrap();
+            assert_eq!(&unpacked, check);
+            assert!(matches!(
+                InstructionType::check(&expect),
+                Some(InstructionType::Swap)
+            ));
+        }
+    }
+
+    #[test]
+    fn test_pack_fee_discount_stake() {
+        for (check, tag) in &[
+            (SwapInstruction::StakeFeeDiscount(1_000), 36u8),
+            (SwapInstruction::UnstakeFeeDiscount(1_000), 37u8),
+        ] {
+            let packed = check.pack();
+            let mut expect = vec![*tag];
+            expect.extend_from_slice(&1_000u64.to_le_bytes());
+            assert_eq!(packed, expect);
+            let unpacked = SwapInstruction::unpack(&expect).unwrap();
+            assert_eq!(&unpacked, check);
+            assert!(matches!(
+                InstructionType::check(&expect),
+                Some(InstructionType::Swap)
+            ));
+        }
+    }
+
+    #[test]
+    fn test_pack_rebalance() {
+        let max_amount_in = 1_000_000u64;
+        let minimum_amount_out = 90_000_000u64;
+        let deadline_slot = 150_000u64;
+        let check = SwapInstruction::Rebalance(RebalanceData {
+            max_amount_in,
+            minimum_amount_out,
+            deadline_slot: Some(deadline_slot),
+        });
+        let packed = check.pack();
+        let mut expect = vec![51];
+        expect.extend_from_slice(&max_amount_in.to_le_bytes());
+        expect.extend_from_slice(&minimum_amount_out.to_le_bytes());
+        expect.extend_from_slice(&deadline_slot.to_le_bytes());
+        assert_eq!(packed, expect);
+        let unpacked = SwapInstruction::unpack(&expect).unwrap();
+        assert_eq!(unpacked, check);
+        assert!(matches!(
+            InstructionType::check(&expect),
+            Some(InstructionType::Swap)
+        ));
+    }
+
+    #[test]
+    fn test_pack_lock_liquidity() {
+        let amount = 1_000_000u64;
+        let unlock_ts = 1_700_000_000i64;
+        let check = SwapInstruction::LockLiquidity(LockLiquidityData { amount, unlock_ts });
+        let packed = check.pack();
+        let mut expect = vec![52];
+        expect.extend_from_slice(&amount.to_le_bytes());
+        expect.extend_from_slice(&unlock_ts.to_le_bytes());
         assert_eq!(packed, expect);
         let unpacked = SwapInstruction::unpack(&expect).unwrap();
         assert_eq!(unpacked, check);
+
+        let check = SwapInstruction::UnlockLiquidity;
+        let expect = vec![53];
+        assert_eq!(check.pack(), expect);
+        assert_eq!(SwapInstruction::unpack(&expect).unwrap(), check);
+        assert!(matches!(
+            InstructionType::check(&expect),
+            Some(InstructionType::Swap)
+        ));
+    }
+
+    #[test]
+    fn test_pack_native_sol() {
+        let swap_data = SwapData {
+            amount_in: 1_000_000,
+            minimum_amount_out: 900_000,
+            deadline_slot: Some(150_000),
+            output_owner: None,
+        };
+        let check = SwapInstruction::SwapNativeSol(swap_data.clone());
+        let mut expect = SwapInstruction::SwapV2(swap_data).pack();
+        expect[0] = 54;
+        assert_eq!(check.pack(), expect);
+        assert_eq!(SwapInstruction::unpack(&expect).unwrap(), check);
+        assert!(matches!(
+            InstructionType::check(&expect),
+            Some(InstructionType::Swap)
+        ));
+
+        let deposit_data = DepositData {
+            token_a_amount: 1_000,
+            token_b_amount: 2_000,
+            min_mint_amount: 500,
+            deadline_slot: None,
+        };
+        let check = SwapInstruction::DepositNativeSol(deposit_data.clone());
+        let mut expect = SwapInstruction::Deposit(deposit_data).pack();
+        expect[0] = 55;
+        assert_eq!(check.pack(), expect);
+        assert_eq!(SwapInstruction::unpack(&expect).unwrap(), check);
+        assert!(matches!(
+            InstructionType::check(&expect),
+            Some(InstructionType::Swap)
+        ));
+    }
+
+    #[test]
+    fn test_pack_set_manual_price() {
+        let market_price = 1_500_000_000_000_000_000u128;
+        let check = SwapInstruction::SetManualPrice(market_price);
+        let mut expect = vec![57];
+        expect.extend_from_slice(&market_price.to_le_bytes());
+        assert_eq!(check.pack(), expect);
+        assert_eq!(SwapInstruction::unpack(&expect).unwrap(), check);
+        assert!(matches!(
+            InstructionType::check(&expect),
+            Some(InstructionType::Swap)
+        ));
+        assert!(SwapInstruction::unpack(&expect[..16]).is_err());
+    }
+
+    #[test]
+    fn test_pack_sync() {
+        let check = SwapInstruction::Sync;
+        let expect = vec![58];
+        assert_eq!(check.pack(), expect);
+        assert_eq!(SwapInstruction::unpack(&expect).unwrap(), check);
+        assert!(matches!(
+            InstructionType::check(&expect),
+            Some(InstructionType::Swap)
+        ));
+        assert!(SwapInstruction::unpack(&[60]).is_err());
     }
 
     #[test]
-    fn test_pack_swap() {
-        let amount_in: u64 = 1_000_000;
-        let minimum_amount_out: u64 = 500_000;
-        let check = SwapInstruction::Swap(SwapData {
-            amount_in,
-            minimum_amount_out,
-        });
-        let packed = check.pack();
-        let mut expect = vec![1];
-        expect.extend_from_slice(&amount_in.to_le_bytes());
-        expect.extend_from_slice(&minimum_amount_out.to_le_bytes());
-        assert_eq!(packed, expect);
-        let unpacked = SwapInstruction::unpack(&expect).unwrap();
-        assert_eq!(unpacked, check);
+    fn test_pack_refresh_price() {
+        let check = SwapInstruction::RefreshPrice;
+        let expect = vec![59];
+        assert_eq!(check.pack(), expect);
+        assert_eq!(SwapInstruction::unpack(&expect).unwrap(), check);
+        assert!(matches!(
+            InstructionType::check(&expect),
+            Some(InstructionType::Swap)
+        ));
     }
 
     #[test]
-    fn test_pack_swap_deposit() {
-        let token_a_amount: u64 = 1_000_000;
-        let token_b_amount: u64 = 500_000;
-        let min_mint_amount: u64 = 500_000;
-        let check = SwapInstruction::Deposit(DepositData {
-            token_a_amount,
-            token_b_amount,
-            min_mint_amount,
+    fn test_pack_swap_partial_fill() {
+        let swap_data = SwapData {
+            amount_in: 1_000_000,
+            minimum_amount_out: 900_000,
+            deadline_slot: None,
+            output_owner: Some(Pubkey::new_unique()),
+        };
+        let check = SwapInstruction::SwapPartialFill(swap_data.clone());
+        let mut expect = SwapInstruction::SwapV2(swap_data.clone()).pack();
+        expect[0] = 56;
+        assert_eq!(check.pack(), expect);
+        assert_eq!(SwapInstruction::unpack(&expect).unwrap(), check);
+        assert!(matches!(
+            InstructionType::check(&expect),
+            Some(InstructionType::Swap)
+        ));
+
+        let instruction = Instruction {
+            program_id: Pubkey::new_unique(),
+            accounts: vec![AccountMeta::new(Pubkey::new_unique(), false)],
+            data: SwapInstruction::SwapV2(swap_data).pack(),
+        };
+        let result = with_partial_fill(instruction.clone()).unwrap();
+        assert_eq!(result.data, check.pack());
+        assert_eq!(result.accounts, instruction.accounts);
+        let instruction = Instruction {
+            data: SwapInstruction::UnlockLiquidity.pack(),
+            ..instruction
+        };
+        assert_eq!(
+            with_partial_fill(instruction),
+            Err(SwapError::InvalidInstruction.into())
+        );
+
+        let fill = SwapFill {
+            amount_in: 600_000,
+            amount_out: 540_000,
+        };
+        let packed = fill.pack();
+        assert_eq!(packed.len(), 16);
+        assert_eq!(SwapFill::unpack(&packed).unwrap(), fill);
+        assert_eq!(
+            SwapFill::unpack(&packed[..8]),
+            Err(SwapError::InstructionUnpackError.into())
+        );
+    }
+
+    #[test]
+    fn test_with_native_sol() {
+        let program_id = Pubkey::new_unique();
+        let keys: Vec<Pubkey> = (0..10).map(|_| Pubkey::new_unique()).collect();
+        let native_sol_pubkey = keys[3];
+        let deposit_data = DepositData {
+            token_a_amount: 1_000,
+            token_b_amount: 2_000,
+            min_mint_amount: 500,
+            deadline_slot: None,
+        };
+        let instruction = deposit(
+            program_id,
+            keys[0],
+            keys[1],
+            keys[2],
+            native_sol_pubkey,
+            keys[4],
+            keys[5],
+            keys[6],
+            keys[7],
+            keys[8],
+            deposit_data.clone(),
+        )
+        .unwrap();
+
+        let result = with_native_sol(instruction.clone(), native_sol_pubkey).unwrap();
+        assert_eq!(result.program_id, program_id);
+        assert_eq!(
+            SwapInstruction::unpack(&result.data).unwrap(),
+            SwapInstruction::DepositNativeSol(deposit_data)
+        );
+        assert_eq!(
+            result.accounts[..5],
+            [
+                AccountMeta::new(native_sol_pubkey, false),
+                AccountMeta::new_readonly(spl_token::native_mint::id(), false),
+                AccountMeta::new_readonly(rent::id(), false),
+                AccountMeta::new_readonly(system_program::id(), false),
+                AccountMeta::new_readonly(spl_token::id(), false),
+            ]
+        );
+        assert_eq!(result.accounts[7], AccountMeta::new(keys[2], true));
+        let mut expected_accounts = instruction.accounts.clone();
+        expected_accounts[2].is_writable = true;
+        assert_eq!(result.accounts[5..], expected_accounts[..]);
+
+        let instruction = unlock_liquidity(program_id, keys[0], keys[1], keys[2], keys[3]).unwrap();
+        assert_eq!(
+            with_native_sol(instruction, native_sol_pubkey),
+            Err(SwapError::InvalidInstruction.into())
+        );
+    }
+
+    #[test]
+    fn test_pack_swap_quote() {
+        let quote = SwapQuote {
+            amount_out: 990_000,
+            trade_fee: 10_000,
+            admin_fee: 2_000,
+            price_impact_bps: 15,
+            market_price: 35_000_000_000_000_000_000,
+        };
+        let packed = quote.pack();
+        assert_eq!(packed.len(), 48);
+        assert_eq!(SwapQuote::unpack(&packed).unwrap(), quote);
+        assert_eq!(
+            SwapQuote::unpack(&packed[..40]),
+            Err(SwapError::InstructionUnpackError.into())
+        );
+    }
+
+    #[test]
+    fn test_pack_flash_swap() {
+        let amount_a: u64 = 1_000_000;
+        let amount_b: u64 = 0;
+        let deadline_slot: u64 = 150_000;
+        let callback_data = vec![1u8, 2, 3];
+        let check = SwapInstruction::FlashSwap(FlashSwapData {
+            amount_a,
+            amount_b,
+            deadline_slot: Some(deadline_slot),
+            callback_data: callback_data.clone(),
         });
         let packed = check.pack();
-        let mut expect = vec![2];
-        expect.extend_from_slice(&token_a_amount.to_le_bytes());
-        expect.extend_from_slice(&token_b_amount.to_le_bytes());
-        expect.extend_from_slice(&min_mint_amount.to_le_bytes());
+        let mut expect = vec![17];
+        expect.extend_from_slice(&amount_a.to_le_bytes());
+        expect.extend_from_slice(&amount_b.to_le_bytes());
+        expect.extend_from_slice(&deadline_slot.to_le_bytes());
+        expect.extend_from_slice(&callback_data);
         assert_eq!(packed, expect);
         let unpacked = SwapInstruction::unpack(&expect).unwrap();
         assert_eq!(unpacked, check);
-    }
+        assert!(matches!(
+            InstructionType::check(&expect),
+            Some(InstructionType::Swap)
+        ));
 
-    #[test]
-    fn test_pack_swap_withdraw() {
-        let minimum_token_a_amount: u64 = 1_000_000;
-        let minimum_token_b_amount: u64 = 500_000;
-        let pool_token_amount: u64 = 500_000;
-        let check = SwapInstruction::Withdraw(WithdrawData {
-            pool_token_amount,
-            minimum_token_a_amount,
-            minimum_token_b_amount,
+        // No deadline is packed as u64::MAX in front of the callback data
+        let check = SwapInstruction::FlashSwap(FlashSwapData {
+            amount_a,
+            amount_b,
+            deadline_slot: None,
+            callback_data: callback_data.clone(),
         });
         let packed = check.pack();
-        let mut expect = vec![3];
-        expect.extend_from_slice(&pool_token_amount.to_le_bytes());
-        expect.extend_from_slice(&minimum_token_a_amount.to_le_bytes());
-        expect.extend_from_slice(&minimum_token_b_amount.to_le_bytes());
+        let mut expect = vec![17];
+        expect.extend_from_slice(&amount_a.to_le_bytes());
+        expect.extend_from_slice(&amount_b.to_le_bytes());
+        expect.extend_from_slice(&u64::MAX.to_le_bytes());
+        expect.extend_from_slice(&callback_data);
         assert_eq!(packed, expect);
         let unpacked = SwapInstruction::unpack(&expect).unwrap();
         assert_eq!(unpacked, check);
@@ -2781,6 +10176,7 @@ mod tests {
             token_a_amount,
             token_b_amount,
             oracle_priority_flags,
+            quote_is_token_a: false,
         };
         let init_data_clone = InitializeData {
             nonce,
@@ -2791,6 +10187,7 @@ mod tests {
             token_a_amount,
             token_b_amount,
             oracle_priority_flags,
+            quote_is_token_a: false,
         };
 
         let result = initialize(
@@ -2824,6 +10221,7 @@ mod tests {
         expected_data.extend_from_slice(&init_data.token_a_amount.to_le_bytes());
         expected_data.extend_from_slice(&init_data.token_b_amount.to_le_bytes());
         expected_data.extend_from_slice(&init_data.oracle_priority_flags.to_le_bytes());
+        expected_data.push(init_data.quote_is_token_a as u8);
 
         let expected_account = vec![
             AccountMeta {
@@ -2933,7 +10331,196 @@ mod tests {
     }
 
     #[test]
-    fn test_swap() {
+    fn test_swap() {
+        let amount_in: u64 = 1_000_000;
+        let minimum_amount_out: u64 = 500_000;
+
+        let program_id = Pubkey::new_unique();
+        let config_pubkey = Pubkey::new_unique();
+        let swap_pubkey = Pubkey::new_unique();
+        let market_authority_pubkey = Pubkey::new_unique();
+        let swap_authority_pubkey = Pubkey::new_unique();
+        let user_transfer_authority_pubkey = Pubkey::new_unique();
+        let source_pubkey = Pubkey::new_unique();
+        let swap_source_pubkey = Pubkey::new_unique();
+        let source_mint_pubkey = Pubkey::new_unique();
+        let swap_destination_pubkey = Pubkey::new_unique();
+        let destination_pubkey = Pubkey::new_unique();
+        let destination_mint_pubkey = Pubkey::new_unique();
+        let reward_token_pubkey = Pubkey::new_unique();
+        let reward_mint_pubkey = Pubkey::new_unique();
+        let admin_fee_destination_pubkey = Pubkey::new_unique();
+        let pyth_a_pubkey = Pubkey::new_unique();
+        let pyth_b_pubkey = Pubkey::new_unique();
+        let swap_data = SwapData {
+            amount_in,
+            minimum_amount_out,
+            deadline_slot: None,
+            output_owner: None,
+        };
+
+        let result = swap(
+            program_id,
+            config_pubkey,
+            swap_pubkey,
+            market_authority_pubkey,
+            swap_authority_pubkey,
+            user_transfer_authority_pubkey,
+            source_pubkey,
+            swap_source_pubkey,
+            source_mint_pubkey,
+            swap_destination_pubkey,
+            destination_pubkey,
+            destination_mint_pubkey,
+            reward_token_pubkey,
+            reward_mint_pubkey,
+            admin_fee_destination_pubkey,
+            pyth_a_pubkey,
+            pyth_b_pubkey,
+            None,
+            None,
+            None,
+            None,
+            None,
+            None,
+            None,
+            swap_data.clone(),
+        );
+
+        let mut expected_data = vec![1];
+        expected_data.extend_from_slice(&swap_data.amount_in.to_le_bytes());
+        expected_data.extend_from_slice(&swap_data.minimum_amount_out.to_le_bytes());
+
+        let expected_account = vec![
+            AccountMeta {
+                pubkey: config_pubkey,
+                is_signer: false,
+                is_writable: false,
+            },
+            AccountMeta {
+                pubkey: swap_pubkey,
+                is_signer: false,
+                is_writable: true,
+            },
+            AccountMeta {
+                pubkey: market_authority_pubkey,
+                is_signer: false,
+                is_writable: false,
+            },
+            AccountMeta {
+                pubkey: swap_authority_pubkey,
+                is_signer: false,
+                is_writable: false,
+            },
+            AccountMeta {
+                pubkey: user_transfer_authority_pubkey,
+                is_signer: true,
+                is_writable: false,
+            },
+            AccountMeta {
+                pubkey: source_pubkey,
+                is_signer: false,
+                is_writable: true,
+            },
+            AccountMeta {
+                pubkey: swap_source_pubkey,
+                is_signer: false,
+                is_writable: true,
+            },
+            AccountMeta {
+                pubkey: source_mint_pubkey,
+                is_signer: false,
+                is_writable: false,
+            },
+            AccountMeta {
+                pubkey: swap_destination_pubkey,
+                is_signer: false,
+                is_writable: true,
+            },
+            AccountMeta {
+                pubkey: destination_pubkey,
+                is_signer: false,
+                is_writable: true,
+            },
+            AccountMeta {
+                pubkey: destination_mint_pubkey,
+                is_signer: false,
+                is_writable: false,
+            },
+            AccountMeta {
+                pubkey: reward_token_pubkey,
+                is_signer: false,
+                is_writable: true,
+            },
+            AccountMeta {
+                pubkey: reward_mint_pubkey,
+                is_signer: false,
+                is_writable: true,
+            },
+            AccountMeta {
+                pubkey: admin_fee_destination_pubkey,
+                is_signer: false,
+                is_writable: true,
+            },
+            AccountMeta {
+                pubkey: pyth_a_pubkey,
+                is_signer: false,
+                is_writable: false,
+            },
+            AccountMeta {
+                pubkey: pyth_b_pubkey,
+                is_signer: false,
+                is_writable: false,
+            },
+            AccountMeta {
+                pubkey: spl_token::id(),
+                is_signer: false,
+                is_writable: false,
+            },
+        ];
+
+        assert_eq!(result.as_ref().unwrap().data, expected_data);
+        assert_eq!(result.as_ref().unwrap().accounts, expected_account);
+
+        // The price cache goes right after the token program
+        let price_cache_pubkey = Pubkey::new_unique();
+        let result = swap(
+            program_id,
+            config_pubkey,
+            swap_pubkey,
+            market_authority_pubkey,
+            swap_authority_pubkey,
+            user_transfer_authority_pubkey,
+            source_pubkey,
+            swap_source_pubkey,
+            source_mint_pubkey,
+            swap_destination_pubkey,
+            destination_pubkey,
+            destination_mint_pubkey,
+            reward_token_pubkey,
+            reward_mint_pubkey,
+            admin_fee_destination_pubkey,
+            pyth_a_pubkey,
+            pyth_b_pubkey,
+            Some(price_cache_pubkey),
+            None,
+            None,
+            None,
+            None,
+            None,
+            None,
+            swap_data,
+        )
+        .unwrap();
+        assert_eq!(result.accounts.len(), expected_account.len() + 1);
+        assert_eq!(
+            result.accounts[expected_account.len()],
+            AccountMeta::new_readonly(price_cache_pubkey, false)
+        );
+    }
+
+    #[test]
+    fn test_swap_v2() {
         let amount_in: u64 = 1_000_000;
         let minimum_amount_out: u64 = 500_000;
 
@@ -2945,21 +10532,24 @@ mod tests {
         let user_transfer_authority_pubkey = Pubkey::new_unique();
         let source_pubkey = Pubkey::new_unique();
         let swap_source_pubkey = Pubkey::new_unique();
-        let source_mint_pubkey = Pubkey::new_unique();
         let swap_destination_pubkey = Pubkey::new_unique();
         let destination_pubkey = Pubkey::new_unique();
-        let destination_mint_pubkey = Pubkey::new_unique();
         let reward_token_pubkey = Pubkey::new_unique();
         let reward_mint_pubkey = Pubkey::new_unique();
         let admin_fee_destination_pubkey = Pubkey::new_unique();
         let pyth_a_pubkey = Pubkey::new_unique();
         let pyth_b_pubkey = Pubkey::new_unique();
+        let serum_market_pubkey = Pubkey::new_unique();
+        let serum_bids_pubkey = Pubkey::new_unique();
+        let serum_asks_pubkey = Pubkey::new_unique();
         let swap_data = SwapData {
             amount_in,
             minimum_amount_out,
+            deadline_slot: None,
+            output_owner: None,
         };
 
-        let result = swap(
+        let result = swap_v2(
             program_id,
             config_pubkey,
             swap_pubkey,
@@ -2968,21 +10558,28 @@ mod tests {
             user_transfer_authority_pubkey,
             source_pubkey,
             swap_source_pubkey,
-            source_mint_pubkey,
             swap_destination_pubkey,
             destination_pubkey,
-            destination_mint_pubkey,
             reward_token_pubkey,
             reward_mint_pubkey,
             admin_fee_destination_pubkey,
             pyth_a_pubkey,
             pyth_b_pubkey,
+            serum_market_pubkey,
+            serum_bids_pubkey,
+            serum_asks_pubkey,
+            None,
+            None,
+            None,
+            None,
+            None,
+            None,
             None,
             None,
             swap_data.clone(),
         );
 
-        let mut expected_data = vec![1];
+        let mut expected_data = vec![5];
         expected_data.extend_from_slice(&swap_data.amount_in.to_le_bytes());
         expected_data.extend_from_slice(&swap_data.minimum_amount_out.to_le_bytes());
 
@@ -3022,11 +10619,6 @@ mod tests {
                 is_signer: false,
                 is_writable: true,
             },
-            AccountMeta {
-                pubkey: source_mint_pubkey,
-                is_signer: false,
-                is_writable: false,
-            },
             AccountMeta {
                 pubkey: swap_destination_pubkey,
                 is_signer: false,
@@ -3037,11 +10629,6 @@ mod tests {
                 is_signer: false,
                 is_writable: true,
             },
-            AccountMeta {
-                pubkey: destination_mint_pubkey,
-                is_signer: false,
-                is_writable: false,
-            },
             AccountMeta {
                 pubkey: reward_token_pubkey,
                 is_signer: false,
@@ -3067,6 +10654,120 @@ mod tests {
                 is_signer: false,
                 is_writable: false,
             },
+            AccountMeta {
+                pubkey: serum_market_pubkey,
+                is_signer: false,
+                is_writable: false,
+            },
+            AccountMeta {
+                pubkey: serum_bids_pubkey,
+                is_signer: false,
+                is_writable: false,
+            },
+            AccountMeta {
+                pubkey: serum_asks_pubkey,
+                is_signer: false,
+                is_writable: false,
+            },
+            AccountMeta {
+                pubkey: spl_token::id(),
+                is_signer: false,
+                is_writable: false,
+            },
+        ];
+
+        assert_eq!(result.as_ref().unwrap().data, expected_data);
+        assert_eq!(result.as_ref().unwrap().accounts, expected_account);
+    }
+
+    #[test]
+    fn test_deposit() {
+        let token_a_amount: u64 = 1_000_000;
+        let token_b_amount: u64 = 500_000;
+        let min_mint_amount: u64 = 500_000;
+
+        let program_id = Pubkey::new_unique();
+        let swap_pubkey = Pubkey::new_unique();
+        let authority_pubkey = Pubkey::new_unique();
+        let user_transfer_authority_pubkey = Pubkey::new_unique();
+        let deposit_token_a_pubkey = Pubkey::new_unique();
+        let deposit_token_b_pubkey = Pubkey::new_unique();
+        let swap_token_a_pubkey = Pubkey::new_unique();
+        let swap_token_b_pubkey = Pubkey::new_unique();
+        let pool_mint_pubkey = Pubkey::new_unique();
+        let destination_pubkey = Pubkey::new_unique();
+        let deposit_data = DepositData {
+            token_a_amount,
+            token_b_amount,
+            min_mint_amount,
+            deadline_slot: None,
+        };
+
+        let result = deposit(
+            program_id,
+            swap_pubkey,
+            authority_pubkey,
+            user_transfer_authority_pubkey,
+            deposit_token_a_pubkey,
+            deposit_token_b_pubkey,
+            swap_token_a_pubkey,
+            swap_token_b_pubkey,
+            pool_mint_pubkey,
+            destination_pubkey,
+            deposit_data.clone(),
+        );
+
+        let mut expected_data = vec![2];
+        expected_data.extend_from_slice(&deposit_data.token_a_amount.to_le_bytes());
+        expected_data.extend_from_slice(&deposit_data.token_b_amount.to_le_bytes());
+        expected_data.extend_from_slice(&deposit_data.min_mint_amount.to_le_bytes());
+
+        let expected_account = vec![
+            AccountMeta {
+                pubkey: swap_pubkey,
+                is_signer: false,
+                is_writable: true,
+            },
+            AccountMeta {
+                pubkey: authority_pubkey,
+                is_signer: false,
+                is_writable: false,
+            },
+            AccountMeta {
+                pubkey: user_transfer_authority_pubkey,
+                is_signer: true,
+                is_writable: false,
+            },
+            AccountMeta {
+                pubkey: deposit_token_a_pubkey,
+                is_signer: false,
+                is_writable: true,
+            },
+            AccountMeta {
+                pubkey: deposit_token_b_pubkey,
+                is_signer: false,
+                is_writable: true,
+            },
+            AccountMeta {
+                pubkey: swap_token_a_pubkey,
+                is_signer: false,
+                is_writable: true,
+            },
+            AccountMeta {
+                pubkey: swap_token_b_pubkey,
+                is_signer: false,
+                is_writable: true,
+            },
+            AccountMeta {
+                pubkey: pool_mint_pubkey,
+                is_signer: false,
+                is_writable: true,
+            },
+            AccountMeta {
+                pubkey: destination_pubkey,
+                is_signer: false,
+                is_writable: true,
+            },
             AccountMeta {
                 pubkey: spl_token::id(),
                 is_signer: false,
@@ -3079,7 +10780,206 @@ mod tests {
     }
 
     #[test]
-    fn test_swap_v2() {
+    fn test_deposit_one() {
+        let amount_in: u64 = 1_000_000;
+        let min_mint_amount: u64 = 500_000;
+
+        let program_id = Pubkey::new_unique();
+        let config_pubkey = Pubkey::new_unique();
+        let swap_pubkey = Pubkey::new_unique();
+        let authority_pubkey = Pubkey::new_unique();
+        let user_transfer_authority_pubkey = Pubkey::new_unique();
+        let source_pubkey = Pubkey::new_unique();
+        let swap_token_a_pubkey = Pubkey::new_unique();
+        let swap_token_b_pubkey = Pubkey::new_unique();
+        let admin_fee_destination_pubkey = Pubkey::new_unique();
+        let pool_mint_pubkey = Pubkey::new_unique();
+        let destination_pubkey = Pubkey::new_unique();
+        let pyth_a_pubkey = Pubkey::new_unique();
+        let pyth_b_pubkey = Pubkey::new_unique();
+        let serum_market_pubkey = Pubkey::new_unique();
+        let serum_bids_pubkey = Pubkey::new_unique();
+        let serum_asks_pubkey = Pubkey::new_unique();
+        let deposit_data = DepositOneData {
+            amount_in,
+            min_mint_amount,
+            deadline_slot: None,
+        };
+
+        let result = deposit_one(
+            program_id,
+            config_pubkey,
+            swap_pubkey,
+            authority_pubkey,
+            user_transfer_authority_pubkey,
+            source_pubkey,
+            swap_token_a_pubkey,
+            swap_token_b_pubkey,
+            admin_fee_destination_pubkey,
+            pool_mint_pubkey,
+            destination_pubkey,
+            pyth_a_pubkey,
+            pyth_b_pubkey,
+            serum_market_pubkey,
+            serum_bids_pubkey,
+            serum_asks_pubkey,
+            deposit_data.clone(),
+        );
+
+        let mut expected_data = vec![6];
+        expected_data.extend_from_slice(&deposit_data.amount_in.to_le_bytes());
+        expected_data.extend_from_slice(&deposit_data.min_mint_amount.to_le_bytes());
+
+        let expected_account = vec![
+            AccountMeta::new_readonly(config_pubkey, false),
+            AccountMeta::new(swap_pubkey, false),
+            AccountMeta::new_readonly(authority_pubkey, false),
+            AccountMeta::new_readonly(user_transfer_authority_pubkey, true),
+            AccountMeta::new(source_pubkey, false),
+            AccountMeta::new(swap_token_a_pubkey, false),
+            AccountMeta::new(swap_token_b_pubkey, false),
+            AccountMeta::new(admin_fee_destination_pubkey, false),
+            AccountMeta::new(pool_mint_pubkey, false),
+            AccountMeta::new(destination_pubkey, false),
+            AccountMeta::new_readonly(pyth_a_pubkey, false),
+            AccountMeta::new_readonly(pyth_b_pubkey, false),
+            AccountMeta::new_readonly(serum_market_pubkey, false),
+            AccountMeta::new_readonly(serum_bids_pubkey, false),
+            AccountMeta::new_readonly(serum_asks_pubkey, false),
+            AccountMeta::new_readonly(spl_token::id(), false),
+        ];
+
+        assert_eq!(result.as_ref().unwrap().data, expected_data);
+        assert_eq!(result.as_ref().unwrap().accounts, expected_account);
+    }
+
+    #[test]
+    fn test_withdraw_one() {
+        let pool_token_amount: u64 = 500_000;
+        let minimum_amount_out: u64 = 1_000_000;
+        let token_side: u8 = 0;
+
+        let program_id = Pubkey::new_unique();
+        let config_pubkey = Pubkey::new_unique();
+        let swap_pubkey = Pubkey::new_unique();
+        let authority_pubkey = Pubkey::new_unique();
+        let user_transfer_authority_pubkey = Pubkey::new_unique();
+        let pool_mint_pubkey = Pubkey::new_unique();
+        let source_pubkey = Pubkey::new_unique();
+        let swap_token_a_pubkey = Pubkey::new_unique();
+        let swap_token_b_pubkey = Pubkey::new_unique();
+        let destination_pubkey = Pubkey::new_unique();
+        let admin_fee_destination_pubkey = Pubkey::new_unique();
+        let pyth_a_pubkey = Pubkey::new_unique();
+        let pyth_b_pubkey = Pubkey::new_unique();
+        let serum_market_pubkey = Pubkey::new_unique();
+        let serum_bids_pubkey = Pubkey::new_unique();
+        let serum_asks_pubkey = Pubkey::new_unique();
+        let withdraw_data = WithdrawOneData {
+            pool_token_amount,
+            minimum_amount_out,
+            token_side,
+            deadline_slot: None,
+        };
+
+        let result = withdraw_one(
+            program_id,
+            config_pubkey,
+            swap_pubkey,
+            authority_pubkey,
+            user_transfer_authority_pubkey,
+            pool_mint_pubkey,
+            source_pubkey,
+            swap_token_a_pubkey,
+            swap_token_b_pubkey,
+            destination_pubkey,
+            admin_fee_destination_pubkey,
+            pyth_a_pubkey,
+            pyth_b_pubkey,
+            serum_market_pubkey,
+            serum_bids_pubkey,
+            serum_asks_pubkey,
+            withdraw_data.clone(),
+        );
+
+        let mut expected_data = vec![7];
+        expected_data.extend_from_slice(&withdraw_data.pool_token_amount.to_le_bytes());
+        expected_data.extend_from_slice(&withdraw_data.minimum_amount_out.to_le_bytes());
+        expected_data.push(withdraw_data.token_side);
+
+        let expected_account = vec![
+            AccountMeta::new_readonly(config_pubkey, false),
+            AccountMeta::new(swap_pubkey, false),
+            AccountMeta::new_readonly(authority_pubkey, false),
+            AccountMeta::new_readonly(user_transfer_authority_pubkey, true),
+            AccountMeta::new(pool_mint_pubkey, false),
+            AccountMeta::new(source_pubkey, false),
+            AccountMeta::new(swap_token_a_pubkey, false),
+            AccountMeta::new(swap_token_b_pubkey, false),
+            AccountMeta::new(destination_pubkey, false),
+            AccountMeta::new(admin_fee_destination_pubkey, false),
+            AccountMeta::new_readonly(pyth_a_pubkey, false),
+            AccountMeta::new_readonly(pyth_b_pubkey, false),
+            AccountMeta::new_readonly(serum_market_pubkey, false),
+            AccountMeta::new_readonly(serum_bids_pubkey, false),
+            AccountMeta::new_readonly(serum_asks_pubkey, false),
+            AccountMeta::new_readonly(spl_token::id(), false),
+        ];
+
+        assert_eq!(result.as_ref().unwrap().data, expected_data);
+        assert_eq!(result.as_ref().unwrap().accounts, expected_account);
+    }
+
+    #[test]
+    fn test_post_quote() {
+        let program_id = Pubkey::new_unique();
+        let config_pubkey = Pubkey::new_unique();
+        let swap_pubkey = Pubkey::new_unique();
+        let quote_pubkey = Pubkey::new_unique();
+        let market_maker_pubkey = Pubkey::new_unique();
+        let market_maker_token_a_pubkey = Pubkey::new_unique();
+        let market_maker_token_b_pubkey = Pubkey::new_unique();
+        let quote_data = QuoteData {
+            swap_direction: 0u8,
+            price: 35_000_000_000_000_000_000u128,
+            max_amount_in: 1_000_000u64,
+            expiry_slot: 150_000u64,
+        };
+
+        let result = post_quote(
+            program_id,
+            config_pubkey,
+            swap_pubkey,
+            quote_pubkey,
+            market_maker_pubkey,
+            market_maker_token_a_pubkey,
+            market_maker_token_b_pubkey,
+            quote_data.clone(),
+        );
+
+        let mut expected_data = vec![8, quote_data.swap_direction];
+        expected_data.extend_from_slice(&quote_data.price.to_le_bytes());
+        expected_data.extend_from_slice(&quote_data.max_amount_in.to_le_bytes());
+        expected_data.extend_from_slice(&quote_data.expiry_slot.to_le_bytes());
+
+        let expected_account = vec![
+            AccountMeta::new_readonly(config_pubkey, false),
+            AccountMeta::new_readonly(swap_pubkey, false),
+            AccountMeta::new(quote_pubkey, false),
+            AccountMeta::new_readonly(market_maker_pubkey, true),
+            AccountMeta::new_readonly(market_maker_token_a_pubkey, false),
+            AccountMeta::new_readonly(market_maker_token_b_pubkey, false),
+            AccountMeta::new_readonly(clock::id(), false),
+            AccountMeta::new_readonly(rent::id(), false),
+            AccountMeta::new_readonly(spl_token::id(), false),
+        ];
+
+        assert_eq!(result.as_ref().unwrap().data, expected_data);
+        assert_eq!(result.as_ref().unwrap().accounts, expected_account);
+    }
+
+    #[test]
+    fn test_fill_quote() {
         let amount_in: u64 = 1_000_000;
         let minimum_amount_out: u64 = 500_000;
 
@@ -3088,143 +10988,59 @@ mod tests {
         let swap_pubkey = Pubkey::new_unique();
         let market_authority_pubkey = Pubkey::new_unique();
         let swap_authority_pubkey = Pubkey::new_unique();
+        let quote_pubkey = Pubkey::new_unique();
         let user_transfer_authority_pubkey = Pubkey::new_unique();
         let source_pubkey = Pubkey::new_unique();
-        let swap_source_pubkey = Pubkey::new_unique();
-        let swap_destination_pubkey = Pubkey::new_unique();
         let destination_pubkey = Pubkey::new_unique();
-        let reward_token_pubkey = Pubkey::new_unique();
-        let reward_mint_pubkey = Pubkey::new_unique();
+        let market_maker_source_pubkey = Pubkey::new_unique();
+        let market_maker_destination_pubkey = Pubkey::new_unique();
         let admin_fee_destination_pubkey = Pubkey::new_unique();
-        let pyth_a_pubkey = Pubkey::new_unique();
-        let pyth_b_pubkey = Pubkey::new_unique();
-        let serum_market_pubkey = Pubkey::new_unique();
-        let serum_bids_pubkey = Pubkey::new_unique();
-        let serum_asks_pubkey = Pubkey::new_unique();
-        let swap_data = SwapData {
-            amount_in,
-            minimum_amount_out,
-        };
+        let reward_token_pubkey = Pubkey::new_unique();
+        let source_reward_token_pubkey = Pubkey::new_unique();
 
-        let result = swap_v2(
+        let result = fill_quote(
             program_id,
             config_pubkey,
             swap_pubkey,
             market_authority_pubkey,
             swap_authority_pubkey,
+            quote_pubkey,
             user_transfer_authority_pubkey,
             source_pubkey,
-            swap_source_pubkey,
-            swap_destination_pubkey,
             destination_pubkey,
-            reward_token_pubkey,
-            reward_mint_pubkey,
+            market_maker_source_pubkey,
+            market_maker_destination_pubkey,
             admin_fee_destination_pubkey,
-            pyth_a_pubkey,
-            pyth_b_pubkey,
-            serum_market_pubkey,
-            serum_bids_pubkey,
-            serum_asks_pubkey,
-            None,
-            None,
-            swap_data.clone(),
+            reward_token_pubkey,
+            source_reward_token_pubkey,
+            SwapData {
+                amount_in,
+                minimum_amount_out,
+                deadline_slot: None,
+                output_owner: None,
+            },
         );
 
-        let mut expected_data = vec![5];
-        expected_data.extend_from_slice(&swap_data.amount_in.to_le_bytes());
-        expected_data.extend_from_slice(&swap_data.minimum_amount_out.to_le_bytes());
+        let mut expected_data = vec![9];
+        expected_data.extend_from_slice(&amount_in.to_le_bytes());
+        expected_data.extend_from_slice(&minimum_amount_out.to_le_bytes());
 
         let expected_account = vec![
-            AccountMeta {
-                pubkey: config_pubkey,
-                is_signer: false,
-                is_writable: false,
-            },
-            AccountMeta {
-                pubkey: swap_pubkey,
-                is_signer: false,
-                is_writable: true,
-            },
-            AccountMeta {
-                pubkey: market_authority_pubkey,
-                is_signer: false,
-                is_writable: false,
-            },
-            AccountMeta {
-                pubkey: swap_authority_pubkey,
-                is_signer: false,
-                is_writable: false,
-            },
-            AccountMeta {
-                pubkey: user_transfer_authority_pubkey,
-                is_signer: true,
-                is_writable: false,
-            },
-            AccountMeta {
-                pubkey: source_pubkey,
-                is_signer: false,
-                is_writable: true,
-            },
-            AccountMeta {
-                pubkey: swap_source_pubkey,
-                is_signer: false,
-                is_writable: true,
-            },
-            AccountMeta {
-                pubkey: swap_destination_pubkey,
-                is_signer: false,
-                is_writable: true,
-            },
-            AccountMeta {
-                pubkey: destination_pubkey,
-                is_signer: false,
-                is_writable: true,
-            },
-            AccountMeta {
-                pubkey: reward_token_pubkey,
-                is_signer: false,
-                is_writable: true,
-            },
-            AccountMeta {
-                pubkey: reward_mint_pubkey,
-                is_signer: false,
-                is_writable: true,
-            },
-            AccountMeta {
-                pubkey: admin_fee_destination_pubkey,
-                is_signer: false,
-                is_writable: true,
-            },
-            AccountMeta {
-                pubkey: pyth_a_pubkey,
-                is_signer: false,
-                is_writable: false,
-            },
-            AccountMeta {
-                pubkey: pyth_b_pubkey,
-                is_signer: false,
-                is_writable: false,
-            },
-            AccountMeta {
-                pubkey: serum_market_pubkey,
-                is_signer: false,
-                is_writable: false,
-            },
-            AccountMeta {
-                pubkey: serum_bids_pubkey,
-                is_signer: false,
-                is_writable: false,
-            },
-            AccountMeta {
-                pubkey: serum_asks_pubkey,
-                is_signer: false,
-                is_writable: false,
-            },
-            AccountMeta {
-                pubkey: spl_token::id(),
-                is_signer: false,
-                is_writable: false,
-            },
+            AccountMeta::new_readonly(config_pubkey, false),
+            AccountMeta::new_readonly(swap_pubkey, false),
+            AccountMeta::new_readonly(market_authority_pubkey, false),
+            AccountMeta::new_readonly(swap_authority_pubkey, false),
+            AccountMeta::new(quote_pubkey, false),
+            AccountMeta::new_readonly(user_transfer_authority_pubkey, true),
+            AccountMeta::new(source_pubkey, false),
+            AccountMeta::new(destination_pubkey, false),
+            AccountMeta::new(market_maker_source_pubkey, false),
+            AccountMeta::new(market_maker_destination_pubkey, false),
+            AccountMeta::new(admin_fee_destination_pubkey, false),
+            AccountMeta::new(reward_token_pubkey, false),
+            AccountMeta::new(source_reward_token_pubkey, false),
+            AccountMeta::new_readonly(clock::id(), false),
+            AccountMeta::new_readonly(spl_token::id(), false),
         ];
 
         assert_eq!(result.as_ref().unwrap().data, expected_data);
@@ -3232,98 +11048,335 @@ mod tests {
     }
 
     #[test]
-    fn test_deposit() {
-        let token_a_amount: u64 = 1_000_000;
-        let token_b_amount: u64 = 500_000;
-        let min_mint_amount: u64 = 500_000;
+    fn test_pack_route() {
+        let amount_in: u64 = 1_000_000;
+        let minimum_amount_out: u64 = 500_000;
+        let check = RouterInstruction::Route(SwapData {
+            amount_in,
+            minimum_amount_out,
+            deadline_slot: None,
+            output_owner: None,
+        });
+        let packed = check.pack();
+        let mut expect = vec![30];
+        expect.extend_from_slice(&amount_in.to_le_bytes());
+        expect.extend_from_slice(&minimum_amount_out.to_le_bytes());
+        assert_eq!(packed, expect);
+        let unpacked = RouterInstruction::unpack(&expect).unwrap();
+        assert_eq!(unpacked, check);
+    }
+
+    #[test]
+    fn test_route() {
+        let amount_in: u64 = 1_000_000;
+        let minimum_amount_out: u64 = 500_000;
 
         let program_id = Pubkey::new_unique();
-        let swap_pubkey = Pubkey::new_unique();
-        let authority_pubkey = Pubkey::new_unique();
+        let config_pubkey = Pubkey::new_unique();
+        let market_authority_pubkey = Pubkey::new_unique();
         let user_transfer_authority_pubkey = Pubkey::new_unique();
-        let deposit_token_a_pubkey = Pubkey::new_unique();
-        let deposit_token_b_pubkey = Pubkey::new_unique();
+        let source_pubkey = Pubkey::new_unique();
+        let destination_pubkey = Pubkey::new_unique();
+        let reward_token_pubkey = Pubkey::new_unique();
+        let source_reward_token_pubkey = Pubkey::new_unique();
+        let fee_discount_pubkey = Pubkey::new_unique();
+        let fee_discount_tiers_pubkey = Pubkey::new_unique();
+        let user_referrer_data_pubkey = Pubkey::new_unique();
+        let referrer_token_pubkey = Pubkey::new_unique();
+        let swap_hop = RouteHop {
+            swap_pubkey: Pubkey::new_unique(),
+            swap_authority_pubkey: Pubkey::new_unique(),
+            swap_source_pubkey: Pubkey::new_unique(),
+            swap_destination_pubkey: Pubkey::new_unique(),
+            admin_fee_destination_pubkey: Pubkey::new_unique(),
+            treasury_token_pubkey: Some(Pubkey::new_unique()),
+            referrer_fee_token_pubkey: Some(Pubkey::new_unique()),
+            price_pubkeys: (0..5).map(|_| Pubkey::new_unique()).collect(),
+        };
+        let stable_swap_hop = RouteHop {
+            swap_pubkey: Pubkey::new_unique(),
+            swap_authority_pubkey: Pubkey::new_unique(),
+            swap_source_pubkey: Pubkey::new_unique(),
+            swap_destination_pubkey: Pubkey::new_unique(),
+            admin_fee_destination_pubkey: Pubkey::new_unique(),
+            treasury_token_pubkey: Some(Pubkey::new_unique()),
+            referrer_fee_token_pubkey: None,
+            price_pubkeys: vec![],
+        };
+        let swap_data = SwapData {
+            amount_in,
+            minimum_amount_out,
+            deadline_slot: None,
+            output_owner: None,
+        };
+
+        let result = route(
+            program_id,
+            config_pubkey,
+            market_authority_pubkey,
+            user_transfer_authority_pubkey,
+            source_pubkey,
+            destination_pubkey,
+            reward_token_pubkey,
+            source_reward_token_pubkey,
+            Some(fee_discount_pubkey),
+            Some(fee_discount_tiers_pubkey),
+            Some(user_referrer_data_pubkey),
+            Some(referrer_token_pubkey),
+            &[swap_hop.clone(), stable_swap_hop.clone()],
+            swap_data,
+        );
+
+        let mut expected_data = vec![30];
+        expected_data.extend_from_slice(&amount_in.to_le_bytes());
+        expected_data.extend_from_slice(&minimum_amount_out.to_le_bytes());
+
+        let mut expected_account = vec![
+            AccountMeta::new_readonly(config_pubkey, false),
+            AccountMeta::new_readonly(market_authority_pubkey, false),
+            AccountMeta::new_readonly(user_transfer_authority_pubkey, true),
+            AccountMeta::new(source_pubkey, false),
+            AccountMeta::new(destination_pubkey, false),
+            AccountMeta::new(reward_token_pubkey, false),
+            AccountMeta::new(source_reward_token_pubkey, false),
+            AccountMeta::new_readonly(spl_token::id(), false),
+            AccountMeta::new_readonly(fee_discount_pubkey, false),
+            AccountMeta::new_readonly(fee_discount_tiers_pubkey, false),
+            AccountMeta::new_readonly(user_referrer_data_pubkey, false),
+            AccountMeta::new(referrer_token_pubkey, false),
+        ];
+        for hop in &[&swap_hop, &stable_swap_hop] {
+            expected_account.extend_from_slice(&[
+                AccountMeta::new(hop.swap_pubkey, false),
+                AccountMeta::new_readonly(hop.swap_authority_pubkey, false),
+                AccountMeta::new(hop.swap_source_pubkey, false),
+                AccountMeta::new(hop.swap_destination_pubkey, false),
+                AccountMeta::new(hop.admin_fee_destination_pubkey, false),
+            ]);
+            for fee_token_pubkey in hop
+                .treasury_token_pubkey
+                .iter()
+                .chain(hop.referrer_fee_token_pubkey.iter())
+            {
+                expected_account.push(AccountMeta::new(*fee_token_pubkey, false));
+            }
+            for price_pubkey in &hop.price_pubkeys {
+                expected_account.push(AccountMeta::new_readonly(*price_pubkey, false));
+            }
+        }
+
+        assert_eq!(result.as_ref().unwrap().data, expected_data);
+        assert_eq!(result.as_ref().unwrap().accounts, expected_account);
+
+        assert_eq!(
+            route(
+                program_id,
+                config_pubkey,
+                market_authority_pubkey,
+                user_transfer_authority_pubkey,
+                source_pubkey,
+                destination_pubkey,
+                reward_token_pubkey,
+                source_reward_token_pubkey,
+                None,
+                None,
+                None,
+                None,
+                &[],
+                SwapData {
+                    amount_in,
+                    minimum_amount_out,
+                    deadline_slot: None,
+                    output_owner: None,
+                },
+            )
+            .unwrap_err(),
+            SwapError::InvalidInput.into()
+        );
+    }
+
+    #[test]
+    fn test_pack_smart_swap() {
+        let amount_in: u64 = 1_000_000;
+        let minimum_amount_out: u64 = 990_000;
+        let check = RouterInstruction::SmartSwap(SwapData {
+            amount_in,
+            minimum_amount_out,
+            deadline_slot: Some(100),
+            output_owner: None,
+        });
+        let packed = check.pack();
+        let mut expect = vec![29];
+        expect.extend_from_slice(&amount_in.to_le_bytes());
+        expect.extend_from_slice(&minimum_amount_out.to_le_bytes());
+        expect.extend_from_slice(&100u64.to_le_bytes());
+        assert_eq!(packed, expect);
+        let unpacked = RouterInstruction::unpack(&expect).unwrap();
+        assert_eq!(unpacked, check);
+    }
+
+    #[test]
+    fn test_smart_swap() {
+        let program_id = Pubkey::new_unique();
+        let config_pubkey = Pubkey::new_unique();
+        let market_authority_pubkey = Pubkey::new_unique();
+        let user_transfer_authority_pubkey = Pubkey::new_unique();
+        let source_pubkey = Pubkey::new_unique();
+        let destination_pubkey = Pubkey::new_unique();
+        let reward_token_pubkey = Pubkey::new_unique();
+        let source_reward_token_pubkey = Pubkey::new_unique();
+        let fee_discount_pubkey = Pubkey::new_unique();
+        let fee_discount_tiers_pubkey = Pubkey::new_unique();
+        let user_referrer_data_pubkey = Pubkey::new_unique();
+        let referrer_token_pubkey = Pubkey::new_unique();
+        let normal_pool = RouteHop {
+            swap_pubkey: Pubkey::new_unique(),
+            swap_authority_pubkey: Pubkey::new_unique(),
+            swap_source_pubkey: Pubkey::new_unique(),
+            swap_destination_pubkey: Pubkey::new_unique(),
+            admin_fee_destination_pubkey: Pubkey::new_unique(),
+            treasury_token_pubkey: None,
+            referrer_fee_token_pubkey: None,
+            price_pubkeys: (0..5).map(|_| Pubkey::new_unique()).collect(),
+        };
+        let stable_pool = RouteHop {
+            swap_pubkey: Pubkey::new_unique(),
+            swap_authority_pubkey: Pubkey::new_unique(),
+            swap_source_pubkey: Pubkey::new_unique(),
+            swap_destination_pubkey: Pubkey::new_unique(),
+            admin_fee_destination_pubkey: Pubkey::new_unique(),
+            treasury_token_pubkey: None,
+            referrer_fee_token_pubkey: None,
+            price_pubkeys: vec![],
+        };
+        let swap_data = SwapData {
+            amount_in: 1_000_000,
+            minimum_amount_out: 990_000,
+            deadline_slot: None,
+            output_owner: None,
+        };
+
+        let instruction = smart_swap(
+            program_id,
+            config_pubkey,
+            market_authority_pubkey,
+            user_transfer_authority_pubkey,
+            source_pubkey,
+            destination_pubkey,
+            reward_token_pubkey,
+            source_reward_token_pubkey,
+            Some(fee_discount_pubkey),
+            Some(fee_discount_tiers_pubkey),
+            Some(user_referrer_data_pubkey),
+            Some(referrer_token_pubkey),
+            normal_pool.clone(),
+            stable_pool.clone(),
+            swap_data.clone(),
+        )
+        .unwrap();
+        assert_eq!(
+            instruction.data,
+            RouterInstruction::SmartSwap(swap_data.clone()).pack()
+        );
+        let route_instruction = route(
+            program_id,
+            config_pubkey,
+            market_authority_pubkey,
+            user_transfer_authority_pubkey,
+            source_pubkey,
+            destination_pubkey,
+            reward_token_pubkey,
+            source_reward_token_pubkey,
+            Some(fee_discount_pubkey),
+            Some(fee_discount_tiers_pubkey),
+            Some(user_referrer_data_pubkey),
+            Some(referrer_token_pubkey),
+            &[normal_pool.clone(), stable_pool],
+            swap_data.clone(),
+        )
+        .unwrap();
+        assert_eq!(instruction.accounts, route_instruction.accounts);
+
+        assert_eq!(
+            smart_swap(
+                program_id,
+                config_pubkey,
+                market_authority_pubkey,
+                user_transfer_authority_pubkey,
+                source_pubkey,
+                destination_pubkey,
+                reward_token_pubkey,
+                source_reward_token_pubkey,
+                None,
+                None,
+                None,
+                None,
+                normal_pool.clone(),
+                normal_pool,
+                swap_data,
+            )
+            .unwrap_err(),
+            SwapError::InvalidInput.into()
+        );
+    }
+
+    #[test]
+    fn test_flash_swap() {
+        let program_id = Pubkey::new_unique();
+        let config_pubkey = Pubkey::new_unique();
+        let swap_pubkey = Pubkey::new_unique();
+        let swap_authority_pubkey = Pubkey::new_unique();
         let swap_token_a_pubkey = Pubkey::new_unique();
         let swap_token_b_pubkey = Pubkey::new_unique();
-        let pool_mint_pubkey = Pubkey::new_unique();
-        let destination_pubkey = Pubkey::new_unique();
-        let deposit_data = DepositData {
-            token_a_amount,
-            token_b_amount,
-            min_mint_amount,
+        let destination_token_a_pubkey = Pubkey::new_unique();
+        let destination_token_b_pubkey = Pubkey::new_unique();
+        let admin_fee_a_pubkey = Pubkey::new_unique();
+        let admin_fee_b_pubkey = Pubkey::new_unique();
+        let callback_program_id = Pubkey::new_unique();
+        let callback_accounts = vec![
+            AccountMeta::new(Pubkey::new_unique(), false),
+            AccountMeta::new_readonly(Pubkey::new_unique(), true),
+        ];
+        let flash_swap_data = FlashSwapData {
+            amount_a: 1_000_000,
+            amount_b: 2_000_000,
+            deadline_slot: None,
+            callback_data: vec![7u8; 4],
         };
 
-        let result = deposit(
+        let result = flash_swap(
             program_id,
+            config_pubkey,
             swap_pubkey,
-            authority_pubkey,
-            user_transfer_authority_pubkey,
-            deposit_token_a_pubkey,
-            deposit_token_b_pubkey,
+            swap_authority_pubkey,
             swap_token_a_pubkey,
             swap_token_b_pubkey,
-            pool_mint_pubkey,
-            destination_pubkey,
-            deposit_data.clone(),
+            destination_token_a_pubkey,
+            destination_token_b_pubkey,
+            admin_fee_a_pubkey,
+            admin_fee_b_pubkey,
+            None,
+            callback_program_id,
+            callback_accounts.clone(),
+            flash_swap_data.clone(),
         );
 
-        let mut expected_data = vec![2];
-        expected_data.extend_from_slice(&deposit_data.token_a_amount.to_le_bytes());
-        expected_data.extend_from_slice(&deposit_data.token_b_amount.to_le_bytes());
-        expected_data.extend_from_slice(&deposit_data.min_mint_amount.to_le_bytes());
-
-        let expected_account = vec![
-            AccountMeta {
-                pubkey: swap_pubkey,
-                is_signer: false,
-                is_writable: true,
-            },
-            AccountMeta {
-                pubkey: authority_pubkey,
-                is_signer: false,
-                is_writable: false,
-            },
-            AccountMeta {
-                pubkey: user_transfer_authority_pubkey,
-                is_signer: true,
-                is_writable: false,
-            },
-            AccountMeta {
-                pubkey: deposit_token_a_pubkey,
-                is_signer: false,
-                is_writable: true,
-            },
-            AccountMeta {
-                pubkey: deposit_token_b_pubkey,
-                is_signer: false,
-                is_writable: true,
-            },
-            AccountMeta {
-                pubkey: swap_token_a_pubkey,
-                is_signer: false,
-                is_writable: true,
-            },
-            AccountMeta {
-                pubkey: swap_token_b_pubkey,
-                is_signer: false,
-                is_writable: true,
-            },
-            AccountMeta {
-                pubkey: pool_mint_pubkey,
-                is_signer: false,
-                is_writable: true,
-            },
-            AccountMeta {
-                pubkey: destination_pubkey,
-                is_signer: false,
-                is_writable: true,
-            },
-            AccountMeta {
-                pubkey: spl_token::id(),
-                is_signer: false,
-                is_writable: false,
-            },
+        let expected_data = SwapInstruction::FlashSwap(flash_swap_data).pack();
+
+        let mut expected_account = vec![
+            AccountMeta::new_readonly(config_pubkey, false),
+            AccountMeta::new(swap_pubkey, false),
+            AccountMeta::new_readonly(swap_authority_pubkey, false),
+            AccountMeta::new(swap_token_a_pubkey, false),
+            AccountMeta::new(swap_token_b_pubkey, false),
+            AccountMeta::new(destination_token_a_pubkey, false),
+            AccountMeta::new(destination_token_b_pubkey, false),
+            AccountMeta::new(admin_fee_a_pubkey, false),
+            AccountMeta::new(admin_fee_b_pubkey, false),
+            AccountMeta::new_readonly(spl_token::id(), false),
+            AccountMeta::new_readonly(callback_program_id, false),
         ];
+        expected_account.extend(callback_accounts);
 
         assert_eq!(result.as_ref().unwrap().data, expected_data);
         assert_eq!(result.as_ref().unwrap().accounts, expected_account);
@@ -3351,6 +11404,7 @@ mod tests {
             pool_token_amount,
             minimum_token_a_amount,
             minimum_token_b_amount,
+            deadline_slot: None,
         };
 
         let result = withdraw(
@@ -3454,6 +11508,38 @@ mod tests {
         assert_eq!(unpacked, Ok(set_referrer_instruction));
     }
 
+    #[test]
+    fn test_update_referrer() {
+        let update_referrer_instruction = SwapInstruction::UpdateReferrer;
+        let packed = update_referrer_instruction.pack();
+
+        let expected = vec![38u8];
+        assert_eq!(expected, packed);
+
+        let unpacked = SwapInstruction::unpack(&expected);
+        assert_eq!(unpacked, Ok(update_referrer_instruction));
+        assert!(matches!(
+            InstructionType::check(&expected),
+            Some(InstructionType::Swap)
+        ));
+    }
+
+    #[test]
+    fn test_init_referrer_stats() {
+        let init_referrer_stats_instruction = SwapInstruction::InitReferrerStats;
+        let packed = init_referrer_stats_instruction.pack();
+
+        let expected = vec![39u8];
+        assert_eq!(expected, packed);
+
+        let unpacked = SwapInstruction::unpack(&expected);
+        assert_eq!(unpacked, Ok(init_referrer_stats_instruction));
+        assert!(matches!(
+            InstructionType::check(&expected),
+            Some(InstructionType::Swap)
+        ));
+    }
+
     #[test]
     fn test_pack_farm_initialization() {
         let fee_numerator = 1;
@@ -3480,6 +11566,23 @@ mod tests {
 
         let unpacked = FarmInstruction::unpack(&expect).unwrap();
         assert_eq!(unpacked, check);
+
+        let check = FarmInstruction::InitializeStaking(FarmInitializeData {
+            fee_numerator,
+            fee_denominator,
+            rewards_numerator,
+            rewards_denominator,
+            bump_seed,
+        });
+        let packed = check.pack();
+        expect[0] = 46;
+        assert_eq!(packed, expect);
+        let unpacked = FarmInstruction::unpack(&expect).unwrap();
+        assert_eq!(unpacked, check);
+        assert!(matches!(
+            InstructionType::check(&expect),
+            Some(InstructionType::Farm)
+        ));
     }
 
     #[test]
@@ -3511,7 +11614,10 @@ mod tests {
     #[test]
     fn test_pack_farm_deposit() {
         let amount = 1_000_000;
-        let check = FarmInstruction::Deposit(FarmDepositData { amount });
+        let check = FarmInstruction::Deposit(FarmDepositData {
+            amount,
+            lock_tier: 0,
+        });
 
         let packed = check.pack();
         let mut expect = vec![24];
@@ -3520,6 +11626,16 @@ mod tests {
 
         let unpacked = FarmInstruction::unpack(&expect).unwrap();
         assert_eq!(unpacked, check);
+
+        let check = FarmInstruction::Deposit(FarmDepositData {
+            amount,
+            lock_tier: 2,
+        });
+        let packed = check.pack();
+        expect.push(2);
+        assert_eq!(packed, expect);
+        let unpacked = FarmInstruction::unpack(&expect).unwrap();
+        assert_eq!(unpacked, check);
     }
 
     #[test]
@@ -3536,6 +11652,150 @@ mod tests {
         assert_eq!(unpacked, check);
     }
 
+    #[test]
+    fn test_pack_farm_set_reward_split() {
+        let percentages = [30u8, 20u8, 0u8, 0u8];
+        let check = FarmInstruction::SetRewardSplit(FarmRewardSplitData { percentages });
+
+        let packed = check.pack();
+        let mut expect = vec![26];
+        expect.extend_from_slice(&percentages);
+        assert_eq!(packed, expect);
+
+        let unpacked = FarmInstruction::unpack(&expect).unwrap();
+        assert_eq!(unpacked, check);
+
+        assert!(FarmInstruction::unpack(&[26u8, 30u8, 20u8]).is_err());
+    }
+
+    #[test]
+    fn test_pack_farm_refresh() {
+        let check = FarmInstruction::Refresh;
+
+        let packed = check.pack();
+        let expect = vec![23];
+        assert_eq!(packed, expect);
+
+        let unpacked = FarmInstruction::unpack(&expect).unwrap();
+        assert_eq!(unpacked, check);
+
+        let instruction = farm_refresh(
+            Pubkey::new_unique(),
+            Pubkey::new_unique(),
+            Pubkey::new_unique(),
+            &[Pubkey::new_unique(), Pubkey::new_unique()],
+        )
+        .unwrap();
+        assert_eq!(instruction.accounts.len(), 4);
+        assert!(instruction.accounts[1].is_writable);
+    }
+
+    #[test]
+    fn test_pack_farm_checkpoint() {
+        let check = FarmInstruction::Checkpoint;
+
+        let packed = check.pack();
+        let expect = vec![27];
+        assert_eq!(packed, expect);
+
+        let unpacked = FarmInstruction::unpack(&expect).unwrap();
+        assert_eq!(unpacked, check);
+        assert!(matches!(
+            InstructionType::check(&expect),
+            Some(InstructionType::Farm)
+        ));
+    }
+
+    #[test]
+    fn test_pack_farm_reward_token_instructions() {
+        let amount = 1_000_000u64;
+        let check = FarmInstruction::FundRewardToken(FarmFundRewardTokenData { index: 1, amount });
+        let packed = check.pack();
+        let mut expect = vec![40, 1];
+        expect.extend_from_slice(&amount.to_le_bytes());
+        assert_eq!(packed, expect);
+        let unpacked = FarmInstruction::unpack(&expect).unwrap();
+        assert_eq!(unpacked, check);
+
+        for (check, tag) in [
+            (FarmInstruction::InitializeFarmUserRewardTokens, 41u8),
+            (FarmInstruction::ClaimRewardTokens, 42u8),
+        ]
+        .iter()
+        {
+            let packed = check.pack();
+            let expect = vec![*tag];
+            assert_eq!(packed, expect);
+            let unpacked = FarmInstruction::unpack(&expect).unwrap();
+            assert_eq!(&unpacked, check);
+            assert!(matches!(
+                InstructionType::check(&expect),
+                Some(InstructionType::Farm)
+            ));
+        }
+    }
+
+    #[test]
+    fn test_pack_farm_compound() {
+        let min_mint_amount = 500u64;
+        let check = FarmInstruction::Compound(FarmCompoundData { min_mint_amount });
+        let packed = check.pack();
+        let mut expect = vec![43];
+        expect.extend_from_slice(&min_mint_amount.to_le_bytes());
+        assert_eq!(packed, expect);
+        let unpacked = FarmInstruction::unpack(&expect).unwrap();
+        assert_eq!(unpacked, check);
+        assert!(matches!(
+            InstructionType::check(&expect),
+            Some(InstructionType::Farm)
+        ));
+    }
+
+    #[test]
+    fn test_pack_farm_set_compound_delegate() {
+        let compound_delegate = Pubkey::new_unique();
+        let check =
+            FarmInstruction::SetCompoundDelegate(FarmCompoundDelegateData { compound_delegate });
+        let packed = check.pack();
+        let mut expect = vec![60];
+        expect.extend_from_slice(compound_delegate.as_ref());
+        assert_eq!(packed, expect);
+        let unpacked = FarmInstruction::unpack(&expect).unwrap();
+        assert_eq!(unpacked, check);
+        assert!(matches!(
+            InstructionType::check(&expect),
+            Some(InstructionType::Farm)
+        ));
+    }
+
+    #[test]
+    fn test_pack_farm_claim_multi() {
+        let check = FarmInstruction::ClaimMulti(FarmClaimMultiData { farm_count: 3 });
+        let packed = check.pack();
+        let expect = vec![45, 3];
+        assert_eq!(packed, expect);
+        let unpacked = FarmInstruction::unpack(&expect).unwrap();
+        assert_eq!(unpacked, check);
+        assert!(matches!(
+            InstructionType::check(&expect),
+            Some(InstructionType::Farm)
+        ));
+    }
+
+    #[test]
+    fn test_pack_farm_transfer_position() {
+        let check = FarmInstruction::TransferPosition;
+        let packed = check.pack();
+        let expect = vec![44];
+        assert_eq!(packed, expect);
+        let unpacked = FarmInstruction::unpack(&expect).unwrap();
+        assert_eq!(unpacked, check);
+        assert!(matches!(
+            InstructionType::check(&expect),
+            Some(InstructionType::Farm)
+        ));
+    }
+
     #[test]
     fn test_farm_initialize() {
         let fee_numerator = 1;
@@ -3669,6 +11929,42 @@ mod tests {
         assert_eq!(result.as_ref().unwrap().accounts, expected_account);
     }
 
+    #[test]
+    fn test_farm_user_initialize_with_registry() {
+        let program_id = Pubkey::new_unique();
+        let config_info_pubkey = Pubkey::new_unique();
+        let farm_pool_pubkey = Pubkey::new_unique();
+        let farm_user_pubkey = Pubkey::new_unique();
+        let farm_owner_pubkey = Pubkey::new_unique();
+        let registry_pubkey = Pubkey::new_unique();
+        let payer_pubkey = Pubkey::new_unique();
+
+        let result = farm_user_initialize_with_registry(
+            program_id,
+            config_info_pubkey,
+            farm_pool_pubkey,
+            farm_user_pubkey,
+            farm_owner_pubkey,
+            registry_pubkey,
+            payer_pubkey,
+        );
+
+        let expected_data = vec![21];
+        let expected_account = vec![
+            AccountMeta::new_readonly(config_info_pubkey, false),
+            AccountMeta::new(farm_pool_pubkey, false),
+            AccountMeta::new(farm_user_pubkey, false),
+            AccountMeta::new_readonly(farm_owner_pubkey, true),
+            AccountMeta::new_readonly(rent::id(), false),
+            AccountMeta::new(registry_pubkey, false),
+            AccountMeta::new(payer_pubkey, true),
+            AccountMeta::new_readonly(system_program::id(), false),
+        ];
+
+        assert_eq!(result.as_ref().unwrap().data, expected_data);
+        assert_eq!(result.as_ref().unwrap().accounts, expected_account);
+    }
+
     #[test]
     fn test_farm_deposit() {
         let amount = 1_000_000;
@@ -3888,4 +12184,69 @@ mod tests {
         assert_eq!(result.as_ref().unwrap().data, expected_data);
         assert_eq!(result.as_ref().unwrap().accounts, expected_account);
     }
+
+    #[test]
+    fn test_farm_set_reward_split() {
+        let program_id = Pubkey::new_unique();
+        let config_pubkey = Pubkey::new_unique();
+        let farm_pool_pubkey = Pubkey::new_unique();
+        let farm_user_pubkey = Pubkey::new_unique();
+        let farm_owner_pubkey = Pubkey::new_unique();
+        let reward_split_pubkey = Pubkey::new_unique();
+        let recipient_pubkeys = [Pubkey::new_unique(), Pubkey::new_unique()];
+        let percentages = [30u8, 20u8, 0u8, 0u8];
+
+        let result = farm_set_reward_split(
+            program_id,
+            config_pubkey,
+            farm_pool_pubkey,
+            farm_user_pubkey,
+            farm_owner_pubkey,
+            reward_split_pubkey,
+            &recipient_pubkeys,
+            FarmRewardSplitData { percentages },
+        );
+
+        let mut expected_data = vec![26];
+        expected_data.extend_from_slice(&percentages);
+        let expected_account = vec![
+            AccountMeta::new_readonly(config_pubkey, false),
+            AccountMeta::new_readonly(farm_pool_pubkey, false),
+            AccountMeta::new(farm_user_pubkey, false),
+            AccountMeta::new_readonly(farm_owner_pubkey, true),
+            AccountMeta::new(reward_split_pubkey, false),
+            AccountMeta::new_readonly(rent::id(), false),
+            AccountMeta::new_readonly(recipient_pubkeys[0], false),
+            AccountMeta::new_readonly(recipient_pubkeys[1], false),
+        ];
+
+        assert_eq!(result.as_ref().unwrap().data, expected_data);
+        assert_eq!(result.as_ref().unwrap().accounts, expected_account);
+    }
+
+    #[test]
+    fn test_farm_checkpoint() {
+        let program_id = Pubkey::new_unique();
+        let config_pubkey = Pubkey::new_unique();
+        let farm_pool_pubkey = Pubkey::new_unique();
+        let farm_user_pubkeys = [Pubkey::new_unique(), Pubkey::new_unique()];
+
+        let result = farm_checkpoint(
+            program_id,
+            config_pubkey,
+            farm_pool_pubkey,
+            &farm_user_pubkeys,
+        );
+
+        let expected_data = vec![27];
+        let expected_account = vec![
+            AccountMeta::new_readonly(config_pubkey, false),
+            AccountMeta::new_readonly(farm_pool_pubkey, false),
+            AccountMeta::new(farm_user_pubkeys[0], false),
+            AccountMeta::new(farm_user_pubkeys[1], false),
+        ];
+
+        assert_eq!(result.as_ref().unwrap().data, expected_data);
+        assert_eq!(result.as_ref().unwrap().accounts, expected_account);
+    }
 }
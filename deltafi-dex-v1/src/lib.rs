@@ -2,15 +2,33 @@
 
 //! An Uniswap-like program for the Solana blockchain.
 
+pub mod adapter;
+#[cfg(feature = "program")]
 pub mod admin;
+pub mod chainlink;
 pub mod curve;
+#[cfg(feature = "idl")]
+pub mod decode;
+#[cfg(feature = "program")]
 pub mod entrypoint;
 pub mod error;
+pub mod event;
+#[cfg(feature = "idl")]
+pub mod idl;
 pub mod instruction;
 pub mod math;
+#[cfg(feature = "program")]
+pub mod orderbook;
+#[cfg(feature = "program")]
 pub mod processor;
 pub mod pyth;
+pub mod pyth_pull;
+pub mod quote;
 pub mod state;
+pub mod time;
+#[cfg(all(feature = "program", feature = "no-entrypoint"))]
+pub mod transaction;
+#[cfg(feature = "program")]
 pub mod utils;
 
 // Export current solana-program types for downstream users who may also be
@@ -20,5 +38,19 @@ pub use solana_program;
 /// Serum-Dex V3 mainnet program id
 pub const SERUM_DEX_V3_PROGRAM_ID: &str = "9xQeWvG816bUx9EPjHmaT23yvVM2ZWbrrpZb9PusVFin";
 
-const DUMMY_REFERRER_ADDRESS: &str = "66666666666666666666666666666666666666666666";
+/// OpenBook-Dex mainnet program id
+pub const OPENBOOK_DEX_PROGRAM_ID: &str = "srmqPvymJeFKQ4zGQed1GFppgkRHL9kaELCbyksJtPX";
+
+/// Referrer of the users not referred by anyone, compared bytewise against the referrer
+/// given to `SetReferrer` and stored in the `UserReferrerData`
+pub const DUMMY_REFERRER_ADDRESS: solana_program::pubkey::Pubkey =
+    solana_program::pubkey::Pubkey::new_from_array([0; 32]);
+
+/// Dummy referrer stored before `DUMMY_REFERRER_ADDRESS`, the bytes of the base58 key
+/// "66666666666666666666666666666666666666666666". Still accepted in place of the current one.
+pub const LEGACY_DUMMY_REFERRER_ADDRESS: solana_program::pubkey::Pubkey =
+    solana_program::pubkey::Pubkey::new_from_array([
+        75, 152, 23, 100, 168, 193, 114, 35, 20, 245, 191, 204, 128, 220, 171, 166, 127, 221, 166,
+        139, 111, 25, 1, 37, 202, 219, 109, 49, 103, 76, 89, 211,
+    ]);
 solana_program::declare_id!("D3UC98n8VwyUUJFQeNshAb1VeZWKXjgWMzvAzK7JX3r7");
@@ -5,8 +5,9 @@
 //! precision up to 18 decimal places. Decimals are sized to support
 //! both serialization and precise math for the full range of
 //! unsigned 64-bit integers. The underlying representation is a
-//! u192 rather than u256 to reduce compute cost while losing
-//! support for arithmetic operations at the high end of u64 range.
+//! u192 rather than u256 to reduce compute cost, while the products
+//! of multiplications and divisions are carried in a u256 so that
+//! large pools near the high end of u64 range don't overflow.
 
 #![allow(clippy::assign_op_pattern)]
 #![allow(clippy::ptr_offset_with_cast)]
@@ -15,13 +16,64 @@
 use super::*;
 use crate::error::SwapError;
 use solana_program::program_error::ProgramError;
-use std::{convert::TryFrom, fmt};
+use std::{convert::TryFrom, fmt, str::FromStr};
 use uint::construct_uint;
 
+#[cfg(feature = "serde")]
+use serde::{de, Deserialize, Deserializer, Serialize, Serializer};
+
 construct_uint! {
     pub struct U192(3);
 }
 
+construct_uint! {
+    /// 256-bit unsigned integer, the width of the intermediate products of decimals
+    pub struct U256(4);
+}
+
+impl From<U192> for U256 {
+    fn from(val: U192) -> Self {
+        let U192(ref limbs) = val;
+        Self([limbs[0], limbs[1], limbs[2], 0])
+    }
+}
+
+impl TryFrom<U256> for U192 {
+    type Error = ProgramError;
+
+    fn try_from(val: U256) -> Result<Self, Self::Error> {
+        let U256(ref limbs) = val;
+        if limbs[3] != 0 {
+            return Err(SwapError::CalculationFailure.into());
+        }
+        Ok(Self([limbs[0], limbs[1], limbs[2]]))
+    }
+}
+
+/// a * b / c with the product carried in a U256, the result must fit in a U192
+fn wide_mul_div(a: U192, b: U192, c: U192) -> Result<U192, ProgramError> {
+    let value = U256::from(a)
+        .checked_mul(U256::from(b))
+        .ok_or(SwapError::CalculationFailure)?
+        .checked_div(U256::from(c))
+        .ok_or(SwapError::CalculationFailure)?;
+    U192::try_from(value)
+}
+
+/// a * b / c rounded up with the product carried in a U256, the result must fit in a U192
+fn wide_mul_div_ceil(a: U192, b: U192, c: U192) -> Result<U192, ProgramError> {
+    let c = U256::from(c);
+    let value = U256::from(a)
+        .checked_mul(U256::from(b))
+        .ok_or(SwapError::CalculationFailure)?
+        .checked_add(c)
+        .and_then(|value| value.checked_sub(U256::one()))
+        .ok_or(SwapError::CalculationFailure)?
+        .checked_div(c)
+        .ok_or(SwapError::CalculationFailure)?;
+    U192::try_from(value)
+}
+
 /// The Decimal number type U192 which is a list of u64 that represents a 192 bit integer
 /// The larget U192 us 2^192-1 = 6277101735386680763835789423207666416102355444464034512895
 /// We need this threshold to be (2^192-1)/(10^12)
@@ -181,6 +233,39 @@ impl fmt::Display for Decimal {
     }
 }
 
+impl FromStr for Decimal {
+    type Err = ProgramError;
+
+    /// Parse the `Display` form of a decimal, up to `SCALE` fractional digits
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        let mut parts = s.splitn(2, '.');
+        let integer = parts.next().unwrap_or_default();
+        let fraction = parts.next().unwrap_or_default();
+        if integer.is_empty() || fraction.len() > SCALE {
+            return Err(SwapError::InvalidInput.into());
+        }
+        let scaled_val = format!("{}{:0<width$}", integer, fraction, width = SCALE);
+        Ok(Self(
+            U192::from_dec_str(&scaled_val).map_err(|_| SwapError::InvalidInput)?,
+        ))
+    }
+}
+
+#[cfg(feature = "serde")]
+impl Serialize for Decimal {
+    fn serialize<S: Serializer>(&self, serializer: S) -> Result<S::Ok, S::Error> {
+        serializer.collect_str(self)
+    }
+}
+
+#[cfg(feature = "serde")]
+impl<'de> Deserialize<'de> for Decimal {
+    fn deserialize<D: Deserializer<'de>>(deserializer: D) -> Result<Self, D::Error> {
+        let s = String::deserialize(deserializer)?;
+        Self::from_str(&s).map_err(|_| de::Error::custom(format!("invalid decimal {}", s)))
+    }
+}
+
 impl Default for Decimal {
     fn default() -> Self {
         Self::zero()
@@ -243,13 +328,31 @@ impl TryDiv<Rate> for Decimal {
 
 impl TryDiv<Decimal> for Decimal {
     fn try_div(self, rhs: Self) -> Result<Self, ProgramError> {
-        Ok(Self(
-            self.0
-                .checked_mul(Self::wad())
-                .ok_or(SwapError::CalculationFailure)?
-                .checked_div(rhs.0)
-                .ok_or(SwapError::CalculationFailure)?,
-        ))
+        Ok(Self(wide_mul_div(self.0, Self::wad(), rhs.0)?))
+    }
+}
+
+impl TryDivRound<u64> for Decimal {
+    fn try_div_floor(self, rhs: u64) -> Result<Self, ProgramError> {
+        self.try_div(rhs)
+    }
+
+    fn try_div_ceil(self, rhs: u64) -> Result<Self, ProgramError> {
+        Ok(Self(wide_mul_div_ceil(
+            self.0,
+            U192::one(),
+            U192::from(rhs),
+        )?))
+    }
+}
+
+impl TryDivRound<Decimal> for Decimal {
+    fn try_div_floor(self, rhs: Self) -> Result<Self, ProgramError> {
+        self.try_div(rhs)
+    }
+
+    fn try_div_ceil(self, rhs: Self) -> Result<Self, ProgramError> {
+        Ok(Self(wide_mul_div_ceil(self.0, Self::wad(), rhs.0)?))
     }
 }
 
@@ -271,19 +374,14 @@ impl TryMul<Rate> for Decimal {
 
 impl TryMul<Decimal> for Decimal {
     fn try_mul(self, rhs: Self) -> Result<Self, ProgramError> {
-        Ok(Self(
-            self.0
-                .checked_mul(rhs.0)
-                .ok_or(SwapError::CalculationFailure)?
-                .checked_div(Self::wad())
-                .ok_or(SwapError::CalculationFailure)?,
-        ))
+        Ok(Self(wide_mul_div(self.0, rhs.0, Self::wad())?))
     }
 }
 
 #[cfg(test)]
 mod test {
     use super::*;
+    use proptest::prelude::*;
 
     #[test]
     fn test_decimal() {
@@ -558,6 +656,103 @@ mod test {
         assert_eq!(U192::exp10(SCALE), Decimal::wad());
     }
 
+    #[test]
+    fn test_decimal_div_round() {
+        let one_third = Decimal::one().try_div_floor(3u64).unwrap();
+        assert_eq!(one_third, Decimal::from_scaled_val(333_333_333_333));
+        assert_eq!(
+            Decimal::one().try_div_ceil(3u64).unwrap(),
+            Decimal::from_scaled_val(333_333_333_334)
+        );
+        assert_eq!(
+            Decimal::one().try_div_floor(Decimal::from(3u64)).unwrap(),
+            one_third
+        );
+        assert_eq!(
+            Decimal::one().try_div_ceil(Decimal::from(3u64)).unwrap(),
+            Decimal::from_scaled_val(333_333_333_334)
+        );
+
+        // Exact quotients are the same both ways
+        let six = Decimal::from(6u64);
+        assert_eq!(six.try_div_floor(3u64), six.try_div_ceil(3u64));
+        assert_eq!(
+            six.try_div_floor(Decimal::from(2u64)),
+            six.try_div_ceil(Decimal::from(2u64))
+        );
+        assert_eq!(
+            Decimal::zero().try_div_ceil(Decimal::from(7u64)).unwrap(),
+            Decimal::zero()
+        );
+
+        assert!(six.try_div_floor(0u64).is_err());
+        assert!(six.try_div_ceil(0u64).is_err());
+        assert!(six.try_div_ceil(Decimal::zero()).is_err());
+        assert_eq!(
+            Decimal(U192::max_value())
+                .try_div_ceil(Decimal::one())
+                .unwrap(),
+            Decimal(U192::max_value())
+        );
+    }
+
+    #[test]
+    fn test_decimal_wide_mul_div() {
+        // Products of u64::MAX pools are past the U192 range but within the U256 range
+        let large = Decimal::from(u64::MAX);
+        assert!(large.0.checked_mul(large.0).is_none());
+        let value = large.try_mul(large).unwrap();
+        assert_eq!(value, Decimal::from(u64::MAX as u128 * u64::MAX as u128));
+        assert_eq!(value.try_div(large).unwrap(), large);
+        assert_eq!(
+            large.try_div(value).unwrap(),
+            Decimal::one().try_div(large).unwrap()
+        );
+
+        let max = Decimal(U192::max_value());
+        assert_eq!(max.try_mul(Decimal::one()).unwrap(), max);
+        assert_eq!(max.try_div(Decimal::one()).unwrap(), max);
+        assert_eq!(
+            max.try_mul(Decimal::from(2u64)).unwrap_err(),
+            SwapError::CalculationFailure.into()
+        );
+        assert_eq!(
+            max.try_div(Decimal::from_scaled_val(1)).unwrap_err(),
+            SwapError::CalculationFailure.into()
+        );
+        assert!(large.try_div(Decimal::zero()).is_err());
+
+        assert_eq!(U256::from(U192::max_value()).0[3], 0);
+        assert_eq!(
+            U192::try_from(U256::from(U192::max_value())).unwrap(),
+            U192::max_value()
+        );
+        assert!(U192::try_from(U256::max_value()).is_err());
+    }
+
+    #[test]
+    fn test_decimal_from_str() {
+        for decimal in &[
+            Decimal::zero(),
+            Decimal::one(),
+            Decimal::from_scaled_val(1),
+            Decimal::from(u64::MAX),
+        ] {
+            assert_eq!(Decimal::from_str(&decimal.to_string()), Ok(*decimal));
+        }
+        assert_eq!(Decimal::from_str("12"), Ok(Decimal::from(12u64)));
+        assert_eq!(
+            Decimal::from_str("0.5"),
+            Ok(Decimal::from_scaled_val(HALF_WAD as u128))
+        );
+        for invalid in &["", ".5", "-1", "1.5e3", "0.0000000000001"] {
+            assert_eq!(
+                Decimal::from_str(invalid),
+                Err(SwapError::InvalidInput.into())
+            );
+        }
+    }
+
     #[test]
     fn test_sqrt() {
         let base = Decimal::from(u64::MAX);
@@ -567,4 +762,25 @@ mod test {
         // comparison with 10_000u64 means confidence in 10^-8
         assert!(u64::MAX - to_u64 < 10_000u64);
     }
+
+    proptest! {
+        #[test]
+        fn test_decimal_div_round_bounds(
+            numerator in 0..=u128::MAX,
+            denominator in 1..=u128::MAX,
+        ) {
+            let numerator = Decimal::from_scaled_val(numerator);
+            let denominator = Decimal::from_scaled_val(denominator);
+            if let (Ok(floor), Ok(ceil)) = (
+                numerator.try_div_floor(denominator),
+                numerator.try_div_ceil(denominator),
+            ) {
+                // The ceil is the floor, or the floor up by the last scaled digit
+                prop_assert!(floor <= ceil);
+                prop_assert!(ceil.0 <= floor.0 + U192::one());
+                let product = U256::from(ceil.0) * U256::from(denominator.0);
+                prop_assert!(product >= U256::from(numerator.0) * U256::from(WAD));
+            }
+        }
+    }
 }
@@ -48,6 +48,15 @@ pub trait TryMul<RHS>: Sized {
     fn try_mul(self, rhs: RHS) -> Result<Self, ProgramError>;
 }
 
+/// Try to divide rounding the last scaled digit in an explicit direction, return an error
+/// on overflow or divide by zero
+pub trait TryDivRound<RHS>: Sized {
+    /// Divide, rounding down
+    fn try_div_floor(self, rhs: RHS) -> Result<Self, ProgramError>;
+    /// Divide, rounding up
+    fn try_div_ceil(self, rhs: RHS) -> Result<Self, ProgramError>;
+}
+
 #[cfg(test)]
 mod tests {
     use super::*;
@@ -0,0 +1,407 @@
+//! Readonly reader of Serum/OpenBook market and order book accounts.
+//!
+//! serum_dex only exposes mutable loaders for the bids and asks slabs, which forces the
+//! accounts to be write locked. The layouts are read here from immutably borrowed data so
+//! the market accounts can be passed readonly.
+
+use arrayref::{array_ref, array_refs};
+use solana_program::{account_info::AccountInfo, program_error::ProgramError, pubkey::Pubkey};
+
+use crate::error::SwapError;
+
+/// "serum" padding at the start of every dex account
+const ACCOUNT_HEAD_PADDING: &[u8; 5] = b"serum";
+/// "padding" at the end of every dex account
+const ACCOUNT_TAIL_PADDING: &[u8; 7] = b"padding";
+
+const ACCOUNT_FLAG_INITIALIZED: u64 = 1;
+const ACCOUNT_FLAG_MARKET: u64 = 1 << 1;
+const ACCOUNT_FLAG_BIDS: u64 = 1 << 5;
+const ACCOUNT_FLAG_ASKS: u64 = 1 << 6;
+
+/// Bytes of the market state read by the program, permissioned markets append more fields
+const MARKET_STATE_USED_BYTES: usize = 360;
+
+const SLAB_HEADER_LEN: usize = 32;
+const SLAB_NODE_LEN: usize = 72;
+const SLAB_NODE_TAG_INNER: u32 = 1;
+const SLAB_NODE_TAG_LEAF: u32 = 2;
+
+/// Market fields needed to price a pool
+#[derive(Clone, Debug, PartialEq)]
+pub struct MarketView {
+    /// Base token mint
+    pub coin_mint: Pubkey,
+    /// Quote token mint
+    pub pc_mint: Pubkey,
+    /// Bids slab account
+    pub bids: Pubkey,
+    /// Asks slab account
+    pub asks: Pubkey,
+    /// Base token lot size
+    pub coin_lot_size: u64,
+    /// Quote token lot size
+    pub pc_lot_size: u64,
+}
+
+/// Side of the order book
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub enum Side {
+    /// Bids, best order is the highest price
+    Bid,
+    /// Asks, best order is the lowest price
+    Ask,
+}
+
+/// Strip the head and tail padding of a dex account and split the account flags
+fn strip_padding(data: &[u8]) -> Result<(u64, &[u8]), ProgramError> {
+    if data.len() < ACCOUNT_HEAD_PADDING.len() + 8 + ACCOUNT_TAIL_PADDING.len() {
+        return Err(SwapError::InvalidSerumData.into());
+    }
+    let (head, rest) = data.split_at(ACCOUNT_HEAD_PADDING.len());
+    let (body, tail) = rest.split_at(rest.len() - ACCOUNT_TAIL_PADDING.len());
+    if head != ACCOUNT_HEAD_PADDING || tail != ACCOUNT_TAIL_PADDING {
+        return Err(SwapError::InvalidSerumData.into());
+    }
+    let (account_flags, body) = body.split_at(8);
+    Ok((u64::from_le_bytes(*array_ref![account_flags, 0, 8]), body))
+}
+
+/// Load the market fields from an immutably borrowed market account
+pub fn load_market(market_info: &AccountInfo) -> Result<MarketView, ProgramError> {
+    let data = market_info.try_borrow_data()?;
+    let (account_flags, body) = strip_padding(&data)?;
+    if account_flags & (ACCOUNT_FLAG_INITIALIZED | ACCOUNT_FLAG_MARKET)
+        != ACCOUNT_FLAG_INITIALIZED | ACCOUNT_FLAG_MARKET
+    {
+        return Err(SwapError::InvalidSerumData.into());
+    }
+    if body.len() < MARKET_STATE_USED_BYTES - 8 {
+        return Err(SwapError::InvalidSerumData.into());
+    }
+
+    let input = array_ref![body, 0, MARKET_STATE_USED_BYTES - 8];
+    #[allow(clippy::ptr_offset_with_cast)]
+    let (own_address, _, coin_mint, pc_mint, _, bids, asks, coin_lot_size, pc_lot_size) =
+        array_refs![input, 32, 8, 32, 32, 168, 32, 32, 8, 8];
+    if own_address != market_info.key.as_ref() {
+        return Err(SwapError::InvalidSerumData.into());
+    }
+
+    Ok(MarketView {
+        coin_mint: Pubkey::new_from_array(*coin_mint),
+        pc_mint: Pubkey::new_from_array(*pc_mint),
+        bids: Pubkey::new_from_array(*bids),
+        asks: Pubkey::new_from_array(*asks),
+        coin_lot_size: u64::from_le_bytes(*coin_lot_size),
+        pc_lot_size: u64::from_le_bytes(*pc_lot_size),
+    })
+}
+
+/// Get the order id of the best order on one side of the book, None if the side is empty.
+/// The price in lots is the upper 64 bits of the order id.
+pub fn best_order_id(
+    market: &MarketView,
+    slab_info: &AccountInfo,
+    side: Side,
+) -> Result<Option<u128>, ProgramError> {
+    let (expected_key, expected_flag) = match side {
+        Side::Bid => (&market.bids, ACCOUNT_FLAG_BIDS),
+        Side::Ask => (&market.asks, ACCOUNT_FLAG_ASKS),
+    };
+    if slab_info.key != expected_key {
+        return Err(SwapError::InvalidSerumData.into());
+    }
+
+    let data = slab_info.try_borrow_data()?;
+    let (account_flags, slab) = strip_padding(&data)?;
+    if account_flags != ACCOUNT_FLAG_INITIALIZED | expected_flag || slab.len() < SLAB_HEADER_LEN {
+        return Err(SwapError::InvalidSerumData.into());
+    }
+    let (header, nodes) = slab.split_at(SLAB_HEADER_LEN);
+    let header = array_ref![header, 0, SLAB_HEADER_LEN];
+    #[allow(clippy::ptr_offset_with_cast)]
+    let (_, root_node, leaf_count) = array_refs![header, 20, 4, 8];
+    if u64::from_le_bytes(*leaf_count) == 0 {
+        return Ok(None);
+    }
+
+    // Walk down the critbit tree, the max key is always on the right and the min on the left
+    let node_count = nodes.len() / SLAB_NODE_LEN;
+    let child_index = match side {
+        Side::Bid => 1,
+        Side::Ask => 0,
+    };
+    let mut handle = u32::from_le_bytes(*root_node) as usize;
+    for _ in 0..node_count {
+        if handle >= node_count {
+            return Err(SwapError::InvalidSerumData.into());
+        }
+        let node = array_ref![nodes, handle * SLAB_NODE_LEN, SLAB_NODE_LEN];
+        #[allow(clippy::ptr_offset_with_cast)]
+        let (tag, _, key, children, _) = array_refs![node, 4, 4, 16, 8, 40];
+        match u32::from_le_bytes(*tag) {
+            SLAB_NODE_TAG_LEAF => return Ok(Some(u128::from_le_bytes(*key))),
+            SLAB_NODE_TAG_INNER => {
+                let child = array_ref![children, child_index * 4, 4];
+                handle = u32::from_le_bytes(*child) as usize;
+            }
+            _ => return Err(SwapError::InvalidSerumData.into()),
+        }
+    }
+
+    Err(SwapError::InvalidSerumData.into())
+}
+
+/// Builders of valid dex account data, to simulate order books without copying the bytes
+/// of live accounts
+#[cfg(any(test, feature = "test-utils"))]
+pub mod test_utils {
+    use super::*;
+    use arrayref::{array_mut_ref, mut_array_refs};
+
+    /// Length of the market state of a permissionless market, flags included
+    pub const MARKET_STATE_LEN: usize = 376;
+
+    /// Pad the body of a dex account with its account flags
+    pub fn dex_account_data(account_flags: u64, body: &[u8]) -> Vec<u8> {
+        let mut data = ACCOUNT_HEAD_PADDING.to_vec();
+        data.extend_from_slice(&account_flags.to_le_bytes());
+        data.extend_from_slice(body);
+        data.extend_from_slice(ACCOUNT_TAIL_PADDING);
+        data
+    }
+
+    /// Inner slab node with the handles of its children
+    pub fn inner_node(children: [u32; 2]) -> Vec<u8> {
+        let mut node = SLAB_NODE_TAG_INNER.to_le_bytes().to_vec();
+        node.extend_from_slice(&[0u8; 20]);
+        node.extend_from_slice(&children[0].to_le_bytes());
+        node.extend_from_slice(&children[1].to_le_bytes());
+        node.extend_from_slice(&[0u8; 40]);
+        node
+    }
+
+    /// Leaf slab node of an order at the price in lots
+    pub fn leaf_node(price_lots: u64) -> Vec<u8> {
+        let mut node = SLAB_NODE_TAG_LEAF.to_le_bytes().to_vec();
+        node.extend_from_slice(&[0u8; 4]);
+        node.extend_from_slice(&(((price_lots as u128) << 64) | 7).to_le_bytes());
+        node.extend_from_slice(&[0u8; 48]);
+        node
+    }
+
+    /// Slab of the nodes rooted at the `root` handle
+    pub fn slab_body(root: u32, leaf_count: u64, nodes: &[Vec<u8>]) -> Vec<u8> {
+        let mut body = vec![0u8; 20];
+        body.extend_from_slice(&root.to_le_bytes());
+        body.extend_from_slice(&leaf_count.to_le_bytes());
+        for node in nodes {
+            body.extend_from_slice(node);
+        }
+        body
+    }
+
+    /// Data of the market account at `market_pubkey`
+    pub fn market_account_data(market_pubkey: &Pubkey, market: &MarketView) -> Vec<u8> {
+        let mut body = vec![0u8; MARKET_STATE_LEN - 8];
+        let output = array_mut_ref![body, 0, MARKET_STATE_USED_BYTES - 8];
+        #[allow(clippy::ptr_offset_with_cast)]
+        let (own_address, _, coin_mint, pc_mint, _, bids, asks, coin_lot_size, pc_lot_size) =
+            mut_array_refs![output, 32, 8, 32, 32, 168, 32, 32, 8, 8];
+        own_address.copy_from_slice(market_pubkey.as_ref());
+        coin_mint.copy_from_slice(market.coin_mint.as_ref());
+        pc_mint.copy_from_slice(market.pc_mint.as_ref());
+        bids.copy_from_slice(market.bids.as_ref());
+        asks.copy_from_slice(market.asks.as_ref());
+        *coin_lot_size = market.coin_lot_size.to_le_bytes();
+        *pc_lot_size = market.pc_lot_size.to_le_bytes();
+        dex_account_data(ACCOUNT_FLAG_INITIALIZED | ACCOUNT_FLAG_MARKET, &body)
+    }
+
+    /// Data of a bids or asks account holding one order at each price in lots
+    pub fn slab_account_data(side: Side, price_lots: &[u64]) -> Vec<u8> {
+        let side_flag = match side {
+            Side::Bid => ACCOUNT_FLAG_BIDS,
+            Side::Ask => ACCOUNT_FLAG_ASKS,
+        };
+        let mut sorted_price_lots = price_lots.to_vec();
+        sorted_price_lots.sort_unstable();
+
+        // Chain of inner nodes, each with the next lowest leaf on the left and the rest of
+        // the book on the right, so the min key is the leftmost leaf and the max the rightmost
+        let leaf_count = sorted_price_lots.len();
+        let mut nodes = vec![];
+        for (i, price_lots) in sorted_price_lots.iter().enumerate() {
+            if i + 1 < leaf_count {
+                let handle = nodes.len() as u32;
+                nodes.push(inner_node([handle + 1, handle + 2]));
+            }
+            nodes.push(leaf_node(*price_lots));
+        }
+        dex_account_data(
+            ACCOUNT_FLAG_INITIALIZED | side_flag,
+            &slab_body(0, leaf_count as u64, &nodes),
+        )
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::{test_utils::*, *};
+
+    #[test]
+    fn test_best_order_id() {
+        let market = MarketView {
+            coin_mint: Pubkey::new_unique(),
+            pc_mint: Pubkey::new_unique(),
+            bids: Pubkey::new_unique(),
+            asks: Pubkey::new_unique(),
+            coin_lot_size: 1,
+            pc_lot_size: 1,
+        };
+        let owner = Pubkey::new_unique();
+        // root inner node with leaves at price 100 on the left and 120 on the right
+        let nodes = vec![inner_node([1, 2]), leaf_node(100), leaf_node(120)];
+
+        let mut lamports = 0u64;
+        let mut data = dex_account_data(
+            ACCOUNT_FLAG_INITIALIZED | ACCOUNT_FLAG_BIDS,
+            &slab_body(0, 2, &nodes),
+        );
+        let bids_info = AccountInfo::new(
+            &market.bids,
+            false,
+            false,
+            &mut lamports,
+            &mut data,
+            &owner,
+            false,
+            0u64,
+        );
+        assert_eq!(
+            best_order_id(&market, &bids_info, Side::Bid).map(|id| id.map(|id| id >> 64)),
+            Ok(Some(120))
+        );
+        assert_eq!(
+            best_order_id(&market, &bids_info, Side::Ask),
+            Err(SwapError::InvalidSerumData.into())
+        );
+
+        let mut lamports = 0u64;
+        let mut data = dex_account_data(
+            ACCOUNT_FLAG_INITIALIZED | ACCOUNT_FLAG_ASKS,
+            &slab_body(0, 2, &nodes),
+        );
+        let asks_info = AccountInfo::new(
+            &market.asks,
+            false,
+            false,
+            &mut lamports,
+            &mut data,
+            &owner,
+            false,
+            0u64,
+        );
+        assert_eq!(
+            best_order_id(&market, &asks_info, Side::Ask).map(|id| id.map(|id| id >> 64)),
+            Ok(Some(100))
+        );
+
+        // empty book
+        let mut lamports = 0u64;
+        let mut data = dex_account_data(
+            ACCOUNT_FLAG_INITIALIZED | ACCOUNT_FLAG_ASKS,
+            &slab_body(0, 0, &[]),
+        );
+        let asks_info = AccountInfo::new(
+            &market.asks,
+            false,
+            false,
+            &mut lamports,
+            &mut data,
+            &owner,
+            false,
+            0u64,
+        );
+        assert_eq!(best_order_id(&market, &asks_info, Side::Ask), Ok(None));
+
+        // inner node pointing to itself
+        let mut lamports = 0u64;
+        let mut data = dex_account_data(
+            ACCOUNT_FLAG_INITIALIZED | ACCOUNT_FLAG_ASKS,
+            &slab_body(0, 1, &[inner_node([0, 0])]),
+        );
+        let asks_info = AccountInfo::new(
+            &market.asks,
+            false,
+            false,
+            &mut lamports,
+            &mut data,
+            &owner,
+            false,
+            0u64,
+        );
+        assert_eq!(
+            best_order_id(&market, &asks_info, Side::Ask),
+            Err(SwapError::InvalidSerumData.into())
+        );
+    }
+    #[test]
+    fn test_mock_accounts() {
+        let market_pubkey = Pubkey::new_unique();
+        let market = MarketView {
+            coin_mint: Pubkey::new_unique(),
+            pc_mint: Pubkey::new_unique(),
+            bids: Pubkey::new_unique(),
+            asks: Pubkey::new_unique(),
+            coin_lot_size: 10_000,
+            pc_lot_size: 100,
+        };
+        let owner = Pubkey::new_unique();
+
+        let mut lamports = 0u64;
+        let mut data = market_account_data(&market_pubkey, &market);
+        assert_eq!(data.len(), 5 + MARKET_STATE_LEN + 7);
+        let market_info = AccountInfo::new(
+            &market_pubkey,
+            false,
+            false,
+            &mut lamports,
+            &mut data,
+            &owner,
+            false,
+            0u64,
+        );
+        assert_eq!(load_market(&market_info), Ok(market.clone()));
+
+        for (side, price_lots, expected) in vec![
+            (Side::Bid, vec![], None),
+            (Side::Bid, vec![24], Some(24)),
+            (Side::Bid, vec![20, 24, 22], Some(24)),
+            (Side::Ask, vec![26], Some(26)),
+            (Side::Ask, vec![28, 26, 30, 27], Some(26)),
+        ] {
+            let slab_pubkey = match side {
+                Side::Bid => market.bids,
+                Side::Ask => market.asks,
+            };
+            let mut lamports = 0u64;
+            let mut data = slab_account_data(side, &price_lots);
+            let slab_info = AccountInfo::new(
+                &slab_pubkey,
+                false,
+                false,
+                &mut lamports,
+                &mut data,
+                &owner,
+                false,
+                0u64,
+            );
+            assert_eq!(
+                best_order_id(&market, &slab_info, side).map(|id| id.map(|id| (id >> 64) as u64)),
+                Ok(expected)
+            );
+        }
+    }
+}
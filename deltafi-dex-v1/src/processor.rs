@@ -2,59 +2,113 @@
 
 #![allow(clippy::too_many_arguments)]
 
-use std::{cmp::min, convert::TryInto, str::FromStr};
+use std::{
+    cmp::min,
+    convert::{TryFrom, TryInto},
+};
 
+use arrayref::array_ref;
 use solana_program::pubkey::PubkeyError;
 use solana_program::{
-    account_info::{next_account_info, AccountInfo},
+    account_info::{next_account_info, next_account_infos, AccountInfo},
     entrypoint::ProgramResult,
     hash::hashv,
-    instruction::Instruction,
+    instruction::{AccountMeta, Instruction},
     msg,
-    program::{invoke, invoke_signed},
+    program::{invoke, invoke_signed, set_return_data},
     program_error::ProgramError,
     program_pack::{IsInitialized, Pack},
     pubkey::Pubkey,
+    system_instruction, system_program,
     sysvar::{clock::Clock, rent::Rent, Sysvar},
 };
 use spl_token::{
     self,
     instruction::AuthorityType,
-    state::{Account, Mint},
+    state::{Account, AccountState, Mint},
 };
 
 use crate::{
+    adapter::get_exchange_rate_price,
     admin::{is_admin, process_admin_instruction},
-    curve::{InitPoolStateParams, PoolState, SwapDirection},
+    chainlink,
+    curve::{
+        check_price_divergence, split_amount_in, InitPoolStateParams, PoolState, SwapDirection,
+        MAX_AMP, MIN_AMP, REBALANCE_BOUNTY_BPS, REFRESH_PRICE_REWARD,
+    },
     error::SwapError,
+    event::{Event, EventType, FarmEvent, LiquidityEvent, SwapEvent},
     instruction::{
-        DepositData, FarmDepositData, FarmInitializeData, FarmInstruction, FarmWithdrawData,
-        InitializeData, InstructionType, StableInitializeData, StableSwapInstruction, SwapData,
-        SwapInstruction, WithdrawData,
+        DepositData, DepositOneData, FarmClaimMultiData, FarmCompoundData,
+        FarmCompoundDelegateData, FarmDepositData, FarmFundRewardTokenData, FarmInitializeData,
+        FarmInstruction, FarmRewardSplitData, FarmWithdrawData, FlashSwapData, GetQuoteData,
+        InitializeData, InstructionType, LockLiquidityData, PeggedInitializeData, QuoteData,
+        RebalanceData, RouterInstruction, StableInitializeData, StableSwapInstruction, SwapData,
+        SwapFill, SwapInstruction, TriDepositData, TriInitializeData, TriSwapData, TriWithdrawData,
+        WithdrawData, WithdrawOneData, MAX_ROUTE_HOPS, SMART_SWAP_SEARCH_STEPS,
     },
     math::{Decimal, TryAdd, TryDiv, TryMul},
+    orderbook::{self, Side},
     pyth::{self, PriceStatus},
+    pyth_pull, quote,
     state::{
-        ConfigInfo, FarmInfo, FarmPosition, FarmUser, OraclePriorityFlag, SwapInfo, SwapType,
-        UserReferrerData,
+        tiered_referral_reward, ConfigInfo, FarmInfo, FarmPosition, FarmRewardSplit,
+        FarmRewardTokens, FarmUser, FarmUserRegistry, FarmUserRewardTokens, FeeDiscountAccount,
+        FeeDiscountTiers, Fees, LiquidityLock, MarketMakerQuote, OracleFeed, OracleParams,
+        OraclePriorityFlag, PriceCache, PriceCommitment, ReferrerStats, Rewards, ScratchAccount,
+        SwapInfo, SwapInfoView, SwapType, TriSwapInfo, UserReferrerData, WhitelistEntry,
+        FARM_USER_REGISTRY_PAGE_SIZE, MAX_QUOTE_DURATION_SLOTS, MAX_REWARD_RECIPIENTS,
+        SWAP_INFO_VERSION, TRI_POOL_TOKENS,
     },
-    utils, DUMMY_REFERRER_ADDRESS, SERUM_DEX_V3_PROGRAM_ID,
+    time::TimeSource,
+    utils, DUMMY_REFERRER_ADDRESS,
 };
 
-use serum_dex::{critbit::SlabView, state::Market};
-
 const SEED_REFRERRER: &str = "referrer";
 const SEED_FARM_USER: &str = "farmUser";
+const SEED_QUOTE: &str = "quote";
+const SEED_PRICE_COMMITMENT: &str = "priceCommitment";
+const SEED_FARM_USER_REGISTRY: &str = "farmUserRegistry";
+const SEED_ORACLE_FEED: &str = "oracleFeed";
+const SEED_PRICE_CACHE: &str = "priceCache";
+const SEED_SCRATCH: &str = "scratch";
+const SEED_SCRATCH_TOKEN: &str = "scratchToken";
+const SEED_SWAP: &str = "swap";
+const SEED_FEE_DISCOUNT: &str = "feeDiscount";
+const SEED_FEE_DISCOUNT_TOKEN: &str = "feeDiscountToken";
+const SEED_FEE_DISCOUNT_TIERS: &str = "feeDiscountTiers";
+const SEED_REFERRER_STATS: &str = "referrerStats";
+const SEED_FARM_REWARD_TOKENS: &str = "farmRewardTokens";
+const SEED_FARM_USER_REWARD_TOKENS: &str = "farmUserRewardTokens";
+const SEED_WHITELIST_ENTRY: &str = "whitelistEntry";
+const SEED_LIQUIDITY_LOCK: &str = "liquidityLock";
+const SEED_LIQUIDITY_LOCK_TOKEN: &str = "liquidityLockToken";
+const SEED_NATIVE_SOL: &str = "nativeSol";
 const MAX_SEED_LEN: usize = 32;
 
+/// Seed of the farm user account of a farm pool, created with `create_account_with_seed`
+/// from the owner
+pub fn get_farm_user_seed(farm_pool_key: &Pubkey) -> String {
+    let mut joint_key = format!("{}{}", SEED_FARM_USER, farm_pool_key);
+    joint_key.truncate(MAX_SEED_LEN);
+    joint_key
+}
+
 /// Generate farm user address from owner, farm pool and program keys.
 pub fn get_farm_user_pubkey(
     owner: &Pubkey,
     farm_pool_key: &Pubkey,
     program_id: &Pubkey,
 ) -> Result<Pubkey, PubkeyError> {
-    let joint_key = format!("{}{}", SEED_FARM_USER, farm_pool_key);
-    Pubkey::create_with_seed(owner, &joint_key.as_str()[0..MAX_SEED_LEN], program_id)
+    Pubkey::create_with_seed(owner, &get_farm_user_seed(farm_pool_key), program_id)
+}
+
+/// Seed of the referrer data account of a config, created with `create_account_with_seed`
+/// from the owner
+pub fn get_referrer_data_seed(config_key: &Pubkey) -> String {
+    let mut joint_key = format!("{}{}", SEED_REFRERRER, config_key);
+    joint_key.truncate(MAX_SEED_LEN);
+    joint_key
 }
 
 /// Generate referrer data address from owner, config key and program keys.
@@ -63,357 +117,5772 @@ pub fn get_referrer_data_pubkey(
     config_key: &Pubkey,
     program_id: &Pubkey,
 ) -> Result<Pubkey, PubkeyError> {
-    let joint_key = format!("{}{}", SEED_REFRERRER, config_key);
-    Pubkey::create_with_seed(owner, &joint_key.as_str()[0..MAX_SEED_LEN], program_id)
+    Pubkey::create_with_seed(owner, &get_referrer_data_seed(config_key), program_id)
 }
 
-fn validate_reward_token_accounts(
-    config: &ConfigInfo,
-    market_authority: &Pubkey,
-    source_token: &Account,
-    destination_token: &Account,
-) -> ProgramResult {
+/// Generate market maker quote address from market maker, token swap and program keys.
+pub fn get_quote_pubkey(
+    market_maker: &Pubkey,
+    swap_key: &Pubkey,
+    program_id: &Pubkey,
+) -> Result<Pubkey, PubkeyError> {
+    let joint_key = format!("{}{}", SEED_QUOTE, swap_key);
+    Pubkey::create_with_seed(
+        market_maker,
+        &joint_key.as_str()[0..MAX_SEED_LEN],
+        program_id,
+    )
+}
+
+/// Generate initial price commitment address from admin, token swap and program keys.
+pub fn get_price_commitment_pubkey(
+    admin: &Pubkey,
+    swap_key: &Pubkey,
+    program_id: &Pubkey,
+) -> Result<Pubkey, PubkeyError> {
+    let joint_key = format!("{}{}", SEED_PRICE_COMMITMENT, swap_key);
+    Pubkey::create_with_seed(admin, &joint_key.as_str()[0..MAX_SEED_LEN], program_id)
+}
+
+/// Find the program address and bump seed of a farm user registry page
+pub fn find_farm_user_registry_address(
+    farm_pool_key: &Pubkey,
+    page: u32,
+    program_id: &Pubkey,
+) -> (Pubkey, u8) {
+    Pubkey::find_program_address(
+        &[
+            SEED_FARM_USER_REGISTRY.as_bytes(),
+            farm_pool_key.as_ref(),
+            &page.to_le_bytes(),
+        ],
+        program_id,
+    )
+}
+
+/// Find the program address and bump seed of the oracle feed registry entry of a mint
+pub fn find_oracle_feed_address(
+    config_key: &Pubkey,
+    mint: &Pubkey,
+    program_id: &Pubkey,
+) -> (Pubkey, u8) {
+    Pubkey::find_program_address(
+        &[
+            SEED_ORACLE_FEED.as_bytes(),
+            config_key.as_ref(),
+            mint.as_ref(),
+        ],
+        program_id,
+    )
+}
+
+/// Find the program address and bump seed of the price cache of a pool
+pub fn find_price_cache_address(swap_key: &Pubkey, program_id: &Pubkey) -> (Pubkey, u8) {
+    Pubkey::find_program_address(
+        &[SEED_PRICE_CACHE.as_bytes(), swap_key.as_ref()],
+        program_id,
+    )
+}
+
+/// Find the program address and bump seed of the whitelist entry of a user on a pool
+pub fn find_whitelist_entry_address(
+    swap_key: &Pubkey,
+    user_key: &Pubkey,
+    program_id: &Pubkey,
+) -> (Pubkey, u8) {
+    Pubkey::find_program_address(
+        &[
+            SEED_WHITELIST_ENTRY.as_bytes(),
+            swap_key.as_ref(),
+            user_key.as_ref(),
+        ],
+        program_id,
+    )
+}
+
+/// Create the whitelist entry of a user on a pool at its program address
+pub fn create_whitelist_entry_account<'a>(
+    program_id: &Pubkey,
+    swap_key: &Pubkey,
+    user_key: &Pubkey,
+    whitelist_entry_info: &AccountInfo<'a>,
+    payer_info: &AccountInfo<'a>,
+    system_program_info: &AccountInfo<'a>,
+    rent: &Rent,
+) -> Result<u8, ProgramError> {
+    let (whitelist_entry_key, bump_seed) =
+        find_whitelist_entry_address(swap_key, user_key, program_id);
     utils::validate(
-        config.deltafi_mint == source_token.mint,
-        SwapError::IncorrectMint,
+        *whitelist_entry_info.key == whitelist_entry_key,
+        SwapError::InvalidWhitelistEntry,
     )?;
     utils::validate(
-        source_token.owner == *market_authority,
-        SwapError::InvalidOwner,
+        system_program::check_id(system_program_info.key),
+        SwapError::InvalidInput,
+    )?;
+    invoke_signed(
+        &system_instruction::create_account(
+            payer_info.key,
+            whitelist_entry_info.key,
+            rent.minimum_balance(WhitelistEntry::LEN),
+            WhitelistEntry::LEN as u64,
+            program_id,
+        ),
+        &[
+            payer_info.clone(),
+            whitelist_entry_info.clone(),
+            system_program_info.clone(),
+        ],
+        &[&[
+            SEED_WHITELIST_ENTRY.as_bytes(),
+            swap_key.as_ref(),
+            user_key.as_ref(),
+            &[bump_seed],
+        ]],
     )?;
+    Ok(bump_seed)
+}
+
+/// Find the program address and bump seed of the liquidity lock of an owner on a pool
+pub fn find_liquidity_lock_address(
+    swap_key: &Pubkey,
+    owner: &Pubkey,
+    program_id: &Pubkey,
+) -> (Pubkey, u8) {
+    Pubkey::find_program_address(
+        &[
+            SEED_LIQUIDITY_LOCK.as_bytes(),
+            swap_key.as_ref(),
+            owner.as_ref(),
+        ],
+        program_id,
+    )
+}
+
+/// Find the program address and bump seed of the token account of a liquidity lock
+pub fn find_liquidity_lock_token_address(
+    liquidity_lock_key: &Pubkey,
+    program_id: &Pubkey,
+) -> (Pubkey, u8) {
+    Pubkey::find_program_address(
+        &[
+            SEED_LIQUIDITY_LOCK_TOKEN.as_bytes(),
+            liquidity_lock_key.as_ref(),
+        ],
+        program_id,
+    )
+}
 
+/// Create the liquidity lock of an owner on a pool and its pool token account owned by the
+/// liquidity lock address
+fn create_liquidity_lock_accounts<'a>(
+    program_id: &Pubkey,
+    swap_key: &Pubkey,
+    liquidity_lock_info: &AccountInfo<'a>,
+    liquidity_lock_token_info: &AccountInfo<'a>,
+    pool_mint_info: &AccountInfo<'a>,
+    owner_info: &AccountInfo<'a>,
+    rent_info: &AccountInfo<'a>,
+    system_program_info: &AccountInfo<'a>,
+    token_program_info: &AccountInfo<'a>,
+) -> Result<u8, ProgramError> {
+    let (liquidity_lock_key, bump_seed) =
+        find_liquidity_lock_address(swap_key, owner_info.key, program_id);
+    let (liquidity_lock_token_key, token_bump_seed) =
+        find_liquidity_lock_token_address(&liquidity_lock_key, program_id);
     utils::validate(
-        config.deltafi_mint == destination_token.mint,
-        SwapError::IncorrectMint,
+        *liquidity_lock_info.key == liquidity_lock_key
+            && *liquidity_lock_token_info.key == liquidity_lock_token_key,
+        SwapError::InvalidLiquidityLock,
     )?;
     utils::validate(
-        destination_token.owner != *market_authority,
-        SwapError::InvalidOwner,
+        system_program::check_id(system_program_info.key),
+        SwapError::InvalidInput,
     )?;
-
-    Ok(())
+    let rent = &Rent::from_account_info(rent_info)?;
+
+    create_program_address_account(
+        owner_info,
+        liquidity_lock_info,
+        LiquidityLock::LEN,
+        program_id,
+        rent,
+        system_program_info,
+        &[
+            SEED_LIQUIDITY_LOCK.as_bytes(),
+            swap_key.as_ref(),
+            owner_info.key.as_ref(),
+            &[bump_seed],
+        ],
+    )?;
+    create_program_address_account(
+        owner_info,
+        liquidity_lock_token_info,
+        Account::LEN,
+        token_program_info.key,
+        rent,
+        system_program_info,
+        &[
+            SEED_LIQUIDITY_LOCK_TOKEN.as_bytes(),
+            liquidity_lock_key.as_ref(),
+            &[token_bump_seed],
+        ],
+    )?;
+    invoke(
+        &spl_token::instruction::initialize_account2(
+            token_program_info.key,
+            liquidity_lock_token_info.key,
+            pool_mint_info.key,
+            &liquidity_lock_key,
+        )?,
+        &[
+            liquidity_lock_token_info.clone(),
+            pool_mint_info.clone(),
+            rent_info.clone(),
+            token_program_info.clone(),
+        ],
+    )?;
+    Ok(bump_seed)
 }
 
-/// Processes an [Instruction](enum.Instruction.html).
-pub fn process(program_id: &Pubkey, accounts: &[AccountInfo], input: &[u8]) -> ProgramResult {
-    match InstructionType::check(input) {
-        Some(InstructionType::Admin) => process_admin_instruction(program_id, accounts, input),
-        Some(InstructionType::Swap) => process_swap_instruction(program_id, accounts, input),
-        Some(InstructionType::StableSwap) => {
-            process_stable_swap_instruction(program_id, accounts, input)
-        }
-        Some(InstructionType::Farm) => process_farm_instruction(program_id, accounts, input),
-        _ => Err(ProgramError::InvalidInstructionData),
+/// Create an account of `space` bytes owned by `owner` at a program address. Anyone can send
+/// lamports to the address ahead of the payer, which makes create_account fail, so a funded
+/// address is topped up to rent exemption then allocated and assigned. Fails if the account
+/// already holds data or is owned by a program.
+fn create_program_address_account<'a>(
+    payer_info: &AccountInfo<'a>,
+    new_account_info: &AccountInfo<'a>,
+    space: usize,
+    owner: &Pubkey,
+    rent: &Rent,
+    system_program_info: &AccountInfo<'a>,
+    signer_seeds: &[&[u8]],
+) -> ProgramResult {
+    let funded_lamports = new_account_info.lamports();
+    if funded_lamports == 0 {
+        return invoke_signed(
+            &system_instruction::create_account(
+                payer_info.key,
+                new_account_info.key,
+                rent.minimum_balance(space),
+                space as u64,
+                owner,
+            ),
+            &[
+                payer_info.clone(),
+                new_account_info.clone(),
+                system_program_info.clone(),
+            ],
+            &[signer_seeds],
+        );
+    }
+
+    let required_lamports = rent.minimum_balance(space).saturating_sub(funded_lamports);
+    if required_lamports > 0 {
+        invoke(
+            &system_instruction::transfer(payer_info.key, new_account_info.key, required_lamports),
+            &[
+                payer_info.clone(),
+                new_account_info.clone(),
+                system_program_info.clone(),
+            ],
+        )?;
     }
+    invoke_signed(
+        &system_instruction::allocate(new_account_info.key, space as u64),
+        &[new_account_info.clone(), system_program_info.clone()],
+        &[signer_seeds],
+    )?;
+    invoke_signed(
+        &system_instruction::assign(new_account_info.key, owner),
+        &[new_account_info.clone(), system_program_info.clone()],
+        &[signer_seeds],
+    )
 }
 
-fn process_swap_instruction(
+/// Find the program address and bump seed of the temporary wSOL account of an owner, created
+/// and closed within the native SOL instructions
+pub fn find_native_sol_address(owner: &Pubkey, program_id: &Pubkey) -> (Pubkey, u8) {
+    Pubkey::find_program_address(&[SEED_NATIVE_SOL.as_bytes(), owner.as_ref()], program_id)
+}
+
+/// Create the temporary wSOL account of an owner and wrap `lamports` of the owner into it
+fn create_native_sol_account<'a>(
     program_id: &Pubkey,
-    accounts: &[AccountInfo],
-    input: &[u8],
+    native_sol_info: &AccountInfo<'a>,
+    native_mint_info: &AccountInfo<'a>,
+    owner_info: &AccountInfo<'a>,
+    rent_info: &AccountInfo<'a>,
+    system_program_info: &AccountInfo<'a>,
+    token_program_info: &AccountInfo<'a>,
+    lamports: u64,
 ) -> ProgramResult {
-    let instruction = SwapInstruction::unpack(input)?;
-    match instruction {
-        SwapInstruction::Initialize(InitializeData {
-            nonce,
-            slope,
-            mid_price,
-            token_a_decimals,
-            token_b_decimals,
-            token_a_amount,
-            token_b_amount,
-            oracle_priority_flags,
-        }) => {
-            msg!("Instruction: Initialize");
-            process_initialize(
-                program_id,
-                nonce,
-                slope,
-                mid_price,
-                token_a_decimals,
-                token_b_decimals,
-                token_a_amount,
-                token_b_amount,
-                oracle_priority_flags,
-                accounts,
-            )
-        }
-        SwapInstruction::Swap(SwapData {
-            amount_in,
-            minimum_amount_out,
-        }) => {
-            msg!("Instruction: Swap");
-            process_swap(program_id, amount_in, minimum_amount_out, accounts)
-        }
-        SwapInstruction::SwapV2(SwapData {
-            amount_in,
-            minimum_amount_out,
-        }) => {
-            msg!("Instruction: SwapV2");
-            process_swap_v2(program_id, amount_in, minimum_amount_out, accounts)
-        }
-        SwapInstruction::Deposit(DepositData {
-            token_a_amount,
-            token_b_amount,
-            min_mint_amount,
-        }) => {
-            msg!("Instruction: Deposit");
-            process_deposit(
-                program_id,
-                SwapType::Normal,
-                token_a_amount,
-                token_b_amount,
-                min_mint_amount,
-                accounts,
-            )
-        }
-        SwapInstruction::Withdraw(WithdrawData {
-            pool_token_amount,
-            minimum_token_a_amount,
-            minimum_token_b_amount,
-        }) => {
-            msg!("Instruction: Withdraw");
-            process_withdraw(
-                program_id,
-                SwapType::Normal,
-                pool_token_amount,
-                minimum_token_a_amount,
-                minimum_token_b_amount,
-                accounts,
-            )
-        }
-        SwapInstruction::SetReferrer => {
-            msg!("Instruction: set referrer");
-            process_set_referrer(program_id, accounts)
-        }
+    let (native_sol_key, bump_seed) = find_native_sol_address(owner_info.key, program_id);
+    utils::validate(
+        *native_sol_info.key == native_sol_key
+            && spl_token::native_mint::check_id(native_mint_info.key),
+        SwapError::InvalidNativeSolAccount,
+    )?;
+    utils::validate(
+        system_program::check_id(system_program_info.key),
+        SwapError::InvalidInput,
+    )?;
+    if &spl_token::ID != token_program_info.key {
+        return Err(ProgramError::IncorrectProgramId);
+    }
+    let rent = &Rent::from_account_info(rent_info)?;
+    let native_sol_signer_seeds: &[&[u8]] = &[
+        SEED_NATIVE_SOL.as_bytes(),
+        owner_info.key.as_ref(),
+        &[bump_seed],
+    ];
+
+    create_program_address_account(
+        owner_info,
+        native_sol_info,
+        Account::LEN,
+        token_program_info.key,
+        rent,
+        system_program_info,
+        native_sol_signer_seeds,
+    )?;
+    invoke(
+        &spl_token::instruction::initialize_account2(
+            token_program_info.key,
+            native_sol_info.key,
+            native_mint_info.key,
+            owner_info.key,
+        )?,
+        &[
+            native_sol_info.clone(),
+            native_mint_info.clone(),
+            rent_info.clone(),
+            token_program_info.clone(),
+        ],
+    )?;
+    if lamports > 0 {
+        invoke(
+            &system_instruction::transfer(owner_info.key, native_sol_info.key, lamports),
+            &[
+                owner_info.clone(),
+                native_sol_info.clone(),
+                system_program_info.clone(),
+            ],
+        )?;
+        token_sync_native(native_sol_info.clone(), token_program_info.clone())?;
     }
+    Ok(())
 }
 
-fn process_initialize(
+/// Run a swap or a deposit with its native SOL side in the temporary wSOL account of the user.
+/// The accounts of the wrapped instruction follow the native SOL accounts, the wSOL account
+/// standing for one of its `token_accounts` given by index with the lamports wrapped into it,
+/// zero for a DESTINATION. The wSOL account is closed to the user once the wrapped
+/// instruction succeeded, unwrapping the output and the lamports left.
+fn process_with_native_sol<'a>(
     program_id: &Pubkey,
-    nonce: u8,
-    slope: u64,
-    mid_price: u128,
-    token_a_decimals: u8,
-    token_b_decimals: u8,
-    token_a_amount: u64,
-    token_b_amount: u64,
-    oracle_priority_flags: u8,
-    accounts: &[AccountInfo],
+    accounts: &[AccountInfo<'a>],
+    user_transfer_authority_index: usize,
+    token_accounts: &[(usize, u64)],
+    process: impl FnOnce(&[AccountInfo<'a>]) -> ProgramResult,
 ) -> ProgramResult {
     let account_info_iter = &mut accounts.iter();
-    let config_info = next_account_info(account_info_iter)?;
-    let swap_info = next_account_info(account_info_iter)?;
-    let authority_info = next_account_info(account_info_iter)?;
-    let admin_fee_a_info = next_account_info(account_info_iter)?;
-    let admin_fee_b_info = next_account_info(account_info_iter)?;
-    let token_a_info = next_account_info(account_info_iter)?;
-    let token_b_info = next_account_info(account_info_iter)?;
-    let pool_mint_info = next_account_info(account_info_iter)?;
-    let destination_info = next_account_info(account_info_iter)?;
-    let pyth_a_product_info = next_account_info(account_info_iter)?;
-    let pyth_a_price_info = next_account_info(account_info_iter)?;
-    let pyth_b_product_info = next_account_info(account_info_iter)?;
-    let pyth_b_price_info = next_account_info(account_info_iter)?;
-    let admin_info = next_account_info(account_info_iter)?;
-    let serum_market_info = next_account_info(account_info_iter)?;
-    let serum_bids_info = next_account_info(account_info_iter)?;
-    let serum_asks_info = next_account_info(account_info_iter)?;
-    let clock = &Clock::from_account_info(next_account_info(account_info_iter)?)?;
-    let rent = &Rent::from_account_info(next_account_info(account_info_iter)?)?;
+    let native_sol_info = next_account_info(account_info_iter)?;
+    let native_mint_info = next_account_info(account_info_iter)?;
+    let rent_info = next_account_info(account_info_iter)?;
+    let system_program_info = next_account_info(account_info_iter)?;
     let token_program_info = next_account_info(account_info_iter)?;
+    let wrapped_accounts = account_info_iter.as_slice();
+    let user_transfer_authority_info = wrapped_accounts
+        .get(user_transfer_authority_index)
+        .ok_or(ProgramError::NotEnoughAccountKeys)?;
+    let (_, lamports) = token_accounts
+        .iter()
+        .find(|(index, _)| {
+            wrapped_accounts.get(*index).map_or(false, |account_info| {
+                account_info.key == native_sol_info.key
+            })
+        })
+        .ok_or(SwapError::InvalidNativeSolAccount)?;
+
+    create_native_sol_account(
+        program_id,
+        native_sol_info,
+        native_mint_info,
+        user_transfer_authority_info,
+        rent_info,
+        system_program_info,
+        token_program_info,
+        *lamports,
+    )?;
+    process(wrapped_accounts)?;
+    token_close_account(
+        native_sol_info.clone(),
+        user_transfer_authority_info.clone(),
+        user_transfer_authority_info.clone(),
+        token_program_info.clone(),
+        &[],
+    )
+}
 
-    spl_token::check_program_account(token_program_info.key)?;
-
-    if swap_info.owner != program_id || config_info.owner != program_id {
-        return Err(ProgramError::IncorrectProgramId);
+/// Check the whitelist entry of the user following the token program account of a swap or a
+/// deposit on a whitelist-gated pool, nothing is read from the accounts on the other pools
+fn next_whitelist_entry(
+    program_id: &Pubkey,
+    swap_key: &Pubkey,
+    token_swap: &SwapInfo,
+    user_key: &Pubkey,
+    account_info_iter: &mut std::slice::Iter<AccountInfo>,
+) -> ProgramResult {
+    if !token_swap.whitelist_enabled {
+        return Ok(());
     }
+    let whitelist_entry_info =
+        next_account_info(account_info_iter).map_err(|_| SwapError::NotWhitelisted)?;
+    if whitelist_entry_info.owner != program_id
+        || whitelist_entry_info.data_len() != WhitelistEntry::LEN
+    {
+        return Err(SwapError::NotWhitelisted.into());
+    }
+    let whitelist_entry = WhitelistEntry::unpack(&whitelist_entry_info.data.borrow())
+        .map_err(|_| SwapError::NotWhitelisted)?;
+    utils::validate(
+        whitelist_entry.swap_key == *swap_key && whitelist_entry.user == *user_key,
+        SwapError::NotWhitelisted,
+    )?;
+    let whitelist_entry_key = Pubkey::create_program_address(
+        &[
+            SEED_WHITELIST_ENTRY.as_bytes(),
+            swap_key.as_ref(),
+            user_key.as_ref(),
+            &[whitelist_entry.bump_seed],
+        ],
+        program_id,
+    )?;
+    utils::validate(
+        *whitelist_entry_info.key == whitelist_entry_key,
+        SwapError::InvalidWhitelistEntry,
+    )
+}
 
-    // Extract only fees and rewards to reduce stack usage
-    let config = ConfigInfo::unpack(&config_info.data.borrow())?;
-    is_admin(&config.admin_key, admin_info)?;
-    let (fees, rewards) = (config.fees, config.rewards);
-
-    let token_program_id = *token_program_info.key;
-    let token_a = unpack_token_account(token_a_info, &token_program_id)?;
-    let token_b = unpack_token_account(token_b_info, &token_program_id)?;
-
-    // Pyth or Serum accounts verification
-    let (pyth_a, pyth_b, serum_combined_address) =
-        match OraclePriorityFlag::from_bits_truncate(oracle_priority_flags) {
-            OraclePriorityFlag::PYTH_ONLY => {
-                check_pyth_accounts(
-                    pyth_a_product_info,
-                    pyth_a_price_info,
-                    &config.pyth_program_id,
-                )?;
-                check_pyth_accounts(
-                    pyth_b_product_info,
-                    pyth_b_price_info,
-                    &config.pyth_program_id,
-                )?;
-                (
-                    *pyth_a_price_info.key,
-                    *pyth_b_price_info.key,
-                    Pubkey::new(&[0u8; 32]),
-                )
-            }
-            OraclePriorityFlag::SERUM_ONLY => {
-                utils::check_serum_program_id(serum_market_info.owner)?;
-                utils::check_serum_program_id(serum_bids_info.owner)?;
-                utils::check_serum_program_id(serum_asks_info.owner)?;
-                // validate serum market base/quote mint with token_a/token_b mint
-                utils::validate_serum_market_mint_address(
-                    serum_market_info,
-                    &token_a.mint,
-                    &token_b.mint,
+/// Create the price cache of a pool at its program address
+pub fn create_price_cache_account<'a>(
+    program_id: &Pubkey,
+    swap_key: &Pubkey,
+    price_cache_info: &AccountInfo<'a>,
+    payer_info: &AccountInfo<'a>,
+    system_program_info: &AccountInfo<'a>,
+    rent: &Rent,
+) -> Result<u8, ProgramError> {
+    let (price_cache_key, bump_seed) = find_price_cache_address(swap_key, program_id);
+    utils::validate(
+        *price_cache_info.key == price_cache_key,
+        SwapError::InvalidPriceCache,
+    )?;
+    utils::validate(
+        system_program::check_id(system_program_info.key),
+        SwapError::InvalidInput,
+    )?;
+    invoke_signed(
+        &system_instruction::create_account(
+            payer_info.key,
+            price_cache_info.key,
+            rent.minimum_balance(PriceCache::LEN),
+            PriceCache::LEN as u64,
+            program_id,
+        ),
+        &[
+            payer_info.clone(),
+            price_cache_info.clone(),
+            system_program_info.clone(),
+        ],
+        &[&[SEED_PRICE_CACHE.as_bytes(), swap_key.as_ref(), &[bump_seed]]],
+    )?;
+    Ok(bump_seed)
+}
+
+/// Mints of a pair in byte order, so the pool address of the pair does not depend on the
+/// token order of the pool
+fn sorted_mints<'a>(token_a_mint: &'a Pubkey, token_b_mint: &'a Pubkey) -> [&'a Pubkey; 2] {
+    if token_a_mint <= token_b_mint {
+        [token_a_mint, token_b_mint]
+    } else {
+        [token_b_mint, token_a_mint]
+    }
+}
+
+/// Find the program address and bump seed of the pool of a pair created with `InitializeV2`
+pub fn find_swap_address(
+    config_key: &Pubkey,
+    token_a_mint: &Pubkey,
+    token_b_mint: &Pubkey,
+    swap_type: SwapType,
+    program_id: &Pubkey,
+) -> (Pubkey, u8) {
+    let [first_mint, second_mint] = sorted_mints(token_a_mint, token_b_mint);
+    Pubkey::find_program_address(
+        &[
+            SEED_SWAP.as_bytes(),
+            config_key.as_ref(),
+            first_mint.as_ref(),
+            second_mint.as_ref(),
+            &[swap_type as u8],
+        ],
+        program_id,
+    )
+}
+
+/// Create the swap account of an `InitializeV2` instruction at the program address of the
+/// mints of its token accounts. The system program follows the token program, the accounts
+/// left are the ones of the initialization.
+fn create_swap_account_v2<'a>(
+    program_id: &Pubkey,
+    swap_type: SwapType,
+    accounts: &[AccountInfo<'a>],
+    admin_index: usize,
+    rent_index: usize,
+    token_program_index: usize,
+) -> Result<Vec<AccountInfo<'a>>, ProgramError> {
+    if accounts.len() <= token_program_index + 1 {
+        return Err(ProgramError::NotEnoughAccountKeys);
+    }
+    let mut accounts = accounts.to_vec();
+    let system_program_info = accounts.remove(token_program_index + 1);
+    let config_info = &accounts[0];
+    let swap_info = &accounts[1];
+    let token_a_info = &accounts[5];
+    let token_b_info = &accounts[6];
+    let admin_info = &accounts[admin_index];
+    let rent = &Rent::from_account_info(&accounts[rent_index])?;
+    let token_program_info = &accounts[token_program_index];
+
+    utils::validate(
+        system_program::check_id(system_program_info.key),
+        SwapError::InvalidInput,
+    )?;
+    let token_a = unpack_token_account(token_a_info, token_program_info.key)?;
+    let token_b = unpack_token_account(token_b_info, token_program_info.key)?;
+    let (swap_key, bump_seed) = find_swap_address(
+        config_info.key,
+        &token_a.mint,
+        &token_b.mint,
+        swap_type,
+        program_id,
+    );
+    utils::validate(*swap_info.key == swap_key, SwapError::InvalidPoolAddress)?;
+    let [first_mint, second_mint] = sorted_mints(&token_a.mint, &token_b.mint);
+
+    // Creating the account fails if the pool of the pair already exists, an address only
+    // funded ahead of the admin is still created
+    create_program_address_account(
+        admin_info,
+        swap_info,
+        SwapInfo::LEN,
+        program_id,
+        rent,
+        &system_program_info,
+        &[
+            SEED_SWAP.as_bytes(),
+            config_info.key.as_ref(),
+            first_mint.as_ref(),
+            second_mint.as_ref(),
+            &[swap_type as u8],
+            &[bump_seed],
+        ],
+    )?;
+    Ok(accounts)
+}
+
+/// Find the program address and bump seed of the scratch account of a mint at the index
+pub fn find_scratch_account_address(
+    config_key: &Pubkey,
+    mint: &Pubkey,
+    index: u8,
+    program_id: &Pubkey,
+) -> (Pubkey, u8) {
+    Pubkey::find_program_address(
+        &[
+            SEED_SCRATCH.as_bytes(),
+            config_key.as_ref(),
+            mint.as_ref(),
+            &[index],
+        ],
+        program_id,
+    )
+}
+
+/// Find the program address and bump seed of the token account of a scratch account
+pub fn find_scratch_token_address(scratch_key: &Pubkey, program_id: &Pubkey) -> (Pubkey, u8) {
+    Pubkey::find_program_address(
+        &[SEED_SCRATCH_TOKEN.as_bytes(), scratch_key.as_ref()],
+        program_id,
+    )
+}
+
+/// Create the scratch account of a mint at the index and its token account owned by the
+/// scratch account address
+pub fn create_scratch_accounts<'a>(
+    program_id: &Pubkey,
+    config_key: &Pubkey,
+    index: u8,
+    scratch_account_info: &AccountInfo<'a>,
+    scratch_token_info: &AccountInfo<'a>,
+    mint_info: &AccountInfo<'a>,
+    payer_info: &AccountInfo<'a>,
+    rent_info: &AccountInfo<'a>,
+    system_program_info: &AccountInfo<'a>,
+    token_program_info: &AccountInfo<'a>,
+) -> Result<u8, ProgramError> {
+    let (scratch_key, bump_seed) =
+        find_scratch_account_address(config_key, mint_info.key, index, program_id);
+    let (scratch_token_key, token_bump_seed) = find_scratch_token_address(&scratch_key, program_id);
+    utils::validate(
+        *scratch_account_info.key == scratch_key && *scratch_token_info.key == scratch_token_key,
+        SwapError::InvalidScratchAccount,
+    )?;
+    utils::validate(
+        system_program::check_id(system_program_info.key),
+        SwapError::InvalidInput,
+    )?;
+    if &spl_token::ID != token_program_info.key {
+        return Err(ProgramError::IncorrectProgramId);
+    }
+    let rent = &Rent::from_account_info(rent_info)?;
+
+    invoke_signed(
+        &system_instruction::create_account(
+            payer_info.key,
+            scratch_account_info.key,
+            rent.minimum_balance(ScratchAccount::LEN),
+            ScratchAccount::LEN as u64,
+            program_id,
+        ),
+        &[
+            payer_info.clone(),
+            scratch_account_info.clone(),
+            system_program_info.clone(),
+        ],
+        &[&[
+            SEED_SCRATCH.as_bytes(),
+            config_key.as_ref(),
+            mint_info.key.as_ref(),
+            &[index],
+            &[bump_seed],
+        ]],
+    )?;
+    invoke_signed(
+        &system_instruction::create_account(
+            payer_info.key,
+            scratch_token_info.key,
+            rent.minimum_balance(Account::LEN),
+            Account::LEN as u64,
+            token_program_info.key,
+        ),
+        &[
+            payer_info.clone(),
+            scratch_token_info.clone(),
+            system_program_info.clone(),
+        ],
+        &[&[
+            SEED_SCRATCH_TOKEN.as_bytes(),
+            scratch_key.as_ref(),
+            &[token_bump_seed],
+        ]],
+    )?;
+    invoke(
+        &spl_token::instruction::initialize_account2(
+            token_program_info.key,
+            scratch_token_info.key,
+            mint_info.key,
+            &scratch_key,
+        )?,
+        &[
+            scratch_token_info.clone(),
+            mint_info.clone(),
+            rent_info.clone(),
+            token_program_info.clone(),
+        ],
+    )?;
+    Ok(bump_seed)
+}
+
+/// Unpack the scratch account and its token account, checking they belong together
+fn unpack_scratch_accounts(
+    program_id: &Pubkey,
+    scratch_account_info: &AccountInfo,
+    scratch_token_info: &AccountInfo,
+    token_program_info: &AccountInfo,
+) -> Result<(ScratchAccount, Account), ProgramError> {
+    if scratch_account_info.owner != program_id {
+        return Err(ProgramError::IncorrectProgramId);
+    }
+    let scratch_account = ScratchAccount::unpack(&scratch_account_info.data.borrow())?;
+    utils::validate(
+        scratch_account.token_account == *scratch_token_info.key,
+        SwapError::InvalidScratchAccount,
+    )?;
+    let scratch_token = unpack_token_account(scratch_token_info, token_program_info.key)?;
+    Ok((scratch_account, scratch_token))
+}
+
+/// Find the program address and bump seed of the fee discount account of an owner
+pub fn find_fee_discount_address(
+    config_key: &Pubkey,
+    owner: &Pubkey,
+    program_id: &Pubkey,
+) -> (Pubkey, u8) {
+    Pubkey::find_program_address(
+        &[
+            SEED_FEE_DISCOUNT.as_bytes(),
+            config_key.as_ref(),
+            owner.as_ref(),
+        ],
+        program_id,
+    )
+}
+
+/// Find the program address and bump seed of the token account of a fee discount account
+pub fn find_fee_discount_token_address(
+    fee_discount_key: &Pubkey,
+    program_id: &Pubkey,
+) -> (Pubkey, u8) {
+    Pubkey::find_program_address(
+        &[
+            SEED_FEE_DISCOUNT_TOKEN.as_bytes(),
+            fee_discount_key.as_ref(),
+        ],
+        program_id,
+    )
+}
+
+/// Find the program address and bump seed of the fee discount tiers of a config
+pub fn find_fee_discount_tiers_address(config_key: &Pubkey, program_id: &Pubkey) -> (Pubkey, u8) {
+    Pubkey::find_program_address(
+        &[SEED_FEE_DISCOUNT_TIERS.as_bytes(), config_key.as_ref()],
+        program_id,
+    )
+}
+
+/// Create the fee discount account of an owner and its DELTAFI token account owned by the fee
+/// discount account address
+fn create_fee_discount_accounts<'a>(
+    program_id: &Pubkey,
+    config_key: &Pubkey,
+    fee_discount_info: &AccountInfo<'a>,
+    fee_discount_token_info: &AccountInfo<'a>,
+    mint_info: &AccountInfo<'a>,
+    owner_info: &AccountInfo<'a>,
+    rent_info: &AccountInfo<'a>,
+    system_program_info: &AccountInfo<'a>,
+    token_program_info: &AccountInfo<'a>,
+) -> Result<u8, ProgramError> {
+    let (fee_discount_key, bump_seed) =
+        find_fee_discount_address(config_key, owner_info.key, program_id);
+    let (fee_discount_token_key, token_bump_seed) =
+        find_fee_discount_token_address(&fee_discount_key, program_id);
+    utils::validate(
+        *fee_discount_info.key == fee_discount_key
+            && *fee_discount_token_info.key == fee_discount_token_key,
+        SwapError::InvalidFeeDiscount,
+    )?;
+    utils::validate(
+        system_program::check_id(system_program_info.key),
+        SwapError::InvalidInput,
+    )?;
+    let rent = &Rent::from_account_info(rent_info)?;
+
+    create_program_address_account(
+        owner_info,
+        fee_discount_info,
+        FeeDiscountAccount::LEN,
+        program_id,
+        rent,
+        system_program_info,
+        &[
+            SEED_FEE_DISCOUNT.as_bytes(),
+            config_key.as_ref(),
+            owner_info.key.as_ref(),
+            &[bump_seed],
+        ],
+    )?;
+    create_program_address_account(
+        owner_info,
+        fee_discount_token_info,
+        Account::LEN,
+        token_program_info.key,
+        rent,
+        system_program_info,
+        &[
+            SEED_FEE_DISCOUNT_TOKEN.as_bytes(),
+            fee_discount_key.as_ref(),
+            &[token_bump_seed],
+        ],
+    )?;
+    invoke(
+        &spl_token::instruction::initialize_account2(
+            token_program_info.key,
+            fee_discount_token_info.key,
+            mint_info.key,
+            &fee_discount_key,
+        )?,
+        &[
+            fee_discount_token_info.clone(),
+            mint_info.clone(),
+            rent_info.clone(),
+            token_program_info.clone(),
+        ],
+    )?;
+    Ok(bump_seed)
+}
+
+/// Create the fee discount tiers of a config at its program address
+pub fn create_fee_discount_tiers_account<'a>(
+    program_id: &Pubkey,
+    config_key: &Pubkey,
+    fee_discount_tiers_info: &AccountInfo<'a>,
+    payer_info: &AccountInfo<'a>,
+    system_program_info: &AccountInfo<'a>,
+    rent: &Rent,
+) -> ProgramResult {
+    let (fee_discount_tiers_key, bump_seed) =
+        find_fee_discount_tiers_address(config_key, program_id);
+    utils::validate(
+        *fee_discount_tiers_info.key == fee_discount_tiers_key,
+        SwapError::InvalidFeeDiscount,
+    )?;
+    utils::validate(
+        system_program::check_id(system_program_info.key),
+        SwapError::InvalidInput,
+    )?;
+    create_program_address_account(
+        payer_info,
+        fee_discount_tiers_info,
+        FeeDiscountTiers::LEN,
+        program_id,
+        rent,
+        system_program_info,
+        &[
+            SEED_FEE_DISCOUNT_TIERS.as_bytes(),
+            config_key.as_ref(),
+            &[bump_seed],
+        ],
+    )
+}
+
+/// Find the program address and bump seed of the referral stats of a referrer token account
+pub fn find_referrer_stats_address(
+    config_key: &Pubkey,
+    referrer_token_key: &Pubkey,
+    program_id: &Pubkey,
+) -> (Pubkey, u8) {
+    Pubkey::find_program_address(
+        &[
+            SEED_REFERRER_STATS.as_bytes(),
+            config_key.as_ref(),
+            referrer_token_key.as_ref(),
+        ],
+        program_id,
+    )
+}
+
+/// Find the program address and bump seed of the partner reward tokens of a farm pool
+pub fn find_farm_reward_tokens_address(
+    farm_pool_key: &Pubkey,
+    program_id: &Pubkey,
+) -> (Pubkey, u8) {
+    Pubkey::find_program_address(
+        &[SEED_FARM_REWARD_TOKENS.as_bytes(), farm_pool_key.as_ref()],
+        program_id,
+    )
+}
+
+/// Create the partner reward tokens account of a farm pool
+pub fn create_farm_reward_tokens_account<'a>(
+    program_id: &Pubkey,
+    farm_pool_key: &Pubkey,
+    farm_reward_tokens_info: &AccountInfo<'a>,
+    payer_info: &AccountInfo<'a>,
+    system_program_info: &AccountInfo<'a>,
+    rent: &Rent,
+) -> Result<u8, ProgramError> {
+    let (farm_reward_tokens_key, bump_seed) =
+        find_farm_reward_tokens_address(farm_pool_key, program_id);
+    utils::validate(
+        *farm_reward_tokens_info.key == farm_reward_tokens_key,
+        SwapError::InvalidFarmRewardToken,
+    )?;
+    utils::validate(
+        system_program::check_id(system_program_info.key),
+        SwapError::InvalidInput,
+    )?;
+    invoke_signed(
+        &system_instruction::create_account(
+            payer_info.key,
+            farm_reward_tokens_info.key,
+            rent.minimum_balance(FarmRewardTokens::LEN),
+            FarmRewardTokens::LEN as u64,
+            program_id,
+        ),
+        &[
+            payer_info.clone(),
+            farm_reward_tokens_info.clone(),
+            system_program_info.clone(),
+        ],
+        &[&[
+            SEED_FARM_REWARD_TOKENS.as_bytes(),
+            farm_pool_key.as_ref(),
+            &[bump_seed],
+        ]],
+    )?;
+    Ok(bump_seed)
+}
+
+/// Find the program address and bump seed of the partner reward tokens owed to a farm user
+pub fn find_farm_user_reward_tokens_address(
+    farm_user_key: &Pubkey,
+    program_id: &Pubkey,
+) -> (Pubkey, u8) {
+    Pubkey::find_program_address(
+        &[
+            SEED_FARM_USER_REWARD_TOKENS.as_bytes(),
+            farm_user_key.as_ref(),
+        ],
+        program_id,
+    )
+}
+
+/// Unpack the partner reward tokens of a farm pool
+pub fn unpack_farm_reward_tokens(
+    program_id: &Pubkey,
+    farm_pool_key: &Pubkey,
+    farm_reward_tokens_info: &AccountInfo,
+) -> Result<FarmRewardTokens, ProgramError> {
+    utils::validate(
+        farm_reward_tokens_info.owner == program_id,
+        SwapError::InvalidAccountOwner,
+    )?;
+    let farm_reward_tokens = FarmRewardTokens::unpack(&farm_reward_tokens_info.data.borrow())?;
+    utils::validate(
+        farm_reward_tokens.farm_pool_key == *farm_pool_key,
+        SwapError::InvalidFarmRewardToken,
+    )?;
+    Ok(farm_reward_tokens)
+}
+
+fn unpack_farm_user_reward_tokens(
+    program_id: &Pubkey,
+    farm_user_key: &Pubkey,
+    farm_user_reward_tokens_info: &AccountInfo,
+) -> Result<FarmUserRewardTokens, ProgramError> {
+    utils::validate(
+        farm_user_reward_tokens_info.owner == program_id,
+        SwapError::InvalidAccountOwner,
+    )?;
+    let farm_user_reward_tokens =
+        FarmUserRewardTokens::unpack(&farm_user_reward_tokens_info.data.borrow())?;
+    utils::validate(
+        farm_user_reward_tokens.farm_user_key == *farm_user_key,
+        SwapError::InvalidFarmRewardToken,
+    )?;
+    Ok(farm_user_reward_tokens)
+}
+
+/// Partner reward token accounts of a farm user, settled before the staked amount changes
+struct FarmRewardTokenAccounts<'a, 'b> {
+    farm_reward_tokens_info: &'a AccountInfo<'b>,
+    farm_reward_tokens: FarmRewardTokens,
+    farm_user_reward_tokens_info: &'a AccountInfo<'b>,
+    farm_user_reward_tokens: FarmUserRewardTokens,
+}
+
+/// Settle the partner rewards of the staked amount of a farm user before it changes, the
+/// reward token accounts follow in the accounts when the farm emits partner reward tokens
+fn settle_farm_reward_tokens<'a, 'b: 'a>(
+    program_id: &Pubkey,
+    farm_pool_key: &Pubkey,
+    farm_info: &FarmInfo,
+    farm_user_key: &Pubkey,
+    staked_amount: u64,
+    slot: u64,
+    account_info_iter: &mut std::slice::Iter<'a, AccountInfo<'b>>,
+) -> Result<Option<FarmRewardTokenAccounts<'a, 'b>>, ProgramError> {
+    if farm_info.reward_token_count == 0 {
+        return Ok(None);
+    }
+    let farm_reward_tokens_info = next_account_info(account_info_iter)?;
+    let farm_user_reward_tokens_info = next_account_info(account_info_iter)?;
+    let mut farm_reward_tokens =
+        unpack_farm_reward_tokens(program_id, farm_pool_key, farm_reward_tokens_info)?;
+    let mut farm_user_reward_tokens =
+        unpack_farm_user_reward_tokens(program_id, farm_user_key, farm_user_reward_tokens_info)?;
+
+    farm_reward_tokens.accrue(farm_info.reserved_amount, slot)?;
+    farm_user_reward_tokens.settle(&farm_reward_tokens, staked_amount)?;
+    Ok(Some(FarmRewardTokenAccounts {
+        farm_reward_tokens_info,
+        farm_reward_tokens,
+        farm_user_reward_tokens_info,
+        farm_user_reward_tokens,
+    }))
+}
+
+/// Reset the partner reward debts of a farm user to the new staked amount
+fn reset_farm_reward_tokens(
+    accounts: Option<FarmRewardTokenAccounts>,
+    staked_amount: u64,
+) -> ProgramResult {
+    let FarmRewardTokenAccounts {
+        farm_reward_tokens_info,
+        farm_reward_tokens,
+        farm_user_reward_tokens_info,
+        mut farm_user_reward_tokens,
+    } = match accounts {
+        Some(accounts) => accounts,
+        None => return Ok(()),
+    };
+    farm_user_reward_tokens.reset_debts(&farm_reward_tokens, staked_amount)?;
+    FarmRewardTokens::pack(
+        farm_reward_tokens,
+        &mut farm_reward_tokens_info.data.borrow_mut(),
+    )?;
+    FarmUserRewardTokens::pack(
+        farm_user_reward_tokens,
+        &mut farm_user_reward_tokens_info.data.borrow_mut(),
+    )
+}
+
+/// Pay the referral reward of a trade reward, at the tier of the referred volume when the
+/// referrer stats follow in the accounts and at the base tier otherwise
+fn next_referral_reward(
+    program_id: &Pubkey,
+    config_key: &Pubkey,
+    referrer_token_key: &Pubkey,
+    trade_reward: u64,
+    account_info_iter: &mut std::slice::Iter<AccountInfo>,
+) -> Result<u64, ProgramError> {
+    match account_info_iter.as_slice().first() {
+        Some(info) if info.owner == program_id && info.data_len() == ReferrerStats::LEN => {}
+        _ => return tiered_referral_reward(trade_reward, 0),
+    }
+    let referrer_stats_info = next_account_info(account_info_iter)?;
+    let mut referrer_stats = ReferrerStats::unpack(&referrer_stats_info.data.borrow())?;
+    utils::validate(
+        referrer_stats.config_key == *config_key && referrer_stats.referrer == *referrer_token_key,
+        SwapError::InvalidReferrerStats,
+    )?;
+    let referral_reward = referrer_stats.record_referral(trade_reward)?;
+    ReferrerStats::pack(referrer_stats, &mut referrer_stats_info.data.borrow_mut())?;
+    Ok(referral_reward)
+}
+
+/// Apply the fee discount of the swap user to the trade fee when its fee discount account
+/// and the fee discount tiers of the config follow in the accounts
+fn next_discounted_trade_fee(
+    program_id: &Pubkey,
+    config_key: &Pubkey,
+    user_key: &Pubkey,
+    trade_fee: u64,
+    clock: &Clock,
+    account_info_iter: &mut std::slice::Iter<AccountInfo>,
+) -> Result<u64, ProgramError> {
+    let fee_discount =
+        next_fee_discount(program_id, config_key, user_key, clock, account_info_iter)?;
+    discounted_trade_fee(fee_discount.as_ref(), trade_fee)
+}
+
+/// Apply a fee discount read by `next_fee_discount` to the trade fee
+fn discounted_trade_fee(
+    fee_discount: Option<&(FeeDiscountTiers, u64)>,
+    trade_fee: u64,
+) -> Result<u64, ProgramError> {
+    match fee_discount {
+        Some((fee_discount_tiers, staked_amount)) => {
+            fee_discount_tiers.discounted_trade_fee(trade_fee, *staked_amount)
+        }
+        None => Ok(trade_fee),
+    }
+}
+
+/// Read the fee discount tiers of the config and the staked amount of the swap user counted
+/// for them when its fee discount account and the tiers follow in the accounts
+fn next_fee_discount(
+    program_id: &Pubkey,
+    config_key: &Pubkey,
+    user_key: &Pubkey,
+    clock: &Clock,
+    account_info_iter: &mut std::slice::Iter<AccountInfo>,
+) -> Result<Option<(FeeDiscountTiers, u64)>, ProgramError> {
+    match account_info_iter.as_slice().first() {
+        Some(info) if info.owner == program_id && info.data_len() == FeeDiscountAccount::LEN => {}
+        _ => return Ok(None),
+    }
+    let fee_discount_info = next_account_info(account_info_iter)?;
+    let fee_discount_tiers_info = next_account_info(account_info_iter)?;
+    let fee_discount = FeeDiscountAccount::unpack(&fee_discount_info.data.borrow())?;
+    utils::validate(
+        fee_discount.config_key == *config_key && fee_discount.owner == *user_key,
+        SwapError::InvalidFeeDiscount,
+    )?;
+    if fee_discount_tiers_info.owner != program_id {
+        return Err(SwapError::InvalidFeeDiscount.into());
+    }
+    let fee_discount_tiers = FeeDiscountTiers::unpack(&fee_discount_tiers_info.data.borrow())?;
+    utils::validate(
+        fee_discount_tiers.config_key == *config_key,
+        SwapError::InvalidFeeDiscount,
+    )?;
+    Ok(Some((
+        fee_discount_tiers,
+        fee_discount.discount_staked_amount(clock.slot),
+    )))
+}
+
+/// Take the treasury share of the trade fee while the protocol fee switch of the config is on,
+/// the treasury token account of the destination mint then follows in the accounts
+fn next_treasury_fee<'a, 'b>(
+    config: &ConfigInfo,
+    fees: &Fees,
+    trade_fee: u64,
+    destination_mint: &Pubkey,
+    token_program_id: &Pubkey,
+    account_info_iter: &mut std::slice::Iter<'a, AccountInfo<'b>>,
+) -> Result<(u64, Option<&'a AccountInfo<'b>>), ProgramError> {
+    match next_treasury_token(
+        config,
+        destination_mint,
+        token_program_id,
+        account_info_iter,
+    )? {
+        Some(treasury_token_info) => Ok((
+            fees.treasury_trade_fee(trade_fee)?,
+            Some(treasury_token_info),
+        )),
+        None => Ok((0, None)),
+    }
+}
+
+/// Read the treasury token account of the destination mint following in the accounts while
+/// the protocol fee switch of the config is on
+fn next_treasury_token<'a, 'b>(
+    config: &ConfigInfo,
+    destination_mint: &Pubkey,
+    token_program_id: &Pubkey,
+    account_info_iter: &mut std::slice::Iter<'a, AccountInfo<'b>>,
+) -> Result<Option<&'a AccountInfo<'b>>, ProgramError> {
+    if !config.is_protocol_fee_enabled {
+        return Ok(None);
+    }
+    let treasury_token_info = next_account_info(account_info_iter)?;
+    let treasury_token = unpack_token_account(treasury_token_info, token_program_id)?;
+    utils::validate(
+        treasury_token.owner == config.treasury_key && treasury_token.mint == *destination_mint,
+        SwapError::InvalidTreasury,
+    )?;
+    Ok(Some(treasury_token_info))
+}
+
+/// Read the referrer token account of the destination mint following in the accounts, taking
+/// the referrer share of the trade fee, when the swap is referred and the pool pays one
+fn next_referrer_fee_token<'a, 'b>(
+    referrer_token_info: Option<&AccountInfo>,
+    rewards: &Rewards,
+    destination_mint: &Pubkey,
+    token_program_id: &Pubkey,
+    account_info_iter: &mut std::slice::Iter<'a, AccountInfo<'b>>,
+) -> Result<Option<&'a AccountInfo<'b>>, ProgramError> {
+    let referrer_token_info = match referrer_token_info {
+        Some(referrer_token_info) if rewards.referral_fee_bps > 0 => referrer_token_info,
+        _ => return Ok(None),
+    };
+    let referrer_fee_token_info = next_account_info(account_info_iter)?;
+    let referrer_token = unpack_token_account(referrer_token_info, token_program_id)?;
+    let referrer_fee_token = unpack_token_account(referrer_fee_token_info, token_program_id)?;
+    utils::validate(
+        referrer_fee_token.owner == referrer_token.owner,
+        SwapError::InvalidOwner,
+    )?;
+    utils::validate(
+        referrer_fee_token.mint == *destination_mint,
+        SwapError::IncorrectMint,
+    )?;
+    Ok(Some(referrer_fee_token_info))
+}
+
+/// Shares of the trade fee of a swap leaving the pool, the liquidity providers keep the rest
+#[derive(Default)]
+struct SwapFees {
+    trade_fee: u64,
+    admin_fee: u64,
+    treasury_fee: u64,
+    referral_fee: u64,
+}
+
+impl SwapFees {
+    /// Split the trade fee, the treasury and referrer shares are only taken when their token
+    /// accounts were given
+    fn new(
+        fees: &Fees,
+        rewards: &Rewards,
+        trade_fee: u64,
+        treasury: bool,
+        referral: bool,
+    ) -> Result<Self, ProgramError> {
+        Ok(Self {
+            trade_fee,
+            admin_fee: fees.admin_trade_fee(trade_fee)?,
+            treasury_fee: if treasury {
+                fees.treasury_trade_fee(trade_fee)?
+            } else {
+                0
+            },
+            referral_fee: if referral {
+                rewards.referral_fee(trade_fee)?
+            } else {
+                0
+            },
+        })
+    }
+
+    /// Share of the trade fee left to the liquidity providers
+    fn lp_fee(&self) -> Result<u64, ProgramError> {
+        self.trade_fee
+            .checked_sub(self.admin_fee)
+            .and_then(|fee| fee.checked_sub(self.treasury_fee))
+            .and_then(|fee| fee.checked_sub(self.referral_fee))
+            .ok_or_else(|| SwapError::CalculationFailure.into())
+    }
+
+    /// Actual token amount moving out of the pool for the swap output,
+    /// amount_out + admin_fee + treasury_fee + referral_fee.
+    fn pool_outflow(&self, amount_out: u64) -> Result<u64, ProgramError> {
+        amount_out
+            .checked_add(self.admin_fee)
+            .and_then(|amount| amount.checked_add(self.treasury_fee))
+            .and_then(|amount| amount.checked_add(self.referral_fee))
+            .ok_or_else(|| SwapError::SwapOutflowOverflow.into())
+    }
+
+    /// Transfer the admin, treasury and referrer shares out of the DESTINATION token account
+    /// of the pool
+    fn transfer<'a>(
+        &self,
+        swap_destination_info: &AccountInfo<'a>,
+        admin_destination_info: &AccountInfo<'a>,
+        treasury_token_info: Option<&AccountInfo<'a>>,
+        referrer_fee_token_info: Option<&AccountInfo<'a>>,
+        swap_authority_info: &AccountInfo<'a>,
+        token_program_info: &AccountInfo<'a>,
+        swap_authority_signer_seeds: &[&[u8]],
+    ) -> ProgramResult {
+        token_transfer(
+            swap_destination_info.clone(),
+            admin_destination_info.clone(),
+            swap_authority_info.clone(),
+            token_program_info.clone(),
+            self.admin_fee,
+            swap_authority_signer_seeds,
+        )?;
+        for (fee_token_info, fee) in [
+            (treasury_token_info, self.treasury_fee),
+            (referrer_fee_token_info, self.referral_fee),
+        ]
+        .iter()
+        {
+            if let Some(fee_token_info) = fee_token_info {
+                token_transfer(
+                    swap_destination_info.clone(),
+                    (*fee_token_info).clone(),
+                    swap_authority_info.clone(),
+                    token_program_info.clone(),
+                    *fee,
+                    swap_authority_signer_seeds,
                 )?;
-                (
-                    Pubkey::new(&[0u8; 32]),
-                    Pubkey::new(&[0u8; 32]),
-                    Pubkey::new(
-                        hashv(&[
-                            serum_market_info.key.as_ref(),
-                            serum_bids_info.key.as_ref(),
-                            serum_asks_info.key.as_ref(),
-                        ])
-                        .as_ref(),
-                    ),
-                )
             }
-            _ => {
-                return Err(SwapError::UnsupportedOraclePriority.into());
-            }
-        };
+        }
+        Ok(())
+    }
+}
+
+/// Validate the referrer data of the source token owner and its referrer token account when
+/// they follow in the accounts, returning the referrer token account unless it is the dummy one
+fn next_referrer_token<'a, 'b>(
+    program_id: &Pubkey,
+    config_key: &Pubkey,
+    user_key: &Pubkey,
+    token_program_id: &Pubkey,
+    account_info_iter: &mut std::slice::Iter<'a, AccountInfo<'b>>,
+) -> Result<Option<&'a AccountInfo<'b>>, ProgramError> {
+    let user_referrer_data_info = match account_info_iter.next() {
+        Some(info) => info,
+        None => return Ok(None),
+    };
+    let referrer_token_info = next_account_info(account_info_iter)?;
+
+    let expected_user_referrer_data_pubkey =
+        get_referrer_data_pubkey(user_key, config_key, program_id)?;
+    utils::validate(
+        expected_user_referrer_data_pubkey == *user_referrer_data_info.key,
+        SwapError::InvalidAccountOwner,
+    )?;
+
+    let user_referrer_data = UserReferrerData::unpack_any(&user_referrer_data_info.data.borrow())?;
+    utils::validate(
+        utils::is_same_referrer(&user_referrer_data.referrer, referrer_token_info.key)
+            && user_referrer_data.config_key == *config_key,
+        SwapError::InvalidAccountOwner,
+    )?;
+
+    // Dummy referrer is a placeholder to indicate that the user is not referred by anyone.
+    if utils::is_dummy_referrer(&user_referrer_data.referrer) {
+        return Ok(None);
+    }
+    // The referrer may have closed its token account since, the user is left unreferred
+    // instead of failing on the transfer to it.
+    if referrer_token_info.owner != token_program_id || referrer_token_info.data_is_empty() {
+        return Ok(None);
+    }
+    Ok(Some(referrer_token_info))
+}
+
+/// Validate the referrer data of the source token owner and its referrer token account when
+/// they follow in the accounts of a router instruction, told apart from the pool accounts by
+/// the program address of the referrer data
+fn next_router_referrer_token<'a, 'b>(
+    program_id: &Pubkey,
+    config_key: &Pubkey,
+    user_key: &Pubkey,
+    token_program_id: &Pubkey,
+    account_info_iter: &mut std::slice::Iter<'a, AccountInfo<'b>>,
+) -> Result<Option<&'a AccountInfo<'b>>, ProgramError> {
+    let user_referrer_data_pubkey = get_referrer_data_pubkey(user_key, config_key, program_id)?;
+    match account_info_iter.as_slice().first() {
+        Some(info) if *info.key == user_referrer_data_pubkey => next_referrer_token(
+            program_id,
+            config_key,
+            user_key,
+            token_program_id,
+            account_info_iter,
+        ),
+        _ => Ok(None),
+    }
+}
+
+/// Create the oracle feed registry entry of a mint at its program address
+pub fn create_oracle_feed_account<'a>(
+    program_id: &Pubkey,
+    config_key: &Pubkey,
+    mint: &Pubkey,
+    oracle_feed_info: &AccountInfo<'a>,
+    payer_info: &AccountInfo<'a>,
+    system_program_info: &AccountInfo<'a>,
+    rent: &Rent,
+) -> ProgramResult {
+    let (oracle_feed_key, bump_seed) = find_oracle_feed_address(config_key, mint, program_id);
+    utils::validate(
+        *oracle_feed_info.key == oracle_feed_key,
+        SwapError::InvalidOracleFeed,
+    )?;
+    utils::validate(
+        system_program::check_id(system_program_info.key),
+        SwapError::InvalidInput,
+    )?;
+    invoke_signed(
+        &system_instruction::create_account(
+            payer_info.key,
+            oracle_feed_info.key,
+            rent.minimum_balance(OracleFeed::LEN),
+            OracleFeed::LEN as u64,
+            program_id,
+        ),
+        &[
+            payer_info.clone(),
+            oracle_feed_info.clone(),
+            system_program_info.clone(),
+        ],
+        &[&[
+            SEED_ORACLE_FEED.as_bytes(),
+            config_key.as_ref(),
+            mint.as_ref(),
+            &[bump_seed],
+        ]],
+    )
+}
+
+/// Unpack the oracle feed registry entry of a mint
+fn unpack_oracle_feed(
+    program_id: &Pubkey,
+    config_key: &Pubkey,
+    mint: &Pubkey,
+    oracle_feed_info: &AccountInfo,
+) -> Result<OracleFeed, ProgramError> {
+    let (oracle_feed_key, _) = find_oracle_feed_address(config_key, mint, program_id);
+    utils::validate(
+        *oracle_feed_info.key == oracle_feed_key && oracle_feed_info.owner == program_id,
+        SwapError::InvalidOracleFeed,
+    )?;
+    OracleFeed::unpack(&oracle_feed_info.data.borrow())
+}
+
+fn validate_reward_token_accounts(
+    config: &ConfigInfo,
+    market_authority: &Pubkey,
+    source_token: &Account,
+    destination_token: &Account,
+) -> ProgramResult {
+    utils::validate(
+        config.deltafi_mint == source_token.mint,
+        SwapError::IncorrectMint,
+    )?;
+    utils::validate(
+        source_token.owner == *market_authority,
+        SwapError::InvalidOwner,
+    )?;
+
+    utils::validate(
+        config.deltafi_mint == destination_token.mint,
+        SwapError::IncorrectMint,
+    )?;
+    utils::validate(
+        destination_token.owner != *market_authority,
+        SwapError::InvalidOwner,
+    )?;
+
+    Ok(())
+}
+
+/// Processes an [Instruction](enum.Instruction.html).
+pub fn process(program_id: &Pubkey, accounts: &[AccountInfo], input: &[u8]) -> ProgramResult {
+    match InstructionType::check(input) {
+        Some(InstructionType::Admin) => process_admin_instruction(program_id, accounts, input),
+        Some(InstructionType::Swap) => process_swap_instruction(program_id, accounts, input),
+        Some(InstructionType::StableSwap) => {
+            process_stable_swap_instruction(program_id, accounts, input)
+        }
+        Some(InstructionType::Farm) => process_farm_instruction(program_id, accounts, input),
+        Some(InstructionType::Router) => process_router_instruction(program_id, accounts, input),
+        _ => Err(ProgramError::InvalidInstructionData),
+    }
+}
+
+fn process_swap_instruction(
+    program_id: &Pubkey,
+    accounts: &[AccountInfo],
+    input: &[u8],
+) -> ProgramResult {
+    let instruction = SwapInstruction::unpack(input)?;
+    match instruction {
+        SwapInstruction::Initialize(InitializeData {
+            nonce,
+            slope,
+            mid_price,
+            token_a_decimals,
+            token_b_decimals,
+            token_a_amount,
+            token_b_amount,
+            oracle_priority_flags,
+            quote_is_token_a,
+        }) => {
+            msg!("Instruction: Initialize");
+            process_initialize(
+                program_id,
+                nonce,
+                slope,
+                mid_price,
+                token_a_decimals,
+                token_b_decimals,
+                token_a_amount,
+                token_b_amount,
+                oracle_priority_flags,
+                quote_is_token_a,
+                None,
+                false,
+                accounts,
+            )
+        }
+        SwapInstruction::RevealInitialize(
+            InitializeData {
+                nonce,
+                slope,
+                mid_price,
+                token_a_decimals,
+                token_b_decimals,
+                token_a_amount,
+                token_b_amount,
+                oracle_priority_flags,
+                quote_is_token_a,
+            },
+            salt,
+        ) => {
+            msg!("Instruction: RevealInitialize");
+            process_initialize(
+                program_id,
+                nonce,
+                slope,
+                mid_price,
+                token_a_decimals,
+                token_b_decimals,
+                token_a_amount,
+                token_b_amount,
+                oracle_priority_flags,
+                quote_is_token_a,
+                Some(salt),
+                false,
+                accounts,
+            )
+        }
+        SwapInstruction::Swap(SwapData {
+            amount_in,
+            minimum_amount_out,
+            deadline_slot,
+            output_owner,
+        }) => {
+            msg!("Instruction: Swap");
+            check_deadline(deadline_slot)?;
+            process_swap(
+                program_id,
+                amount_in,
+                minimum_amount_out,
+                output_owner,
+                accounts,
+            )
+        }
+        SwapInstruction::SwapV2(SwapData {
+            amount_in,
+            minimum_amount_out,
+            deadline_slot,
+            output_owner,
+        }) => {
+            msg!("Instruction: SwapV2");
+            check_deadline(deadline_slot)?;
+            process_swap_v2(
+                program_id,
+                amount_in,
+                minimum_amount_out,
+                output_owner,
+                false,
+                accounts,
+            )
+        }
+        SwapInstruction::SwapPartialFill(SwapData {
+            amount_in,
+            minimum_amount_out,
+            deadline_slot,
+            output_owner,
+        }) => {
+            msg!("Instruction: SwapPartialFill");
+            check_deadline(deadline_slot)?;
+            process_swap_v2(
+                program_id,
+                amount_in,
+                minimum_amount_out,
+                output_owner,
+                true,
+                accounts,
+            )
+        }
+        SwapInstruction::SetManualPrice(market_price) => {
+            msg!("Instruction: SetManualPrice");
+            process_set_manual_price(program_id, market_price, accounts)
+        }
+        SwapInstruction::Sync => {
+            msg!("Instruction: Sync");
+            process_sync(program_id, accounts)
+        }
+        SwapInstruction::RefreshPrice => {
+            msg!("Instruction: RefreshPrice");
+            process_refresh_price(program_id, accounts)
+        }
+        SwapInstruction::Deposit(DepositData {
+            token_a_amount,
+            token_b_amount,
+            min_mint_amount,
+            deadline_slot,
+        }) => {
+            msg!("Instruction: Deposit");
+            check_deadline(deadline_slot)?;
+            process_deposit(
+                program_id,
+                SwapType::Normal,
+                token_a_amount,
+                token_b_amount,
+                min_mint_amount,
+                accounts,
+            )
+        }
+        SwapInstruction::Withdraw(WithdrawData {
+            pool_token_amount,
+            minimum_token_a_amount,
+            minimum_token_b_amount,
+            deadline_slot,
+        }) => {
+            msg!("Instruction: Withdraw");
+            check_deadline(deadline_slot)?;
+            process_withdraw(
+                program_id,
+                SwapType::Normal,
+                pool_token_amount,
+                minimum_token_a_amount,
+                minimum_token_b_amount,
+                accounts,
+            )
+        }
+        SwapInstruction::SetReferrer => {
+            msg!("Instruction: set referrer");
+            process_set_referrer(program_id, accounts)
+        }
+        SwapInstruction::DepositOne(DepositOneData {
+            amount_in,
+            min_mint_amount,
+            deadline_slot,
+        }) => {
+            msg!("Instruction: DepositOne");
+            check_deadline(deadline_slot)?;
+            process_deposit_one(
+                program_id,
+                SwapType::Normal,
+                amount_in,
+                min_mint_amount,
+                accounts,
+            )
+        }
+        SwapInstruction::WithdrawOne(WithdrawOneData {
+            pool_token_amount,
+            minimum_amount_out,
+            token_side,
+            deadline_slot,
+        }) => {
+            msg!("Instruction: WithdrawOne");
+            check_deadline(deadline_slot)?;
+            process_withdraw_one(
+                program_id,
+                SwapType::Normal,
+                pool_token_amount,
+                minimum_amount_out,
+                token_side,
+                accounts,
+            )
+        }
+        SwapInstruction::PostQuote(QuoteData {
+            swap_direction,
+            price,
+            max_amount_in,
+            expiry_slot,
+        }) => {
+            msg!("Instruction: PostQuote");
+            process_post_quote(
+                program_id,
+                swap_direction,
+                price,
+                max_amount_in,
+                expiry_slot,
+                accounts,
+            )
+        }
+        SwapInstruction::FillQuote(SwapData {
+            amount_in,
+            minimum_amount_out,
+            deadline_slot,
+            output_owner,
+        }) => {
+            msg!("Instruction: FillQuote");
+            check_deadline(deadline_slot)?;
+            utils::validate(output_owner.is_none(), SwapError::InvalidInput)?;
+            process_fill_quote(program_id, amount_in, minimum_amount_out, accounts)
+        }
+        SwapInstruction::FlashSwap(FlashSwapData {
+            amount_a,
+            amount_b,
+            deadline_slot,
+            callback_data,
+        }) => {
+            msg!("Instruction: FlashSwap");
+            check_deadline(deadline_slot)?;
+            process_flash_swap(program_id, amount_a, amount_b, callback_data, accounts)
+        }
+        SwapInstruction::GetQuote(GetQuoteData {
+            amount_in,
+            swap_direction,
+        }) => {
+            msg!("Instruction: GetQuote");
+            process_get_quote(program_id, amount_in, swap_direction, accounts)
+        }
+        SwapInstruction::UpdatePriceCache => {
+            msg!("Instruction: UpdatePriceCache");
+            process_update_price_cache(program_id, accounts)
+        }
+        SwapInstruction::InitializeV2(InitializeData {
+            nonce,
+            slope,
+            mid_price,
+            token_a_decimals,
+            token_b_decimals,
+            token_a_amount,
+            token_b_amount,
+            oracle_priority_flags,
+            quote_is_token_a,
+        }) => {
+            msg!("Instruction: InitializeV2");
+            let accounts =
+                create_swap_account_v2(program_id, SwapType::Normal, accounts, 13, 18, 19)?;
+            process_initialize(
+                program_id,
+                nonce,
+                slope,
+                mid_price,
+                token_a_decimals,
+                token_b_decimals,
+                token_a_amount,
+                token_b_amount,
+                oracle_priority_flags,
+                quote_is_token_a,
+                None,
+                true,
+                &accounts,
+            )
+        }
+        SwapInstruction::AcquireScratch => {
+            msg!("Instruction: AcquireScratch");
+            process_acquire_scratch(program_id, accounts)
+        }
+        SwapInstruction::ReleaseScratch => {
+            msg!("Instruction: ReleaseScratch");
+            process_release_scratch(program_id, accounts)
+        }
+        SwapInstruction::StakeFeeDiscount(amount) => {
+            msg!("Instruction: StakeFeeDiscount");
+            process_stake_fee_discount(program_id, amount, accounts)
+        }
+        SwapInstruction::UnstakeFeeDiscount(amount) => {
+            msg!("Instruction: UnstakeFeeDiscount");
+            process_unstake_fee_discount(program_id, amount, accounts)
+        }
+        SwapInstruction::UpdateReferrer => {
+            msg!("Instruction: UpdateReferrer");
+            process_update_referrer(program_id, accounts)
+        }
+        SwapInstruction::InitReferrerStats => {
+            msg!("Instruction: InitReferrerStats");
+            process_init_referrer_stats(program_id, accounts)
+        }
+        SwapInstruction::Rebalance(RebalanceData {
+            max_amount_in,
+            minimum_amount_out,
+            deadline_slot,
+        }) => {
+            msg!("Instruction: Rebalance");
+            check_deadline(deadline_slot)?;
+            process_rebalance(program_id, max_amount_in, minimum_amount_out, accounts)
+        }
+        SwapInstruction::LockLiquidity(LockLiquidityData { amount, unlock_ts }) => {
+            msg!("Instruction: LockLiquidity");
+            process_lock_liquidity(program_id, amount, unlock_ts, accounts)
+        }
+        SwapInstruction::UnlockLiquidity => {
+            msg!("Instruction: UnlockLiquidity");
+            process_unlock_liquidity(program_id, accounts)
+        }
+        SwapInstruction::SwapNativeSol(SwapData {
+            amount_in,
+            minimum_amount_out,
+            deadline_slot,
+            output_owner,
+        }) => {
+            msg!("Instruction: SwapNativeSol");
+            check_deadline(deadline_slot)?;
+            utils::validate(output_owner.is_none(), SwapError::InvalidInput)?;
+            process_with_native_sol(
+                program_id,
+                accounts,
+                4,
+                &[(5, amount_in), (8, 0)],
+                |accounts| {
+                    process_swap_v2(
+                        program_id,
+                        amount_in,
+                        minimum_amount_out,
+                        None,
+                        false,
+                        accounts,
+                    )
+                },
+            )
+        }
+        SwapInstruction::DepositNativeSol(DepositData {
+            token_a_amount,
+            token_b_amount,
+            min_mint_amount,
+            deadline_slot,
+        }) => {
+            msg!("Instruction: DepositNativeSol");
+            check_deadline(deadline_slot)?;
+            process_with_native_sol(
+                program_id,
+                accounts,
+                2,
+                &[(3, token_a_amount), (4, token_b_amount)],
+                |accounts| {
+                    process_deposit(
+                        program_id,
+                        SwapType::Normal,
+                        token_a_amount,
+                        token_b_amount,
+                        min_mint_amount,
+                        accounts,
+                    )
+                },
+            )
+        }
+    }
+}
+
+/// Check the revealed mid price against the commitment and close the commitment account
+#[inline(never)]
+fn reveal_price_commitment<'a>(
+    program_id: &Pubkey,
+    config_info: &AccountInfo<'a>,
+    swap_info: &AccountInfo<'a>,
+    admin_info: &AccountInfo<'a>,
+    price_commitment_info: &AccountInfo<'a>,
+    clock: &Clock,
+    mid_price: u128,
+    salt: &[u8; 32],
+) -> ProgramResult {
+    if price_commitment_info.owner != program_id {
+        return Err(SwapError::InvalidAccountOwner.into());
+    }
+    let expected_commitment_pubkey =
+        get_price_commitment_pubkey(admin_info.key, swap_info.key, program_id)?;
+    utils::validate(
+        expected_commitment_pubkey == *price_commitment_info.key,
+        SwapError::InvalidAccountOwner,
+    )?;
+
+    let price_commitment = PriceCommitment::unpack(&price_commitment_info.data.borrow())?;
+    utils::validate(
+        price_commitment.config_key == *config_info.key
+            && price_commitment.swap_key == *swap_info.key,
+        SwapError::InvalidAccountOwner,
+    )?;
+    // The reveal must land in a later slot than the commitment it opens
+    utils::validate(
+        price_commitment.commit_slot < clock.slot
+            && price_commitment.price_hash == PriceCommitment::hash_price(mid_price, salt),
+        SwapError::InvalidPriceCommitment,
+    )?;
+
+    let admin_lamports = admin_info.lamports();
+    **admin_info.lamports.borrow_mut() = admin_lamports
+        .checked_add(price_commitment_info.lamports())
+        .ok_or(SwapError::CalculationFailure)?;
+    **price_commitment_info.lamports.borrow_mut() = 0;
+    PriceCommitment::pack(
+        PriceCommitment::default(),
+        &mut price_commitment_info.data.borrow_mut(),
+    )
+}
+
+fn process_initialize(
+    program_id: &Pubkey,
+    nonce: u8,
+    slope: u64,
+    mid_price: u128,
+    token_a_decimals: u8,
+    token_b_decimals: u8,
+    token_a_amount: u64,
+    token_b_amount: u64,
+    oracle_priority_flags: u8,
+    quote_is_token_a: bool,
+    salt: Option<[u8; 32]>,
+    swap_address_checked: bool,
+    accounts: &[AccountInfo],
+) -> ProgramResult {
+    let account_info_iter = &mut accounts.iter();
+    let config_info = next_account_info(account_info_iter)?;
+    let swap_info = next_account_info(account_info_iter)?;
+    let authority_info = next_account_info(account_info_iter)?;
+    let admin_fee_a_info = next_account_info(account_info_iter)?;
+    let admin_fee_b_info = next_account_info(account_info_iter)?;
+    let token_a_info = next_account_info(account_info_iter)?;
+    let token_b_info = next_account_info(account_info_iter)?;
+    let pool_mint_info = next_account_info(account_info_iter)?;
+    let destination_info = next_account_info(account_info_iter)?;
+    let pyth_a_product_info = next_account_info(account_info_iter)?;
+    let pyth_a_price_info = next_account_info(account_info_iter)?;
+    let pyth_b_product_info = next_account_info(account_info_iter)?;
+    let pyth_b_price_info = next_account_info(account_info_iter)?;
+    let admin_info = next_account_info(account_info_iter)?;
+    let serum_market_info = next_account_info(account_info_iter)?;
+    let serum_bids_info = next_account_info(account_info_iter)?;
+    let serum_asks_info = next_account_info(account_info_iter)?;
+    let clock = &Clock::from_account_info(next_account_info(account_info_iter)?)?;
+    let rent = &Rent::from_account_info(next_account_info(account_info_iter)?)?;
+    let token_program_info = next_account_info(account_info_iter)?;
+
+    spl_token::check_program_account(token_program_info.key)?;
+
+    if swap_info.owner != program_id || config_info.owner != program_id {
+        return Err(ProgramError::IncorrectProgramId);
+    }
+
+    // The config, the token accounts and the new pool live on the heap, the frame of the
+    // initialization keeps the account infos and the keys only
+    let config = Box::new(ConfigInfo::unpack(&config_info.data.borrow())?);
+    is_admin(&config.admin_key, admin_info)?;
+
+    if let Some(salt) = salt {
+        reveal_price_commitment(
+            program_id,
+            config_info,
+            swap_info,
+            admin_info,
+            next_account_info(account_info_iter)?,
+            clock,
+            mid_price,
+            &salt,
+        )?;
+    }
+
+    let token_program_id = *token_program_info.key;
+    let token_a = Box::new(unpack_token_account(token_a_info, &token_program_id)?);
+    let token_b = Box::new(unpack_token_account(token_b_info, &token_program_id)?);
+
+    let oracle_flags = OraclePriorityFlag::from_bits_truncate(oracle_priority_flags);
+    let orderbook_program_id = config.accepted_orderbook_program_id();
+    let (pyth_a, pyth_b, serum_combined_address) = check_initial_oracles(
+        &config,
+        oracle_flags,
+        quote_is_token_a,
+        &token_a.mint,
+        &token_b.mint,
+        pyth_a_product_info,
+        pyth_a_price_info,
+        pyth_b_product_info,
+        pyth_b_price_info,
+        serum_market_info,
+        serum_bids_info,
+        serum_asks_info,
+    )?;
+
+    if config.is_oracle_feed_registry_enabled {
+        check_initial_oracle_feeds(
+            program_id,
+            config_info.key,
+            oracle_flags,
+            quote_is_token_a,
+            &token_a.mint,
+            &token_b.mint,
+            pyth_a_price_info.key,
+            pyth_b_price_info.key,
+            serum_market_info.key,
+            account_info_iter,
+        )?;
+    }
+
+    // A pool created at its program address by `create_swap_account_v2` can't sign
+    utils::validate(
+        swap_address_checked || swap_info.is_signer,
+        SwapError::InvalidSigner,
+    )?;
+
+    assert_rent_exempt(rent, swap_info)?;
+    assert_uninitialized::<SwapInfo>(swap_info)?;
+
+    let swap_authority_signer_seeds = &[swap_info.key.as_ref(), &[nonce]];
+    utils::validate(
+        *authority_info.key
+            == Pubkey::create_program_address(swap_authority_signer_seeds, program_id)?,
+        SwapError::InvalidProgramAddress,
+    )?;
+
+    validate_initial_pool_accounts(
+        authority_info.key,
+        &token_a,
+        &token_b,
+        token_a_amount,
+        token_b_amount,
+        token_a_decimals,
+        destination_info,
+        pool_mint_info,
+        admin_fee_a_info,
+        admin_fee_b_info,
+        &token_program_id,
+    )?;
+
+    if Decimal::from_scaled_val(slope as u128) > Decimal::one() {
+        return Err(SwapError::InvalidSlope.into());
+    }
+
+    // The mid price is quoted in the conventional order of the pair
+    let pool_mid_price = if quote_is_token_a {
+        Decimal::one().try_div(Decimal::from_scaled_val(mid_price))?
+    } else {
+        Decimal::from_scaled_val(mid_price)
+    };
+    let (market_price, valid_slot) = get_market_price(
+        oracle_priority_flags,
+        0u16,
+        &OracleParams::default(),
+        pyth_a_price_info,
+        pyth_b_price_info,
+        clock,
+        serum_market_info,
+        serum_bids_info,
+        serum_asks_info,
+        &orderbook_program_id,
+        token_a_decimals,
+        token_b_decimals,
+        quote_is_token_a,
+    )
+    .unwrap_or_else(|_| (pool_mid_price, clock.slot));
+
+    let (pool_state, mint_amount) = init_pool_state(
+        market_price,
+        slope,
+        min(clock.slot, valid_slot),
+        token_a_decimals,
+        token_b_decimals,
+        token_a.amount,
+        token_b.amount,
+    )?;
+
+    pack_new_swap_info(
+        swap_info,
+        Box::new(SwapInfo {
+            is_initialized: true,
+            is_paused: false,
+            nonce,
+            swap_type: SwapType::Normal,
+            config_key: *config_info.key,
+            token_a: *token_a_info.key,
+            token_b: *token_b_info.key,
+            pool_mint: *pool_mint_info.key,
+            token_a_mint: token_a.mint,
+            token_b_mint: token_b.mint,
+            admin_fee_key_a: *admin_fee_a_info.key,
+            admin_fee_key_b: *admin_fee_b_info.key,
+            pyth_a,
+            pyth_b,
+            fees: config.fees,
+            rewards: config.rewards,
+            pool_state: *pool_state,
+            token_a_decimals,
+            token_b_decimals,
+            oracle_priority_flags,
+            serum_combined_address,
+            quote_is_token_a,
+            version: SWAP_INFO_VERSION,
+            ..SwapInfo::default()
+        }),
+    )?;
+
+    token_mint_to(
+        pool_mint_info.clone(),
+        destination_info.clone(),
+        authority_info.clone(),
+        token_program_info.clone(),
+        mint_amount,
+        swap_authority_signer_seeds,
+    )?;
+
+    let pool_mint = unpack_mint(pool_mint_info, &token_program_id)?;
+    utils::validate(
+        pool_mint.supply == mint_amount,
+        SwapError::InconsistentMintSupply,
+    )?;
+
+    Ok(())
+}
+
+/// Check the pyth and serum accounts of the oracle priority of a new pool, returns the pyth
+/// price keys and the serum combined address kept by the pool
+#[inline(never)]
+pub fn check_initial_oracles(
+    config: &ConfigInfo,
+    oracle_flags: OraclePriorityFlag,
+    quote_is_token_a: bool,
+    token_a_mint: &Pubkey,
+    token_b_mint: &Pubkey,
+    pyth_a_product_info: &AccountInfo,
+    pyth_a_price_info: &AccountInfo,
+    pyth_b_product_info: &AccountInfo,
+    pyth_b_price_info: &AccountInfo,
+    serum_market_info: &AccountInfo,
+    serum_bids_info: &AccountInfo,
+    serum_asks_info: &AccountInfo,
+) -> Result<(Pubkey, Pubkey, Pubkey), ProgramError> {
+    match oracle_flags {
+        OraclePriorityFlag::PYTH_ONLY
+        | OraclePriorityFlag::SERUM_ONLY
+        | OraclePriorityFlag::PYTH_WITH_SERUM_FALLBACK
+        | OraclePriorityFlag::MANUAL
+        | OraclePriorityFlag::CHAINLINK
+        | OraclePriorityFlag::PYTH_PULL => {}
+        _ => {
+            return Err(SwapError::UnsupportedOraclePriority.into());
+        }
+    }
+
+    let (pyth_a, pyth_b) = if oracle_flags.is_chainlink() {
+        // The chainlink feeds of the tokens take the pyth price accounts of the pool
+        chainlink::Feed::load(pyth_a_price_info)?;
+        chainlink::Feed::load(pyth_b_price_info)?;
+        (*pyth_a_price_info.key, *pyth_b_price_info.key)
+    } else if oracle_flags.is_pyth_pull() {
+        // The pool keeps the feed ids of the price updates, the update accounts are posted
+        // again with each new price
+        let feed_a = pyth_pull::PriceUpdate::load(pyth_a_price_info)?;
+        let feed_b = pyth_pull::PriceUpdate::load(pyth_b_price_info)?;
+        utils::validate(
+            feed_a.fully_verified && feed_b.fully_verified,
+            SwapError::InvalidPythPriceUpdate,
+        )?;
+        (
+            Pubkey::new_from_array(feed_a.price_message.feed_id),
+            Pubkey::new_from_array(feed_b.price_message.feed_id),
+        )
+    } else if !oracle_flags.uses_pyth() {
+        (Pubkey::new(&[0u8; 32]), Pubkey::new(&[0u8; 32]))
+    } else {
+        check_pyth_accounts(
+            pyth_a_product_info,
+            pyth_a_price_info,
+            &config.pyth_program_id,
+        )?;
+        check_pyth_accounts(
+            pyth_b_product_info,
+            pyth_b_price_info,
+            &config.pyth_program_id,
+        )?;
+        (*pyth_a_price_info.key, *pyth_b_price_info.key)
+    };
+
+    let orderbook_program_id = config.accepted_orderbook_program_id();
+    let serum_combined_address = if oracle_flags.uses_serum() {
+        utils::check_serum_program_id(serum_market_info.owner, &orderbook_program_id)?;
+        utils::check_serum_program_id(serum_bids_info.owner, &orderbook_program_id)?;
+        utils::check_serum_program_id(serum_asks_info.owner, &orderbook_program_id)?;
+        // validate serum market base/quote mint with the conventional order of the pair
+        let (base_mint, quote_mint) = if quote_is_token_a {
+            (token_b_mint, token_a_mint)
+        } else {
+            (token_a_mint, token_b_mint)
+        };
+        utils::validate_serum_market_mint_address(serum_market_info, base_mint, quote_mint)?;
+        Pubkey::new(
+            hashv(&[
+                serum_market_info.key.as_ref(),
+                serum_bids_info.key.as_ref(),
+                serum_asks_info.key.as_ref(),
+            ])
+            .as_ref(),
+        )
+    } else {
+        Pubkey::new(&[0u8; 32])
+    };
+
+    Ok((pyth_a, pyth_b, serum_combined_address))
+}
+
+/// Check the oracles of a new pool against the oracle feeds registered for its mints
+#[inline(never)]
+pub fn check_initial_oracle_feeds<'a, 'b: 'a>(
+    program_id: &Pubkey,
+    config_key: &Pubkey,
+    oracle_flags: OraclePriorityFlag,
+    quote_is_token_a: bool,
+    token_a_mint: &Pubkey,
+    token_b_mint: &Pubkey,
+    pyth_a_price_key: &Pubkey,
+    pyth_b_price_key: &Pubkey,
+    serum_market_key: &Pubkey,
+    account_info_iter: &mut std::slice::Iter<'a, AccountInfo<'b>>,
+) -> ProgramResult {
+    let oracle_feed_a = unpack_oracle_feed(
+        program_id,
+        config_key,
+        token_a_mint,
+        next_account_info(account_info_iter)?,
+    )?;
+    let oracle_feed_b = unpack_oracle_feed(
+        program_id,
+        config_key,
+        token_b_mint,
+        next_account_info(account_info_iter)?,
+    )?;
+    if oracle_flags.uses_pyth() {
+        utils::validate(
+            oracle_feed_a.pyth_price == *pyth_a_price_key
+                && oracle_feed_b.pyth_price == *pyth_b_price_key,
+            SwapError::InvalidOracleFeed,
+        )?;
+    }
+    if oracle_flags.uses_serum() {
+        let base_oracle_feed = if quote_is_token_a {
+            &oracle_feed_b
+        } else {
+            &oracle_feed_a
+        };
+        utils::validate(
+            base_oracle_feed.serum_market == *serum_market_key,
+            SwapError::InvalidOracleFeed,
+        )?;
+    }
+    Ok(())
+}
+
+/// Check the token accounts and the pool mint of a new pool, the pool token accounts are
+/// held by the swap authority with the initial amounts and the pool mint is empty
+#[inline(never)]
+fn validate_initial_pool_accounts(
+    swap_authority_key: &Pubkey,
+    token_a: &Account,
+    token_b: &Account,
+    token_a_amount: u64,
+    token_b_amount: u64,
+    token_a_decimals: u8,
+    destination_info: &AccountInfo,
+    pool_mint_info: &AccountInfo,
+    admin_fee_a_info: &AccountInfo,
+    admin_fee_b_info: &AccountInfo,
+    token_program_id: &Pubkey,
+) -> ProgramResult {
+    let destination = unpack_token_account(destination_info, token_program_id)?;
+    let pool_mint = unpack_mint(pool_mint_info, token_program_id)?;
+    let admin_fee_key_a = unpack_token_account(admin_fee_a_info, token_program_id)?;
+    let admin_fee_key_b = unpack_token_account(admin_fee_b_info, token_program_id)?;
+
+    utils::validate(
+        *swap_authority_key == token_a.owner,
+        SwapError::InvalidOwner,
+    )?;
+    utils::validate(
+        *swap_authority_key == token_b.owner,
+        SwapError::InvalidOwner,
+    )?;
+
+    utils::validate(
+        *swap_authority_key != destination.owner,
+        SwapError::InvalidOutputOwner,
+    )?;
+    utils::validate(
+        *swap_authority_key != admin_fee_key_a.owner,
+        SwapError::InvalidOutputOwner,
+    )?;
+    utils::validate(
+        *swap_authority_key != admin_fee_key_b.owner,
+        SwapError::InvalidOutputOwner,
+    )?;
+
+    utils::validate(token_a.mint != token_b.mint, SwapError::RepeatedMint)?;
+    utils::validate(
+        token_a.mint == admin_fee_key_a.mint,
+        SwapError::InvalidAdmin,
+    )?;
+    utils::validate(
+        token_b.mint == admin_fee_key_b.mint,
+        SwapError::InvalidAdmin,
+    )?;
+
+    utils::validate(
+        token_a.amount == token_a_amount,
+        SwapError::InconsistentInitialPoolTokenBalance,
+    )?;
+    utils::validate(
+        token_b.amount == token_b_amount,
+        SwapError::InconsistentInitialPoolTokenBalance,
+    )?;
+
+    utils::validate(!token_a.delegate.is_some(), SwapError::InvalidDelegate)?;
+    utils::validate(!token_b.delegate.is_some(), SwapError::InvalidDelegate)?;
+
+    utils::validate(
+        !token_a.close_authority.is_some(),
+        SwapError::InvalidCloseAuthority,
+    )?;
+    utils::validate(
+        !token_b.close_authority.is_some(),
+        SwapError::InvalidCloseAuthority,
+    )?;
+
+    if pool_mint.mint_authority.is_some()
+        && *swap_authority_key != pool_mint.mint_authority.unwrap()
+    {
+        return Err(SwapError::InvalidOwner.into());
+    }
+    if pool_mint.freeze_authority.is_some() {
+        return Err(SwapError::InvalidFreezeAuthority.into());
+    }
+    if pool_mint.supply != 0 {
+        return Err(SwapError::InvalidSupply.into());
+    }
+    // Initial shares are minted 1:1 with the base token amount.
+    utils::validate(
+        pool_mint.decimals == token_a_decimals,
+        SwapError::InvalidPoolMintDecimals,
+    )
+}
+
+/// Create the pool state of a new pool holding the initial amounts, returns the boxed pool
+/// state and the pool tokens minted for the initial amounts
+#[inline(never)]
+fn init_pool_state(
+    market_price: Decimal,
+    slope: u64,
+    last_valid_market_price_slot: u64,
+    token_a_decimals: u8,
+    token_b_decimals: u8,
+    token_a_amount: u64,
+    token_b_amount: u64,
+) -> Result<(Box<PoolState>, u64), ProgramError> {
+    let mut pool_state = Box::new(PoolState::new(InitPoolStateParams {
+        market_price,
+        slope: Decimal::from_scaled_val(slope.into()),
+        base_reserve: Decimal::zero(),
+        quote_reserve: Decimal::zero(),
+        total_supply: 0,
+        last_market_price: market_price,
+        last_valid_market_price_slot,
+    }));
+    pool_state.set_market_price(token_a_decimals, token_b_decimals, market_price)?;
+
+    let (mint_amount, token_a_output, token_b_output) =
+        pool_state.buy_shares(token_a_amount, token_b_amount)?;
+    utils::validate(
+        token_a_output == token_a_amount && token_b_output == token_b_amount,
+        SwapError::CalculationFailure,
+    )?;
+
+    pool_state.check_reserve_amount(token_a_amount, token_b_amount)?;
+    pool_state.check_share_price_floor()?;
+    Ok((pool_state, mint_amount))
+}
+
+/// Pack a new pool from the heap into the swap account
+#[inline(never)]
+fn pack_new_swap_info(swap_info: &AccountInfo, token_swap: Box<SwapInfo>) -> ProgramResult {
+    let mut data = swap_info.try_borrow_mut_data()?;
+    if data.len() != SwapInfo::LEN {
+        return Err(ProgramError::InvalidAccountData);
+    }
+    token_swap.pack_into_slice(&mut data);
+    Ok(())
+}
+
+fn process_swap(
+    program_id: &Pubkey,
+    amount_in: u64,
+    minimum_amount_out: u64,
+    output_owner: Option<Pubkey>,
+    accounts: &[AccountInfo],
+) -> ProgramResult {
+    let account_info_iter = &mut accounts.iter();
+    let config_info = next_account_info(account_info_iter)?;
+    let swap_info = next_account_info(account_info_iter)?;
+    let market_authority_info = next_account_info(account_info_iter)?;
+    let swap_authority_info = next_account_info(account_info_iter)?;
+    let user_transfer_authority_info = next_account_info(account_info_iter)?;
+    let source_info = next_account_info(account_info_iter)?;
+    let swap_source_info = next_account_info(account_info_iter)?;
+    let source_mint_info = next_account_info(account_info_iter)?;
+    let swap_destination_info = next_account_info(account_info_iter)?;
+    let destination_info = next_account_info(account_info_iter)?;
+    let destination_mint_info = next_account_info(account_info_iter)?;
+    let reward_token_info = next_account_info(account_info_iter)?;
+    let source_reward_token_info = next_account_info(account_info_iter)?;
+    let admin_destination_info = next_account_info(account_info_iter)?;
+    let pyth_a_price_info = next_account_info(account_info_iter)?;
+    let pyth_b_price_info = next_account_info(account_info_iter)?;
+    let clock = &Clock::get()?;
+    let token_program_info = next_account_info(account_info_iter)?;
+
+    spl_token::check_program_account(token_program_info.key)?;
+
+    if swap_info.owner != program_id || config_info.owner != program_id {
+        return Err(ProgramError::IncorrectProgramId);
+    }
+
+    let config = ConfigInfo::unpack(&config_info.data.borrow())?;
+    let mut token_swap = SwapInfo::unpack(&swap_info.data.borrow())?;
+
+    utils::validate_swap_config_key(&token_swap, config_info.key)?;
+    utils::validate(
+        token_swap.swap_type == SwapType::Normal,
+        SwapError::IncorrectSwapType,
+    )?;
+
+    if token_swap.is_paused || token_swap.pause_swap {
+        return Err(SwapError::IsPaused.into());
+    }
+    next_whitelist_entry(
+        program_id,
+        swap_info.key,
+        &token_swap,
+        user_transfer_authority_info.key,
+        account_info_iter,
+    )?;
+    let swap_authority_signer_seeds = &[swap_info.key.as_ref(), &[token_swap.nonce]];
+    if *swap_authority_info.key
+        != Pubkey::create_program_address(swap_authority_signer_seeds, program_id)?
+    {
+        return Err(SwapError::InvalidProgramAddress.into());
+    }
+    let market_authority_signer_seeds = &[config_info.key.as_ref(), &[config.bump_seed]];
+    if *market_authority_info.key
+        != Pubkey::create_program_address(market_authority_signer_seeds, program_id)?
+    {
+        return Err(SwapError::InvalidProgramAddress.into());
+    }
+    // Only the pyth price accounts of the pool price a v1 swap, the pools on the other oracles
+    // swap with SwapV2
+    if !OraclePriorityFlag::from_bits_truncate(token_swap.oracle_priority_flags).uses_pyth() {
+        return Err(SwapError::UnsupportedOraclePriority.into());
+    }
+    check_pool_oracle_accounts(&token_swap, pyth_a_price_info, pyth_b_price_info)?;
+    if source_info.key == destination_info.key {
+        return Err(SwapError::InvalidInput.into());
+    }
+    if token_swap.strict_destination_owner && output_owner.is_none() {
+        let destination_token = unpack_token_account(destination_info, token_program_info.key)?;
+        utils::validate_destination_owner(&destination_token.owner, accounts)?;
+    }
+    if source_info.key == swap_source_info.key || destination_info.key == swap_destination_info.key
+    {
+        return Err(SwapError::InvalidInput.into());
+    }
+    let swap_direction = if *swap_source_info.key == token_swap.token_a
+        && *swap_destination_info.key == token_swap.token_b
+    {
+        SwapDirection::SellBase
+    } else if *swap_source_info.key == token_swap.token_b
+        && *swap_destination_info.key == token_swap.token_a
+    {
+        SwapDirection::SellQuote
+    } else {
+        return Err(SwapError::IncorrectSwapAccount.into());
+    };
+
+    match swap_direction {
+        SwapDirection::SellQuote => utils::validate(
+            token_swap.admin_fee_key_a == *admin_destination_info.key,
+            SwapError::InvalidAdmin,
+        )?,
+        SwapDirection::SellBase => utils::validate(
+            token_swap.admin_fee_key_b == *admin_destination_info.key,
+            SwapError::InvalidAdmin,
+        )?,
+    }
+
+    // verify source and dest mint address
+    utils::validate_swap_token_mint(
+        swap_direction,
+        &token_swap.token_a_mint,
+        &token_swap.token_b_mint,
+        source_mint_info.key,
+        destination_mint_info.key,
+    )?;
+
+    let source_mint = Mint::unpack(&source_mint_info.data.borrow())?;
+    let destination_mint = Mint::unpack(&destination_mint_info.data.borrow())?;
+
+    let source_decimals = source_mint.decimals;
+    let destination_decimals = destination_mint.decimals;
+    let (base_decimals, quote_decimals) = match swap_direction {
+        SwapDirection::SellBase => (source_decimals, destination_decimals),
+        SwapDirection::SellQuote => (destination_decimals, source_decimals),
+    };
+
+    skim_pool_surplus(
+        &mut token_swap,
+        swap_direction,
+        swap_source_info,
+        swap_destination_info,
+        admin_destination_info,
+        swap_authority_info,
+        token_program_info,
+        swap_authority_signer_seeds,
+    )?;
+
+    let token_program_id = *token_program_info.key;
+    let reward_token = unpack_token_account(reward_token_info, &token_program_id)?;
+    let source_reward_token = unpack_token_account(source_reward_token_info, &token_program_id)?;
+    validate_reward_token_accounts(
+        &config,
+        market_authority_info.key,
+        &source_reward_token,
+        &reward_token,
+    )?;
+
+    let cached_price = next_cached_price(
+        program_id,
+        swap_info.key,
+        &token_swap,
+        clock,
+        account_info_iter,
+    )?;
+    match cached_price.map(Ok).unwrap_or_else(|| {
+        get_market_price_from_pyth(
+            pyth_a_price_info,
+            pyth_b_price_info,
+            clock,
+            &token_swap.oracle_params,
+        )
+    }) {
+        Ok((market_price, valid_slot)) => {
+            let market_price = token_swap
+                .pool_state
+                .check_and_update_market_price_and_slot(
+                    market_price,
+                    valid_slot,
+                    token_swap.price_jump_policy,
+                )?;
+            let market_price = token_swap.pool_state.update_ema_market_price(
+                market_price,
+                valid_slot,
+                token_swap.ema_window_slots,
+            )?;
+
+            token_swap
+                .pool_state
+                .set_market_price(base_decimals, quote_decimals, market_price)?;
+        }
+        Err(e) => {
+            return Err(e);
+        }
+    }
+
+    let receive_amount = token_swap
+        .pool_state
+        .get_out_amount(amount_in, swap_direction)?;
+    if token_swap.check_circuit_breaker(amount_in, receive_amount, swap_direction, clock.slot)? {
+        return Err(SwapError::CircuitBreakerTripped.into());
+    }
+    let fees = &token_swap.fees;
+    let trade_fee = next_discounted_trade_fee(
+        program_id,
+        config_info.key,
+        user_transfer_authority_info.key,
+        fees.trade_fee(receive_amount)?,
+        clock,
+        account_info_iter,
+    )?;
+    let admin_fee = fees.admin_trade_fee(trade_fee)?;
+    let (treasury_fee, treasury_token_info) = next_treasury_fee(
+        &config,
+        fees,
+        trade_fee,
+        destination_mint_info.key,
+        token_program_info.key,
+        account_info_iter,
+    )?;
+    let lp_fee = trade_fee
+        .checked_sub(admin_fee)
+        .and_then(|fee| fee.checked_sub(treasury_fee))
+        .ok_or(SwapError::CalculationFailure)?;
+    let rewards = &token_swap.rewards;
+    let amount_out = receive_amount
+        .checked_sub(trade_fee)
+        .ok_or(SwapError::CalculationFailure)?;
+
+    token_swap.check_swap_out_amount(amount_out, swap_direction)?;
+    if amount_out < minimum_amount_out {
+        return Err(SwapError::ExceededSlippage.into());
+    }
+
+    // Token price is fluctuated and need verification.
+    // To consider token price in reward calculation may not be a reliable solution.
+    // The awarded amount can be adjusted by config setting if required.
+    let amount_to_reward =
+        rewards.trade_reward_u64(if swap_direction == SwapDirection::SellBase {
+            amount_in
+        } else {
+            amount_out
+        })?;
+
+    // The actual token amount moving out of the pool is amount_out + admin_fee + treasury_fee.
+    token_swap.pool_state.swap_with_fee(
+        amount_in,
+        amount_out
+            .checked_add(admin_fee)
+            .and_then(|amount| amount.checked_add(treasury_fee))
+            .ok_or(SwapError::SwapOutflowOverflow)?,
+        lp_fee,
+        swap_direction,
+    )?;
+
+    token_transfer(
+        source_info.clone(),
+        swap_source_info.clone(),
+        user_transfer_authority_info.clone(),
+        token_program_info.clone(),
+        amount_in,
+        &[],
+    )?;
+    token_transfer(
+        swap_destination_info.clone(),
+        destination_info.clone(),
+        swap_authority_info.clone(),
+        token_program_info.clone(),
+        amount_out,
+        swap_authority_signer_seeds,
+    )?;
+    token_transfer(
+        swap_destination_info.clone(),
+        admin_destination_info.clone(),
+        swap_authority_info.clone(),
+        token_program_info.clone(),
+        admin_fee,
+        swap_authority_signer_seeds,
+    )?;
+    if let Some(treasury_token_info) = treasury_token_info {
+        token_transfer(
+            swap_destination_info.clone(),
+            treasury_token_info.clone(),
+            swap_authority_info.clone(),
+            token_program_info.clone(),
+            treasury_fee,
+            swap_authority_signer_seeds,
+        )?;
+    }
+    token_transfer(
+        source_reward_token_info.clone(),
+        reward_token_info.clone(),
+        market_authority_info.clone(),
+        token_program_info.clone(),
+        amount_to_reward,
+        market_authority_signer_seeds,
+    )?;
+
+    let swap_source_token = unpack_token_account(swap_source_info, &token_program_id)?;
+    let swap_dest_token = unpack_token_account(swap_destination_info, &token_program_id)?;
+    if swap_direction == SwapDirection::SellBase {
+        token_swap
+            .pool_state
+            .check_reserve_amount(swap_source_token.amount, swap_dest_token.amount)?;
+    } else {
+        token_swap
+            .pool_state
+            .check_reserve_amount(swap_dest_token.amount, swap_source_token.amount)?;
+    }
+    token_swap.pool_state.check_share_price_floor()?;
+    token_swap.update_virtual_price()?;
+
+    if let Some(output_owner) = output_owner {
+        let instructions_sysvar_info = next_account_info(account_info_iter)?;
+        let destination_token = unpack_token_account(destination_info, &token_program_id)?;
+        utils::validate_output_owner(
+            program_id,
+            &destination_token.owner,
+            &output_owner,
+            instructions_sysvar_info,
+        )?;
+    }
+
+    // Handle referral reward
+    let source_token = unpack_token_account(source_info, &token_program_id)?;
+    if let Some(referrer_token_info) = next_referrer_token(
+        program_id,
+        config_info.key,
+        &source_token.owner,
+        &token_program_id,
+        account_info_iter,
+    )? {
+        let referral_reward = next_referral_reward(
+            program_id,
+            config_info.key,
+            referrer_token_info.key,
+            amount_to_reward,
+            account_info_iter,
+        )?;
+        token_transfer(
+            source_reward_token_info.clone(),
+            referrer_token_info.clone(),
+            market_authority_info.clone(),
+            token_program_info.clone(),
+            referral_reward,
+            market_authority_signer_seeds,
+        )?;
+    }
+
+    token_swap.record_swap(amount_in, amount_out, trade_fee, swap_direction)?;
+
+    SwapEvent {
+        swap: *swap_info.key,
+        user: *user_transfer_authority_info.key,
+        direction: swap_direction,
+        amount_in,
+        amount_out,
+        trade_fee,
+        admin_fee,
+        market_price: token_swap.pool_state.market_price,
+    }
+    .emit();
+
+    // Only the pool state and the circuit breaker change, written in place of repacking
+    SwapInfoView::load_mut(swap_info)?.store_swap_state(&token_swap);
+
+    Ok(())
+}
+
+fn process_swap_v2(
+    program_id: &Pubkey,
+    amount_in: u64,
+    minimum_amount_out: u64,
+    output_owner: Option<Pubkey>,
+    partial_fill: bool,
+    accounts: &[AccountInfo],
+) -> ProgramResult {
+    let account_info_iter = &mut accounts.iter();
+    let config_info = next_account_info(account_info_iter)?;
+    let swap_info = next_account_info(account_info_iter)?;
+    let market_authority_info = next_account_info(account_info_iter)?;
+    let swap_authority_info = next_account_info(account_info_iter)?;
+    let user_transfer_authority_info = next_account_info(account_info_iter)?;
+    let source_info = next_account_info(account_info_iter)?;
+    let swap_source_info = next_account_info(account_info_iter)?;
+    let swap_destination_info = next_account_info(account_info_iter)?;
+    let destination_info = next_account_info(account_info_iter)?;
+    let reward_token_info = next_account_info(account_info_iter)?;
+    let source_reward_token_info = next_account_info(account_info_iter)?;
+    let admin_destination_info = next_account_info(account_info_iter)?;
+    let pyth_a_price_info = next_account_info(account_info_iter)?;
+    let pyth_b_price_info = next_account_info(account_info_iter)?;
+    let serum_market_info = next_account_info(account_info_iter)?;
+    let serum_bids_info = next_account_info(account_info_iter)?;
+    let serum_asks_info = next_account_info(account_info_iter)?;
+    let clock = &Clock::get()?;
+    let token_program_info = next_account_info(account_info_iter)?;
+
+    spl_token::check_program_account(token_program_info.key)?;
+
+    if swap_info.owner != program_id || config_info.owner != program_id {
+        return Err(ProgramError::IncorrectProgramId);
+    }
+
+    let config = ConfigInfo::unpack(&config_info.data.borrow())?;
+    let orderbook_program_id = config.accepted_orderbook_program_id();
+    let mut token_swap = SwapInfo::unpack(&swap_info.data.borrow())?;
+
+    utils::validate_swap_config_key(&token_swap, config_info.key)?;
+    utils::validate(
+        token_swap.swap_type == SwapType::Normal,
+        SwapError::IncorrectSwapType,
+    )?;
+
+    if token_swap.is_paused || token_swap.pause_swap {
+        return Err(SwapError::IsPaused.into());
+    }
+    next_whitelist_entry(
+        program_id,
+        swap_info.key,
+        &token_swap,
+        user_transfer_authority_info.key,
+        account_info_iter,
+    )?;
+    let swap_authority_signer_seeds = &[swap_info.key.as_ref(), &[token_swap.nonce]];
+    if *swap_authority_info.key
+        != Pubkey::create_program_address(swap_authority_signer_seeds, program_id)?
+    {
+        return Err(SwapError::InvalidProgramAddress.into());
+    }
+    let market_authority_signer_seeds = &[config_info.key.as_ref(), &[config.bump_seed]];
+    if *market_authority_info.key
+        != Pubkey::create_program_address(market_authority_signer_seeds, program_id)?
+    {
+        return Err(SwapError::InvalidProgramAddress.into());
+    }
+    check_pool_oracle_accounts(&token_swap, pyth_a_price_info, pyth_b_price_info)?;
+    if source_info.key == destination_info.key {
+        return Err(SwapError::InvalidInput.into());
+    }
+    // The user token accounts are unpacked once, the transfers leave their mints and owners
+    let source_token = unpack_token_account(source_info, token_program_info.key)?;
+    let destination_token = unpack_token_account(destination_info, token_program_info.key)?;
+    if token_swap.strict_destination_owner && output_owner.is_none() {
+        utils::validate_destination_owner(&destination_token.owner, accounts)?;
+    }
+    if source_info.key == swap_source_info.key || destination_info.key == swap_destination_info.key
+    {
+        return Err(SwapError::InvalidInput.into());
+    }
+    let swap_direction = if *swap_source_info.key == token_swap.token_a
+        && *swap_destination_info.key == token_swap.token_b
+    {
+        SwapDirection::SellBase
+    } else if *swap_source_info.key == token_swap.token_b
+        && *swap_destination_info.key == token_swap.token_a
+    {
+        SwapDirection::SellQuote
+    } else {
+        return Err(SwapError::IncorrectSwapAccount.into());
+    };
+
+    match swap_direction {
+        SwapDirection::SellQuote => utils::validate(
+            token_swap.admin_fee_key_a == *admin_destination_info.key,
+            SwapError::InvalidAdmin,
+        )?,
+        SwapDirection::SellBase => utils::validate(
+            token_swap.admin_fee_key_b == *admin_destination_info.key,
+            SwapError::InvalidAdmin,
+        )?,
+    }
+
+    // verify source and dest mint address
+    utils::validate_swap_token_mint(
+        swap_direction,
+        &token_swap.token_a_mint,
+        &token_swap.token_b_mint,
+        &source_token.mint,
+        &destination_token.mint,
+    )?;
+
+    skim_pool_surplus(
+        &mut token_swap,
+        swap_direction,
+        swap_source_info,
+        swap_destination_info,
+        admin_destination_info,
+        swap_authority_info,
+        token_program_info,
+        swap_authority_signer_seeds,
+    )?;
+
+    let token_program_id = *token_program_info.key;
+    {
+        let reward_token = unpack_token_account(reward_token_info, &token_program_id)?;
+        let source_reward_token =
+            unpack_token_account(source_reward_token_info, &token_program_id)?;
+        validate_reward_token_accounts(
+            &config,
+            market_authority_info.key,
+            &source_reward_token,
+            &reward_token,
+        )?;
+        if OraclePriorityFlag::from_bits_truncate(token_swap.oracle_priority_flags).uses_serum() {
+            utils::check_serum_accounts(
+                serum_market_info,
+                serum_bids_info,
+                serum_asks_info,
+                &token_swap.serum_combined_address,
+                &orderbook_program_id,
+            )?;
+            let (base_mint, quote_mint) = token_swap.base_quote_mints();
+            utils::validate_serum_market_mint_address(serum_market_info, &base_mint, &quote_mint)?;
+        }
+    }
+
+    // Only pyth priced pools read the cache, the other oracle prices are not cached.
+    let cached_price = next_cached_price(
+        program_id,
+        swap_info.key,
+        &token_swap,
+        clock,
+        account_info_iter,
+    )?
+    .filter(|_| {
+        let oracle_flags = OraclePriorityFlag::from_bits_truncate(token_swap.oracle_priority_flags);
+        oracle_flags.uses_pyth() && !oracle_flags.uses_serum()
+    });
+    // The curve still prices at the last market price, the deviation of its mid price from
+    // the oracle price drives the dynamic trade fee.
+    let mid_price = token_swap.pool_state.mid_price()?;
+    match cached_price.map(Ok).unwrap_or_else(|| {
+        get_pool_market_price(
+            &token_swap,
+            pyth_a_price_info,
+            pyth_b_price_info,
+            clock,
+            serum_market_info,
+            serum_bids_info,
+            serum_asks_info,
+            &orderbook_program_id,
+        )
+    }) {
+        Ok((market_price, valid_slot)) => {
+            let market_price = token_swap
+                .pool_state
+                .check_and_update_market_price_and_slot(
+                    market_price,
+                    valid_slot,
+                    token_swap.price_jump_policy,
+                )?;
+            let market_price = token_swap.pool_state.update_ema_market_price(
+                market_price,
+                valid_slot,
+                token_swap.ema_window_slots,
+            )?;
+
+            token_swap.pool_state.set_market_price(
+                token_swap.token_a_decimals,
+                token_swap.token_b_decimals,
+                market_price,
+            )?;
+        }
+        Err(e) => {
+            return Err(e);
+        }
+    }
+    let price_deviation = token_swap.pool_state.market_price_deviation(mid_price)?;
+
+    // A partial fill takes in the largest part of the input within the swap out limit and the
+    // circuit breaker band, the rest stays in the SOURCE account. The minimum output is scaled
+    // down with the filled input.
+    let (amount_in, minimum_amount_out) = if partial_fill {
+        let filled_amount_in =
+            token_swap.partial_fill_amount_in(amount_in, swap_direction, clock.slot)?;
+        if filled_amount_in == 0 {
+            return Err(SwapError::ExceededSwapOutAmount.into());
+        }
+        let filled_minimum_amount_out = (minimum_amount_out as u128)
+            .checked_mul(filled_amount_in as u128)
+            .and_then(|amount| amount.checked_add(amount_in as u128 - 1))
+            .map(|amount| amount / amount_in as u128)
+            .and_then(|amount| u64::try_from(amount).ok())
+            .ok_or(SwapError::CalculationFailure)?;
+        (filled_amount_in, filled_minimum_amount_out)
+    } else {
+        (amount_in, minimum_amount_out)
+    };
+
+    let receive_amount = token_swap
+        .pool_state
+        .get_out_amount(amount_in, swap_direction)?;
+    if token_swap.check_circuit_breaker(amount_in, receive_amount, swap_direction, clock.slot)? {
+        return Err(SwapError::CircuitBreakerTripped.into());
+    }
+    let fees = &token_swap.fees;
+    let trade_fee = next_discounted_trade_fee(
+        program_id,
+        config_info.key,
+        user_transfer_authority_info.key,
+        fees.dynamic_trade_fee(receive_amount, price_deviation)?,
+        clock,
+        account_info_iter,
+    )?;
+    let destination_mint = match swap_direction {
+        SwapDirection::SellBase => token_swap.token_b_mint,
+        SwapDirection::SellQuote => token_swap.token_a_mint,
+    };
+    let treasury_token_info = next_treasury_token(
+        &config,
+        &destination_mint,
+        token_program_info.key,
+        account_info_iter,
+    )?;
+    let instructions_sysvar_info = match output_owner {
+        Some(_) => Some(next_account_info(account_info_iter)?),
+        None => None,
+    };
+    let referrer_token_info = next_referrer_token(
+        program_id,
+        config_info.key,
+        &source_token.owner,
+        &token_program_id,
+        account_info_iter,
+    )?;
+    let rewards = &token_swap.rewards;
+    // The referrer share of the trade fee goes to the referrer token account of the destination
+    // mint following the referrer token account.
+    let referrer_fee_token_info = next_referrer_fee_token(
+        referrer_token_info,
+        rewards,
+        &destination_mint,
+        &token_program_id,
+        account_info_iter,
+    )?;
+    let swap_fees = SwapFees::new(
+        fees,
+        rewards,
+        trade_fee,
+        treasury_token_info.is_some(),
+        referrer_fee_token_info.is_some(),
+    )?;
+    let amount_out = receive_amount
+        .checked_sub(trade_fee)
+        .ok_or(SwapError::CalculationFailure)?;
+    token_swap.check_swap_out_amount(amount_out, swap_direction)?;
+    if amount_out < minimum_amount_out {
+        return Err(SwapError::ExceededSlippage.into());
+    }
+
+    // Token price is fluctuated and need verification.
+    // To consider token price in reward calculation may not be a reliable solution.
+    // The awarded amount can be adjusted by config setting if required.
+    let amount_to_reward =
+        rewards.trade_reward_u64(if swap_direction == SwapDirection::SellBase {
+            amount_in
+        } else {
+            amount_out
+        })?;
+
+    token_swap.pool_state.swap_with_fee(
+        amount_in,
+        swap_fees.pool_outflow(amount_out)?,
+        swap_fees.lp_fee()?,
+        swap_direction,
+    )?;
+
+    token_transfer(
+        source_info.clone(),
+        swap_source_info.clone(),
+        user_transfer_authority_info.clone(),
+        token_program_info.clone(),
+        amount_in,
+        &[],
+    )?;
+    token_transfer(
+        swap_destination_info.clone(),
+        destination_info.clone(),
+        swap_authority_info.clone(),
+        token_program_info.clone(),
+        amount_out,
+        swap_authority_signer_seeds,
+    )?;
+    swap_fees.transfer(
+        swap_destination_info,
+        admin_destination_info,
+        treasury_token_info,
+        referrer_fee_token_info,
+        swap_authority_info,
+        token_program_info,
+        swap_authority_signer_seeds,
+    )?;
+    token_transfer(
+        source_reward_token_info.clone(),
+        reward_token_info.clone(),
+        market_authority_info.clone(),
+        token_program_info.clone(),
+        amount_to_reward,
+        market_authority_signer_seeds,
+    )?;
+
+    // Only the amounts of the pool token accounts changed by the transfers are read
+    let swap_source_amount = token_account_amount(swap_source_info, &token_program_id)?;
+    let swap_dest_amount = token_account_amount(swap_destination_info, &token_program_id)?;
+    if swap_direction == SwapDirection::SellBase {
+        token_swap
+            .pool_state
+            .check_reserve_amount(swap_source_amount, swap_dest_amount)?;
+    } else {
+        token_swap
+            .pool_state
+            .check_reserve_amount(swap_dest_amount, swap_source_amount)?;
+    }
+    token_swap.pool_state.check_share_price_floor()?;
+    token_swap.update_virtual_price()?;
+
+    if let (Some(output_owner), Some(instructions_sysvar_info)) =
+        (output_owner, instructions_sysvar_info)
+    {
+        utils::validate_output_owner(
+            program_id,
+            &destination_token.owner,
+            &output_owner,
+            instructions_sysvar_info,
+        )?;
+    }
+
+    // Handle referral reward
+    if let Some(referrer_token_info) = referrer_token_info {
+        let referral_reward = next_referral_reward(
+            program_id,
+            config_info.key,
+            referrer_token_info.key,
+            amount_to_reward,
+            account_info_iter,
+        )?;
+        token_transfer(
+            source_reward_token_info.clone(),
+            referrer_token_info.clone(),
+            market_authority_info.clone(),
+            token_program_info.clone(),
+            referral_reward,
+            market_authority_signer_seeds,
+        )?;
+    }
+
+    token_swap.record_swap(amount_in, amount_out, trade_fee, swap_direction)?;
+
+    SwapEvent {
+        swap: *swap_info.key,
+        user: *user_transfer_authority_info.key,
+        direction: swap_direction,
+        amount_in,
+        amount_out,
+        trade_fee,
+        admin_fee: swap_fees.admin_fee,
+        market_price: token_swap.pool_state.market_price,
+    }
+    .emit();
+
+    // Only the pool state and the circuit breaker change, written in place of repacking
+    SwapInfoView::load_mut(swap_info)?.store_swap_state(&token_swap);
+
+    if partial_fill {
+        set_return_data(
+            &SwapFill {
+                amount_in,
+                amount_out,
+            }
+            .pack(),
+        );
+    }
+
+    Ok(())
+}
+
+fn process_deposit(
+    program_id: &Pubkey,
+    swap_type: SwapType,
+    token_a_amount: u64,
+    token_b_amount: u64,
+    min_mint_amount: u64,
+    accounts: &[AccountInfo],
+) -> ProgramResult {
+    let account_info_iter = &mut accounts.iter();
+    let swap_info = next_account_info(account_info_iter)?;
+    let authority_info = next_account_info(account_info_iter)?;
+    let user_transfer_authority_info = next_account_info(account_info_iter)?;
+    let source_a_info = next_account_info(account_info_iter)?;
+    let source_b_info = next_account_info(account_info_iter)?;
+    let token_a_info = next_account_info(account_info_iter)?;
+    let token_b_info = next_account_info(account_info_iter)?;
+    let pool_mint_info = next_account_info(account_info_iter)?;
+    let destination_info = next_account_info(account_info_iter)?;
+    let token_program_info = next_account_info(account_info_iter)?;
+
+    spl_token::check_program_account(token_program_info.key)?;
+
+    if swap_info.owner != program_id {
+        return Err(SwapError::InvalidAccountOwner.into());
+    }
+
+    let mut token_swap = SwapInfo::unpack(&swap_info.data.borrow())?;
+    utils::validate(
+        token_swap.swap_type.is_traded_as(swap_type),
+        SwapError::IncorrectSwapType,
+    )?;
+
+    if token_swap.is_paused || token_swap.pause_deposit {
+        return Err(SwapError::IsPaused.into());
+    }
+    next_whitelist_entry(
+        program_id,
+        swap_info.key,
+        &token_swap,
+        user_transfer_authority_info.key,
+        account_info_iter,
+    )?;
+    let swap_authority_signer_seeds = &[swap_info.key.as_ref(), &[token_swap.nonce]];
+    if *authority_info.key
+        != Pubkey::create_program_address(swap_authority_signer_seeds, program_id)?
+    {
+        return Err(SwapError::InvalidProgramAddress.into());
+    }
+    if *token_a_info.key != token_swap.token_a {
+        return Err(SwapError::IncorrectSwapAccount.into());
+    }
+    if *token_b_info.key != token_swap.token_b {
+        return Err(SwapError::IncorrectSwapAccount.into());
+    }
+    if *pool_mint_info.key != token_swap.pool_mint {
+        return Err(SwapError::IncorrectMint.into());
+    }
+    if token_a_info.key == source_a_info.key {
+        return Err(SwapError::InvalidInput.into());
+    }
+    if token_b_info.key == source_b_info.key {
+        return Err(SwapError::InvalidInput.into());
+    }
+
+    let last_total_supply = token_swap.pool_state.total_supply;
+    let last_mint_supply = unpack_mint(pool_mint_info, token_program_info.key)?.supply;
+
+    let (pool_mint_amount, token_a_output, token_b_output) = token_swap
+        .pool_state
+        .buy_shares(token_a_amount, token_b_amount)?;
+
+    if pool_mint_amount < min_mint_amount {
+        return Err(SwapError::ExceededSlippage.into());
+    }
+    token_swap.check_deposit_cap()?;
+
+    token_transfer(
+        source_a_info.clone(),
+        token_a_info.clone(),
+        user_transfer_authority_info.clone(),
+        token_program_info.clone(),
+        token_a_output,
+        &[],
+    )?;
+    token_transfer(
+        source_b_info.clone(),
+        token_b_info.clone(),
+        user_transfer_authority_info.clone(),
+        token_program_info.clone(),
+        token_b_output,
+        &[],
+    )?;
+    token_mint_to(
+        pool_mint_info.clone(),
+        destination_info.clone(),
+        authority_info.clone(),
+        token_program_info.clone(),
+        pool_mint_amount,
+        swap_authority_signer_seeds,
+    )?;
+
+    let token_a = unpack_token_account(token_a_info, token_program_info.key)?;
+    let token_b = unpack_token_account(token_b_info, token_program_info.key)?;
+    token_swap
+        .pool_state
+        .check_reserve_amount(token_a.amount, token_b.amount)?;
+    token_swap.pool_state.check_share_price_floor()?;
+    token_swap.update_virtual_price()?;
+
+    let pool_mint = unpack_mint(pool_mint_info, token_program_info.key)?;
+    token_swap.pool_state.check_mint_supply_change(
+        last_total_supply,
+        last_mint_supply,
+        pool_mint.supply,
+    )?;
+
+    LiquidityEvent {
+        event_type: EventType::Deposit,
+        swap: *swap_info.key,
+        user: *user_transfer_authority_info.key,
+        token_a_amount: token_a_output,
+        token_b_amount: token_b_output,
+        pool_token_amount: pool_mint_amount,
+        fee_a: 0,
+        fee_b: 0,
+        admin_fee_a: 0,
+        admin_fee_b: 0,
+        market_price: token_swap.pool_state.market_price,
+    }
+    .emit();
+
+    SwapInfo::pack(token_swap, &mut swap_info.data.borrow_mut())?;
+
+    Ok(())
+}
+
+/// Refresh the pool market price from the oracle accounts for normal pools,
+/// stable pools always trade at price 1.
+fn update_pool_market_price<'a, 'b: 'a>(
+    token_swap: &mut SwapInfo,
+    swap_type: SwapType,
+    orderbook_program_id: &Pubkey,
+    account_info_iter: &mut std::slice::Iter<'a, AccountInfo<'b>>,
+) -> ProgramResult {
+    if swap_type == SwapType::Normal {
+        let pyth_a_price_info = next_account_info(account_info_iter)?;
+        let pyth_b_price_info = next_account_info(account_info_iter)?;
+        let serum_market_info = next_account_info(account_info_iter)?;
+        let serum_bids_info = next_account_info(account_info_iter)?;
+        let serum_asks_info = next_account_info(account_info_iter)?;
+        let clock = &Clock::get()?;
+
+        check_pool_oracle_accounts(token_swap, pyth_a_price_info, pyth_b_price_info)?;
+        if OraclePriorityFlag::from_bits_truncate(token_swap.oracle_priority_flags).uses_serum() {
+            utils::check_serum_accounts(
+                serum_market_info,
+                serum_bids_info,
+                serum_asks_info,
+                &token_swap.serum_combined_address,
+                orderbook_program_id,
+            )?;
+            let (base_mint, quote_mint) = token_swap.base_quote_mints();
+            utils::validate_serum_market_mint_address(serum_market_info, &base_mint, &quote_mint)?;
+        }
+
+        let (market_price, valid_slot) = get_pool_market_price(
+            token_swap,
+            pyth_a_price_info,
+            pyth_b_price_info,
+            clock,
+            serum_market_info,
+            serum_bids_info,
+            serum_asks_info,
+            orderbook_program_id,
+        )?;
+        let market_price = token_swap
+            .pool_state
+            .check_and_update_market_price_and_slot(
+                market_price,
+                valid_slot,
+                token_swap.price_jump_policy,
+            )?;
+        let market_price = token_swap.pool_state.update_ema_market_price(
+            market_price,
+            valid_slot,
+            token_swap.ema_window_slots,
+        )?;
+        token_swap.pool_state.set_market_price(
+            token_swap.token_a_decimals,
+            token_swap.token_b_decimals,
+            market_price,
+        )?;
+    } else {
+        let market_price = get_stable_market_price(
+            token_swap,
+            token_swap.token_a_decimals,
+            token_swap.token_b_decimals,
+            account_info_iter,
+        )?;
+        token_swap.pool_state.set_market_price(
+            token_swap.token_a_decimals,
+            token_swap.token_b_decimals,
+            market_price,
+        )?;
+    }
+
+    Ok(())
+}
+
+/// Get the market price of a stable pool, read from the exchange rate account if the pool
+/// has an exchange rate adapter, otherwise the price is 1.
+/// Pegged pools keep their configured price as the last market price.
+fn get_stable_market_price<'a, 'b: 'a>(
+    token_swap: &SwapInfo,
+    base_decimals: u8,
+    quote_decimals: u8,
+    account_info_iter: &mut std::slice::Iter<'a, AccountInfo<'b>>,
+) -> Result<Decimal, ProgramError> {
+    if token_swap.swap_type == SwapType::Pegged {
+        return Ok(token_swap.pool_state.last_market_price);
+    }
+    match token_swap.exchange_rate_adapter.adapter() {
+        Some(adapter) => {
+            let exchange_rate_info = next_account_info(account_info_iter)?;
+            if exchange_rate_info.key != token_swap.exchange_rate_account() {
+                return Err(SwapError::InvalidExchangeRateAccount.into());
+            }
+            get_exchange_rate_price(
+                adapter,
+                exchange_rate_info,
+                &Clock::get()?,
+                base_decimals,
+                quote_decimals,
+            )
+        }
+        None => Ok(Decimal::one()),
+    }
+}
+
+fn process_deposit_one(
+    program_id: &Pubkey,
+    swap_type: SwapType,
+    amount_in: u64,
+    min_mint_amount: u64,
+    accounts: &[AccountInfo],
+) -> ProgramResult {
+    let account_info_iter = &mut accounts.iter();
+    let config_info = next_account_info(account_info_iter)?;
+    let swap_info = next_account_info(account_info_iter)?;
+    let authority_info = next_account_info(account_info_iter)?;
+    let user_transfer_authority_info = next_account_info(account_info_iter)?;
+    let source_info = next_account_info(account_info_iter)?;
+    let token_a_info = next_account_info(account_info_iter)?;
+    let token_b_info = next_account_info(account_info_iter)?;
+    let admin_destination_info = next_account_info(account_info_iter)?;
+    let pool_mint_info = next_account_info(account_info_iter)?;
+    let destination_info = next_account_info(account_info_iter)?;
+
+    if swap_info.owner != program_id || config_info.owner != program_id {
+        return Err(ProgramError::IncorrectProgramId);
+    }
+
+    let mut token_swap = SwapInfo::unpack(&swap_info.data.borrow())?;
+    utils::validate_swap_config_key(&token_swap, config_info.key)?;
+    utils::validate(
+        token_swap.swap_type.is_traded_as(swap_type),
+        SwapError::IncorrectSwapType,
+    )?;
+
+    let orderbook_program_id =
+        ConfigInfo::unpack(&config_info.data.borrow())?.accepted_orderbook_program_id();
+    update_pool_market_price(
+        &mut token_swap,
+        swap_type,
+        &orderbook_program_id,
+        account_info_iter,
+    )?;
+
+    let token_program_info = next_account_info(account_info_iter)?;
+    spl_token::check_program_account(token_program_info.key)?;
+    next_whitelist_entry(
+        program_id,
+        swap_info.key,
+        &token_swap,
+        user_transfer_authority_info.key,
+        account_info_iter,
+    )?;
+
+    deposit_one_into_pool(
+        program_id,
+        token_swap,
+        swap_info,
+        authority_info,
+        user_transfer_authority_info,
+        &[],
+        source_info,
+        token_a_info,
+        token_b_info,
+        admin_destination_info,
+        pool_mint_info,
+        destination_info,
+        token_program_info,
+        user_transfer_authority_info.key,
+        amount_in,
+        min_mint_amount,
+    )?;
+
+    Ok(())
+}
+
+/// Deposit a single token into the pool, part of it swapped to the other side, and mint the
+/// pool tokens to the destination. What the pool does not take stays in the source.
+/// Returns the pool tokens minted and the amount taken from the source.
+fn deposit_one_into_pool<'a>(
+    program_id: &Pubkey,
+    mut token_swap: SwapInfo,
+    swap_info: &AccountInfo<'a>,
+    authority_info: &AccountInfo<'a>,
+    source_authority_info: &AccountInfo<'a>,
+    source_signer_seeds: &[&[u8]],
+    source_info: &AccountInfo<'a>,
+    token_a_info: &AccountInfo<'a>,
+    token_b_info: &AccountInfo<'a>,
+    admin_destination_info: &AccountInfo<'a>,
+    pool_mint_info: &AccountInfo<'a>,
+    destination_info: &AccountInfo<'a>,
+    token_program_info: &AccountInfo<'a>,
+    user: &Pubkey,
+    amount_in: u64,
+    min_mint_amount: u64,
+) -> Result<(u64, u64), ProgramError> {
+    if token_swap.is_paused || token_swap.pause_deposit {
+        return Err(SwapError::IsPaused.into());
+    }
+    let swap_authority_signer_seeds = &[swap_info.key.as_ref(), &[token_swap.nonce]];
+    if *authority_info.key
+        != Pubkey::create_program_address(swap_authority_signer_seeds, program_id)?
+    {
+        return Err(SwapError::InvalidProgramAddress.into());
+    }
+    if *token_a_info.key != token_swap.token_a {
+        return Err(SwapError::IncorrectSwapAccount.into());
+    }
+    if *token_b_info.key != token_swap.token_b {
+        return Err(SwapError::IncorrectSwapAccount.into());
+    }
+    if *pool_mint_info.key != token_swap.pool_mint {
+        return Err(SwapError::IncorrectMint.into());
+    }
+    if source_info.key == token_a_info.key || source_info.key == token_b_info.key {
+        return Err(SwapError::InvalidInput.into());
+    }
+
+    let token_program_id = *token_program_info.key;
+    let source_token = unpack_token_account(source_info, &token_program_id)?;
+    let (swap_direction, swap_source_info, swap_destination_info) =
+        if source_token.mint == token_swap.token_a_mint {
+            (SwapDirection::SellBase, token_a_info, token_b_info)
+        } else if source_token.mint == token_swap.token_b_mint {
+            (SwapDirection::SellQuote, token_b_info, token_a_info)
+        } else {
+            return Err(SwapError::IncorrectMint.into());
+        };
+
+    match swap_direction {
+        SwapDirection::SellQuote => utils::validate(
+            token_swap.admin_fee_key_a == *admin_destination_info.key,
+            SwapError::InvalidAdmin,
+        )?,
+        SwapDirection::SellBase => utils::validate(
+            token_swap.admin_fee_key_b == *admin_destination_info.key,
+            SwapError::InvalidAdmin,
+        )?,
+    }
+
+    let last_total_supply = token_swap.pool_state.total_supply;
+    let last_mint_supply = unpack_mint(pool_mint_info, &token_program_id)?.supply;
+
+    let swap_amount = token_swap
+        .pool_state
+        .deposit_one_swap_amount(amount_in, swap_direction)?;
+    let receive_amount = token_swap
+        .pool_state
+        .get_out_amount(swap_amount, swap_direction)?;
+    let fees = &token_swap.fees;
+    let trade_fee = fees.trade_fee(receive_amount)?;
+    let admin_fee = fees.admin_trade_fee(trade_fee)?;
+    let amount_out = receive_amount
+        .checked_sub(trade_fee)
+        .ok_or(SwapError::CalculationFailure)?;
+    token_swap.check_swap_out_amount(amount_out, swap_direction)?;
+
+    // The swap output stays in the pool, only the admin fee moves out.
+    token_swap.pool_state.swap_with_fee(
+        swap_amount,
+        amount_out
+            .checked_add(admin_fee)
+            .ok_or(SwapError::SwapOutflowOverflow)?,
+        trade_fee
+            .checked_sub(admin_fee)
+            .ok_or(SwapError::LpFeeUnderflow)?,
+        swap_direction,
+    )?;
+
+    let deposit_amount = amount_in
+        .checked_sub(swap_amount)
+        .ok_or(SwapError::CalculationFailure)?;
+    let (pool_mint_amount, deposit_used) =
+        token_swap
+            .pool_state
+            .buy_shares_one_side(deposit_amount, amount_out, swap_direction)?;
+
+    if pool_mint_amount < min_mint_amount {
+        return Err(SwapError::ExceededSlippage.into());
+    }
+    token_swap.check_deposit_cap()?;
+
+    let deposit_in_amount = swap_amount
+        .checked_add(deposit_used)
+        .ok_or(SwapError::CalculationFailure)?;
+    token_transfer(
+        source_info.clone(),
+        swap_source_info.clone(),
+        source_authority_info.clone(),
+        token_program_info.clone(),
+        deposit_in_amount,
+        source_signer_seeds,
+    )?;
+    token_transfer(
+        swap_destination_info.clone(),
+        admin_destination_info.clone(),
+        authority_info.clone(),
+        token_program_info.clone(),
+        admin_fee,
+        swap_authority_signer_seeds,
+    )?;
+    token_mint_to(
+        pool_mint_info.clone(),
+        destination_info.clone(),
+        authority_info.clone(),
+        token_program_info.clone(),
+        pool_mint_amount,
+        swap_authority_signer_seeds,
+    )?;
+
+    let token_a = unpack_token_account(token_a_info, &token_program_id)?;
+    let token_b = unpack_token_account(token_b_info, &token_program_id)?;
+    token_swap
+        .pool_state
+        .check_reserve_amount(token_a.amount, token_b.amount)?;
+    token_swap.pool_state.check_share_price_floor()?;
+    token_swap.update_virtual_price()?;
+
+    let pool_mint = unpack_mint(pool_mint_info, &token_program_id)?;
+    token_swap.pool_state.check_mint_supply_change(
+        last_total_supply,
+        last_mint_supply,
+        pool_mint.supply,
+    )?;
+
+    // The trade fee of the swapped part is in the other token of the pool.
+    let (token_a_amount, token_b_amount, fee_a, fee_b, admin_fee_a, admin_fee_b) =
+        match swap_direction {
+            SwapDirection::SellBase => (deposit_in_amount, 0, 0, trade_fee, 0, admin_fee),
+            SwapDirection::SellQuote => (0, deposit_in_amount, trade_fee, 0, admin_fee, 0),
+        };
+    LiquidityEvent {
+        event_type: EventType::Deposit,
+        swap: *swap_info.key,
+        user: *user,
+        token_a_amount,
+        token_b_amount,
+        pool_token_amount: pool_mint_amount,
+        fee_a,
+        fee_b,
+        admin_fee_a,
+        admin_fee_b,
+        market_price: token_swap.pool_state.market_price,
+    }
+    .emit();
+
+    SwapInfo::pack(token_swap, &mut swap_info.data.borrow_mut())?;
+
+    Ok((pool_mint_amount, deposit_in_amount))
+}
+
+fn process_withdraw_one(
+    program_id: &Pubkey,
+    swap_type: SwapType,
+    pool_token_amount: u64,
+    minimum_amount_out: u64,
+    token_side: u8,
+    accounts: &[AccountInfo],
+) -> ProgramResult {
+    let account_info_iter = &mut accounts.iter();
+    let config_info = next_account_info(account_info_iter)?;
+    let swap_info = next_account_info(account_info_iter)?;
+    let authority_info = next_account_info(account_info_iter)?;
+    let user_transfer_authority_info = next_account_info(account_info_iter)?;
+    let pool_mint_info = next_account_info(account_info_iter)?;
+    let source_info = next_account_info(account_info_iter)?;
+    let token_a_info = next_account_info(account_info_iter)?;
+    let token_b_info = next_account_info(account_info_iter)?;
+    let destination_info = next_account_info(account_info_iter)?;
+    let admin_destination_info = next_account_info(account_info_iter)?;
+
+    if swap_info.owner != program_id || config_info.owner != program_id {
+        return Err(ProgramError::IncorrectProgramId);
+    }
+
+    let mut token_swap = SwapInfo::unpack(&swap_info.data.borrow())?;
+    utils::validate_swap_config_key(&token_swap, config_info.key)?;
+    utils::validate(
+        token_swap.swap_type.is_traded_as(swap_type),
+        SwapError::IncorrectSwapType,
+    )?;
+    if token_swap.pause_withdraw {
+        return Err(SwapError::IsPaused.into());
+    }
+
+    let orderbook_program_id =
+        ConfigInfo::unpack(&config_info.data.borrow())?.accepted_orderbook_program_id();
+    update_pool_market_price(
+        &mut token_swap,
+        swap_type,
+        &orderbook_program_id,
+        account_info_iter,
+    )?;
+
+    let token_program_info = next_account_info(account_info_iter)?;
+    spl_token::check_program_account(token_program_info.key)?;
+
+    let swap_authority_signer_seeds = &[swap_info.key.as_ref(), &[token_swap.nonce]];
+    if *authority_info.key
+        != Pubkey::create_program_address(swap_authority_signer_seeds, program_id)?
+    {
+        return Err(SwapError::InvalidProgramAddress.into());
+    }
+    if *token_a_info.key != token_swap.token_a {
+        return Err(SwapError::IncorrectSwapAccount.into());
+    }
+    if *token_b_info.key != token_swap.token_b {
+        return Err(SwapError::IncorrectSwapAccount.into());
+    }
+    if *pool_mint_info.key != token_swap.pool_mint {
+        return Err(SwapError::IncorrectMint.into());
+    }
+    if destination_info.key == token_a_info.key || destination_info.key == token_b_info.key {
+        return Err(SwapError::InvalidInput.into());
+    }
+
+    // The other side of the withdrawn liquidity is sold for the requested token.
+    let (swap_direction, withdraw_token_info, admin_fee_key) = match token_side {
+        0 => (
+            SwapDirection::SellQuote,
+            token_a_info,
+            token_swap.admin_fee_key_a,
+        ),
+        1 => (
+            SwapDirection::SellBase,
+            token_b_info,
+            token_swap.admin_fee_key_b,
+        ),
+        _ => return Err(SwapError::InvalidInput.into()),
+    };
+    if *admin_destination_info.key != admin_fee_key {
+        return Err(SwapError::InvalidAdmin.into());
+    }
+
+    let token_program_id = *token_program_info.key;
+    let pool_mint = unpack_mint(pool_mint_info, &token_program_id)?;
+    if pool_mint.supply == 0 {
+        return Err(SwapError::EmptySupply.into());
+    }
+
+    let last_total_supply = token_swap.pool_state.total_supply;
+    let last_mint_supply = pool_mint.supply;
+
+    let (withdraw_amount, swap_amount) = token_swap
+        .pool_state
+        .sell_shares_one_side(pool_token_amount, swap_direction)?;
+
+    let receive_amount = token_swap
+        .pool_state
+        .get_out_amount(swap_amount, swap_direction)?;
+    let fees = &token_swap.fees;
+    let trade_fee = fees.trade_fee(receive_amount)?;
+    let admin_trade_fee = fees.admin_trade_fee(trade_fee)?;
+    let swap_out_amount = receive_amount
+        .checked_sub(trade_fee)
+        .ok_or(SwapError::CalculationFailure)?;
+    token_swap.check_swap_out_amount(swap_out_amount, swap_direction)?;
+
+    // The swap input never left the pool, only its output is paid out.
+    token_swap.pool_state.swap_with_fee(
+        swap_amount,
+        swap_out_amount
+            .checked_add(admin_trade_fee)
+            .ok_or(SwapError::SwapOutflowOverflow)?,
+        trade_fee
+            .checked_sub(admin_trade_fee)
+            .ok_or(SwapError::LpFeeUnderflow)?,
+        swap_direction,
+    )?;
+
+    let out_amount = withdraw_amount
+        .checked_add(swap_out_amount)
+        .ok_or(SwapError::CalculationFailure)?;
+    let withdraw_fee = fees.withdraw_fee(out_amount)?;
+    let admin_withdraw_fee = fees.admin_withdraw_fee(withdraw_fee)?;
+    let out_amount = out_amount
+        .checked_sub(withdraw_fee)
+        .ok_or(SwapError::CalculationFailure)?;
+    if out_amount < minimum_amount_out {
+        return Err(SwapError::WithdrawNotEnough.into());
+    }
+
+    let withdraw_fee_left = withdraw_fee
+        .checked_sub(admin_withdraw_fee)
+        .ok_or(SwapError::CalculationFailure)?;
+    match swap_direction {
+        SwapDirection::SellQuote => token_swap
+            .pool_state
+            .collect_trade_fee(withdraw_fee_left, 0)?,
+        SwapDirection::SellBase => token_swap
+            .pool_state
+            .collect_trade_fee(0, withdraw_fee_left)?,
+    }
+
+    let admin_fee = admin_trade_fee
+        .checked_add(admin_withdraw_fee)
+        .ok_or(SwapError::CalculationFailure)?;
+    token_transfer(
+        withdraw_token_info.clone(),
+        destination_info.clone(),
+        authority_info.clone(),
+        token_program_info.clone(),
+        out_amount,
+        swap_authority_signer_seeds,
+    )?;
+    token_transfer(
+        withdraw_token_info.clone(),
+        admin_destination_info.clone(),
+        authority_info.clone(),
+        token_program_info.clone(),
+        admin_fee,
+        swap_authority_signer_seeds,
+    )?;
+    token_burn(
+        pool_mint_info.clone(),
+        source_info.clone(),
+        user_transfer_authority_info.clone(),
+        token_program_info.clone(),
+        pool_token_amount,
+        &[],
+    )?;
+
+    let token_a = unpack_token_account(token_a_info, &token_program_id)?;
+    let token_b = unpack_token_account(token_b_info, &token_program_id)?;
+    token_swap
+        .pool_state
+        .check_reserve_amount(token_a.amount, token_b.amount)?;
+    token_swap.pool_state.check_share_price_floor()?;
+    token_swap.update_virtual_price()?;
+
+    let pool_mint = unpack_mint(pool_mint_info, &token_program_id)?;
+    token_swap.pool_state.check_mint_supply_change(
+        last_total_supply,
+        last_mint_supply,
+        pool_mint.supply,
+    )?;
+
+    // Both the trade fee and the withdraw fee are in the withdrawn token.
+    let fee = trade_fee
+        .checked_add(withdraw_fee)
+        .ok_or(SwapError::CalculationFailure)?;
+    let (token_a_amount, token_b_amount, fee_a, fee_b, admin_fee_a, admin_fee_b) =
+        match swap_direction {
+            SwapDirection::SellQuote => (out_amount, 0, fee, 0, admin_fee, 0),
+            SwapDirection::SellBase => (0, out_amount, 0, fee, 0, admin_fee),
+        };
+    LiquidityEvent {
+        event_type: EventType::Withdraw,
+        swap: *swap_info.key,
+        user: *user_transfer_authority_info.key,
+        token_a_amount,
+        token_b_amount,
+        pool_token_amount,
+        fee_a,
+        fee_b,
+        admin_fee_a,
+        admin_fee_b,
+        market_price: token_swap.pool_state.market_price,
+    }
+    .emit();
+
+    SwapInfo::pack(token_swap, &mut swap_info.data.borrow_mut())?;
+
+    Ok(())
+}
+
+fn process_withdraw(
+    program_id: &Pubkey,
+    swap_type: SwapType,
+    pool_token_amount: u64,
+    minimum_token_a_amount: u64,
+    minimum_token_b_amount: u64,
+    accounts: &[AccountInfo],
+) -> ProgramResult {
+    let account_info_iter = &mut accounts.iter();
+    let swap_info = next_account_info(account_info_iter)?;
+    let authority_info = next_account_info(account_info_iter)?;
+    let user_transfer_authority_info = next_account_info(account_info_iter)?;
+    let pool_mint_info = next_account_info(account_info_iter)?;
+    let source_info = next_account_info(account_info_iter)?;
+    let token_a_info = next_account_info(account_info_iter)?;
+    let token_b_info = next_account_info(account_info_iter)?;
+    let dest_token_a_info = next_account_info(account_info_iter)?;
+    let dest_token_b_info = next_account_info(account_info_iter)?;
+    let admin_fee_dest_a_info = next_account_info(account_info_iter)?;
+    let admin_fee_dest_b_info = next_account_info(account_info_iter)?;
+    let token_program_info = next_account_info(account_info_iter)?;
+    spl_token::check_program_account(token_program_info.key)?;
+
+    if swap_info.owner != program_id {
+        return Err(SwapError::InvalidAccountOwner.into());
+    }
+
+    let mut token_swap = SwapInfo::unpack(&swap_info.data.borrow())?;
+    utils::validate(
+        token_swap.swap_type.is_traded_as(swap_type),
+        SwapError::InvalidAccount,
+    )?;
+    if token_swap.pause_withdraw {
+        return Err(SwapError::IsPaused.into());
+    }
+
+    let swap_authority_signer_seeds = &[swap_info.key.as_ref(), &[token_swap.nonce]];
+    if *authority_info.key
+        != Pubkey::create_program_address(swap_authority_signer_seeds, program_id)?
+    {
+        return Err(SwapError::InvalidProgramAddress.into());
+    }
+    if *token_a_info.key != token_swap.token_a {
+        return Err(SwapError::IncorrectSwapAccount.into());
+    }
+    if *token_b_info.key != token_swap.token_b {
+        return Err(SwapError::IncorrectSwapAccount.into());
+    }
+    if token_a_info.key == dest_token_a_info.key {
+        return Err(SwapError::InvalidInput.into());
+    }
+    if token_b_info.key == dest_token_b_info.key {
+        return Err(SwapError::InvalidInput.into());
+    }
+    if *pool_mint_info.key != token_swap.pool_mint {
+        return Err(SwapError::IncorrectMint.into());
+    }
+    if *admin_fee_dest_a_info.key != token_swap.admin_fee_key_a {
+        return Err(SwapError::InvalidAdmin.into());
+    }
+    if *admin_fee_dest_b_info.key != token_swap.admin_fee_key_b {
+        return Err(SwapError::InvalidAdmin.into());
+    }
+
+    let pool_mint = unpack_mint(pool_mint_info, token_program_info.key)?;
+    if pool_mint.supply == 0 {
+        return Err(SwapError::EmptySupply.into());
+    }
+
+    let last_total_supply = token_swap.pool_state.total_supply;
+    let last_mint_supply = pool_mint.supply;
+
+    let (base_out_amount, quote_out_amount) = token_swap.pool_state.sell_shares(
+        pool_token_amount,
+        minimum_token_a_amount,
+        minimum_token_b_amount,
+    )?;
+
+    let fees = &token_swap.fees;
+    let withdraw_fee_base = fees.withdraw_fee(base_out_amount)?;
+    let admin_fee_base = fees.admin_withdraw_fee(withdraw_fee_base)?;
+    let base_out_amount = base_out_amount
+        .checked_sub(withdraw_fee_base)
+        .ok_or(SwapError::CalculationFailure)?;
+
+    let withdraw_fee_quote = fees.withdraw_fee(quote_out_amount)?;
+    let admin_fee_quote = fees.admin_withdraw_fee(withdraw_fee_quote)?;
+    let quote_out_amount = quote_out_amount
+        .checked_sub(withdraw_fee_quote)
+        .ok_or(SwapError::CalculationFailure)?;
+
+    token_swap.pool_state.collect_trade_fee(
+        withdraw_fee_base
+            .checked_sub(admin_fee_base)
+            .ok_or(SwapError::CalculationFailure)?,
+        withdraw_fee_quote
+            .checked_sub(admin_fee_quote)
+            .ok_or(SwapError::CalculationFailure)?,
+    )?;
+
+    token_transfer(
+        token_a_info.clone(),
+        dest_token_a_info.clone(),
+        authority_info.clone(),
+        token_program_info.clone(),
+        base_out_amount,
+        swap_authority_signer_seeds,
+    )?;
+    token_transfer(
+        token_a_info.clone(),
+        admin_fee_dest_a_info.clone(),
+        authority_info.clone(),
+        token_program_info.clone(),
+        admin_fee_base,
+        swap_authority_signer_seeds,
+    )?;
+    token_transfer(
+        token_b_info.clone(),
+        dest_token_b_info.clone(),
+        authority_info.clone(),
+        token_program_info.clone(),
+        quote_out_amount,
+        swap_authority_signer_seeds,
+    )?;
+    token_transfer(
+        token_b_info.clone(),
+        admin_fee_dest_b_info.clone(),
+        authority_info.clone(),
+        token_program_info.clone(),
+        admin_fee_quote,
+        swap_authority_signer_seeds,
+    )?;
+    token_burn(
+        pool_mint_info.clone(),
+        source_info.clone(),
+        user_transfer_authority_info.clone(),
+        token_program_info.clone(),
+        pool_token_amount,
+        &[],
+    )?;
+
+    let token_a = unpack_token_account(token_a_info, token_program_info.key)?;
+    let token_b = unpack_token_account(token_b_info, token_program_info.key)?;
+    token_swap
+        .pool_state
+        .check_reserve_amount(token_a.amount, token_b.amount)?;
+    token_swap.pool_state.check_share_price_floor()?;
+    token_swap.update_virtual_price()?;
+
+    let pool_mint = unpack_mint(pool_mint_info, token_program_info.key)?;
+    token_swap.pool_state.check_mint_supply_change(
+        last_total_supply,
+        last_mint_supply,
+        pool_mint.supply,
+    )?;
+
+    LiquidityEvent {
+        event_type: EventType::Withdraw,
+        swap: *swap_info.key,
+        user: *user_transfer_authority_info.key,
+        token_a_amount: base_out_amount,
+        token_b_amount: quote_out_amount,
+        pool_token_amount,
+        fee_a: withdraw_fee_base,
+        fee_b: withdraw_fee_quote,
+        admin_fee_a: admin_fee_base,
+        admin_fee_b: admin_fee_quote,
+        market_price: token_swap.pool_state.market_price,
+    }
+    .emit();
+
+    SwapInfo::pack(token_swap, &mut swap_info.data.borrow_mut())?;
+
+    Ok(())
+}
+
+/// Check the referrer token account is a DELTAFI token account of another user, or the
+/// dummy referrer
+fn validate_referrer_token(
+    config: &ConfigInfo,
+    owner_key: &Pubkey,
+    referrer_token_info: &AccountInfo,
+    token_program_id: &Pubkey,
+) -> ProgramResult {
+    if utils::is_dummy_referrer(referrer_token_info.key) {
+        return Ok(());
+    }
+    let referrer_token = unpack_token_account(referrer_token_info, token_program_id)?;
+    utils::validate(
+        referrer_token.mint == config.deltafi_mint,
+        SwapError::ExpectedMint,
+    )?;
+    // The owner should not set the referrer to self.
+    utils::validate(
+        referrer_token.owner != *owner_key,
+        SwapError::InvalidAccountOwner,
+    )
+}
+
+/// The legacy dummy referrer is stored as the current one
+fn stored_referrer(referrer_token_key: &Pubkey) -> Pubkey {
+    if utils::is_dummy_referrer(referrer_token_key) {
+        DUMMY_REFERRER_ADDRESS
+    } else {
+        *referrer_token_key
+    }
+}
+
+fn process_set_referrer(program_id: &Pubkey, accounts: &[AccountInfo]) -> ProgramResult {
+    let account_info_iter = &mut accounts.iter();
+    let config_info = next_account_info(account_info_iter)?;
+    let owner_info = next_account_info(account_info_iter)?;
+    let user_referrer_info = next_account_info(account_info_iter)?;
+    let referrer_token_info = next_account_info(account_info_iter)?;
+    let rent = &Rent::from_account_info(next_account_info(account_info_iter)?)?;
+    let token_program_info = next_account_info(account_info_iter)?;
+
+    spl_token::check_program_account(token_program_info.key)?;
+
+    utils::validate(
+        user_referrer_info.owner == program_id && config_info.owner == program_id,
+        SwapError::InvalidAccountOwner,
+    )?;
+
+    let expected_user_referrer_data_pubkey =
+        get_referrer_data_pubkey(owner_info.key, config_info.key, program_id)?;
+    utils::validate(
+        expected_user_referrer_data_pubkey == *user_referrer_info.key,
+        SwapError::InvalidAccountOwner,
+    )?;
+
+    let config = ConfigInfo::unpack(&config_info.data.borrow())?;
+
+    // the valid address is either a valid DELFI token accout or the dummy referrer address
+    validate_referrer_token(
+        &config,
+        owner_info.key,
+        referrer_token_info,
+        token_program_info.key,
+    )?;
+
+    assert_rent_exempt(rent, user_referrer_info)?;
+    let mut user_referrer_data = assert_uninitialized::<UserReferrerData>(user_referrer_info)?;
+    if !owner_info.is_signer {
+        return Err(SwapError::InvalidSigner.into());
+    }
+
+    user_referrer_data.is_initialized = true;
+    user_referrer_data.config_key = *config_info.key;
+    user_referrer_data.owner = *owner_info.key;
+    user_referrer_data.referrer = stored_referrer(referrer_token_info.key);
+    user_referrer_data.last_update_slot = Clock::get()?.slot;
+
+    UserReferrerData::pack(
+        user_referrer_data,
+        &mut user_referrer_info.data.borrow_mut(),
+    )?;
+
+    Ok(())
+}
+
+fn process_update_referrer(program_id: &Pubkey, accounts: &[AccountInfo]) -> ProgramResult {
+    let account_info_iter = &mut accounts.iter();
+    let config_info = next_account_info(account_info_iter)?;
+    let owner_info = next_account_info(account_info_iter)?;
+    let user_referrer_info = next_account_info(account_info_iter)?;
+    let referrer_token_info = next_account_info(account_info_iter)?;
+    let token_program_info = next_account_info(account_info_iter)?;
+
+    spl_token::check_program_account(token_program_info.key)?;
+
+    utils::validate(
+        user_referrer_info.owner == program_id && config_info.owner == program_id,
+        SwapError::InvalidAccountOwner,
+    )?;
+    if !owner_info.is_signer {
+        return Err(SwapError::InvalidSigner.into());
+    }
+
+    // The legacy referrer data has no room for the update slot, so it cannot be updated.
+    let mut user_referrer_data = UserReferrerData::unpack(&user_referrer_info.data.borrow())?;
+    utils::validate(
+        user_referrer_data.owner == *owner_info.key
+            && user_referrer_data.config_key == *config_info.key,
+        SwapError::InvalidAccountOwner,
+    )?;
+
+    let config = ConfigInfo::unpack(&config_info.data.borrow())?;
+    validate_referrer_token(
+        &config,
+        owner_info.key,
+        referrer_token_info,
+        token_program_info.key,
+    )?;
+
+    let clock = Clock::get()?;
+    user_referrer_data.check_update_cooldown(clock.slot)?;
+    user_referrer_data.referrer = stored_referrer(referrer_token_info.key);
+    user_referrer_data.last_update_slot = clock.slot;
+
+    UserReferrerData::pack(
+        user_referrer_data,
+        &mut user_referrer_info.data.borrow_mut(),
+    )?;
+
+    Ok(())
+}
+
+fn process_init_referrer_stats(program_id: &Pubkey, accounts: &[AccountInfo]) -> ProgramResult {
+    let account_info_iter = &mut accounts.iter();
+    let config_info = next_account_info(account_info_iter)?;
+    let referrer_token_info = next_account_info(account_info_iter)?;
+    let referrer_stats_info = next_account_info(account_info_iter)?;
+    let payer_info = next_account_info(account_info_iter)?;
+    let rent = &Rent::from_account_info(next_account_info(account_info_iter)?)?;
+    let system_program_info = next_account_info(account_info_iter)?;
+    let token_program_info = next_account_info(account_info_iter)?;
+
+    spl_token::check_program_account(token_program_info.key)?;
+    if config_info.owner != program_id {
+        return Err(ProgramError::IncorrectProgramId);
+    }
+    if !payer_info.is_signer {
+        return Err(ProgramError::MissingRequiredSignature);
+    }
+    let config = ConfigInfo::unpack(&config_info.data.borrow())?;
+    let referrer_token = unpack_token_account(referrer_token_info, token_program_info.key)?;
+    utils::validate(
+        referrer_token.mint == config.deltafi_mint,
+        SwapError::ExpectedMint,
+    )?;
+
+    let (referrer_stats_key, bump_seed) =
+        find_referrer_stats_address(config_info.key, referrer_token_info.key, program_id);
+    utils::validate(
+        *referrer_stats_info.key == referrer_stats_key,
+        SwapError::InvalidReferrerStats,
+    )?;
+    utils::validate(
+        system_program::check_id(system_program_info.key),
+        SwapError::InvalidInput,
+    )?;
+    create_program_address_account(
+        payer_info,
+        referrer_stats_info,
+        ReferrerStats::LEN,
+        program_id,
+        rent,
+        system_program_info,
+        &[
+            SEED_REFERRER_STATS.as_bytes(),
+            config_info.key.as_ref(),
+            referrer_token_info.key.as_ref(),
+            &[bump_seed],
+        ],
+    )?;
+
+    ReferrerStats::pack(
+        ReferrerStats {
+            is_initialized: true,
+            bump_seed,
+            config_key: *config_info.key,
+            referrer: *referrer_token_info.key,
+            ..ReferrerStats::default()
+        },
+        &mut referrer_stats_info.data.borrow_mut(),
+    )
+}
+
+/// Trade the drifted reserves of a normal pool back to the targets at the oracle price
+#[inline(never)]
+fn process_rebalance(
+    program_id: &Pubkey,
+    max_amount_in: u64,
+    minimum_amount_out: u64,
+    accounts: &[AccountInfo],
+) -> ProgramResult {
+    let account_info_iter = &mut accounts.iter();
+    let config_info = next_account_info(account_info_iter)?;
+    let swap_info = next_account_info(account_info_iter)?;
+    let swap_authority_info = next_account_info(account_info_iter)?;
+    let user_transfer_authority_info = next_account_info(account_info_iter)?;
+    let source_info = next_account_info(account_info_iter)?;
+    let destination_info = next_account_info(account_info_iter)?;
+    let token_a_info = next_account_info(account_info_iter)?;
+    let token_b_info = next_account_info(account_info_iter)?;
+
+    if swap_info.owner != program_id || config_info.owner != program_id {
+        return Err(ProgramError::IncorrectProgramId);
+    }
+
+    let mut token_swap = SwapInfo::unpack(&swap_info.data.borrow())?;
+    utils::validate_swap_config_key(&token_swap, config_info.key)?;
+    utils::validate(
+        token_swap.swap_type == SwapType::Normal,
+        SwapError::IncorrectSwapType,
+    )?;
+    if token_swap.is_paused || token_swap.pause_swap {
+        return Err(SwapError::IsPaused.into());
+    }
+    if token_swap.whitelist_enabled {
+        return Err(SwapError::NotWhitelisted.into());
+    }
+    if token_swap.rebalance_threshold_bps == 0 {
+        return Err(SwapError::RebalanceNotNeeded.into());
+    }
+
+    let orderbook_program_id =
+        ConfigInfo::unpack(&config_info.data.borrow())?.accepted_orderbook_program_id();
+    update_pool_market_price(
+        &mut token_swap,
+        SwapType::Normal,
+        &orderbook_program_id,
+        account_info_iter,
+    )?;
+
+    let token_program_info = next_account_info(account_info_iter)?;
+    spl_token::check_program_account(token_program_info.key)?;
+
+    let swap_authority_signer_seeds = &[swap_info.key.as_ref(), &[token_swap.nonce]];
+    if *swap_authority_info.key
+        != Pubkey::create_program_address(swap_authority_signer_seeds, program_id)?
+    {
+        return Err(SwapError::InvalidProgramAddress.into());
+    }
+    if *token_a_info.key != token_swap.token_a || *token_b_info.key != token_swap.token_b {
+        return Err(SwapError::IncorrectSwapAccount.into());
+    }
+    if source_info.key == token_a_info.key
+        || source_info.key == token_b_info.key
+        || destination_info.key == token_a_info.key
+        || destination_info.key == token_b_info.key
+    {
+        return Err(SwapError::InvalidInput.into());
+    }
+
+    let pool_state = &token_swap.pool_state;
+    let deviation = pool_state.market_price_deviation(pool_state.mid_price()?)?;
+    if deviation < Decimal::from(token_swap.rebalance_threshold_bps as u64).try_div(10_000u64)? {
+        return Err(SwapError::RebalanceNotNeeded.into());
+    }
+
+    let (swap_direction, amount_in, amount_out) =
+        pool_state.rebalance_amounts(max_amount_in, REBALANCE_BOUNTY_BPS)?;
+    if amount_out < minimum_amount_out {
+        return Err(SwapError::ExceededSlippage.into());
+    }
+    let (swap_source_info, swap_destination_info) = match swap_direction {
+        SwapDirection::SellBase => (token_a_info, token_b_info),
+        SwapDirection::SellQuote => (token_b_info, token_a_info),
+    };
+
+    token_swap
+        .pool_state
+        .swap(amount_in, amount_out, swap_direction)?;
+
+    token_transfer(
+        source_info.clone(),
+        swap_source_info.clone(),
+        user_transfer_authority_info.clone(),
+        token_program_info.clone(),
+        amount_in,
+        &[],
+    )?;
+    token_transfer(
+        swap_destination_info.clone(),
+        destination_info.clone(),
+        swap_authority_info.clone(),
+        token_program_info.clone(),
+        amount_out,
+        swap_authority_signer_seeds,
+    )?;
+
+    let token_a = unpack_token_account(token_a_info, token_program_info.key)?;
+    let token_b = unpack_token_account(token_b_info, token_program_info.key)?;
+    token_swap
+        .pool_state
+        .check_reserve_amount(token_a.amount, token_b.amount)?;
+
+    token_swap.record_swap(amount_in, amount_out, 0, swap_direction)?;
+
+    SwapEvent {
+        swap: *swap_info.key,
+        user: *user_transfer_authority_info.key,
+        direction: swap_direction,
+        amount_in,
+        amount_out,
+        trade_fee: 0,
+        admin_fee: 0,
+        market_price: token_swap.pool_state.market_price,
+    }
+    .emit();
+
+    SwapInfo::pack(token_swap, &mut swap_info.data.borrow_mut())?;
+
+    Ok(())
+}
+
+fn process_post_quote(
+    program_id: &Pubkey,
+    swap_direction: u8,
+    price: u128,
+    max_amount_in: u64,
+    expiry_slot: u64,
+    accounts: &[AccountInfo],
+) -> ProgramResult {
+    let account_info_iter = &mut accounts.iter();
+    let config_info = next_account_info(account_info_iter)?;
+    let swap_info = next_account_info(account_info_iter)?;
+    let quote_info = next_account_info(account_info_iter)?;
+    let market_maker_info = next_account_info(account_info_iter)?;
+    let market_maker_token_a_info = next_account_info(account_info_iter)?;
+    let market_maker_token_b_info = next_account_info(account_info_iter)?;
+    let clock = &Clock::from_account_info(next_account_info(account_info_iter)?)?;
+    let rent = &Rent::from_account_info(next_account_info(account_info_iter)?)?;
+    let token_program_info = next_account_info(account_info_iter)?;
+
+    spl_token::check_program_account(token_program_info.key)?;
+
+    utils::validate(
+        config_info.owner == program_id
+            && swap_info.owner == program_id
+            && quote_info.owner == program_id,
+        SwapError::InvalidAccountOwner,
+    )?;
+    if !market_maker_info.is_signer {
+        return Err(SwapError::InvalidSigner.into());
+    }
+
+    let config = ConfigInfo::unpack(&config_info.data.borrow())?;
+    utils::validate(
+        config.is_market_maker(market_maker_info.key),
+        SwapError::InvalidMarketMaker,
+    )?;
+
+    let token_swap = SwapInfo::unpack(&swap_info.data.borrow())?;
+    utils::validate_swap_config_key(&token_swap, config_info.key)?;
+    if token_swap.is_paused || token_swap.pause_swap {
+        return Err(SwapError::IsPaused.into());
+    }
+
+    // The quote address is derived from the market maker and the pool,
+    // so a quote can only be replaced by the market maker who posted it
+    let expected_quote_pubkey = get_quote_pubkey(market_maker_info.key, swap_info.key, program_id)?;
+    utils::validate(
+        expected_quote_pubkey == *quote_info.key,
+        SwapError::InvalidAccountOwner,
+    )?;
+    assert_rent_exempt(rent, quote_info)?;
+
+    let token_program_id = *token_program_info.key;
+    let market_maker_token_a = unpack_token_account(market_maker_token_a_info, &token_program_id)?;
+    let market_maker_token_b = unpack_token_account(market_maker_token_b_info, &token_program_id)?;
+    utils::validate(
+        market_maker_token_a.mint == token_swap.token_a_mint
+            && market_maker_token_b.mint == token_swap.token_b_mint,
+        SwapError::IncorrectMint,
+    )?;
+    utils::validate(
+        market_maker_token_a.owner == *market_maker_info.key
+            && market_maker_token_b.owner == *market_maker_info.key,
+        SwapError::InvalidOwner,
+    )?;
+
+    let swap_direction =
+        SwapDirection::try_from(swap_direction).map_err(|_| SwapError::InvalidInput)?;
+    // Zero amount cancels the quote, the price and expiry are not used then
+    if max_amount_in > 0 {
+        utils::validate(price > 0, SwapError::InvalidInput)?;
+        if expiry_slot < clock.slot {
+            return Err(SwapError::QuoteExpired.into());
+        }
+        let max_expiry_slot = clock
+            .slot
+            .checked_add(MAX_QUOTE_DURATION_SLOTS)
+            .ok_or(SwapError::CalculationFailure)?;
+        utils::validate(expiry_slot <= max_expiry_slot, SwapError::InvalidInput)?;
+    }
+
+    let mut quote = MarketMakerQuote::unpack_unchecked(&quote_info.data.borrow())?;
+    quote.is_initialized = true;
+    quote.config_key = *config_info.key;
+    quote.swap_key = *swap_info.key;
+    quote.market_maker = *market_maker_info.key;
+    quote.swap_direction = swap_direction;
+    quote.price = Decimal::from_scaled_val(price);
+    quote.amount_in_remaining = max_amount_in;
+    quote.expiry_slot = expiry_slot;
+    quote.market_maker_token_a = *market_maker_token_a_info.key;
+    quote.market_maker_token_b = *market_maker_token_b_info.key;
+
+    MarketMakerQuote::pack(quote, &mut quote_info.data.borrow_mut())?;
+
+    Ok(())
+}
+
+fn process_fill_quote(
+    program_id: &Pubkey,
+    amount_in: u64,
+    minimum_amount_out: u64,
+    accounts: &[AccountInfo],
+) -> ProgramResult {
+    let account_info_iter = &mut accounts.iter();
+    let config_info = next_account_info(account_info_iter)?;
+    let swap_info = next_account_info(account_info_iter)?;
+    let market_authority_info = next_account_info(account_info_iter)?;
+    let swap_authority_info = next_account_info(account_info_iter)?;
+    let quote_info = next_account_info(account_info_iter)?;
+    let user_transfer_authority_info = next_account_info(account_info_iter)?;
+    let source_info = next_account_info(account_info_iter)?;
+    let destination_info = next_account_info(account_info_iter)?;
+    let market_maker_source_info = next_account_info(account_info_iter)?;
+    let market_maker_destination_info = next_account_info(account_info_iter)?;
+    let admin_destination_info = next_account_info(account_info_iter)?;
+    let reward_token_info = next_account_info(account_info_iter)?;
+    let source_reward_token_info = next_account_info(account_info_iter)?;
+    let clock = &Clock::from_account_info(next_account_info(account_info_iter)?)?;
+    let token_program_info = next_account_info(account_info_iter)?;
+
+    spl_token::check_program_account(token_program_info.key)?;
+
+    if swap_info.owner != program_id
+        || config_info.owner != program_id
+        || quote_info.owner != program_id
+    {
+        return Err(ProgramError::IncorrectProgramId);
+    }
+
+    let config = ConfigInfo::unpack(&config_info.data.borrow())?;
+    let token_swap = SwapInfo::unpack(&swap_info.data.borrow())?;
+
+    utils::validate_swap_config_key(&token_swap, config_info.key)?;
+    if token_swap.is_paused || token_swap.pause_swap {
+        return Err(SwapError::IsPaused.into());
+    }
+    if token_swap.whitelist_enabled {
+        return Err(SwapError::NotWhitelisted.into());
+    }
+    let swap_authority_signer_seeds = &[swap_info.key.as_ref(), &[token_swap.nonce]];
+    if *swap_authority_info.key
+        != Pubkey::create_program_address(swap_authority_signer_seeds, program_id)?
+    {
+        return Err(SwapError::InvalidProgramAddress.into());
+    }
+    let market_authority_signer_seeds = &[config_info.key.as_ref(), &[config.bump_seed]];
+    if *market_authority_info.key
+        != Pubkey::create_program_address(market_authority_signer_seeds, program_id)?
+    {
+        return Err(SwapError::InvalidProgramAddress.into());
+    }
+
+    let mut quote = MarketMakerQuote::unpack(&quote_info.data.borrow())?;
+    utils::validate(
+        quote.swap_key == *swap_info.key && quote.config_key == *config_info.key,
+        SwapError::InvalidInput,
+    )?;
+    // A market maker removed from the allowlist cannot be filled anymore
+    utils::validate(
+        config.is_market_maker(&quote.market_maker),
+        SwapError::InvalidMarketMaker,
+    )?;
+    if clock.slot > quote.expiry_slot {
+        return Err(SwapError::QuoteExpired.into());
+    }
+    if amount_in > quote.amount_in_remaining {
+        return Err(SwapError::ExceededQuoteAmount.into());
+    }
+    if source_info.key == destination_info.key {
+        return Err(SwapError::InvalidInput.into());
+    }
+    if token_swap.strict_destination_owner {
+        let destination_token = unpack_token_account(destination_info, token_program_info.key)?;
+        utils::validate_destination_owner(&destination_token.owner, accounts)?;
+    }
+
+    let swap_direction = quote.swap_direction;
+    let (market_maker_source_key, market_maker_destination_key, admin_fee_key) =
+        match swap_direction {
+            SwapDirection::SellBase => (
+                quote.market_maker_token_a,
+                quote.market_maker_token_b,
+                token_swap.admin_fee_key_b,
+            ),
+            SwapDirection::SellQuote => (
+                quote.market_maker_token_b,
+                quote.market_maker_token_a,
+                token_swap.admin_fee_key_a,
+            ),
+        };
+    utils::validate(
+        *market_maker_source_info.key == market_maker_source_key
+            && *market_maker_destination_info.key == market_maker_destination_key,
+        SwapError::IncorrectSwapAccount,
+    )?;
+    utils::validate(
+        *admin_destination_info.key == admin_fee_key,
+        SwapError::InvalidAdmin,
+    )?;
+
+    let token_program_id = *token_program_info.key;
+    let reward_token = unpack_token_account(reward_token_info, &token_program_id)?;
+    let source_reward_token = unpack_token_account(source_reward_token_info, &token_program_id)?;
+    validate_reward_token_accounts(
+        &config,
+        market_authority_info.key,
+        &source_reward_token,
+        &reward_token,
+    )?;
+
+    let receive_amount = quote.get_out_amount(
+        amount_in,
+        token_swap.token_a_decimals,
+        token_swap.token_b_decimals,
+    )?;
+    // Quotes are only filled when they beat the pool curve for the same trade
+    let pool_receive_amount = token_swap
+        .pool_state
+        .get_out_amount(amount_in, swap_direction)
+        .unwrap_or(0);
+    if receive_amount <= pool_receive_amount {
+        return Err(SwapError::QuoteWorseThanPool.into());
+    }
+
+    let fees = &token_swap.fees;
+    let trade_fee = fees.trade_fee(receive_amount)?;
+    let admin_fee = fees.admin_trade_fee(trade_fee)?;
+    let rewards = &token_swap.rewards;
+    let amount_out = receive_amount
+        .checked_sub(trade_fee)
+        .ok_or(SwapError::CalculationFailure)?;
+    if amount_out < minimum_amount_out {
+        return Err(SwapError::ExceededSlippage.into());
+    }
+
+    let amount_to_reward =
+        rewards.trade_reward_u64(if swap_direction == SwapDirection::SellBase {
+            amount_in
+        } else {
+            amount_out
+        })?;
+
+    quote.amount_in_remaining = quote
+        .amount_in_remaining
+        .checked_sub(amount_in)
+        .ok_or(SwapError::CalculationFailure)?;
+
+    token_transfer(
+        source_info.clone(),
+        market_maker_source_info.clone(),
+        user_transfer_authority_info.clone(),
+        token_program_info.clone(),
+        amount_in,
+        &[],
+    )?;
+    // The market maker inventory is moved by $swap_authority as its delegate.
+    // The trade fee left after the admin fee stays with the market maker as the rebate.
+    token_transfer(
+        market_maker_destination_info.clone(),
+        destination_info.clone(),
+        swap_authority_info.clone(),
+        token_program_info.clone(),
+        amount_out,
+        swap_authority_signer_seeds,
+    )?;
+    token_transfer(
+        market_maker_destination_info.clone(),
+        admin_destination_info.clone(),
+        swap_authority_info.clone(),
+        token_program_info.clone(),
+        admin_fee,
+        swap_authority_signer_seeds,
+    )?;
+    token_transfer(
+        source_reward_token_info.clone(),
+        reward_token_info.clone(),
+        market_authority_info.clone(),
+        token_program_info.clone(),
+        amount_to_reward,
+        market_authority_signer_seeds,
+    )?;
+
+    MarketMakerQuote::pack(quote, &mut quote_info.data.borrow_mut())?;
+
+    Ok(())
+}
+
+/// Reject the instruction when the current slot is past its deadline slot
+fn check_deadline(deadline_slot: Option<u64>) -> ProgramResult {
+    if let Some(deadline_slot) = deadline_slot {
+        if Clock::get()?.slot > deadline_slot {
+            return Err(SwapError::TransactionExpired.into());
+        }
+    }
+    Ok(())
+}
+
+fn process_flash_swap(
+    program_id: &Pubkey,
+    amount_a: u64,
+    amount_b: u64,
+    callback_data: Vec<u8>,
+    accounts: &[AccountInfo],
+) -> ProgramResult {
+    let account_info_iter = &mut accounts.iter();
+    let config_info = next_account_info(account_info_iter)?;
+    let swap_info = next_account_info(account_info_iter)?;
+    let swap_authority_info = next_account_info(account_info_iter)?;
+    let token_a_info = next_account_info(account_info_iter)?;
+    let token_b_info = next_account_info(account_info_iter)?;
+    let destination_a_info = next_account_info(account_info_iter)?;
+    let destination_b_info = next_account_info(account_info_iter)?;
+    let admin_fee_a_info = next_account_info(account_info_iter)?;
+    let admin_fee_b_info = next_account_info(account_info_iter)?;
+    let token_program_info = next_account_info(account_info_iter)?;
+
+    spl_token::check_program_account(token_program_info.key)?;
+
+    if swap_info.owner != program_id || config_info.owner != program_id {
+        return Err(ProgramError::IncorrectProgramId);
+    }
+
+    let mut token_swap = SwapInfo::unpack(&swap_info.data.borrow())?;
+    utils::validate_swap_config_key(&token_swap, config_info.key)?;
+    if token_swap.is_paused || token_swap.pause_swap {
+        return Err(SwapError::IsPaused.into());
+    }
+    if token_swap.whitelist_enabled {
+        return Err(SwapError::NotWhitelisted.into());
+    }
+    let swap_authority_signer_seeds = &[swap_info.key.as_ref(), &[token_swap.nonce]];
+    if *swap_authority_info.key
+        != Pubkey::create_program_address(swap_authority_signer_seeds, program_id)?
+    {
+        return Err(SwapError::InvalidProgramAddress.into());
+    }
+    if *token_a_info.key != token_swap.token_a || *token_b_info.key != token_swap.token_b {
+        return Err(SwapError::IncorrectSwapAccount.into());
+    }
+    if *admin_fee_a_info.key != token_swap.admin_fee_key_a
+        || *admin_fee_b_info.key != token_swap.admin_fee_key_b
+    {
+        return Err(SwapError::InvalidAdmin.into());
+    }
+    if destination_a_info.key == token_a_info.key || destination_b_info.key == token_b_info.key {
+        return Err(SwapError::InvalidInput.into());
+    }
+    if amount_a == 0 && amount_b == 0 {
+        return Err(SwapError::InvalidInput.into());
+    }
+
+    let token_program_id = *token_program_info.key;
+    let token_a_amount = unpack_token_account(token_a_info, &token_program_id)?.amount;
+    let token_b_amount = unpack_token_account(token_b_info, &token_program_id)?.amount;
+    if amount_a >= token_a_amount || amount_b >= token_b_amount {
+        return Err(SwapError::InsufficientFunds.into());
+    }
+
+    let flash_fee_a = token_swap.fees.flash_fee(amount_a)?;
+    let flash_fee_b = token_swap.fees.flash_fee(amount_b)?;
+    let config = ConfigInfo::unpack(&config_info.data.borrow())?;
+    let (treasury_fee_a, treasury_token_a_info) = next_treasury_fee(
+        &config,
+        &token_swap.fees,
+        flash_fee_a,
+        &token_swap.token_a_mint,
+        &token_program_id,
+        account_info_iter,
+    )?;
+    let (treasury_fee_b, treasury_token_b_info) = next_treasury_fee(
+        &config,
+        &token_swap.fees,
+        flash_fee_b,
+        &token_swap.token_b_mint,
+        &token_program_id,
+        account_info_iter,
+    )?;
+    let callback_program_info = next_account_info(account_info_iter)?;
+    if !callback_program_info.executable {
+        return Err(SwapError::InvalidInput.into());
+    }
+    let callback_account_infos: Vec<AccountInfo> = account_info_iter.cloned().collect();
+
+    if amount_a > 0 {
+        token_transfer(
+            token_a_info.clone(),
+            destination_a_info.clone(),
+            swap_authority_info.clone(),
+            token_program_info.clone(),
+            amount_a,
+            swap_authority_signer_seeds,
+        )?;
+    }
+    if amount_b > 0 {
+        token_transfer(
+            token_b_info.clone(),
+            destination_b_info.clone(),
+            swap_authority_info.clone(),
+            token_program_info.clone(),
+            amount_b,
+            swap_authority_signer_seeds,
+        )?;
+    }
+
+    // Lock the pool while the callback runs so it cannot trade against the lent reserves
+    token_swap.is_locked = true;
+    SwapInfo::pack(token_swap.clone(), &mut swap_info.data.borrow_mut())?;
+
+    let callback_instruction = Instruction {
+        program_id: *callback_program_info.key,
+        accounts: callback_account_infos
+            .iter()
+            .map(|account_info| AccountMeta {
+                pubkey: *account_info.key,
+                is_signer: account_info.is_signer,
+                is_writable: account_info.is_writable,
+            })
+            .collect(),
+        data: callback_data,
+    };
+    let mut callback_invoke_infos = callback_account_infos;
+    callback_invoke_infos.push(callback_program_info.clone());
+    invoke(&callback_instruction, &callback_invoke_infos)?;
+
+    let repaid_a_amount = unpack_token_account(token_a_info, &token_program_id)?.amount;
+    let repaid_b_amount = unpack_token_account(token_b_info, &token_program_id)?.amount;
+    if repaid_a_amount
+        < token_a_amount
+            .checked_add(flash_fee_a)
+            .ok_or(SwapError::CalculationFailure)?
+        || repaid_b_amount
+            < token_b_amount
+                .checked_add(flash_fee_b)
+                .ok_or(SwapError::CalculationFailure)?
+    {
+        return Err(SwapError::InsufficientFlashRepayment.into());
+    }
+
+    let admin_fee_a = token_swap.fees.admin_trade_fee(flash_fee_a)?;
+    let admin_fee_b = token_swap.fees.admin_trade_fee(flash_fee_b)?;
+    token_transfer(
+        token_a_info.clone(),
+        admin_fee_a_info.clone(),
+        swap_authority_info.clone(),
+        token_program_info.clone(),
+        admin_fee_a,
+        swap_authority_signer_seeds,
+    )?;
+    token_transfer(
+        token_b_info.clone(),
+        admin_fee_b_info.clone(),
+        swap_authority_info.clone(),
+        token_program_info.clone(),
+        admin_fee_b,
+        swap_authority_signer_seeds,
+    )?;
+
+    for (token_info, treasury_token_info, treasury_fee) in [
+        (token_a_info, treasury_token_a_info, treasury_fee_a),
+        (token_b_info, treasury_token_b_info, treasury_fee_b),
+    ]
+    .iter()
+    {
+        if let Some(treasury_token_info) = treasury_token_info {
+            token_transfer(
+                (*token_info).clone(),
+                (*treasury_token_info).clone(),
+                swap_authority_info.clone(),
+                token_program_info.clone(),
+                *treasury_fee,
+                swap_authority_signer_seeds,
+            )?;
+        }
+    }
+
+    // The flash fee left after the admin and treasury fees goes to the liquidity providers
+    token_swap.pool_state.collect_trade_fee(
+        flash_fee_a
+            .checked_sub(admin_fee_a)
+            .and_then(|fee| fee.checked_sub(treasury_fee_a))
+            .ok_or(SwapError::CalculationFailure)?,
+        flash_fee_b
+            .checked_sub(admin_fee_b)
+            .and_then(|fee| fee.checked_sub(treasury_fee_b))
+            .ok_or(SwapError::CalculationFailure)?,
+    )?;
+    token_swap.pool_state.check_reserve_amount(
+        unpack_token_account(token_a_info, &token_program_id)?.amount,
+        unpack_token_account(token_b_info, &token_program_id)?.amount,
+    )?;
+    token_swap.pool_state.check_share_price_floor()?;
+    token_swap.update_virtual_price()?;
+
+    token_swap.is_locked = false;
+    SwapInfo::pack(token_swap, &mut swap_info.data.borrow_mut())?;
+
+    Ok(())
+}
+
+fn process_get_quote(
+    program_id: &Pubkey,
+    amount_in: u64,
+    swap_direction: u8,
+    accounts: &[AccountInfo],
+) -> ProgramResult {
+    let account_info_iter = &mut accounts.iter();
+    let config_info = next_account_info(account_info_iter)?;
+    let swap_info = next_account_info(account_info_iter)?;
+
+    if swap_info.owner != program_id || config_info.owner != program_id {
+        return Err(ProgramError::IncorrectProgramId);
+    }
+
+    let mut token_swap = SwapInfo::unpack(&swap_info.data.borrow())?;
+    utils::validate_swap_config_key(&token_swap, config_info.key)?;
+    if token_swap.is_paused || token_swap.pause_swap {
+        return Err(SwapError::IsPaused.into());
+    }
+    let swap_direction = SwapDirection::try_from(swap_direction)?;
+
+    let orderbook_program_id =
+        ConfigInfo::unpack(&config_info.data.borrow())?.accepted_orderbook_program_id();
+    let swap_type = token_swap.swap_type;
+    update_pool_market_price(
+        &mut token_swap,
+        swap_type,
+        &orderbook_program_id,
+        account_info_iter,
+    )?;
+
+    // Same pricing as the swap instructions, nothing is written back.
+    let quote = quote::quote_swap(&token_swap, amount_in, swap_direction)?;
+    set_return_data(&quote.pack());
+
+    Ok(())
+}
+
+fn process_update_price_cache(program_id: &Pubkey, accounts: &[AccountInfo]) -> ProgramResult {
+    let account_info_iter = &mut accounts.iter();
+    let swap_info = next_account_info(account_info_iter)?;
+    let price_cache_info = next_account_info(account_info_iter)?;
+    let relayer_info = next_account_info(account_info_iter)?;
+    let pyth_a_price_info = next_account_info(account_info_iter)?;
+    let pyth_b_price_info = next_account_info(account_info_iter)?;
+    let clock = &Clock::get()?;
+
+    if swap_info.owner != program_id || price_cache_info.owner != program_id {
+        return Err(ProgramError::IncorrectProgramId);
+    }
+
+    let token_swap = SwapInfo::unpack(&swap_info.data.borrow())?;
+    utils::validate(
+        token_swap.swap_type == SwapType::Normal,
+        SwapError::IncorrectSwapType,
+    )?;
+
+    let mut price_cache = PriceCache::unpack(&price_cache_info.data.borrow())?;
+    utils::validate(
+        price_cache.swap_key == *swap_info.key && price_cache.relayer == *relayer_info.key,
+        SwapError::InvalidPriceCache,
+    )?;
+    if !relayer_info.is_signer {
+        return Err(ProgramError::MissingRequiredSignature);
+    }
+    if token_swap.pyth_a != *pyth_a_price_info.key || token_swap.pyth_b != *pyth_b_price_info.key {
+        return Err(SwapError::InvalidInput.into());
+    }
+
+    let (market_price, valid_slot) = get_market_price_from_pyth(
+        pyth_a_price_info,
+        pyth_b_price_info,
+        clock,
+        &token_swap.oracle_params,
+    )?;
+
+    price_cache.market_price = market_price;
+    price_cache.valid_slot = valid_slot;
+    price_cache.pyth_a = *pyth_a_price_info.key;
+    price_cache.pyth_b = *pyth_b_price_info.key;
+    price_cache.updated_by = *relayer_info.key;
+    price_cache.update_slot = clock.slot;
+    PriceCache::pack(price_cache, &mut price_cache_info.data.borrow_mut())?;
+
+    Ok(())
+}
+
+/// Push the market price of a MANUAL oracle pool, signed by the price setter of the pool
+fn process_set_manual_price(
+    program_id: &Pubkey,
+    market_price: u128,
+    accounts: &[AccountInfo],
+) -> ProgramResult {
+    let account_info_iter = &mut accounts.iter();
+    let swap_info = next_account_info(account_info_iter)?;
+    let price_setter_info = next_account_info(account_info_iter)?;
+    let clock = &Clock::get()?;
+
+    if swap_info.owner != program_id {
+        return Err(ProgramError::IncorrectProgramId);
+    }
+
+    let mut token_swap = SwapInfo::unpack(&swap_info.data.borrow())?;
+    utils::validate(
+        token_swap.swap_type == SwapType::Normal,
+        SwapError::IncorrectSwapType,
+    )?;
+    utils::validate(
+        OraclePriorityFlag::from_bits_truncate(token_swap.oracle_priority_flags).is_manual(),
+        SwapError::UnsupportedOraclePriority,
+    )?;
+    utils::validate(
+        token_swap.price_setter != Pubkey::default()
+            && token_swap.price_setter == *price_setter_info.key,
+        SwapError::InvalidSigner,
+    )?;
+    if !price_setter_info.is_signer {
+        return Err(ProgramError::MissingRequiredSignature);
+    }
+    utils::validate(market_price > 0, SwapError::InvalidInput)?;
+
+    // The price is quoted in the conventional order of the pair like the initial mid price
+    let market_price = if token_swap.quote_is_token_a {
+        Decimal::one().try_div(Decimal::from_scaled_val(market_price))?
+    } else {
+        Decimal::from_scaled_val(market_price)
+    };
+    token_swap.check_manual_price_change(market_price)?;
+
+    token_swap.manual_price = market_price;
+    token_swap.manual_price_slot = clock.slot;
+    SwapInfo::pack(token_swap, &mut swap_info.data.borrow_mut())?;
+
+    Ok(())
+}
+
+/// Credit the tokens sent directly to the pool token accounts to the protocol fees of the pool
+fn process_sync(program_id: &Pubkey, accounts: &[AccountInfo]) -> ProgramResult {
+    let account_info_iter = &mut accounts.iter();
+    let swap_info = next_account_info(account_info_iter)?;
+    let token_a_info = next_account_info(account_info_iter)?;
+    let token_b_info = next_account_info(account_info_iter)?;
+    let token_program_info = next_account_info(account_info_iter)?;
+
+    if swap_info.owner != program_id {
+        return Err(ProgramError::IncorrectProgramId);
+    }
+    spl_token::check_program_account(token_program_info.key)?;
+
+    let mut token_swap = SwapInfo::unpack(&swap_info.data.borrow())?;
+    if token_swap.is_paused {
+        return Err(SwapError::IsPaused.into());
+    }
+    if *token_a_info.key != token_swap.token_a || *token_b_info.key != token_swap.token_b {
+        return Err(SwapError::IncorrectSwapAccount.into());
+    }
+
+    token_swap.pool_state.sync_reserve_amount(
+        token_account_amount(token_a_info, token_program_info.key)?,
+        token_account_amount(token_b_info, token_program_info.key)?,
+    )?;
+    SwapInfo::pack(token_swap, &mut swap_info.data.borrow_mut())?;
+
+    Ok(())
+}
+
+/// Refresh the market price of a quiet normal pool from its oracle, paying the caller a
+/// DELTAFI reward for a newer oracle price
+fn process_refresh_price(program_id: &Pubkey, accounts: &[AccountInfo]) -> ProgramResult {
+    let account_info_iter = &mut accounts.iter();
+    let config_info = next_account_info(account_info_iter)?;
+    let swap_info = next_account_info(account_info_iter)?;
+    let market_authority_info = next_account_info(account_info_iter)?;
+    let reward_token_info = next_account_info(account_info_iter)?;
+    let source_reward_token_info = next_account_info(account_info_iter)?;
+    let clock = &Clock::get()?;
+
+    if swap_info.owner != program_id || config_info.owner != program_id {
+        return Err(ProgramError::IncorrectProgramId);
+    }
+
+    let config = ConfigInfo::unpack(&config_info.data.borrow())?;
+    let mut token_swap = SwapInfo::unpack(&swap_info.data.borrow())?;
+    utils::validate_swap_config_key(&token_swap, config_info.key)?;
+    utils::validate(
+        token_swap.swap_type == SwapType::Normal,
+        SwapError::IncorrectSwapType,
+    )?;
+    if token_swap.is_paused || token_swap.pause_swap {
+        return Err(SwapError::IsPaused.into());
+    }
+    if !token_swap.pool_state.needs_price_refresh(clock.slot) {
+        return Err(SwapError::PriceRefreshNotNeeded.into());
+    }
+
+    // The reward is only paid for a newer oracle price, so the crank can't be repeated on the
+    // same price
+    let last_valid_market_price_slot = token_swap.pool_state.last_valid_market_price_slot;
+    update_pool_market_price(
+        &mut token_swap,
+        SwapType::Normal,
+        &config.accepted_orderbook_program_id(),
+        account_info_iter,
+    )?;
+    if token_swap.pool_state.last_valid_market_price_slot <= last_valid_market_price_slot {
+        return Err(SwapError::PriceRefreshNotNeeded.into());
+    }
+
+    let token_program_info = next_account_info(account_info_iter)?;
+    spl_token::check_program_account(token_program_info.key)?;
+
+    let market_authority_signer_seeds = &[config_info.key.as_ref(), &[config.bump_seed]];
+    if *market_authority_info.key
+        != Pubkey::create_program_address(market_authority_signer_seeds, program_id)?
+    {
+        return Err(SwapError::InvalidProgramAddress.into());
+    }
+    let reward_token = unpack_token_account(reward_token_info, token_program_info.key)?;
+    let source_reward_token =
+        unpack_token_account(source_reward_token_info, token_program_info.key)?;
+    validate_reward_token_accounts(
+        &config,
+        market_authority_info.key,
+        &source_reward_token,
+        &reward_token,
+    )?;
+    token_transfer(
+        source_reward_token_info.clone(),
+        reward_token_info.clone(),
+        market_authority_info.clone(),
+        token_program_info.clone(),
+        REFRESH_PRICE_REWARD,
+        market_authority_signer_seeds,
+    )?;
+
+    SwapInfo::pack(token_swap, &mut swap_info.data.borrow_mut())?;
+
+    Ok(())
+}
+
+/// Skim the surplus of both pool token accounts over the reserves and fees, on the pools
+/// skimming the tokens sent directly to them. The DESTINATION surplus is transferred to the
+/// admin fee account of the token, the SOURCE surplus, whose admin fee account is not passed to
+/// the swap, is credited to the protocol fees of the pool.
+fn skim_pool_surplus<'a>(
+    token_swap: &mut SwapInfo,
+    swap_direction: SwapDirection,
+    swap_source_info: &AccountInfo<'a>,
+    swap_destination_info: &AccountInfo<'a>,
+    admin_destination_info: &AccountInfo<'a>,
+    swap_authority_info: &AccountInfo<'a>,
+    token_program_info: &AccountInfo<'a>,
+    swap_authority_signer_seeds: &[&[u8]],
+) -> ProgramResult {
+    if !token_swap.skim_surplus {
+        return Ok(());
+    }
+    let swap_source_amount = token_account_amount(swap_source_info, token_program_info.key)?;
+    let swap_destination_amount =
+        token_account_amount(swap_destination_info, token_program_info.key)?;
+    let (base_surplus, quote_surplus) = match swap_direction {
+        SwapDirection::SellBase => token_swap
+            .pool_state
+            .surplus_amounts(swap_source_amount, swap_destination_amount)?,
+        SwapDirection::SellQuote => token_swap
+            .pool_state
+            .surplus_amounts(swap_destination_amount, swap_source_amount)?,
+    };
+    // Only the SOURCE surplus is left for the protocol fees once the DESTINATION one is out
+    let surplus = match swap_direction {
+        SwapDirection::SellBase => {
+            token_swap.pool_state.sync_reserve_amount(
+                swap_source_amount,
+                swap_destination_amount
+                    .checked_sub(quote_surplus)
+                    .ok_or(SwapError::CalculationFailure)?,
+            )?;
+            quote_surplus
+        }
+        SwapDirection::SellQuote => {
+            token_swap.pool_state.sync_reserve_amount(
+                swap_destination_amount
+                    .checked_sub(base_surplus)
+                    .ok_or(SwapError::CalculationFailure)?,
+                swap_source_amount,
+            )?;
+            base_surplus
+        }
+    };
+    if surplus == 0 {
+        return Ok(());
+    }
+    token_transfer(
+        swap_destination_info.clone(),
+        admin_destination_info.clone(),
+        swap_authority_info.clone(),
+        token_program_info.clone(),
+        surplus,
+        swap_authority_signer_seeds,
+    )
+}
+
+fn process_acquire_scratch(program_id: &Pubkey, accounts: &[AccountInfo]) -> ProgramResult {
+    let account_info_iter = &mut accounts.iter();
+    let scratch_account_info = next_account_info(account_info_iter)?;
+    let scratch_token_info = next_account_info(account_info_iter)?;
+    let holder_info = next_account_info(account_info_iter)?;
+    let token_program_info = next_account_info(account_info_iter)?;
+    let clock = &Clock::get()?;
+
+    let (mut scratch_account, scratch_token) = unpack_scratch_accounts(
+        program_id,
+        scratch_account_info,
+        scratch_token_info,
+        token_program_info,
+    )?;
+    if !holder_info.is_signer {
+        return Err(ProgramError::MissingRequiredSignature);
+    }
+    utils::validate(
+        scratch_account.can_acquire(scratch_token.amount, clock.slot),
+        SwapError::ScratchAccountInUse,
+    )?;
+
+    token_approve(
+        scratch_token_info.clone(),
+        holder_info.clone(),
+        scratch_account_info.clone(),
+        token_program_info.clone(),
+        u64::MAX,
+        &[
+            SEED_SCRATCH.as_bytes(),
+            scratch_account.config_key.as_ref(),
+            scratch_account.mint.as_ref(),
+            &[scratch_account.index],
+            &[scratch_account.bump_seed],
+        ],
+    )?;
+
+    scratch_account.acquire(*holder_info.key, clock.slot);
+    ScratchAccount::pack(scratch_account, &mut scratch_account_info.data.borrow_mut())?;
+
+    Ok(())
+}
+
+fn process_release_scratch(program_id: &Pubkey, accounts: &[AccountInfo]) -> ProgramResult {
+    let account_info_iter = &mut accounts.iter();
+    let scratch_account_info = next_account_info(account_info_iter)?;
+    let scratch_token_info = next_account_info(account_info_iter)?;
+    let holder_info = next_account_info(account_info_iter)?;
+    let destination_info = next_account_info(account_info_iter)?;
+    let token_program_info = next_account_info(account_info_iter)?;
+
+    let (mut scratch_account, scratch_token) = unpack_scratch_accounts(
+        program_id,
+        scratch_account_info,
+        scratch_token_info,
+        token_program_info,
+    )?;
+    if !holder_info.is_signer {
+        return Err(ProgramError::MissingRequiredSignature);
+    }
+    utils::validate(
+        scratch_account.holder == *holder_info.key,
+        SwapError::InvalidScratchAccount,
+    )?;
+
+    let seeds: &[&[u8]] = &[
+        SEED_SCRATCH.as_bytes(),
+        scratch_account.config_key.as_ref(),
+        scratch_account.mint.as_ref(),
+        &[scratch_account.index],
+        &[scratch_account.bump_seed],
+    ];
+    if scratch_token.amount > 0 {
+        token_transfer(
+            scratch_token_info.clone(),
+            destination_info.clone(),
+            scratch_account_info.clone(),
+            token_program_info.clone(),
+            scratch_token.amount,
+            seeds,
+        )?;
+    }
+    token_revoke(
+        scratch_token_info.clone(),
+        scratch_account_info.clone(),
+        token_program_info.clone(),
+        seeds,
+    )?;
+
+    scratch_account.release();
+    ScratchAccount::pack(scratch_account, &mut scratch_account_info.data.borrow_mut())?;
+
+    Ok(())
+}
+
+fn process_stake_fee_discount(
+    program_id: &Pubkey,
+    amount: u64,
+    accounts: &[AccountInfo],
+) -> ProgramResult {
+    let account_info_iter = &mut accounts.iter();
+    let config_info = next_account_info(account_info_iter)?;
+    let fee_discount_info = next_account_info(account_info_iter)?;
+    let fee_discount_token_info = next_account_info(account_info_iter)?;
+    let source_info = next_account_info(account_info_iter)?;
+    let owner_info = next_account_info(account_info_iter)?;
+    let mint_info = next_account_info(account_info_iter)?;
+    let rent_info = next_account_info(account_info_iter)?;
+    let system_program_info = next_account_info(account_info_iter)?;
+    let token_program_info = next_account_info(account_info_iter)?;
+
+    spl_token::check_program_account(token_program_info.key)?;
+    if config_info.owner != program_id {
+        return Err(ProgramError::IncorrectProgramId);
+    }
+    if !owner_info.is_signer {
+        return Err(ProgramError::MissingRequiredSignature);
+    }
+    let config = ConfigInfo::unpack(&config_info.data.borrow())?;
+    utils::validate(
+        config.deltafi_mint == *mint_info.key,
+        SwapError::IncorrectMint,
+    )?;
+
+    let mut fee_discount = if fee_discount_info.owner != program_id {
+        let bump_seed = create_fee_discount_accounts(
+            program_id,
+            config_info.key,
+            fee_discount_info,
+            fee_discount_token_info,
+            mint_info,
+            owner_info,
+            rent_info,
+            system_program_info,
+            token_program_info,
+        )?;
+        FeeDiscountAccount {
+            is_initialized: true,
+            bump_seed,
+            config_key: *config_info.key,
+            owner: *owner_info.key,
+            token_account: *fee_discount_token_info.key,
+            ..FeeDiscountAccount::default()
+        }
+    } else {
+        let fee_discount = FeeDiscountAccount::unpack(&fee_discount_info.data.borrow())?;
+        utils::validate(
+            fee_discount.config_key == *config_info.key
+                && fee_discount.owner == *owner_info.key
+                && fee_discount.token_account == *fee_discount_token_info.key,
+            SwapError::InvalidFeeDiscount,
+        )?;
+        fee_discount
+    };
+
+    token_transfer(
+        source_info.clone(),
+        fee_discount_token_info.clone(),
+        owner_info.clone(),
+        token_program_info.clone(),
+        amount,
+        &[],
+    )?;
+
+    fee_discount.staked_amount = fee_discount
+        .staked_amount
+        .checked_add(amount)
+        .ok_or(SwapError::CalculationFailure)?;
+    fee_discount.last_stake_slot = Clock::get()?.slot;
+    FeeDiscountAccount::pack(fee_discount, &mut fee_discount_info.data.borrow_mut())
+}
+
+fn process_unstake_fee_discount(
+    program_id: &Pubkey,
+    amount: u64,
+    accounts: &[AccountInfo],
+) -> ProgramResult {
+    let account_info_iter = &mut accounts.iter();
+    let fee_discount_info = next_account_info(account_info_iter)?;
+    let fee_discount_token_info = next_account_info(account_info_iter)?;
+    let owner_info = next_account_info(account_info_iter)?;
+    let destination_info = next_account_info(account_info_iter)?;
+    let token_program_info = next_account_info(account_info_iter)?;
+
+    spl_token::check_program_account(token_program_info.key)?;
+    if fee_discount_info.owner != program_id {
+        return Err(ProgramError::IncorrectProgramId);
+    }
+    if !owner_info.is_signer {
+        return Err(ProgramError::MissingRequiredSignature);
+    }
+    let mut fee_discount = FeeDiscountAccount::unpack(&fee_discount_info.data.borrow())?;
+    utils::validate(
+        fee_discount.owner == *owner_info.key
+            && fee_discount.token_account == *fee_discount_token_info.key,
+        SwapError::InvalidFeeDiscount,
+    )?;
+    fee_discount.staked_amount = fee_discount
+        .staked_amount
+        .checked_sub(amount)
+        .ok_or(SwapError::InsufficientFunds)?;
+
+    token_transfer(
+        fee_discount_token_info.clone(),
+        destination_info.clone(),
+        fee_discount_info.clone(),
+        token_program_info.clone(),
+        amount,
+        &[
+            SEED_FEE_DISCOUNT.as_bytes(),
+            fee_discount.config_key.as_ref(),
+            fee_discount.owner.as_ref(),
+            &[fee_discount.bump_seed],
+        ],
+    )?;
+
+    FeeDiscountAccount::pack(fee_discount, &mut fee_discount_info.data.borrow_mut())
+}
+
+fn process_lock_liquidity(
+    program_id: &Pubkey,
+    amount: u64,
+    unlock_ts: i64,
+    accounts: &[AccountInfo],
+) -> ProgramResult {
+    let account_info_iter = &mut accounts.iter();
+    let swap_info = next_account_info(account_info_iter)?;
+    let liquidity_lock_info = next_account_info(account_info_iter)?;
+    let liquidity_lock_token_info = next_account_info(account_info_iter)?;
+    let source_info = next_account_info(account_info_iter)?;
+    let owner_info = next_account_info(account_info_iter)?;
+    let pool_mint_info = next_account_info(account_info_iter)?;
+    let rent_info = next_account_info(account_info_iter)?;
+    let system_program_info = next_account_info(account_info_iter)?;
+    let token_program_info = next_account_info(account_info_iter)?;
+
+    spl_token::check_program_account(token_program_info.key)?;
+    if swap_info.owner != program_id {
+        return Err(ProgramError::IncorrectProgramId);
+    }
+    if !owner_info.is_signer {
+        return Err(ProgramError::MissingRequiredSignature);
+    }
+    let token_swap = SwapInfo::unpack(&swap_info.data.borrow())?;
+    utils::validate(
+        token_swap.pool_mint == *pool_mint_info.key,
+        SwapError::IncorrectMint,
+    )?;
+    utils::validate(
+        unlock_ts > Clock::get()?.unix_timestamp,
+        SwapError::InvalidInput,
+    )?;
+
+    let mut liquidity_lock = if liquidity_lock_info.owner != program_id {
+        let bump_seed = create_liquidity_lock_accounts(
+            program_id,
+            swap_info.key,
+            liquidity_lock_info,
+            liquidity_lock_token_info,
+            pool_mint_info,
+            owner_info,
+            rent_info,
+            system_program_info,
+            token_program_info,
+        )?;
+        LiquidityLock {
+            is_initialized: true,
+            bump_seed,
+            swap_key: *swap_info.key,
+            owner: *owner_info.key,
+            token_account: *liquidity_lock_token_info.key,
+            ..LiquidityLock::default()
+        }
+    } else {
+        let liquidity_lock = LiquidityLock::unpack(&liquidity_lock_info.data.borrow())?;
+        utils::validate(
+            liquidity_lock.swap_key == *swap_info.key
+                && liquidity_lock.owner == *owner_info.key
+                && liquidity_lock.token_account == *liquidity_lock_token_info.key,
+            SwapError::InvalidLiquidityLock,
+        )?;
+        liquidity_lock
+    };
+    liquidity_lock.lock(amount, unlock_ts)?;
+
+    token_transfer(
+        source_info.clone(),
+        liquidity_lock_token_info.clone(),
+        owner_info.clone(),
+        token_program_info.clone(),
+        amount,
+        &[],
+    )?;
+
+    LiquidityLock::pack(liquidity_lock, &mut liquidity_lock_info.data.borrow_mut())
+}
+
+fn process_unlock_liquidity(program_id: &Pubkey, accounts: &[AccountInfo]) -> ProgramResult {
+    let account_info_iter = &mut accounts.iter();
+    let liquidity_lock_info = next_account_info(account_info_iter)?;
+    let liquidity_lock_token_info = next_account_info(account_info_iter)?;
+    let owner_info = next_account_info(account_info_iter)?;
+    let destination_info = next_account_info(account_info_iter)?;
+    let token_program_info = next_account_info(account_info_iter)?;
+
+    spl_token::check_program_account(token_program_info.key)?;
+    if liquidity_lock_info.owner != program_id {
+        return Err(ProgramError::IncorrectProgramId);
+    }
+    if !owner_info.is_signer {
+        return Err(ProgramError::MissingRequiredSignature);
+    }
+    let mut liquidity_lock = LiquidityLock::unpack(&liquidity_lock_info.data.borrow())?;
+    utils::validate(
+        liquidity_lock.owner == *owner_info.key
+            && liquidity_lock.token_account == *liquidity_lock_token_info.key,
+        SwapError::InvalidLiquidityLock,
+    )?;
+    let amount = liquidity_lock.unlock(Clock::get()?.unix_timestamp)?;
+
+    token_transfer(
+        liquidity_lock_token_info.clone(),
+        destination_info.clone(),
+        liquidity_lock_info.clone(),
+        token_program_info.clone(),
+        amount,
+        &[
+            SEED_LIQUIDITY_LOCK.as_bytes(),
+            liquidity_lock.swap_key.as_ref(),
+            liquidity_lock.owner.as_ref(),
+            &[liquidity_lock.bump_seed],
+        ],
+    )?;
+
+    LiquidityLock::pack(liquidity_lock, &mut liquidity_lock_info.data.borrow_mut())
+}
+
+fn process_stable_swap_instruction(
+    program_id: &Pubkey,
+    accounts: &[AccountInfo],
+    input: &[u8],
+) -> ProgramResult {
+    let instruction = StableSwapInstruction::unpack(input)?;
+    match instruction {
+        StableSwapInstruction::Initialize(StableInitializeData {
+            nonce,
+            slope,
+            token_a_decimals,
+            token_b_decimals,
+            token_a_amount,
+            token_b_amount,
+        }) => {
+            msg!("Instruction: Stable Initialize");
+            process_stable_initialize(
+                program_id,
+                SwapType::Stable,
+                nonce,
+                slope,
+                Decimal::one(),
+                token_a_decimals,
+                token_b_decimals,
+                token_a_amount,
+                token_b_amount,
+                false,
+                accounts,
+            )
+        }
+        StableSwapInstruction::Swap(SwapData {
+            amount_in,
+            minimum_amount_out,
+            deadline_slot,
+            output_owner,
+        }) => {
+            msg!("Instruction: Stable Swap");
+            check_deadline(deadline_slot)?;
+            process_stable_swap(
+                program_id,
+                amount_in,
+                minimum_amount_out,
+                output_owner,
+                accounts,
+            )
+        }
+        StableSwapInstruction::SwapV2(SwapData {
+            amount_in,
+            minimum_amount_out,
+            deadline_slot,
+            output_owner,
+        }) => {
+            msg!("Instruction: Stable SwapV2");
+            check_deadline(deadline_slot)?;
+            process_stable_swap_v2(
+                program_id,
+                amount_in,
+                minimum_amount_out,
+                output_owner,
+                accounts,
+            )
+        }
+        StableSwapInstruction::Deposit(DepositData {
+            token_a_amount,
+            token_b_amount,
+            min_mint_amount,
+            deadline_slot,
+        }) => {
+            msg!("Instruction: Stable Deposit");
+            check_deadline(deadline_slot)?;
+            process_deposit(
+                program_id,
+                SwapType::Stable,
+                token_a_amount,
+                token_b_amount,
+                min_mint_amount,
+                accounts,
+            )
+        }
+        StableSwapInstruction::Withdraw(WithdrawData {
+            pool_token_amount,
+            minimum_token_a_amount,
+            minimum_token_b_amount,
+            deadline_slot,
+        }) => {
+            msg!("Instruction: Stable Withdraw");
+            check_deadline(deadline_slot)?;
+            process_withdraw(
+                program_id,
+                SwapType::Stable,
+                pool_token_amount,
+                minimum_token_a_amount,
+                minimum_token_b_amount,
+                accounts,
+            )
+        }
+        StableSwapInstruction::DepositOne(DepositOneData {
+            amount_in,
+            min_mint_amount,
+            deadline_slot,
+        }) => {
+            msg!("Instruction: Stable DepositOne");
+            check_deadline(deadline_slot)?;
+            process_deposit_one(
+                program_id,
+                SwapType::Stable,
+                amount_in,
+                min_mint_amount,
+                accounts,
+            )
+        }
+        StableSwapInstruction::WithdrawOne(WithdrawOneData {
+            pool_token_amount,
+            minimum_amount_out,
+            token_side,
+            deadline_slot,
+        }) => {
+            msg!("Instruction: Stable WithdrawOne");
+            check_deadline(deadline_slot)?;
+            process_withdraw_one(
+                program_id,
+                SwapType::Stable,
+                pool_token_amount,
+                minimum_amount_out,
+                token_side,
+                accounts,
+            )
+        }
+        StableSwapInstruction::PeggedInitialize(PeggedInitializeData {
+            nonce,
+            pegged_price,
+            token_a_decimals,
+            token_b_decimals,
+            token_a_amount,
+            token_b_amount,
+        }) => {
+            msg!("Instruction: Pegged Initialize");
+            // A flat curve keeps the pool at the pegged price until a reserve runs out
+            process_stable_initialize(
+                program_id,
+                SwapType::Pegged,
+                nonce,
+                0,
+                Decimal::from_scaled_val(pegged_price),
+                token_a_decimals,
+                token_b_decimals,
+                token_a_amount,
+                token_b_amount,
+                false,
+                accounts,
+            )
+        }
+        StableSwapInstruction::InitializeV2(StableInitializeData {
+            nonce,
+            slope,
+            token_a_decimals,
+            token_b_decimals,
+            token_a_amount,
+            token_b_amount,
+        }) => {
+            msg!("Instruction: Stable InitializeV2");
+            let accounts =
+                create_swap_account_v2(program_id, SwapType::Stable, accounts, 9, 10, 11)?;
+            process_stable_initialize(
+                program_id,
+                SwapType::Stable,
+                nonce,
+                slope,
+                Decimal::one(),
+                token_a_decimals,
+                token_b_decimals,
+                token_a_amount,
+                token_b_amount,
+                true,
+                &accounts,
+            )
+        }
+        StableSwapInstruction::TriInitialize(TriInitializeData {
+            nonce,
+            amp,
+            token_decimals,
+        }) => {
+            msg!("Instruction: Tri Initialize");
+            process_tri_initialize(program_id, nonce, amp, token_decimals, accounts)
+        }
+        StableSwapInstruction::TriSwap(TriSwapData {
+            amount_in,
+            minimum_amount_out,
+            deadline_slot,
+        }) => {
+            msg!("Instruction: Tri Swap");
+            check_deadline(deadline_slot)?;
+            process_tri_swap(program_id, amount_in, minimum_amount_out, accounts)
+        }
+        StableSwapInstruction::TriDeposit(TriDepositData {
+            token_amounts,
+            min_mint_amount,
+            deadline_slot,
+        }) => {
+            msg!("Instruction: Tri Deposit");
+            check_deadline(deadline_slot)?;
+            process_tri_deposit(program_id, token_amounts, min_mint_amount, accounts)
+        }
+        StableSwapInstruction::TriWithdraw(TriWithdrawData {
+            pool_token_amount,
+            minimum_token_amounts,
+            deadline_slot,
+        }) => {
+            msg!("Instruction: Tri Withdraw");
+            check_deadline(deadline_slot)?;
+            process_tri_withdraw(
+                program_id,
+                pool_token_amount,
+                minimum_token_amounts,
+                accounts,
+            )
+        }
+    }
+}
+
+fn process_stable_initialize(
+    program_id: &Pubkey,
+    swap_type: SwapType,
+    nonce: u8,
+    slope: u64,
+    market_price: Decimal,
+    token_a_decimals: u8,
+    token_b_decimals: u8,
+    token_a_amount: u64,
+    token_b_amount: u64,
+    swap_address_checked: bool,
+    accounts: &[AccountInfo],
+) -> ProgramResult {
+    let account_info_iter = &mut accounts.iter();
+    let config_info = next_account_info(account_info_iter)?;
+    let swap_info = next_account_info(account_info_iter)?;
+    let swap_authority_info = next_account_info(account_info_iter)?;
+    let admin_fee_a_info = next_account_info(account_info_iter)?;
+    let admin_fee_b_info = next_account_info(account_info_iter)?;
+    let token_a_info = next_account_info(account_info_iter)?;
+    let token_b_info = next_account_info(account_info_iter)?;
+    let pool_mint_info = next_account_info(account_info_iter)?;
+    let destination_info = next_account_info(account_info_iter)?;
+    let admin_info = next_account_info(account_info_iter)?;
+    let rent = &Rent::from_account_info(next_account_info(account_info_iter)?)?;
+    let token_program_info = next_account_info(account_info_iter)?;
 
-    utils::validate(swap_info.is_signer, SwapError::InvalidSigner)?;
+    spl_token::check_program_account(token_program_info.key)?;
+
+    // A pool created at its program address by `create_swap_account_v2` can't sign
+    utils::validate(
+        swap_address_checked || swap_info.is_signer,
+        SwapError::InvalidSigner,
+    )?;
 
     assert_rent_exempt(rent, swap_info)?;
     assert_uninitialized::<SwapInfo>(swap_info)?;
+    if config_info.owner != program_id || swap_info.owner != program_id {
+        return Err(SwapError::InvalidAccountOwner.into());
+    }
+
+    // Extract only fees and rewards to reduce stack usage
+    let (fees, rewards) = {
+        let config = ConfigInfo::unpack(&config_info.data.borrow())?;
+        is_admin(&config.admin_key, admin_info)?;
+        (config.fees, config.rewards)
+    };
 
     let swap_authority_signer_seeds = &[swap_info.key.as_ref(), &[nonce]];
     utils::validate(
-        *authority_info.key
+        *swap_authority_info.key
             == Pubkey::create_program_address(swap_authority_signer_seeds, program_id)?,
         SwapError::InvalidProgramAddress,
     )?;
 
-    // This block does validation and release the memory on stack.
-    {
-        let destination = unpack_token_account(destination_info, &token_program_id)?;
-        let pool_mint = unpack_mint(pool_mint_info, &token_program_id)?;
-        let admin_fee_key_a = unpack_token_account(admin_fee_a_info, &token_program_id)?;
-        let admin_fee_key_b = unpack_token_account(admin_fee_b_info, &token_program_id)?;
-
-        utils::validate(
-            *authority_info.key == token_a.owner,
-            SwapError::InvalidOwner,
-        )?;
-        utils::validate(
-            *authority_info.key == token_b.owner,
-            SwapError::InvalidOwner,
-        )?;
-
-        utils::validate(
-            *authority_info.key != destination.owner,
-            SwapError::InvalidOutputOwner,
-        )?;
-        utils::validate(
-            *authority_info.key != admin_fee_key_a.owner,
-            SwapError::InvalidOutputOwner,
-        )?;
-        utils::validate(
-            *authority_info.key != admin_fee_key_b.owner,
-            SwapError::InvalidOutputOwner,
-        )?;
-
-        utils::validate(token_a.mint != token_b.mint, SwapError::RepeatedMint)?;
-        utils::validate(
-            token_a.mint == admin_fee_key_a.mint,
-            SwapError::InvalidAdmin,
-        )?;
-        utils::validate(
-            token_b.mint == admin_fee_key_b.mint,
-            SwapError::InvalidAdmin,
-        )?;
-
-        utils::validate(
-            token_a.amount == token_a_amount,
-            SwapError::InconsistentInitialPoolTokenBalance,
-        )?;
-        utils::validate(
-            token_b.amount == token_b_amount,
-            SwapError::InconsistentInitialPoolTokenBalance,
-        )?;
-
-        utils::validate(!token_a.delegate.is_some(), SwapError::InvalidDelegate)?;
-        utils::validate(!token_b.delegate.is_some(), SwapError::InvalidDelegate)?;
-
-        utils::validate(
-            !token_a.close_authority.is_some(),
-            SwapError::InvalidCloseAuthority,
-        )?;
-        utils::validate(
-            !token_b.close_authority.is_some(),
-            SwapError::InvalidCloseAuthority,
-        )?;
-
-        if pool_mint.mint_authority.is_some()
-            && *authority_info.key != pool_mint.mint_authority.unwrap()
-        {
-            return Err(SwapError::InvalidOwner.into());
-        }
-        if pool_mint.freeze_authority.is_some() {
-            return Err(SwapError::InvalidFreezeAuthority.into());
-        }
-        if pool_mint.supply != 0 {
-            return Err(SwapError::InvalidSupply.into());
-        }
-    }
+    let token_program_id = *token_program_info.key;
+    let token_a = Box::new(unpack_token_account(token_a_info, &token_program_id)?);
+    let token_b = Box::new(unpack_token_account(token_b_info, &token_program_id)?);
+
+    validate_initial_pool_accounts(
+        swap_authority_info.key,
+        &token_a,
+        &token_b,
+        token_a_amount,
+        token_b_amount,
+        token_a_decimals,
+        destination_info,
+        pool_mint_info,
+        admin_fee_a_info,
+        admin_fee_b_info,
+        &token_program_id,
+    )?;
 
-    if Decimal::from_scaled_val(slope as u128) > Decimal::one() {
-        return Err(SwapError::InvalidSlope.into());
+    if market_price.is_zero() {
+        return Err(SwapError::InvalidInput.into());
     }
 
-    let (market_price, valid_slot) = get_market_price(
-        oracle_priority_flags,
-        pyth_a_price_info,
-        pyth_b_price_info,
-        clock,
-        serum_market_info,
-        serum_bids_info,
-        serum_asks_info,
+    let (pool_state, mint_amount) = init_pool_state(
+        market_price,
+        slope,
+        0,
         token_a_decimals,
         token_b_decimals,
-    )
-    .unwrap_or_else(|_| (Decimal::from_scaled_val(mid_price), clock.slot));
-
-    let mut pool_state = PoolState::new(InitPoolStateParams {
-        market_price,
-        slope: Decimal::from_scaled_val(slope.into()),
-        base_reserve: Decimal::zero(),
-        quote_reserve: Decimal::zero(),
-        total_supply: 0,
-        last_market_price: market_price,
-        last_valid_market_price_slot: min(clock.slot, valid_slot),
-    });
-    pool_state.set_market_price(token_a_decimals, token_b_decimals, market_price)?;
-
-    let (mint_amount, token_a_output, token_b_output) =
-        pool_state.buy_shares(token_a.amount, token_b.amount)?;
-    utils::validate(
-        token_a_output == token_a.amount && token_b_output == token_b.amount,
-        SwapError::CalculationFailure,
+        token_a.amount,
+        token_b.amount,
     )?;
 
-    pool_state.check_reserve_amount(token_a.amount, token_b.amount)?;
-
-    SwapInfo::pack(
-        SwapInfo {
+    pack_new_swap_info(
+        swap_info,
+        Box::new(SwapInfo {
             is_initialized: true,
             is_paused: false,
             nonce,
-            swap_type: SwapType::Normal,
+            swap_type,
             config_key: *config_info.key,
             token_a: *token_a_info.key,
             token_b: *token_b_info.key,
@@ -422,36 +5891,42 @@ fn process_initialize(
             token_b_mint: token_b.mint,
             admin_fee_key_a: *admin_fee_a_info.key,
             admin_fee_key_b: *admin_fee_b_info.key,
-            pyth_a,
-            pyth_b,
             fees,
             rewards,
-            pool_state,
+            virtual_price: pool_state.get_virtual_price()?,
+            pool_state: *pool_state,
             token_a_decimals,
             token_b_decimals,
-            oracle_priority_flags,
-            serum_combined_address,
+            version: SWAP_INFO_VERSION,
+            // stable swap use same data structure as swap
+            // we set pyth price accounts to null by using default value
             ..SwapInfo::default()
-        },
-        &mut swap_info.data.borrow_mut(),
+        }),
     )?;
 
     token_mint_to(
         pool_mint_info.clone(),
         destination_info.clone(),
-        authority_info.clone(),
+        swap_authority_info.clone(),
         token_program_info.clone(),
         mint_amount,
         swap_authority_signer_seeds,
     )?;
 
+    let pool_mint = unpack_mint(pool_mint_info, &token_program_id)?;
+    utils::validate(
+        pool_mint.supply == mint_amount,
+        SwapError::InconsistentMintSupply,
+    )?;
+
     Ok(())
 }
 
-fn process_swap(
+fn process_stable_swap(
     program_id: &Pubkey,
     amount_in: u64,
     minimum_amount_out: u64,
+    output_owner: Option<Pubkey>,
     accounts: &[AccountInfo],
 ) -> ProgramResult {
     let account_info_iter = &mut accounts.iter();
@@ -469,9 +5944,6 @@ fn process_swap(
     let reward_token_info = next_account_info(account_info_iter)?;
     let source_reward_token_info = next_account_info(account_info_iter)?;
     let admin_destination_info = next_account_info(account_info_iter)?;
-    let pyth_a_price_info = next_account_info(account_info_iter)?;
-    let pyth_b_price_info = next_account_info(account_info_iter)?;
-    let clock = &Clock::get()?;
     let token_program_info = next_account_info(account_info_iter)?;
 
     spl_token::check_program_account(token_program_info.key)?;
@@ -485,13 +5957,20 @@ fn process_swap(
 
     utils::validate_swap_config_key(&token_swap, config_info.key)?;
     utils::validate(
-        token_swap.swap_type == SwapType::Normal,
+        token_swap.swap_type.is_traded_as(SwapType::Stable),
         SwapError::IncorrectSwapType,
     )?;
 
-    if token_swap.is_paused {
+    if token_swap.is_paused || token_swap.pause_swap {
         return Err(SwapError::IsPaused.into());
     }
+    next_whitelist_entry(
+        program_id,
+        swap_info.key,
+        &token_swap,
+        user_transfer_authority_info.key,
+        account_info_iter,
+    )?;
     let swap_authority_signer_seeds = &[swap_info.key.as_ref(), &[token_swap.nonce]];
     if *swap_authority_info.key
         != Pubkey::create_program_address(swap_authority_signer_seeds, program_id)?
@@ -504,12 +5983,13 @@ fn process_swap(
     {
         return Err(SwapError::InvalidProgramAddress.into());
     }
-    if token_swap.pyth_a != *pyth_a_price_info.key || token_swap.pyth_b != *pyth_b_price_info.key {
-        return Err(SwapError::InvalidInput.into());
-    }
     if source_info.key == destination_info.key {
         return Err(SwapError::InvalidInput.into());
     }
+    if token_swap.strict_destination_owner && output_owner.is_none() {
+        let destination_token = unpack_token_account(destination_info, token_program_info.key)?;
+        utils::validate_destination_owner(&destination_token.owner, accounts)?;
+    }
     if source_info.key == swap_source_info.key || destination_info.key == swap_destination_info.key
     {
         return Err(SwapError::InvalidInput.into());
@@ -556,6 +6036,17 @@ fn process_swap(
         SwapDirection::SellQuote => (destination_decimals, source_decimals),
     };
 
+    skim_pool_surplus(
+        &mut token_swap,
+        swap_direction,
+        swap_source_info,
+        swap_destination_info,
+        admin_destination_info,
+        swap_authority_info,
+        token_program_info,
+        swap_authority_signer_seeds,
+    )?;
+
     let token_program_id = *token_program_info.key;
     let reward_token = unpack_token_account(reward_token_info, &token_program_id)?;
     let source_reward_token = unpack_token_account(source_reward_token_info, &token_program_id)?;
@@ -566,27 +6057,38 @@ fn process_swap(
         &reward_token,
     )?;
 
-    match get_market_price_from_pyth(pyth_a_price_info, pyth_b_price_info, clock) {
-        Ok((market_price, valid_slot)) => {
-            token_swap
-                .pool_state
-                .check_and_update_market_price_and_slot(market_price, valid_slot)?;
-
-            token_swap
-                .pool_state
-                .set_market_price(base_decimals, quote_decimals, market_price)?;
-        }
-        Err(e) => {
-            return Err(e);
-        }
-    }
+    let market_price = get_stable_market_price(
+        &token_swap,
+        base_decimals,
+        quote_decimals,
+        account_info_iter,
+    )?;
+    token_swap
+        .pool_state
+        .set_market_price(base_decimals, quote_decimals, market_price)?;
 
     let receive_amount = token_swap
         .pool_state
         .get_out_amount(amount_in, swap_direction)?;
+    if token_swap.check_circuit_breaker(
+        amount_in,
+        receive_amount,
+        swap_direction,
+        Clock::get()?.slot,
+    )? {
+        return Err(SwapError::CircuitBreakerTripped.into());
+    }
     let fees = &token_swap.fees;
     let trade_fee = fees.trade_fee(receive_amount)?;
     let admin_fee = fees.admin_trade_fee(trade_fee)?;
+    let (treasury_fee, treasury_token_info) = next_treasury_fee(
+        &config,
+        fees,
+        trade_fee,
+        destination_mint_info.key,
+        token_program_info.key,
+        account_info_iter,
+    )?;
     let rewards = &token_swap.rewards;
     let amount_out = receive_amount
         .checked_sub(trade_fee)
@@ -597,9 +6099,6 @@ fn process_swap(
         return Err(SwapError::ExceededSlippage.into());
     }
 
-    // Token price is fluctuated and need verification.
-    // To consider token price in reward calculation may not be a reliable solution.
-    // The awarded amount can be adjusted by config setting if required.
     let amount_to_reward =
         rewards.trade_reward_u64(if swap_direction == SwapDirection::SellBase {
             amount_in
@@ -607,10 +6106,19 @@ fn process_swap(
             amount_out
         })?;
 
-    // The actual token amount moving out of the pool is amount_out + admin_fee.
-    token_swap
-        .pool_state
-        .swap(amount_in, amount_out + admin_fee, swap_direction)?;
+    // The actual token amount moving out of the pool is amount_out + admin_fee + treasury_fee.
+    token_swap.pool_state.swap_with_fee(
+        amount_in,
+        amount_out
+            .checked_add(admin_fee)
+            .and_then(|amount| amount.checked_add(treasury_fee))
+            .ok_or(SwapError::SwapOutflowOverflow)?,
+        trade_fee
+            .checked_sub(admin_fee)
+            .and_then(|fee| fee.checked_sub(treasury_fee))
+            .ok_or(SwapError::LpFeeUnderflow)?,
+        swap_direction,
+    )?;
 
     token_transfer(
         source_info.clone(),
@@ -636,6 +6144,16 @@ fn process_swap(
         admin_fee,
         swap_authority_signer_seeds,
     )?;
+    if let Some(treasury_token_info) = treasury_token_info {
+        token_transfer(
+            swap_destination_info.clone(),
+            treasury_token_info.clone(),
+            swap_authority_info.clone(),
+            token_program_info.clone(),
+            treasury_fee,
+            swap_authority_signer_seeds,
+        )?;
+    }
     token_transfer(
         source_reward_token_info.clone(),
         reward_token_info.clone(),
@@ -656,50 +6174,70 @@ fn process_swap(
             .pool_state
             .check_reserve_amount(swap_dest_token.amount, swap_source_token.amount)?;
     }
+    token_swap.pool_state.check_share_price_floor()?;
+    token_swap.update_virtual_price()?;
+
+    if let Some(output_owner) = output_owner {
+        let instructions_sysvar_info = next_account_info(account_info_iter)?;
+        let destination_token = unpack_token_account(destination_info, &token_program_id)?;
+        utils::validate_output_owner(
+            program_id,
+            &destination_token.owner,
+            &output_owner,
+            instructions_sysvar_info,
+        )?;
+    }
 
     // Handle referral reward
-    if let Some(user_referrer_data_info) = account_info_iter.next() {
-        let referrer_token_info = next_account_info(account_info_iter)?;
-
-        let source_token = unpack_token_account(source_info, &token_program_id)?;
-        let expected_user_referrer_data_pubkey =
-            get_referrer_data_pubkey(&source_token.owner, config_info.key, program_id)?;
-        utils::validate(
-            expected_user_referrer_data_pubkey == *user_referrer_data_info.key,
-            SwapError::InvalidAccountOwner,
+    let source_token = unpack_token_account(source_info, &token_program_id)?;
+    if let Some(referrer_token_info) = next_referrer_token(
+        program_id,
+        config_info.key,
+        &source_token.owner,
+        &token_program_id,
+        account_info_iter,
+    )? {
+        let referral_reward = next_referral_reward(
+            program_id,
+            config_info.key,
+            referrer_token_info.key,
+            amount_to_reward,
+            account_info_iter,
         )?;
-
-        let user_referrer_data = UserReferrerData::unpack(&user_referrer_data_info.data.borrow())?;
-        utils::validate(
-            user_referrer_data.referrer == *referrer_token_info.key
-                && user_referrer_data.config_key == *config_info.key,
-            SwapError::InvalidAccountOwner,
+        token_transfer(
+            source_reward_token_info.clone(),
+            referrer_token_info.clone(),
+            market_authority_info.clone(),
+            token_program_info.clone(),
+            referral_reward,
+            market_authority_signer_seeds,
         )?;
+    }
 
-        // Dummy referrer is a placeholder to indicate that the user is not referred by anyone.
-        // If it is given in the input, skip the referral reward distribution here.
-        if user_referrer_data.referrer.to_string() != DUMMY_REFERRER_ADDRESS {
-            let referral_reward = rewards.referral_reward(amount_to_reward)?;
-            token_transfer(
-                source_reward_token_info.clone(),
-                referrer_token_info.clone(),
-                market_authority_info.clone(),
-                token_program_info.clone(),
-                referral_reward,
-                market_authority_signer_seeds,
-            )?;
-        }
+    token_swap.record_swap(amount_in, amount_out, trade_fee, swap_direction)?;
+
+    SwapEvent {
+        swap: *swap_info.key,
+        user: *user_transfer_authority_info.key,
+        direction: swap_direction,
+        amount_in,
+        amount_out,
+        trade_fee,
+        admin_fee,
+        market_price: token_swap.pool_state.market_price,
     }
+    .emit();
 
     SwapInfo::pack(token_swap, &mut swap_info.data.borrow_mut())?;
 
     Ok(())
 }
 
-fn process_swap_v2(
+fn process_stable_swap_v2(
     program_id: &Pubkey,
     amount_in: u64,
     minimum_amount_out: u64,
+    output_owner: Option<Pubkey>,
     accounts: &[AccountInfo],
 ) -> ProgramResult {
     let account_info_iter = &mut accounts.iter();
@@ -715,12 +6253,6 @@ fn process_swap_v2(
     let reward_token_info = next_account_info(account_info_iter)?;
     let source_reward_token_info = next_account_info(account_info_iter)?;
     let admin_destination_info = next_account_info(account_info_iter)?;
-    let pyth_a_price_info = next_account_info(account_info_iter)?;
-    let pyth_b_price_info = next_account_info(account_info_iter)?;
-    let serum_market_info = next_account_info(account_info_iter)?;
-    let serum_bids_info = next_account_info(account_info_iter)?;
-    let serum_asks_info = next_account_info(account_info_iter)?;
-    let clock = &Clock::get()?;
     let token_program_info = next_account_info(account_info_iter)?;
 
     spl_token::check_program_account(token_program_info.key)?;
@@ -734,13 +6266,20 @@ fn process_swap_v2(
 
     utils::validate_swap_config_key(&token_swap, config_info.key)?;
     utils::validate(
-        token_swap.swap_type == SwapType::Normal,
+        token_swap.swap_type.is_traded_as(SwapType::Stable),
         SwapError::IncorrectSwapType,
     )?;
 
-    if token_swap.is_paused {
+    if token_swap.is_paused || token_swap.pause_swap {
         return Err(SwapError::IsPaused.into());
     }
+    next_whitelist_entry(
+        program_id,
+        swap_info.key,
+        &token_swap,
+        user_transfer_authority_info.key,
+        account_info_iter,
+    )?;
     let swap_authority_signer_seeds = &[swap_info.key.as_ref(), &[token_swap.nonce]];
     if *swap_authority_info.key
         != Pubkey::create_program_address(swap_authority_signer_seeds, program_id)?
@@ -753,12 +6292,13 @@ fn process_swap_v2(
     {
         return Err(SwapError::InvalidProgramAddress.into());
     }
-    if token_swap.pyth_a != *pyth_a_price_info.key || token_swap.pyth_b != *pyth_b_price_info.key {
-        return Err(SwapError::InvalidInput.into());
-    }
     if source_info.key == destination_info.key {
         return Err(SwapError::InvalidInput.into());
     }
+    if token_swap.strict_destination_owner && output_owner.is_none() {
+        let destination_token = unpack_token_account(destination_info, token_program_info.key)?;
+        utils::validate_destination_owner(&destination_token.owner, accounts)?;
+    }
     if source_info.key == swap_source_info.key || destination_info.key == swap_destination_info.key
     {
         return Err(SwapError::InvalidInput.into());
@@ -796,82 +6336,79 @@ fn process_swap_v2(
             &token_swap.token_a_mint,
             &token_swap.token_b_mint,
             &source_token.mint,
-            &destination_token.mint,
-        )?;
-    }
-
-    let token_program_id = *token_program_info.key;
-    {
-        let reward_token = unpack_token_account(reward_token_info, &token_program_id)?;
-        let source_reward_token =
-            unpack_token_account(source_reward_token_info, &token_program_id)?;
-        validate_reward_token_accounts(
-            &config,
-            market_authority_info.key,
-            &source_reward_token,
-            &reward_token,
-        )?;
-        if !OraclePriorityFlag::from_bits_truncate(token_swap.oracle_priority_flags).is_pyth_only()
-        {
-            utils::check_serum_accounts(
-                serum_market_info,
-                serum_bids_info,
-                serum_asks_info,
-                &token_swap.serum_combined_address,
-            )?;
-            utils::validate_serum_market_mint_address(
-                serum_market_info,
-                &token_swap.token_a_mint,
-                &token_swap.token_b_mint,
-            )?;
-        }
+            &destination_token.mint,
+        )?;
     }
 
-    match get_market_price(
-        token_swap.oracle_priority_flags,
-        pyth_a_price_info,
-        pyth_b_price_info,
-        clock,
-        serum_market_info,
-        serum_bids_info,
-        serum_asks_info,
+    skim_pool_surplus(
+        &mut token_swap,
+        swap_direction,
+        swap_source_info,
+        swap_destination_info,
+        admin_destination_info,
+        swap_authority_info,
+        token_program_info,
+        swap_authority_signer_seeds,
+    )?;
+
+    let token_program_id = *token_program_info.key;
+    let reward_token = unpack_token_account(reward_token_info, &token_program_id)?;
+    let source_reward_token = unpack_token_account(source_reward_token_info, &token_program_id)?;
+    validate_reward_token_accounts(
+        &config,
+        market_authority_info.key,
+        &source_reward_token,
+        &reward_token,
+    )?;
+
+    let market_price = get_stable_market_price(
+        &token_swap,
         token_swap.token_a_decimals,
         token_swap.token_b_decimals,
-    ) {
-        Ok((market_price, valid_slot)) => {
-            token_swap
-                .pool_state
-                .check_and_update_market_price_and_slot(market_price, valid_slot)?;
-
-            token_swap.pool_state.set_market_price(
-                token_swap.token_a_decimals,
-                token_swap.token_b_decimals,
-                market_price,
-            )?;
-        }
-        Err(e) => {
-            return Err(e);
-        }
-    }
+        account_info_iter,
+    )?;
+    token_swap.pool_state.set_market_price(
+        token_swap.token_a_decimals,
+        token_swap.token_b_decimals,
+        market_price,
+    )?;
 
     let receive_amount = token_swap
         .pool_state
         .get_out_amount(amount_in, swap_direction)?;
+    if token_swap.check_circuit_breaker(
+        amount_in,
+        receive_amount,
+        swap_direction,
+        Clock::get()?.slot,
+    )? {
+        return Err(SwapError::CircuitBreakerTripped.into());
+    }
     let fees = &token_swap.fees;
     let trade_fee = fees.trade_fee(receive_amount)?;
     let admin_fee = fees.admin_trade_fee(trade_fee)?;
+    let destination_mint = match swap_direction {
+        SwapDirection::SellBase => token_swap.token_b_mint,
+        SwapDirection::SellQuote => token_swap.token_a_mint,
+    };
+    let (treasury_fee, treasury_token_info) = next_treasury_fee(
+        &config,
+        fees,
+        trade_fee,
+        &destination_mint,
+        token_program_info.key,
+        account_info_iter,
+    )?;
     let rewards = &token_swap.rewards;
     let amount_out = receive_amount
         .checked_sub(trade_fee)
         .ok_or(SwapError::CalculationFailure)?;
+
     token_swap.check_swap_out_amount(amount_out, swap_direction)?;
     if amount_out < minimum_amount_out {
         return Err(SwapError::ExceededSlippage.into());
     }
 
-    // Token price is fluctuated and need verification.
-    // To consider token price in reward calculation may not be a reliable solution.
-    // The awarded amount can be adjusted by config setting if required.
     let amount_to_reward =
         rewards.trade_reward_u64(if swap_direction == SwapDirection::SellBase {
             amount_in
@@ -879,10 +6416,19 @@ fn process_swap_v2(
             amount_out
         })?;
 
-    // The actual token amount moving out of the pool is amount_out + admin_fee.
-    token_swap
-        .pool_state
-        .swap(amount_in, amount_out + admin_fee, swap_direction)?;
+    // The actual token amount moving out of the pool is amount_out + admin_fee + treasury_fee.
+    token_swap.pool_state.swap_with_fee(
+        amount_in,
+        amount_out
+            .checked_add(admin_fee)
+            .and_then(|amount| amount.checked_add(treasury_fee))
+            .ok_or(SwapError::SwapOutflowOverflow)?,
+        trade_fee
+            .checked_sub(admin_fee)
+            .and_then(|fee| fee.checked_sub(treasury_fee))
+            .ok_or(SwapError::LpFeeUnderflow)?,
+        swap_direction,
+    )?;
 
     token_transfer(
         source_info.clone(),
@@ -908,6 +6454,16 @@ fn process_swap_v2(
         admin_fee,
         swap_authority_signer_seeds,
     )?;
+    if let Some(treasury_token_info) = treasury_token_info {
+        token_transfer(
+            swap_destination_info.clone(),
+            treasury_token_info.clone(),
+            swap_authority_info.clone(),
+            token_program_info.clone(),
+            treasury_fee,
+            swap_authority_signer_seeds,
+        )?;
+    }
     token_transfer(
         source_reward_token_info.clone(),
         reward_token_info.clone(),
@@ -928,1242 +6484,2250 @@ fn process_swap_v2(
             .pool_state
             .check_reserve_amount(swap_dest_token.amount, swap_source_token.amount)?;
     }
+    token_swap.pool_state.check_share_price_floor()?;
+    token_swap.update_virtual_price()?;
+
+    if let Some(output_owner) = output_owner {
+        let instructions_sysvar_info = next_account_info(account_info_iter)?;
+        let destination_token = unpack_token_account(destination_info, &token_program_id)?;
+        utils::validate_output_owner(
+            program_id,
+            &destination_token.owner,
+            &output_owner,
+            instructions_sysvar_info,
+        )?;
+    }
 
     // Handle referral reward
-    if let Some(user_referrer_data_info) = account_info_iter.next() {
-        let referrer_token_info = next_account_info(account_info_iter)?;
+    let source_token = unpack_token_account(source_info, &token_program_id)?;
+    if let Some(referrer_token_info) = next_referrer_token(
+        program_id,
+        config_info.key,
+        &source_token.owner,
+        &token_program_id,
+        account_info_iter,
+    )? {
+        let referral_reward = next_referral_reward(
+            program_id,
+            config_info.key,
+            referrer_token_info.key,
+            amount_to_reward,
+            account_info_iter,
+        )?;
+        token_transfer(
+            source_reward_token_info.clone(),
+            referrer_token_info.clone(),
+            market_authority_info.clone(),
+            token_program_info.clone(),
+            referral_reward,
+            market_authority_signer_seeds,
+        )?;
+    }
+
+    token_swap.record_swap(amount_in, amount_out, trade_fee, swap_direction)?;
+
+    SwapEvent {
+        swap: *swap_info.key,
+        user: *user_transfer_authority_info.key,
+        direction: swap_direction,
+        amount_in,
+        amount_out,
+        trade_fee,
+        admin_fee,
+        market_price: token_swap.pool_state.market_price,
+    }
+    .emit();
+
+    SwapInfo::pack(token_swap, &mut swap_info.data.borrow_mut())?;
+
+    Ok(())
+}
+
+/// Unpack a tri pool of the market config and check its swap authority
+fn unpack_tri_swap(
+    program_id: &Pubkey,
+    config_info: &AccountInfo,
+    tri_swap_info: &AccountInfo,
+    authority_info: &AccountInfo,
+) -> Result<TriSwapInfo, ProgramError> {
+    if config_info.owner != program_id || tri_swap_info.owner != program_id {
+        return Err(SwapError::InvalidAccountOwner.into());
+    }
+    let tri_swap = TriSwapInfo::unpack(&tri_swap_info.data.borrow())?;
+    if tri_swap.config_key != *config_info.key {
+        return Err(SwapError::InvalidAccount.into());
+    }
+    if tri_swap.is_paused {
+        return Err(SwapError::IsPaused.into());
+    }
+    if *authority_info.key
+        != Pubkey::create_program_address(
+            &[tri_swap_info.key.as_ref(), &[tri_swap.nonce]],
+            program_id,
+        )?
+    {
+        return Err(SwapError::InvalidProgramAddress.into());
+    }
+    Ok(tri_swap)
+}
+
+fn process_tri_initialize(
+    program_id: &Pubkey,
+    nonce: u8,
+    amp: u64,
+    token_decimals: [u8; TRI_POOL_TOKENS],
+    accounts: &[AccountInfo],
+) -> ProgramResult {
+    let account_info_iter = &mut accounts.iter();
+    let config_info = next_account_info(account_info_iter)?;
+    let tri_swap_info = next_account_info(account_info_iter)?;
+    let swap_authority_info = next_account_info(account_info_iter)?;
+    let admin_fee_infos = next_account_infos(account_info_iter, TRI_POOL_TOKENS)?;
+    let token_infos = next_account_infos(account_info_iter, TRI_POOL_TOKENS)?;
+    let pool_mint_info = next_account_info(account_info_iter)?;
+    let destination_info = next_account_info(account_info_iter)?;
+    let admin_info = next_account_info(account_info_iter)?;
+    let rent = &Rent::from_account_info(next_account_info(account_info_iter)?)?;
+    let token_program_info = next_account_info(account_info_iter)?;
+
+    spl_token::check_program_account(token_program_info.key)?;
+
+    utils::validate(tri_swap_info.is_signer, SwapError::InvalidSigner)?;
+
+    assert_rent_exempt(rent, tri_swap_info)?;
+    assert_uninitialized::<TriSwapInfo>(tri_swap_info)?;
+    if config_info.owner != program_id || tri_swap_info.owner != program_id {
+        return Err(SwapError::InvalidAccountOwner.into());
+    }
+    utils::validate((MIN_AMP..=MAX_AMP).contains(&amp), SwapError::InvalidAmp)?;
+
+    let fees = {
+        let config = ConfigInfo::unpack(&config_info.data.borrow())?;
+        is_admin(&config.admin_key, admin_info)?;
+        config.fees
+    };
+
+    let swap_authority_signer_seeds = &[tri_swap_info.key.as_ref(), &[nonce]];
+    utils::validate(
+        *swap_authority_info.key
+            == Pubkey::create_program_address(swap_authority_signer_seeds, program_id)?,
+        SwapError::InvalidProgramAddress,
+    )?;
+
+    let token_program_id = *token_program_info.key;
+    let mut tri_swap = TriSwapInfo {
+        is_initialized: true,
+        is_paused: false,
+        nonce,
+        config_key: *config_info.key,
+        pool_mint: *pool_mint_info.key,
+        token_decimals,
+        amp,
+        fees,
+        ..TriSwapInfo::default()
+    };
+    let mut token_amounts = [0u64; TRI_POOL_TOKENS];
+    for (index, token_info) in token_infos.iter().enumerate() {
+        let token = unpack_token_account(token_info, &token_program_id)?;
+        let admin_fee = unpack_token_account(&admin_fee_infos[index], &token_program_id)?;
+        utils::validate(
+            *swap_authority_info.key == token.owner,
+            SwapError::InvalidOwner,
+        )?;
+        utils::validate(
+            *swap_authority_info.key != admin_fee.owner,
+            SwapError::InvalidOutputOwner,
+        )?;
+        utils::validate(token.mint == admin_fee.mint, SwapError::InvalidAdmin)?;
+        utils::validate(
+            !tri_swap.token_mints[..index].contains(&token.mint),
+            SwapError::RepeatedMint,
+        )?;
+        utils::validate(!token.delegate.is_some(), SwapError::InvalidDelegate)?;
+        utils::validate(
+            !token.close_authority.is_some(),
+            SwapError::InvalidCloseAuthority,
+        )?;
+
+        tri_swap.tokens[index] = *token_info.key;
+        tri_swap.token_mints[index] = token.mint;
+        tri_swap.admin_fee_keys[index] = *admin_fee_infos[index].key;
+        token_amounts[index] = token.amount;
+    }
+
+    {
+        let destination = unpack_token_account(destination_info, &token_program_id)?;
+        let pool_mint = unpack_mint(pool_mint_info, &token_program_id)?;
+        utils::validate(
+            *swap_authority_info.key != destination.owner,
+            SwapError::InvalidOutputOwner,
+        )?;
+        if pool_mint.mint_authority.is_some()
+            && *swap_authority_info.key != pool_mint.mint_authority.unwrap()
+        {
+            return Err(SwapError::InvalidOwner.into());
+        }
+        if pool_mint.freeze_authority.is_some() {
+            return Err(SwapError::InvalidFreezeAuthority.into());
+        }
+        if pool_mint.supply != 0 {
+            return Err(SwapError::InvalidSupply.into());
+        }
+        // Initial shares are minted 1:1 with the invariant at the most decimals of the tokens.
+        utils::validate(
+            pool_mint.decimals == tri_swap.precision(),
+            SwapError::InvalidPoolMintDecimals,
+        )?;
+    }
+
+    let mint_amount = tri_swap.deposit_mint_amount(&token_amounts, 0)?;
+    tri_swap.deposit(&token_amounts)?;
+    for (index, token_amount) in token_amounts.iter().enumerate() {
+        tri_swap.check_reserve_amount(index, *token_amount)?;
+    }
+
+    TriSwapInfo::pack(tri_swap, &mut tri_swap_info.data.borrow_mut())?;
+
+    token_mint_to(
+        pool_mint_info.clone(),
+        destination_info.clone(),
+        swap_authority_info.clone(),
+        token_program_info.clone(),
+        mint_amount,
+        swap_authority_signer_seeds,
+    )?;
+
+    let pool_mint = unpack_mint(pool_mint_info, &token_program_id)?;
+    utils::validate(
+        pool_mint.supply == mint_amount,
+        SwapError::InconsistentMintSupply,
+    )?;
+
+    Ok(())
+}
+
+fn process_tri_swap(
+    program_id: &Pubkey,
+    amount_in: u64,
+    minimum_amount_out: u64,
+    accounts: &[AccountInfo],
+) -> ProgramResult {
+    let account_info_iter = &mut accounts.iter();
+    let config_info = next_account_info(account_info_iter)?;
+    let tri_swap_info = next_account_info(account_info_iter)?;
+    let swap_authority_info = next_account_info(account_info_iter)?;
+    let user_transfer_authority_info = next_account_info(account_info_iter)?;
+    let source_info = next_account_info(account_info_iter)?;
+    let swap_source_info = next_account_info(account_info_iter)?;
+    let swap_destination_info = next_account_info(account_info_iter)?;
+    let destination_info = next_account_info(account_info_iter)?;
+    let admin_fee_destination_info = next_account_info(account_info_iter)?;
+    let token_program_info = next_account_info(account_info_iter)?;
+
+    spl_token::check_program_account(token_program_info.key)?;
+
+    let mut tri_swap =
+        unpack_tri_swap(program_id, config_info, tri_swap_info, swap_authority_info)?;
+    let source_index = tri_swap.token_index(swap_source_info.key)?;
+    let destination_index = tri_swap.token_index(swap_destination_info.key)?;
+    if swap_source_info.key == source_info.key || swap_destination_info.key == destination_info.key
+    {
+        return Err(SwapError::InvalidInput.into());
+    }
+    if *admin_fee_destination_info.key != tri_swap.admin_fee_keys[destination_index] {
+        return Err(SwapError::InvalidAdmin.into());
+    }
+
+    let config = ConfigInfo::unpack(&config_info.data.borrow())?;
+    let amount_out = tri_swap.get_out_amount(source_index, destination_index, amount_in)?;
+    let trade_fee = tri_swap.fees.trade_fee(amount_out)?;
+    let admin_fee = tri_swap.fees.admin_trade_fee(trade_fee)?;
+    let (treasury_fee, treasury_token_info) = next_treasury_fee(
+        &config,
+        &tri_swap.fees,
+        trade_fee,
+        &tri_swap.token_mints[destination_index],
+        token_program_info.key,
+        account_info_iter,
+    )?;
+    let amount_out = amount_out
+        .checked_sub(trade_fee)
+        .ok_or(SwapError::CalculationFailure)?;
+    if amount_out < minimum_amount_out {
+        return Err(SwapError::ExceededSlippage.into());
+    }
+    // The rest of the trade fee stays in the reserve of the destination token
+    let amount_from_pool = amount_out
+        .checked_add(admin_fee)
+        .and_then(|amount| amount.checked_add(treasury_fee))
+        .ok_or(SwapError::SwapOutflowOverflow)?;
+    tri_swap.swap(source_index, destination_index, amount_in, amount_from_pool)?;
+
+    let swap_authority_signer_seeds = &[tri_swap_info.key.as_ref(), &[tri_swap.nonce]];
+    token_transfer(
+        source_info.clone(),
+        swap_source_info.clone(),
+        user_transfer_authority_info.clone(),
+        token_program_info.clone(),
+        amount_in,
+        &[],
+    )?;
+    token_transfer(
+        swap_destination_info.clone(),
+        destination_info.clone(),
+        swap_authority_info.clone(),
+        token_program_info.clone(),
+        amount_out,
+        swap_authority_signer_seeds,
+    )?;
+    token_transfer(
+        swap_destination_info.clone(),
+        admin_fee_destination_info.clone(),
+        swap_authority_info.clone(),
+        token_program_info.clone(),
+        admin_fee,
+        swap_authority_signer_seeds,
+    )?;
+    if let Some(treasury_token_info) = treasury_token_info {
+        token_transfer(
+            swap_destination_info.clone(),
+            treasury_token_info.clone(),
+            swap_authority_info.clone(),
+            token_program_info.clone(),
+            treasury_fee,
+            swap_authority_signer_seeds,
+        )?;
+    }
+
+    let swap_source = unpack_token_account(swap_source_info, token_program_info.key)?;
+    let swap_destination = unpack_token_account(swap_destination_info, token_program_info.key)?;
+    tri_swap.check_reserve_amount(source_index, swap_source.amount)?;
+    tri_swap.check_reserve_amount(destination_index, swap_destination.amount)?;
+
+    TriSwapInfo::pack(tri_swap, &mut tri_swap_info.data.borrow_mut())?;
+
+    Ok(())
+}
+
+fn process_tri_deposit(
+    program_id: &Pubkey,
+    token_amounts: [u64; TRI_POOL_TOKENS],
+    min_mint_amount: u64,
+    accounts: &[AccountInfo],
+) -> ProgramResult {
+    let account_info_iter = &mut accounts.iter();
+    let config_info = next_account_info(account_info_iter)?;
+    let tri_swap_info = next_account_info(account_info_iter)?;
+    let swap_authority_info = next_account_info(account_info_iter)?;
+    let user_transfer_authority_info = next_account_info(account_info_iter)?;
+    let source_infos = next_account_infos(account_info_iter, TRI_POOL_TOKENS)?;
+    let token_infos = next_account_infos(account_info_iter, TRI_POOL_TOKENS)?;
+    let pool_mint_info = next_account_info(account_info_iter)?;
+    let destination_info = next_account_info(account_info_iter)?;
+    let token_program_info = next_account_info(account_info_iter)?;
+
+    spl_token::check_program_account(token_program_info.key)?;
+
+    let mut tri_swap =
+        unpack_tri_swap(program_id, config_info, tri_swap_info, swap_authority_info)?;
+    for (index, token_info) in token_infos.iter().enumerate() {
+        if *token_info.key != tri_swap.tokens[index] {
+            return Err(SwapError::IncorrectSwapAccount.into());
+        }
+        if token_info.key == source_infos[index].key {
+            return Err(SwapError::InvalidInput.into());
+        }
+    }
+    if *pool_mint_info.key != tri_swap.pool_mint {
+        return Err(SwapError::IncorrectMint.into());
+    }
 
-        let source_token = unpack_token_account(source_info, &token_program_id)?;
-        let expected_user_referrer_data_pubkey =
-            get_referrer_data_pubkey(&source_token.owner, config_info.key, program_id)?;
-        utils::validate(
-            expected_user_referrer_data_pubkey == *user_referrer_data_info.key,
-            SwapError::InvalidAccountOwner,
-        )?;
+    let supply = unpack_mint(pool_mint_info, token_program_info.key)?.supply;
+    let mint_amount = tri_swap.deposit_mint_amount(&token_amounts, supply)?;
+    if mint_amount < min_mint_amount {
+        return Err(SwapError::ExceededSlippage.into());
+    }
+    tri_swap.deposit(&token_amounts)?;
 
-        let user_referrer_data = UserReferrerData::unpack(&user_referrer_data_info.data.borrow())?;
-        utils::validate(
-            user_referrer_data.referrer == *referrer_token_info.key
-                && user_referrer_data.config_key == *config_info.key,
-            SwapError::InvalidAccountOwner,
+    for (index, token_info) in token_infos.iter().enumerate() {
+        token_transfer(
+            source_infos[index].clone(),
+            token_info.clone(),
+            user_transfer_authority_info.clone(),
+            token_program_info.clone(),
+            token_amounts[index],
+            &[],
         )?;
+    }
+    token_mint_to(
+        pool_mint_info.clone(),
+        destination_info.clone(),
+        swap_authority_info.clone(),
+        token_program_info.clone(),
+        mint_amount,
+        &[tri_swap_info.key.as_ref(), &[tri_swap.nonce]],
+    )?;
 
-        // Dummy referrer is a placeholder to indicate that the user is not referred by anyone.
-        // If it is given in the input, skip the referral reward distribution here.
-        if user_referrer_data.referrer.to_string() != DUMMY_REFERRER_ADDRESS {
-            let referral_reward = rewards.referral_reward(amount_to_reward)?;
-            token_transfer(
-                source_reward_token_info.clone(),
-                referrer_token_info.clone(),
-                market_authority_info.clone(),
-                token_program_info.clone(),
-                referral_reward,
-                market_authority_signer_seeds,
-            )?;
-        }
+    for (index, token_info) in token_infos.iter().enumerate() {
+        let token = unpack_token_account(token_info, token_program_info.key)?;
+        tri_swap.check_reserve_amount(index, token.amount)?;
     }
 
-    SwapInfo::pack(token_swap, &mut swap_info.data.borrow_mut())?;
+    TriSwapInfo::pack(tri_swap, &mut tri_swap_info.data.borrow_mut())?;
 
     Ok(())
 }
 
-fn process_deposit(
+fn process_tri_withdraw(
     program_id: &Pubkey,
-    swap_type: SwapType,
-    token_a_amount: u64,
-    token_b_amount: u64,
-    min_mint_amount: u64,
+    pool_token_amount: u64,
+    minimum_token_amounts: [u64; TRI_POOL_TOKENS],
     accounts: &[AccountInfo],
 ) -> ProgramResult {
     let account_info_iter = &mut accounts.iter();
-    let swap_info = next_account_info(account_info_iter)?;
-    let authority_info = next_account_info(account_info_iter)?;
+    let config_info = next_account_info(account_info_iter)?;
+    let tri_swap_info = next_account_info(account_info_iter)?;
+    let swap_authority_info = next_account_info(account_info_iter)?;
     let user_transfer_authority_info = next_account_info(account_info_iter)?;
-    let source_a_info = next_account_info(account_info_iter)?;
-    let source_b_info = next_account_info(account_info_iter)?;
-    let token_a_info = next_account_info(account_info_iter)?;
-    let token_b_info = next_account_info(account_info_iter)?;
     let pool_mint_info = next_account_info(account_info_iter)?;
-    let destination_info = next_account_info(account_info_iter)?;
+    let source_info = next_account_info(account_info_iter)?;
+    let token_infos = next_account_infos(account_info_iter, TRI_POOL_TOKENS)?;
+    let destination_infos = next_account_infos(account_info_iter, TRI_POOL_TOKENS)?;
+    let admin_fee_infos = next_account_infos(account_info_iter, TRI_POOL_TOKENS)?;
     let token_program_info = next_account_info(account_info_iter)?;
 
     spl_token::check_program_account(token_program_info.key)?;
 
-    if swap_info.owner != program_id {
-        return Err(SwapError::InvalidAccountOwner.into());
-    }
-
-    let mut token_swap = SwapInfo::unpack(&swap_info.data.borrow())?;
-    utils::validate(
-        token_swap.swap_type == swap_type,
-        SwapError::IncorrectSwapType,
-    )?;
-
-    if token_swap.is_paused {
-        return Err(SwapError::IsPaused.into());
-    }
-    let swap_authority_signer_seeds = &[swap_info.key.as_ref(), &[token_swap.nonce]];
-    if *authority_info.key
-        != Pubkey::create_program_address(swap_authority_signer_seeds, program_id)?
-    {
-        return Err(SwapError::InvalidProgramAddress.into());
-    }
-    if *token_a_info.key != token_swap.token_a {
-        return Err(SwapError::IncorrectSwapAccount.into());
-    }
-    if *token_b_info.key != token_swap.token_b {
-        return Err(SwapError::IncorrectSwapAccount.into());
+    let mut tri_swap =
+        unpack_tri_swap(program_id, config_info, tri_swap_info, swap_authority_info)?;
+    for (index, token_info) in token_infos.iter().enumerate() {
+        if *token_info.key != tri_swap.tokens[index] {
+            return Err(SwapError::IncorrectSwapAccount.into());
+        }
+        if token_info.key == destination_infos[index].key {
+            return Err(SwapError::InvalidInput.into());
+        }
+        if *admin_fee_infos[index].key != tri_swap.admin_fee_keys[index] {
+            return Err(SwapError::InvalidAdmin.into());
+        }
     }
-    if *pool_mint_info.key != token_swap.pool_mint {
+    if *pool_mint_info.key != tri_swap.pool_mint {
         return Err(SwapError::IncorrectMint.into());
     }
-    if token_a_info.key == source_a_info.key {
-        return Err(SwapError::InvalidInput.into());
+
+    let pool_mint = unpack_mint(pool_mint_info, token_program_info.key)?;
+    if pool_mint.supply == 0 {
+        return Err(SwapError::EmptySupply.into());
     }
-    if token_b_info.key == source_b_info.key {
-        return Err(SwapError::InvalidInput.into());
+    let amounts = tri_swap.withdraw_amounts(pool_token_amount, pool_mint.supply)?;
+
+    // The withdrawal fee net of the admin fee stays in the pool
+    let mut amounts_out = [0u64; TRI_POOL_TOKENS];
+    let mut admin_fees = [0u64; TRI_POOL_TOKENS];
+    let mut amounts_from_pool = [0u64; TRI_POOL_TOKENS];
+    for (index, amount) in amounts.iter().enumerate() {
+        let withdraw_fee = tri_swap.fees.withdraw_fee(*amount)?;
+        admin_fees[index] = tri_swap.fees.admin_withdraw_fee(withdraw_fee)?;
+        amounts_out[index] = amount
+            .checked_sub(withdraw_fee)
+            .ok_or(SwapError::CalculationFailure)?;
+        if amounts_out[index] < minimum_token_amounts[index] {
+            return Err(SwapError::ExceededSlippage.into());
+        }
+        amounts_from_pool[index] = amounts_out[index]
+            .checked_add(admin_fees[index])
+            .ok_or(SwapError::CalculationFailure)?;
     }
-
-    let (pool_mint_amount, token_a_output, token_b_output) = token_swap
-        .pool_state
-        .buy_shares(token_a_amount, token_b_amount)?;
-
-    if pool_mint_amount < min_mint_amount {
-        return Err(SwapError::ExceededSlippage.into());
+    tri_swap.withdraw(&amounts_from_pool)?;
+
+    let swap_authority_signer_seeds = &[tri_swap_info.key.as_ref(), &[tri_swap.nonce]];
+    for (index, token_info) in token_infos.iter().enumerate() {
+        token_transfer(
+            token_info.clone(),
+            destination_infos[index].clone(),
+            swap_authority_info.clone(),
+            token_program_info.clone(),
+            amounts_out[index],
+            swap_authority_signer_seeds,
+        )?;
+        token_transfer(
+            token_info.clone(),
+            admin_fee_infos[index].clone(),
+            swap_authority_info.clone(),
+            token_program_info.clone(),
+            admin_fees[index],
+            swap_authority_signer_seeds,
+        )?;
     }
-
-    token_transfer(
-        source_a_info.clone(),
-        token_a_info.clone(),
-        user_transfer_authority_info.clone(),
-        token_program_info.clone(),
-        token_a_output,
-        &[],
-    )?;
-    token_transfer(
-        source_b_info.clone(),
-        token_b_info.clone(),
+    token_burn(
+        pool_mint_info.clone(),
+        source_info.clone(),
         user_transfer_authority_info.clone(),
         token_program_info.clone(),
-        token_b_output,
+        pool_token_amount,
         &[],
     )?;
-    token_mint_to(
-        pool_mint_info.clone(),
-        destination_info.clone(),
-        authority_info.clone(),
-        token_program_info.clone(),
-        pool_mint_amount,
-        swap_authority_signer_seeds,
-    )?;
 
-    let token_a = unpack_token_account(token_a_info, token_program_info.key)?;
-    let token_b = unpack_token_account(token_b_info, token_program_info.key)?;
-    token_swap
-        .pool_state
-        .check_reserve_amount(token_a.amount, token_b.amount)?;
-
-    let pool_mint = unpack_mint(pool_mint_info, token_program_info.key)?;
-    token_swap.pool_state.check_mint_supply(pool_mint.supply)?;
+    for (index, token_info) in token_infos.iter().enumerate() {
+        let token = unpack_token_account(token_info, token_program_info.key)?;
+        tri_swap.check_reserve_amount(index, token.amount)?;
+    }
 
-    SwapInfo::pack(token_swap, &mut swap_info.data.borrow_mut())?;
+    TriSwapInfo::pack(tri_swap, &mut tri_swap_info.data.borrow_mut())?;
 
     Ok(())
 }
 
-fn process_withdraw(
+fn process_router_instruction(
     program_id: &Pubkey,
-    swap_type: SwapType,
-    pool_token_amount: u64,
-    minimum_token_a_amount: u64,
-    minimum_token_b_amount: u64,
+    accounts: &[AccountInfo],
+    input: &[u8],
+) -> ProgramResult {
+    let instruction = RouterInstruction::unpack(input)?;
+    match instruction {
+        RouterInstruction::Route(SwapData {
+            amount_in,
+            minimum_amount_out,
+            deadline_slot,
+            output_owner,
+        }) => {
+            msg!("Instruction: Route");
+            check_deadline(deadline_slot)?;
+            utils::validate(output_owner.is_none(), SwapError::InvalidInput)?;
+            process_route(program_id, amount_in, minimum_amount_out, accounts)
+        }
+        RouterInstruction::SmartSwap(SwapData {
+            amount_in,
+            minimum_amount_out,
+            deadline_slot,
+            output_owner,
+        }) => {
+            msg!("Instruction: Smart swap");
+            check_deadline(deadline_slot)?;
+            utils::validate(output_owner.is_none(), SwapError::InvalidInput)?;
+            process_smart_swap(program_id, amount_in, minimum_amount_out, accounts)
+        }
+    }
+}
+
+/// Pool traded by a route, with the amounts computed before any token is moved
+struct RouteHopState<'a, 'b> {
+    swap_info: &'a AccountInfo<'b>,
+    swap_authority_info: &'a AccountInfo<'b>,
+    swap_source_info: &'a AccountInfo<'b>,
+    swap_destination_info: &'a AccountInfo<'b>,
+    admin_destination_info: &'a AccountInfo<'b>,
+    treasury_token_info: Option<&'a AccountInfo<'b>>,
+    referrer_fee_token_info: Option<&'a AccountInfo<'b>>,
+    token_swap: SwapInfo,
+    swap_direction: SwapDirection,
+    source_mint: Pubkey,
+    destination_mint: Pubkey,
+    price_deviation: Decimal,
+    amount_in: u64,
+    amount_out: u64,
+    swap_fees: SwapFees,
+}
+
+impl<'a, 'b> RouteHopState<'a, 'b> {
+    /// Read the accounts of the next pool of a route, check them and update the market price
+    /// of the pool
+    #[allow(clippy::too_many_arguments)]
+    fn next(
+        program_id: &Pubkey,
+        config_info: &AccountInfo,
+        config: &ConfigInfo,
+        source_info: &AccountInfo,
+        destination_info: &AccountInfo,
+        destination_owner: &Pubkey,
+        referrer_token_info: Option<&AccountInfo>,
+        token_program_id: &Pubkey,
+        accounts: &[AccountInfo],
+        swap_info: &'a AccountInfo<'b>,
+        account_info_iter: &mut std::slice::Iter<'a, AccountInfo<'b>>,
+    ) -> Result<Self, ProgramError> {
+        let swap_authority_info = next_account_info(account_info_iter)?;
+        let swap_source_info = next_account_info(account_info_iter)?;
+        let swap_destination_info = next_account_info(account_info_iter)?;
+        let admin_destination_info = next_account_info(account_info_iter)?;
+
+        if swap_info.owner != program_id {
+            return Err(ProgramError::IncorrectProgramId);
+        }
+
+        let mut token_swap = SwapInfo::unpack(&swap_info.data.borrow())?;
+        utils::validate_swap_config_key(&token_swap, config_info.key)?;
+        if token_swap.is_paused || token_swap.pause_swap {
+            return Err(SwapError::IsPaused.into());
+        }
+        if token_swap.whitelist_enabled {
+            return Err(SwapError::NotWhitelisted.into());
+        }
+        if token_swap.strict_destination_owner {
+            utils::validate_destination_owner(destination_owner, accounts)?;
+        }
+        if *swap_authority_info.key
+            != Pubkey::create_program_address(
+                &[swap_info.key.as_ref(), &[token_swap.nonce]],
+                program_id,
+            )?
+        {
+            return Err(SwapError::InvalidProgramAddress.into());
+        }
+        if source_info.key == swap_source_info.key
+            || destination_info.key == swap_destination_info.key
+        {
+            return Err(SwapError::InvalidInput.into());
+        }
+
+        let swap_direction = if *swap_source_info.key == token_swap.token_a
+            && *swap_destination_info.key == token_swap.token_b
+        {
+            SwapDirection::SellBase
+        } else if *swap_source_info.key == token_swap.token_b
+            && *swap_destination_info.key == token_swap.token_a
+        {
+            SwapDirection::SellQuote
+        } else {
+            return Err(SwapError::IncorrectSwapAccount.into());
+        };
+
+        let (source_mint, destination_mint) = match swap_direction {
+            SwapDirection::SellBase => {
+                utils::validate(
+                    token_swap.admin_fee_key_b == *admin_destination_info.key,
+                    SwapError::InvalidAdmin,
+                )?;
+                (token_swap.token_a_mint, token_swap.token_b_mint)
+            }
+            SwapDirection::SellQuote => {
+                utils::validate(
+                    token_swap.admin_fee_key_a == *admin_destination_info.key,
+                    SwapError::InvalidAdmin,
+                )?;
+                (token_swap.token_b_mint, token_swap.token_a_mint)
+            }
+        };
+
+        // A pool charges the fees of its own swap instruction, only the normal pools pay the
+        // referrer share of the trade fee
+        let treasury_token_info = next_treasury_token(
+            config,
+            &destination_mint,
+            token_program_id,
+            account_info_iter,
+        )?;
+        let referrer_fee_token_info = if token_swap.swap_type == SwapType::Normal {
+            next_referrer_fee_token(
+                referrer_token_info,
+                &token_swap.rewards,
+                &destination_mint,
+                token_program_id,
+                account_info_iter,
+            )?
+        } else {
+            None
+        };
+
+        let mid_price = match token_swap.swap_type {
+            SwapType::Normal => Some(token_swap.pool_state.mid_price()?),
+            _ => None,
+        };
+        update_pool_market_price(
+            &mut token_swap,
+            token_swap.swap_type,
+            &config.accepted_orderbook_program_id(),
+            account_info_iter,
+        )?;
+        // The deviation of the mid price from the oracle price drives the dynamic trade fee
+        let price_deviation = match mid_price {
+            Some(mid_price) => token_swap.pool_state.market_price_deviation(mid_price)?,
+            None => Decimal::zero(),
+        };
+
+        Ok(Self {
+            swap_info,
+            swap_authority_info,
+            swap_source_info,
+            swap_destination_info,
+            admin_destination_info,
+            treasury_token_info,
+            referrer_fee_token_info,
+            token_swap,
+            swap_direction,
+            source_mint,
+            destination_mint,
+            price_deviation,
+            amount_in: 0,
+            amount_out: 0,
+            swap_fees: SwapFees::default(),
+        })
+    }
+
+    /// Trade fee of the pool on the output, only the normal pools charge the dynamic trade fee
+    /// and apply the fee discount of the user
+    fn trade_fee(
+        &self,
+        receive_amount: u64,
+        fee_discount: Option<&(FeeDiscountTiers, u64)>,
+    ) -> Result<u64, ProgramError> {
+        let fees = &self.token_swap.fees;
+        if self.token_swap.swap_type != SwapType::Normal {
+            return fees.trade_fee(receive_amount);
+        }
+        discounted_trade_fee(
+            fee_discount,
+            fees.dynamic_trade_fee(receive_amount, self.price_deviation)?,
+        )
+    }
+
+    /// Output of the pool for the input after the trade fee, without updating the pool
+    fn quote(
+        &self,
+        amount_in: u64,
+        fee_discount: Option<&(FeeDiscountTiers, u64)>,
+    ) -> Result<u64, ProgramError> {
+        let receive_amount = self
+            .token_swap
+            .pool_state
+            .get_out_amount(amount_in, self.swap_direction)?;
+        let amount_out = receive_amount
+            .checked_sub(self.trade_fee(receive_amount, fee_discount)?)
+            .ok_or(SwapError::CalculationFailure)?;
+        self.token_swap
+            .check_swap_out_amount(amount_out, self.swap_direction)?;
+        Ok(amount_out)
+    }
+
+    /// Swap the input in the pool state, the tokens are moved later. Returns the DELTAFI
+    /// trade reward of the swap.
+    fn swap(
+        &mut self,
+        amount_in: u64,
+        fee_discount: Option<&(FeeDiscountTiers, u64)>,
+        current_slot: u64,
+    ) -> Result<u64, ProgramError> {
+        let swap_direction = self.swap_direction;
+        let receive_amount = self
+            .token_swap
+            .pool_state
+            .get_out_amount(amount_in, swap_direction)?;
+        if self.token_swap.check_circuit_breaker(
+            amount_in,
+            receive_amount,
+            swap_direction,
+            current_slot,
+        )? {
+            return Err(SwapError::CircuitBreakerTripped.into());
+        }
+        let swap_fees = SwapFees::new(
+            &self.token_swap.fees,
+            &self.token_swap.rewards,
+            self.trade_fee(receive_amount, fee_discount)?,
+            self.treasury_token_info.is_some(),
+            self.referrer_fee_token_info.is_some(),
+        )?;
+        let token_swap = &mut self.token_swap;
+        let amount_out = receive_amount
+            .checked_sub(swap_fees.trade_fee)
+            .ok_or(SwapError::CalculationFailure)?;
+        token_swap.check_swap_out_amount(amount_out, swap_direction)?;
+
+        let reward =
+            token_swap
+                .rewards
+                .trade_reward_u64(if swap_direction == SwapDirection::SellBase {
+                    amount_in
+                } else {
+                    amount_out
+                })?;
+
+        token_swap.pool_state.swap_with_fee(
+            amount_in,
+            swap_fees.pool_outflow(amount_out)?,
+            swap_fees.lp_fee()?,
+            swap_direction,
+        )?;
+        token_swap.record_swap(amount_in, amount_out, swap_fees.trade_fee, swap_direction)?;
+
+        self.amount_in = amount_in;
+        self.amount_out = amount_out;
+        self.swap_fees = swap_fees;
+        Ok(reward)
+    }
+
+    /// Transfer the admin, treasury and referrer fees of the swap out of the pool
+    fn transfer_fees(&self, token_program_info: &AccountInfo<'b>) -> ProgramResult {
+        self.swap_fees.transfer(
+            self.swap_destination_info,
+            self.admin_destination_info,
+            self.treasury_token_info,
+            self.referrer_fee_token_info,
+            self.swap_authority_info,
+            token_program_info,
+            &[self.swap_info.key.as_ref(), &[self.token_swap.nonce]],
+        )
+    }
+
+    /// Transfer the output of the swap out of the pool
+    fn transfer_out(
+        &self,
+        destination_info: &AccountInfo<'b>,
+        token_program_info: &AccountInfo<'b>,
+    ) -> ProgramResult {
+        token_transfer(
+            self.swap_destination_info.clone(),
+            destination_info.clone(),
+            self.swap_authority_info.clone(),
+            token_program_info.clone(),
+            self.amount_out,
+            &[self.swap_info.key.as_ref(), &[self.token_swap.nonce]],
+        )
+    }
+
+    /// Check the reserves against the token balances after the transfers and save the pool
+    fn check_and_save(self, token_program_id: &Pubkey) -> ProgramResult {
+        let swap_source_token = unpack_token_account(self.swap_source_info, token_program_id)?;
+        let swap_dest_token = unpack_token_account(self.swap_destination_info, token_program_id)?;
+        if self.swap_direction == SwapDirection::SellBase {
+            self.token_swap
+                .pool_state
+                .check_reserve_amount(swap_source_token.amount, swap_dest_token.amount)?;
+        } else {
+            self.token_swap
+                .pool_state
+                .check_reserve_amount(swap_dest_token.amount, swap_source_token.amount)?;
+        }
+        self.token_swap.pool_state.check_share_price_floor()?;
+        self.token_swap.update_virtual_price()?;
+
+        SwapInfo::pack(self.token_swap, &mut self.swap_info.data.borrow_mut())
+    }
+}
+
+fn process_route(
+    program_id: &Pubkey,
+    amount_in: u64,
+    minimum_amount_out: u64,
     accounts: &[AccountInfo],
 ) -> ProgramResult {
     let account_info_iter = &mut accounts.iter();
-    let swap_info = next_account_info(account_info_iter)?;
-    let authority_info = next_account_info(account_info_iter)?;
+    let config_info = next_account_info(account_info_iter)?;
+    let market_authority_info = next_account_info(account_info_iter)?;
     let user_transfer_authority_info = next_account_info(account_info_iter)?;
-    let pool_mint_info = next_account_info(account_info_iter)?;
     let source_info = next_account_info(account_info_iter)?;
-    let token_a_info = next_account_info(account_info_iter)?;
-    let token_b_info = next_account_info(account_info_iter)?;
-    let dest_token_a_info = next_account_info(account_info_iter)?;
-    let dest_token_b_info = next_account_info(account_info_iter)?;
-    let admin_fee_dest_a_info = next_account_info(account_info_iter)?;
-    let admin_fee_dest_b_info = next_account_info(account_info_iter)?;
+    let destination_info = next_account_info(account_info_iter)?;
+    let reward_token_info = next_account_info(account_info_iter)?;
+    let source_reward_token_info = next_account_info(account_info_iter)?;
     let token_program_info = next_account_info(account_info_iter)?;
+
     spl_token::check_program_account(token_program_info.key)?;
 
-    if swap_info.owner != program_id {
-        return Err(SwapError::InvalidAccountOwner.into());
+    if config_info.owner != program_id {
+        return Err(ProgramError::IncorrectProgramId);
+    }
+    if source_info.key == destination_info.key {
+        return Err(SwapError::InvalidInput.into());
+    }
+
+    let config = ConfigInfo::unpack(&config_info.data.borrow())?;
+    let market_authority_signer_seeds = &[config_info.key.as_ref(), &[config.bump_seed]];
+    if *market_authority_info.key
+        != Pubkey::create_program_address(market_authority_signer_seeds, program_id)?
+    {
+        return Err(SwapError::InvalidProgramAddress.into());
+    }
+
+    let token_program_id = *token_program_info.key;
+    let source_token = unpack_token_account(source_info, &token_program_id)?;
+    let destination_token = unpack_token_account(destination_info, &token_program_id)?;
+    {
+        let reward_token = unpack_token_account(reward_token_info, &token_program_id)?;
+        let source_reward_token =
+            unpack_token_account(source_reward_token_info, &token_program_id)?;
+        validate_reward_token_accounts(
+            &config,
+            market_authority_info.key,
+            &source_reward_token,
+            &reward_token,
+        )?;
     }
 
-    let mut token_swap = SwapInfo::unpack(&swap_info.data.borrow())?;
-    utils::validate(token_swap.swap_type == swap_type, SwapError::InvalidAccount)?;
+    // The fee discount and the referrer of the user apply to every pool of the route
+    let clock = &Clock::get()?;
+    let fee_discount = next_fee_discount(
+        program_id,
+        config_info.key,
+        user_transfer_authority_info.key,
+        clock,
+        account_info_iter,
+    )?;
+    let referrer_token_info = next_router_referrer_token(
+        program_id,
+        config_info.key,
+        &source_token.owner,
+        &token_program_id,
+        account_info_iter,
+    )?;
+
+    // Quote every pool of the route first, the output of a pool is the input of the next one
+    let mut hops: Vec<RouteHopState> = Vec::with_capacity(MAX_ROUTE_HOPS);
+    let mut current_mint = source_token.mint;
+    let mut current_amount = amount_in;
+    let mut amount_to_reward = 0u64;
+    while let Some(swap_info) = account_info_iter.next() {
+        if hops.len() == MAX_ROUTE_HOPS {
+            return Err(SwapError::InvalidInput.into());
+        }
+        if hops.iter().any(|hop| hop.swap_info.key == swap_info.key) {
+            return Err(SwapError::InvalidInput.into());
+        }
+        let mut hop = RouteHopState::next(
+            program_id,
+            config_info,
+            &config,
+            source_info,
+            destination_info,
+            &destination_token.owner,
+            referrer_token_info,
+            &token_program_id,
+            accounts,
+            swap_info,
+            account_info_iter,
+        )?;
+        if hop.source_mint != current_mint {
+            return Err(SwapError::IncorrectMint.into());
+        }
+
+        amount_to_reward = amount_to_reward
+            .checked_add(hop.swap(current_amount, fee_discount.as_ref(), clock.slot)?)
+            .ok_or(SwapError::CalculationFailure)?;
 
-    let swap_authority_signer_seeds = &[swap_info.key.as_ref(), &[token_swap.nonce]];
-    if *authority_info.key
-        != Pubkey::create_program_address(swap_authority_signer_seeds, program_id)?
-    {
-        return Err(SwapError::InvalidProgramAddress.into());
-    }
-    if *token_a_info.key != token_swap.token_a {
-        return Err(SwapError::IncorrectSwapAccount.into());
-    }
-    if *token_b_info.key != token_swap.token_b {
-        return Err(SwapError::IncorrectSwapAccount.into());
-    }
-    if token_a_info.key == dest_token_a_info.key {
-        return Err(SwapError::InvalidInput.into());
+        current_mint = hop.destination_mint;
+        current_amount = hop.amount_out;
+        hops.push(hop);
     }
-    if token_b_info.key == dest_token_b_info.key {
+
+    if hops.is_empty() {
         return Err(SwapError::InvalidInput.into());
     }
-    if *pool_mint_info.key != token_swap.pool_mint {
+    if destination_token.mint != current_mint {
         return Err(SwapError::IncorrectMint.into());
     }
-    if *admin_fee_dest_a_info.key != token_swap.admin_fee_key_a {
-        return Err(SwapError::InvalidAdmin.into());
-    }
-    if *admin_fee_dest_b_info.key != token_swap.admin_fee_key_b {
-        return Err(SwapError::InvalidAdmin.into());
+    // Only the output of the whole route is checked against the slippage
+    if current_amount < minimum_amount_out {
+        return Err(SwapError::ExceededSlippage.into());
     }
 
-    let pool_mint = unpack_mint(pool_mint_info, token_program_info.key)?;
-    if pool_mint.supply == 0 {
-        return Err(SwapError::EmptySupply.into());
+    for (index, hop) in hops.iter().enumerate() {
+        if index == 0 {
+            token_transfer(
+                source_info.clone(),
+                hop.swap_source_info.clone(),
+                user_transfer_authority_info.clone(),
+                token_program_info.clone(),
+                amount_in,
+                &[],
+            )?;
+        } else {
+            hops[index - 1].transfer_out(hop.swap_source_info, token_program_info)?;
+        }
+        hop.transfer_fees(token_program_info)?;
     }
 
-    let (base_out_amount, quote_out_amount) = token_swap.pool_state.sell_shares(
-        pool_token_amount,
-        minimum_token_a_amount,
-        minimum_token_b_amount,
-    )?;
-
-    let fees = &token_swap.fees;
-    let withdraw_fee_base = fees.withdraw_fee(base_out_amount)?;
-    let admin_fee_base = fees.admin_withdraw_fee(withdraw_fee_base)?;
-    let base_out_amount = base_out_amount
-        .checked_sub(withdraw_fee_base)
-        .ok_or(SwapError::CalculationFailure)?;
-
-    let withdraw_fee_quote = fees.withdraw_fee(quote_out_amount)?;
-    let admin_fee_quote = fees.admin_withdraw_fee(withdraw_fee_quote)?;
-    let quote_out_amount = quote_out_amount
-        .checked_sub(withdraw_fee_quote)
-        .ok_or(SwapError::CalculationFailure)?;
-
-    token_swap.pool_state.collect_trade_fee(
-        withdraw_fee_base - admin_fee_base,
-        withdraw_fee_quote - admin_fee_quote,
-    )?;
-
-    token_transfer(
-        token_a_info.clone(),
-        dest_token_a_info.clone(),
-        authority_info.clone(),
-        token_program_info.clone(),
-        base_out_amount,
-        swap_authority_signer_seeds,
-    )?;
+    hops[hops.len() - 1].transfer_out(destination_info, token_program_info)?;
     token_transfer(
-        token_a_info.clone(),
-        admin_fee_dest_a_info.clone(),
-        authority_info.clone(),
-        token_program_info.clone(),
-        admin_fee_base,
-        swap_authority_signer_seeds,
-    )?;
-    token_transfer(
-        token_b_info.clone(),
-        dest_token_b_info.clone(),
-        authority_info.clone(),
-        token_program_info.clone(),
-        quote_out_amount,
-        swap_authority_signer_seeds,
-    )?;
-    token_transfer(
-        token_b_info.clone(),
-        admin_fee_dest_b_info.clone(),
-        authority_info.clone(),
-        token_program_info.clone(),
-        admin_fee_quote,
-        swap_authority_signer_seeds,
-    )?;
-    token_burn(
-        pool_mint_info.clone(),
-        source_info.clone(),
-        user_transfer_authority_info.clone(),
+        source_reward_token_info.clone(),
+        reward_token_info.clone(),
+        market_authority_info.clone(),
         token_program_info.clone(),
-        pool_token_amount,
-        &[],
+        amount_to_reward,
+        market_authority_signer_seeds,
     )?;
 
-    let token_a = unpack_token_account(token_a_info, token_program_info.key)?;
-    let token_b = unpack_token_account(token_b_info, token_program_info.key)?;
-    token_swap
-        .pool_state
-        .check_reserve_amount(token_a.amount, token_b.amount)?;
-
-    let pool_mint = unpack_mint(pool_mint_info, token_program_info.key)?;
-    token_swap.pool_state.check_mint_supply(pool_mint.supply)?;
-
-    SwapInfo::pack(token_swap, &mut swap_info.data.borrow_mut())?;
+    for hop in hops {
+        hop.check_and_save(&token_program_id)?;
+    }
 
     Ok(())
 }
 
-fn process_set_referrer(program_id: &Pubkey, accounts: &[AccountInfo]) -> ProgramResult {
+fn process_smart_swap(
+    program_id: &Pubkey,
+    amount_in: u64,
+    minimum_amount_out: u64,
+    accounts: &[AccountInfo],
+) -> ProgramResult {
     let account_info_iter = &mut accounts.iter();
     let config_info = next_account_info(account_info_iter)?;
-    let owner_info = next_account_info(account_info_iter)?;
-    let user_referrer_info = next_account_info(account_info_iter)?;
-    let referrer_token_info = next_account_info(account_info_iter)?;
-    let rent = &Rent::from_account_info(next_account_info(account_info_iter)?)?;
+    let market_authority_info = next_account_info(account_info_iter)?;
+    let user_transfer_authority_info = next_account_info(account_info_iter)?;
+    let source_info = next_account_info(account_info_iter)?;
+    let destination_info = next_account_info(account_info_iter)?;
+    let reward_token_info = next_account_info(account_info_iter)?;
+    let source_reward_token_info = next_account_info(account_info_iter)?;
     let token_program_info = next_account_info(account_info_iter)?;
 
     spl_token::check_program_account(token_program_info.key)?;
 
-    utils::validate(
-        user_referrer_info.owner == program_id && config_info.owner == program_id,
-        SwapError::InvalidAccountOwner,
-    )?;
-
-    let expected_user_referrer_data_pubkey =
-        get_referrer_data_pubkey(owner_info.key, config_info.key, program_id)?;
-    utils::validate(
-        expected_user_referrer_data_pubkey == *user_referrer_info.key,
-        SwapError::InvalidAccountOwner,
-    )?;
+    if config_info.owner != program_id {
+        return Err(ProgramError::IncorrectProgramId);
+    }
+    if source_info.key == destination_info.key {
+        return Err(SwapError::InvalidInput.into());
+    }
 
     let config = ConfigInfo::unpack(&config_info.data.borrow())?;
+    let market_authority_signer_seeds = &[config_info.key.as_ref(), &[config.bump_seed]];
+    if *market_authority_info.key
+        != Pubkey::create_program_address(market_authority_signer_seeds, program_id)?
+    {
+        return Err(SwapError::InvalidProgramAddress.into());
+    }
 
-    // the valid address is either a valid DELFI token accout or the dummy referrer address
-    if referrer_token_info.key.to_string() != DUMMY_REFERRER_ADDRESS {
-        let referrer_token = unpack_token_account(referrer_token_info, token_program_info.key)?;
-        utils::validate(
-            referrer_token.mint == config.deltafi_mint,
-            SwapError::ExpectedMint,
-        )?;
-        // The owner should not set the referrer to self.
-        utils::validate(
-            referrer_token.owner != *owner_info.key,
-            SwapError::InvalidAccountOwner,
+    let token_program_id = *token_program_info.key;
+    let source_token = unpack_token_account(source_info, &token_program_id)?;
+    let destination_token = unpack_token_account(destination_info, &token_program_id)?;
+    {
+        let reward_token = unpack_token_account(reward_token_info, &token_program_id)?;
+        let source_reward_token =
+            unpack_token_account(source_reward_token_info, &token_program_id)?;
+        validate_reward_token_accounts(
+            &config,
+            market_authority_info.key,
+            &source_reward_token,
+            &reward_token,
         )?;
     }
 
-    assert_rent_exempt(rent, user_referrer_info)?;
-    let mut user_referrer_data = assert_uninitialized::<UserReferrerData>(user_referrer_info)?;
-    if !owner_info.is_signer {
-        return Err(SwapError::InvalidSigner.into());
+    // The fee discount and the referrer of the user apply to both pools
+    let clock = &Clock::get()?;
+    let fee_discount = next_fee_discount(
+        program_id,
+        config_info.key,
+        user_transfer_authority_info.key,
+        clock,
+        account_info_iter,
+    )?;
+    let referrer_token_info = next_router_referrer_token(
+        program_id,
+        config_info.key,
+        &source_token.owner,
+        &token_program_id,
+        account_info_iter,
+    )?;
+
+    let normal_pool = RouteHopState::next(
+        program_id,
+        config_info,
+        &config,
+        source_info,
+        destination_info,
+        &destination_token.owner,
+        referrer_token_info,
+        &token_program_id,
+        accounts,
+        next_account_info(account_info_iter)?,
+        account_info_iter,
+    )?;
+    let stable_pool = RouteHopState::next(
+        program_id,
+        config_info,
+        &config,
+        source_info,
+        destination_info,
+        &destination_token.owner,
+        referrer_token_info,
+        &token_program_id,
+        accounts,
+        next_account_info(account_info_iter)?,
+        account_info_iter,
+    )?;
+    if account_info_iter.next().is_some() {
+        return Err(SwapError::InvalidInput.into());
+    }
+    if normal_pool.token_swap.swap_type != SwapType::Normal
+        || stable_pool.token_swap.swap_type == SwapType::Normal
+    {
+        return Err(SwapError::IncorrectSwapAccount.into());
+    }
+    let mut pools = vec![normal_pool, stable_pool];
+    for pool in &pools {
+        if pool.source_mint != source_token.mint || pool.destination_mint != destination_token.mint
+        {
+            return Err(SwapError::IncorrectMint.into());
+        }
     }
 
-    user_referrer_data.is_initialized = true;
-    user_referrer_data.config_key = *config_info.key;
-    user_referrer_data.owner = *owner_info.key;
-    user_referrer_data.referrer = *referrer_token_info.key;
+    // A pool failing to quote its share, e.g. over its swap out limit, gets no share
+    let normal_amount_in = split_amount_in(
+        amount_in,
+        SMART_SWAP_SEARCH_STEPS,
+        |amount| pools[0].quote(amount, fee_discount.as_ref()).unwrap_or(0),
+        |amount| pools[1].quote(amount, fee_discount.as_ref()).unwrap_or(0),
+    );
+    let stable_amount_in = amount_in
+        .checked_sub(normal_amount_in)
+        .ok_or(SwapError::CalculationFailure)?;
 
-    UserReferrerData::pack(
-        user_referrer_data,
-        &mut user_referrer_info.data.borrow_mut(),
+    let mut amount_to_reward = 0u64;
+    let mut amount_out = 0u64;
+    for (pool, pool_amount_in) in pools.iter_mut().zip(&[normal_amount_in, stable_amount_in]) {
+        if *pool_amount_in == 0 {
+            continue;
+        }
+        amount_to_reward = amount_to_reward
+            .checked_add(pool.swap(*pool_amount_in, fee_discount.as_ref(), clock.slot)?)
+            .ok_or(SwapError::CalculationFailure)?;
+        amount_out = amount_out
+            .checked_add(pool.amount_out)
+            .ok_or(SwapError::CalculationFailure)?;
+    }
+    // Only the total output of the pools is checked against the slippage
+    if amount_out < minimum_amount_out {
+        return Err(SwapError::ExceededSlippage.into());
+    }
+
+    pools.retain(|pool| pool.amount_in > 0);
+    for pool in &pools {
+        token_transfer(
+            source_info.clone(),
+            pool.swap_source_info.clone(),
+            user_transfer_authority_info.clone(),
+            token_program_info.clone(),
+            pool.amount_in,
+            &[],
+        )?;
+        pool.transfer_fees(token_program_info)?;
+        pool.transfer_out(destination_info, token_program_info)?;
+    }
+    token_transfer(
+        source_reward_token_info.clone(),
+        reward_token_info.clone(),
+        market_authority_info.clone(),
+        token_program_info.clone(),
+        amount_to_reward,
+        market_authority_signer_seeds,
     )?;
 
+    for pool in pools {
+        pool.check_and_save(&token_program_id)?;
+    }
+
     Ok(())
 }
 
-fn process_stable_swap_instruction(
+fn process_farm_instruction(
     program_id: &Pubkey,
     accounts: &[AccountInfo],
     input: &[u8],
 ) -> ProgramResult {
-    let instruction = StableSwapInstruction::unpack(input)?;
+    let instruction = FarmInstruction::unpack(input)?;
     match instruction {
-        StableSwapInstruction::Initialize(StableInitializeData {
-            nonce,
-            slope,
-            token_a_decimals,
-            token_b_decimals,
-            token_a_amount,
-            token_b_amount,
-        }) => {
-            msg!("Instruction: Stable Initialize");
-            process_stable_initialize(
-                program_id,
-                nonce,
-                slope,
-                token_a_decimals,
-                token_b_decimals,
-                token_a_amount,
-                token_b_amount,
-                accounts,
-            )
-        }
-        StableSwapInstruction::Swap(SwapData {
-            amount_in,
-            minimum_amount_out,
-        }) => {
-            msg!("Instruction: Stable Swap");
-            process_stable_swap(program_id, amount_in, minimum_amount_out, accounts)
-        }
-        StableSwapInstruction::SwapV2(SwapData {
-            amount_in,
-            minimum_amount_out,
-        }) => {
-            msg!("Instruction: Stable SwapV2");
-            process_stable_swap_v2(program_id, amount_in, minimum_amount_out, accounts)
-        }
-        StableSwapInstruction::Deposit(DepositData {
-            token_a_amount,
-            token_b_amount,
-            min_mint_amount,
+        FarmInstruction::Initialize(FarmInitializeData {
+            fee_numerator,
+            fee_denominator,
+            rewards_numerator,
+            rewards_denominator,
+            bump_seed,
         }) => {
-            msg!("Instruction: Stable Deposit");
-            process_deposit(
+            msg!("Instruction: Farm initialize");
+            process_farm_initialize(
                 program_id,
-                SwapType::Stable,
-                token_a_amount,
-                token_b_amount,
-                min_mint_amount,
+                fee_numerator,
+                fee_denominator,
+                rewards_numerator,
+                rewards_denominator,
+                bump_seed,
                 accounts,
             )
         }
-        StableSwapInstruction::Withdraw(WithdrawData {
-            pool_token_amount,
-            minimum_token_a_amount,
-            minimum_token_b_amount,
+        FarmInstruction::InitializeFarmUser => {
+            msg!("Instruction: Farm user initialize");
+            process_farm_user_initialize(program_id, accounts)
+        }
+        FarmInstruction::Claim => {
+            msg!("Instruction: Farm claim");
+            process_farm_claim(program_id, accounts)
+        }
+        FarmInstruction::Refresh => {
+            msg!("Instruction: Farm refresh");
+            process_farm_refresh(program_id, accounts)
+        }
+        FarmInstruction::Deposit(FarmDepositData { amount, lock_tier }) => {
+            msg!("Instruction: Farm deposit");
+            process_farm_deposit(program_id, amount, lock_tier, accounts)
+        }
+        FarmInstruction::Withdraw(FarmWithdrawData { amount }) => {
+            msg!("Instruction: Farm withdraw");
+            process_farm_withdraw(program_id, amount, accounts)
+        }
+        FarmInstruction::SetRewardSplit(FarmRewardSplitData { percentages }) => {
+            msg!("Instruction: Farm set reward split");
+            process_farm_set_reward_split(program_id, &percentages, accounts)
+        }
+        FarmInstruction::Checkpoint => {
+            msg!("Instruction: Farm checkpoint");
+            process_farm_checkpoint(program_id, accounts)
+        }
+        FarmInstruction::FundRewardToken(FarmFundRewardTokenData { index, amount }) => {
+            msg!("Instruction: Farm fund reward token");
+            process_farm_fund_reward_token(program_id, index, amount, accounts)
+        }
+        FarmInstruction::InitializeFarmUserRewardTokens => {
+            msg!("Instruction: Farm user reward tokens initialize");
+            process_farm_user_reward_tokens_initialize(program_id, accounts)
+        }
+        FarmInstruction::ClaimRewardTokens => {
+            msg!("Instruction: Farm claim reward tokens");
+            process_farm_claim_reward_tokens(program_id, accounts)
+        }
+        FarmInstruction::Compound(FarmCompoundData { min_mint_amount }) => {
+            msg!("Instruction: Farm compound");
+            process_farm_compound(program_id, min_mint_amount, accounts)
+        }
+        FarmInstruction::TransferPosition => {
+            msg!("Instruction: Farm transfer position");
+            process_farm_transfer_position(program_id, accounts)
+        }
+        FarmInstruction::ClaimMulti(FarmClaimMultiData { farm_count }) => {
+            msg!("Instruction: Farm claim multi");
+            process_farm_claim_multi(program_id, farm_count, accounts)
+        }
+        FarmInstruction::InitializeStaking(FarmInitializeData {
+            fee_numerator,
+            fee_denominator,
+            rewards_numerator,
+            rewards_denominator,
+            bump_seed,
         }) => {
-            msg!("Instruction: Stable Withdraw");
-            process_withdraw(
+            msg!("Instruction: Farm staking initialize");
+            process_farm_staking_initialize(
                 program_id,
-                SwapType::Stable,
-                pool_token_amount,
-                minimum_token_a_amount,
-                minimum_token_b_amount,
+                fee_numerator,
+                fee_denominator,
+                rewards_numerator,
+                rewards_denominator,
+                bump_seed,
                 accounts,
             )
         }
+        FarmInstruction::SetCompoundDelegate(FarmCompoundDelegateData { compound_delegate }) => {
+            msg!("Instruction: Farm set compound delegate");
+            process_farm_set_compound_delegate(program_id, &compound_delegate, accounts)
+        }
     }
 }
 
-fn process_stable_initialize(
+fn process_farm_initialize(
     program_id: &Pubkey,
-    nonce: u8,
-    slope: u64,
-    token_a_decimals: u8,
-    token_b_decimals: u8,
-    token_a_amount: u64,
-    token_b_amount: u64,
+    fee_numerator: u64,
+    fee_denominator: u64,
+    apr_numerator: u64,
+    apr_denominator: u64,
+    bump_seed: u8,
     accounts: &[AccountInfo],
 ) -> ProgramResult {
     let account_info_iter = &mut accounts.iter();
     let config_info = next_account_info(account_info_iter)?;
     let swap_info = next_account_info(account_info_iter)?;
-    let swap_authority_info = next_account_info(account_info_iter)?;
-    let admin_fee_a_info = next_account_info(account_info_iter)?;
-    let admin_fee_b_info = next_account_info(account_info_iter)?;
-    let token_a_info = next_account_info(account_info_iter)?;
-    let token_b_info = next_account_info(account_info_iter)?;
-    let pool_mint_info = next_account_info(account_info_iter)?;
-    let destination_info = next_account_info(account_info_iter)?;
+    let farm_pool_info = next_account_info(account_info_iter)?;
+    let authority_info = next_account_info(account_info_iter)?;
+    let pool_token_info = next_account_info(account_info_iter)?;
     let admin_info = next_account_info(account_info_iter)?;
     let rent = &Rent::from_account_info(next_account_info(account_info_iter)?)?;
-    let token_program_info = next_account_info(account_info_iter)?;
 
-    spl_token::check_program_account(token_program_info.key)?;
+    utils::validate(
+        swap_info.owner == program_id,
+        SwapError::InvalidAccountOwner,
+    )?;
+    let token_swap = SwapInfo::unpack(&swap_info.data.borrow())?;
 
-    utils::validate(swap_info.is_signer, SwapError::InvalidSigner)?;
+    initialize_farm_pool(
+        program_id,
+        config_info,
+        farm_pool_info,
+        authority_info,
+        pool_token_info,
+        admin_info,
+        rent,
+        token_swap.pool_mint,
+        fee_numerator,
+        fee_denominator,
+        apr_numerator,
+        apr_denominator,
+        bump_seed,
+    )
+}
 
-    assert_rent_exempt(rent, swap_info)?;
-    assert_uninitialized::<SwapInfo>(swap_info)?;
-    if config_info.owner != program_id || swap_info.owner != program_id {
-        return Err(SwapError::InvalidAccountOwner.into());
-    }
+fn process_farm_staking_initialize(
+    program_id: &Pubkey,
+    fee_numerator: u64,
+    fee_denominator: u64,
+    apr_numerator: u64,
+    apr_denominator: u64,
+    bump_seed: u8,
+    accounts: &[AccountInfo],
+) -> ProgramResult {
+    let account_info_iter = &mut accounts.iter();
+    let config_info = next_account_info(account_info_iter)?;
+    let farm_pool_info = next_account_info(account_info_iter)?;
+    let authority_info = next_account_info(account_info_iter)?;
+    let pool_token_info = next_account_info(account_info_iter)?;
+    let admin_info = next_account_info(account_info_iter)?;
+    let rent = &Rent::from_account_info(next_account_info(account_info_iter)?)?;
 
-    // Extract only fees and rewards to reduce stack usage
-    let (fees, rewards) = {
-        let config = ConfigInfo::unpack(&config_info.data.borrow())?;
-        is_admin(&config.admin_key, admin_info)?;
-        (config.fees, config.rewards)
-    };
+    utils::validate(
+        config_info.owner == program_id,
+        SwapError::InvalidAccountOwner,
+    )?;
+    let config = ConfigInfo::unpack(&config_info.data.borrow())?;
 
-    let swap_authority_signer_seeds = &[swap_info.key.as_ref(), &[nonce]];
+    initialize_farm_pool(
+        program_id,
+        config_info,
+        farm_pool_info,
+        authority_info,
+        pool_token_info,
+        admin_info,
+        rent,
+        config.deltafi_mint,
+        fee_numerator,
+        fee_denominator,
+        apr_numerator,
+        apr_denominator,
+        bump_seed,
+    )
+}
+
+/// Initialize a farm pool staking the pool mint, the LP token of a swap or DELTAFI
+fn initialize_farm_pool(
+    program_id: &Pubkey,
+    config_info: &AccountInfo,
+    farm_pool_info: &AccountInfo,
+    authority_info: &AccountInfo,
+    pool_token_info: &AccountInfo,
+    admin_info: &AccountInfo,
+    rent: &Rent,
+    pool_mint: Pubkey,
+    fee_numerator: u64,
+    fee_denominator: u64,
+    apr_numerator: u64,
+    apr_denominator: u64,
+    bump_seed: u8,
+) -> ProgramResult {
     utils::validate(
-        *swap_authority_info.key
-            == Pubkey::create_program_address(swap_authority_signer_seeds, program_id)?,
-        SwapError::InvalidProgramAddress,
+        farm_pool_info.owner == program_id && config_info.owner == program_id,
+        SwapError::InvalidAccountOwner,
     )?;
 
-    let token_program_id = *token_program_info.key;
-    let token_a = unpack_token_account(token_a_info, &token_program_id)?;
-    let token_b = unpack_token_account(token_b_info, &token_program_id)?;
+    utils::validate(farm_pool_info.is_signer, SwapError::InvalidSigner)?;
+    let config = ConfigInfo::unpack(&config_info.data.borrow())?;
+    is_admin(&config.admin_key, admin_info)?;
+
+    if pool_token_info.owner != &spl_token::ID {
+        return Err(SwapError::InvalidAccountOwner.into());
+    }
+
+    assert_rent_exempt(rent, farm_pool_info)?;
+    assert_uninitialized::<FarmInfo>(farm_pool_info)?;
 
-    // This block does validation and release the memory on stack.
+    if *authority_info.key
+        != Pubkey::create_program_address(&[farm_pool_info.key.as_ref(), &[bump_seed]], program_id)?
     {
-        let destination = unpack_token_account(destination_info, &token_program_id)?;
-        let pool_mint = unpack_mint(pool_mint_info, &token_program_id)?;
-        let admin_fee_key_a = unpack_token_account(admin_fee_a_info, &token_program_id)?;
-        let admin_fee_key_b = unpack_token_account(admin_fee_b_info, &token_program_id)?;
+        return Err(SwapError::InvalidAccountOwner.into());
+    }
+    let pool_token = unpack_token_account(pool_token_info, &spl_token::id())?;
+    if pool_token.mint != pool_mint {
+        return Err(SwapError::IncorrectMint.into());
+    }
+    if pool_token.owner != *authority_info.key {
+        return Err(SwapError::InvalidOwner.into());
+    }
+    if pool_token.delegate.is_some() {
+        return Err(SwapError::InvalidDelegate.into());
+    }
+    if pool_token.close_authority.is_some() {
+        return Err(SwapError::InvalidCloseAuthority.into());
+    }
 
-        utils::validate(
-            *swap_authority_info.key == token_a.owner,
-            SwapError::InvalidOwner,
-        )?;
-        utils::validate(
-            *swap_authority_info.key == token_b.owner,
-            SwapError::InvalidOwner,
-        )?;
+    FarmInfo::pack(
+        FarmInfo {
+            is_initialized: true,
+            bump_seed,
+            config_key: *config_info.key,
+            pool_mint,
+            pool_token: *pool_token_info.key,
+            reserved_amount: 0,
+            fee_numerator,
+            fee_denominator,
+            apr_numerator,
+            apr_denominator,
+            ..FarmInfo::default()
+        },
+        &mut farm_pool_info.data.borrow_mut(),
+    )?;
 
-        utils::validate(
-            *swap_authority_info.key != destination.owner,
-            SwapError::InvalidOutputOwner,
-        )?;
-        utils::validate(
-            *swap_authority_info.key != admin_fee_key_a.owner,
-            SwapError::InvalidOutputOwner,
-        )?;
-        utils::validate(
-            *swap_authority_info.key != admin_fee_key_b.owner,
-            SwapError::InvalidOutputOwner,
-        )?;
-        utils::validate(token_a.mint != token_b.mint, SwapError::RepeatedMint)?;
-        utils::validate(
-            token_a.mint == admin_fee_key_a.mint,
-            SwapError::InvalidAdmin,
-        )?;
-        utils::validate(
-            token_b.mint == admin_fee_key_b.mint,
-            SwapError::InvalidAdmin,
-        )?;
+    Ok(())
+}
 
-        utils::validate(
-            token_a.amount == token_a_amount,
-            SwapError::InconsistentInitialPoolTokenBalance,
-        )?;
-        utils::validate(
-            token_b.amount == token_b_amount,
-            SwapError::InconsistentInitialPoolTokenBalance,
-        )?;
+fn process_farm_user_initialize(program_id: &Pubkey, accounts: &[AccountInfo]) -> ProgramResult {
+    let account_info_iter = &mut accounts.iter();
+    let config_info = next_account_info(account_info_iter)?;
+    let farm_pool_info = next_account_info(account_info_iter)?;
+    let farm_user_info = next_account_info(account_info_iter)?;
+    let owner_info = next_account_info(account_info_iter)?;
+    let rent = &Rent::from_account_info(next_account_info(account_info_iter)?)?;
+    let clock = &Clock::get()?;
+
+    utils::validate(
+        farm_user_info.owner == program_id
+            && farm_pool_info.owner == program_id
+            && config_info.owner == program_id,
+        SwapError::InvalidAccountOwner,
+    )?;
 
-        utils::validate(!token_a.delegate.is_some(), SwapError::InvalidDelegate)?;
-        utils::validate(!token_b.delegate.is_some(), SwapError::InvalidDelegate)?;
+    let mut farm_info = FarmInfo::unpack(&farm_pool_info.data.borrow_mut())?;
+    utils::validate_farm_config_key(&farm_info, config_info.key)?;
 
-        utils::validate(
-            !token_a.close_authority.is_some(),
-            SwapError::InvalidCloseAuthority,
-        )?;
-        utils::validate(
-            !token_b.close_authority.is_some(),
-            SwapError::InvalidCloseAuthority,
-        )?;
+    let farm_user_pubkey = get_farm_user_pubkey(owner_info.key, farm_pool_info.key, program_id)?;
+    utils::validate(
+        *farm_user_info.key == farm_user_pubkey,
+        SwapError::InvalidAccountOwner,
+    )?;
 
-        if pool_mint.mint_authority.is_some()
-            && *swap_authority_info.key != pool_mint.mint_authority.unwrap()
-        {
-            return Err(SwapError::InvalidOwner.into());
-        }
-        if pool_mint.freeze_authority.is_some() {
-            return Err(SwapError::InvalidFreezeAuthority.into());
-        }
-        if pool_mint.supply != 0 {
-            return Err(SwapError::InvalidSupply.into());
-        }
+    assert_rent_exempt(rent, farm_user_info)?;
+    let mut farm_user = assert_uninitialized::<FarmUser>(farm_user_info)?;
+
+    if !owner_info.is_signer {
+        return Err(SwapError::InvalidSigner.into());
+    }
+
+    farm_user.init(
+        *config_info.key,
+        *farm_pool_info.key,
+        *owner_info.key,
+        FarmPosition::new(*farm_pool_info.key, clock.unix_timestamp)?,
+    );
+    farm_user.position.checkpoint_apr(&farm_info)?;
+    FarmUser::pack_any(farm_user, &mut farm_user_info.data.borrow_mut())?;
+
+    if farm_info.is_farm_user_registry_enabled {
+        register_farm_user(
+            program_id,
+            farm_pool_info,
+            &mut farm_info,
+            farm_user_info.key,
+            rent,
+            account_info_iter,
+        )?;
+        FarmInfo::pack(farm_info, &mut farm_pool_info.data.borrow_mut())?;
     }
 
-    let mut pool_state = PoolState::new(InitPoolStateParams {
-        market_price: Decimal::one(),
-        slope: Decimal::from_scaled_val(slope.into()),
-        base_reserve: Decimal::zero(),
-        quote_reserve: Decimal::zero(),
-        total_supply: 0,
-        last_market_price: Decimal::one(),
-        last_valid_market_price_slot: 0,
-    });
-    pool_state.set_market_price(token_a_decimals, token_b_decimals, Decimal::one())?;
+    Ok(())
+}
 
-    let (mint_amount, token_a_output, token_b_output) =
-        pool_state.buy_shares(token_a.amount, token_b.amount)?;
+/// Append the farm user to the current page of the registry, the first farm user of a page
+/// opens the page paid by the payer
+#[inline(never)]
+fn register_farm_user<'a, 'b: 'a>(
+    program_id: &Pubkey,
+    farm_pool_info: &AccountInfo<'b>,
+    farm_info: &mut FarmInfo,
+    farm_user_key: &Pubkey,
+    rent: &Rent,
+    account_info_iter: &mut std::slice::Iter<'a, AccountInfo<'b>>,
+) -> ProgramResult {
+    let registry_info = next_account_info(account_info_iter)?;
+    let payer_info = next_account_info(account_info_iter)?;
+    let system_program_info = next_account_info(account_info_iter)?;
+
+    let page_size = FARM_USER_REGISTRY_PAGE_SIZE as u64;
+    let page = u32::try_from(farm_info.farm_user_count / page_size)
+        .map_err(|_| SwapError::CalculationFailure)?;
+    let (registry_key, bump_seed) =
+        find_farm_user_registry_address(farm_pool_info.key, page, program_id);
     utils::validate(
-        token_a_output == token_a.amount && token_b_output == token_b.amount,
-        SwapError::CalculationFailure,
-    )?;
-
-    pool_state.check_reserve_amount(token_a.amount, token_b.amount)?;
-
-    SwapInfo::pack(
-        SwapInfo {
-            is_initialized: true,
-            is_paused: false,
-            nonce,
-            swap_type: SwapType::Stable,
-            config_key: *config_info.key,
-            token_a: *token_a_info.key,
-            token_b: *token_b_info.key,
-            pool_mint: *pool_mint_info.key,
-            token_a_mint: token_a.mint,
-            token_b_mint: token_b.mint,
-            admin_fee_key_a: *admin_fee_a_info.key,
-            admin_fee_key_b: *admin_fee_b_info.key,
-            fees,
-            rewards,
-            pool_state,
-            token_a_decimals,
-            token_b_decimals,
-            // stable swap use same data structure as swap
-            // we set pyth price accounts to null by using default value
-            ..SwapInfo::default()
-        },
-        &mut swap_info.data.borrow_mut(),
+        *registry_info.key == registry_key,
+        SwapError::InvalidAccountOwner,
     )?;
 
-    token_mint_to(
-        pool_mint_info.clone(),
-        destination_info.clone(),
-        swap_authority_info.clone(),
-        token_program_info.clone(),
-        mint_amount,
-        swap_authority_signer_seeds,
-    )?;
+    let mut registry = if farm_info.farm_user_count % page_size == 0 {
+        utils::validate(
+            system_program::check_id(system_program_info.key),
+            SwapError::InvalidInput,
+        )?;
+        create_program_address_account(
+            payer_info,
+            registry_info,
+            FarmUserRegistry::LEN,
+            program_id,
+            rent,
+            system_program_info,
+            &[
+                SEED_FARM_USER_REGISTRY.as_bytes(),
+                farm_pool_info.key.as_ref(),
+                &page.to_le_bytes(),
+                &[bump_seed],
+            ],
+        )?;
+        FarmUserRegistry::new(*farm_pool_info.key, page)
+    } else {
+        utils::validate(
+            registry_info.owner == program_id,
+            SwapError::InvalidAccountOwner,
+        )?;
+        FarmUserRegistry::unpack(&registry_info.data.borrow())?
+    };
 
+    registry.push(*farm_user_key)?;
+    FarmUserRegistry::pack(registry, &mut registry_info.data.borrow_mut())?;
+    farm_info.farm_user_count = farm_info
+        .farm_user_count
+        .checked_add(1)
+        .ok_or(SwapError::CalculationFailure)?;
     Ok(())
 }
 
-fn process_stable_swap(
+fn process_farm_deposit(
     program_id: &Pubkey,
-    amount_in: u64,
-    minimum_amount_out: u64,
+    amount: u64,
+    lock_tier: u8,
     accounts: &[AccountInfo],
 ) -> ProgramResult {
     let account_info_iter = &mut accounts.iter();
     let config_info = next_account_info(account_info_iter)?;
-    let swap_info = next_account_info(account_info_iter)?;
-    let market_authority_info = next_account_info(account_info_iter)?;
-    let swap_authority_info = next_account_info(account_info_iter)?;
+    let farm_pool_info = next_account_info(account_info_iter)?;
     let user_transfer_authority_info = next_account_info(account_info_iter)?;
     let source_info = next_account_info(account_info_iter)?;
-    let swap_source_info = next_account_info(account_info_iter)?;
-    let source_mint_info = next_account_info(account_info_iter)?;
-    let swap_destination_info = next_account_info(account_info_iter)?;
     let destination_info = next_account_info(account_info_iter)?;
-    let destination_mint_info = next_account_info(account_info_iter)?;
-    let reward_token_info = next_account_info(account_info_iter)?;
-    let source_reward_token_info = next_account_info(account_info_iter)?;
-    let admin_destination_info = next_account_info(account_info_iter)?;
+    let farm_user_info = next_account_info(account_info_iter)?;
+    let farm_owner_info = next_account_info(account_info_iter)?;
+    let clock = &Clock::get()?;
     let token_program_info = next_account_info(account_info_iter)?;
 
+    utils::validate(
+        farm_user_info.owner == program_id
+            && farm_pool_info.owner == program_id
+            && config_info.owner == program_id,
+        SwapError::InvalidAccountOwner,
+    )?;
     spl_token::check_program_account(token_program_info.key)?;
 
-    if swap_info.owner != program_id || config_info.owner != program_id {
-        return Err(ProgramError::IncorrectProgramId);
+    let mut farm_user = FarmUser::unpack_any(&farm_user_info.data.borrow_mut()).unwrap();
+    if farm_user.owner != *farm_owner_info.key {
+        return Err(SwapError::InvalidOwner.into());
+    }
+    if !farm_owner_info.is_signer {
+        return Err(SwapError::InvalidSigner.into());
     }
 
-    let config = ConfigInfo::unpack(&config_info.data.borrow())?;
-    let mut token_swap = SwapInfo::unpack(&swap_info.data.borrow())?;
-
-    utils::validate_swap_config_key(&token_swap, config_info.key)?;
+    let mut farm_info = FarmInfo::unpack(&farm_pool_info.data.borrow_mut())?;
+    utils::validate_farm_config_key(&farm_info, config_info.key)?;
+    let farm_user_pubkey =
+        get_farm_user_pubkey(farm_owner_info.key, farm_pool_info.key, program_id)?;
     utils::validate(
-        token_swap.swap_type == SwapType::Stable,
-        SwapError::IncorrectSwapType,
+        *farm_user_info.key == farm_user_pubkey,
+        SwapError::InvalidAccountOwner,
     )?;
 
-    if token_swap.is_paused {
-        return Err(SwapError::IsPaused.into());
-    }
-    let swap_authority_signer_seeds = &[swap_info.key.as_ref(), &[token_swap.nonce]];
-    if *swap_authority_info.key
-        != Pubkey::create_program_address(swap_authority_signer_seeds, program_id)?
-    {
-        return Err(SwapError::InvalidProgramAddress.into());
-    }
-    let market_authority_signer_seeds = &[config_info.key.as_ref(), &[config.bump_seed]];
-    if *market_authority_info.key
-        != Pubkey::create_program_address(market_authority_signer_seeds, program_id)?
-    {
-        return Err(SwapError::InvalidProgramAddress.into());
-    }
-    if source_info.key == destination_info.key {
+    if farm_info.pool_token != *destination_info.key {
         return Err(SwapError::InvalidInput.into());
     }
-    if source_info.key == swap_source_info.key || destination_info.key == swap_destination_info.key
-    {
-        return Err(SwapError::InvalidInput.into());
+    let token_program_id = *token_program_info.key;
+    let source_token = unpack_token_account(source_info, &token_program_id)?;
+    let destination_token = unpack_token_account(destination_info, &token_program_id)?;
+    if source_token.mint != destination_token.mint {
+        return Err(SwapError::IncorrectMint.into());
     }
-    let swap_direction = if *swap_source_info.key == token_swap.token_a
-        && *swap_destination_info.key == token_swap.token_b
-    {
-        SwapDirection::SellBase
-    } else if *swap_source_info.key == token_swap.token_b
-        && *swap_destination_info.key == token_swap.token_a
-    {
-        SwapDirection::SellQuote
-    } else {
-        return Err(SwapError::IncorrectSwapAccount.into());
-    };
-
-    match swap_direction {
-        SwapDirection::SellQuote => utils::validate(
-            token_swap.admin_fee_key_a == *admin_destination_info.key,
-            SwapError::InvalidAdmin,
-        )?,
-        SwapDirection::SellBase => utils::validate(
-            token_swap.admin_fee_key_b == *admin_destination_info.key,
-            SwapError::InvalidAdmin,
-        )?,
+    if source_token.mint != farm_info.pool_mint {
+        return Err(SwapError::IncorrectMint.into());
     }
 
-    // verify source and dest mint address
-    utils::validate_swap_token_mint(
-        swap_direction,
-        &token_swap.token_a_mint,
-        &token_swap.token_b_mint,
-        source_mint_info.key,
-        destination_mint_info.key,
-    )?;
-
-    let source_mint = Mint::unpack(&source_mint_info.data.borrow())?;
-    let destination_mint = Mint::unpack(&destination_mint_info.data.borrow())?;
-
-    let source_decimals = source_mint.decimals;
-    let destination_decimals = destination_mint.decimals;
-    let (base_decimals, quote_decimals) = match swap_direction {
-        SwapDirection::SellBase => (source_decimals, destination_decimals),
-        SwapDirection::SellQuote => (destination_decimals, source_decimals),
-    };
-
-    let token_program_id = *token_program_info.key;
-    let reward_token = unpack_token_account(reward_token_info, &token_program_id)?;
-    let source_reward_token = unpack_token_account(source_reward_token_info, &token_program_id)?;
-    validate_reward_token_accounts(
-        &config,
-        market_authority_info.key,
-        &source_reward_token,
-        &reward_token,
+    let farm_reward_token_accounts = settle_farm_reward_tokens(
+        program_id,
+        farm_pool_info.key,
+        &farm_info,
+        farm_user_info.key,
+        farm_user.position.deposited_amount,
+        clock.slot,
+        account_info_iter,
     )?;
 
-    // Set the price to 1 for stable swap.
-    token_swap
-        .pool_state
-        .set_market_price(base_decimals, quote_decimals, Decimal::one())?;
-
-    let receive_amount = token_swap
-        .pool_state
-        .get_out_amount(amount_in, swap_direction)?;
-    let fees = &token_swap.fees;
-    let trade_fee = fees.trade_fee(receive_amount)?;
-    let admin_fee = fees.admin_trade_fee(trade_fee)?;
-    let rewards = &token_swap.rewards;
-    let amount_out = receive_amount
-        .checked_sub(trade_fee)
-        .ok_or(SwapError::CalculationFailure)?;
-
-    token_swap.check_swap_out_amount(amount_out, swap_direction)?;
-    if amount_out < minimum_amount_out {
-        return Err(SwapError::ExceededSlippage.into());
-    }
-
-    let amount_to_reward =
-        rewards.trade_reward_u64(if swap_direction == SwapDirection::SellBase {
-            amount_in
-        } else {
-            amount_out
-        })?;
-
-    // The actual token amount moving out of the pool is amount_out + admin_fee.
-    token_swap
-        .pool_state
-        .swap(amount_in, amount_out + admin_fee, swap_direction)?;
-
-    token_transfer(
-        source_info.clone(),
-        swap_source_info.clone(),
-        user_transfer_authority_info.clone(),
-        token_program_info.clone(),
-        amount_in,
-        &[],
-    )?;
-    token_transfer(
-        swap_destination_info.clone(),
-        destination_info.clone(),
-        swap_authority_info.clone(),
-        token_program_info.clone(),
-        amount_out,
-        swap_authority_signer_seeds,
-    )?;
-    token_transfer(
-        swap_destination_info.clone(),
-        admin_destination_info.clone(),
-        swap_authority_info.clone(),
-        token_program_info.clone(),
-        admin_fee,
-        swap_authority_signer_seeds,
-    )?;
-    token_transfer(
-        source_reward_token_info.clone(),
-        reward_token_info.clone(),
-        market_authority_info.clone(),
-        token_program_info.clone(),
-        amount_to_reward,
-        market_authority_signer_seeds,
+    // calculate and refresh reward before deposit
+    farm_user.deposit_into_farm(&mut farm_info, amount, clock)?;
+    farm_user.position.lock(lock_tier, clock.unix_timestamp)?;
+    reset_farm_reward_tokens(
+        farm_reward_token_accounts,
+        farm_user.position.deposited_amount,
     )?;
+    FarmUser::pack_any(farm_user, &mut farm_user_info.data.borrow_mut())?;
 
-    let swap_source_token = unpack_token_account(swap_source_info, &token_program_id)?;
-    let swap_dest_token = unpack_token_account(swap_destination_info, &token_program_id)?;
-    if swap_direction == SwapDirection::SellBase {
-        token_swap
-            .pool_state
-            .check_reserve_amount(swap_source_token.amount, swap_dest_token.amount)?;
-    } else {
-        token_swap
-            .pool_state
-            .check_reserve_amount(swap_dest_token.amount, swap_source_token.amount)?;
-    }
-
-    // Handle referral reward
-    if let Some(user_referrer_data_info) = account_info_iter.next() {
-        let referrer_token_info = next_account_info(account_info_iter)?;
-
-        let source_token = unpack_token_account(source_info, &token_program_id)?;
-        let expected_user_referrer_data_pubkey =
-            get_referrer_data_pubkey(&source_token.owner, config_info.key, program_id)?;
-        utils::validate(
-            expected_user_referrer_data_pubkey == *user_referrer_data_info.key,
-            SwapError::InvalidAccountOwner,
-        )?;
-
-        let user_referrer_data = UserReferrerData::unpack(&user_referrer_data_info.data.borrow())?;
-        utils::validate(
-            user_referrer_data.referrer == *referrer_token_info.key
-                && user_referrer_data.config_key == *config_info.key,
-            SwapError::InvalidAccountOwner,
-        )?;
+    token_transfer(
+        source_info.clone(),
+        destination_info.clone(),
+        user_transfer_authority_info.clone(),
+        token_program_info.clone(),
+        amount,
+        &[],
+    )?;
 
-        // Dummy referrer is a placeholder to indicate that the user is not referred by anyone.
-        // If it is given in the input, skip the referral reward distribution here.
-        if user_referrer_data.referrer.to_string() != DUMMY_REFERRER_ADDRESS {
-            let referral_reward = rewards.referral_reward(amount_to_reward)?;
-            token_transfer(
-                source_reward_token_info.clone(),
-                referrer_token_info.clone(),
-                market_authority_info.clone(),
-                token_program_info.clone(),
-                referral_reward,
-                market_authority_signer_seeds,
-            )?;
-        }
+    let destination_token = unpack_token_account(destination_info, &token_program_id)?;
+    farm_info.check_reserve_amount(destination_token.amount)?;
+    FarmEvent {
+        event_type: EventType::FarmDeposit,
+        farm_pool: *farm_pool_info.key,
+        farm_user: *farm_user_info.key,
+        amount,
     }
-
-    SwapInfo::pack(token_swap, &mut swap_info.data.borrow_mut())?;
+    .emit();
+    FarmInfo::pack(farm_info, &mut farm_pool_info.data.borrow_mut())?;
 
     Ok(())
 }
 
-fn process_stable_swap_v2(
+fn process_farm_withdraw(
     program_id: &Pubkey,
-    amount_in: u64,
-    minimum_amount_out: u64,
+    amount: u64,
     accounts: &[AccountInfo],
 ) -> ProgramResult {
     let account_info_iter = &mut accounts.iter();
     let config_info = next_account_info(account_info_iter)?;
-    let swap_info = next_account_info(account_info_iter)?;
-    let market_authority_info = next_account_info(account_info_iter)?;
-    let swap_authority_info = next_account_info(account_info_iter)?;
-    let user_transfer_authority_info = next_account_info(account_info_iter)?;
+    let farm_pool_info = next_account_info(account_info_iter)?;
+    let farm_user_info = next_account_info(account_info_iter)?;
+    let authority_info = next_account_info(account_info_iter)?;
     let source_info = next_account_info(account_info_iter)?;
-    let swap_source_info = next_account_info(account_info_iter)?;
-    let swap_destination_info = next_account_info(account_info_iter)?;
     let destination_info = next_account_info(account_info_iter)?;
-    let reward_token_info = next_account_info(account_info_iter)?;
-    let source_reward_token_info = next_account_info(account_info_iter)?;
-    let admin_destination_info = next_account_info(account_info_iter)?;
+    let farm_owner_info = next_account_info(account_info_iter)?;
+    let clock = &Clock::get()?;
     let token_program_info = next_account_info(account_info_iter)?;
 
+    utils::validate(
+        farm_user_info.owner == program_id
+            && farm_pool_info.owner == program_id
+            && config_info.owner == program_id,
+        SwapError::InvalidAccountOwner,
+    )?;
     spl_token::check_program_account(token_program_info.key)?;
 
-    if swap_info.owner != program_id || config_info.owner != program_id {
-        return Err(ProgramError::IncorrectProgramId);
+    let mut farm_user = FarmUser::unpack_any(&farm_user_info.data.borrow_mut())?;
+    if farm_user.owner != *farm_owner_info.key {
+        return Err(SwapError::InvalidOwner.into());
+    }
+    if !farm_owner_info.is_signer {
+        return Err(SwapError::InvalidSigner.into());
     }
 
-    let config = ConfigInfo::unpack(&config_info.data.borrow())?;
-    let mut token_swap = SwapInfo::unpack(&swap_info.data.borrow())?;
+    let mut farm_info = FarmInfo::unpack(&farm_pool_info.data.borrow_mut())?;
+    utils::validate_farm_config_key(&farm_info, config_info.key)?;
 
-    utils::validate_swap_config_key(&token_swap, config_info.key)?;
+    let farm_user_pubkey =
+        get_farm_user_pubkey(farm_owner_info.key, farm_pool_info.key, program_id)?;
     utils::validate(
-        token_swap.swap_type == SwapType::Stable,
-        SwapError::IncorrectSwapType,
+        *farm_user_info.key == farm_user_pubkey,
+        SwapError::InvalidAccountOwner,
     )?;
 
-    if token_swap.is_paused {
-        return Err(SwapError::IsPaused.into());
-    }
-    let swap_authority_signer_seeds = &[swap_info.key.as_ref(), &[token_swap.nonce]];
-    if *swap_authority_info.key
-        != Pubkey::create_program_address(swap_authority_signer_seeds, program_id)?
-    {
-        return Err(SwapError::InvalidProgramAddress.into());
-    }
-    let market_authority_signer_seeds = &[config_info.key.as_ref(), &[config.bump_seed]];
-    if *market_authority_info.key
-        != Pubkey::create_program_address(market_authority_signer_seeds, program_id)?
+    let farm_authority_signer_seeds = &[farm_pool_info.key.as_ref(), &[farm_info.bump_seed]];
+    if *authority_info.key
+        != Pubkey::create_program_address(farm_authority_signer_seeds, program_id)?
     {
         return Err(SwapError::InvalidProgramAddress.into());
     }
-    if source_info.key == destination_info.key {
-        return Err(SwapError::InvalidInput.into());
+    let token_program_id = *token_program_info.key;
+    let source_token = unpack_token_account(source_info, &token_program_id)?;
+    let destination_token = unpack_token_account(destination_info, &token_program_id)?;
+    if source_token.mint != destination_token.mint {
+        return Err(SwapError::IncorrectMint.into());
     }
-    if source_info.key == swap_source_info.key || destination_info.key == swap_destination_info.key
-    {
-        return Err(SwapError::InvalidInput.into());
+    if source_token.mint != farm_info.pool_mint {
+        return Err(SwapError::IncorrectMint.into());
     }
-    let swap_direction = if *swap_source_info.key == token_swap.token_a
-        && *swap_destination_info.key == token_swap.token_b
-    {
-        SwapDirection::SellBase
-    } else if *swap_source_info.key == token_swap.token_b
-        && *swap_destination_info.key == token_swap.token_a
-    {
-        SwapDirection::SellQuote
-    } else {
-        return Err(SwapError::IncorrectSwapAccount.into());
-    };
 
-    match swap_direction {
-        SwapDirection::SellQuote => utils::validate(
-            token_swap.admin_fee_key_a == *admin_destination_info.key,
-            SwapError::InvalidAdmin,
-        )?,
-        SwapDirection::SellBase => utils::validate(
-            token_swap.admin_fee_key_b == *admin_destination_info.key,
-            SwapError::InvalidAdmin,
-        )?,
+    /* make sure source_info token account is farm_pool's token account */
+    if farm_info.pool_token != *source_info.key {
+        return Err(SwapError::InvalidInput.into());
     }
 
-    {
-        // verify source and dest mint address
-        let source_token = unpack_token_account(source_info, token_program_info.key)?;
-        let destination_token = unpack_token_account(destination_info, token_program_info.key)?;
+    let farm_reward_token_accounts = settle_farm_reward_tokens(
+        program_id,
+        farm_pool_info.key,
+        &farm_info,
+        farm_user_info.key,
+        farm_user.position.deposited_amount,
+        clock.slot,
+        account_info_iter,
+    )?;
 
-        utils::validate_swap_token_mint(
-            swap_direction,
-            &token_swap.token_a_mint,
-            &token_swap.token_b_mint,
-            &source_token.mint,
-            &destination_token.mint,
+    // The penalty goes to an admin token account of the pool mint, the user gets the rest
+    let withdraw_fee = farm_info.withdraw_fee(amount)?;
+    let fee_destination_info = if withdraw_fee > 0 {
+        let fee_destination_info = next_account_info(account_info_iter)?;
+        let fee_destination = unpack_token_account(fee_destination_info, &token_program_id)?;
+        let config = ConfigInfo::unpack(&config_info.data.borrow())?;
+        utils::validate(
+            fee_destination.mint == farm_info.pool_mint,
+            SwapError::IncorrectMint,
         )?;
-    }
-
-    let token_program_id = *token_program_info.key;
-    let reward_token = unpack_token_account(reward_token_info, &token_program_id)?;
-    let source_reward_token = unpack_token_account(source_reward_token_info, &token_program_id)?;
-    validate_reward_token_accounts(
-        &config,
-        market_authority_info.key,
-        &source_reward_token,
-        &reward_token,
-    )?;
+        utils::validate(
+            fee_destination.owner == config.admin_key,
+            SwapError::InvalidAdmin,
+        )?;
+        Some(fee_destination_info)
+    } else {
+        None
+    };
 
-    // Set the price to 1 for stable swap.
-    token_swap.pool_state.set_market_price(
-        token_swap.token_a_decimals,
-        token_swap.token_b_decimals,
-        Decimal::one(),
+    farm_user.position.check_unlocked(clock.unix_timestamp)?;
+    // calculate and refresh reward before withdraw
+    farm_user.withdraw_from_farm(&mut farm_info, amount, clock)?;
+    reset_farm_reward_tokens(
+        farm_reward_token_accounts,
+        farm_user.position.deposited_amount,
     )?;
-
-    let receive_amount = token_swap
-        .pool_state
-        .get_out_amount(amount_in, swap_direction)?;
-    let fees = &token_swap.fees;
-    let trade_fee = fees.trade_fee(receive_amount)?;
-    let admin_fee = fees.admin_trade_fee(trade_fee)?;
-    let rewards = &token_swap.rewards;
-    let amount_out = receive_amount
-        .checked_sub(trade_fee)
-        .ok_or(SwapError::CalculationFailure)?;
-
-    token_swap.check_swap_out_amount(amount_out, swap_direction)?;
-    if amount_out < minimum_amount_out {
-        return Err(SwapError::ExceededSlippage.into());
+    FarmUser::pack_any(farm_user, &mut farm_user_info.data.borrow_mut())?;
+
+    if let Some(fee_destination_info) = fee_destination_info {
+        token_transfer(
+            source_info.clone(),
+            fee_destination_info.clone(),
+            authority_info.clone(),
+            token_program_info.clone(),
+            withdraw_fee,
+            farm_authority_signer_seeds,
+        )?;
     }
-
-    let amount_to_reward =
-        rewards.trade_reward_u64(if swap_direction == SwapDirection::SellBase {
-            amount_in
-        } else {
-            amount_out
-        })?;
-
-    // The actual token amount moving out of the pool is amount_out + admin_fee.
-    token_swap
-        .pool_state
-        .swap(amount_in, amount_out + admin_fee, swap_direction)?;
-
     token_transfer(
         source_info.clone(),
-        swap_source_info.clone(),
-        user_transfer_authority_info.clone(),
-        token_program_info.clone(),
-        amount_in,
-        &[],
-    )?;
-    token_transfer(
-        swap_destination_info.clone(),
         destination_info.clone(),
-        swap_authority_info.clone(),
+        authority_info.clone(),
         token_program_info.clone(),
-        amount_out,
-        swap_authority_signer_seeds,
+        amount
+            .checked_sub(withdraw_fee)
+            .ok_or(SwapError::WithdrawFeeUnderflow)?,
+        farm_authority_signer_seeds,
     )?;
-    token_transfer(
-        swap_destination_info.clone(),
-        admin_destination_info.clone(),
-        swap_authority_info.clone(),
-        token_program_info.clone(),
-        admin_fee,
-        swap_authority_signer_seeds,
+
+    let source_token = unpack_token_account(source_info, &token_program_id)?;
+    farm_info.check_reserve_amount(source_token.amount)?;
+    FarmEvent {
+        event_type: EventType::FarmWithdraw,
+        farm_pool: *farm_pool_info.key,
+        farm_user: *farm_user_info.key,
+        amount,
+    }
+    .emit();
+    FarmInfo::pack(farm_info, &mut farm_pool_info.data.borrow_mut())?;
+    Ok(())
+}
+
+fn process_farm_claim(program_id: &Pubkey, accounts: &[AccountInfo]) -> ProgramResult {
+    let account_info_iter = &mut accounts.iter();
+    let config_info = next_account_info(account_info_iter)?;
+    let farm_pool_info = next_account_info(account_info_iter)?;
+    let farm_user_info = next_account_info(account_info_iter)?;
+    let farm_owner_info = next_account_info(account_info_iter)?;
+    let market_authority_info = next_account_info(account_info_iter)?;
+    let claim_destination_info = next_account_info(account_info_iter)?;
+    let claim_source_info = next_account_info(account_info_iter)?;
+    let clock = &Clock::get()?;
+    let token_program_info = next_account_info(account_info_iter)?;
+
+    utils::validate(
+        farm_user_info.owner == program_id
+            && farm_pool_info.owner == program_id
+            && config_info.owner == program_id,
+        SwapError::InvalidAccountOwner,
     )?;
-    token_transfer(
-        source_reward_token_info.clone(),
-        reward_token_info.clone(),
-        market_authority_info.clone(),
-        token_program_info.clone(),
-        amount_to_reward,
-        market_authority_signer_seeds,
+    spl_token::check_program_account(token_program_info.key)?;
+
+    let farm_info = FarmInfo::unpack(&farm_pool_info.data.borrow_mut())?;
+    utils::validate_farm_config_key(&farm_info, config_info.key)?;
+
+    let farm_user_pubkey =
+        get_farm_user_pubkey(farm_owner_info.key, farm_pool_info.key, program_id)?;
+    utils::validate(
+        *farm_user_info.key == farm_user_pubkey,
+        SwapError::InvalidAccountOwner,
     )?;
 
-    let swap_source_token = unpack_token_account(swap_source_info, &token_program_id)?;
-    let swap_dest_token = unpack_token_account(swap_destination_info, &token_program_id)?;
-    if swap_direction == SwapDirection::SellBase {
-        token_swap
-            .pool_state
-            .check_reserve_amount(swap_source_token.amount, swap_dest_token.amount)?;
-    } else {
-        token_swap
-            .pool_state
-            .check_reserve_amount(swap_dest_token.amount, swap_source_token.amount)?;
+    let mut farm_user = FarmUser::unpack_any(&farm_user_info.data.borrow_mut())?;
+    if farm_user.config_key != *config_info.key {
+        return Err(SwapError::InvalidOwner.into());
+    }
+    if farm_user.owner != *farm_owner_info.key {
+        return Err(SwapError::InvalidOwner.into());
+    }
+    if !farm_owner_info.is_signer {
+        return Err(SwapError::InvalidSigner.into());
+    }
+
+    let config = ConfigInfo::unpack(&config_info.data.borrow())?;
+    let market_authority_signer_seeds = &[config_info.key.as_ref(), &[config.bump_seed]];
+    if *market_authority_info.key
+        != Pubkey::create_program_address(market_authority_signer_seeds, program_id)?
+    {
+        return Err(SwapError::InvalidProgramAddress.into());
+    }
+
+    if claim_destination_info.owner == market_authority_info.key {
+        return Err(SwapError::InvalidOwner.into());
     }
 
-    // Handle referral reward
-    if let Some(user_referrer_data_info) = account_info_iter.next() {
-        let referrer_token_info = next_account_info(account_info_iter)?;
+    // calculate and refresh reward before deposit
+    farm_user.accrue_rewards(&farm_info, clock, false)?;
+
+    let token_program_id = *token_program_info.key;
+    let claim_destination = unpack_token_account(claim_destination_info, &token_program_id)?;
+    let claim_source = unpack_token_account(claim_source_info, &token_program_id)?;
+    validate_reward_token_accounts(
+        &config,
+        market_authority_info.key,
+        &claim_source,
+        &claim_destination,
+    )?;
 
-        let source_token = unpack_token_account(source_info, &token_program_id)?;
-        let expected_user_referrer_data_pubkey =
-            get_referrer_data_pubkey(&source_token.owner, config_info.key, program_id)?;
+    let reward_amount = farm_user.claim()?;
+
+    // Pay the registered recipients their share, the owner receives the rest.
+    let mut owner_reward_amount = reward_amount;
+    if farm_user.reward_split != Pubkey::default() {
+        let reward_split_info = next_account_info(account_info_iter)?;
         utils::validate(
-            expected_user_referrer_data_pubkey == *user_referrer_data_info.key,
+            *reward_split_info.key == farm_user.reward_split
+                && reward_split_info.owner == program_id,
             SwapError::InvalidAccountOwner,
         )?;
-
-        let user_referrer_data = UserReferrerData::unpack(&user_referrer_data_info.data.borrow())?;
+        let reward_split = FarmRewardSplit::unpack(&reward_split_info.data.borrow())?;
         utils::validate(
-            user_referrer_data.referrer == *referrer_token_info.key
-                && user_referrer_data.config_key == *config_info.key,
-            SwapError::InvalidAccountOwner,
+            reward_split.farm_user == *farm_user_info.key,
+            SwapError::InvalidRewardSplit,
         )?;
 
-        // Dummy referrer is a placeholder to indicate that the user is not referred by anyone.
-        // If it is given in the input, skip the referral reward distribution here.
-        if user_referrer_data.referrer.to_string() != DUMMY_REFERRER_ADDRESS {
-            let referral_reward = rewards.referral_reward(amount_to_reward)?;
+        let (owner_amount, recipient_amounts) = reward_split.split_rewards(reward_amount)?;
+        owner_reward_amount = owner_amount;
+        for (recipient, amount) in reward_split
+            .recipients
+            .iter()
+            .zip(recipient_amounts.iter())
+            .take(reward_split.recipients_len as usize)
+        {
+            let recipient_info = next_account_info(account_info_iter)?;
+            utils::validate(
+                recipient_info.key == recipient,
+                SwapError::InvalidRewardSplit,
+            )?;
             token_transfer(
-                source_reward_token_info.clone(),
-                referrer_token_info.clone(),
+                claim_source_info.clone(),
+                recipient_info.clone(),
                 market_authority_info.clone(),
                 token_program_info.clone(),
-                referral_reward,
+                *amount,
                 market_authority_signer_seeds,
             )?;
         }
     }
 
-    SwapInfo::pack(token_swap, &mut swap_info.data.borrow_mut())?;
+    token_transfer(
+        claim_source_info.clone(),
+        claim_destination_info.clone(),
+        market_authority_info.clone(),
+        token_program_info.clone(),
+        owner_reward_amount,
+        market_authority_signer_seeds,
+    )?;
+
+    // Handle referral reward
+    if let Some(referrer_token_info) = next_referrer_token(
+        program_id,
+        config_info.key,
+        &farm_user.owner,
+        &token_program_id,
+        account_info_iter,
+    )? {
+        let referral_reward = config.rewards.referral_reward(reward_amount)?;
+        token_transfer(
+            claim_source_info.clone(),
+            referrer_token_info.clone(),
+            market_authority_info.clone(),
+            token_program_info.clone(),
+            referral_reward,
+            market_authority_signer_seeds,
+        )?;
+    }
+
+    FarmEvent {
+        event_type: EventType::FarmClaim,
+        farm_pool: *farm_pool_info.key,
+        farm_user: *farm_user_info.key,
+        amount: reward_amount,
+    }
+    .emit();
+
+    FarmUser::pack_any(farm_user, &mut farm_user_info.data.borrow_mut())?;
 
     Ok(())
 }
 
-fn process_farm_instruction(
+fn process_farm_claim_multi(
     program_id: &Pubkey,
+    farm_count: u8,
     accounts: &[AccountInfo],
-    input: &[u8],
 ) -> ProgramResult {
-    let instruction = FarmInstruction::unpack(input)?;
-    match instruction {
-        FarmInstruction::Initialize(FarmInitializeData {
-            fee_numerator,
-            fee_denominator,
-            rewards_numerator,
-            rewards_denominator,
-            bump_seed,
-        }) => {
-            msg!("Instruction: Farm initialize");
-            process_farm_initialize(
-                program_id,
-                fee_numerator,
-                fee_denominator,
-                rewards_numerator,
-                rewards_denominator,
-                bump_seed,
-                accounts,
-            )
-        }
-        FarmInstruction::InitializeFarmUser => {
-            msg!("Instruction: Farm user initialize");
-            process_farm_user_initialize(program_id, accounts)
-        }
-        FarmInstruction::Claim => {
-            msg!("Instruction: Farm claim");
-            process_farm_claim(program_id, accounts)
-        }
-        FarmInstruction::Refresh => {
-            msg!("Instruction: Farm refresh");
-            // refresh instruction is removed
-            Err(SwapError::InvalidInstruction.into())
+    let account_info_iter = &mut accounts.iter();
+    let config_info = next_account_info(account_info_iter)?;
+    let farm_owner_info = next_account_info(account_info_iter)?;
+    let market_authority_info = next_account_info(account_info_iter)?;
+    let claim_destination_info = next_account_info(account_info_iter)?;
+    let claim_source_info = next_account_info(account_info_iter)?;
+    let clock = &Clock::get()?;
+    let token_program_info = next_account_info(account_info_iter)?;
+
+    utils::validate(
+        config_info.owner == program_id,
+        SwapError::InvalidAccountOwner,
+    )?;
+    spl_token::check_program_account(token_program_info.key)?;
+    if !farm_owner_info.is_signer {
+        return Err(SwapError::InvalidSigner.into());
+    }
+
+    let config = ConfigInfo::unpack(&config_info.data.borrow())?;
+    let market_authority_signer_seeds = &[config_info.key.as_ref(), &[config.bump_seed]];
+    if *market_authority_info.key
+        != Pubkey::create_program_address(market_authority_signer_seeds, program_id)?
+    {
+        return Err(SwapError::InvalidProgramAddress.into());
+    }
+
+    if claim_destination_info.owner == market_authority_info.key {
+        return Err(SwapError::InvalidOwner.into());
+    }
+
+    let token_program_id = *token_program_info.key;
+    let claim_destination = unpack_token_account(claim_destination_info, &token_program_id)?;
+    let claim_source = unpack_token_account(claim_source_info, &token_program_id)?;
+    validate_reward_token_accounts(
+        &config,
+        market_authority_info.key,
+        &claim_source,
+        &claim_destination,
+    )?;
+
+    // Each farm user is packed once claimed, so a farm user listed twice claims nothing
+    // the second time.
+    let mut reward_amount = 0u64;
+    for _ in 0..farm_count {
+        let farm_pool_info = next_account_info(account_info_iter)?;
+        let farm_user_info = next_account_info(account_info_iter)?;
+        utils::validate(
+            farm_user_info.owner == program_id && farm_pool_info.owner == program_id,
+            SwapError::InvalidAccountOwner,
+        )?;
+
+        let farm_info = FarmInfo::unpack(&farm_pool_info.data.borrow())?;
+        utils::validate_farm_config_key(&farm_info, config_info.key)?;
+
+        let farm_user_pubkey =
+            get_farm_user_pubkey(farm_owner_info.key, farm_pool_info.key, program_id)?;
+        utils::validate(
+            *farm_user_info.key == farm_user_pubkey,
+            SwapError::InvalidAccountOwner,
+        )?;
+
+        let mut farm_user = FarmUser::unpack_any(&farm_user_info.data.borrow())?;
+        if farm_user.config_key != *config_info.key
+            || farm_user.farm_pool_key != *farm_pool_info.key
+            || farm_user.owner != *farm_owner_info.key
+        {
+            return Err(SwapError::InvalidOwner.into());
         }
-        FarmInstruction::Deposit(FarmDepositData { amount }) => {
-            msg!("Instruction: Farm deposit");
-            process_farm_deposit(program_id, amount, accounts)
+        // The recipients of a reward split are paid by the single farm claim
+        if farm_user.reward_split != Pubkey::default() {
+            return Err(SwapError::InvalidRewardSplit.into());
         }
-        FarmInstruction::Withdraw(FarmWithdrawData { amount }) => {
-            msg!("Instruction: Farm withdraw");
-            process_farm_withdraw(program_id, amount, accounts)
+
+        farm_user.accrue_rewards(&farm_info, clock, false)?;
+        let farm_reward_amount = farm_user.claim()?;
+        reward_amount = reward_amount
+            .checked_add(farm_reward_amount)
+            .ok_or(SwapError::CalculationFailure)?;
+
+        FarmEvent {
+            event_type: EventType::FarmClaim,
+            farm_pool: *farm_pool_info.key,
+            farm_user: *farm_user_info.key,
+            amount: farm_reward_amount,
         }
+        .emit();
+
+        FarmUser::pack_any(farm_user, &mut farm_user_info.data.borrow_mut())?;
+    }
+
+    token_transfer(
+        claim_source_info.clone(),
+        claim_destination_info.clone(),
+        market_authority_info.clone(),
+        token_program_info.clone(),
+        reward_amount,
+        market_authority_signer_seeds,
+    )?;
+
+    // Handle referral reward
+    if let Some(referrer_token_info) = next_referrer_token(
+        program_id,
+        config_info.key,
+        farm_owner_info.key,
+        &token_program_id,
+        account_info_iter,
+    )? {
+        let referral_reward = config.rewards.referral_reward(reward_amount)?;
+        token_transfer(
+            claim_source_info.clone(),
+            referrer_token_info.clone(),
+            market_authority_info.clone(),
+            token_program_info.clone(),
+            referral_reward,
+            market_authority_signer_seeds,
+        )?;
     }
+
+    Ok(())
 }
 
-fn process_farm_initialize(
+fn process_farm_set_reward_split(
     program_id: &Pubkey,
-    fee_numerator: u64,
-    fee_denominator: u64,
-    apr_numerator: u64,
-    apr_denominator: u64,
-    bump_seed: u8,
+    percentages: &[u8; MAX_REWARD_RECIPIENTS],
     accounts: &[AccountInfo],
 ) -> ProgramResult {
     let account_info_iter = &mut accounts.iter();
     let config_info = next_account_info(account_info_iter)?;
-    let swap_info = next_account_info(account_info_iter)?;
     let farm_pool_info = next_account_info(account_info_iter)?;
-    let authority_info = next_account_info(account_info_iter)?;
-    let pool_token_info = next_account_info(account_info_iter)?;
-    let admin_info = next_account_info(account_info_iter)?;
+    let farm_user_info = next_account_info(account_info_iter)?;
+    let farm_owner_info = next_account_info(account_info_iter)?;
+    let reward_split_info = next_account_info(account_info_iter)?;
     let rent = &Rent::from_account_info(next_account_info(account_info_iter)?)?;
+    let recipient_infos = account_info_iter.as_slice();
 
     utils::validate(
-        farm_pool_info.owner == program_id
+        farm_user_info.owner == program_id
+            && farm_pool_info.owner == program_id
             && config_info.owner == program_id
-            && swap_info.owner == program_id,
+            && reward_split_info.owner == program_id,
         SwapError::InvalidAccountOwner,
     )?;
 
-    utils::validate(farm_pool_info.is_signer, SwapError::InvalidSigner)?;
-    let config = ConfigInfo::unpack(&config_info.data.borrow())?;
-    is_admin(&config.admin_key, admin_info)?;
-
-    if pool_token_info.owner != &spl_token::ID {
-        return Err(SwapError::InvalidAccountOwner.into());
-    }
+    let farm_info = FarmInfo::unpack(&farm_pool_info.data.borrow())?;
+    utils::validate_farm_config_key(&farm_info, config_info.key)?;
 
-    assert_rent_exempt(rent, farm_pool_info)?;
-    assert_uninitialized::<FarmInfo>(farm_pool_info)?;
+    let farm_user_pubkey =
+        get_farm_user_pubkey(farm_owner_info.key, farm_pool_info.key, program_id)?;
+    utils::validate(
+        *farm_user_info.key == farm_user_pubkey,
+        SwapError::InvalidAccountOwner,
+    )?;
 
-    if *authority_info.key
-        != Pubkey::create_program_address(&[farm_pool_info.key.as_ref(), &[bump_seed]], program_id)?
-    {
-        return Err(SwapError::InvalidAccountOwner.into());
-    }
-    let token_swap = SwapInfo::unpack(&swap_info.data.borrow())?;
-    let pool_token = unpack_token_account(pool_token_info, &spl_token::id())?;
-    if pool_token.mint != token_swap.pool_mint {
-        return Err(SwapError::IncorrectMint.into());
-    }
-    if pool_token.owner != *authority_info.key {
+    let mut farm_user = FarmUser::unpack_any(&farm_user_info.data.borrow())?;
+    if farm_user.config_key != *config_info.key || farm_user.owner != *farm_owner_info.key {
         return Err(SwapError::InvalidOwner.into());
     }
-    if pool_token.delegate.is_some() {
-        return Err(SwapError::InvalidDelegate.into());
+    if !farm_owner_info.is_signer {
+        return Err(SwapError::InvalidSigner.into());
     }
-    if pool_token.close_authority.is_some() {
-        return Err(SwapError::InvalidCloseAuthority.into());
+
+    assert_rent_exempt(rent, reward_split_info)?;
+    let mut reward_split = FarmRewardSplit::unpack_unchecked(&reward_split_info.data.borrow())?;
+    // An initialized split account can only be updated by its farm user.
+    if reward_split.is_initialized && reward_split.farm_user != *farm_user_info.key {
+        return Err(SwapError::InvalidRewardSplit.into());
     }
 
-    FarmInfo::pack(
-        FarmInfo {
+    FarmRewardSplit::validate_percentages(recipient_infos.len(), percentages)?;
+
+    let config = ConfigInfo::unpack(&config_info.data.borrow())?;
+    let market_authority_signer_seeds = &[config_info.key.as_ref(), &[config.bump_seed]];
+    let market_authority =
+        Pubkey::create_program_address(market_authority_signer_seeds, program_id)?;
+
+    let mut recipients = [Pubkey::default(); MAX_REWARD_RECIPIENTS];
+    for (recipient, recipient_info) in recipients.iter_mut().zip(recipient_infos.iter()) {
+        let recipient_token = unpack_token_account(recipient_info, &spl_token::id())?;
+        utils::validate(
+            recipient_token.mint == config.deltafi_mint,
+            SwapError::IncorrectMint,
+        )?;
+        utils::validate(
+            recipient_token.owner != market_authority,
+            SwapError::InvalidOwner,
+        )?;
+        *recipient = *recipient_info.key;
+    }
+
+    if recipient_infos.is_empty() {
+        reward_split = FarmRewardSplit::default();
+        farm_user.reward_split = Pubkey::default();
+    } else {
+        reward_split = FarmRewardSplit {
             is_initialized: true,
-            bump_seed,
-            config_key: *config_info.key,
-            pool_mint: token_swap.pool_mint,
-            pool_token: *pool_token_info.key,
-            reserved_amount: 0,
-            fee_numerator,
-            fee_denominator,
-            apr_numerator,
-            apr_denominator,
-            ..FarmInfo::default()
-        },
-        &mut farm_pool_info.data.borrow_mut(),
+            farm_user: *farm_user_info.key,
+            recipients_len: recipient_infos.len() as u8,
+            recipients,
+            percentages: *percentages,
+        };
+        farm_user.reward_split = *reward_split_info.key;
+    }
+
+    FarmRewardSplit::pack(reward_split, &mut reward_split_info.data.borrow_mut())?;
+    FarmUser::pack_any(farm_user, &mut farm_user_info.data.borrow_mut())?;
+
+    Ok(())
+}
+
+fn process_farm_checkpoint(program_id: &Pubkey, accounts: &[AccountInfo]) -> ProgramResult {
+    let account_info_iter = &mut accounts.iter();
+    let config_info = next_account_info(account_info_iter)?;
+    let farm_pool_info = next_account_info(account_info_iter)?;
+    let farm_user_infos = account_info_iter.as_slice();
+    let clock = &Clock::get()?;
+
+    utils::validate(
+        farm_pool_info.owner == program_id && config_info.owner == program_id,
+        SwapError::InvalidAccountOwner,
+    )?;
+
+    let farm_info = FarmInfo::unpack(&farm_pool_info.data.borrow())?;
+    utils::validate_farm_config_key(&farm_info, config_info.key)?;
+
+    accrue_farm_users(
+        program_id,
+        config_info.key,
+        farm_pool_info.key,
+        &farm_info,
+        farm_user_infos,
+        clock,
+    )
+}
+
+fn process_farm_refresh(program_id: &Pubkey, accounts: &[AccountInfo]) -> ProgramResult {
+    let account_info_iter = &mut accounts.iter();
+    let config_info = next_account_info(account_info_iter)?;
+    let farm_pool_info = next_account_info(account_info_iter)?;
+    let farm_user_infos = account_info_iter.as_slice();
+    let clock = &Clock::get()?;
+
+    utils::validate(
+        farm_pool_info.owner == program_id && config_info.owner == program_id,
+        SwapError::InvalidAccountOwner,
+    )?;
+
+    let mut farm_info = FarmInfo::unpack(&farm_pool_info.data.borrow())?;
+    utils::validate_farm_config_key(&farm_info, config_info.key)?;
+
+    farm_info.update_emission(clock.slot)?;
+    accrue_farm_users(
+        program_id,
+        config_info.key,
+        farm_pool_info.key,
+        &farm_info,
+        farm_user_infos,
+        clock,
     )?;
+    FarmInfo::pack(farm_info, &mut farm_pool_info.data.borrow_mut())
+}
+
+/// Accrue the rewards of the farm users of a farm pool for the permissionless cranks
+fn accrue_farm_users(
+    program_id: &Pubkey,
+    config_key: &Pubkey,
+    farm_pool_key: &Pubkey,
+    farm_info: &FarmInfo,
+    farm_user_infos: &[AccountInfo],
+    clock: &Clock,
+) -> ProgramResult {
+    for farm_user_info in farm_user_infos {
+        utils::validate(
+            farm_user_info.owner == program_id,
+            SwapError::InvalidAccountOwner,
+        )?;
+        let mut farm_user = FarmUser::unpack_any(&farm_user_info.data.borrow())?;
+        if farm_user.config_key != *config_key || farm_user.farm_pool_key != *farm_pool_key {
+            return Err(SwapError::InvalidOwner.into());
+        }
+
+        // The crank is permissionless, so the position is only moved forward when it
+        // accrued rewards, otherwise repeated checkpoints would round the rewards away.
+        farm_user.accrue_rewards(farm_info, clock, false)?;
+        FarmUser::pack_any(farm_user, &mut farm_user_info.data.borrow_mut())?;
+    }
 
     Ok(())
 }
 
-fn process_farm_user_initialize(program_id: &Pubkey, accounts: &[AccountInfo]) -> ProgramResult {
+fn process_farm_fund_reward_token(
+    program_id: &Pubkey,
+    index: u8,
+    amount: u64,
+    accounts: &[AccountInfo],
+) -> ProgramResult {
     let account_info_iter = &mut accounts.iter();
     let config_info = next_account_info(account_info_iter)?;
     let farm_pool_info = next_account_info(account_info_iter)?;
-    let farm_user_info = next_account_info(account_info_iter)?;
-    let owner_info = next_account_info(account_info_iter)?;
-    let rent = &Rent::from_account_info(next_account_info(account_info_iter)?)?;
-    let clock = &Clock::get()?;
+    let farm_reward_tokens_info = next_account_info(account_info_iter)?;
+    let vault_info = next_account_info(account_info_iter)?;
+    let source_info = next_account_info(account_info_iter)?;
+    let user_transfer_authority_info = next_account_info(account_info_iter)?;
+    let token_program_info = next_account_info(account_info_iter)?;
 
     utils::validate(
-        farm_user_info.owner == program_id
-            && farm_pool_info.owner == program_id
-            && config_info.owner == program_id,
+        farm_pool_info.owner == program_id && config_info.owner == program_id,
         SwapError::InvalidAccountOwner,
     )?;
+    spl_token::check_program_account(token_program_info.key)?;
 
-    let farm_info = FarmInfo::unpack(&farm_pool_info.data.borrow_mut())?;
+    let farm_info = FarmInfo::unpack(&farm_pool_info.data.borrow())?;
     utils::validate_farm_config_key(&farm_info, config_info.key)?;
+    let mut farm_reward_tokens =
+        unpack_farm_reward_tokens(program_id, farm_pool_info.key, farm_reward_tokens_info)?;
 
-    let farm_user_pubkey = get_farm_user_pubkey(owner_info.key, farm_pool_info.key, program_id)?;
+    // The funded tokens only emit from now on
+    farm_reward_tokens.accrue(farm_info.reserved_amount, Clock::get()?.slot)?;
+    let reward_token = farm_reward_tokens.get_mut(index)?;
     utils::validate(
-        *farm_user_info.key == farm_user_pubkey,
-        SwapError::InvalidAccountOwner,
+        reward_token.vault == *vault_info.key,
+        SwapError::InvalidFarmRewardToken,
     )?;
+    reward_token.remaining_amount = reward_token
+        .remaining_amount
+        .checked_add(amount)
+        .ok_or(SwapError::CalculationFailure)?;
 
-    assert_rent_exempt(rent, farm_user_info)?;
-    let mut farm_user = assert_uninitialized::<FarmUser>(farm_user_info)?;
-
-    if !owner_info.is_signer {
-        return Err(SwapError::InvalidSigner.into());
-    }
-
-    farm_user.init(
-        *config_info.key,
-        *farm_pool_info.key,
-        *owner_info.key,
-        FarmPosition::new(*farm_pool_info.key, clock.unix_timestamp)?,
-    );
-    FarmUser::pack(farm_user, &mut farm_user_info.data.borrow_mut())?;
+    token_transfer(
+        source_info.clone(),
+        vault_info.clone(),
+        user_transfer_authority_info.clone(),
+        token_program_info.clone(),
+        amount,
+        &[],
+    )?;
 
-    Ok(())
+    FarmRewardTokens::pack(
+        farm_reward_tokens,
+        &mut farm_reward_tokens_info.data.borrow_mut(),
+    )
 }
 
-fn process_farm_deposit(
+fn process_farm_user_reward_tokens_initialize(
     program_id: &Pubkey,
-    amount: u64,
     accounts: &[AccountInfo],
 ) -> ProgramResult {
     let account_info_iter = &mut accounts.iter();
     let config_info = next_account_info(account_info_iter)?;
     let farm_pool_info = next_account_info(account_info_iter)?;
-    let user_transfer_authority_info = next_account_info(account_info_iter)?;
-    let source_info = next_account_info(account_info_iter)?;
-    let destination_info = next_account_info(account_info_iter)?;
     let farm_user_info = next_account_info(account_info_iter)?;
+    let farm_user_reward_tokens_info = next_account_info(account_info_iter)?;
     let farm_owner_info = next_account_info(account_info_iter)?;
-    let clock = &Clock::get()?;
-    let token_program_info = next_account_info(account_info_iter)?;
+    let rent = &Rent::from_account_info(next_account_info(account_info_iter)?)?;
+    let system_program_info = next_account_info(account_info_iter)?;
 
     utils::validate(
         farm_user_info.owner == program_id
@@ -2171,78 +8735,75 @@ fn process_farm_deposit(
             && config_info.owner == program_id,
         SwapError::InvalidAccountOwner,
     )?;
-    spl_token::check_program_account(token_program_info.key)?;
-
-    let mut farm_user = FarmUser::unpack(&farm_user_info.data.borrow_mut()).unwrap();
-    if farm_user.owner != *farm_owner_info.key {
-        return Err(SwapError::InvalidOwner.into());
-    }
     if !farm_owner_info.is_signer {
         return Err(SwapError::InvalidSigner.into());
     }
 
-    let mut farm_info = FarmInfo::unpack(&farm_pool_info.data.borrow_mut())?;
+    let farm_info = FarmInfo::unpack(&farm_pool_info.data.borrow())?;
     utils::validate_farm_config_key(&farm_info, config_info.key)?;
-    let farm_user_pubkey =
-        get_farm_user_pubkey(farm_owner_info.key, farm_pool_info.key, program_id)?;
+    let farm_user = FarmUser::unpack_any(&farm_user_info.data.borrow())?;
     utils::validate(
-        *farm_user_info.key == farm_user_pubkey,
+        farm_user.farm_pool_key == *farm_pool_info.key,
         SwapError::InvalidAccountOwner,
     )?;
-
-    if farm_info.pool_token != *destination_info.key {
-        return Err(SwapError::InvalidInput.into());
-    }
-    let token_program_id = *token_program_info.key;
-    let source_token = unpack_token_account(source_info, &token_program_id)?;
-    let destination_token = unpack_token_account(destination_info, &token_program_id)?;
-    if source_token.mint != destination_token.mint {
-        return Err(SwapError::IncorrectMint.into());
-    }
-    if source_token.mint != farm_info.pool_mint {
-        return Err(SwapError::IncorrectMint.into());
+    if farm_user.owner != *farm_owner_info.key {
+        return Err(SwapError::InvalidOwner.into());
     }
 
-    // calculate and refresh reward before deposit
-    let apr = Decimal::from(farm_info.apr_numerator).try_div(farm_info.apr_denominator)?;
-    farm_user
-        .position
-        .calc_and_update_rewards(apr, clock.unix_timestamp, true)?;
-    farm_user.position.deposit(amount, clock.slot)?;
-    FarmUser::pack(farm_user, &mut farm_user_info.data.borrow_mut())?;
-
-    farm_info.deposit(amount)?;
-
-    token_transfer(
-        source_info.clone(),
-        destination_info.clone(),
-        user_transfer_authority_info.clone(),
-        token_program_info.clone(),
-        amount,
-        &[],
+    let (farm_user_reward_tokens_key, bump_seed) =
+        find_farm_user_reward_tokens_address(farm_user_info.key, program_id);
+    utils::validate(
+        *farm_user_reward_tokens_info.key == farm_user_reward_tokens_key,
+        SwapError::InvalidFarmRewardToken,
+    )?;
+    utils::validate(
+        system_program::check_id(system_program_info.key),
+        SwapError::InvalidInput,
+    )?;
+    invoke_signed(
+        &system_instruction::create_account(
+            farm_owner_info.key,
+            farm_user_reward_tokens_info.key,
+            rent.minimum_balance(FarmUserRewardTokens::LEN),
+            FarmUserRewardTokens::LEN as u64,
+            program_id,
+        ),
+        &[
+            farm_owner_info.clone(),
+            farm_user_reward_tokens_info.clone(),
+            system_program_info.clone(),
+        ],
+        &[&[
+            SEED_FARM_USER_REWARD_TOKENS.as_bytes(),
+            farm_user_info.key.as_ref(),
+            &[bump_seed],
+        ]],
     )?;
 
-    let destination_token = unpack_token_account(destination_info, &token_program_id)?;
-    farm_info.check_reserve_amount(destination_token.amount)?;
-    FarmInfo::pack(farm_info, &mut farm_pool_info.data.borrow_mut())?;
-
-    Ok(())
+    // The staked amount can not change without the account once the farm emits reward tokens,
+    // so it was staked since every reward token was added and owes it all from zero debts
+    FarmUserRewardTokens::pack(
+        FarmUserRewardTokens {
+            is_initialized: true,
+            farm_user_key: *farm_user_info.key,
+            ..FarmUserRewardTokens::default()
+        },
+        &mut farm_user_reward_tokens_info.data.borrow_mut(),
+    )
 }
 
-fn process_farm_withdraw(
+fn process_farm_claim_reward_tokens(
     program_id: &Pubkey,
-    amount: u64,
     accounts: &[AccountInfo],
 ) -> ProgramResult {
     let account_info_iter = &mut accounts.iter();
     let config_info = next_account_info(account_info_iter)?;
     let farm_pool_info = next_account_info(account_info_iter)?;
     let farm_user_info = next_account_info(account_info_iter)?;
-    let authority_info = next_account_info(account_info_iter)?;
-    let source_info = next_account_info(account_info_iter)?;
-    let destination_info = next_account_info(account_info_iter)?;
+    let farm_reward_tokens_info = next_account_info(account_info_iter)?;
+    let farm_user_reward_tokens_info = next_account_info(account_info_iter)?;
     let farm_owner_info = next_account_info(account_info_iter)?;
-    let clock = &Clock::get()?;
+    let authority_info = next_account_info(account_info_iter)?;
     let token_program_info = next_account_info(account_info_iter)?;
 
     utils::validate(
@@ -2253,15 +8814,7 @@ fn process_farm_withdraw(
     )?;
     spl_token::check_program_account(token_program_info.key)?;
 
-    let mut farm_user = FarmUser::unpack(&farm_user_info.data.borrow_mut())?;
-    if farm_user.owner != *farm_owner_info.key {
-        return Err(SwapError::InvalidOwner.into());
-    }
-    if !farm_owner_info.is_signer {
-        return Err(SwapError::InvalidSigner.into());
-    }
-
-    let mut farm_info = FarmInfo::unpack(&farm_pool_info.data.borrow_mut())?;
+    let farm_info = FarmInfo::unpack(&farm_pool_info.data.borrow())?;
     utils::validate_farm_config_key(&farm_info, config_info.key)?;
 
     let farm_user_pubkey =
@@ -2270,6 +8823,13 @@ fn process_farm_withdraw(
         *farm_user_info.key == farm_user_pubkey,
         SwapError::InvalidAccountOwner,
     )?;
+    let farm_user = FarmUser::unpack_any(&farm_user_info.data.borrow())?;
+    if farm_user.owner != *farm_owner_info.key {
+        return Err(SwapError::InvalidOwner.into());
+    }
+    if !farm_owner_info.is_signer {
+        return Err(SwapError::InvalidSigner.into());
+    }
 
     let farm_authority_signer_seeds = &[farm_pool_info.key.as_ref(), &[farm_info.bump_seed]];
     if *authority_info.key
@@ -2277,87 +8837,104 @@ fn process_farm_withdraw(
     {
         return Err(SwapError::InvalidProgramAddress.into());
     }
-    let token_program_id = *token_program_info.key;
-    let source_token = unpack_token_account(source_info, &token_program_id)?;
-    let destination_token = unpack_token_account(destination_info, &token_program_id)?;
-    if source_token.mint != destination_token.mint {
-        return Err(SwapError::IncorrectMint.into());
-    }
-    if source_token.mint != farm_info.pool_mint {
-        return Err(SwapError::IncorrectMint.into());
-    }
 
-    /* make sure source_info token account is farm_pool's token account */
-    if farm_info.pool_token != *source_info.key {
-        return Err(SwapError::InvalidInput.into());
+    let mut farm_reward_tokens =
+        unpack_farm_reward_tokens(program_id, farm_pool_info.key, farm_reward_tokens_info)?;
+    let mut farm_user_reward_tokens = unpack_farm_user_reward_tokens(
+        program_id,
+        farm_user_info.key,
+        farm_user_reward_tokens_info,
+    )?;
+    farm_reward_tokens.accrue(farm_info.reserved_amount, Clock::get()?.slot)?;
+    farm_user_reward_tokens.settle(&farm_reward_tokens, farm_user.position.deposited_amount)?;
+    let claimed_amounts = farm_user_reward_tokens.claim();
+
+    // Each reward token in use is paid from its vault to the destination following it
+    for (reward_token, amount) in farm_reward_tokens.used().iter().zip(claimed_amounts.iter()) {
+        let vault_info = next_account_info(account_info_iter)?;
+        let destination_info = next_account_info(account_info_iter)?;
+        utils::validate(
+            reward_token.vault == *vault_info.key,
+            SwapError::InvalidFarmRewardToken,
+        )?;
+        if *amount > 0 {
+            token_transfer(
+                vault_info.clone(),
+                destination_info.clone(),
+                authority_info.clone(),
+                token_program_info.clone(),
+                *amount,
+                farm_authority_signer_seeds,
+            )?;
+        }
     }
 
-    // calculate and refresh reward before withdraw
-    let apr = Decimal::from(farm_info.apr_numerator).try_div(farm_info.apr_denominator)?;
-    farm_user
-        .position
-        .calc_and_update_rewards(apr, clock.unix_timestamp, true)?;
-
-    farm_user.withdraw(amount, clock.slot)?;
-    FarmUser::pack(farm_user, &mut farm_user_info.data.borrow_mut())?;
-
-    farm_info.withdraw(amount)?;
-
-    token_transfer(
-        source_info.clone(),
-        destination_info.clone(),
-        authority_info.clone(),
-        token_program_info.clone(),
-        amount,
-        farm_authority_signer_seeds,
+    FarmRewardTokens::pack(
+        farm_reward_tokens,
+        &mut farm_reward_tokens_info.data.borrow_mut(),
     )?;
-
-    let source_token = unpack_token_account(source_info, &token_program_id)?;
-    farm_info.check_reserve_amount(source_token.amount)?;
-    FarmInfo::pack(farm_info, &mut farm_pool_info.data.borrow_mut())?;
-    Ok(())
+    FarmUserRewardTokens::pack(
+        farm_user_reward_tokens,
+        &mut farm_user_reward_tokens_info.data.borrow_mut(),
+    )
 }
 
-fn process_farm_claim(program_id: &Pubkey, accounts: &[AccountInfo]) -> ProgramResult {
+fn process_farm_compound(
+    program_id: &Pubkey,
+    min_mint_amount: u64,
+    accounts: &[AccountInfo],
+) -> ProgramResult {
     let account_info_iter = &mut accounts.iter();
     let config_info = next_account_info(account_info_iter)?;
     let farm_pool_info = next_account_info(account_info_iter)?;
     let farm_user_info = next_account_info(account_info_iter)?;
-    let farm_owner_info = next_account_info(account_info_iter)?;
+    let compound_authority_info = next_account_info(account_info_iter)?;
     let market_authority_info = next_account_info(account_info_iter)?;
-    let claim_destination_info = next_account_info(account_info_iter)?;
     let claim_source_info = next_account_info(account_info_iter)?;
+    let swap_info = next_account_info(account_info_iter)?;
+    let swap_authority_info = next_account_info(account_info_iter)?;
+    let token_a_info = next_account_info(account_info_iter)?;
+    let token_b_info = next_account_info(account_info_iter)?;
+    let admin_destination_info = next_account_info(account_info_iter)?;
+    let pool_mint_info = next_account_info(account_info_iter)?;
+    let farm_pool_token_info = next_account_info(account_info_iter)?;
     let clock = &Clock::get()?;
-    let token_program_info = next_account_info(account_info_iter)?;
 
     utils::validate(
         farm_user_info.owner == program_id
             && farm_pool_info.owner == program_id
-            && config_info.owner == program_id,
+            && config_info.owner == program_id
+            && swap_info.owner == program_id,
         SwapError::InvalidAccountOwner,
     )?;
-    spl_token::check_program_account(token_program_info.key)?;
 
-    let farm_info = FarmInfo::unpack(&farm_pool_info.data.borrow_mut())?;
+    let mut farm_info = FarmInfo::unpack(&farm_pool_info.data.borrow())?;
     utils::validate_farm_config_key(&farm_info, config_info.key)?;
+    if farm_info.pool_token != *farm_pool_token_info.key {
+        return Err(SwapError::InvalidInput.into());
+    }
 
-    let farm_user_pubkey =
-        get_farm_user_pubkey(farm_owner_info.key, farm_pool_info.key, program_id)?;
+    let mut farm_user = FarmUser::unpack_any(&farm_user_info.data.borrow())?;
+    let farm_user_pubkey = get_farm_user_pubkey(&farm_user.owner, farm_pool_info.key, program_id)?;
     utils::validate(
         *farm_user_info.key == farm_user_pubkey,
         SwapError::InvalidAccountOwner,
     )?;
-
-    let mut farm_user = FarmUser::unpack(&farm_user_info.data.borrow_mut())?;
-    if farm_user.config_key != *config_info.key {
-        return Err(SwapError::InvalidOwner.into());
-    }
-    if farm_user.owner != *farm_owner_info.key {
+    // Only the owner or the delegate it set can compound the rewards of the farm user
+    if *compound_authority_info.key != farm_user.owner
+        && (farm_user.compound_delegate == Pubkey::default()
+            || *compound_authority_info.key != farm_user.compound_delegate)
+    {
         return Err(SwapError::InvalidOwner.into());
     }
-    if !farm_owner_info.is_signer {
+    if !compound_authority_info.is_signer {
         return Err(SwapError::InvalidSigner.into());
     }
+    // The recipients of a reward split are paid at claim only
+    utils::validate(
+        farm_user.reward_split == Pubkey::default(),
+        SwapError::InvalidRewardSplit,
+    )?;
 
     let config = ConfigInfo::unpack(&config_info.data.borrow())?;
     let market_authority_signer_seeds = &[config_info.key.as_ref(), &[config.bump_seed]];
@@ -2367,94 +8944,310 @@ fn process_farm_claim(program_id: &Pubkey, accounts: &[AccountInfo]) -> ProgramR
         return Err(SwapError::InvalidProgramAddress.into());
     }
 
-    if claim_destination_info.owner == market_authority_info.key {
+    let mut token_swap = SwapInfo::unpack(&swap_info.data.borrow())?;
+    utils::validate_swap_config_key(&token_swap, config_info.key)?;
+    utils::validate(
+        token_swap.pool_mint == farm_info.pool_mint,
+        SwapError::IncorrectMint,
+    )?;
+    // The compounded deposit has no whitelist entry to present
+    if token_swap.whitelist_enabled {
+        return Err(SwapError::NotWhitelisted.into());
+    }
+    let swap_type = token_swap.swap_type;
+    update_pool_market_price(
+        &mut token_swap,
+        swap_type,
+        &config.accepted_orderbook_program_id(),
+        account_info_iter,
+    )?;
+
+    let token_program_info = next_account_info(account_info_iter)?;
+    spl_token::check_program_account(token_program_info.key)?;
+    let token_program_id = *token_program_info.key;
+    let claim_source = unpack_token_account(claim_source_info, &token_program_id)?;
+    utils::validate(
+        config.deltafi_mint == claim_source.mint,
+        SwapError::IncorrectMint,
+    )?;
+    utils::validate(
+        claim_source.owner == *market_authority_info.key,
+        SwapError::InvalidOwner,
+    )?;
+
+    let farm_reward_token_accounts = settle_farm_reward_tokens(
+        program_id,
+        farm_pool_info.key,
+        &farm_info,
+        farm_user_info.key,
+        farm_user.position.deposited_amount,
+        clock.slot,
+        account_info_iter,
+    )?;
+
+    farm_user.accrue_rewards(&farm_info, clock, false)?;
+    let reward_amount = farm_user.claim()?;
+    let (pool_mint_amount, deposit_in_amount) = deposit_one_into_pool(
+        program_id,
+        token_swap,
+        swap_info,
+        swap_authority_info,
+        market_authority_info,
+        market_authority_signer_seeds,
+        claim_source_info,
+        token_a_info,
+        token_b_info,
+        admin_destination_info,
+        pool_mint_info,
+        farm_pool_token_info,
+        token_program_info,
+        farm_user_info.key,
+        reward_amount,
+        min_mint_amount,
+    )?;
+    farm_user.position.restore_rewards(
+        reward_amount
+            .checked_sub(deposit_in_amount)
+            .ok_or(SwapError::CalculationFailure)?,
+    )?;
+
+    farm_user.deposit_into_farm(&mut farm_info, pool_mint_amount, clock)?;
+    reset_farm_reward_tokens(
+        farm_reward_token_accounts,
+        farm_user.position.deposited_amount,
+    )?;
+    FarmUser::pack_any(farm_user, &mut farm_user_info.data.borrow_mut())?;
+
+    let farm_pool_token = unpack_token_account(farm_pool_token_info, &token_program_id)?;
+    farm_info.check_reserve_amount(farm_pool_token.amount)?;
+    FarmEvent {
+        event_type: EventType::FarmCompound,
+        farm_pool: *farm_pool_info.key,
+        farm_user: *farm_user_info.key,
+        amount: pool_mint_amount,
+    }
+    .emit();
+    FarmInfo::pack(farm_info, &mut farm_pool_info.data.borrow_mut())
+}
+
+fn process_farm_set_compound_delegate(
+    program_id: &Pubkey,
+    compound_delegate: &Pubkey,
+    accounts: &[AccountInfo],
+) -> ProgramResult {
+    let account_info_iter = &mut accounts.iter();
+    let config_info = next_account_info(account_info_iter)?;
+    let farm_pool_info = next_account_info(account_info_iter)?;
+    let farm_user_info = next_account_info(account_info_iter)?;
+    let farm_owner_info = next_account_info(account_info_iter)?;
+
+    utils::validate(
+        farm_user_info.owner == program_id
+            && farm_pool_info.owner == program_id
+            && config_info.owner == program_id,
+        SwapError::InvalidAccountOwner,
+    )?;
+
+    let farm_info = FarmInfo::unpack(&farm_pool_info.data.borrow())?;
+    utils::validate_farm_config_key(&farm_info, config_info.key)?;
+
+    let farm_user_pubkey =
+        get_farm_user_pubkey(farm_owner_info.key, farm_pool_info.key, program_id)?;
+    utils::validate(
+        *farm_user_info.key == farm_user_pubkey,
+        SwapError::InvalidAccountOwner,
+    )?;
+
+    let mut farm_user = FarmUser::unpack_any(&farm_user_info.data.borrow())?;
+    if farm_user.config_key != *config_info.key || farm_user.owner != *farm_owner_info.key {
         return Err(SwapError::InvalidOwner.into());
     }
+    if !farm_owner_info.is_signer {
+        return Err(SwapError::InvalidSigner.into());
+    }
 
-    // calculate and refresh reward before deposit
-    let apr = Decimal::from(farm_info.apr_numerator).try_div(farm_info.apr_denominator)?;
-    farm_user
-        .position
-        .calc_and_update_rewards(apr, clock.unix_timestamp, false)?;
+    // The farm users created before the padding have no room for the delegate
+    farm_user.compound_delegate = *compound_delegate;
+    FarmUser::pack_any(farm_user, &mut farm_user_info.data.borrow_mut())
+}
 
-    let token_program_id = *token_program_info.key;
-    let claim_destination = unpack_token_account(claim_destination_info, &token_program_id)?;
-    let claim_source = unpack_token_account(claim_source_info, &token_program_id)?;
-    validate_reward_token_accounts(
-        &config,
-        market_authority_info.key,
-        &claim_source,
-        &claim_destination,
-    )?;
+fn process_farm_transfer_position(program_id: &Pubkey, accounts: &[AccountInfo]) -> ProgramResult {
+    let account_info_iter = &mut accounts.iter();
+    let config_info = next_account_info(account_info_iter)?;
+    let farm_pool_info = next_account_info(account_info_iter)?;
+    let source_farm_user_info = next_account_info(account_info_iter)?;
+    let source_owner_info = next_account_info(account_info_iter)?;
+    let destination_farm_user_info = next_account_info(account_info_iter)?;
+    let clock = &Clock::get()?;
 
-    let reward_amount = farm_user.claim()?;
-    token_transfer(
-        claim_source_info.clone(),
-        claim_destination_info.clone(),
-        market_authority_info.clone(),
-        token_program_info.clone(),
-        reward_amount,
-        market_authority_signer_seeds,
+    utils::validate(
+        source_farm_user_info.owner == program_id
+            && destination_farm_user_info.owner == program_id
+            && farm_pool_info.owner == program_id
+            && config_info.owner == program_id,
+        SwapError::InvalidAccountOwner,
     )?;
+    if source_farm_user_info.key == destination_farm_user_info.key {
+        return Err(SwapError::InvalidInput.into());
+    }
 
-    // Handle referral reward
-    if let Some(user_referrer_data_info) = account_info_iter.next() {
-        let referrer_token_info = next_account_info(account_info_iter)?;
+    let mut source_farm_user = FarmUser::unpack_any(&source_farm_user_info.data.borrow())?;
+    if source_farm_user.owner != *source_owner_info.key {
+        return Err(SwapError::InvalidOwner.into());
+    }
+    if !source_owner_info.is_signer {
+        return Err(SwapError::InvalidSigner.into());
+    }
+    let mut destination_farm_user =
+        FarmUser::unpack_any(&destination_farm_user_info.data.borrow())?;
+
+    let farm_info = FarmInfo::unpack(&farm_pool_info.data.borrow())?;
+    utils::validate_farm_config_key(&farm_info, config_info.key)?;
 
-        let expected_user_referrer_data_pubkey =
-            get_referrer_data_pubkey(&farm_user.owner, config_info.key, program_id)?;
+    for (farm_user_info, farm_user) in &[
+        (source_farm_user_info, &source_farm_user),
+        (destination_farm_user_info, &destination_farm_user),
+    ] {
+        if farm_user.config_key != *config_info.key
+            || farm_user.farm_pool_key != *farm_pool_info.key
+        {
+            return Err(SwapError::InvalidOwner.into());
+        }
+        let farm_user_pubkey =
+            get_farm_user_pubkey(&farm_user.owner, farm_pool_info.key, program_id)?;
         utils::validate(
-            expected_user_referrer_data_pubkey == *user_referrer_data_info.key,
+            *farm_user_info.key == farm_user_pubkey,
             SwapError::InvalidAccountOwner,
         )?;
+    }
 
-        let user_referrer_data = UserReferrerData::unpack(&user_referrer_data_info.data.borrow())?;
-        utils::validate(
-            user_referrer_data.referrer == *referrer_token_info.key
-                && user_referrer_data.config_key == *config_info.key,
-            SwapError::InvalidAccountOwner,
+    // The partner rewards staked so far stay claimable by the source owner, the
+    // destination accrues them from the transfer on
+    let staked_amount = source_farm_user.position.deposited_amount;
+    if farm_info.reward_token_count > 0 {
+        let farm_reward_tokens_info = next_account_info(account_info_iter)?;
+        let source_reward_tokens_info = next_account_info(account_info_iter)?;
+        let destination_reward_tokens_info = next_account_info(account_info_iter)?;
+        let mut farm_reward_tokens =
+            unpack_farm_reward_tokens(program_id, farm_pool_info.key, farm_reward_tokens_info)?;
+        let mut source_reward_tokens = unpack_farm_user_reward_tokens(
+            program_id,
+            source_farm_user_info.key,
+            source_reward_tokens_info,
+        )?;
+        let mut destination_reward_tokens = unpack_farm_user_reward_tokens(
+            program_id,
+            destination_farm_user_info.key,
+            destination_reward_tokens_info,
         )?;
 
-        // Dummy referrer is a placeholder to indicate that the user is not referred by anyone.
-        // If it is given in the input, skip the referral reward distribution here.
-        if user_referrer_data.referrer.to_string() != DUMMY_REFERRER_ADDRESS {
-            let referral_reward = config.rewards.referral_reward(reward_amount)?;
-            token_transfer(
-                claim_source_info.clone(),
-                referrer_token_info.clone(),
-                market_authority_info.clone(),
-                token_program_info.clone(),
-                referral_reward,
-                market_authority_signer_seeds,
-            )?;
-        }
+        farm_reward_tokens.accrue(farm_info.reserved_amount, clock.slot)?;
+        source_reward_tokens.settle(&farm_reward_tokens, staked_amount)?;
+        source_reward_tokens.reset_debts(&farm_reward_tokens, 0)?;
+        destination_reward_tokens.settle(&farm_reward_tokens, 0)?;
+        destination_reward_tokens.reset_debts(&farm_reward_tokens, staked_amount)?;
+        FarmRewardTokens::pack(
+            farm_reward_tokens,
+            &mut farm_reward_tokens_info.data.borrow_mut(),
+        )?;
+        FarmUserRewardTokens::pack(
+            source_reward_tokens,
+            &mut source_reward_tokens_info.data.borrow_mut(),
+        )?;
+        FarmUserRewardTokens::pack(
+            destination_reward_tokens,
+            &mut destination_reward_tokens_info.data.borrow_mut(),
+        )?;
     }
 
-    FarmUser::pack(farm_user, &mut farm_user_info.data.borrow_mut())?;
+    source_farm_user.transfer_position(&mut destination_farm_user, &farm_info, clock)?;
+    FarmUser::pack_any(
+        source_farm_user,
+        &mut source_farm_user_info.data.borrow_mut(),
+    )?;
+    FarmUser::pack_any(
+        destination_farm_user,
+        &mut destination_farm_user_info.data.borrow_mut(),
+    )
+}
 
-    Ok(())
+/// Consume the price cache if it is the next account and return its price if fresh,
+/// the pyth accounts are read otherwise
+fn next_cached_price(
+    program_id: &Pubkey,
+    swap_key: &Pubkey,
+    token_swap: &SwapInfo,
+    clock: &impl TimeSource,
+    account_info_iter: &mut std::slice::Iter<AccountInfo>,
+) -> Result<Option<(Decimal, u64)>, ProgramError> {
+    match account_info_iter.as_slice().first() {
+        Some(info) if info.owner == program_id && info.data_len() == PriceCache::LEN => {}
+        _ => return Ok(None),
+    }
+    let price_cache_info = next_account_info(account_info_iter)?;
+    let price_cache = PriceCache::unpack(&price_cache_info.data.borrow())?;
+    utils::validate(
+        price_cache.swap_key == *swap_key,
+        SwapError::InvalidPriceCache,
+    )?;
+    Ok(price_cache.fresh_price(token_swap, clock))
 }
 
 fn get_market_price_from_pyth(
     pyth_a_price_info: &AccountInfo,
     pyth_b_price_info: &AccountInfo,
-    clock: &Clock,
+    clock: &impl TimeSource,
+    oracle_params: &OracleParams,
+) -> Result<(Decimal, u64), ProgramError> {
+    let (price_a, slot_a) = get_pyth_price(pyth_a_price_info, clock, oracle_params)?;
+    let (price_b, slot_b) = get_pyth_price(pyth_b_price_info, clock, oracle_params)?;
+    let market_price = price_a.try_div(price_b)?;
+    let valid_slot = min(slot_a, slot_b);
+
+    Ok((market_price, valid_slot))
+}
+
+fn get_market_price_from_chainlink(
+    chainlink_a_feed_info: &AccountInfo,
+    chainlink_b_feed_info: &AccountInfo,
+    clock: &impl TimeSource,
+    oracle_params: &OracleParams,
 ) -> Result<(Decimal, u64), ProgramError> {
-    let (price_a, slot_a) = get_pyth_price(pyth_a_price_info, clock)?;
-    let (price_b, slot_b) = get_pyth_price(pyth_b_price_info, clock)?;
+    let (price_a, slot_a) = get_chainlink_price(chainlink_a_feed_info, clock, oracle_params)?;
+    let (price_b, slot_b) = get_chainlink_price(chainlink_b_feed_info, clock, oracle_params)?;
     let market_price = price_a.try_div(price_b)?;
     let valid_slot = min(slot_a, slot_b);
 
     Ok((market_price, valid_slot))
 }
 
+fn get_chainlink_price(
+    chainlink_feed_info: &AccountInfo,
+    clock: &impl TimeSource,
+    oracle_params: &OracleParams,
+) -> Result<(Decimal, u64), ProgramError> {
+    let feed = chainlink::Feed::load(chainlink_feed_info)?;
+
+    // Stale chainlink round
+    let slots_elapsed = clock
+        .slot()
+        .checked_sub(feed.latest_round.slot)
+        .ok_or(SwapError::CalculationFailure)?;
+    if slots_elapsed >= oracle_params.chainlink_stale_after_slots() {
+        msg!("Chainlink price is stale");
+        return Err(SwapError::StaleChainlinkPrice.into());
+    }
+
+    // The answer is scaled by the decimals of the feed, the token decimals are applied
+    // with the market price of the pool
+    Ok((feed.price()?, feed.latest_round.slot))
+}
+
 fn get_pyth_price(
     pyth_price_info: &AccountInfo,
-    clock: &Clock,
+    clock: &impl TimeSource,
+    oracle_params: &OracleParams,
 ) -> Result<(Decimal, u64), ProgramError> {
-    // Each slot has minimum 400ms. Set the stale timeout to 4s.
-    const STALE_AFTER_SLOTS_ELAPSED: u64 = 10;
-
     let pyth_price_data = pyth_price_info.try_borrow_data()?;
     let pyth_price = pyth::load::<pyth::Price>(&pyth_price_data)
         .map_err(|_| ProgramError::InvalidAccountData)?;
@@ -2469,13 +9262,15 @@ fn get_pyth_price(
         return Err(SwapError::InvalidPythConfig.into());
     }
 
-    // Too few Pyth data providers
+    // Too few Pyth data providers, the count stops at the required publishers
+    let required_publishers = oracle_params.required_publishers();
     if pyth_price
         .comp
         .iter()
         .filter(|comp| comp.is_active())
+        .take(required_publishers)
         .count()
-        < 3
+        < required_publishers
     {
         msg!("Pyth price is not guaranteed");
         return Err(SwapError::InvalidPythConfig.into());
@@ -2483,10 +9278,10 @@ fn get_pyth_price(
 
     // Stale Pyth price data
     let slots_elapsed = clock
-        .slot
+        .slot()
         .checked_sub(pyth_price.valid_slot)
         .ok_or(SwapError::CalculationFailure)?;
-    if slots_elapsed >= STALE_AFTER_SLOTS_ELAPSED {
+    if slots_elapsed >= oracle_params.stale_after_slots() {
         msg!("Pyth price is stale");
         return Err(SwapError::StalePythPrice.into());
     }
@@ -2496,21 +9291,7 @@ fn get_pyth_price(
         SwapError::InvalidPythConfig
     })?;
 
-    // Pyth confidence interval is larger than 2% of the price.
-    // pyth_price.agg.conf is the confidence interval length of the pyth price.
-    // The confident price range will be [price - pyth_price.agg.conf, price + pyth_price.agg.conf]
-    // Example: price=100, conf=2, it means the interval is in [98, 102]
-    if pyth_price.agg.conf > 0
-        && price
-            < pyth_price
-                .agg
-                .conf
-                .checked_mul(50u64)
-                .ok_or(SwapError::CalculationFailure)?
-    {
-        msg!("Pyth suggests market is volatile");
-        return Err(SwapError::InconfidentPythPrice.into());
-    }
+    check_pyth_confidence(price, pyth_price.agg.conf, oracle_params)?;
 
     // Too volatile Pyth price
     if pyth_price.agg.price
@@ -2526,18 +9307,95 @@ fn get_pyth_price(
         return Err(SwapError::UnstableMarketPrice.into());
     }
 
-    let market_price = if pyth_price.expo >= 0 {
-        let exponent = pyth_price
-            .expo
-            .try_into()
-            .map_err(|_| SwapError::CalculationFailure)?;
+    let market_price = pyth_price_to_decimal(price, pyth_price.expo)?;
+
+    Ok((market_price, min(clock.slot(), pyth_price.valid_slot)))
+}
+
+fn get_market_price_from_pyth_pull(
+    pyth_a_feed_id: &Pubkey,
+    pyth_b_feed_id: &Pubkey,
+    pyth_a_price_update_info: &AccountInfo,
+    pyth_b_price_update_info: &AccountInfo,
+    clock: &impl TimeSource,
+    oracle_params: &OracleParams,
+) -> Result<(Decimal, u64), ProgramError> {
+    let (price_a, slot_a) = get_pyth_pull_price(
+        pyth_a_price_update_info,
+        pyth_a_feed_id,
+        clock,
+        oracle_params,
+    )?;
+    let (price_b, slot_b) = get_pyth_pull_price(
+        pyth_b_price_update_info,
+        pyth_b_feed_id,
+        clock,
+        oracle_params,
+    )?;
+    let market_price = price_a.try_div(price_b)?;
+    let valid_slot = min(slot_a, slot_b);
+
+    Ok((market_price, valid_slot))
+}
+
+fn get_pyth_pull_price(
+    pyth_price_update_info: &AccountInfo,
+    pyth_feed_id: &Pubkey,
+    clock: &impl TimeSource,
+    oracle_params: &OracleParams,
+) -> Result<(Decimal, u64), ProgramError> {
+    let price_update = pyth_pull::PriceUpdate::load(pyth_price_update_info)?;
+    let price_message = price_update.checked_price_message(&pyth_feed_id.to_bytes())?;
+
+    // Stale pyth price update, the publish time is the time of the price on pythnet
+    if clock
+        .unix_timestamp()
+        .saturating_sub(price_message.publish_time)
+        >= oracle_params.pyth_pull_stale_after_seconds()
+    {
+        msg!("Pyth price update is stale");
+        return Err(SwapError::StalePythPrice.into());
+    }
+
+    let price: u64 = price_message.price.try_into().map_err(|_| {
+        msg!("Pyth price cannot be negative");
+        SwapError::InvalidPythPriceUpdate
+    })?;
+    check_pyth_confidence(price, price_message.conf, oracle_params)?;
+
+    let market_price = pyth_price_to_decimal(price, price_message.exponent)?;
+
+    Ok((market_price, min(clock.slot(), price_update.posted_slot)))
+}
+
+fn check_pyth_confidence(price: u64, conf: u64, oracle_params: &OracleParams) -> ProgramResult {
+    // Pyth confidence interval is larger than the pool limit, 2% of the price by default.
+    // conf is the confidence interval length of the pyth price.
+    // The confident price range will be [price - conf, price + conf]
+    // Example: price=100, conf=2, it means the interval is in [98, 102]
+    if conf > 0
+        && (price as u128)
+            .checked_mul(oracle_params.confidence_limit_bps() as u128)
+            .ok_or(SwapError::CalculationFailure)?
+            < (conf as u128)
+                .checked_mul(10_000u128)
+                .ok_or(SwapError::CalculationFailure)?
+    {
+        msg!("Pyth suggests market is volatile");
+        return Err(SwapError::InconfidentPythPrice.into());
+    }
+    Ok(())
+}
+
+fn pyth_price_to_decimal(price: u64, expo: i32) -> Result<Decimal, ProgramError> {
+    if expo >= 0 {
+        let exponent = expo.try_into().map_err(|_| SwapError::CalculationFailure)?;
         let zeros = 10u64
             .checked_pow(exponent)
             .ok_or(SwapError::CalculationFailure)?;
-        Decimal::from(price).try_mul(zeros)?
+        Decimal::from(price).try_mul(zeros)
     } else {
-        let exponent = pyth_price
-            .expo
+        let exponent = expo
             .checked_abs()
             .ok_or(SwapError::CalculationFailure)?
             .try_into()
@@ -2545,10 +9403,8 @@ fn get_pyth_price(
         let decimals = 10u64
             .checked_pow(exponent)
             .ok_or(SwapError::CalculationFailure)?;
-        Decimal::from(price).try_div(decimals)?
-    };
-
-    Ok((market_price, min(clock.slot, pyth_price.valid_slot)))
+        Decimal::from(price).try_div(decimals)
+    }
 }
 
 fn calculate_serum_market_price(
@@ -2594,37 +9450,23 @@ fn get_market_price_from_serum(
     token_b_decimals: u8,
     serum_program_id: &Pubkey,
 ) -> Result<Decimal, ProgramError> {
-    utils::check_serum_program_id(serum_program_id)?;
+    utils::check_orderbook_program_id(serum_program_id)?;
+    utils::check_serum_program_id(serum_market_info.owner, serum_program_id)?;
 
     // The logic to get price from serum market orerbook
-    // Market -> Slab -> Nodehandle -> AnyNode -> LeafNode -> OrderId -> priceLot -> price
-    let market = Market::load(serum_market_info, serum_program_id, true)?;
-    // serum_dex only have this mutable api to load bids/asks, which is a verified usage.
-    let bids_slab_refmut = market.load_bids_mut(serum_bids_info)?;
-    let asks_slab_refmut = market.load_asks_mut(serum_asks_info)?;
-
-    let bids_node_handle = bids_slab_refmut
-        .find_max()
-        .ok_or(SwapError::InvalidSerumData)?;
-    let asks_node_handle = asks_slab_refmut
-        .find_min()
-        .ok_or(SwapError::InvalidSerumData)?;
-
-    let bids_anynode = bids_slab_refmut
-        .get(bids_node_handle)
+    // Market -> Slab -> best LeafNode -> OrderId -> priceLot -> price
+    // The accounts are only borrowed immutably so they can be passed readonly.
+    let market = orderbook::load_market(serum_market_info)?;
+    let best_bid_order_id = orderbook::best_order_id(&market, serum_bids_info, Side::Bid)?
         .ok_or(SwapError::InvalidSerumData)?;
-    let asks_anynode = asks_slab_refmut
-        .get(asks_node_handle)
+    let best_ask_order_id = orderbook::best_order_id(&market, serum_asks_info, Side::Ask)?
         .ok_or(SwapError::InvalidSerumData)?;
 
-    let bids_leaf = bids_anynode.as_leaf().ok_or(SwapError::InvalidSerumData)?;
-    let asks_leaf = asks_anynode.as_leaf().ok_or(SwapError::InvalidSerumData)?;
-
     let market_price: Decimal = calculate_serum_market_price(
         market.coin_lot_size,
         market.pc_lot_size,
-        bids_leaf.order_id() >> 64, // priceLot = orderId >> 64
-        asks_leaf.order_id() >> 64,
+        best_bid_order_id >> 64, // priceLot = orderId >> 64
+        best_ask_order_id >> 64,
         token_a_decimals,
         token_b_decimals,
     )?;
@@ -2633,32 +9475,73 @@ fn get_market_price_from_serum(
 
 fn get_market_price(
     oracle_priority_flags: u8,
+    max_price_divergence: u16,
+    oracle_params: &OracleParams,
     pyth_a_price_info: &AccountInfo,
     pyth_b_price_info: &AccountInfo,
-    clock: &Clock,
+    clock: &impl TimeSource,
     serum_market_info: &AccountInfo,
     serum_bids_info: &AccountInfo,
     serum_asks_info: &AccountInfo,
+    orderbook_program_id: &Pubkey,
     token_a_decimals: u8,
     token_b_decimals: u8,
+    quote_is_token_a: bool,
 ) -> Result<(Decimal, u64), ProgramError> {
-    match OraclePriorityFlag::from_bits_truncate(oracle_priority_flags) {
-        OraclePriorityFlag::PYTH_ONLY => {
-            match get_market_price_from_pyth(pyth_a_price_info, pyth_b_price_info, clock) {
-                Ok((market_price, valid_slot)) => Ok((market_price, valid_slot)),
-                Err(e) => Err(e),
-            }
-        }
-        OraclePriorityFlag::SERUM_ONLY => {
-            match get_market_price_from_serum(
+    // The serum market trades the pair in its conventional order, token B in token A when
+    // token A is the quote, so its price is inverted into the token A/B order of the pool.
+    let get_serum_price = || {
+        if quote_is_token_a {
+            Decimal::one().try_div(get_market_price_from_serum(
+                serum_market_info,
+                serum_bids_info,
+                serum_asks_info,
+                token_b_decimals,
+                token_a_decimals,
+                orderbook_program_id,
+            )?)
+        } else {
+            get_market_price_from_serum(
                 serum_market_info,
                 serum_bids_info,
                 serum_asks_info,
                 token_a_decimals,
                 token_b_decimals,
-                &Pubkey::from_str(SERUM_DEX_V3_PROGRAM_ID).unwrap(),
+                orderbook_program_id,
+            )
+        }
+    };
+
+    match OraclePriorityFlag::from_bits_truncate(oracle_priority_flags) {
+        OraclePriorityFlag::PYTH_ONLY => {
+            get_market_price_from_pyth(pyth_a_price_info, pyth_b_price_info, clock, oracle_params)
+        }
+        OraclePriorityFlag::SERUM_ONLY => Ok((get_serum_price()?, clock.slot())),
+        // The chainlink feeds take the pyth price accounts of the pool
+        OraclePriorityFlag::CHAINLINK => get_market_price_from_chainlink(
+            pyth_a_price_info,
+            pyth_b_price_info,
+            clock,
+            oracle_params,
+        ),
+        OraclePriorityFlag::PYTH_WITH_SERUM_FALLBACK => {
+            match get_market_price_from_pyth(
+                pyth_a_price_info,
+                pyth_b_price_info,
+                clock,
+                oracle_params,
             ) {
-                Ok(market_price) => Ok((market_price, clock.slot)),
+                Ok((market_price, valid_slot)) => {
+                    check_price_divergence(market_price, get_serum_price()?, max_price_divergence)?;
+                    Ok((market_price, valid_slot))
+                }
+                Err(e)
+                    if e == ProgramError::from(SwapError::StalePythPrice)
+                        || e == ProgramError::from(SwapError::InconfidentPythPrice) =>
+                {
+                    msg!("Falling back to serum price");
+                    Ok((get_serum_price()?, clock.slot()))
+                }
                 Err(e) => Err(e),
             }
         }
@@ -2666,6 +9549,67 @@ fn get_market_price(
     }
 }
 
+/// Check the oracle price accounts passed for a normal pool, the price update accounts of a
+/// PYTH_PULL pool are checked by their feed ids when read
+fn check_pool_oracle_accounts(
+    token_swap: &SwapInfo,
+    pyth_a_price_info: &AccountInfo,
+    pyth_b_price_info: &AccountInfo,
+) -> ProgramResult {
+    if OraclePriorityFlag::from_bits_truncate(token_swap.oracle_priority_flags).is_pyth_pull() {
+        return Ok(());
+    }
+    if token_swap.pyth_a != *pyth_a_price_info.key || token_swap.pyth_b != *pyth_b_price_info.key {
+        return Err(SwapError::InvalidInput.into());
+    }
+    Ok(())
+}
+
+/// Market price of a normal pool, pushed by the price setter of a MANUAL oracle pool, read
+/// from the price updates of the feeds of a PYTH_PULL pool or from the oracle accounts of
+/// the pool
+fn get_pool_market_price(
+    token_swap: &SwapInfo,
+    pyth_a_price_info: &AccountInfo,
+    pyth_b_price_info: &AccountInfo,
+    clock: &impl TimeSource,
+    serum_market_info: &AccountInfo,
+    serum_bids_info: &AccountInfo,
+    serum_asks_info: &AccountInfo,
+    orderbook_program_id: &Pubkey,
+) -> Result<(Decimal, u64), ProgramError> {
+    let oracle_flags = OraclePriorityFlag::from_bits_truncate(token_swap.oracle_priority_flags);
+    if oracle_flags.is_manual() {
+        return token_swap.manual_market_price(clock.slot());
+    }
+    if oracle_flags.is_pyth_pull() {
+        // The pyth price keys of the pool keep the feed ids of the tokens
+        return get_market_price_from_pyth_pull(
+            &token_swap.pyth_a,
+            &token_swap.pyth_b,
+            pyth_a_price_info,
+            pyth_b_price_info,
+            clock,
+            &token_swap.oracle_params,
+        );
+    }
+    get_market_price(
+        token_swap.oracle_priority_flags,
+        token_swap.max_price_divergence,
+        &token_swap.oracle_params,
+        pyth_a_price_info,
+        pyth_b_price_info,
+        clock,
+        serum_market_info,
+        serum_bids_info,
+        serum_asks_info,
+        orderbook_program_id,
+        token_swap.token_a_decimals,
+        token_swap.token_b_decimals,
+        token_swap.quote_is_token_a,
+    )
+}
+
 /// Assert and unpack account data
 pub fn assert_uninitialized<T: Pack + IsInitialized>(
     account_info: &AccountInfo,
@@ -2701,7 +9645,7 @@ pub fn unpack_mint(
 
 /// Issue a spl_token `Transfer` instruction.
 #[inline(always)]
-fn token_transfer<'a>(
+pub fn token_transfer<'a>(
     source: AccountInfo<'a>,
     destination: AccountInfo<'a>,
     authority: AccountInfo<'a>,
@@ -2728,6 +9672,66 @@ fn token_transfer<'a>(
     result.map_err(|_| SwapError::TokenTransferFailed.into())
 }
 
+/// Issue a spl_token `Approve` instruction.
+fn token_approve<'a>(
+    source: AccountInfo<'a>,
+    delegate: AccountInfo<'a>,
+    authority: AccountInfo<'a>,
+    token_program: AccountInfo<'a>,
+    amount: u64,
+    authority_signer_seeds: &[&[u8]],
+) -> ProgramResult {
+    if &spl_token::ID != token_program.key {
+        return Err(ProgramError::IncorrectProgramId);
+    }
+
+    invoke_optionally_signed(
+        &spl_token::instruction::approve(
+            token_program.key,
+            source.key,
+            delegate.key,
+            authority.key,
+            &[],
+            amount,
+        )?,
+        &[source, delegate, authority, token_program],
+        authority_signer_seeds,
+    )
+}
+
+/// Issue a spl_token `Revoke` instruction.
+fn token_revoke<'a>(
+    source: AccountInfo<'a>,
+    authority: AccountInfo<'a>,
+    token_program: AccountInfo<'a>,
+    authority_signer_seeds: &[&[u8]],
+) -> ProgramResult {
+    if &spl_token::ID != token_program.key {
+        return Err(ProgramError::IncorrectProgramId);
+    }
+
+    invoke_optionally_signed(
+        &spl_token::instruction::revoke(token_program.key, source.key, authority.key, &[])?,
+        &[source, authority, token_program],
+        authority_signer_seeds,
+    )
+}
+
+/// Issue a spl_token `SyncNative` instruction.
+fn token_sync_native<'a>(
+    account: AccountInfo<'a>,
+    token_program: AccountInfo<'a>,
+) -> ProgramResult {
+    if &spl_token::ID != token_program.key {
+        return Err(ProgramError::IncorrectProgramId);
+    }
+
+    invoke(
+        &spl_token::instruction::sync_native(token_program.key, account.key)?,
+        &[account, token_program],
+    )
+}
+
 /// Issue a spl_token `MintTo` instruction.
 fn token_mint_to<'a>(
     mint: AccountInfo<'a>,
@@ -2785,13 +9789,57 @@ fn token_burn<'a>(
     result.map_err(|_| SwapError::TokenBurnFailed.into())
 }
 
-/// Set account authority
-pub fn set_authority<'a>(
+/// Issue a spl_token `CloseAccount` instruction.
+pub fn token_close_account<'a>(
+    account: AccountInfo<'a>,
+    destination: AccountInfo<'a>,
+    authority: AccountInfo<'a>,
+    token_program: AccountInfo<'a>,
+    authority_signer_seeds: &[&[u8]],
+) -> ProgramResult {
+    if &spl_token::ID != token_program.key {
+        return Err(ProgramError::IncorrectProgramId);
+    }
+
+    invoke_optionally_signed(
+        &spl_token::instruction::close_account(
+            token_program.key,
+            account.key,
+            destination.key,
+            authority.key,
+            &[],
+        )?,
+        &[account, destination, authority, token_program],
+        authority_signer_seeds,
+    )
+}
+
+/// Set account authority
+pub fn set_authority<'a>(
+    token_program: &AccountInfo<'a>,
+    account_to_transfer_ownership: &AccountInfo<'a>,
+    new_authority: Option<Pubkey>,
+    authority_type: AuthorityType,
+    owner: &AccountInfo<'a>,
+) -> ProgramResult {
+    set_authority_signed(
+        token_program,
+        account_to_transfer_ownership,
+        new_authority,
+        authority_type,
+        owner,
+        &[],
+    )
+}
+
+/// Set account authority, the owner signs with the seeds when it is a program address
+pub fn set_authority_signed<'a>(
     token_program: &AccountInfo<'a>,
     account_to_transfer_ownership: &AccountInfo<'a>,
     new_authority: Option<Pubkey>,
     authority_type: AuthorityType,
     owner: &AccountInfo<'a>,
+    authority_signer_seeds: &[&[u8]],
 ) -> ProgramResult {
     if &spl_token::ID != token_program.key {
         return Err(ProgramError::IncorrectProgramId);
@@ -2805,15 +9853,15 @@ pub fn set_authority<'a>(
         owner.key,
         &[],
     )?;
-    invoke(
+    invoke_optionally_signed(
         &ix,
         &[
             account_to_transfer_ownership.clone(),
             owner.clone(),
             token_program.clone(),
         ],
-    )?;
-    Ok(())
+        authority_signer_seeds,
+    )
 }
 
 /// Unpacks a spl_token `Account`.
@@ -2829,6 +9877,22 @@ pub fn unpack_token_account(
     }
 }
 
+/// Reads the amount of a token account without unpacking the rest of it
+pub fn token_account_amount(
+    account_info: &AccountInfo,
+    token_program_id: &Pubkey,
+) -> Result<u64, ProgramError> {
+    if account_info.owner != token_program_id {
+        return Err(SwapError::IncorrectTokenProgramId.into());
+    }
+    let data = account_info.try_borrow_data()?;
+    // The amount follows the mint and the owner, the state is at byte 108
+    if data.len() != Account::LEN || data[108] == AccountState::Uninitialized as u8 {
+        return Err(SwapError::ExpectedAccount.into());
+    }
+    Ok(u64::from_le_bytes(*array_ref![data, 64, 8]))
+}
+
 fn check_pyth_accounts(
     pyth_product_info: &AccountInfo,
     pyth_price_info: &AccountInfo,
@@ -2884,8 +9948,10 @@ fn invoke_optionally_signed(
 #[cfg(test)]
 mod test {
     use super::*;
-    use crate::pyth::PYTH_PROGRAM_ID;
-    use bytemuck::{bytes_of_mut, from_bytes_mut};
+    use crate::chainlink::{test_utils::MockFeed, CHAINLINK_STORE_PROGRAM_ID};
+    use crate::pyth::{test_utils::MockPrice, PYTH_PROGRAM_ID};
+    use crate::pyth_pull::{test_utils::MockPriceUpdate, PYTH_RECEIVER_PROGRAM_ID};
+    use bytemuck::bytes_of_mut;
     use std::str::FromStr;
 
     #[test]
@@ -2933,6 +9999,94 @@ mod test {
         assert!(assert_rent_exempt(&rent, &test_account_info).is_ok());
     }
 
+    #[test]
+    fn test_swap_fees() {
+        let fees = Fees {
+            treasury_fee_numerator: 1,
+            treasury_fee_denominator: 10,
+            ..crate::state::DEFAULT_TEST_FEES
+        };
+        let rewards = Rewards {
+            referral_fee_bps: 2_000,
+            ..crate::state::DEFAULT_TEST_REWARDS
+        };
+
+        let swap_fees = SwapFees::new(&fees, &rewards, 1_000, true, true).unwrap();
+        assert_eq!(swap_fees.admin_fee, 500);
+        assert_eq!(swap_fees.treasury_fee, 100);
+        assert_eq!(swap_fees.referral_fee, 200);
+        assert_eq!(swap_fees.lp_fee().unwrap(), 200);
+        assert_eq!(swap_fees.pool_outflow(10_000).unwrap(), 10_800);
+
+        // No treasury nor referrer token account, the liquidity providers keep their shares
+        let swap_fees = SwapFees::new(&fees, &rewards, 1_000, false, false).unwrap();
+        assert_eq!(swap_fees.treasury_fee, 0);
+        assert_eq!(swap_fees.referral_fee, 0);
+        assert_eq!(swap_fees.lp_fee().unwrap(), 500);
+        assert_eq!(swap_fees.pool_outflow(10_000).unwrap(), 10_500);
+
+        assert_eq!(
+            SwapFees::new(&fees, &rewards, 1_000, true, true)
+                .unwrap()
+                .pool_outflow(u64::MAX)
+                .unwrap_err(),
+            SwapError::SwapOutflowOverflow.into()
+        );
+    }
+
+    #[test]
+    fn test_token_account_amount() {
+        let account_key = Pubkey::new_unique();
+        let token_program_id = spl_token::id();
+        let token_account = Account {
+            mint: Pubkey::new_unique(),
+            owner: Pubkey::new_unique(),
+            amount: 1_234_567,
+            state: AccountState::Initialized,
+            ..Account::default()
+        };
+        let mut data = [0u8; Account::LEN];
+        Account::pack(token_account, &mut data).unwrap();
+        let mut lamports = 0u64;
+        let account_info = AccountInfo::new(
+            &account_key,
+            false,
+            false,
+            &mut lamports,
+            &mut data,
+            &token_program_id,
+            false,
+            0u64,
+        );
+        assert_eq!(
+            token_account_amount(&account_info, &token_program_id).unwrap(),
+            unpack_token_account(&account_info, &token_program_id)
+                .unwrap()
+                .amount
+        );
+        assert_eq!(
+            token_account_amount(&account_info, &Pubkey::new_unique()).unwrap_err(),
+            SwapError::IncorrectTokenProgramId.into()
+        );
+
+        let mut data = [0u8; Account::LEN];
+        let mut lamports = 0u64;
+        let account_info = AccountInfo::new(
+            &account_key,
+            false,
+            false,
+            &mut lamports,
+            &mut data,
+            &token_program_id,
+            false,
+            0u64,
+        );
+        assert_eq!(
+            token_account_amount(&account_info, &token_program_id).unwrap_err(),
+            SwapError::ExpectedAccount.into()
+        );
+    }
+
     fn get_check_pyth_accounts_result(option: u8) -> ProgramResult {
         let pyth_program_id = if option == 8u8 {
             Pubkey::new_unique()
@@ -3004,6 +10158,119 @@ mod test {
         check_pyth_accounts(&pyth_product_info, &pyth_price_info, &pyth_program_id)
     }
 
+    fn get_reveal_price_commitment_result(option: u8) -> (ProgramResult, u64) {
+        let program_id = Pubkey::new_unique();
+        let config_key = Pubkey::new_unique();
+        let swap_key = Pubkey::new_unique();
+        let admin_key = Pubkey::new_unique();
+        let price_commitment_key = if option == 1u8 {
+            Pubkey::new_unique()
+        } else {
+            get_price_commitment_pubkey(&admin_key, &swap_key, &program_id).unwrap()
+        };
+        let mid_price = 35_000_000_000_000_000_000u128;
+        let salt = [7u8; 32];
+        let clock = Clock {
+            slot: if option == 3u8 { 100u64 } else { 101u64 },
+            ..Clock::default()
+        };
+
+        let mut config_lamports = 0u64;
+        let mut config_data = [0u8];
+        let config_info = AccountInfo::new(
+            &config_key,
+            false,
+            false,
+            &mut config_lamports,
+            &mut config_data,
+            &program_id,
+            false,
+            0u64,
+        );
+
+        let mut swap_lamports = 0u64;
+        let mut swap_data = [0u8];
+        let swap_info = AccountInfo::new(
+            &swap_key,
+            true,
+            true,
+            &mut swap_lamports,
+            &mut swap_data,
+            &program_id,
+            false,
+            0u64,
+        );
+
+        let mut admin_lamports = 1_000u64;
+        let mut admin_data = [0u8];
+        let admin_info = AccountInfo::new(
+            &admin_key,
+            true,
+            true,
+            &mut admin_lamports,
+            &mut admin_data,
+            &program_id,
+            false,
+            0u64,
+        );
+
+        let mut price_commitment_lamports = 2_000u64;
+        let mut price_commitment_data = [0u8; PriceCommitment::LEN];
+        PriceCommitment {
+            is_initialized: true,
+            config_key,
+            swap_key,
+            price_hash: PriceCommitment::hash_price(mid_price, &salt),
+            commit_slot: 100u64,
+        }
+        .pack_into_slice(&mut price_commitment_data);
+        let price_commitment_info = AccountInfo::new(
+            &price_commitment_key,
+            false,
+            true,
+            &mut price_commitment_lamports,
+            &mut price_commitment_data,
+            &program_id,
+            false,
+            0u64,
+        );
+
+        let result = reveal_price_commitment(
+            &program_id,
+            &config_info,
+            &swap_info,
+            &admin_info,
+            &price_commitment_info,
+            &clock,
+            if option == 2u8 {
+                mid_price + 1
+            } else {
+                mid_price
+            },
+            &salt,
+        );
+        let admin_lamports = admin_info.lamports();
+        (result, admin_lamports)
+    }
+
+    #[test]
+    fn test_reveal_price_commitment() {
+        assert_eq!(get_reveal_price_commitment_result(0u8), (Ok(()), 3_000u64));
+
+        assert_eq!(
+            get_reveal_price_commitment_result(1u8),
+            (Err(SwapError::InvalidAccountOwner.into()), 1_000u64)
+        );
+        assert_eq!(
+            get_reveal_price_commitment_result(2u8),
+            (Err(SwapError::InvalidPriceCommitment.into()), 1_000u64)
+        );
+        assert_eq!(
+            get_reveal_price_commitment_result(3u8),
+            (Err(SwapError::InvalidPriceCommitment.into()), 1_000u64)
+        );
+    }
+
     #[test]
     fn test_check_pyth_accounts() {
         assert!(get_check_pyth_accounts_result(0u8).is_ok());
@@ -3042,97 +10309,39 @@ mod test {
         );
     }
 
-    fn get_get_pyth_price_result(option: u8) -> Result<(Decimal, u64), ProgramError> {
+    fn get_get_pyth_price_result(
+        option: u8,
+        oracle_params: &OracleParams,
+    ) -> Result<(Decimal, u64), ProgramError> {
         let program_id = Pubkey::new_unique();
         let pyth_price_key = Pubkey::new_unique();
-        let mut pyth_price_data_vec = vec![0u8];
-        let pyth_price_data = [0u8; std::mem::size_of::<pyth::Price>() * 2];
-        pyth_price_data_vec.extend_from_slice(&pyth_price_data);
         let mut idle_data = [0u8];
 
-        let price_align = std::mem::align_of::<pyth::Price>();
-        let shift = price_align - ((pyth_price_data_vec.as_ptr() as usize) % price_align);
-        assert_eq!(
-            (pyth_price_data_vec[shift..shift + price_align].as_ptr() as usize) % price_align,
-            0
-        );
-
-        let pyth_price: &mut pyth::Price = from_bytes_mut(
-            &mut pyth_price_data_vec[shift..shift + std::mem::size_of::<pyth::Price>()],
-        );
-
-        pyth_price.ptype = if option == 3u8 {
-            pyth::PriceType::Unknown
-        } else {
-            pyth::PriceType::Price
-        };
-        pyth_price.agg = pyth::PriceInfo {
+        let mut pyth_price = MockPrice {
             price: if option == 6u8 { -2i64 } else { 120_000_000i64 },
             conf: if option == 7u8 {
                 20_000_000u64
             } else {
                 200_000u64
             },
+            expo: if option == 1u8 { -2i32 } else { 2i32 },
+            valid_slot: 150_000u64,
             status: if option == 4u8 {
                 pyth::PriceStatus::Halted
             } else {
                 pyth::PriceStatus::Trading
             },
-            corp_act: pyth::CorpAction::NoCorpAct,
-            pub_slot: 10_000u64,
-        };
-
-        pyth_price.prev_price = if option == 8u8 {
-            100_000_000i64
-        } else {
-            119_000_000i64
-        };
-
-        let pyth_active_price_agg = pyth::PriceInfo {
-            price: 120_000_000i64,
-            conf: 200_000u64,
-            status: pyth::PriceStatus::Trading,
-            corp_act: pyth::CorpAction::NoCorpAct,
-            pub_slot: 10_000u64,
-        };
-        let mut pyth_inactive_price_agg = pyth_active_price_agg;
-        pyth_inactive_price_agg.status = pyth::PriceStatus::Halted;
-        let mut pyth_price_comp_vec = vec![];
-
-        if option == 5u8 {
-            let active_slice = [pyth::PriceComp::new(
-                pyth::AccKey { val: [0u8; 32] },
-                pyth_active_price_agg,
-                pyth_active_price_agg,
-            ); 2];
-            let inactive_slice = [pyth::PriceComp::new(
-                pyth::AccKey { val: [0u8; 32] },
-                pyth_inactive_price_agg,
-                pyth_inactive_price_agg,
-            ); 30];
-
-            pyth_price_comp_vec.extend_from_slice(&active_slice);
-            pyth_price_comp_vec.extend_from_slice(&inactive_slice);
-        } else {
-            let active_slice = [pyth::PriceComp::new(
-                pyth::AccKey { val: [0u8; 32] },
-                pyth_active_price_agg,
-                pyth_active_price_agg,
-            ); 4];
-            let inactive_slice = [pyth::PriceComp::new(
-                pyth::AccKey { val: [0u8; 32] },
-                pyth_inactive_price_agg,
-                pyth_inactive_price_agg,
-            ); 28];
-
-            pyth_price_comp_vec.extend_from_slice(&active_slice);
-            pyth_price_comp_vec.extend_from_slice(&inactive_slice);
+            active_publishers: if option == 5u8 { 2 } else { 4 },
+            prev_price: Some(if option == 8u8 {
+                100_000_000i64
+            } else {
+                119_000_000i64
+            }),
+        }
+        .to_price(&Pubkey::new_unique());
+        if option == 3u8 {
+            pyth_price.ptype = pyth::PriceType::Unknown;
         }
-
-        pyth_price.comp.copy_from_slice(&pyth_price_comp_vec[0..32]);
-        pyth_price.expo = if option == 1u8 { -2i32 } else { 2i32 };
-
-        pyth_price.valid_slot = 150_000u64;
 
         let mut lamport = 0u64;
         let pyth_price_info = AccountInfo::new(
@@ -3143,7 +10352,7 @@ mod test {
             if option == 2u8 {
                 &mut idle_data
             } else {
-                &mut pyth_price_data_vec[shift..shift + std::mem::size_of::<pyth::Price>()]
+                bytes_of_mut(&mut pyth_price)
             },
             &program_id,
             false,
@@ -3160,19 +10369,20 @@ mod test {
             clock.slot = 150_001u64;
         }
 
-        get_pyth_price(&pyth_price_info, &clock)
+        get_pyth_price(&pyth_price_info, &clock, oracle_params)
     }
 
     #[test]
     fn test_get_pyth_price() {
-        let ok_result = get_get_pyth_price_result(0u8);
+        let default_params = OracleParams::default();
+        let ok_result = get_get_pyth_price_result(0u8, &default_params);
         assert!(ok_result.is_ok());
         assert_eq!(
             ok_result.unwrap(),
             (Decimal::from(12_000_000_000u64), 150_000u64)
         );
 
-        let ok_result_neg = get_get_pyth_price_result(1u8);
+        let ok_result_neg = get_get_pyth_price_result(1u8, &default_params);
         assert!(ok_result_neg.is_ok());
         assert_eq!(
             ok_result_neg.unwrap(),
@@ -3180,37 +10390,322 @@ mod test {
         );
 
         assert_eq!(
-            get_get_pyth_price_result(2u8),
+            get_get_pyth_price_result(2u8, &default_params),
             Err(ProgramError::InvalidAccountData)
         );
         assert_eq!(
-            get_get_pyth_price_result(3u8),
+            get_get_pyth_price_result(3u8, &default_params),
             Err(ProgramError::from(SwapError::InvalidPythConfig))
         );
         assert_eq!(
-            get_get_pyth_price_result(4u8),
+            get_get_pyth_price_result(4u8, &default_params),
             Err(ProgramError::from(SwapError::InvalidPythConfig))
         );
         assert_eq!(
-            get_get_pyth_price_result(5u8),
+            get_get_pyth_price_result(5u8, &default_params),
             Err(ProgramError::from(SwapError::InvalidPythConfig))
         );
         assert_eq!(
-            get_get_pyth_price_result(6u8),
+            get_get_pyth_price_result(6u8, &default_params),
             Err(ProgramError::from(SwapError::InvalidPythConfig))
         );
         assert_eq!(
-            get_get_pyth_price_result(7u8),
+            get_get_pyth_price_result(7u8, &default_params),
             Err(ProgramError::from(SwapError::InconfidentPythPrice))
         );
         assert_eq!(
-            get_get_pyth_price_result(8u8),
+            get_get_pyth_price_result(8u8, &default_params),
             Err(ProgramError::from(SwapError::UnstableMarketPrice))
         );
         assert_eq!(
-            get_get_pyth_price_result(9u8),
+            get_get_pyth_price_result(9u8, &default_params),
+            Err(ProgramError::from(SwapError::StalePythPrice))
+        );
+    }
+
+    #[test]
+    fn test_get_pyth_price_with_oracle_params() {
+        // 2 active publishers
+        let oracle_params = OracleParams {
+            min_publishers: 2u8,
+            ..OracleParams::default()
+        };
+        assert!(get_get_pyth_price_result(5u8, &oracle_params).is_ok());
+
+        // confidence interval is 1/6 of the price
+        let oracle_params = OracleParams {
+            max_confidence_bps: 2_000u16,
+            ..OracleParams::default()
+        };
+        assert!(get_get_pyth_price_result(7u8, &oracle_params).is_ok());
+        let oracle_params = OracleParams {
+            max_confidence_bps: 1_000u16,
+            ..OracleParams::default()
+        };
+        assert_eq!(
+            get_get_pyth_price_result(7u8, &oracle_params),
+            Err(ProgramError::from(SwapError::InconfidentPythPrice))
+        );
+
+        // confidence interval is 1/600 of the price
+        let oracle_params = OracleParams {
+            max_confidence_bps: 15u16,
+            ..OracleParams::default()
+        };
+        assert_eq!(
+            get_get_pyth_price_result(0u8, &oracle_params),
+            Err(ProgramError::from(SwapError::InconfidentPythPrice))
+        );
+
+        // 12 slots elapsed
+        let oracle_params = OracleParams {
+            stale_slots: 20u16,
+            ..OracleParams::default()
+        };
+        assert!(get_get_pyth_price_result(9u8, &oracle_params).is_ok());
+        let oracle_params = OracleParams {
+            stale_slots: 12u16,
+            ..OracleParams::default()
+        };
+        assert_eq!(
+            get_get_pyth_price_result(9u8, &oracle_params),
+            Err(ProgramError::from(SwapError::StalePythPrice))
+        );
+    }
+
+    fn get_get_chainlink_price_result(
+        feeds: [MockFeed; 2],
+        owner: &Pubkey,
+        slot: u64,
+        oracle_params: &OracleParams,
+    ) -> Result<(Decimal, u64), ProgramError> {
+        let mut feed_data: Vec<Vec<u8>> = feeds.iter().map(|feed| feed.to_account_data()).collect();
+        let feed_keys = [Pubkey::new_unique(), Pubkey::new_unique()];
+        let mut lamports = [0u64; 2];
+        let feed_infos: Vec<AccountInfo> = feed_keys
+            .iter()
+            .zip(lamports.iter_mut())
+            .zip(feed_data.iter_mut())
+            .map(|((key, lamports), data)| {
+                AccountInfo::new(key, false, false, lamports, data, owner, false, 0u64)
+            })
+            .collect();
+        let clock = Clock {
+            slot,
+            ..Default::default()
+        };
+        get_market_price_from_chainlink(&feed_infos[0], &feed_infos[1], &clock, oracle_params)
+    }
+
+    #[test]
+    fn test_get_chainlink_price() {
+        let chainlink_program_id = Pubkey::from_str(CHAINLINK_STORE_PROGRAM_ID).unwrap();
+        let default_params = OracleParams::default();
+        // 25.5 USD with 8 decimals against 0.5 USD with 6 decimals
+        let feeds = [
+            MockFeed {
+                answer: 2_550_000_000,
+                decimals: 8,
+                slot: 1_000,
+                ..MockFeed::default()
+            },
+            MockFeed {
+                answer: 500_000,
+                decimals: 6,
+                slot: 1_100,
+                ..MockFeed::default()
+            },
+        ];
+        assert_eq!(
+            get_get_chainlink_price_result(feeds, &chainlink_program_id, 1_149, &default_params),
+            Ok((Decimal::from(51u64), 1_000u64))
+        );
+        assert_eq!(
+            get_get_chainlink_price_result(feeds, &chainlink_program_id, 1_150, &default_params),
+            Err(ProgramError::from(SwapError::StaleChainlinkPrice))
+        );
+        let oracle_params = OracleParams {
+            stale_slots: 300u16,
+            ..OracleParams::default()
+        };
+        assert!(get_get_chainlink_price_result(
+            feeds,
+            &chainlink_program_id,
+            1_150,
+            &oracle_params
+        )
+        .is_ok());
+
+        assert_eq!(
+            get_get_chainlink_price_result(feeds, &Pubkey::new_unique(), 1_100, &default_params),
+            Err(ProgramError::from(SwapError::InvalidChainlinkFeed))
+        );
+        let negative_feeds = [
+            feeds[0],
+            MockFeed {
+                answer: -500_000,
+                ..feeds[1]
+            },
+        ];
+        assert_eq!(
+            get_get_chainlink_price_result(
+                negative_feeds,
+                &chainlink_program_id,
+                1_100,
+                &default_params
+            ),
+            Err(ProgramError::from(SwapError::InvalidChainlinkFeed))
+        );
+    }
+
+    fn get_get_pyth_pull_price_result(
+        updates: [MockPriceUpdate; 2],
+        feed_ids: [[u8; 32]; 2],
+        owner: &Pubkey,
+        unix_timestamp: i64,
+        oracle_params: &OracleParams,
+    ) -> Result<(Decimal, u64), ProgramError> {
+        let mut update_data: Vec<Vec<u8>> = updates
+            .iter()
+            .map(|update| update.to_account_data())
+            .collect();
+        let update_keys = [Pubkey::new_unique(), Pubkey::new_unique()];
+        let mut lamports = [0u64; 2];
+        let update_infos: Vec<AccountInfo> = update_keys
+            .iter()
+            .zip(lamports.iter_mut())
+            .zip(update_data.iter_mut())
+            .map(|((key, lamports), data)| {
+                AccountInfo::new(key, false, false, lamports, data, owner, false, 0u64)
+            })
+            .collect();
+        let clock = Clock {
+            slot: 2_000,
+            unix_timestamp,
+            ..Default::default()
+        };
+        get_market_price_from_pyth_pull(
+            &Pubkey::new_from_array(feed_ids[0]),
+            &Pubkey::new_from_array(feed_ids[1]),
+            &update_infos[0],
+            &update_infos[1],
+            &clock,
+            oracle_params,
+        )
+    }
+
+    #[test]
+    fn test_get_pyth_pull_price() {
+        let receiver_program_id = Pubkey::from_str(PYTH_RECEIVER_PROGRAM_ID).unwrap();
+        let default_params = OracleParams::default();
+        let feed_ids = [[1u8; 32], [2u8; 32]];
+        // 25.5 USD with exponent -8 against 0.5 USD with exponent -6
+        let updates = [
+            MockPriceUpdate {
+                feed_id: feed_ids[0],
+                price: 2_550_000_000,
+                exponent: -8,
+                publish_time: 1_000,
+                posted_slot: 1_900,
+                ..MockPriceUpdate::default()
+            },
+            MockPriceUpdate {
+                feed_id: feed_ids[1],
+                price: 500_000,
+                exponent: -6,
+                publish_time: 1_010,
+                posted_slot: 1_950,
+                ..MockPriceUpdate::default()
+            },
+        ];
+        assert_eq!(
+            get_get_pyth_pull_price_result(
+                updates,
+                feed_ids,
+                &receiver_program_id,
+                1_029,
+                &default_params
+            ),
+            Ok((Decimal::from(51u64), 1_900u64))
+        );
+        assert_eq!(
+            get_get_pyth_pull_price_result(
+                updates,
+                feed_ids,
+                &receiver_program_id,
+                1_030,
+                &default_params
+            ),
             Err(ProgramError::from(SwapError::StalePythPrice))
         );
+        let oracle_params = OracleParams {
+            stale_slots: 60u16,
+            ..OracleParams::default()
+        };
+        assert!(get_get_pyth_pull_price_result(
+            updates,
+            feed_ids,
+            &receiver_program_id,
+            1_030,
+            &oracle_params
+        )
+        .is_ok());
+
+        // The updates are of the feeds of the pool, in the token order of the pool
+        assert_eq!(
+            get_get_pyth_pull_price_result(
+                updates,
+                [feed_ids[1], feed_ids[0]],
+                &receiver_program_id,
+                1_020,
+                &default_params
+            ),
+            Err(ProgramError::from(SwapError::PythFeedIdMismatch))
+        );
+        assert_eq!(
+            get_get_pyth_pull_price_result(
+                updates,
+                feed_ids,
+                &Pubkey::new_unique(),
+                1_020,
+                &default_params
+            ),
+            Err(ProgramError::from(SwapError::InvalidPythPriceUpdate))
+        );
+        let partial_updates = [
+            updates[0],
+            MockPriceUpdate {
+                fully_verified: false,
+                ..updates[1]
+            },
+        ];
+        assert_eq!(
+            get_get_pyth_pull_price_result(
+                partial_updates,
+                feed_ids,
+                &receiver_program_id,
+                1_020,
+                &default_params
+            ),
+            Err(ProgramError::from(SwapError::InvalidPythPriceUpdate))
+        );
+        let inconfident_updates = [
+            updates[0],
+            MockPriceUpdate {
+                conf: 10_001,
+                ..updates[1]
+            },
+        ];
+        assert_eq!(
+            get_get_pyth_pull_price_result(
+                inconfident_updates,
+                feed_ids,
+                &receiver_program_id,
+                1_020,
+                &default_params
+            ),
+            Err(ProgramError::from(SwapError::InconfidentPythPrice))
+        );
     }
 
     #[test]
@@ -3231,4 +10726,211 @@ mod test {
             Decimal::from(25u64).try_div(1000u64).unwrap()
         );
     }
+
+    #[test]
+    fn test_register_farm_user() {
+        let program_id = Pubkey::new_unique();
+        let farm_pool_key = Pubkey::new_unique();
+        let registered_farm_user_key = Pubkey::new_unique();
+        let farm_user_key = Pubkey::new_unique();
+        let (registry_key, _) = find_farm_user_registry_address(&farm_pool_key, 0, &program_id);
+        let rent = Rent::default();
+
+        let mut farm_info = FarmInfo {
+            is_initialized: true,
+            is_farm_user_registry_enabled: true,
+            farm_user_count: 1,
+            ..FarmInfo::default()
+        };
+
+        let mut farm_pool_lamports = 0u64;
+        let mut farm_pool_data = [0u8; FarmInfo::LEN];
+        farm_info.pack_into_slice(&mut farm_pool_data);
+        let farm_pool_info = AccountInfo::new(
+            &farm_pool_key,
+            false,
+            true,
+            &mut farm_pool_lamports,
+            &mut farm_pool_data,
+            &program_id,
+            false,
+            0u64,
+        );
+
+        let mut registry = FarmUserRegistry::new(farm_pool_key, 0);
+        registry.push(registered_farm_user_key).unwrap();
+        let mut registry_lamports = 0u64;
+        let mut registry_data = vec![0u8; FarmUserRegistry::LEN];
+        registry.pack_into_slice(&mut registry_data);
+        let registry_info = AccountInfo::new(
+            &registry_key,
+            false,
+            true,
+            &mut registry_lamports,
+            &mut registry_data,
+            &program_id,
+            false,
+            0u64,
+        );
+
+        let payer_key = Pubkey::new_unique();
+        let mut payer_lamports = 0u64;
+        let mut payer_data = [0u8];
+        let payer_info = AccountInfo::new(
+            &payer_key,
+            true,
+            true,
+            &mut payer_lamports,
+            &mut payer_data,
+            &program_id,
+            false,
+            0u64,
+        );
+
+        let system_program_key = system_program::id();
+        let mut system_program_lamports = 0u64;
+        let mut system_program_data = [0u8];
+        let system_program_info = AccountInfo::new(
+            &system_program_key,
+            false,
+            false,
+            &mut system_program_lamports,
+            &mut system_program_data,
+            &program_id,
+            true,
+            0u64,
+        );
+
+        let accounts = [registry_info, payer_info, system_program_info];
+        assert_eq!(
+            register_farm_user(
+                &program_id,
+                &farm_pool_info,
+                &mut farm_info,
+                &farm_user_key,
+                &rent,
+                &mut accounts.iter(),
+            ),
+            Ok(())
+        );
+        assert_eq!(farm_info.farm_user_count, 2);
+        assert_eq!(
+            FarmUserRegistry::unpack(&accounts[0].data.borrow())
+                .unwrap()
+                .farm_users,
+            vec![registered_farm_user_key, farm_user_key]
+        );
+
+        // farm users past the first page can't be appended to it
+        let mut wrong_farm_info = FarmInfo {
+            farm_user_count: FARM_USER_REGISTRY_PAGE_SIZE as u64 + 1,
+            ..farm_info.clone()
+        };
+        assert_eq!(
+            register_farm_user(
+                &program_id,
+                &farm_pool_info,
+                &mut wrong_farm_info,
+                &farm_user_key,
+                &rent,
+                &mut accounts.iter(),
+            ),
+            Err(SwapError::InvalidAccountOwner.into())
+        );
+    }
+
+    #[test]
+    fn test_find_swap_address() {
+        let program_id = Pubkey::new_unique();
+        let config_key = Pubkey::new_unique();
+        let token_a_mint = Pubkey::new_unique();
+        let token_b_mint = Pubkey::new_unique();
+
+        let swap_address = find_swap_address(
+            &config_key,
+            &token_a_mint,
+            &token_b_mint,
+            SwapType::Normal,
+            &program_id,
+        );
+        // A pair has a single pool of a swap type whatever its token order
+        assert_eq!(
+            find_swap_address(
+                &config_key,
+                &token_b_mint,
+                &token_a_mint,
+                SwapType::Normal,
+                &program_id
+            ),
+            swap_address
+        );
+        assert_ne!(
+            find_swap_address(
+                &config_key,
+                &token_a_mint,
+                &token_b_mint,
+                SwapType::Stable,
+                &program_id
+            ),
+            swap_address
+        );
+        assert_ne!(
+            find_swap_address(
+                &Pubkey::new_unique(),
+                &token_a_mint,
+                &token_b_mint,
+                SwapType::Normal,
+                &program_id
+            ),
+            swap_address
+        );
+    }
+
+    #[test]
+    fn test_unpack_oracle_feed() {
+        let program_id = Pubkey::new_unique();
+        let config_key = Pubkey::new_unique();
+        let mint = Pubkey::new_unique();
+        let (oracle_feed_key, _) = find_oracle_feed_address(&config_key, &mint, &program_id);
+
+        let oracle_feed = OracleFeed {
+            is_initialized: true,
+            config_key,
+            mint,
+            pyth_price: Pubkey::new_unique(),
+            serum_market: Pubkey::new_unique(),
+        };
+        let mut oracle_feed_lamports = 0u64;
+        let mut oracle_feed_data = [0u8; OracleFeed::LEN];
+        oracle_feed.pack_into_slice(&mut oracle_feed_data);
+        let oracle_feed_info = AccountInfo::new(
+            &oracle_feed_key,
+            false,
+            false,
+            &mut oracle_feed_lamports,
+            &mut oracle_feed_data,
+            &program_id,
+            false,
+            0u64,
+        );
+
+        assert_eq!(
+            unpack_oracle_feed(&program_id, &config_key, &mint, &oracle_feed_info),
+            Ok(oracle_feed)
+        );
+        // the entry of another mint can't stand in for the mint of the pool
+        assert_eq!(
+            unpack_oracle_feed(
+                &program_id,
+                &config_key,
+                &Pubkey::new_unique(),
+                &oracle_feed_info
+            ),
+            Err(SwapError::InvalidOracleFeed.into())
+        );
+        assert_eq!(
+            unpack_oracle_feed(&Pubkey::new_unique(), &config_key, &mint, &oracle_feed_info),
+            Err(SwapError::InvalidOracleFeed.into())
+        );
+    }
 }
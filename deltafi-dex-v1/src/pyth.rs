@@ -193,6 +193,109 @@ pub fn load_mut<T: Pod>(data: &mut [u8]) -> Result<&mut T, PodCastError> {
     )))
 }
 
+/// Builders of valid Pyth account data, to simulate oracle states without copying the
+/// bytes of live accounts
+#[cfg(any(test, feature = "test-utils"))]
+pub mod test_utils {
+    use super::*;
+    use bytemuck::bytes_of;
+    use solana_program::pubkey::Pubkey;
+
+    /// Aggregate price of a mock price account
+    #[derive(Clone, Copy)]
+    pub struct MockPrice {
+        /// Aggregate price, in units of 10^expo
+        pub price: i64,
+        /// Confidence interval of the aggregate price
+        pub conf: u64,
+        /// Price exponent
+        pub expo: i32,
+        /// Slot of the aggregate price
+        pub valid_slot: u64,
+        /// Status of the aggregate price
+        pub status: PriceStatus,
+        /// Number of publishers trading, the other components are halted
+        pub active_publishers: usize,
+        /// Aggregate price of the previous update, the current price if None
+        pub prev_price: Option<i64>,
+    }
+
+    impl Default for MockPrice {
+        fn default() -> Self {
+            Self {
+                price: 0,
+                conf: 0,
+                expo: 0,
+                valid_slot: 0,
+                status: PriceStatus::Trading,
+                active_publishers: 32,
+                prev_price: None,
+            }
+        }
+    }
+
+    impl MockPrice {
+        /// Price account of the product
+        pub fn to_price(&self, product_pubkey: &Pubkey) -> Price {
+            let agg = PriceInfo {
+                price: self.price,
+                conf: self.conf,
+                status: self.status,
+                corp_act: CorpAction::NoCorpAct,
+                pub_slot: self.valid_slot,
+            };
+            let mut halted = agg;
+            halted.status = PriceStatus::Halted;
+
+            let mut price = Price::zeroed();
+            price.magic = MAGIC;
+            price.ver = VERSION;
+            price.atype = AccountType::Price as u32;
+            price.size = size_of::<Price>() as u32;
+            price.ptype = PriceType::Price;
+            price.expo = self.expo;
+            price.num = self.active_publishers.min(price.comp.len()) as u32;
+            price.num_qt = price.num;
+            price.last_slot = self.valid_slot;
+            price.valid_slot = self.valid_slot;
+            price.prod = AccKey {
+                val: product_pubkey.to_bytes(),
+            };
+            price.prev_slot = self.valid_slot;
+            price.prev_price = self.prev_price.unwrap_or(self.price);
+            price.prev_conf = self.conf;
+            price.agg = agg;
+            for (i, comp) in price.comp.iter_mut().enumerate() {
+                let info = if i < self.active_publishers {
+                    agg
+                } else {
+                    halted
+                };
+                *comp = PriceComp::new(AccKey { val: [0u8; 32] }, info, info);
+            }
+            price
+        }
+
+        /// Data of the price account of the product
+        pub fn to_account_data(&self, product_pubkey: &Pubkey) -> Vec<u8> {
+            bytes_of(&self.to_price(product_pubkey)).to_vec()
+        }
+    }
+
+    /// Data of a product account listing the price account
+    pub fn product_account_data(price_pubkey: &Pubkey) -> Vec<u8> {
+        let mut product = Product::zeroed();
+        product.magic = MAGIC;
+        product.ver = VERSION;
+        product.atype = AccountType::Product as u32;
+        product.size = PROD_HDR_SIZE as u32;
+        product.px_acc = AccKey {
+            val: price_pubkey.to_bytes(),
+        };
+        bytes_of(&product).to_vec()
+    }
+}
+
 #[cfg(test)]
 mod test {
     use super::*;
@@ -235,4 +338,39 @@ mod test {
         assert!(_pyth_price.is_ok());
         assert_eq!(_pyth_price.unwrap().num, 999_999u32);
     }
+    #[test]
+    fn test_mock_accounts() {
+        let product_pubkey = solana_program::pubkey::Pubkey::new_unique();
+        let price_pubkey = solana_program::pubkey::Pubkey::new_unique();
+
+        let data = test_utils::MockPrice {
+            price: 15_000_000_000,
+            conf: 1_000_000,
+            expo: -8,
+            valid_slot: 42,
+            active_publishers: 5,
+            ..test_utils::MockPrice::default()
+        }
+        .to_account_data(&product_pubkey);
+        assert_eq!(data.len(), std::mem::size_of::<Price>());
+        let price = load::<Price>(&data).unwrap();
+        assert_eq!(price.magic, MAGIC);
+        assert_eq!(price.atype, AccountType::Price as u32);
+        assert!(price.ptype == PriceType::Price);
+        assert_eq!(price.prod.val, product_pubkey.to_bytes());
+        assert_eq!(price.expo, -8);
+        assert_eq!(price.valid_slot, 42);
+        assert_eq!(price.agg.price, 15_000_000_000);
+        assert_eq!(price.agg.conf, 1_000_000);
+        assert!(price.agg.status == PriceStatus::Trading);
+        assert_eq!(price.prev_price, price.agg.price);
+        assert_eq!(price.comp.iter().filter(|comp| comp.is_active()).count(), 5);
+
+        let data = test_utils::product_account_data(&price_pubkey);
+        let product = load::<Product>(&data).unwrap();
+        assert_eq!(product.magic, MAGIC);
+        assert_eq!(product.ver, VERSION_2);
+        assert_eq!(product.atype, AccountType::Product as u32);
+        assert_eq!(product.px_acc.val, price_pubkey.to_bytes());
+    }
 }
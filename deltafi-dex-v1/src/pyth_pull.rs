@@ -0,0 +1,262 @@
+//! Pyth pull oracle prices, read from the price update accounts posted by the pyth solana
+//! receiver program.
+//! Derived from https://github.com/pyth-network/pyth-crosschain/blob/main/target_chains/solana/pyth_solana_receiver_sdk/src/price_update.rs
+
+use std::str::FromStr;
+
+use arrayref::{array_ref, array_refs};
+use solana_program::{account_info::AccountInfo, program_error::ProgramError, pubkey::Pubkey};
+
+use crate::error::SwapError;
+
+/// Pyth solana receiver program mainnet id, owner of the price update accounts
+pub const PYTH_RECEIVER_PROGRAM_ID: &str = "rec5EKMGg6MxZYaMdyBfgwp4d5rB9T1VQH5pJv5LtFJ";
+
+/// Anchor discriminator of the price update account, the first 8 bytes of
+/// sha256("account:PriceUpdateV2")
+pub const PRICE_UPDATE_V2_DISCRIMINATOR: [u8; 8] = [34, 241, 35, 99, 157, 126, 244, 205];
+/// Borsh tag of the full verification level, the update is checked by all the guardians
+pub const VERIFICATION_LEVEL_FULL: u8 = 1;
+/// Borsh tag of the partial verification level, followed by the number of signatures
+pub const VERIFICATION_LEVEL_PARTIAL: u8 = 0;
+/// Bytes of the price feed message
+pub const PRICE_MESSAGE_SIZE: usize = 84;
+/// Bytes of the price update account, allocated for the longest verification level
+pub const PRICE_UPDATE_V2_SIZE: usize = 8 + 32 + 2 + PRICE_MESSAGE_SIZE + 8;
+
+/// Price feed message of a price update
+#[derive(Clone, Copy, Debug, Default, PartialEq)]
+pub struct PriceMessage {
+    /// Pyth feed id of the price
+    pub feed_id: [u8; 32],
+    /// Price in units of 10^exponent
+    pub price: i64,
+    /// Confidence interval of the price, in units of 10^exponent
+    pub conf: u64,
+    /// Exponent of the price and the confidence interval
+    pub exponent: i32,
+    /// Unix timestamp of the price
+    pub publish_time: i64,
+    /// Unix timestamp of the previous price
+    pub prev_publish_time: i64,
+    /// Exponential moving average of the price
+    pub ema_price: i64,
+    /// Confidence interval of the ema price
+    pub ema_conf: u64,
+}
+
+/// Price update account of the receiver program
+#[derive(Clone, Copy, Debug, Default, PartialEq)]
+pub struct PriceUpdate {
+    /// Account allowed to overwrite the update
+    pub write_authority: Pubkey,
+    /// Whether the update is verified by all the guardians
+    pub fully_verified: bool,
+    /// Price feed message of the update
+    pub price_message: PriceMessage,
+    /// Slot the update is posted at
+    pub posted_slot: u64,
+}
+
+impl PriceUpdate {
+    /// Unpacks the price update account, checked to be owned by the receiver program
+    pub fn load(price_update_info: &AccountInfo) -> Result<Self, ProgramError> {
+        if *price_update_info.owner != Pubkey::from_str(PYTH_RECEIVER_PROGRAM_ID).unwrap() {
+            return Err(SwapError::InvalidPythPriceUpdate.into());
+        }
+        Self::unpack(&price_update_info.try_borrow_data()?)
+    }
+
+    /// Unpacks the data of a price update account
+    pub fn unpack(data: &[u8]) -> Result<Self, ProgramError> {
+        if data.len() < 8 + 32 + 1 {
+            return Err(SwapError::InvalidPythPriceUpdate.into());
+        }
+        let input = array_ref![data, 0, 8 + 32 + 1];
+        let (discriminator, write_authority, verification_level) = array_refs![input, 8, 32, 1];
+        if *discriminator != PRICE_UPDATE_V2_DISCRIMINATOR {
+            return Err(SwapError::InvalidPythPriceUpdate.into());
+        }
+        // The borsh encoded verification level has a variable length
+        let (fully_verified, offset) = match verification_level[0] {
+            VERIFICATION_LEVEL_PARTIAL => (false, 8 + 32 + 2),
+            VERIFICATION_LEVEL_FULL => (true, 8 + 32 + 1),
+            _ => return Err(SwapError::InvalidPythPriceUpdate.into()),
+        };
+        if data.len() < offset + PRICE_MESSAGE_SIZE + 8 {
+            return Err(SwapError::InvalidPythPriceUpdate.into());
+        }
+        let input = array_ref![data, offset, PRICE_MESSAGE_SIZE + 8];
+        #[allow(clippy::ptr_offset_with_cast)]
+        let (
+            feed_id,
+            price,
+            conf,
+            exponent,
+            publish_time,
+            prev_publish_time,
+            ema_price,
+            ema_conf,
+            posted_slot,
+        ) = array_refs![input, 32, 8, 8, 4, 8, 8, 8, 8, 8];
+
+        Ok(Self {
+            write_authority: Pubkey::new_from_array(*write_authority),
+            fully_verified,
+            price_message: PriceMessage {
+                feed_id: *feed_id,
+                price: i64::from_le_bytes(*price),
+                conf: u64::from_le_bytes(*conf),
+                exponent: i32::from_le_bytes(*exponent),
+                publish_time: i64::from_le_bytes(*publish_time),
+                prev_publish_time: i64::from_le_bytes(*prev_publish_time),
+                ema_price: i64::from_le_bytes(*ema_price),
+                ema_conf: u64::from_le_bytes(*ema_conf),
+            },
+            posted_slot: u64::from_le_bytes(*posted_slot),
+        })
+    }
+
+    /// Price message of a fully verified update of the feed
+    pub fn checked_price_message(&self, feed_id: &[u8; 32]) -> Result<PriceMessage, ProgramError> {
+        if !self.fully_verified {
+            return Err(SwapError::InvalidPythPriceUpdate.into());
+        }
+        if self.price_message.feed_id != *feed_id {
+            return Err(SwapError::PythFeedIdMismatch.into());
+        }
+        Ok(self.price_message)
+    }
+}
+
+/// Builders of valid price update account data, to simulate oracle states without copying
+/// the bytes of live accounts
+#[cfg(any(test, feature = "test-utils"))]
+pub mod test_utils {
+    use super::*;
+
+    /// Price message of a mock price update account
+    #[derive(Clone, Copy)]
+    pub struct MockPriceUpdate {
+        /// Pyth feed id of the price
+        pub feed_id: [u8; 32],
+        /// Price in units of 10^exponent
+        pub price: i64,
+        /// Confidence interval of the price
+        pub conf: u64,
+        /// Exponent of the price
+        pub exponent: i32,
+        /// Unix timestamp of the price
+        pub publish_time: i64,
+        /// Slot the update is posted at
+        pub posted_slot: u64,
+        /// Whether the update is verified by all the guardians
+        pub fully_verified: bool,
+    }
+
+    impl Default for MockPriceUpdate {
+        fn default() -> Self {
+            Self {
+                feed_id: [0u8; 32],
+                price: 0,
+                conf: 0,
+                exponent: -8,
+                publish_time: 0,
+                posted_slot: 0,
+                fully_verified: true,
+            }
+        }
+    }
+
+    impl MockPriceUpdate {
+        /// Data of the price update account
+        pub fn to_account_data(&self) -> Vec<u8> {
+            let mut data = vec![0u8; PRICE_UPDATE_V2_SIZE];
+            data[..8].copy_from_slice(&PRICE_UPDATE_V2_DISCRIMINATOR);
+            let offset = if self.fully_verified {
+                data[40] = VERIFICATION_LEVEL_FULL;
+                41
+            } else {
+                data[40] = VERIFICATION_LEVEL_PARTIAL;
+                data[41] = 5;
+                42
+            };
+            data[offset..offset + 32].copy_from_slice(&self.feed_id);
+            data[offset + 32..offset + 40].copy_from_slice(&self.price.to_le_bytes());
+            data[offset + 40..offset + 48].copy_from_slice(&self.conf.to_le_bytes());
+            data[offset + 48..offset + 52].copy_from_slice(&self.exponent.to_le_bytes());
+            data[offset + 52..offset + 60].copy_from_slice(&self.publish_time.to_le_bytes());
+            data[offset + 60..offset + 68].copy_from_slice(&self.publish_time.to_le_bytes());
+            data[offset + 68..offset + 76].copy_from_slice(&self.price.to_le_bytes());
+            data[offset + 76..offset + 84].copy_from_slice(&self.conf.to_le_bytes());
+            data[offset + 84..offset + 92].copy_from_slice(&self.posted_slot.to_le_bytes());
+            data
+        }
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+
+    #[test]
+    fn test_unpack_price_update() {
+        let feed_id = [7u8; 32];
+        let data = test_utils::MockPriceUpdate {
+            feed_id,
+            price: 2_512_345_678,
+            conf: 1_000,
+            exponent: -8,
+            publish_time: 1_700_000_000,
+            posted_slot: 42,
+            fully_verified: true,
+        }
+        .to_account_data();
+        let update = PriceUpdate::unpack(&data).unwrap();
+        assert!(update.fully_verified);
+        assert_eq!(update.posted_slot, 42);
+        let message = update.checked_price_message(&feed_id).unwrap();
+        assert_eq!(message.price, 2_512_345_678);
+        assert_eq!(message.conf, 1_000);
+        assert_eq!(message.exponent, -8);
+        assert_eq!(message.publish_time, 1_700_000_000);
+        assert_eq!(
+            update.checked_price_message(&[8u8; 32]),
+            Err(SwapError::PythFeedIdMismatch.into())
+        );
+
+        // The partially verified update has a longer verification level
+        let data = test_utils::MockPriceUpdate {
+            feed_id,
+            price: 100,
+            posted_slot: 43,
+            fully_verified: false,
+            ..test_utils::MockPriceUpdate::default()
+        }
+        .to_account_data();
+        let update = PriceUpdate::unpack(&data).unwrap();
+        assert!(!update.fully_verified);
+        assert_eq!(update.price_message.price, 100);
+        assert_eq!(update.posted_slot, 43);
+        assert_eq!(
+            update.checked_price_message(&feed_id),
+            Err(SwapError::InvalidPythPriceUpdate.into())
+        );
+
+        let mut data = test_utils::MockPriceUpdate::default().to_account_data();
+        assert_eq!(
+            PriceUpdate::unpack(&data[..PRICE_UPDATE_V2_SIZE - 2]),
+            Err(SwapError::InvalidPythPriceUpdate.into())
+        );
+        data[40] = 2;
+        assert_eq!(
+            PriceUpdate::unpack(&data),
+            Err(SwapError::InvalidPythPriceUpdate.into())
+        );
+        data[0] = 0;
+        assert_eq!(
+            PriceUpdate::unpack(&data),
+            Err(SwapError::InvalidPythPriceUpdate.into())
+        );
+    }
+}
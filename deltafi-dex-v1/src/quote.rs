@@ -0,0 +1,172 @@
+//! Swap quotes computed from the pool account data alone, usable off chain
+//! without `AccountInfo` or oracle accounts
+
+use solana_program::program_error::ProgramError;
+
+use crate::{
+    curve::SwapDirection, error::SwapError, instruction::SwapQuote, math::Decimal, state::SwapInfo,
+};
+
+/// Quote a swap of the pool at the market price, priced the same as the swap instructions.
+///
+/// # Arguments
+/// * token_swap - swap info of the pool, as fetched from its account.
+/// * amount_in - SOURCE amount to swap.
+/// * swap_direction - direction of the swap.
+/// * market_price - market price of the base token in quote token, not adjusted to the
+///   token decimals, as read from the oracles.
+///
+/// # Return value
+/// quote of the swap
+pub fn get_swap_out_amount(
+    token_swap: &SwapInfo,
+    amount_in: u64,
+    swap_direction: SwapDirection,
+    market_price: Decimal,
+) -> Result<SwapQuote, ProgramError> {
+    let mut token_swap = token_swap.clone();
+    token_swap.pool_state.set_market_price(
+        token_swap.token_a_decimals,
+        token_swap.token_b_decimals,
+        market_price,
+    )?;
+    quote_swap(&token_swap, amount_in, swap_direction)
+}
+
+/// Quote a swap of the pool at the market price already set in its pool state
+pub fn quote_swap(
+    token_swap: &SwapInfo,
+    amount_in: u64,
+    swap_direction: SwapDirection,
+) -> Result<SwapQuote, ProgramError> {
+    let receive_amount = token_swap
+        .pool_state
+        .get_out_amount(amount_in, swap_direction)?;
+    let trade_fee = token_swap.fees.trade_fee(receive_amount)?;
+    let admin_fee = token_swap.fees.admin_trade_fee(trade_fee)?;
+    let amount_out = receive_amount
+        .checked_sub(trade_fee)
+        .ok_or(SwapError::CalculationFailure)?;
+    token_swap.check_swap_out_amount(amount_out, swap_direction)?;
+
+    Ok(SwapQuote {
+        amount_out,
+        trade_fee,
+        admin_fee,
+        price_impact_bps: token_swap.pool_state.price_impact_bps(
+            amount_in,
+            receive_amount,
+            swap_direction,
+        )?,
+        market_price: token_swap.conventional_market_price()?.to_scaled_val()?,
+    })
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    use crate::{
+        curve::{default_slope, InitPoolStateParams, PoolState},
+        state::Fees,
+    };
+
+    fn test_swap_info() -> SwapInfo {
+        SwapInfo {
+            token_a_decimals: 6,
+            token_b_decimals: 9,
+            fees: Fees {
+                admin_trade_fee_numerator: 1,
+                admin_trade_fee_denominator: 5,
+                trade_fee_numerator: 1,
+                trade_fee_denominator: 1_000,
+                ..Fees::default()
+            },
+            pool_state: PoolState::new(InitPoolStateParams {
+                market_price: Decimal::zero(),
+                slope: default_slope(),
+                base_reserve: Decimal::from(1_000_000_000u64),
+                quote_reserve: Decimal::from(100_000_000_000_000u64),
+                total_supply: 1_000_000_000,
+                last_market_price: Decimal::zero(),
+                last_valid_market_price_slot: 0,
+            }),
+            ..SwapInfo::default()
+        }
+    }
+
+    #[test]
+    fn test_get_swap_out_amount() {
+        let token_swap = test_swap_info();
+        let market_price = Decimal::from(100u64);
+
+        let quote = get_swap_out_amount(
+            &token_swap,
+            1_000_000,
+            SwapDirection::SellBase,
+            market_price,
+        )
+        .unwrap();
+
+        // Same as the pool state priced by the instructions
+        let mut priced_swap = token_swap.clone();
+        priced_swap
+            .pool_state
+            .set_market_price(6, 9, market_price)
+            .unwrap();
+        let receive_amount = priced_swap
+            .pool_state
+            .get_out_amount(1_000_000, SwapDirection::SellBase)
+            .unwrap();
+        assert_eq!(quote.trade_fee, receive_amount / 1_000);
+        assert_eq!(quote.admin_fee, quote.trade_fee / 5);
+        assert_eq!(quote.amount_out, receive_amount - quote.trade_fee);
+        assert_eq!(
+            quote,
+            quote_swap(&priced_swap, 1_000_000, SwapDirection::SellBase).unwrap()
+        );
+
+        // 1 base at the price of 100 is 100 quote, with the 3 extra decimals of the quote token
+        assert!(quote.amount_out < 100_000_000_000);
+        assert!(quote.amount_out > 99_000_000_000);
+        assert_eq!(
+            Decimal::from_scaled_val(quote.market_price),
+            Decimal::from(100_000u64)
+        );
+
+        // The pool account data is left untouched
+        assert_eq!(token_swap, test_swap_info());
+    }
+
+    #[test]
+    fn test_quote_market_price_of_quote_token_a() {
+        let mut token_swap = test_swap_info();
+        token_swap
+            .pool_state
+            .set_market_price(6, 9, Decimal::from(100u64))
+            .unwrap();
+        token_swap.quote_is_token_a = true;
+
+        // Token A is the quote, the price of token B in token A is quoted
+        let quote = quote_swap(&token_swap, 1_000_000, SwapDirection::SellBase).unwrap();
+        assert_eq!(
+            Decimal::from_scaled_val(quote.market_price),
+            Decimal::from_scaled_val(10_000_000)
+        );
+    }
+
+    #[test]
+    fn test_get_swap_out_amount_limit() {
+        let mut token_swap = test_swap_info();
+        token_swap.swap_out_limit_percentage = 10;
+        assert_eq!(
+            get_swap_out_amount(
+                &token_swap,
+                500_000_000,
+                SwapDirection::SellBase,
+                Decimal::from(100u64)
+            ),
+            Err(SwapError::ExceededSwapOutAmount.into())
+        );
+    }
+}
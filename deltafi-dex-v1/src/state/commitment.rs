@@ -0,0 +1,116 @@
+//! Commitment of the initial price of a pool, revealed when the pool is initialized
+
+use arrayref::{array_mut_ref, array_ref, array_refs, mut_array_refs};
+use solana_program::{
+    hash::{hashv, HASH_BYTES},
+    program_error::ProgramError,
+    program_pack::{IsInitialized, Pack, Sealed},
+    pubkey::{Pubkey, PUBKEY_BYTES},
+};
+
+use super::*;
+
+/// Hash of the committed initial price posted by the admin before the pool is initialized,
+/// so the seed price of a launch is not known to the mempool until the pool goes live
+#[derive(Clone, Debug, Default, PartialEq)]
+pub struct PriceCommitment {
+    /// Initialization status
+    pub is_initialized: bool,
+    /// Config pubkey
+    pub config_key: Pubkey,
+    /// Token swap pubkey of the pool to be initialized
+    pub swap_key: Pubkey,
+    /// Hash of the mid price and the salt
+    pub price_hash: [u8; HASH_BYTES],
+    /// Slot the commitment was posted
+    pub commit_slot: u64,
+}
+
+impl PriceCommitment {
+    /// Hash a mid price with the salt the same way the commitment is verified at reveal
+    pub fn hash_price(mid_price: u128, salt: &[u8; 32]) -> [u8; HASH_BYTES] {
+        hashv(&[&mid_price.to_le_bytes(), salt]).to_bytes()
+    }
+}
+
+impl Sealed for PriceCommitment {}
+impl IsInitialized for PriceCommitment {
+    fn is_initialized(&self) -> bool {
+        self.is_initialized
+    }
+}
+
+const PRICE_COMMITMENT_SIZE: usize = 1 + PUBKEY_BYTES * 2 + HASH_BYTES + 8;
+
+impl Pack for PriceCommitment {
+    const LEN: usize = PRICE_COMMITMENT_SIZE;
+
+    fn pack_into_slice(&self, dst: &mut [u8]) {
+        let output = array_mut_ref![dst, 0, PRICE_COMMITMENT_SIZE];
+        #[allow(clippy::ptr_offset_with_cast)]
+        let (is_initialized, config_key, swap_key, price_hash, commit_slot) =
+            mut_array_refs![output, 1, PUBKEY_BYTES, PUBKEY_BYTES, HASH_BYTES, 8];
+        pack_bool(self.is_initialized, is_initialized);
+        config_key.copy_from_slice(self.config_key.as_ref());
+        swap_key.copy_from_slice(self.swap_key.as_ref());
+        price_hash.copy_from_slice(&self.price_hash);
+        *commit_slot = self.commit_slot.to_le_bytes();
+    }
+
+    fn unpack_from_slice(src: &[u8]) -> Result<Self, ProgramError> {
+        let input = array_ref![src, 0, PRICE_COMMITMENT_SIZE];
+        #[allow(clippy::ptr_offset_with_cast)]
+        let (is_initialized, config_key, swap_key, price_hash, commit_slot) =
+            array_refs![input, 1, PUBKEY_BYTES, PUBKEY_BYTES, HASH_BYTES, 8];
+
+        Ok(Self {
+            is_initialized: unpack_bool(is_initialized)?,
+            config_key: Pubkey::new_from_array(*config_key),
+            swap_key: Pubkey::new_from_array(*swap_key),
+            price_hash: *price_hash,
+            commit_slot: u64::from_le_bytes(*commit_slot),
+        })
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_price_commitment_packing() {
+        let salt = [7u8; 32];
+        let price_commitment = PriceCommitment {
+            is_initialized: true,
+            config_key: Pubkey::new_unique(),
+            swap_key: Pubkey::new_unique(),
+            price_hash: PriceCommitment::hash_price(35_000_000_000_000_000_000u128, &salt),
+            commit_slot: 150_000u64,
+        };
+
+        let mut packed = [0u8; PriceCommitment::LEN];
+        PriceCommitment::pack_into_slice(&price_commitment, &mut packed);
+        let unpacked = PriceCommitment::unpack(&packed).unwrap();
+        assert_eq!(price_commitment, unpacked);
+
+        let packed = [0u8; PriceCommitment::LEN];
+        let unpacked = PriceCommitment::unpack_unchecked(&packed).unwrap();
+        assert_eq!(unpacked, PriceCommitment::default());
+        assert_eq!(
+            PriceCommitment::unpack(&packed).unwrap_err(),
+            ProgramError::UninitializedAccount
+        );
+    }
+
+    #[test]
+    fn test_hash_price() {
+        let salt = [7u8; 32];
+        let price_hash = PriceCommitment::hash_price(1_000u128, &salt);
+        assert_eq!(price_hash, PriceCommitment::hash_price(1_000u128, &salt));
+        assert_ne!(price_hash, PriceCommitment::hash_price(1_001u128, &salt));
+        assert_ne!(
+            price_hash,
+            PriceCommitment::hash_price(1_000u128, &[8u8; 32])
+        );
+    }
+}
@@ -4,8 +4,13 @@ use solana_program::{
     program_pack::{IsInitialized, Pack, Sealed},
     pubkey::{Pubkey, PUBKEY_BYTES},
 };
+use std::{convert::TryFrom, str::FromStr};
 
 use super::*;
+use crate::SERUM_DEX_V3_PROGRAM_ID;
+
+#[cfg(feature = "serde")]
+use serde::{Deserialize, Serialize};
 
 /// Current version of the program and all new accounts created
 pub const PROGRAM_VERSION: u8 = 1;
@@ -17,6 +22,7 @@ pub const UNINITIALIZED_VERSION: u8 = 0;
 /// Dex Default Configuration information
 #[repr(C)]
 #[derive(Clone, Debug, Default, PartialEq)]
+#[cfg_attr(feature = "serde", derive(Serialize, Deserialize))]
 pub struct ConfigInfo {
     /// Version of DELTAFI
     pub version: u8,
@@ -42,11 +48,134 @@ pub struct ConfigInfo {
     /// Token account to send the rewards
     pub deltafi_token: Pubkey,
 
-    /// Reserved 8 * 16 = 128 bytes for future use
-    /// We use u64 here, because `Default` trait doesn't support u8 array longer than 32.
+    /// Orderbook program accepted for serum market prices
+    pub orderbook_program_id: Pubkey,
+
+    /// Market makers allowed to post quotes, default pubkey for an empty slot
+    pub market_makers: [Pubkey; MAX_MARKET_MAKERS],
+
+    /// Key allowed to update the fees, rewards and fee accounts, default pubkey if not delegated
+    pub fee_manager_key: Pubkey,
+
+    /// Key allowed to pause and unpause the pools, default pubkey if not delegated
+    pub pauser_key: Pubkey,
+
+    /// Key allowed to update the oracle settings and pegged prices, default pubkey if not delegated
+    pub oracle_manager_key: Pubkey,
+
+    /// Key allowed to pause every pool of the config for incident response, unpause is left
+    /// to the admin. Default pubkey if not delegated
+    pub guardian_key: Pubkey,
+
+    /// Pools are initialized with the oracle accounts registered for their mints only
+    pub is_oracle_feed_registry_enabled: bool,
+
+    /// Epoch of the admin instructions counted in `admin_ops_count`
+    pub admin_ops_epoch: u64,
+
+    /// Admin instructions executed in `admin_ops_epoch`
+    pub admin_ops_count: u16,
+
+    /// Max admin instructions per epoch set by the guardian, 0 if not limited
+    pub max_admin_ops_per_epoch: u16,
+
+    /// Admin instructions are paused until the guardian co-signs, set once the max is exceeded
+    pub is_admin_ops_paused: bool,
+
+    /// Protocol fee switch, swaps send the protocol share of the trade fee to the treasury
+    pub is_protocol_fee_enabled: bool,
+
+    /// Owner of the treasury token accounts receiving the protocol fees
+    pub treasury_key: Pubkey,
+
+    /// Reserved u8 array for alignment
+    pub reserved_u8: [u8; CONFIG_INFO_RESERVED_U8],
+    /// Reserved u64 array for future use
     pub reserved: [u64; CONFIG_INFO_RESERVED_U64],
 }
 
+impl ConfigInfo {
+    /// Orderbook program id used to read serum market prices,
+    /// configs created before it was configurable fall back to Serum-Dex V3
+    pub fn accepted_orderbook_program_id(&self) -> Pubkey {
+        if self.orderbook_program_id == Pubkey::default() {
+            Pubkey::from_str(SERUM_DEX_V3_PROGRAM_ID).unwrap()
+        } else {
+            self.orderbook_program_id
+        }
+    }
+
+    /// Get the key delegated the role, the admin key for the super-admin
+    pub fn role_key(&self, role: AdminRole) -> Pubkey {
+        match role {
+            AdminRole::SuperAdmin => self.admin_key,
+            AdminRole::FeeManager => self.fee_manager_key,
+            AdminRole::Pauser => self.pauser_key,
+            AdminRole::OracleManager => self.oracle_manager_key,
+            AdminRole::Guardian => self.guardian_key,
+        }
+    }
+
+    /// Count an admin instruction of the epoch, pausing the admin instructions once the max
+    /// of the epoch is exceeded. Returns if the instruction is within the max.
+    pub fn record_admin_op(&mut self, epoch: u64) -> bool {
+        if self.max_admin_ops_per_epoch == 0 {
+            return true;
+        }
+        if self.admin_ops_epoch != epoch {
+            self.admin_ops_epoch = epoch;
+            self.admin_ops_count = 0;
+        }
+        self.admin_ops_count = self.admin_ops_count.saturating_add(1);
+        if self.admin_ops_count > self.max_admin_ops_per_epoch {
+            self.is_admin_ops_paused = true;
+        }
+        !self.is_admin_ops_paused
+    }
+
+    /// Check if the key holds the role, the super-admin holds every role
+    pub fn has_role(&self, role: AdminRole, key: &Pubkey) -> bool {
+        let role_key = self.role_key(role);
+        *key == self.admin_key || (role_key != Pubkey::default() && *key == role_key)
+    }
+
+    /// Check if the key is an approved market maker
+    pub fn is_market_maker(&self, key: &Pubkey) -> bool {
+        *key != Pubkey::default() && self.market_makers.contains(key)
+    }
+}
+
+/// Roles the admin instructions are delegated to
+#[repr(C)]
+#[derive(Copy, Clone, Debug, PartialEq)]
+pub enum AdminRole {
+    /// Config admin, allowed every admin instruction
+    SuperAdmin,
+    /// Fees, rewards and fee accounts of the pools
+    FeeManager,
+    /// Pause and unpause of the pools
+    Pauser,
+    /// Oracle settings, price divergence guards and pegged prices of the pools
+    OracleManager,
+    /// Emergency pause of all the pools of the config
+    Guardian,
+}
+
+impl TryFrom<u8> for AdminRole {
+    type Error = ProgramError;
+
+    fn try_from(role: u8) -> Result<Self, Self::Error> {
+        match role {
+            0 => Ok(AdminRole::SuperAdmin),
+            1 => Ok(AdminRole::FeeManager),
+            2 => Ok(AdminRole::Pauser),
+            3 => Ok(AdminRole::OracleManager),
+            4 => Ok(AdminRole::Guardian),
+            _ => Err(ProgramError::InvalidInstructionData),
+        }
+    }
+}
+
 impl Sealed for ConfigInfo {}
 impl IsInitialized for ConfigInfo {
     fn is_initialized(&self) -> bool {
@@ -54,11 +183,32 @@ impl IsInitialized for ConfigInfo {
     }
 }
 
-const CONFIG_INFO_RESERVED_U64: usize = 16;
-const CONFIG_INFO_RESERVED_BYTES: usize = CONFIG_INFO_RESERVED_U64 * 8;
+/// Max number of market makers on the config allowlist
+pub const MAX_MARKET_MAKERS: usize = 2;
+
+const CONFIG_INFO_RESERVED_U8: usize = 1;
+const CONFIG_INFO_RESERVED_U64: usize = 8;
+const CONFIG_INFO_RESERVED_BYTES: usize = CONFIG_INFO_RESERVED_U8 + CONFIG_INFO_RESERVED_U64 * 8;
 
 #[doc(hidden)]
-pub const CONFIG_INFO_SIZE: usize = 228 + CONFIG_INFO_RESERVED_BYTES;
+pub const CONFIG_INFO_SIZE: usize =
+    309 + PUBKEY_BYTES * MAX_MARKET_MAKERS + PUBKEY_BYTES * 5 + CONFIG_INFO_RESERVED_BYTES;
+
+/// Length of the configs created before the reserved bytes were padded for the future fields,
+/// `MigrateConfigInfo` reallocates them to the current length
+pub const LEGACY_CONFIG_INFO_LEN: usize = CONFIG_INFO_SIZE - CONFIG_INFO_RESERVED_U64 * 8;
+
+impl ConfigInfo {
+    /// Unpacks the config of the current or the legacy length
+    pub fn unpack_any(src: &[u8]) -> Result<Self, ProgramError> {
+        if src.len() == LEGACY_CONFIG_INFO_LEN {
+            let mut data = [0u8; CONFIG_INFO_SIZE];
+            data[..LEGACY_CONFIG_INFO_LEN].copy_from_slice(src);
+            return Self::unpack(&data);
+        }
+        Self::unpack(src)
+    }
+}
 
 impl Pack for ConfigInfo {
     const LEN: usize = CONFIG_INFO_SIZE;
@@ -75,6 +225,19 @@ impl Pack for ConfigInfo {
             fees,
             rewards,
             deltafi_token,
+            orderbook_program_id,
+            market_makers,
+            fee_manager_key,
+            pauser_key,
+            oracle_manager_key,
+            guardian_key,
+            is_oracle_feed_registry_enabled,
+            admin_ops_epoch,
+            admin_ops_count,
+            max_admin_ops_per_epoch,
+            is_admin_ops_paused,
+            is_protocol_fee_enabled,
+            treasury_key,
             _, // reserved bytes
         ) = array_refs![
             src,
@@ -86,6 +249,19 @@ impl Pack for ConfigInfo {
             Fees::LEN,
             Rewards::LEN,
             PUBKEY_BYTES,
+            PUBKEY_BYTES,
+            PUBKEY_BYTES * MAX_MARKET_MAKERS,
+            PUBKEY_BYTES,
+            PUBKEY_BYTES,
+            PUBKEY_BYTES,
+            PUBKEY_BYTES,
+            1,
+            8,
+            2,
+            2,
+            1,
+            1,
+            PUBKEY_BYTES,
             CONFIG_INFO_RESERVED_BYTES
         ];
 
@@ -103,7 +279,21 @@ impl Pack for ConfigInfo {
             fees: Fees::unpack_from_slice(fees)?,
             rewards: Rewards::unpack_from_slice(rewards)?,
             deltafi_token: Pubkey::new_from_array(*deltafi_token),
+            orderbook_program_id: Pubkey::new_from_array(*orderbook_program_id),
+            market_makers: unpack_market_makers(market_makers),
+            fee_manager_key: Pubkey::new_from_array(*fee_manager_key),
+            pauser_key: Pubkey::new_from_array(*pauser_key),
+            oracle_manager_key: Pubkey::new_from_array(*oracle_manager_key),
+            guardian_key: Pubkey::new_from_array(*guardian_key),
+            is_oracle_feed_registry_enabled: unpack_bool(is_oracle_feed_registry_enabled)?,
+            admin_ops_epoch: u64::from_le_bytes(*admin_ops_epoch),
+            admin_ops_count: u16::from_le_bytes(*admin_ops_count),
+            max_admin_ops_per_epoch: u16::from_le_bytes(*max_admin_ops_per_epoch),
+            is_admin_ops_paused: unpack_bool(is_admin_ops_paused)?,
+            is_protocol_fee_enabled: unpack_bool(is_protocol_fee_enabled)?,
+            treasury_key: Pubkey::new_from_array(*treasury_key),
             // Set all reserved bytes to 0
+            reserved_u8: [0u8; CONFIG_INFO_RESERVED_U8],
             reserved: [0u64; CONFIG_INFO_RESERVED_U64],
         })
     }
@@ -120,6 +310,19 @@ impl Pack for ConfigInfo {
             fees,
             rewards,
             deltafi_token,
+            orderbook_program_id,
+            market_makers,
+            fee_manager_key,
+            pauser_key,
+            oracle_manager_key,
+            guardian_key,
+            is_oracle_feed_registry_enabled,
+            admin_ops_epoch,
+            admin_ops_count,
+            max_admin_ops_per_epoch,
+            is_admin_ops_paused,
+            is_protocol_fee_enabled,
+            treasury_key,
             reserved_bytes,
         ) = mut_array_refs![
             dst,
@@ -131,6 +334,19 @@ impl Pack for ConfigInfo {
             Fees::LEN,
             Rewards::LEN,
             PUBKEY_BYTES,
+            PUBKEY_BYTES,
+            PUBKEY_BYTES * MAX_MARKET_MAKERS,
+            PUBKEY_BYTES,
+            PUBKEY_BYTES,
+            PUBKEY_BYTES,
+            PUBKEY_BYTES,
+            1,
+            8,
+            2,
+            2,
+            1,
+            1,
+            PUBKEY_BYTES,
             CONFIG_INFO_RESERVED_BYTES
         ];
         *version = self.version.to_le_bytes();
@@ -141,11 +357,42 @@ impl Pack for ConfigInfo {
         self.fees.pack_into_slice(&mut fees[..]);
         self.rewards.pack_into_slice(&mut rewards[..]);
         deltafi_token.copy_from_slice(self.deltafi_token.as_ref());
+        orderbook_program_id.copy_from_slice(self.orderbook_program_id.as_ref());
+        for (dst, market_maker) in market_makers
+            .chunks_exact_mut(PUBKEY_BYTES)
+            .zip(self.market_makers.iter())
+        {
+            dst.copy_from_slice(market_maker.as_ref());
+        }
+        fee_manager_key.copy_from_slice(self.fee_manager_key.as_ref());
+        pauser_key.copy_from_slice(self.pauser_key.as_ref());
+        oracle_manager_key.copy_from_slice(self.oracle_manager_key.as_ref());
+        guardian_key.copy_from_slice(self.guardian_key.as_ref());
+        pack_bool(
+            self.is_oracle_feed_registry_enabled,
+            is_oracle_feed_registry_enabled,
+        );
+        *admin_ops_epoch = self.admin_ops_epoch.to_le_bytes();
+        *admin_ops_count = self.admin_ops_count.to_le_bytes();
+        *max_admin_ops_per_epoch = self.max_admin_ops_per_epoch.to_le_bytes();
+        pack_bool(self.is_admin_ops_paused, is_admin_ops_paused);
+        pack_bool(self.is_protocol_fee_enabled, is_protocol_fee_enabled);
+        treasury_key.copy_from_slice(self.treasury_key.as_ref());
         // Set all reserved bytes to 0
         *reserved_bytes = [0u8; CONFIG_INFO_RESERVED_BYTES];
     }
 }
 
+fn unpack_market_makers(
+    src: &[u8; PUBKEY_BYTES * MAX_MARKET_MAKERS],
+) -> [Pubkey; MAX_MARKET_MAKERS] {
+    let mut market_makers = [Pubkey::default(); MAX_MARKET_MAKERS];
+    for (market_maker, src) in market_makers.iter_mut().zip(src.chunks_exact(PUBKEY_BYTES)) {
+        *market_maker = Pubkey::new(src);
+    }
+    market_makers
+}
+
 #[cfg(test)]
 mod tests {
     use super::*;
@@ -158,6 +405,13 @@ mod tests {
         let deltafi_mint_raw = [3u8; 32];
         let pyth_program_id_raw = [4u8; 32];
         let deltafi_token_raw = [5u8; 32];
+        let orderbook_program_id_raw = [6u8; 32];
+        let market_maker_raw = [7u8; 32];
+        let fee_manager_key_raw = [8u8; 32];
+        let pauser_key_raw = [9u8; 32];
+        let oracle_manager_key_raw = [10u8; 32];
+        let guardian_key_raw = [11u8; 32];
+        let treasury_key_raw = [12u8; 32];
 
         let admin_key = Pubkey::new_from_array(admin_key_raw);
         let deltafi_mint = Pubkey::new_from_array(deltafi_mint_raw);
@@ -165,7 +419,13 @@ mod tests {
         let fees = DEFAULT_TEST_FEES;
         let rewards = DEFAULT_TEST_REWARDS;
         let deltafi_token = Pubkey::new_from_array(deltafi_token_raw);
-        let reserved = [0u64; CONFIG_INFO_RESERVED_U64];
+        let orderbook_program_id = Pubkey::new_from_array(orderbook_program_id_raw);
+        let market_makers = [Pubkey::new_from_array(market_maker_raw), Pubkey::default()];
+        let fee_manager_key = Pubkey::new_from_array(fee_manager_key_raw);
+        let pauser_key = Pubkey::new_from_array(pauser_key_raw);
+        let oracle_manager_key = Pubkey::new_from_array(oracle_manager_key_raw);
+        let guardian_key = Pubkey::new_from_array(guardian_key_raw);
+        let treasury_key = Pubkey::new_from_array(treasury_key_raw);
 
         let config_info = ConfigInfo {
             version,
@@ -176,7 +436,21 @@ mod tests {
             fees,
             rewards,
             deltafi_token,
-            reserved,
+            orderbook_program_id,
+            market_makers,
+            fee_manager_key,
+            pauser_key,
+            oracle_manager_key,
+            guardian_key,
+            is_oracle_feed_registry_enabled: true,
+            admin_ops_epoch: 300,
+            admin_ops_count: 4,
+            max_admin_ops_per_epoch: 20,
+            is_admin_ops_paused: true,
+            is_protocol_fee_enabled: true,
+            treasury_key,
+            reserved_u8: [0u8; CONFIG_INFO_RESERVED_U8],
+            reserved: [0u64; CONFIG_INFO_RESERVED_U64],
         };
 
         let mut packed = [0u8; ConfigInfo::LEN];
@@ -203,14 +477,35 @@ mod tests {
         packed.extend_from_slice(&DEFAULT_TEST_FEES.trade_fee_denominator.to_le_bytes());
         packed.extend_from_slice(&DEFAULT_TEST_FEES.withdraw_fee_numerator.to_le_bytes());
         packed.extend_from_slice(&DEFAULT_TEST_FEES.withdraw_fee_denominator.to_le_bytes());
+        packed.extend_from_slice(&DEFAULT_TEST_FEES.flash_fee_numerator.to_le_bytes());
+        packed.extend_from_slice(&DEFAULT_TEST_FEES.flash_fee_denominator.to_le_bytes());
+        packed.extend_from_slice(&DEFAULT_TEST_FEES.dynamic_fee_factor.to_le_bytes());
+        packed.extend_from_slice(&DEFAULT_TEST_FEES.max_dynamic_fee_multiplier.to_le_bytes());
+        packed.extend_from_slice(&DEFAULT_TEST_FEES.treasury_fee_numerator.to_le_bytes());
+        packed.extend_from_slice(&DEFAULT_TEST_FEES.treasury_fee_denominator.to_le_bytes());
         let is_initialized = vec![1, DEFAULT_TEST_REWARDS.is_initialized as u8];
         packed.extend_from_slice(&is_initialized[0].to_le_bytes());
         packed.extend_from_slice(&DEFAULT_TEST_REWARDS.decimals.to_le_bytes());
+        packed.extend_from_slice(&DEFAULT_TEST_REWARDS.referral_fee_bps.to_le_bytes());
         packed.extend_from_slice(&DEFAULT_TEST_REWARDS.reserved);
         packed.extend_from_slice(&DEFAULT_TEST_REWARDS.trade_reward_numerator.to_le_bytes());
         packed.extend_from_slice(&DEFAULT_TEST_REWARDS.trade_reward_denominator.to_le_bytes());
         packed.extend_from_slice(&DEFAULT_TEST_REWARDS.trade_reward_cap.to_le_bytes());
         packed.extend_from_slice(&deltafi_token_raw);
+        packed.extend_from_slice(&orderbook_program_id_raw);
+        packed.extend_from_slice(&market_maker_raw);
+        packed.extend_from_slice(&[0u8; 32]);
+        packed.extend_from_slice(&fee_manager_key_raw);
+        packed.extend_from_slice(&pauser_key_raw);
+        packed.extend_from_slice(&oracle_manager_key_raw);
+        packed.extend_from_slice(&guardian_key_raw);
+        packed.push(1u8);
+        packed.extend_from_slice(&300u64.to_le_bytes());
+        packed.extend_from_slice(&4u16.to_le_bytes());
+        packed.extend_from_slice(&20u16.to_le_bytes());
+        packed.push(1u8);
+        packed.push(1u8);
+        packed.extend_from_slice(&treasury_key_raw);
         packed.extend_from_slice(&[0u8; CONFIG_INFO_RESERVED_BYTES]);
         let unpacked = ConfigInfo::unpack(&packed).unwrap();
         assert_eq!(config_info, unpacked);
@@ -222,4 +517,113 @@ mod tests {
         let err = ConfigInfo::unpack(&packed).unwrap_err();
         assert_eq!(err, ProgramError::UninitializedAccount);
     }
+
+    #[test]
+    fn test_config_info_len() {
+        // Future fields take the reserved bytes, the length of the config must not change
+        assert_eq!(ConfigInfo::LEN, 598);
+        assert_eq!(CONFIG_INFO_RESERVED_BYTES, 65);
+
+        let config_info = ConfigInfo {
+            version: PROGRAM_VERSION,
+            admin_key: Pubkey::new_unique(),
+            fees: DEFAULT_TEST_FEES,
+            rewards: DEFAULT_TEST_REWARDS,
+            ..ConfigInfo::default()
+        };
+        let mut packed = [0u8; ConfigInfo::LEN];
+        ConfigInfo::pack_into_slice(&config_info, &mut packed);
+        assert_eq!(
+            ConfigInfo::unpack_any(&packed[..LEGACY_CONFIG_INFO_LEN]).unwrap(),
+            config_info
+        );
+        assert_eq!(ConfigInfo::unpack_any(&packed).unwrap(), config_info);
+    }
+
+    #[test]
+    fn test_accepted_orderbook_program_id() {
+        let mut config_info = ConfigInfo::default();
+        assert_eq!(
+            config_info.accepted_orderbook_program_id(),
+            Pubkey::from_str(SERUM_DEX_V3_PROGRAM_ID).unwrap()
+        );
+
+        let orderbook_program_id = Pubkey::new_unique();
+        config_info.orderbook_program_id = orderbook_program_id;
+        assert_eq!(
+            config_info.accepted_orderbook_program_id(),
+            orderbook_program_id
+        );
+    }
+
+    #[test]
+    fn test_is_market_maker() {
+        let market_maker = Pubkey::new_unique();
+        let mut config_info = ConfigInfo::default();
+        assert!(!config_info.is_market_maker(&market_maker));
+        assert!(!config_info.is_market_maker(&Pubkey::default()));
+
+        config_info.market_makers[1] = market_maker;
+        assert!(config_info.is_market_maker(&market_maker));
+        assert!(!config_info.is_market_maker(&Pubkey::new_unique()));
+        assert!(!config_info.is_market_maker(&Pubkey::default()));
+    }
+
+    #[test]
+    fn test_record_admin_op() {
+        let mut config_info = ConfigInfo {
+            max_admin_ops_per_epoch: 2,
+            ..ConfigInfo::default()
+        };
+        assert!(config_info.record_admin_op(10));
+        assert!(config_info.record_admin_op(10));
+        assert_eq!(config_info.admin_ops_count, 2);
+
+        // The count restarts every epoch
+        assert!(config_info.record_admin_op(11));
+        assert_eq!(config_info.admin_ops_epoch, 11);
+        assert_eq!(config_info.admin_ops_count, 1);
+        assert!(config_info.record_admin_op(11));
+
+        assert!(!config_info.record_admin_op(11));
+        assert!(config_info.is_admin_ops_paused);
+        // Paused until the guardian co-signs, whatever the epoch
+        assert!(!config_info.record_admin_op(12));
+    }
+
+    #[test]
+    fn test_has_role() {
+        let admin_key = Pubkey::new_unique();
+        let pauser_key = Pubkey::new_unique();
+        let mut config_info = ConfigInfo {
+            admin_key,
+            ..ConfigInfo::default()
+        };
+        for &role in &[
+            AdminRole::SuperAdmin,
+            AdminRole::FeeManager,
+            AdminRole::Pauser,
+            AdminRole::OracleManager,
+            AdminRole::Guardian,
+        ] {
+            assert!(config_info.has_role(role, &admin_key));
+            assert!(!config_info.has_role(role, &pauser_key));
+            assert!(!config_info.has_role(role, &Pubkey::default()));
+        }
+
+        config_info.pauser_key = pauser_key;
+        assert!(config_info.has_role(AdminRole::Pauser, &pauser_key));
+        assert!(config_info.has_role(AdminRole::Pauser, &admin_key));
+        assert!(!config_info.has_role(AdminRole::SuperAdmin, &pauser_key));
+        assert!(!config_info.has_role(AdminRole::FeeManager, &pauser_key));
+        assert!(!config_info.has_role(AdminRole::OracleManager, &pauser_key));
+        assert!(!config_info.has_role(AdminRole::Guardian, &pauser_key));
+        assert!(!config_info.has_role(AdminRole::FeeManager, &Pubkey::default()));
+
+        assert_eq!(AdminRole::try_from(2u8), Ok(AdminRole::Pauser));
+        assert_eq!(
+            AdminRole::try_from(5u8),
+            Err(ProgramError::InvalidInstructionData)
+        );
+    }
 }
@@ -10,11 +10,15 @@ use std::convert::TryFrom;
 
 use crate::{
     error::SwapError,
-    math::{Decimal, TryDiv, TryMul},
+    math::{Decimal, TryAdd, TryDiv, TryMul, TrySub},
+    time::TimeSource,
 };
 
 use super::*;
 
+#[cfg(feature = "serde")]
+use serde::{Deserialize, Serialize};
+
 /// Max number of farm positions
 pub const MAX_FARM_POSITIONS: usize = 1;
 
@@ -24,9 +28,31 @@ pub const MIN_CLAIM_PERIOD: UnixTimestamp = 60;
 /// Seconds per year
 pub const SECONDS_OF_YEAR: UnixTimestamp = 31556926;
 
+/// Seconds per day
+pub const SECONDS_OF_DAY: UnixTimestamp = 86_400;
+
+/// Lock duration and reward multiplier percentage of each farm lock tier, tier 0 is unlocked
+pub const FARM_LOCK_TIERS: [(UnixTimestamp, u8); 4] = [
+    (0, 100),
+    (30 * SECONDS_OF_DAY, 125),
+    (90 * SECONDS_OF_DAY, 150),
+    (180 * SECONDS_OF_DAY, 200),
+];
+
+/// Max number of reward recipients of a farm user
+pub const MAX_REWARD_RECIPIENTS: usize = 4;
+
+/// Scaler from the apr recorded by a farm position to the decimal scaled value
+const FARM_POSITION_APR_SCALER: u128 = 1_000_000;
+
+/// Packed apr epoch of the positions migrated to the per slot emission, their reward debt is
+/// packed in place of the rewards remainder
+const FARM_POSITION_EMISSION_EPOCH: u64 = u64::MAX;
+
 /// Farm states
 #[repr(C)]
 #[derive(Clone, Debug, Default, PartialEq)]
+#[cfg_attr(feature = "serde", derive(Serialize, Deserialize))]
 pub struct FarmInfo {
     /// Initialized state
     pub is_initialized: bool,
@@ -55,7 +81,31 @@ pub struct FarmInfo {
     /// APR denominator
     pub apr_denominator: u64,
 
-    /// Reserved 8 * 8 = 64 bytes for future use
+    /// Farm users are appended to the registry pages at initialization
+    pub is_farm_user_registry_enabled: bool,
+    /// Number of farm users in the registry
+    pub farm_user_count: u64,
+
+    /// APR epoch, bumped on every apr change
+    pub apr_epoch: u64,
+
+    /// Number of partner reward tokens emitted on top of DELTAFI, their accounts are then
+    /// required on deposit and withdrawal
+    pub reward_token_count: u8,
+
+    /// Timestamp the farm switched from the apr to the per slot emission of the rewards,
+    /// 0 while the rewards accrue at the apr
+    pub emission_start_ts: UnixTimestamp,
+    /// DELTAFI rewards emitted per slot to the stakers once the emission started
+    pub reward_per_slot: u64,
+    /// Rewards emitted per staked LP token, scaled by `REWARD_PER_SHARE_SCALER`
+    pub acc_reward_per_share: u128,
+    /// Slot the accumulator was last updated at
+    pub last_reward_slot: Slot,
+
+    /// Reserved u8 array for alignment
+    pub reserved_u8: [u8; FARM_INFO_RESERVED_U8],
+    /// Reserved u64 array for future use
     pub reserved: [u64; FARM_INFO_RESERVED_U64],
 }
 
@@ -85,6 +135,74 @@ impl FarmInfo {
         Ok(())
     }
 
+    /// Penalty kept from a withdrawal of the farm, 0 without fee
+    pub fn withdraw_fee(&self, amount: u64) -> Result<u64, ProgramError> {
+        if self.fee_numerator == 0 || self.fee_denominator == 0 {
+            return Ok(0);
+        }
+        (amount as u128)
+            .checked_mul(self.fee_numerator as u128)
+            .and_then(|value| value.checked_div(self.fee_denominator as u128))
+            .and_then(|value| u64::try_from(value).ok())
+            .filter(|fee| *fee <= amount)
+            .ok_or_else(|| SwapError::CalculationFailure.into())
+    }
+
+    /// Annual percentage ratio of the farm rewards
+    pub fn apr(&self) -> Result<Decimal, ProgramError> {
+        Decimal::from(self.apr_numerator).try_div(self.apr_denominator)
+    }
+
+    /// If the rewards are emitted per slot instead of accruing at the apr
+    pub fn is_emission_enabled(&self) -> bool {
+        self.emission_start_ts > 0
+    }
+
+    /// Rewards emitted per staked LP token up to the slot, not yet recorded if the slot is
+    /// past the last update
+    pub fn current_acc_reward_per_share(&self, slot: Slot) -> Result<u128, ProgramError> {
+        if slot <= self.last_reward_slot || self.reserved_amount == 0 {
+            return Ok(self.acc_reward_per_share);
+        }
+        let emitted = ((slot - self.last_reward_slot) as u128)
+            .checked_mul(self.reward_per_slot as u128)
+            .and_then(|emitted| emitted.checked_mul(REWARD_PER_SHARE_SCALER))
+            .ok_or(SwapError::CalculationFailure)?;
+        self.acc_reward_per_share
+            .checked_add(emitted / self.reserved_amount as u128)
+            .ok_or_else(|| SwapError::CalculationFailure.into())
+    }
+
+    /// Record the rewards emitted up to the slot, before the staked amount or the emission
+    /// rate change
+    pub fn update_emission(&mut self, slot: Slot) -> ProgramResult {
+        if !self.is_emission_enabled() {
+            return Ok(());
+        }
+        self.acc_reward_per_share = self.current_acc_reward_per_share(slot)?;
+        self.last_reward_slot = self.last_reward_slot.max(slot);
+        Ok(())
+    }
+
+    /// Set the rewards emitted per slot, starting the emission if the rewards accrued at the apr
+    pub fn set_emission_rate<T: TimeSource>(
+        &mut self,
+        reward_per_slot: u64,
+        time: &T,
+    ) -> ProgramResult {
+        if self.is_emission_enabled() {
+            self.update_emission(time.slot())?;
+        } else {
+            // The positions accrue at the apr up to the start and are migrated on their next
+            // update
+            self.emission_start_ts = time.unix_timestamp();
+            self.last_reward_slot = time.slot();
+            self.acc_reward_per_share = 0;
+        }
+        self.reward_per_slot = reward_per_slot;
+        Ok(())
+    }
+
     /// Check the reserve amount match the token amount in the farm.
     pub fn check_reserve_amount(&self, token_amount: u64) -> ProgramResult {
         if self.reserved_amount > token_amount {
@@ -94,9 +212,26 @@ impl FarmInfo {
     }
 }
 
+const FARM_INFO_RESERVED_U8: usize = 6;
 const FARM_INFO_RESERVED_U64: usize = 8;
-const FARM_INFO_RESERVED_BYTES: usize = FARM_INFO_RESERVED_U64 * 8;
-const FARM_INFO_SIZE: usize = 138 + FARM_INFO_RESERVED_BYTES;
+const FARM_INFO_RESERVED_BYTES: usize = FARM_INFO_RESERVED_U8 + FARM_INFO_RESERVED_U64 * 8;
+const FARM_INFO_SIZE: usize = 196 + FARM_INFO_RESERVED_BYTES;
+
+/// Length of the farm pools created before the reserved bytes were padded for the future
+/// fields, `MigrateFarmInfo` reallocates them to the current length
+pub const LEGACY_FARM_INFO_LEN: usize = FARM_INFO_SIZE - FARM_INFO_RESERVED_U64 * 8;
+
+impl FarmInfo {
+    /// Unpacks the farm pool of the current or the legacy length
+    pub fn unpack_any(src: &[u8]) -> Result<Self, ProgramError> {
+        if src.len() == LEGACY_FARM_INFO_LEN {
+            let mut data = [0u8; FARM_INFO_SIZE];
+            data[..LEGACY_FARM_INFO_LEN].copy_from_slice(src);
+            return Self::unpack(&data);
+        }
+        Self::unpack(src)
+    }
+}
 
 impl Pack for FarmInfo {
     const LEN: usize = FARM_INFO_SIZE;
@@ -116,6 +251,14 @@ impl Pack for FarmInfo {
             fee_denominator,
             apr_numerator,
             apr_denominator,
+            is_farm_user_registry_enabled,
+            farm_user_count,
+            apr_epoch,
+            reward_token_count,
+            emission_start_ts,
+            reward_per_slot,
+            acc_reward_per_share,
+            last_reward_slot,
             _, // reserved bytes
         ) = array_refs![
             input,
@@ -129,6 +272,14 @@ impl Pack for FarmInfo {
             8,
             8,
             8,
+            1,
+            8,
+            8,
+            1,
+            8,
+            8,
+            16,
+            8,
             FARM_INFO_RESERVED_BYTES
         ];
 
@@ -143,7 +294,16 @@ impl Pack for FarmInfo {
             fee_denominator: u64::from_le_bytes(*fee_denominator),
             apr_numerator: u64::from_le_bytes(*apr_numerator),
             apr_denominator: u64::from_le_bytes(*apr_denominator),
+            is_farm_user_registry_enabled: unpack_bool(is_farm_user_registry_enabled)?,
+            farm_user_count: u64::from_le_bytes(*farm_user_count),
+            apr_epoch: u64::from_le_bytes(*apr_epoch),
+            reward_token_count: reward_token_count[0],
+            emission_start_ts: i64::from_le_bytes(*emission_start_ts),
+            reward_per_slot: u64::from_le_bytes(*reward_per_slot),
+            acc_reward_per_share: u128::from_le_bytes(*acc_reward_per_share),
+            last_reward_slot: u64::from_le_bytes(*last_reward_slot),
             // Set all reserved bytes to 0
+            reserved_u8: [0u8; FARM_INFO_RESERVED_U8],
             reserved: [0u64; FARM_INFO_RESERVED_U64],
         })
     }
@@ -161,6 +321,14 @@ impl Pack for FarmInfo {
             fee_denominator,
             apr_numerator,
             apr_denominator,
+            is_farm_user_registry_enabled,
+            farm_user_count,
+            apr_epoch,
+            reward_token_count,
+            emission_start_ts,
+            reward_per_slot,
+            acc_reward_per_share,
+            last_reward_slot,
             reserved_bytes,
         ) = mut_array_refs![
             output,
@@ -174,6 +342,14 @@ impl Pack for FarmInfo {
             8,
             8,
             8,
+            1,
+            8,
+            8,
+            1,
+            8,
+            8,
+            16,
+            8,
             FARM_INFO_RESERVED_BYTES
         ];
         pack_bool(self.is_initialized, is_initialized);
@@ -186,6 +362,17 @@ impl Pack for FarmInfo {
         *fee_denominator = self.fee_denominator.to_le_bytes();
         *apr_numerator = self.apr_numerator.to_le_bytes();
         *apr_denominator = self.apr_denominator.to_le_bytes();
+        pack_bool(
+            self.is_farm_user_registry_enabled,
+            is_farm_user_registry_enabled,
+        );
+        *farm_user_count = self.farm_user_count.to_le_bytes();
+        *apr_epoch = self.apr_epoch.to_le_bytes();
+        reward_token_count[0] = self.reward_token_count;
+        *emission_start_ts = self.emission_start_ts.to_le_bytes();
+        *reward_per_slot = self.reward_per_slot.to_le_bytes();
+        *acc_reward_per_share = self.acc_reward_per_share.to_le_bytes();
+        *last_reward_slot = self.last_reward_slot.to_le_bytes();
         // Set all reserved bytes to 0
         *reserved_bytes = [0u8; FARM_INFO_RESERVED_BYTES];
     }
@@ -193,6 +380,7 @@ impl Pack for FarmInfo {
 
 /// Liquidity farm user
 #[derive(Clone, Debug, Default, PartialEq)]
+#[cfg_attr(feature = "serde", derive(Serialize, Deserialize))]
 pub struct FarmUser {
     /// Initialization status
     pub is_initialized: bool,
@@ -204,8 +392,10 @@ pub struct FarmUser {
     pub owner: Pubkey,
     /// Farm position
     pub position: FarmPosition,
-    /// Reserved 8 * 8 = 64 bytes for future use
-    pub reserved: [u64; FARM_INFO_RESERVED_U64],
+    /// Reward split account, default pubkey if the rewards are not split
+    pub reward_split: Pubkey,
+    /// Authority allowed to compound besides the owner, default pubkey if none
+    pub compound_delegate: Pubkey,
 }
 
 impl FarmUser {
@@ -257,6 +447,132 @@ impl FarmUser {
         Ok(())
     }
 
+    /// Accrue the rewards of the position up to the current time, at the apr recorded
+    /// by the position if the farm apr changed since its last checkpoint
+    ///
+    /// # Arguments
+    /// * farm_info - farm of the position.
+    /// * time - current slot and timestamp.
+    /// * is_deposit_withdraw - if called before deposit/withdraw
+    pub fn accrue_rewards<T: TimeSource>(
+        &mut self,
+        farm_info: &FarmInfo,
+        time: &T,
+        is_deposit_withdraw: bool,
+    ) -> ProgramResult {
+        let current_ts = time.unix_timestamp();
+        if farm_info.is_emission_enabled() {
+            if self.position.reward_debt.is_none() {
+                // Migrate the position, accruing at the apr up to the emission start
+                let apr = self.position.accrual_apr(farm_info)?;
+                self.position.calc_and_update_locked_rewards(
+                    apr,
+                    current_ts.min(farm_info.emission_start_ts),
+                    true,
+                )?;
+                self.position.migrate_to_emission();
+            }
+            let acc_reward_per_share = farm_info.current_acc_reward_per_share(time.slot())?;
+            return self
+                .position
+                .calc_and_update_emission_rewards(acc_reward_per_share, current_ts);
+        }
+
+        let apr = self.position.accrual_apr(farm_info)?;
+        self.position
+            .calc_and_update_locked_rewards(apr, current_ts, is_deposit_withdraw)?;
+        // The farm apr applies from the checkpoint on, a position not checkpointed
+        // because nothing accrued keeps its apr for the elapsed time
+        if self.position.last_update_ts == current_ts {
+            self.position.checkpoint_apr(farm_info)?;
+        }
+        Ok(())
+    }
+
+    /// Account the emitted rewards up to the new deposited amount, after a deposit or a
+    /// withdrawal settled the rewards of the previous one
+    fn reset_reward_debt(&mut self, farm_info: &FarmInfo) -> ProgramResult {
+        if farm_info.is_emission_enabled() {
+            self.position.reward_debt = Some(
+                (self.position.deposited_amount as u128)
+                    .checked_mul(farm_info.acc_reward_per_share)
+                    .ok_or(SwapError::CalculationFailure)?,
+            );
+        }
+        Ok(())
+    }
+
+    /// Accrue the rewards then deposit liquidity into the farm
+    ///
+    /// # Arguments
+    /// * farm_info - farm to deposit into.
+    /// * amount - amount to deposit.
+    /// * time - current slot and timestamp.
+    pub fn deposit_into_farm<T: TimeSource>(
+        &mut self,
+        farm_info: &mut FarmInfo,
+        amount: u64,
+        time: &T,
+    ) -> ProgramResult {
+        farm_info.update_emission(time.slot())?;
+        self.accrue_rewards(farm_info, time, true)?;
+        self.position.deposit(amount, time.slot())?;
+        self.reset_reward_debt(farm_info)?;
+        farm_info.deposit(amount)
+    }
+
+    /// Accrue the rewards then withdraw liquidity from the farm
+    ///
+    /// # Arguments
+    /// * farm_info - farm to withdraw from.
+    /// * amount - amount to withdraw.
+    /// * time - current slot and timestamp.
+    pub fn withdraw_from_farm<T: TimeSource>(
+        &mut self,
+        farm_info: &mut FarmInfo,
+        amount: u64,
+        time: &T,
+    ) -> ProgramResult {
+        farm_info.update_emission(time.slot())?;
+        self.accrue_rewards(farm_info, time, true)?;
+        self.withdraw(amount, time.slot())?;
+        self.reset_reward_debt(farm_info)?;
+        farm_info.withdraw(amount)
+    }
+
+    /// Move the position, its lock and accrued rewards included, to the farm user of another
+    /// owner of the same farm, leaving a new position behind. The destination position must
+    /// be empty.
+    ///
+    /// # Arguments
+    /// * destination - farm user receiving the position.
+    /// * farm_info - farm of the position.
+    /// * time - current slot and timestamp.
+    pub fn transfer_position<T: TimeSource>(
+        &mut self,
+        destination: &mut FarmUser,
+        farm_info: &FarmInfo,
+        time: &T,
+    ) -> ProgramResult {
+        let empty_position = &destination.position;
+        if empty_position.deposited_amount != 0
+            || empty_position.rewards_owed != 0
+            || empty_position.rewards_estimated != 0
+        {
+            return Err(SwapError::FarmPositionNotEmpty.into());
+        }
+
+        self.accrue_rewards(farm_info, time, true)?;
+        let mut position = FarmPosition::new(self.position.pool, time.unix_timestamp())?;
+        if farm_info.is_emission_enabled() {
+            position.migrate_to_emission();
+        } else {
+            position.checkpoint_apr(farm_info)?;
+        }
+        destination.position = std::mem::replace(&mut self.position, position);
+        Ok(())
+    }
+
     /// Claim rewards in corresponding position
     ///
     /// # Arguments
@@ -277,11 +593,48 @@ impl IsInitialized for FarmUser {
     }
 }
 
-const FARM_USER_RESERVED_U64: usize = 8;
-const FARM_USER_RESERVED_BYTES: usize = FARM_USER_RESERVED_U64 * 8;
 const FARM_POSITION_SIZE: usize = 88;
-const FARM_USER_SIZE: usize =
-    1 + PUBKEY_BYTES * 3 + 1 + FARM_POSITION_SIZE * MAX_FARM_POSITIONS + FARM_USER_RESERVED_BYTES;
+/// Length of the farm users created before the lock tiers
+pub const LEGACY_FARM_USER_LEN: usize =
+    1 + PUBKEY_BYTES * 3 + 1 + FARM_POSITION_SIZE * MAX_FARM_POSITIONS + PUBKEY_BYTES + 16 + 8 + 8;
+/// Length of the farm users created before the reserved bytes were padded for the future
+/// fields
+pub const UNPADDED_FARM_USER_LEN: usize = LEGACY_FARM_USER_LEN + 1 + 8;
+const FARM_USER_RESERVED_BYTES: usize = 32;
+const FARM_USER_SIZE: usize = UNPADDED_FARM_USER_LEN + PUBKEY_BYTES + FARM_USER_RESERVED_BYTES;
+
+impl FarmUser {
+    /// Unpacks the farm user of any length, the legacy farm user is never locked
+    pub fn unpack_any(src: &[u8]) -> Result<Self, ProgramError> {
+        if src.len() == LEGACY_FARM_USER_LEN || src.len() == UNPADDED_FARM_USER_LEN {
+            let mut data = [0u8; FARM_USER_SIZE];
+            data[..src.len()].copy_from_slice(src);
+            return Self::unpack(&data);
+        }
+        Self::unpack(src)
+    }
+
+    /// Packs the farm user into an account of any length, the legacy farm user has no room
+    /// for a lock and neither has room for a compound delegate
+    pub fn pack_any(farm_user: Self, dst: &mut [u8]) -> ProgramResult {
+        if dst.len() == LEGACY_FARM_USER_LEN && farm_user.position.lock_tier != 0 {
+            return Err(SwapError::InvalidFarmLockTier.into());
+        }
+        if (dst.len() == LEGACY_FARM_USER_LEN || dst.len() == UNPADDED_FARM_USER_LEN)
+            && farm_user.compound_delegate != Pubkey::default()
+        {
+            return Err(SwapError::InvalidCompoundDelegate.into());
+        }
+        if dst.len() == LEGACY_FARM_USER_LEN || dst.len() == UNPADDED_FARM_USER_LEN {
+            let mut data = [0u8; FARM_USER_SIZE];
+            Self::pack(farm_user, &mut data)?;
+            dst.copy_from_slice(&data[..dst.len()]);
+            return Ok(());
+        }
+        Self::pack(farm_user, dst)
+    }
+}
+
 impl Pack for FarmUser {
     const LEN: usize = FARM_USER_SIZE;
 
@@ -295,6 +648,13 @@ impl Pack for FarmUser {
             owner,
             positions_len,
             data_flat,
+            reward_split,
+            rewards_remainder,
+            apr_epoch,
+            apr,
+            lock_tier,
+            unlock_ts,
+            compound_delegate,
             reserved_bytes,
         ) = mut_array_refs![
             output,
@@ -304,6 +664,13 @@ impl Pack for FarmUser {
             PUBKEY_BYTES,
             1,
             FARM_POSITION_SIZE * MAX_FARM_POSITIONS,
+            PUBKEY_BYTES,
+            16,
+            8,
+            8,
+            1,
+            8,
+            PUBKEY_BYTES,
             FARM_USER_RESERVED_BYTES
         ];
         is_initialized[0] = self.is_initialized as u8;
@@ -334,13 +701,46 @@ impl Pack for FarmUser {
         *next_claim_ts = position.next_claim_ts.to_le_bytes();
         *latest_deposit_slot = position.latest_deposit_slot.to_le_bytes();
 
+        reward_split.copy_from_slice(self.reward_split.as_ref());
+        if let Some(reward_debt) = position.reward_debt {
+            // A migrated position has no rewards remainder nor apr
+            *rewards_remainder = reward_debt.to_le_bytes();
+            *apr_epoch = FARM_POSITION_EMISSION_EPOCH.to_le_bytes();
+        } else {
+            pack_decimal(position.rewards_remainder, rewards_remainder);
+            // 0 for a position without apr epoch, the epoch + 1 otherwise
+            *apr_epoch = position
+                .apr_epoch
+                .map_or(0, |epoch| epoch.saturating_add(1))
+                .to_le_bytes();
+        }
+        *apr = position.apr.to_le_bytes();
+        lock_tier[0] = position.lock_tier;
+        *unlock_ts = position.unlock_ts.to_le_bytes();
+        compound_delegate.copy_from_slice(self.compound_delegate.as_ref());
+        // Set all reserved bytes to 0
         *reserved_bytes = [0u8; FARM_USER_RESERVED_BYTES];
     }
 
     fn unpack_from_slice(src: &[u8]) -> Result<Self, ProgramError> {
         let input = array_ref![src, 0, FARM_USER_SIZE];
         #[allow(clippy::ptr_offset_with_cast)]
-        let (is_initialized, config_key, farm_pool_key, owner, _, data_flat, _) = array_refs![
+        let (
+            is_initialized,
+            config_key,
+            farm_pool_key,
+            owner,
+            _,
+            data_flat,
+            reward_split,
+            rewards_remainder,
+            apr_epoch,
+            apr,
+            lock_tier,
+            unlock_ts,
+            compound_delegate,
+            _, // reserved bytes
+        ) = array_refs![
             input,
             1,
             PUBKEY_BYTES,
@@ -348,6 +748,13 @@ impl Pack for FarmUser {
             PUBKEY_BYTES,
             1,
             FARM_POSITION_SIZE * MAX_FARM_POSITIONS,
+            PUBKEY_BYTES,
+            16,
+            8,
+            8,
+            1,
+            8,
+            PUBKEY_BYTES,
             FARM_USER_RESERVED_BYTES
         ];
 
@@ -365,15 +772,35 @@ impl Pack for FarmUser {
             next_claim_ts,
             latest_deposit_slot,
         ) = array_refs![positions_flat, PUBKEY_BYTES, 8, 8, 8, 8, 8, 8, 8];
+        let apr_epoch = u64::from_le_bytes(*apr_epoch);
+        let is_emission_position = apr_epoch == FARM_POSITION_EMISSION_EPOCH;
         let position = FarmPosition {
             pool: Pubkey::new(pool),
             deposited_amount: u64::from_le_bytes(*depositied_amount),
             rewards_owed: u64::from_le_bytes(*rewards_owed),
             rewards_estimated: u64::from_le_bytes(*rewards_estimated),
+            rewards_remainder: if is_emission_position {
+                Decimal::zero()
+            } else {
+                unpack_decimal(rewards_remainder)
+            },
             cumulative_interest: u64::from_le_bytes(*cumulative_interest),
             last_update_ts: i64::from_le_bytes(*last_update_ts),
             next_claim_ts: i64::from_le_bytes(*next_claim_ts),
             latest_deposit_slot: u64::from_le_bytes(*latest_deposit_slot),
+            apr_epoch: if is_emission_position {
+                None
+            } else {
+                apr_epoch.checked_sub(1)
+            },
+            apr: u64::from_le_bytes(*apr),
+            reward_debt: if is_emission_position {
+                Some(u128::from_le_bytes(*rewards_remainder))
+            } else {
+                None
+            },
+            lock_tier: lock_tier[0],
+            unlock_ts: i64::from_le_bytes(*unlock_ts),
         };
         Ok(Self {
             is_initialized,
@@ -381,13 +808,148 @@ impl Pack for FarmUser {
             farm_pool_key: Pubkey::new(farm_pool_key),
             owner: Pubkey::new(owner),
             position,
-            reserved: [0u64; FARM_USER_RESERVED_U64],
+            reward_split: Pubkey::new(reward_split),
+            compound_delegate: Pubkey::new(compound_delegate),
+        })
+    }
+}
+
+/// Reward payout split of a farm user
+#[derive(Clone, Debug, Default, PartialEq)]
+pub struct FarmRewardSplit {
+    /// Initialization status
+    pub is_initialized: bool,
+    /// Farm user pubkey
+    pub farm_user: Pubkey,
+    /// Number of recipients in use
+    pub recipients_len: u8,
+    /// Reward token accounts of the recipients
+    pub recipients: [Pubkey; MAX_REWARD_RECIPIENTS],
+    /// Percentage of the claimed rewards paid to each recipient
+    pub percentages: [u8; MAX_REWARD_RECIPIENTS],
+}
+
+impl FarmRewardSplit {
+    /// Validate the recipient percentages, the owner keeps what is left of 100%.
+    pub fn validate_percentages(
+        recipients_len: usize,
+        percentages: &[u8; MAX_REWARD_RECIPIENTS],
+    ) -> ProgramResult {
+        if recipients_len > MAX_REWARD_RECIPIENTS {
+            return Err(SwapError::InvalidRewardSplit.into());
+        }
+        let mut total = 0u64;
+        for (i, percentage) in percentages.iter().enumerate() {
+            if (i < recipients_len) != (*percentage > 0) {
+                return Err(SwapError::InvalidRewardSplit.into());
+            }
+            total += *percentage as u64;
+        }
+        if total > 100 {
+            return Err(SwapError::InvalidRewardSplit.into());
+        }
+        Ok(())
+    }
+
+    /// Split the claimed rewards
+    ///
+    /// # Arguments
+    /// * reward_amount - claimed rewards.
+    ///
+    /// # Return value
+    /// amount left to the owner, amount of each recipient
+    pub fn split_rewards(
+        &self,
+        reward_amount: u64,
+    ) -> Result<(u64, [u64; MAX_REWARD_RECIPIENTS]), ProgramError> {
+        let mut amounts = [0u64; MAX_REWARD_RECIPIENTS];
+        let mut owner_amount = reward_amount;
+        for (amount, percentage) in amounts
+            .iter_mut()
+            .zip(self.percentages.iter())
+            .take(self.recipients_len as usize)
+        {
+            *amount = (reward_amount as u128)
+                .checked_mul(*percentage as u128)
+                .and_then(|value| value.checked_div(100))
+                .and_then(|value| u64::try_from(value).ok())
+                .ok_or(SwapError::CalculationFailure)?;
+            owner_amount = owner_amount
+                .checked_sub(*amount)
+                .ok_or(SwapError::CalculationFailure)?;
+        }
+        Ok((owner_amount, amounts))
+    }
+}
+
+impl Sealed for FarmRewardSplit {}
+impl IsInitialized for FarmRewardSplit {
+    fn is_initialized(&self) -> bool {
+        self.is_initialized
+    }
+}
+
+const FARM_REWARD_SPLIT_SIZE: usize =
+    1 + PUBKEY_BYTES + 1 + PUBKEY_BYTES * MAX_REWARD_RECIPIENTS + MAX_REWARD_RECIPIENTS;
+impl Pack for FarmRewardSplit {
+    const LEN: usize = FARM_REWARD_SPLIT_SIZE;
+
+    fn pack_into_slice(&self, dst: &mut [u8]) {
+        let output = array_mut_ref![dst, 0, FARM_REWARD_SPLIT_SIZE];
+        #[allow(clippy::ptr_offset_with_cast)]
+        let (is_initialized, farm_user, recipients_len, recipients_flat, percentages) = mut_array_refs![
+            output,
+            1,
+            PUBKEY_BYTES,
+            1,
+            PUBKEY_BYTES * MAX_REWARD_RECIPIENTS,
+            MAX_REWARD_RECIPIENTS
+        ];
+        pack_bool(self.is_initialized, is_initialized);
+        farm_user.copy_from_slice(self.farm_user.as_ref());
+        *recipients_len = self.recipients_len.to_le_bytes();
+        for (dst, recipient) in recipients_flat
+            .chunks_exact_mut(PUBKEY_BYTES)
+            .zip(self.recipients.iter())
+        {
+            dst.copy_from_slice(recipient.as_ref());
+        }
+        *percentages = self.percentages;
+    }
+
+    fn unpack_from_slice(src: &[u8]) -> Result<Self, ProgramError> {
+        let input = array_ref![src, 0, FARM_REWARD_SPLIT_SIZE];
+        #[allow(clippy::ptr_offset_with_cast)]
+        let (is_initialized, farm_user, recipients_len, recipients_flat, percentages) = array_refs![
+            input,
+            1,
+            PUBKEY_BYTES,
+            1,
+            PUBKEY_BYTES * MAX_REWARD_RECIPIENTS,
+            MAX_REWARD_RECIPIENTS
+        ];
+
+        let mut recipients = [Pubkey::default(); MAX_REWARD_RECIPIENTS];
+        for (recipient, src) in recipients
+            .iter_mut()
+            .zip(recipients_flat.chunks_exact(PUBKEY_BYTES))
+        {
+            *recipient = Pubkey::new(src);
+        }
+
+        Ok(Self {
+            is_initialized: unpack_bool(is_initialized)?,
+            farm_user: Pubkey::new_from_array(*farm_user),
+            recipients_len: u8::from_le_bytes(*recipients_len),
+            recipients,
+            percentages: *percentages,
         })
     }
 }
 
 /// Farm position of a pool
 #[derive(Clone, Debug, Default, PartialEq)]
+#[cfg_attr(feature = "serde", derive(Serialize, Deserialize))]
 pub struct FarmPosition {
     /// Staked LP_Token amount owned by this position
     pub deposited_amount: u64,
@@ -397,6 +959,8 @@ pub struct FarmPosition {
     pub rewards_owed: u64,
     /// Rewards amount estimated in new claim period
     pub rewards_estimated: u64,
+    /// Fraction of a token accrued on top of rewards_estimated, carried over between updates
+    pub rewards_remainder: Decimal,
     /// Cumulative interest
     pub cumulative_interest: u64,
     /// Last updated timestamp
@@ -405,6 +969,18 @@ pub struct FarmPosition {
     pub next_claim_ts: UnixTimestamp,
     /// Latest deposit clock slot
     pub latest_deposit_slot: Slot,
+    /// Farm apr epoch the position accrues at, None for positions not checkpointed
+    /// since apr epochs were introduced
+    pub apr_epoch: Option<u64>,
+    /// Apr of the epoch, scaled by 10^12
+    pub apr: u64,
+    /// Rewards emitted per slot to the deposited amount already accounted, scaled by
+    /// `REWARD_PER_SHARE_SCALER`, None for positions still accruing at the apr
+    pub reward_debt: Option<u128>,
+    /// Lock tier boosting the rewards until the unlock timestamp, 0 if not locked
+    pub lock_tier: u8,
+    /// Timestamp the deposited amount can be withdrawn from
+    pub unlock_ts: UnixTimestamp,
 }
 
 impl FarmPosition {
@@ -471,6 +1047,43 @@ impl FarmPosition {
         Ok(())
     }
 
+    /// Lock the whole position in a lock tier, a position already locked keeps the higher
+    /// tier and the later unlock timestamp
+    ///
+    /// # Arguments
+    /// * lock_tier - index in `FARM_LOCK_TIERS`, 0 does not lock.
+    /// * current_ts - current unix timestamp.
+    pub fn lock(&mut self, lock_tier: u8, current_ts: UnixTimestamp) -> ProgramResult {
+        let (lock_duration, _) = FARM_LOCK_TIERS
+            .get(lock_tier as usize)
+            .ok_or(SwapError::InvalidFarmLockTier)?;
+        if lock_tier == 0 {
+            return Ok(());
+        }
+        let unlock_ts = current_ts
+            .checked_add(*lock_duration)
+            .ok_or(SwapError::CalculationFailure)?;
+        self.lock_tier = self.lock_tier.max(lock_tier);
+        self.unlock_ts = self.unlock_ts.max(unlock_ts);
+        Ok(())
+    }
+
+    /// Check the position can be withdrawn from
+    pub fn check_unlocked(&self, current_ts: UnixTimestamp) -> ProgramResult {
+        if current_ts < self.unlock_ts {
+            return Err(SwapError::FarmPositionLocked.into());
+        }
+        Ok(())
+    }
+
+    /// Reward multiplier percentage of the lock tier
+    pub fn lock_multiplier(&self) -> Result<u8, ProgramError> {
+        FARM_LOCK_TIERS
+            .get(self.lock_tier as usize)
+            .map(|(_, multiplier)| *multiplier)
+            .ok_or_else(|| SwapError::InvalidFarmLockTier.into())
+    }
+
     /// Update next claim timestamp
     ///
     /// # Return value
@@ -484,6 +1097,148 @@ impl FarmPosition {
         Ok(())
     }
 
+    /// Apr to accrue the rewards at since the last update, the farm apr unless the
+    /// farm apr changed since the position was checkpointed
+    ///
+    /// # Arguments
+    /// * farm_info - farm of the position.
+    pub fn accrual_apr(&self, farm_info: &FarmInfo) -> Result<Decimal, ProgramError> {
+        match self.apr_epoch {
+            Some(epoch) if epoch != farm_info.apr_epoch => Ok(Decimal::from_scaled_val(
+                (self.apr as u128)
+                    .checked_mul(FARM_POSITION_APR_SCALER)
+                    .ok_or(SwapError::CalculationFailure)?,
+            )),
+            _ => farm_info.apr(),
+        }
+    }
+
+    /// Record the current apr epoch and apr of the farm
+    ///
+    /// # Arguments
+    /// * farm_info - farm of the position.
+    pub fn checkpoint_apr(&mut self, farm_info: &FarmInfo) -> ProgramResult {
+        if self.apr_epoch != Some(farm_info.apr_epoch) {
+            self.apr = u64::try_from(farm_info.apr()?.to_scaled_val()? / FARM_POSITION_APR_SCALER)
+                .map_err(|_| SwapError::CalculationFailure)?;
+            self.apr_epoch = Some(farm_info.apr_epoch);
+        }
+        Ok(())
+    }
+
+    /// Move the position from the apr to the per slot emission. The deposited amount did not
+    /// change since the emission started, so the rewards emitted since then are owed to it
+    /// from a zero reward debt.
+    pub fn migrate_to_emission(&mut self) {
+        self.reward_debt = Some(0);
+        self.rewards_remainder = Decimal::zero();
+        self.apr_epoch = None;
+        self.apr = 0;
+    }
+
+    /// Calculate and update the rewards emitted per slot since the last update
+    ///
+    /// # Arguments
+    /// * acc_reward_per_share - current rewards emitted per staked LP token, scaled.
+    /// * current_ts - current unix timestamp.
+    pub fn calc_and_update_emission_rewards(
+        &mut self,
+        acc_reward_per_share: u128,
+        current_ts: UnixTimestamp,
+    ) -> ProgramResult {
+        let accumulated = (self.deposited_amount as u128)
+            .checked_mul(acc_reward_per_share)
+            .ok_or(SwapError::CalculationFailure)?;
+        // The fraction of a token below the scaler stays in the debt for the next update
+        let reward_debt = self.reward_debt.unwrap_or_default();
+        let accrued_tokens = accumulated
+            .checked_sub(reward_debt)
+            .ok_or(SwapError::CalculationFailure)?
+            / REWARD_PER_SHARE_SCALER;
+        let lock_bonus = self.calc_lock_bonus(accrued_tokens, current_ts)?;
+        self.rewards_estimated = u64::try_from(accrued_tokens)
+            .ok()
+            .and_then(|accrued_tokens| self.rewards_estimated.checked_add(accrued_tokens))
+            .and_then(|rewards_estimated| rewards_estimated.checked_add(lock_bonus))
+            .ok_or(SwapError::CalculationFailure)?;
+        self.reward_debt = Some(
+            reward_debt
+                .checked_add(accrued_tokens * REWARD_PER_SHARE_SCALER)
+                .ok_or(SwapError::CalculationFailure)?,
+        );
+        self.last_update_ts = current_ts;
+
+        if current_ts >= self.next_claim_ts {
+            self.rewards_owed = self
+                .rewards_owed
+                .checked_add(self.rewards_estimated)
+                .ok_or(SwapError::CalculationFailure)?;
+            self.rewards_estimated = 0;
+            self.update_claim_ts(current_ts)?;
+        }
+        Ok(())
+    }
+
+    /// Bonus of the lock multiplier on the rewards emitted since the last update, pro rata to
+    /// the time locked since then as the emission is per slot. Ends the lock once unlocked.
+    fn calc_lock_bonus(
+        &mut self,
+        accrued_tokens: u128,
+        current_ts: UnixTimestamp,
+    ) -> Result<u64, ProgramError> {
+        if self.lock_tier == 0 {
+            return Ok(0);
+        }
+        let bonus_percent = self.lock_multiplier()?.saturating_sub(100) as u128;
+        let elapsed = current_ts.saturating_sub(self.last_update_ts);
+        let (locked, elapsed) = if elapsed > 0 {
+            (
+                self.unlock_ts
+                    .min(current_ts)
+                    .saturating_sub(self.last_update_ts)
+                    .max(0),
+                elapsed,
+            )
+        } else {
+            ((current_ts < self.unlock_ts) as i64, 1)
+        };
+        if current_ts >= self.unlock_ts {
+            self.lock_tier = 0;
+        }
+        accrued_tokens
+            .checked_mul(bonus_percent)
+            .and_then(|value| value.checked_mul(locked as u128))
+            .and_then(|value| value.checked_div(100 * elapsed as u128))
+            .and_then(|value| u64::try_from(value).ok())
+            .ok_or_else(|| SwapError::CalculationFailure.into())
+    }
+
+    /// Calculate and update the rewards at the apr boosted by the lock multiplier, the time
+    /// after the unlock timestamp accruing at the apr
+    ///
+    /// # Arguments
+    /// * apr - annual percentage ratio.
+    /// * current_ts - current unix timestamp.
+    /// * is_deposit_withdraw - if called before deposit/withdraw
+    pub fn calc_and_update_locked_rewards(
+        &mut self,
+        apr: Decimal,
+        current_ts: UnixTimestamp,
+        is_deposit_withdraw: bool,
+    ) -> ProgramResult {
+        if self.lock_tier != 0 && current_ts >= self.unlock_ts {
+            let locked_apr = apr.try_mul(Decimal::from_percent(self.lock_multiplier()?))?;
+            self.calc_and_update_rewards(
+                locked_apr,
+                self.unlock_ts.max(self.last_update_ts),
+                true,
+            )?;
+            self.lock_tier = 0;
+        }
+        let apr = apr.try_mul(Decimal::from_percent(self.lock_multiplier()?))?;
+        self.calc_and_update_rewards(apr, current_ts, is_deposit_withdraw)
+    }
+
     /// Calculate and update rewards
     ///
     /// # Arguments
@@ -503,18 +1258,24 @@ impl FarmPosition {
             .checked_sub(self.last_update_ts)
             .ok_or(SwapError::CalculationFailure)?;
         if calc_period > 0 {
-            let new_rewards_estimated = apr
+            // Keep the accrued rewards scaled, only whole tokens move to rewards_estimated
+            // and the fraction is carried to the next update instead of being truncated.
+            let accrued_rewards = apr
                 .try_mul(self.deposited_amount)?
                 .try_div(u64::try_from(SECONDS_OF_YEAR).unwrap())?
                 .try_mul(u64::try_from(calc_period).unwrap())?
-                .try_floor_u64()?
-                .checked_add(self.rewards_estimated)
+                .try_add(self.rewards_remainder)?;
+            let accrued_tokens = accrued_rewards.try_floor_u64()?;
+            let new_rewards_estimated = self
+                .rewards_estimated
+                .checked_add(accrued_tokens)
                 .ok_or(SwapError::CalculationFailure)?;
 
             // Only update the rewards_estimated when there is non-zero rewards
             // OR this is called before deposit/withdraw.
             if is_deposit_withdraw || new_rewards_estimated > self.rewards_estimated {
                 self.rewards_estimated = new_rewards_estimated;
+                self.rewards_remainder = accrued_rewards.try_sub(Decimal::from(accrued_tokens))?;
                 self.last_update_ts = current_ts;
             }
         }
@@ -530,6 +1291,19 @@ impl FarmPosition {
         Ok(())
     }
 
+    /// Return claimed rewards that were not paid out to the rewards owed
+    pub fn restore_rewards(&mut self, amount: u64) -> ProgramResult {
+        self.rewards_owed = self
+            .rewards_owed
+            .checked_add(amount)
+            .ok_or(SwapError::CalculationFailure)?;
+        self.cumulative_interest = self
+            .cumulative_interest
+            .checked_sub(amount)
+            .ok_or(SwapError::CalculationFailure)?;
+        Ok(())
+    }
+
     /// Claim rewards owed
     ///
     /// # Return value
@@ -552,7 +1326,7 @@ impl FarmPosition {
 mod tests {
 
     use super::*;
-    use crate::{math::*, solana_program::clock::Clock};
+    use crate::{math::*, solana_program::clock::Clock, time::ManualClock};
     use proptest::prelude::*;
     use rand::random;
     use rand::seq::SliceRandom;
@@ -587,10 +1361,16 @@ mod tests {
                 deposited_amount,
                 rewards_owed: 0u64,
                 rewards_estimated: 0u64,
+                rewards_remainder: Decimal::zero(),
                 cumulative_interest: 0u64,
                 last_update_ts: 0i64,
                 next_claim_ts: max_ts + 1,
                 latest_deposit_slot: 0,
+                apr_epoch: None,
+                apr: 0,
+                reward_debt: None,
+                lock_tier: 0,
+                unlock_ts: 0,
             };
 
             for ts in time_stamps {
@@ -610,10 +1390,16 @@ mod tests {
                 deposited_amount,
                 rewards_owed: 0u64,
                 rewards_estimated: 0u64,
+                rewards_remainder: Decimal::zero(),
                 cumulative_interest: 0u64,
                 last_update_ts: 0i64,
                 next_claim_ts: 1,
                 latest_deposit_slot: 0,
+                apr_epoch: None,
+                apr: 0,
+                reward_debt: None,
+                lock_tier: 0,
+                unlock_ts: 0,
             };
 
             time_stamps = vec![rng.gen::<i64>(); time_stamp_count as usize];
@@ -710,6 +1496,9 @@ mod tests {
         let fee_denominator = 2;
         let apr_numerator = 12;
         let apr_denominator = 100;
+        let farm_user_count = 65u64;
+        let apr_epoch = 3u64;
+        let reserved_u8 = [0u8; FARM_INFO_RESERVED_U8];
         let reserved = [0u64; FARM_INFO_RESERVED_U64];
 
         let farm_info = FarmInfo {
@@ -723,6 +1512,15 @@ mod tests {
             fee_denominator,
             apr_numerator,
             apr_denominator,
+            is_farm_user_registry_enabled: true,
+            farm_user_count,
+            apr_epoch,
+            reward_token_count: 2,
+            emission_start_ts: 1_000,
+            reward_per_slot: 50,
+            acc_reward_per_share: 1_000_000,
+            last_reward_slot: 200,
+            reserved_u8,
             reserved,
         };
 
@@ -740,6 +1538,14 @@ mod tests {
         packed.extend_from_slice(&fee_denominator.to_le_bytes());
         packed.extend_from_slice(&apr_numerator.to_le_bytes());
         packed.extend_from_slice(&apr_denominator.to_le_bytes());
+        packed.push(1u8);
+        packed.extend_from_slice(&farm_user_count.to_le_bytes());
+        packed.extend_from_slice(&apr_epoch.to_le_bytes());
+        packed.push(2u8);
+        packed.extend_from_slice(&1_000i64.to_le_bytes());
+        packed.extend_from_slice(&50u64.to_le_bytes());
+        packed.extend_from_slice(&1_000_000u128.to_le_bytes());
+        packed.extend_from_slice(&200u64.to_le_bytes());
         packed.extend_from_slice(&[0u8; FARM_INFO_RESERVED_BYTES]);
 
         let unpacked = FarmInfo::unpack(&packed).unwrap();
@@ -753,6 +1559,37 @@ mod tests {
         assert_eq!(err, ProgramError::UninitializedAccount);
     }
 
+    #[test]
+    fn test_farm_info_len() {
+        // Future fields take the reserved bytes, the length of the farm must not change
+        assert_eq!(FarmInfo::LEN, 266);
+        assert_eq!(FARM_INFO_RESERVED_BYTES, 70);
+
+        let farm_info = FarmInfo {
+            is_initialized: true,
+            bump_seed: 255,
+            config_key: Pubkey::new_unique(),
+            pool_mint: Pubkey::new_unique(),
+            reward_token_count: 1,
+            ..FarmInfo::default()
+        };
+        let mut packed = [0u8; FarmInfo::LEN];
+        FarmInfo::pack_into_slice(&farm_info, &mut packed);
+        assert_eq!(
+            FarmInfo::unpack_any(&packed[..LEGACY_FARM_INFO_LEN]).unwrap(),
+            farm_info
+        );
+        assert_eq!(FarmInfo::unpack_any(&packed).unwrap(), farm_info);
+    }
+
+    #[test]
+    fn test_farm_user_len() {
+        // Future fields take the reserved bytes, the length of the farm user must not change
+        assert_eq!(FarmUser::LEN, 323);
+        assert_eq!(UNPADDED_FARM_USER_LEN, 259);
+        assert_eq!(LEGACY_FARM_USER_LEN, 250);
+    }
+
     #[test]
     fn test_farm_user_packing() {
         let is_initialized = true;
@@ -767,22 +1604,33 @@ mod tests {
         let deposited_amount_1: u64 = 300;
         let rewards_owed_1: u64 = 100;
         let rewards_estimated_1: u64 = 40;
+        let rewards_remainder_1 = Decimal::from_percent(25);
         let cumulative_interest_1: u64 = 1000;
         let last_update_ts_1 = Clock::clone(&Default::default()).unix_timestamp + 300;
         let next_claim_ts_1 = last_update_ts_1 + MIN_CLAIM_PERIOD;
         let latest_deposit_slot_1: Slot = 10000;
+        let apr_epoch_1: u64 = 2;
+        let apr_1: u64 = 120_000_000_000;
+        let unlock_ts_1 = last_update_ts_1 + 30 * SECONDS_OF_DAY;
 
         let position_1 = FarmPosition {
             pool: pool_1,
             deposited_amount: deposited_amount_1,
             rewards_owed: rewards_owed_1,
             rewards_estimated: rewards_estimated_1,
+            rewards_remainder: rewards_remainder_1,
             cumulative_interest: cumulative_interest_1,
             last_update_ts: last_update_ts_1,
             next_claim_ts: next_claim_ts_1,
             latest_deposit_slot: latest_deposit_slot_1,
+            apr_epoch: Some(apr_epoch_1),
+            apr: apr_1,
+            reward_debt: None,
+            lock_tier: 1,
+            unlock_ts: unlock_ts_1,
         };
-        let reserved = [0u64; FARM_USER_RESERVED_U64];
+        let reward_split = Pubkey::new_unique();
+        let compound_delegate = Pubkey::new_unique();
 
         let farm_user = FarmUser {
             is_initialized,
@@ -790,7 +1638,8 @@ mod tests {
             farm_pool_key,
             owner,
             position: position_1,
-            reserved,
+            reward_split,
+            compound_delegate,
         };
 
         let mut packed = [0u8; FarmUser::LEN];
@@ -811,11 +1660,55 @@ mod tests {
         packed.extend_from_slice(&last_update_ts_1.to_le_bytes());
         packed.extend_from_slice(&next_claim_ts_1.to_le_bytes());
         packed.extend_from_slice(&latest_deposit_slot_1.to_le_bytes());
+        packed.extend_from_slice(reward_split.as_ref());
+        packed.extend_from_slice(&rewards_remainder_1.to_scaled_val().unwrap().to_le_bytes());
+        packed.extend_from_slice(&(apr_epoch_1 + 1).to_le_bytes());
+        packed.extend_from_slice(&apr_1.to_le_bytes());
+        packed.push(1u8);
+        packed.extend_from_slice(&unlock_ts_1.to_le_bytes());
+        packed.extend_from_slice(compound_delegate.as_ref());
         packed.extend_from_slice(&[0u8; FARM_USER_RESERVED_BYTES]);
 
         let unpacked = FarmUser::unpack(&packed).unwrap();
         assert_eq!(farm_user, unpacked);
 
+        // The farm user created before the padding keeps its lock but has no compound delegate
+        let unpadded = FarmUser::unpack_any(&packed[..UNPADDED_FARM_USER_LEN]).unwrap();
+        assert_eq!(unpadded.compound_delegate, Pubkey::default());
+        let mut unpadded_packed = [0u8; UNPADDED_FARM_USER_LEN];
+        assert_eq!(
+            FarmUser::pack_any(farm_user.clone(), &mut unpadded_packed).unwrap_err(),
+            SwapError::InvalidCompoundDelegate.into()
+        );
+        let farm_user = FarmUser {
+            compound_delegate: Pubkey::default(),
+            ..farm_user
+        };
+        assert_eq!(unpadded, farm_user);
+        FarmUser::pack_any(farm_user.clone(), &mut unpadded_packed).unwrap();
+        assert_eq!(unpadded_packed[..], packed[..UNPADDED_FARM_USER_LEN]);
+
+        // The legacy farm user is unpacked unlocked and cannot be packed with a lock
+        let legacy = FarmUser::unpack_any(&packed[..LEGACY_FARM_USER_LEN]).unwrap();
+        assert_eq!(legacy.position.lock_tier, 0);
+        assert_eq!(legacy.position.unlock_ts, 0);
+        let mut legacy_packed = [0u8; LEGACY_FARM_USER_LEN];
+        assert_eq!(
+            FarmUser::pack_any(farm_user.clone(), &mut legacy_packed).unwrap_err(),
+            SwapError::InvalidFarmLockTier.into()
+        );
+        FarmUser::pack_any(legacy.clone(), &mut legacy_packed).unwrap();
+        assert_eq!(legacy_packed[..], packed[..LEGACY_FARM_USER_LEN]);
+        assert_eq!(FarmUser::unpack_any(&legacy_packed).unwrap(), legacy);
+
+        // A migrated position packs its reward debt in place of the remainder
+        let mut farm_user = farm_user;
+        farm_user.position.migrate_to_emission();
+        farm_user.position.reward_debt = Some(u128::MAX - 1);
+        let mut packed = [0u8; FarmUser::LEN];
+        FarmUser::pack_into_slice(&farm_user, &mut packed);
+        assert_eq!(FarmUser::unpack(&packed).unwrap(), farm_user);
+
         let packed = [0u8; FarmUser::LEN];
         let farm_user: FarmUser = Default::default();
         let unpack_unchecked = FarmUser::unpack_unchecked(&packed).unwrap();
@@ -825,6 +1718,91 @@ mod tests {
         assert_eq!(err, ProgramError::UninitializedAccount);
     }
 
+    #[test]
+    fn test_farm_reward_split_packing() {
+        let farm_reward_split = FarmRewardSplit {
+            is_initialized: true,
+            farm_user: Pubkey::new_unique(),
+            recipients_len: 2,
+            recipients: [
+                Pubkey::new_unique(),
+                Pubkey::new_unique(),
+                Pubkey::default(),
+                Pubkey::default(),
+            ],
+            percentages: [30, 20, 0, 0],
+        };
+
+        let mut packed = [0u8; FarmRewardSplit::LEN];
+        FarmRewardSplit::pack_into_slice(&farm_reward_split, &mut packed);
+        let unpacked = FarmRewardSplit::unpack(&packed).unwrap();
+        assert_eq!(farm_reward_split, unpacked);
+
+        let mut expected: Vec<u8> = vec![1];
+        expected.extend_from_slice(farm_reward_split.farm_user.as_ref());
+        expected.push(2);
+        for recipient in farm_reward_split.recipients.iter() {
+            expected.extend_from_slice(recipient.as_ref());
+        }
+        expected.extend_from_slice(&[30, 20, 0, 0]);
+        assert_eq!(packed.to_vec(), expected);
+
+        let packed = [0u8; FarmRewardSplit::LEN];
+        let err = FarmRewardSplit::unpack(&packed).unwrap_err();
+        assert_eq!(err, ProgramError::UninitializedAccount);
+    }
+
+    #[test]
+    fn test_farm_reward_split() {
+        assert_eq!(
+            FarmRewardSplit::validate_percentages(2, &[30, 20, 0, 0]),
+            Ok(())
+        );
+        assert_eq!(
+            FarmRewardSplit::validate_percentages(4, &[25, 25, 25, 25]),
+            Ok(())
+        );
+        assert_eq!(
+            FarmRewardSplit::validate_percentages(0, &[0, 0, 0, 0]),
+            Ok(())
+        );
+        // The sum can not exceed 100%.
+        assert_eq!(
+            FarmRewardSplit::validate_percentages(4, &[25, 25, 25, 26]),
+            Err(SwapError::InvalidRewardSplit.into())
+        );
+        // Every recipient gets a share, unused slots stay empty.
+        assert_eq!(
+            FarmRewardSplit::validate_percentages(2, &[30, 0, 0, 0]),
+            Err(SwapError::InvalidRewardSplit.into())
+        );
+        assert_eq!(
+            FarmRewardSplit::validate_percentages(1, &[30, 20, 0, 0]),
+            Err(SwapError::InvalidRewardSplit.into())
+        );
+        assert_eq!(
+            FarmRewardSplit::validate_percentages(5, &[25, 25, 25, 25]),
+            Err(SwapError::InvalidRewardSplit.into())
+        );
+
+        let farm_reward_split = FarmRewardSplit {
+            is_initialized: true,
+            recipients_len: 3,
+            percentages: [30, 20, 1, 0],
+            ..FarmRewardSplit::default()
+        };
+        assert_eq!(
+            farm_reward_split.split_rewards(1_000),
+            Ok((490, [300, 200, 10, 0]))
+        );
+        // Rounding leftovers go to the owner.
+        assert_eq!(
+            farm_reward_split.split_rewards(99),
+            Ok((51, [29, 19, 0, 0]))
+        );
+        assert_eq!(farm_reward_split.split_rewards(0), Ok((0, [0, 0, 0, 0])));
+    }
+
     #[test]
     fn test_farm_position_deposit_withdraw() {
         let farm_position_res = FarmPosition::new(Pubkey::new_unique(), 0);
@@ -893,6 +1871,399 @@ mod tests {
         assert!(farm_user.withdraw(500_000, current_slot).is_ok());
     }
 
+    #[test]
+    fn test_farm_user_accrual_with_manual_clock() {
+        let mut farm_info = FarmInfo {
+            apr_numerator: 1,
+            apr_denominator: 10,
+            ..FarmInfo::default()
+        };
+        let mut clock = ManualClock::new(100, 1_000);
+        let mut farm_user = FarmUser::new(
+            Pubkey::new_unique(),
+            Pubkey::new_unique(),
+            Pubkey::new_unique(),
+            FarmPosition::new(Pubkey::new_unique(), clock.unix_timestamp).unwrap(),
+        );
+
+        farm_user
+            .deposit_into_farm(&mut farm_info, 1_000_000_000, &clock)
+            .unwrap();
+        assert_eq!(farm_info.reserved_amount, 1_000_000_000);
+        assert_eq!(farm_user.position.latest_deposit_slot, 100);
+
+        // Withdrawals in the slot of the deposit are rejected
+        assert_eq!(
+            farm_user.withdraw_from_farm(&mut farm_info, 1, &clock),
+            Err(SwapError::PotentialFlashLoanAttack.into())
+        );
+
+        // 10% of 1_000_000_000 a year, 3.16... tokens per second
+        clock.advance(1, 10);
+        farm_user.accrue_rewards(&farm_info, &clock, false).unwrap();
+        assert_eq!(farm_user.position.rewards_estimated, 31);
+        assert_eq!(farm_user.position.last_update_ts, 1_010);
+
+        // Each second accrues from the carried remainder
+        for _ in 0..10 {
+            clock.advance(1, 1);
+            farm_user.accrue_rewards(&farm_info, &clock, false).unwrap();
+        }
+        assert_eq!(farm_user.position.rewards_estimated, 63);
+
+        // Past the claim period the estimated rewards are owed
+        clock.advance(1, MIN_CLAIM_PERIOD);
+        farm_user
+            .withdraw_from_farm(&mut farm_info, 500_000_000, &clock)
+            .unwrap();
+        assert_eq!(farm_info.reserved_amount, 500_000_000);
+        assert_eq!(farm_user.position.deposited_amount, 500_000_000);
+        assert_eq!(farm_user.position.rewards_estimated, 0);
+        assert_eq!(farm_user.position.rewards_owed, 253);
+    }
+
+    #[test]
+    fn test_farm_user_emission_migration() {
+        let mut farm_info = FarmInfo {
+            apr_numerator: 1,
+            apr_denominator: 10,
+            ..FarmInfo::default()
+        };
+        let mut clock = ManualClock::new(100, 1_000);
+        let mut user_a = FarmUser::new(
+            Pubkey::new_unique(),
+            Pubkey::new_unique(),
+            Pubkey::new_unique(),
+            FarmPosition::new(Pubkey::new_unique(), clock.unix_timestamp).unwrap(),
+        );
+        user_a
+            .deposit_into_farm(&mut farm_info, 1_000_000_000, &clock)
+            .unwrap();
+
+        // 100 tokens per slot from slot 110
+        clock.advance(10, 10);
+        farm_info.set_emission_rate(100, &clock).unwrap();
+        assert!(farm_info.is_emission_enabled());
+        assert_eq!(farm_info.emission_start_ts, 1_010);
+        assert_eq!(farm_info.last_reward_slot, 110);
+
+        // User a accrues 31 tokens at the apr up to the start, then 1_000 emitted tokens
+        clock.advance(10, 10);
+        user_a.accrue_rewards(&farm_info, &clock, false).unwrap();
+        assert_eq!(user_a.position.rewards_estimated, 31 + 1_000);
+        assert_eq!(user_a.position.apr_epoch, None);
+        assert!(user_a.position.reward_debt.is_some());
+
+        // User b stakes as much from slot 120, the emission is shared from then on
+        let mut user_b = FarmUser::new(
+            Pubkey::new_unique(),
+            Pubkey::new_unique(),
+            Pubkey::new_unique(),
+            FarmPosition::new(Pubkey::new_unique(), clock.unix_timestamp).unwrap(),
+        );
+        user_b
+            .deposit_into_farm(&mut farm_info, 1_000_000_000, &clock)
+            .unwrap();
+        assert_eq!(farm_info.last_reward_slot, 120);
+        assert_eq!(user_b.position.rewards_estimated, 0);
+
+        clock.advance(10, 10);
+        user_a.accrue_rewards(&farm_info, &clock, false).unwrap();
+        user_b.accrue_rewards(&farm_info, &clock, false).unwrap();
+        assert_eq!(user_a.position.rewards_estimated, 1_031 + 500);
+        assert_eq!(user_b.position.rewards_estimated, 500);
+
+        // No rewards are emitted past a zero rate, whatever the apr
+        farm_info.set_emission_rate(0, &clock).unwrap();
+        clock.advance(10, 10);
+        user_b.accrue_rewards(&farm_info, &clock, false).unwrap();
+        assert_eq!(user_b.position.rewards_estimated, 500);
+
+        // Past the claim period the emitted rewards are owed
+        clock.advance(1, MIN_CLAIM_PERIOD);
+        user_b
+            .withdraw_from_farm(&mut farm_info, 1_000_000_000, &clock)
+            .unwrap();
+        assert_eq!(user_b.position.rewards_owed, 500);
+        assert_eq!(user_b.claim().unwrap(), 500);
+    }
+
+    #[test]
+    fn test_farm_user_transfer_position() {
+        let mut farm_info = FarmInfo {
+            apr_numerator: 1,
+            apr_denominator: 10,
+            ..FarmInfo::default()
+        };
+        let farm_pool_key = Pubkey::new_unique();
+        let mut clock = ManualClock::new(100, 0);
+        let new_farm_user = |clock: &ManualClock| {
+            FarmUser::new(
+                Pubkey::new_unique(),
+                farm_pool_key,
+                Pubkey::new_unique(),
+                FarmPosition::new(farm_pool_key, clock.unix_timestamp).unwrap(),
+            )
+        };
+        let mut source = new_farm_user(&clock);
+        let mut destination = new_farm_user(&clock);
+        source
+            .deposit_into_farm(&mut farm_info, SECONDS_OF_YEAR as u64 * 10, &clock)
+            .unwrap();
+        source.position.lock(1, clock.unix_timestamp).unwrap();
+
+        clock.advance(1, 100);
+        source
+            .transfer_position(&mut destination, &farm_info, &clock)
+            .unwrap();
+        // The lock and the rewards accrued up to the transfer move with the stake
+        assert_eq!(
+            destination.position.deposited_amount,
+            SECONDS_OF_YEAR as u64 * 10
+        );
+        assert_eq!(destination.position.rewards_estimated, 125);
+        assert_eq!(destination.position.lock_tier, 1);
+        assert_eq!(destination.position.unlock_ts, 30 * SECONDS_OF_DAY);
+        assert_eq!(source.position.deposited_amount, 0);
+        assert_eq!(source.position.rewards_estimated, 0);
+        assert_eq!(source.position.lock_tier, 0);
+        assert_eq!(source.position.last_update_ts, 100);
+        assert_eq!(farm_info.reserved_amount, SECONDS_OF_YEAR as u64 * 10);
+
+        // A position cannot be moved onto a position with a stake or rewards
+        assert_eq!(
+            source
+                .transfer_position(&mut destination, &farm_info, &clock)
+                .unwrap_err(),
+            SwapError::FarmPositionNotEmpty.into()
+        );
+    }
+
+    #[test]
+    fn test_farm_position_restore_rewards() {
+        let mut position = FarmPosition {
+            rewards_owed: 1_000,
+            ..FarmPosition::default()
+        };
+        assert_eq!(position.claim_rewards().unwrap(), 1_000);
+        position.restore_rewards(300).unwrap();
+        assert_eq!(position.rewards_owed, 300);
+        assert_eq!(position.cumulative_interest, 700);
+        assert_eq!(
+            position.restore_rewards(701).unwrap_err(),
+            SwapError::CalculationFailure.into()
+        );
+    }
+
+    #[test]
+    fn test_farm_withdraw_fee() {
+        let mut farm_info = FarmInfo::default();
+        assert_eq!(farm_info.withdraw_fee(1_000).unwrap(), 0);
+
+        farm_info.fee_numerator = 3;
+        farm_info.fee_denominator = 1_000;
+        assert_eq!(farm_info.withdraw_fee(1_000).unwrap(), 3);
+        assert_eq!(farm_info.withdraw_fee(999).unwrap(), 2);
+        assert_eq!(farm_info.withdraw_fee(0).unwrap(), 0);
+
+        farm_info.fee_numerator = 2_000;
+        assert_eq!(
+            farm_info.withdraw_fee(1_000).unwrap_err(),
+            SwapError::CalculationFailure.into()
+        );
+    }
+
+    #[test]
+    fn test_farm_position_lock() {
+        let mut position = FarmPosition::new(Pubkey::new_unique(), 1_000).unwrap();
+        assert_eq!(
+            position
+                .lock(FARM_LOCK_TIERS.len() as u8, 1_000)
+                .unwrap_err(),
+            SwapError::InvalidFarmLockTier.into()
+        );
+        position.lock(0, 1_000).unwrap();
+        assert_eq!(position.lock_tier, 0);
+        position.check_unlocked(1_000).unwrap();
+
+        position.lock(2, 1_000).unwrap();
+        assert_eq!(position.lock_tier, 2);
+        assert_eq!(position.unlock_ts, 1_000 + 90 * SECONDS_OF_DAY);
+        assert_eq!(position.lock_multiplier().unwrap(), 150);
+
+        // A shorter lock keeps the higher tier and the later unlock
+        position.lock(1, 2_000).unwrap();
+        assert_eq!(position.lock_tier, 2);
+        assert_eq!(position.unlock_ts, 1_000 + 90 * SECONDS_OF_DAY);
+
+        assert_eq!(
+            position
+                .check_unlocked(1_000 + 90 * SECONDS_OF_DAY - 1)
+                .unwrap_err(),
+            SwapError::FarmPositionLocked.into()
+        );
+        position
+            .check_unlocked(1_000 + 90 * SECONDS_OF_DAY)
+            .unwrap();
+    }
+
+    #[test]
+    fn test_farm_user_locked_accrual() {
+        // A token per second at the farm apr
+        let mut farm_info = FarmInfo {
+            apr_numerator: 1,
+            apr_denominator: 10,
+            ..FarmInfo::default()
+        };
+        let deposit_amount = SECONDS_OF_YEAR as u64 * 10;
+        let mut clock = ManualClock::new(100, 0);
+        let mut locked = FarmUser::new(
+            Pubkey::new_unique(),
+            Pubkey::new_unique(),
+            Pubkey::new_unique(),
+            FarmPosition::new(Pubkey::new_unique(), clock.unix_timestamp).unwrap(),
+        );
+        let mut unlocked = locked.clone();
+        locked
+            .deposit_into_farm(&mut farm_info, deposit_amount, &clock)
+            .unwrap();
+        locked.position.lock(3, clock.unix_timestamp).unwrap();
+        unlocked
+            .deposit_into_farm(&mut farm_info, deposit_amount, &clock)
+            .unwrap();
+
+        // Twice the rewards while locked
+        clock.advance(1, 180 * SECONDS_OF_DAY);
+        locked.accrue_rewards(&farm_info, &clock, false).unwrap();
+        unlocked.accrue_rewards(&farm_info, &clock, false).unwrap();
+        assert_eq!(unlocked.position.rewards_owed, 180 * SECONDS_OF_DAY as u64);
+        assert_eq!(
+            locked.position.rewards_owed,
+            2 * 180 * SECONDS_OF_DAY as u64
+        );
+        assert_eq!(locked.position.lock_tier, 0);
+
+        // The same rewards once unlocked
+        clock.advance(1, 30 * SECONDS_OF_DAY);
+        locked.accrue_rewards(&farm_info, &clock, false).unwrap();
+        unlocked.accrue_rewards(&farm_info, &clock, false).unwrap();
+        assert_eq!(unlocked.position.rewards_owed, 210 * SECONDS_OF_DAY as u64);
+        assert_eq!(locked.position.rewards_owed, 390 * SECONDS_OF_DAY as u64);
+        locked
+            .withdraw_from_farm(&mut farm_info, deposit_amount, &clock)
+            .unwrap();
+    }
+
+    #[test]
+    fn test_farm_position_emission_lock_bonus() {
+        let mut position = FarmPosition::new(Pubkey::new_unique(), 1_000).unwrap();
+        position.migrate_to_emission();
+        position.deposited_amount = 1_000;
+        position.lock(1, 1_000).unwrap();
+        position.unlock_ts = 1_100;
+
+        // 125% of the 1_000 tokens emitted while locked
+        position
+            .calc_and_update_emission_rewards(REWARD_PER_SHARE_SCALER, 1_050)
+            .unwrap();
+        assert_eq!(position.rewards_estimated, 1_250);
+        assert_eq!(position.lock_tier, 1);
+
+        // Half of the update is locked, the lock ends at the unlock timestamp
+        position
+            .calc_and_update_emission_rewards(2 * REWARD_PER_SHARE_SCALER, 1_150)
+            .unwrap();
+        assert_eq!(position.rewards_owed, 1_250 + 1_125);
+        assert_eq!(position.lock_tier, 0);
+
+        position
+            .calc_and_update_emission_rewards(3 * REWARD_PER_SHARE_SCALER, 1_200)
+            .unwrap();
+        assert_eq!(position.rewards_estimated, 1_000);
+    }
+
+    #[test]
+    fn test_farm_user_apr_change_from_next_checkpoint() {
+        let mut farm_info = FarmInfo {
+            apr_numerator: 1,
+            apr_denominator: 10,
+            ..FarmInfo::default()
+        };
+        let mut clock = ManualClock::new(100, 1_000);
+        let mut farm_user = FarmUser::new(
+            Pubkey::new_unique(),
+            Pubkey::new_unique(),
+            Pubkey::new_unique(),
+            FarmPosition::new(Pubkey::new_unique(), clock.unix_timestamp).unwrap(),
+        );
+        farm_user.position.checkpoint_apr(&farm_info).unwrap();
+        assert_eq!(farm_user.position.apr_epoch, Some(0));
+        assert_eq!(farm_user.position.apr, 100_000_000_000);
+
+        farm_user
+            .deposit_into_farm(&mut farm_info, 1_000_000_000, &clock)
+            .unwrap();
+
+        // The apr is doubled 10 seconds after the last checkpoint
+        clock.advance(1, 10);
+        farm_info.apr_numerator = 2;
+        farm_info.apr_epoch += 1;
+
+        // The 10 seconds accrue at the old apr, then the position adopts the new one
+        farm_user.accrue_rewards(&farm_info, &clock, false).unwrap();
+        assert_eq!(farm_user.position.rewards_estimated, 31);
+        assert_eq!(farm_user.position.apr_epoch, Some(1));
+        assert_eq!(farm_user.position.apr, 200_000_000_000);
+
+        clock.advance(1, 10);
+        farm_user.accrue_rewards(&farm_info, &clock, false).unwrap();
+        assert_eq!(farm_user.position.rewards_estimated, 95);
+    }
+
+    #[test]
+    fn test_farm_user_legacy_position_accrual() {
+        let farm_info = FarmInfo {
+            apr_numerator: 1,
+            apr_denominator: 10,
+            apr_epoch: 4,
+            ..FarmInfo::default()
+        };
+        let mut farm_user = FarmUser::new(
+            Pubkey::new_unique(),
+            Pubkey::new_unique(),
+            Pubkey::new_unique(),
+            FarmPosition {
+                deposited_amount: 1_000_000_000,
+                ..FarmPosition::new(Pubkey::new_unique(), 1_000).unwrap()
+            },
+        );
+        assert_eq!(farm_user.position.apr_epoch, None);
+
+        // Positions without apr epoch accrue at the current farm apr until checkpointed
+        let clock = ManualClock::new(100, 1_010);
+        farm_user.accrue_rewards(&farm_info, &clock, false).unwrap();
+        assert_eq!(farm_user.position.rewards_estimated, 31);
+        assert_eq!(farm_user.position.apr_epoch, Some(4));
+    }
+
+    #[test]
+    fn test_farm_position_small_rewards() {
+        // 1 token at 10% APR accrues far less than a token between updates
+        let mut farm_position = FarmPosition {
+            deposited_amount: 1_000u64,
+            ..FarmPosition::new(Pubkey::new_unique(), 0i64).unwrap()
+        };
+        let apr = Decimal::from_percent(10);
+        for ts in (1_000..=SECONDS_OF_YEAR).step_by(1_000) {
+            assert!(farm_position.calc_and_update_rewards(apr, ts, true).is_ok());
+        }
+        assert_eq!(
+            farm_position.rewards_owed + farm_position.rewards_estimated,
+            99
+        );
+        assert!(farm_position.rewards_remainder < Decimal::one());
+    }
+
     #[test]
     fn test_farm_user_claim() {
         let mut farm_user = FarmUser::new(
@@ -922,10 +2293,16 @@ mod tests {
             deposited_amount: 100_000u64,
             rewards_owed: 0u64,
             rewards_estimated: 0u64,
+            rewards_remainder: Decimal::zero(),
             cumulative_interest: 0u64,
             last_update_ts: 0i64,
             next_claim_ts: max_ts + 1,
             latest_deposit_slot: 0,
+            apr_epoch: None,
+            apr: 0,
+            reward_debt: None,
+            lock_tier: 0,
+            unlock_ts: 0,
         };
         assert_eq!(farm_position_1.rewards_estimated, 0);
         assert_eq!(farm_position_1.deposited_amount, 100_000u64);
@@ -946,7 +2323,7 @@ mod tests {
         assert!(farm_position_1
             .calc_and_update_rewards(Decimal::from(apr), 2000, true)
             .is_ok());
-        assert_eq!(farm_position_1.rewards_estimated, 31); // 15 + 5 * 101_000 * 1000 / SECONDS_OF_YEAR = 31
+        assert_eq!(farm_position_1.rewards_estimated, 31); // 15.84 + 5 * 101_000 * 1000 / SECONDS_OF_YEAR = 31.85
 
         assert!(farm_position_1.deposit(100_000u64, current_slot).is_ok()); // deposit 100_000u64
         assert_eq!(farm_position_1.deposited_amount, 201_000u64);
@@ -955,7 +2332,7 @@ mod tests {
         assert!(farm_position_1
             .calc_and_update_rewards(Decimal::from(apr), 3000, true)
             .is_ok());
-        assert_eq!(farm_position_1.rewards_estimated, 62); // 31 + 5 * 201_000 * 1000 / SECONDS_OF_YEAR = 62
+        assert_eq!(farm_position_1.rewards_estimated, 63); // 31.85 + 5 * 201_000 * 1000 / SECONDS_OF_YEAR = 63.69
 
         /* Test flash loan */
         let mut farm_position_2 = FarmPosition {
@@ -963,10 +2340,16 @@ mod tests {
             deposited_amount: 0u64,
             rewards_owed: 0u64,
             rewards_estimated: 0u64,
+            rewards_remainder: Decimal::zero(),
             cumulative_interest: 0u64,
             last_update_ts: 0i64,
             next_claim_ts: max_ts + 1,
             latest_deposit_slot: 0,
+            apr_epoch: None,
+            apr: 0,
+            reward_debt: None,
+            lock_tier: 0,
+            unlock_ts: 0,
         };
 
         // 1. calc_and_update_rewards, deposit 1 token at ts 1
@@ -1003,8 +2386,8 @@ mod tests {
         assert!(farm_position_2
             .calc_and_update_rewards(Decimal::from(apr), max_ts + 10, true)
             .is_ok());
-        assert_eq!(farm_position_2.rewards_owed, 141); // 15 + 100_001 * (10000-2000) *5 /SECONDS_OF_YEAR = 141
-        assert_eq!(farm_position_2.claim_rewards(), Ok(141));
+        assert_eq!(farm_position_2.rewards_owed, 142); // 15.84 + 100_001 * (10000-2000) *5 /SECONDS_OF_YEAR = 142.76
+        assert_eq!(farm_position_2.claim_rewards(), Ok(142));
         assert_eq!(
             farm_position_2.next_claim_ts,
             max_ts + 10 + MIN_CLAIM_PERIOD
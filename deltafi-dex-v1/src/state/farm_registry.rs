@@ -0,0 +1,159 @@
+//! Paged registry of the farm users of a farm pool, enumerable without scanning the program accounts
+
+use arrayref::{array_mut_ref, array_ref, array_refs, mut_array_refs};
+use solana_program::{
+    program_error::ProgramError,
+    program_pack::{IsInitialized, Pack, Sealed},
+    pubkey::{Pubkey, PUBKEY_BYTES},
+};
+
+use super::*;
+use crate::error::SwapError;
+
+/// Max number of farm users in a registry page
+pub const FARM_USER_REGISTRY_PAGE_SIZE: usize = 64;
+
+/// Page of the farm user registry, the farm user at index `i` of the farm is at
+/// `farm_users[i % FARM_USER_REGISTRY_PAGE_SIZE]` of page `i / FARM_USER_REGISTRY_PAGE_SIZE`
+#[derive(Clone, Debug, Default, PartialEq)]
+pub struct FarmUserRegistry {
+    /// Initialization status
+    pub is_initialized: bool,
+    /// Farm pool pubkey
+    pub farm_pool_key: Pubkey,
+    /// Index of the page
+    pub page: u32,
+    /// Farm user pubkeys of the page
+    pub farm_users: Vec<Pubkey>,
+}
+
+impl FarmUserRegistry {
+    /// Create an empty registry page
+    pub fn new(farm_pool_key: Pubkey, page: u32) -> Self {
+        Self {
+            is_initialized: true,
+            farm_pool_key,
+            page,
+            farm_users: Vec::with_capacity(FARM_USER_REGISTRY_PAGE_SIZE),
+        }
+    }
+
+    /// Append a farm user to the page
+    pub fn push(&mut self, farm_user_key: Pubkey) -> Result<(), ProgramError> {
+        if self.farm_users.len() >= FARM_USER_REGISTRY_PAGE_SIZE {
+            return Err(SwapError::InvalidInput.into());
+        }
+        self.farm_users.push(farm_user_key);
+        Ok(())
+    }
+}
+
+impl Sealed for FarmUserRegistry {}
+impl IsInitialized for FarmUserRegistry {
+    fn is_initialized(&self) -> bool {
+        self.is_initialized
+    }
+}
+
+const FARM_USER_REGISTRY_SIZE: usize =
+    1 + PUBKEY_BYTES + 4 + 1 + PUBKEY_BYTES * FARM_USER_REGISTRY_PAGE_SIZE;
+
+impl Pack for FarmUserRegistry {
+    const LEN: usize = FARM_USER_REGISTRY_SIZE;
+
+    fn pack_into_slice(&self, dst: &mut [u8]) {
+        let output = array_mut_ref![dst, 0, FARM_USER_REGISTRY_SIZE];
+        #[allow(clippy::ptr_offset_with_cast)]
+        let (is_initialized, farm_pool_key, page, farm_user_len, farm_users) = mut_array_refs![
+            output,
+            1,
+            PUBKEY_BYTES,
+            4,
+            1,
+            PUBKEY_BYTES * FARM_USER_REGISTRY_PAGE_SIZE
+        ];
+        pack_bool(self.is_initialized, is_initialized);
+        farm_pool_key.copy_from_slice(self.farm_pool_key.as_ref());
+        *page = self.page.to_le_bytes();
+        farm_user_len[0] = self.farm_users.len() as u8;
+        for (dst, farm_user) in farm_users
+            .chunks_exact_mut(PUBKEY_BYTES)
+            .zip(self.farm_users.iter())
+        {
+            dst.copy_from_slice(farm_user.as_ref());
+        }
+    }
+
+    fn unpack_from_slice(src: &[u8]) -> Result<Self, ProgramError> {
+        let input = array_ref![src, 0, FARM_USER_REGISTRY_SIZE];
+        #[allow(clippy::ptr_offset_with_cast)]
+        let (is_initialized, farm_pool_key, page, farm_user_len, farm_users) = array_refs![
+            input,
+            1,
+            PUBKEY_BYTES,
+            4,
+            1,
+            PUBKEY_BYTES * FARM_USER_REGISTRY_PAGE_SIZE
+        ];
+
+        let farm_user_len = farm_user_len[0] as usize;
+        if farm_user_len > FARM_USER_REGISTRY_PAGE_SIZE {
+            return Err(ProgramError::InvalidAccountData);
+        }
+
+        Ok(Self {
+            is_initialized: unpack_bool(is_initialized)?,
+            farm_pool_key: Pubkey::new_from_array(*farm_pool_key),
+            page: u32::from_le_bytes(*page),
+            farm_users: farm_users
+                .chunks_exact(PUBKEY_BYTES)
+                .take(farm_user_len)
+                .map(Pubkey::new)
+                .collect(),
+        })
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_farm_user_registry_packing() {
+        let mut farm_user_registry = FarmUserRegistry::new(Pubkey::new_unique(), 3);
+        farm_user_registry.push(Pubkey::new_unique()).unwrap();
+        farm_user_registry.push(Pubkey::new_unique()).unwrap();
+
+        let mut packed = [0u8; FarmUserRegistry::LEN];
+        FarmUserRegistry::pack_into_slice(&farm_user_registry, &mut packed);
+        let unpacked = FarmUserRegistry::unpack(&packed).unwrap();
+        assert_eq!(farm_user_registry, unpacked);
+
+        let packed = [0u8; FarmUserRegistry::LEN];
+        let unpacked = FarmUserRegistry::unpack_unchecked(&packed).unwrap();
+        assert_eq!(unpacked, FarmUserRegistry::default());
+        assert_eq!(
+            FarmUserRegistry::unpack(&packed).unwrap_err(),
+            ProgramError::UninitializedAccount
+        );
+    }
+
+    #[test]
+    fn test_farm_user_registry_full_page() {
+        let mut farm_user_registry = FarmUserRegistry::new(Pubkey::new_unique(), 0);
+        for _ in 0..FARM_USER_REGISTRY_PAGE_SIZE {
+            farm_user_registry.push(Pubkey::new_unique()).unwrap();
+        }
+        assert_eq!(
+            farm_user_registry.push(Pubkey::new_unique()),
+            Err(SwapError::InvalidInput.into())
+        );
+
+        let mut packed = [0u8; FarmUserRegistry::LEN];
+        FarmUserRegistry::pack_into_slice(&farm_user_registry, &mut packed);
+        assert_eq!(
+            FarmUserRegistry::unpack(&packed).unwrap().farm_users,
+            farm_user_registry.farm_users
+        );
+    }
+}
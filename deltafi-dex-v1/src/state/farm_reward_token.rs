@@ -0,0 +1,506 @@
+//! Partner reward tokens of the farms, emitted per slot to the stakers on top of DELTAFI
+
+use arrayref::{array_mut_ref, array_ref, array_refs, mut_array_refs};
+use solana_program::{
+    clock::Slot,
+    entrypoint::ProgramResult,
+    program_error::ProgramError,
+    program_pack::{IsInitialized, Pack, Sealed},
+    pubkey::{Pubkey, PUBKEY_BYTES},
+};
+use std::convert::TryFrom;
+
+use crate::error::SwapError;
+
+use super::*;
+
+/// Max number of partner reward tokens of a farm
+pub const MAX_FARM_REWARD_TOKENS: usize = 2;
+
+/// Scaler of the rewards accumulated per staked LP token
+pub const REWARD_PER_SHARE_SCALER: u128 = 1_000_000_000_000;
+
+/// Reward token of a farm with its own emission rate
+#[derive(Clone, Copy, Debug, Default, PartialEq)]
+pub struct FarmRewardToken {
+    /// Reward token mint
+    pub mint: Pubkey,
+    /// Token account of the mint owned by the farm authority, paying the rewards
+    pub vault: Pubkey,
+    /// Reward tokens emitted per slot to the farm stakers
+    pub reward_per_slot: u64,
+    /// Funded reward tokens not emitted yet, the emission stops once they run out
+    pub remaining_amount: u64,
+    /// Rewards accumulated per staked LP token, scaled by `REWARD_PER_SHARE_SCALER`
+    pub acc_reward_per_share: u128,
+    /// Slot the accumulator was last updated at
+    pub last_update_slot: Slot,
+}
+
+const FARM_REWARD_TOKEN_SIZE: usize = PUBKEY_BYTES * 2 + 8 + 8 + 16 + 8;
+
+impl FarmRewardToken {
+    /// Emit the rewards of the slots since the last update to the total staked amount
+    pub fn accrue(&mut self, total_staked: u64, slot: Slot) -> ProgramResult {
+        if slot <= self.last_update_slot {
+            return Ok(());
+        }
+        // Nothing is emitted while no LP token is staked
+        if total_staked > 0 {
+            let emitted = (slot - self.last_update_slot)
+                .saturating_mul(self.reward_per_slot)
+                .min(self.remaining_amount);
+            let reward_per_share = (emitted as u128)
+                .checked_mul(REWARD_PER_SHARE_SCALER)
+                .ok_or(SwapError::CalculationFailure)?
+                / total_staked as u128;
+            self.acc_reward_per_share = self
+                .acc_reward_per_share
+                .checked_add(reward_per_share)
+                .ok_or(SwapError::CalculationFailure)?;
+            self.remaining_amount -= emitted;
+        }
+        self.last_update_slot = slot;
+        Ok(())
+    }
+
+    /// Rewards accumulated by a staked amount since the token was added
+    pub fn accumulated_rewards(&self, staked_amount: u64) -> Result<u128, ProgramError> {
+        (staked_amount as u128)
+            .checked_mul(self.acc_reward_per_share)
+            .map(|rewards| rewards / REWARD_PER_SHARE_SCALER)
+            .ok_or_else(|| SwapError::CalculationFailure.into())
+    }
+
+    fn unpack_from_array(src: &[u8; FARM_REWARD_TOKEN_SIZE]) -> Self {
+        #[allow(clippy::ptr_offset_with_cast)]
+        let (
+            mint,
+            vault,
+            reward_per_slot,
+            remaining_amount,
+            acc_reward_per_share,
+            last_update_slot,
+        ) = array_refs![src, PUBKEY_BYTES, PUBKEY_BYTES, 8, 8, 16, 8];
+        Self {
+            mint: Pubkey::new_from_array(*mint),
+            vault: Pubkey::new_from_array(*vault),
+            reward_per_slot: u64::from_le_bytes(*reward_per_slot),
+            remaining_amount: u64::from_le_bytes(*remaining_amount),
+            acc_reward_per_share: u128::from_le_bytes(*acc_reward_per_share),
+            last_update_slot: u64::from_le_bytes(*last_update_slot),
+        }
+    }
+
+    fn pack_into_array(&self, dst: &mut [u8; FARM_REWARD_TOKEN_SIZE]) {
+        #[allow(clippy::ptr_offset_with_cast)]
+        let (
+            mint,
+            vault,
+            reward_per_slot,
+            remaining_amount,
+            acc_reward_per_share,
+            last_update_slot,
+        ) = mut_array_refs![dst, PUBKEY_BYTES, PUBKEY_BYTES, 8, 8, 16, 8];
+        mint.copy_from_slice(self.mint.as_ref());
+        vault.copy_from_slice(self.vault.as_ref());
+        *reward_per_slot = self.reward_per_slot.to_le_bytes();
+        *remaining_amount = self.remaining_amount.to_le_bytes();
+        *acc_reward_per_share = self.acc_reward_per_share.to_le_bytes();
+        *last_update_slot = self.last_update_slot.to_le_bytes();
+    }
+}
+
+/// Partner reward tokens of a farm, at the program address of the farm pool
+#[derive(Clone, Debug, Default, PartialEq)]
+pub struct FarmRewardTokens {
+    /// Initialization status
+    pub is_initialized: bool,
+    /// Bump seed of the farm reward tokens address
+    pub bump_seed: u8,
+    /// Farm pool pubkey
+    pub farm_pool_key: Pubkey,
+    /// Number of reward tokens in use
+    pub reward_tokens_len: u8,
+    /// Reward tokens, the unused ones last
+    pub reward_tokens: [FarmRewardToken; MAX_FARM_REWARD_TOKENS],
+}
+
+impl FarmRewardTokens {
+    /// Reward tokens in use
+    pub fn used(&self) -> &[FarmRewardToken] {
+        &self.reward_tokens[..self.reward_tokens_len as usize]
+    }
+
+    /// Emit the rewards of every reward token up to the slot
+    pub fn accrue(&mut self, total_staked: u64, slot: Slot) -> ProgramResult {
+        let reward_tokens_len = self.reward_tokens_len as usize;
+        for reward_token in self.reward_tokens[..reward_tokens_len].iter_mut() {
+            reward_token.accrue(total_staked, slot)?;
+        }
+        Ok(())
+    }
+
+    /// Add a reward token emitting from the slot
+    pub fn add(
+        &mut self,
+        mint: Pubkey,
+        vault: Pubkey,
+        reward_per_slot: u64,
+        slot: Slot,
+    ) -> ProgramResult {
+        let index = self.reward_tokens_len as usize;
+        if index >= MAX_FARM_REWARD_TOKENS
+            || self
+                .used()
+                .iter()
+                .any(|reward_token| reward_token.mint == mint)
+        {
+            return Err(SwapError::InvalidFarmRewardToken.into());
+        }
+        self.reward_tokens[index] = FarmRewardToken {
+            mint,
+            vault,
+            reward_per_slot,
+            last_update_slot: slot,
+            ..FarmRewardToken::default()
+        };
+        self.reward_tokens_len += 1;
+        Ok(())
+    }
+
+    /// Reward token in use at the index
+    pub fn get_mut(&mut self, index: u8) -> Result<&mut FarmRewardToken, ProgramError> {
+        if index >= self.reward_tokens_len {
+            return Err(SwapError::InvalidFarmRewardToken.into());
+        }
+        Ok(&mut self.reward_tokens[index as usize])
+    }
+}
+
+impl Sealed for FarmRewardTokens {}
+impl IsInitialized for FarmRewardTokens {
+    fn is_initialized(&self) -> bool {
+        self.is_initialized
+    }
+}
+
+const FARM_REWARD_TOKENS_SIZE: usize =
+    1 + 1 + PUBKEY_BYTES + 1 + FARM_REWARD_TOKEN_SIZE * MAX_FARM_REWARD_TOKENS;
+
+impl Pack for FarmRewardTokens {
+    const LEN: usize = FARM_REWARD_TOKENS_SIZE;
+
+    fn pack_into_slice(&self, dst: &mut [u8]) {
+        let output = array_mut_ref![dst, 0, FARM_REWARD_TOKENS_SIZE];
+        #[allow(clippy::ptr_offset_with_cast)]
+        let (is_initialized, bump_seed, farm_pool_key, reward_tokens_len, reward_tokens) = mut_array_refs![
+            output,
+            1,
+            1,
+            PUBKEY_BYTES,
+            1,
+            FARM_REWARD_TOKEN_SIZE * MAX_FARM_REWARD_TOKENS
+        ];
+        pack_bool(self.is_initialized, is_initialized);
+        bump_seed[0] = self.bump_seed;
+        farm_pool_key.copy_from_slice(self.farm_pool_key.as_ref());
+        reward_tokens_len[0] = self.reward_tokens_len;
+        for (reward_token, dst) in self
+            .reward_tokens
+            .iter()
+            .zip(reward_tokens.chunks_exact_mut(FARM_REWARD_TOKEN_SIZE))
+        {
+            reward_token.pack_into_array(array_mut_ref![dst, 0, FARM_REWARD_TOKEN_SIZE]);
+        }
+    }
+
+    fn unpack_from_slice(src: &[u8]) -> Result<Self, ProgramError> {
+        let input = array_ref![src, 0, FARM_REWARD_TOKENS_SIZE];
+        #[allow(clippy::ptr_offset_with_cast)]
+        let (is_initialized, bump_seed, farm_pool_key, reward_tokens_len, reward_tokens_src) = array_refs![
+            input,
+            1,
+            1,
+            PUBKEY_BYTES,
+            1,
+            FARM_REWARD_TOKEN_SIZE * MAX_FARM_REWARD_TOKENS
+        ];
+        let reward_tokens_len = reward_tokens_len[0];
+        if reward_tokens_len as usize > MAX_FARM_REWARD_TOKENS {
+            return Err(ProgramError::InvalidAccountData);
+        }
+        let mut reward_tokens = [FarmRewardToken::default(); MAX_FARM_REWARD_TOKENS];
+        for (reward_token, src) in reward_tokens
+            .iter_mut()
+            .zip(reward_tokens_src.chunks_exact(FARM_REWARD_TOKEN_SIZE))
+        {
+            *reward_token =
+                FarmRewardToken::unpack_from_array(array_ref![src, 0, FARM_REWARD_TOKEN_SIZE]);
+        }
+        Ok(Self {
+            is_initialized: unpack_bool(is_initialized)?,
+            bump_seed: bump_seed[0],
+            farm_pool_key: Pubkey::new_from_array(*farm_pool_key),
+            reward_tokens_len,
+            reward_tokens,
+        })
+    }
+}
+
+/// Partner reward tokens owed to a farm user, at the program address of the farm user
+#[derive(Clone, Debug, Default, PartialEq)]
+pub struct FarmUserRewardTokens {
+    /// Initialization status
+    pub is_initialized: bool,
+    /// Farm user pubkey
+    pub farm_user_key: Pubkey,
+    /// Rewards accumulated by the staked amount already settled, per reward token
+    pub reward_debts: [u128; MAX_FARM_REWARD_TOKENS],
+    /// Rewards settled and not claimed yet, per reward token
+    pub rewards_owed: [u64; MAX_FARM_REWARD_TOKENS],
+}
+
+impl FarmUserRewardTokens {
+    /// Move the rewards accumulated by the staked amount since the last settlement to the owed
+    /// rewards, the farm reward tokens accrued up to the current slot
+    pub fn settle(
+        &mut self,
+        farm_reward_tokens: &FarmRewardTokens,
+        staked_amount: u64,
+    ) -> ProgramResult {
+        for (i, reward_token) in farm_reward_tokens.used().iter().enumerate() {
+            let accumulated = reward_token.accumulated_rewards(staked_amount)?;
+            let pending = accumulated
+                .checked_sub(self.reward_debts[i])
+                .ok_or(SwapError::CalculationFailure)?;
+            self.rewards_owed[i] = (self.rewards_owed[i] as u128)
+                .checked_add(pending)
+                .and_then(|owed| u64::try_from(owed).ok())
+                .ok_or(SwapError::CalculationFailure)?;
+            self.reward_debts[i] = accumulated;
+        }
+        Ok(())
+    }
+
+    /// Reset the reward debts to a new staked amount, once the previous one is settled
+    pub fn reset_debts(
+        &mut self,
+        farm_reward_tokens: &FarmRewardTokens,
+        staked_amount: u64,
+    ) -> ProgramResult {
+        for (i, reward_token) in farm_reward_tokens.used().iter().enumerate() {
+            self.reward_debts[i] = reward_token.accumulated_rewards(staked_amount)?;
+        }
+        Ok(())
+    }
+
+    /// Claim the owed rewards of every reward token
+    pub fn claim(&mut self) -> [u64; MAX_FARM_REWARD_TOKENS] {
+        std::mem::take(&mut self.rewards_owed)
+    }
+}
+
+impl Sealed for FarmUserRewardTokens {}
+impl IsInitialized for FarmUserRewardTokens {
+    fn is_initialized(&self) -> bool {
+        self.is_initialized
+    }
+}
+
+const FARM_USER_REWARD_TOKENS_SIZE: usize =
+    1 + PUBKEY_BYTES + 16 * MAX_FARM_REWARD_TOKENS + 8 * MAX_FARM_REWARD_TOKENS;
+
+impl Pack for FarmUserRewardTokens {
+    const LEN: usize = FARM_USER_REWARD_TOKENS_SIZE;
+
+    fn pack_into_slice(&self, dst: &mut [u8]) {
+        let output = array_mut_ref![dst, 0, FARM_USER_REWARD_TOKENS_SIZE];
+        #[allow(clippy::ptr_offset_with_cast)]
+        let (is_initialized, farm_user_key, reward_debts, rewards_owed) = mut_array_refs![
+            output,
+            1,
+            PUBKEY_BYTES,
+            16 * MAX_FARM_REWARD_TOKENS,
+            8 * MAX_FARM_REWARD_TOKENS
+        ];
+        pack_bool(self.is_initialized, is_initialized);
+        farm_user_key.copy_from_slice(self.farm_user_key.as_ref());
+        for (reward_debt, dst) in self
+            .reward_debts
+            .iter()
+            .zip(reward_debts.chunks_exact_mut(16))
+        {
+            dst.copy_from_slice(&reward_debt.to_le_bytes());
+        }
+        for (reward_owed, dst) in self
+            .rewards_owed
+            .iter()
+            .zip(rewards_owed.chunks_exact_mut(8))
+        {
+            dst.copy_from_slice(&reward_owed.to_le_bytes());
+        }
+    }
+
+    fn unpack_from_slice(src: &[u8]) -> Result<Self, ProgramError> {
+        let input = array_ref![src, 0, FARM_USER_REWARD_TOKENS_SIZE];
+        #[allow(clippy::ptr_offset_with_cast)]
+        let (is_initialized, farm_user_key, reward_debts_src, rewards_owed_src) = array_refs![
+            input,
+            1,
+            PUBKEY_BYTES,
+            16 * MAX_FARM_REWARD_TOKENS,
+            8 * MAX_FARM_REWARD_TOKENS
+        ];
+        let mut reward_debts = [0u128; MAX_FARM_REWARD_TOKENS];
+        for (reward_debt, src) in reward_debts
+            .iter_mut()
+            .zip(reward_debts_src.chunks_exact(16))
+        {
+            *reward_debt = u128::from_le_bytes(*array_ref![src, 0, 16]);
+        }
+        let mut rewards_owed = [0u64; MAX_FARM_REWARD_TOKENS];
+        for (reward_owed, src) in rewards_owed
+            .iter_mut()
+            .zip(rewards_owed_src.chunks_exact(8))
+        {
+            *reward_owed = u64::from_le_bytes(*array_ref![src, 0, 8]);
+        }
+        Ok(Self {
+            is_initialized: unpack_bool(is_initialized)?,
+            farm_user_key: Pubkey::new_from_array(*farm_user_key),
+            reward_debts,
+            rewards_owed,
+        })
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn test_farm_reward_tokens() -> FarmRewardTokens {
+        let mut farm_reward_tokens = FarmRewardTokens {
+            is_initialized: true,
+            farm_pool_key: Pubkey::new_unique(),
+            ..FarmRewardTokens::default()
+        };
+        farm_reward_tokens
+            .add(Pubkey::new_unique(), Pubkey::new_unique(), 100, 10)
+            .unwrap();
+        farm_reward_tokens
+            .add(Pubkey::new_unique(), Pubkey::new_unique(), 10, 10)
+            .unwrap();
+        farm_reward_tokens.reward_tokens[0].remaining_amount = 1_000_000;
+        farm_reward_tokens.reward_tokens[1].remaining_amount = 50;
+        farm_reward_tokens
+    }
+
+    #[test]
+    fn test_add_farm_reward_token() {
+        let mut farm_reward_tokens = test_farm_reward_tokens();
+        assert_eq!(farm_reward_tokens.used().len(), 2);
+        assert_eq!(
+            farm_reward_tokens.add(Pubkey::new_unique(), Pubkey::new_unique(), 1, 10),
+            Err(SwapError::InvalidFarmRewardToken.into())
+        );
+
+        farm_reward_tokens.reward_tokens_len = 1;
+        let mint = farm_reward_tokens.reward_tokens[0].mint;
+        assert_eq!(
+            farm_reward_tokens.add(mint, Pubkey::new_unique(), 1, 10),
+            Err(SwapError::InvalidFarmRewardToken.into())
+        );
+        assert_eq!(
+            farm_reward_tokens.get_mut(1),
+            Err(SwapError::InvalidFarmRewardToken.into())
+        );
+    }
+
+    #[test]
+    fn test_farm_reward_token_accrual() {
+        let mut farm_reward_tokens = test_farm_reward_tokens();
+
+        // Nothing is emitted without stakers
+        farm_reward_tokens.accrue(0, 20).unwrap();
+        assert_eq!(farm_reward_tokens.reward_tokens[0].acc_reward_per_share, 0);
+        assert_eq!(
+            farm_reward_tokens.reward_tokens[0].remaining_amount,
+            1_000_000
+        );
+        assert_eq!(farm_reward_tokens.reward_tokens[0].last_update_slot, 20);
+
+        // 10 slots emit 1_000 of the first token and the 50 left of the second one
+        farm_reward_tokens.accrue(500, 30).unwrap();
+        assert_eq!(
+            farm_reward_tokens.reward_tokens[0].acc_reward_per_share,
+            2 * REWARD_PER_SHARE_SCALER
+        );
+        assert_eq!(
+            farm_reward_tokens.reward_tokens[0].remaining_amount,
+            999_000
+        );
+        assert_eq!(
+            farm_reward_tokens.reward_tokens[1].acc_reward_per_share,
+            REWARD_PER_SHARE_SCALER / 10
+        );
+        assert_eq!(farm_reward_tokens.reward_tokens[1].remaining_amount, 0);
+
+        // A past slot does not accrue
+        farm_reward_tokens.accrue(500, 25).unwrap();
+        assert_eq!(farm_reward_tokens.reward_tokens[0].last_update_slot, 30);
+    }
+
+    #[test]
+    fn test_farm_user_reward_tokens_settlement() {
+        let mut farm_reward_tokens = test_farm_reward_tokens();
+        let mut user_a = FarmUserRewardTokens::default();
+        let mut user_b = FarmUserRewardTokens::default();
+
+        // User a stakes 100 alone for 10 slots
+        user_a.reset_debts(&farm_reward_tokens, 100).unwrap();
+        farm_reward_tokens.accrue(100, 20).unwrap();
+
+        // User b stakes 300 next to user a for 10 more slots
+        user_b.reset_debts(&farm_reward_tokens, 300).unwrap();
+        farm_reward_tokens.accrue(400, 30).unwrap();
+
+        user_a.settle(&farm_reward_tokens, 100).unwrap();
+        user_b.settle(&farm_reward_tokens, 300).unwrap();
+        assert_eq!(user_a.rewards_owed, [1_000 + 250, 50]);
+        assert_eq!(user_b.rewards_owed, [750, 0]);
+
+        assert_eq!(user_a.claim(), [1_250, 50]);
+        assert_eq!(user_a.rewards_owed, [0, 0]);
+
+        // Settling again without accrual owes nothing more
+        user_a.settle(&farm_reward_tokens, 100).unwrap();
+        assert_eq!(user_a.rewards_owed, [0, 0]);
+    }
+
+    #[test]
+    fn test_farm_reward_tokens_packing() {
+        let mut farm_reward_tokens = test_farm_reward_tokens();
+        farm_reward_tokens.bump_seed = 253;
+        farm_reward_tokens.accrue(400, 30).unwrap();
+        let mut packed = [0u8; FarmRewardTokens::LEN];
+        FarmRewardTokens::pack_into_slice(&farm_reward_tokens, &mut packed);
+        assert_eq!(
+            FarmRewardTokens::unpack(&packed).unwrap(),
+            farm_reward_tokens
+        );
+
+        let farm_user_reward_tokens = FarmUserRewardTokens {
+            is_initialized: true,
+            farm_user_key: Pubkey::new_unique(),
+            reward_debts: [u128::MAX, 1],
+            rewards_owed: [2, u64::MAX],
+        };
+        let mut packed = [0u8; FarmUserRewardTokens::LEN];
+        FarmUserRewardTokens::pack_into_slice(&farm_user_reward_tokens, &mut packed);
+        assert_eq!(
+            FarmUserRewardTokens::unpack(&packed).unwrap(),
+            farm_user_reward_tokens
+        );
+    }
+}
@@ -0,0 +1,350 @@
+//! Trade fee discounts of the users staking DELTAFI
+
+use arrayref::{array_mut_ref, array_ref, array_refs, mut_array_refs};
+use solana_program::{
+    program_error::ProgramError,
+    program_pack::{IsInitialized, Pack, Sealed},
+    pubkey::{Pubkey, PUBKEY_BYTES},
+};
+
+use crate::error::SwapError;
+
+use super::*;
+
+/// Max number of fee discount tiers of a config
+pub const MAX_FEE_DISCOUNT_TIERS: usize = 4;
+
+/// Fee discount granted from a staked DELTAFI amount
+#[derive(Clone, Copy, Debug, Default, PartialEq)]
+pub struct FeeDiscountTier {
+    /// Staked DELTAFI amount to reach the tier, 0 for an unused tier
+    pub min_staked_amount: u64,
+    /// Discount of the trade fee in basis points
+    pub discount_bps: u16,
+}
+
+const FEE_DISCOUNT_TIER_SIZE: usize = 10;
+
+impl FeeDiscountTier {
+    /// Unpack a tier
+    pub fn unpack_from_array(src: &[u8; FEE_DISCOUNT_TIER_SIZE]) -> Self {
+        #[allow(clippy::ptr_offset_with_cast)]
+        let (min_staked_amount, discount_bps) = array_refs![src, 8, 2];
+        Self {
+            min_staked_amount: u64::from_le_bytes(*min_staked_amount),
+            discount_bps: u16::from_le_bytes(*discount_bps),
+        }
+    }
+
+    /// Pack a tier
+    pub fn pack_into_array(&self, dst: &mut [u8; FEE_DISCOUNT_TIER_SIZE]) {
+        #[allow(clippy::ptr_offset_with_cast)]
+        let (min_staked_amount, discount_bps) = mut_array_refs![dst, 8, 2];
+        *min_staked_amount = self.min_staked_amount.to_le_bytes();
+        *discount_bps = self.discount_bps.to_le_bytes();
+    }
+}
+
+/// Fee discount tiers of a config, set by the fee manager
+#[derive(Clone, Debug, Default, PartialEq)]
+pub struct FeeDiscountTiers {
+    /// Initialization status
+    pub is_initialized: bool,
+    /// Config key
+    pub config_key: Pubkey,
+    /// Tiers by increasing staked amount, the unused tiers last
+    pub tiers: [FeeDiscountTier; MAX_FEE_DISCOUNT_TIERS],
+}
+
+impl FeeDiscountTiers {
+    /// Check the used tiers are ordered by strictly increasing staked amount and
+    /// non-decreasing discount, with the unused tiers last
+    pub fn validate_tiers(tiers: &[FeeDiscountTier; MAX_FEE_DISCOUNT_TIERS]) -> bool {
+        let used = tiers
+            .iter()
+            .take_while(|tier| tier.min_staked_amount > 0)
+            .count();
+        tiers[..used].iter().all(|tier| tier.discount_bps <= 10_000)
+            && tiers[..used].windows(2).all(|pair| {
+                pair[0].min_staked_amount < pair[1].min_staked_amount
+                    && pair[0].discount_bps <= pair[1].discount_bps
+            })
+            && tiers[used..]
+                .iter()
+                .all(|tier| *tier == FeeDiscountTier::default())
+    }
+
+    /// Discount in basis points of the highest tier reached by the staked amount
+    pub fn discount_bps(&self, staked_amount: u64) -> u16 {
+        self.tiers
+            .iter()
+            .rev()
+            .find(|tier| tier.min_staked_amount > 0 && tier.min_staked_amount <= staked_amount)
+            .map_or(0, |tier| tier.discount_bps)
+    }
+
+    /// Apply the discount of the staked amount to the trade fee
+    pub fn discounted_trade_fee(
+        &self,
+        trade_fee: u64,
+        staked_amount: u64,
+    ) -> Result<u64, ProgramError> {
+        let discount = (trade_fee as u128)
+            .checked_mul(self.discount_bps(staked_amount) as u128)
+            .ok_or(SwapError::CalculationFailure)?
+            / 10_000;
+        trade_fee
+            .checked_sub(discount as u64)
+            .ok_or_else(|| SwapError::CalculationFailure.into())
+    }
+}
+
+impl Sealed for FeeDiscountTiers {}
+impl IsInitialized for FeeDiscountTiers {
+    fn is_initialized(&self) -> bool {
+        self.is_initialized
+    }
+}
+
+const FEE_DISCOUNT_TIERS_SIZE: usize =
+    1 + PUBKEY_BYTES + FEE_DISCOUNT_TIER_SIZE * MAX_FEE_DISCOUNT_TIERS;
+
+impl Pack for FeeDiscountTiers {
+    const LEN: usize = FEE_DISCOUNT_TIERS_SIZE;
+
+    fn pack_into_slice(&self, dst: &mut [u8]) {
+        let output = array_mut_ref![dst, 0, FEE_DISCOUNT_TIERS_SIZE];
+        #[allow(clippy::ptr_offset_with_cast)]
+        let (is_initialized, config_key, tiers) = mut_array_refs![
+            output,
+            1,
+            PUBKEY_BYTES,
+            FEE_DISCOUNT_TIER_SIZE * MAX_FEE_DISCOUNT_TIERS
+        ];
+        pack_bool(self.is_initialized, is_initialized);
+        config_key.copy_from_slice(self.config_key.as_ref());
+        for (tier, dst) in self
+            .tiers
+            .iter()
+            .zip(tiers.chunks_exact_mut(FEE_DISCOUNT_TIER_SIZE))
+        {
+            tier.pack_into_array(array_mut_ref![dst, 0, FEE_DISCOUNT_TIER_SIZE]);
+        }
+    }
+
+    fn unpack_from_slice(src: &[u8]) -> Result<Self, ProgramError> {
+        let input = array_ref![src, 0, FEE_DISCOUNT_TIERS_SIZE];
+        #[allow(clippy::ptr_offset_with_cast)]
+        let (is_initialized, config_key, tiers_src) = array_refs![
+            input,
+            1,
+            PUBKEY_BYTES,
+            FEE_DISCOUNT_TIER_SIZE * MAX_FEE_DISCOUNT_TIERS
+        ];
+        let mut tiers = [FeeDiscountTier::default(); MAX_FEE_DISCOUNT_TIERS];
+        for (tier, src) in tiers
+            .iter_mut()
+            .zip(tiers_src.chunks_exact(FEE_DISCOUNT_TIER_SIZE))
+        {
+            *tier = FeeDiscountTier::unpack_from_array(array_ref![src, 0, FEE_DISCOUNT_TIER_SIZE]);
+        }
+        Ok(Self {
+            is_initialized: unpack_bool(is_initialized)?,
+            config_key: Pubkey::new_from_array(*config_key),
+            tiers,
+        })
+    }
+}
+
+/// Fee discount account of a user, its token account holding the staked DELTAFI is owned by
+/// the fee discount account address
+#[derive(Clone, Debug, Default, PartialEq)]
+pub struct FeeDiscountAccount {
+    /// Initialization status
+    pub is_initialized: bool,
+    /// Bump seed of the fee discount account address
+    pub bump_seed: u8,
+    /// Config key
+    pub config_key: Pubkey,
+    /// Owner of the staked DELTAFI, the swap user granted the discount
+    pub owner: Pubkey,
+    /// DELTAFI token account owned by the fee discount account address
+    pub token_account: Pubkey,
+    /// Staked DELTAFI amount
+    pub staked_amount: u64,
+    /// Slot of the last stake, the discount of a stake applies from the next slot
+    pub last_stake_slot: u64,
+}
+
+impl FeeDiscountAccount {
+    /// Staked amount counted for the discount of a swap at the slot
+    pub fn discount_staked_amount(&self, slot: u64) -> u64 {
+        if self.last_stake_slot < slot {
+            self.staked_amount
+        } else {
+            0
+        }
+    }
+}
+
+impl Sealed for FeeDiscountAccount {}
+impl IsInitialized for FeeDiscountAccount {
+    fn is_initialized(&self) -> bool {
+        self.is_initialized
+    }
+}
+
+const FEE_DISCOUNT_ACCOUNT_SIZE: usize = 1 + 1 + PUBKEY_BYTES * 3 + 8 + 8;
+
+impl Pack for FeeDiscountAccount {
+    const LEN: usize = FEE_DISCOUNT_ACCOUNT_SIZE;
+
+    fn pack_into_slice(&self, dst: &mut [u8]) {
+        let output = array_mut_ref![dst, 0, FEE_DISCOUNT_ACCOUNT_SIZE];
+        #[allow(clippy::ptr_offset_with_cast)]
+        let (
+            is_initialized,
+            bump_seed,
+            config_key,
+            owner,
+            token_account,
+            staked_amount,
+            last_stake_slot,
+        ) = mut_array_refs![output, 1, 1, PUBKEY_BYTES, PUBKEY_BYTES, PUBKEY_BYTES, 8, 8];
+        pack_bool(self.is_initialized, is_initialized);
+        bump_seed[0] = self.bump_seed;
+        config_key.copy_from_slice(self.config_key.as_ref());
+        owner.copy_from_slice(self.owner.as_ref());
+        token_account.copy_from_slice(self.token_account.as_ref());
+        *staked_amount = self.staked_amount.to_le_bytes();
+        *last_stake_slot = self.last_stake_slot.to_le_bytes();
+    }
+
+    fn unpack_from_slice(src: &[u8]) -> Result<Self, ProgramError> {
+        let input = array_ref![src, 0, FEE_DISCOUNT_ACCOUNT_SIZE];
+        #[allow(clippy::ptr_offset_with_cast)]
+        let (
+            is_initialized,
+            bump_seed,
+            config_key,
+            owner,
+            token_account,
+            staked_amount,
+            last_stake_slot,
+        ) = array_refs![input, 1, 1, PUBKEY_BYTES, PUBKEY_BYTES, PUBKEY_BYTES, 8, 8];
+        Ok(Self {
+            is_initialized: unpack_bool(is_initialized)?,
+            bump_seed: bump_seed[0],
+            config_key: Pubkey::new_from_array(*config_key),
+            owner: Pubkey::new_from_array(*owner),
+            token_account: Pubkey::new_from_array(*token_account),
+            staked_amount: u64::from_le_bytes(*staked_amount),
+            last_stake_slot: u64::from_le_bytes(*last_stake_slot),
+        })
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn test_tiers() -> [FeeDiscountTier; MAX_FEE_DISCOUNT_TIERS] {
+        [
+            FeeDiscountTier {
+                min_staked_amount: 1_000,
+                discount_bps: 1_000,
+            },
+            FeeDiscountTier {
+                min_staked_amount: 10_000,
+                discount_bps: 2_500,
+            },
+            FeeDiscountTier::default(),
+            FeeDiscountTier::default(),
+        ]
+    }
+
+    #[test]
+    fn test_fee_discount_packing() {
+        let fee_discount_tiers = FeeDiscountTiers {
+            is_initialized: true,
+            config_key: Pubkey::new_unique(),
+            tiers: test_tiers(),
+        };
+        let mut packed = [0u8; FeeDiscountTiers::LEN];
+        FeeDiscountTiers::pack_into_slice(&fee_discount_tiers, &mut packed);
+        assert_eq!(FeeDiscountTiers::unpack(&packed), Ok(fee_discount_tiers));
+
+        let fee_discount_account = FeeDiscountAccount {
+            is_initialized: true,
+            bump_seed: 254,
+            config_key: Pubkey::new_unique(),
+            owner: Pubkey::new_unique(),
+            token_account: Pubkey::new_unique(),
+            staked_amount: 5_000,
+            last_stake_slot: 100,
+        };
+        let mut packed = [0u8; FeeDiscountAccount::LEN];
+        FeeDiscountAccount::pack_into_slice(&fee_discount_account, &mut packed);
+        assert_eq!(
+            FeeDiscountAccount::unpack(&packed),
+            Ok(fee_discount_account)
+        );
+
+        let packed = [0u8; FeeDiscountAccount::LEN];
+        assert_eq!(
+            FeeDiscountAccount::unpack(&packed).unwrap_err(),
+            ProgramError::UninitializedAccount
+        );
+    }
+
+    #[test]
+    fn test_fee_discount_tiers() {
+        let mut tiers = test_tiers();
+        assert!(FeeDiscountTiers::validate_tiers(&tiers));
+        assert!(FeeDiscountTiers::validate_tiers(
+            &[FeeDiscountTier::default(); MAX_FEE_DISCOUNT_TIERS]
+        ));
+
+        let fee_discount_tiers = FeeDiscountTiers {
+            is_initialized: true,
+            config_key: Pubkey::new_unique(),
+            tiers,
+        };
+        assert_eq!(fee_discount_tiers.discount_bps(0), 0);
+        assert_eq!(fee_discount_tiers.discount_bps(999), 0);
+        assert_eq!(fee_discount_tiers.discount_bps(1_000), 1_000);
+        assert_eq!(fee_discount_tiers.discount_bps(20_000), 2_500);
+        assert_eq!(fee_discount_tiers.discounted_trade_fee(1_000, 0), Ok(1_000));
+        assert_eq!(
+            fee_discount_tiers.discounted_trade_fee(1_000, 10_000),
+            Ok(750)
+        );
+
+        // unordered thresholds
+        tiers[1].min_staked_amount = 1_000;
+        assert!(!FeeDiscountTiers::validate_tiers(&tiers));
+        // lower discount for a higher tier
+        tiers[1].min_staked_amount = 10_000;
+        tiers[1].discount_bps = 500;
+        assert!(!FeeDiscountTiers::validate_tiers(&tiers));
+        // discount above 100%
+        tiers[1].discount_bps = 10_001;
+        assert!(!FeeDiscountTiers::validate_tiers(&tiers));
+        // used tier after an unused one
+        tiers[1].discount_bps = 2_500;
+        tiers[3].min_staked_amount = 100_000;
+        assert!(!FeeDiscountTiers::validate_tiers(&tiers));
+    }
+
+    #[test]
+    fn test_discount_staked_amount() {
+        let fee_discount_account = FeeDiscountAccount {
+            is_initialized: true,
+            staked_amount: 5_000,
+            last_stake_slot: 100,
+            ..FeeDiscountAccount::default()
+        };
+        assert_eq!(fee_discount_account.discount_staked_amount(100), 0);
+        assert_eq!(fee_discount_account.discount_staked_amount(101), 5_000);
+    }
+}
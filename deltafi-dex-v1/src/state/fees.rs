@@ -6,11 +6,16 @@ use solana_program::{
     program_pack::{IsInitialized, Pack, Sealed},
 };
 
-use crate::error::SwapError;
+use crate::{error::SwapError, math::TryMul};
 
 use super::*;
+
+#[cfg(feature = "serde")]
+use serde::{Deserialize, Serialize};
+
 /// Fees struct
 #[derive(Clone, Debug, Default, PartialEq)]
+#[cfg_attr(feature = "serde", derive(Serialize, Deserialize))]
 pub struct Fees {
     /// Initialized state
     pub is_initialized: bool,
@@ -30,6 +35,20 @@ pub struct Fees {
     pub withdraw_fee_numerator: u64,
     /// Withdraw fee denominator
     pub withdraw_fee_denominator: u64,
+    /// Flash swap fee numerator
+    pub flash_fee_numerator: u64,
+    /// Flash swap fee denominator
+    pub flash_fee_denominator: u64,
+    /// Percentage of the deviation between the pool mid price and the oracle price added
+    /// to the trade fee rate, 0 disables the dynamic trade fee
+    pub dynamic_fee_factor: u8,
+    /// Cap of the dynamic trade fee rate as a multiple of the trade fee rate
+    pub max_dynamic_fee_multiplier: u8,
+    /// Treasury trade fee numerator, the share of the trade fee sent to the config treasury
+    /// while the protocol fee switch of the config is on
+    pub treasury_fee_numerator: u64,
+    /// Treasury trade fee denominator
+    pub treasury_fee_denominator: u64,
 }
 
 impl Fees {
@@ -53,6 +72,12 @@ impl Fees {
             trade_fee_denominator: params.trade_fee_denominator,
             withdraw_fee_numerator: params.withdraw_fee_numerator,
             withdraw_fee_denominator: params.withdraw_fee_denominator,
+            flash_fee_numerator: params.flash_fee_numerator,
+            flash_fee_denominator: params.flash_fee_denominator,
+            dynamic_fee_factor: params.dynamic_fee_factor,
+            max_dynamic_fee_multiplier: params.max_dynamic_fee_multiplier,
+            treasury_fee_numerator: params.treasury_fee_numerator,
+            treasury_fee_denominator: params.treasury_fee_denominator,
         }
     }
 
@@ -90,6 +115,26 @@ impl Fees {
             .ok_or_else(|| SwapError::CalculationFailure.into())
     }
 
+    /// Apply treasury trade fee, zero for fees without a treasury share
+    ///
+    /// # Arguments
+    ///
+    /// * fee_amount - fee amount.
+    ///
+    /// # Return value
+    ///
+    /// treasury trade fee
+    pub fn treasury_trade_fee(&self, fee_amount: u64) -> Result<u64, ProgramError> {
+        if self.treasury_fee_numerator == 0 {
+            return Ok(0);
+        }
+        fee_amount
+            .checked_mul(self.treasury_fee_numerator)
+            .ok_or(SwapError::CalculationFailure)?
+            .checked_div(self.treasury_fee_denominator)
+            .ok_or_else(|| SwapError::CalculationFailure.into())
+    }
+
     /// Compute trade fee from amount
     ///
     /// # Arguments
@@ -107,6 +152,39 @@ impl Fees {
             .ok_or_else(|| SwapError::CalculationFailure.into())
     }
 
+    /// Compute trade fee from amount, the trade fee rate raised by the dynamic fee factor
+    /// share of the pool mid price deviation from the oracle price, up to the cap
+    ///
+    /// # Arguments
+    ///
+    /// * trade_amount - trade amount.
+    /// * deviation - relative deviation of the pool mid price from the oracle price.
+    ///
+    /// # Return value
+    ///
+    /// trade fee
+    pub fn dynamic_trade_fee(
+        &self,
+        trade_amount: u64,
+        deviation: Decimal,
+    ) -> Result<u64, ProgramError> {
+        let trade_fee = self.trade_fee(trade_amount)?;
+        if self.dynamic_fee_factor == 0 || self.max_dynamic_fee_multiplier <= 1 {
+            return Ok(trade_fee);
+        }
+        let max_trade_fee = trade_fee
+            .checked_mul(self.max_dynamic_fee_multiplier as u64)
+            .ok_or(SwapError::CalculationFailure)?;
+        let dynamic_fee = Decimal::from(trade_amount)
+            .try_mul(deviation)?
+            .try_mul(Decimal::from_percent(self.dynamic_fee_factor))?
+            .try_floor_u64()?;
+        Ok(trade_fee
+            .checked_add(dynamic_fee)
+            .ok_or(SwapError::CalculationFailure)?
+            .min(max_trade_fee))
+    }
+
     /// Compute withdraw fee from amount
     ///
     /// # Arguments
@@ -123,6 +201,30 @@ impl Fees {
             .checked_div(self.withdraw_fee_denominator)
             .ok_or_else(|| SwapError::CalculationFailure.into())
     }
+
+    /// Compute flash swap fee from the borrowed amount, rounded up so that
+    /// small loans are not free
+    ///
+    /// # Arguments
+    ///
+    /// * flash_amount - borrowed amount.
+    ///
+    /// # Return value
+    ///
+    /// flash swap fee
+    pub fn flash_fee(&self, flash_amount: u64) -> Result<u64, ProgramError> {
+        let rounding = self
+            .flash_fee_denominator
+            .checked_sub(1)
+            .ok_or(SwapError::CalculationFailure)?;
+        flash_amount
+            .checked_mul(self.flash_fee_numerator)
+            .ok_or(SwapError::CalculationFailure)?
+            .checked_add(rounding)
+            .ok_or(SwapError::CalculationFailure)?
+            .checked_div(self.flash_fee_denominator)
+            .ok_or_else(|| SwapError::CalculationFailure.into())
+    }
 }
 
 impl Sealed for Fees {}
@@ -132,7 +234,7 @@ impl IsInitialized for Fees {
     }
 }
 
-const FEES_SIZE: usize = 65;
+const FEES_SIZE: usize = 99;
 impl Pack for Fees {
     const LEN: usize = FEES_SIZE;
     fn unpack_from_slice(input: &[u8]) -> Result<Self, ProgramError> {
@@ -148,7 +250,13 @@ impl Pack for Fees {
             trade_fee_denominator,
             withdraw_fee_numerator,
             withdraw_fee_denominator,
-        ) = array_refs![input, 1, 8, 8, 8, 8, 8, 8, 8, 8];
+            flash_fee_numerator,
+            flash_fee_denominator,
+            dynamic_fee_factor,
+            max_dynamic_fee_multiplier,
+            treasury_fee_numerator,
+            treasury_fee_denominator,
+        ) = array_refs![input, 1, 8, 8, 8, 8, 8, 8, 8, 8, 8, 8, 1, 1, 8, 8];
         Ok(Self {
             is_initialized: unpack_bool(is_initialized)?,
             admin_trade_fee_numerator: u64::from_le_bytes(*admin_trade_fee_numerator),
@@ -159,6 +267,12 @@ impl Pack for Fees {
             trade_fee_denominator: u64::from_le_bytes(*trade_fee_denominator),
             withdraw_fee_numerator: u64::from_le_bytes(*withdraw_fee_numerator),
             withdraw_fee_denominator: u64::from_le_bytes(*withdraw_fee_denominator),
+            flash_fee_numerator: u64::from_le_bytes(*flash_fee_numerator),
+            flash_fee_denominator: u64::from_le_bytes(*flash_fee_denominator),
+            dynamic_fee_factor: u8::from_le_bytes(*dynamic_fee_factor),
+            max_dynamic_fee_multiplier: u8::from_le_bytes(*max_dynamic_fee_multiplier),
+            treasury_fee_numerator: u64::from_le_bytes(*treasury_fee_numerator),
+            treasury_fee_denominator: u64::from_le_bytes(*treasury_fee_denominator),
         })
     }
 
@@ -174,7 +288,13 @@ impl Pack for Fees {
             trade_fee_denominator,
             withdraw_fee_numerator,
             withdraw_fee_denominator,
-        ) = mut_array_refs![output, 1, 8, 8, 8, 8, 8, 8, 8, 8];
+            flash_fee_numerator,
+            flash_fee_denominator,
+            dynamic_fee_factor,
+            max_dynamic_fee_multiplier,
+            treasury_fee_numerator,
+            treasury_fee_denominator,
+        ) = mut_array_refs![output, 1, 8, 8, 8, 8, 8, 8, 8, 8, 8, 8, 1, 1, 8, 8];
         pack_bool(self.is_initialized, is_initialized);
         *admin_trade_fee_numerator = self.admin_trade_fee_numerator.to_le_bytes();
         *admin_trade_fee_denominator = self.admin_trade_fee_denominator.to_le_bytes();
@@ -184,6 +304,12 @@ impl Pack for Fees {
         *trade_fee_denominator = self.trade_fee_denominator.to_le_bytes();
         *withdraw_fee_numerator = self.withdraw_fee_numerator.to_le_bytes();
         *withdraw_fee_denominator = self.withdraw_fee_denominator.to_le_bytes();
+        *flash_fee_numerator = self.flash_fee_numerator.to_le_bytes();
+        *flash_fee_denominator = self.flash_fee_denominator.to_le_bytes();
+        *dynamic_fee_factor = self.dynamic_fee_factor.to_le_bytes();
+        *max_dynamic_fee_multiplier = self.max_dynamic_fee_multiplier.to_le_bytes();
+        *treasury_fee_numerator = self.treasury_fee_numerator.to_le_bytes();
+        *treasury_fee_denominator = self.treasury_fee_denominator.to_le_bytes();
     }
 }
 
@@ -191,6 +317,7 @@ impl Pack for Fees {
 mod tests {
     use super::*;
     use crate::state::DEFAULT_TEST_FEES;
+    use proptest::prelude::*;
 
     #[test]
     fn pack_fees() {
@@ -212,6 +339,12 @@ mod tests {
         packed.extend_from_slice(&fees.trade_fee_denominator.to_le_bytes());
         packed.extend_from_slice(&fees.withdraw_fee_numerator.to_le_bytes());
         packed.extend_from_slice(&fees.withdraw_fee_denominator.to_le_bytes());
+        packed.extend_from_slice(&fees.flash_fee_numerator.to_le_bytes());
+        packed.extend_from_slice(&fees.flash_fee_denominator.to_le_bytes());
+        packed.extend_from_slice(&fees.dynamic_fee_factor.to_le_bytes());
+        packed.extend_from_slice(&fees.max_dynamic_fee_multiplier.to_le_bytes());
+        packed.extend_from_slice(&fees.treasury_fee_numerator.to_le_bytes());
+        packed.extend_from_slice(&fees.treasury_fee_denominator.to_le_bytes());
         let unpacked = Fees::unpack_from_slice(&packed).unwrap();
         assert_eq!(fees, unpacked);
     }
@@ -245,5 +378,82 @@ mod tests {
             fees.admin_withdraw_fee(expected_withdraw_fee).unwrap(),
             expected_admin_withdraw_fee
         );
+
+        let flash_amount = 1_000_000_000;
+        assert_eq!(
+            fees.flash_fee(flash_amount).unwrap(),
+            flash_amount * fees.flash_fee_numerator / fees.flash_fee_denominator
+        );
+        // rounded up
+        assert_eq!(fees.flash_fee(1).unwrap(), 1);
+        assert_eq!(fees.flash_fee(0).unwrap(), 0);
+        assert_eq!(
+            Fees::default().flash_fee(flash_amount),
+            Err(SwapError::CalculationFailure.into())
+        );
+
+        assert_eq!(fees.treasury_trade_fee(trade_fee), Ok(0));
+        let fees = Fees {
+            treasury_fee_numerator: 1,
+            treasury_fee_denominator: 10,
+            ..fees
+        };
+        assert_eq!(fees.treasury_trade_fee(trade_fee), Ok(trade_fee / 10));
+    }
+
+    #[test]
+    fn dynamic_fee_results() {
+        let mut fees = DEFAULT_TEST_FEES;
+        let trade_amount = 1_000_000_000;
+        let trade_fee = fees.trade_fee(trade_amount).unwrap();
+
+        // disabled
+        fees.dynamic_fee_factor = 0;
+        assert_eq!(
+            fees.dynamic_trade_fee(trade_amount, Decimal::from_percent(5)),
+            Ok(trade_fee)
+        );
+
+        // half of the 5% deviation added to the 6% trade fee rate
+        fees.dynamic_fee_factor = 50;
+        fees.max_dynamic_fee_multiplier = 2;
+        assert_eq!(
+            fees.dynamic_trade_fee(trade_amount, Decimal::zero()),
+            Ok(trade_fee)
+        );
+        assert_eq!(
+            fees.dynamic_trade_fee(trade_amount, Decimal::from_percent(5)),
+            Ok(trade_fee + trade_amount / 40)
+        );
+
+        // capped at twice the trade fee rate
+        assert_eq!(
+            fees.dynamic_trade_fee(trade_amount, Decimal::from_percent(50)),
+            Ok(trade_fee * 2)
+        );
+    }
+
+    proptest! {
+        #[test]
+        fn test_admin_fee_within_trade_fee(
+            amount in 0..=u64::MAX,
+            trade_fee_numerator in 0..=100u64,
+            admin_trade_fee_numerator in 0..=100u64,
+        ) {
+            let fees = Fees {
+                trade_fee_numerator,
+                trade_fee_denominator: 100,
+                admin_trade_fee_numerator,
+                admin_trade_fee_denominator: 100,
+                ..DEFAULT_TEST_FEES
+            };
+            // An overflow is an error, the admin fee never exceeds the trade fee it is taken from
+            if let Ok(trade_fee) = fees.trade_fee(amount) {
+                prop_assert!(trade_fee <= amount);
+                if let Ok(admin_fee) = fees.admin_trade_fee(trade_fee) {
+                    prop_assert!(admin_fee <= trade_fee);
+                }
+            }
+        }
     }
 }
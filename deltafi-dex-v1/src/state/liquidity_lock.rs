@@ -0,0 +1,141 @@
+//! Liquidity locks escrowing the pool tokens of a pool creator until a cliff
+
+use arrayref::{array_mut_ref, array_ref, array_refs, mut_array_refs};
+use solana_program::{
+    clock::UnixTimestamp,
+    program_error::ProgramError,
+    program_pack::{IsInitialized, Pack, Sealed},
+    pubkey::{Pubkey, PUBKEY_BYTES},
+};
+
+use crate::error::SwapError;
+
+use super::*;
+
+/// Liquidity lock of an owner on a pool, its token account holding the locked pool tokens is
+/// owned by the liquidity lock address
+#[derive(Clone, Debug, Default, PartialEq)]
+pub struct LiquidityLock {
+    /// Initialization status
+    pub is_initialized: bool,
+    /// Bump seed of the liquidity lock address
+    pub bump_seed: u8,
+    /// Token swap of the pool
+    pub swap_key: Pubkey,
+    /// Owner of the locked pool tokens
+    pub owner: Pubkey,
+    /// Pool token account owned by the liquidity lock address
+    pub token_account: Pubkey,
+    /// Locked pool token amount
+    pub locked_amount: u64,
+    /// Cliff timestamp, the locked pool tokens are withdrawable from it
+    pub unlock_ts: UnixTimestamp,
+}
+
+impl LiquidityLock {
+    /// Lock more pool tokens, the cliff can only be kept or pushed back
+    pub fn lock(&mut self, amount: u64, unlock_ts: UnixTimestamp) -> Result<(), ProgramError> {
+        if unlock_ts < self.unlock_ts {
+            return Err(SwapError::InvalidLiquidityLock.into());
+        }
+        self.locked_amount = self
+            .locked_amount
+            .checked_add(amount)
+            .ok_or(SwapError::CalculationFailure)?;
+        self.unlock_ts = unlock_ts;
+        Ok(())
+    }
+
+    /// Release all the locked pool tokens past the cliff
+    pub fn unlock(&mut self, current_ts: UnixTimestamp) -> Result<u64, ProgramError> {
+        if current_ts < self.unlock_ts {
+            return Err(SwapError::LiquidityLocked.into());
+        }
+        Ok(std::mem::take(&mut self.locked_amount))
+    }
+}
+
+impl Sealed for LiquidityLock {}
+impl IsInitialized for LiquidityLock {
+    fn is_initialized(&self) -> bool {
+        self.is_initialized
+    }
+}
+
+const LIQUIDITY_LOCK_SIZE: usize = 1 + 1 + PUBKEY_BYTES * 3 + 8 + 8;
+
+impl Pack for LiquidityLock {
+    const LEN: usize = LIQUIDITY_LOCK_SIZE;
+
+    fn pack_into_slice(&self, dst: &mut [u8]) {
+        let output = array_mut_ref![dst, 0, LIQUIDITY_LOCK_SIZE];
+        #[allow(clippy::ptr_offset_with_cast)]
+        let (is_initialized, bump_seed, swap_key, owner, token_account, locked_amount, unlock_ts) =
+            mut_array_refs![output, 1, 1, PUBKEY_BYTES, PUBKEY_BYTES, PUBKEY_BYTES, 8, 8];
+        pack_bool(self.is_initialized, is_initialized);
+        bump_seed[0] = self.bump_seed;
+        swap_key.copy_from_slice(self.swap_key.as_ref());
+        owner.copy_from_slice(self.owner.as_ref());
+        token_account.copy_from_slice(self.token_account.as_ref());
+        *locked_amount = self.locked_amount.to_le_bytes();
+        *unlock_ts = self.unlock_ts.to_le_bytes();
+    }
+
+    fn unpack_from_slice(src: &[u8]) -> Result<Self, ProgramError> {
+        let input = array_ref![src, 0, LIQUIDITY_LOCK_SIZE];
+        #[allow(clippy::ptr_offset_with_cast)]
+        let (is_initialized, bump_seed, swap_key, owner, token_account, locked_amount, unlock_ts) =
+            array_refs![input, 1, 1, PUBKEY_BYTES, PUBKEY_BYTES, PUBKEY_BYTES, 8, 8];
+        Ok(Self {
+            is_initialized: unpack_bool(is_initialized)?,
+            bump_seed: bump_seed[0],
+            swap_key: Pubkey::new_from_array(*swap_key),
+            owner: Pubkey::new_from_array(*owner),
+            token_account: Pubkey::new_from_array(*token_account),
+            locked_amount: u64::from_le_bytes(*locked_amount),
+            unlock_ts: i64::from_le_bytes(*unlock_ts),
+        })
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_liquidity_lock_packing() {
+        let liquidity_lock = LiquidityLock {
+            is_initialized: true,
+            bump_seed: 254,
+            swap_key: Pubkey::new_unique(),
+            owner: Pubkey::new_unique(),
+            token_account: Pubkey::new_unique(),
+            locked_amount: 1_000_000,
+            unlock_ts: 1_700_000_000,
+        };
+        let mut packed = [0u8; LiquidityLock::LEN];
+        LiquidityLock::pack_into_slice(&liquidity_lock, &mut packed);
+        assert_eq!(LiquidityLock::unpack(&packed).unwrap(), liquidity_lock);
+    }
+
+    #[test]
+    fn test_liquidity_lock_cliff() {
+        let mut liquidity_lock = LiquidityLock::default();
+        liquidity_lock.lock(1_000, 1_700_000_000).unwrap();
+        liquidity_lock.lock(500, 1_800_000_000).unwrap();
+        assert_eq!(liquidity_lock.locked_amount, 1_500);
+
+        // The cliff is never brought forward
+        assert_eq!(
+            liquidity_lock.lock(500, 1_750_000_000).unwrap_err(),
+            SwapError::InvalidLiquidityLock.into()
+        );
+
+        assert_eq!(
+            liquidity_lock.unlock(1_799_999_999).unwrap_err(),
+            SwapError::LiquidityLocked.into()
+        );
+        assert_eq!(liquidity_lock.unlock(1_800_000_000).unwrap(), 1_500);
+        assert_eq!(liquidity_lock.locked_amount, 0);
+    }
+}
@@ -1,16 +1,40 @@
 //! State used in DeFi
 
+mod commitment;
 mod config;
 mod farm;
+mod farm_registry;
+mod farm_reward_token;
+mod fee_discount;
 mod fees;
+mod liquidity_lock;
+mod oracle_feed;
+mod price_cache;
+mod quote;
+mod referrer_stats;
 mod rewards;
+mod scratch;
 mod swap;
+mod tri_swap;
+mod whitelist;
 
+pub use commitment::*;
 pub use config::*;
 pub use farm::*;
+pub use farm_registry::*;
+pub use farm_reward_token::*;
+pub use fee_discount::*;
 pub use fees::*;
+pub use liquidity_lock::*;
+pub use oracle_feed::*;
+pub use price_cache::*;
+pub use quote::*;
+pub use referrer_stats::*;
 pub use rewards::*;
+pub use scratch::*;
 pub use swap::*;
+pub use tri_swap::*;
+pub use whitelist::*;
 
 pub use crate::math::Decimal;
 
@@ -55,6 +79,12 @@ pub const DEFAULT_TEST_FEES: Fees = Fees {
     trade_fee_denominator: 100,
     withdraw_fee_numerator: 6,
     withdraw_fee_denominator: 100,
+    flash_fee_numerator: 9,
+    flash_fee_denominator: 10_000,
+    dynamic_fee_factor: 0,
+    max_dynamic_fee_multiplier: 0,
+    treasury_fee_numerator: 0,
+    treasury_fee_denominator: 0,
 };
 
 #[cfg(test)]
@@ -62,7 +92,8 @@ pub const DEFAULT_TEST_FEES: Fees = Fees {
 pub const DEFAULT_TEST_REWARDS: Rewards = Rewards {
     is_initialized: true,
     decimals: 9,
-    reserved: [0u8; 7],
+    referral_fee_bps: 0,
+    reserved: [0u8; 5],
     trade_reward_numerator: 1,
     trade_reward_denominator: 2,
     trade_reward_cap: 100,
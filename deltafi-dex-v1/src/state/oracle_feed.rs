@@ -0,0 +1,105 @@
+//! Oracle accounts registered for a token mint, pools are initialized against them
+
+use arrayref::{array_mut_ref, array_ref, array_refs, mut_array_refs};
+use solana_program::{
+    program_error::ProgramError,
+    program_pack::{IsInitialized, Pack, Sealed},
+    pubkey::{Pubkey, PUBKEY_BYTES},
+};
+
+use super::*;
+
+/// Oracle feed registry entry of a mint, default pubkey for a feed not available for the mint
+#[derive(Clone, Debug, Default, PartialEq)]
+pub struct OracleFeed {
+    /// Initialization status
+    pub is_initialized: bool,
+    /// Config pubkey
+    pub config_key: Pubkey,
+    /// Token mint pubkey
+    pub mint: Pubkey,
+    /// Pyth price account of the mint
+    pub pyth_price: Pubkey,
+    /// Serum market with the mint as base token
+    pub serum_market: Pubkey,
+}
+
+impl Sealed for OracleFeed {}
+impl IsInitialized for OracleFeed {
+    fn is_initialized(&self) -> bool {
+        self.is_initialized
+    }
+}
+
+const ORACLE_FEED_SIZE: usize = 1 + PUBKEY_BYTES * 4;
+
+impl Pack for OracleFeed {
+    const LEN: usize = ORACLE_FEED_SIZE;
+
+    fn pack_into_slice(&self, dst: &mut [u8]) {
+        let output = array_mut_ref![dst, 0, ORACLE_FEED_SIZE];
+        #[allow(clippy::ptr_offset_with_cast)]
+        let (is_initialized, config_key, mint, pyth_price, serum_market) = mut_array_refs![
+            output,
+            1,
+            PUBKEY_BYTES,
+            PUBKEY_BYTES,
+            PUBKEY_BYTES,
+            PUBKEY_BYTES
+        ];
+        pack_bool(self.is_initialized, is_initialized);
+        config_key.copy_from_slice(self.config_key.as_ref());
+        mint.copy_from_slice(self.mint.as_ref());
+        pyth_price.copy_from_slice(self.pyth_price.as_ref());
+        serum_market.copy_from_slice(self.serum_market.as_ref());
+    }
+
+    fn unpack_from_slice(src: &[u8]) -> Result<Self, ProgramError> {
+        let input = array_ref![src, 0, ORACLE_FEED_SIZE];
+        #[allow(clippy::ptr_offset_with_cast)]
+        let (is_initialized, config_key, mint, pyth_price, serum_market) = array_refs![
+            input,
+            1,
+            PUBKEY_BYTES,
+            PUBKEY_BYTES,
+            PUBKEY_BYTES,
+            PUBKEY_BYTES
+        ];
+        Ok(Self {
+            is_initialized: unpack_bool(is_initialized)?,
+            config_key: Pubkey::new_from_array(*config_key),
+            mint: Pubkey::new_from_array(*mint),
+            pyth_price: Pubkey::new_from_array(*pyth_price),
+            serum_market: Pubkey::new_from_array(*serum_market),
+        })
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_oracle_feed_packing() {
+        let oracle_feed = OracleFeed {
+            is_initialized: true,
+            config_key: Pubkey::new_unique(),
+            mint: Pubkey::new_unique(),
+            pyth_price: Pubkey::new_unique(),
+            serum_market: Pubkey::default(),
+        };
+
+        let mut packed = [0u8; OracleFeed::LEN];
+        OracleFeed::pack_into_slice(&oracle_feed, &mut packed);
+        let unpacked = OracleFeed::unpack(&packed).unwrap();
+        assert_eq!(oracle_feed, unpacked);
+
+        let packed = [0u8; OracleFeed::LEN];
+        let unpacked = OracleFeed::unpack_unchecked(&packed).unwrap();
+        assert_eq!(unpacked, OracleFeed::default());
+        assert_eq!(
+            OracleFeed::unpack(&packed).unwrap_err(),
+            ProgramError::UninitializedAccount
+        );
+    }
+}
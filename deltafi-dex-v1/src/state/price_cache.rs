@@ -0,0 +1,224 @@
+//! Pyth derived market price of a pool cached by a trusted relayer, cheaper to read than
+//! the pyth accounts on the swap path
+
+use arrayref::{array_mut_ref, array_ref, array_refs, mut_array_refs};
+use solana_program::{
+    program_error::ProgramError,
+    program_pack::{IsInitialized, Pack, Sealed},
+    pubkey::{Pubkey, PUBKEY_BYTES},
+};
+
+use super::*;
+use crate::time::TimeSource;
+
+/// Price cache of a pool, updated by its relayer from the pyth accounts of the pool
+#[derive(Clone, Debug, Default, PartialEq)]
+pub struct PriceCache {
+    /// Initialization status
+    pub is_initialized: bool,
+    /// Bump seed of the price cache address
+    pub bump_seed: u8,
+    /// Swap pubkey
+    pub swap_key: Pubkey,
+    /// Relayer allowed to update the cache
+    pub relayer: Pubkey,
+    /// Base token pyth price account the price was read from
+    pub pyth_a: Pubkey,
+    /// Quote token pyth price account the price was read from
+    pub pyth_b: Pubkey,
+    /// Market price of the base token in quote token
+    pub market_price: Decimal,
+    /// Valid slot of the pyth prices
+    pub valid_slot: u64,
+    /// Signer of the last update
+    pub updated_by: Pubkey,
+    /// Slot of the last update
+    pub update_slot: u64,
+}
+
+impl PriceCache {
+    /// Cached market price and valid slot, none if the cache was never updated, was read
+    /// from other pyth accounts than the ones of the pool or is stale
+    pub fn fresh_price(
+        &self,
+        token_swap: &SwapInfo,
+        clock: &impl TimeSource,
+    ) -> Option<(Decimal, u64)> {
+        if self.market_price == Decimal::zero()
+            || self.pyth_a != token_swap.pyth_a
+            || self.pyth_b != token_swap.pyth_b
+            || clock.slot().saturating_sub(self.valid_slot)
+                >= token_swap.oracle_params.stale_after_slots()
+        {
+            return None;
+        }
+        Some((self.market_price, self.valid_slot))
+    }
+}
+
+impl Sealed for PriceCache {}
+impl IsInitialized for PriceCache {
+    fn is_initialized(&self) -> bool {
+        self.is_initialized
+    }
+}
+
+const PRICE_CACHE_SIZE: usize = 1 + 1 + PUBKEY_BYTES * 5 + 16 + 8 + 8;
+
+impl Pack for PriceCache {
+    const LEN: usize = PRICE_CACHE_SIZE;
+
+    fn pack_into_slice(&self, dst: &mut [u8]) {
+        let output = array_mut_ref![dst, 0, PRICE_CACHE_SIZE];
+        #[allow(clippy::ptr_offset_with_cast)]
+        let (
+            is_initialized,
+            bump_seed,
+            swap_key,
+            relayer,
+            pyth_a,
+            pyth_b,
+            market_price,
+            valid_slot,
+            updated_by,
+            update_slot,
+        ) = mut_array_refs![
+            output,
+            1,
+            1,
+            PUBKEY_BYTES,
+            PUBKEY_BYTES,
+            PUBKEY_BYTES,
+            PUBKEY_BYTES,
+            16,
+            8,
+            PUBKEY_BYTES,
+            8
+        ];
+        pack_bool(self.is_initialized, is_initialized);
+        bump_seed[0] = self.bump_seed;
+        swap_key.copy_from_slice(self.swap_key.as_ref());
+        relayer.copy_from_slice(self.relayer.as_ref());
+        pyth_a.copy_from_slice(self.pyth_a.as_ref());
+        pyth_b.copy_from_slice(self.pyth_b.as_ref());
+        pack_decimal(self.market_price, market_price);
+        *valid_slot = self.valid_slot.to_le_bytes();
+        updated_by.copy_from_slice(self.updated_by.as_ref());
+        *update_slot = self.update_slot.to_le_bytes();
+    }
+
+    fn unpack_from_slice(src: &[u8]) -> Result<Self, ProgramError> {
+        let input = array_ref![src, 0, PRICE_CACHE_SIZE];
+        #[allow(clippy::ptr_offset_with_cast)]
+        let (
+            is_initialized,
+            bump_seed,
+            swap_key,
+            relayer,
+            pyth_a,
+            pyth_b,
+            market_price,
+            valid_slot,
+            updated_by,
+            update_slot,
+        ) = array_refs![
+            input,
+            1,
+            1,
+            PUBKEY_BYTES,
+            PUBKEY_BYTES,
+            PUBKEY_BYTES,
+            PUBKEY_BYTES,
+            16,
+            8,
+            PUBKEY_BYTES,
+            8
+        ];
+        Ok(Self {
+            is_initialized: unpack_bool(is_initialized)?,
+            bump_seed: bump_seed[0],
+            swap_key: Pubkey::new_from_array(*swap_key),
+            relayer: Pubkey::new_from_array(*relayer),
+            pyth_a: Pubkey::new_from_array(*pyth_a),
+            pyth_b: Pubkey::new_from_array(*pyth_b),
+            market_price: unpack_decimal(market_price),
+            valid_slot: u64::from_le_bytes(*valid_slot),
+            updated_by: Pubkey::new_from_array(*updated_by),
+            update_slot: u64::from_le_bytes(*update_slot),
+        })
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    use crate::time::ManualClock;
+
+    #[test]
+    fn test_price_cache_packing() {
+        let price_cache = PriceCache {
+            is_initialized: true,
+            bump_seed: 254,
+            swap_key: Pubkey::new_unique(),
+            relayer: Pubkey::new_unique(),
+            pyth_a: Pubkey::new_unique(),
+            pyth_b: Pubkey::new_unique(),
+            market_price: Decimal::from(150u64),
+            valid_slot: 1_000,
+            updated_by: Pubkey::new_unique(),
+            update_slot: 1_001,
+        };
+
+        let mut packed = [0u8; PriceCache::LEN];
+        PriceCache::pack_into_slice(&price_cache, &mut packed);
+        let unpacked = PriceCache::unpack(&packed).unwrap();
+        assert_eq!(price_cache, unpacked);
+
+        let packed = [0u8; PriceCache::LEN];
+        let unpacked = PriceCache::unpack_unchecked(&packed).unwrap();
+        assert_eq!(unpacked, PriceCache::default());
+        assert_eq!(
+            PriceCache::unpack(&packed).unwrap_err(),
+            ProgramError::UninitializedAccount
+        );
+    }
+
+    #[test]
+    fn test_price_cache_fresh_price() {
+        let token_swap = SwapInfo {
+            pyth_a: Pubkey::new_unique(),
+            pyth_b: Pubkey::new_unique(),
+            ..SwapInfo::default()
+        };
+        let stale_after_slots = token_swap.oracle_params.stale_after_slots();
+        let mut price_cache = PriceCache {
+            is_initialized: true,
+            swap_key: Pubkey::new_unique(),
+            pyth_a: token_swap.pyth_a,
+            pyth_b: token_swap.pyth_b,
+            valid_slot: 100,
+            ..PriceCache::default()
+        };
+
+        // Never updated
+        let mut clock = ManualClock::new(100, 0);
+        assert_eq!(price_cache.fresh_price(&token_swap, &clock), None);
+
+        price_cache.market_price = Decimal::from(150u64);
+        assert_eq!(
+            price_cache.fresh_price(&token_swap, &clock),
+            Some((Decimal::from(150u64), 100))
+        );
+
+        clock.advance(stale_after_slots - 1, 0);
+        assert!(price_cache.fresh_price(&token_swap, &clock).is_some());
+        clock.advance(1, 0);
+        assert_eq!(price_cache.fresh_price(&token_swap, &clock), None);
+
+        // Read from other pyth accounts than the ones of the pool
+        let clock = ManualClock::new(100, 0);
+        price_cache.pyth_b = Pubkey::new_unique();
+        assert_eq!(price_cache.fresh_price(&token_swap, &clock), None);
+    }
+}
@@ -0,0 +1,247 @@
+//! Market maker quotes filled outside the pool curve
+
+use std::convert::TryFrom;
+
+use arrayref::{array_mut_ref, array_ref, array_refs, mut_array_refs};
+use solana_program::{
+    program_error::ProgramError,
+    program_pack::{IsInitialized, Pack, Sealed},
+    pubkey::{Pubkey, PUBKEY_BYTES},
+};
+
+use super::*;
+use crate::{
+    curve::SwapDirection,
+    error::SwapError,
+    math::{TryDiv, TryMul},
+};
+
+/// Max number of slots a quote stays valid after it is posted, about one minute
+pub const MAX_QUOTE_DURATION_SLOTS: u64 = 150;
+
+/// Quote posted by an approved market maker to sell its inventory to the users of a pool
+#[derive(Clone, Debug, PartialEq)]
+pub struct MarketMakerQuote {
+    /// Initialization status
+    pub is_initialized: bool,
+    /// Config pubkey
+    pub config_key: Pubkey,
+    /// Token swap pubkey of the quoted pool
+    pub swap_key: Pubkey,
+    /// Market maker pubkey, must be on the config allowlist
+    pub market_maker: Pubkey,
+    /// Direction of the user trade filling the quote
+    pub swap_direction: SwapDirection,
+    /// Price of the base token in quote token
+    pub price: Decimal,
+    /// Amount of SOURCE token the quote can still take in
+    pub amount_in_remaining: u64,
+    /// Last slot the quote can be filled
+    pub expiry_slot: u64,
+    /// Base token inventory account of the market maker
+    pub market_maker_token_a: Pubkey,
+    /// Quote token inventory account of the market maker
+    pub market_maker_token_b: Pubkey,
+}
+
+impl Default for MarketMakerQuote {
+    fn default() -> Self {
+        Self {
+            is_initialized: false,
+            config_key: Pubkey::default(),
+            swap_key: Pubkey::default(),
+            market_maker: Pubkey::default(),
+            swap_direction: SwapDirection::SellBase,
+            price: Decimal::zero(),
+            amount_in_remaining: 0,
+            expiry_slot: 0,
+            market_maker_token_a: Pubkey::default(),
+            market_maker_token_b: Pubkey::default(),
+        }
+    }
+}
+
+impl MarketMakerQuote {
+    /// Get the amount of DESTINATION token quoted for amount_in SOURCE token
+    ///
+    /// # Arguments
+    /// * amount_in - SOURCE token amount.
+    /// * base_decimals - decimals of the base token.
+    /// * quote_decimals - decimals of the quote token.
+    ///
+    /// # Return value
+    /// DESTINATION token amount before the trade fee.
+    pub fn get_out_amount(
+        &self,
+        amount_in: u64,
+        base_decimals: u8,
+        quote_decimals: u8,
+    ) -> Result<u64, ProgramError> {
+        let base_multiplier = 10u64
+            .checked_pow(base_decimals as u32)
+            .ok_or(SwapError::CalculationFailure)?;
+        let quote_multiplier = 10u64
+            .checked_pow(quote_decimals as u32)
+            .ok_or(SwapError::CalculationFailure)?;
+
+        match self.swap_direction {
+            SwapDirection::SellBase => Decimal::from(amount_in)
+                .try_mul(self.price)?
+                .try_mul(quote_multiplier)?
+                .try_div(base_multiplier)?
+                .try_floor_u64(),
+            SwapDirection::SellQuote => Decimal::from(amount_in)
+                .try_mul(base_multiplier)?
+                .try_div(self.price.try_mul(quote_multiplier)?)?
+                .try_floor_u64(),
+        }
+    }
+}
+
+impl Sealed for MarketMakerQuote {}
+impl IsInitialized for MarketMakerQuote {
+    fn is_initialized(&self) -> bool {
+        self.is_initialized
+    }
+}
+
+const MARKET_MAKER_QUOTE_SIZE: usize = 1 + PUBKEY_BYTES * 3 + 1 + 16 + 8 + 8 + PUBKEY_BYTES * 2;
+
+impl Pack for MarketMakerQuote {
+    const LEN: usize = MARKET_MAKER_QUOTE_SIZE;
+
+    fn pack_into_slice(&self, dst: &mut [u8]) {
+        let output = array_mut_ref![dst, 0, MARKET_MAKER_QUOTE_SIZE];
+        #[allow(clippy::ptr_offset_with_cast)]
+        let (
+            is_initialized,
+            config_key,
+            swap_key,
+            market_maker,
+            swap_direction,
+            price,
+            amount_in_remaining,
+            expiry_slot,
+            market_maker_token_a,
+            market_maker_token_b,
+        ) = mut_array_refs![
+            output,
+            1,
+            PUBKEY_BYTES,
+            PUBKEY_BYTES,
+            PUBKEY_BYTES,
+            1,
+            16,
+            8,
+            8,
+            PUBKEY_BYTES,
+            PUBKEY_BYTES
+        ];
+        pack_bool(self.is_initialized, is_initialized);
+        config_key.copy_from_slice(self.config_key.as_ref());
+        swap_key.copy_from_slice(self.swap_key.as_ref());
+        market_maker.copy_from_slice(self.market_maker.as_ref());
+        swap_direction[0] = self.swap_direction as u8;
+        pack_decimal(self.price, price);
+        *amount_in_remaining = self.amount_in_remaining.to_le_bytes();
+        *expiry_slot = self.expiry_slot.to_le_bytes();
+        market_maker_token_a.copy_from_slice(self.market_maker_token_a.as_ref());
+        market_maker_token_b.copy_from_slice(self.market_maker_token_b.as_ref());
+    }
+
+    fn unpack_from_slice(src: &[u8]) -> Result<Self, ProgramError> {
+        let input = array_ref![src, 0, MARKET_MAKER_QUOTE_SIZE];
+        #[allow(clippy::ptr_offset_with_cast)]
+        let (
+            is_initialized,
+            config_key,
+            swap_key,
+            market_maker,
+            swap_direction,
+            price,
+            amount_in_remaining,
+            expiry_slot,
+            market_maker_token_a,
+            market_maker_token_b,
+        ) = array_refs![
+            input,
+            1,
+            PUBKEY_BYTES,
+            PUBKEY_BYTES,
+            PUBKEY_BYTES,
+            1,
+            16,
+            8,
+            8,
+            PUBKEY_BYTES,
+            PUBKEY_BYTES
+        ];
+
+        Ok(Self {
+            is_initialized: unpack_bool(is_initialized)?,
+            config_key: Pubkey::new_from_array(*config_key),
+            swap_key: Pubkey::new_from_array(*swap_key),
+            market_maker: Pubkey::new_from_array(*market_maker),
+            swap_direction: SwapDirection::try_from(swap_direction[0])?,
+            price: unpack_decimal(price),
+            amount_in_remaining: u64::from_le_bytes(*amount_in_remaining),
+            expiry_slot: u64::from_le_bytes(*expiry_slot),
+            market_maker_token_a: Pubkey::new_from_array(*market_maker_token_a),
+            market_maker_token_b: Pubkey::new_from_array(*market_maker_token_b),
+        })
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_market_maker_quote_packing() {
+        let market_maker_quote = MarketMakerQuote {
+            is_initialized: true,
+            config_key: Pubkey::new_unique(),
+            swap_key: Pubkey::new_unique(),
+            market_maker: Pubkey::new_unique(),
+            swap_direction: SwapDirection::SellQuote,
+            price: Decimal::from(35u64),
+            amount_in_remaining: 1_000_000u64,
+            expiry_slot: 150_000u64,
+            market_maker_token_a: Pubkey::new_unique(),
+            market_maker_token_b: Pubkey::new_unique(),
+        };
+
+        let mut packed = [0u8; MarketMakerQuote::LEN];
+        MarketMakerQuote::pack_into_slice(&market_maker_quote, &mut packed);
+        let unpacked = MarketMakerQuote::unpack(&packed).unwrap();
+        assert_eq!(market_maker_quote, unpacked);
+
+        let packed = [0u8; MarketMakerQuote::LEN];
+        let unpacked = MarketMakerQuote::unpack_unchecked(&packed).unwrap();
+        assert_eq!(unpacked, MarketMakerQuote::default());
+        assert_eq!(
+            MarketMakerQuote::unpack(&packed).unwrap_err(),
+            ProgramError::UninitializedAccount
+        );
+    }
+
+    #[test]
+    fn test_market_maker_quote_out_amount() {
+        // 1 base = 35 quote, base with 9 decimals and quote with 6 decimals
+        let mut market_maker_quote = MarketMakerQuote {
+            price: Decimal::from(35u64),
+            ..MarketMakerQuote::default()
+        };
+        assert_eq!(
+            market_maker_quote.get_out_amount(2_000_000_000u64, 9, 6),
+            Ok(70_000_000u64)
+        );
+
+        market_maker_quote.swap_direction = SwapDirection::SellQuote;
+        assert_eq!(
+            market_maker_quote.get_out_amount(70_000_000u64, 9, 6),
+            Ok(2_000_000_000u64)
+        );
+        assert_eq!(market_maker_quote.get_out_amount(1u64, 9, 6), Ok(28u64));
+    }
+}
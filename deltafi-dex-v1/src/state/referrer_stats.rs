@@ -0,0 +1,165 @@
+//! Referral volume of the referrers
+
+use arrayref::{array_mut_ref, array_ref, array_refs, mut_array_refs};
+use solana_program::{
+    program_error::ProgramError,
+    program_pack::{IsInitialized, Pack, Sealed},
+    pubkey::{Pubkey, PUBKEY_BYTES},
+};
+
+use crate::error::SwapError;
+
+use super::*;
+
+/// Referral reward tiers by increasing referred volume, as the referred volume to reach the
+/// tier and the share of the trade reward in basis points paid to the referrer
+pub const REFERRAL_REWARD_TIERS: [(u64, u16); 3] = [
+    (0, 1_000),
+    (100_000_000_000, 1_500),
+    (1_000_000_000_000, 2_000),
+];
+
+/// Share of the trade reward in basis points paid to a referrer with the referred volume
+pub fn referral_reward_bps(referred_volume: u64) -> u16 {
+    REFERRAL_REWARD_TIERS
+        .iter()
+        .rev()
+        .find(|(min_referred_volume, _)| *min_referred_volume <= referred_volume)
+        .map_or(0, |(_, reward_bps)| *reward_bps)
+}
+
+/// Referral reward of a trade reward at the tier of the referred volume
+pub fn tiered_referral_reward(
+    trade_reward: u64,
+    referred_volume: u64,
+) -> Result<u64, ProgramError> {
+    let referral_reward = (trade_reward as u128)
+        .checked_mul(referral_reward_bps(referred_volume) as u128)
+        .ok_or(SwapError::CalculationFailure)?
+        / 10_000;
+    Ok(referral_reward as u64)
+}
+
+/// Referral stats of a referrer token account, at the program address of the config and
+/// the referrer token account
+#[derive(Clone, Debug, Default, PartialEq)]
+pub struct ReferrerStats {
+    /// Initialization status
+    pub is_initialized: bool,
+    /// Bump seed of the referrer stats address
+    pub bump_seed: u8,
+    /// Config key
+    pub config_key: Pubkey,
+    /// Referrer DELTAFI token account
+    pub referrer: Pubkey,
+    /// Trade rewards of the referred swaps, the DELTAFI measure of the referred volume
+    pub referred_volume: u64,
+    /// DELTAFI referral rewards paid to the referrer
+    pub reward_paid: u64,
+}
+
+impl ReferrerStats {
+    /// Record a referred swap, returning the referral reward of its trade reward at the tier
+    /// of the volume referred before it
+    pub fn record_referral(&mut self, trade_reward: u64) -> Result<u64, ProgramError> {
+        let referral_reward = tiered_referral_reward(trade_reward, self.referred_volume)?;
+        self.referred_volume = self
+            .referred_volume
+            .checked_add(trade_reward)
+            .ok_or(SwapError::CalculationFailure)?;
+        self.reward_paid = self
+            .reward_paid
+            .checked_add(referral_reward)
+            .ok_or(SwapError::CalculationFailure)?;
+        Ok(referral_reward)
+    }
+}
+
+impl Sealed for ReferrerStats {}
+impl IsInitialized for ReferrerStats {
+    fn is_initialized(&self) -> bool {
+        self.is_initialized
+    }
+}
+
+const REFERRER_STATS_SIZE: usize = 1 + 1 + PUBKEY_BYTES * 2 + 8 + 8;
+
+impl Pack for ReferrerStats {
+    const LEN: usize = REFERRER_STATS_SIZE;
+
+    fn pack_into_slice(&self, dst: &mut [u8]) {
+        let output = array_mut_ref![dst, 0, REFERRER_STATS_SIZE];
+        #[allow(clippy::ptr_offset_with_cast)]
+        let (is_initialized, bump_seed, config_key, referrer, referred_volume, reward_paid) =
+            mut_array_refs![output, 1, 1, PUBKEY_BYTES, PUBKEY_BYTES, 8, 8];
+        pack_bool(self.is_initialized, is_initialized);
+        bump_seed[0] = self.bump_seed;
+        config_key.copy_from_slice(self.config_key.as_ref());
+        referrer.copy_from_slice(self.referrer.as_ref());
+        *referred_volume = self.referred_volume.to_le_bytes();
+        *reward_paid = self.reward_paid.to_le_bytes();
+    }
+
+    fn unpack_from_slice(src: &[u8]) -> Result<Self, ProgramError> {
+        let input = array_ref![src, 0, REFERRER_STATS_SIZE];
+        #[allow(clippy::ptr_offset_with_cast)]
+        let (is_initialized, bump_seed, config_key, referrer, referred_volume, reward_paid) =
+            array_refs![input, 1, 1, PUBKEY_BYTES, PUBKEY_BYTES, 8, 8];
+        Ok(Self {
+            is_initialized: unpack_bool(is_initialized)?,
+            bump_seed: bump_seed[0],
+            config_key: Pubkey::new_from_array(*config_key),
+            referrer: Pubkey::new_from_array(*referrer),
+            referred_volume: u64::from_le_bytes(*referred_volume),
+            reward_paid: u64::from_le_bytes(*reward_paid),
+        })
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_referral_reward_bps() {
+        assert_eq!(referral_reward_bps(0), 1_000);
+        assert_eq!(referral_reward_bps(99_999_999_999), 1_000);
+        assert_eq!(referral_reward_bps(100_000_000_000), 1_500);
+        assert_eq!(referral_reward_bps(1_000_000_000_000), 2_000);
+        assert_eq!(referral_reward_bps(u64::MAX), 2_000);
+    }
+
+    #[test]
+    fn test_record_referral() {
+        let mut stats = ReferrerStats {
+            referred_volume: 99_999_000_000,
+            ..ReferrerStats::default()
+        };
+        assert_eq!(stats.record_referral(1_000_000).unwrap(), 100_000);
+        assert_eq!(stats.referred_volume, 100_000_000_000);
+        assert_eq!(stats.record_referral(1_000_000).unwrap(), 150_000);
+        assert_eq!(stats.referred_volume, 100_001_000_000);
+        assert_eq!(stats.reward_paid, 250_000);
+
+        stats.referred_volume = u64::MAX;
+        assert_eq!(
+            stats.record_referral(1),
+            Err(SwapError::CalculationFailure.into())
+        );
+    }
+
+    #[test]
+    fn test_referrer_stats_packing() {
+        let stats = ReferrerStats {
+            is_initialized: true,
+            bump_seed: 254,
+            config_key: Pubkey::new_unique(),
+            referrer: Pubkey::new_unique(),
+            referred_volume: 1_000_000,
+            reward_paid: 100_000,
+        };
+        let mut packed = [0u8; ReferrerStats::LEN];
+        ReferrerStats::pack_into_slice(&stats, &mut packed);
+        assert_eq!(ReferrerStats::unpack(&packed).unwrap(), stats);
+    }
+}
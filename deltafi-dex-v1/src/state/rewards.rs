@@ -6,22 +6,32 @@ use solana_program::{
     program_pack::{IsInitialized, Pack, Sealed},
 };
 
-use crate::math::{Decimal, TryDiv, TryMul};
+use crate::{
+    error::SwapError,
+    math::{Decimal, TryDiv, TryMul},
+};
 
 use super::*;
 
+#[cfg(feature = "serde")]
+use serde::{Deserialize, Serialize};
+
 const REWARD_TOKEN_DECIMALS: u8 = 6;
 const REWARD_TOKEN_THRESHOLD: u64 = 1000000;
 
 /// Rewards structure
 #[derive(Clone, Debug, Default, PartialEq)]
+#[cfg_attr(feature = "serde", derive(Serialize, Deserialize))]
 pub struct Rewards {
     /// Initialized state
     pub is_initialized: bool,
     /// Base token decimals
     pub decimals: u8,
+    /// Share of the trade fee in basis points paid to the referrer in the output token,
+    /// 0 to only reward the referrer in DELTAFI
+    pub referral_fee_bps: u16,
     /// Reserved for future use
-    pub reserved: [u8; 7],
+    pub reserved: [u8; 5],
     /// Trade reward numerator
     pub trade_reward_numerator: u64,
     /// Trade reward denominator
@@ -30,9 +40,6 @@ pub struct Rewards {
     pub trade_reward_cap: u64,
 }
 
-const REFERRAL_REWARD_NUMERATOR: u64 = 1;
-const REFERRAL_REWARD_DENOMINATOR: u64 = 20;
-
 impl Rewards {
     /// Create new rewards
     ///
@@ -47,6 +54,7 @@ impl Rewards {
         Rewards {
             is_initialized: params.is_initialized,
             decimals: params.decimals,
+            referral_fee_bps: params.referral_fee_bps,
             reserved: params.reserved,
             trade_reward_numerator: params.trade_reward_numerator,
             trade_reward_denominator: params.trade_reward_denominator,
@@ -88,12 +96,17 @@ impl Rewards {
         })
     }
 
-    /// Calculate the referral rewards.
+    /// Calculate the referral rewards at the base tier, for the referrers without stats.
     pub fn referral_reward(&self, trade_reward: u64) -> Result<u64, ProgramError> {
-        Decimal::from(trade_reward)
-            .try_mul(REFERRAL_REWARD_NUMERATOR)?
-            .try_div(REFERRAL_REWARD_DENOMINATOR)?
-            .try_floor_u64()
+        tiered_referral_reward(trade_reward, 0)
+    }
+
+    /// Calculate the referrer share of the trade fee paid in the output token
+    pub fn referral_fee(&self, trade_fee: u64) -> Result<u64, ProgramError> {
+        trade_fee
+            .checked_mul(self.referral_fee_bps as u64)
+            .map(|fee| fee / 10_000)
+            .ok_or_else(|| SwapError::CalculationFailure.into())
     }
 }
 
@@ -115,14 +128,16 @@ impl Pack for Rewards {
         let (
             is_initialized,
             decimals,
+            referral_fee_bps,
             reserved,
             trade_reward_numerator,
             trade_reward_denominator,
             trade_reward_cap,
-        ) = array_refs![input, 1, 1, 7, 8, 8, 8];
+        ) = array_refs![input, 1, 1, 2, 5, 8, 8, 8];
         Ok(Self {
             is_initialized: unpack_bool(is_initialized)?,
             decimals: u8::from_le_bytes(*decimals),
+            referral_fee_bps: u16::from_le_bytes(*referral_fee_bps),
             reserved: *reserved,
             trade_reward_numerator: u64::from_le_bytes(*trade_reward_numerator),
             trade_reward_denominator: u64::from_le_bytes(*trade_reward_denominator),
@@ -134,13 +149,15 @@ impl Pack for Rewards {
         let (
             is_initialized,
             decimals,
+            referral_fee_bps,
             reserved,
             trade_reward_numerator,
             trade_reward_denominator,
             trade_reward_cap,
-        ) = mut_array_refs![output, 1, 1, 7, 8, 8, 8];
+        ) = mut_array_refs![output, 1, 1, 2, 5, 8, 8, 8];
         pack_bool(self.is_initialized, is_initialized);
         *decimals = self.decimals.to_le_bytes();
+        *referral_fee_bps = self.referral_fee_bps.to_le_bytes();
         *reserved = self.reserved;
         *trade_reward_numerator = self.trade_reward_numerator.to_le_bytes();
         *trade_reward_denominator = self.trade_reward_denominator.to_le_bytes();
@@ -166,7 +183,8 @@ mod tests {
         let is_initialized = vec![1, rewards.is_initialized as u8];
         packed.extend_from_slice(&is_initialized[0].to_le_bytes());
         packed.extend_from_slice(&rewards.decimals.to_le_bytes());
-        packed.extend_from_slice(&[0u8; 7]);
+        packed.extend_from_slice(&rewards.referral_fee_bps.to_le_bytes());
+        packed.extend_from_slice(&[0u8; 5]);
         packed.extend_from_slice(&rewards.trade_reward_numerator.to_le_bytes());
         packed.extend_from_slice(&rewards.trade_reward_denominator.to_le_bytes());
         packed.extend_from_slice(&rewards.trade_reward_cap.to_le_bytes());
@@ -179,12 +197,21 @@ mod tests {
         let rewards = Rewards {
             is_initialized: true,
             decimals: 6,
-            reserved: [0u8; 7],
+            referral_fee_bps: 0,
+            reserved: [0u8; 5],
             trade_reward_numerator: 1,
             trade_reward_denominator: 100,
             trade_reward_cap: 1_000_000u64,
         };
-        assert_eq!(rewards.referral_reward(100), Ok(5));
+        assert_eq!(rewards.referral_reward(100), Ok(10));
+        assert_eq!(rewards.referral_fee(1_000), Ok(0));
+
+        let rewards = Rewards {
+            referral_fee_bps: 2_500,
+            ..rewards
+        };
+        assert_eq!(rewards.referral_fee(1_000), Ok(250));
+        assert_eq!(rewards.referral_fee(3), Ok(0));
     }
 
     #[test]
@@ -192,7 +219,8 @@ mod tests {
         let rewards = Rewards {
             is_initialized: true,
             decimals: 6,
-            reserved: [0u8; 7],
+            referral_fee_bps: 0,
+            reserved: [0u8; 5],
             trade_reward_numerator: 1,
             trade_reward_denominator: 100,
             trade_reward_cap: 1_000_000u64,
@@ -220,7 +248,8 @@ mod tests {
         let rewards = Rewards {
             is_initialized: true,
             decimals: 9,
-            reserved: [0u8; 7],
+            referral_fee_bps: 0,
+            reserved: [0u8; 5],
             trade_reward_numerator: 1,
             trade_reward_denominator: 100,
             trade_reward_cap: 1_000_000u64,
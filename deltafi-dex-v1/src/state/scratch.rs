@@ -0,0 +1,188 @@
+//! Reusable token account of the program for the intermediate custody of multi-instruction
+//! flows, e.g. the output of a route deposited into a pool in the same transaction
+
+use arrayref::{array_mut_ref, array_ref, array_refs, mut_array_refs};
+use solana_program::{
+    program_error::ProgramError,
+    program_pack::{IsInitialized, Pack, Sealed},
+    pubkey::{Pubkey, PUBKEY_BYTES},
+};
+
+use super::*;
+
+/// Scratch account of a mint, its token account is owned by the scratch account address and
+/// delegated to the holder between `AcquireScratch` and `ReleaseScratch`
+#[derive(Clone, Debug, Default, PartialEq)]
+pub struct ScratchAccount {
+    /// Initialization status
+    pub is_initialized: bool,
+    /// Bump seed of the scratch account address
+    pub bump_seed: u8,
+    /// Index of the scratch account among the ones of the mint
+    pub index: u8,
+    /// Config key
+    pub config_key: Pubkey,
+    /// Mint of the token account
+    pub mint: Pubkey,
+    /// Token account owned by the scratch account address
+    pub token_account: Pubkey,
+    /// Signer holding the scratch account, the default pubkey when free
+    pub holder: Pubkey,
+    /// Slot the scratch account was acquired at
+    pub acquired_slot: u64,
+}
+
+impl ScratchAccount {
+    /// The scratch account can be acquired when free, or when the holder left it empty
+    /// after an earlier slot. Holders release it in the transaction acquiring it.
+    pub fn can_acquire(&self, token_amount: u64, slot: u64) -> bool {
+        self.holder == Pubkey::default() || (token_amount == 0 && self.acquired_slot < slot)
+    }
+
+    /// Set the holder of the scratch account
+    pub fn acquire(&mut self, holder: Pubkey, slot: u64) {
+        self.holder = holder;
+        self.acquired_slot = slot;
+    }
+
+    /// Free the scratch account
+    pub fn release(&mut self) {
+        self.holder = Pubkey::default();
+        self.acquired_slot = 0;
+    }
+}
+
+impl Sealed for ScratchAccount {}
+impl IsInitialized for ScratchAccount {
+    fn is_initialized(&self) -> bool {
+        self.is_initialized
+    }
+}
+
+const SCRATCH_ACCOUNT_SIZE: usize = 1 + 1 + 1 + PUBKEY_BYTES * 4 + 8;
+
+impl Pack for ScratchAccount {
+    const LEN: usize = SCRATCH_ACCOUNT_SIZE;
+
+    fn pack_into_slice(&self, dst: &mut [u8]) {
+        let output = array_mut_ref![dst, 0, SCRATCH_ACCOUNT_SIZE];
+        #[allow(clippy::ptr_offset_with_cast)]
+        let (
+            is_initialized,
+            bump_seed,
+            index,
+            config_key,
+            mint,
+            token_account,
+            holder,
+            acquired_slot,
+        ) = mut_array_refs![
+            output,
+            1,
+            1,
+            1,
+            PUBKEY_BYTES,
+            PUBKEY_BYTES,
+            PUBKEY_BYTES,
+            PUBKEY_BYTES,
+            8
+        ];
+        pack_bool(self.is_initialized, is_initialized);
+        bump_seed[0] = self.bump_seed;
+        index[0] = self.index;
+        config_key.copy_from_slice(self.config_key.as_ref());
+        mint.copy_from_slice(self.mint.as_ref());
+        token_account.copy_from_slice(self.token_account.as_ref());
+        holder.copy_from_slice(self.holder.as_ref());
+        *acquired_slot = self.acquired_slot.to_le_bytes();
+    }
+
+    fn unpack_from_slice(src: &[u8]) -> Result<Self, ProgramError> {
+        let input = array_ref![src, 0, SCRATCH_ACCOUNT_SIZE];
+        #[allow(clippy::ptr_offset_with_cast)]
+        let (
+            is_initialized,
+            bump_seed,
+            index,
+            config_key,
+            mint,
+            token_account,
+            holder,
+            acquired_slot,
+        ) = array_refs![
+            input,
+            1,
+            1,
+            1,
+            PUBKEY_BYTES,
+            PUBKEY_BYTES,
+            PUBKEY_BYTES,
+            PUBKEY_BYTES,
+            8
+        ];
+        Ok(Self {
+            is_initialized: unpack_bool(is_initialized)?,
+            bump_seed: bump_seed[0],
+            index: index[0],
+            config_key: Pubkey::new_from_array(*config_key),
+            mint: Pubkey::new_from_array(*mint),
+            token_account: Pubkey::new_from_array(*token_account),
+            holder: Pubkey::new_from_array(*holder),
+            acquired_slot: u64::from_le_bytes(*acquired_slot),
+        })
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_scratch_account_packing() {
+        let scratch_account = ScratchAccount {
+            is_initialized: true,
+            bump_seed: 253,
+            index: 2,
+            config_key: Pubkey::new_unique(),
+            mint: Pubkey::new_unique(),
+            token_account: Pubkey::new_unique(),
+            holder: Pubkey::new_unique(),
+            acquired_slot: 1_000,
+        };
+
+        let mut packed = [0u8; ScratchAccount::LEN];
+        ScratchAccount::pack_into_slice(&scratch_account, &mut packed);
+        let unpacked = ScratchAccount::unpack(&packed).unwrap();
+        assert_eq!(scratch_account, unpacked);
+
+        let packed = [0u8; ScratchAccount::LEN];
+        let unpacked = ScratchAccount::unpack_unchecked(&packed).unwrap();
+        assert_eq!(unpacked, ScratchAccount::default());
+        assert_eq!(
+            ScratchAccount::unpack(&packed).unwrap_err(),
+            ProgramError::UninitializedAccount
+        );
+    }
+
+    #[test]
+    fn test_scratch_account_acquire() {
+        let mut scratch_account = ScratchAccount {
+            is_initialized: true,
+            ..ScratchAccount::default()
+        };
+        assert!(scratch_account.can_acquire(100, 10));
+
+        let holder = Pubkey::new_unique();
+        scratch_account.acquire(holder, 10);
+        assert_eq!(scratch_account.holder, holder);
+        // Held in the same slot, or with tokens left in custody
+        assert!(!scratch_account.can_acquire(0, 10));
+        assert!(!scratch_account.can_acquire(100, 11));
+        // Left empty by a holder of an earlier slot
+        assert!(scratch_account.can_acquire(0, 11));
+
+        scratch_account.release();
+        assert_eq!(scratch_account.holder, Pubkey::default());
+        assert!(scratch_account.can_acquire(100, 10));
+    }
+}
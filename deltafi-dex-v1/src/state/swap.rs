@@ -1,11 +1,14 @@
 use std::{
+    cell::{Ref, RefMut},
     cmp::Ordering,
     convert::{TryFrom, TryInto},
 };
 
 use arrayref::{array_mut_ref, array_ref, array_refs, mut_array_refs};
 use bitflags::bitflags;
+use bytemuck::{from_bytes, from_bytes_mut, Pod, Zeroable};
 use solana_program::{
+    account_info::AccountInfo,
     entrypoint::ProgramResult,
     program_error::ProgramError,
     program_pack::{IsInitialized, Pack, Sealed},
@@ -14,19 +17,48 @@ use solana_program::{
 
 use super::*;
 use crate::{
-    curve::{PoolState, SwapDirection},
+    adapter::ExchangeRateAdapterType,
+    curve::{PoolState, PriceJumpPolicy, SwapDirection, POOL_STATE_EMA_SIZE},
     error::SwapError,
-    math::{Decimal, TryDiv, TryMul},
+    math::{Decimal, TryDiv, TryMul, TrySub},
 };
 
+#[cfg(feature = "serde")]
+use serde::{Deserialize, Serialize};
+
+/// Slots between the commitment of a new pegged price and its application, about one day
+pub const PEGGED_PRICE_TIMELOCK_SLOTS: u64 = 216_000;
+
+/// Slots a user waits between two updates of its referrer, about one week
+pub const REFERRER_UPDATE_COOLDOWN_SLOTS: u64 = 1_512_000;
+
+/// Bisection steps searching the input of a partial fill swap, the fill is within
+/// `amount_in / 2^24` of the largest fillable input
+pub const PARTIAL_FILL_SEARCH_STEPS: usize = 24;
+
 /// SwapType enumerated definition
 #[repr(C)]
 #[derive(Copy, Clone, Debug, PartialEq)]
+#[cfg_attr(feature = "serde", derive(Serialize, Deserialize))]
 pub enum SwapType {
     /// Standard swap pool with external price guidence
     Normal,
     /// Stable swap pool
     Stable,
+    /// Pool trading at a constant price set by the admin, traded with the stable swap
+    /// instructions without any oracle account
+    Pegged,
+}
+
+impl SwapType {
+    /// Check the pool takes the instructions of the swap type, pegged pools are traded with
+    /// the stable swap instructions
+    pub fn is_traded_as(self, swap_type: SwapType) -> bool {
+        match self {
+            Self::Pegged => swap_type == Self::Stable,
+            _ => self == swap_type,
+        }
+    }
 }
 
 impl Default for SwapType {
@@ -42,6 +74,7 @@ impl TryFrom<u8> for SwapType {
         match curve_type {
             0 => Ok(SwapType::Normal),
             1 => Ok(SwapType::Stable),
+            2 => Ok(SwapType::Pegged),
             _ => Err(ProgramError::InvalidAccountData),
         }
     }
@@ -56,6 +89,28 @@ bitflags! {
         const PYTH_ONLY = 0b00000000;
         /// SERUM_ONLY = 0b1
         const SERUM_ONLY = 0b00000001;
+        /// PYTH_WITH_SERUM_FALLBACK = 0b10
+        const PYTH_WITH_SERUM_FALLBACK = 0b00000010;
+        /// MANUAL = 0b100, the price setter of the pool pushes the market price
+        const MANUAL = 0b00000100;
+        /// CHAINLINK = 0b1000, the chainlink feeds of the tokens take the pyth price accounts
+        const CHAINLINK = 0b00001000;
+        /// PYTH_PULL = 0b10000, the pyth price update accounts of the feeds of the pool are read
+        const PYTH_PULL = 0b00010000;
+    }
+}
+
+bitflags! {
+#[derive(Default)]
+#[repr(C)]
+    /// Operations paused independently of `is_paused`, set with `SetPauseFlags`
+    pub struct PauseFlag: u8 {
+        /// Swaps, quote fills, flash swaps and routes
+        const SWAP = 0b00000001;
+        /// Deposits
+        const DEPOSIT = 0b00000010;
+        /// Withdrawals
+        const WITHDRAW = 0b00000100;
     }
 }
 
@@ -71,10 +126,125 @@ impl OraclePriorityFlag {
     pub fn is_serum_only(&self) -> bool {
         self.contains(OraclePriorityFlag::SERUM_ONLY)
     }
+
+    /// is_pyth_with_serum_fallback
+    #[inline(always)]
+    pub fn is_pyth_with_serum_fallback(&self) -> bool {
+        self.contains(OraclePriorityFlag::PYTH_WITH_SERUM_FALLBACK)
+    }
+
+    /// check if the serum market accounts are used to get the price
+    #[inline(always)]
+    pub fn uses_serum(&self) -> bool {
+        self.is_serum_only() || self.is_pyth_with_serum_fallback()
+    }
+
+    /// is_manual
+    #[inline(always)]
+    pub fn is_manual(&self) -> bool {
+        self.contains(OraclePriorityFlag::MANUAL)
+    }
+
+    /// is_chainlink
+    #[inline(always)]
+    pub fn is_chainlink(&self) -> bool {
+        self.contains(OraclePriorityFlag::CHAINLINK)
+    }
+
+    /// is_pyth_pull
+    #[inline(always)]
+    pub fn is_pyth_pull(&self) -> bool {
+        self.contains(OraclePriorityFlag::PYTH_PULL)
+    }
+
+    /// check if the pyth price accounts are used to get the price
+    #[inline(always)]
+    pub fn uses_pyth(&self) -> bool {
+        !self.is_serum_only() && !self.is_manual() && !self.is_chainlink() && !self.is_pyth_pull()
+    }
+}
+
+/// Default slots elapsed after which a pyth price is stale.
+/// Each slot has minimum 400ms, so the stale timeout is 4s.
+pub const DEFAULT_PYTH_STALE_SLOTS: u16 = 10;
+/// Default max pyth confidence interval in basis points of the price
+pub const DEFAULT_PYTH_MAX_CONFIDENCE_BPS: u16 = 200;
+/// Default min number of active pyth publishers
+pub const DEFAULT_PYTH_MIN_PUBLISHERS: u8 = 3;
+/// Max number of pyth publishers of a price account
+pub const MAX_PYTH_PUBLISHERS: u8 = 32;
+/// Default slots elapsed after which a chainlink round is stale, about one minute.
+/// The chainlink feeds update on a deviation threshold or a heartbeat, less often than pyth.
+pub const DEFAULT_CHAINLINK_STALE_SLOTS: u16 = 150;
+/// Default seconds elapsed after the publish time of a pyth price update before it is stale
+pub const DEFAULT_PYTH_PULL_STALE_SECONDS: u16 = 30;
+
+/// Pyth price tolerances of a pool, a zero value falls back to the default
+#[derive(Clone, Copy, Debug, Default, PartialEq)]
+#[cfg_attr(feature = "serde", derive(Serialize, Deserialize))]
+pub struct OracleParams {
+    /// slots elapsed after which the pyth price or the chainlink round is stale, seconds
+    /// elapsed after the publish time of a pyth price update
+    pub stale_slots: u16,
+    /// max confidence interval in basis points of the price
+    pub max_confidence_bps: u16,
+    /// min number of active pyth publishers
+    pub min_publishers: u8,
+}
+
+impl OracleParams {
+    /// check the params are in range
+    pub fn validate(&self) -> ProgramResult {
+        if self.max_confidence_bps > 10_000 || self.min_publishers > MAX_PYTH_PUBLISHERS {
+            return Err(SwapError::InvalidInput.into());
+        }
+        Ok(())
+    }
+
+    /// slots elapsed after which the pyth price is stale
+    pub fn stale_after_slots(&self) -> u64 {
+        match self.stale_slots {
+            0 => DEFAULT_PYTH_STALE_SLOTS as u64,
+            stale_slots => stale_slots as u64,
+        }
+    }
+
+    /// slots elapsed after which the chainlink round is stale
+    pub fn chainlink_stale_after_slots(&self) -> u64 {
+        match self.stale_slots {
+            0 => DEFAULT_CHAINLINK_STALE_SLOTS as u64,
+            stale_slots => stale_slots as u64,
+        }
+    }
+
+    /// seconds elapsed after the publish time of a pyth price update before it is stale
+    pub fn pyth_pull_stale_after_seconds(&self) -> i64 {
+        match self.stale_slots {
+            0 => DEFAULT_PYTH_PULL_STALE_SECONDS as i64,
+            stale_slots => stale_slots as i64,
+        }
+    }
+
+    /// max confidence interval in basis points of the price
+    pub fn confidence_limit_bps(&self) -> u64 {
+        match self.max_confidence_bps {
+            0 => DEFAULT_PYTH_MAX_CONFIDENCE_BPS as u64,
+            max_confidence_bps => max_confidence_bps as u64,
+        }
+    }
+
+    /// min number of active pyth publishers
+    pub fn required_publishers(&self) -> usize {
+        match self.min_publishers {
+            0 => DEFAULT_PYTH_MIN_PUBLISHERS as usize,
+            min_publishers => min_publishers as usize,
+        }
+    }
 }
 
 /// User referrer data
 #[derive(Clone, Debug, Default, PartialEq)]
+#[cfg_attr(feature = "serde", derive(Serialize, Deserialize))]
 pub struct UserReferrerData {
     /// Initialized state
     pub is_initialized: bool,
@@ -84,9 +254,37 @@ pub struct UserReferrerData {
     pub owner: Pubkey,
     /// Referrer pubkey
     pub referrer: Pubkey,
+    /// Slot the referrer was last set or updated at
+    pub last_update_slot: u64,
 }
 
-const USER_REFERRER_DATA_LEN: usize = 1 + PUBKEY_BYTES * 3;
+/// Length of the user referrer data created before its referrer could be updated
+pub const LEGACY_USER_REFERRER_DATA_LEN: usize = 1 + PUBKEY_BYTES * 3;
+const USER_REFERRER_DATA_LEN: usize = LEGACY_USER_REFERRER_DATA_LEN + 8;
+
+impl UserReferrerData {
+    /// Unpacks the user referrer data of either length, the legacy data was never updated
+    pub fn unpack_any(src: &[u8]) -> Result<Self, ProgramError> {
+        if src.len() == LEGACY_USER_REFERRER_DATA_LEN {
+            let mut data = [0u8; USER_REFERRER_DATA_LEN];
+            data[..LEGACY_USER_REFERRER_DATA_LEN].copy_from_slice(src);
+            return Self::unpack(&data);
+        }
+        Self::unpack(src)
+    }
+
+    /// Check the referrer cooldown has passed since the last update
+    pub fn check_update_cooldown(&self, current_slot: u64) -> ProgramResult {
+        let next_update_slot = self
+            .last_update_slot
+            .checked_add(REFERRER_UPDATE_COOLDOWN_SLOTS)
+            .ok_or(SwapError::CalculationFailure)?;
+        if current_slot < next_update_slot {
+            return Err(SwapError::ReferrerUpdateCooldown.into());
+        }
+        Ok(())
+    }
+}
 
 impl Pack for UserReferrerData {
     const LEN: usize = USER_REFERRER_DATA_LEN;
@@ -95,27 +293,29 @@ impl Pack for UserReferrerData {
     fn unpack_from_slice(src: &[u8]) -> Result<Self, ProgramError> {
         let input = array_ref![src, 0, USER_REFERRER_DATA_LEN];
         #[allow(clippy::ptr_offset_with_cast)]
-        let (is_initialized, config_key, owner, referrer) =
-            array_refs![input, 1, PUBKEY_BYTES, PUBKEY_BYTES, PUBKEY_BYTES];
+        let (is_initialized, config_key, owner, referrer, last_update_slot) =
+            array_refs![input, 1, PUBKEY_BYTES, PUBKEY_BYTES, PUBKEY_BYTES, 8];
 
         Ok(Self {
             is_initialized: unpack_bool(is_initialized)?,
             config_key: Pubkey::new_from_array(*config_key),
             owner: Pubkey::new_from_array(*owner),
             referrer: Pubkey::new_from_array(*referrer),
+            last_update_slot: u64::from_le_bytes(*last_update_slot),
         })
     }
 
     fn pack_into_slice(&self, dst: &mut [u8]) {
         let output = array_mut_ref![dst, 0, USER_REFERRER_DATA_LEN];
         #[allow(clippy::ptr_offset_with_cast)]
-        let (is_initialized, config_key, owner, referrer) =
-            mut_array_refs![output, 1, PUBKEY_BYTES, PUBKEY_BYTES, PUBKEY_BYTES];
+        let (is_initialized, config_key, owner, referrer, last_update_slot) =
+            mut_array_refs![output, 1, PUBKEY_BYTES, PUBKEY_BYTES, PUBKEY_BYTES, 8];
 
         pack_bool(self.is_initialized, is_initialized);
         config_key.copy_from_slice(self.config_key.as_ref());
         owner.copy_from_slice(self.owner.as_ref());
         referrer.copy_from_slice(self.referrer.as_ref());
+        *last_update_slot = self.last_update_slot.to_le_bytes();
     }
 }
 
@@ -129,6 +329,7 @@ impl IsInitialized for UserReferrerData {
 /// Swap states.
 #[repr(C)]
 #[derive(Clone, Debug, Default, PartialEq)]
+#[cfg_attr(feature = "serde", derive(Serialize, Deserialize))]
 pub struct SwapInfo {
     /// Initialized state
     pub is_initialized: bool,
@@ -192,7 +393,112 @@ pub struct SwapInfo {
     /// Public key combined from serumMarket, serumBids and serumAsks together
     pub serum_combined_address: Pubkey,
 
-    /// reserved u8 array for alignment
+    /// policy applied when the oracle price jumps too fast
+    pub price_jump_policy: PriceJumpPolicy,
+
+    /// max divergence in basis points between pyth and serum prices
+    pub max_price_divergence: u16,
+
+    /// exchange rate adapter pricing the base token of a stable pool
+    pub exchange_rate_adapter: ExchangeRateAdapterType,
+
+    /// pyth price tolerances
+    pub oracle_params: OracleParams,
+
+    /// set while a flash swap hands control to its callback, a locked pool cannot be unpacked
+    pub is_locked: bool,
+
+    /// price committed for a pegged pool, applied once the timelock is over
+    pub future_pegged_price: Decimal,
+    /// first slot the committed pegged price can be applied, 0 if no price is committed
+    pub pegged_price_apply_slot: u64,
+
+    /// swaps must send the output to a token account owned by a signer of the transaction,
+    /// off by default to keep the pool composable with other programs
+    pub strict_destination_owner: bool,
+
+    /// swaps are paused, unlike `is_paused` deposits and withdrawals are left open
+    pub pause_swap: bool,
+    /// deposits are paused
+    pub pause_deposit: bool,
+    /// withdrawals are paused
+    pub pause_withdraw: bool,
+
+    /// token A is the quote token of the pair, e.g. USDC/SOL listed as SOL/USDC. The pool
+    /// math stays in token A/B terms, only the serum market and the quoted price are read
+    /// in the conventional order of the pair
+    pub quote_is_token_a: bool,
+
+    /// fees and rewards are pinned to the custom values set on the pool, skipped when
+    /// syncing the pools from the config defaults
+    pub fee_override: bool,
+
+    /// min deviation in basis points of the pool mid price from the oracle price for the
+    /// permissionless rebalance, 0 when the rebalance is off
+    pub rebalance_threshold_bps: u16,
+
+    /// max deviation in basis points of the swap execution price from the reference price of
+    /// the circuit breaker window, 0 when the circuit breaker is off
+    pub circuit_breaker_bps: u16,
+    /// length in slots of the circuit breaker window
+    pub circuit_breaker_window_slots: u64,
+    /// market price at the start of the circuit breaker window, zero before the first swap
+    pub circuit_breaker_price: Decimal,
+    /// first slot of the circuit breaker window
+    pub circuit_breaker_slot: u64,
+    /// slot the circuit breaker paused the swaps at, 0 if it did not trip
+    pub circuit_breaker_tripped_slot: u64,
+
+    /// max total supply of the pool tokens the deposits can mint up to, 0 when the deposits
+    /// are not capped
+    pub max_total_supply: u64,
+
+    /// swaps and deposits require a whitelist entry of the user
+    pub whitelist_enabled: bool,
+
+    /// layout version, 0 for the pools created before the versioning until `MigrateSwapInfo`
+    pub version: u8,
+
+    /// key allowed to push the market price of a MANUAL oracle pool with `SetManualPrice`
+    pub price_setter: Pubkey,
+    /// max change in basis points of a pushed market price from the previous one
+    pub manual_max_change_bps: u16,
+    /// slots after which the pushed market price is stale
+    pub manual_stale_slots: u64,
+    /// market price of token A in token B pushed by the price setter, zero before the first push
+    pub manual_price: Decimal,
+    /// slot of the last pushed market price
+    pub manual_price_slot: u64,
+    /// slots for the moving average of the oracle price to fully follow the price, the curve
+    /// is repriced at the average, 0 reprices it at the oracle price
+    pub ema_window_slots: u64,
+
+    /// token A amount swapped in since the pool creation
+    pub volume_a_in: u128,
+    /// token B amount swapped in since the pool creation
+    pub volume_b_in: u128,
+    /// token A trade fees collected since the pool creation, the admin, protocol and referral
+    /// shares included
+    pub fees_collected_a: u128,
+    /// token B trade fees collected since the pool creation, the admin, protocol and referral
+    /// shares included
+    pub fees_collected_b: u128,
+    /// number of swaps since the pool creation
+    pub swap_count: u64,
+    /// price of token A in token B realized by the last swap after the trade fee, in atomic
+    /// units like the market price of the pool state
+    pub last_trade_price: Decimal,
+
+    /// virtual price of the pool token of a stable pool after its last state transition,
+    /// zero for the normal pools
+    pub virtual_price: Decimal,
+
+    /// whether the swaps skim the surplus of the pool token accounts over the reserves and
+    /// fees to the admin fee account of the destination and the protocol fees of the source
+    /// before trading
+    pub skim_surplus: bool,
+
+    /// reserved u8 array for alignment, too short for most new fields which grow the layout
     pub reserved_u8: [u8; SWAP_INFO_RESERVED_U8],
 
     /// rest of reserved bytes
@@ -225,6 +531,250 @@ impl SwapInfo {
             Ordering::Equal | Ordering::Less => Err(SwapError::ExceededSwapOutAmount.into()),
         }
     }
+
+    /// check if the pool token supply exceeds the deposit cap
+    pub fn check_deposit_cap(&self) -> ProgramResult {
+        // value 0 means no cap, this makes it compatible with the old version
+        if self.max_total_supply != 0 && self.pool_state.total_supply > self.max_total_supply {
+            return Err(SwapError::DepositCapExceeded.into());
+        }
+        Ok(())
+    }
+
+    /// Check the execution price of a swap against the reference price of the circuit breaker
+    /// window. A new window starts at the market price once the last one is over. The breaker
+    /// trips on a deviation above the max, the swap then fails with `CircuitBreakerTripped`.
+    ///
+    /// Returns if the circuit breaker tripped.
+    pub fn check_circuit_breaker(
+        &mut self,
+        amount_in: u64,
+        amount_out: u64,
+        swap_direction: SwapDirection,
+        current_slot: u64,
+    ) -> Result<bool, ProgramError> {
+        if self.circuit_breaker_bps == 0 {
+            return Ok(false);
+        }
+        if self.is_circuit_breaker_window_over(current_slot) {
+            self.circuit_breaker_price = self.pool_state.market_price;
+            self.circuit_breaker_slot = current_slot;
+        }
+        if !self.exceeds_circuit_breaker(
+            self.circuit_breaker_price,
+            amount_in,
+            amount_out,
+            swap_direction,
+        )? {
+            return Ok(false);
+        }
+
+        self.pause_swap = true;
+        self.circuit_breaker_tripped_slot = current_slot;
+        Ok(true)
+    }
+
+    fn is_circuit_breaker_window_over(&self, current_slot: u64) -> bool {
+        self.circuit_breaker_price == Decimal::zero()
+            || current_slot.saturating_sub(self.circuit_breaker_slot)
+                > self.circuit_breaker_window_slots
+    }
+
+    /// Check if the execution price of a swap deviates from the reference price by more than
+    /// the max of the circuit breaker
+    fn exceeds_circuit_breaker(
+        &self,
+        reference_price: Decimal,
+        amount_in: u64,
+        amount_out: u64,
+        swap_direction: SwapDirection,
+    ) -> Result<bool, ProgramError> {
+        if self.circuit_breaker_bps == 0 || amount_in == 0 || amount_out == 0 {
+            return Ok(false);
+        }
+
+        let price = match swap_direction {
+            SwapDirection::SellBase => Decimal::from(amount_out).try_div(amount_in)?,
+            SwapDirection::SellQuote => Decimal::from(amount_in).try_div(amount_out)?,
+        };
+        let diff = if price > reference_price {
+            price.try_sub(reference_price)?
+        } else {
+            reference_price.try_sub(price)?
+        };
+        Ok(diff.try_mul(10_000u64)? > reference_price.try_mul(self.circuit_breaker_bps as u64)?)
+    }
+
+    /// Largest part of `amount_in` a partial fill swap takes in, keeping the output under the
+    /// swap out limit and the execution price within the circuit breaker band. The limit is
+    /// checked on the output before the trade fee, an input the curve cannot price is not
+    /// filled.
+    pub fn partial_fill_amount_in(
+        &self,
+        amount_in: u64,
+        swap_direction: SwapDirection,
+        current_slot: u64,
+    ) -> Result<u64, ProgramError> {
+        let reference_price = if self.is_circuit_breaker_window_over(current_slot) {
+            self.pool_state.market_price
+        } else {
+            self.circuit_breaker_price
+        };
+        let fills = |amount_in: u64| -> Result<bool, ProgramError> {
+            let amount_out = match self.pool_state.get_out_amount(amount_in, swap_direction) {
+                Ok(amount_out) => amount_out,
+                Err(_) => return Ok(false),
+            };
+            Ok(self
+                .check_swap_out_amount(amount_out, swap_direction)
+                .is_ok()
+                && !self.exceeds_circuit_breaker(
+                    reference_price,
+                    amount_in,
+                    amount_out,
+                    swap_direction,
+                )?)
+        };
+        if fills(amount_in)? {
+            return Ok(amount_in);
+        }
+
+        // The output grows with the input, `low` always fills and `high` never does
+        let (mut low, mut high) = (0, amount_in);
+        for _ in 0..PARTIAL_FILL_SEARCH_STEPS {
+            if high - low <= 1 {
+                break;
+            }
+            let mid = low + (high - low) / 2;
+            if fills(mid)? {
+                low = mid;
+            } else {
+                high = mid;
+            }
+        }
+        Ok(low)
+    }
+
+    /// Clear the trip of the circuit breaker, the next swap starts a new window
+    pub fn reset_circuit_breaker(&mut self) {
+        self.circuit_breaker_price = Decimal::zero();
+        self.circuit_breaker_slot = 0;
+        self.circuit_breaker_tripped_slot = 0;
+    }
+
+    /// Pushed market price of a MANUAL oracle pool and its slot, rejected before the first
+    /// push and once stale
+    pub fn manual_market_price(&self, current_slot: u64) -> Result<(Decimal, u64), ProgramError> {
+        if self.manual_price == Decimal::zero()
+            || current_slot.saturating_sub(self.manual_price_slot) >= self.manual_stale_slots
+        {
+            return Err(SwapError::StaleManualPrice.into());
+        }
+        Ok((self.manual_price, self.manual_price_slot))
+    }
+
+    /// Check the change of a market price pushed by the price setter from the previous one,
+    /// the first push is bounded by the last market price of the pool
+    pub fn check_manual_price_change(&self, market_price: Decimal) -> ProgramResult {
+        let last_price = if self.manual_price == Decimal::zero() {
+            self.pool_state.last_market_price
+        } else {
+            self.manual_price
+        };
+        let price_diff = if market_price > last_price {
+            market_price.try_sub(last_price)?
+        } else {
+            last_price.try_sub(market_price)?
+        };
+        if price_diff.try_mul(10_000u64)? > last_price.try_mul(self.manual_max_change_bps as u64)? {
+            return Err(SwapError::ManualPriceChangeTooLarge.into());
+        }
+        Ok(())
+    }
+
+    /// base and quote mints in the conventional order of the pair
+    pub fn base_quote_mints(&self) -> (Pubkey, Pubkey) {
+        if self.quote_is_token_a {
+            (self.token_b_mint, self.token_a_mint)
+        } else {
+            (self.token_a_mint, self.token_b_mint)
+        }
+    }
+
+    /// market price as quote per base in the conventional order of the pair
+    pub fn conventional_market_price(&self) -> Result<Decimal, ProgramError> {
+        if self.quote_is_token_a {
+            Decimal::one().try_div(self.pool_state.market_price)
+        } else {
+            Ok(self.pool_state.market_price)
+        }
+    }
+
+    /// exchange rate account read by the adapter, stable pools have no pyth
+    /// oracles so the account is kept in the pyth_a slot
+    pub fn exchange_rate_account(&self) -> &Pubkey {
+        &self.pyth_a
+    }
+
+    /// Record a swap in the cumulative stats of the pool, the trade fee is in the destination
+    /// token and the output is after the trade fee
+    pub fn record_swap(
+        &mut self,
+        amount_in: u64,
+        amount_out: u64,
+        trade_fee: u64,
+        swap_direction: SwapDirection,
+    ) -> ProgramResult {
+        // The stats never fail a swap, a u128 total does not overflow in practice
+        let (amount_a, amount_b) = match swap_direction {
+            SwapDirection::SellBase => {
+                self.volume_a_in = self.volume_a_in.saturating_add(amount_in as u128);
+                self.fees_collected_b = self.fees_collected_b.saturating_add(trade_fee as u128);
+                (amount_in, amount_out)
+            }
+            SwapDirection::SellQuote => {
+                self.volume_b_in = self.volume_b_in.saturating_add(amount_in as u128);
+                self.fees_collected_a = self.fees_collected_a.saturating_add(trade_fee as u128);
+                (amount_out, amount_in)
+            }
+        };
+        self.swap_count = self.swap_count.saturating_add(1);
+        if amount_a > 0 {
+            self.last_trade_price = Decimal::from(amount_b).try_div(amount_a)?;
+        }
+        Ok(())
+    }
+
+    /// Persist the virtual price of the pool token of a stable pool after a state transition
+    pub fn update_virtual_price(&mut self) -> ProgramResult {
+        if self.swap_type.is_traded_as(SwapType::Stable) {
+            self.virtual_price = self.pool_state.get_virtual_price()?;
+        }
+        Ok(())
+    }
+
+    /// set the exchange rate adapter and its rate account
+    pub fn set_exchange_rate_adapter(
+        &mut self,
+        adapter_type: ExchangeRateAdapterType,
+        rate_account: Pubkey,
+    ) {
+        self.exchange_rate_adapter = adapter_type;
+        self.pyth_a = rate_account;
+    }
+}
+
+impl SwapInfo {
+    /// Unpacks the swap info of the current or a legacy length, the fields missing from the
+    /// legacy layout are zero
+    pub fn unpack_any(src: &[u8]) -> Result<Self, ProgramError> {
+        if LEGACY_SWAP_INFO_LENS.contains(&src.len()) {
+            let mut data = [0u8; SWAP_INFO_SIZE];
+            data[..src.len()].copy_from_slice(src);
+            return Self::unpack(&data);
+        }
+        Self::unpack(src)
+    }
 }
 
 impl Sealed for SwapInfo {}
@@ -234,10 +784,23 @@ impl IsInitialized for SwapInfo {
     }
 }
 
-/// this should not be changed
-const SWAP_INFO_SIZE: usize = 615;
+/// Layout version of the pools initialized or migrated by the program, bumped with every
+/// growth of the layout: 1 at 871 bytes with the version, 2 at 935 bytes with the moving
+/// average of the oracle price and 3 at 999 bytes with the swap stats
+pub const SWAP_INFO_VERSION: u8 = 3;
+
+/// Lengths of the pools created before the last growth of the layout, their missing fields
+/// are zero and `MigrateSwapInfo` reallocates them to the current length
+pub const LEGACY_SWAP_INFO_LENS: [usize; 6] = [615, 679, 743, 807, 871, 935];
+
+/// Length of the pool layout, grown from 615 bytes once the reserved bytes ran out for the
+/// pool fee accrual, from 679 bytes for the protocol fee accrual, from 743 bytes for the
+/// circuit breaker, from 807 bytes for the version, from 871 bytes for the moving average
+/// of the oracle price and from 935 bytes for the swap stats. A growth adds the previous
+/// length to `LEGACY_SWAP_INFO_LENS` and bumps `SWAP_INFO_VERSION`.
+const SWAP_INFO_SIZE: usize = 999;
 /// this should be updated every time we add new field
-const USED_BYTES: usize = 587;
+const USED_BYTES: usize = 998;
 const SWAP_INFO_RESERVED_BYTES: usize = SWAP_INFO_SIZE - USED_BYTES;
 
 const SWAP_INFO_RESERVED_U64: usize = SWAP_INFO_RESERVED_BYTES / 8;
@@ -273,6 +836,45 @@ impl Pack for SwapInfo {
             swap_out_limit_percentage,
             oracle_priority_flags,
             serum_combined_address,
+            price_jump_policy,
+            max_price_divergence,
+            exchange_rate_adapter,
+            stale_slots,
+            max_confidence_bps,
+            min_publishers,
+            is_locked,
+            future_pegged_price,
+            pegged_price_apply_slot,
+            strict_destination_owner,
+            pause_swap,
+            pause_deposit,
+            pause_withdraw,
+            quote_is_token_a,
+            fee_override,
+            rebalance_threshold_bps,
+            circuit_breaker_bps,
+            circuit_breaker_window_slots,
+            circuit_breaker_price,
+            circuit_breaker_slot,
+            circuit_breaker_tripped_slot,
+            max_total_supply,
+            whitelist_enabled,
+            version,
+            price_setter,
+            manual_max_change_bps,
+            manual_stale_slots,
+            manual_price,
+            manual_price_slot,
+            ema_window_slots,
+            pool_state_ema,
+            volume_a_in,
+            volume_b_in,
+            fees_collected_a,
+            fees_collected_b,
+            swap_count,
+            last_trade_price,
+            virtual_price,
+            skim_surplus,
             _,
         ) = array_refs![
             input,
@@ -298,8 +900,56 @@ impl Pack for SwapInfo {
             1,
             1,
             PUBKEY_BYTES,
+            1,
+            2,
+            1,
+            2,
+            2,
+            1,
+            1,
+            16,
+            8,
+            1,
+            1,
+            1,
+            1,
+            1,
+            1,
+            2,
+            2,
+            8,
+            16,
+            8,
+            8,
+            8,
+            1,
+            1,
+            PUBKEY_BYTES,
+            2,
+            8,
+            16,
+            8,
+            8,
+            POOL_STATE_EMA_SIZE,
+            16,
+            16,
+            16,
+            16,
+            8,
+            16,
+            16,
+            1,
             SWAP_INFO_RESERVED_BYTES
         ];
+        // Reject every instruction on the pool while a flash swap is in progress
+        if unpack_bool(is_locked)? {
+            return Err(SwapError::ReentrancyLocked.into());
+        }
+        if version[0] > SWAP_INFO_VERSION {
+            return Err(ProgramError::InvalidAccountData);
+        }
+        let mut pool_state = PoolState::unpack_from_slice(pool_state)?;
+        pool_state.unpack_ema_from_slice(pool_state_ema);
         Ok(Self {
             is_initialized: unpack_bool(is_initialized)?,
             is_paused: unpack_bool(is_paused)?,
@@ -317,12 +967,51 @@ impl Pack for SwapInfo {
             admin_fee_key_b: Pubkey::new_from_array(*admin_fee_key_b),
             fees: Fees::unpack_from_slice(fees)?,
             rewards: Rewards::unpack_from_slice(rewards)?,
-            pool_state: PoolState::unpack_from_slice(pool_state)?,
+            pool_state,
             token_a_decimals: u8::from_le_bytes(*token_a_decimals),
             token_b_decimals: u8::from_le_bytes(*token_b_decimals),
             swap_out_limit_percentage: u8::from_le_bytes(*swap_out_limit_percentage),
             oracle_priority_flags: u8::from_le_bytes(*oracle_priority_flags),
             serum_combined_address: Pubkey::new_from_array(*serum_combined_address),
+            price_jump_policy: price_jump_policy[0].try_into()?,
+            max_price_divergence: u16::from_le_bytes(*max_price_divergence),
+            exchange_rate_adapter: exchange_rate_adapter[0].try_into()?,
+            oracle_params: OracleParams {
+                stale_slots: u16::from_le_bytes(*stale_slots),
+                max_confidence_bps: u16::from_le_bytes(*max_confidence_bps),
+                min_publishers: min_publishers[0],
+            },
+            future_pegged_price: unpack_decimal(future_pegged_price),
+            pegged_price_apply_slot: u64::from_le_bytes(*pegged_price_apply_slot),
+            strict_destination_owner: unpack_bool(strict_destination_owner)?,
+            pause_swap: unpack_bool(pause_swap)?,
+            pause_deposit: unpack_bool(pause_deposit)?,
+            pause_withdraw: unpack_bool(pause_withdraw)?,
+            quote_is_token_a: unpack_bool(quote_is_token_a)?,
+            fee_override: unpack_bool(fee_override)?,
+            rebalance_threshold_bps: u16::from_le_bytes(*rebalance_threshold_bps),
+            circuit_breaker_bps: u16::from_le_bytes(*circuit_breaker_bps),
+            circuit_breaker_window_slots: u64::from_le_bytes(*circuit_breaker_window_slots),
+            circuit_breaker_price: unpack_decimal(circuit_breaker_price),
+            circuit_breaker_slot: u64::from_le_bytes(*circuit_breaker_slot),
+            circuit_breaker_tripped_slot: u64::from_le_bytes(*circuit_breaker_tripped_slot),
+            max_total_supply: u64::from_le_bytes(*max_total_supply),
+            whitelist_enabled: unpack_bool(whitelist_enabled)?,
+            version: version[0],
+            price_setter: Pubkey::new_from_array(*price_setter),
+            manual_max_change_bps: u16::from_le_bytes(*manual_max_change_bps),
+            manual_stale_slots: u64::from_le_bytes(*manual_stale_slots),
+            manual_price: unpack_decimal(manual_price),
+            manual_price_slot: u64::from_le_bytes(*manual_price_slot),
+            ema_window_slots: u64::from_le_bytes(*ema_window_slots),
+            volume_a_in: u128::from_le_bytes(*volume_a_in),
+            volume_b_in: u128::from_le_bytes(*volume_b_in),
+            fees_collected_a: u128::from_le_bytes(*fees_collected_a),
+            fees_collected_b: u128::from_le_bytes(*fees_collected_b),
+            swap_count: u64::from_le_bytes(*swap_count),
+            last_trade_price: unpack_decimal(last_trade_price),
+            virtual_price: unpack_decimal(virtual_price),
+            skim_surplus: unpack_bool(skim_surplus)?,
             ..Self::default()
         })
     }
@@ -353,6 +1042,45 @@ impl Pack for SwapInfo {
             swap_out_limit_percentage,
             oracle_priority_flags,
             serum_combined_address,
+            price_jump_policy,
+            max_price_divergence,
+            exchange_rate_adapter,
+            stale_slots,
+            max_confidence_bps,
+            min_publishers,
+            is_locked,
+            future_pegged_price,
+            pegged_price_apply_slot,
+            strict_destination_owner,
+            pause_swap,
+            pause_deposit,
+            pause_withdraw,
+            quote_is_token_a,
+            fee_override,
+            rebalance_threshold_bps,
+            circuit_breaker_bps,
+            circuit_breaker_window_slots,
+            circuit_breaker_price,
+            circuit_breaker_slot,
+            circuit_breaker_tripped_slot,
+            max_total_supply,
+            whitelist_enabled,
+            version,
+            price_setter,
+            manual_max_change_bps,
+            manual_stale_slots,
+            manual_price,
+            manual_price_slot,
+            ema_window_slots,
+            pool_state_ema,
+            volume_a_in,
+            volume_b_in,
+            fees_collected_a,
+            fees_collected_b,
+            swap_count,
+            last_trade_price,
+            virtual_price,
+            skim_surplus,
             _,
         ) = mut_array_refs![
             output,
@@ -378,6 +1106,45 @@ impl Pack for SwapInfo {
             1,
             1,
             PUBKEY_BYTES,
+            1,
+            2,
+            1,
+            2,
+            2,
+            1,
+            1,
+            16,
+            8,
+            1,
+            1,
+            1,
+            1,
+            1,
+            1,
+            2,
+            2,
+            8,
+            16,
+            8,
+            8,
+            8,
+            1,
+            1,
+            PUBKEY_BYTES,
+            2,
+            8,
+            16,
+            8,
+            8,
+            POOL_STATE_EMA_SIZE,
+            16,
+            16,
+            16,
+            16,
+            8,
+            16,
+            16,
+            1,
             SWAP_INFO_RESERVED_BYTES
         ];
         pack_bool(self.is_initialized, is_initialized);
@@ -402,6 +1169,241 @@ impl Pack for SwapInfo {
         swap_out_limit_percentage.copy_from_slice(&self.swap_out_limit_percentage.to_le_bytes());
         oracle_priority_flags.copy_from_slice(&self.oracle_priority_flags.to_le_bytes());
         serum_combined_address.copy_from_slice(self.serum_combined_address.as_ref());
+        price_jump_policy[0] = self.price_jump_policy as u8;
+        max_price_divergence.copy_from_slice(&self.max_price_divergence.to_le_bytes());
+        exchange_rate_adapter[0] = self.exchange_rate_adapter as u8;
+        stale_slots.copy_from_slice(&self.oracle_params.stale_slots.to_le_bytes());
+        max_confidence_bps.copy_from_slice(&self.oracle_params.max_confidence_bps.to_le_bytes());
+        min_publishers[0] = self.oracle_params.min_publishers;
+        pack_bool(self.is_locked, is_locked);
+        pack_decimal(self.future_pegged_price, future_pegged_price);
+        *pegged_price_apply_slot = self.pegged_price_apply_slot.to_le_bytes();
+        pack_bool(self.strict_destination_owner, strict_destination_owner);
+        pack_bool(self.pause_swap, pause_swap);
+        pack_bool(self.pause_deposit, pause_deposit);
+        pack_bool(self.pause_withdraw, pause_withdraw);
+        pack_bool(self.quote_is_token_a, quote_is_token_a);
+        pack_bool(self.fee_override, fee_override);
+        *rebalance_threshold_bps = self.rebalance_threshold_bps.to_le_bytes();
+        *circuit_breaker_bps = self.circuit_breaker_bps.to_le_bytes();
+        *circuit_breaker_window_slots = self.circuit_breaker_window_slots.to_le_bytes();
+        pack_decimal(self.circuit_breaker_price, circuit_breaker_price);
+        *circuit_breaker_slot = self.circuit_breaker_slot.to_le_bytes();
+        *circuit_breaker_tripped_slot = self.circuit_breaker_tripped_slot.to_le_bytes();
+        *max_total_supply = self.max_total_supply.to_le_bytes();
+        pack_bool(self.whitelist_enabled, whitelist_enabled);
+        version[0] = self.version;
+        price_setter.copy_from_slice(self.price_setter.as_ref());
+        *manual_max_change_bps = self.manual_max_change_bps.to_le_bytes();
+        *manual_stale_slots = self.manual_stale_slots.to_le_bytes();
+        pack_decimal(self.manual_price, manual_price);
+        *manual_price_slot = self.manual_price_slot.to_le_bytes();
+        *ema_window_slots = self.ema_window_slots.to_le_bytes();
+        self.pool_state.pack_ema_into_slice(pool_state_ema);
+        *volume_a_in = self.volume_a_in.to_le_bytes();
+        *volume_b_in = self.volume_b_in.to_le_bytes();
+        *fees_collected_a = self.fees_collected_a.to_le_bytes();
+        *fees_collected_b = self.fees_collected_b.to_le_bytes();
+        *swap_count = self.swap_count.to_le_bytes();
+        pack_decimal(self.last_trade_price, last_trade_price);
+        pack_decimal(self.virtual_price, virtual_price);
+        pack_bool(self.skim_surplus, skim_surplus);
+    }
+}
+
+/// Zero-copy view of the packed [SwapInfo](struct.SwapInfo.html), cast from the account data
+/// without a copy like the serum market state. Every field is a key or a byte array, so the
+/// view has the alignment of a byte and no padding, the layout is the one of `Pack`.
+/// The hot paths read and write the fields they use through the view, the other
+/// instructions keep unpacking the whole pool.
+#[repr(C)]
+#[derive(Clone, Copy)]
+pub struct SwapInfoView {
+    is_initialized: [u8; 1],
+    is_paused: [u8; 1],
+    nonce: [u8; 1],
+    swap_type: [u8; 1],
+    /// Market config
+    pub config_key: Pubkey,
+    /// Token A
+    pub token_a: Pubkey,
+    /// Token B
+    pub token_b: Pubkey,
+    /// Pyth A price
+    pub pyth_a: Pubkey,
+    /// Pyth B price
+    pub pyth_b: Pubkey,
+    /// Pool token mint
+    pub pool_mint: Pubkey,
+    /// Mint of token A
+    pub token_a_mint: Pubkey,
+    /// Mint of token B
+    pub token_b_mint: Pubkey,
+    /// Admin fee account of token A
+    pub admin_fee_key_a: Pubkey,
+    /// Admin fee account of token B
+    pub admin_fee_key_b: Pubkey,
+    fees: [u8; Fees::LEN],
+    rewards: [u8; Rewards::LEN],
+    pool_state: [u8; PoolState::LEN],
+    token_a_decimals: [u8; 1],
+    token_b_decimals: [u8; 1],
+    swap_out_limit_percentage: [u8; 1],
+    oracle_priority_flags: [u8; 1],
+    /// Public key combined from serumMarket, serumBids and serumAsks together
+    pub serum_combined_address: Pubkey,
+    price_jump_policy: [u8; 1],
+    max_price_divergence: [u8; 2],
+    exchange_rate_adapter: [u8; 1],
+    stale_slots: [u8; 2],
+    max_confidence_bps: [u8; 2],
+    min_publishers: [u8; 1],
+    is_locked: [u8; 1],
+    future_pegged_price: [u8; 16],
+    pegged_price_apply_slot: [u8; 8],
+    strict_destination_owner: [u8; 1],
+    pause_swap: [u8; 1],
+    pause_deposit: [u8; 1],
+    pause_withdraw: [u8; 1],
+    quote_is_token_a: [u8; 1],
+    fee_override: [u8; 1],
+    rebalance_threshold_bps: [u8; 2],
+    circuit_breaker_bps: [u8; 2],
+    circuit_breaker_window_slots: [u8; 8],
+    circuit_breaker_price: [u8; 16],
+    circuit_breaker_slot: [u8; 8],
+    circuit_breaker_tripped_slot: [u8; 8],
+    max_total_supply: [u8; 8],
+    whitelist_enabled: [u8; 1],
+    version: [u8; 1],
+    /// Price setter of a MANUAL oracle pool
+    pub price_setter: Pubkey,
+    manual_max_change_bps: [u8; 2],
+    manual_stale_slots: [u8; 8],
+    manual_price: [u8; 16],
+    manual_price_slot: [u8; 8],
+    ema_window_slots: [u8; 8],
+    pool_state_ema: [u8; POOL_STATE_EMA_SIZE],
+    volume_a_in: [u8; 16],
+    volume_b_in: [u8; 16],
+    fees_collected_a: [u8; 16],
+    fees_collected_b: [u8; 16],
+    swap_count: [u8; 8],
+    last_trade_price: [u8; 16],
+    virtual_price: [u8; 16],
+    skim_surplus: [u8; 1],
+    reserved: [u8; SWAP_INFO_RESERVED_BYTES],
+}
+
+// Safe as every field is a byte array or a key, any bytes are a valid view
+unsafe impl Zeroable for SwapInfoView {}
+unsafe impl Pod for SwapInfoView {}
+
+impl SwapInfoView {
+    /// Casts the data of a pool, rejecting the same pools as `SwapInfo::unpack`
+    pub fn from_slice(data: &[u8]) -> Result<&Self, ProgramError> {
+        Self::check(data)?;
+        Ok(from_bytes(data))
+    }
+
+    /// Casts the data of a pool for writing, rejecting the same pools as `SwapInfo::unpack`
+    pub fn from_slice_mut(data: &mut [u8]) -> Result<&mut Self, ProgramError> {
+        Self::check(data)?;
+        Ok(from_bytes_mut(data))
+    }
+
+    /// Borrows the view of the pool account
+    pub fn load<'a>(swap_info: &'a AccountInfo) -> Result<Ref<'a, Self>, ProgramError> {
+        let data = swap_info.try_borrow_data()?;
+        Self::check(&data)?;
+        Ok(Ref::map(data, |data| from_bytes(data)))
+    }
+
+    /// Borrows the view of the pool account for writing
+    pub fn load_mut<'a>(swap_info: &'a AccountInfo) -> Result<RefMut<'a, Self>, ProgramError> {
+        let data = swap_info.try_borrow_mut_data()?;
+        Self::check(&data)?;
+        Ok(RefMut::map(data, |data| from_bytes_mut(data)))
+    }
+
+    fn check(data: &[u8]) -> ProgramResult {
+        if data.len() != SWAP_INFO_SIZE {
+            return Err(ProgramError::InvalidAccountData);
+        }
+        let view: &Self = from_bytes(data);
+        // Reject every instruction on the pool while a flash swap is in progress
+        if unpack_bool(&view.is_locked)? {
+            return Err(SwapError::ReentrancyLocked.into());
+        }
+        if view.version[0] > SWAP_INFO_VERSION {
+            return Err(ProgramError::InvalidAccountData);
+        }
+        if !unpack_bool(&view.is_initialized)? {
+            return Err(ProgramError::UninitializedAccount);
+        }
+        Ok(())
+    }
+
+    /// Nonce of the swap authority
+    pub fn nonce(&self) -> u8 {
+        self.nonce[0]
+    }
+
+    /// Swap type of the pool
+    pub fn swap_type(&self) -> Result<SwapType, ProgramError> {
+        self.swap_type[0].try_into()
+    }
+
+    /// Swaps are paused, by the pause of the whole pool or of the swaps only
+    pub fn is_swap_paused(&self) -> Result<bool, ProgramError> {
+        Ok(unpack_bool(&self.is_paused)? || unpack_bool(&self.pause_swap)?)
+    }
+
+    /// Oracle priority flags
+    pub fn oracle_priority_flags(&self) -> u8 {
+        self.oracle_priority_flags[0]
+    }
+
+    /// Unpacks the fees of the pool
+    pub fn fees(&self) -> Result<Fees, ProgramError> {
+        Fees::unpack_from_slice(&self.fees)
+    }
+
+    /// Unpacks the rewards of the pool
+    pub fn rewards(&self) -> Result<Rewards, ProgramError> {
+        Rewards::unpack_from_slice(&self.rewards)
+    }
+
+    /// Unpacks the pool state
+    pub fn pool_state(&self) -> Result<PoolState, ProgramError> {
+        let mut pool_state = PoolState::unpack_from_slice(&self.pool_state)?;
+        pool_state.unpack_ema_from_slice(&self.pool_state_ema);
+        Ok(pool_state)
+    }
+
+    /// Packs the pool state
+    pub fn set_pool_state(&mut self, pool_state: &PoolState) {
+        pool_state.pack_into_slice(&mut self.pool_state);
+        pool_state.pack_ema_into_slice(&mut self.pool_state_ema);
+    }
+
+    /// Writes back the fields a swap changes, the pool state, the circuit breaker and the
+    /// swap stats, the rest of the pool is left as it is
+    pub fn store_swap_state(&mut self, token_swap: &SwapInfo) {
+        self.set_pool_state(&token_swap.pool_state);
+        pack_bool(token_swap.pause_swap, &mut self.pause_swap);
+        pack_decimal(
+            token_swap.circuit_breaker_price,
+            &mut self.circuit_breaker_price,
+        );
+        self.circuit_breaker_slot = token_swap.circuit_breaker_slot.to_le_bytes();
+        self.circuit_breaker_tripped_slot = token_swap.circuit_breaker_tripped_slot.to_le_bytes();
+        self.volume_a_in = token_swap.volume_a_in.to_le_bytes();
+        self.volume_b_in = token_swap.volume_b_in.to_le_bytes();
+        self.fees_collected_a = token_swap.fees_collected_a.to_le_bytes();
+        self.fees_collected_b = token_swap.fees_collected_b.to_le_bytes();
+        self.swap_count = token_swap.swap_count.to_le_bytes();
+        pack_decimal(token_swap.last_trade_price, &mut self.last_trade_price);
     }
 }
 
@@ -410,6 +1412,21 @@ mod tests {
     use super::*;
     use crate::curve::{default_market_price, default_slope, InitPoolStateParams, PoolState};
 
+    #[test]
+    fn test_swap_type() {
+        assert_eq!(SwapType::try_from(2u8), Ok(SwapType::Pegged));
+        assert_eq!(
+            SwapType::try_from(3u8),
+            Err(ProgramError::InvalidAccountData)
+        );
+
+        assert!(SwapType::Normal.is_traded_as(SwapType::Normal));
+        assert!(!SwapType::Normal.is_traded_as(SwapType::Stable));
+        assert!(SwapType::Stable.is_traded_as(SwapType::Stable));
+        assert!(SwapType::Pegged.is_traded_as(SwapType::Stable));
+        assert!(!SwapType::Pegged.is_traded_as(SwapType::Normal));
+    }
+
     #[test]
     fn test_swap_info_packing() {
         let is_initialized = true;
@@ -426,12 +1443,14 @@ mod tests {
         let pyth_a_raw = [10u8; 32];
         let pyth_b_raw = [11u8; 32];
         let serum_combined_address_raw = [12u8; 32];
+        let price_setter_raw = [13u8; 32];
         let config_key = Pubkey::new_from_array(config_key_raw);
         let token_a = Pubkey::new_from_array(token_a_raw);
         let token_b = Pubkey::new_from_array(token_b_raw);
         let pyth_a = Pubkey::new_from_array(pyth_a_raw);
         let pyth_b = Pubkey::new_from_array(pyth_b_raw);
         let serum_combined_address = Pubkey::new_from_array(serum_combined_address_raw);
+        let price_setter = Pubkey::new_from_array(price_setter_raw);
         let pool_mint = Pubkey::new_from_array(pool_mint_raw);
         let token_a_mint = Pubkey::new_from_array(token_a_mint_raw);
         let token_b_mint = Pubkey::new_from_array(token_b_mint_raw);
@@ -450,11 +1469,21 @@ mod tests {
             last_valid_market_price_slot: 0,
         });
         pool_state.adjust_target().unwrap();
+        pool_state.ema_market_price = Decimal::from(5u64);
+        pool_state.ema_slot = 1_300u64;
 
         let token_a_decimals = 6u8;
         let token_b_decimals = 9u8;
         let swap_out_limit_percentage = 20u8;
         let oracle_priority_flags = 0b11u8;
+        let price_jump_policy = PriceJumpPolicy::Clamp;
+        let max_price_divergence = 150u16;
+        let exchange_rate_adapter = ExchangeRateAdapterType::SplStakePool;
+        let oracle_params = OracleParams {
+            stale_slots: 25u16,
+            max_confidence_bps: 500u16,
+            min_publishers: 5u8,
+        };
 
         let swap_info = SwapInfo {
             is_initialized,
@@ -479,6 +1508,39 @@ mod tests {
             swap_out_limit_percentage,
             oracle_priority_flags,
             serum_combined_address,
+            price_jump_policy,
+            max_price_divergence,
+            exchange_rate_adapter,
+            oracle_params,
+            future_pegged_price: Decimal::from(2u64),
+            pegged_price_apply_slot: 216_000u64,
+            strict_destination_owner: true,
+            pause_deposit: true,
+            quote_is_token_a: true,
+            fee_override: true,
+            rebalance_threshold_bps: 50,
+            circuit_breaker_bps: 500,
+            circuit_breaker_window_slots: 150,
+            circuit_breaker_price: Decimal::from(3u64),
+            circuit_breaker_slot: 1_000u64,
+            circuit_breaker_tripped_slot: 1_100u64,
+            max_total_supply: 5_000_000u64,
+            whitelist_enabled: true,
+            version: SWAP_INFO_VERSION,
+            price_setter,
+            manual_max_change_bps: 300,
+            manual_stale_slots: 1_500,
+            manual_price: Decimal::from(4u64),
+            manual_price_slot: 1_200u64,
+            ema_window_slots: 50u64,
+            volume_a_in: 6_000_000u128,
+            volume_b_in: 7_000_000u128,
+            fees_collected_a: 6_000u128,
+            fees_collected_b: 7_000u128,
+            swap_count: 42u64,
+            last_trade_price: Decimal::from(6u64),
+            virtual_price: Decimal::from(7u64),
+            skim_surplus: true,
             ..SwapInfo::default()
         };
 
@@ -514,11 +1576,78 @@ mod tests {
         packed.extend_from_slice(&swap_out_limit_percentage.to_le_bytes());
         packed.extend_from_slice(&oracle_priority_flags.to_le_bytes());
         packed.extend_from_slice(&serum_combined_address_raw);
+        packed.push(price_jump_policy as u8);
+        packed.extend_from_slice(&max_price_divergence.to_le_bytes());
+        packed.push(exchange_rate_adapter as u8);
+        packed.extend_from_slice(&oracle_params.stale_slots.to_le_bytes());
+        packed.extend_from_slice(&oracle_params.max_confidence_bps.to_le_bytes());
+        packed.push(oracle_params.min_publishers);
+        packed.push(0u8);
+        let mut packed_future_pegged_price = [0u8; 16];
+        pack_decimal(Decimal::from(2u64), &mut packed_future_pegged_price);
+        packed.extend_from_slice(&packed_future_pegged_price);
+        packed.extend_from_slice(&216_000u64.to_le_bytes());
+        packed.push(1u8);
+        packed.extend_from_slice(&[0u8, 1u8, 0u8, 1u8, 1u8]);
+        packed.extend_from_slice(&50u16.to_le_bytes());
+        packed.extend_from_slice(&500u16.to_le_bytes());
+        packed.extend_from_slice(&150u64.to_le_bytes());
+        let mut packed_circuit_breaker_price = [0u8; 16];
+        pack_decimal(Decimal::from(3u64), &mut packed_circuit_breaker_price);
+        packed.extend_from_slice(&packed_circuit_breaker_price);
+        packed.extend_from_slice(&1_000u64.to_le_bytes());
+        packed.extend_from_slice(&1_100u64.to_le_bytes());
+        packed.extend_from_slice(&5_000_000u64.to_le_bytes());
+        packed.push(1u8);
+        let version_offset = packed.len();
+        packed.push(SWAP_INFO_VERSION);
+        packed.extend_from_slice(&price_setter_raw);
+        packed.extend_from_slice(&300u16.to_le_bytes());
+        packed.extend_from_slice(&1_500u64.to_le_bytes());
+        let mut packed_manual_price = [0u8; 16];
+        pack_decimal(Decimal::from(4u64), &mut packed_manual_price);
+        packed.extend_from_slice(&packed_manual_price);
+        packed.extend_from_slice(&1_200u64.to_le_bytes());
+        packed.extend_from_slice(&50u64.to_le_bytes());
+        let mut packed_ema = [0u8; POOL_STATE_EMA_SIZE];
+        pool_state.pack_ema_into_slice(&mut packed_ema);
+        packed.extend_from_slice(&packed_ema);
+        packed.extend_from_slice(&6_000_000u128.to_le_bytes());
+        packed.extend_from_slice(&7_000_000u128.to_le_bytes());
+        packed.extend_from_slice(&6_000u128.to_le_bytes());
+        packed.extend_from_slice(&7_000u128.to_le_bytes());
+        packed.extend_from_slice(&42u64.to_le_bytes());
+        let mut packed_last_trade_price = [0u8; 16];
+        pack_decimal(Decimal::from(6u64), &mut packed_last_trade_price);
+        packed.extend_from_slice(&packed_last_trade_price);
+        let mut packed_virtual_price = [0u8; 16];
+        pack_decimal(Decimal::from(7u64), &mut packed_virtual_price);
+        packed.extend_from_slice(&packed_virtual_price);
+        packed.push(1u8);
+        assert_eq!(packed.len(), USED_BYTES);
         packed.extend_from_slice(&[0u8; SWAP_INFO_RESERVED_BYTES]);
 
         let unpacked = SwapInfo::unpack(&packed).unwrap();
         assert_eq!(swap_info, unpacked);
 
+        // The pools of a later layout version are not read
+        packed[version_offset] = SWAP_INFO_VERSION + 1;
+        assert_eq!(
+            SwapInfo::unpack(&packed).unwrap_err(),
+            ProgramError::InvalidAccountData
+        );
+
+        let locked_swap_info = SwapInfo {
+            is_locked: true,
+            ..swap_info.clone()
+        };
+        let mut packed = [0u8; SwapInfo::LEN];
+        SwapInfo::pack_into_slice(&locked_swap_info, &mut packed);
+        assert_eq!(
+            SwapInfo::unpack(&packed).unwrap_err(),
+            SwapError::ReentrancyLocked.into()
+        );
+
         let packed = [0u8; SwapInfo::LEN];
         let swap_info: SwapInfo = Default::default();
         let unpack_unchecked = SwapInfo::unpack_unchecked(&packed).unwrap();
@@ -527,6 +1656,130 @@ mod tests {
         assert_eq!(err, ProgramError::UninitializedAccount);
     }
 
+    #[test]
+    fn test_swap_info_len() {
+        // The reserved bytes are used up, the next field grows the layout with a version bump
+        // and a `MigrateSwapInfo` of the legacy pools
+        assert_eq!(SwapInfo::LEN, 999);
+        assert_eq!(SWAP_INFO_RESERVED_BYTES, 1);
+        assert_eq!(SWAP_INFO_VERSION, 3);
+    }
+
+    #[test]
+    fn test_swap_info_view() {
+        assert_eq!(std::mem::size_of::<SwapInfoView>(), SwapInfo::LEN);
+        assert_eq!(std::mem::align_of::<SwapInfoView>(), 1);
+
+        let mut pool_state = PoolState::new(InitPoolStateParams {
+            market_price: default_market_price(),
+            slope: default_slope(),
+            base_reserve: Decimal::zero(),
+            quote_reserve: Decimal::zero(),
+            total_supply: 0,
+            last_market_price: default_market_price(),
+            last_valid_market_price_slot: 0,
+        });
+        pool_state.adjust_target().unwrap();
+        let mut swap_info = SwapInfo {
+            is_initialized: true,
+            nonce: 254,
+            config_key: Pubkey::new_unique(),
+            token_a: Pubkey::new_unique(),
+            token_b: Pubkey::new_unique(),
+            pool_mint: Pubkey::new_unique(),
+            fees: DEFAULT_TEST_FEES,
+            rewards: DEFAULT_TEST_REWARDS,
+            pool_state,
+            oracle_priority_flags: OraclePriorityFlag::PYTH_ONLY.bits(),
+            circuit_breaker_bps: 100,
+            version: SWAP_INFO_VERSION,
+            price_setter: Pubkey::new_unique(),
+            ..SwapInfo::default()
+        };
+        let mut packed = [0u8; SwapInfo::LEN];
+        SwapInfo::pack_into_slice(&swap_info, &mut packed);
+
+        // The view reads the fields in place
+        let view = SwapInfoView::from_slice(&packed).unwrap();
+        assert_eq!(view.config_key, swap_info.config_key);
+        assert_eq!(view.price_setter, swap_info.price_setter);
+        assert_eq!(view.token_a, swap_info.token_a);
+        assert_eq!(view.token_b, swap_info.token_b);
+        assert_eq!(view.pool_mint, swap_info.pool_mint);
+        assert_eq!(view.nonce(), 254);
+        assert_eq!(view.swap_type().unwrap(), SwapType::Normal);
+        assert!(!view.is_swap_paused().unwrap());
+        assert_eq!(
+            view.oracle_priority_flags(),
+            swap_info.oracle_priority_flags
+        );
+        assert_eq!(view.fees().unwrap(), swap_info.fees);
+        assert_eq!(view.rewards().unwrap(), swap_info.rewards);
+        assert_eq!(view.pool_state().unwrap(), swap_info.pool_state);
+
+        // Storing the swap state through the view matches packing the whole pool
+        swap_info.pool_state.base_reserve = Decimal::from(1_500u64);
+        swap_info.pool_state.ema_market_price = default_market_price();
+        swap_info.pool_state.ema_slot = 10;
+        swap_info.pause_swap = true;
+        swap_info.circuit_breaker_price = default_market_price();
+        swap_info.circuit_breaker_slot = 10;
+        swap_info.circuit_breaker_tripped_slot = 20;
+        swap_info
+            .record_swap(1_000u64, 1_990u64, 10u64, SwapDirection::SellBase)
+            .unwrap();
+        SwapInfoView::from_slice_mut(&mut packed)
+            .unwrap()
+            .store_swap_state(&swap_info);
+        assert_eq!(SwapInfo::unpack(&packed).unwrap(), swap_info);
+        assert!(SwapInfoView::from_slice(&packed)
+            .unwrap()
+            .is_swap_paused()
+            .unwrap());
+
+        // The view rejects the same pools as unpack
+        assert_eq!(
+            SwapInfoView::from_slice(&packed[..SwapInfo::LEN - 1]).unwrap_err(),
+            ProgramError::InvalidAccountData
+        );
+        swap_info.is_locked = true;
+        SwapInfo::pack_into_slice(&swap_info, &mut packed);
+        assert_eq!(
+            SwapInfoView::from_slice(&packed).unwrap_err(),
+            SwapError::ReentrancyLocked.into()
+        );
+        let packed = [0u8; SwapInfo::LEN];
+        assert_eq!(
+            SwapInfoView::from_slice(&packed).unwrap_err(),
+            ProgramError::UninitializedAccount
+        );
+    }
+
+    #[test]
+    fn test_swap_info_unpack_any() {
+        let swap_info = SwapInfo {
+            is_initialized: true,
+            nonce: 254,
+            config_key: Pubkey::new_unique(),
+            pool_mint: Pubkey::new_unique(),
+            fees: DEFAULT_TEST_FEES,
+            rewards: DEFAULT_TEST_REWARDS,
+            ..SwapInfo::default()
+        };
+        let mut packed = [0u8; SwapInfo::LEN];
+        SwapInfo::pack_into_slice(&swap_info, &mut packed);
+
+        // The legacy pools read as the unversioned pools with the later fields off
+        for len in LEGACY_SWAP_INFO_LENS.iter() {
+            assert_eq!(SwapInfo::unpack_any(&packed[..*len]).unwrap(), swap_info);
+        }
+        assert_eq!(SwapInfo::unpack_any(&packed).unwrap(), swap_info);
+        assert_eq!(
+            SwapInfo::unpack_any(&packed[..700]).unwrap_err(),
+            ProgramError::InvalidAccountData
+        );
+    }
+
     #[test]
     fn test_user_referrer_data_packing() {
         let is_initialized = true;
@@ -539,6 +1792,7 @@ mod tests {
             config_key,
             owner,
             referrer,
+            last_update_slot: 1_000,
         };
 
         let mut packed = [0u8; UserReferrerData::LEN];
@@ -546,6 +1800,59 @@ mod tests {
 
         let unpacked = UserReferrerData::unpack(&packed).unwrap();
         assert_eq!(user_referrer_data, unpacked);
+
+        let unpacked =
+            UserReferrerData::unpack_any(&packed[..LEGACY_USER_REFERRER_DATA_LEN]).unwrap();
+        assert_eq!(
+            unpacked,
+            UserReferrerData {
+                last_update_slot: 0,
+                ..user_referrer_data
+            }
+        );
+    }
+
+    #[test]
+    fn test_user_referrer_update_cooldown() {
+        let user_referrer_data = UserReferrerData {
+            last_update_slot: 1_000,
+            ..UserReferrerData::default()
+        };
+        assert_eq!(
+            user_referrer_data.check_update_cooldown(1_000 + REFERRER_UPDATE_COOLDOWN_SLOTS - 1),
+            Err(SwapError::ReferrerUpdateCooldown.into())
+        );
+        assert!(user_referrer_data
+            .check_update_cooldown(1_000 + REFERRER_UPDATE_COOLDOWN_SLOTS)
+            .is_ok());
+    }
+
+    #[test]
+    fn test_conventional_pair_order() {
+        let mut token_swap = SwapInfo {
+            token_a_mint: Pubkey::new_unique(),
+            token_b_mint: Pubkey::new_unique(),
+            ..SwapInfo::default()
+        };
+        token_swap.pool_state.market_price = Decimal::from(4u64);
+        assert_eq!(
+            token_swap.base_quote_mints(),
+            (token_swap.token_a_mint, token_swap.token_b_mint)
+        );
+        assert_eq!(
+            token_swap.conventional_market_price().unwrap(),
+            Decimal::from(4u64)
+        );
+
+        token_swap.quote_is_token_a = true;
+        assert_eq!(
+            token_swap.base_quote_mints(),
+            (token_swap.token_b_mint, token_swap.token_a_mint)
+        );
+        assert_eq!(
+            token_swap.conventional_market_price().unwrap(),
+            Decimal::from_scaled_val(250_000_000_000)
+        );
     }
 
     #[test]
@@ -585,6 +1892,273 @@ mod tests {
         );
     }
 
+    #[test]
+    fn test_check_deposit_cap() {
+        let mut token_swap = SwapInfo {
+            max_total_supply: 1_000_000u64,
+            pool_state: PoolState {
+                total_supply: 1_000_000u64,
+                ..PoolState::default()
+            },
+            ..SwapInfo::default()
+        };
+        assert_eq!(token_swap.check_deposit_cap(), Ok(()));
+
+        token_swap.pool_state.total_supply = 1_000_001u64;
+        assert_eq!(
+            token_swap.check_deposit_cap(),
+            Err(SwapError::DepositCapExceeded.into())
+        );
+
+        token_swap.max_total_supply = 0u64;
+        assert_eq!(token_swap.check_deposit_cap(), Ok(()));
+    }
+
+    #[test]
+    fn test_record_swap() {
+        let mut swap_info = SwapInfo::default();
+        swap_info
+            .record_swap(1_000u64, 1_990u64, 10u64, SwapDirection::SellBase)
+            .unwrap();
+        swap_info
+            .record_swap(4_000u64, 2_000u64, 5u64, SwapDirection::SellQuote)
+            .unwrap();
+        assert_eq!(swap_info.volume_a_in, 1_000u128);
+        assert_eq!(swap_info.volume_b_in, 4_000u128);
+        assert_eq!(swap_info.fees_collected_a, 5u128);
+        assert_eq!(swap_info.fees_collected_b, 10u128);
+        assert_eq!(swap_info.swap_count, 2u64);
+        // 4000 B in for 2000 A out
+        assert_eq!(swap_info.last_trade_price, Decimal::from(2u64));
+
+        // A swap without output keeps the last price
+        swap_info
+            .record_swap(1u64, 0u64, 0u64, SwapDirection::SellQuote)
+            .unwrap();
+        assert_eq!(swap_info.swap_count, 3u64);
+        assert_eq!(swap_info.last_trade_price, Decimal::from(2u64));
+
+        swap_info.volume_a_in = u128::MAX;
+        swap_info
+            .record_swap(1u64, 1u64, 0u64, SwapDirection::SellBase)
+            .unwrap();
+        assert_eq!(swap_info.volume_a_in, u128::MAX);
+    }
+
+    #[test]
+    fn test_update_virtual_price() {
+        let mut swap_info = SwapInfo {
+            swap_type: SwapType::Stable,
+            pool_state: PoolState::new(InitPoolStateParams {
+                market_price: Decimal::one(),
+                slope: default_slope(),
+                base_reserve: Decimal::from(1_000_000u64),
+                quote_reserve: Decimal::from(1_000_000u64),
+                total_supply: 1_000_000,
+                last_market_price: Decimal::one(),
+                last_valid_market_price_slot: 0,
+            }),
+            ..SwapInfo::default()
+        };
+        swap_info.update_virtual_price().unwrap();
+        assert_eq!(swap_info.virtual_price, Decimal::from(2u64));
+
+        // The LP fee raises the virtual price
+        swap_info
+            .pool_state
+            .swap_with_fee(10_000, 9_900, 100, SwapDirection::SellBase)
+            .unwrap();
+        swap_info.update_virtual_price().unwrap();
+        assert!(swap_info.virtual_price > Decimal::from(2u64));
+
+        // Normal pools don't persist the virtual price
+        swap_info.swap_type = SwapType::Normal;
+        swap_info.virtual_price = Decimal::zero();
+        swap_info.update_virtual_price().unwrap();
+        assert_eq!(swap_info.virtual_price, Decimal::zero());
+    }
+
+    #[test]
+    fn test_manual_market_price() {
+        let mut token_swap = SwapInfo {
+            manual_max_change_bps: 500,
+            manual_stale_slots: 100,
+            pool_state: PoolState {
+                last_market_price: Decimal::from(20u64),
+                ..PoolState::default()
+            },
+            ..SwapInfo::default()
+        };
+        // Never pushed
+        assert_eq!(
+            token_swap.manual_market_price(1_000u64),
+            Err(SwapError::StaleManualPrice.into())
+        );
+
+        // The first push is bounded by the last market price of the pool
+        assert_eq!(
+            token_swap.check_manual_price_change(Decimal::from(21u64)),
+            Ok(())
+        );
+        assert_eq!(
+            token_swap.check_manual_price_change(Decimal::from(22u64)),
+            Err(SwapError::ManualPriceChangeTooLarge.into())
+        );
+
+        token_swap.manual_price = Decimal::from(10u64);
+        token_swap.manual_price_slot = 1_000u64;
+        assert_eq!(
+            token_swap.manual_market_price(1_099u64),
+            Ok((Decimal::from(10u64), 1_000u64))
+        );
+        assert_eq!(
+            token_swap.manual_market_price(1_100u64),
+            Err(SwapError::StaleManualPrice.into())
+        );
+
+        // Later pushes are bounded by the previous one
+        assert_eq!(
+            token_swap
+                .check_manual_price_change(Decimal::from_scaled_val(9_500_000_000_000_000_000)),
+            Ok(())
+        );
+        assert_eq!(
+            token_swap
+                .check_manual_price_change(Decimal::from_scaled_val(10_600_000_000_000_000_000)),
+            Err(SwapError::ManualPriceChangeTooLarge.into())
+        );
+    }
+
+    #[test]
+    fn test_check_circuit_breaker() {
+        let mut token_swap = SwapInfo {
+            circuit_breaker_bps: 500,
+            circuit_breaker_window_slots: 100,
+            pool_state: PoolState {
+                market_price: Decimal::from(10u64),
+                ..PoolState::default()
+            },
+            ..SwapInfo::default()
+        };
+
+        // The first swap starts the window at the market price
+        assert_eq!(
+            token_swap.check_circuit_breaker(1_000, 9_600, SwapDirection::SellBase, 1_000),
+            Ok(false)
+        );
+        assert_eq!(token_swap.circuit_breaker_price, Decimal::from(10u64));
+        assert_eq!(token_swap.circuit_breaker_slot, 1_000);
+
+        // The market price moving within the window keeps the reference price
+        token_swap.pool_state.market_price = Decimal::from(12u64);
+        assert_eq!(
+            token_swap.check_circuit_breaker(10_400, 1_000, SwapDirection::SellQuote, 1_050),
+            Ok(false)
+        );
+        assert_eq!(
+            token_swap.check_circuit_breaker(1_000, 11_900, SwapDirection::SellBase, 1_100),
+            Ok(true)
+        );
+        assert!(token_swap.pause_swap);
+        assert_eq!(token_swap.circuit_breaker_tripped_slot, 1_100);
+
+        // A new window starts at the market price once the last one is over
+        token_swap.pause_swap = false;
+        token_swap.reset_circuit_breaker();
+        assert_eq!(token_swap.circuit_breaker_tripped_slot, 0);
+        assert_eq!(
+            token_swap.check_circuit_breaker(1_000, 11_900, SwapDirection::SellBase, 1_200),
+            Ok(false)
+        );
+        assert_eq!(token_swap.circuit_breaker_price, Decimal::from(12u64));
+        assert_eq!(
+            token_swap.check_circuit_breaker(1_000, 11_900, SwapDirection::SellBase, 1_301),
+            Ok(false)
+        );
+        assert_eq!(token_swap.circuit_breaker_slot, 1_301);
+
+        token_swap.circuit_breaker_bps = 0;
+        assert_eq!(
+            token_swap.check_circuit_breaker(1_000, 1, SwapDirection::SellBase, 1_302),
+            Ok(false)
+        );
+    }
+
+    #[test]
+    fn test_partial_fill_amount_in() {
+        let swap_direction = SwapDirection::SellBase;
+        let mut token_swap = SwapInfo {
+            swap_out_limit_percentage: 10u8,
+            pool_state: PoolState::new(InitPoolStateParams {
+                market_price: Decimal::from(100u64),
+                slope: default_slope(),
+                base_reserve: Decimal::from(1_000_000u64),
+                quote_reserve: Decimal::from(100_000_000u64),
+                total_supply: 1_000_000,
+                last_market_price: Decimal::from(100u64),
+                last_valid_market_price_slot: 0,
+            }),
+            ..SwapInfo::default()
+        };
+        let amount_out = |token_swap: &SwapInfo, amount_in: u64| {
+            token_swap
+                .pool_state
+                .get_out_amount(amount_in, swap_direction)
+                .unwrap()
+        };
+
+        // A swap within the limits is filled in full
+        assert_eq!(
+            token_swap.partial_fill_amount_in(1_000, swap_direction, 0),
+            Ok(1_000)
+        );
+
+        // The fill stops right under the swap out limit
+        let amount_in = token_swap
+            .partial_fill_amount_in(1_000_000, swap_direction, 0)
+            .unwrap();
+        assert!(amount_in > 0 && amount_in < 1_000_000);
+        assert_eq!(
+            token_swap.check_swap_out_amount(amount_out(&token_swap, amount_in), swap_direction),
+            Ok(())
+        );
+        assert_eq!(
+            token_swap
+                .check_swap_out_amount(amount_out(&token_swap, amount_in + 1), swap_direction),
+            Err(SwapError::ExceededSwapOutAmount.into())
+        );
+
+        // The fill keeps the execution price within the circuit breaker band of the market price
+        token_swap.swap_out_limit_percentage = 0u8;
+        token_swap.circuit_breaker_bps = 100;
+        token_swap.circuit_breaker_window_slots = 100;
+        let amount_in = token_swap
+            .partial_fill_amount_in(1_000_000, swap_direction, 1_000)
+            .unwrap();
+        assert!(amount_in > 0 && amount_in < 1_000_000);
+        let reference_price = Decimal::from(100u64);
+        assert_eq!(
+            token_swap.exceeds_circuit_breaker(
+                reference_price,
+                amount_in,
+                amount_out(&token_swap, amount_in),
+                swap_direction
+            ),
+            Ok(false)
+        );
+        assert_eq!(
+            token_swap.exceeds_circuit_breaker(
+                reference_price,
+                amount_in + 1,
+                amount_out(&token_swap, amount_in + 1),
+                swap_direction
+            ),
+            Ok(true)
+        );
+        // The search does not start a circuit breaker window
+        assert_eq!(token_swap.circuit_breaker_price, Decimal::zero());
+    }
+
     #[test]
     fn test_check_oracle_flags() {
         assert!(OraclePriorityFlag::from_bits_truncate(0b00).is_pyth_only());
@@ -597,6 +2171,99 @@ mod tests {
             OraclePriorityFlag::from_bits_truncate(0b01),
             OraclePriorityFlag::SERUM_ONLY
         );
-        assert_eq!(OraclePriorityFlag::from_bits(0b10), None);
+        assert_eq!(
+            OraclePriorityFlag::from_bits_truncate(0b10),
+            OraclePriorityFlag::PYTH_WITH_SERUM_FALLBACK
+        );
+        assert!(OraclePriorityFlag::from_bits_truncate(0b10).is_pyth_with_serum_fallback());
+        assert!(OraclePriorityFlag::from_bits_truncate(0b01).uses_serum());
+        assert!(OraclePriorityFlag::from_bits_truncate(0b10).uses_serum());
+        assert!(!OraclePriorityFlag::from_bits_truncate(0b00).uses_serum());
+        assert_eq!(
+            OraclePriorityFlag::from_bits_truncate(0b100),
+            OraclePriorityFlag::MANUAL
+        );
+        assert!(OraclePriorityFlag::MANUAL.is_manual());
+        assert!(!OraclePriorityFlag::MANUAL.uses_serum());
+        assert!(!OraclePriorityFlag::MANUAL.uses_pyth());
+        assert!(!OraclePriorityFlag::SERUM_ONLY.uses_pyth());
+        assert!(OraclePriorityFlag::PYTH_ONLY.uses_pyth());
+        assert!(OraclePriorityFlag::PYTH_WITH_SERUM_FALLBACK.uses_pyth());
+        assert!(OraclePriorityFlag::CHAINLINK.is_chainlink());
+        assert!(!OraclePriorityFlag::CHAINLINK.uses_serum());
+        assert!(!OraclePriorityFlag::CHAINLINK.uses_pyth());
+        assert!(OraclePriorityFlag::PYTH_PULL.is_pyth_pull());
+        assert!(!OraclePriorityFlag::PYTH_PULL.uses_serum());
+        assert!(!OraclePriorityFlag::PYTH_PULL.uses_pyth());
+        assert_eq!(OraclePriorityFlag::from_bits(0b1000_0000), None);
+    }
+
+    #[test]
+    fn test_oracle_params() {
+        let default_params = OracleParams::default();
+        assert_eq!(default_params.validate(), Ok(()));
+        assert_eq!(default_params.stale_after_slots(), 10u64);
+        assert_eq!(default_params.chainlink_stale_after_slots(), 150u64);
+        assert_eq!(default_params.pyth_pull_stale_after_seconds(), 30i64);
+        assert_eq!(default_params.confidence_limit_bps(), 200u64);
+        assert_eq!(default_params.required_publishers(), 3usize);
+
+        let oracle_params = OracleParams {
+            stale_slots: 30u16,
+            max_confidence_bps: 50u16,
+            min_publishers: 7u8,
+        };
+        assert_eq!(oracle_params.validate(), Ok(()));
+        assert_eq!(oracle_params.stale_after_slots(), 30u64);
+        assert_eq!(oracle_params.chainlink_stale_after_slots(), 30u64);
+        assert_eq!(oracle_params.pyth_pull_stale_after_seconds(), 30i64);
+        assert_eq!(oracle_params.confidence_limit_bps(), 50u64);
+        assert_eq!(oracle_params.required_publishers(), 7usize);
+
+        assert_eq!(
+            OracleParams {
+                max_confidence_bps: 10_001u16,
+                ..OracleParams::default()
+            }
+            .validate(),
+            Err(SwapError::InvalidInput.into())
+        );
+        assert_eq!(
+            OracleParams {
+                min_publishers: 33u8,
+                ..OracleParams::default()
+            }
+            .validate(),
+            Err(SwapError::InvalidInput.into())
+        );
+    }
+
+    #[cfg(feature = "serde")]
+    #[test]
+    fn test_swap_info_serde() {
+        let swap_info = SwapInfo {
+            is_initialized: true,
+            swap_type: SwapType::Stable,
+            pyth_a: Pubkey::new_unique(),
+            fees: DEFAULT_TEST_FEES,
+            rewards: DEFAULT_TEST_REWARDS,
+            pool_state: PoolState::new(InitPoolStateParams {
+                market_price: default_market_price(),
+                slope: default_slope(),
+                base_reserve: Decimal::from(1_000u64),
+                quote_reserve: Decimal::from(2_000u64),
+                total_supply: 1_000,
+                last_market_price: default_market_price(),
+                last_valid_market_price_slot: 10,
+            }),
+            price_jump_policy: PriceJumpPolicy::Clamp,
+            exchange_rate_adapter: ExchangeRateAdapterType::SplStakePool,
+            ..SwapInfo::default()
+        };
+
+        let yaml = serde_yaml::to_string(&swap_info).unwrap();
+        assert!(yaml.contains("swap_type: Stable"));
+        assert!(yaml.contains("base_reserve: \"1000.000000000000\""));
+        assert_eq!(serde_yaml::from_str::<SwapInfo>(&yaml).unwrap(), swap_info);
     }
 }
@@ -0,0 +1,520 @@
+//! Stable pools of three tokens traded on the StableSwap invariant
+
+use std::convert::TryFrom;
+
+use arrayref::{array_mut_ref, array_ref, array_refs, mut_array_refs};
+use solana_program::{
+    entrypoint::ProgramResult,
+    program_error::ProgramError,
+    program_pack::{IsInitialized, Pack, Sealed},
+    pubkey::{Pubkey, PUBKEY_BYTES},
+};
+
+use super::*;
+use crate::{
+    curve::{compute_stable_balance, compute_stable_invariant},
+    error::SwapError,
+};
+
+#[cfg(feature = "serde")]
+use serde::{Deserialize, Serialize};
+
+/// Number of tokens of a tri pool
+pub const TRI_POOL_TOKENS: usize = 3;
+
+/// Stable pool of three tokens, e.g. USDC/USDT/PAI. The reserves are normalized to the
+/// most decimals of the tokens and traded on the StableSwap invariant.
+#[repr(C)]
+#[derive(Clone, Debug, Default, PartialEq)]
+#[cfg_attr(feature = "serde", derive(Serialize, Deserialize))]
+pub struct TriSwapInfo {
+    /// Initialized state
+    pub is_initialized: bool,
+    /// Paused state
+    pub is_paused: bool,
+    /// Nonce of the swap authority, the program address of the pool
+    pub nonce: u8,
+    /// Config info
+    pub config_key: Pubkey,
+    /// Pool mint, minted 1:1 with the invariant on initialization
+    pub pool_mint: Pubkey,
+    /// Token accounts of the pool owned by the swap authority
+    pub tokens: [Pubkey; TRI_POOL_TOKENS],
+    /// Mints of the tokens
+    pub token_mints: [Pubkey; TRI_POOL_TOKENS],
+    /// Admin token accounts receiving the trading and withdrawal fees of each token
+    pub admin_fee_keys: [Pubkey; TRI_POOL_TOKENS],
+    /// Decimals of the tokens
+    pub token_decimals: [u8; TRI_POOL_TOKENS],
+    /// Amplification coefficient of the invariant
+    pub amp: u64,
+    /// Fees
+    pub fees: Fees,
+    /// Reserves of the tokens, excluding the admin fees
+    pub reserves: [u64; TRI_POOL_TOKENS],
+    /// reserved bytes
+    pub reserved: [u64; TRI_SWAP_INFO_RESERVED_U64],
+}
+
+impl TriSwapInfo {
+    /// Index of a token account of the pool
+    pub fn token_index(&self, token: &Pubkey) -> Result<usize, ProgramError> {
+        self.tokens
+            .iter()
+            .position(|key| key == token)
+            .ok_or_else(|| SwapError::IncorrectSwapAccount.into())
+    }
+
+    /// Decimals the balances are normalized to, the most decimals of the tokens and the
+    /// decimals of the pool mint
+    pub fn precision(&self) -> u8 {
+        self.token_decimals
+            .iter()
+            .copied()
+            .max()
+            .unwrap_or_default()
+    }
+
+    fn multiplier(&self, index: usize) -> Result<u128, ProgramError> {
+        10u128
+            .checked_pow((self.precision() - self.token_decimals[index]) as u32)
+            .ok_or_else(|| SwapError::CalculationFailure.into())
+    }
+
+    fn normalized_balances(
+        &self,
+        balances: &[u64; TRI_POOL_TOKENS],
+    ) -> Result<[u128; TRI_POOL_TOKENS], ProgramError> {
+        let mut normalized = [0u128; TRI_POOL_TOKENS];
+        for (index, balance) in balances.iter().enumerate() {
+            normalized[index] = (*balance as u128)
+                .checked_mul(self.multiplier(index)?)
+                .ok_or(SwapError::CalculationFailure)?;
+        }
+        Ok(normalized)
+    }
+
+    /// Invariant of the balances, normalized to the precision of the pool
+    pub fn invariant(&self, balances: &[u64; TRI_POOL_TOKENS]) -> Result<u128, ProgramError> {
+        compute_stable_invariant(self.amp, &self.normalized_balances(balances)?)
+    }
+
+    /// Amount of a token out of the pool for an amount of another token in, before fees
+    ///
+    /// # Arguments
+    /// * source_index - index of the token swapped in.
+    /// * destination_index - index of the token swapped out.
+    /// * amount_in - amount swapped in.
+    pub fn get_out_amount(
+        &self,
+        source_index: usize,
+        destination_index: usize,
+        amount_in: u64,
+    ) -> Result<u64, ProgramError> {
+        if source_index == destination_index {
+            return Err(SwapError::InvalidInput.into());
+        }
+        let mut balances = self.normalized_balances(&self.reserves)?;
+        let d = compute_stable_invariant(self.amp, &balances)?;
+        let destination_balance = balances[destination_index];
+        balances[source_index] = (amount_in as u128)
+            .checked_mul(self.multiplier(source_index)?)
+            .and_then(|amount| amount.checked_add(balances[source_index]))
+            .ok_or(SwapError::CalculationFailure)?;
+        let new_balance = compute_stable_balance(self.amp, &balances, destination_index, d)?;
+        // One unit is kept in the pool against the rounding of the invariant
+        let amount_out = destination_balance
+            .checked_sub(new_balance)
+            .and_then(|amount| amount.checked_sub(1))
+            .ok_or(SwapError::CalculationFailure)?
+            / self.multiplier(destination_index)?;
+        u64::try_from(amount_out).map_err(|_| SwapError::CalculationFailure.into())
+    }
+
+    /// Update the reserves with a swap
+    ///
+    /// # Arguments
+    /// * source_index - index of the token swapped in.
+    /// * destination_index - index of the token swapped out.
+    /// * amount_in - amount swapped in.
+    /// * amount_out - amount leaving the pool, the admin and treasury fees included.
+    pub fn swap(
+        &mut self,
+        source_index: usize,
+        destination_index: usize,
+        amount_in: u64,
+        amount_out: u64,
+    ) -> ProgramResult {
+        self.reserves[source_index] = self.reserves[source_index]
+            .checked_add(amount_in)
+            .ok_or(SwapError::CalculationFailure)?;
+        self.reserves[destination_index] = self.reserves[destination_index]
+            .checked_sub(amount_out)
+            .ok_or(SwapError::CalculationFailure)?;
+        Ok(())
+    }
+
+    /// Pool tokens minted for a deposit. The first deposit mints the invariant, the next
+    /// ones the share of the invariant they add, after an imbalance fee on the amounts
+    /// deposited away from the ratio of the pool, kept in the pool.
+    ///
+    /// # Arguments
+    /// * amounts - amounts of the tokens to deposit.
+    /// * supply - supply of the pool mint.
+    pub fn deposit_mint_amount(
+        &self,
+        amounts: &[u64; TRI_POOL_TOKENS],
+        supply: u64,
+    ) -> Result<u64, ProgramError> {
+        let mut new_reserves = [0u64; TRI_POOL_TOKENS];
+        for (index, amount) in amounts.iter().enumerate() {
+            new_reserves[index] = self.reserves[index]
+                .checked_add(*amount)
+                .ok_or(SwapError::CalculationFailure)?;
+        }
+        let new_d = self.invariant(&new_reserves)?;
+        if supply == 0 {
+            if amounts.iter().any(|amount| *amount == 0) {
+                return Err(SwapError::InsufficientFunds.into());
+            }
+            return u64::try_from(new_d).map_err(|_| SwapError::CalculationFailure.into());
+        }
+
+        let d = self.invariant(&self.reserves)?;
+        if new_d <= d {
+            return Err(SwapError::CalculationFailure.into());
+        }
+        // The imbalance is charged at n / (4 * (n - 1)) of the trade fee
+        let mut charged_reserves = new_reserves;
+        for (index, charged_reserve) in charged_reserves.iter_mut().enumerate() {
+            let ideal_reserve = (self.reserves[index] as u128)
+                .checked_mul(new_d)
+                .ok_or(SwapError::CalculationFailure)?
+                / d;
+            let new_reserve = new_reserves[index] as u128;
+            let imbalance = if ideal_reserve > new_reserve {
+                ideal_reserve - new_reserve
+            } else {
+                new_reserve - ideal_reserve
+            };
+            let imbalance = u64::try_from(imbalance).map_err(|_| SwapError::CalculationFailure)?;
+            let imbalance_fee = self
+                .fees
+                .trade_fee(imbalance)?
+                .checked_mul(TRI_POOL_TOKENS as u64)
+                .ok_or(SwapError::CalculationFailure)?
+                / (4 * (TRI_POOL_TOKENS as u64 - 1));
+            *charged_reserve = charged_reserve
+                .checked_sub(imbalance_fee)
+                .ok_or(SwapError::CalculationFailure)?;
+        }
+        let charged_d = self.invariant(&charged_reserves)?;
+        let mint_amount = (supply as u128)
+            .checked_mul(charged_d.saturating_sub(d))
+            .ok_or(SwapError::CalculationFailure)?
+            / d;
+        u64::try_from(mint_amount).map_err(|_| SwapError::CalculationFailure.into())
+    }
+
+    /// Update the reserves with a deposit
+    pub fn deposit(&mut self, amounts: &[u64; TRI_POOL_TOKENS]) -> ProgramResult {
+        for (reserve, amount) in self.reserves.iter_mut().zip(amounts.iter()) {
+            *reserve = reserve
+                .checked_add(*amount)
+                .ok_or(SwapError::CalculationFailure)?;
+        }
+        Ok(())
+    }
+
+    /// Amounts of the tokens backing an amount of pool tokens, at the ratio of the pool
+    ///
+    /// # Arguments
+    /// * pool_token_amount - amount of pool tokens to withdraw.
+    /// * supply - supply of the pool mint.
+    pub fn withdraw_amounts(
+        &self,
+        pool_token_amount: u64,
+        supply: u64,
+    ) -> Result<[u64; TRI_POOL_TOKENS], ProgramError> {
+        if pool_token_amount > supply || supply == 0 {
+            return Err(SwapError::CalculationFailure.into());
+        }
+        let mut amounts = [0u64; TRI_POOL_TOKENS];
+        for (amount, reserve) in amounts.iter_mut().zip(self.reserves.iter()) {
+            *amount = ((*reserve as u128)
+                .checked_mul(pool_token_amount as u128)
+                .ok_or(SwapError::CalculationFailure)?
+                / supply as u128) as u64;
+        }
+        Ok(amounts)
+    }
+
+    /// Update the reserves with a withdrawal
+    pub fn withdraw(&mut self, amounts: &[u64; TRI_POOL_TOKENS]) -> ProgramResult {
+        for (reserve, amount) in self.reserves.iter_mut().zip(amounts.iter()) {
+            *reserve = reserve
+                .checked_sub(*amount)
+                .ok_or(SwapError::CalculationFailure)?;
+        }
+        Ok(())
+    }
+
+    /// Check the reserve of a token matches the token amount in the pool
+    pub fn check_reserve_amount(&self, index: usize, token_amount: u64) -> ProgramResult {
+        if self.reserves[index] > token_amount {
+            return Err(SwapError::InconsistentPoolState.into());
+        }
+        if self.reserves[index] == 0 {
+            return Err(SwapError::InsufficientFunds.into());
+        }
+        Ok(())
+    }
+}
+
+impl Sealed for TriSwapInfo {}
+impl IsInitialized for TriSwapInfo {
+    fn is_initialized(&self) -> bool {
+        self.is_initialized
+    }
+}
+
+const TRI_SWAP_INFO_SIZE: usize = 553;
+const TRI_SWAP_INFO_USED_BYTES: usize = 1
+    + 1
+    + 1
+    + PUBKEY_BYTES * 2
+    + PUBKEY_BYTES * TRI_POOL_TOKENS * 3
+    + TRI_POOL_TOKENS
+    + 8
+    + Fees::LEN
+    + 8 * TRI_POOL_TOKENS;
+const TRI_SWAP_INFO_RESERVED_U64: usize = (TRI_SWAP_INFO_SIZE - TRI_SWAP_INFO_USED_BYTES) / 8;
+
+impl Pack for TriSwapInfo {
+    const LEN: usize = TRI_SWAP_INFO_SIZE;
+
+    fn unpack_from_slice(src: &[u8]) -> Result<Self, ProgramError> {
+        let input = array_ref![src, 0, TRI_SWAP_INFO_SIZE];
+        #[allow(clippy::ptr_offset_with_cast)]
+        let (
+            is_initialized,
+            is_paused,
+            nonce,
+            config_key,
+            pool_mint,
+            tokens,
+            token_mints,
+            admin_fee_keys,
+            token_decimals,
+            amp,
+            fees,
+            reserves,
+            _,
+        ) = array_refs![
+            input,
+            1,
+            1,
+            1,
+            PUBKEY_BYTES,
+            PUBKEY_BYTES,
+            PUBKEY_BYTES * TRI_POOL_TOKENS,
+            PUBKEY_BYTES * TRI_POOL_TOKENS,
+            PUBKEY_BYTES * TRI_POOL_TOKENS,
+            TRI_POOL_TOKENS,
+            8,
+            Fees::LEN,
+            8 * TRI_POOL_TOKENS,
+            TRI_SWAP_INFO_SIZE - TRI_SWAP_INFO_USED_BYTES
+        ];
+        let unpack_pubkeys = |src: &[u8; PUBKEY_BYTES * TRI_POOL_TOKENS]| {
+            let mut keys = [Pubkey::default(); TRI_POOL_TOKENS];
+            for (key, chunk) in keys.iter_mut().zip(src.chunks_exact(PUBKEY_BYTES)) {
+                *key = Pubkey::new(chunk);
+            }
+            keys
+        };
+        let mut unpacked_reserves = [0u64; TRI_POOL_TOKENS];
+        for (reserve, chunk) in unpacked_reserves.iter_mut().zip(reserves.chunks_exact(8)) {
+            *reserve = u64::from_le_bytes(*array_ref![chunk, 0, 8]);
+        }
+
+        Ok(Self {
+            is_initialized: unpack_bool(is_initialized)?,
+            is_paused: unpack_bool(is_paused)?,
+            nonce: nonce[0],
+            config_key: Pubkey::new_from_array(*config_key),
+            pool_mint: Pubkey::new_from_array(*pool_mint),
+            tokens: unpack_pubkeys(tokens),
+            token_mints: unpack_pubkeys(token_mints),
+            admin_fee_keys: unpack_pubkeys(admin_fee_keys),
+            token_decimals: *token_decimals,
+            amp: u64::from_le_bytes(*amp),
+            fees: Fees::unpack_from_slice(fees)?,
+            reserves: unpacked_reserves,
+            ..Self::default()
+        })
+    }
+
+    fn pack_into_slice(&self, dst: &mut [u8]) {
+        let output = array_mut_ref![dst, 0, TRI_SWAP_INFO_SIZE];
+        #[allow(clippy::ptr_offset_with_cast)]
+        let (
+            is_initialized,
+            is_paused,
+            nonce,
+            config_key,
+            pool_mint,
+            tokens,
+            token_mints,
+            admin_fee_keys,
+            token_decimals,
+            amp,
+            fees,
+            reserves,
+            _,
+        ) = mut_array_refs![
+            output,
+            1,
+            1,
+            1,
+            PUBKEY_BYTES,
+            PUBKEY_BYTES,
+            PUBKEY_BYTES * TRI_POOL_TOKENS,
+            PUBKEY_BYTES * TRI_POOL_TOKENS,
+            PUBKEY_BYTES * TRI_POOL_TOKENS,
+            TRI_POOL_TOKENS,
+            8,
+            Fees::LEN,
+            8 * TRI_POOL_TOKENS,
+            TRI_SWAP_INFO_SIZE - TRI_SWAP_INFO_USED_BYTES
+        ];
+        let pack_pubkeys = |keys: &[Pubkey; TRI_POOL_TOKENS], dst: &mut [u8]| {
+            for (key, chunk) in keys.iter().zip(dst.chunks_exact_mut(PUBKEY_BYTES)) {
+                chunk.copy_from_slice(key.as_ref());
+            }
+        };
+        pack_bool(self.is_initialized, is_initialized);
+        pack_bool(self.is_paused, is_paused);
+        nonce[0] = self.nonce;
+        config_key.copy_from_slice(self.config_key.as_ref());
+        pool_mint.copy_from_slice(self.pool_mint.as_ref());
+        pack_pubkeys(&self.tokens, tokens);
+        pack_pubkeys(&self.token_mints, token_mints);
+        pack_pubkeys(&self.admin_fee_keys, admin_fee_keys);
+        *token_decimals = self.token_decimals;
+        *amp = self.amp.to_le_bytes();
+        self.fees.pack_into_slice(&mut fees[..]);
+        for (reserve, chunk) in self.reserves.iter().zip(reserves.chunks_exact_mut(8)) {
+            chunk.copy_from_slice(&reserve.to_le_bytes());
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn tri_swap_info() -> TriSwapInfo {
+        TriSwapInfo {
+            is_initialized: true,
+            nonce: 255,
+            config_key: Pubkey::new_unique(),
+            pool_mint: Pubkey::new_unique(),
+            tokens: [
+                Pubkey::new_unique(),
+                Pubkey::new_unique(),
+                Pubkey::new_unique(),
+            ],
+            token_mints: [
+                Pubkey::new_unique(),
+                Pubkey::new_unique(),
+                Pubkey::new_unique(),
+            ],
+            admin_fee_keys: [
+                Pubkey::new_unique(),
+                Pubkey::new_unique(),
+                Pubkey::new_unique(),
+            ],
+            token_decimals: [6, 6, 9],
+            amp: 100,
+            fees: DEFAULT_TEST_FEES,
+            reserves: [1_000_000_000, 1_000_000_000, 1_000_000_000_000],
+            ..TriSwapInfo::default()
+        }
+    }
+
+    #[test]
+    fn test_tri_swap_info_packing() {
+        let tri_swap = tri_swap_info();
+        let mut packed = [0u8; TriSwapInfo::LEN];
+        TriSwapInfo::pack(tri_swap.clone(), &mut packed).unwrap();
+        assert_eq!(TriSwapInfo::unpack(&packed).unwrap(), tri_swap);
+        assert_eq!(tri_swap.token_index(&tri_swap.tokens[2]).unwrap(), 2);
+        assert_eq!(
+            tri_swap.token_index(&tri_swap.pool_mint).unwrap_err(),
+            SwapError::IncorrectSwapAccount.into()
+        );
+    }
+
+    #[test]
+    fn test_tri_swap_get_out_amount() {
+        let mut tri_swap = tri_swap_info();
+        assert_eq!(tri_swap.precision(), 9);
+        // 1 of the 6 decimals token buys about 1 of the 9 decimals token
+        let amount_out = tri_swap.get_out_amount(0, 2, 1_000_000).unwrap();
+        assert!(amount_out > 999_000_000 && amount_out < 1_000_000_000);
+        assert_eq!(
+            tri_swap.get_out_amount(1, 1, 1_000).unwrap_err(),
+            SwapError::InvalidInput.into()
+        );
+
+        tri_swap.swap(0, 2, 1_000_000, amount_out).unwrap();
+        assert_eq!(tri_swap.reserves[0], 1_001_000_000);
+        assert_eq!(tri_swap.reserves[2], 1_000_000_000_000 - amount_out);
+        // Selling back the 9 decimals token returns no more than the 6 decimals token sold
+        let amount_back = tri_swap.get_out_amount(2, 0, amount_out).unwrap();
+        assert!(amount_back < 1_000_000);
+    }
+
+    #[test]
+    fn test_tri_swap_deposit_withdraw() {
+        let mut tri_swap = TriSwapInfo {
+            reserves: [0; TRI_POOL_TOKENS],
+            ..tri_swap_info()
+        };
+        let initial_amounts = [1_000_000_000, 1_000_000_000, 1_000_000_000_000];
+        assert_eq!(
+            tri_swap
+                .deposit_mint_amount(&[1_000_000_000, 0, 1_000_000_000_000], 0)
+                .unwrap_err(),
+            SwapError::InsufficientFunds.into()
+        );
+        // The first deposit mints the invariant at 9 decimals
+        let supply = tri_swap.deposit_mint_amount(&initial_amounts, 0).unwrap();
+        assert_eq!(supply, 3_000_000_000_000);
+        tri_swap.deposit(&initial_amounts).unwrap();
+
+        // A balanced deposit mints its share, an imbalanced one pays the imbalance fee
+        let balanced = tri_swap
+            .deposit_mint_amount(&[1_000_000, 1_000_000, 1_000_000_000], supply)
+            .unwrap();
+        assert_eq!(balanced, 3_000_000_000);
+        let imbalanced = tri_swap
+            .deposit_mint_amount(&[3_000_000, 0, 0], supply)
+            .unwrap();
+        assert!(imbalanced < balanced);
+
+        let amounts = tri_swap.withdraw_amounts(supply / 10, supply).unwrap();
+        assert_eq!(amounts, [100_000_000, 100_000_000, 100_000_000_000]);
+        tri_swap.withdraw(&amounts).unwrap();
+        tri_swap.check_reserve_amount(1, 900_000_000).unwrap();
+        assert_eq!(
+            tri_swap.check_reserve_amount(1, 899_999_999).unwrap_err(),
+            SwapError::InconsistentPoolState.into()
+        );
+        assert_eq!(
+            tri_swap.withdraw_amounts(supply + 1, supply).unwrap_err(),
+            SwapError::CalculationFailure.into()
+        );
+    }
+}
@@ -0,0 +1,84 @@
+//! Whitelist entries of the users of the whitelist-gated pools
+
+use arrayref::{array_mut_ref, array_ref, array_refs, mut_array_refs};
+use solana_program::{
+    program_error::ProgramError,
+    program_pack::{IsInitialized, Pack, Sealed},
+    pubkey::{Pubkey, PUBKEY_BYTES},
+};
+
+use super::*;
+
+/// Whitelist entry of a user on a pool, at the program address of the pool and the user
+#[derive(Clone, Debug, Default, PartialEq)]
+pub struct WhitelistEntry {
+    /// Initialization status
+    pub is_initialized: bool,
+    /// Bump seed of the whitelist entry address
+    pub bump_seed: u8,
+    /// Token swap of the pool
+    pub swap_key: Pubkey,
+    /// Whitelisted user, the transfer authority of the swaps and deposits
+    pub user: Pubkey,
+}
+
+impl Sealed for WhitelistEntry {}
+impl IsInitialized for WhitelistEntry {
+    fn is_initialized(&self) -> bool {
+        self.is_initialized
+    }
+}
+
+const WHITELIST_ENTRY_SIZE: usize = 1 + 1 + PUBKEY_BYTES * 2;
+
+impl Pack for WhitelistEntry {
+    const LEN: usize = WHITELIST_ENTRY_SIZE;
+
+    fn pack_into_slice(&self, dst: &mut [u8]) {
+        let output = array_mut_ref![dst, 0, WHITELIST_ENTRY_SIZE];
+        #[allow(clippy::ptr_offset_with_cast)]
+        let (is_initialized, bump_seed, swap_key, user) =
+            mut_array_refs![output, 1, 1, PUBKEY_BYTES, PUBKEY_BYTES];
+        pack_bool(self.is_initialized, is_initialized);
+        bump_seed[0] = self.bump_seed;
+        swap_key.copy_from_slice(self.swap_key.as_ref());
+        user.copy_from_slice(self.user.as_ref());
+    }
+
+    fn unpack_from_slice(src: &[u8]) -> Result<Self, ProgramError> {
+        let input = array_ref![src, 0, WHITELIST_ENTRY_SIZE];
+        #[allow(clippy::ptr_offset_with_cast)]
+        let (is_initialized, bump_seed, swap_key, user) =
+            array_refs![input, 1, 1, PUBKEY_BYTES, PUBKEY_BYTES];
+        Ok(Self {
+            is_initialized: unpack_bool(is_initialized)?,
+            bump_seed: bump_seed[0],
+            swap_key: Pubkey::new_from_array(*swap_key),
+            user: Pubkey::new_from_array(*user),
+        })
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_whitelist_entry_packing() {
+        let whitelist_entry = WhitelistEntry {
+            is_initialized: true,
+            bump_seed: 253,
+            swap_key: Pubkey::new_unique(),
+            user: Pubkey::new_unique(),
+        };
+        let mut packed = [0u8; WhitelistEntry::LEN];
+        WhitelistEntry::pack_into_slice(&whitelist_entry, &mut packed);
+        assert_eq!(WhitelistEntry::unpack(&packed).unwrap(), whitelist_entry);
+
+        let packed = [0u8; WhitelistEntry::LEN];
+        assert_eq!(
+            WhitelistEntry::unpack(&packed).unwrap_err(),
+            ProgramError::UninitializedAccount
+        );
+    }
+}
@@ -0,0 +1,57 @@
+//! Time source of the slot and timestamp dependent logic
+
+use solana_program::clock::{Clock, Slot, UnixTimestamp};
+
+/// Source of the current slot and unix timestamp, the clock sysvar on chain
+/// and a manually stepped clock in tests
+pub trait TimeSource {
+    /// Current slot
+    fn slot(&self) -> Slot;
+    /// Current unix timestamp
+    fn unix_timestamp(&self) -> UnixTimestamp;
+}
+
+impl TimeSource for Clock {
+    fn slot(&self) -> Slot {
+        self.slot
+    }
+
+    fn unix_timestamp(&self) -> UnixTimestamp {
+        self.unix_timestamp
+    }
+}
+
+/// Time source stepped by hand
+#[derive(Clone, Copy, Debug, Default, PartialEq)]
+pub struct ManualClock {
+    /// Current slot
+    pub slot: Slot,
+    /// Current unix timestamp
+    pub unix_timestamp: UnixTimestamp,
+}
+
+impl ManualClock {
+    /// Create a clock at the slot and timestamp
+    pub fn new(slot: Slot, unix_timestamp: UnixTimestamp) -> Self {
+        Self {
+            slot,
+            unix_timestamp,
+        }
+    }
+
+    /// Move the clock forward
+    pub fn advance(&mut self, slots: Slot, seconds: UnixTimestamp) {
+        self.slot += slots;
+        self.unix_timestamp += seconds;
+    }
+}
+
+impl TimeSource for ManualClock {
+    fn slot(&self) -> Slot {
+        self.slot
+    }
+
+    fn unix_timestamp(&self) -> UnixTimestamp {
+        self.unix_timestamp
+    }
+}
@@ -0,0 +1,443 @@
+//! Instructions of a whole `SwapV2` transaction of a wallet, composed for integrators: the
+//! associated token accounts are created, native SOL is wrapped in and out of wSOL, and the
+//! optional accounts of the swap are derived from the pool, the config and the referrer.
+//!
+//! The price cache and the fee discount of the user are left out, the swap then reads the
+//! oracles and charges the full trade fee.
+
+use crate::{
+    error::SwapError,
+    instruction::{swap_v2, SwapData},
+    processor::{
+        find_referrer_stats_address, find_whitelist_entry_address, get_referrer_data_pubkey,
+    },
+    state::{ConfigInfo, SwapInfo},
+};
+use solana_program::{
+    instruction::{AccountMeta, Instruction},
+    program_error::ProgramError,
+    pubkey::Pubkey,
+    system_instruction,
+};
+use spl_associated_token_account::{create_associated_token_account, get_associated_token_address};
+use spl_token::{
+    instruction::{close_account, sync_native},
+    native_mint,
+};
+
+/// Index of the whitelist entry in the `SwapV2` accounts, right after the token program
+const WHITELIST_ENTRY_INDEX: usize = 18;
+
+/// Serum market of a pool, its key is only stored hashed in the pool
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub struct SerumMarketKeys {
+    /// Market
+    pub market: Pubkey,
+    /// Bids of the market
+    pub bids: Pubkey,
+    /// Asks of the market
+    pub asks: Pubkey,
+}
+
+/// Referrer of the user as set with `SetReferrer`
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub struct ReferrerKeys {
+    /// DELTAFI token account of the referrer, stored in the user referrer data
+    pub token: Pubkey,
+    /// Owner of the referrer token account, receiving the referral fee in its associated
+    /// token account of the destination mint
+    pub owner: Pubkey,
+}
+
+/// Swap of a wallet, the token accounts are its associated token accounts
+#[derive(Clone, Debug, PartialEq)]
+pub struct SwapV2Params {
+    /// Wallet swapping, signer and payer of the created accounts
+    pub user: Pubkey,
+    /// Mint sold, the native mint to swap native SOL
+    pub source_mint: Pubkey,
+    /// Amount sold
+    pub amount_in: u64,
+    /// Minimum amount bought
+    pub minimum_amount_out: u64,
+    /// Last slot the swap can land in
+    pub deadline_slot: Option<u64>,
+    /// Referrer of the user, none if the user has no referrer data or the dummy referrer
+    pub referrer: Option<ReferrerKeys>,
+    /// Create the associated token account of the source mint, native SOL is always
+    /// wrapped in a new one
+    pub create_source_account: bool,
+    /// Create the associated token account of the destination mint
+    pub create_destination_account: bool,
+    /// Create the associated token account of DELTAFI receiving the trade reward
+    pub create_reward_account: bool,
+}
+
+/// Instructions swapping `params.amount_in` of the source mint of the user on a normal pool.
+///
+/// Native SOL is transferred into the associated wSOL account of the user and the wSOL
+/// left is unwrapped after the swap, the same for the wSOL bought. The wSOL account is then
+/// closed, it must not be open before a swap of native SOL.
+pub fn swap_v2_instructions(
+    program_id: &Pubkey,
+    config_key: &Pubkey,
+    config: &ConfigInfo,
+    swap_key: &Pubkey,
+    swap_info: &SwapInfo,
+    serum: &SerumMarketKeys,
+    params: &SwapV2Params,
+) -> Result<Vec<Instruction>, ProgramError> {
+    let user = &params.user;
+    let (destination_mint, swap_source, swap_destination, admin_fee_destination) =
+        if params.source_mint == swap_info.token_a_mint {
+            (
+                swap_info.token_b_mint,
+                swap_info.token_a,
+                swap_info.token_b,
+                swap_info.admin_fee_key_b,
+            )
+        } else if params.source_mint == swap_info.token_b_mint {
+            (
+                swap_info.token_a_mint,
+                swap_info.token_b,
+                swap_info.token_a,
+                swap_info.admin_fee_key_a,
+            )
+        } else {
+            return Err(SwapError::IncorrectMint.into());
+        };
+    let source = get_associated_token_address(user, &params.source_mint);
+    let destination = get_associated_token_address(user, &destination_mint);
+    let reward_token = get_associated_token_address(user, &config.deltafi_mint);
+    let is_native_source = params.source_mint == native_mint::id();
+    let is_native_destination = destination_mint == native_mint::id();
+
+    let mut instructions = vec![];
+    if params.create_source_account || is_native_source {
+        instructions.push(create_associated_token_account(
+            user,
+            user,
+            &params.source_mint,
+        ));
+    }
+    if is_native_source {
+        instructions.push(system_instruction::transfer(
+            user,
+            &source,
+            params.amount_in,
+        ));
+        instructions.push(sync_native(&spl_token::id(), &source)?);
+    }
+    if params.create_destination_account {
+        instructions.push(create_associated_token_account(
+            user,
+            user,
+            &destination_mint,
+        ));
+    }
+    if params.create_reward_account {
+        instructions.push(create_associated_token_account(
+            user,
+            user,
+            &config.deltafi_mint,
+        ));
+    }
+
+    let market_authority =
+        Pubkey::create_program_address(&[config_key.as_ref(), &[config.bump_seed]], program_id)?;
+    let swap_authority =
+        Pubkey::create_program_address(&[swap_key.as_ref(), &[swap_info.nonce]], program_id)?;
+    // The protocol fee goes to the associated token account of the treasury
+    let treasury_token = if config.is_protocol_fee_enabled {
+        Some(get_associated_token_address(
+            &config.treasury_key,
+            &destination_mint,
+        ))
+    } else {
+        None
+    };
+    let (user_referrer_data, referrer_token, referrer_fee_token, referrer_stats) =
+        match &params.referrer {
+            Some(referrer) => (
+                Some(get_referrer_data_pubkey(user, config_key, program_id)?),
+                Some(referrer.token),
+                if swap_info.rewards.referral_fee_bps > 0 {
+                    Some(get_associated_token_address(
+                        &referrer.owner,
+                        &destination_mint,
+                    ))
+                } else {
+                    None
+                },
+                // The stats are ignored by the swap while the referrer has none
+                Some(find_referrer_stats_address(config_key, &referrer.token, program_id).0),
+            ),
+            None => (None, None, None, None),
+        };
+
+    let mut swap = swap_v2(
+        *program_id,
+        *config_key,
+        *swap_key,
+        market_authority,
+        swap_authority,
+        *user,
+        source,
+        swap_source,
+        swap_destination,
+        destination,
+        reward_token,
+        config.deltafi_token,
+        admin_fee_destination,
+        swap_info.pyth_a,
+        swap_info.pyth_b,
+        serum.market,
+        serum.bids,
+        serum.asks,
+        None,
+        None,
+        None,
+        treasury_token,
+        user_referrer_data,
+        referrer_token,
+        referrer_fee_token,
+        referrer_stats,
+        SwapData {
+            amount_in: params.amount_in,
+            minimum_amount_out: params.minimum_amount_out,
+            deadline_slot: params.deadline_slot,
+            output_owner: None,
+        },
+    )?;
+    if swap_info.whitelist_enabled {
+        let (whitelist_entry, _) = find_whitelist_entry_address(swap_key, user, program_id);
+        swap.accounts.insert(
+            WHITELIST_ENTRY_INDEX,
+            AccountMeta::new_readonly(whitelist_entry, false),
+        );
+    }
+    instructions.push(swap);
+
+    for (is_native, account) in [
+        (is_native_source, source),
+        (is_native_destination, destination),
+    ] {
+        if is_native {
+            instructions.push(close_account(&spl_token::id(), &account, user, user, &[])?);
+        }
+    }
+    Ok(instructions)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::{instruction::SwapInstruction, state::Rewards};
+
+    struct TestPool {
+        config_key: Pubkey,
+        config: ConfigInfo,
+        swap_key: Pubkey,
+        swap_info: SwapInfo,
+        serum: SerumMarketKeys,
+    }
+
+    fn test_pool(token_b_mint: Pubkey) -> TestPool {
+        let program_id = crate::id();
+        let config_key = Pubkey::new_unique();
+        let swap_key = Pubkey::new_unique();
+        let (_, bump_seed) = Pubkey::find_program_address(&[config_key.as_ref()], &program_id);
+        let (_, nonce) = Pubkey::find_program_address(&[swap_key.as_ref()], &program_id);
+        TestPool {
+            config_key,
+            config: ConfigInfo {
+                bump_seed,
+                deltafi_mint: Pubkey::new_unique(),
+                deltafi_token: Pubkey::new_unique(),
+                treasury_key: Pubkey::new_unique(),
+                ..ConfigInfo::default()
+            },
+            swap_key,
+            swap_info: SwapInfo {
+                nonce,
+                token_a: Pubkey::new_unique(),
+                token_b: Pubkey::new_unique(),
+                token_a_mint: Pubkey::new_unique(),
+                token_b_mint,
+                admin_fee_key_a: Pubkey::new_unique(),
+                admin_fee_key_b: Pubkey::new_unique(),
+                pyth_a: Pubkey::new_unique(),
+                pyth_b: Pubkey::new_unique(),
+                ..SwapInfo::default()
+            },
+            serum: SerumMarketKeys {
+                market: Pubkey::new_unique(),
+                bids: Pubkey::new_unique(),
+                asks: Pubkey::new_unique(),
+            },
+        }
+    }
+
+    fn swap_params(source_mint: Pubkey) -> SwapV2Params {
+        SwapV2Params {
+            user: Pubkey::new_unique(),
+            source_mint,
+            amount_in: 1_000,
+            minimum_amount_out: 900,
+            deadline_slot: None,
+            referrer: None,
+            create_source_account: false,
+            create_destination_account: false,
+            create_reward_account: false,
+        }
+    }
+
+    fn build(pool: &TestPool, params: &SwapV2Params) -> Vec<Instruction> {
+        swap_v2_instructions(
+            &crate::id(),
+            &pool.config_key,
+            &pool.config,
+            &pool.swap_key,
+            &pool.swap_info,
+            &pool.serum,
+            params,
+        )
+        .unwrap()
+    }
+
+    #[test]
+    fn test_swap_v2_instructions() {
+        let pool = test_pool(Pubkey::new_unique());
+        let params = swap_params(pool.swap_info.token_b_mint);
+        let instructions = build(&pool, &params);
+        assert_eq!(instructions.len(), 1);
+
+        let swap = &instructions[0];
+        assert_eq!(swap.accounts.len(), 18);
+        assert_eq!(
+            swap.accounts[5].pubkey,
+            get_associated_token_address(&params.user, &pool.swap_info.token_b_mint)
+        );
+        assert_eq!(swap.accounts[6].pubkey, pool.swap_info.token_b);
+        assert_eq!(swap.accounts[7].pubkey, pool.swap_info.token_a);
+        assert_eq!(
+            swap.accounts[8].pubkey,
+            get_associated_token_address(&params.user, &pool.swap_info.token_a_mint)
+        );
+        assert_eq!(swap.accounts[11].pubkey, pool.swap_info.admin_fee_key_a);
+        assert_eq!(swap.accounts[14].pubkey, pool.serum.market);
+        assert!(matches!(
+            SwapInstruction::unpack(&swap.data).unwrap(),
+            SwapInstruction::SwapV2(SwapData {
+                amount_in: 1_000,
+                minimum_amount_out: 900,
+                ..
+            })
+        ));
+
+        assert_eq!(
+            swap_v2_instructions(
+                &crate::id(),
+                &pool.config_key,
+                &pool.config,
+                &pool.swap_key,
+                &pool.swap_info,
+                &pool.serum,
+                &swap_params(Pubkey::new_unique()),
+            ),
+            Err(SwapError::IncorrectMint.into())
+        );
+    }
+
+    #[test]
+    fn test_swap_v2_instructions_native_sol() {
+        let pool = test_pool(native_mint::id());
+
+        // Selling SOL, wrapped then closed
+        let params = SwapV2Params {
+            create_destination_account: true,
+            ..swap_params(native_mint::id())
+        };
+        let instructions = build(&pool, &params);
+        let wsol = get_associated_token_address(&params.user, &native_mint::id());
+        assert_eq!(instructions.len(), 6);
+        assert_eq!(
+            instructions[0],
+            create_associated_token_account(&params.user, &params.user, &native_mint::id())
+        );
+        assert_eq!(
+            instructions[1],
+            system_instruction::transfer(&params.user, &wsol, 1_000)
+        );
+        assert_eq!(
+            instructions[2],
+            sync_native(&spl_token::id(), &wsol).unwrap()
+        );
+        assert_eq!(
+            instructions[3],
+            create_associated_token_account(
+                &params.user,
+                &params.user,
+                &pool.swap_info.token_a_mint
+            )
+        );
+        assert_eq!(instructions[4].program_id, crate::id());
+        assert_eq!(
+            instructions[5],
+            close_account(&spl_token::id(), &wsol, &params.user, &params.user, &[]).unwrap()
+        );
+
+        // Buying SOL, unwrapped after the swap
+        let params = SwapV2Params {
+            create_destination_account: true,
+            ..swap_params(pool.swap_info.token_a_mint)
+        };
+        let instructions = build(&pool, &params);
+        assert_eq!(instructions.len(), 3);
+        assert_eq!(
+            instructions[2],
+            close_account(&spl_token::id(), &wsol, &params.user, &params.user, &[]).unwrap()
+        );
+    }
+
+    #[test]
+    fn test_swap_v2_instructions_optional_accounts() {
+        let mut pool = test_pool(Pubkey::new_unique());
+        pool.config.is_protocol_fee_enabled = true;
+        pool.swap_info.whitelist_enabled = true;
+        pool.swap_info.rewards = Rewards {
+            referral_fee_bps: 1_000,
+            ..Rewards::default()
+        };
+        let referrer = ReferrerKeys {
+            token: Pubkey::new_unique(),
+            owner: Pubkey::new_unique(),
+        };
+        let params = SwapV2Params {
+            referrer: Some(referrer),
+            ..swap_params(pool.swap_info.token_a_mint)
+        };
+        let swap = &build(&pool, &params)[0];
+        let optional: Vec<Pubkey> = swap.accounts[18..].iter().map(|a| a.pubkey).collect();
+        assert_eq!(
+            optional,
+            vec![
+                find_whitelist_entry_address(&pool.swap_key, &params.user, &crate::id()).0,
+                get_associated_token_address(
+                    &pool.config.treasury_key,
+                    &pool.swap_info.token_b_mint
+                ),
+                get_referrer_data_pubkey(&params.user, &pool.config_key, &crate::id()).unwrap(),
+                referrer.token,
+                get_associated_token_address(&referrer.owner, &pool.swap_info.token_b_mint),
+                find_referrer_stats_address(&pool.config_key, &referrer.token, &crate::id()).0,
+            ]
+        );
+
+        // Without referral fee the referrer is only paid the DELTAFI reward
+        pool.swap_info.rewards.referral_fee_bps = 0;
+        let swap = &build(&pool, &params)[0];
+        assert_eq!(swap.accounts.len(), 23);
+        assert_eq!(swap.accounts[21].pubkey, referrer.token);
+    }
+}
@@ -1,19 +1,23 @@
 //! Util functions
 
 use solana_program::{
-    account_info::AccountInfo, entrypoint::ProgramResult, hash::hashv, pubkey::Pubkey,
+    account_info::AccountInfo,
+    entrypoint::ProgramResult,
+    hash::hashv,
+    pubkey::Pubkey,
+    sysvar::instructions::{load_current_index_checked, load_instruction_at_checked},
 };
 
 use crate::{
     curve::SwapDirection,
     error::SwapError,
+    orderbook,
     pyth::PYTH_PROGRAM_ID,
     state::{FarmInfo, SwapInfo},
+    DUMMY_REFERRER_ADDRESS, LEGACY_DUMMY_REFERRER_ADDRESS, OPENBOOK_DEX_PROGRAM_ID,
     SERUM_DEX_V3_PROGRAM_ID,
 };
-use safe_transmute::to_bytes::transmute_to_bytes;
-use serum_dex::state::Market;
-use std::{convert::identity, str::FromStr};
+use std::str::FromStr;
 
 /// Utils funciton to validate certain condition.
 pub fn validate(condition: bool, error_code: SwapError) -> ProgramResult {
@@ -23,6 +27,16 @@ pub fn validate(condition: bool, error_code: SwapError) -> ProgramResult {
     Ok(())
 }
 
+/// Whether the referrer is the placeholder of the users not referred by anyone.
+pub fn is_dummy_referrer(referrer: &Pubkey) -> bool {
+    *referrer == DUMMY_REFERRER_ADDRESS || *referrer == LEGACY_DUMMY_REFERRER_ADDRESS
+}
+
+/// Whether the referrer given matches the stored one, the dummy referrers match each other.
+pub fn is_same_referrer(stored: &Pubkey, given: &Pubkey) -> bool {
+    stored == given || (is_dummy_referrer(stored) && is_dummy_referrer(given))
+}
+
 /// Validate the relationship between swap info and the config.
 pub fn validate_swap_config_key(token_swap: &SwapInfo, config_key: &Pubkey) -> ProgramResult {
     validate(
@@ -39,6 +53,47 @@ pub fn validate_farm_config_key(farm: &FarmInfo, config_key: &Pubkey) -> Program
     Ok(())
 }
 
+/// Validate the swap output goes to a token account owned by a signer of the instruction,
+/// enforced on pools with the strict destination owner flag
+pub fn validate_destination_owner(
+    destination_owner: &Pubkey,
+    accounts: &[AccountInfo],
+) -> ProgramResult {
+    validate(
+        accounts
+            .iter()
+            .any(|account_info| account_info.is_signer && account_info.key == destination_owner),
+        SwapError::InvalidDestinationOwner,
+    )
+}
+
+/// Validate the swap output goes to a token account owned by the output owner, which must be
+/// an account of the instruction of the program invoking the swap
+pub fn validate_output_owner(
+    program_id: &Pubkey,
+    destination_owner: &Pubkey,
+    output_owner: &Pubkey,
+    instructions_sysvar_info: &AccountInfo,
+) -> ProgramResult {
+    validate(
+        destination_owner == output_owner,
+        SwapError::InvalidSwapOutputOwner,
+    )?;
+
+    // The top level instruction being processed is the one of the calling program
+    let current_index = load_current_index_checked(instructions_sysvar_info)?;
+    let caller_instruction =
+        load_instruction_at_checked(current_index as usize, instructions_sysvar_info)?;
+    validate(
+        caller_instruction.program_id != *program_id
+            && caller_instruction
+                .accounts
+                .iter()
+                .any(|account_meta| account_meta.pubkey == *output_owner),
+        SwapError::InvalidSwapOutputOwner,
+    )
+}
+
 /// Checks that the supplied program ID is the correct one for pyth program
 pub fn check_pyth_program_account(pyth_program_id: &Pubkey) -> ProgramResult {
     let expected_pyth_program_id = Pubkey::from_str(PYTH_PROGRAM_ID).unwrap();
@@ -48,9 +103,22 @@ pub fn check_pyth_program_account(pyth_program_id: &Pubkey) -> ProgramResult {
     Ok(())
 }
 
-/// Checks that the supplied program ID is the correct one for serum program
-pub fn check_serum_program_id(serum_program_id: &Pubkey) -> ProgramResult {
-    if *serum_program_id != Pubkey::from_str(SERUM_DEX_V3_PROGRAM_ID).unwrap() {
+/// Checks that the supplied program ID is one of the supported orderbook programs
+pub fn check_orderbook_program_id(orderbook_program_id: &Pubkey) -> ProgramResult {
+    if *orderbook_program_id != Pubkey::from_str(SERUM_DEX_V3_PROGRAM_ID).unwrap()
+        && *orderbook_program_id != Pubkey::from_str(OPENBOOK_DEX_PROGRAM_ID).unwrap()
+    {
+        return Err(SwapError::InvalidSerumProgramId.into());
+    }
+    Ok(())
+}
+
+/// Checks that the supplied program ID is the orderbook program accepted by the config
+pub fn check_serum_program_id(
+    serum_program_id: &Pubkey,
+    orderbook_program_id: &Pubkey,
+) -> ProgramResult {
+    if serum_program_id != orderbook_program_id {
         return Err(SwapError::InvalidSerumProgramId.into());
     }
     Ok(())
@@ -62,10 +130,11 @@ pub fn check_serum_accounts(
     serum_bids_info: &AccountInfo,
     serum_asks_info: &AccountInfo,
     expected_serum_address: &Pubkey,
+    orderbook_program_id: &Pubkey,
 ) -> ProgramResult {
-    check_serum_program_id(serum_market_info.owner)?;
-    check_serum_program_id(serum_bids_info.owner)?;
-    check_serum_program_id(serum_asks_info.owner)?;
+    check_serum_program_id(serum_market_info.owner, orderbook_program_id)?;
+    check_serum_program_id(serum_bids_info.owner, orderbook_program_id)?;
+    check_serum_program_id(serum_asks_info.owner, orderbook_program_id)?;
 
     let serum_combined_address = Pubkey::new(
         hashv(&[
@@ -89,10 +158,9 @@ pub fn validate_serum_market_mint_address(
     token_a_mint: &Pubkey,
     token_b_mint: &Pubkey,
 ) -> ProgramResult {
-    let market = Market::load(serum_market_info, serum_market_info.owner, true)?;
+    let market = orderbook::load_market(serum_market_info)?;
     validate(
-        (token_a_mint.as_ref() == transmute_to_bytes(&identity(market.coin_mint)))
-            && (token_b_mint.as_ref() == transmute_to_bytes(&identity(market.pc_mint))),
+        market.coin_mint == *token_a_mint && market.pc_mint == *token_b_mint,
         SwapError::InvalidSerumMarketMintAddress,
     )?;
     Ok(())
@@ -129,8 +197,35 @@ mod test {
     use arrayref::mut_array_refs;
     use bytemuck::try_cast_slice_mut;
     use enumflags2::BitFlags;
+    use safe_transmute::to_bytes::transmute_to_bytes;
     use serum_dex::state::{AccountFlag, MarketState, ACCOUNT_HEAD_PADDING, ACCOUNT_TAIL_PADDING};
-    use std::mem::size_of;
+    use std::{convert::identity, mem::size_of};
+
+    #[test]
+    fn test_dummy_referrer() {
+        assert_eq!(
+            LEGACY_DUMMY_REFERRER_ADDRESS,
+            Pubkey::from_str("66666666666666666666666666666666666666666666").unwrap()
+        );
+        assert_eq!(DUMMY_REFERRER_ADDRESS, Pubkey::default());
+
+        let referrer = Pubkey::new_unique();
+        assert!(is_dummy_referrer(&DUMMY_REFERRER_ADDRESS));
+        assert!(is_dummy_referrer(&LEGACY_DUMMY_REFERRER_ADDRESS));
+        assert!(!is_dummy_referrer(&referrer));
+
+        assert!(is_same_referrer(&referrer, &referrer));
+        assert!(is_same_referrer(
+            &LEGACY_DUMMY_REFERRER_ADDRESS,
+            &DUMMY_REFERRER_ADDRESS
+        ));
+        assert!(is_same_referrer(
+            &DUMMY_REFERRER_ADDRESS,
+            &LEGACY_DUMMY_REFERRER_ADDRESS
+        ));
+        assert!(!is_same_referrer(&LEGACY_DUMMY_REFERRER_ADDRESS, &referrer));
+        assert!(!is_same_referrer(&referrer, &DUMMY_REFERRER_ADDRESS));
+    }
 
     #[test]
     fn test_validate_swap_config_key() {
@@ -148,6 +243,51 @@ mod test {
         );
     }
 
+    #[test]
+    fn test_validate_destination_owner() {
+        let user_transfer_authority_key = Pubkey::new_unique();
+        let owner_key = Pubkey::new_unique();
+        let mut lamports = 0u64;
+        let mut data = [0u8];
+        let user_transfer_authority_info = AccountInfo::new(
+            &user_transfer_authority_key,
+            true,
+            false,
+            &mut lamports,
+            &mut data,
+            &owner_key,
+            false,
+            0u64,
+        );
+        let mut owner_lamports = 0u64;
+        let mut owner_data = [0u8];
+        let owner_info = AccountInfo::new(
+            &owner_key,
+            false,
+            false,
+            &mut owner_lamports,
+            &mut owner_data,
+            &owner_key,
+            false,
+            0u64,
+        );
+        let accounts = [user_transfer_authority_info, owner_info];
+
+        assert_eq!(
+            validate_destination_owner(&user_transfer_authority_key, &accounts),
+            Ok(())
+        );
+        // the owner is passed but doesn't sign
+        assert_eq!(
+            validate_destination_owner(&owner_key, &accounts),
+            Err(SwapError::InvalidDestinationOwner.into())
+        );
+        assert_eq!(
+            validate_destination_owner(&Pubkey::new_unique(), &accounts),
+            Err(SwapError::InvalidDestinationOwner.into())
+        );
+    }
+
     #[test]
     fn test_validate_farm_config_key() {
         let config_key = Pubkey::new_unique();
@@ -176,6 +316,27 @@ mod test {
         );
     }
 
+    #[test]
+    fn test_check_orderbook_program_id() {
+        let serum_program_id = Pubkey::from_str(SERUM_DEX_V3_PROGRAM_ID).unwrap();
+        let openbook_program_id = Pubkey::from_str(OPENBOOK_DEX_PROGRAM_ID).unwrap();
+        assert_eq!(check_orderbook_program_id(&serum_program_id), Ok(()));
+        assert_eq!(check_orderbook_program_id(&openbook_program_id), Ok(()));
+        assert_eq!(
+            check_orderbook_program_id(&Pubkey::new_unique()),
+            Err(SwapError::InvalidSerumProgramId.into())
+        );
+
+        assert_eq!(
+            check_serum_program_id(&openbook_program_id, &openbook_program_id),
+            Ok(())
+        );
+        assert_eq!(
+            check_serum_program_id(&serum_program_id, &openbook_program_id),
+            Err(SwapError::InvalidSerumProgramId.into())
+        );
+    }
+
     #[test]
     fn test_validate_swap_token_mint() {
         let token_a_mint = Pubkey::new_unique();
@@ -251,6 +412,7 @@ mod test {
             BitFlags::bits(AccountFlag::Initialized | AccountFlag::Market);
         market_state[0].coin_mint = [6u64; 4]; // set coin_mint address
         market_state[0].pc_mint = [7u64; 4]; // set pc_mint address
+        market_state[0].own_address = bytemuck::cast(serum_market_key.to_bytes());
 
         let serum_market_account = AccountInfo::new(
             &serum_market_key,
@@ -0,0 +1,137 @@
+#![cfg(feature = "test-bpf")]
+
+mod utils;
+
+use deltafi_swap::{
+    instruction::close_pool,
+    processor::process,
+    state::{SwapInfo, SwapType},
+};
+use solana_program::{program_option::COption, program_pack::Pack, pubkey::Pubkey};
+use solana_program_test::*;
+use solana_sdk::{
+    signature::{Keypair, Signer},
+    transaction::Transaction,
+};
+use spl_token::state::{Account as Token, AccountState, Mint};
+use utils::*;
+
+fn add_token_account(test: &mut ProgramTest, mint: Pubkey, owner: Pubkey, amount: u64) -> Pubkey {
+    let pubkey = Pubkey::new_unique();
+    test.add_packable_account(
+        pubkey,
+        u32::MAX as u64,
+        &Token {
+            mint,
+            owner,
+            amount,
+            state: AccountState::Initialized,
+            ..Token::default()
+        },
+        &spl_token::id(),
+    );
+    pubkey
+}
+
+#[tokio::test]
+async fn test_success() {
+    let mut test = ProgramTest::new("deltafi_swap", deltafi_swap::id(), processor!(process));
+
+    let swap_config = add_swap_config(&mut test);
+    let usdc_mint = add_token_mint(&mut test, USDC_MINT, 6);
+    let usdt_mint = add_token_mint(&mut test, USDT_MINT, 6);
+
+    let swap_pubkey = Pubkey::new_unique();
+    let (authority, nonce) =
+        Pubkey::find_program_address(&[swap_pubkey.as_ref()], &deltafi_swap::id());
+
+    let pool_mint = Pubkey::new_unique();
+    test.add_packable_account(
+        pool_mint,
+        u32::MAX as u64,
+        &Mint {
+            is_initialized: true,
+            decimals: 6,
+            mint_authority: COption::Some(authority),
+            supply: 0,
+            ..Mint::default()
+        },
+        &spl_token::id(),
+    );
+
+    // Dust left in the pool after the last withdrawal
+    let token_a = add_token_account(&mut test, usdc_mint.pubkey, authority, 1_000);
+    let token_b = add_token_account(&mut test, usdt_mint.pubkey, authority, 2_000);
+    // The admin fee accounts are owned by the fee owner, not by the pool authority
+    let fee_owner = Keypair::new();
+    let admin_fee_a = add_token_account(&mut test, usdc_mint.pubkey, fee_owner.pubkey(), 300);
+    let admin_fee_b = add_token_account(&mut test, usdt_mint.pubkey, fee_owner.pubkey(), 400);
+    let destination_owner = Keypair::new();
+    let destination_a =
+        add_token_account(&mut test, usdc_mint.pubkey, destination_owner.pubkey(), 0);
+    let destination_b =
+        add_token_account(&mut test, usdt_mint.pubkey, destination_owner.pubkey(), 0);
+
+    test.add_packable_account(
+        swap_pubkey,
+        u32::MAX as u64,
+        &SwapInfo {
+            is_initialized: true,
+            is_paused: true,
+            nonce,
+            swap_type: SwapType::Stable,
+            config_key: swap_config.pubkey,
+            token_a,
+            token_b,
+            pool_mint,
+            token_a_mint: usdc_mint.pubkey,
+            token_b_mint: usdt_mint.pubkey,
+            admin_fee_key_a: admin_fee_a,
+            admin_fee_key_b: admin_fee_b,
+            fees: swap_config.fees.clone(),
+            rewards: swap_config.rewards.clone(),
+            ..SwapInfo::default()
+        },
+        &deltafi_swap::id(),
+    );
+
+    let (mut banks_client, payer, recent_blockhash) = test.start().await;
+
+    let mut transaction = Transaction::new_with_payer(
+        &[close_pool(
+            deltafi_swap::id(),
+            swap_config.pubkey,
+            swap_pubkey,
+            authority,
+            swap_config.admin.pubkey(),
+            pool_mint,
+            token_a,
+            token_b,
+            destination_a,
+            destination_b,
+        )
+        .unwrap()],
+        Some(&payer.pubkey()),
+    );
+    transaction.sign(&[&payer, &swap_config.admin], recent_blockhash);
+    banks_client.process_transaction(transaction).await.unwrap();
+
+    assert_eq!(
+        get_token_balance(&mut banks_client, destination_a).await,
+        1_000
+    );
+    assert_eq!(
+        get_token_balance(&mut banks_client, destination_b).await,
+        2_000
+    );
+    assert_eq!(get_token_balance(&mut banks_client, admin_fee_a).await, 300);
+    assert_eq!(get_token_balance(&mut banks_client, admin_fee_b).await, 400);
+
+    for closed in [swap_pubkey, token_a, token_b].iter() {
+        assert!(banks_client.get_account(*closed).await.unwrap().is_none());
+    }
+
+    let pool_mint_account = banks_client.get_account(pool_mint).await.unwrap().unwrap();
+    let pool_mint = Mint::unpack(&pool_mint_account.data).unwrap();
+    assert_eq!(pool_mint.mint_authority, COption::None);
+}
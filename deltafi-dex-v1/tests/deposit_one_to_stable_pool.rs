@@ -0,0 +1,204 @@
+#![cfg(feature = "test-bpf")]
+
+mod utils;
+
+use deltafi_swap::{
+    error::SwapError,
+    instruction::{stable_deposit_one, DepositOneData},
+    math::{Decimal, TryDiv},
+    processor::process,
+    state::SwapType,
+};
+
+use solana_program::instruction::InstructionError;
+use solana_program_test::*;
+use solana_sdk::{
+    signature::{Keypair, Signer},
+    transaction::{Transaction, TransactionError},
+};
+use utils::*;
+
+#[tokio::test]
+async fn test_success() {
+    let mut test = ProgramTest::new("deltafi_swap", deltafi_swap::id(), processor!(process));
+
+    // limit to track compute unit increase
+    test.set_bpf_compute_max_units(200_000);
+
+    let swap_config = add_swap_config(&mut test);
+
+    let usdc_mint = add_token_mint(&mut test, USDC_MINT, 6);
+    let usdt_mint = add_token_mint(&mut test, USDT_MINT, 6);
+    let user_account_owner = Keypair::new();
+    let admin_account_owner = Keypair::new();
+
+    let swap_info = add_swap_info(
+        SwapType::Stable,
+        &mut test,
+        &swap_config,
+        &user_account_owner,
+        &admin_account_owner,
+        AddSwapInfoArgs {
+            token_a_mint: usdc_mint.pubkey,
+            token_b_mint: usdt_mint.pubkey,
+            token_a_amount: 400_000_000_000,
+            token_b_amount: 400_000_000_000,
+            market_price: Decimal::one(),
+            slope: Decimal::one().try_div(2).unwrap(),
+            swap_out_limit_percentage: 10u8,
+            // ignored pyth, oracle for stable swap
+            ..AddSwapInfoArgs::default()
+        },
+    );
+
+    let pool_owner = Keypair::new();
+
+    let (mut banks_client, payer, _recent_blockhash) = test.start().await;
+
+    let usdc_deposit_account = create_and_mint_to_token_account(
+        &mut banks_client,
+        usdc_mint.pubkey,
+        Some(&usdc_mint.authority),
+        &payer,
+        pool_owner.pubkey(),
+        10_000_000_000,
+    )
+    .await;
+
+    let pool_token_account = create_and_mint_to_token_account(
+        &mut banks_client,
+        swap_info.pool_mint,
+        None,
+        &payer,
+        pool_owner.pubkey(),
+        0,
+    )
+    .await;
+
+    swap_info
+        .deposit_one(
+            SwapType::Stable,
+            &mut banks_client,
+            &swap_config,
+            &pool_owner,
+            usdc_deposit_account,
+            pool_token_account,
+            8_000_000_000,
+            0,
+            &payer,
+        )
+        .await;
+
+    // Only the swapped part and the deposited part of the input leave the user account.
+    let usdc_left = get_token_balance(&mut banks_client, usdc_deposit_account).await;
+    assert!(usdc_left >= 2_000_000_000);
+    assert!(usdc_left < 2_000_000_000 + 8_000_000_000 / 100);
+    assert!(get_token_balance(&mut banks_client, pool_token_account).await > 0);
+
+    // Only the admin fee of the internal swap moves out of the quote side.
+    assert!(get_token_balance(&mut banks_client, swap_info.token_b).await <= 400_000_000_000);
+    assert_eq!(
+        get_token_balance(&mut banks_client, swap_info.token_a).await,
+        400_000_000_000 + 10_000_000_000 - usdc_left,
+    );
+}
+
+#[tokio::test]
+async fn test_expired_deadline() {
+    let mut test = ProgramTest::new("deltafi_swap", deltafi_swap::id(), processor!(process));
+
+    let swap_config = add_swap_config(&mut test);
+
+    let usdc_mint = add_token_mint(&mut test, USDC_MINT, 6);
+    let usdt_mint = add_token_mint(&mut test, USDT_MINT, 6);
+    let user_account_owner = Keypair::new();
+    let admin_account_owner = Keypair::new();
+
+    let swap_info = add_swap_info(
+        SwapType::Stable,
+        &mut test,
+        &swap_config,
+        &user_account_owner,
+        &admin_account_owner,
+        AddSwapInfoArgs {
+            token_a_mint: usdc_mint.pubkey,
+            token_b_mint: usdt_mint.pubkey,
+            token_a_amount: 400_000_000_000,
+            token_b_amount: 400_000_000_000,
+            market_price: Decimal::one(),
+            slope: Decimal::one().try_div(2).unwrap(),
+            swap_out_limit_percentage: 10u8,
+            // ignored pyth, oracle for stable swap
+            ..AddSwapInfoArgs::default()
+        },
+    );
+
+    let pool_owner = Keypair::new();
+
+    let mut context = test.start_with_context().await;
+    context.warp_to_slot(100).unwrap();
+
+    let usdc_deposit_account = create_and_mint_to_token_account(
+        &mut context.banks_client,
+        usdc_mint.pubkey,
+        Some(&usdc_mint.authority),
+        &context.payer,
+        pool_owner.pubkey(),
+        10_000_000_000,
+    )
+    .await;
+
+    let pool_token_account = create_and_mint_to_token_account(
+        &mut context.banks_client,
+        swap_info.pool_mint,
+        None,
+        &context.payer,
+        pool_owner.pubkey(),
+        0,
+    )
+    .await;
+
+    // The deposit one landing after its deadline slot is rejected before moving any token
+    let mut transaction = Transaction::new_with_payer(
+        &[stable_deposit_one(
+            deltafi_swap::id(),
+            swap_config.pubkey,
+            swap_info.pubkey,
+            swap_info.authority,
+            pool_owner.pubkey(),
+            usdc_deposit_account,
+            swap_info.token_a,
+            swap_info.token_b,
+            swap_info.admin_fee_b_key,
+            swap_info.pool_mint,
+            pool_token_account,
+            None,
+            DepositOneData {
+                amount_in: 8_000_000_000,
+                min_mint_amount: 0,
+                deadline_slot: Some(50),
+            },
+        )
+        .unwrap()],
+        Some(&context.payer.pubkey()),
+    );
+    let recent_blockhash = context.banks_client.get_recent_blockhash().await.unwrap();
+    transaction.sign(&[&context.payer, &pool_owner], recent_blockhash);
+
+    assert_eq!(
+        context
+            .banks_client
+            .process_transaction(transaction)
+            .await
+            .unwrap_err()
+            .unwrap(),
+        TransactionError::InstructionError(
+            0,
+            InstructionError::Custom(SwapError::TransactionExpired as u32)
+        )
+    );
+    assert_eq!(
+        get_token_balance(&mut context.banks_client, usdc_deposit_account).await,
+        10_000_000_000
+    );
+}
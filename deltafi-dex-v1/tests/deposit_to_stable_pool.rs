@@ -103,8 +103,8 @@ async fn test_success() {
     );
     assert_eq!(
         get_token_balance(&mut banks_client, usdt_deposit_account).await,
-        // The deposit amount is 800/42*8_000_000_000 = 152_380_952_380
-        200_000_000_000 - 152_380_952_380,
+        // The deposit amount is 800/42*8_000_000_000 = 152_380_952_380.95, rounded up
+        200_000_000_000 - 152_380_952_381,
     );
     assert!(get_token_balance(&mut banks_client, pool_token_account).await > 0);
     assert_eq!(
@@ -113,7 +113,7 @@ async fn test_success() {
     );
     assert_eq!(
         get_token_balance(&mut banks_client, swap_info.token_b).await,
-        // The deposit amount is 800/42*8_000_000_000 = 152_380_952_380
-        800_000_000_000 + 152_380_952_380,
+        // The deposit amount is 800/42*8_000_000_000 = 152_380_952_380.95, rounded up
+        800_000_000_000 + 152_380_952_381,
     );
 }
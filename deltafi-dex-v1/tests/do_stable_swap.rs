@@ -143,7 +143,7 @@ async fn test_success() {
     );
     assert_eq!(
         get_token_balance(&mut banks_client, deltafi_referrer_account).await,
-        70
+        141
     );
 
     // Swap without referrer should still work.
@@ -295,6 +295,6 @@ async fn test_swap_with_different_decimals() {
     );
     assert_eq!(
         get_token_balance(&mut banks_client, deltafi_referrer_account).await,
-        70
+        141
     );
 }
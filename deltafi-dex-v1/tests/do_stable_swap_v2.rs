@@ -141,7 +141,7 @@ async fn test_success() {
     );
     assert_eq!(
         get_token_balance(&mut banks_client, deltafi_referrer_account).await,
-        70
+        141
     );
 
     // Swap without referrer should still work.
@@ -289,6 +289,6 @@ async fn test_swap_with_different_decimals() {
     );
     assert_eq!(
         get_token_balance(&mut banks_client, deltafi_referrer_account).await,
-        70
+        141
     );
 }
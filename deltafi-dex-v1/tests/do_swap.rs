@@ -3,15 +3,19 @@
 mod utils;
 
 use deltafi_swap::{
+    error::SwapError,
+    instruction::{swap, SwapData},
     math::{Decimal, TryDiv},
     processor::{get_referrer_data_pubkey, process},
-    state::SwapType,
+    state::{OraclePriorityFlag, SwapType},
 };
 
+use solana_program::instruction::InstructionError;
 use solana_program_test::*;
 use solana_sdk::{
     pubkey::Pubkey,
     signature::{Keypair, Signer},
+    transaction::{Transaction, TransactionError},
 };
 use utils::*;
 
@@ -144,7 +148,7 @@ async fn test_success() {
     );
     assert_eq!(
         get_token_balance(&mut banks_client, deltafi_referrer_account).await,
-        70
+        141
     );
 
     // Swap without referrer should still work.
@@ -297,6 +301,230 @@ async fn test_swap_with_different_decimals() {
     );
     assert_eq!(
         get_token_balance(&mut banks_client, deltafi_referrer_account).await,
-        70
+        141
+    );
+}
+
+#[tokio::test]
+async fn test_swap_in_large_pool() {
+    let mut test = ProgramTest::new("deltafi_swap", deltafi_swap::id(), processor!(process));
+
+    // the wide products of the pool math stay within the same compute units
+    test.set_bpf_compute_max_units(200_000);
+
+    let swap_config = add_swap_config(&mut test);
+
+    let sol_oracle = add_sol_oracle(&mut test);
+    let srm_oracle = add_srm_oracle(&mut test);
+    let sol_mint = add_new_mint(&mut test, 9u8);
+    let srm_mint = add_new_mint(&mut test, 9u8);
+
+    let user_account_owner = Keypair::new();
+    let admin_account_owner = Keypair::new();
+
+    let swap_info = add_swap_info(
+        SwapType::Normal,
+        &mut test,
+        &swap_config,
+        &user_account_owner,
+        &admin_account_owner,
+        AddSwapInfoArgs {
+            token_a_mint: sol_mint.pubkey,
+            token_b_mint: srm_mint.pubkey,
+            token_a_amount: 900_000_000_000_000_000,
+            token_b_amount: 17_000_000_000_000_000_000,
+            oracle_a: sol_oracle.price_pubkey,
+            oracle_b: srm_oracle.price_pubkey,
+            market_price: sol_oracle.price.try_div(srm_oracle.price).unwrap(),
+            slope: Decimal::one().try_div(2).unwrap(),
+            last_market_price: sol_oracle.price.try_div(srm_oracle.price).unwrap(),
+            last_valid_market_price_slot: 0,
+            swap_out_limit_percentage: 10u8,
+            ..AddSwapInfoArgs::default()
+        },
+    );
+
+    let (mut banks_client, payer, _recent_blockhash) = test.start().await;
+
+    let user_account_owner = Keypair::new();
+    let sol_user_account = create_and_mint_to_token_account(
+        &mut banks_client,
+        sol_mint.pubkey,
+        Some(&sol_mint.authority),
+        &payer,
+        user_account_owner.pubkey(),
+        10_000_000_000_000_000,
+    )
+    .await;
+
+    let srm_user_account = create_and_mint_to_token_account(
+        &mut banks_client,
+        srm_mint.pubkey,
+        Some(&srm_mint.authority),
+        &payer,
+        user_account_owner.pubkey(),
+        0,
+    )
+    .await;
+
+    let deltafi_user_account = create_and_mint_to_token_account(
+        &mut banks_client,
+        swap_config.deltafi_mint,
+        None,
+        &payer,
+        user_account_owner.pubkey(),
+        0,
+    )
+    .await;
+
+    swap_info
+        .swap(
+            SwapType::Normal,
+            &mut banks_client,
+            &swap_config,
+            &user_account_owner,
+            sol_user_account,
+            sol_mint.pubkey,
+            srm_user_account,
+            srm_mint.pubkey,
+            deltafi_user_account,
+            2_000_000_000_000_000,
+            15_000_000_000_000_000,
+            &payer,
+            None,
+            None,
+        )
+        .await;
+
+    assert_eq!(
+        get_token_balance(&mut banks_client, sol_user_account).await,
+        8_000_000_000_000_000,
+    );
+    assert!(get_token_balance(&mut banks_client, srm_user_account).await > 15_000_000_000_000_000);
+}
+
+#[tokio::test]
+async fn test_pyth_pull_pool_rejected() {
+    let mut test = ProgramTest::new("deltafi_swap", deltafi_swap::id(), processor!(process));
+
+    let swap_config = add_swap_config(&mut test);
+
+    let sol_oracle = add_sol_oracle(&mut test);
+    let srm_oracle = add_srm_oracle(&mut test);
+    let srm_mint = add_srm_mint(&mut test);
+
+    let user_account_owner = Keypair::new();
+    let admin_account_owner = Keypair::new();
+
+    // The pool keys the feed ids of its pull price updates in place of the pyth price accounts
+    let swap_info = add_swap_info(
+        SwapType::Normal,
+        &mut test,
+        &swap_config,
+        &user_account_owner,
+        &admin_account_owner,
+        AddSwapInfoArgs {
+            token_a_mint: spl_token::native_mint::id(),
+            token_b_mint: srm_mint.pubkey,
+            token_a_amount: 4_200_000_000_000,
+            token_b_amount: 80_000_000_000_000,
+            oracle_a: Pubkey::new_unique(),
+            oracle_b: Pubkey::new_unique(),
+            market_price: sol_oracle.price.try_div(srm_oracle.price).unwrap(),
+            slope: Decimal::one().try_div(2).unwrap(),
+            last_market_price: sol_oracle.price.try_div(srm_oracle.price).unwrap(),
+            last_valid_market_price_slot: 0,
+            swap_out_limit_percentage: 10u8,
+            oracle_priority_flags: OraclePriorityFlag::PYTH_PULL.bits(),
+            ..AddSwapInfoArgs::default()
+        },
+    );
+
+    let (mut banks_client, payer, recent_blockhash) = test.start().await;
+
+    let user_account_owner = Keypair::new();
+    let sol_user_account = create_and_mint_to_token_account(
+        &mut banks_client,
+        spl_token::native_mint::id(),
+        None,
+        &payer,
+        user_account_owner.pubkey(),
+        10_000_000_000,
+    )
+    .await;
+
+    let srm_user_account = create_and_mint_to_token_account(
+        &mut banks_client,
+        srm_mint.pubkey,
+        Some(&srm_mint.authority),
+        &payer,
+        user_account_owner.pubkey(),
+        0,
+    )
+    .await;
+
+    let deltafi_user_account = create_and_mint_to_token_account(
+        &mut banks_client,
+        swap_config.deltafi_mint,
+        None,
+        &payer,
+        user_account_owner.pubkey(),
+        0,
+    )
+    .await;
+
+    // Swap v1 with price accounts of the caller's choosing in place of the pull price updates
+    let mut transaction = Transaction::new_with_payer(
+        &[swap(
+            deltafi_swap::id(),
+            swap_config.pubkey,
+            swap_info.pubkey,
+            swap_config.market_authority,
+            swap_info.authority,
+            user_account_owner.pubkey(),
+            sol_user_account,
+            swap_info.token_a,
+            spl_token::native_mint::id(),
+            swap_info.token_b,
+            srm_user_account,
+            srm_mint.pubkey,
+            deltafi_user_account,
+            swap_config.deltafi_token,
+            swap_info.admin_fee_b_key,
+            sol_oracle.price_pubkey,
+            srm_oracle.price_pubkey,
+            None,
+            None,
+            None,
+            None,
+            None,
+            None,
+            None,
+            SwapData {
+                amount_in: 2_000_000_000,
+                minimum_amount_out: 0,
+                deadline_slot: None,
+                output_owner: None,
+            },
+        )
+        .unwrap()],
+        Some(&payer.pubkey()),
+    );
+    transaction.sign(&[&payer, &user_account_owner], recent_blockhash);
+
+    assert_eq!(
+        banks_client
+            .process_transaction(transaction)
+            .await
+            .unwrap_err()
+            .unwrap(),
+        TransactionError::InstructionError(
+            0,
+            InstructionError::Custom(SwapError::UnsupportedOraclePriority as u32)
+        )
+    );
+    assert_eq!(
+        get_token_balance(&mut banks_client, sol_user_account).await,
+        10_000_000_000
     );
 }
@@ -0,0 +1,560 @@
+#![cfg(feature = "test-bpf")]
+
+mod utils;
+
+use std::convert::TryInto;
+
+use deltafi_swap::{
+    math::{Decimal, TryDiv},
+    processor::{get_referrer_data_pubkey, process},
+    state::{OraclePriorityFlag, SwapType},
+};
+use solana_program::program_pack::Pack;
+use solana_program_test::*;
+use solana_sdk::{
+    clock::Clock,
+    pubkey::Pubkey,
+    signature::{Keypair, Signer},
+};
+use spl_token::state::Mint;
+use utils::*;
+
+async fn get_mint_supply(banks_client: &mut BanksClient, mint_pubkey: Pubkey) -> u64 {
+    let mint_account = banks_client
+        .get_account(mint_pubkey)
+        .await
+        .unwrap()
+        .unwrap();
+    Mint::unpack(&mint_account.data[..]).unwrap().supply
+}
+
+async fn get_token_total(banks_client: &mut BanksClient, pubkeys: &[Pubkey]) -> u64 {
+    let mut total = 0;
+    for pubkey in pubkeys {
+        total += get_token_balance(banks_client, *pubkey).await;
+    }
+    total
+}
+
+#[tokio::test]
+async fn test_full_lifecycle() {
+    let mut test = ProgramTest::new("deltafi_swap", deltafi_swap::id(), processor!(process));
+
+    // limit to track compute unit increase
+    test.set_bpf_compute_max_units(200_000);
+
+    let sol_oracle = add_sol_oracle(&mut test);
+    let srm_oracle = add_srm_oracle(&mut test);
+    let srm_mint = add_srm_mint(&mut test);
+    let (serum_market, serum_bids, serum_asks) = add_srm_sol_serum_market(&mut test);
+    let mut context = test.start_with_context().await;
+
+    // config
+    let swap_config = TestSwapConfig::init(&mut context.banks_client, &context.payer).await;
+    swap_config.validate_state(&mut context.banks_client).await;
+
+    // pools, a SOL-SRM pool with PYTH_ONLY and a SRM-SOL pool with SERUM_ONLY
+    let pool_owner = Keypair::new();
+    let admin_fee_owner = Keypair::new();
+    let sol_admin_account = create_and_mint_to_token_account(
+        &mut context.banks_client,
+        spl_token::native_mint::id(),
+        None,
+        &context.payer,
+        admin_fee_owner.pubkey(),
+        0,
+    )
+    .await;
+    let srm_admin_account = create_and_mint_to_token_account(
+        &mut context.banks_client,
+        srm_mint.pubkey,
+        Some(&srm_mint.authority),
+        &context.payer,
+        admin_fee_owner.pubkey(),
+        0,
+    )
+    .await;
+    let slope: u64 = Decimal::one()
+        .try_div(2)
+        .unwrap()
+        .to_scaled_val()
+        .unwrap()
+        .try_into()
+        .unwrap();
+
+    let sol_pool_account = create_and_mint_to_token_account(
+        &mut context.banks_client,
+        spl_token::native_mint::id(),
+        None,
+        &context.payer,
+        pool_owner.pubkey(),
+        42_000_000_000,
+    )
+    .await;
+    let srm_pool_account = create_and_mint_to_token_account(
+        &mut context.banks_client,
+        srm_mint.pubkey,
+        Some(&srm_mint.authority),
+        &context.payer,
+        pool_owner.pubkey(),
+        800_000_000_000,
+    )
+    .await;
+    let pyth_pool = TestSwapInfo::init(
+        SwapType::Normal,
+        &mut context.banks_client,
+        &swap_config,
+        &sol_oracle,
+        &srm_oracle,
+        spl_token::native_mint::id(),
+        srm_mint.pubkey,
+        sol_pool_account,
+        srm_pool_account,
+        sol_admin_account,
+        srm_admin_account,
+        &pool_owner,
+        &swap_config.admin,
+        serum_market,
+        serum_bids,
+        serum_asks,
+        &context.payer,
+        &SwapInitArgs {
+            mid_price: 0,
+            slope,
+            token_a_amount: 42_000_000_000,
+            token_b_amount: 800_000_000_000,
+            oracle_priority_flags: OraclePriorityFlag::PYTH_ONLY.bits(),
+            quote_is_token_a: false,
+        },
+    )
+    .await;
+    pyth_pool.validate_state(&mut context.banks_client).await;
+
+    let srm_pool_account = create_and_mint_to_token_account(
+        &mut context.banks_client,
+        srm_mint.pubkey,
+        Some(&srm_mint.authority),
+        &context.payer,
+        pool_owner.pubkey(),
+        800_000_000_000,
+    )
+    .await;
+    let sol_pool_account = create_and_mint_to_token_account(
+        &mut context.banks_client,
+        spl_token::native_mint::id(),
+        None,
+        &context.payer,
+        pool_owner.pubkey(),
+        42_000_000_000,
+    )
+    .await;
+    let serum_pool = TestSwapInfo::init(
+        SwapType::Normal,
+        &mut context.banks_client,
+        &swap_config,
+        &srm_oracle,
+        &sol_oracle,
+        srm_mint.pubkey,
+        spl_token::native_mint::id(),
+        srm_pool_account,
+        sol_pool_account,
+        srm_admin_account,
+        sol_admin_account,
+        &pool_owner,
+        &swap_config.admin,
+        serum_market,
+        serum_bids,
+        serum_asks,
+        &context.payer,
+        &SwapInitArgs {
+            mid_price: 0,
+            slope,
+            token_a_amount: 800_000_000_000,
+            token_b_amount: 42_000_000_000,
+            oracle_priority_flags: OraclePriorityFlag::SERUM_ONLY.bits(),
+            quote_is_token_a: false,
+        },
+    )
+    .await;
+    serum_pool.validate_state(&mut context.banks_client).await;
+
+    // referrer, bob refers alice
+    let alice = Keypair::new();
+    let alice_sol_account = create_and_mint_to_token_account(
+        &mut context.banks_client,
+        spl_token::native_mint::id(),
+        None,
+        &context.payer,
+        alice.pubkey(),
+        10_000_000_000,
+    )
+    .await;
+    let alice_srm_account = create_and_mint_to_token_account(
+        &mut context.banks_client,
+        srm_mint.pubkey,
+        Some(&srm_mint.authority),
+        &context.payer,
+        alice.pubkey(),
+        0,
+    )
+    .await;
+    let alice_deltafi_account = create_and_mint_to_token_account(
+        &mut context.banks_client,
+        swap_config.deltafi_mint,
+        None,
+        &context.payer,
+        alice.pubkey(),
+        0,
+    )
+    .await;
+
+    let bob = Keypair::new();
+    let bob_deltafi_account = create_and_mint_to_token_account(
+        &mut context.banks_client,
+        swap_config.deltafi_mint,
+        None,
+        &context.payer,
+        bob.pubkey(),
+        0,
+    )
+    .await;
+
+    let alice_referrer_data_pubkey =
+        get_referrer_data_pubkey(&alice.pubkey(), &swap_config.pubkey, &deltafi_swap::id())
+            .unwrap();
+    pyth_pool
+        .set_referrer(
+            &mut context.banks_client,
+            &swap_config,
+            &alice,
+            alice_referrer_data_pubkey,
+            bob_deltafi_account,
+            &context.payer,
+        )
+        .await;
+
+    // swaps in both directions on the PYTH_ONLY pool
+    let sol_accounts = [pyth_pool.token_a, alice_sol_account, sol_admin_account];
+    let srm_accounts = [pyth_pool.token_b, alice_srm_account, srm_admin_account];
+    let sol_total = get_token_total(&mut context.banks_client, &sol_accounts).await;
+    let srm_total = get_token_total(&mut context.banks_client, &srm_accounts).await;
+
+    pyth_pool
+        .swap(
+            SwapType::Normal,
+            &mut context.banks_client,
+            &swap_config,
+            &alice,
+            alice_sol_account,
+            spl_token::native_mint::id(),
+            alice_srm_account,
+            srm_mint.pubkey,
+            alice_deltafi_account,
+            1_000_000_000,
+            15_000_000_000,
+            &context.payer,
+            Some(alice_referrer_data_pubkey),
+            Some(bob_deltafi_account),
+        )
+        .await;
+
+    assert_eq!(
+        get_token_balance(&mut context.banks_client, alice_sol_account).await,
+        9_000_000_000,
+    );
+    let alice_srm_balance = get_token_balance(&mut context.banks_client, alice_srm_account).await;
+    assert!(alice_srm_balance > 15_000_000_000);
+    let alice_deltafi_balance =
+        get_token_balance(&mut context.banks_client, alice_deltafi_account).await;
+    assert!(alice_deltafi_balance > 0);
+    let bob_deltafi_balance =
+        get_token_balance(&mut context.banks_client, bob_deltafi_account).await;
+    assert!(bob_deltafi_balance > 0);
+
+    pyth_pool
+        .swap(
+            SwapType::Normal,
+            &mut context.banks_client,
+            &swap_config,
+            &alice,
+            alice_srm_account,
+            srm_mint.pubkey,
+            alice_sol_account,
+            spl_token::native_mint::id(),
+            alice_deltafi_account,
+            10_000_000_000,
+            300_000_000,
+            &context.payer,
+            Some(alice_referrer_data_pubkey),
+            Some(bob_deltafi_account),
+        )
+        .await;
+
+    assert_eq!(
+        get_token_balance(&mut context.banks_client, alice_srm_account).await,
+        alice_srm_balance - 10_000_000_000,
+    );
+    assert!(get_token_balance(&mut context.banks_client, alice_sol_account).await > 9_300_000_000);
+    assert!(
+        get_token_balance(&mut context.banks_client, alice_deltafi_account).await
+            > alice_deltafi_balance
+    );
+    assert!(
+        get_token_balance(&mut context.banks_client, bob_deltafi_account).await
+            > bob_deltafi_balance
+    );
+    assert!(get_token_balance(&mut context.banks_client, sol_admin_account).await > 0);
+    assert!(get_token_balance(&mut context.banks_client, srm_admin_account).await > 0);
+    assert_eq!(
+        get_token_total(&mut context.banks_client, &sol_accounts).await,
+        sol_total
+    );
+    assert_eq!(
+        get_token_total(&mut context.banks_client, &srm_accounts).await,
+        srm_total
+    );
+
+    // swap on the SERUM_ONLY pool
+    let sol_accounts = [serum_pool.token_b, alice_sol_account, sol_admin_account];
+    let srm_accounts = [serum_pool.token_a, alice_srm_account, srm_admin_account];
+    let sol_total = get_token_total(&mut context.banks_client, &sol_accounts).await;
+    let srm_total = get_token_total(&mut context.banks_client, &srm_accounts).await;
+    let alice_sol_balance = get_token_balance(&mut context.banks_client, alice_sol_account).await;
+    let alice_srm_balance = get_token_balance(&mut context.banks_client, alice_srm_account).await;
+
+    serum_pool
+        .swap_v2(
+            SwapType::Normal,
+            &mut context.banks_client,
+            &swap_config,
+            &alice,
+            alice_srm_account,
+            alice_sol_account,
+            alice_deltafi_account,
+            2_000_000_000,
+            100_000_000,
+            &context.payer,
+            None,
+            None,
+        )
+        .await;
+
+    assert_eq!(
+        get_token_balance(&mut context.banks_client, alice_srm_account).await,
+        alice_srm_balance - 2_000_000_000,
+    );
+    assert!(
+        get_token_balance(&mut context.banks_client, alice_sol_account).await
+            > alice_sol_balance + 100_000_000
+    );
+    assert_eq!(
+        get_token_total(&mut context.banks_client, &sol_accounts).await,
+        sol_total
+    );
+    assert_eq!(
+        get_token_total(&mut context.banks_client, &srm_accounts).await,
+        srm_total
+    );
+
+    // deposit into the PYTH_ONLY pool
+    let carol = Keypair::new();
+    let carol_sol_account = create_and_mint_to_token_account(
+        &mut context.banks_client,
+        spl_token::native_mint::id(),
+        None,
+        &context.payer,
+        carol.pubkey(),
+        1_000_000_000,
+    )
+    .await;
+    let carol_srm_account = create_and_mint_to_token_account(
+        &mut context.banks_client,
+        srm_mint.pubkey,
+        Some(&srm_mint.authority),
+        &context.payer,
+        carol.pubkey(),
+        20_000_000_000,
+    )
+    .await;
+    let carol_pool_token_account = create_and_mint_to_token_account(
+        &mut context.banks_client,
+        pyth_pool.pool_mint,
+        None,
+        &context.payer,
+        carol.pubkey(),
+        0,
+    )
+    .await;
+    let carol_deltafi_account = create_and_mint_to_token_account(
+        &mut context.banks_client,
+        swap_config.deltafi_mint,
+        None,
+        &context.payer,
+        carol.pubkey(),
+        0,
+    )
+    .await;
+
+    pyth_pool
+        .deposit(
+            SwapType::Normal,
+            &mut context.banks_client,
+            &carol,
+            carol_sol_account,
+            carol_srm_account,
+            carol_pool_token_account,
+            1_000_000_000,
+            20_000_000_000,
+            1,
+            &context.payer,
+        )
+        .await;
+
+    let carol_pool_token_amount =
+        get_token_balance(&mut context.banks_client, carol_pool_token_account).await;
+    assert!(carol_pool_token_amount > 0);
+    assert_eq!(
+        get_mint_supply(&mut context.banks_client, pyth_pool.pool_mint).await,
+        get_token_balance(&mut context.banks_client, pyth_pool.pool_token).await
+            + carol_pool_token_amount,
+    );
+
+    // farm the pool tokens
+    let farm_pool_info = TestFarmPoolInfo::init(
+        &mut context.banks_client,
+        &swap_config,
+        &pyth_pool,
+        &context.payer,
+        0,
+        100,
+        1,
+        10,
+    )
+    .await;
+    let farm_user = TestFarmUser::init(
+        &mut context.banks_client,
+        swap_config.pubkey,
+        farm_pool_info.farm_pool_key,
+        &carol,
+        &context.payer,
+    )
+    .await;
+    farm_user.validate_state(&mut context.banks_client).await;
+
+    farm_user
+        .do_farm_deposit(
+            &mut context.banks_client,
+            &carol,
+            carol_pool_token_account,
+            farm_pool_info.farm_pool_token,
+            carol_pool_token_amount,
+            &context.payer,
+        )
+        .await;
+    assert_eq!(
+        get_token_balance(&mut context.banks_client, carol_pool_token_account).await,
+        0
+    );
+    assert_eq!(
+        get_token_balance(&mut context.banks_client, farm_pool_info.farm_pool_token).await,
+        carol_pool_token_amount,
+    );
+    assert_eq!(
+        farm_user
+            .get_state(&mut context.banks_client)
+            .await
+            .position
+            .deposited_amount,
+        carol_pool_token_amount,
+    );
+
+    // an hour later, past the claim period and the deposit slot
+    let clock = context.banks_client.get_sysvar::<Clock>().await.unwrap();
+    context.warp_to_slot(clock.slot + 10).unwrap();
+    let mut clock = context.banks_client.get_sysvar::<Clock>().await.unwrap();
+    clock.unix_timestamp += 3_600;
+    context.set_sysvar(&clock);
+
+    let reward_source_balance =
+        get_token_balance(&mut context.banks_client, swap_config.deltafi_token).await;
+    farm_user
+        .do_farm_claim(
+            &mut context.banks_client,
+            &swap_config,
+            &carol,
+            carol_deltafi_account,
+            &context.payer,
+        )
+        .await;
+    let carol_deltafi_balance =
+        get_token_balance(&mut context.banks_client, carol_deltafi_account).await;
+    assert!(carol_deltafi_balance > 0);
+    assert_eq!(
+        get_token_balance(&mut context.banks_client, swap_config.deltafi_token).await,
+        reward_source_balance - carol_deltafi_balance,
+    );
+
+    farm_user
+        .do_farm_withdraw(
+            &mut context.banks_client,
+            &carol,
+            carol_pool_token_account,
+            farm_pool_info.farm_pool_token,
+            farm_pool_info.authority,
+            carol_pool_token_amount,
+            &context.payer,
+        )
+        .await;
+    assert_eq!(
+        get_token_balance(&mut context.banks_client, carol_pool_token_account).await,
+        carol_pool_token_amount,
+    );
+    assert_eq!(
+        get_token_balance(&mut context.banks_client, farm_pool_info.farm_pool_token).await,
+        0
+    );
+
+    // withdraw from the PYTH_ONLY pool
+    let carol_sol_balance = get_token_balance(&mut context.banks_client, carol_sol_account).await;
+    let carol_srm_balance = get_token_balance(&mut context.banks_client, carol_srm_account).await;
+    pyth_pool
+        .withdraw(
+            SwapType::Normal,
+            &mut context.banks_client,
+            &carol,
+            carol_sol_account,
+            carol_srm_account,
+            carol_pool_token_account,
+            carol_pool_token_amount,
+            1,
+            1,
+            &context.payer,
+        )
+        .await;
+
+    assert_eq!(
+        get_token_balance(&mut context.banks_client, carol_pool_token_account).await,
+        0
+    );
+    assert!(
+        get_token_balance(&mut context.banks_client, carol_sol_account).await > carol_sol_balance
+    );
+    assert!(
+        get_token_balance(&mut context.banks_client, carol_srm_account).await > carol_srm_balance
+    );
+    assert_eq!(
+        get_mint_supply(&mut context.banks_client, pyth_pool.pool_mint).await,
+        get_token_balance(&mut context.banks_client, pyth_pool.pool_token).await,
+    );
+
+    let pool_state = pyth_pool
+        .get_state(&mut context.banks_client)
+        .await
+        .pool_state;
+    pool_state
+        .check_reserve_amount(
+            get_token_balance(&mut context.banks_client, pyth_pool.token_a).await,
+            get_token_balance(&mut context.banks_client, pyth_pool.token_b).await,
+        )
+        .unwrap();
+}
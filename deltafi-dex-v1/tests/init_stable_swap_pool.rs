@@ -106,6 +106,7 @@ async fn test_success() {
             token_a_amount: 42_000_000_000,
             token_b_amount: 800_000_000_000,
             oracle_priority_flags: 0,
+            quote_is_token_a: false,
         },
     )
     .await;
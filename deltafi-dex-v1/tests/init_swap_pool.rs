@@ -106,6 +106,7 @@ async fn test_success() {
             token_a_amount: 42_000_000_000,
             token_b_amount: 800_000_000_000,
             oracle_priority_flags: OraclePriorityFlag::PYTH_ONLY.bits(),
+            quote_is_token_a: false,
         },
     )
     .await;
@@ -161,6 +162,7 @@ async fn test_success() {
             token_a_amount: 800_000_000_000,
             token_b_amount: 42_000_000_000,
             oracle_priority_flags: OraclePriorityFlag::SERUM_ONLY.bits(),
+            quote_is_token_a: false,
         },
     )
     .await;
@@ -248,6 +250,7 @@ async fn test_already_initialized() {
                 token_a_amount: 42_000_000_000,
                 token_b_amount: 800_000_000_000,
                 oracle_priority_flags: 0,
+                quote_is_token_a: false,
             },
         )
         .unwrap()],
@@ -0,0 +1,334 @@
+#![cfg(feature = "test-bpf")]
+
+mod utils;
+
+use std::convert::TryInto;
+
+use deltafi_swap::{
+    math::{Decimal, TryDiv},
+    processor::{find_swap_address, process},
+    state::{OraclePriorityFlag, SwapType},
+};
+use solana_program_test::*;
+use solana_sdk::{
+    signature::{read_keypair_file, Keypair, Signer},
+    system_instruction,
+    transaction::Transaction,
+};
+use utils::*;
+
+#[tokio::test]
+async fn test_success() {
+    let mut test = ProgramTest::new("deltafi_swap", deltafi_swap::id(), processor!(process));
+
+    // limit to track compute unit increase
+    test.set_bpf_compute_max_units(150_000);
+
+    let swap_config = add_swap_config(&mut test);
+    let sol_oracle = add_sol_oracle(&mut test);
+    let srm_oracle = add_srm_oracle(&mut test);
+    let srm_mint = add_srm_mint(&mut test);
+    let (serum_market, serum_bids, serum_asks) = add_srm_sol_serum_market(&mut test);
+    let (mut banks_client, payer, _recent_blockhash) = test.start().await;
+
+    let user_accounts_owner = Keypair::new();
+    let sol_user_account = create_and_mint_to_token_account(
+        &mut banks_client,
+        spl_token::native_mint::id(),
+        None,
+        &payer,
+        user_accounts_owner.pubkey(),
+        42_000_000_000,
+    )
+    .await;
+    let srm_user_account = create_and_mint_to_token_account(
+        &mut banks_client,
+        srm_mint.pubkey,
+        Some(&srm_mint.authority),
+        &payer,
+        user_accounts_owner.pubkey(),
+        800_000_000_000,
+    )
+    .await;
+
+    let admin_fee_accounts = Keypair::new();
+    let sol_admin_account = create_and_mint_to_token_account(
+        &mut banks_client,
+        spl_token::native_mint::id(),
+        None,
+        &payer,
+        admin_fee_accounts.pubkey(),
+        0,
+    )
+    .await;
+    let srm_admin_account = create_and_mint_to_token_account(
+        &mut banks_client,
+        srm_mint.pubkey,
+        Some(&srm_mint.authority),
+        &payer,
+        admin_fee_accounts.pubkey(),
+        0,
+    )
+    .await;
+
+    let admin = read_keypair_file("tests/fixtures/deltafi-owner.json").unwrap();
+    let test_swap_info = TestSwapInfo::init_v2(
+        SwapType::Normal,
+        &mut banks_client,
+        &swap_config,
+        &sol_oracle,
+        &srm_oracle,
+        spl_token::native_mint::id(),
+        srm_mint.pubkey,
+        sol_user_account,
+        srm_user_account,
+        sol_admin_account,
+        srm_admin_account,
+        &user_accounts_owner,
+        &admin,
+        serum_market,
+        serum_bids,
+        serum_asks,
+        &payer,
+        &SwapInitArgs {
+            mid_price: 0,
+            slope: Decimal::one()
+                .try_div(2)
+                .unwrap()
+                .to_scaled_val()
+                .unwrap()
+                .try_into()
+                .unwrap(),
+            token_a_amount: 42_000_000_000,
+            token_b_amount: 800_000_000_000,
+            oracle_priority_flags: OraclePriorityFlag::PYTH_ONLY.bits(),
+            quote_is_token_a: false,
+        },
+    )
+    .await;
+
+    let (swap_address, _) = find_swap_address(
+        &swap_config.pubkey,
+        &srm_mint.pubkey,
+        &spl_token::native_mint::id(),
+        SwapType::Normal,
+        &deltafi_swap::id(),
+    );
+    assert_eq!(test_swap_info.pubkey, swap_address);
+    test_swap_info.validate_state(&mut banks_client).await;
+}
+
+#[tokio::test]
+async fn test_prefunded_address() {
+    let mut test = ProgramTest::new("deltafi_swap", deltafi_swap::id(), processor!(process));
+
+    // limit to track compute unit increase
+    test.set_bpf_compute_max_units(150_000);
+
+    let swap_config = add_swap_config(&mut test);
+    let sol_oracle = add_sol_oracle(&mut test);
+    let srm_oracle = add_srm_oracle(&mut test);
+    let srm_mint = add_srm_mint(&mut test);
+    let (serum_market, serum_bids, serum_asks) = add_srm_sol_serum_market(&mut test);
+    let (mut banks_client, payer, recent_blockhash) = test.start().await;
+
+    let user_accounts_owner = Keypair::new();
+    let sol_user_account = create_and_mint_to_token_account(
+        &mut banks_client,
+        spl_token::native_mint::id(),
+        None,
+        &payer,
+        user_accounts_owner.pubkey(),
+        42_000_000_000,
+    )
+    .await;
+    let srm_user_account = create_and_mint_to_token_account(
+        &mut banks_client,
+        srm_mint.pubkey,
+        Some(&srm_mint.authority),
+        &payer,
+        user_accounts_owner.pubkey(),
+        800_000_000_000,
+    )
+    .await;
+
+    let admin_fee_accounts = Keypair::new();
+    let sol_admin_account = create_and_mint_to_token_account(
+        &mut banks_client,
+        spl_token::native_mint::id(),
+        None,
+        &payer,
+        admin_fee_accounts.pubkey(),
+        0,
+    )
+    .await;
+    let srm_admin_account = create_and_mint_to_token_account(
+        &mut banks_client,
+        srm_mint.pubkey,
+        Some(&srm_mint.authority),
+        &payer,
+        admin_fee_accounts.pubkey(),
+        0,
+    )
+    .await;
+
+    // Anyone can fund the address of the pool ahead of the admin
+    let (swap_address, _) = find_swap_address(
+        &swap_config.pubkey,
+        &srm_mint.pubkey,
+        &spl_token::native_mint::id(),
+        SwapType::Normal,
+        &deltafi_swap::id(),
+    );
+    let mut transaction = Transaction::new_with_payer(
+        &[system_instruction::transfer(
+            &payer.pubkey(),
+            &swap_address,
+            1_000_000,
+        )],
+        Some(&payer.pubkey()),
+    );
+    transaction.sign(&[&payer], recent_blockhash);
+    banks_client.process_transaction(transaction).await.unwrap();
+
+    let admin = read_keypair_file("tests/fixtures/deltafi-owner.json").unwrap();
+    let test_swap_info = TestSwapInfo::init_v2(
+        SwapType::Normal,
+        &mut banks_client,
+        &swap_config,
+        &sol_oracle,
+        &srm_oracle,
+        spl_token::native_mint::id(),
+        srm_mint.pubkey,
+        sol_user_account,
+        srm_user_account,
+        sol_admin_account,
+        srm_admin_account,
+        &user_accounts_owner,
+        &admin,
+        serum_market,
+        serum_bids,
+        serum_asks,
+        &payer,
+        &SwapInitArgs {
+            mid_price: 0,
+            slope: Decimal::one()
+                .try_div(2)
+                .unwrap()
+                .to_scaled_val()
+                .unwrap()
+                .try_into()
+                .unwrap(),
+            token_a_amount: 42_000_000_000,
+            token_b_amount: 800_000_000_000,
+            oracle_priority_flags: OraclePriorityFlag::PYTH_ONLY.bits(),
+            quote_is_token_a: false,
+        },
+    )
+    .await;
+
+    assert_eq!(test_swap_info.pubkey, swap_address);
+    test_swap_info.validate_state(&mut banks_client).await;
+}
+
+#[tokio::test]
+async fn test_stable_success() {
+    let mut test = ProgramTest::new("deltafi_swap", deltafi_swap::id(), processor!(process));
+
+    // limit to track compute unit increase
+    test.set_bpf_compute_max_units(150_000);
+
+    let swap_config = add_swap_config(&mut test);
+    let sol_oracle = add_sol_oracle(&mut test);
+    let srm_oracle = add_srm_oracle(&mut test);
+    let usdc_mint = add_token_mint(&mut test, USDC_MINT, 6);
+    let usdt_mint = add_token_mint(&mut test, USDT_MINT, 6);
+    let (serum_market, serum_bids, serum_asks) = add_srm_sol_serum_market(&mut test);
+    let (mut banks_client, payer, _recent_blockhash) = test.start().await;
+
+    let user_accounts_owner = Keypair::new();
+    let usdc_user_account = create_and_mint_to_token_account(
+        &mut banks_client,
+        usdc_mint.pubkey,
+        Some(&usdc_mint.authority),
+        &payer,
+        user_accounts_owner.pubkey(),
+        42_000_000_000,
+    )
+    .await;
+    let usdt_user_account = create_and_mint_to_token_account(
+        &mut banks_client,
+        usdt_mint.pubkey,
+        Some(&usdt_mint.authority),
+        &payer,
+        user_accounts_owner.pubkey(),
+        800_000_000_000,
+    )
+    .await;
+
+    let admin_fee_accounts = Keypair::new();
+    let usdc_admin_account = create_and_mint_to_token_account(
+        &mut banks_client,
+        usdc_mint.pubkey,
+        Some(&usdc_mint.authority),
+        &payer,
+        admin_fee_accounts.pubkey(),
+        0,
+    )
+    .await;
+    let usdt_admin_account = create_and_mint_to_token_account(
+        &mut banks_client,
+        usdt_mint.pubkey,
+        Some(&usdt_mint.authority),
+        &payer,
+        admin_fee_accounts.pubkey(),
+        0,
+    )
+    .await;
+
+    let admin = read_keypair_file("tests/fixtures/deltafi-owner.json").unwrap();
+    let test_swap_info = TestSwapInfo::init_v2(
+        SwapType::Stable,
+        &mut banks_client,
+        &swap_config,
+        &sol_oracle,
+        &srm_oracle,
+        usdc_mint.pubkey,
+        usdt_mint.pubkey,
+        usdc_user_account,
+        usdt_user_account,
+        usdc_admin_account,
+        usdt_admin_account,
+        &user_accounts_owner,
+        &admin,
+        serum_market,
+        serum_bids,
+        serum_asks,
+        &payer,
+        &SwapInitArgs {
+            mid_price: 0, // ignored for stable swap
+            slope: Decimal::one()
+                .try_div(2)
+                .unwrap()
+                .to_scaled_val()
+                .unwrap()
+                .try_into()
+                .unwrap(),
+            token_a_amount: 42_000_000_000,
+            token_b_amount: 800_000_000_000,
+            oracle_priority_flags: 0,
+            quote_is_token_a: false,
+        },
+    )
+    .await;
+
+    let (swap_address, _) = find_swap_address(
+        &swap_config.pubkey,
+        &usdt_mint.pubkey,
+        &usdc_mint.pubkey,
+        SwapType::Stable,
+        &deltafi_swap::id(),
+    );
+    assert_eq!(test_swap_info.pubkey, swap_address);
+    test_swap_info.validate_state(&mut banks_client).await;
+}
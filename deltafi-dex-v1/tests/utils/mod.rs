@@ -5,15 +5,19 @@ use assert_matches::*;
 use deltafi_swap::{
     curve::{InitPoolStateParams, PoolState},
     instruction::{
-        deposit, farm_deposit, farm_initialize, farm_user_initialize, farm_withdraw, initialize,
-        initialize_config, set_referrer, stable_deposit, stable_initialize, stable_swap,
-        stable_swap_v2, stable_withdraw, swap, swap_v2, withdraw, DepositData, FarmDepositData,
-        FarmInitializeData, FarmWithdrawData, InitializeData, StableInitializeData, SwapData,
-        WithdrawData,
+        deposit, deposit_one, farm_claim, farm_deposit, farm_initialize, farm_user_initialize,
+        farm_withdraw, initialize, initialize_config, initialize_v2, set_referrer, stable_deposit,
+        stable_deposit_one, stable_initialize, stable_initialize_v2, stable_swap, stable_swap_v2,
+        stable_withdraw, stable_withdraw_one, swap, swap_v2, withdraw, withdraw_one, DepositData,
+        DepositOneData, FarmDepositData, FarmInitializeData, FarmWithdrawData, InitializeData,
+        StableInitializeData, SwapData, WithdrawData, WithdrawOneData,
     },
     math::Decimal,
-    processor::get_farm_user_pubkey,
-    pyth,
+    processor::{find_swap_address, get_farm_user_pubkey},
+    pyth::{
+        self,
+        test_utils::{product_account_data, MockPrice},
+    },
     state::{
         ConfigInfo, FarmInfo, FarmPosition, FarmUser, Fees, OraclePriorityFlag, Rewards, SwapInfo,
         SwapType, UserReferrerData, PROGRAM_VERSION,
@@ -28,7 +32,7 @@ use solana_sdk::{
     hash::hashv,
     signature::{read_keypair_file, Keypair},
     signer::Signer,
-    system_instruction::{create_account, create_account_with_seed},
+    system_instruction::{create_account, create_account_with_seed, transfer},
     transaction::Transaction,
 };
 use spl_token::{
@@ -53,12 +57,19 @@ pub const TEST_FEES: Fees = Fees {
     trade_fee_denominator: 1_000,
     withdraw_fee_numerator: 2,
     withdraw_fee_denominator: 100,
+    flash_fee_numerator: 3,
+    flash_fee_denominator: 1_000,
+    dynamic_fee_factor: 0,
+    max_dynamic_fee_multiplier: 0,
+    treasury_fee_numerator: 0,
+    treasury_fee_denominator: 0,
 };
 
 pub const TEST_REWARDS: Rewards = Rewards {
     is_initialized: true,
     decimals: 9,
-    reserved: [0u8; 7],
+    referral_fee_bps: 0,
+    reserved: [0u8; 5],
     trade_reward_numerator: 1,
     trade_reward_denominator: 1_000,
     trade_reward_cap: 10_000_000_000,
@@ -593,6 +604,7 @@ pub struct SwapInitArgs {
     pub token_a_amount: u64,
     pub token_b_amount: u64,
     pub oracle_priority_flags: u8,
+    pub quote_is_token_a: bool,
 }
 
 impl TestSwapInfo {
@@ -615,9 +627,110 @@ impl TestSwapInfo {
         serum_asks_pubkey: Pubkey,
         payer: &Keypair,
         args: &SwapInitArgs,
+    ) -> Self {
+        Self::create(
+            swap_type,
+            false,
+            banks_client,
+            swap_config,
+            oracle_a,
+            oracle_b,
+            token_a_mint,
+            token_b_mint,
+            token_a,
+            token_b,
+            admin_fee_a_key,
+            admin_fee_b_key,
+            user_account_owner,
+            admin_keypair,
+            serum_market_pubkey,
+            serum_bids_pubkey,
+            serum_asks_pubkey,
+            payer,
+            args,
+        )
+        .await
+    }
+
+    /// Initialize the pool with `InitializeV2` at the program address of its pair
+    pub async fn init_v2(
+        swap_type: SwapType,
+        banks_client: &mut BanksClient,
+        swap_config: &TestSwapConfig,
+        oracle_a: &TestOracle,
+        oracle_b: &TestOracle,
+        token_a_mint: Pubkey,
+        token_b_mint: Pubkey,
+        token_a: Pubkey,
+        token_b: Pubkey,
+        admin_fee_a_key: Pubkey,
+        admin_fee_b_key: Pubkey,
+        user_account_owner: &Keypair,
+        admin_keypair: &Keypair,
+        serum_market_pubkey: Pubkey,
+        serum_bids_pubkey: Pubkey,
+        serum_asks_pubkey: Pubkey,
+        payer: &Keypair,
+        args: &SwapInitArgs,
+    ) -> Self {
+        Self::create(
+            swap_type,
+            true,
+            banks_client,
+            swap_config,
+            oracle_a,
+            oracle_b,
+            token_a_mint,
+            token_b_mint,
+            token_a,
+            token_b,
+            admin_fee_a_key,
+            admin_fee_b_key,
+            user_account_owner,
+            admin_keypair,
+            serum_market_pubkey,
+            serum_bids_pubkey,
+            serum_asks_pubkey,
+            payer,
+            args,
+        )
+        .await
+    }
+
+    async fn create(
+        swap_type: SwapType,
+        v2: bool,
+        banks_client: &mut BanksClient,
+        swap_config: &TestSwapConfig,
+        oracle_a: &TestOracle,
+        oracle_b: &TestOracle,
+        token_a_mint: Pubkey,
+        token_b_mint: Pubkey,
+        token_a: Pubkey,
+        token_b: Pubkey,
+        admin_fee_a_key: Pubkey,
+        admin_fee_b_key: Pubkey,
+        user_account_owner: &Keypair,
+        admin_keypair: &Keypair,
+        serum_market_pubkey: Pubkey,
+        serum_bids_pubkey: Pubkey,
+        serum_asks_pubkey: Pubkey,
+        payer: &Keypair,
+        args: &SwapInitArgs,
     ) -> Self {
         let swap_info_keypair = Keypair::new();
-        let swap_info_pubkey = swap_info_keypair.pubkey();
+        let swap_info_pubkey = if v2 {
+            find_swap_address(
+                &swap_config.pubkey,
+                &token_a_mint,
+                &token_b_mint,
+                swap_type,
+                &deltafi_swap::id(),
+            )
+            .0
+        } else {
+            swap_info_keypair.pubkey()
+        };
 
         let (swap_authority_pubkey, nonce) = Pubkey::find_program_address(
             &[&swap_info_pubkey.to_bytes()[..32]],
@@ -627,6 +740,13 @@ impl TestSwapInfo {
         let pool_mint_keypair = Keypair::new();
         let user_pool_token_keypair = Keypair::new();
 
+        let initialize_ix = if v2 { initialize_v2 } else { initialize };
+        let stable_initialize_ix = if v2 {
+            stable_initialize_v2
+        } else {
+            stable_initialize
+        };
+
         let rent = banks_client.get_rent().await.unwrap();
         let mut transaction = Transaction::new_with_payer(
             &[
@@ -677,15 +797,24 @@ impl TestSwapInfo {
                     &[],
                 )
                 .unwrap(),
-                create_account(
-                    &payer.pubkey(),
-                    &swap_info_pubkey,
-                    rent.minimum_balance(SwapInfo::LEN),
-                    SwapInfo::LEN as u64,
-                    &deltafi_swap::id(),
-                ),
+                if v2 {
+                    // The admin pays for the pool account created by the program
+                    transfer(
+                        &payer.pubkey(),
+                        &admin_keypair.pubkey(),
+                        rent.minimum_balance(SwapInfo::LEN),
+                    )
+                } else {
+                    create_account(
+                        &payer.pubkey(),
+                        &swap_info_pubkey,
+                        rent.minimum_balance(SwapInfo::LEN),
+                        SwapInfo::LEN as u64,
+                        &deltafi_swap::id(),
+                    )
+                },
                 match swap_type {
-                    SwapType::Normal => initialize(
+                    SwapType::Normal => initialize_ix(
                         deltafi_swap::id(),
                         swap_config.pubkey,
                         swap_info_pubkey,
@@ -713,10 +842,11 @@ impl TestSwapInfo {
                             token_a_amount: args.token_a_amount,
                             token_b_amount: args.token_b_amount,
                             oracle_priority_flags: args.oracle_priority_flags,
+                            quote_is_token_a: args.quote_is_token_a,
                         },
                     )
                     .unwrap(),
-                    SwapType::Stable => stable_initialize(
+                    SwapType::Stable => stable_initialize_ix(
                         deltafi_swap::id(),
                         swap_config.pubkey,
                         swap_info_pubkey,
@@ -744,17 +874,18 @@ impl TestSwapInfo {
         );
 
         let recent_blockhash = banks_client.get_recent_blockhash().await.unwrap();
-        transaction.sign(
-            &vec![
-                payer,
-                user_account_owner,
-                &swap_info_keypair,
-                &pool_mint_keypair,
-                &user_pool_token_keypair,
-                &admin_keypair,
-            ],
-            recent_blockhash,
-        );
+        let mut signers = vec![
+            payer,
+            user_account_owner,
+            &pool_mint_keypair,
+            &user_pool_token_keypair,
+            admin_keypair,
+        ];
+        // The program address of an `InitializeV2` pool has no keypair
+        if !v2 {
+            signers.push(&swap_info_keypair);
+        }
+        transaction.sign(&signers, recent_blockhash);
 
         assert_matches!(banks_client.process_transaction(transaction).await, Ok(()));
 
@@ -821,6 +952,15 @@ impl TestSwapInfo {
         assert_matches!(banks_client.process_transaction(transaction).await, Ok(()));
     }
 
+    /// Swap source, swap destination and admin fee accounts of a swap selling the source mint
+    fn swap_accounts(&self, source_mint_pubkey: Pubkey) -> (Pubkey, Pubkey, Pubkey) {
+        if source_mint_pubkey == self.token_a_mint {
+            (self.token_a, self.token_b, self.admin_fee_b_key)
+        } else {
+            (self.token_b, self.token_a, self.admin_fee_a_key)
+        }
+    }
+
     pub async fn swap(
         &self,
         swap_type: SwapType,
@@ -838,6 +978,8 @@ impl TestSwapInfo {
         user_referrer_data_pubkey: Option<Pubkey>,
         referral_pubkey: Option<Pubkey>,
     ) {
+        let (swap_source_pubkey, swap_destination_pubkey, admin_fee_destination_pubkey) =
+            self.swap_accounts(source_mint_pubkey);
         let user_transfer_authority = Keypair::new();
         let mut transaction = Transaction::new_with_payer(
             &[
@@ -859,21 +1001,28 @@ impl TestSwapInfo {
                         self.authority,
                         user_transfer_authority.pubkey(),
                         source_pubkey,
-                        self.token_a,
+                        swap_source_pubkey,
                         source_mint_pubkey,
-                        self.token_b,
+                        swap_destination_pubkey,
                         destination_pubkey,
                         destination_mint_pubkey,
                         reward_token_pubkey,
                         config_info.deltafi_token,
-                        self.admin_fee_b_key,
+                        admin_fee_destination_pubkey,
                         self.oracle_a,
                         self.oracle_b,
+                        None,
+                        None,
+                        None,
+                        None,
                         user_referrer_data_pubkey,
                         referral_pubkey,
+                        None,
                         SwapData {
                             amount_in,
                             minimum_amount_out,
+                            deadline_slot: None,
+                            output_owner: None,
                         },
                     )
                     .unwrap(),
@@ -885,19 +1034,24 @@ impl TestSwapInfo {
                         self.authority,
                         user_transfer_authority.pubkey(),
                         source_pubkey,
-                        self.token_a,
+                        swap_source_pubkey,
                         source_mint_pubkey,
-                        self.token_b,
+                        swap_destination_pubkey,
                         destination_pubkey,
                         destination_mint_pubkey,
                         reward_token_pubkey,
                         config_info.deltafi_token,
-                        self.admin_fee_b_key,
+                        admin_fee_destination_pubkey,
+                        None,
+                        None,
                         user_referrer_data_pubkey,
                         referral_pubkey,
+                        None,
                         SwapData {
                             amount_in,
                             minimum_amount_out,
+                            deadline_slot: None,
+                            output_owner: None,
                         },
                     )
                     .unwrap(),
@@ -962,11 +1116,19 @@ impl TestSwapInfo {
                         self.serum_market,
                         self.serum_bids,
                         self.serum_asks,
+                        None,
+                        None,
+                        None,
+                        None,
                         user_referrer_data_pubkey,
                         referral_pubkey,
+                        None,
+                        None,
                         SwapData {
                             amount_in,
                             minimum_amount_out,
+                            deadline_slot: None,
+                            output_owner: None,
                         },
                     )
                     .unwrap(),
@@ -984,11 +1146,16 @@ impl TestSwapInfo {
                         reward_token_pubkey,
                         config_info.deltafi_token,
                         self.admin_fee_b_key,
+                        None,
+                        None,
                         user_referrer_data_pubkey,
                         referral_pubkey,
+                        None,
                         SwapData {
                             amount_in,
                             minimum_amount_out,
+                            deadline_slot: None,
+                            output_owner: None,
                         },
                     )
                     .unwrap(),
@@ -1056,6 +1223,7 @@ impl TestSwapInfo {
                             token_a_amount,
                             token_b_amount,
                             min_mint_amount,
+                            deadline_slot: None,
                         },
                     )
                     .unwrap(),
@@ -1074,6 +1242,90 @@ impl TestSwapInfo {
                             token_a_amount,
                             token_b_amount,
                             min_mint_amount,
+                            deadline_slot: None,
+                        },
+                    )
+                    .unwrap(),
+                },
+            ],
+            Some(&payer.pubkey()),
+        );
+
+        let recent_blockhash = banks_client.get_recent_blockhash().await.unwrap();
+        transaction.sign(
+            &[payer, user_account_owner, &user_transfer_authority],
+            recent_blockhash,
+        );
+
+        assert_matches!(banks_client.process_transaction(transaction).await, Ok(()));
+    }
+
+    pub async fn deposit_one(
+        &self,
+        swap_type: SwapType,
+        banks_client: &mut BanksClient,
+        config_info: &TestSwapConfig,
+        user_account_owner: &Keypair,
+        source_pubkey: Pubkey,
+        pool_token_pubkey: Pubkey,
+        amount_in: u64,
+        min_mint_amount: u64,
+        payer: &Keypair,
+    ) {
+        let user_transfer_authority = Keypair::new();
+        let mut transaction = Transaction::new_with_payer(
+            &[
+                approve(
+                    &spl_token::id(),
+                    &source_pubkey,
+                    &user_transfer_authority.pubkey(),
+                    &user_account_owner.pubkey(),
+                    &[],
+                    amount_in,
+                )
+                .unwrap(),
+                match swap_type {
+                    SwapType::Normal => deposit_one(
+                        deltafi_swap::id(),
+                        config_info.pubkey,
+                        self.pubkey,
+                        self.authority,
+                        user_transfer_authority.pubkey(),
+                        source_pubkey,
+                        self.token_a,
+                        self.token_b,
+                        self.admin_fee_b_key,
+                        self.pool_mint,
+                        pool_token_pubkey,
+                        self.oracle_a,
+                        self.oracle_b,
+                        self.serum_market,
+                        self.serum_bids,
+                        self.serum_asks,
+                        DepositOneData {
+                            amount_in,
+                            min_mint_amount,
+                            deadline_slot: None,
+                        },
+                    )
+                    .unwrap(),
+                    SwapType::Stable => stable_deposit_one(
+                        deltafi_swap::id(),
+                        config_info.pubkey,
+                        self.pubkey,
+                        self.authority,
+                        user_transfer_authority.pubkey(),
+                        source_pubkey,
+                        self.token_a,
+                        self.token_b,
+                        self.admin_fee_b_key,
+                        self.pool_mint,
+                        pool_token_pubkey,
+                        None,
+                        DepositOneData {
+                            amount_in,
+                            min_mint_amount,
+                            deadline_slot: None,
                         },
                     )
                     .unwrap(),
@@ -1091,6 +1343,96 @@ impl TestSwapInfo {
         assert_matches!(banks_client.process_transaction(transaction).await, Ok(()));
     }
 
+    pub async fn withdraw_one(
+        &self,
+        swap_type: SwapType,
+        banks_client: &mut BanksClient,
+        config_info: &TestSwapConfig,
+        user_account_owner: &Keypair,
+        pool_token_pubkey: Pubkey,
+        destination_pubkey: Pubkey,
+        pool_token_amount: u64,
+        minimum_amount_out: u64,
+        token_side: u8,
+        payer: &Keypair,
+    ) {
+        let user_transfer_authority = Keypair::new();
+        let admin_fee_destination = if token_side == 0 {
+            self.admin_fee_a_key
+        } else {
+            self.admin_fee_b_key
+        };
+        let mut transaction = Transaction::new_with_payer(
+            &[
+                approve(
+                    &spl_token::id(),
+                    &pool_token_pubkey,
+                    &user_transfer_authority.pubkey(),
+                    &user_account_owner.pubkey(),
+                    &[],
+                    pool_token_amount,
+                )
+                .unwrap(),
+                match swap_type {
+                    SwapType::Normal => withdraw_one(
+                        deltafi_swap::id(),
+                        config_info.pubkey,
+                        self.pubkey,
+                        self.authority,
+                        user_transfer_authority.pubkey(),
+                        self.pool_mint,
+                        pool_token_pubkey,
+                        self.token_a,
+                        self.token_b,
+                        destination_pubkey,
+                        admin_fee_destination,
+                        self.oracle_a,
+                        self.oracle_b,
+                        self.serum_market,
+                        self.serum_bids,
+                        self.serum_asks,
+                        WithdrawOneData {
+                            pool_token_amount,
+                            minimum_amount_out,
+                            token_side,
+                            deadline_slot: None,
+                        },
+                    )
+                    .unwrap(),
+                    SwapType::Stable => stable_withdraw_one(
+                        deltafi_swap::id(),
+                        config_info.pubkey,
+                        self.pubkey,
+                        self.authority,
+                        user_transfer_authority.pubkey(),
+                        self.pool_mint,
+                        pool_token_pubkey,
+                        self.token_a,
+                        self.token_b,
+                        destination_pubkey,
+                        admin_fee_destination,
+                        None,
+                        WithdrawOneData {
+                            pool_token_amount,
+                            minimum_amount_out,
+                            token_side,
+                            deadline_slot: None,
+                        },
+                    )
+                    .unwrap(),
+                },
+            ],
+            Some(&payer.pubkey()),
+        );
+
+        let recent_blockhash = banks_client.get_recent_blockhash().await.unwrap();
+        transaction.sign(
+            &[payer, user_account_owner, &user_transfer_authority],
+            recent_blockhash,
+        );
+        assert_matches!(banks_client.process_transaction(transaction).await, Ok(()));
+    }
+
     pub async fn withdraw(
         &self,
         swap_type: SwapType,
@@ -1134,6 +1476,7 @@ impl TestSwapInfo {
                             pool_token_amount,
                             minimum_token_a_amount,
                             minimum_token_b_amount,
+                            deadline_slot: None,
                         },
                     )
                     .unwrap(),
@@ -1154,6 +1497,7 @@ impl TestSwapInfo {
                             pool_token_amount,
                             minimum_token_a_amount,
                             minimum_token_b_amount,
+                            deadline_slot: None,
                         },
                     )
                     .unwrap(),
@@ -1381,7 +1725,10 @@ impl TestFarmUser {
                     pool_token_pubkey,
                     self.pubkey,
                     user_account_owner.pubkey(),
-                    FarmDepositData { amount },
+                    FarmDepositData {
+                        amount,
+                        lock_tier: 0,
+                    },
                 )
                 .unwrap(),
             ],
@@ -1428,6 +1775,35 @@ impl TestFarmUser {
 
         assert_matches!(banks_client.process_transaction(transaction).await, Ok(()));
     }
+
+    pub async fn do_farm_claim(
+        &self,
+        banks_client: &mut BanksClient,
+        config_info: &TestSwapConfig,
+        user_account_owner: &Keypair,
+        claim_destination_pubkey: Pubkey,
+        payer: &Keypair,
+    ) {
+        let mut transaction = Transaction::new_with_payer(
+            &[farm_claim(
+                deltafi_swap::id(),
+                self.config_key,
+                self.farm_pool_key,
+                self.pubkey,
+                user_account_owner.pubkey(),
+                config_info.market_authority,
+                claim_destination_pubkey,
+                config_info.deltafi_token,
+            )
+            .unwrap()],
+            Some(&payer.pubkey()),
+        );
+
+        let recent_blockhash = banks_client.get_recent_blockhash().await.unwrap();
+        transaction.sign(&[payer, user_account_owner], recent_blockhash);
+
+        assert_matches!(banks_client.process_transaction(transaction).await, Ok(()));
+    }
 }
 
 pub async fn create_and_mint_to_token_account(
@@ -1549,35 +1925,31 @@ pub fn add_oracle(
 ) -> TestOracle {
     let pyth_program_id = Pubkey::from_str(pyth::PYTH_PROGRAM_ID).unwrap();
     // Add Pyth product account
-    test.add_account_with_file_data(
+    test.add_account(
         product_pubkey,
-        u32::MAX as u64,
-        pyth_program_id,
-        &format!("{}.bin", product_pubkey.to_string()),
+        Account {
+            lamports: u32::MAX as u64,
+            data: product_account_data(&price_pubkey),
+            owner: pyth_program_id,
+            executable: false,
+            rent_epoch: 0,
+        },
     );
 
-    // Add Pyth price account after setting the price
-    let filename = &format!("{}.bin", price_pubkey.to_string());
-    let mut pyth_price_data = read_file(find_file(filename).unwrap_or_else(|| {
-        panic!("Unable to locate {}", filename);
-    }));
-
-    let mut pyth_price = pyth::load_mut::<pyth::Price>(pyth_price_data.as_mut_slice()).unwrap();
-
-    let decimals = 10u64
-        .checked_pow(pyth_price.expo.checked_abs().unwrap().try_into().unwrap())
-        .unwrap();
-
-    pyth_price.valid_slot = 0;
-    pyth_price.agg.price = price
-        .try_round_u64()
-        .unwrap()
-        .checked_mul(decimals)
-        .unwrap()
-        .try_into()
-        .unwrap();
-
-    pyth_price.prev_price = pyth_price.agg.price;
+    // Add Pyth price account with the price in 8 decimals, valid from the first slot
+    let pyth_price_data = MockPrice {
+        price: price
+            .try_round_u64()
+            .unwrap()
+            .checked_mul(100_000_000)
+            .unwrap()
+            .try_into()
+            .unwrap(),
+        expo: -8,
+        valid_slot: 0,
+        ..MockPrice::default()
+    }
+    .to_account_data(&product_pubkey);
 
     test.add_account(
         price_pubkey,
@@ -0,0 +1,105 @@
+#![cfg(feature = "test-bpf")]
+
+mod utils;
+
+use deltafi_swap::{
+    math::{Decimal, TryDiv},
+    processor::process,
+    state::SwapType,
+};
+
+use solana_program_test::*;
+use solana_sdk::{
+    program_pack::Pack,
+    signature::{Keypair, Signer},
+};
+use spl_token::state::{Account as Token, Mint};
+use utils::*;
+
+#[tokio::test]
+async fn test_success() {
+    let mut test = ProgramTest::new("deltafi_swap", deltafi_swap::id(), processor!(process));
+
+    // limit to track compute unit increase
+    test.set_bpf_compute_max_units(200_000);
+
+    let swap_config = add_swap_config(&mut test);
+
+    let usdc_mint = add_token_mint(&mut test, USDC_MINT, 6);
+    let usdt_mint = add_token_mint(&mut test, USDT_MINT, 6);
+    let user_account_owner = Keypair::new();
+    let admin_account_owner = Keypair::new();
+
+    let swap_info = add_swap_info(
+        SwapType::Stable,
+        &mut test,
+        &swap_config,
+        &user_account_owner,
+        &admin_account_owner,
+        AddSwapInfoArgs {
+            token_a_mint: usdc_mint.pubkey,
+            token_b_mint: usdt_mint.pubkey,
+            token_a_amount: 400_000_000_000,
+            token_b_amount: 400_000_000_000,
+            market_price: Decimal::one(),
+            slope: Decimal::one().try_div(2).unwrap(),
+            swap_out_limit_percentage: 10u8,
+            // ignored pyth, oracle for stable swap
+            ..AddSwapInfoArgs::default()
+        },
+    );
+
+    let (mut banks_client, payer, _recent_blockhash) = test.start().await;
+
+    let usdc_withdraw_account = create_and_mint_to_token_account(
+        &mut banks_client,
+        usdc_mint.pubkey,
+        Some(&usdc_mint.authority),
+        &payer,
+        user_account_owner.pubkey(),
+        0,
+    )
+    .await;
+
+    swap_info
+        .withdraw_one(
+            SwapType::Stable,
+            &mut banks_client,
+            &swap_config,
+            &user_account_owner,
+            swap_info.pool_token,
+            usdc_withdraw_account,
+            2_000_000_000, // withdraw share amount
+            3_800_000_000, // minimum usdc amount
+            0,             // receive token a
+            &payer,
+        )
+        .await;
+
+    // Both sides of the share end up in USDC, minus the swap slippage and the fees.
+    let usdc_amount = get_token_balance(&mut banks_client, usdc_withdraw_account).await;
+    assert!(usdc_amount >= 3_800_000_000);
+    assert!(usdc_amount < 4_000_000_000);
+
+    // The USDT side never leaves the pool.
+    assert_eq!(
+        get_token_balance(&mut banks_client, swap_info.token_b).await,
+        400_000_000_000
+    );
+
+    let pool_token_account = banks_client
+        .get_account(swap_info.pool_token)
+        .await
+        .unwrap()
+        .unwrap();
+    let pool_token = Token::unpack(&pool_token_account.data[..]).unwrap();
+
+    let pool_mint_account = banks_client
+        .get_account(swap_info.pool_mint)
+        .await
+        .unwrap()
+        .unwrap();
+    let pool_mint = Mint::unpack(&pool_mint_account.data[..]).unwrap();
+
+    assert_eq!(pool_token.amount, pool_mint.supply);
+}